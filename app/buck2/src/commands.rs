@@ -14,3 +14,4 @@ pub mod docs;
 pub mod forkserver;
 pub mod internal_test_runner;
 pub(crate) mod schedule_termination;
+pub(crate) mod schema;
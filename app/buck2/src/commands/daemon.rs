@@ -29,6 +29,7 @@ use buck2_common::memory;
 use buck2_core::buck2_env;
 use buck2_core::fs::fs_util;
 use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
+use buck2_core::fs::project::ProjectRoot;
 use buck2_core::logging::LogConfigurationReloadHandle;
 use buck2_server::builtin_docs::docs::docs_command;
 use buck2_server::daemon::daemon_tcp::create_listener;
@@ -46,6 +47,7 @@ use buck2_util::threads::thread_spawn;
 use buck2_util::tokio_runtime::new_tokio_runtime;
 use dice::DetectCycles;
 use dice::WhichDice;
+use dupe::Dupe;
 use futures::channel::mpsc;
 use futures::channel::mpsc::UnboundedSender;
 use futures::pin_mut;
@@ -62,6 +64,8 @@ use crate::commands::schedule_termination::maybe_schedule_termination;
 enum DaemonError {
     #[error("The buckd pid file at `{}` had a mismatched pid, expected `{1}`, got `{2}`", _0.display())]
     PidFileMismatch(PathBuf, u32, u32),
+    #[error("The project root `{0}` was deleted")]
+    ProjectRootDeleted(ProjectRoot),
 }
 
 /// Start or run buck daemon.
@@ -190,6 +194,15 @@ fn verify_current_daemon(daemon_dir: &DaemonDir) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// A daemon whose checkout was deleted cannot run any more commands, so it would only hold on
+/// to its memory until the inactivity timeout.
+fn verify_project_root(project_root: &ProjectRoot) -> anyhow::Result<()> {
+    if !fs_util::try_exists(project_root.root())? {
+        return Err(DaemonError::ProjectRootDeleted(project_root.dupe()).into());
+    }
+    Ok(())
+}
+
 fn gen_auth_token() -> String {
     (0..20)
         .map(|_| rand::thread_rng().gen_range('a'..='z'))
@@ -393,6 +406,7 @@ impl DaemonCommand {
                 hard_shutdown_sender: hard_shutdown_sender.clone(),
             });
             let daemon_dir = paths.daemon_dir()?;
+            let project_root = paths.project_root().dupe();
 
             listener.set_nonblocking(true)?;
             let listener = tokio::net::TcpListener::from_std(listener)?;
@@ -428,6 +442,7 @@ impl DaemonCommand {
                 Self::check_daemon_dir_thread(
                     checker_interval_seconds,
                     daemon_dir,
+                    project_root,
                     hard_shutdown_sender,
                 )
             })?;
@@ -450,13 +465,14 @@ impl DaemonCommand {
 
     /// We start a dedicated thread to periodically check that the files in the daemon
     /// dir still reflect that we are the current buckd and verify that when you connect
-    /// to the server it is our server.
+    /// to the server it is our server. It also shuts down the daemon if its checkout was deleted.
     /// It gets a dedicated thread so that if somehow the main runtime gets all jammed up,
     /// this will still run (and presumably connecting to the server or our request would
     /// then fail and we'd do a hard shutdown).
     fn check_daemon_dir_thread(
         checker_interval_seconds: u64,
         daemon_dir: DaemonDir,
+        project_root: ProjectRoot,
         hard_shutdown_sender: UnboundedSender<String>,
     ) {
         let this_rt = Builder::new_current_thread().enable_all().build().unwrap();
@@ -464,7 +480,9 @@ impl DaemonCommand {
         this_rt.block_on(async move {
             loop {
                 tokio::time::sleep(Duration::from_secs(checker_interval_seconds)).await;
-                match verify_current_daemon(&daemon_dir) {
+                match verify_current_daemon(&daemon_dir)
+                    .and_then(|()| verify_project_root(&project_root))
+                {
                     Ok(()) => {}
                     Err(e) => {
                         // This bit of code cannot relay errors, ignoring that we can't log
@@ -0,0 +1,66 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use buck2_build_api::build::build_report::BuildReport;
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_event_log::stream_value::StreamValue;
+use buck2_event_log::utils::Invocation;
+use buck2_server_commands::commands::targets::json_schema::targets_json_schema;
+use buck2_test::command::TestReport;
+use schemars::JsonSchema;
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum SchemaKind {
+    /// The report written by `--build-report`.
+    BuildReport,
+    /// The test report.
+    TestReport,
+    /// The output of `buck2 targets --json`.
+    Targets,
+    /// The lines of `buck2 log show`.
+    EventLog,
+}
+
+/// A line of `buck2 log show`: the invocation on the first line, then the values streamed by
+/// the command.
+#[derive(JsonSchema)]
+#[schemars(untagged)]
+#[allow(dead_code)]
+enum EventLogLine {
+    Invocation(Invocation),
+    StreamValue(StreamValue),
+}
+
+#[derive(Debug, clap::Parser)]
+#[clap(
+    name = "schema",
+    about = "Print the JSON Schema of a machine-readable output of buck2"
+)]
+pub(crate) struct SchemaCommand {
+    /// The output to print the schema of.
+    #[clap(value_enum)]
+    kind: SchemaKind,
+}
+
+impl SchemaCommand {
+    pub(crate) fn exec(
+        self,
+        _matches: &clap::ArgMatches,
+        _ctx: ClientCommandContext<'_>,
+    ) -> anyhow::Result<()> {
+        let schema = match self.kind {
+            SchemaKind::BuildReport => BuildReport::json_schema(),
+            SchemaKind::TestReport => TestReport::json_schema(),
+            SchemaKind::Targets => targets_json_schema(),
+            SchemaKind::EventLog => schemars::schema_for!(EventLogLine),
+        };
+        buck2_client_ctx::println!("{}", serde_json::to_string_pretty(&schema)?)?;
+        Ok(())
+    }
+}
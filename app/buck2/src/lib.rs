@@ -14,14 +14,17 @@
 
 use anyhow::Context as _;
 use buck2_audit::AuditCommand;
+use buck2_client::commands::attach::AttachCommand;
 use buck2_client::commands::build::BuildCommand;
 use buck2_client::commands::bxl::BxlCommand;
 use buck2_client::commands::clean::CleanCommand;
+use buck2_client::commands::complete::CompleteCommand;
 use buck2_client::commands::completion::CompletionCommand;
 use buck2_client::commands::ctargets::ConfiguredTargetsCommand;
 use buck2_client::commands::debug::DebugCommand;
 use buck2_client::commands::expand_external_cell::ExpandExternalCellCommand;
 use buck2_client::commands::explain::ExplainCommand;
+use buck2_client::commands::explain_failure::ExplainFailureCommand;
 use buck2_client::commands::help_env::HelpEnvCommand;
 use buck2_client::commands::init::InitCommand;
 use buck2_client::commands::install::InstallCommand;
@@ -41,9 +44,14 @@ use buck2_client::commands::status::StatusCommand;
 use buck2_client::commands::subscribe::SubscribeCommand;
 use buck2_client::commands::targets::TargetsCommand;
 use buck2_client::commands::test::TestCommand;
+use buck2_client::commands::toolchain::ToolchainCommand;
+use buck2_client::commands::verify::VerifyCommand;
 use buck2_client_ctx::argfiles::expand_argfiles_with_context;
 use buck2_client_ctx::client_ctx::ClientCommandContext;
 use buck2_client_ctx::client_metadata::ClientMetadata;
+use buck2_client_ctx::command_alias::expand_command_aliases;
+use buck2_client_ctx::daemon::client::remote::RemoteDaemon;
+use buck2_client_ctx::error_format::ErrorFormat;
 use buck2_client_ctx::exit_result::ExitResult;
 use buck2_client_ctx::immediate_config::ImmediateConfigContext;
 use buck2_client_ctx::streaming::BuckSubcommand;
@@ -56,7 +64,11 @@ use buck2_core::buck2_env;
 use buck2_core::fs::paths::file_name::FileNameBuf;
 use buck2_event_observer::verbosity::Verbosity;
 pub use buck2_server_ctx::logging::TracingLogFile;
+use buck2_starlark::debug::DebugStarlarkCommand;
+use buck2_starlark::fmt::FmtCommand;
+use buck2_starlark::lint::StarlarkLintCommand;
 use buck2_starlark::StarlarkCommand;
+use buck2_starlark::StarlarkOpaqueCommand;
 use buck2_util::cleanup_ctx::AsyncCleanupContextGuard;
 use clap::CommandFactory;
 use clap::FromArgMatches;
@@ -68,6 +80,7 @@ use crate::commands::daemon::DaemonCommand;
 use crate::commands::docs::DocsCommand;
 use crate::commands::forkserver::ForkserverCommand;
 use crate::commands::internal_test_runner::InternalTestRunnerCommand;
+use crate::commands::schema::SchemaCommand;
 use crate::process_context::ProcessContext;
 
 mod check_user_allowed;
@@ -109,7 +122,8 @@ struct BeforeSubcommandOptions {
     /// 4 = more info about everything + stderr;
     ///
     /// It can be combined with specific log items (stderr, full_failed_command, commands, actions,
-    /// status, stats, success) to fine-tune the verbosity of the log. Example usage "-v=1,stderr"
+    /// status, stats, success, summary) to fine-tune the verbosity of the log. Example usage
+    /// "-v=1,stderr"
     #[clap(
         short = 'v',
         long = "verbose",
@@ -119,6 +133,11 @@ struct BeforeSubcommandOptions {
     )]
     verbosity: Verbosity,
 
+    /// How errors are printed to stderr when the command fails. With `json`, each error is printed
+    /// as a JSON object on its own line, with its category, tags and source location.
+    #[clap(long, global = true, default_value = "text", value_enum)]
+    error_format: ErrorFormat,
+
     /// The oncall executing this command
     #[clap(long, global = true)]
     oncall: Option<String>,
@@ -129,6 +148,20 @@ struct BeforeSubcommandOptions {
     #[clap(long, global = true)]
     client_metadata: Vec<ClientMetadata>,
 
+    /// Run commands with the daemon of a checkout on another machine, given as
+    /// `[user@]host:/path/to/checkout`, instead of a local one. The client connects to it with
+    /// `ssh`, which must not prompt for a password, and the current directory must be in a copy
+    /// of that checkout. Paths of the checkout, like `--config-file` ones, are translated to the
+    /// remote one, while options which write files on the daemon's machine or start other
+    /// daemons, like `--out` or `--offload-analysis`, are rejected.
+    #[clap(
+        env("BUCK2_REMOTE_DAEMON"),
+        long,
+        global = true,
+        value_name = "HOST:PATH"
+    )]
+    remote_daemon: Option<RemoteDaemon>,
+
     /// Do not launch a daemon process, run buck server in client process.
     ///
     /// Note even when running in no-buckd mode, it still writes state files.
@@ -185,28 +218,37 @@ impl Opt {
             None => panic!("Parsed a subcommand but couldn't extract subcommand argument matches"),
         };
 
-        self.cmd.exec(
-            process,
-            immediate_config,
-            subcommand_matches,
-            argv,
-            self.common_opts,
-        )
+        let error_format = self.common_opts.error_format;
+        self.cmd
+            .exec(
+                process,
+                immediate_config,
+                subcommand_matches,
+                argv,
+                self.common_opts,
+            )
+            .with_error_format(error_format)
     }
 }
 
 pub fn exec(process: ProcessContext<'_>) -> ExitResult {
     let mut immediate_config = ImmediateConfigContext::new(process.working_dir);
-    let mut expanded_args =
-        expand_argfiles_with_context(process.args.to_vec(), &mut immediate_config)
-            .context("Error expanding argsfiles")?;
+    let clap = Opt::command();
+    let aliased_args = expand_command_aliases(
+        process.args.to_vec(),
+        &BeforeSubcommandOptions::command(),
+        &|name| clap.find_subcommand(name).is_some(),
+        &mut immediate_config,
+    )
+    .context("Error expanding command aliases")?;
+    let mut expanded_args = expand_argfiles_with_context(aliased_args, &mut immediate_config)
+        .context("Error expanding argsfiles")?;
 
     // Override arg0 in `buck2 help`.
     if let Some(arg0) = buck2_env!("BUCK2_ARG0")? {
         expanded_args[0] = arg0.to_owned();
     }
 
-    let clap = Opt::command();
     let matches = clap.get_matches_from(&expanded_args);
     let opt: Opt = Opt::from_arg_matches(&matches)?;
 
@@ -242,6 +284,7 @@ pub(crate) enum CommandKind {
     #[clap(subcommand)]
     Audit(AuditCommand),
     Aquery(AqueryCommand),
+    Attach(AttachCommand),
     Build(BuildCommand),
     Bxl(BxlCommand),
     // TODO(nga): implement `buck2 help-buckconfig` too
@@ -252,14 +295,19 @@ pub(crate) enum CommandKind {
     Init(InitCommand),
     #[clap(hide = true)] // TODO iguridi: remove
     Explain(ExplainCommand),
+    ExplainFailure(ExplainFailureCommand),
     ExpandExternalCell(ExpandExternalCellCommand),
+    Fmt(FmtCommand),
     Install(InstallCommand),
     Kill(KillCommand),
     Killall(KillallCommand),
+    /// Alias for `starlark lint`.
+    Lint(StarlarkLintCommand),
     Root(RootCommand),
     /// Alias for `uquery`.
     Query(UqueryCommand),
     Run(RunCommand),
+    Schema(SchemaCommand),
     Server(ServerCommand),
     Status(StatusCommand),
     #[clap(subcommand)]
@@ -270,8 +318,10 @@ pub(crate) enum CommandKind {
     Ctargets(ConfiguredTargetsCommand),
     Uquery(UqueryCommand),
     #[clap(subcommand, hide = true)]
-    Debug(DebugCommand),
+    Debug(DebugCommandKind),
     Completion(CompletionCommand),
+    #[clap(hide = true)]
+    Complete(CompleteCommand),
     Docs(DocsCommand),
     #[clap(subcommand)]
     Profile(ProfileCommand),
@@ -282,6 +332,30 @@ pub(crate) enum CommandKind {
     Log(LogCommand),
     Lsp(LspCommand),
     Subscribe(SubscribeCommand),
+    Verify(VerifyCommand),
+    #[clap(subcommand)]
+    Toolchain(ToolchainCommand),
+}
+
+/// `buck2 debug` subcommands, including those not implemented in `buck2_client`.
+#[derive(Debug, clap::Subcommand)]
+#[clap(about = "Hidden debug commands useful for testing buck2")]
+pub(crate) enum DebugCommandKind {
+    #[clap(flatten)]
+    Client(DebugCommand),
+    Starlark(DebugStarlarkCommand),
+}
+
+impl DebugCommandKind {
+    fn exec(self, matches: &clap::ArgMatches, ctx: ClientCommandContext<'_>) -> ExitResult {
+        match self {
+            DebugCommandKind::Client(cmd) => cmd.exec(matches, ctx),
+            DebugCommandKind::Starlark(cmd) => {
+                let matches = matches.subcommand().expect("subcommand not found").1;
+                cmd.exec(matches, ctx)
+            }
+        }
+    }
 }
 
 impl CommandKind {
@@ -334,6 +408,7 @@ impl CommandKind {
             immediate_config,
             paths,
             verbosity: common_opts.verbosity,
+            error_format: common_opts.error_format,
             start_in_process_daemon,
             working_dir: process.working_dir.clone(),
             trace_id: process.trace_id.dupe(),
@@ -345,6 +420,7 @@ impl CommandKind {
             runtime: &runtime,
             oncall: common_opts.oncall,
             client_metadata: common_opts.client_metadata,
+            remote_daemon: common_opts.remote_daemon,
         };
 
         match self {
@@ -354,6 +430,7 @@ impl CommandKind {
                 .into(),
             CommandKind::InternalTestRunner(cmd) => cmd.exec(matches, command_ctx).into(),
             CommandKind::Aquery(cmd) => cmd.exec(matches, command_ctx),
+            CommandKind::Attach(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Build(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Bxl(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Test(cmd) => cmd.exec(matches, command_ctx),
@@ -361,6 +438,7 @@ impl CommandKind {
             CommandKind::HelpEnv(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Kill(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Killall(cmd) => cmd.exec(matches, command_ctx),
+            CommandKind::Lint(cmd) => StarlarkOpaqueCommand::Lint(cmd).exec(matches, command_ctx),
             CommandKind::Clean(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Root(cmd) => cmd.exec(matches, command_ctx).into(),
             CommandKind::Query(cmd) => {
@@ -369,6 +447,7 @@ impl CommandKind {
                 )?;
                 cmd.exec(matches, command_ctx)
             }
+            CommandKind::Schema(cmd) => cmd.exec(matches, command_ctx).into(),
             CommandKind::Server(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Status(cmd) => cmd.exec(matches, command_ctx).into(),
             CommandKind::Targets(cmd) => cmd.exec(matches, command_ctx),
@@ -380,16 +459,21 @@ impl CommandKind {
             CommandKind::Uquery(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Debug(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Completion(cmd) => cmd.exec(&mut Opt::command(), matches, command_ctx),
+            CommandKind::Complete(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Docs(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Profile(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Rage(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Init(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Explain(cmd) => cmd.exec(matches, command_ctx),
+            CommandKind::ExplainFailure(cmd) => cmd.exec(matches, command_ctx),
+            CommandKind::Fmt(cmd) => cmd.exec(matches, command_ctx).into(),
             CommandKind::Install(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Log(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Lsp(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Subscribe(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::ExpandExternalCell(cmd) => cmd.exec(matches, command_ctx),
+            CommandKind::Verify(cmd) => cmd.exec(matches, command_ctx),
+            CommandKind::Toolchain(cmd) => cmd.exec(matches, command_ctx),
         }
     }
 }
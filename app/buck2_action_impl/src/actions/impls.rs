@@ -9,10 +9,12 @@
 
 pub(crate) mod cas_artifact;
 pub(crate) mod copy;
+pub(crate) mod download_archive;
 pub(crate) mod download_file;
 pub(crate) mod offline;
 pub(crate) mod run;
 pub(crate) mod symlinked_dir;
 pub(crate) mod write;
+pub(crate) mod write_action_metadata;
 pub(crate) mod write_json;
 pub(crate) mod write_macros;
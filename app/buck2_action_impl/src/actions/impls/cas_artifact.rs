@@ -31,6 +31,7 @@ use buck2_common::file_ops::TrackedFileDigest;
 use buck2_common::io::trace::TracingIoProvider;
 use buck2_core::category::Category;
 use buck2_core::execution_types::executor_config::RemoteExecutorUseCase;
+use buck2_execute::artifact::fs::ExecutorFs;
 use buck2_execute::artifact_value::ArtifactValue;
 use buck2_execute::digest::CasDigestToReExt;
 use buck2_execute::directory::re_directory_to_re_tree;
@@ -43,6 +44,8 @@ use chrono::DateTime;
 use chrono::TimeZone;
 use chrono::Utc;
 use dupe::Dupe;
+use indexmap::indexmap;
+use indexmap::IndexMap;
 use indexmap::IndexSet;
 use once_cell::sync::Lazy;
 use remote_execution as RE;
@@ -190,6 +193,21 @@ impl Action for CasArtifactAction {
     fn identifier(&self) -> Option<&str> {
         Some(self.output.get_path().path().as_str())
     }
+
+    fn aquery_attributes(&self, _fs: &ExecutorFs) -> IndexMap<String, String> {
+        let kind = match self.inner.kind {
+            ArtifactKind::File => "file",
+            ArtifactKind::Directory(DirectoryKind::Directory) => "directory",
+            ArtifactKind::Directory(DirectoryKind::Tree) => "tree",
+        };
+        indexmap! {
+            "digest".to_owned() => self.inner.digest.to_string(),
+            "kind".to_owned() => kind.to_owned(),
+            "re_use_case".to_owned() => self.inner.re_use_case.to_string(),
+            "expires_after".to_owned() => self.inner.expires_after.to_rfc3339(),
+            "is_executable".to_owned() => self.inner.executable.to_string(),
+        }
+    }
 }
 
 #[async_trait]
@@ -26,11 +26,14 @@ use buck2_build_api::actions::UnregisteredAction;
 use buck2_build_api::artifact_groups::ArtifactGroup;
 use buck2_core::category::Category;
 use buck2_execute::artifact::artifact_dyn::ArtifactDyn;
+use buck2_execute::artifact::fs::ExecutorFs;
 use buck2_execute::artifact_utils::ArtifactValueBuilder;
 use buck2_execute::execute::command_executor::ActionExecutionTimingData;
 use buck2_execute::materialize::materializer::CopiedArtifact;
 use dupe::Dupe;
 use gazebo::prelude::*;
+use indexmap::indexmap;
+use indexmap::IndexMap;
 use indexmap::IndexSet;
 use once_cell::sync::Lazy;
 use starlark::values::OwnedFrozenValue;
@@ -151,6 +154,16 @@ impl Action for CopyAction {
     fn identifier(&self) -> Option<&str> {
         Some(self.output().get_path().path().as_str())
     }
+
+    fn aquery_attributes(&self, _fs: &ExecutorFs) -> IndexMap<String, String> {
+        let mode = match self.copy {
+            CopyMode::Copy => "copy",
+            CopyMode::Symlink => "symlink",
+        };
+        indexmap! {
+            "mode".to_owned() => mode.to_owned(),
+        }
+    }
 }
 
 #[async_trait]
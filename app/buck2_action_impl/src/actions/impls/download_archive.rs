@@ -0,0 +1,658 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::borrow::Cow;
+use std::io;
+use std::io::Read;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use std::slice;
+use std::sync::Arc;
+
+use allocative::Allocative;
+use anyhow::Context as _;
+use async_trait::async_trait;
+use buck2_artifact::artifact::build_artifact::BuildArtifact;
+use buck2_build_api::actions::execute::action_executor::ActionExecutionKind;
+use buck2_build_api::actions::execute::action_executor::ActionExecutionMetadata;
+use buck2_build_api::actions::execute::action_executor::ActionOutputs;
+use buck2_build_api::actions::execute::error::ExecuteError;
+use buck2_build_api::actions::Action;
+use buck2_build_api::actions::ActionExecutable;
+use buck2_build_api::actions::ActionExecutionCtx;
+use buck2_build_api::actions::IncrementalActionExecutable;
+use buck2_build_api::actions::UnregisteredAction;
+use buck2_build_api::artifact_groups::ArtifactGroup;
+use buck2_common::file_ops::FileDigestConfig;
+use buck2_common::io::trace::TracingIoProvider;
+use buck2_core::category::Category;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::fs_util::FileReadGuard;
+use buck2_core::fs::paths::abs_path::AbsPath;
+use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
+use buck2_core::fs::paths::forward_rel_path::ForwardRelativePathBuf;
+use buck2_execute::artifact::fs::ExecutorFs;
+use buck2_execute::artifact_value::ArtifactValue;
+use buck2_execute::directory::INTERNER;
+use buck2_execute::entry::build_entry_from_disk;
+use buck2_execute::execute::command_executor::ActionExecutionTimingData;
+use buck2_execute::materialize::http::http_download;
+use buck2_execute::materialize::http::Checksum;
+use dupe::Dupe;
+use indexmap::IndexMap;
+use indexmap::IndexSet;
+use itertools::Itertools;
+use once_cell::sync::Lazy;
+use starlark::values::OwnedFrozenValue;
+
+use crate::actions::impls::offline;
+
+#[derive(Debug, buck2_error::Error)]
+enum DownloadArchiveActionError {
+    #[error("download archive action should not have inputs, got {0}")]
+    WrongNumberOfInputs(usize),
+    #[error(
+        "Exactly one output directory must be specified for a download archive action, got {0}"
+    )]
+    WrongNumberOfOutputs(usize),
+    #[error("Unknown archive type `{0}`, expected one of {1}")]
+    #[buck2(input)]
+    UnknownArchiveType(String, String),
+    #[error("Cannot infer the archive type of `{0}`, pass `type` explicitly")]
+    #[buck2(input)]
+    CannotInferArchiveType(String),
+    #[error("Archive entry `{0}` escapes the output directory")]
+    UnsafeEntry(String),
+    #[error("Archive entry `{path}` is a symlink to `{target}`, outside of the output directory")]
+    UnsafeSymlink { path: String, target: String },
+    #[error("Archive downloaded from `{url}` has no entries under `{strip_prefix}`")]
+    NothingExtracted { url: String, strip_prefix: String },
+}
+
+#[derive(Debug, Copy, Clone, Dupe, PartialEq, Eq, Allocative)]
+pub(crate) enum ArchiveType {
+    Tar,
+    TarGz,
+    TarZst,
+    Zip,
+}
+
+impl ArchiveType {
+    const SUFFIXES: &'static [(&'static str, ArchiveType)] = &[
+        ("tar", ArchiveType::Tar),
+        ("tar.gz", ArchiveType::TarGz),
+        ("tgz", ArchiveType::TarGz),
+        ("tar.zst", ArchiveType::TarZst),
+        ("tzst", ArchiveType::TarZst),
+        ("zip", ArchiveType::Zip),
+    ];
+
+    pub(crate) fn parse(s: &str) -> anyhow::Result<Self> {
+        Self::SUFFIXES
+            .iter()
+            .find(|(suffix, _)| *suffix == s)
+            .map(|(_, t)| *t)
+            .ok_or_else(|| {
+                let expected = Self::SUFFIXES
+                    .iter()
+                    .map(|(suffix, _)| format!("`{}`", suffix))
+                    .join(", ");
+                DownloadArchiveActionError::UnknownArchiveType(s.to_owned(), expected).into()
+            })
+    }
+
+    /// Infer the archive type from the extension of the path of a URL.
+    pub(crate) fn from_url(url: &str) -> anyhow::Result<Self> {
+        let path = url.split(['?', '#']).next().unwrap_or(url);
+        Self::SUFFIXES
+            .iter()
+            .find(|(suffix, _)| {
+                path.strip_suffix(suffix)
+                    .map_or(false, |rest| rest.ends_with('.'))
+            })
+            .map(|(_, t)| *t)
+            .ok_or_else(|| {
+                DownloadArchiveActionError::CannotInferArchiveType(url.to_owned()).into()
+            })
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            ArchiveType::Tar => "tar",
+            ArchiveType::TarGz => "tar.gz",
+            ArchiveType::TarZst => "tar.zst",
+            ArchiveType::Zip => "zip",
+        }
+    }
+}
+
+#[derive(Debug, Allocative)]
+pub(crate) struct UnregisteredDownloadArchiveAction {
+    checksum: Checksum,
+    url: Arc<str>,
+    vpnless_url: Option<Arc<str>>,
+    archive_type: ArchiveType,
+    strip_prefix: Option<ForwardRelativePathBuf>,
+}
+
+impl UnregisteredDownloadArchiveAction {
+    pub(crate) fn new(
+        checksum: Checksum,
+        url: Arc<str>,
+        vpnless_url: Option<Arc<str>>,
+        archive_type: ArchiveType,
+        strip_prefix: Option<ForwardRelativePathBuf>,
+    ) -> Self {
+        Self {
+            checksum,
+            url,
+            vpnless_url,
+            archive_type,
+            strip_prefix,
+        }
+    }
+}
+
+impl UnregisteredAction for UnregisteredDownloadArchiveAction {
+    fn register(
+        self: Box<Self>,
+        inputs: IndexSet<ArtifactGroup>,
+        outputs: IndexSet<BuildArtifact>,
+        _starlark_data: Option<OwnedFrozenValue>,
+        _error_handler: Option<OwnedFrozenValue>,
+    ) -> anyhow::Result<Box<dyn Action>> {
+        Ok(Box::new(DownloadArchiveAction::new(
+            inputs, outputs, *self,
+        )?))
+    }
+}
+
+/// Downloads an archive and unpacks it into a directory in the daemon, so that consumers (for
+/// example toolchains used by remote actions) only see the unpacked tree, which is uploaded to
+/// the CAS like any other output.
+#[derive(Debug, Allocative)]
+struct DownloadArchiveAction {
+    output: BuildArtifact,
+    inner: UnregisteredDownloadArchiveAction,
+}
+
+impl DownloadArchiveAction {
+    fn new(
+        inputs: IndexSet<ArtifactGroup>,
+        outputs: IndexSet<BuildArtifact>,
+        inner: UnregisteredDownloadArchiveAction,
+    ) -> anyhow::Result<Self> {
+        if !inputs.is_empty() {
+            return Err(DownloadArchiveActionError::WrongNumberOfInputs(inputs.len()).into());
+        }
+        if outputs.len() != 1 {
+            return Err(DownloadArchiveActionError::WrongNumberOfOutputs(outputs.len()).into());
+        }
+        Ok(Self {
+            output: outputs.into_iter().next().unwrap(),
+            inner,
+        })
+    }
+
+    async fn execute_for_offline(
+        &self,
+        ctx: &mut dyn ActionExecutionCtx,
+    ) -> anyhow::Result<(ActionOutputs, ActionExecutionMetadata)> {
+        let outputs = offline::declare_copy_from_offline_cache(ctx, &self.output).await?;
+
+        Ok((
+            outputs,
+            ActionExecutionMetadata {
+                execution_kind: ActionExecutionKind::Simple,
+                timing: ActionExecutionTimingData::default(),
+            },
+        ))
+    }
+}
+
+#[async_trait]
+impl Action for DownloadArchiveAction {
+    fn kind(&self) -> buck2_data::ActionKind {
+        buck2_data::ActionKind::DownloadArchive
+    }
+
+    fn inputs(&self) -> anyhow::Result<Cow<'_, [ArtifactGroup]>> {
+        Ok(Cow::Borrowed(&[]))
+    }
+
+    fn outputs(&self) -> anyhow::Result<Cow<'_, [BuildArtifact]>> {
+        Ok(Cow::Borrowed(slice::from_ref(&self.output)))
+    }
+
+    fn as_executable(&self) -> ActionExecutable<'_> {
+        ActionExecutable::Incremental(self)
+    }
+
+    fn category(&self) -> &Category {
+        static DOWNLOAD_ARCHIVE_CATEGORY: Lazy<Category> =
+            Lazy::new(|| Category::try_from("download_archive").unwrap());
+
+        &DOWNLOAD_ARCHIVE_CATEGORY
+    }
+
+    fn identifier(&self) -> Option<&str> {
+        Some(self.output.get_path().path().as_str())
+    }
+
+    fn aquery_attributes(&self, _fs: &ExecutorFs) -> IndexMap<String, String> {
+        let mut attrs = IndexMap::new();
+        attrs.insert("url".to_owned(), self.inner.url.to_string());
+        attrs.insert(
+            "type".to_owned(),
+            self.inner.archive_type.as_str().to_owned(),
+        );
+        if let Some(strip_prefix) = &self.inner.strip_prefix {
+            attrs.insert("strip_prefix".to_owned(), strip_prefix.to_string());
+        }
+        attrs
+    }
+}
+
+#[async_trait]
+impl IncrementalActionExecutable for DownloadArchiveAction {
+    async fn execute(
+        &self,
+        ctx: &mut dyn ActionExecutionCtx,
+    ) -> Result<(ActionOutputs, ActionExecutionMetadata), ExecuteError> {
+        if ctx.run_action_knobs().use_network_action_output_cache {
+            return self.execute_for_offline(ctx).await.map_err(Into::into);
+        }
+
+        ctx.cleanup_outputs().await?;
+
+        let client = ctx.http_client();
+        let url = if client.supports_vpnless() {
+            self.inner.vpnless_url.as_ref().unwrap_or(&self.inner.url)
+        } else {
+            &self.inner.url
+        };
+
+        let artifact_fs = ctx.fs();
+        let project_fs = artifact_fs.fs().dupe();
+        let output_path = artifact_fs.resolve_build(self.output.get_path());
+        let scratch_path = artifact_fs
+            .buck_out_path_resolver()
+            .resolve_scratch(&ctx.target().scratch_path());
+        let archive_path = scratch_path.join(ForwardRelativePath::unchecked_new("archive"));
+
+        http_download(
+            &client,
+            &project_fs,
+            ctx.digest_config(),
+            &archive_path,
+            url,
+            &self.inner.checksum,
+            false,
+        )
+        .await?;
+
+        let archive_abs = project_fs.resolve(&archive_path);
+        let staging_abs =
+            project_fs.resolve(&scratch_path.join(ForwardRelativePath::unchecked_new("unpacked")));
+        let output_abs = project_fs.resolve(&output_path);
+        let archive_type = self.inner.archive_type;
+        let strip_prefix = self.inner.strip_prefix.clone();
+        let unpacked = ctx
+            .blocking_executor()
+            .execute_io_inline(|| {
+                fs_util::create_dir_all(&output_abs)?;
+                let extracted = unpack_archive(
+                    archive_abs.as_abs_path(),
+                    archive_type,
+                    strip_prefix.as_ref().map(|p| Path::new(p.as_str())),
+                    output_abs.as_abs_path(),
+                    staging_abs.as_abs_path(),
+                )
+                .with_context(|| format!("Error unpacking archive downloaded from `{}`", url))?;
+                fs_util::remove_all(project_fs.resolve(&scratch_path))?;
+                match (extracted, &strip_prefix) {
+                    (0, Some(strip_prefix)) => Err(DownloadArchiveActionError::NothingExtracted {
+                        url: url.to_string(),
+                        strip_prefix: strip_prefix.to_string(),
+                    }
+                    .into()),
+                    _ => Ok(()),
+                }
+            })
+            .await;
+        if let Err(e) = unpacked {
+            // Don't leave a partially unpacked toolchain lying around in buck-out.
+            let _ignored = fs_util::remove_all(&output_abs);
+            return Err(e.into());
+        }
+
+        let (entry, _hashing_info) = build_entry_from_disk(
+            output_abs,
+            FileDigestConfig::build(ctx.digest_config().cas_digest_config()),
+            ctx.blocking_executor(),
+            project_fs.root(),
+        )
+        .await?;
+        let entry = entry
+            .context("Unpacked archive is missing")?
+            .map_dir(|dir| {
+                dir.fingerprint(ctx.digest_config().as_directory_serializer())
+                    .shared(&*INTERNER)
+            });
+        let value = ArtifactValue::from(entry);
+
+        ctx.materializer()
+            .declare_existing(vec![(output_path, value.dupe())])
+            .await?;
+
+        // If we're tracing I/O, get the materializer to copy to the offline cache
+        // so we can include it in the offline archive manifest later.
+        let io_provider = ctx.io_provider();
+        if let Some(tracer) = TracingIoProvider::from_io(&*io_provider) {
+            let offline_cache_path =
+                offline::declare_copy_to_offline_output_cache(ctx, &self.output, value.dupe())
+                    .await?;
+            tracer.add_buck_out_entry(offline_cache_path);
+        }
+
+        Ok((
+            ActionOutputs::from_single(self.output.get_path().dupe(), value),
+            ActionExecutionMetadata {
+                execution_kind: ActionExecutionKind::Simple,
+                timing: ActionExecutionTimingData::default(),
+            },
+        ))
+    }
+}
+
+/// Returns where an archive entry should be unpacked, relative to the output directory, or
+/// `None` if it is outside `strip_prefix`.
+fn entry_dest(path: &Path, strip_prefix: Option<&Path>) -> anyhow::Result<Option<PathBuf>> {
+    let mut rel = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(c) => rel.push(c),
+            Component::CurDir => {}
+            _ => {
+                return Err(
+                    DownloadArchiveActionError::UnsafeEntry(path.display().to_string()).into(),
+                );
+            }
+        }
+    }
+    match strip_prefix {
+        None => Ok(Some(rel)),
+        Some(prefix) => match rel.strip_prefix(prefix) {
+            Ok(rest) if !rest.as_os_str().is_empty() => Ok(Some(rest.to_owned())),
+            _ => Ok(None),
+        },
+    }
+}
+
+/// Unpack an archive into `dest`, returning the number of entries extracted. `staging` is a
+/// scratch directory which may be used to unpack the archive first.
+fn unpack_archive(
+    archive: &AbsPath,
+    archive_type: ArchiveType,
+    strip_prefix: Option<&Path>,
+    dest: &AbsPath,
+    staging: &AbsPath,
+) -> anyhow::Result<usize> {
+    let file = fs_util::open_file(archive)?;
+    match archive_type {
+        ArchiveType::Tar => unpack_tar(file, strip_prefix, dest, staging),
+        ArchiveType::TarGz => unpack_tar(
+            flate2::read::GzDecoder::new(file),
+            strip_prefix,
+            dest,
+            staging,
+        ),
+        ArchiveType::TarZst => unpack_tar(zstd::Decoder::new(file)?, strip_prefix, dest, staging),
+        ArchiveType::Zip => unpack_zip(file, strip_prefix, dest),
+    }
+}
+
+fn unpack_tar(
+    reader: impl Read,
+    strip_prefix: Option<&Path>,
+    dest: &AbsPath,
+    staging: &AbsPath,
+) -> anyhow::Result<usize> {
+    let mut archive = tar::Archive::new(reader);
+    // Only keep the permission bits of the entries, not setuid, setgid or sticky.
+    archive.set_preserve_permissions(false);
+    archive.set_preserve_mtime(false);
+
+    // `Entry::unpack_in` takes care of not writing outside of the directory it unpacks into, e.g.
+    // through a symlink unpacked earlier, but it unpacks the entries at their path in the
+    // archive. So with `strip_prefix`, unpack into a staging directory and move the prefix to
+    // `dest` afterwards.
+    let staging = match strip_prefix {
+        None => dest,
+        Some(_) => {
+            fs_util::remove_all(staging)?;
+            fs_util::create_dir_all(staging)?;
+            staging
+        }
+    };
+
+    let mut extracted = 0;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let rel = match entry_dest(&entry.path()?, strip_prefix)? {
+            Some(rel) => rel,
+            None => continue,
+        };
+        match entry.header().entry_type() {
+            tar::EntryType::Symlink => {
+                let target = entry
+                    .link_name()?
+                    .context("Symlink without a target")?
+                    .into_owned();
+                if symlink_escapes(&rel, &target) {
+                    return Err(DownloadArchiveActionError::UnsafeSymlink {
+                        path: rel.display().to_string(),
+                        target: target.display().to_string(),
+                    }
+                    .into());
+                }
+            }
+            tar::EntryType::Link => {
+                // Hard links point at an earlier entry of the archive, which must have been
+                // unpacked too.
+                let target = entry
+                    .link_name()?
+                    .context("Hard link without a target")?
+                    .into_owned();
+                entry_dest(&target, strip_prefix)?.with_context(|| {
+                    format!(
+                        "Hard link `{}` points outside of `strip_prefix`",
+                        rel.display()
+                    )
+                })?;
+            }
+            _ => {}
+        }
+        if !entry.unpack_in(staging)? {
+            return Err(DownloadArchiveActionError::UnsafeEntry(rel.display().to_string()).into());
+        }
+        extracted += 1;
+    }
+
+    if let Some(prefix) = strip_prefix {
+        if extracted != 0 {
+            fs_util::remove_dir_all(dest)?;
+            fs_util::rename(staging.join(prefix), dest)?;
+        }
+        fs_util::remove_dir_all(staging)?;
+    }
+    Ok(extracted)
+}
+
+/// Whether a symlink unpacked at `path`, relative to the output directory, and pointing at
+/// `target` resolves to something outside of the output directory.
+fn symlink_escapes(path: &Path, target: &Path) -> bool {
+    // The depth of the directory containing the symlink.
+    let mut depth = path.components().count().saturating_sub(1);
+    for component in target.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir => match depth.checked_sub(1) {
+                Some(d) => depth = d,
+                None => return true,
+            },
+            Component::RootDir | Component::Prefix(_) => return true,
+        }
+    }
+    false
+}
+
+/// The file type bits of a Unix mode, and their value for symlinks.
+const S_IFMT: u32 = 0o170000;
+const S_IFLNK: u32 = 0o120000;
+
+fn unpack_zip(
+    file: FileReadGuard,
+    strip_prefix: Option<&Path>,
+    dest: &AbsPath,
+) -> anyhow::Result<usize> {
+    let mut archive = zip::ZipArchive::new(file)?;
+    let mut extracted = 0;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        let name = entry
+            .enclosed_name()
+            .ok_or_else(|| DownloadArchiveActionError::UnsafeEntry(entry.name().to_owned()))?
+            .to_owned();
+        let rel = match entry_dest(&name, strip_prefix)? {
+            Some(rel) => rel,
+            None => continue,
+        };
+        let path = dest.join(&rel);
+        // Like in tar archives, symlinks are stored with their target as content.
+        if entry
+            .unix_mode()
+            .map_or(false, |mode| mode & S_IFMT == S_IFLNK)
+        {
+            let mut target = String::new();
+            entry.read_to_string(&mut target)?;
+            let target = PathBuf::from(target);
+            if symlink_escapes(&rel, &target) {
+                return Err(DownloadArchiveActionError::UnsafeSymlink {
+                    path: rel.display().to_string(),
+                    target: target.display().to_string(),
+                }
+                .into());
+            }
+            if let Some(parent) = path.parent() {
+                fs_util::create_dir_all(parent)?;
+            }
+            fs_util::symlink(&target, &path)?;
+        } else if entry.is_dir() {
+            fs_util::create_dir_all(&path)?;
+        } else {
+            if let Some(parent) = path.parent() {
+                fs_util::create_dir_all(parent)?;
+            }
+            io::copy(&mut entry, &mut fs_util::create_file(&path)?)?;
+            #[cfg(unix)]
+            if let Some(mode) = entry.unix_mode() {
+                use std::os::unix::fs::PermissionsExt;
+                fs_util::set_permissions(&path, std::fs::Permissions::from_mode(mode & 0o777))?;
+            }
+        }
+        extracted += 1;
+    }
+    Ok(extracted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_type_from_url() {
+        assert_eq!(
+            ArchiveType::TarGz,
+            ArchiveType::from_url("https://example.com/clang-17.0.6.tar.gz").unwrap()
+        );
+        assert_eq!(
+            ArchiveType::TarZst,
+            ArchiveType::from_url("https://example.com/rust.tar.zst?raw=1").unwrap()
+        );
+        assert_eq!(
+            ArchiveType::Zip,
+            ArchiveType::from_url("https://example.com/python.zip").unwrap()
+        );
+        assert!(ArchiveType::from_url("https://example.com/star").is_err());
+    }
+
+    #[test]
+    fn test_archive_type_parse() {
+        assert_eq!(ArchiveType::TarZst, ArchiveType::parse("tzst").unwrap());
+        let err = ArchiveType::parse("rar").unwrap_err().to_string();
+        for (suffix, _) in ArchiveType::SUFFIXES {
+            assert!(err.contains(&format!("`{}`", suffix)), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_entry_dest() {
+        assert_eq!(
+            Some(PathBuf::from("bin/clang")),
+            entry_dest(
+                Path::new("./clang-17/bin/clang"),
+                Some(Path::new("clang-17"))
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            None,
+            entry_dest(Path::new("clang-17"), Some(Path::new("clang-17"))).unwrap()
+        );
+        assert_eq!(
+            None,
+            entry_dest(Path::new("other/bin"), Some(Path::new("clang-17"))).unwrap()
+        );
+        assert!(entry_dest(Path::new("../etc/passwd"), None).is_err());
+        assert!(entry_dest(Path::new("/etc/passwd"), None).is_err());
+    }
+
+    #[test]
+    fn test_symlink_escapes() {
+        assert!(!symlink_escapes(
+            Path::new("bin/clang++"),
+            Path::new("clang")
+        ));
+        assert!(!symlink_escapes(
+            Path::new("lib/libc++.so"),
+            Path::new("../lib64/./libc++.so.1")
+        ));
+        assert!(!symlink_escapes(
+            Path::new("a/b/link"),
+            Path::new("../../a/../c")
+        ));
+        assert!(symlink_escapes(Path::new("link"), Path::new("../etc")));
+        assert!(symlink_escapes(
+            Path::new("bin/link"),
+            Path::new("../../etc/passwd")
+        ));
+        assert!(symlink_escapes(
+            Path::new("bin/link"),
+            Path::new("/usr/bin/clang")
+        ));
+        // Intermediate components going above the output directory escape too.
+        assert!(symlink_escapes(
+            Path::new("bin/link"),
+            Path::new("../../app/bin")
+        ));
+    }
+}
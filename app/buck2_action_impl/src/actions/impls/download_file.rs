@@ -30,6 +30,8 @@ use buck2_common::file_ops::FileMetadata;
 use buck2_common::file_ops::TrackedFileDigest;
 use buck2_common::io::trace::TracingIoProvider;
 use buck2_core::category::Category;
+use buck2_core::fs::fs_util;
+use buck2_execute::artifact::fs::ExecutorFs;
 use buck2_execute::artifact_value::ArtifactValue;
 use buck2_execute::digest_config::DigestConfig;
 use buck2_execute::execute::command_executor::ActionExecutionTimingData;
@@ -37,8 +39,10 @@ use buck2_execute::materialize::http::http_download;
 use buck2_execute::materialize::http::http_head;
 use buck2_execute::materialize::http::Checksum;
 use buck2_execute::materialize::materializer::HttpDownloadInfo;
+use buck2_execute::materialize::signature::Signature;
 use buck2_http::HttpClient;
 use dupe::Dupe;
+use indexmap::IndexMap;
 use indexmap::IndexSet;
 use once_cell::sync::Lazy;
 use starlark::values::OwnedFrozenValue;
@@ -58,6 +62,7 @@ pub(crate) struct UnregisteredDownloadFileAction {
     checksum: Checksum,
     url: Arc<str>,
     vpnless_url: Option<Arc<str>>,
+    signature: Option<Signature>,
     is_executable: bool,
     is_deferrable: bool,
 }
@@ -67,6 +72,7 @@ impl UnregisteredDownloadFileAction {
         checksum: Checksum,
         url: Arc<str>,
         vpnless_url: Option<Arc<str>>,
+        signature: Option<Signature>,
         is_executable: bool,
         is_deferrable: bool,
     ) -> Self {
@@ -74,6 +80,7 @@ impl UnregisteredDownloadFileAction {
             checksum,
             url,
             vpnless_url,
+            signature,
             is_executable,
             is_deferrable,
         }
@@ -143,7 +150,9 @@ impl DownloadFileAction {
         client: &HttpClient,
         digest_config: DigestConfig,
     ) -> anyhow::Result<Option<FileMetadata>> {
-        if !self.inner.is_deferrable {
+        // Signed downloads can't be deferred: the signature has to be checked before anything
+        // gets to use the file.
+        if !self.inner.is_deferrable || self.inner.signature.is_some() {
             return Ok(None);
         }
 
@@ -253,6 +262,33 @@ impl Action for DownloadFileAction {
             .next()
             .map(|o| o.get_path().path().as_str())
     }
+
+    fn aquery_attributes(&self, _fs: &ExecutorFs) -> IndexMap<String, String> {
+        let mut attrs = IndexMap::new();
+        attrs.insert("url".to_owned(), self.inner.url.to_string());
+        if let Some(vpnless_url) = &self.inner.vpnless_url {
+            attrs.insert("vpnless_url".to_owned(), vpnless_url.to_string());
+        }
+        if let Some(sha1) = self.inner.checksum.sha1() {
+            attrs.insert("sha1".to_owned(), sha1.to_owned());
+        }
+        if let Some(sha256) = self.inner.checksum.sha256() {
+            attrs.insert("sha256".to_owned(), sha256.to_owned());
+        }
+        if let Some(signature) = &self.inner.signature {
+            attrs.insert("signature_kind".to_owned(), signature.kind.to_string());
+            attrs.insert("signature_url".to_owned(), signature.url.to_string());
+            attrs.insert(
+                "signature_public_key".to_owned(),
+                signature.public_key.to_string(),
+            );
+        }
+        attrs.insert(
+            "is_executable".to_owned(),
+            self.inner.is_executable.to_string(),
+        );
+        attrs
+    }
 }
 
 #[async_trait]
@@ -314,6 +350,15 @@ impl IncrementalActionExecutable for DownloadFileAction {
                     )
                     .await?;
 
+                    if let Some(signature) = &self.inner.signature {
+                        if let Err(e) = signature.verify(&client, project_fs, &rel_path, url).await
+                        {
+                            // Don't leave an untrusted file lying around in buck-out.
+                            let _ignored = fs_util::remove_file(project_fs.resolve(&rel_path));
+                            return Err(e.into());
+                        }
+                    }
+
                     let metadata = FileMetadata {
                         digest,
                         is_executable: self.inner.is_executable,
@@ -10,6 +10,7 @@
 use std::borrow::Cow;
 use std::fmt::Display;
 use std::ops::ControlFlow;
+use std::time::Duration;
 
 use allocative::Allocative;
 use anyhow::Context;
@@ -44,7 +45,6 @@ use buck2_events::dispatch::span_async;
 use buck2_execute::artifact::fs::ExecutorFs;
 use buck2_execute::execute::action_digest::ActionDigest;
 use buck2_execute::execute::cache_uploader::force_cache_upload;
-use buck2_execute::execute::environment_inheritance::EnvironmentInheritance;
 use buck2_execute::execute::manager::CommandExecutionManager;
 use buck2_execute::execute::request::ActionMetadataBlob;
 use buck2_execute::execute::request::CommandExecutionInput;
@@ -167,6 +167,12 @@ pub(crate) struct UnregisteredRunAction {
     pub(crate) force_full_hybrid_if_capable: bool,
     pub(crate) unique_input_inodes: bool,
     pub(crate) remote_execution_dependencies: Vec<RemoteExecutorDependency>,
+    pub(crate) stream_output: bool,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) reproducible: bool,
+    pub(crate) scratch: bool,
+    /// Client environment variables forwarded to the action.
+    pub(crate) env_passthrough: Vec<String>,
 }
 
 impl UnregisteredAction for UnregisteredRunAction {
@@ -397,6 +403,28 @@ impl RunAction {
             extra_env.push((metadata_param.env_var.to_owned(), env));
         }
 
+        let knobs = ctx.run_action_knobs();
+        if knobs.reproducible && self.inner.reproducible {
+            let source_date_epoch = knobs.source_date_epoch.to_string();
+            for (k, v) in [
+                ("SOURCE_DATE_EPOCH", source_date_epoch.as_str()),
+                ("TZ", "UTC"),
+            ] {
+                // Values set by the action itself take precedence.
+                if !expanded.env.contains_key(k) {
+                    extra_env.push((k.to_owned(), v.to_owned()));
+                }
+            }
+        }
+
+        // Client environment variables the action declared a dependency on. They are part of the
+        // command, so a change in their values changes the action digest.
+        for (k, v) in ctx.passthrough_env() {
+            if !expanded.env.contains_key(k) {
+                extra_env.push((k.to_owned(), v.to_owned()));
+            }
+        }
+
         let scratch = ctx.target().scratch_path();
         let scratch_path = fs.buck_out_path_resolver().resolve_scratch(&scratch);
         extra_env.push((
@@ -586,8 +614,22 @@ impl Action for RunAction {
             .add_to_command_line(&mut cli_rendered, &mut ctx)
             .unwrap();
         let cmd = format!("[{}]", cli_rendered.iter().join(", "));
+        let env = values
+            .env
+            .iter()
+            .map(|(k, v)| {
+                let mut rendered = String::new();
+                v.add_to_command_line(
+                    &mut SpaceSeparatedCommandLineBuilder::wrap_string(&mut rendered),
+                    &mut ctx,
+                )
+                .unwrap();
+                format!("{}={}", k, rendered)
+            })
+            .join(", ");
         indexmap! {
             "cmd".to_owned() => cmd,
+            "env".to_owned() => format!("[{}]", env),
             "executor_preference".to_owned() => self.inner.executor_preference.to_string(),
             "always_print_stderr".to_owned() => self.inner.always_print_stderr.to_string(),
             "weight".to_owned() => self.inner.weight.to_string(),
@@ -599,9 +641,21 @@ impl Action for RunAction {
             "no_outputs_cleanup".to_owned() => self.inner.no_outputs_cleanup.to_string(),
             "allow_cache_upload".to_owned() => self.inner.allow_cache_upload.to_string(),
             "allow_dep_file_cache_upload".to_owned() => self.inner.allow_dep_file_cache_upload.to_string(),
+            "stream_output".to_owned() => self.inner.stream_output.to_string(),
+            "timeout".to_owned() => match self.inner.timeout {
+                None => "None".to_owned(),
+                Some(x) => format!("{}s", x.as_secs()),
+            },
+            "reproducible".to_owned() => self.inner.reproducible.to_string(),
+            "scratch".to_owned() => self.inner.scratch.to_string(),
+            "env_passthrough".to_owned() => format!("[{}]", self.inner.env_passthrough.iter().join(", ")),
         }
     }
 
+    fn env_passthrough(&self) -> &[String] {
+        &self.inner.env_passthrough
+    }
+
     fn error_handler(&self) -> Option<OwnedFrozenValue> {
         self.error_handler.clone()
     }
@@ -630,6 +684,16 @@ impl IncrementalActionExecutable for RunAction {
         // Run actions are assumed to be shared
         let host_sharing_requirements = HostSharingRequirements::Shared(self.inner.weight);
 
+        let stream_output = if self.inner.stream_output {
+            let owner = ctx.target().owner();
+            Some(match owner.unpack_target_label() {
+                Some(label) => label.unconfigured().to_string(),
+                None => owner.to_string(),
+            })
+        } else {
+            None
+        };
+
         let req = prepared_run_action
             .into_command_execution_request()
             .with_prefetch_lossy_stderr(true)
@@ -637,10 +701,30 @@ impl IncrementalActionExecutable for RunAction {
             .with_host_sharing_requirements(host_sharing_requirements)
             .with_low_pass_filter(self.inner.low_pass_filter)
             .with_outputs_cleanup(!self.inner.no_outputs_cleanup)
-            .with_local_environment_inheritance(EnvironmentInheritance::local_command_exclusions())
+            .with_local_environment_inheritance(knobs.local_env_inheritance())
             .with_force_full_hybrid_if_capable(self.inner.force_full_hybrid_if_capable)
+            .with_hybrid_policy(
+                knobs
+                    .category_hybrid_policies
+                    .get(self.inner.category.as_str())
+                    .copied(),
+            )
             .with_unique_input_inodes(self.inner.unique_input_inodes)
-            .with_remote_execution_dependencies(self.inner.remote_execution_dependencies.clone());
+            .with_remote_execution_dependencies(self.inner.remote_execution_dependencies.clone())
+            .with_stream_output(stream_output)
+            .with_reproducible(knobs.reproducible && self.inner.reproducible)
+            .with_declared_scratch(self.inner.scratch);
+
+        let timeout = self.inner.timeout.or_else(|| {
+            knobs
+                .category_timeouts
+                .get(self.inner.category.as_str())
+                .copied()
+        });
+        let req = match timeout {
+            Some(timeout) => req.with_timeout(timeout),
+            None => req,
+        };
 
         let (mut dep_file_bundle, req) = if let Some(visitor) = dep_file_visitor {
             let bundle = make_dep_file_bundle(ctx, visitor, cmdline_digest, req.paths())?;
@@ -8,6 +8,8 @@
  */
 
 use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::time::Instant;
 
 use allocative::Allocative;
 use anyhow::Context as _;
@@ -27,17 +29,25 @@ use buck2_build_api::artifact_groups::ArtifactGroup;
 use buck2_build_api::interpreter::rule_defs::artifact::associated::AssociatedArtifacts;
 use buck2_build_api::interpreter::rule_defs::artifact::starlark_artifact_like::ValueAsArtifactLike;
 use buck2_core::category::Category;
+use buck2_core::fs::fs_util;
 use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
 use buck2_core::fs::paths::forward_rel_path::ForwardRelativePathBuf;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
 use buck2_execute::artifact::artifact_dyn::ArtifactDyn;
+use buck2_execute::artifact::fs::ExecutorFs;
 use buck2_execute::artifact_utils::ArtifactValueBuilder;
 use buck2_execute::execute::command_executor::ActionExecutionTimingData;
 use buck2_execute::materialize::materializer::CopiedArtifact;
+use buck2_execute::materialize::materializer::WriteRequest;
 use dupe::Dupe;
 use gazebo::prelude::*;
+use indexmap::indexmap;
+use indexmap::IndexMap;
 use indexmap::IndexSet;
 use itertools::Itertools;
 use once_cell::sync::Lazy;
+use relative_path::RelativePath;
+use relative_path::RelativePathBuf;
 use starlark::values::dict::UnpackDictEntries;
 use starlark::values::OwnedFrozenValue;
 use starlark::values::ValueError;
@@ -56,6 +66,8 @@ enum SymlinkedDirError {
 #[derive(Allocative)]
 pub(crate) struct UnregisteredSymlinkedDirAction {
     copy: bool,
+    /// Whether the action also writes a runfiles manifest, as its second output.
+    manifest: bool,
     args: Vec<(ArtifactGroup, Box<ForwardRelativePath>)>,
     // All associated artifacts of inputs unioned together
     unioned_associated_artifacts: AssociatedArtifacts,
@@ -143,11 +155,22 @@ impl UnregisteredSymlinkedDirAction {
         }
         Ok(Self {
             copy,
+            manifest: false,
             args,
             unioned_associated_artifacts: AssociatedArtifacts::from(unioned_associated_artifacts),
         })
     }
 
+    /// A symlinked dir which also writes a manifest of its contents, see [`runfiles_manifest`].
+    pub(crate) fn runfiles<'v>(
+        srcs: UnpackDictEntries<&'v str, ValueAsArtifactLike<'v>>,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            manifest: true,
+            ..Self::new(false, srcs)?
+        })
+    }
+
     pub(crate) fn inputs(&self) -> IndexSet<ArtifactGroup> {
         self.args.iter().map(|x| x.0.dupe()).collect()
     }
@@ -167,6 +190,7 @@ impl UnregisteredAction for UnregisteredSymlinkedDirAction {
     ) -> anyhow::Result<Box<dyn Action>> {
         Ok(Box::new(SymlinkedDirAction {
             copy: self.copy,
+            manifest: self.manifest,
             args: self.args,
             inputs: BoxSliceSet::from(inputs),
             outputs: BoxSliceSet::from(outputs),
@@ -177,6 +201,7 @@ impl UnregisteredAction for UnregisteredSymlinkedDirAction {
 #[derive(Debug, Allocative)]
 struct SymlinkedDirAction {
     copy: bool,
+    manifest: bool,
     args: Vec<(ArtifactGroup, Box<ForwardRelativePath>)>,
     inputs: BoxSliceSet<ArtifactGroup>,
     outputs: BoxSliceSet<BuildArtifact>,
@@ -189,6 +214,19 @@ impl SymlinkedDirAction {
             .next()
             .expect("a single artifact by construction")
     }
+
+    fn manifest_output(&self) -> Option<&BuildArtifact> {
+        if self.manifest {
+            Some(
+                self.outputs
+                    .iter()
+                    .nth(1)
+                    .expect("a manifest artifact by construction"),
+            )
+        } else {
+            None
+        }
+    }
 }
 
 #[async_trait]
@@ -212,13 +250,32 @@ impl Action for SymlinkedDirAction {
     fn category(&self) -> &Category {
         static SYMLINKED_DIR_CATEGORY: Lazy<Category> =
             Lazy::new(|| Category::try_from("symlinked_dir").unwrap());
+        static RUNFILES_CATEGORY: Lazy<Category> =
+            Lazy::new(|| Category::try_from("runfiles").unwrap());
 
-        &SYMLINKED_DIR_CATEGORY
+        if self.manifest {
+            &RUNFILES_CATEGORY
+        } else {
+            &SYMLINKED_DIR_CATEGORY
+        }
     }
 
     fn identifier(&self) -> Option<&str> {
         Some(self.output().get_path().path().as_str())
     }
+
+    fn aquery_attributes(&self, _fs: &ExecutorFs) -> IndexMap<String, String> {
+        let srcs = self
+            .args
+            .iter()
+            .map(|(input, path)| format!("{}={}", path, input))
+            .join(", ");
+        indexmap! {
+            "copy".to_owned() => self.copy.to_string(),
+            "manifest".to_owned() => self.manifest.to_string(),
+            "srcs".to_owned() => format!("[{}]", srcs),
+        }
+    }
 }
 
 #[async_trait]
@@ -231,6 +288,7 @@ impl IncrementalActionExecutable for SymlinkedDirAction {
         let output = ctx.fs().resolve_build(self.output().get_path());
         let mut builder = ArtifactValueBuilder::new(fs, ctx.digest_config());
         let mut srcs = Vec::new();
+        let mut linked = Vec::new();
 
         for (group, dest) in &self.args {
             let (src_artifact, value) = ctx
@@ -240,6 +298,9 @@ impl IncrementalActionExecutable for SymlinkedDirAction {
                 .context("Input did not dereference to exactly one artifact")?;
 
             let src = src_artifact.resolve_path(ctx.fs())?;
+            if self.manifest {
+                linked.push((dest, src.clone()));
+            }
             let dest = output.join(dest);
 
             if self.copy {
@@ -256,18 +317,85 @@ impl IncrementalActionExecutable for SymlinkedDirAction {
 
         let value = builder.build(output.as_ref())?;
         ctx.materializer()
-            .declare_copy(output, value.dupe(), srcs, ctx.cancellation_context())
+            .declare_copy(
+                output.clone(),
+                value.dupe(),
+                srcs,
+                ctx.cancellation_context(),
+            )
             .await?;
+
+        let Some(manifest) = self.manifest_output() else {
+            return Ok((
+                ActionOutputs::from_single(self.output().get_path().dupe(), value),
+                ActionExecutionMetadata {
+                    execution_kind: ActionExecutionKind::Simple,
+                    timing: ActionExecutionTimingData::default(),
+                },
+            ));
+        };
+
+        let execution_start = Instant::now();
+        let manifest_path = ctx.fs().resolve_build(manifest.get_path());
+        let relative = |target: &ProjectRelativePath| {
+            let path = fs.relative_path(target, &manifest_path);
+            anyhow::Ok(fs_util::relative_path_from_system(&path)?.into_owned())
+        };
+        let root = relative(&output)?;
+        let files = linked
+            .iter()
+            .map(|(dest, src)| Ok((dest.as_str(), relative(src)?)))
+            .collect::<anyhow::Result<_>>()?;
+        let content = runfiles_manifest(&root, &files)?;
+        let manifest_value = ctx
+            .materializer()
+            .declare_write(Box::new(|| {
+                Ok(vec![WriteRequest {
+                    path: manifest_path,
+                    content: content.into_bytes(),
+                    is_executable: false,
+                }])
+            }))
+            .await?
+            .into_iter()
+            .next()
+            .context("Write did not execute")?;
+
         Ok((
-            ActionOutputs::from_single(self.output().get_path().dupe(), value),
+            ActionOutputs::new(indexmap![
+                self.output().get_path().dupe() => value,
+                manifest.get_path().dupe() => manifest_value,
+            ]),
             ActionExecutionMetadata {
                 execution_kind: ActionExecutionKind::Simple,
-                timing: ActionExecutionTimingData::default(),
+                timing: ActionExecutionTimingData {
+                    wall_time: execution_start.elapsed(),
+                },
             },
         ))
     }
 }
 
+/// Contents of a runfiles manifest, version 1: a JSON object with the `root` directory of the
+/// runfiles tree and `files`, mapping each path in the tree to the path it links to. Paths in
+/// the tree are relative to `root`, all other paths are relative to the directory containing the
+/// manifest, so that the tree and the manifest can be relocated together.
+fn runfiles_manifest(
+    root: &RelativePath,
+    files: &BTreeMap<&str, RelativePathBuf>,
+) -> anyhow::Result<String> {
+    let mut manifest = serde_json::to_string_pretty(&serde_json::json!({
+        "version": 1,
+        "root": root.as_str(),
+        "files": files
+            .iter()
+            .map(|(dest, src)| (*dest, src.as_str()))
+            .collect::<BTreeMap<_, _>>(),
+    }))?;
+    manifest.push('\n');
+    Ok(manifest)
+}
+
 #[cfg(test)]
 mod tests {
     use buck2_artifact::artifact::artifact_type::Artifact;
@@ -309,4 +437,25 @@ mod tests {
         assert!(validate(&["test", "other", "test"]).is_err());
         assert!(validate(&["test", "test/child"]).is_err());
     }
+
+    #[test]
+    fn test_runfiles_manifest() -> anyhow::Result<()> {
+        let files = BTreeMap::from([
+            ("lib/data.txt", RelativePathBuf::from("../../pkg/data.txt")),
+            ("bin/tool", RelativePathBuf::from("../__tool__/tool")),
+        ]);
+        let manifest = runfiles_manifest(RelativePath::new("tool.runfiles"), &files)?;
+        assert_eq!(
+            serde_json::json!({
+                "version": 1,
+                "root": "tool.runfiles",
+                "files": {
+                    "bin/tool": "../__tool__/tool",
+                    "lib/data.txt": "../../pkg/data.txt",
+                },
+            }),
+            serde_json::from_str::<serde_json::Value>(&manifest)?
+        );
+        Ok(())
+    }
 }
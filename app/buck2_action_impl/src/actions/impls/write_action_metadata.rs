@@ -0,0 +1,195 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::borrow::Cow;
+use std::slice;
+use std::time::Instant;
+
+use allocative::Allocative;
+use anyhow::Context;
+use async_trait::async_trait;
+use buck2_artifact::artifact::artifact_type::Artifact;
+use buck2_artifact::artifact::build_artifact::BuildArtifact;
+use buck2_build_api::actions::box_slice_set::BoxSliceSet;
+use buck2_build_api::actions::execute::action_executor::ActionExecutionKind;
+use buck2_build_api::actions::execute::action_executor::ActionExecutionMetadata;
+use buck2_build_api::actions::execute::action_executor::ActionOutputs;
+use buck2_build_api::actions::execute::error::ExecuteError;
+use buck2_build_api::actions::Action;
+use buck2_build_api::actions::ActionExecutable;
+use buck2_build_api::actions::ActionExecutionCtx;
+use buck2_build_api::actions::IncrementalActionExecutable;
+use buck2_build_api::actions::UnregisteredAction;
+use buck2_build_api::artifact_groups::ArtifactGroup;
+use buck2_core::category::Category;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+use buck2_execute::artifact::artifact_dyn::ArtifactDyn;
+use buck2_execute::artifact_value::ArtifactValue;
+use buck2_execute::execute::command_executor::ActionExecutionTimingData;
+use buck2_execute::materialize::materializer::WriteRequest;
+use buck2_execute::output_size::OutputSize;
+use dupe::Dupe;
+use indexmap::indexmap;
+use indexmap::IndexSet;
+use once_cell::sync::Lazy;
+use serde_json::json;
+use starlark::values::OwnedFrozenValue;
+
+#[derive(Debug, buck2_error::Error)]
+enum WriteActionMetadataActionValidationError {
+    #[error("WriteActionMetadataAction received no outputs")]
+    NoOutputs,
+    #[error("WriteActionMetadataAction received too many outputs")]
+    TooManyOutputs,
+}
+
+#[derive(Allocative, Debug)]
+pub(crate) struct UnregisteredWriteActionMetadataAction;
+
+impl UnregisteredAction for UnregisteredWriteActionMetadataAction {
+    fn register(
+        self: Box<Self>,
+        inputs: IndexSet<ArtifactGroup>,
+        outputs: IndexSet<BuildArtifact>,
+        _starlark_data: Option<OwnedFrozenValue>,
+        _error_handler: Option<OwnedFrozenValue>,
+    ) -> anyhow::Result<Box<dyn Action>> {
+        Ok(Box::new(WriteActionMetadataAction::new(inputs, outputs)?))
+    }
+}
+
+/// Writes, as JSON, the paths, owners, sizes and digests of its inputs.
+#[derive(Debug, Allocative)]
+struct WriteActionMetadataAction {
+    inputs: BoxSliceSet<ArtifactGroup>,
+    output: BuildArtifact,
+}
+
+impl WriteActionMetadataAction {
+    fn new(
+        inputs: IndexSet<ArtifactGroup>,
+        outputs: IndexSet<BuildArtifact>,
+    ) -> anyhow::Result<Self> {
+        let mut outputs = outputs.into_iter();
+
+        let output = match (outputs.next(), outputs.next()) {
+            (Some(o), None) => o,
+            (None, ..) => return Err(WriteActionMetadataActionValidationError::NoOutputs.into()),
+            (Some(..), Some(..)) => {
+                return Err(WriteActionMetadataActionValidationError::TooManyOutputs.into());
+            }
+        };
+
+        Ok(WriteActionMetadataAction {
+            inputs: BoxSliceSet::from(inputs),
+            output,
+        })
+    }
+
+    /// The artifacts of the inputs, each once.
+    fn input_artifacts<'c>(
+        &self,
+        ctx: &'c dyn ActionExecutionCtx,
+    ) -> anyhow::Result<Vec<(ProjectRelativePathBuf, &'c Artifact, &'c ArtifactValue)>> {
+        let mut seen = IndexSet::new();
+        let mut artifacts = Vec::new();
+        for input in self.inputs.iter() {
+            for (artifact, value) in ctx.artifact_values(input).iter() {
+                let path = artifact.resolve_path(ctx.fs())?;
+                if seen.insert(path.clone()) {
+                    artifacts.push((path, artifact, value));
+                }
+            }
+        }
+        Ok(artifacts)
+    }
+
+    fn get_contents(&self, ctx: &dyn ActionExecutionCtx) -> anyhow::Result<Vec<u8>> {
+        let artifacts: Vec<_> = self
+            .input_artifacts(ctx)?
+            .into_iter()
+            .map(|(path, artifact, value)| {
+                json!({
+                    "path": path.as_str(),
+                    "owner": artifact.owner().map(|o| o.to_string()),
+                    "size": value.calc_output_count_and_bytes().bytes,
+                    "digest": value.digest().map(|d| d.to_string()),
+                })
+            })
+            .collect();
+        Ok(serde_json::to_vec_pretty(
+            &json!({ "artifacts": artifacts }),
+        )?)
+    }
+}
+
+#[async_trait]
+impl Action for WriteActionMetadataAction {
+    fn kind(&self) -> buck2_data::ActionKind {
+        buck2_data::ActionKind::WriteActionMetadata
+    }
+
+    fn inputs(&self) -> anyhow::Result<Cow<'_, [ArtifactGroup]>> {
+        Ok(Cow::Borrowed(self.inputs.as_slice()))
+    }
+
+    fn outputs(&self) -> anyhow::Result<Cow<'_, [BuildArtifact]>> {
+        Ok(Cow::Borrowed(slice::from_ref(&self.output)))
+    }
+
+    fn as_executable(&self) -> ActionExecutable<'_> {
+        ActionExecutable::Incremental(self)
+    }
+
+    fn category(&self) -> &Category {
+        static WRITE_ACTION_METADATA_CATEGORY: Lazy<Category> =
+            Lazy::new(|| Category::try_from("write_action_metadata").unwrap());
+
+        &WRITE_ACTION_METADATA_CATEGORY
+    }
+
+    fn identifier(&self) -> Option<&str> {
+        Some(self.output.get_path().path().as_str())
+    }
+}
+
+#[async_trait]
+impl IncrementalActionExecutable for WriteActionMetadataAction {
+    async fn execute(
+        &self,
+        ctx: &mut dyn ActionExecutionCtx,
+    ) -> Result<(ActionOutputs, ActionExecutionMetadata), ExecuteError> {
+        let fs = ctx.fs();
+        let execution_start = Instant::now();
+        let content = self.get_contents(ctx)?;
+        let value = ctx
+            .materializer()
+            .declare_write(Box::new(|| {
+                Ok(vec![WriteRequest {
+                    path: fs.resolve_build(self.output.get_path()),
+                    content,
+                    is_executable: false,
+                }])
+            }))
+            .await?
+            .into_iter()
+            .next()
+            .context("WriteActionMetadata did not execute")?;
+
+        Ok((
+            ActionOutputs::new(indexmap![self.output.get_path().dupe() => value]),
+            ActionExecutionMetadata {
+                execution_kind: ActionExecutionKind::Simple,
+                timing: ActionExecutionTimingData {
+                    wall_time: execution_start.elapsed(),
+                },
+            },
+        ))
+    }
+}
@@ -39,7 +39,10 @@ use buck2_execute::artifact::fs::ExecutorFs;
 use buck2_execute::execute::command_executor::ActionExecutionTimingData;
 use buck2_execute::materialize::materializer::WriteRequest;
 use dupe::Dupe;
+use indexmap::indexmap;
+use indexmap::IndexMap;
 use indexmap::IndexSet;
+use itertools::Itertools;
 use once_cell::sync::Lazy;
 use starlark::values::OwnedFrozenValue;
 use starlark::values::UnpackValue;
@@ -154,6 +157,22 @@ impl Action for WriteMacrosToFileAction {
     fn identifier(&self) -> Option<&str> {
         Some(&self.identifier)
     }
+
+    fn aquery_attributes(&self, fs: &ExecutorFs) -> IndexMap<String, String> {
+        let mut contents = Vec::with_capacity(self.outputs.len());
+        let res: anyhow::Result<()> = try {
+            ValueAsCommandLineLike::unpack_value_err(self.contents.value())?
+                .0
+                .visit_write_to_file_macros(&mut MacroToFileWriter::new(fs, &mut contents))?
+        };
+        // TODO(cjhopman): We should change this api to support returning a Result.
+        indexmap! {
+            "contents".to_owned() => match res {
+                Ok(()) => format!("[{}]", contents.iter().join(", ")),
+                Err(e) => format!("ERROR: constructing contents ({})", e),
+            },
+        }
+    }
 }
 
 #[async_trait]
@@ -7,12 +7,15 @@
  * of this source tree.
  */
 
+use buck2_build_api::artifact_groups::ArtifactGroup;
 use buck2_build_api::interpreter::rule_defs::artifact::associated::AssociatedArtifacts;
 use buck2_build_api::interpreter::rule_defs::artifact::output_artifact_like::OutputArtifactArg;
+use buck2_build_api::interpreter::rule_defs::artifact::starlark_artifact_like::StarlarkArtifactLike;
 use buck2_build_api::interpreter::rule_defs::artifact::starlark_artifact_like::ValueAsArtifactLike;
 use buck2_build_api::interpreter::rule_defs::artifact::starlark_declared_artifact::StarlarkDeclaredArtifact;
 use buck2_build_api::interpreter::rule_defs::context::AnalysisActions;
 use buck2_execute::execute::request::OutputType;
+use dupe::Dupe;
 use dupe::OptionDupedExt;
 use indexmap::indexset;
 use starlark::environment::MethodsBuilder;
@@ -157,6 +160,52 @@ pub(crate) fn analysis_actions_methods_copy(methods: &mut MethodsBuilder) {
         create_dir_tree(eval, this, output, srcs, false)
     }
 
+    /// Returns a runfiles tree, as a pair of `artifact`s: a directory laid out like
+    /// `symlinked_dir`, and a `manifest` file describing it.
+    ///
+    /// The manifest is a JSON object with a `version` (currently `1`), the `root` directory of
+    /// the tree, and `files`, mapping each path in the tree (relative to `root`) to the path of the
+    /// `artifact` it links to. All other paths are relative to the directory containing the
+    /// manifest, so the tree and the manifest can be relocated together, e.g. by an installer.
+    ///
+    /// The manifest carries the directory as an associated artifact, so passing either of them
+    /// to a `RunInfo`, a test or an install makes the whole tree available. Rules should prefer
+    /// this to inventing their own runfiles layout.
+    fn runfiles<'v>(
+        this: &AnalysisActions<'v>,
+        #[starlark(require = pos)] output: OutputArtifactArg<'v>,
+        #[starlark(require = pos)] srcs: UnpackDictEntries<&'v str, ValueAsArtifactLike<'v>>,
+        #[starlark(require = named)] manifest: OutputArtifactArg<'v>,
+        eval: &mut Evaluator<'v, '_, '_>,
+    ) -> anyhow::Result<(
+        ValueTyped<'v, StarlarkDeclaredArtifact>,
+        ValueTyped<'v, StarlarkDeclaredArtifact>,
+    )> {
+        let action = UnregisteredSymlinkedDirAction::runfiles(srcs)?;
+        let inputs = action.inputs();
+        let unioned_associated_artifacts = action.unioned_associated_artifacts();
+
+        let mut this = this.state();
+        let (dir_declaration, dir_artifact) =
+            this.get_or_declare_output(eval, output, OutputType::Directory)?;
+        let (manifest_declaration, manifest_artifact) =
+            this.get_or_declare_output(eval, manifest, OutputType::File)?;
+        this.register_action(
+            inputs,
+            indexset![dir_artifact, manifest_artifact],
+            action,
+            None,
+            None,
+        )?;
+
+        let dir = dir_declaration.into_declared_artifact(unioned_associated_artifacts.dupe());
+        let manifest =
+            manifest_declaration.into_declared_artifact(unioned_associated_artifacts.union(
+                AssociatedArtifacts::from([ArtifactGroup::Artifact(dir.get_bound_artifact()?)]),
+            ));
+        Ok((dir, manifest))
+    }
+
     /// Returns an `artifact` which is a directory containing copied files.
     /// The srcs must be a dictionary of path (as string, relative to the result directory) to the bound `artifact`, which will be laid out in the directory.
     fn copied_dir<'v>(
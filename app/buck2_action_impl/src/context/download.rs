@@ -16,8 +16,10 @@ use buck2_build_api::interpreter::rule_defs::artifact::starlark_declared_artifac
 use buck2_build_api::interpreter::rule_defs::context::AnalysisActions;
 use buck2_common::cas_digest::CasDigest;
 use buck2_core::execution_types::executor_config::RemoteExecutorUseCase;
+use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
 use buck2_execute::execute::request::OutputType;
 use buck2_execute::materialize::http::Checksum;
+use buck2_execute::materialize::signature::Signature;
 use chrono::TimeZone;
 use chrono::Utc;
 use indexmap::indexset;
@@ -31,6 +33,8 @@ use starlark::values::ValueTyped;
 use crate::actions::impls::cas_artifact::ArtifactKind;
 use crate::actions::impls::cas_artifact::DirectoryKind;
 use crate::actions::impls::cas_artifact::UnregisteredCasArtifactAction;
+use crate::actions::impls::download_archive::ArchiveType;
+use crate::actions::impls::download_archive::UnregisteredDownloadArchiveAction;
 use crate::actions::impls::download_file::UnregisteredDownloadFileAction;
 
 #[derive(buck2_error::Error, Debug)]
@@ -41,6 +45,20 @@ enum CasArtifactError {
     TreeAndDirectory,
 }
 
+#[derive(buck2_error::Error, Debug)]
+enum DownloadFileError {
+    #[error("`signature_url` and `signature_public_key` must be passed together")]
+    #[buck2(input)]
+    IncompleteSignature,
+}
+
+#[derive(buck2_error::Error, Debug)]
+enum DownloadArchiveError {
+    #[error("`strip_prefix` must be a forward relative path, got `{0}`")]
+    #[buck2(input)]
+    InvalidStripPrefix(String),
+}
+
 #[starlark_module]
 pub(crate) fn analysis_actions_methods_download(methods: &mut MethodsBuilder) {
     /// Downloads a URL to an output (filename as string or output artifact). The file at the URL
@@ -48,6 +66,13 @@ pub(crate) fn analysis_actions_methods_download(methods: &mut MethodsBuilder) {
     /// indicates whether the resulting file should be marked with executable permissions.
     /// (Meta-internal) The optional parameter vpnless_url indicates a url from which this resource
     /// can be downloaded off VPN; this has the same restrictions as `url` above.
+    ///
+    /// To additionally check who published the file (e.g. for toolchain archives), pass a detached
+    /// signature with `signature_url` and the key it must be signed with as
+    /// `signature_public_key`. `signature_kind` is either `"minisign"` (the default, the key is the
+    /// base64 public key) or `"gpg"` (the key is ASCII armored). The file is only made available
+    /// to other actions once the signature verifies, which requires the corresponding tool
+    /// (`minisign`, or `gpg` and `gpgv`) to be installed. Signed downloads are never deferred.
     fn download_file<'v>(
         this: &AnalysisActions<'v>,
         #[starlark(require = pos)] output: OutputArtifactArg<'v>,
@@ -55,6 +80,9 @@ pub(crate) fn analysis_actions_methods_download(methods: &mut MethodsBuilder) {
         #[starlark(require = named, default = NoneOr::None)] vpnless_url: NoneOr<&str>,
         #[starlark(require = named, default = NoneOr::None)] sha1: NoneOr<&str>,
         #[starlark(require = named, default = NoneOr::None)] sha256: NoneOr<&str>,
+        #[starlark(require = named, default = NoneOr::None)] signature_url: NoneOr<&str>,
+        #[starlark(require = named, default = NoneOr::None)] signature_public_key: NoneOr<&str>,
+        #[starlark(require = named, default = "minisign")] signature_kind: &str,
         #[starlark(require = named, default = false)] is_executable: bool,
         #[starlark(require = named, default = false)] is_deferrable: bool,
         eval: &mut Evaluator<'v, '_, '_>,
@@ -64,6 +92,14 @@ pub(crate) fn analysis_actions_methods_download(methods: &mut MethodsBuilder) {
             this.get_or_declare_output(eval, output, OutputType::File)?;
 
         let checksum = Checksum::new(sha1.into_option(), sha256.into_option())?;
+        let signature = match (
+            signature_url.into_option(),
+            signature_public_key.into_option(),
+        ) {
+            (Some(url), Some(public_key)) => Some(Signature::new(signature_kind, url, public_key)?),
+            (None, None) => None,
+            _ => return Err(DownloadFileError::IncompleteSignature.into()),
+        };
 
         this.register_action(
             IndexSet::new(),
@@ -72,6 +108,7 @@ pub(crate) fn analysis_actions_methods_download(methods: &mut MethodsBuilder) {
                 checksum,
                 Arc::from(url),
                 vpnless_url.into_option().map(Arc::from),
+                signature,
                 is_executable,
                 is_deferrable,
             ),
@@ -82,6 +119,65 @@ pub(crate) fn analysis_actions_methods_download(methods: &mut MethodsBuilder) {
         Ok(declaration.into_declared_artifact(AssociatedArtifacts::new()))
     }
 
+    /// Downloads an archive from a URL and unpacks it into an output directory. The archive must
+    /// have the given sha1 or sha256 or the command will fail.
+    ///
+    /// * `type`: one of `"tar"`, `"tar.gz"` (or `"tgz"`), `"tar.zst"` or `"zip"`. Inferred from
+    ///   the extension of `url` if not given.
+    /// * `strip_prefix`: only unpack the entries under this directory of the archive, relative to
+    ///   it. Useful for archives which wrap everything in a versioned top-level directory.
+    ///
+    /// The archive is unpacked by Buck2 itself, so no tools need to be installed on the host. This
+    /// makes it possible to use downloaded toolchains for remote actions: the unpacked directory is
+    /// an ordinary artifact, so any action that references it (e.g. through a toolchain's
+    /// `RunInfo`) gets it uploaded as an input, and remote workers don't need the toolchain
+    /// preinstalled.
+    fn download_archive<'v>(
+        this: &AnalysisActions<'v>,
+        #[starlark(require = pos)] output: OutputArtifactArg<'v>,
+        #[starlark(require = pos)] url: &str,
+        #[starlark(require = named, default = NoneOr::None)] vpnless_url: NoneOr<&str>,
+        #[starlark(require = named, default = NoneOr::None)] sha1: NoneOr<&str>,
+        #[starlark(require = named, default = NoneOr::None)] sha256: NoneOr<&str>,
+        #[starlark(require = named, default = NoneOr::None)] r#type: NoneOr<&str>,
+        #[starlark(require = named, default = NoneOr::None)] strip_prefix: NoneOr<&str>,
+        eval: &mut Evaluator<'v, '_, '_>,
+    ) -> anyhow::Result<ValueTyped<'v, StarlarkDeclaredArtifact>> {
+        let mut this = this.state();
+        let (declaration, output_artifact) =
+            this.get_or_declare_output(eval, output, OutputType::Directory)?;
+
+        let checksum = Checksum::new(sha1.into_option(), sha256.into_option())?;
+        let archive_type = match r#type.into_option() {
+            Some(t) => ArchiveType::parse(t)?,
+            None => ArchiveType::from_url(url)?,
+        };
+        let strip_prefix = strip_prefix
+            .into_option()
+            .map(|p| {
+                ForwardRelativePath::new(p)
+                    .map(|p| p.to_buf())
+                    .with_context(|| DownloadArchiveError::InvalidStripPrefix(p.to_owned()))
+            })
+            .transpose()?;
+
+        this.register_action(
+            IndexSet::new(),
+            indexset![output_artifact],
+            UnregisteredDownloadArchiveAction::new(
+                checksum,
+                Arc::from(url),
+                vpnless_url.into_option().map(Arc::from),
+                archive_type,
+                strip_prefix,
+            ),
+            None,
+            None,
+        )?;
+
+        Ok(declaration.into_declared_artifact(AssociatedArtifacts::new()))
+    }
+
     /// Downloads a CAS artifact to an output
     ///
     /// * `digest`: must look like `SHA1:SIZE`
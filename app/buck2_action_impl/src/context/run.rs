@@ -9,6 +9,7 @@
 
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Context;
 use buck2_artifact::artifact::artifact_type::OutputArtifact;
@@ -58,6 +59,8 @@ enum RunActionError {
     InvalidWeight(i32),
     #[error("`weight` and `weight_percentage` cannot both be passed")]
     DuplicateWeightsSpecified,
+    #[error("`timeout_seconds` must be a positive integer, got `{0}`")]
+    InvalidTimeout(i32),
     #[error("`dep_files` value with key `{}` has an invalid count of associated outputs. Expected 1, got {}.", .key, .count)]
     InvalidDepFileOutputs { key: String, count: usize },
     #[error("`dep_files` with keys `{}` and {} are using the same tag", .first, .second)]
@@ -74,6 +77,10 @@ enum RunActionError {
         "Recursion limit exceeded when visiting artifacts: do you have a cycle in your inputs or outputs?"
     )]
     ArtifactVisitRecursionLimitExceeded,
+    #[error("`env_passthrough` contains an invalid environment variable name: `{0}`")]
+    InvalidEnvPassthrough(String),
+    #[error("`{0}` cannot be both set in `env` and listed in `env_passthrough`")]
+    ConflictingEnvPassthrough(String),
 }
 
 #[starlark_module]
@@ -118,6 +125,24 @@ pub(crate) fn analysis_actions_methods_run(methods: &mut MethodsBuilder) {
     ///   Each dependency is dictionary with the following keys:
     ///     * `smc_tier`: name of the SMC tier to call by RE Scheduler.
     ///     * `id`: name of the dependency.
+    /// * `stream_output`: when the action runs locally, forward its stdout and stderr to the
+    ///   console line by line while it runs, each line prefixed with the target, instead of only
+    ///   making them available when it finishes. Useful for long running actions such as large
+    ///   links, whose progress is otherwise invisible. Output of remote actions is not streamed.
+    /// * `timeout_seconds`: fail the action if it runs for longer than this. Local commands are
+    ///   sent `SIGTERM` and then `SIGKILL` after a grace period (configured by
+    ///   `build.action_timeout_kill_grace_period_s`); remote commands are cancelled by Remote
+    ///   Execution. Defaults to the value for the action's category in the `[action_timeouts]`
+    ///   buckconfig section, if any, and otherwise to no timeout.
+    /// * `reproducible`: set to `False` to opt this action out of reproducibility mode
+    ///   (`build.reproducible`), e.g. for actions that stamp the current time. In reproducibility
+    ///   mode, actions get `SOURCE_DATE_EPOCH` (from `build.source_date_epoch`) and `TZ=UTC` unless
+    ///   they set them, and a project-relative `TMPDIR`.
+    /// * `env_passthrough`: names of environment variables to forward from the environment of the
+    ///   client which invoked Buck2. They must be listed in `buck2.env_passthrough` of the root
+    ///   buckconfig. Their values become part of the action's environment (and so of its digest),
+    ///   and the action is re-run when they change. Variables which are not set in the client are
+    ///   not set for the action either. Actions otherwise never see the client's environment.
     ///
     /// When actions execute, they'll do so from the root of the repository. As they execute,
     /// actions have exclusive access to their output directory.
@@ -127,7 +152,12 @@ pub(crate) fn analysis_actions_methods_run(methods: &mut MethodsBuilder) {
     /// directory (i.e. relative to the project). This path is guaranteed to exist when the action
     /// executes.
     ///
-    /// When actions run locally, the scratch path is also used as the `TMPDIR`.
+    /// When actions run locally, the scratch path is also used as the `TMPDIR`. Actions which set
+    /// `scratch = True` declare that they need it: it is then also their temporary directory
+    /// (`TMPDIR`, `TMP` and `TEMP`, unless set in `env`) on Remote Execution, and it is deleted as
+    /// soon as the action finishes instead of being left in `buck-out`. Rules should use it rather
+    /// than the system temporary directory, which is not cleaned up and is not shared with the
+    /// action's outputs on Remote Execution.
     fn run<'v>(
         this: &AnalysisActions<'v>,
         #[starlark(require = pos)] arguments: StarlarkCommandLineValueUnpack<'v>,
@@ -157,6 +187,11 @@ pub(crate) fn analysis_actions_methods_run(methods: &mut MethodsBuilder) {
         eval: &mut Evaluator<'v, '_, '_>,
         #[starlark(require = named, default=UnpackList::default())]
         remote_execution_dependencies: UnpackList<SmallMap<&'v str, &'v str>>,
+        #[starlark(require = named, default = false)] stream_output: bool,
+        #[starlark(require = named)] timeout_seconds: Option<i32>,
+        #[starlark(require = named, default = true)] reproducible: bool,
+        #[starlark(require = named, default = false)] scratch: bool,
+        #[starlark(require = named)] env_passthrough: Option<UnpackList<String>>,
     ) -> anyhow::Result<NoneType> {
         struct RunCommandArtifactVisitor {
             inner: SimpleCommandLineArtifactVisitor,
@@ -247,6 +282,20 @@ pub(crate) fn analysis_actions_methods_run(methods: &mut MethodsBuilder) {
             }
         };
 
+        let mut env_passthrough = env_passthrough.map_or_else(Vec::new, |l| l.items);
+        for name in &env_passthrough {
+            if name.is_empty() || name.contains('=') || name.contains('\0') {
+                return Err(RunActionError::InvalidEnvPassthrough(name.clone()).into());
+            }
+            if let Some(env) = &env {
+                if env.typed.contains_key(name.as_str()) {
+                    return Err(RunActionError::ConflictingEnvPassthrough(name.clone()).into());
+                }
+            }
+        }
+        env_passthrough.sort();
+        env_passthrough.dedup();
+
         let starlark_env = match env {
             None => Value::new_none(),
             Some(env) => {
@@ -321,6 +370,12 @@ pub(crate) fn analysis_actions_methods_run(methods: &mut MethodsBuilder) {
             worker: heap.alloc(starlark_worker),
         });
 
+        let timeout = match timeout_seconds {
+            None => None,
+            Some(seconds) if seconds > 0 => Some(Duration::from_secs(seconds as u64)),
+            Some(seconds) => return Err(RunActionError::InvalidTimeout(seconds).into()),
+        };
+
         let re_dependencies = remote_execution_dependencies
             .into_iter()
             .map(RemoteExecutorDependency::parse)
@@ -341,6 +396,11 @@ pub(crate) fn analysis_actions_methods_run(methods: &mut MethodsBuilder) {
             force_full_hybrid_if_capable,
             unique_input_inodes,
             remote_execution_dependencies: re_dependencies,
+            stream_output,
+            timeout,
+            reproducible,
+            scratch,
+            env_passthrough,
         };
         this.state().register_action(
             artifacts.inputs,
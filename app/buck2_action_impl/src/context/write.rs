@@ -12,6 +12,8 @@ use buck2_build_api::actions::impls::json::JsonUnpack;
 use buck2_build_api::artifact_groups::ArtifactGroup;
 use buck2_build_api::interpreter::rule_defs::artifact::associated::AssociatedArtifacts;
 use buck2_build_api::interpreter::rule_defs::artifact::output_artifact_like::OutputArtifactArg;
+use buck2_build_api::interpreter::rule_defs::artifact::starlark_artifact_like::StarlarkArtifactLike;
+use buck2_build_api::interpreter::rule_defs::artifact::starlark_artifact_like::ValueAsArtifactLike;
 use buck2_build_api::interpreter::rule_defs::artifact::starlark_declared_artifact::StarlarkDeclaredArtifact;
 use buck2_build_api::interpreter::rule_defs::artifact_tagging::ArtifactTag;
 use buck2_build_api::interpreter::rule_defs::cmd_args::value::CommandLineArg;
@@ -22,6 +24,7 @@ use buck2_build_api::interpreter::rule_defs::cmd_args::StarlarkCmdArgs;
 use buck2_build_api::interpreter::rule_defs::cmd_args::StarlarkCommandLineValueUnpack;
 use buck2_build_api::interpreter::rule_defs::cmd_args::WriteToFileMacroVisitor;
 use buck2_build_api::interpreter::rule_defs::context::AnalysisActions;
+use buck2_build_api::interpreter::rule_defs::provider::builtin::action_metadata_info::ActionMetadataInfo;
 use buck2_build_api::interpreter::rule_defs::resolved_macro::ResolvedMacro;
 use buck2_execute::execute::request::OutputType;
 use dupe::Dupe;
@@ -34,14 +37,18 @@ use sha1::Sha1;
 use starlark::environment::MethodsBuilder;
 use starlark::eval::Evaluator;
 use starlark::starlark_module;
+use starlark::values::list::AllocList;
+use starlark::values::list_or_tuple::UnpackListOrTuple;
 use starlark::values::type_repr::StarlarkTypeRepr;
 use starlark::values::AllocValue;
 use starlark::values::UnpackValue;
+use starlark::values::Value;
 use starlark::values::ValueOf;
 use starlark::values::ValueTyped;
 use starlark_map::small_set::SmallSet;
 
 use crate::actions::impls::write::UnregisteredWriteAction;
+use crate::actions::impls::write_action_metadata::UnregisteredWriteActionMetadataAction;
 use crate::actions::impls::write_json::UnregisteredWriteJsonAction;
 use crate::actions::impls::write_macros::UnregisteredWriteMacrosToFileAction;
 
@@ -317,4 +324,51 @@ pub(crate) fn analysis_actions_methods_write(methods: &mut MethodsBuilder) {
             Ok(Either::Left(value))
         }
     }
+
+    /// Writes to `output`, as JSON, metadata about the outputs of the actions producing
+    /// `artifacts`, and returns an `ActionMetadataInfo` provider with `output` as its `metadata`
+    /// and `artifacts`. Rules return it, or forward it from their dependencies, so that packaging
+    /// or reporting rules can consume it without leaving the build graph.
+    ///
+    /// The output is an object with an `artifacts` list, with one entry per input artifact:
+    /// `path`, `owner` (the target or action which produced it, `null` for source artifacts),
+    /// `size` (in bytes, summed over the files of a directory) and `digest` (`null` for
+    /// symlinks).
+    ///
+    /// The output only depends on the contents of `artifacts`, so it is the same whichever daemon
+    /// or machine builds it, and it can be the input of cacheable actions.
+    fn write_action_metadata<'v>(
+        this: &AnalysisActions<'v>,
+        #[starlark(require = pos)] output: OutputArtifactArg<'v>,
+        #[starlark(require = pos)] artifacts: UnpackListOrTuple<Value<'v>>,
+        eval: &mut Evaluator<'v, '_, '_>,
+    ) -> anyhow::Result<ActionMetadataInfo<'v>> {
+        let inputs = artifacts
+            .items
+            .iter()
+            .map(|a| {
+                ValueAsArtifactLike::unpack_value_err(*a)?
+                    .0
+                    .get_artifact_group()
+            })
+            .collect::<anyhow::Result<IndexSet<_>>>()?;
+
+        let mut this = this.state();
+        let (declaration, output_artifact) =
+            this.get_or_declare_output(eval, output, OutputType::File)?;
+
+        this.register_action(
+            inputs,
+            indexset![output_artifact],
+            UnregisteredWriteActionMetadataAction,
+            None,
+            None,
+        )?;
+
+        let metadata = declaration.into_declared_artifact(AssociatedArtifacts::new());
+        Ok(ActionMetadataInfo {
+            metadata: metadata.to_value(),
+            artifacts: eval.heap().alloc(AllocList(artifacts.items)),
+        })
+    }
 }
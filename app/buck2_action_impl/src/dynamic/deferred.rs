@@ -291,6 +291,7 @@ impl Deferred for DynamicLambda {
                             dynamic_lambda_ctx_data.lambda.attributes()?,
                             self.owner.configured_label(),
                             dynamic_lambda_ctx_data.lambda.plugins()?,
+                            None,
                             dynamic_lambda_ctx_data.registry,
                             dynamic_lambda_ctx_data.digest_config,
                         );
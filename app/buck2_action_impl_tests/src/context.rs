@@ -16,6 +16,7 @@ use buck2_core::configuration::data::ConfigurationData;
 use buck2_core::execution_types::execution::ExecutionPlatformResolution;
 use buck2_core::target::label::label::TargetLabel;
 use buck2_execute::digest_config::DigestConfig;
+use buck2_node::feature_flags::FeatureFlags;
 use dupe::Dupe;
 use indoc::indoc;
 use maplit::hashmap;
@@ -81,6 +82,7 @@ fn run_ctx_test(
         Some(attributes),
         Some(label),
         Some(plugins),
+        Some(FeatureFlags::default()),
         registry,
         DigestConfig::testing_default(),
     ));
@@ -221,3 +223,38 @@ fn declare_output_require_bound() -> anyhow::Result<()> {
         ),
     })
 }
+
+#[test]
+fn run_with_scratch() -> anyhow::Result<()> {
+    let content = indoc!(
+        r#"
+         def test(c):
+             out = c.actions.declare_output("out")
+             c.actions.run(["gen", out.as_output()], category = "gen", scratch = True)
+         "#
+    );
+    run_ctx_test(content, |ret| {
+        ret?;
+        Ok(())
+    })
+}
+
+#[test]
+fn run_with_scratch_requires_bool() -> anyhow::Result<()> {
+    let content = indoc!(
+        r#"
+         def test(c):
+             out = c.actions.declare_output("out")
+             c.actions.run(["gen", out.as_output()], category = "gen", scratch = "yes")
+         "#
+    );
+
+    let expect = "scratch";
+    run_ctx_test(content, |ret| match ret {
+        Err(e) if e.to_string().contains(expect) => Ok(()),
+        _ => panic!(
+            "Expected a specific failure containing `{}`, got {:?}",
+            expect, ret
+        ),
+    })
+}
@@ -300,6 +300,7 @@ async fn run_analysis_with_env_underlying(
                 Some(attributes),
                 Some(analysis_env.label),
                 Some(plugins.into()),
+                Some(node.feature_flags().clone()),
                 registry,
                 dice.global_data().get_digest_config(),
             );
@@ -63,6 +63,8 @@ use buck2_interpreter_for_build::rule::FrozenRuleCallable;
 use buck2_node::attrs::attr_type::AttrType;
 use buck2_node::attrs::coerced_attr::CoercedAttr;
 use buck2_node::attrs::internal::internal_attrs;
+use buck2_node::attrs::str_interner::intern_str_globally;
+use buck2_node::feature_flags::FeatureFlags;
 use buck2_util::arc_str::ArcStr;
 use derive_more::Display;
 use dice::DiceComputations;
@@ -363,6 +365,8 @@ impl AnonTargetKey {
                                     .alloc_typed(AnalysisPlugins::new(SmallMap::new()))
                                     .into(),
                             ),
+                            // Anon targets do not belong to a package, so no flags are set.
+                            Some(FeatureFlags::default()),
                             registry,
                             dice.global_data().get_digest_config(),
                         );
@@ -469,8 +473,7 @@ impl AnonAttrCtx {
     }
 
     pub(crate) fn intern_str(&self, value: &str) -> ArcStr {
-        // TODO(scottcao): do intern.
-        ArcStr::from(value)
+        intern_str_globally(value)
     }
 }
 
@@ -0,0 +1,135 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Aspects: rules applied over existing configured targets and their transitive deps with
+//! `buck2 build --aspect` or the `aspects` of `ctx.build` in BXL.
+//!
+//! An aspect is a rule with a `target` attribute declared with `attrs.dep()`, whose other
+//! attributes all have defaults. Applying it to a target analyzes it as an anon target with
+//! `target` set to that target, so its implementation can read the providers of the target and
+//! declare actions of its own (e.g. to generate IDE metadata or run linters) without modifying
+//! the rule of the target.
+
+use std::sync::Arc;
+
+use anyhow::Context;
+use buck2_build_api::analysis::AnalysisResult;
+use buck2_build_api::deferred::calculation::EVAL_ASPECT;
+use buck2_core::provider::label::ConfiguredProvidersLabel;
+use buck2_interpreter::load_module::InterpreterCalculation;
+use buck2_interpreter_for_build::rule::FrozenRuleCallable;
+use buck2_node::attrs::attr_type::dep::DepAttr;
+use buck2_node::attrs::attr_type::dep::DepAttrTransition;
+use buck2_node::attrs::attr_type::AttrTypeInner;
+use buck2_node::attrs::internal::internal_attrs;
+use buck2_node::nodes::configured_frontend::ConfiguredTargetNodeCalculation;
+use buck2_node::rule_type::StarlarkRuleType;
+use dice::DiceComputations;
+use dupe::Dupe;
+use starlark_map::ordered_map::OrderedMap;
+
+use crate::anon_target_attr::AnonTargetAttr;
+use crate::anon_target_node::AnonTarget;
+use crate::anon_targets::AnonTargetKey;
+
+/// The attribute of an aspect which is set to the target it is applied to.
+const TARGET_ATTR: &str = "target";
+
+#[derive(Debug, buck2_error::Error)]
+#[buck2(input)]
+enum AspectError {
+    #[error("Aspect `{0}` is not a rule")]
+    NotARule(String),
+    #[error("Aspect `{0}` must have a `target` attribute declared with `attrs.dep()`")]
+    NoTargetAttribute(String),
+    #[error("Attribute `{1}` of aspect `{0}` must have a default value")]
+    MissingDefault(String, String),
+}
+
+async fn aspect_key(
+    dice: &mut DiceComputations<'_>,
+    aspect: &StarlarkRuleType,
+    target: &ConfiguredProvidersLabel,
+) -> anyhow::Result<AnonTargetKey> {
+    // Like the anon targets a rule creates, the aspect runs on the execution platform of the
+    // target it is applied to.
+    let exec_cfg = dice
+        .get_configured_target_node(target.target())
+        .await?
+        .require_compatible()?
+        .execution_platform_resolution()
+        .cfg();
+
+    let module = dice
+        .get_loaded_module_from_import_path(&aspect.import_path)
+        .await?;
+    let rule = module
+        .env()
+        .get_any_visibility(&aspect.name)
+        .with_context(|| format!("Couldn't find aspect `{}`", aspect))?
+        .0;
+    let rule = rule
+        .value()
+        .downcast_frozen_ref::<FrozenRuleCallable>()
+        .ok_or_else(|| AspectError::NotARule(aspect.to_string()))?;
+    let attrs_spec = rule.attributes();
+
+    let dep = match attrs_spec
+        .attribute(TARGET_ATTR)
+        .map(|attr| &attr.coercer().0.inner)
+    {
+        Some(AttrTypeInner::Dep(dep))
+            if matches!(dep.transition, DepAttrTransition::Identity(..)) =>
+        {
+            dep.clone()
+        }
+        _ => return Err(AspectError::NoTargetAttribute(aspect.to_string()).into()),
+    };
+
+    let internal_attrs = internal_attrs();
+    let mut attrs = OrderedMap::with_capacity(attrs_spec.len());
+    attrs.insert(
+        TARGET_ATTR.to_owned(),
+        AnonTargetAttr::Dep(Box::new(DepAttr {
+            attr_type: dep,
+            label: target.clone(),
+        })),
+    );
+    for (name, _, attr) in attrs_spec.attr_specs() {
+        if name == TARGET_ATTR || internal_attrs.contains_key(name) {
+            continue;
+        }
+        let default = attr
+            .default()
+            .ok_or_else(|| AspectError::MissingDefault(aspect.to_string(), name.to_owned()))?;
+        attrs.insert(
+            name.to_owned(),
+            AnonTargetAttr::from_coerced_attr(default, attr.coercer())?,
+        );
+    }
+
+    Ok(AnonTargetKey(Arc::new(AnonTarget::new(
+        rule.rule_type().dupe(),
+        target.target().unconfigured().dupe(),
+        attrs.into(),
+        exec_cfg,
+    ))))
+}
+
+async fn eval_aspect(
+    dice: &mut DiceComputations<'_>,
+    aspect: &StarlarkRuleType,
+    target: &ConfiguredProvidersLabel,
+) -> anyhow::Result<AnalysisResult> {
+    aspect_key(dice, aspect, target).await?.resolve(dice).await
+}
+
+pub(crate) fn init_eval_aspect() {
+    EVAL_ASPECT.init(|dice, aspect, target| Box::pin(eval_aspect(dice, aspect, target)));
+}
@@ -18,6 +18,7 @@ pub(crate) mod anon_target_attr_coerce;
 pub(crate) mod anon_target_attr_resolve;
 pub(crate) mod anon_target_node;
 pub(crate) mod anon_targets;
+pub(crate) mod aspects;
 pub(crate) mod promise_artifacts;
 pub(crate) mod starlark_defs;
 
@@ -27,6 +28,7 @@ pub fn init_late_bindings() {
         anon_targets::init_anon_target_registry_new();
         anon_targets::init_eval_anon_target();
         anon_targets::init_get_promised_artifact();
+        aspects::init_eval_aspect();
         starlark_defs::init_analysis_actions_methods_anon_target();
         starlark_defs::init_register_anon_target_types();
     });
@@ -40,6 +40,29 @@ pub struct AuditCellCommand {
     )]
     pub aliases_to_resolve: Vec<String>,
 
+    #[clap(
+        long = "layout",
+        help = "Print the full cell layout: the path, external origin and alias map of every cell, along with where each alias was defined.",
+        conflicts_with_all = &["paths_only", "aliases", "CELL_ALIASES", "resolve"]
+    )]
+    pub layout: bool,
+
+    #[clap(
+        long = "resolve",
+        value_name = "ALIAS",
+        help = "Trace how the given cell alias resolves from the working directory (or the directory passed to `--from`).",
+        conflicts_with_all = &["paths_only", "aliases", "CELL_ALIASES"]
+    )]
+    pub resolve: Option<String>,
+
+    #[clap(
+        long = "from",
+        value_name = "PATH",
+        requires = "resolve",
+        help = "Directory, relative to the working directory, to resolve the `--resolve` alias from."
+    )]
+    pub resolve_from: Option<String>,
+
     /// Command doesn't need these flags, but they are used in mode files, so we need to keep them.
     #[clap(flatten)]
     _target_cfg: TargetCfgUnusedOptions,
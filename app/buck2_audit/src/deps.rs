@@ -0,0 +1,60 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use async_trait::async_trait;
+use buck2_client_ctx::common::target_cfg::TargetCfgWithUniverseOptions;
+use buck2_client_ctx::common::CommonCommandOptions;
+
+use crate::AuditSubcommand;
+
+/// Print the dependencies of configured targets, with the reason for each edge.
+///
+/// Every dependency is annotated with the attribute that references it and the `select()`
+/// branches taken in that attribute. With `--transitive`, dependencies are grouped by their
+/// shortest distance from the root, and each one is attributed to the first edge reaching it.
+#[derive(Debug, clap::Parser, serde::Serialize, serde::Deserialize)]
+#[clap(name = "audit-deps")]
+pub struct AuditDepsCommand {
+    /// Patterns of the targets to inspect, like `//foo:bar` or `//foo/...`.
+    #[clap(name = "TARGET_PATTERNS", required = true)]
+    pub patterns: Vec<String>,
+
+    /// Print transitive dependencies instead of direct ones.
+    #[clap(long)]
+    pub transitive: bool,
+
+    /// Do not print dependencies further than this many edges from the root.
+    #[clap(long, value_name = "N", requires = "transitive")]
+    pub max_depth: Option<u32>,
+
+    /// Include dependencies on toolchain rules.
+    #[clap(long)]
+    pub include_toolchain_deps: bool,
+
+    /// Include dependencies configured for the execution platform.
+    #[clap(long)]
+    pub include_exec_deps: bool,
+
+    /// Print one JSON object per edge.
+    #[clap(long)]
+    pub json: bool,
+
+    #[clap(flatten)]
+    pub target_cfg: TargetCfgWithUniverseOptions,
+
+    #[clap(flatten)]
+    common_opts: CommonCommandOptions,
+}
+
+#[async_trait]
+impl AuditSubcommand for AuditDepsCommand {
+    fn common_opts(&self) -> &CommonCommandOptions {
+        &self.common_opts
+    }
+}
@@ -0,0 +1,65 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use async_trait::async_trait;
+use buck2_client_ctx::common::CommonCommandOptions;
+
+use crate::AuditSubcommand;
+
+/// List the environment variables which influenced the build.
+///
+/// Includes variables read by the daemon and the interpreter from the daemon's environment,
+/// and variables forwarded to actions with `env_passthrough` in one invocation (by default the
+/// last one which ran such actions), together with the actions depending on them. Variables
+/// whose value changed since the invocation before are marked, as they caused those actions to
+/// re-run.
+#[derive(Debug, clap::Parser, serde::Serialize, serde::Deserialize)]
+#[clap(name = "audit-env")]
+pub struct AuditEnvCommand {
+    /// Only list variables read by this source.
+    #[clap(long, value_enum)]
+    pub source: Option<AuditEnvSource>,
+
+    /// List the variables forwarded to actions by the invocation with this trace id, instead of
+    /// the last one which forwarded any.
+    #[clap(long)]
+    pub trace_id: Option<String>,
+
+    /// Only list variables whose value changed since the previous invocation.
+    #[clap(long)]
+    pub changed: bool,
+
+    /// Print the variables as JSON.
+    #[clap(long)]
+    pub json: bool,
+
+    #[clap(flatten)]
+    common_opts: CommonCommandOptions,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    clap::ValueEnum,
+    serde::Serialize,
+    serde::Deserialize
+)]
+pub enum AuditEnvSource {
+    Daemon,
+    Interpreter,
+    Action,
+}
+
+#[async_trait]
+impl AuditSubcommand for AuditEnvCommand {
+    fn common_opts(&self) -> &CommonCommandOptions {
+        &self.common_opts
+    }
+}
@@ -0,0 +1,48 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use async_trait::async_trait;
+use buck2_client_ctx::common::target_cfg::TargetCfgUnusedOptions;
+use buck2_client_ctx::common::CommonCommandOptions;
+
+use crate::AuditSubcommand;
+
+/// List the feature flags of packages.
+///
+/// Feature flags are set with `write_feature_flag` in `PACKAGE` files,
+/// and apply to the package of the `PACKAGE` file and all the packages below it.
+#[derive(Debug, clap::Parser, serde::Serialize, serde::Deserialize)]
+#[clap(name = "audit-feature-flags")]
+pub struct AuditFeatureFlagsCommand {
+    /// Patterns of the packages to inspect, like `//foo/bar:` or `//foo/...`.
+    #[clap(name = "PACKAGE_PATTERNS", required = true)]
+    pub patterns: Vec<String>,
+
+    /// Only print the state of this flag in each package.
+    #[clap(long, value_name = "NAME")]
+    pub flag: Option<String>,
+
+    /// Print the flags as JSON, keyed by package.
+    #[clap(long)]
+    pub json: bool,
+
+    /// Command doesn't need these flags, but they are used in mode files, so we need to keep them.
+    #[clap(flatten)]
+    _target_cfg: TargetCfgUnusedOptions,
+
+    #[clap(flatten)]
+    common_opts: CommonCommandOptions,
+}
+
+#[async_trait]
+impl AuditSubcommand for AuditFeatureFlagsCommand {
+    fn common_opts(&self) -> &CommonCommandOptions {
+        &self.common_opts
+    }
+}
@@ -30,15 +30,21 @@ use crate::config::AuditConfigCommand;
 use crate::configurations::AuditConfigurationsCommand;
 use crate::deferred_materializer::DeferredMaterializerCommand;
 use crate::dep_files::AuditDepFilesCommand;
+use crate::deps::AuditDepsCommand;
+use crate::env::AuditEnvCommand;
 use crate::execution_platform_resolution::AuditExecutionPlatformResolutionCommand;
+use crate::feature_flags::AuditFeatureFlagsCommand;
 use crate::includes::AuditIncludesCommand;
 use crate::output::command::AuditOutputCommand;
 use crate::output::parse::AuditParseCommand;
 use crate::package_values::PackageValuesCommand;
 use crate::prelude::AuditPreludeCommand;
 use crate::providers::AuditProvidersCommand;
+use crate::select_coverage::AuditSelectCoverageCommand;
+use crate::source_references::AuditSourceReferencesCommand;
 use crate::starlark::StarlarkCommand;
 use crate::subtargets::AuditSubtargetsCommand;
+use crate::tsets::AuditTsetsCommand;
 use crate::visibility::AuditVisibilityCommand;
 
 pub mod analysis_queries;
@@ -48,14 +54,20 @@ pub mod config;
 pub mod configurations;
 pub mod deferred_materializer;
 pub mod dep_files;
+pub mod deps;
+pub mod env;
 pub mod execution_platform_resolution;
+pub mod feature_flags;
 pub mod includes;
 pub mod output;
 pub mod package_values;
 pub mod prelude;
 pub mod providers;
+pub mod select_coverage;
+pub mod source_references;
 pub mod starlark;
 pub mod subtargets;
+pub mod tsets;
 pub mod visibility;
 
 #[derive(Debug, clap::Subcommand, serde::Serialize, serde::Deserialize)]
@@ -79,6 +91,12 @@ pub enum AuditCommand {
     Output(AuditOutputCommand),
     Parse(AuditParseCommand),
     PackageValues(PackageValuesCommand),
+    FeatureFlags(AuditFeatureFlagsCommand),
+    SourceReferences(AuditSourceReferencesCommand),
+    SelectCoverage(AuditSelectCoverageCommand),
+    Deps(AuditDepsCommand),
+    Tsets(AuditTsetsCommand),
+    Env(AuditEnvCommand),
 }
 
 /// `buck2 audit` subcommands have a somewhat unique approach to make it really easy to
@@ -114,6 +132,12 @@ impl AuditCommand {
             AuditCommand::Output(cmd) => cmd,
             AuditCommand::Parse(cmd) => cmd,
             AuditCommand::PackageValues(cmd) => cmd,
+            AuditCommand::FeatureFlags(cmd) => cmd,
+            AuditCommand::SourceReferences(cmd) => cmd,
+            AuditCommand::SelectCoverage(cmd) => cmd,
+            AuditCommand::Deps(cmd) => cmd,
+            AuditCommand::Tsets(cmd) => cmd,
+            AuditCommand::Env(cmd) => cmd,
         }
     }
 }
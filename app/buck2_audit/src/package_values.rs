@@ -22,6 +22,11 @@ pub struct PackageValuesCommand {
     /// Package names to inspect (like `//foo/bar`, no trailing colon).
     pub packages: Vec<String>,
 
+    /// For each value, also print the chain of `PACKAGE` files which wrote it,
+    /// starting from the project root, and whether a write overwrote a parent value.
+    #[clap(long)]
+    pub provenance: bool,
+
     /// Command doesn't need these flags, but they are used in mode files, so we need to keep them.
     #[clap(flatten)]
     _target_cfg: TargetCfgUnusedOptions,
@@ -0,0 +1,54 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use async_trait::async_trait;
+use buck2_client_ctx::common::target_cfg::TargetCfgUnusedOptions;
+use buck2_client_ctx::common::CommonCommandOptions;
+
+use crate::AuditSubcommand;
+
+/// Report `select()` branches which are not taken in any of the given configurations.
+///
+/// Each target is configured for each `--platform` (or the default target platform if none is
+/// given). A branch whose key never matches in a configuration the target is compatible with is
+/// reported as unreachable. Selects without a `DEFAULT` are reported too, because they fail in
+/// configurations matching none of their keys.
+#[derive(Debug, clap::Parser, serde::Serialize, serde::Deserialize)]
+#[clap(name = "audit-select-coverage")]
+pub struct AuditSelectCoverageCommand {
+    /// Patterns of the targets to inspect, like `//foo:bar` or `//foo/...`.
+    #[clap(name = "TARGET_PATTERNS", required = true)]
+    pub patterns: Vec<String>,
+
+    /// Target platform to configure the targets with, can be repeated.
+    #[clap(long = "platform", value_name = "PLATFORM")]
+    pub platforms: Vec<String>,
+
+    /// Do not report selects without a `DEFAULT`.
+    #[clap(long)]
+    pub ignore_missing_default: bool,
+
+    /// Print one JSON object per finding.
+    #[clap(long)]
+    pub json: bool,
+
+    /// Command doesn't need these flags, but they are used in mode files, so we need to keep them.
+    #[clap(flatten)]
+    _target_cfg: TargetCfgUnusedOptions,
+
+    #[clap(flatten)]
+    common_opts: CommonCommandOptions,
+}
+
+#[async_trait]
+impl AuditSubcommand for AuditSelectCoverageCommand {
+    fn common_opts(&self) -> &CommonCommandOptions {
+        &self.common_opts
+    }
+}
@@ -0,0 +1,91 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::fmt;
+
+use async_trait::async_trait;
+use buck2_client_ctx::common::target_cfg::TargetCfgUnusedOptions;
+use buck2_client_ctx::common::CommonCommandOptions;
+use dupe::Dupe;
+
+use crate::AuditSubcommand;
+
+#[derive(
+    Debug,
+    Dupe,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    serde::Serialize,
+    serde::Deserialize,
+    clap::ValueEnum
+)]
+#[clap(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ReferenceSeverity {
+    /// A string attribute looks like a path outside the package, e.g. `-I../include`.
+    Advice,
+    /// A source file belongs to a subpackage of the target's package.
+    Warning,
+    /// A source file belongs to another cell.
+    Error,
+}
+
+impl fmt::Display for ReferenceSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReferenceSeverity::Advice => write!(f, "advice"),
+            ReferenceSeverity::Warning => write!(f, "warning"),
+            ReferenceSeverity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Report targets whose sources reference files outside their own package or cell.
+///
+/// Sources in a subpackage (usually allowed by `project.package_boundary_exceptions`) are
+/// warnings, sources in another cell are errors, and string attributes which look like paths
+/// escaping the package (`../foo`, `/abs/path`) are advice. Meant to find the targets to fix
+/// before enforcing strict package encapsulation.
+#[derive(Debug, clap::Parser, serde::Serialize, serde::Deserialize)]
+#[clap(name = "audit-source-references")]
+pub struct AuditSourceReferencesCommand {
+    /// Patterns of the targets to inspect, like `//foo:bar` or `//foo/...`.
+    #[clap(name = "TARGET_PATTERNS", required = true)]
+    pub patterns: Vec<String>,
+
+    /// Only report references of at least this severity.
+    #[clap(long, value_enum, default_value = "advice")]
+    pub min_severity: ReferenceSeverity,
+
+    /// Fail if any reference of at least this severity is found.
+    #[clap(long, value_enum)]
+    pub fail_on: Option<ReferenceSeverity>,
+
+    /// Print one JSON object per reference.
+    #[clap(long)]
+    pub json: bool,
+
+    /// Command doesn't need these flags, but they are used in mode files, so we need to keep them.
+    #[clap(flatten)]
+    _target_cfg: TargetCfgUnusedOptions,
+
+    #[clap(flatten)]
+    common_opts: CommonCommandOptions,
+}
+
+#[async_trait]
+impl AuditSubcommand for AuditSourceReferencesCommand {
+    fn common_opts(&self) -> &CommonCommandOptions {
+        &self.common_opts
+    }
+}
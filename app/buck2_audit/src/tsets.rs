@@ -0,0 +1,48 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use async_trait::async_trait;
+use buck2_client_ctx::common::target_cfg::TargetCfgWithUniverseOptions;
+use buck2_client_ctx::common::CommonCommandOptions;
+
+use crate::AuditSubcommand;
+
+/// Report the sizes of the transitive sets reachable from the providers of targets.
+///
+/// For each transitive set definition, prints the number of distinct sets, the number of sets
+/// visited by a traversal without deduplication and their ratio (the duplication factor), and
+/// the number of projection values computed. A high duplication factor usually means sets are
+/// built in a quadratic way, e.g. by passing both a set and its children as children.
+#[derive(Debug, clap::Parser, serde::Serialize, serde::Deserialize)]
+#[clap(name = "audit-tsets")]
+pub struct AuditTsetsCommand {
+    #[clap(
+        name = "TARGET_PATTERNS",
+        help = "Patterns to analyze",
+        required = true
+    )]
+    pub patterns: Vec<String>,
+
+    /// Print one JSON object per target and definition.
+    #[clap(long)]
+    pub json: bool,
+
+    #[clap(flatten)]
+    pub target_cfg: TargetCfgWithUniverseOptions,
+
+    #[clap(flatten)]
+    pub common_opts: CommonCommandOptions,
+}
+
+#[async_trait]
+impl AuditSubcommand for AuditTsetsCommand {
+    fn common_opts(&self) -> &CommonCommandOptions {
+        &self.common_opts
+    }
+}
@@ -14,9 +14,13 @@ use buck2_analysis::analysis::calculation::resolve_queries;
 use buck2_audit::analysis_queries::AuditAnalysisQueriesCommand;
 use buck2_cli_proto::ClientContext;
 use buck2_common::pattern::parse_from_cli::parse_and_resolve_patterns_from_cli_args;
+use buck2_core::configuration::compatibility::MaybeCompatible;
+use buck2_core::pattern::pattern::PackageSpec;
 use buck2_core::pattern::pattern_type::TargetPatternExtra;
 use buck2_core::target::label::label::TargetLabel;
+use buck2_core::target::name::TargetName;
 use buck2_node::nodes::configured_frontend::ConfiguredTargetNodeCalculation;
+use buck2_node::nodes::frontend::TargetGraphCalculation;
 use buck2_server_ctx::ctx::ServerCommandContextTrait;
 use buck2_server_ctx::ctx::ServerCommandDiceContext;
 use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
@@ -50,39 +54,56 @@ impl ServerAuditSubcommand for AuditAnalysisQueriesCommand {
                 let mut stdout = stdout.as_writer();
 
                 for (package, spec) in resolved_pattern.specs {
-                    match spec {
-                        buck2_core::pattern::pattern::PackageSpec::Targets(targets) => {
-                            for (target, TargetPatternExtra) in targets {
-                                let label = TargetLabel::new(package.dupe(), target.as_ref());
-                                for configured_target in target_resolution_config
-                                    .get_configured_target(&mut ctx, &label)
-                                    .await?
-                                {
-                                    let node =
-                                        ctx.get_configured_target_node(&configured_target).await?;
-                                    let node = node.require_compatible()?;
-                                    let query_results =
-                                        resolve_queries(&mut ctx, node.as_ref()).await?;
-                                    writeln!(stdout, "{}:", label)?;
-                                    for (query, result) in &query_results {
-                                        writeln!(stdout, "  {}", query)?;
-                                        for (target, providers) in &result.result {
-                                            writeln!(stdout, "    {}", target.unconfigured())?;
-                                            if self.include_outputs {
-                                                let outputs = providers
-                                                    .provider_collection()
-                                                    .default_info()
-                                                    .default_outputs_raw();
-                                                writeln!(stdout, "        {}", outputs)?;
-                                            }
-                                        }
+                    // Wildcard patterns skip incompatible targets, like `buck2 build` does.
+                    let (targets, skip_incompatible): (Vec<TargetName>, bool) = match spec {
+                        PackageSpec::Targets(targets) => (
+                            targets
+                                .into_iter()
+                                .map(|(target, TargetPatternExtra)| target)
+                                .collect(),
+                            false,
+                        ),
+                        spec @ (PackageSpec::All | PackageSpec::AllExcept(_)) => {
+                            let interpreter_results =
+                                ctx.get_interpreter_results(package.dupe()).await?;
+                            let targets = interpreter_results
+                                .targets()
+                                .keys()
+                                .filter(|target| !spec.excludes(target))
+                                .map(|target| target.to_owned())
+                                .collect();
+                            (targets, true)
+                        }
+                    };
+                    for target in targets {
+                        let label = TargetLabel::new(package.dupe(), target.as_ref());
+                        for configured_target in target_resolution_config
+                            .get_configured_target(&mut ctx, &label)
+                            .await?
+                        {
+                            let node = match ctx
+                                .get_configured_target_node(&configured_target)
+                                .await?
+                            {
+                                MaybeCompatible::Incompatible(_) if skip_incompatible => continue,
+                                node => node.require_compatible()?,
+                            };
+                            let query_results = resolve_queries(&mut ctx, node.as_ref()).await?;
+                            writeln!(stdout, "{}:", label)?;
+                            for (query, result) in &query_results {
+                                writeln!(stdout, "  {}", query)?;
+                                for (target, providers) in &result.result {
+                                    writeln!(stdout, "    {}", target.unconfigured())?;
+                                    if self.include_outputs {
+                                        let outputs = providers
+                                            .provider_collection()
+                                            .default_info()
+                                            .default_outputs_raw();
+                                        writeln!(stdout, "        {}", outputs)?;
                                     }
                                 }
                             }
                         }
-                        buck2_core::pattern::pattern::PackageSpec::All => {
-                            unimplemented!()
-                        }
                     }
                 }
 
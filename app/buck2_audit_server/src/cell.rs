@@ -7,6 +7,7 @@
  * of this source tree.
  */
 
+use std::collections::BTreeMap;
 use std::io::Write;
 
 use async_trait::async_trait;
@@ -14,6 +15,9 @@ use buck2_audit::cell::AuditCellCommand;
 use buck2_build_api::audit_cell::AUDIT_CELL;
 use buck2_cli_proto::ClientContext;
 use buck2_common::dice::cells::HasCellResolver;
+use buck2_common::legacy_configs::configs::LegacyBuckConfig;
+use buck2_common::legacy_configs::dice::HasLegacyConfigs;
+use buck2_core::cells::name::CellName;
 use buck2_core::cells::CellResolver;
 use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
 use buck2_core::fs::project::ProjectRoot;
@@ -21,6 +25,7 @@ use buck2_core::fs::project_rel_path::ProjectRelativePath;
 use buck2_server_ctx::ctx::ServerCommandContextTrait;
 use buck2_server_ctx::ctx::ServerCommandDiceContext;
 use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
+use dice::DiceComputations;
 use indexmap::IndexMap;
 
 use crate::ServerAuditSubcommand;
@@ -34,11 +39,61 @@ impl ServerAuditSubcommand for AuditCellCommand {
         _client_ctx: ClientContext,
     ) -> anyhow::Result<()> {
         server_ctx
-            .with_dice_ctx(|server_ctx, mut ctx| async move {
+            .with_dice_ctx_read_only(|server_ctx, mut ctx| async move {
                 let cells = ctx.get_cell_resolver().await?;
                 let fs = server_ctx.project_root();
                 let cwd = server_ctx.working_dir();
 
+                if self.layout {
+                    let layout = audit_cell_layout(&mut ctx, &cells, fs).await?;
+                    let mut stdout = stdout.as_writer();
+                    if self.json {
+                        writeln!(stdout, "{}", serde_json::to_string_pretty(&layout)?)?;
+                    } else {
+                        for (name, cell) in layout {
+                            writeln!(stdout, "{}: {}", name, cell.path)?;
+                            if let Some(external) = &cell.external {
+                                writeln!(stdout, "  external: {}", external)?;
+                            }
+                            for (alias, mapping) in cell.aliases {
+                                writeln!(
+                                    stdout,
+                                    "  {} -> {} ({})",
+                                    alias, mapping.cell, mapping.source
+                                )?;
+                            }
+                        }
+                    }
+                    return Ok(());
+                }
+
+                if let Some(alias) = &self.resolve {
+                    let from = match &self.resolve_from {
+                        Some(from) => cwd.join_normalized(from)?,
+                        None => cwd.to_buf(),
+                    };
+                    let trace = trace_alias_resolution(&mut ctx, &cells, fs, &from, alias).await?;
+                    let mut stdout = stdout.as_writer();
+                    if self.json {
+                        writeln!(stdout, "{}", serde_json::to_string_pretty(&trace)?)?;
+                    } else {
+                        writeln!(
+                            stdout,
+                            "`{}` is in cell `{}` ({})",
+                            trace.from, trace.from_cell, trace.from_cell_path
+                        )?;
+                        for step in &trace.steps {
+                            writeln!(stdout, "  {}", step)?;
+                        }
+                        writeln!(
+                            stdout,
+                            "`{}` resolves to cell `{}` ({})",
+                            trace.alias, trace.resolved_cell, trace.resolved_path
+                        )?;
+                    }
+                    return Ok(());
+                }
+
                 let mappings = audit_cell(&self.aliases_to_resolve, self.aliases, &cells, cwd, fs)?;
 
                 let mut stdout = stdout.as_writer();
@@ -123,8 +178,306 @@ pub(crate) fn audit_cell(
     Ok(mappings)
 }
 
+#[derive(serde::Serialize)]
+struct CellLayout {
+    path: AbsNormPathBuf,
+    /// The external cell origin, if any.
+    external: Option<String>,
+    aliases: BTreeMap<String, AliasMapping>,
+}
+
+#[derive(serde::Serialize)]
+struct AliasMapping {
+    cell: String,
+    /// Where the mapping was defined, e.g. ``[cells] at /repo/.buckconfig:3``.
+    source: String,
+}
+
+#[derive(serde::Serialize)]
+struct AliasResolutionTrace {
+    alias: String,
+    from: String,
+    from_cell: String,
+    from_cell_path: AbsNormPathBuf,
+    steps: Vec<String>,
+    resolved_cell: String,
+    resolved_path: AbsNormPathBuf,
+}
+
+/// The sections that define new cells, and the sections that only define aliases of cells.
+const CELLS_SECTIONS: &[&str] = &["cells", "repositories"];
+const CELL_ALIASES_SECTIONS: &[&str] = &["cell_aliases", "repository_aliases"];
+
+/// Where a single step of alias resolution came from.
+enum AliasDefinition {
+    /// `alias` names a cell directly.
+    Cell { source: String },
+    /// `alias` is another name for `destination`, which needs to be resolved further.
+    Alias { destination: String, source: String },
+}
+
+fn find_alias_definition(
+    config: &LegacyBuckConfig,
+    alias: &str,
+    include_cells_section: bool,
+) -> Option<AliasDefinition> {
+    let find = |sections: &[&str]| {
+        sections.iter().find_map(|section| {
+            let value = config.get_section(section)?.get(alias)?;
+            Some((
+                value.as_str().to_owned(),
+                format!("[{}] {}", section, value.location()),
+            ))
+        })
+    };
+    if include_cells_section {
+        if let Some((_, source)) = find(CELLS_SECTIONS) {
+            return Some(AliasDefinition::Cell { source });
+        }
+    }
+    find(CELL_ALIASES_SECTIONS).map(|(destination, source)| AliasDefinition::Alias {
+        destination,
+        source,
+    })
+}
+
+/// Explain where `alias` comes from in `cell`. Mirrors the way cell alias resolvers are built: a
+/// cell's own `[cells]` and `[cell_aliases]` take precedence over the root cell's `[cells]`, which
+/// every cell inherits. External cells ignore their `[cells]` section.
+fn trace_alias(
+    alias: &str,
+    cell: CellName,
+    cells: &CellResolver,
+    config: &LegacyBuckConfig,
+    root_config: &LegacyBuckConfig,
+) -> anyhow::Result<Vec<String>> {
+    let is_external = cells.get(cell)?.external().is_some();
+    let mut steps = Vec::new();
+    let mut alias = alias.to_owned();
+    // Aliases can only refer to cells or to other aliases defined before them, but be defensive
+    // about cycles anyway.
+    for _ in 0..16 {
+        if alias == cell.as_str() {
+            steps.push(format!("`{}` is the name of the cell itself", alias));
+            return Ok(steps);
+        }
+        match find_alias_definition(config, &alias, !is_external) {
+            Some(AliasDefinition::Cell { source }) => {
+                steps.push(format!("`{}` defined in cell `{}` {}", alias, cell, source));
+                return Ok(steps);
+            }
+            Some(AliasDefinition::Alias {
+                destination,
+                source,
+            }) => {
+                steps.push(format!(
+                    "`{}` is an alias of `{}` in cell `{}` {}",
+                    alias, destination, cell, source
+                ));
+                alias = destination;
+                continue;
+            }
+            None => {}
+        }
+        if !cells.is_root_cell(cell) {
+            match find_alias_definition(root_config, &alias, true) {
+                Some(AliasDefinition::Cell { source }) => {
+                    steps.push(format!(
+                        "`{}` inherited from root cell `{}` {}",
+                        alias,
+                        cells.root_cell(),
+                        source
+                    ));
+                    return Ok(steps);
+                }
+                Some(AliasDefinition::Alias {
+                    destination,
+                    source,
+                }) => {
+                    steps.push(format!(
+                        "`{}` inherited as an alias of `{}` from root cell `{}` {}",
+                        alias,
+                        destination,
+                        cells.root_cell(),
+                        source
+                    ));
+                    alias = destination;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        steps.push(format!("no definition found for `{}`", alias));
+        return Ok(steps);
+    }
+    steps.push("alias chain too long, giving up".to_owned());
+    Ok(steps)
+}
+
+async fn audit_cell_layout(
+    ctx: &mut DiceComputations<'_>,
+    cells: &CellResolver,
+    fs: &ProjectRoot,
+) -> anyhow::Result<BTreeMap<String, CellLayout>> {
+    let root_config = ctx.get_legacy_config_for_cell(cells.root_cell()).await?;
+    let mut layout = BTreeMap::new();
+    for (name, instance) in cells.cells() {
+        let config = ctx.get_legacy_config_for_cell(name).await?;
+        let resolver = ctx.get_cell_alias_resolver(name).await?;
+        let mut aliases = BTreeMap::new();
+        for (alias, target) in resolver.mappings() {
+            let steps = trace_alias(alias.as_str(), name, cells, &config, &root_config)?;
+            aliases.insert(
+                alias.to_string(),
+                AliasMapping {
+                    cell: target.as_str().to_owned(),
+                    source: steps.join(", "),
+                },
+            );
+        }
+        layout.insert(
+            name.as_str().to_owned(),
+            CellLayout {
+                path: fs.resolve(instance.path().as_project_relative_path()),
+                external: instance.external().map(|origin| origin.to_string()),
+                aliases,
+            },
+        );
+    }
+    Ok(layout)
+}
+
+async fn trace_alias_resolution(
+    ctx: &mut DiceComputations<'_>,
+    cells: &CellResolver,
+    fs: &ProjectRoot,
+    from: &ProjectRelativePath,
+    alias: &str,
+) -> anyhow::Result<AliasResolutionTrace> {
+    let from_cell = cells.find(from)?;
+    let root_config = ctx.get_legacy_config_for_cell(cells.root_cell()).await?;
+    let config = ctx.get_legacy_config_for_cell(from_cell).await?;
+    let resolver = ctx.get_cell_alias_resolver(from_cell).await?;
+
+    let steps = trace_alias(alias, from_cell, cells, &config, &root_config)?;
+    // The trace is only an explanation, the resolver is the source of truth.
+    let resolved_cell = resolver.resolve(alias)?;
+
+    Ok(AliasResolutionTrace {
+        alias: alias.to_owned(),
+        from: from.to_string(),
+        from_cell: from_cell.as_str().to_owned(),
+        from_cell_path: fs.resolve(cells.get(from_cell)?.path().as_project_relative_path()),
+        steps,
+        resolved_cell: resolved_cell.as_str().to_owned(),
+        resolved_path: fs.resolve(cells.get(resolved_cell)?.path().as_project_relative_path()),
+    })
+}
+
 pub(crate) fn init_audit_cell() {
     AUDIT_CELL.init(|aliases_to_resolve, aliases, cells, cwd, fs| {
         audit_cell(aliases_to_resolve, aliases, cells, cwd, fs)
     });
 }
+
+// The expected locations are Unix paths.
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use buck2_common::legacy_configs::configs::testing::parse;
+    use buck2_core::cells::cell_root_path::CellRootPathBuf;
+
+    use super::*;
+
+    fn cells() -> CellResolver {
+        CellResolver::testing_with_names_and_paths(&[
+            (
+                CellName::testing_new("root"),
+                CellRootPathBuf::testing_new(""),
+            ),
+            (
+                CellName::testing_new("foo"),
+                CellRootPathBuf::testing_new("foo"),
+            ),
+        ])
+    }
+
+    fn root_config() -> LegacyBuckConfig {
+        parse(
+            &[(
+                "/.buckconfig",
+                "[cells]\n  root = .\n  foo = foo\n[cell_aliases]\n  f = foo\n  ff = f\n",
+            )],
+            "/.buckconfig",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_find_alias_definition() {
+        let config = root_config();
+        match find_alias_definition(&config, "foo", true) {
+            Some(AliasDefinition::Cell { source }) => {
+                assert_eq!("[cells] at /.buckconfig:3", source)
+            }
+            _ => panic!("expected a cell definition"),
+        }
+        match find_alias_definition(&config, "ff", true) {
+            Some(AliasDefinition::Alias {
+                destination,
+                source,
+            }) => {
+                assert_eq!("f", destination);
+                assert_eq!("[cell_aliases] at /.buckconfig:6", source);
+            }
+            _ => panic!("expected an alias definition"),
+        }
+        // External cells ignore their `[cells]` section.
+        assert!(find_alias_definition(&config, "foo", false).is_none());
+        assert!(find_alias_definition(&config, "bar", true).is_none());
+    }
+
+    #[test]
+    fn test_trace_alias_chain() -> anyhow::Result<()> {
+        let config = root_config();
+        let steps = trace_alias(
+            "ff",
+            CellName::testing_new("root"),
+            &cells(),
+            &config,
+            &config,
+        )?;
+        assert_eq!(
+            vec![
+                "`ff` is an alias of `f` in cell `root` [cell_aliases] at /.buckconfig:6",
+                "`f` is an alias of `foo` in cell `root` [cell_aliases] at /.buckconfig:5",
+                "`foo` defined in cell `root` [cells] at /.buckconfig:3",
+            ],
+            steps
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_trace_alias_inherited_from_root() -> anyhow::Result<()> {
+        let root_config = root_config();
+        let foo_config = parse(
+            &[("/foo/.buckconfig", "[cells]\n  foo = .\n")],
+            "/foo/.buckconfig",
+        )?;
+        let foo = CellName::testing_new("foo");
+
+        assert_eq!(
+            vec!["`foo` is the name of the cell itself"],
+            trace_alias("foo", foo, &cells(), &foo_config, &root_config)?
+        );
+        assert_eq!(
+            vec!["`root` inherited from root cell `root` [cells] at /.buckconfig:2"],
+            trace_alias("root", foo, &cells(), &foo_config, &root_config)?
+        );
+        assert_eq!(
+            vec!["no definition found for `bar`"],
+            trace_alias("bar", foo, &cells(), &foo_config, &root_config)?
+        );
+        Ok(())
+    }
+}
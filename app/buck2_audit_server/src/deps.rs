@@ -0,0 +1,442 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::io::Write;
+
+use async_trait::async_trait;
+use buck2_audit::deps::AuditDepsCommand;
+use buck2_cli_proto::ClientContext;
+use buck2_core::provider::label::ConfiguredProvidersLabel;
+use buck2_core::target::configured_target_label::ConfiguredTargetLabel;
+use buck2_node::attrs::configured_traversal::ConfiguredAttrTraversal;
+use buck2_node::attrs::inspect_options::AttrInspectOptions;
+use buck2_node::nodes::configured::ConfiguredTargetNode;
+use buck2_node::nodes::configured_frontend::ConfiguredTargetNodeCalculation;
+use buck2_node::nodes::unconfigured::RuleKind;
+use buck2_server_ctx::ctx::ServerCommandContextTrait;
+use buck2_server_ctx::ctx::ServerCommandDiceContext;
+use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
+use dupe::Dupe;
+
+use crate::common::configured_target_labels::audit_command_configured_target_labels;
+use crate::ServerAuditSubcommand;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum DepKind {
+    Target,
+    Exec,
+    Toolchain,
+}
+
+impl DepKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            DepKind::Target => "target",
+            DepKind::Exec => "exec",
+            DepKind::Toolchain => "toolchain",
+        }
+    }
+}
+
+/// An edge from `parent` to `node`, with the reason it exists.
+struct DepEdge {
+    parent: ConfiguredTargetLabel,
+    node: ConfiguredTargetNode,
+    kind: DepKind,
+    /// Attributes of `parent` referencing `node`. Empty for deps added by the execution platform
+    /// resolution rather than an attribute.
+    attributes: Vec<String>,
+    /// Keys of the `select()` branches taken in those attributes.
+    select_keys: Vec<String>,
+}
+
+impl DepEdge {
+    fn to_json(&self, root: &ConfiguredTargetLabel, depth: u32) -> serde_json::Value {
+        serde_json::json!({
+            "root": root.to_string(),
+            "depth": depth,
+            "target": self.node.label().to_string(),
+            "parent": self.parent.to_string(),
+            "kind": self.kind.as_str(),
+            "attributes": self.attributes,
+            "select_keys": self.select_keys,
+        })
+    }
+
+    fn describe(&self, depth: u32) -> String {
+        let mut s = self.node.label().to_string();
+        if self.kind != DepKind::Target {
+            write!(s, " ({} dep)", self.kind.as_str()).unwrap();
+        }
+        match self.attributes.as_slice() {
+            [] => write!(s, " via execution platform resolution").unwrap(),
+            attrs => write!(
+                s,
+                " via {}",
+                attrs
+                    .iter()
+                    .map(|a| format!("`{}`", a))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+            .unwrap(),
+        }
+        if depth > 1 {
+            write!(s, " of {}", self.parent).unwrap();
+        }
+        if !self.select_keys.is_empty() {
+            write!(
+                s,
+                ", select branch {}",
+                self.select_keys
+                    .iter()
+                    .map(|k| format!("`{}`", k))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+            .unwrap();
+        }
+        s
+    }
+}
+
+#[derive(Default)]
+struct DepCollector {
+    deps: Vec<ConfiguredTargetLabel>,
+}
+
+impl ConfiguredAttrTraversal for DepCollector {
+    fn dep(&mut self, dep: &ConfiguredProvidersLabel) -> anyhow::Result<()> {
+        self.deps.push(dep.target().dupe());
+        Ok(())
+    }
+}
+
+/// The deps to list, from the command flags.
+#[derive(Clone, Copy)]
+struct DepsFilter {
+    max_depth: u32,
+    include_exec_deps: bool,
+    include_toolchain_deps: bool,
+}
+
+impl DepsFilter {
+    fn new(command: &AuditDepsCommand) -> Self {
+        DepsFilter {
+            max_depth: match (command.transitive, command.max_depth) {
+                (false, _) => 1,
+                (true, Some(max_depth)) => max_depth,
+                (true, None) => u32::MAX,
+            },
+            include_exec_deps: command.include_exec_deps,
+            include_toolchain_deps: command.include_toolchain_deps,
+        }
+    }
+}
+
+/// Direct deps of `node` allowed by `filter`, with their reasons.
+fn dep_edges(filter: &DepsFilter, node: &ConfiguredTargetNode) -> anyhow::Result<Vec<DepEdge>> {
+    let mut attributes: HashMap<ConfiguredTargetLabel, BTreeSet<&str>> = HashMap::new();
+    for attr in node.attrs(AttrInspectOptions::All) {
+        let mut collector = DepCollector::default();
+        attr.traverse(node.label().pkg(), &mut collector)?;
+        for dep in collector.deps {
+            attributes.entry(dep).or_default().insert(attr.name);
+        }
+    }
+
+    let mut selected: HashMap<&str, Vec<String>> = HashMap::new();
+    for (attr, key) in node.selected_keys()? {
+        selected.entry(attr).or_default().push(key.to_string());
+    }
+
+    let exec_deps: HashSet<&ConfiguredTargetLabel> = node.exec_deps().map(|d| d.label()).collect();
+
+    let mut edges = Vec::new();
+    for dep in node.deps() {
+        let kind = if exec_deps.contains(dep.label()) {
+            DepKind::Exec
+        } else {
+            match dep.rule_kind() {
+                RuleKind::Normal => DepKind::Target,
+                RuleKind::Toolchain => DepKind::Toolchain,
+                // Already reported as the keys of the `select()` branches.
+                RuleKind::Configuration => continue,
+            }
+        };
+        match kind {
+            DepKind::Exec if !filter.include_exec_deps => continue,
+            DepKind::Toolchain if !filter.include_toolchain_deps => continue,
+            _ => {}
+        }
+        let attrs = attributes.get(dep.label());
+        let select_keys = attrs
+            .into_iter()
+            .flatten()
+            .filter_map(|a| selected.get(a))
+            .flatten()
+            .cloned()
+            .collect();
+        edges.push(DepEdge {
+            parent: node.label().dupe(),
+            node: dep.dupe(),
+            kind,
+            attributes: attrs
+                .into_iter()
+                .flatten()
+                .map(|a| (*a).to_owned())
+                .collect(),
+            select_keys,
+        });
+    }
+    Ok(edges)
+}
+
+/// Deps of `root` allowed by `filter`, by distance from it. Breadth-first, so each dep is
+/// reported at its shortest distance from the root, attributed to the first edge reaching it.
+fn dep_levels(
+    filter: &DepsFilter,
+    root: ConfiguredTargetNode,
+) -> anyhow::Result<Vec<Vec<DepEdge>>> {
+    let mut visited: HashSet<ConfiguredTargetLabel> = HashSet::new();
+    visited.insert(root.label().dupe());
+    let mut levels: Vec<Vec<DepEdge>> = Vec::new();
+    let mut frontier = vec![root];
+    while !frontier.is_empty() && (levels.len() as u32) < filter.max_depth {
+        let mut level = Vec::new();
+        for node in &frontier {
+            for edge in dep_edges(filter, node)? {
+                if visited.insert(edge.node.label().dupe()) {
+                    level.push(edge);
+                }
+            }
+        }
+        if level.is_empty() {
+            break;
+        }
+        frontier = level.iter().map(|e| e.node.dupe()).collect();
+        levels.push(level);
+    }
+    Ok(levels)
+}
+
+#[async_trait]
+impl ServerAuditSubcommand for AuditDepsCommand {
+    async fn server_execute(
+        &self,
+        server_ctx: &dyn ServerCommandContextTrait,
+        mut stdout: PartialResultDispatcher<buck2_cli_proto::StdoutBytes>,
+        _client_ctx: ClientContext,
+    ) -> anyhow::Result<()> {
+        server_ctx
+            .with_dice_ctx_read_only(|server_ctx, mut ctx| async move {
+                let roots = audit_command_configured_target_labels(
+                    &mut ctx,
+                    &self.patterns,
+                    &self.target_cfg,
+                    server_ctx,
+                )
+                .await?;
+
+                let filter = DepsFilter::new(self);
+
+                let mut stdout = stdout.as_writer();
+                for root in roots {
+                    let root_node = ctx
+                        .get_configured_target_node(&root)
+                        .await?
+                        .require_compatible()?;
+                    let levels = dep_levels(&filter, root_node)?;
+
+                    if !self.json {
+                        writeln!(stdout, "{}", root)?;
+                    }
+                    for (i, level) in levels.iter().enumerate() {
+                        let depth = i as u32 + 1;
+                        if self.json {
+                            for edge in level {
+                                serde_json::to_writer(&mut stdout, &edge.to_json(&root, depth))?;
+                                writeln!(stdout)?;
+                            }
+                            continue;
+                        }
+                        if self.transitive {
+                            writeln!(stdout, "  depth {}:", depth)?;
+                        }
+                        let indent = if self.transitive { "    " } else { "  " };
+                        for edge in level {
+                            writeln!(stdout, "{}{}", indent, edge.describe(depth))?;
+                        }
+                    }
+                }
+                Ok(())
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use buck2_core::bzl::ImportPath;
+    use buck2_core::configuration::data::ConfigurationData;
+    use buck2_core::configuration::pair::ConfigurationNoExec;
+    use buck2_core::execution_types::execution::ExecutionPlatformResolution;
+    use buck2_core::plugins::PluginKindSet;
+    use buck2_core::plugins::PluginLists;
+    use buck2_core::provider::label::ProvidersLabel;
+    use buck2_core::provider::label::ProvidersName;
+    use buck2_node::attrs::attr::Attribute;
+    use buck2_node::attrs::attr_type::list::ListLiteral;
+    use buck2_node::attrs::attr_type::AttrType;
+    use buck2_node::attrs::coerced_attr::CoercedAttr;
+    use buck2_node::configuration::resolved::ResolvedConfiguration;
+    use buck2_node::configuration::resolved::ResolvedConfigurationSettings;
+    use buck2_node::nodes::unconfigured::testing::TargetNodeExt;
+    use buck2_node::nodes::unconfigured::TargetNode;
+    use buck2_node::provider_id_set::ProviderIdSet;
+    use buck2_node::rule_type::RuleType;
+    use buck2_node::rule_type::StarlarkRuleType;
+    use buck2_util::arc_str::ArcSlice;
+    use starlark_map::ordered_map::OrderedMap;
+
+    use super::*;
+
+    /// A node whose `deps` attribute references `deps`, and which has `exec_deps`.
+    fn node(
+        name: &str,
+        deps: Vec<ConfiguredTargetNode>,
+        exec_deps: Vec<ConfiguredTargetNode>,
+    ) -> ConfiguredTargetNode {
+        let label = ConfiguredTargetLabel::testing_parse(
+            &format!("cell//pkg:{}", name),
+            ConfigurationData::testing_new(),
+        );
+        let rule_type = RuleType::Starlark(Arc::new(StarlarkRuleType {
+            import_path: ImportPath::testing_new("cell//pkg:rules.bzl"),
+            name: "some_rule".to_owned(),
+        }));
+        let attrs = vec![(
+            "deps",
+            Attribute::new(
+                None,
+                "",
+                AttrType::list(AttrType::dep(ProviderIdSet::EMPTY, PluginKindSet::EMPTY)),
+            ),
+            CoercedAttr::List(ListLiteral(ArcSlice::from_iter(deps.iter().map(|dep| {
+                CoercedAttr::Dep(ProvidersLabel::new(
+                    dep.label().unconfigured().dupe(),
+                    ProvidersName::Default,
+                ))
+            })))),
+        )];
+        ConfiguredTargetNode::new(
+            label.dupe(),
+            TargetNode::testing_new(label.unconfigured().dupe(), rule_type, attrs, vec![]),
+            ResolvedConfiguration::new(
+                ConfigurationNoExec::new(label.cfg().dupe()),
+                ResolvedConfigurationSettings::empty(),
+            ),
+            OrderedMap::new(),
+            ExecutionPlatformResolution::unspecified(),
+            deps,
+            exec_deps,
+            OrderedMap::new(),
+            PluginLists::new(),
+        )
+    }
+
+    fn filter(max_depth: u32, include_exec_deps: bool) -> DepsFilter {
+        DepsFilter {
+            max_depth,
+            include_exec_deps,
+            include_toolchain_deps: false,
+        }
+    }
+
+    /// The names of the deps at each depth.
+    fn names(levels: &[Vec<DepEdge>]) -> Vec<Vec<String>> {
+        levels
+            .iter()
+            .map(|level| {
+                level
+                    .iter()
+                    .map(|edge| edge.node.label().name().as_str().to_owned())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// `root` depends on `lib` and `leaf`, `lib` on `leaf`, and `root` runs `tool`.
+    fn graph() -> ConfiguredTargetNode {
+        let leaf = node("leaf", vec![], vec![]);
+        let lib = node("lib", vec![leaf.dupe()], vec![]);
+        let tool = node("tool", vec![], vec![]);
+        node("root", vec![lib, leaf], vec![tool])
+    }
+
+    #[test]
+    fn test_direct_deps() -> anyhow::Result<()> {
+        let levels = dep_levels(&filter(1, false), graph())?;
+        assert_eq!(vec![vec!["lib", "leaf"]], names(&levels));
+        let lib = &levels[0][0];
+        assert_eq!(DepKind::Target, lib.kind);
+        assert_eq!(vec!["deps"], lib.attributes);
+        assert!(lib.select_keys.is_empty());
+        assert!(lib.describe(1).ends_with(" via `deps`"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_transitive_deps() -> anyhow::Result<()> {
+        let leaf = node("leaf", vec![], vec![]);
+        let lib = node("lib", vec![leaf], vec![]);
+        let root = node("root", vec![lib], vec![]);
+
+        let levels = dep_levels(&filter(u32::MAX, false), root.dupe())?;
+        assert_eq!(vec![vec!["lib"], vec!["leaf"]], names(&levels));
+        let leaf = &levels[1][0];
+        assert_eq!("lib", leaf.parent.name().as_str());
+        assert!(leaf.describe(2).contains(" of cell//pkg:lib"));
+        let json = leaf.to_json(root.label(), 2);
+        assert_eq!(2, json["depth"]);
+        assert_eq!(serde_json::json!(["deps"]), json["attributes"]);
+
+        let levels = dep_levels(&filter(1, false), root)?;
+        assert_eq!(vec![vec!["lib"]], names(&levels));
+        Ok(())
+    }
+
+    #[test]
+    fn test_deps_reported_at_shortest_distance() -> anyhow::Result<()> {
+        let levels = dep_levels(&filter(u32::MAX, false), graph())?;
+        // `leaf` is also a dep of `lib`, but it is only reported as a direct dep of `root`.
+        assert_eq!(vec![vec!["lib", "leaf"]], names(&levels));
+        Ok(())
+    }
+
+    #[test]
+    fn test_exec_deps_filtered() -> anyhow::Result<()> {
+        let levels = dep_levels(&filter(1, true), graph())?;
+        assert_eq!(vec![vec!["lib", "leaf", "tool"]], names(&levels));
+        let tool = &levels[0][2];
+        assert_eq!(DepKind::Exec, tool.kind);
+        assert!(tool.attributes.is_empty());
+        assert!(
+            tool.describe(1)
+                .ends_with(" (exec dep) via execution platform resolution")
+        );
+        Ok(())
+    }
+}
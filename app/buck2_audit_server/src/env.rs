@@ -0,0 +1,76 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::io::Write;
+
+use async_trait::async_trait;
+use buck2_audit::env::AuditEnvCommand;
+use buck2_audit::env::AuditEnvSource;
+use buck2_build_api::actions::execute::env_usage::HasActionEnvUsage;
+use buck2_cli_proto::ClientContext;
+use buck2_core::env::usage::process_env_usage;
+use buck2_core::env::usage::EnvUsageSource;
+use buck2_server_ctx::ctx::ServerCommandContextTrait;
+use buck2_server_ctx::ctx::ServerCommandDiceContext;
+use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
+
+use crate::ServerAuditSubcommand;
+
+#[async_trait]
+impl ServerAuditSubcommand for AuditEnvCommand {
+    async fn server_execute(
+        &self,
+        server_ctx: &dyn ServerCommandContextTrait,
+        mut stdout: PartialResultDispatcher<buck2_cli_proto::StdoutBytes>,
+        _client_ctx: ClientContext,
+    ) -> anyhow::Result<()> {
+        let source = self.source.map(|s| match s {
+            AuditEnvSource::Daemon => EnvUsageSource::Daemon,
+            AuditEnvSource::Interpreter => EnvUsageSource::Interpreter,
+            AuditEnvSource::Action => EnvUsageSource::Action,
+        });
+        let action_usage = server_ctx
+            .with_dice_ctx_read_only(|_server_ctx, ctx| async move {
+                Ok(ctx
+                    .per_transaction_data()
+                    .get_action_env_usage()
+                    .usage(self.trace_id.as_deref()))
+            })
+            .await?;
+        let usages: Vec<_> = process_env_usage()
+            .into_iter()
+            .chain(action_usage)
+            .filter(|u| source.map_or(true, |s| s == u.source))
+            .filter(|u| !self.changed || u.changed)
+            .collect();
+
+        let mut stdout = stdout.as_writer();
+        if self.json {
+            serde_json::to_writer_pretty(&mut stdout, &usages)?;
+            writeln!(stdout)?;
+        } else {
+            for usage in &usages {
+                let value = match &usage.value {
+                    Some(value) => format!("{:?}", value),
+                    None => "<unset>".to_owned(),
+                };
+                let changed = if usage.changed { " (changed)" } else { "" };
+                writeln!(
+                    stdout,
+                    "{}\t{}={}{}",
+                    usage.source, usage.name, value, changed
+                )?;
+                for consumer in &usage.consumers {
+                    writeln!(stdout, "    {}", consumer)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
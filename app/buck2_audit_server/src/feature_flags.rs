@@ -0,0 +1,96 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::io::Write;
+
+use async_trait::async_trait;
+use buck2_audit::feature_flags::AuditFeatureFlagsCommand;
+use buck2_cli_proto::ClientContext;
+use buck2_common::pattern::parse_from_cli::parse_and_resolve_patterns_from_cli_args;
+use buck2_core::package::PackageLabel;
+use buck2_core::pattern::pattern_type::TargetPatternExtra;
+use buck2_node::feature_flags::FeatureFlags;
+use buck2_node::package_values_calculation::PACKAGE_VALUES_CALCULATION;
+use buck2_server_ctx::ctx::ServerCommandContextTrait;
+use buck2_server_ctx::ctx::ServerCommandDiceContext;
+use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
+use dupe::Dupe;
+use futures::FutureExt;
+use starlark_map::small_map::SmallMap;
+
+use crate::ServerAuditSubcommand;
+
+#[async_trait]
+impl ServerAuditSubcommand for AuditFeatureFlagsCommand {
+    async fn server_execute(
+        &self,
+        server_ctx: &dyn ServerCommandContextTrait,
+        mut stdout: PartialResultDispatcher<buck2_cli_proto::StdoutBytes>,
+        _client_ctx: ClientContext,
+    ) -> anyhow::Result<()> {
+        server_ctx
+            .with_dice_ctx_read_only(|server_ctx, mut ctx| async move {
+                let resolved = parse_and_resolve_patterns_from_cli_args::<TargetPatternExtra>(
+                    &mut ctx,
+                    &self.patterns,
+                    server_ctx.working_dir(),
+                )
+                .await?;
+                let packages: Vec<PackageLabel> = resolved.specs.keys().map(|p| p.dupe()).collect();
+
+                let flags_by_package = ctx
+                    .try_compute_join(packages, |ctx, package| {
+                        async move {
+                            let package_values = PACKAGE_VALUES_CALCULATION
+                                .get()?
+                                .package_values(ctx, package.dupe())
+                                .await?;
+                            let flags = FeatureFlags::from_package_values_json(&package_values)?;
+                            anyhow::Ok((package, flags))
+                        }
+                        .boxed()
+                    })
+                    .await?;
+
+                let mut stdout = stdout.as_writer();
+                if self.json {
+                    let json: SmallMap<String, SmallMap<&str, _>> = flags_by_package
+                        .iter()
+                        .map(|(package, flags)| {
+                            let flags = flags
+                                .iter()
+                                .filter(|(name, _)| {
+                                    self.flag.as_deref().map_or(true, |f| f == *name)
+                                })
+                                .collect();
+                            (package.to_string(), flags)
+                        })
+                        .collect();
+                    serde_json::to_writer_pretty(&mut stdout, &json)?;
+                    writeln!(stdout)?;
+                } else if let Some(flag) = &self.flag {
+                    for (package, flags) in &flags_by_package {
+                        match flags.get(flag) {
+                            Some(value) => writeln!(stdout, "{}: {}", package, value)?,
+                            None => writeln!(stdout, "{}: <unset>", package)?,
+                        }
+                    }
+                } else {
+                    for (package, flags) in &flags_by_package {
+                        writeln!(stdout, "{}", package)?;
+                        for (name, value) in flags.iter() {
+                            writeln!(stdout, "    {} = {}", name, value)?;
+                        }
+                    }
+                }
+                Ok(())
+            })
+            .await
+    }
+}
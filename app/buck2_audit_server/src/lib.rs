@@ -27,15 +27,21 @@ mod config;
 mod configurations;
 pub mod deferred_materializer;
 mod dep_files;
+mod deps;
+mod env;
 mod execution_platform_resolution;
+mod feature_flags;
 mod includes;
 pub mod output;
 mod package_values;
 mod prelude;
 mod providers;
+mod select_coverage;
 pub mod server;
+mod source_references;
 mod starlark;
 mod subtargets;
+mod tsets;
 mod visibility;
 
 /// `buck2 audit` subcommands have a somewhat unique approach to make it really easy to
@@ -98,6 +104,12 @@ impl AuditCommandExt for AuditCommand {
             AuditCommand::Output(cmd) => cmd,
             AuditCommand::Parse(cmd) => cmd,
             AuditCommand::PackageValues(cmd) => cmd,
+            AuditCommand::FeatureFlags(cmd) => cmd,
+            AuditCommand::SourceReferences(cmd) => cmd,
+            AuditCommand::SelectCoverage(cmd) => cmd,
+            AuditCommand::Deps(cmd) => cmd,
+            AuditCommand::Tsets(cmd) => cmd,
+            AuditCommand::Env(cmd) => cmd,
         }
     }
 }
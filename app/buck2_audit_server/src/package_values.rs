@@ -16,6 +16,7 @@ use buck2_core::package::PackageLabel;
 use buck2_core::pattern::parse_package::parse_package;
 use buck2_events::dispatch::console_message;
 use buck2_node::metadata::key::MetadataKey;
+use buck2_node::package_values_calculation::PackageFileValues;
 use buck2_node::package_values_calculation::PACKAGE_VALUES_CALCULATION;
 use buck2_server_ctx::ctx::ServerCommandContextTrait;
 use buck2_server_ctx::ctx::ServerCommandDiceContext;
@@ -27,6 +28,61 @@ use starlark_map::small_map::SmallMap;
 
 use crate::ServerAuditSubcommand;
 
+#[derive(serde::Serialize)]
+struct PackageValuesProvenance {
+    /// All the `PACKAGE` files applying to the package, starting from the project root.
+    package_files: Vec<String>,
+    values: SmallMap<MetadataKey, PackageValueProvenance>,
+}
+
+#[derive(serde::Serialize)]
+struct PackageValueProvenance {
+    /// The value seen by the package.
+    value: serde_json::Value,
+    /// Writes of the value, the last one wins.
+    set_by: Vec<PackageValueWrite>,
+}
+
+#[derive(serde::Serialize)]
+struct PackageValueWrite {
+    file: String,
+    value: serde_json::Value,
+    overwrite: bool,
+}
+
+fn provenance(
+    package_values: SmallMap<MetadataKey, serde_json::Value>,
+    package_files: Vec<PackageFileValues>,
+) -> PackageValuesProvenance {
+    let mut values: SmallMap<MetadataKey, PackageValueProvenance> = package_values
+        .into_iter()
+        .map(|(key, value)| {
+            (
+                key,
+                PackageValueProvenance {
+                    value,
+                    set_by: Vec::new(),
+                },
+            )
+        })
+        .collect();
+    for file in &package_files {
+        for (key, written) in &file.values {
+            if let Some(value) = values.get_mut(key) {
+                value.set_by.push(PackageValueWrite {
+                    file: file.path.clone(),
+                    value: written.value.clone(),
+                    overwrite: written.overwrite,
+                });
+            }
+        }
+    }
+    PackageValuesProvenance {
+        package_files: package_files.into_iter().map(|file| file.path).collect(),
+        values,
+    }
+}
+
 #[async_trait]
 impl ServerAuditSubcommand for PackageValuesCommand {
     async fn server_execute(
@@ -40,7 +96,7 @@ impl ServerAuditSubcommand for PackageValuesCommand {
         }
 
         server_ctx
-            .with_dice_ctx(|server_ctx, mut dice_ctx| async move {
+            .with_dice_ctx_read_only(|server_ctx, mut dice_ctx| async move {
                 let cell_resolver = dice_ctx.get_cell_resolver().await?;
                 let cell_alias_resolver =
                     cell_resolver.get_cwd_cell_alias_resolver(server_ctx.working_dir())?;
@@ -49,6 +105,7 @@ impl ServerAuditSubcommand for PackageValuesCommand {
                     .packages
                     .try_map(|package| parse_package(package.dupe(), cell_alias_resolver))?;
 
+                let provenance_requested = self.provenance;
                 let package_values_by_package = dice_ctx
                     .try_compute_join(packages, |ctx, package| {
                         async move {
@@ -56,18 +113,45 @@ impl ServerAuditSubcommand for PackageValuesCommand {
                                 .get()?
                                 .package_values(ctx, package.dupe())
                                 .await?;
-                            anyhow::Ok((package, package_values))
+                            let package_files = if provenance_requested {
+                                Some(
+                                    PACKAGE_VALUES_CALCULATION
+                                        .get()?
+                                        .package_values_provenance(ctx, package.dupe())
+                                        .await?,
+                                )
+                            } else {
+                                None
+                            };
+                            anyhow::Ok((package, package_values, package_files))
                         }
                         .boxed()
                     })
                     .await?;
-                let package_values_by_package: SmallMap<
-                    PackageLabel,
-                    SmallMap<MetadataKey, serde_json::Value>,
-                > = package_values_by_package.into_iter().collect();
 
                 let mut stdout = stdout.as_writer();
-                serde_json::to_writer_pretty(&mut stdout, &package_values_by_package)?;
+                if provenance_requested {
+                    let provenance_by_package: SmallMap<PackageLabel, PackageValuesProvenance> =
+                        package_values_by_package
+                            .into_iter()
+                            .map(|(package, package_values, package_files)| {
+                                (
+                                    package,
+                                    provenance(package_values, package_files.unwrap_or_default()),
+                                )
+                            })
+                            .collect();
+                    serde_json::to_writer_pretty(&mut stdout, &provenance_by_package)?;
+                } else {
+                    let package_values_by_package: SmallMap<
+                        PackageLabel,
+                        SmallMap<MetadataKey, serde_json::Value>,
+                    > = package_values_by_package
+                        .into_iter()
+                        .map(|(package, package_values, _)| (package, package_values))
+                        .collect();
+                    serde_json::to_writer_pretty(&mut stdout, &package_values_by_package)?;
+                }
                 // Because serde does not write a trailing newline.
                 writeln!(stdout)?;
                 Ok(())
@@ -0,0 +1,334 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashSet;
+use std::io::Write;
+
+use async_trait::async_trait;
+use buck2_audit::select_coverage::AuditSelectCoverageCommand;
+use buck2_cli_proto::ClientContext;
+use buck2_common::dice::cells::HasCellResolver;
+use buck2_common::global_cfg_options::GlobalCfgOptions;
+use buck2_common::pattern::parse_from_cli::parse_patterns_from_cli_args;
+use buck2_core::configuration::compatibility::MaybeCompatible;
+use buck2_core::pattern::pattern::ParsedPattern;
+use buck2_core::pattern::pattern_type::TargetPatternExtra;
+use buck2_core::target::label::label::TargetLabel;
+use buck2_node::attrs::inspect_options::AttrInspectOptions;
+use buck2_node::configuration::resolved::ConfigurationSettingKey;
+use buck2_node::load_patterns::load_patterns;
+use buck2_node::load_patterns::MissingTargetBehavior;
+use buck2_node::nodes::configured_frontend::ConfiguredTargetNodeCalculation;
+use buck2_node::nodes::unconfigured::TargetNodeRef;
+use buck2_node::target_calculation::ConfiguredTargetCalculation;
+use buck2_server_ctx::ctx::ServerCommandContextTrait;
+use buck2_server_ctx::ctx::ServerCommandDiceContext;
+use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
+use dupe::Dupe;
+
+use crate::ServerAuditSubcommand;
+
+/// The selects of one attribute of a target.
+#[derive(Default)]
+struct AttrSelects {
+    keys: BTreeSet<ConfigurationSettingKey>,
+    missing_default: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum Finding {
+    UnreachableBranch(ConfigurationSettingKey),
+    MissingDefault,
+}
+
+impl Finding {
+    fn to_json(&self, target: &TargetLabel, attribute: &str) -> serde_json::Value {
+        match self {
+            Finding::UnreachableBranch(key) => serde_json::json!({
+                "target": target.to_string(),
+                "attribute": attribute,
+                "kind": "unreachable_branch",
+                "key": key.to_string(),
+            }),
+            Finding::MissingDefault => serde_json::json!({
+                "target": target.to_string(),
+                "attribute": attribute,
+                "kind": "missing_default",
+            }),
+        }
+    }
+}
+
+/// Record the selects in the attributes of `node`, by target and attribute.
+fn collect_selects(
+    node: TargetNodeRef<'_>,
+    selects: &mut BTreeMap<(TargetLabel, String), AttrSelects>,
+) {
+    for attr in node.attrs(AttrInspectOptions::All) {
+        attr.value.visit_selectors(&mut |select| {
+            let entry = selects
+                .entry((node.label().dupe(), attr.name.to_owned()))
+                .or_default();
+            entry.keys.extend(select.keys().cloned());
+            entry.missing_default |= !select.has_default();
+        });
+    }
+}
+
+/// The findings for the selects of the targets in `compatible`: the branches not `taken` in any
+/// configuration, and the selects without a default.
+fn find_uncovered<'a>(
+    selects: &'a BTreeMap<(TargetLabel, String), AttrSelects>,
+    compatible: &HashSet<TargetLabel>,
+    taken: &HashSet<(TargetLabel, String, ConfigurationSettingKey)>,
+    ignore_missing_default: bool,
+) -> Vec<(&'a TargetLabel, &'a str, Finding)> {
+    let mut findings = Vec::new();
+    for ((target, attribute), attr_selects) in selects {
+        if !compatible.contains(target) {
+            continue;
+        }
+        for key in &attr_selects.keys {
+            if !taken.contains(&(target.dupe(), attribute.clone(), key.clone())) {
+                findings.push((
+                    target,
+                    attribute.as_str(),
+                    Finding::UnreachableBranch(key.clone()),
+                ));
+            }
+        }
+        if attr_selects.missing_default && !ignore_missing_default {
+            findings.push((target, attribute.as_str(), Finding::MissingDefault));
+        }
+    }
+    findings
+}
+
+#[async_trait]
+impl ServerAuditSubcommand for AuditSelectCoverageCommand {
+    async fn server_execute(
+        &self,
+        server_ctx: &dyn ServerCommandContextTrait,
+        mut stdout: PartialResultDispatcher<buck2_cli_proto::StdoutBytes>,
+        _client_ctx: ClientContext,
+    ) -> anyhow::Result<()> {
+        server_ctx
+            .with_dice_ctx_read_only(|server_ctx, mut ctx| async move {
+                let cell_resolver = ctx.get_cell_resolver().await?;
+                let cell_alias_resolver =
+                    cell_resolver.get_cwd_cell_alias_resolver(server_ctx.working_dir())?;
+                let cwd = cell_resolver.get_cell_path(server_ctx.working_dir())?;
+                let mut configurations = Vec::new();
+                for platform in &self.platforms {
+                    configurations.push(GlobalCfgOptions {
+                        target_platform: Some(
+                            ParsedPattern::parse_precise(
+                                platform,
+                                cwd.cell(),
+                                &cell_resolver,
+                                cell_alias_resolver,
+                            )?
+                            .as_target_label(platform)?,
+                        ),
+                        cli_modifiers: Default::default(),
+                    });
+                }
+                if configurations.is_empty() {
+                    configurations.push(GlobalCfgOptions::default());
+                }
+
+                let parsed_patterns = parse_patterns_from_cli_args::<TargetPatternExtra>(
+                    &mut ctx,
+                    &self.patterns,
+                    server_ctx.working_dir(),
+                )
+                .await?;
+                let loaded =
+                    load_patterns(&mut ctx, parsed_patterns, MissingTargetBehavior::Fail).await?;
+
+                let mut selects: BTreeMap<(TargetLabel, String), AttrSelects> = BTreeMap::new();
+                for node in loaded.iter_loaded_targets() {
+                    collect_selects(node?, &mut selects);
+                }
+
+                // Targets compatible with at least one configuration, and the branches they take.
+                let mut compatible = HashSet::new();
+                let mut taken = HashSet::new();
+                let targets: BTreeSet<&TargetLabel> = selects.keys().map(|(t, _)| t).collect();
+                for target in targets {
+                    for configuration in &configurations {
+                        let label = ctx.get_configured_target(target, configuration).await?;
+                        match ctx.get_configured_target_node(&label).await? {
+                            MaybeCompatible::Incompatible(_) => {}
+                            MaybeCompatible::Compatible(node) => {
+                                compatible.insert(target.dupe());
+                                for (attr, key) in node.selected_keys()? {
+                                    taken.insert((target.dupe(), attr.to_owned(), key.clone()));
+                                }
+                            }
+                        }
+                    }
+                }
+
+                let mut stdout = stdout.as_writer();
+                for (target, attribute, finding) in
+                    find_uncovered(&selects, &compatible, &taken, self.ignore_missing_default)
+                {
+                    if self.json {
+                        serde_json::to_writer(&mut stdout, &finding.to_json(target, attribute))?;
+                        writeln!(stdout)?;
+                        continue;
+                    }
+                    match finding {
+                        Finding::UnreachableBranch(key) => writeln!(
+                            stdout,
+                            "{} (attribute `{}`): branch `{}` is not taken in any configuration",
+                            target, attribute, key
+                        )?,
+                        Finding::MissingDefault => writeln!(
+                            stdout,
+                            "{} (attribute `{}`): select without `DEFAULT` fails in configurations matching none of its keys",
+                            target, attribute
+                        )?,
+                    }
+                }
+                Ok(())
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use buck2_core::bzl::ImportPath;
+    use buck2_node::attrs::attr::Attribute;
+    use buck2_node::attrs::attr_type::string::StringLiteral;
+    use buck2_node::attrs::attr_type::AttrType;
+    use buck2_node::attrs::coerced_attr::CoercedAttr;
+    use buck2_node::attrs::coerced_attr::CoercedSelector;
+    use buck2_node::nodes::unconfigured::testing::TargetNodeExt;
+    use buck2_node::nodes::unconfigured::TargetNode;
+    use buck2_node::rule_type::RuleType;
+    use buck2_node::rule_type::StarlarkRuleType;
+    use buck2_util::arc_str::ArcSlice;
+
+    use super::*;
+
+    fn string(s: &str) -> CoercedAttr {
+        CoercedAttr::String(StringLiteral(s.into()))
+    }
+
+    /// A target whose `flags` attribute is a select on `:linux` and `:macos`, with a default
+    /// when `default` is set.
+    fn target(default: bool) -> anyhow::Result<TargetNode> {
+        let select = CoercedSelector::new(
+            ArcSlice::new([
+                (
+                    ConfigurationSettingKey::testing_parse("cell//os:linux"),
+                    string("-DLINUX"),
+                ),
+                (
+                    ConfigurationSettingKey::testing_parse("cell//os:macos"),
+                    string("-DMACOS"),
+                ),
+            ]),
+            default.then(|| string("")),
+        )?;
+        Ok(TargetNode::testing_new(
+            TargetLabel::testing_parse("cell//pkg:foo"),
+            RuleType::Starlark(Arc::new(StarlarkRuleType {
+                import_path: ImportPath::testing_new("cell//pkg:rules.bzl"),
+                name: "some_rule".to_owned(),
+            })),
+            vec![(
+                "flags",
+                Attribute::new(None, "", AttrType::string()),
+                CoercedAttr::Selector(Box::new(select)),
+            )],
+            vec![],
+        ))
+    }
+
+    fn taken(keys: &[&str]) -> HashSet<(TargetLabel, String, ConfigurationSettingKey)> {
+        keys.iter()
+            .map(|key| {
+                (
+                    TargetLabel::testing_parse("cell//pkg:foo"),
+                    "flags".to_owned(),
+                    ConfigurationSettingKey::testing_parse(key),
+                )
+            })
+            .collect()
+    }
+
+    fn findings(
+        node: &TargetNode,
+        taken: &HashSet<(TargetLabel, String, ConfigurationSettingKey)>,
+        ignore_missing_default: bool,
+    ) -> Vec<(String, Finding)> {
+        let mut selects = BTreeMap::new();
+        collect_selects(node.as_ref(), &mut selects);
+        let compatible = HashSet::from([node.label().dupe()]);
+        find_uncovered(&selects, &compatible, taken, ignore_missing_default)
+            .into_iter()
+            .map(|(target, attribute, finding)| (format!("{} {}", target, attribute), finding))
+            .collect()
+    }
+
+    #[test]
+    fn test_all_branches_covered() -> anyhow::Result<()> {
+        let node = target(true)?;
+        let taken = taken(&["cell//os:linux", "cell//os:macos"]);
+        assert_eq!(
+            Vec::<(String, Finding)>::new(),
+            findings(&node, &taken, false)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_uncovered_branch() -> anyhow::Result<()> {
+        let node = target(true)?;
+        assert_eq!(
+            vec![(
+                "cell//pkg:foo flags".to_owned(),
+                Finding::UnreachableBranch(ConfigurationSettingKey::testing_parse(
+                    "cell//os:macos"
+                )),
+            )],
+            findings(&node, &taken(&["cell//os:linux"]), false)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_default() -> anyhow::Result<()> {
+        let node = target(false)?;
+        let taken = taken(&["cell//os:linux", "cell//os:macos"]);
+        assert_eq!(
+            vec![("cell//pkg:foo flags".to_owned(), Finding::MissingDefault)],
+            findings(&node, &taken, false)
+        );
+        assert!(findings(&node, &taken, true).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_incompatible_target_ignored() -> anyhow::Result<()> {
+        let node = target(false)?;
+        let mut selects = BTreeMap::new();
+        collect_selects(node.as_ref(), &mut selects);
+        assert!(find_uncovered(&selects, &HashSet::new(), &taken(&[]), false).is_empty());
+        Ok(())
+    }
+}
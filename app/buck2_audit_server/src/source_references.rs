@@ -0,0 +1,226 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::cell::RefCell;
+use std::io::Write;
+
+use async_trait::async_trait;
+use buck2_audit::source_references::AuditSourceReferencesCommand;
+use buck2_audit::source_references::ReferenceSeverity;
+use buck2_cli_proto::ClientContext;
+use buck2_common::dice::cells::HasCellResolver;
+use buck2_common::package_listing::dice::DicePackageListingResolver;
+use buck2_common::pattern::parse_from_cli::parse_patterns_from_cli_args;
+use buck2_core::package::package_relative_path::PackageRelativePath;
+use buck2_core::package::PackageLabel;
+use buck2_core::pattern::pattern_type::TargetPatternExtra;
+use buck2_core::target::label::label::TargetLabel;
+use buck2_node::attrs::inspect_options::AttrInspectOptions;
+use buck2_node::load_patterns::load_patterns;
+use buck2_node::load_patterns::MissingTargetBehavior;
+use buck2_server_ctx::ctx::ServerCommandContextTrait;
+use buck2_server_ctx::ctx::ServerCommandDiceContext;
+use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
+use dupe::Dupe;
+
+use crate::ServerAuditSubcommand;
+
+#[derive(Debug, buck2_error::Error)]
+#[buck2(input)]
+enum SourceReferencesError {
+    #[error("Found {0} reference(s) with severity `{1}` or higher")]
+    Found(usize, ReferenceSeverity),
+}
+
+struct SourceReference {
+    target: TargetLabel,
+    severity: ReferenceSeverity,
+    attribute: Option<String>,
+    reference: String,
+    reason: String,
+}
+
+impl SourceReference {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "target": self.target.to_string(),
+            "severity": self.severity,
+            "attribute": self.attribute,
+            "reference": self.reference,
+            "reason": self.reason,
+        })
+    }
+}
+
+/// Whether a token of a string attribute looks like a path leaving the package: a relative path
+/// going up (`../include`, `-I../include`) or an absolute path (`/usr/include`). Target labels
+/// (`//foo:bar`) are not paths.
+fn is_escaping_path(token: &str) -> bool {
+    token == ".."
+        || token.contains("../")
+        || token.ends_with("/..")
+        || (token.starts_with('/') && !token.starts_with("//") && token.len() > 1)
+}
+
+fn escaping_paths(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split(|c: char| c.is_whitespace() || c == '=' || c == ',' || c == '"')
+        .filter(|token| is_escaping_path(token))
+}
+
+#[async_trait]
+impl ServerAuditSubcommand for AuditSourceReferencesCommand {
+    async fn server_execute(
+        &self,
+        server_ctx: &dyn ServerCommandContextTrait,
+        mut stdout: PartialResultDispatcher<buck2_cli_proto::StdoutBytes>,
+        _client_ctx: ClientContext,
+    ) -> anyhow::Result<()> {
+        server_ctx
+            .with_dice_ctx_read_only(|server_ctx, mut ctx| async move {
+                let parsed_patterns = parse_patterns_from_cli_args::<TargetPatternExtra>(
+                    &mut ctx,
+                    &self.patterns,
+                    server_ctx.working_dir(),
+                )
+                .await?;
+                let loaded =
+                    load_patterns(&mut ctx, parsed_patterns, MissingTargetBehavior::Fail).await?;
+                let cell_resolver = ctx.get_cell_resolver().await?;
+
+                let mut references = Vec::new();
+                for (package, result) in loaded.iter() {
+                    let nodes = result.as_ref().map_err(Dupe::dupe)?;
+                    let listing = DicePackageListingResolver(&mut ctx)
+                        .resolve_package_listing(package.dupe())
+                        .await?;
+                    let subpackages: Vec<&PackageRelativePath> = listing
+                        .subpackages_within(PackageRelativePath::empty())
+                        .collect();
+
+                    for node in nodes.values() {
+                        for input in node.inputs() {
+                            let project_path = cell_resolver.resolve_path(input.as_ref())?;
+                            let cell = cell_resolver.find(&project_path)?;
+                            if cell != input.cell() {
+                                references.push(SourceReference {
+                                    target: node.label().dupe(),
+                                    severity: ReferenceSeverity::Error,
+                                    attribute: None,
+                                    reference: project_path.to_string(),
+                                    reason: format!("source file belongs to cell `{}`", cell),
+                                });
+                                continue;
+                            }
+                            let relative = input.strip_prefix(package.as_cell_path())?;
+                            if let Some(subpackage) =
+                                subpackages.iter().find(|p| relative.starts_with(p))
+                            {
+                                references.push(SourceReference {
+                                    target: node.label().dupe(),
+                                    severity: ReferenceSeverity::Warning,
+                                    attribute: None,
+                                    reference: input.to_string(),
+                                    reason: format!(
+                                        "source file belongs to package `{}`",
+                                        PackageLabel::from_cell_path(
+                                            package.as_cell_path().join(subpackage).as_ref()
+                                        )
+                                    ),
+                                });
+                            }
+                        }
+
+                        for attr in node.attrs(AttrInspectOptions::DefinedOnly) {
+                            let found = RefCell::new(Vec::new());
+                            attr.value.any_matches(&|value| {
+                                found
+                                    .borrow_mut()
+                                    .extend(escaping_paths(value).map(|x| x.to_owned()));
+                                Ok(false)
+                            })?;
+                            for path in found.into_inner() {
+                                references.push(SourceReference {
+                                    target: node.label().dupe(),
+                                    severity: ReferenceSeverity::Advice,
+                                    attribute: Some(attr.name.to_owned()),
+                                    reason: "string looks like a path outside the package"
+                                        .to_owned(),
+                                    reference: path,
+                                });
+                            }
+                        }
+                    }
+                }
+
+                references.retain(|r| r.severity >= self.min_severity);
+                // Most severe first.
+                references.sort_by(|a, b| {
+                    b.severity
+                        .cmp(&a.severity)
+                        .then_with(|| a.target.cmp(&b.target))
+                        .then_with(|| a.reference.cmp(&b.reference))
+                });
+
+                let mut stdout = stdout.as_writer();
+                for reference in &references {
+                    if self.json {
+                        serde_json::to_writer(&mut stdout, &reference.to_json())?;
+                        writeln!(stdout)?;
+                    } else {
+                        match &reference.attribute {
+                            Some(attribute) => writeln!(
+                                stdout,
+                                "{}: {} (attribute `{}`): `{}`: {}",
+                                reference.severity,
+                                reference.target,
+                                attribute,
+                                reference.reference,
+                                reference.reason
+                            )?,
+                            None => writeln!(
+                                stdout,
+                                "{}: {}: `{}`: {}",
+                                reference.severity,
+                                reference.target,
+                                reference.reference,
+                                reference.reason
+                            )?,
+                        }
+                    }
+                }
+
+                if let Some(fail_on) = self.fail_on {
+                    let count = references.iter().filter(|r| r.severity >= fail_on).count();
+                    if count > 0 {
+                        return Err(SourceReferencesError::Found(count, fail_on).into());
+                    }
+                }
+                Ok(())
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escaping_paths() {
+        assert_eq!(
+            vec!["-I../include", "/usr/lib/libfoo.so", "../data"],
+            escaping_paths(
+                "-I../include -L. /usr/lib/libfoo.so //foo:bar $(location :x) --data=../data"
+            )
+            .collect::<Vec<_>>()
+        );
+        assert_eq!(0, escaping_paths("//foo/... src/a.c ...").count());
+    }
+}
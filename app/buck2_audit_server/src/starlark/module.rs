@@ -29,7 +29,7 @@ pub(crate) async fn server_execute(
     _client_ctx: ClientContext,
 ) -> anyhow::Result<()> {
     server_ctx
-        .with_dice_ctx(|server_ctx, mut dice_ctx| async move {
+        .with_dice_ctx_read_only(|server_ctx, mut dice_ctx| async move {
             let cell_resolver = dice_ctx.get_cell_resolver().await?;
             let cwd = server_ctx.working_dir();
             let current_cell_path = cell_resolver.get_cell_path(cwd)?;
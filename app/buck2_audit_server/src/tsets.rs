@@ -0,0 +1,89 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::io::Write;
+
+use async_trait::async_trait;
+use buck2_audit::tsets::AuditTsetsCommand;
+use buck2_build_api::analysis::calculation::RuleAnalysisCalculation;
+use buck2_build_api::interpreter::rule_defs::transitive_set::stats::TransitiveSetStatsCollector;
+use buck2_cli_proto::ClientContext;
+use buck2_server_ctx::ctx::ServerCommandContextTrait;
+use buck2_server_ctx::ctx::ServerCommandDiceContext;
+use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
+use buck2_server_ctx::pattern_parse_and_resolve::parse_and_resolve_provider_labels_from_cli_args;
+
+use crate::common::target_resolution_config::audit_command_target_resolution_config;
+use crate::ServerAuditSubcommand;
+
+#[async_trait]
+impl ServerAuditSubcommand for AuditTsetsCommand {
+    async fn server_execute(
+        &self,
+        server_ctx: &dyn ServerCommandContextTrait,
+        mut stdout: PartialResultDispatcher<buck2_cli_proto::StdoutBytes>,
+        _client_ctx: ClientContext,
+    ) -> anyhow::Result<()> {
+        server_ctx
+            .with_dice_ctx_read_only(|server_ctx, mut ctx| async move {
+                let target_resolution_config =
+                    audit_command_target_resolution_config(&mut ctx, &self.target_cfg, server_ctx)
+                        .await?;
+                let provider_labels = parse_and_resolve_provider_labels_from_cli_args(
+                    &mut ctx,
+                    &self.patterns,
+                    server_ctx.working_dir(),
+                )
+                .await?;
+
+                let mut stdout = stdout.as_writer();
+                for label in provider_labels {
+                    for providers_label in target_resolution_config
+                        .get_configured_provider_label(&mut ctx, &label)
+                        .await?
+                    {
+                        let providers = ctx
+                            .get_providers(&providers_label)
+                            .await?
+                            .require_compatible()?;
+                        let mut collector = TransitiveSetStatsCollector::new();
+                        collector.add_providers(providers.provider_collection())?;
+                        let stats = collector.finish();
+
+                        if self.json {
+                            for s in &stats {
+                                let mut value = serde_json::to_value(s)?;
+                                value["target"] = providers_label.to_string().into();
+                                serde_json::to_writer(&mut stdout, &value)?;
+                                writeln!(stdout)?;
+                            }
+                            continue;
+                        }
+                        writeln!(stdout, "{}:", providers_label)?;
+                        for s in &stats {
+                            writeln!(
+                                stdout,
+                                "  {}: {} roots, {} sets ({} with values), {} edges, {:.0} visited without dedupe ({:.1}x), {} projection values",
+                                s.definition,
+                                s.roots,
+                                s.sets,
+                                s.values,
+                                s.edges,
+                                s.expanded,
+                                s.duplication,
+                                s.projection_values,
+                            )?;
+                        }
+                    }
+                }
+                Ok(())
+            })
+            .await
+    }
+}
@@ -62,6 +62,7 @@ use dupe::Dupe;
 use indexmap::indexmap;
 use indexmap::IndexMap;
 use indexmap::IndexSet;
+use sorted_vector_map::SortedVectorMap;
 use starlark::values::OwnedFrozenValue;
 use static_assertions::_core::ops::Deref;
 
@@ -162,6 +163,13 @@ pub trait Action: Allocative + Debug + Send + Sync + 'static {
         None
     }
 
+    /// Names of client environment variables this action depends on. Their values are made
+    /// available through `ActionExecutionCtx::passthrough_env`, and a change to any of them
+    /// re-runs the action.
+    fn env_passthrough(&self) -> &[String] {
+        &[]
+    }
+
     // TODO this probably wants more data for execution, like printing a short_name and the target
 }
 
@@ -266,6 +274,10 @@ pub trait ActionExecutionCtx: Send + Sync {
     /// Obtain per-command knobs for RunAction.
     fn run_action_knobs(&self) -> RunActionKnobs;
 
+    /// Values of the client environment variables declared by `Action::env_passthrough` which
+    /// are set. Unset variables are omitted.
+    fn passthrough_env(&self) -> &SortedVectorMap<String, String>;
+
     fn cancellation_context(&self) -> &CancellationContext;
 
     /// I/O layer access to add non-source files (e.g. downloaded files) to
@@ -30,12 +30,13 @@ pub trait ArtifactMaterializer {
     async fn materialize(&mut self, artifact: &Artifact) -> anyhow::Result<ProjectRelativePathBuf>;
 
     /// called to materialized the final set of requested artifacts for the build of a target.
-    /// This method will render events in superconsole
+    /// This method will render events in superconsole. Returns whether the artifact was
+    /// materialized, rather than skipped.
     async fn try_materialize_requested_artifact(
         &mut self,
         artifact: &BuildArtifact,
         required: bool,
-    ) -> anyhow::Result<()>;
+    ) -> anyhow::Result<bool>;
 }
 
 #[async_trait]
@@ -52,7 +53,7 @@ impl ArtifactMaterializer for DiceComputations<'_> {
         &mut self,
         artifact: &BuildArtifact,
         required: bool,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<bool> {
         let materializer = self.per_transaction_data().get_materializer();
         let artifact_fs = self.get_artifact_fs().await?;
         let path = artifact_fs.resolve_build(artifact.get_path());
@@ -67,8 +68,9 @@ impl ArtifactMaterializer for DiceComputations<'_> {
             let result: anyhow::Result<_> = try {
                 if required {
                     materializer.ensure_materialized(vec![path]).await?;
+                    true
                 } else {
-                    materializer.try_materialize_final_artifact(path).await?;
+                    materializer.try_materialize_final_artifact(path).await?
                 }
             };
 
@@ -17,6 +17,7 @@ use async_trait::async_trait;
 use buck2_artifact::actions::key::ActionKey;
 use buck2_artifact::artifact::build_artifact::BuildArtifact;
 use buck2_build_signals::NodeDuration;
+use buck2_common::dice::client_env::GetClientEnvironment;
 use buck2_common::events::HasEvents;
 use buck2_core::base_deferred_key::BaseDeferredKey;
 use buck2_data::ActionErrorDiagnostics;
@@ -53,6 +54,8 @@ use crate::actions::error_handler::ActionSubErrorResult;
 use crate::actions::error_handler::StarlarkActionErrorContext;
 use crate::actions::execute::action_executor::ActionOutputs;
 use crate::actions::execute::action_executor::HasActionExecutor;
+use crate::actions::execute::cache_hit_stats::HasActionCacheHitStats;
+use crate::actions::execute::env_usage::HasActionEnvUsage;
 use crate::actions::key::ActionKeyExt;
 use crate::actions::RegisteredAction;
 use crate::artifact_groups::calculation::ensure_artifact_group_staged;
@@ -139,6 +142,22 @@ async fn build_action_no_redirect(
         .await
         .context(format!("for action `{}`", action))?;
 
+    let passthrough_env = ctx.get_client_env_vars(action.env_passthrough()).await?;
+    if !action.env_passthrough().is_empty() {
+        let data = ctx.per_transaction_data();
+        let env_usage = data.get_action_env_usage();
+        let trace_id = data.get_dispatcher().trace_id().to_string();
+        let consumer = format!("{} ({})", action.owner(), action.name());
+        for name in action.env_passthrough() {
+            env_usage.record(
+                &trace_id,
+                name,
+                passthrough_env.get(name).map(|v| v.as_str()),
+                &consumer,
+            );
+        }
+    }
+
     let now = Instant::now();
     let action = &action;
 
@@ -162,7 +181,7 @@ async fn build_action_no_redirect(
     let ctx = &*ctx;
     let fut = async move {
         let (execute_result, command_reports) = executor
-            .execute(materialized_inputs, action, cancellation)
+            .execute(materialized_inputs, action, &passthrough_env, cancellation)
             .await;
 
         let allow_omit_details = execute_result.is_ok();
@@ -300,14 +319,23 @@ async fn build_action_no_redirect(
                 outputs
                     .iter()
                     .filter_map(|(_artifact, value)| {
+                        let digest = value.digest()?;
                         Some(buck2_data::ActionOutput {
-                            tiny_digest: value.digest()?.tiny_digest().to_string(),
+                            tiny_digest: digest.tiny_digest().to_string(),
+                            digest: digest.to_string(),
                         })
                     })
                     .collect()
             })
             .unwrap_or_default();
 
+        if let (Some(kind), Some(stats)) = (
+            execution_kind,
+            ctx.per_transaction_data().get_action_cache_hit_stats(),
+        ) {
+            stats.record(action.category().as_str(), kind);
+        }
+
         (
             ActionExecutionData {
                 action_result,
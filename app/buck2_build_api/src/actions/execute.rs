@@ -9,5 +9,8 @@
 
 pub mod action_execution_target;
 pub mod action_executor;
+pub mod cache_hit_stats;
 pub mod dice_data;
+pub mod env_usage;
 pub mod error;
+pub mod infra_retry;
@@ -23,12 +23,17 @@ use buck2_common::liveliness_observer::NoopLivelinessObserver;
 use buck2_core::execution_types::executor_config::CommandExecutorConfig;
 use buck2_core::fs::artifact_path_resolver::ArtifactFs;
 use buck2_core::fs::buck_out_path::BuckOutPath;
+use buck2_data::ToProtoMessage;
 use buck2_events::dispatch::EventDispatcher;
 use buck2_execute::artifact::fs::ExecutorFs;
 use buck2_execute::artifact_value::ArtifactValue;
 use buck2_execute::digest_config::DigestConfig;
 use buck2_execute::digest_config::HasDigestConfig;
+use buck2_execute::execute::action_digest::ActionDigest;
 use buck2_execute::execute::action_digest_and_blobs::ActionDigestAndBlobs;
+use buck2_execute::execute::action_digest_history::record_action_digest;
+use buck2_execute::execute::action_digest_history::ActionDigestHistoryKey;
+use buck2_execute::execute::action_digest_history::ActionDigestRecord;
 use buck2_execute::execute::blocking::BlockingExecutor;
 use buck2_execute::execute::blocking::HasBlockingExecutor;
 use buck2_execute::execute::cache_uploader::CacheUploadInfo;
@@ -64,6 +69,7 @@ use dupe::Dupe;
 use indexmap::indexmap;
 use indexmap::IndexMap;
 use itertools::Itertools;
+use sorted_vector_map::SortedVectorMap;
 
 use crate::actions::artifact::get_artifact_fs::GetArtifactFs;
 use crate::actions::execute::action_execution_target::ActionExecutionTarget;
@@ -103,6 +109,10 @@ impl OutputSize for ActionOutputs {
 #[derivative(PartialEq, Eq)]
 struct ActionOutputsData {
     outputs: IndexMap<BuckOutPath, ArtifactValue>,
+    /// Digest of the command that produced the outputs, if the action ran one. Kept with the
+    /// outputs so that it is known even when the action is not executed again.
+    #[derivative(PartialEq = "ignore")]
+    action_digest: Option<ActionDigest>,
 }
 
 /// Metadata associated with the execution of this action.
@@ -190,7 +200,17 @@ impl ActionExecutionKind {
 
 impl ActionOutputs {
     pub fn new(outputs: IndexMap<BuckOutPath, ArtifactValue>) -> Self {
-        Self(Arc::new(ActionOutputsData { outputs }))
+        Self::new_with_action_digest(outputs, None)
+    }
+
+    pub fn new_with_action_digest(
+        outputs: IndexMap<BuckOutPath, ArtifactValue>,
+        action_digest: Option<ActionDigest>,
+    ) -> Self {
+        Self(Arc::new(ActionOutputsData {
+            outputs,
+            action_digest,
+        }))
     }
 
     pub fn from_single(artifact: BuckOutPath, value: ArtifactValue) -> Self {
@@ -208,6 +228,10 @@ impl ActionOutputs {
     pub fn values(&self) -> impl Iterator<Item = &ArtifactValue> {
         self.0.outputs.values()
     }
+
+    pub fn action_digest(&self) -> Option<&ActionDigest> {
+        self.0.action_digest.as_ref()
+    }
 }
 
 #[async_trait]
@@ -308,8 +332,9 @@ impl BuckActionExecutor {
 struct BuckActionExecutionContext<'a> {
     executor: &'a BuckActionExecutor,
     action: &'a RegisteredAction,
-    inputs: IndexMap<ArtifactGroup, ArtifactGroupValues>,
+    inputs: &'a IndexMap<ArtifactGroup, ArtifactGroupValues>,
     outputs: &'a [BuildArtifact],
+    passthrough_env: &'a SortedVectorMap<String, String>,
     command_reports: &'a mut Vec<CommandExecutionReport>,
     cancellations: &'a CancellationContext<'a>,
 }
@@ -366,7 +391,11 @@ impl ActionExecutionCtx for BuckActionExecutionContext<'_> {
     }
 
     fn run_action_knobs(&self) -> RunActionKnobs {
-        self.executor.run_action_knobs
+        self.executor.run_action_knobs.dupe()
+    }
+
+    fn passthrough_env(&self) -> &SortedVectorMap<String, String> {
+        self.passthrough_env
     }
 
     fn cancellation_context(&self) -> &CancellationContext {
@@ -381,9 +410,21 @@ impl ActionExecutionCtx for BuckActionExecutionContext<'_> {
         &mut self,
         request: &CommandExecutionRequest,
     ) -> anyhow::Result<PreparedAction> {
-        self.executor
+        let prepared = self
+            .executor
             .command_executor
-            .prepare_action(request, self.digest_config())
+            .prepare_action(request, self.digest_config())?;
+        if self.executor.run_action_knobs.record_action_digests {
+            record_action_digest(
+                ActionDigestHistoryKey {
+                    owner: self.action.owner().to_string(),
+                    category: self.action.category().to_string(),
+                    identifier: self.action.identifier().map(|i| i.to_owned()),
+                },
+                ActionDigestRecord::new(request, &prepared),
+            );
+        }
+        Ok(prepared)
     }
 
     async fn action_cache(
@@ -430,13 +471,14 @@ impl ActionExecutionCtx for BuckActionExecutionContext<'_> {
             CommandExecutionStatus::Success { execution_kind } => {
                 let result = (
                     // TODO(T156483516): We should also validate that the outputs match the expected outputs
-                    ActionOutputs::new(
+                    ActionOutputs::new_with_action_digest(
                         outputs
                             .into_iter()
                             .filter_map(|(output, value)| {
                                 Some((output.into_build_artifact()?.0, value))
                             })
                             .collect(),
+                        execution_kind.action_digest().copied(),
                     ),
                     ActionExecutionMetadata {
                         execution_kind: ActionExecutionKind::Command {
@@ -552,14 +594,68 @@ impl BuckActionExecutor {
         &self,
         inputs: IndexMap<ArtifactGroup, ArtifactGroupValues>,
         action: &RegisteredAction,
+        passthrough_env: &SortedVectorMap<String, String>,
         cancellations: &CancellationContext<'_>,
     ) -> (
         Result<(ActionOutputs, ActionExecutionMetadata), ExecuteError>,
         Vec<CommandExecutionReport>,
     ) {
         let mut command_reports = Vec::new();
+        let policy = self.run_action_knobs.infra_retry;
+        let mut retry = 0;
+
+        loop {
+            let res = self
+                .execute_attempt(
+                    &inputs,
+                    action,
+                    passthrough_env,
+                    cancellations,
+                    &mut command_reports,
+                )
+                .await;
+
+            // Only retry when the command itself could not be run because of an infrastructure
+            // error. Commands that ran and failed are never retried.
+            let retryable = match (&res, command_reports.last()) {
+                (Err(ExecuteError::CommandExecutionError), Some(report))
+                    if retry < policy.max_retries && report.status.is_infra_error() =>
+                {
+                    Some(&report.status)
+                }
+                _ => None,
+            };
+            let Some(CommandExecutionStatus::Error { stage, error, .. }) = retryable else {
+                return (res, command_reports);
+            };
 
-        let res = async {
+            retry += 1;
+            let backoff = policy.backoff(retry);
+            self.events.instant_event(buck2_data::ActionInfraRetry {
+                key: Some(action.key().as_proto()),
+                name: Some(buck2_data::ActionName {
+                    category: action.category().as_str().to_owned(),
+                    identifier: action.identifier().unwrap_or("").to_owned(),
+                }),
+                retry,
+                max_retries: policy.max_retries,
+                stage: (*stage).to_owned(),
+                error: format!("{:#}", error),
+                backoff: backoff.try_into().ok(),
+            });
+            tokio::time::sleep(backoff).await;
+        }
+    }
+
+    async fn execute_attempt(
+        &self,
+        inputs: &IndexMap<ArtifactGroup, ArtifactGroupValues>,
+        action: &RegisteredAction,
+        passthrough_env: &SortedVectorMap<String, String>,
+        cancellations: &CancellationContext<'_>,
+        command_reports: &mut Vec<CommandExecutionReport>,
+    ) -> Result<(ActionOutputs, ActionExecutionMetadata), ExecuteError> {
+        async {
             let outputs = action.outputs()?;
 
             let mut ctx = BuckActionExecutionContext {
@@ -567,7 +663,8 @@ impl BuckActionExecutor {
                 action,
                 inputs,
                 outputs: outputs.as_ref(),
-                command_reports: &mut command_reports,
+                passthrough_env,
+                command_reports,
                 cancellations,
             };
 
@@ -635,9 +732,7 @@ impl BuckActionExecutor {
                 Ok((result, metadata))
             }
         }
-        .await;
-
-        (res, command_reports)
+        .await
     }
 }
 
@@ -912,7 +1007,12 @@ mod tests {
         );
         let res = with_dispatcher_async(
             EventDispatcher::null(),
-            executor.execute(Default::default(), &action, CancellationContext::testing()),
+            executor.execute(
+                Default::default(),
+                &action,
+                &SortedVectorMap::new(),
+                CancellationContext::testing(),
+            ),
         )
         .await
         .0
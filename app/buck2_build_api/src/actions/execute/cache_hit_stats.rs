@@ -0,0 +1,128 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Remote cache hits and misses of the actions run by a command, per action category, so that a
+//! build can enforce `--min-cache-hit-rate` and report where the misses come from.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use dice::UserComputationData;
+use dupe::Dupe;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Hits and misses of the actions of one category.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct CategoryCacheHits {
+    /// Actions served by the remote action cache, or the remote dep file cache.
+    pub cached: u64,
+    /// Actions executed locally or remotely.
+    pub executed: u64,
+}
+
+#[derive(Default)]
+pub struct ActionCacheHitStats {
+    by_category: Mutex<BTreeMap<String, CategoryCacheHits>>,
+}
+
+impl ActionCacheHitStats {
+    /// Record how an action of `category` was run. Actions which ran no command (e.g. writes or
+    /// symlinks) or hit the local dep file cache do not count toward the hit rate.
+    pub fn record(&self, category: &str, kind: buck2_data::ActionExecutionKind) {
+        use buck2_data::ActionExecutionKind;
+
+        let cached = match kind {
+            ActionExecutionKind::ActionCache | ActionExecutionKind::RemoteDepFileCache => true,
+            ActionExecutionKind::Local
+            | ActionExecutionKind::LocalWorker
+            | ActionExecutionKind::Remote => false,
+            ActionExecutionKind::NotSet
+            | ActionExecutionKind::Simple
+            | ActionExecutionKind::Deferred
+            | ActionExecutionKind::LocalDepFile => return,
+        };
+        let mut by_category = self.by_category.lock().unwrap();
+        let hits = by_category.entry(category.to_owned()).or_default();
+        if cached {
+            hits.cached += 1;
+        } else {
+            hits.executed += 1;
+        }
+    }
+
+    pub fn by_category(&self) -> BTreeMap<String, CategoryCacheHits> {
+        self.by_category.lock().unwrap().clone()
+    }
+}
+
+/// The fraction of `hits` served by a cache, or `None` if no action ran a command.
+pub fn cache_hit_rate<'a>(hits: impl IntoIterator<Item = &'a CategoryCacheHits>) -> Option<f64> {
+    let (cached, executed) = hits
+        .into_iter()
+        .fold((0, 0), |(c, e), h| (c + h.cached, e + h.executed));
+    if cached + executed == 0 {
+        None
+    } else {
+        Some(cached as f64 / (cached + executed) as f64)
+    }
+}
+
+pub trait HasActionCacheHitStats {
+    fn set_action_cache_hit_stats(&mut self, stats: Arc<ActionCacheHitStats>);
+
+    fn get_action_cache_hit_stats(&self) -> Option<Arc<ActionCacheHitStats>>;
+}
+
+impl HasActionCacheHitStats for UserComputationData {
+    fn set_action_cache_hit_stats(&mut self, stats: Arc<ActionCacheHitStats>) {
+        self.data.set(stats);
+    }
+
+    fn get_action_cache_hit_stats(&self) -> Option<Arc<ActionCacheHitStats>> {
+        self.data
+            .get::<Arc<ActionCacheHitStats>>()
+            .ok()
+            .map(|s| s.dupe())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_rate() {
+        use buck2_data::ActionExecutionKind;
+
+        let stats = ActionCacheHitStats::default();
+        assert_eq!(None, cache_hit_rate(stats.by_category().values()));
+
+        stats.record("cxx_compile", ActionExecutionKind::ActionCache);
+        stats.record("cxx_compile", ActionExecutionKind::ActionCache);
+        stats.record("cxx_compile", ActionExecutionKind::Remote);
+        stats.record("cxx_link", ActionExecutionKind::Local);
+        stats.record("write", ActionExecutionKind::Simple);
+
+        let by_category = stats.by_category();
+        assert_eq!(
+            vec!["cxx_compile", "cxx_link"],
+            by_category.keys().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            CategoryCacheHits {
+                cached: 2,
+                executed: 1
+            },
+            by_category["cxx_compile"]
+        );
+        assert_eq!(Some(0.5), cache_hit_rate(by_category.values()));
+    }
+}
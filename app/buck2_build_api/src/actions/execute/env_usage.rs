@@ -0,0 +1,221 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! The client environment variables read by actions via `env_passthrough`, retained by the
+//! daemon per invocation for `buck2 audit env`.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use allocative::Allocative;
+use buck2_core::env::usage::EnvUsage;
+use buck2_core::env::usage::EnvUsageSource;
+use dice::UserComputationData;
+use dupe::Dupe;
+use parking_lot::Mutex;
+
+/// How many invocations to keep the reads of. Commands can run concurrently, so the reads of
+/// each are kept apart.
+const MAX_INVOCATIONS: usize = 16;
+
+struct InvocationEnvUsage {
+    trace_id: String,
+    reads: BTreeMap<String, EnvUsage>,
+}
+
+#[derive(Default)]
+struct ActionEnvUsageLog {
+    /// Invocations which ran actions depending on the client environment, in the order of their
+    /// first read, most recent last.
+    invocations: VecDeque<InvocationEnvUsage>,
+    /// Values seen in the invocations evicted from `invocations`.
+    evicted_values: BTreeMap<String, Option<String>>,
+}
+
+impl ActionEnvUsageLog {
+    fn record(&mut self, trace_id: &str, name: &str, value: Option<&str>, consumer: &str) {
+        let index = match self
+            .invocations
+            .iter()
+            .position(|invocation| invocation.trace_id == trace_id)
+        {
+            Some(index) => index,
+            None => {
+                if self.invocations.len() == MAX_INVOCATIONS {
+                    let evicted = self.invocations.pop_front().unwrap();
+                    for (name, usage) in evicted.reads {
+                        self.evicted_values.insert(name, usage.value);
+                    }
+                }
+                self.invocations.push_back(InvocationEnvUsage {
+                    trace_id: trace_id.to_owned(),
+                    reads: BTreeMap::new(),
+                });
+                self.invocations.len() - 1
+            }
+        };
+        let changed = self
+            .previous_value(index, name)
+            .map_or(false, |previous| previous.as_deref() != value);
+        self.invocations[index]
+            .reads
+            .entry(name.to_owned())
+            .or_insert_with(|| EnvUsage {
+                source: EnvUsageSource::Action,
+                name: name.to_owned(),
+                value: value.map(|v| v.to_owned()),
+                changed,
+                consumers: BTreeSet::new(),
+            })
+            .consumers
+            .insert(consumer.to_owned());
+    }
+
+    /// The value of `name` seen by the last invocation before `invocations[index]` which read it.
+    fn previous_value(&self, index: usize, name: &str) -> Option<&Option<String>> {
+        self.invocations
+            .range(..index)
+            .rev()
+            .find_map(|invocation| invocation.reads.get(name).map(|usage| &usage.value))
+            .or_else(|| self.evicted_values.get(name))
+    }
+}
+
+/// Owned by the daemon and shared by its commands.
+#[derive(Default, Allocative)]
+pub struct ActionEnvUsage {
+    #[allocative(skip)]
+    log: Mutex<ActionEnvUsageLog>,
+}
+
+impl ActionEnvUsage {
+    /// Record that `consumer`, an action run by the invocation `trace_id`, depends on the client
+    /// environment variable `name`.
+    pub fn record(&self, trace_id: &str, name: &str, value: Option<&str>, consumer: &str) {
+        self.log.lock().record(trace_id, name, value, consumer);
+    }
+
+    /// The reads of the invocation `trace_id`, or of the last invocation which had any, sorted
+    /// by name.
+    pub fn usage(&self, trace_id: Option<&str>) -> Vec<EnvUsage> {
+        let log = self.log.lock();
+        let invocation = match trace_id {
+            Some(trace_id) => log
+                .invocations
+                .iter()
+                .find(|invocation| invocation.trace_id == trace_id),
+            None => log.invocations.back(),
+        };
+        invocation
+            .into_iter()
+            .flat_map(|invocation| invocation.reads.values().cloned())
+            .collect()
+    }
+}
+
+pub trait HasActionEnvUsage {
+    fn set_action_env_usage(&mut self, usage: Arc<ActionEnvUsage>);
+
+    fn get_action_env_usage(&self) -> Arc<ActionEnvUsage>;
+}
+
+impl HasActionEnvUsage for UserComputationData {
+    fn set_action_env_usage(&mut self, usage: Arc<ActionEnvUsage>) {
+        self.data.set(usage);
+    }
+
+    fn get_action_env_usage(&self) -> Arc<ActionEnvUsage> {
+        self.data
+            .get::<Arc<ActionEnvUsage>>()
+            .expect("Action env usage should be set")
+            .dupe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_env_usage_changed() {
+        let usage = ActionEnvUsage::default();
+        let name = "TEST_ACTION_ENV_USAGE_CHANGED";
+        let find = |usage: &ActionEnvUsage| {
+            usage
+                .usage(None)
+                .into_iter()
+                .find(|u| u.name == name)
+                .unwrap()
+        };
+
+        usage.record("trace-1", name, Some("a"), "action-1");
+        assert!(!find(&usage).changed);
+
+        usage.record("trace-2", name, Some("a"), "action-1");
+        assert!(!find(&usage).changed);
+
+        usage.record("trace-3", name, Some("b"), "action-1");
+        usage.record("trace-3", name, Some("b"), "action-2");
+        let found = find(&usage);
+        assert!(found.changed);
+        assert_eq!(Some("b"), found.value.as_deref());
+        assert_eq!(2, found.consumers.len());
+    }
+
+    #[test]
+    fn test_action_env_usage_of_invocation() {
+        let usage = ActionEnvUsage::default();
+        usage.record("trace-1", "A", None, "action-1");
+        usage.record("trace-2", "B", None, "action-1");
+        let names = |trace_id| {
+            usage
+                .usage(trace_id)
+                .into_iter()
+                .map(|u| u.name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec!["A"], names(Some("trace-1")));
+        assert_eq!(vec!["B"], names(Some("trace-2")));
+        assert_eq!(vec!["B"], names(None));
+        assert!(names(Some("trace-3")).is_empty());
+    }
+
+    #[test]
+    fn test_action_env_usage_interleaved_invocations() {
+        let mut log = ActionEnvUsageLog::default();
+        let name = "TEST_ACTION_ENV_USAGE_INTERLEAVED";
+        let usage = |log: &ActionEnvUsageLog, trace_id: &str| {
+            log.invocations
+                .iter()
+                .find(|invocation| invocation.trace_id == trace_id)
+                .unwrap()
+                .reads[name]
+                .clone()
+        };
+
+        log.record("trace-1", name, Some("a"), "action-1");
+        log.record("trace-2", name, Some("b"), "action-1");
+        // Going back to the first invocation does not make it compare with the second one.
+        log.record("trace-1", name, Some("a"), "action-2");
+        log.record("trace-2", name, Some("b"), "action-2");
+        assert!(!usage(&log, "trace-1").changed);
+        assert_eq!(2, usage(&log, "trace-1").consumers.len());
+        assert!(usage(&log, "trace-2").changed);
+        assert_eq!(2, usage(&log, "trace-2").consumers.len());
+
+        // Evicted invocations are still compared with.
+        for i in 0..MAX_INVOCATIONS {
+            log.record(&format!("other-{}", i), "OTHER", None, "action-1");
+        }
+        log.record("trace-3", name, Some("b"), "action-1");
+        assert!(!usage(&log, "trace-3").changed);
+    }
+}
@@ -0,0 +1,67 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::time::Duration;
+
+use dupe::Dupe;
+
+/// Policy for retrying actions whose execution failed with an infrastructure error (see
+/// `CommandExecutionStatus::is_infra_error`).
+#[derive(Copy, Clone, Dupe, Debug, PartialEq, Eq)]
+pub struct InfraRetryPolicy {
+    /// How many times an action may be retried. Zero disables retries.
+    pub max_retries: u32,
+    /// How long to wait before the first retry. Doubled for every subsequent retry.
+    pub initial_backoff: Duration,
+    /// Upper bound on the wait between retries.
+    pub max_backoff: Duration,
+}
+
+impl Default for InfraRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 0,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl InfraRetryPolicy {
+    /// The wait before the given retry, counting from 1.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(retry.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::actions::execute::infra_retry::InfraRetryPolicy;
+
+    #[test]
+    fn test_backoff() {
+        let policy = InfraRetryPolicy {
+            max_retries: 10,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(3),
+        };
+        assert_eq!(Duration::from_millis(500), policy.backoff(1));
+        assert_eq!(Duration::from_secs(1), policy.backoff(2));
+        assert_eq!(Duration::from_secs(2), policy.backoff(3));
+        assert_eq!(Duration::from_secs(3), policy.backoff(4));
+        assert_eq!(Duration::from_secs(3), policy.backoff(40));
+    }
+}
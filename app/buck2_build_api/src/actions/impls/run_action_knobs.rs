@@ -7,11 +7,19 @@
  * of this source tree.
  */
 
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use buck2_core::execution_types::executor_config::HybridExecutionPolicy;
+use buck2_execute::execute::environment_inheritance::EnvironmentInheritance;
 use dice::UserComputationData;
 use dupe::Dupe;
 
+use crate::actions::execute::infra_retry::InfraRetryPolicy;
+
 /// Knobs controlling how RunAction works.
-#[derive(Copy, Clone, Dupe, Default)]
+#[derive(Clone, Dupe, Default)]
 pub struct RunActionKnobs {
     /// Process dep files as they are generated.
     pub eager_dep_files: bool,
@@ -27,6 +35,40 @@ pub struct RunActionKnobs {
 
     /// TODO(minglunli): Modifies action digest, remove after confirming bvb works fine
     pub unique_scratch_path: bool,
+
+    /// Timeouts for run actions by category, from the `[action_timeouts]` buckconfig section.
+    /// An action's own `timeout_seconds` takes precedence.
+    pub category_timeouts: Arc<HashMap<String, Duration>>,
+
+    /// How hybrid executors schedule run actions by category, from the
+    /// `[hybrid_execution_policies]` buckconfig section.
+    pub category_hybrid_policies: Arc<HashMap<String, HybridExecutionPolicy>>,
+
+    /// How actions failing with infrastructure errors are retried.
+    pub infra_retry: InfraRetryPolicy,
+
+    /// Scrub known sources of nondeterminism from run actions that do not opt out.
+    pub reproducible: bool,
+
+    /// Value of `SOURCE_DATE_EPOCH` for run actions when `reproducible` is set.
+    pub source_date_epoch: u64,
+
+    /// Environment local run actions get from the daemon, from `build.action_env_isolation` and
+    /// `build.action_env_passthrough`. `None` means the daemon environment minus
+    /// `EnvironmentInheritance::local_command_exclusions`.
+    pub local_env_inheritance: Option<EnvironmentInheritance>,
+
+    /// Record what the digests of actions are computed from, for `buck2 debug why-miss`, from
+    /// `build.record_action_digests`.
+    pub record_action_digests: bool,
+}
+
+impl RunActionKnobs {
+    pub fn local_env_inheritance(&self) -> EnvironmentInheritance {
+        self.local_env_inheritance
+            .clone()
+            .unwrap_or_else(EnvironmentInheritance::local_command_exclusions)
+    }
 }
 
 pub trait HasRunActionKnobs {
@@ -41,9 +83,9 @@ impl HasRunActionKnobs for UserComputationData {
     }
 
     fn get_run_action_knobs(&self) -> RunActionKnobs {
-        *self
-            .data
+        self.data
             .get::<RunActionKnobs>()
             .expect("RunActionKnobs should be set")
+            .dupe()
     }
 }
@@ -25,7 +25,9 @@ use buck2_core::provider::label::ProvidersLabel;
 use buck2_error::BuckErrorContext;
 use buck2_events::dispatch::console_message;
 use buck2_execute::artifact::fs::ExecutorFs;
+use buck2_execute::materialize::materializer::HasMaterializer;
 use buck2_node::nodes::configured_frontend::ConfiguredTargetNodeCalculation;
+use buck2_node::rule_type::StarlarkRuleType;
 use dashmap::DashMap;
 use dice::DiceComputations;
 use dice::LinearRecomputeDiceComputations;
@@ -58,9 +60,15 @@ use crate::interpreter::rule_defs::provider::builtin::run_info::FrozenRunInfo;
 use crate::interpreter::rule_defs::provider::test_provider::TestProvider;
 use crate::keep_going::KeepGoing;
 
+pub mod action_digests;
 mod action_error;
+pub mod action_graph_checksum;
+pub mod aspects;
 pub mod build_report;
+pub mod dependency_failure;
 mod graph_size;
+pub mod last_successful_build;
+mod validation;
 /// The types of provider to build on the configured providers label
 #[derive(Debug, Clone, Dupe, Allocative)]
 pub enum BuildProviderType {
@@ -68,6 +76,10 @@ pub enum BuildProviderType {
     DefaultOther,
     Run,
     Test,
+    /// Validation outputs declared via `ValidationInfo` by the target or its transitive deps.
+    Validation,
+    /// Default outputs of the aspects applied to the target and its transitive deps.
+    Aspect,
 }
 
 #[derive(Clone, Debug, Allocative)]
@@ -280,104 +292,153 @@ pub async fn build_configured_label<'a>(
     })
 }
 
-async fn build_configured_label_inner<'a>(
-    ctx: &'a LinearRecomputeDiceComputations<'_>,
-    materialization_context: &MaterializationContext,
-    providers_label: Arc<ConfiguredProvidersLabel>,
+/// What building a configured providers label requests, known after analysis and before
+/// anything is built.
+pub struct RequestedOutputs {
+    /// The outputs to build, with the provider each came from, in the order the rule author
+    /// wrote them.
+    pub outputs: Vec<(ArtifactGroup, BuildProviderType)>,
+    pub run_args: Option<Vec<String>>,
+    pub target_rule_type_name: String,
+}
+
+/// The outputs `providers_to_build` requests from `providers_label`.
+pub async fn requested_outputs(
+    ctx: &mut DiceComputations<'_>,
+    providers_label: &ConfiguredProvidersLabel,
     providers_to_build: &ProvidersToBuild,
-    opts: BuildConfiguredLabelOptions,
-) -> anyhow::Result<BoxStream<'a, ConfiguredBuildEvent>> {
-    let artifact_fs = ctx.get().get_artifact_fs().await?;
-
-    let (outputs, run_args, target_rule_type_name) = {
-        // A couple of these objects aren't Send and so scope them here so async transform doesn't get concerned.
-        let providers = match ctx.get().get_providers(providers_label.as_ref()).await? {
-            MaybeCompatible::Incompatible(reason) => {
-                return if opts.skippable {
-                    console_message(reason.skipping_message(providers_label.target()));
-                    Ok(
-                        futures::stream::once(futures::future::ready(ConfiguredBuildEvent {
-                            label: providers_label.dupe(),
-                            variant: ConfiguredBuildEventVariant::SkippedIncompatible,
-                        }))
-                        .boxed(),
-                    )
-                } else {
-                    Err(reason.to_err())
-                };
-            }
-            MaybeCompatible::Compatible(v) => v,
-        };
-
-        // Important we use an ordered collections, so the order matches the order the rule
-        // author wrote.
-        let mut outputs = Vec::new();
-        // Providers that produced each output, in the order of outputs above. We use a separate collection
-        // otherwise we'd build the same output twice when it's both in DefaultInfo and RunInfo
-        let collection = providers.provider_collection();
-
-        let mut run_args: Option<Vec<String>> = None;
-
-        if providers_to_build.default {
-            collection
-                .default_info()
-                .for_each_default_output_artifact_only(&mut |o| {
-                    outputs.push((ArtifactGroup::Artifact(o), BuildProviderType::Default))
-                })?;
-        }
-        if providers_to_build.default_other {
-            collection
-                .default_info()
-                .for_each_default_output_other_artifacts_only(&mut |o| {
-                    outputs.push((o, BuildProviderType::DefaultOther))
-                })?;
-            collection.default_info().for_each_other_output(&mut |o| {
+) -> anyhow::Result<MaybeCompatible<RequestedOutputs>> {
+    let artifact_fs = ctx.get_artifact_fs().await?;
+
+    let providers = match ctx.get_providers(providers_label).await? {
+        MaybeCompatible::Incompatible(reason) => return Ok(MaybeCompatible::Incompatible(reason)),
+        MaybeCompatible::Compatible(v) => v,
+    };
+
+    // Important we use an ordered collections, so the order matches the order the rule
+    // author wrote.
+    let mut outputs = Vec::new();
+    // Providers that produced each output, in the order of outputs above. We use a separate collection
+    // otherwise we'd build the same output twice when it's both in DefaultInfo and RunInfo
+    let collection = providers.provider_collection();
+
+    let mut run_args: Option<Vec<String>> = None;
+
+    if providers_to_build.default {
+        collection
+            .default_info()
+            .for_each_default_output_artifact_only(&mut |o| {
+                outputs.push((ArtifactGroup::Artifact(o), BuildProviderType::Default))
+            })?;
+    }
+    if providers_to_build.default_other {
+        collection
+            .default_info()
+            .for_each_default_output_other_artifacts_only(&mut |o| {
                 outputs.push((o, BuildProviderType::DefaultOther))
             })?;
-        }
-        if providers_to_build.run {
-            if let Some(runinfo) = providers
-                .provider_collection()
-                .builtin_provider::<FrozenRunInfo>()
-            {
-                let mut artifact_visitor = SimpleCommandLineArtifactVisitor::new();
-                runinfo.visit_artifacts(&mut artifact_visitor)?;
-                for input in artifact_visitor.inputs {
-                    outputs.push((input, BuildProviderType::Run));
-                }
-                // Produce arguments to run on a local machine.
-                let path_separator = if cfg!(windows) {
-                    PathSeparatorKind::Windows
-                } else {
-                    PathSeparatorKind::Unix
-                };
-                let executor_fs = ExecutorFs::new(&artifact_fs, path_separator);
-                let mut cli = Vec::<String>::new();
-                let mut ctx = AbsCommandLineContext::new(&executor_fs);
-                runinfo.add_to_command_line(&mut cli, &mut ctx)?;
-                run_args = Some(cli);
+        collection
+            .default_info()
+            .for_each_other_output(&mut |o| outputs.push((o, BuildProviderType::DefaultOther)))?;
+    }
+    if providers_to_build.run {
+        if let Some(runinfo) = providers
+            .provider_collection()
+            .builtin_provider::<FrozenRunInfo>()
+        {
+            let mut artifact_visitor = SimpleCommandLineArtifactVisitor::new();
+            runinfo.visit_artifacts(&mut artifact_visitor)?;
+            for input in artifact_visitor.inputs {
+                outputs.push((input, BuildProviderType::Run));
             }
+            // Produce arguments to run on a local machine.
+            let path_separator = if cfg!(windows) {
+                PathSeparatorKind::Windows
+            } else {
+                PathSeparatorKind::Unix
+            };
+            let executor_fs = ExecutorFs::new(&artifact_fs, path_separator);
+            let mut cli = Vec::<String>::new();
+            let mut ctx = AbsCommandLineContext::new(&executor_fs);
+            runinfo.add_to_command_line(&mut cli, &mut ctx)?;
+            run_args = Some(cli);
         }
-        if providers_to_build.tests {
-            if let Some(test_provider) = <dyn TestProvider>::from_collection(collection) {
-                let mut artifact_visitor = SimpleCommandLineArtifactVisitor::new();
-                test_provider.visit_artifacts(&mut artifact_visitor)?;
-                for input in artifact_visitor.inputs {
-                    outputs.push((input, BuildProviderType::Test));
-                }
+    }
+    if providers_to_build.tests {
+        if let Some(test_provider) = <dyn TestProvider>::from_collection(collection) {
+            let mut artifact_visitor = SimpleCommandLineArtifactVisitor::new();
+            test_provider.visit_artifacts(&mut artifact_visitor)?;
+            for input in artifact_visitor.inputs {
+                outputs.push((input, BuildProviderType::Test));
             }
         }
+    }
+    if providers_to_build.default {
+        for validation in validation::transitive_validations(ctx, providers_label.target())
+            .await?
+            .iter()
+        {
+            outputs.push((
+                ArtifactGroup::Artifact(validation.dupe()),
+                BuildProviderType::Validation,
+            ));
+        }
+    }
 
-        let target_rule_type_name: String = ctx
-            .get()
-            .get_configured_target_node(providers_label.target())
+    for aspect in &providers_to_build.aspects {
+        for output in aspects::transitive_aspect_outputs(ctx, aspect, providers_label.target())
             .await?
-            .require_compatible()?
-            .rule_type()
-            .name()
-            .to_owned();
+            .iter()
+        {
+            outputs.push((
+                ArtifactGroup::Artifact(output.dupe()),
+                BuildProviderType::Aspect,
+            ));
+        }
+    }
+
+    let target_rule_type_name: String = ctx
+        .get_configured_target_node(providers_label.target())
+        .await?
+        .require_compatible()?
+        .rule_type()
+        .name()
+        .to_owned();
+
+    Ok(MaybeCompatible::Compatible(RequestedOutputs {
+        outputs,
+        run_args,
+        target_rule_type_name,
+    }))
+}
 
-        (outputs, run_args, target_rule_type_name)
+async fn build_configured_label_inner<'a>(
+    ctx: &'a LinearRecomputeDiceComputations<'_>,
+    materialization_context: &MaterializationContext,
+    providers_label: Arc<ConfiguredProvidersLabel>,
+    providers_to_build: &ProvidersToBuild,
+    opts: BuildConfiguredLabelOptions,
+) -> anyhow::Result<BoxStream<'a, ConfiguredBuildEvent>> {
+    let RequestedOutputs {
+        outputs,
+        run_args,
+        target_rule_type_name,
+    } = match requested_outputs(&mut ctx.get(), &providers_label, providers_to_build).await? {
+        MaybeCompatible::Incompatible(reason) => {
+            return if opts.skippable {
+                console_message(reason.skipping_message(providers_label.target()));
+                Ok(
+                    futures::stream::once(futures::future::ready(ConfiguredBuildEvent {
+                        label: providers_label.dupe(),
+                        variant: ConfiguredBuildEventVariant::SkippedIncompatible,
+                    }))
+                    .boxed(),
+                )
+            } else {
+                Err(reason.to_err())
+            };
+        }
+        MaybeCompatible::Compatible(v) => v,
     };
 
     if let Some(signals) = ctx
@@ -498,6 +559,9 @@ pub struct ProvidersToBuild {
     pub default_other: bool,
     pub run: bool,
     pub tests: bool,
+    /// Aspects to apply to the target and to each of its transitive deps, whose default outputs
+    /// are built along with those of the target.
+    pub aspects: Vec<Arc<StarlarkRuleType>>,
 }
 
 impl Debug for ProviderArtifacts {
@@ -517,21 +581,39 @@ pub async fn materialize_artifact_group(
     let values = ctx.ensure_artifact_group(artifact_group).await?;
 
     if let MaterializationContext::Materialize { map, force } = materialization_context {
+        let materializer = ctx.per_transaction_data().get_materializer();
+        // Read before materializing, so that an invalidation racing with the materialization
+        // makes the recorded generation stale.
+        let generation = materializer.materialized_generation();
+        let artifact_fs = ctx.get_artifact_fs().await?;
+
         let mut artifacts_to_materialize = Vec::new();
-        for (artifact, _value) in values.iter() {
+        for (artifact, value) in values.iter() {
             if let BaseArtifactKind::Build(artifact) = artifact.as_parts().0 {
                 if map.insert(artifact.dupe(), ()).is_some() {
                     // We've already requested this artifact, no use requesting it again.
                     continue;
                 }
-                artifacts_to_materialize.push(artifact);
+                let path = artifact_fs.resolve_build(artifact.get_path());
+                if materializer.is_requested_materialized(&path, value) {
+                    continue;
+                }
+                artifacts_to_materialize.push((artifact, path, value));
             }
         }
 
-        ctx.try_compute_join(artifacts_to_materialize, |ctx, artifact| {
+        ctx.try_compute_join(artifacts_to_materialize, |ctx, (artifact, path, value)| {
+            let materializer = materializer.dupe();
             async move {
-                ctx.try_materialize_requested_artifact(artifact, *force)
-                    .await
+                if ctx
+                    .try_materialize_requested_artifact(artifact, *force)
+                    .await?
+                {
+                    if let Some(generation) = generation {
+                        materializer.record_requested_materialized(path, value.dupe(), generation);
+                    }
+                }
+                anyhow::Ok(())
             }
             .boxed()
         })
@@ -0,0 +1,86 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Digests of all the actions needed to build a set of artifacts. Unlike the
+//! `ActionExecutionEnd` events, this covers actions whose outputs the daemon already had and
+//! which were therefore not executed by the build.
+
+use std::collections::HashSet;
+
+use buck2_artifact::actions::key::ActionKey;
+use buck2_artifact::artifact::artifact_type::Artifact;
+use buck2_data::ToProtoMessage;
+use dice::DiceComputations;
+use dupe::Dupe;
+use dupe::OptionDupedExt;
+use futures::FutureExt;
+
+use crate::actions::calculation::ActionCalculation;
+use crate::artifact_groups::calculation::ArtifactGroupCalculation;
+
+/// Returns the digests of the actions producing `artifacts` and of all their transitive
+/// dependencies. All the actions must have been built already.
+pub async fn action_digests<'a>(
+    ctx: &mut DiceComputations<'_>,
+    artifacts: impl IntoIterator<Item = &'a Artifact>,
+) -> anyhow::Result<Vec<buck2_cli_proto::BuildActionDigests>> {
+    let mut visited = HashSet::new();
+    let mut recorded = HashSet::new();
+    let mut queue: Vec<ActionKey> = artifacts
+        .into_iter()
+        .filter_map(|a| a.action_key().duped())
+        .collect();
+    let mut digests = Vec::new();
+
+    while !queue.is_empty() {
+        let keys: Vec<ActionKey> = std::mem::take(&mut queue)
+            .into_iter()
+            .filter(|key| visited.insert(key.dupe()))
+            .collect();
+        let visited_actions = ctx
+            .try_compute_join(keys, |ctx, key| {
+                async move {
+                    let action = ActionCalculation::get_action(ctx, &key).await?;
+                    let outputs = ActionCalculation::build_action(ctx, key).await?;
+
+                    let mut deps = Vec::new();
+                    for input in action.inputs()?.iter() {
+                        let values = ctx.ensure_artifact_group(input).await?;
+                        deps.extend(values.iter().filter_map(|(a, _)| a.action_key().duped()));
+                    }
+
+                    let digests = buck2_cli_proto::BuildActionDigests {
+                        key: Some(action.key().as_proto()),
+                        name: Some(buck2_data::ActionName {
+                            category: action.category().as_str().to_owned(),
+                            identifier: action.identifier().unwrap_or("").to_owned(),
+                        }),
+                        action_digest: outputs.action_digest().map(|d| d.to_string()),
+                        output_digests: outputs
+                            .values()
+                            .filter_map(|v| Some(v.digest()?.to_string()))
+                            .collect(),
+                    };
+                    anyhow::Ok((action.key().dupe(), digests, deps))
+                }
+                .boxed()
+            })
+            .await?;
+
+        for (key, action_digests, deps) in visited_actions {
+            // Dynamic outputs can make several keys point at the same action.
+            if recorded.insert(key) {
+                digests.push(action_digests);
+            }
+            queue.extend(deps);
+        }
+    }
+
+    Ok(digests)
+}
@@ -15,22 +15,23 @@ use buck2_data::CommandExecutionDetails;
 use buck2_event_observer::display::display_action_owner;
 use buck2_event_observer::display::get_action_error_reason;
 use buck2_event_observer::display::TargetDisplayOptions;
+use schemars::JsonSchema;
 use serde::Serialize;
 
 use crate::build::build_report::BuildReportCollector;
 
-#[derive(Debug, Clone, Serialize, PartialOrd, Ord, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, JsonSchema, PartialOrd, Ord, PartialEq, Eq)]
 struct BuildReportActionName {
     category: String,
     identifier: String,
 }
 
-#[derive(Debug, Clone, Serialize, PartialOrd, Ord, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, JsonSchema, PartialOrd, Ord, PartialEq, Eq)]
 struct BuildReportActionKey {
     owner: String,
 }
 
-#[derive(Debug, Clone, Serialize, PartialOrd, Ord, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, JsonSchema, PartialOrd, Ord, PartialEq, Eq)]
 enum BuildReportActionErrorDiagnostics {
     #[serde(rename = "sub_errors")]
     SubErrors(Vec<BuildReportActionSubError>),
@@ -38,21 +39,21 @@ enum BuildReportActionErrorDiagnostics {
     HandlerInvocationError(String),
 }
 
-#[derive(Debug, Clone, Serialize, PartialOrd, Ord, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, JsonSchema, PartialOrd, Ord, PartialEq, Eq)]
 struct BuildReportActionSubError {
     category: String,
     message_content: Option<String>,
     locations: Option<Vec<BuildReportActionErrorLocation>>,
 }
 
-#[derive(Debug, Clone, Serialize, PartialOrd, Ord, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, JsonSchema, PartialOrd, Ord, PartialEq, Eq)]
 struct BuildReportActionErrorLocation {
     file: String,
     line: Option<u64>,
 }
 
 /// DO NOT UPDATE WITHOUT UPDATING `docs/users/build_observability/build_report.md`!
-#[derive(Debug, Clone, Serialize, PartialOrd, Ord, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, JsonSchema, PartialOrd, Ord, PartialEq, Eq)]
 pub(crate) struct BuildReportActionError {
     name: BuildReportActionName,
     key: BuildReportActionKey,
@@ -61,6 +62,7 @@ pub(crate) struct BuildReportActionError {
     stderr_content: String,
     stdout_content: String,
     error_diagnostics: Option<BuildReportActionErrorDiagnostics>,
+    timed_out: bool,
 }
 
 impl BuildReportActionError {
@@ -126,6 +128,11 @@ impl BuildReportActionError {
             }
         });
 
+        let timed_out = matches!(
+            error.last_command.as_ref().and_then(|c| c.status.as_ref()),
+            Some(buck2_data::command_execution::Status::Timeout(_))
+        );
+
         let stderr = command_details.map_or(String::default(), |c| c.stderr.clone());
         let stdout = command_details.map_or(String::default(), |c| c.stdout.clone());
 
@@ -141,6 +148,7 @@ impl BuildReportActionError {
             stdout_content,
             digest: get_action_digest(command_details).unwrap_or_default(),
             error_diagnostics,
+            timed_out,
         }
     }
 }
@@ -0,0 +1,358 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Checksums of the action graph behind requested outputs, computed without running any action
+//! other than those `dynamic_output` reads to define its actions.
+//!
+//! The checksum of an action covers its definition and the checksums of its inputs, down to the
+//! digests of the source files the graph reads. Each checksum is a DICE key, so after a change
+//! only the actions depending on it are hashed again.
+//!
+//! Everything is hashed through an explicit encoding of length-prefixed fields, rather than
+//! through `Debug` representations, so that checksums are the same across runs and daemons.
+
+use allocative::Allocative;
+use anyhow::Context as _;
+use async_trait::async_trait;
+use buck2_artifact::actions::key::ActionKey;
+use buck2_core::configuration::compatibility::MaybeCompatible;
+use buck2_core::execution_types::executor_config::CommandExecutorConfig;
+use buck2_core::execution_types::executor_config::Executor;
+use buck2_core::execution_types::executor_config::OutputPathsBehavior;
+use buck2_core::execution_types::executor_config::PathSeparatorKind;
+use buck2_core::provider::label::ConfiguredProvidersLabel;
+use buck2_execute::artifact::fs::ExecutorFs;
+use buck2_execute::directory::ActionDirectoryEntry;
+use buck2_execute::directory::ActionDirectoryMember;
+use buck2_execute::directory::ActionSharedDirectory;
+use buck2_futures::cancellation::CancellationContext;
+use derive_more::Display;
+use dice::DiceComputations;
+use dice::Key;
+use dupe::Dupe;
+use futures::FutureExt;
+use ref_cast::RefCast;
+
+use crate::actions::artifact::get_artifact_fs::GetArtifactFs;
+use crate::actions::calculation::ActionCalculation;
+use crate::actions::RegisteredAction;
+use crate::artifact_groups::calculation::ArtifactGroupCalculation;
+use crate::artifact_groups::ArtifactGroup;
+use crate::artifact_groups::ResolvedArtifactGroup;
+use crate::artifact_groups::TransitiveSetProjectionKey;
+use crate::build::requested_outputs;
+use crate::build::BuildProviderType;
+use crate::build::ProvidersToBuild;
+use crate::build::RequestedOutputs;
+use crate::deferred::calculation::DeferredCalculation;
+
+#[derive(Clone, Copy, Dupe, Debug, PartialEq, Eq, Allocative)]
+pub struct ActionGraphChecksum([u8; 32]);
+
+impl ActionGraphChecksum {
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+/// Hash `field` prefixed with its length, so that consecutive fields can't be confused.
+fn update_field(hasher: &mut blake3::Hasher, field: impl AsRef<[u8]>) {
+    let field = field.as_ref();
+    hasher.update(&(field.len() as u64).to_le_bytes());
+    hasher.update(field);
+}
+
+/// Hash what an action is: its key, what it does (including the command and environment of
+/// `run` actions, as exposed to `aquery`), its outputs and where it runs. Its inputs are hashed
+/// separately.
+fn update_action(
+    hasher: &mut blake3::Hasher,
+    action: &RegisteredAction,
+    fs: &ExecutorFs,
+) -> anyhow::Result<()> {
+    update_field(hasher, action.key().to_string());
+    update_field(hasher, (action.kind() as i32).to_le_bytes());
+    update_field(hasher, action.category().as_str());
+    update_field(hasher, action.identifier().unwrap_or_default());
+    let attributes = action.aquery_attributes(fs);
+    update_field(hasher, (attributes.len() as u64).to_le_bytes());
+    for (name, value) in &attributes {
+        update_field(hasher, name);
+        update_field(hasher, value);
+    }
+    let outputs = action.outputs()?;
+    update_field(hasher, (outputs.len() as u64).to_le_bytes());
+    for output in outputs.iter() {
+        update_field(hasher, output.get_path().to_string());
+    }
+    for env in action.env_passthrough() {
+        update_field(hasher, env);
+    }
+    update_executor_config(hasher, action.execution_config());
+    Ok(())
+}
+
+fn update_executor_config(hasher: &mut blake3::Hasher, config: &CommandExecutorConfig) {
+    update_field(hasher, config.executor.to_string());
+    if let Executor::RemoteEnabled {
+        re_properties,
+        re_use_case,
+        re_action_key,
+        dependencies,
+        ..
+    } = &config.executor
+    {
+        for (name, value) in re_properties.properties.iter() {
+            update_field(hasher, name);
+            update_field(hasher, value);
+        }
+        update_field(hasher, re_use_case.to_string());
+        update_field(hasher, re_action_key.as_deref().unwrap_or_default());
+        for dependency in dependencies {
+            update_field(hasher, &dependency.smc_tier);
+            update_field(hasher, &dependency.id);
+        }
+    }
+    update_field(
+        hasher,
+        match config.options.path_separator {
+            PathSeparatorKind::Unix => "unix",
+            PathSeparatorKind::Windows => "windows",
+        },
+    );
+    update_field(
+        hasher,
+        match config.options.output_paths_behavior {
+            OutputPathsBehavior::Strict => "strict",
+            OutputPathsBehavior::Compatibility => "compatibility",
+            OutputPathsBehavior::OutputPaths => "output_paths",
+        },
+    );
+}
+
+/// Hash the value of a source artifact.
+fn update_entry(hasher: &mut blake3::Hasher, entry: &ActionDirectoryEntry<ActionSharedDirectory>) {
+    match entry {
+        ActionDirectoryEntry::Dir(d) => {
+            update_field(hasher, "dir");
+            update_field(hasher, d.fingerprint().data().to_string());
+        }
+        ActionDirectoryEntry::Leaf(ActionDirectoryMember::File(f)) => {
+            update_field(hasher, "file");
+            update_field(hasher, f.digest.data().to_string());
+            update_field(hasher, [f.is_executable as u8]);
+        }
+        ActionDirectoryEntry::Leaf(ActionDirectoryMember::Symlink(s)) => {
+            update_field(hasher, "symlink");
+            update_field(hasher, s.target().as_str());
+        }
+        ActionDirectoryEntry::Leaf(ActionDirectoryMember::ExternalSymlink(s)) => {
+            update_field(hasher, "external_symlink");
+            update_field(hasher, s.to_string());
+        }
+    }
+}
+
+/// Checksum of the outputs `providers_to_build` requests from `providers_label`, and of the
+/// actions producing them.
+pub async fn requested_outputs_checksum(
+    ctx: &mut DiceComputations<'_>,
+    providers_label: &ConfiguredProvidersLabel,
+    providers_to_build: &ProvidersToBuild,
+) -> anyhow::Result<ActionGraphChecksum> {
+    let mut hasher = blake3::Hasher::new();
+    update_field(&mut hasher, providers_label.to_string());
+    let RequestedOutputs {
+        outputs,
+        run_args,
+        target_rule_type_name,
+    } = match requested_outputs(ctx, providers_label, providers_to_build).await? {
+        MaybeCompatible::Incompatible(reason) => {
+            update_field(&mut hasher, "incompatible");
+            update_field(&mut hasher, reason.to_string());
+            return Ok(ActionGraphChecksum(*hasher.finalize().as_bytes()));
+        }
+        MaybeCompatible::Compatible(v) => v,
+    };
+
+    update_field(&mut hasher, "compatible");
+    update_field(&mut hasher, target_rule_type_name);
+    match run_args {
+        None => update_field(&mut hasher, "no_run_args"),
+        Some(run_args) => {
+            update_field(&mut hasher, (run_args.len() as u64).to_le_bytes());
+            for arg in run_args {
+                update_field(&mut hasher, arg);
+            }
+        }
+    }
+    let checksums =
+        artifact_groups_checksums(ctx, outputs.iter().map(|(output, _)| output)).await?;
+    for ((_, provider_type), checksum) in outputs.iter().zip(checksums) {
+        update_field(
+            &mut hasher,
+            match provider_type {
+                BuildProviderType::Default => "default",
+                BuildProviderType::DefaultOther => "default_other",
+                BuildProviderType::Run => "run",
+                BuildProviderType::Test => "test",
+                BuildProviderType::Validation => "validation",
+                BuildProviderType::Aspect => "aspect",
+            },
+        );
+        update_field(&mut hasher, checksum.as_bytes());
+    }
+    Ok(ActionGraphChecksum(*hasher.finalize().as_bytes()))
+}
+
+async fn artifact_groups_checksums<'a>(
+    ctx: &mut DiceComputations<'_>,
+    groups: impl IntoIterator<Item = &'a ArtifactGroup>,
+) -> anyhow::Result<Vec<ActionGraphChecksum>> {
+    ctx.try_compute_join(groups, |ctx, group| {
+        async move { artifact_group_checksum(ctx, group).await }.boxed()
+    })
+    .await
+}
+
+async fn artifact_group_checksum(
+    ctx: &mut DiceComputations<'_>,
+    group: &ArtifactGroup,
+) -> anyhow::Result<ActionGraphChecksum> {
+    let mut hasher = blake3::Hasher::new();
+    match group.resolved_artifact(ctx).await? {
+        ResolvedArtifactGroup::Artifact(artifact) => {
+            update_field(&mut hasher, artifact.to_string());
+            match artifact.action_key() {
+                Some(key) => {
+                    let action = ctx.compute(&ActionChecksumKey(key.dupe())).await??;
+                    update_field(&mut hasher, action.as_bytes());
+                }
+                None => {
+                    // Reading a source only reads its metadata, no action runs.
+                    let values = ctx.ensure_artifact_group(group).await?;
+                    for (_, value) in values.iter() {
+                        update_entry(&mut hasher, value.entry());
+                    }
+                }
+            }
+        }
+        ResolvedArtifactGroup::TransitiveSetProjection(key) => {
+            let projection = ctx
+                .compute(TransitiveSetProjectionChecksumKey::ref_cast(key))
+                .await??;
+            update_field(&mut hasher, projection.as_bytes());
+        }
+    }
+    Ok(ActionGraphChecksum(*hasher.finalize().as_bytes()))
+}
+
+#[derive(Clone, Dupe, Display, Debug, Eq, PartialEq, Hash, Allocative)]
+struct ActionChecksumKey(ActionKey);
+
+#[async_trait]
+impl Key for ActionChecksumKey {
+    type Value = buck2_error::Result<ActionGraphChecksum>;
+
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellation: &CancellationContext,
+    ) -> Self::Value {
+        let action = ctx.get_action(&self.0).await?;
+        let artifact_fs = ctx.get_artifact_fs().await?;
+        let inputs = action.inputs()?;
+        let checksums = artifact_groups_checksums(ctx, inputs.iter()).await?;
+
+        let mut hasher = blake3::Hasher::new();
+        update_action(
+            &mut hasher,
+            &action,
+            &ExecutorFs::new(
+                &artifact_fs,
+                action.execution_config().options.path_separator,
+            ),
+        )?;
+        for checksum in checksums {
+            update_field(&mut hasher, checksum.as_bytes());
+        }
+        Ok(ActionGraphChecksum(*hasher.finalize().as_bytes()))
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        match (x, y) {
+            (Ok(x), Ok(y)) => x == y,
+            _ => false,
+        }
+    }
+
+    fn validity(x: &Self::Value) -> bool {
+        x.is_ok()
+    }
+}
+
+#[derive(Clone, Dupe, Display, Debug, Eq, PartialEq, Hash, Allocative, RefCast)]
+#[repr(transparent)]
+struct TransitiveSetProjectionChecksumKey(TransitiveSetProjectionKey);
+
+#[async_trait]
+impl Key for TransitiveSetProjectionChecksumKey {
+    type Value = buck2_error::Result<ActionGraphChecksum>;
+
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellation: &CancellationContext,
+    ) -> Self::Value {
+        let set = ctx
+            .compute_deferred_data(&self.0.key)
+            .await
+            .context("Failed to compute deferred")?;
+        let sub_inputs = set
+            .as_transitive_set()
+            .get_projection_sub_inputs(self.0.projection)?;
+        let checksums = artifact_groups_checksums(ctx, sub_inputs.iter()).await?;
+
+        let mut hasher = blake3::Hasher::new();
+        for checksum in checksums {
+            update_field(&mut hasher, checksum.as_bytes());
+        }
+        Ok(ActionGraphChecksum(*hasher.finalize().as_bytes()))
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        match (x, y) {
+            (Ok(x), Ok(y)) => x == y,
+            _ => false,
+        }
+    }
+
+    fn validity(x: &Self::Value) -> bool {
+        x.is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fields_are_delimited() {
+        let hash = |fields: &[&str]| {
+            let mut hasher = blake3::Hasher::new();
+            for field in fields {
+                update_field(&mut hasher, field);
+            }
+            hasher.finalize()
+        };
+        assert_eq!(hash(&["ab", "c"]), hash(&["ab", "c"]));
+        assert_ne!(hash(&["ab", "c"]), hash(&["a", "bc"]));
+        assert_ne!(hash(&["abc"]), hash(&["abc", ""]));
+    }
+}
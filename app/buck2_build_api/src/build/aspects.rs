@@ -0,0 +1,197 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::sync::Arc;
+
+use allocative::Allocative;
+use anyhow::Context;
+use async_trait::async_trait;
+use buck2_artifact::artifact::artifact_type::Artifact;
+use buck2_core::bzl::ImportPath;
+use buck2_core::cells::CellResolver;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_core::provider::label::ConfiguredProvidersLabel;
+use buck2_core::target::configured_target_label::ConfiguredTargetLabel;
+use buck2_interpreter::parse_import::parse_import_with_config;
+use buck2_interpreter::parse_import::ParseImportOptions;
+use buck2_interpreter::parse_import::RelativeImports;
+use buck2_node::nodes::configured_frontend::ConfiguredTargetNodeCalculation;
+use buck2_node::rule_type::StarlarkRuleType;
+use dice::CancellationContext;
+use dice::DiceComputations;
+use dice::Key;
+use dupe::Dupe;
+use futures::FutureExt;
+use indexmap::IndexSet;
+
+use crate::deferred::calculation::EVAL_ASPECT;
+
+#[derive(Debug, buck2_error::Error)]
+#[buck2(input)]
+enum AspectLabelError {
+    #[error("Aspect should be of format `<cell>//path/to/file.bzl:rule_name`, but got `{0}`")]
+    Format(String),
+}
+
+/// Parse aspects given as `<cell>//path/to/file.bzl:rule_name`, relative to `cwd`, e.g. those
+/// passed to `buck2 build --aspect`.
+pub fn parse_aspects(
+    cwd: &ProjectRelativePath,
+    aspects: &[String],
+    cell_resolver: &CellResolver,
+) -> anyhow::Result<Vec<Arc<StarlarkRuleType>>> {
+    if aspects.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let current_cell = cell_resolver.get_cell_path(cwd)?;
+    let cell_alias_resolver = cell_resolver.get_cwd_cell_alias_resolver(cwd)?;
+    let opts = ParseImportOptions {
+        allow_missing_at_symbol: true,
+        relative_import_option: RelativeImports::Allow {
+            current_dir: &current_cell,
+        },
+    };
+
+    aspects
+        .iter()
+        .map(|aspect| {
+            let (path, name) = aspect
+                .rsplit_once(':')
+                .ok_or_else(|| AspectLabelError::Format(aspect.to_owned()))?;
+            if name.is_empty() || !path.ends_with(".bzl") {
+                return Err(AspectLabelError::Format(aspect.to_owned()).into());
+            }
+            let import_path = parse_import_with_config(cell_alias_resolver, path, &opts)?;
+            Ok(Arc::new(StarlarkRuleType {
+                import_path: ImportPath::new_same_cell(import_path)?,
+                name: name.to_owned(),
+            }))
+        })
+        .collect()
+}
+
+/// Default outputs of an aspect applied to a target and to each of its transitive target deps.
+/// Computed per target so the traversal is shared between the targets of a build and across
+/// builds.
+#[derive(Clone, derive_more::Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "{} applied to {}", aspect, target)]
+struct TransitiveAspectOutputsKey {
+    aspect: Arc<StarlarkRuleType>,
+    target: ConfiguredTargetLabel,
+}
+
+#[async_trait]
+impl Key for TransitiveAspectOutputsKey {
+    type Value = buck2_error::Result<Arc<[Artifact]>>;
+
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellation: &CancellationContext,
+    ) -> Self::Value {
+        let node = ctx
+            .get_configured_target_node(&self.target)
+            .await?
+            .require_compatible()?;
+
+        let mut outputs = IndexSet::new();
+
+        let label = ConfiguredProvidersLabel::default_for(self.target.dupe());
+        let analysis = (EVAL_ASPECT.get()?)(ctx, &self.aspect, &label)
+            .await
+            .with_context(|| {
+                format!(
+                    "Error applying aspect `{}` to `{}`",
+                    self.aspect, self.target
+                )
+            })?;
+        analysis
+            .providers()
+            .provider_collection()
+            .default_info()
+            .for_each_default_output_artifact_only(&mut |o| {
+                outputs.insert(o);
+            })?;
+
+        let deps: Vec<Arc<[Artifact]>> = ctx
+            .try_compute_join(node.target_deps(), |ctx, dep| {
+                let key = TransitiveAspectOutputsKey {
+                    aspect: self.aspect.dupe(),
+                    target: dep.label().dupe(),
+                };
+                async move { anyhow::Ok(ctx.compute(&key).await??) }.boxed()
+            })
+            .await?;
+        for dep in deps {
+            outputs.extend(dep.iter().cloned());
+        }
+
+        Ok(outputs.into_iter().collect())
+    }
+
+    fn equality(a: &Self::Value, b: &Self::Value) -> bool {
+        match (a, b) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// Returns the default outputs of `aspect` applied to a target and to all of its transitive
+/// target deps.
+pub(crate) async fn transitive_aspect_outputs(
+    ctx: &mut DiceComputations<'_>,
+    aspect: &Arc<StarlarkRuleType>,
+    target: &ConfiguredTargetLabel,
+) -> anyhow::Result<Arc<[Artifact]>> {
+    Ok(ctx
+        .compute(&TransitiveAspectOutputsKey {
+            aspect: aspect.dupe(),
+            target: target.dupe(),
+        })
+        .await??)
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_core::cells::cell_root_path::CellRootPathBuf;
+    use buck2_core::cells::name::CellName;
+
+    use super::*;
+
+    fn parse(cwd: &str, aspect: &str) -> anyhow::Result<String> {
+        let cell_resolver = CellResolver::testing_with_name_and_path(
+            CellName::testing_new("root"),
+            CellRootPathBuf::testing_new(""),
+        );
+        let aspects = parse_aspects(
+            ProjectRelativePath::new(cwd)?,
+            &[aspect.to_owned()],
+            &cell_resolver,
+        )?;
+        Ok(aspects[0].to_string())
+    }
+
+    #[test]
+    fn test_parse_aspects() -> anyhow::Result<()> {
+        assert_eq!(
+            "root//tools/aspects.bzl:ide_info",
+            parse("", "//tools/aspects.bzl:ide_info")?
+        );
+        assert_eq!(
+            "root//tools/aspects.bzl:lint",
+            parse("tools", ":aspects.bzl:lint")?
+        );
+        assert!(parse("", "//tools/aspects.bzl").is_err());
+        assert!(parse("", "//tools:aspects.bzl:").is_err());
+        assert!(parse("", "//tools:lint").is_err());
+        Ok(())
+    }
+}
@@ -32,6 +32,7 @@ use buck2_core::provider::label::ConfiguredProvidersLabel;
 use buck2_core::provider::label::NonDefaultProvidersName;
 use buck2_core::provider::label::ProvidersLabel;
 use buck2_core::provider::label::ProvidersName;
+use buck2_core::target::configured_target_label::ConfiguredTargetLabel;
 use buck2_core::target::label::label::TargetLabel;
 use buck2_error::UniqueRootId;
 use buck2_events::errors::create_error_report;
@@ -42,14 +43,18 @@ use dupe::Dupe;
 use itertools::Either;
 use itertools::EitherOrBoth;
 use itertools::Itertools;
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
 use serde::Serialize;
 use starlark_map::small_set::SmallSet;
 
+use crate::actions::execute::cache_hit_stats::CategoryCacheHits;
 use crate::build::action_error::BuildReportActionError;
+use crate::build::dependency_failure::TargetFailure;
 use crate::build::BuildProviderType;
 use crate::build::ConfiguredBuildTargetResult;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[allow(clippy::upper_case_acronyms)] // We care about how they serialise
 enum BuildOutcome {
     SUCCESS,
@@ -58,6 +63,16 @@ enum BuildOutcome {
     CANCELED,
 }
 
+/// DO NOT UPDATE WITHOUT UPDATING `docs/users/build_observability/build_report.md`!
+#[derive(Debug, Serialize, JsonSchema)]
+#[allow(clippy::upper_case_acronyms)] // We care about how they serialise
+enum BuildReportFailureKind {
+    /// The target itself failed
+    FAILED,
+    /// Only dependencies of the target failed
+    SKIPPED_DUE_TO_FAILED_DEPENDENCY,
+}
+
 impl Default for BuildOutcome {
     fn default() -> Self {
         Self::SUCCESS
@@ -65,33 +80,58 @@ impl Default for BuildOutcome {
 }
 
 /// DO NOT UPDATE WITHOUT UPDATING `docs/users/build_observability/build_report.md`!
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct BuildReport {
+    #[schemars(with = "String")]
     trace_id: TraceId,
     success: bool,
     results: HashMap<EntryLabel, BuildReportEntry>,
     /// filled only when fill-out-failures is passed for Buck1 backcompat only
     failures: HashMap<EntryLabel, String>,
+    #[schemars(with = "String")]
     project_root: AbsNormPathBuf,
     truncated: bool,
     strings: BTreeMap<String, String>,
+    /// Remote cache hits of the actions run by the build. Not set by BXL.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cache_hits: Option<BuildReportCacheHits>,
+}
+
+/// Remote cache hits of the actions which ran a command in a build.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BuildReportCacheHits {
+    /// The fraction of these actions served by the remote cache, or `None` if no action ran a
+    /// command.
+    pub hit_rate: Option<f64>,
+    /// The `--min-cache-hit-rate` of the build, as a fraction.
+    pub min_hit_rate: Option<f64>,
+    pub by_category: BTreeMap<String, CategoryCacheHits>,
+}
+
+impl BuildReport {
+    /// JSON Schema of the build report, as written by `--build-report`.
+    pub fn json_schema() -> RootSchema {
+        schemars::schema_for!(BuildReport)
+    }
 }
 
 /// The fields that stored in the unconfigured `BuildReportEntry` for buck1 backcompat.
 ///
 /// Do not put new fields in here. Put them in `ConfiguredBuildReportEntry`
-#[derive(Default, Debug, Serialize)]
+#[derive(Default, Debug, Serialize, JsonSchema)]
 struct MaybeConfiguredBuildReportEntry {
     /// whether this particular target was successful
     success: BuildOutcome,
     /// a map of each subtarget of the current target (outputted as a `|` delimited list) to
     /// the default exposed output of the subtarget
+    #[schemars(with = "HashMap<String, Vec<String>>")]
     outputs: HashMap<Arc<str>, SmallSet<ProjectRelativePathBuf>>,
     /// a map of each subtarget of the current target (outputted as a `|` delimited list) to
     /// the hidden, implicitly built outputs of the subtarget. There are multiple outputs
     /// per subtarget
     ///
     /// FIXME(JakobDegen): This should be in `ConfiguredBuildReportEntry`
+    #[schemars(with = "HashMap<String, Vec<String>>")]
     other_outputs: HashMap<Arc<str>, SmallSet<ProjectRelativePathBuf>>,
     /// The size of the graph for this target, if it was produced
     ///
@@ -100,16 +140,24 @@ struct MaybeConfiguredBuildReportEntry {
 }
 
 /// DO NOT UPDATE WITHOUT UPDATING `docs/users/build_observability/build_report.md`!
-#[derive(Default, Debug, Serialize)]
+#[derive(Default, Debug, Serialize, JsonSchema)]
 pub(crate) struct ConfiguredBuildReportEntry {
     /// A list of errors that occurred while building this target
     errors: Vec<BuildReportError>,
+    /// Set if the target did not build: whether it failed itself or was only skipped because
+    /// one of its dependencies failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    failure_kind: Option<BuildReportFailureKind>,
+    /// For targets skipped due to a failed dependency, the dependency path from this target to
+    /// the target whose action failed
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    root_cause_chain: Vec<String>,
     #[serde(flatten)]
     inner: MaybeConfiguredBuildReportEntry,
 }
 
 /// DO NOT UPDATE WITHOUT UPDATING `docs/users/build_observability/build_report.md`!
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 struct BuildReportEntry {
     /// The buck1 build report did not support multiple configurations of the same target. We
     /// do, which is why we have the `configured` field below, which users should ideally use.
@@ -130,11 +178,12 @@ struct BuildReportEntry {
 
     /// The path to the package where this target is defined, relative to the project root.
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
     package_project_relative_path: Option<ProjectRelativePathBuf>,
 }
 
 /// DO NOT UPDATE WITHOUT UPDATING `docs/users/build_observability/build_report.md`!
-#[derive(Debug, Clone, Serialize, PartialOrd, Ord, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, JsonSchema, PartialOrd, Ord, PartialEq, Eq)]
 struct BuildReportError {
     message_content: String,
     action_error: Option<BuildReportActionError>,
@@ -174,6 +223,7 @@ pub struct BuildReportCollector<'a> {
     failures: HashMap<EntryLabel, String>,
     include_failures: bool,
     include_package_project_relative_paths: bool,
+    target_failures: &'a BTreeMap<ConfiguredTargetLabel, TargetFailure>,
 }
 
 impl<'a> BuildReportCollector<'a> {
@@ -188,6 +238,7 @@ impl<'a> BuildReportCollector<'a> {
         include_package_project_relative_paths: bool,
         configured: &BTreeMap<ConfiguredProvidersLabel, Option<ConfiguredBuildTargetResult>>,
         other_errors: &BTreeMap<Option<ProvidersLabel>, Vec<buck2_error::Error>>,
+        target_failures: &'a BTreeMap<ConfiguredTargetLabel, TargetFailure>,
     ) -> BuildReport {
         let mut this: BuildReportCollector<'_> = Self {
             artifact_fs,
@@ -201,6 +252,7 @@ impl<'a> BuildReportCollector<'a> {
             failures: HashMap::default(),
             include_failures,
             include_package_project_relative_paths,
+            target_failures,
         };
         let mut entries = HashMap::new();
 
@@ -246,6 +298,7 @@ impl<'a> BuildReportCollector<'a> {
             // Setting this to false since we don't currently truncate buck2's build report.
             truncated: false,
             strings: this.strings,
+            cache_hits: None,
         }
     }
 
@@ -292,7 +345,7 @@ impl<'a> BuildReportCollector<'a> {
             .filter_map(|(label, result)| Some((label, result.as_ref()?)))
             .group_by(|x| x.0.target().dupe())
         {
-            let configured_report = self.collect_results_for_configured(target.dupe(), results);
+            let configured_report = self.collect_results_for_configured(&label, results);
             if let Some(report) = unconfigured_report.as_mut() {
                 if !configured_report.errors.is_empty() {
                     report.success = BuildOutcome::FAIL;
@@ -339,7 +392,7 @@ impl<'a> BuildReportCollector<'a> {
 
     fn collect_results_for_configured<'b>(
         &mut self,
-        target: TargetLabel,
+        target: &ConfiguredTargetLabel,
         results: impl IntoIterator<
             Item = (
                 &'b ConfiguredProvidersLabel,
@@ -367,13 +420,18 @@ impl<'a> BuildReportCollector<'a> {
                             }
                             BuildProviderType::DefaultOther
                             | BuildProviderType::Run
-                            | BuildProviderType::Test => {
+                            | BuildProviderType::Test
+                            | BuildProviderType::Aspect => {
                                 // as long as the output isn't the default, we add it to other outputs.
                                 // This means that the same artifact may appear twice if its part of the
                                 // default AND the other outputs, but this is intended as it accurately
                                 // describes the type of the artifact
                                 is_other = true;
                             }
+                            BuildProviderType::Validation => {
+                                // validations must build successfully but are not outputs of
+                                // the target, so they are not reported.
+                            }
                         }
 
                         for (artifact, _value) in artifacts.values.iter() {
@@ -408,9 +466,19 @@ impl<'a> BuildReportCollector<'a> {
                 configured_report.inner.configured_graph_size = Some(configured_graph_size);
             }
         }
-        configured_report.errors = self.convert_error_list(&errors, target);
+        configured_report.errors = self.convert_error_list(&errors, target.unconfigured().dupe());
         if !configured_report.errors.is_empty() {
             configured_report.inner.success = BuildOutcome::FAIL;
+            match self.target_failures.get(target) {
+                Some(TargetFailure::SkippedDueToFailedDependency { root_cause_chain }) => {
+                    configured_report.failure_kind =
+                        Some(BuildReportFailureKind::SKIPPED_DUE_TO_FAILED_DEPENDENCY);
+                    configured_report.root_cause_chain = root_cause_chain.clone();
+                }
+                Some(TargetFailure::Failed) | None => {
+                    configured_report.failure_kind = Some(BuildReportFailureKind::FAILED);
+                }
+            }
         }
         configured_report
     }
@@ -543,8 +611,10 @@ pub fn generate_build_report(
     trace_id: &TraceId,
     configured: &BTreeMap<ConfiguredProvidersLabel, Option<ConfiguredBuildTargetResult>>,
     other_errors: &BTreeMap<Option<ProvidersLabel>, Vec<buck2_error::Error>>,
+    target_failures: &BTreeMap<ConfiguredTargetLabel, TargetFailure>,
+    cache_hits: Option<BuildReportCacheHits>,
 ) -> Result<Option<String>, buck2_error::Error> {
-    let build_report = BuildReportCollector::convert(
+    let mut build_report = BuildReportCollector::convert(
         trace_id,
         artifact_fs,
         cell_resolver,
@@ -555,7 +625,9 @@ pub fn generate_build_report(
         opts.unstable_include_package_project_relative_paths,
         configured,
         other_errors,
+        target_failures,
     );
+    build_report.cache_hits = cache_hits;
 
     let mut serialized_build_report = None;
 
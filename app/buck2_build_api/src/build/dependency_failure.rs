@@ -0,0 +1,263 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Telling apart targets that failed from targets that were skipped because a dependency failed.
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::hash::Hash;
+
+use buck2_core::target::configured_target_label::ConfiguredTargetLabel;
+use buck2_data::action_key::Owner;
+use buck2_data::ToProtoMessage;
+use buck2_event_observer::display::display_configured_target_label;
+use buck2_event_observer::display::TargetDisplayOptions;
+
+use crate::build::ConfiguredBuildTargetResult;
+
+/// Why a requested target did not build.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TargetFailure {
+    /// The target itself failed, e.g. in analysis or in one of its own actions.
+    Failed,
+    /// Only actions owned by dependencies failed.
+    SkippedDueToFailedDependency {
+        /// The dependency path from the target to the target owning the failed action, both
+        /// inclusive.
+        root_cause_chain: Vec<String>,
+    },
+}
+
+/// What caused a requested target not to build.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FailureCause {
+    /// Something owned by the target itself failed.
+    Target,
+    /// Only actions owned by this dependency failed.
+    Dependency(buck2_data::ConfiguredTargetLabel),
+}
+
+/// What caused `result` to fail, or `None` if it built.
+pub fn failure_cause(
+    target: &ConfiguredTargetLabel,
+    result: &ConfiguredBuildTargetResult,
+) -> Option<FailureCause> {
+    if result.errors.is_empty() && result.outputs.iter().all(|output| output.is_ok()) {
+        return None;
+    }
+    Some(match failed_dependency(target, result) {
+        None => FailureCause::Target,
+        Some(root_cause) => FailureCause::Dependency(root_cause),
+    })
+}
+
+/// Returns the target owning the failed action that caused `result` to fail, provided that
+/// nothing owned by `target` itself failed.
+pub fn failed_dependency(
+    target: &ConfiguredTargetLabel,
+    result: &ConfiguredBuildTargetResult,
+) -> Option<buck2_data::ConfiguredTargetLabel> {
+    let errors = result
+        .outputs
+        .iter()
+        .filter_map(|output| output.as_ref().err())
+        .chain(&result.errors);
+
+    let mut root_cause = None;
+    for error in errors {
+        // Errors that aren't action errors (e.g. analysis errors) are attributed to the target.
+        let owner = error.action_error()?.key.as_ref()?.owner.as_ref()?;
+        let owner = match owner {
+            Owner::TargetLabel(label)
+            | Owner::TestTargetLabel(label)
+            | Owner::LocalResourceSetup(label) => label,
+            // Anonymous targets and BXL actions are not dependencies in the configured graph.
+            Owner::AnonTarget(_) | Owner::BxlKey(_) => return None,
+        };
+        if is_label_of(owner, target) {
+            return None;
+        }
+        root_cause.get_or_insert_with(|| owner.clone());
+    }
+    root_cause
+}
+
+/// Whether the action owner `owner` refers to `target`.
+pub fn is_label_of(
+    owner: &buck2_data::ConfiguredTargetLabel,
+    target: &ConfiguredTargetLabel,
+) -> bool {
+    owner.label.as_ref() == Some(&target.unconfigured().as_proto())
+        && owner.configuration.as_ref() == Some(&target.cfg().as_proto())
+}
+
+pub fn display_root_cause(owner: &buck2_data::ConfiguredTargetLabel) -> String {
+    display_configured_target_label(owner, TargetDisplayOptions::for_build_report())
+        .unwrap_or_default()
+}
+
+/// The shortest dependency paths from each of `targets` to `root_cause`, both inclusive, given the
+/// reverse dependencies of the graph. This is a single breadth-first search from `root_cause`, so
+/// that all the targets skipped because of the same failure share it. Targets from which
+/// `root_cause` is not reachable are omitted.
+pub fn dependency_chains<'a, T: Hash + Eq + Clone + 'a>(
+    rdeps: &HashMap<T, Vec<T>>,
+    root_cause: &T,
+    targets: impl IntoIterator<Item = &'a T>,
+) -> HashMap<T, Vec<T>> {
+    // For each target reached, the next target on its path to `root_cause`.
+    let mut next = HashMap::new();
+    next.insert(root_cause.clone(), None);
+    let mut queue = VecDeque::from([root_cause]);
+    while let Some(target) = queue.pop_front() {
+        for rdep in rdeps.get(target).into_iter().flatten() {
+            if !next.contains_key(rdep) {
+                next.insert(rdep.clone(), Some(target.clone()));
+                queue.push_back(rdep);
+            }
+        }
+    }
+
+    let mut chains = HashMap::new();
+    for target in targets {
+        if !next.contains_key(target) {
+            continue;
+        }
+        let mut chain = vec![target.clone()];
+        while let Some(Some(hop)) = next.get(chain.last().unwrap()) {
+            chain.push(hop.clone());
+        }
+        chains.insert(target.clone(), chain);
+    }
+    chains
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_core::configuration::data::ConfigurationData;
+
+    use super::*;
+
+    #[derive(Debug, derive_more::Display)]
+    #[display(fmt = "action failed")]
+    struct TestActionError(buck2_data::ActionError);
+
+    impl std::error::Error for TestActionError {
+        fn provide<'a>(&'a self, request: &mut std::error::Request<'a>) {
+            buck2_error::provide_metadata(
+                request,
+                None,
+                None,
+                [],
+                std::file!(),
+                None,
+                Some(self.0.clone()),
+            );
+        }
+    }
+
+    fn label(s: &str) -> ConfiguredTargetLabel {
+        ConfiguredTargetLabel::testing_parse(s, ConfigurationData::testing_new())
+    }
+
+    fn action_error(owner: &ConfiguredTargetLabel) -> buck2_error::Error {
+        TestActionError(buck2_data::ActionError {
+            key: Some(buck2_data::ActionKey {
+                owner: Some(Owner::TargetLabel(owner.as_proto())),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .into()
+    }
+
+    fn result(errors: Vec<buck2_error::Error>) -> ConfiguredBuildTargetResult {
+        ConfiguredBuildTargetResult {
+            outputs: Vec::new(),
+            run_args: None,
+            target_rule_type_name: None,
+            configured_graph_size: None,
+            errors,
+        }
+    }
+
+    #[test]
+    fn test_is_label_of() {
+        let foo = label("root//pkg:foo");
+        assert!(is_label_of(&foo.as_proto(), &foo));
+        assert!(!is_label_of(&label("root//pkg:bar").as_proto(), &foo));
+        assert!(!is_label_of(
+            &ConfiguredTargetLabel::testing_parse(
+                "root//pkg:foo",
+                ConfigurationData::unspecified()
+            )
+            .as_proto(),
+            &foo
+        ));
+    }
+
+    #[test]
+    fn test_failed_dependency() {
+        let foo = label("root//pkg:foo");
+        let dep = label("root//pkg:dep");
+        assert_eq!(
+            Some(dep.as_proto()),
+            failed_dependency(&foo, &result(vec![action_error(&dep)]))
+        );
+        // A failed action of the target itself makes it fail, whatever else failed.
+        assert_eq!(
+            None,
+            failed_dependency(&foo, &result(vec![action_error(&dep), action_error(&foo)]))
+        );
+        // As do errors which are not action errors.
+        assert_eq!(
+            None,
+            failed_dependency(
+                &foo,
+                &result(vec![
+                    action_error(&dep),
+                    buck2_error::Error::from(anyhow::anyhow!("analysis failed")),
+                ])
+            )
+        );
+    }
+
+    #[test]
+    fn test_failure_cause() {
+        let foo = label("root//pkg:foo");
+        let dep = label("root//pkg:dep");
+        assert_eq!(None, failure_cause(&foo, &result(Vec::new())));
+        assert_eq!(
+            Some(FailureCause::Target),
+            failure_cause(&foo, &result(vec![action_error(&foo)]))
+        );
+        assert_eq!(
+            Some(FailureCause::Dependency(dep.as_proto())),
+            failure_cause(&foo, &result(vec![action_error(&dep)]))
+        );
+    }
+
+    #[test]
+    fn test_dependency_chains() {
+        // a -> b -> c -> d, a -> d, e -> c, f
+        let rdeps = HashMap::from([
+            ("b", vec!["a"]),
+            ("c", vec!["b", "e"]),
+            ("d", vec!["c", "a"]),
+        ]);
+        let chains = dependency_chains(&rdeps, &"c", &["a", "e", "c", "f"]);
+        assert_eq!(Some(&vec!["a", "b", "c"]), chains.get("a"));
+        assert_eq!(Some(&vec!["e", "c"]), chains.get("e"));
+        assert_eq!(Some(&vec!["c"]), chains.get("c"));
+        assert_eq!(None, chains.get("f"));
+
+        let chains = dependency_chains(&rdeps, &"d", &["a"]);
+        assert_eq!(Some(&vec!["a", "d"]), chains.get("a"));
+    }
+}
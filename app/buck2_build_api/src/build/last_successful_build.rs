@@ -0,0 +1,100 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! The response to the last successful build, retained by the daemon so that a build with the
+//! same checksum can return it without building anything.
+
+use std::sync::Arc;
+
+use allocative::Allocative;
+use buck2_cli_proto::BuildResponse;
+use dice::UserComputationData;
+use dupe::Dupe;
+use parking_lot::Mutex;
+
+/// Owned by the daemon and shared by its build commands.
+#[derive(Default, Allocative)]
+pub struct LastSuccessfulBuild {
+    /// The checksum of the build and its response.
+    #[allocative(skip)]
+    last: Mutex<Option<([u8; 32], BuildResponse)>>,
+}
+
+impl LastSuccessfulBuild {
+    /// The response of the last successful build, if it had the same checksum.
+    pub fn up_to_date_response(&self, checksum: &[u8; 32]) -> Option<BuildResponse> {
+        match &*self.last.lock() {
+            Some((last, response)) if last == checksum => Some(response.clone()),
+            _ => None,
+        }
+    }
+
+    /// Remember `response` for the next build, if the build succeeded.
+    pub fn record_build(&self, checksum: [u8; 32], response: &BuildResponse) {
+        *self.last.lock() = if response.errors.is_empty() && response.failures.is_empty() {
+            Some((checksum, response.clone()))
+        } else {
+            None
+        };
+    }
+}
+
+pub trait HasLastSuccessfulBuild {
+    fn set_last_successful_build(&mut self, last: Arc<LastSuccessfulBuild>);
+
+    fn get_last_successful_build(&self) -> Arc<LastSuccessfulBuild>;
+}
+
+impl HasLastSuccessfulBuild for UserComputationData {
+    fn set_last_successful_build(&mut self, last: Arc<LastSuccessfulBuild>) {
+        self.data.set(last);
+    }
+
+    fn get_last_successful_build(&self) -> Arc<LastSuccessfulBuild> {
+        self.data
+            .get::<Arc<LastSuccessfulBuild>>()
+            .expect("Last successful build should be set")
+            .dupe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_cli_proto::BuildResponse;
+
+    use super::LastSuccessfulBuild;
+
+    #[test]
+    fn test_up_to_date_response() {
+        let last = LastSuccessfulBuild::default();
+        assert!(last.up_to_date_response(&[0; 32]).is_none());
+
+        let response = BuildResponse {
+            project_root: "root".to_owned(),
+            ..Default::default()
+        };
+        last.record_build([0; 32], &response);
+        assert_eq!(Some(response), last.up_to_date_response(&[0; 32]));
+        assert!(last.up_to_date_response(&[1; 32]).is_none());
+    }
+
+    #[test]
+    fn test_failed_build_is_not_reused() {
+        let last = LastSuccessfulBuild::default();
+        last.record_build([0; 32], &BuildResponse::default());
+        last.record_build(
+            [0; 32],
+            &BuildResponse {
+                errors: vec![Default::default()],
+                ..Default::default()
+            },
+        );
+        assert!(last.up_to_date_response(&[0; 32]).is_none());
+    }
+}
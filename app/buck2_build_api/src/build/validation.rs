@@ -0,0 +1,106 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::sync::Arc;
+
+use allocative::Allocative;
+use async_trait::async_trait;
+use buck2_artifact::artifact::artifact_type::Artifact;
+use buck2_core::provider::label::ConfiguredProvidersLabel;
+use buck2_core::target::configured_target_label::ConfiguredTargetLabel;
+use buck2_node::nodes::configured_frontend::ConfiguredTargetNodeCalculation;
+use dice::CancellationContext;
+use dice::DiceComputations;
+use dice::Key;
+use dupe::Dupe;
+use futures::FutureExt;
+use indexmap::IndexSet;
+
+use crate::analysis::calculation::RuleAnalysisCalculation;
+use crate::interpreter::rule_defs::provider::builtin::validation_info::FrozenValidationInfo;
+
+/// Validations of a target and its transitive target deps. Computed per target so the
+/// traversal is shared between the targets of a build and across builds.
+#[derive(
+    Clone,
+    Dupe,
+    derive_more::Display,
+    Debug,
+    Eq,
+    Hash,
+    PartialEq,
+    Allocative
+)]
+struct TransitiveValidationsKey(ConfiguredTargetLabel);
+
+#[async_trait]
+impl Key for TransitiveValidationsKey {
+    type Value = buck2_error::Result<Arc<[Artifact]>>;
+
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellation: &CancellationContext,
+    ) -> Self::Value {
+        let node = ctx
+            .get_configured_target_node(&self.0)
+            .await?
+            .require_compatible()?;
+
+        let mut validations = IndexSet::new();
+
+        let providers = ctx
+            .get_providers(&ConfiguredProvidersLabel::default_for(self.0.dupe()))
+            .await?
+            .require_compatible()?;
+        if let Some(info) = providers
+            .provider_collection()
+            .builtin_provider::<FrozenValidationInfo>()
+        {
+            info.for_each_validation(&mut |_name, artifact| {
+                validations.insert(artifact);
+            })?;
+        }
+
+        let deps: Vec<Arc<[Artifact]>> = ctx
+            .try_compute_join(node.target_deps(), |ctx, dep| {
+                async move {
+                    anyhow::Ok(
+                        ctx.compute(&TransitiveValidationsKey(dep.label().dupe()))
+                            .await??,
+                    )
+                }
+                .boxed()
+            })
+            .await?;
+        for dep in deps {
+            validations.extend(dep.iter().cloned());
+        }
+
+        Ok(validations.into_iter().collect())
+    }
+
+    fn equality(a: &Self::Value, b: &Self::Value) -> bool {
+        match (a, b) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// Returns the validation artifacts declared via `ValidationInfo` by a target and by all of
+/// its transitive target deps.
+pub(crate) async fn transitive_validations(
+    ctx: &mut DiceComputations<'_>,
+    target: &ConfiguredTargetLabel,
+) -> anyhow::Result<Arc<[Artifact]>> {
+    Ok(ctx
+        .compute(&TransitiveValidationsKey(target.dupe()))
+        .await??)
+}
@@ -24,6 +24,7 @@ use buck2_common::dice::data::HasIoProvider;
 use buck2_core::base_deferred_key::BaseDeferredKey;
 use buck2_core::base_deferred_key::BaseDeferredKeyDyn;
 use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+use buck2_core::provider::label::ConfiguredProvidersLabel;
 use buck2_events::dispatch::create_span;
 use buck2_events::dispatch::Span;
 use buck2_execute::artifact::artifact_dyn::ArtifactDyn;
@@ -31,6 +32,7 @@ use buck2_execute::digest_config::HasDigestConfig;
 use buck2_execute::materialize::materializer::HasMaterializer;
 use buck2_futures::cancellation::CancellationContext;
 use buck2_node::nodes::configured_frontend::ConfiguredTargetNodeCalculation;
+use buck2_node::rule_type::StarlarkRuleType;
 use buck2_util::late_binding::LateBinding;
 use derive_more::Display;
 use dice::DiceComputations;
@@ -104,6 +106,16 @@ pub static EVAL_ANON_TARGET: LateBinding<
     ) -> Pin<Box<dyn Future<Output = anyhow::Result<AnalysisResult>> + Send + 'c>>,
 > = LateBinding::new("EVAL_ANON_TARGET");
 
+/// Analyze an aspect, a rule with a `target` dependency attribute, applied to a configured target
+/// (see `buck2 build --aspect`).
+pub static EVAL_ASPECT: LateBinding<
+    for<'c> fn(
+        &'c mut DiceComputations,
+        &'c StarlarkRuleType,
+        &'c ConfiguredProvidersLabel,
+    ) -> Pin<Box<dyn Future<Output = anyhow::Result<AnalysisResult>> + Send + 'c>>,
+> = LateBinding::new("EVAL_ASPECT");
+
 pub static GET_PROMISED_ARTIFACT: LateBinding<
     for<'c> fn(
         &'c PromiseArtifact,
@@ -21,6 +21,8 @@ use buck2_core::provider::label::ProvidersName;
 use buck2_core::target::configured_target_label::ConfiguredTargetLabel;
 use buck2_execute::digest_config::DigestConfig;
 use buck2_interpreter::types::configured_providers_label::StarlarkConfiguredProvidersLabel;
+use buck2_node::feature_flags::FeatureFlagValue;
+use buck2_node::feature_flags::FeatureFlags;
 use buck2_util::late_binding::LateBinding;
 use derive_more::Display;
 use dice::DiceComputations;
@@ -181,6 +183,8 @@ pub struct AnalysisContext<'v> {
     /// Only `None` when running a `dynamic_output` action from Bxl.
     label: Option<ValueTyped<'v, StarlarkConfiguredProvidersLabel>>,
     plugins: Option<ValueTypedComplex<'v, AnalysisPlugins<'v>>>,
+    /// Feature flags of the package of the target. `None` for `dynamic_output` and BXL.
+    feature_flags: Option<FeatureFlags>,
 }
 
 impl<'v> Display for AnalysisContext<'v> {
@@ -203,6 +207,7 @@ impl<'v> AnalysisContext<'v> {
         attrs: Option<ValueOfUnchecked<'v, StructRef<'static>>>,
         label: Option<ValueTyped<'v, StarlarkConfiguredProvidersLabel>>,
         plugins: Option<ValueTypedComplex<'v, AnalysisPlugins<'v>>>,
+        feature_flags: Option<FeatureFlags>,
         registry: AnalysisRegistry<'v>,
         digest_config: DigestConfig,
     ) -> Self {
@@ -216,6 +221,7 @@ impl<'v> AnalysisContext<'v> {
             }),
             label,
             plugins,
+            feature_flags,
         }
     }
 
@@ -224,6 +230,7 @@ impl<'v> AnalysisContext<'v> {
         attrs: Option<ValueOfUnchecked<'v, StructRef<'static>>>,
         label: Option<ConfiguredTargetLabel>,
         plugins: Option<ValueTypedComplex<'v, AnalysisPlugins<'v>>>,
+        feature_flags: Option<FeatureFlags>,
         registry: AnalysisRegistry<'v>,
         digest_config: DigestConfig,
     ) -> ValueTyped<'v, AnalysisContext<'v>> {
@@ -233,7 +240,15 @@ impl<'v> AnalysisContext<'v> {
             ))
         });
 
-        let analysis_context = Self::new(heap, attrs, label, plugins, registry, digest_config);
+        let analysis_context = Self::new(
+            heap,
+            attrs,
+            label,
+            plugins,
+            feature_flags,
+            registry,
+            digest_config,
+        );
         heap.alloc_typed(analysis_context)
     }
 
@@ -337,6 +352,33 @@ fn analysis_context_methods(builder: &mut MethodsBuilder) {
             .plugins
             .context("`plugins` is not available for `dynamic_output` or BXL")
     }
+
+    /// Returns the value of the feature flag `name`, set with `write_feature_flag` in the
+    /// `PACKAGE` files of the package of the target (or of its parents), or `default`
+    /// if the flag is not set.
+    ///
+    /// ```python
+    /// if ctx.feature_flag("new_link_strategy", default = False):
+    ///     ...
+    /// ```
+    fn feature_flag<'v>(
+        this: RefAnalysisContext,
+        #[starlark(require = pos)] name: &str,
+        #[starlark(require = named)] default: Option<Value<'v>>,
+        heap: &'v Heap,
+    ) -> anyhow::Result<Value<'v>> {
+        let feature_flags = this
+            .0
+            .feature_flags
+            .as_ref()
+            .context("`feature_flag` is not available for `dynamic_output` or BXL")?;
+        Ok(match feature_flags.get(name) {
+            Some(FeatureFlagValue::Bool(x)) => Value::new_bool(*x),
+            Some(FeatureFlagValue::Int(x)) => heap.alloc(*x),
+            Some(FeatureFlagValue::String(x)) => heap.alloc(x.as_str()),
+            None => default.unwrap_or_else(Value::new_none),
+        })
+    }
 }
 
 #[starlark_module]
@@ -80,6 +80,7 @@ pub mod dependency;
 pub(crate) mod doc;
 pub mod execution_platform;
 pub mod registration;
+mod summary;
 pub mod test_provider;
 pub(crate) mod ty;
 pub(crate) mod user;
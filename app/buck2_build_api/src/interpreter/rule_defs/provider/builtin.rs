@@ -9,6 +9,8 @@
 
 //! Builtin providers.
 
+pub mod action_metadata_info;
+pub mod artifact_assertions_info;
 pub mod configuration_info;
 pub mod constraint_setting_info;
 pub mod constraint_value_info;
@@ -22,5 +24,6 @@ pub mod platform_info;
 pub mod run_info;
 pub mod template_placeholder_info;
 pub(crate) mod ty;
+pub mod validation_info;
 pub mod worker_info;
 pub mod worker_run_info;
@@ -0,0 +1,74 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::fmt::Debug;
+
+use allocative::Allocative;
+use buck2_build_api_derive::internal_provider;
+use starlark::any::ProvidesStaticType;
+use starlark::coerce::Coerce;
+use starlark::environment::GlobalsBuilder;
+use starlark::eval::Evaluator;
+use starlark::values::list::AllocList;
+use starlark::values::list_or_tuple::UnpackListOrTuple;
+use starlark::values::Freeze;
+use starlark::values::Trace;
+use starlark::values::UnpackValue;
+use starlark::values::Value;
+use starlark::values::ValueError;
+use starlark::values::ValueLifetimeless;
+use starlark::StarlarkResultExt;
+
+use crate::interpreter::rule_defs::artifact::starlark_artifact::StarlarkArtifact;
+use crate::interpreter::rule_defs::artifact::starlark_artifact_like::ValueAsArtifactLike;
+
+/// A provider carrying metadata about the outputs of actions, as returned by
+/// `ctx.actions.write_action_metadata`. Rules return it, or forward it, so that packaging or
+/// reporting rules can consume the metadata of their dependencies.
+#[internal_provider(action_metadata_info_creator)]
+#[derive(Clone, Debug, Trace, Coerce, Freeze, ProvidesStaticType, Allocative)]
+#[repr(C)]
+pub struct ActionMetadataInfoGen<V: ValueLifetimeless> {
+    /// A JSON file with the path, owner, size and digest of each of `artifacts`.
+    #[provider(field_type = StarlarkArtifact)]
+    metadata: V,
+    /// The artifacts described by `metadata`.
+    #[provider(field_type = Vec<StarlarkArtifact>)]
+    artifacts: V,
+}
+
+#[starlark_module]
+fn action_metadata_info_creator(globals: &mut GlobalsBuilder) {
+    #[starlark(as_type = FrozenActionMetadataInfo)]
+    fn ActionMetadataInfo<'v>(
+        #[starlark(require = named)] metadata: Value<'v>,
+        #[starlark(require = named, default = UnpackListOrTuple::default())]
+        artifacts: UnpackListOrTuple<Value<'v>>,
+        eval: &mut Evaluator<'v, '_, '_>,
+    ) -> anyhow::Result<ActionMetadataInfo<'v>> {
+        if ValueAsArtifactLike::unpack_value(metadata)
+            .into_anyhow_result()?
+            .is_none()
+        {
+            return Err(ValueError::IncorrectParameterTypeNamed("metadata".to_owned()).into());
+        }
+        for artifact in &artifacts.items {
+            if ValueAsArtifactLike::unpack_value(*artifact)
+                .into_anyhow_result()?
+                .is_none()
+            {
+                return Err(ValueError::IncorrectParameterTypeNamed("artifacts".to_owned()).into());
+            }
+        }
+        Ok(ActionMetadataInfo {
+            metadata,
+            artifacts: eval.heap().alloc(AllocList(artifacts.items)),
+        })
+    }
+}
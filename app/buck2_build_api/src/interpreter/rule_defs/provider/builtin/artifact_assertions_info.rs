@@ -0,0 +1,120 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::fmt::Debug;
+
+use allocative::Allocative;
+use buck2_artifact::artifact::artifact_type::Artifact;
+use buck2_build_api_derive::internal_provider;
+use starlark::any::ProvidesStaticType;
+use starlark::coerce::Coerce;
+use starlark::environment::GlobalsBuilder;
+use starlark::eval::Evaluator;
+use starlark::values::list::AllocList;
+use starlark::values::list::ListRef;
+use starlark::values::list_or_tuple::UnpackListOrTuple;
+use starlark::values::none::NoneOr;
+use starlark::values::Freeze;
+use starlark::values::Trace;
+use starlark::values::UnpackValue;
+use starlark::values::Value;
+use starlark::values::ValueError;
+use starlark::values::ValueLifetimeless;
+use starlark::values::ValueLike;
+use starlark::StarlarkResultExt;
+
+use crate::interpreter::rule_defs::artifact::starlark_artifact::StarlarkArtifact;
+use crate::interpreter::rule_defs::artifact::starlark_artifact_like::ValueAsArtifactLike;
+
+/// A provider that declares properties a built artifact must satisfy. The assertions are checked
+/// by `buck2 verify` after the artifact is built.
+#[internal_provider(artifact_assertions_info_creator)]
+#[derive(Clone, Debug, Trace, Coerce, Freeze, ProvidesStaticType, Allocative)]
+#[repr(C)]
+pub struct ArtifactAssertionsInfoGen<V: ValueLifetimeless> {
+    /// The artifact the assertions apply to.
+    #[provider(field_type = StarlarkArtifact)]
+    artifact: V,
+    /// Maximum size of the artifact in bytes.
+    #[provider(field_type = NoneOr<i64>)]
+    max_size_bytes: V,
+    /// Names of dynamic libraries the artifact must not depend on.
+    #[provider(field_type = Vec<String>)]
+    disallowed_dynamic_deps: V,
+    /// Names of symbols the artifact must define.
+    #[provider(field_type = Vec<String>)]
+    required_symbols: V,
+}
+
+impl<'v, V: ValueLike<'v>> ArtifactAssertionsInfoGen<V> {
+    pub fn artifact(&self) -> anyhow::Result<Artifact> {
+        ValueAsArtifactLike::unpack_value_err(self.artifact.to_value())?
+            .0
+            .get_bound_artifact()
+    }
+
+    pub fn max_size_bytes(&self) -> anyhow::Result<Option<u64>> {
+        Ok(
+            NoneOr::<i64>::unpack_value_err(self.max_size_bytes.to_value())?
+                .into_option()
+                .map(|v| v as u64),
+        )
+    }
+
+    pub fn disallowed_dynamic_deps(&self) -> Vec<&'v str> {
+        Self::strings(self.disallowed_dynamic_deps.to_value())
+    }
+
+    pub fn required_symbols(&self) -> Vec<&'v str> {
+        Self::strings(self.required_symbols.to_value())
+    }
+
+    fn strings(value: Value<'v>) -> Vec<&'v str> {
+        ListRef::from_value(value)
+            .expect("validated at construction")
+            .iter()
+            .map(|v| v.unpack_str().expect("validated at construction"))
+            .collect()
+    }
+}
+
+#[starlark_module]
+fn artifact_assertions_info_creator(globals: &mut GlobalsBuilder) {
+    #[starlark(as_type = FrozenArtifactAssertionsInfo)]
+    fn ArtifactAssertionsInfo<'v>(
+        #[starlark(require = named)] artifact: Value<'v>,
+        #[starlark(require = named, default = NoneOr::None)] max_size_bytes: NoneOr<i64>,
+        #[starlark(require = named, default = UnpackListOrTuple::default())]
+        disallowed_dynamic_deps: UnpackListOrTuple<String>,
+        #[starlark(require = named, default = UnpackListOrTuple::default())]
+        required_symbols: UnpackListOrTuple<String>,
+        eval: &mut Evaluator<'v, '_, '_>,
+    ) -> anyhow::Result<ArtifactAssertionsInfo<'v>> {
+        if ValueAsArtifactLike::unpack_value(artifact)
+            .into_anyhow_result()?
+            .is_none()
+        {
+            return Err(ValueError::IncorrectParameterTypeNamed("artifact".to_owned()).into());
+        }
+        if let NoneOr::Other(max_size_bytes) = max_size_bytes {
+            if max_size_bytes < 0 {
+                return Err(
+                    ValueError::IncorrectParameterTypeNamed("max_size_bytes".to_owned()).into(),
+                );
+            }
+        }
+        let heap = eval.heap();
+        Ok(ArtifactAssertionsInfo {
+            artifact,
+            max_size_bytes: heap.alloc(max_size_bytes),
+            disallowed_dynamic_deps: heap.alloc(AllocList(disallowed_dynamic_deps.items)),
+            required_symbols: heap.alloc(AllocList(required_symbols.items)),
+        })
+    }
+}
@@ -106,6 +106,13 @@ pub struct ExternalRunnerTestInfoGen<V: ValueLifetimeless> {
     /// command related to test execution, including listing.
     #[provider(field_type = WorkerInfo<'v>)]
     worker: V,
+
+    /// Resources a single run of this test needs, e.g. `{"cpus": 4, "memory_mb": 8192, "gpus": 1}`.
+    /// `cpus` and `memory_mb` limit how many tests run concurrently on the local host, any
+    /// other key is a device that tests can't share locally. All of them are also added to the
+    /// Remote Execution platform properties.
+    #[provider(field_type = DictType<String, u64>)]
+    resource_requirements: V,
 }
 
 // NOTE: All the methods here unwrap because we validate at freeze time.
@@ -166,6 +173,13 @@ impl FrozenExternalRunnerTestInfo {
         unpack_opt_worker(self.worker.to_value()).unwrap()
     }
 
+    pub fn resource_requirements(&self) -> IndexMap<&str, u64> {
+        unwrap_all(iter_resource_requirements(
+            self.resource_requirements.to_value(),
+        ))
+        .collect()
+    }
+
     pub fn visit_artifacts(
         &self,
         visitor: &mut dyn CommandLineArtifactVisitor,
@@ -389,6 +403,46 @@ fn iter_local_resources<'v>(
     }))
 }
 
+fn iter_resource_requirements<'v>(
+    resource_requirements: Value<'v>,
+) -> impl Iterator<Item = anyhow::Result<(&'v str, u64)>> {
+    if resource_requirements.is_none() {
+        return Either::Left(Either::Left(empty()));
+    }
+
+    let resource_requirements = match DictRef::from_value(resource_requirements) {
+        Some(resource_requirements) => resource_requirements,
+        None => {
+            return Either::Left(Either::Right(once(Err(anyhow::anyhow!(
+                "Invalid `resource_requirements`: Expected a dict, got: `{}`",
+                resource_requirements
+            )))));
+        }
+    };
+
+    let resource_requirements = resource_requirements.iter().collect::<Vec<_>>();
+
+    Either::Right(resource_requirements.into_iter().map(|(key, value)| {
+        let key = key.unpack_str().with_context(|| {
+            format!(
+                "Invalid key in `resource_requirements`: Expected a str, got: `{}`",
+                key
+            )
+        })?;
+
+        let amount = u64::unpack_value(value)
+            .into_anyhow_result()?
+            .with_context(|| {
+                format!(
+                    "Invalid value in `resource_requirements` for key `{}`: Expected a non-negative int, got: `{}`",
+                    key, value
+                )
+            })?;
+
+        Ok((key, amount))
+    }))
+}
+
 fn unpack_opt_executor<'v>(
     executor: Value<'v>,
 ) -> anyhow::Result<Option<&'v StarlarkCommandExecutorConfig>> {
@@ -442,6 +496,9 @@ where
     check_all(iter_opt_str_list(info.contacts.to_value(), "contacts"))?;
     check_all(iter_executor_overrides(info.executor_overrides.to_value()))?;
     check_all(iter_local_resources(info.local_resources.to_value()))?;
+    check_all(iter_resource_requirements(
+        info.resource_requirements.to_value(),
+    ))?;
     NoneOr::<bool>::unpack_value(info.use_project_relative_paths.to_value())
         .into_anyhow_result()?
         .context("`use_project_relative_paths` must be a bool if provided")?;
@@ -472,6 +529,7 @@ fn external_runner_test_info_creator(globals: &mut GlobalsBuilder) {
         #[starlark(default = NoneType)] executor_overrides: Value<'v>,
         #[starlark(default = NoneType)] local_resources: Value<'v>,
         #[starlark(default = NoneType)] worker: Value<'v>,
+        #[starlark(default = NoneType)] resource_requirements: Value<'v>,
     ) -> anyhow::Result<ExternalRunnerTestInfo<'v>> {
         let res = ExternalRunnerTestInfo {
             test_type: r#type,
@@ -485,6 +543,7 @@ fn external_runner_test_info_creator(globals: &mut GlobalsBuilder) {
             executor_overrides,
             local_resources,
             worker,
+            resource_requirements,
         };
         validate_external_runner_test_info(&res)?;
         Ok(res)
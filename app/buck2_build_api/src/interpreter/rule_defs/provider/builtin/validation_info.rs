@@ -0,0 +1,102 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::fmt::Debug;
+
+use allocative::Allocative;
+use buck2_artifact::artifact::artifact_type::Artifact;
+use buck2_build_api_derive::internal_provider;
+use starlark::any::ProvidesStaticType;
+use starlark::coerce::Coerce;
+use starlark::collections::SmallMap;
+use starlark::environment::GlobalsBuilder;
+use starlark::eval::Evaluator;
+use starlark::values::dict::Dict;
+use starlark::values::dict::DictRef;
+use starlark::values::type_repr::DictType;
+use starlark::values::Freeze;
+use starlark::values::Trace;
+use starlark::values::UnpackValue;
+use starlark::values::Value;
+use starlark::values::ValueLifetimeless;
+use starlark::values::ValueLike;
+use starlark::StarlarkResultExt;
+
+use crate::interpreter::rule_defs::artifact::starlark_artifact::StarlarkArtifact;
+use crate::interpreter::rule_defs::artifact::starlark_artifact_like::ValueAsArtifactLike;
+
+#[derive(Debug, buck2_error::Error)]
+enum ValidationInfoError {
+    #[error("Expected ValidationInfo.validations[`{0}`] to be an artifact, got `{1}`")]
+    #[buck2(input)]
+    NotAnArtifact(String, String),
+}
+
+/// A provider that declares validation outputs of a target.
+///
+/// Each validation is an artifact that must build successfully whenever the target is built,
+/// either directly or as a transitive dependency of a built target. Validations are not outputs
+/// of the target: they are not reported in `--show-output` and are not inputs to any action
+/// that consumes the target.
+///
+/// Fields:
+///  - validations: A mapping of validation names to the artifacts that perform them.
+#[internal_provider(validation_info_creator)]
+#[derive(Clone, Debug, Trace, Coerce, Freeze, ProvidesStaticType, Allocative)]
+#[repr(C)]
+pub struct ValidationInfoGen<V: ValueLifetimeless> {
+    #[provider(field_type = DictType<String, StarlarkArtifact>)]
+    validations: V,
+}
+
+impl<'v, V: ValueLike<'v>> ValidationInfoGen<V> {
+    pub fn for_each_validation(
+        &self,
+        processor: &mut dyn FnMut(&str, Artifact),
+    ) -> anyhow::Result<()> {
+        let validations =
+            DictRef::from_value(self.validations.to_value()).expect("validated at construction");
+        for (name, artifact) in validations.iter() {
+            let name = name.unpack_str().expect("validated at construction");
+            processor(
+                name,
+                ValueAsArtifactLike::unpack_value_err(artifact)?
+                    .0
+                    .get_bound_artifact()?,
+            );
+        }
+        Ok(())
+    }
+}
+
+#[starlark_module]
+fn validation_info_creator(globals: &mut GlobalsBuilder) {
+    #[starlark(as_type = FrozenValidationInfo)]
+    fn ValidationInfo<'v>(
+        #[starlark(require = named)] validations: SmallMap<String, Value<'v>>,
+        eval: &mut Evaluator<'v, '_, '_>,
+    ) -> anyhow::Result<ValidationInfo<'v>> {
+        let heap = eval.heap();
+        let validations = validations
+            .into_iter()
+            .map(|(name, artifact)| {
+                if ValueAsArtifactLike::unpack_value(artifact)
+                    .into_anyhow_result()?
+                    .is_none()
+                {
+                    return Err(ValidationInfoError::NotAnArtifact(name, artifact.to_repr()).into());
+                }
+                Ok((heap.alloc_str(&name).get_hashed_value(), artifact))
+            })
+            .collect::<anyhow::Result<SmallMap<Value<'v>, Value<'v>>>>()?;
+        Ok(ValidationInfo {
+            validations: heap.alloc(Dict::new(validations)),
+        })
+    }
+}
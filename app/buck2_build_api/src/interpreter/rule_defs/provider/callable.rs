@@ -142,6 +142,23 @@ fn create_callable_function_signature(
     )
 }
 
+fn user_provider_callable_documentation(
+    docs: &Option<DocString>,
+    fields: &IndexMap<String, UserProviderField, StarlarkHasherSmallPromoteBuilder>,
+) -> DocItem {
+    let field_types = fields
+        .values()
+        .map(|field| field.ty.as_ty().dupe())
+        .collect::<Vec<_>>();
+    provider_callable_documentation(
+        None,
+        docs,
+        &fields.keys().map(|x| x.as_str()).collect::<Vec<_>>(),
+        &vec![None; fields.len()],
+        &field_types,
+    )
+}
+
 #[derive(Debug, Allocative)]
 pub(crate) struct UserProviderCallableData {
     pub(crate) provider_id: Arc<ProviderId>,
@@ -402,14 +419,9 @@ impl<'v> StarlarkValue<'v> for UserProviderCallable {
     }
 
     fn documentation(&self) -> Option<DocItem> {
-        let return_types = vec![Ty::any(); self.fields.len()];
-        Some(provider_callable_documentation(
-            None,
+        Some(user_provider_callable_documentation(
             &self.docs,
-            &self.fields.keys().map(|x| x.as_str()).collect::<Vec<_>>(),
-            // TODO(nga): types.
-            &vec![None; self.fields.len()],
-            &return_types,
+            &self.fields,
         ))
     }
 
@@ -473,13 +485,9 @@ impl<'v> StarlarkValue<'v> for FrozenUserProviderCallable {
     }
 
     fn documentation(&self) -> Option<DocItem> {
-        let return_types = vec![Ty::any(); self.fields.len()];
-        Some(provider_callable_documentation(
-            None,
+        Some(user_provider_callable_documentation(
             &self.docs,
-            &self.fields.keys().map(|x| x.as_str()).collect::<Vec<_>>(),
-            &vec![None; self.fields.len()],
-            &return_types,
+            &self.fields,
         ))
     }
 
@@ -0,0 +1,157 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! A shallow JSON rendering of the providers of a target, so that `cquery --list-providers` can
+//! show the contract of a rule without dumping the (possibly huge) provider values.
+
+use serde_json::Map;
+use starlark::values::dict::DictRef;
+use starlark::values::list::ListRef;
+use starlark::values::tuple::TupleRef;
+use starlark::values::Value;
+
+use crate::interpreter::rule_defs::provider::collection::FrozenProviderCollection;
+use crate::interpreter::rule_defs::provider::ValueAsProviderLike;
+
+/// Maximum number of items of a list or dict rendered.
+const MAX_ITEMS: usize = 20;
+/// Maximum length of the `repr` of a value which has no JSON rendering.
+const MAX_REPR_LEN: usize = 200;
+
+impl FrozenProviderCollection {
+    /// The providers of this collection by name, each with a shallow rendering of its fields for
+    /// which `include_field(provider, field)` is true.
+    pub fn summary(
+        &self,
+        include_field: impl Fn(&str, &str) -> bool,
+    ) -> Map<String, serde_json::Value> {
+        self.providers
+            .iter()
+            .map(|(id, provider)| {
+                let fields = match ValueAsProviderLike::unpack(provider.to_value()) {
+                    Some(provider) => provider
+                        .0
+                        .items()
+                        .into_iter()
+                        .filter(|(field, _)| include_field(id.name(), field))
+                        .map(|(field, value)| (field.to_owned(), shallow_json(value, 1)))
+                        .collect(),
+                    None => Map::new(),
+                };
+                (id.name().to_owned(), serde_json::Value::Object(fields))
+            })
+            .collect()
+    }
+}
+
+/// Render `value` as JSON, rendering the items of lists and dicts up to `depth` levels deep.
+/// Deeper containers are rendered as their size, and other values (e.g. artifacts) as their
+/// truncated `repr`.
+fn shallow_json(value: Value, depth: usize) -> serde_json::Value {
+    if value.is_none() {
+        return serde_json::Value::Null;
+    }
+    if let Some(b) = value.unpack_bool() {
+        return serde_json::Value::Bool(b);
+    }
+    if let Some(i) = value.unpack_i32() {
+        return serde_json::Value::from(i);
+    }
+    if let Some(s) = value.unpack_str() {
+        return serde_json::Value::from(s);
+    }
+
+    let items = ListRef::from_value(value)
+        .map(|l| l.content())
+        .or_else(|| TupleRef::from_value(value).map(|t| t.content()));
+    if let Some(items) = items {
+        if depth == 0 {
+            return serde_json::Value::from(format!("<{} of {}>", value.get_type(), items.len()));
+        }
+        let mut json: Vec<_> = items
+            .iter()
+            .take(MAX_ITEMS)
+            .map(|v| shallow_json(*v, depth - 1))
+            .collect();
+        if items.len() > MAX_ITEMS {
+            json.push(serde_json::Value::from(format!(
+                "... and {} more",
+                items.len() - MAX_ITEMS
+            )));
+        }
+        return serde_json::Value::Array(json);
+    }
+
+    if let Some(dict) = DictRef::from_value(value) {
+        if depth == 0 {
+            return serde_json::Value::from(format!("<dict of {}>", dict.len()));
+        }
+        let mut json: Map<_, _> = dict
+            .iter()
+            .take(MAX_ITEMS)
+            .map(|(k, v)| {
+                let key = match k.unpack_str() {
+                    Some(k) => k.to_owned(),
+                    None => k.to_repr(),
+                };
+                (key, shallow_json(v, depth - 1))
+            })
+            .collect();
+        if dict.len() > MAX_ITEMS {
+            json.insert(
+                "...".to_owned(),
+                serde_json::Value::from(format!("and {} more", dict.len() - MAX_ITEMS)),
+            );
+        }
+        return serde_json::Value::Object(json);
+    }
+
+    let mut repr = value.to_repr();
+    if repr.len() > MAX_REPR_LEN {
+        let mut end = MAX_REPR_LEN;
+        while !repr.is_char_boundary(end) {
+            end -= 1;
+        }
+        repr.truncate(end);
+        repr.push_str("...");
+    }
+    serde_json::Value::from(repr)
+}
+
+#[cfg(test)]
+mod tests {
+    use starlark::values::Heap;
+
+    use super::*;
+
+    #[test]
+    fn test_shallow_json() {
+        let heap = Heap::new();
+        let nested = heap.alloc(vec![
+            heap.alloc(vec![1, 2, 3]),
+            heap.alloc("a"),
+            Value::new_none(),
+        ]);
+        assert_eq!(
+            serde_json::json!([["<list of 3>", "a", null]]),
+            shallow_json(heap.alloc(vec![nested]), 2)
+        );
+        assert_eq!(
+            serde_json::json!(["<list of 3>", "a", null]),
+            shallow_json(nested, 1)
+        );
+        assert_eq!(serde_json::json!("<list of 3>"), shallow_json(nested, 0));
+
+        let long = heap.alloc((0..30).collect::<Vec<_>>());
+        let json = shallow_json(long, 1);
+        let json = json.as_array().unwrap();
+        assert_eq!(MAX_ITEMS + 1, json.len());
+        assert_eq!(serde_json::json!("... and 10 more"), json[MAX_ITEMS]);
+    }
+}
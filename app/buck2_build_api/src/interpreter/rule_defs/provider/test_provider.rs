@@ -33,6 +33,7 @@ pub trait TestProvider {
         target: ConfiguredTarget,
         executor: Arc<dyn TestExecutor + 'exec>,
         working_dir_cell: CellName,
+        extra_args: Vec<String>,
     ) -> BoxFuture<'exec, anyhow::Result<()>>;
 }
 
@@ -50,6 +51,7 @@ impl TestProvider for FrozenExternalRunnerTestInfo {
         target: ConfiguredTarget,
         executor: Arc<dyn TestExecutor + 'exec>,
         working_dir_cell: CellName,
+        extra_args: Vec<String>,
     ) -> BoxFuture<'exec, anyhow::Result<()>> {
         let mut handle_index = 0;
 
@@ -66,6 +68,11 @@ impl TestProvider for FrozenExternalRunnerTestInfo {
                     handle
                 }
             })
+            .chain(
+                extra_args
+                    .into_iter()
+                    .map(ExternalRunnerSpecValue::Verbatim),
+            )
             .collect();
 
         let env = self
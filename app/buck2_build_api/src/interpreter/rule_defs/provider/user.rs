@@ -42,9 +42,10 @@ use crate::interpreter::rule_defs::provider::callable::UserProviderCallableData;
 use crate::interpreter::rule_defs::provider::ProviderLike;
 
 #[derive(Debug, buck2_error::Error)]
+#[buck2(input)]
 enum UserProviderError {
-    #[error("Value for parameter `{0}` mismatches type `{1}`: `{2}`")]
-    MismatchedType(String, Ty, String),
+    #[error("Value for parameter `{0}` of `{1}` mismatches type `{2}`: `{3}` (type `{4}`)")]
+    MismatchedType(String, String, Ty, String, &'static str),
     #[error("Required parameter `{0}` is missing")]
     MissingParameter(String),
 }
@@ -187,8 +188,10 @@ pub(crate) fn user_provider_creator<'v>(
                 if !field.ty.matches(value) {
                     return Err(UserProviderError::MismatchedType(
                         name.to_owned(),
+                        callable.provider_id.name.clone(),
                         field.ty.as_ty().dupe(),
                         value.to_repr(),
+                        value.get_type(),
                     )
                     .into());
                 }
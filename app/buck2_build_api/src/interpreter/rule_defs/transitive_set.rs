@@ -8,6 +8,7 @@
  */
 
 pub mod globals;
+pub mod stats;
 mod transitive_set;
 mod transitive_set_args_projection;
 pub mod transitive_set_definition;
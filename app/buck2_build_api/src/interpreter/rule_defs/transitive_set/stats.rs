@@ -0,0 +1,166 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Size accounting for transitive sets.
+//!
+//! A set is a DAG, so a set whose children share their own children is cheap to build but
+//! expensive to traverse without deduplication. Comparing the number of distinct sets with the
+//! number of sets visited by a naive traversal shows the definitions used in a quadratic way.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use serde::Serialize;
+use starlark::values::dict::DictRef;
+use starlark::values::list::ListRef;
+use starlark::values::tuple::TupleRef;
+use starlark::values::Value;
+use starlark::values::ValueIdentity;
+
+use crate::interpreter::rule_defs::provider::collection::FrozenProviderCollection;
+use crate::interpreter::rule_defs::provider::ValueAsProviderLike;
+use crate::interpreter::rule_defs::transitive_set::transitive_set_definition::TransitiveSetDefinitionLike;
+use crate::interpreter::rule_defs::transitive_set::TransitiveSet;
+
+/// Sizes of the sets of one transitive set definition.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct TransitiveSetDefinitionStats {
+    pub definition: String,
+    /// Sets reachable from a value, like the providers of a target.
+    pub roots: u64,
+    /// Distinct sets reachable from the roots.
+    pub sets: u64,
+    /// Distinct sets holding a value.
+    pub values: u64,
+    /// Edges from sets to their children.
+    pub edges: u64,
+    /// Sets visited when traversing from the roots without deduplication.
+    pub expanded: f64,
+    /// How many times on average each set is reached by a traversal without deduplication.
+    pub duplication: f64,
+    /// Projection values computed for the sets, one per projection per set holding a value.
+    pub projection_values: u64,
+}
+
+#[derive(Default)]
+pub struct TransitiveSetStatsCollector<'v> {
+    seen_values: HashSet<ValueIdentity<'v>>,
+    /// Size of the naive traversal of each visited set.
+    expanded: HashMap<ValueIdentity<'v>, f64>,
+    stats: BTreeMap<String, TransitiveSetDefinitionStats>,
+}
+
+impl<'v> TransitiveSetStatsCollector<'v> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Account for the sets reachable from the providers of a target.
+    pub fn add_providers(&mut self, providers: &FrozenProviderCollection) -> anyhow::Result<()> {
+        for provider in providers.providers.values() {
+            self.add_value(provider.to_value())?;
+        }
+        Ok(())
+    }
+
+    /// Account for the sets reachable from `value`, looking into providers, lists, tuples
+    /// and dicts.
+    pub fn add_value(&mut self, value: Value<'v>) -> anyhow::Result<()> {
+        if !self.seen_values.insert(value.identity()) {
+            return Ok(());
+        }
+        if let Some(set) = TransitiveSet::from_value(value) {
+            let expanded = self.add_set(value)?;
+            if let Some(stats) = self.stats.get_mut(&set.definition.to_string()) {
+                stats.roots += 1;
+                stats.expanded += expanded;
+            }
+        } else if let Some(provider) = ValueAsProviderLike::unpack(value) {
+            for (_, v) in provider.0.items() {
+                self.add_value(v)?;
+            }
+        } else if let Some(list) = ListRef::from_value(value) {
+            for v in list.iter() {
+                self.add_value(v)?;
+            }
+        } else if let Some(tuple) = TupleRef::from_value(value) {
+            for v in tuple.iter() {
+                self.add_value(v)?;
+            }
+        } else if let Some(dict) = DictRef::from_value(value) {
+            for (k, v) in dict.iter() {
+                self.add_value(k)?;
+                self.add_value(v)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the number of sets visited by a traversal of `root` without deduplication.
+    ///
+    /// Sets can be arbitrarily deep, so this walks them with an explicit stack, computing the
+    /// size of a set once the sizes of all its children are known.
+    fn add_set(&mut self, root: Value<'v>) -> anyhow::Result<f64> {
+        let mut stack = vec![(root, false)];
+        while let Some((value, children_visited)) = stack.pop() {
+            if self.expanded.contains_key(&value.identity()) {
+                continue;
+            }
+            let set = TransitiveSet::from_value(value)
+                .ok_or_else(|| anyhow::anyhow!("Children of a transitive set must be sets"))?;
+
+            if !children_visited {
+                stack.push((value, true));
+                for child in set.children.iter() {
+                    if !self.expanded.contains_key(&child.identity()) {
+                        stack.push((*child, false));
+                    }
+                }
+                continue;
+            }
+
+            let definition = set.definition.to_string();
+            let projections = set.definition.as_ref().operations().projections.len() as u64;
+            let stats = self.stats.entry(definition.clone()).or_insert_with(|| {
+                TransitiveSetDefinitionStats {
+                    definition,
+                    ..Default::default()
+                }
+            });
+            stats.sets += 1;
+            stats.edges += set.children.len() as u64;
+            if set.node.is_some() {
+                stats.values += 1;
+                stats.projection_values += projections;
+            }
+
+            let mut expanded = 1.0;
+            for child in set.children.iter() {
+                expanded += self.expanded[&child.identity()];
+            }
+            self.expanded.insert(value.identity(), expanded);
+        }
+        Ok(self.expanded[&root.identity()])
+    }
+
+    /// Stats per definition, the most duplicated first.
+    pub fn finish(self) -> Vec<TransitiveSetDefinitionStats> {
+        let mut stats: Vec<_> = self
+            .stats
+            .into_values()
+            .map(|mut s| {
+                s.duplication = s.expanded / s.sets as f64;
+                s
+            })
+            .collect();
+        stats.sort_by(|a, b| b.expanded.total_cmp(&a.expanded));
+        stats
+    }
+}
@@ -38,6 +38,7 @@ pub mod dynamic;
 pub mod interpreter;
 pub mod keep_going;
 pub mod query;
+pub mod sent_install_files;
 pub mod spawner;
 pub mod transition;
 
@@ -7,9 +7,12 @@
  * of this source tree.
  */
 
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use buck2_common::global_cfg_options::GlobalCfgOptions;
 use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_node::configured_universe::CqueryUniverse;
 use buck2_node::nodes::configured::ConfiguredTargetNode;
 use buck2_node::nodes::unconfigured::TargetNode;
 use buck2_query::query::syntax::simple::eval::values::QueryEvaluationResult;
@@ -38,6 +41,19 @@ pub trait QueryFrontend: Send + Sync + 'static {
         target_universe: Option<&[String]>,
     ) -> anyhow::Result<QueryEvaluationResult<ConfiguredTargetNode>>;
 
+    /// Evaluate a cquery against the configured graph retained from an earlier build.
+    /// Literals are resolved and nodes are taken from the snapshot rather than recomputed,
+    /// so the result reflects the graph at the time it was built.
+    async fn eval_cquery_in_snapshot(
+        &self,
+        ctx: &mut DiceComputations<'_>,
+        working_dir: &ProjectRelativePath,
+        query: &str,
+        query_args: &[String],
+        global_cfg_options: GlobalCfgOptions,
+        snapshot: Arc<CqueryUniverse>,
+    ) -> anyhow::Result<QueryEvaluationResult<ConfiguredTargetNode>>;
+
     async fn eval_aquery(
         &self,
         ctx: &mut DiceComputations<'_>,
@@ -0,0 +1,202 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! The files last sent to installers which support file diffs, retained by the daemon so that
+//! `buck2 install` only sends them what changed since.
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use allocative::Allocative;
+use buck2_core::provider::label::ConfiguredProvidersLabel;
+use buck2_execute::artifact_value::ArtifactValue;
+use dice::UserComputationData;
+use dupe::Dupe;
+use parking_lot::Mutex;
+
+/// How many installers files are retained for. The least recently used are forgotten first.
+const MAX_INSTALLERS: usize = 16;
+
+/// Files keyed by install id and name.
+type InstallerFiles = HashMap<(String, String), ArtifactValue>;
+
+/// Owned by the daemon and shared by its install commands.
+#[derive(Default, Allocative)]
+pub struct SentInstallFiles {
+    /// Files keyed by installer, most recently used last.
+    installers: Mutex<VecDeque<(String, InstallerFiles)>>,
+}
+
+impl SentInstallFiles {
+    /// The value of the file last sent to `installer`, if any.
+    pub fn get(
+        &self,
+        installer: &ConfiguredProvidersLabel,
+        install_id: &str,
+        name: &str,
+    ) -> Option<ArtifactValue> {
+        let installer = installer.to_string();
+        self.installers
+            .lock()
+            .iter()
+            .find(|(label, _)| *label == installer)
+            .and_then(|(_, files)| files.get(&(install_id.to_owned(), name.to_owned())))
+            .map(|value| value.dupe())
+    }
+
+    /// Record that `value` was sent to `installer`, or forget about the file if sending it
+    /// failed.
+    pub fn record(
+        &self,
+        installer: &ConfiguredProvidersLabel,
+        install_id: &str,
+        name: &str,
+        value: Option<ArtifactValue>,
+    ) {
+        let mut installers = self.installers.lock();
+        let mut files = take_installer(&mut installers, installer);
+        let key = (install_id.to_owned(), name.to_owned());
+        match value {
+            Some(value) => {
+                files.insert(key, value);
+            }
+            None => {
+                files.remove(&key);
+            }
+        }
+        installers.push_back((installer.to_string(), files));
+        while installers.len() > MAX_INSTALLERS {
+            installers.pop_front();
+        }
+    }
+
+    /// Forget about the files sent to `installer` for installs other than `install_ids`, which
+    /// replaced them.
+    pub fn retain_installs(&self, installer: &ConfiguredProvidersLabel, install_ids: &[String]) {
+        let installer = installer.to_string();
+        if let Some((_, files)) = self
+            .installers
+            .lock()
+            .iter_mut()
+            .find(|(label, _)| *label == installer)
+        {
+            files.retain(|(install_id, _), _| install_ids.contains(install_id));
+        }
+    }
+}
+
+fn take_installer(
+    installers: &mut VecDeque<(String, InstallerFiles)>,
+    installer: &ConfiguredProvidersLabel,
+) -> InstallerFiles {
+    let installer = installer.to_string();
+    match installers.iter().position(|(label, _)| *label == installer) {
+        Some(index) => installers
+            .remove(index)
+            .map(|(_, files)| files)
+            .unwrap_or_default(),
+        None => InstallerFiles::new(),
+    }
+}
+
+pub trait HasSentInstallFiles {
+    fn set_sent_install_files(&mut self, files: Arc<SentInstallFiles>);
+
+    fn get_sent_install_files(&self) -> Arc<SentInstallFiles>;
+}
+
+impl HasSentInstallFiles for UserComputationData {
+    fn set_sent_install_files(&mut self, files: Arc<SentInstallFiles>) {
+        self.data.set(files);
+    }
+
+    fn get_sent_install_files(&self) -> Arc<SentInstallFiles> {
+        self.data
+            .get::<Arc<SentInstallFiles>>()
+            .expect("Sent install files should be set")
+            .dupe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_common::file_ops::FileMetadata;
+    use buck2_common::file_ops::TrackedFileDigest;
+    use buck2_core::configuration::data::ConfigurationData;
+    use buck2_core::package::PackageLabel;
+    use buck2_core::provider::label::ConfiguredProvidersLabel;
+    use buck2_core::provider::label::ProvidersLabel;
+    use buck2_core::target::label::label::TargetLabel;
+    use buck2_core::target::name::TargetNameRef;
+    use buck2_execute::artifact_value::ArtifactValue;
+    use buck2_execute::digest_config::DigestConfig;
+
+    use super::SentInstallFiles;
+    use super::MAX_INSTALLERS;
+
+    fn installer(name: &str) -> ConfiguredProvidersLabel {
+        ProvidersLabel::default_for(TargetLabel::new(
+            PackageLabel::testing_parse("root//pkg"),
+            TargetNameRef::unchecked_new(name),
+        ))
+        .configure(ConfigurationData::testing_new())
+    }
+
+    fn value(content: &str) -> ArtifactValue {
+        ArtifactValue::file(FileMetadata {
+            digest: TrackedFileDigest::from_content(
+                content.as_bytes(),
+                DigestConfig::testing_default().cas_digest_config(),
+            ),
+            is_executable: false,
+        })
+    }
+
+    #[test]
+    fn test_record() {
+        let files = SentInstallFiles::default();
+        let a = installer("a");
+        assert_eq!(None, files.get(&a, "id", "apk"));
+
+        files.record(&a, "id", "apk", Some(value("1")));
+        assert_eq!(Some(value("1")), files.get(&a, "id", "apk"));
+        assert_eq!(None, files.get(&a, "other", "apk"));
+        assert_eq!(None, files.get(&installer("b"), "id", "apk"));
+
+        // Sending the file failed.
+        files.record(&a, "id", "apk", None);
+        assert_eq!(None, files.get(&a, "id", "apk"));
+    }
+
+    #[test]
+    fn test_retain_installs() {
+        let files = SentInstallFiles::default();
+        let a = installer("a");
+        files.record(&a, "x", "apk", Some(value("1")));
+        files.record(&a, "y", "apk", Some(value("2")));
+        files.retain_installs(&a, &["y".to_owned()]);
+        assert_eq!(None, files.get(&a, "x", "apk"));
+        assert_eq!(Some(value("2")), files.get(&a, "y", "apk"));
+    }
+
+    #[test]
+    fn test_least_recently_used_installer_is_evicted() {
+        let files = SentInstallFiles::default();
+        for i in 0..MAX_INSTALLERS {
+            files.record(&installer(&i.to_string()), "id", "apk", Some(value("1")));
+        }
+        // Installer 0 is used again, so installer 1 is the least recently used.
+        files.record(&installer("0"), "id", "apk", Some(value("2")));
+        files.record(&installer("new"), "id", "apk", Some(value("1")));
+        assert_eq!(Some(value("2")), files.get(&installer("0"), "id", "apk"));
+        assert_eq!(None, files.get(&installer("1"), "id", "apk"));
+        assert_eq!(Some(value("1")), files.get(&installer("new"), "id", "apk"));
+    }
+}
@@ -7,6 +7,8 @@
  * of this source tree.
  */
 
+mod action_metadata_info;
+mod artifact_assertions_info;
 mod configuration_info;
 mod default_info;
 mod dependency;
@@ -15,4 +17,5 @@ mod install_info;
 mod local_resource_info;
 mod run_info;
 mod tests;
+mod validation_info;
 mod worker_info;
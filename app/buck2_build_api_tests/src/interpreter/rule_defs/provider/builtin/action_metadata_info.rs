@@ -0,0 +1,63 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use buck2_build_api::interpreter::rule_defs::register_rule_defs;
+use buck2_interpreter_for_build::interpreter::testing::expect_error;
+use buck2_interpreter_for_build::interpreter::testing::Tester;
+use indoc::indoc;
+
+use crate::interpreter::rule_defs::artifact::testing::artifactory;
+
+fn new_tester() -> Tester {
+    let mut tester = Tester::new().unwrap();
+    tester.additional_globals(register_rule_defs);
+    tester.additional_globals(artifactory);
+    tester
+}
+
+#[test]
+fn test_fields() -> anyhow::Result<()> {
+    let mut tester = new_tester();
+    tester.run_starlark_bzl_test(indoc!(
+        r#"
+        def test():
+            metadata = source_artifact("foo", "metadata.json")
+            a = source_artifact("foo", "a")
+            info = ActionMetadataInfo(metadata = metadata, artifacts = [a])
+            assert_eq(metadata, info.metadata)
+            assert_eq([a], info.artifacts)
+            assert_eq([], ActionMetadataInfo(metadata = metadata).artifacts)
+        "#
+    ))?;
+    Ok(())
+}
+
+#[test]
+fn test_metadata_must_be_artifact() {
+    let mut tester = new_tester();
+    let test = indoc!(
+        r#"
+        def test():
+            ActionMetadataInfo(metadata = "foo")
+        "#
+    );
+    expect_error(tester.run_starlark_bzl_test(test), test, "`metadata`");
+}
+
+#[test]
+fn test_artifacts_must_be_artifacts() {
+    let mut tester = new_tester();
+    let test = indoc!(
+        r#"
+        def test():
+            ActionMetadataInfo(metadata = source_artifact("foo", "metadata.json"), artifacts = ["bar"])
+        "#
+    );
+    expect_error(tester.run_starlark_bzl_test(test), test, "`artifacts`");
+}
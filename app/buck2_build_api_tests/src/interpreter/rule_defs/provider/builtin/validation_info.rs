@@ -0,0 +1,48 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use buck2_build_api::interpreter::rule_defs::register_rule_defs;
+use buck2_interpreter_for_build::interpreter::testing::expect_error;
+use buck2_interpreter_for_build::interpreter::testing::Tester;
+use indoc::indoc;
+
+fn new_tester() -> Tester {
+    let mut tester = Tester::new().unwrap();
+    tester.additional_globals(register_rule_defs);
+    tester
+}
+
+#[test]
+fn test_construction() -> anyhow::Result<()> {
+    let mut tester = new_tester();
+    let test = indoc!(
+        r#"
+        def test():
+            ValidationInfo(validations={})
+        "#
+    );
+    tester.run_starlark_bzl_test(test)?;
+    Ok(())
+}
+
+#[test]
+fn test_validations_must_be_artifacts() {
+    let mut tester = new_tester();
+    let test = indoc!(
+        r#"
+        def test():
+            ValidationInfo(validations={"lint": "not an artifact"})
+        "#
+    );
+    expect_error(
+        tester.run_starlark_bzl_test(test),
+        test,
+        "Expected ValidationInfo.validations[`lint`] to be an artifact",
+    );
+}
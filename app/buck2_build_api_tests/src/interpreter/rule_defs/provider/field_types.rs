@@ -78,7 +78,7 @@ def p():
 def test():
     p()(x = "")
 "#,
-        "mismatches type",
+        "Value for parameter `x` of `P` mismatches type `int`: `\"\"` (type `string`)",
     );
 }
 
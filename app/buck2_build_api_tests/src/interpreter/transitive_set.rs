@@ -7,5 +7,6 @@
  * of this source tree.
  */
 
+mod stats;
 pub(crate) mod testing;
 mod tests;
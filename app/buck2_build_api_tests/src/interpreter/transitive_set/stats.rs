@@ -0,0 +1,74 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use buck2_build_api::interpreter::rule_defs::transitive_set::stats::TransitiveSetDefinitionStats;
+use buck2_build_api::interpreter::rule_defs::transitive_set::stats::TransitiveSetStatsCollector;
+use indoc::indoc;
+
+use crate::interpreter::transitive_set::testing::new_transitive_set;
+
+fn stats(code: &str) -> anyhow::Result<Vec<TransitiveSetDefinitionStats>> {
+    let set = new_transitive_set(code)?;
+    let mut collector = TransitiveSetStatsCollector::new();
+    collector.add_value(set.to_value())?;
+    Ok(collector.finish())
+}
+
+#[test]
+fn test_stats_diamond() -> anyhow::Result<()> {
+    let stats = stats(indoc!(
+        r#"
+        FooSet = transitive_set()
+
+        def make():
+            a = make_tset(FooSet, value = "a")
+            b = make_tset(FooSet, value = "b", children = [a])
+            c = make_tset(FooSet, children = [a])
+            return make_tset(FooSet, value = "d", children = [b, c])
+        "#
+    ))?;
+
+    assert_eq!(stats.len(), 1);
+    let stats = &stats[0];
+    assert_eq!(stats.roots, 1);
+    assert_eq!(stats.sets, 4);
+    assert_eq!(stats.values, 3);
+    assert_eq!(stats.edges, 4);
+    // d, b, a, c, a.
+    assert_eq!(stats.expanded, 5.0);
+    assert_eq!(stats.duplication, 1.25);
+    assert_eq!(stats.projection_values, 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_stats_deep_chain() -> anyhow::Result<()> {
+    let stats = stats(indoc!(
+        r#"
+        FooSet = transitive_set()
+
+        def make():
+            s = make_tset(FooSet, value = 0)
+            for i in range(1, 1000):
+                s = make_tset(FooSet, value = i, children = [s])
+            return s
+        "#
+    ))?;
+
+    assert_eq!(stats.len(), 1);
+    let stats = &stats[0];
+    assert_eq!(stats.sets, 1000);
+    assert_eq!(stats.values, 1000);
+    assert_eq!(stats.edges, 999);
+    assert_eq!(stats.expanded, 1000.0);
+    assert_eq!(stats.duplication, 1.0);
+
+    Ok(())
+}
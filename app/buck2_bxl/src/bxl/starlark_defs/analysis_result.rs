@@ -12,6 +12,7 @@ use std::fmt;
 use allocative::Allocative;
 use buck2_build_api::analysis::AnalysisResult;
 use buck2_build_api::interpreter::rule_defs::provider::dependency::Dependency;
+use buck2_build_api::interpreter::rule_defs::transitive_set::stats::TransitiveSetStatsCollector;
 use buck2_core::provider::label::ConfiguredProvidersLabel;
 use starlark::any::ProvidesStaticType;
 use starlark::environment::Methods;
@@ -97,6 +98,24 @@ fn starlark_analysis_result_methods(builder: &mut MethodsBuilder) {
         }
     }
 
+    /// Sizes of the transitive sets reachable from the providers, one dict per transitive set
+    /// definition, the most duplicated first. A `duplication` much larger than one means that
+    /// traversing the sets without deduplication revisits the same sets many times, which is
+    /// usually a sign of sets built in a quadratic way.
+    ///
+    /// Sample usage:
+    /// ```text
+    /// def _impl_tset_stats(ctx):
+    ///     for stats in ctx.analysis("//:bin").tset_stats():
+    ///         ctx.output.print(stats["definition"], stats["sets"], stats["duplication"])
+    /// ```
+    fn tset_stats<'v>(this: &'v StarlarkAnalysisResult) -> anyhow::Result<serde_json::Value> {
+        let providers = this.analysis.lookup_inner(&this.label)?;
+        let mut collector = TransitiveSetStatsCollector::new();
+        collector.add_providers(providers.provider_collection())?;
+        Ok(serde_json::to_value(collector.finish())?)
+    }
+
     /// Converts the analysis result into a `dependency`. Currently, you can only get a `dependency` without any
     /// transitions. This means that you cannot create an exec dep or toolchain from an analysis result.
 
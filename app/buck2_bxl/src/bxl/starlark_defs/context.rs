@@ -28,6 +28,7 @@ use buck2_artifact::artifact::build_artifact::BuildArtifact;
 use buck2_build_api::actions::artifact::get_artifact_fs::GetArtifactFs;
 use buck2_build_api::analysis::registry::AnalysisRegistry;
 use buck2_build_api::artifact_groups::ArtifactGroup;
+use buck2_build_api::build::aspects::parse_aspects;
 use buck2_build_api::deferred::types::DeferredCtx;
 use buck2_build_api::interpreter::rule_defs::context::AnalysisActions;
 use buck2_cli_proto::build_request::Materializations;
@@ -88,6 +89,7 @@ use starlark::environment::MethodsStatic;
 use starlark::environment::Module;
 use starlark::eval::Evaluator;
 use starlark::starlark_module;
+use starlark::values::list_or_tuple::UnpackListOrTuple;
 use starlark::values::none::NoneOr;
 use starlark::values::none::NoneType;
 use starlark::values::starlark_value;
@@ -1237,6 +1239,10 @@ fn bxl_context_methods(builder: &mut MethodsBuilder) {
     /// This returns a dict keyed by sub target labels mapped to `bxl_build_result`s if the
     /// given `labels` argument is list-like.
     ///
+    /// `aspects`, given as `cell//path/to/file.bzl:rule_name` (relative to the root of the
+    /// cell of the bxl script), are applied to each of the `labels` and to their transitive deps,
+    /// like with `buck2 build --aspect`, and their default outputs are built too.
+    ///
     /// This function is not available on the `bxl_ctx` when called from `dynamic_output`.
     fn build<'v>(
         this: &'v BxlContext<'v>,
@@ -1245,6 +1251,8 @@ fn bxl_context_methods(builder: &mut MethodsBuilder) {
         #[starlark(default = ValueAsStarlarkTargetLabel::NONE)]
         target_platform: ValueAsStarlarkTargetLabel<'v>,
         #[starlark(require = named, default = "default")] materializations: &str,
+        #[starlark(require = named, default = UnpackListOrTuple::default())]
+        aspects: UnpackListOrTuple<String>,
         eval: &mut Evaluator<'v, '_, '_>,
     ) -> anyhow::Result<
         SmallMap<
@@ -1259,11 +1267,13 @@ fn bxl_context_methods(builder: &mut MethodsBuilder) {
             .unpack_root()
             .context(BxlContextDynamicError::Unsupported("build".to_owned()))?
             .materializations;
+        let aspects = parse_aspects(&this.working_dir()?, &aspects.items, this.cell_resolver())?;
         build::build(
             this,
             materializations,
             labels,
             target_platform,
+            aspects,
             Materializations::from_str_name(&materialization_setting.to_uppercase()).ok_or_else(
                 || {
                     anyhow::anyhow!(
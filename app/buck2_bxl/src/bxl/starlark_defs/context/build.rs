@@ -27,6 +27,7 @@ use buck2_cli_proto::build_request::Materializations;
 use buck2_common::global_cfg_options::GlobalCfgOptions;
 use buck2_core::provider::label::ConfiguredProvidersLabel;
 use buck2_interpreter::types::configured_providers_label::StarlarkConfiguredProvidersLabel;
+use buck2_node::rule_type::StarlarkRuleType;
 use dashmap::DashMap;
 use derive_more::Display;
 use dupe::Dupe;
@@ -182,6 +183,7 @@ pub(crate) fn build<'v>(
     materializations_map: &Arc<DashMap<BuildArtifact, ()>>,
     spec: ConfiguredProvidersExprArg<'v>,
     target_platform: ValueAsStarlarkTargetLabel<'v>,
+    aspects: Vec<Arc<StarlarkRuleType>>,
     materializations: Materializations,
     eval: &Evaluator<'v, '_, '_>,
 ) -> anyhow::Result<
@@ -216,6 +218,13 @@ pub(crate) fn build<'v>(
                 .await?;
 
                 let materializations = &materializations;
+                let providers_to_build = &ProvidersToBuild {
+                    default: true,
+                    default_other: true,
+                    run: true,
+                    tests: true,
+                    aspects,
+                }; // TODO support skipping/configuring?
                 let per_spec_results: Vec<Vec<ConfiguredBuildEvent>> = dice
                     .compute_join(build_spec.labels().unique(), |ctx, target| {
                         async move {
@@ -226,12 +235,7 @@ pub(crate) fn build<'v>(
                                     &ctx,
                                     materializations,
                                     target,
-                                    &ProvidersToBuild {
-                                        default: true,
-                                        default_other: true,
-                                        run: true,
-                                        tests: true,
-                                    }, // TODO support skipping/configuring?
+                                    providers_to_build,
                                     BuildConfiguredLabelOptions {
                                         skippable: false,
                                         want_configured_graph_size: false,
@@ -244,6 +244,8 @@ async fn bxl(
                 .map(|(k, v)| (k.to_owned(), Some(v.to_owned())))
                 .collect::<BTreeMap<_, _>>(),
             &BTreeMap::default(),
+            &BTreeMap::default(),
+            None,
         )?
     } else {
         None
@@ -33,15 +33,16 @@ fn main() -> io::Result<()> {
         .setup_protoc()
         .type_attribute(".", "#[derive(::serde::Serialize, ::serde::Deserialize)] #[serde(rename_all = \"snake_case\")]")
         .type_attribute(".", "#[derive(::allocative::Allocative)]")
-        .field_attribute("start_time", "#[serde(with = \"serialize_timestamp\")]")
-        .field_attribute("timeout", "#[serde(rename = \"timeout_us\", with = \"buck2_data::serialize_duration_as_micros\")]")
-        .field_attribute("uptime", "#[serde(rename = \"uptime_us\", with = \"buck2_data::serialize_duration_as_micros\")]")
-        .field_attribute("delay", "#[serde(rename = \"delay_us\", with = \"buck2_data::serialize_duration_as_micros\")]")
-        .field_attribute("ProfileResponse.elapsed", "#[serde(rename = \"elapsed_us\", with = \"buck2_data::serialize_duration_as_micros\")]")
+        .type_attribute(".", "#[derive(::schemars::JsonSchema)]")
+        .field_attribute("start_time", "#[serde(with = \"serialize_timestamp\")] #[schemars(with = \"Option<(i64, i32)>\")]")
+        .field_attribute("timeout", "#[serde(rename = \"timeout_us\", with = \"buck2_data::serialize_duration_as_micros\")] #[schemars(with = \"Option<i64>\")]")
+        .field_attribute("uptime", "#[serde(rename = \"uptime_us\", with = \"buck2_data::serialize_duration_as_micros\")] #[schemars(with = \"Option<i64>\")]")
+        .field_attribute("delay", "#[serde(rename = \"delay_us\", with = \"buck2_data::serialize_duration_as_micros\")] #[schemars(with = \"Option<i64>\")]")
+        .field_attribute("ProfileResponse.elapsed", "#[serde(rename = \"elapsed_us\", with = \"buck2_data::serialize_duration_as_micros\")] #[schemars(with = \"Option<i64>\")]")
         .boxed("CommandProgress.progress.event")
         .boxed("CommandProgress.progress.result")
         .boxed("CommandProgress.progress.partial_result")
-        .field_attribute("expires_at", "#[serde(with = \"serialize_timestamp\")]")
+        .field_attribute("expires_at", "#[serde(with = \"serialize_timestamp\")] #[schemars(with = \"Option<(i64, i32)>\")]")
         .extern_path(".buck.data", "::buck2_data")
         .extern_path(".buck.subscription", "::buck2_subscription_proto")
         .compile(proto_files, &[".", &data_include, &subscription_include])
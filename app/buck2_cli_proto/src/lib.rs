@@ -270,6 +270,16 @@ result_convert!(SubscriptionCommandResponse);
 result_convert!(TraceIoResponse);
 result_convert!(NewGenericResponseMessage);
 
+/// The result of a command a client attached to, which can be of any command's type.
+pub struct AttachResponse(pub command_result::Result);
+
+impl TryFrom<command_result::Result> for AttachResponse {
+    type Error = command_result::Result;
+    fn try_from(r: command_result::Result) -> Result<Self, Self::Error> {
+        Ok(Self(r))
+    }
+}
+
 partial_result_convert!(StdoutBytes);
 partial_result_convert!(LspMessage);
 partial_result_convert!(SubscriptionResponseWrapper);
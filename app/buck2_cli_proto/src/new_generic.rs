@@ -19,6 +19,11 @@ pub enum NewGenericRequest {
     DebugEval(DebugEvalRequest),
     Explain(ExplainRequest),
     ExpandExternalCell(ExpandExternalCellRequest),
+    Verify(VerifyRequest),
+    CorruptScan(CorruptScanRequest),
+    ToolchainList(ToolchainListRequest),
+    ClockCheck(ClockCheckRequest),
+    WhyMiss(WhyMissRequest),
 }
 
 #[derive(Serialize, Deserialize)]
@@ -27,6 +32,11 @@ pub enum NewGenericResponse {
     DebugEval(DebugEvalResponse),
     Explain(ExplainResponse),
     ExpandExternalCell(ExpandExternalCellResponse),
+    Verify(VerifyResponse),
+    CorruptScan(CorruptScanResponse),
+    ToolchainList(ToolchainListResponse),
+    ClockCheck(ClockCheckResponse),
+    WhyMiss(WhyMissResponse),
 }
 
 #[derive(Serialize, Deserialize)]
@@ -70,3 +80,136 @@ pub struct ExpandExternalCellRequest {
 pub struct ExpandExternalCellResponse {
     pub path: String,
 }
+
+#[derive(Serialize, Deserialize)]
+pub struct VerifyRequest {
+    pub target_patterns: Vec<String>,
+    pub target_cfg: TargetCfg,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct VerifyResponse {
+    pub targets: Vec<VerifyTargetResult>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct VerifyTargetResult {
+    /// The configured target that declared the assertions.
+    pub target: String,
+    /// Path to the checked artifact, relative to the project root.
+    pub artifact: String,
+    pub assertions: Vec<VerifyAssertionResult>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct VerifyAssertionResult {
+    /// Name of the assertion, e.g. `max_size_bytes`.
+    pub assertion: String,
+    pub passed: bool,
+    /// Human-readable description of the check outcome.
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CorruptScanRequest {
+    /// Fraction of materialized artifacts to check, between 0 and 1.
+    pub sample_rate: f64,
+    /// Re-fetch corrupted artifacts from the CAS.
+    pub repair: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CorruptScanResponse {
+    /// Number of artifacts that were checked.
+    pub checked: u64,
+    pub corrupt: Vec<CorruptArtifact>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CorruptArtifact {
+    /// Path to the artifact, relative to the project root.
+    pub path: String,
+    /// How the artifact on disk differs from what the materializer recorded.
+    pub reason: String,
+    /// Whether the artifact was re-fetched. Only set if a repair was requested.
+    pub repaired: Option<bool>,
+    /// Why the repair failed, if it did.
+    pub repair_error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ToolchainListRequest {}
+
+#[derive(Serialize, Deserialize)]
+pub struct ToolchainListResponse {
+    pub toolchains: Vec<HostToolchainEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct HostToolchainEntry {
+    /// e.g. `clang`.
+    pub name: String,
+    /// Binaries the probe looks for on `PATH`.
+    pub binaries: Vec<String>,
+    /// Path to the binary that was found, if any.
+    pub path: Option<String>,
+    pub version: Option<String>,
+    /// Files whose evaluation requested this toolchain.
+    pub used_by: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ClockCheckRequest {
+    /// Directories to check the mtimes of files in, relative to the project root. The whole
+    /// project if empty.
+    pub paths: Vec<String>,
+    /// Stop after checking this many files.
+    pub max_files: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ClockCheckResponse {
+    /// Number of files whose mtime was checked.
+    pub checked_files: u64,
+    pub warnings: Vec<ClockCheckWarning>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ClockCheckWarning {
+    /// e.g. `future_mtime`, `coarse_timestamps`, `re_clock_skew`.
+    pub kind: String,
+    /// What was detected, and what to do about it.
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct WhyMissRequest {
+    /// An action digest (`hash:size`), or the target label of the action's owner.
+    pub action: String,
+    /// Only consider actions of this category.
+    pub category: Option<String>,
+    /// Only consider actions with this identifier.
+    pub identifier: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct WhyMissResponse {
+    /// The action, e.g. `cell//pkg:foo (cfg) (cxx_compile foo.cpp)`.
+    pub action: String,
+    pub digest: String,
+    /// The digest of the previous version of the action, if any was recorded.
+    pub previous_digest: Option<String>,
+    pub differences: Vec<WhyMissDifference>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct WhyMissDifference {
+    /// e.g. `argv`, `env`, `input`.
+    pub kind: String,
+    /// e.g. the environment variable, or the path of the input.
+    pub name: String,
+    /// The value in the previous version of the action, if it was present.
+    pub old: Option<String>,
+    /// The value in this version of the action, if it is present.
+    pub new: Option<String>,
+}
@@ -7,15 +7,18 @@
  * of this source tree.
  */
 
+pub mod attach;
 pub mod build;
 pub mod bxl;
 pub mod clean;
 pub mod clean_stale;
+pub mod complete;
 pub mod completion;
 pub mod ctargets;
 pub mod debug;
 pub mod expand_external_cell;
 pub mod explain;
+pub mod explain_failure;
 pub mod help_env;
 pub mod init;
 pub mod install;
@@ -33,3 +36,5 @@ pub mod status;
 pub mod subscribe;
 pub mod targets;
 pub mod test;
+pub mod toolchain;
+pub mod verify;
@@ -0,0 +1,111 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use async_trait::async_trait;
+use buck2_cli_proto::command_result;
+use buck2_cli_proto::AttachRequest;
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::common::ui::CommonConsoleOptions;
+use buck2_client_ctx::common::CommonBuildConfigurationOptions;
+use buck2_client_ctx::common::CommonEventLogOptions;
+use buck2_client_ctx::common::CommonStarlarkOptions;
+use buck2_client_ctx::daemon::client::BuckdClientConnector;
+use buck2_client_ctx::daemon::client::NoPartialResultHandler;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_client_ctx::streaming::StreamingCommand;
+use buck2_wrapper_common::invocation_id::TraceId;
+
+use crate::commands::build::print_build_result;
+
+/// Resume streaming the console output of a running command.
+///
+/// The command must have been started with `--reattachable`, and be still running: a reattachable
+/// command whose client disconnects keeps running for ten minutes, waiting for a client to attach.
+///
+/// Output of the command that was printed before attaching is not replayed, and the outputs a
+/// command prints on success (such as `--show-output`) are not printed.
+#[derive(Debug, clap::Parser)]
+#[clap(name = "attach")]
+pub struct AttachCommand {
+    /// The invocation id of the command, as printed in the `Build ID:` line of its console.
+    #[clap(value_name = "INVOCATION_ID")]
+    trace_id: TraceId,
+
+    #[clap(flatten)]
+    console_opts: CommonConsoleOptions,
+
+    #[clap(flatten)]
+    event_log_opts: CommonEventLogOptions,
+}
+
+#[async_trait]
+impl StreamingCommand for AttachCommand {
+    const COMMAND_NAME: &'static str = "attach";
+
+    async fn exec_impl(
+        self,
+        buckd: &mut BuckdClientConnector,
+        _matches: &clap::ArgMatches,
+        ctx: &mut ClientCommandContext<'_>,
+    ) -> ExitResult {
+        let response = buckd
+            .with_flushing()
+            .attach(
+                AttachRequest {
+                    trace_id: self.trace_id.to_string(),
+                },
+                ctx.stdin().console_interaction_stream(&self.console_opts),
+                &mut NoPartialResultHandler,
+            )
+            .await??;
+
+        let console = self.console_opts.final_console();
+        match response.0 {
+            command_result::Result::BuildResponse(response) if !response.errors.is_empty() => {
+                print_build_result(&console, &response.errors)?;
+                ExitResult::from_errors(&response.errors)
+            }
+            command_result::Result::BxlResponse(response) if !response.errors.is_empty() => {
+                print_build_result(&console, &response.errors)?;
+                ExitResult::from_errors(&response.errors)
+            }
+            command_result::Result::TestResponse(response) => {
+                print_build_result(&console, &response.errors)?;
+                if !response.errors.is_empty() {
+                    ExitResult::from_errors(&response.errors)
+                } else if let Some(exit_code) = response.exit_code {
+                    ExitResult::status_extended(exit_code)
+                } else {
+                    ExitResult::bail("Test executor did not provide an exit code")
+                }
+            }
+            _ => ExitResult::success(),
+        }
+    }
+
+    fn existing_only() -> bool {
+        true
+    }
+
+    fn console_opts(&self) -> &CommonConsoleOptions {
+        &self.console_opts
+    }
+
+    fn event_log_opts(&self) -> &CommonEventLogOptions {
+        &self.event_log_opts
+    }
+
+    fn build_config_opts(&self) -> &CommonBuildConfigurationOptions {
+        CommonBuildConfigurationOptions::default_ref()
+    }
+
+    fn starlark_opts(&self) -> &CommonStarlarkOptions {
+        CommonStarlarkOptions::default_ref()
+    }
+}
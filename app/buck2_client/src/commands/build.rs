@@ -8,6 +8,8 @@
  */
 
 use std::io::Write;
+use std::mem;
+use std::path::Path;
 use std::path::PathBuf;
 
 use anyhow::Context;
@@ -36,12 +38,47 @@ use buck2_client_ctx::final_console::FinalConsole;
 use buck2_client_ctx::output_destination_arg::OutputDestinationArg;
 use buck2_client_ctx::path_arg::PathArg;
 use buck2_client_ctx::streaming::StreamingCommand;
+use buck2_client_ctx::subscribers::action_lockfile::ActionLockfileSubscriber;
+use buck2_client_ctx::subscribers::subscriber::EventSubscriber;
+use buck2_core::fs::fs_util;
 use dupe::Dupe;
 
+use crate::commands::build::analysis_offload::AnalysisOffloadOptions;
+use crate::commands::build::analysis_offload::OffloadedShards;
+use crate::commands::build::federation::build_federated;
 use crate::commands::build::out::copy_to_out;
+use crate::commands::build::shadow::ShadowBuild;
+use crate::commands::build::shadow::ShadowBuildOptions;
+use crate::commands::build::targets_file::TargetsFileOptions;
 use crate::print::PrintOutputs;
 
+mod analysis_offload;
+mod federation;
+mod nested_build;
 mod out;
+mod shadow;
+pub(crate) mod targets_file;
+
+#[derive(Debug, buck2_error::Error)]
+#[buck2(input)]
+enum BuildCommandError {
+    #[error(
+        "`--out` cannot be used with `--remote-daemon`, the outputs are on the remote machine"
+    )]
+    OutWithRemoteDaemon,
+    #[error(
+        "`--output-hashes-file` cannot be used with `--remote-daemon`, the file would be written on the remote machine"
+    )]
+    OutputHashesFileWithRemoteDaemon,
+    #[error(
+        "Shadow builds cannot be used with `--remote-daemon`, the shadow daemon would run on this machine"
+    )]
+    ShadowWithRemoteDaemon,
+    #[error(
+        "`--offload-analysis` cannot be used with `--remote-daemon`, the worker daemons would run on this machine"
+    )]
+    OffloadWithRemoteDaemon,
+}
 
 #[derive(Debug, clap::Parser)]
 #[clap(name = "build", about = "Build the specified targets")]
@@ -105,11 +142,25 @@ pub struct BuildCommand {
 
     #[clap(
         long = "out",
-        help = "Copy the output of the built target to this path (`-` to stdout)"
+        help = "Copy the output of the built target to this path (`-` to stdout). \
+            When building multiple outputs, this must be a directory: each output is copied \
+            inside of it, next to a `buck2-out-manifest.json` manifest"
     )]
     output_path: Option<OutputDestinationArg>,
 
-    #[clap(name = "TARGET_PATTERNS", help = "Patterns to build")]
+    /// Patterns to build.
+    ///
+    /// Patterns prefixed with `-` exclude the targets they match from the patterns before them.
+    /// They must follow `--` so they aren't taken for flags, e.g.
+    /// `buck2 build -- //app/... -//app/experimental/...`. Patterns apply left to right, so a
+    /// later pattern can add back targets that an earlier exclusion removed.
+    ///
+    /// Patterns prefixed with the name of a checkout from the `[federation]` section of the root
+    /// .buckconfig, e.g. `deps@//lib:foo`, are built in that checkout by its own daemon, with the
+    /// same config, target platforms and modifiers, before the other patterns are built. Their
+    /// outputs are written to buck-out and linked from `federated/<name>/` here (see
+    /// `buck2.federation_dir`), so targets of this checkout can use them as source files.
+    #[clap(name = "TARGET_PATTERNS")]
     patterns: Vec<String>,
 
     #[clap(
@@ -118,6 +169,43 @@ pub struct BuildCommand {
     )]
     output_hashes_file: Option<PathArg>,
 
+    /// Write the action digests and output digests of all the actions needed by this build to
+    /// this file, so that another build can be checked against it with `--check-against`.
+    #[clap(long, value_name = "PATH")]
+    lockfile_output: Option<PathBuf>,
+
+    /// Fail if an action needed by this build has a different action digest than the one
+    /// recorded in this lockfile. Actions missing from either build are not compared.
+    #[clap(long, value_name = "PATH")]
+    check_against: Option<PathBuf>,
+
+    /// Point the links in `buck-out/<isolation>/last-build` to the outputs of this build if it
+    /// succeeds, overriding `buck2.last_build_links`.
+    #[clap(long, group = "last-build-links")]
+    last_build_links: bool,
+
+    /// Leave the links in `buck-out/<isolation>/last-build` unchanged, overriding
+    /// `buck2.last_build_links`.
+    #[clap(long, group = "last-build-links")]
+    no_last_build_links: bool,
+
+    /// Fail the build if less than this percentage of the actions running a command were served
+    /// by the remote cache. The build report breaks cache hits down per action category.
+    #[clap(long, value_name = "PERCENT", value_parser = parse_percentage)]
+    min_cache_hit_rate: Option<f64>,
+
+    /// Only print a warning, rather than failing the build, when the cache hit rate is below
+    /// `--min-cache-hit-rate`.
+    #[clap(long, requires = "min_cache_hit_rate")]
+    warn_on_low_cache_hit_rate: bool,
+
+    /// Apply this aspect, given as `cell//path/to/file.bzl:rule_name`, to each target built and
+    /// to its transitive deps, and build its default outputs too. An aspect is a rule with a
+    /// `target` attribute declared with `attrs.dep()`, set to the target, and defaults for all its
+    /// other attributes. Can be repeated.
+    #[clap(long, value_name = "ASPECT")]
+    aspect: Vec<String>,
+
     /// This option does nothing. It is here to keep compatibility with Buck1 and ci
     #[clap(long = "deep", hide = true)]
     _deep: bool,
@@ -128,6 +216,15 @@ pub struct BuildCommand {
     #[clap(flatten)]
     target_cfg: TargetCfgWithUniverseOptions,
 
+    #[clap(flatten)]
+    shadow_opts: ShadowBuildOptions,
+
+    #[clap(flatten)]
+    offload_opts: AnalysisOffloadOptions,
+
+    #[clap(flatten)]
+    targets_file_opts: TargetsFileOptions,
+
     #[clap(flatten)]
     common_opts: CommonCommandOptions,
 }
@@ -140,6 +237,16 @@ impl BuildCommand {
         build_providers::Action::Build
     }
 
+    fn last_build_links(&self) -> Option<bool> {
+        if self.last_build_links {
+            Some(true)
+        } else if self.no_last_build_links {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
     fn run_info(&self) -> build_providers::Action {
         if self.skip_run_info {
             return build_providers::Action::Skip;
@@ -201,6 +308,48 @@ pub fn print_build_result(
     Ok(())
 }
 
+/// Groups the targets that did not build into those that failed and those that were skipped
+/// because a dependency failed, along with the dependency that caused each skip.
+fn print_build_failures(
+    console: &FinalConsole,
+    failures: &[buck2_cli_proto::BuildFailure],
+) -> anyhow::Result<()> {
+    let (skipped, failed): (Vec<_>, Vec<_>) = failures
+        .iter()
+        .partition(|f| f.skipped_due_to_failed_dependency);
+    if !failed.is_empty() {
+        console.print_stderr(&format!("Failed ({}):", failed.len()))?;
+        for failure in failed {
+            console.print_stderr(&format!("  {}", failure.target))?;
+        }
+    }
+    if !skipped.is_empty() {
+        console.print_stderr(&format!(
+            "Skipped due to failed dependency ({}):",
+            skipped.len()
+        ))?;
+        for failure in skipped {
+            console.print_stderr(&format!("  {}", failure.target))?;
+            console.print_stderr(&format!(
+                "    root cause: {}",
+                failure.root_cause_chain.join(" -> ")
+            ))?;
+        }
+    }
+    Ok(())
+}
+
+fn parse_percentage(s: &str) -> anyhow::Result<f64> {
+    let percentage: f64 = s.parse()?;
+    if !(0.0..=100.0).contains(&percentage) {
+        return Err(anyhow::anyhow!(
+            "Expected a percentage between 0 and 100, got `{}`",
+            s
+        ));
+    }
+    Ok(percentage)
+}
+
 #[async_trait]
 impl StreamingCommand for BuildCommand {
     const COMMAND_NAME: &'static str = "build";
@@ -212,45 +361,118 @@ impl StreamingCommand for BuildCommand {
         ctx: &mut ClientCommandContext<'_>,
     ) -> ExitResult {
         let show_default_other_outputs = false;
+        if ctx.remote_daemon.is_some() {
+            let error = if self.output_path.is_some() {
+                Some(BuildCommandError::OutWithRemoteDaemon)
+            } else if self.output_hashes_file.is_some() {
+                Some(BuildCommandError::OutputHashesFileWithRemoteDaemon)
+            } else if self.shadow_opts.enabled() {
+                Some(BuildCommandError::ShadowWithRemoteDaemon)
+            } else if self.offload_opts.enabled() {
+                Some(BuildCommandError::OffloadWithRemoteDaemon)
+            } else {
+                None
+            };
+            if let Some(error) = error {
+                return ExitResult::err(error.into());
+            }
+        }
         let context = ctx.client_context(matches, &self)?;
 
-        let result = buckd
+        let mut patterns = self.patterns.clone();
+        patterns.extend(self.targets_file_opts.read(&ctx.working_dir)?.patterns);
+        // Patterns of other checkouts are built first, as their outputs are sources of this one.
+        let patterns = build_federated(
+            patterns,
+            &self.common_opts.config_opts,
+            &self.target_cfg,
+            ctx,
+        )
+        .await?;
+
+        let mut shadow = if self.shadow_opts.enabled() {
+            Some(ShadowBuild::spawn(
+                &self.shadow_opts,
+                &patterns,
+                &self.common_opts.config_opts,
+                &self.target_cfg,
+                ctx,
+            )?)
+        } else {
+            None
+        };
+
+        let mut output_hashes_file = self
+            .output_hashes_file
+            .as_ref()
+            .map(|p| p.resolve(&ctx.working_dir));
+        if let (Some(shadow), None) = (&mut shadow, &output_hashes_file) {
+            // The shadow build is compared against the primary build's output hashes.
+            output_hashes_file = Some(shadow.temporary_primary_hashes_file());
+        }
+
+        let mut build_opts = self.build_opts.to_proto();
+        // A remote daemon would write the build report on its machine, and the build report of
+        // this daemon is merged with those of the worker daemons, so it is returned instead and
+        // written here.
+        let build_report_file = if ctx.remote_daemon.is_some() || self.offload_opts.enabled() {
+            Some(mem::take(&mut build_opts.unstable_build_report_filename))
+                .filter(|file| !file.is_empty())
+        } else {
+            None
+        };
+
+        let mut request = BuildRequest {
+            context: Some(context),
+            target_patterns: patterns.clone(),
+            target_cfg: Some(self.target_cfg.target_cfg.target_cfg()),
+            build_providers: Some(BuildProviders {
+                default_info: self.default_info() as i32,
+                run_info: self.run_info() as i32,
+                test_info: self.test_info() as i32,
+            }),
+            response_options: Some(ResponseOptions {
+                return_outputs: self.show_output.format().is_some() || self.output_path.is_some(),
+                return_default_other_outputs: show_default_other_outputs,
+                return_action_digests: self.lockfile_output.is_some()
+                    || self.check_against.is_some(),
+                return_output_hashes: false,
+            }),
+            build_opts: Some(build_opts),
+            final_artifact_materializations: self.materializations.to_proto() as i32,
+            target_universe: self.target_cfg.target_universe,
+            output_hashes_file: output_hashes_file
+                .clone()
+                .map(|p| {
+                    p.into_string()
+                        .with_context(|| "Failed to convert output hashes file path to string")
+                })
+                .transpose()?,
+            last_build_links: self.last_build_links(),
+            min_cache_hit_rate: self.min_cache_hit_rate.map(|p| p / 100.0),
+            warn_on_low_cache_hit_rate: self.warn_on_low_cache_hit_rate,
+            offloaded_outputs: Vec::new(),
+            aspects: self.aspect.clone(),
+        };
+
+        let offloaded = if self.offload_opts.enabled() {
+            Some(OffloadedShards::build(&self.offload_opts, &mut request, ctx).await??)
+        } else {
+            None
+        };
+
+        let mut result = buckd
             .with_flushing()
             .build(
-                BuildRequest {
-                    context: Some(context),
-                    target_patterns: self.patterns.clone(),
-                    target_cfg: Some(self.target_cfg.target_cfg.target_cfg()),
-                    build_providers: Some(BuildProviders {
-                        default_info: self.default_info() as i32,
-                        run_info: self.run_info() as i32,
-                        test_info: self.test_info() as i32,
-                    }),
-                    response_options: Some(ResponseOptions {
-                        return_outputs: self.show_output.format().is_some()
-                            || self.output_path.is_some(),
-                        return_default_other_outputs: show_default_other_outputs,
-                    }),
-                    build_opts: Some(self.build_opts.to_proto()),
-                    final_artifact_materializations: self.materializations.to_proto() as i32,
-                    target_universe: self.target_cfg.target_universe,
-                    output_hashes_file: self
-                        .output_hashes_file
-                        .map(|p| {
-                            p.resolve(&ctx.working_dir).into_string().with_context(|| {
-                                format!(
-                                    "Failed to convert output hashes file path ({}) to string",
-                                    p.display()
-                                )
-                            })
-                        })
-                        .transpose()?,
-                },
+                request,
                 ctx.stdin()
                     .console_interaction_stream(&self.common_opts.console_opts),
                 &mut NoPartialResultHandler,
             )
             .await;
+        if let (Some(offloaded), Ok(CommandOutcome::Success(response))) = (offloaded, &mut result) {
+            offloaded.merge_into(response)?;
+        }
         let success = match &result {
             Ok(CommandOutcome::Success(response)) => response.errors.is_empty(),
             Ok(CommandOutcome::Failure(_)) => false,
@@ -260,7 +482,7 @@ impl StreamingCommand for BuildCommand {
         let console = self.common_opts.console_opts.final_console();
 
         if success {
-            if self.patterns.is_empty() {
+            if patterns.is_empty() {
                 console.print_warning("NO BUILD TARGET PATTERNS SPECIFIED")?;
             } else {
                 print_build_succeeded(&console, ctx)?;
@@ -274,12 +496,21 @@ impl StreamingCommand for BuildCommand {
         let response = result??;
 
         print_build_result(&console, &response.errors)?;
+        print_build_failures(&console, &response.failures)?;
 
         let mut stdout = Vec::new();
 
         if let Some(build_report) = response.serialized_build_report {
-            stdout.extend(build_report.as_bytes());
-            writeln!(&mut stdout)?;
+            match &build_report_file {
+                Some(file) => {
+                    fs_util::write(ctx.working_dir.resolve(Path::new(file)), build_report)
+                        .context("Error writing build report")?
+                }
+                None => {
+                    stdout.extend(build_report.as_bytes());
+                    writeln!(&mut stdout)?;
+                }
+            }
         }
 
         let res = if success {
@@ -298,12 +529,19 @@ impl StreamingCommand for BuildCommand {
                 print_outputs(
                     &mut stdout,
                     response.build_targets,
-                    self.show_output.is_full().then_some(response.project_root),
+                    self.show_output
+                        .is_full()
+                        .then(|| ctx.daemon_path_to_local(response.project_root))
+                        .transpose()?,
                     format,
                     show_default_other_outputs,
                 )?;
             }
 
+            if let (Some(shadow), Some(output_hashes_file)) = (shadow, &output_hashes_file) {
+                shadow.finish(&console, output_hashes_file).await?;
+            }
+
             ExitResult::success()
         } else {
             ExitResult::from_errors(&response.errors)
@@ -327,6 +565,16 @@ impl StreamingCommand for BuildCommand {
     fn starlark_opts(&self) -> &CommonStarlarkOptions {
         &self.common_opts.starlark_opts
     }
+
+    fn extra_subscribers(&self) -> Vec<Box<dyn EventSubscriber>> {
+        if self.lockfile_output.is_none() && self.check_against.is_none() {
+            return Vec::new();
+        }
+        vec![Box::new(ActionLockfileSubscriber::new(
+            self.lockfile_output.clone(),
+            self.check_against.clone(),
+        ))]
+    }
 }
 
 pub(crate) fn print_build_succeeded(
@@ -455,4 +703,35 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn aspect() -> anyhow::Result<()> {
+        let opts = parse(&[
+            "--aspect",
+            "//tools/aspects.bzl:ide_info",
+            "--aspect",
+            "//tools/aspects.bzl:lint",
+        ])?;
+        assert_eq!(
+            opts.aspect,
+            vec![
+                "//tools/aspects.bzl:ide_info".to_owned(),
+                "//tools/aspects.bzl:lint".to_owned()
+            ]
+        );
+        assert!(parse(&[])?.aspect.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn min_cache_hit_rate() -> anyhow::Result<()> {
+        let opts = parse(&["--min-cache-hit-rate", "87.5"])?;
+        assert_eq!(opts.min_cache_hit_rate, Some(87.5));
+        assert!(!opts.warn_on_low_cache_hit_rate);
+
+        assert_matches!(parse(&["--min-cache-hit-rate", "101"]), Err(..));
+        assert_matches!(parse(&["--warn-on-low-cache-hit-rate"]), Err(..));
+
+        Ok(())
+    }
 }
@@ -0,0 +1,505 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Analysis offloading: analyze and build the target patterns under some package prefixes in
+//! worker daemons, rather than in the daemon of this build.
+//!
+//! Each prefix is a shard with its own worker daemon: a daemon of this checkout in another
+//! isolation dir, or, given as `PREFIX=[user@]host:/path/to/checkout`, the daemon of a checkout on
+//! another machine, as with `--remote-daemon`. Workers keep the DICE graph of their packages from
+//! one build to the next. The client sends each of them the request of this build, restricted to
+//! the patterns of its shard, concurrently, and they return the digests of their outputs. The
+//! daemon of this build then builds the other patterns and declares the outputs of the workers in
+//! its materializer, so they are downloaded from the CAS: the workers must build remotely or
+//! upload their outputs to the cache. The targets, failures and build reports of the workers are
+//! merged into those of this build.
+
+use std::str::FromStr;
+
+use anyhow::Context;
+use buck2_cli_proto::build_request::Materializations;
+use buck2_cli_proto::BuildRequest;
+use buck2_cli_proto::BuildResponse;
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::command_outcome::CommandOutcome;
+use buck2_client_ctx::daemon::client::connect::BuckdConnectConstraints;
+use buck2_client_ctx::daemon::client::connect::BuckdConnectOptions;
+use buck2_client_ctx::daemon::client::connect::DaemonConstraintsRequest;
+use buck2_client_ctx::daemon::client::connect::DesiredTraceIoState;
+use buck2_client_ctx::daemon::client::remote::RemoteDaemon;
+use buck2_client_ctx::daemon::client::NoPartialResultHandler;
+use buck2_common::invocation_paths::InvocationPaths;
+use buck2_core::fs::paths::file_name::FileNameBuf;
+use futures::future;
+use serde_json::Value;
+
+#[derive(Debug, buck2_error::Error)]
+#[buck2(input)]
+enum AnalysisOffloadError {
+    #[error(
+        "Offloaded shard should be of format `PREFIX` or `PREFIX=[user@]host:/path/to/checkout`, but got `{0}`"
+    )]
+    Format(String),
+    #[error("Worker daemons must use a different isolation dir than this build (`{0}`)")]
+    SameIsolationDir(String),
+}
+
+/// A package prefix whose target patterns are built by a worker daemon.
+#[derive(Clone, Debug, PartialEq)]
+struct OffloadShard {
+    prefix: String,
+    /// The worker is the daemon of this checkout on another machine rather than a local one.
+    remote: Option<RemoteDaemon>,
+}
+
+impl FromStr for OffloadShard {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (prefix, remote) = match s.split_once('=') {
+            Some((prefix, remote)) => (prefix, Some(remote.parse()?)),
+            None => (s, None),
+        };
+        if prefix.is_empty() {
+            return Err(AnalysisOffloadError::Format(s.to_owned()).into());
+        }
+        Ok(OffloadShard {
+            prefix: prefix.to_owned(),
+            remote,
+        })
+    }
+}
+
+#[derive(Debug, clap::Parser, Default)]
+#[clap(next_help_heading = "Analysis Offload Options")]
+pub(crate) struct AnalysisOffloadOptions {
+    /// Experimental: analyze and build the target patterns under this package prefix (e.g.
+    /// `cell//foo/`) in a worker daemon rather than in the daemon of this build. Can be repeated:
+    /// each prefix is a shard with its own worker daemon. With `=[user@]host:/path/to/checkout`,
+    /// the worker is the daemon of that checkout on another machine, reached with `ssh` as with
+    /// `--remote-daemon`.
+    #[clap(long, value_name = "PREFIX[=HOST:PATH]")]
+    offload_analysis: Vec<OffloadShard>,
+
+    /// Isolation dirs of the worker daemons are this name followed by the index of their prefix.
+    #[clap(long, value_name = "NAME", default_value = "analysis-worker")]
+    offload_isolation_dir_prefix: String,
+}
+
+impl AnalysisOffloadOptions {
+    pub(crate) fn enabled(&self) -> bool {
+        !self.offload_analysis.is_empty()
+    }
+}
+
+/// Split `patterns` into those of each prefix, in the order of `prefixes`, and those which stay
+/// in this build.
+///
+/// A pattern goes to the longest prefix it starts with. Exclusions apply to every shard, and
+/// shards without patterns to build are empty.
+fn shard_patterns(prefixes: &[&str], patterns: &[String]) -> (Vec<Vec<String>>, Vec<String>) {
+    let mut shards = vec![Vec::new(); prefixes.len()];
+    let mut local = Vec::new();
+    for pattern in patterns {
+        if pattern.starts_with('-') {
+            continue;
+        }
+        let shard = prefixes
+            .iter()
+            .enumerate()
+            .filter(|(_, prefix)| pattern.starts_with(*prefix))
+            .max_by_key(|(_, prefix)| prefix.len())
+            .map(|(i, _)| i);
+        match shard {
+            Some(i) => shards[i].push(pattern.clone()),
+            None => local.push(pattern.clone()),
+        }
+    }
+
+    // Keep the exclusions in their place relative to the patterns they apply to.
+    let with_exclusions = |included: &[String]| -> Vec<String> {
+        if included.is_empty() {
+            return Vec::new();
+        }
+        patterns
+            .iter()
+            .filter(|p| p.starts_with('-') || included.contains(p))
+            .cloned()
+            .collect()
+    };
+    (
+        shards.iter().map(|s| with_exclusions(s)).collect(),
+        with_exclusions(&local),
+    )
+}
+
+/// The request of this build for a worker, restricted to `patterns`.
+fn worker_request(request: &BuildRequest, patterns: Vec<String>) -> BuildRequest {
+    let mut response_options = request.response_options.clone().unwrap_or_default();
+    response_options.return_output_hashes = true;
+    BuildRequest {
+        target_patterns: patterns,
+        response_options: Some(response_options),
+        // Only the daemon of this build materializes the outputs.
+        final_artifact_materializations: Materializations::Skip as i32,
+        output_hashes_file: None,
+        last_build_links: Some(false),
+        offloaded_outputs: Vec::new(),
+        ..request.clone()
+    }
+}
+
+/// Rewrite `path`, relative to the project root, from the buck-out of a worker to the one of
+/// this build, where the daemon of this build declares the outputs of the worker.
+fn rewrite_output_path(path: &mut String, worker_buck_out: &str, buck_out: &str) {
+    if let Some(rest) = path
+        .strip_prefix(worker_buck_out)
+        .and_then(|rest| rest.strip_prefix('/'))
+    {
+        *path = format!("{}/{}", buck_out, rest);
+    }
+}
+
+/// The largest `cause_index` of the errors in a build report.
+fn max_cause_index(value: &Value) -> Option<u64> {
+    match value {
+        Value::Object(map) => map
+            .iter()
+            .filter_map(|(key, value)| match (key.as_str(), value) {
+                ("cause_index", Value::Number(index)) => index.as_u64(),
+                _ => max_cause_index(value),
+            })
+            .max(),
+        Value::Array(values) => values.iter().filter_map(max_cause_index).max(),
+        _ => None,
+    }
+}
+
+/// Rewrite the output paths of a part of the build report of a worker, and offset its error
+/// cause indices, so they don't collide with those of the build report they are merged into.
+fn rebase_build_report(
+    value: &mut Value,
+    worker_buck_out: &str,
+    buck_out: &str,
+    cause_index_offset: u64,
+) {
+    match value {
+        Value::String(path) => rewrite_output_path(path, worker_buck_out, buck_out),
+        Value::Array(values) => {
+            for value in values {
+                rebase_build_report(value, worker_buck_out, buck_out, cause_index_offset);
+            }
+        }
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                match (key.as_str(), value.as_u64()) {
+                    ("cause_index", Some(index)) => *value = (index + cause_index_offset).into(),
+                    _ => rebase_build_report(value, worker_buck_out, buck_out, cause_index_offset),
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Merge the build report of a worker into `report`, the build report of this build. The cache
+/// hits are those of this build only.
+fn merge_build_report(
+    report: &mut Value,
+    mut worker_report: Value,
+    worker_buck_out: &str,
+    buck_out: &str,
+) -> anyhow::Result<()> {
+    let cause_index_offset = max_cause_index(report).map_or(0, |index| index + 1);
+    rebase_build_report(
+        &mut worker_report,
+        worker_buck_out,
+        buck_out,
+        cause_index_offset,
+    );
+    let (Value::Object(report), Value::Object(worker_report)) = (report, worker_report) else {
+        return Err(anyhow::anyhow!("Build report is not a JSON object"));
+    };
+    for (key, value) in worker_report {
+        match (key.as_str(), report.get_mut(&key), value) {
+            ("success", Some(Value::Bool(this)), Value::Bool(worker)) => *this &= worker,
+            ("truncated", Some(Value::Bool(this)), Value::Bool(worker)) => *this |= worker,
+            (
+                "results" | "failures" | "strings",
+                Some(Value::Object(this)),
+                Value::Object(worker),
+            ) => {
+                this.extend(worker);
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// A worker which built its shard.
+struct OffloadedShard {
+    /// The buck-out of the worker, relative to its project root.
+    buck_out: String,
+    response: BuildResponse,
+}
+
+/// The results of the shards built by worker daemons.
+pub(crate) struct OffloadedShards {
+    /// The buck-out of this build, relative to the project root.
+    buck_out: String,
+    shards: Vec<OffloadedShard>,
+}
+
+impl OffloadedShards {
+    /// Build the shards of the patterns of `request` in their worker daemons. `request` is then
+    /// left with the patterns to build here, and the outputs of the workers to declare.
+    ///
+    /// A worker which fails to run the build fails this one, with its exit result: the errors it
+    /// returned are printed as with `--console none`. Failures of targets are merged instead.
+    pub(crate) async fn build(
+        opts: &AnalysisOffloadOptions,
+        request: &mut BuildRequest,
+        ctx: &ClientCommandContext<'_>,
+    ) -> anyhow::Result<CommandOutcome<OffloadedShards>> {
+        let paths = ctx.paths()?;
+        let prefixes: Vec<&str> = opts
+            .offload_analysis
+            .iter()
+            .map(|shard| shard.prefix.as_str())
+            .collect();
+        let (shards, local) = shard_patterns(&prefixes, &request.target_patterns);
+        let context = request.context.clone().unwrap_or_default();
+
+        let mut workers = Vec::new();
+        let mut worker_prefixes = Vec::new();
+        for (i, (shard, patterns)) in opts.offload_analysis.iter().zip(shards).enumerate() {
+            if patterns.is_empty() {
+                continue;
+            }
+            let isolation_dir = format!("{}-{}", opts.offload_isolation_dir_prefix, i);
+            if shard.remote.is_none() && paths.isolation.as_str() == isolation_dir {
+                return Err(AnalysisOffloadError::SameIsolationDir(isolation_dir).into());
+            }
+            let worker_paths = InvocationPaths {
+                roots: paths.roots.clone(),
+                isolation: FileNameBuf::try_from(isolation_dir)?,
+            };
+            let mut worker_request = worker_request(request, patterns);
+            if let Some(remote) = &shard.remote {
+                let mut context = context.clone();
+                remote.translate_client_context(paths.project_root().root(), &mut context)?;
+                worker_request.context = Some(context);
+            }
+            let constraints = BuckdConnectConstraints::Constraints(DaemonConstraintsRequest::new(
+                ctx.immediate_config,
+                DesiredTraceIoState::Existing,
+            )?);
+
+            worker_prefixes.push(&shard.prefix);
+            workers.push(async move {
+                let connect = BuckdConnectOptions::no_console(constraints);
+                let mut buckd = match &shard.remote {
+                    Some(remote) => connect.connect_remote(remote, &worker_paths).await?,
+                    None => connect.connect(&worker_paths).await?,
+                };
+                let outcome = buckd
+                    .with_flushing()
+                    .build(worker_request, None, &mut NoPartialResultHandler)
+                    .await?;
+                anyhow::Ok(match outcome {
+                    CommandOutcome::Success(response) => CommandOutcome::Success(OffloadedShard {
+                        buck_out: worker_paths.buck_out_dir().to_string(),
+                        response,
+                    }),
+                    CommandOutcome::Failure(exit) => CommandOutcome::Failure(exit),
+                })
+            });
+        }
+
+        let mut offloaded = OffloadedShards {
+            buck_out: paths.buck_out_dir().to_string(),
+            shards: Vec::new(),
+        };
+        for (prefix, outcome) in worker_prefixes
+            .into_iter()
+            .zip(future::join_all(workers).await)
+        {
+            match outcome.with_context(|| format!("Worker daemon for `{}` failed", prefix))? {
+                CommandOutcome::Success(shard) => offloaded.shards.push(shard),
+                CommandOutcome::Failure(exit) => return Ok(CommandOutcome::Failure(exit)),
+            }
+        }
+
+        for shard in &offloaded.shards {
+            request
+                .offloaded_outputs
+                .extend(shard.response.output_hashes.clone());
+        }
+        request.target_patterns = local;
+        Ok(CommandOutcome::Success(offloaded))
+    }
+
+    /// Merge the targets, errors, failures, action digests and build reports of the workers into
+    /// `response`, the response of this build.
+    pub(crate) fn merge_into(self, response: &mut BuildResponse) -> anyhow::Result<()> {
+        let mut report = response
+            .serialized_build_report
+            .as_deref()
+            .map(serde_json::from_str::<Value>)
+            .transpose()?;
+        for shard in self.shards {
+            let mut worker = shard.response;
+            for target in &mut worker.build_targets {
+                for output in &mut target.outputs {
+                    rewrite_output_path(&mut output.path, &shard.buck_out, &self.buck_out);
+                }
+            }
+            response.build_targets.extend(worker.build_targets);
+            response.errors.extend(worker.errors);
+            response.failures.extend(worker.failures);
+            response.action_digests.extend(worker.action_digests);
+            if let (Some(report), Some(worker_report)) =
+                (&mut report, worker.serialized_build_report)
+            {
+                merge_build_report(
+                    report,
+                    serde_json::from_str(&worker_report)?,
+                    &shard.buck_out,
+                    &self.buck_out,
+                )?;
+            }
+        }
+        if let Some(report) = report {
+            response.serialized_build_report = Some(serde_json::to_string(&report)?);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn strings(s: &[&str]) -> Vec<String> {
+        s.iter().map(|s| (*s).to_owned()).collect()
+    }
+
+    #[test]
+    fn test_shard_patterns() {
+        let (shards, local) = shard_patterns(
+            &["root//a/", "root//a/b/", "root//c/"],
+            &strings(&[
+                "root//a/...",
+                "root//a/b:x",
+                "root//d:y",
+                "-root//a/z/...",
+                "//relative:t",
+            ]),
+        );
+        assert_eq!(
+            vec![
+                strings(&["root//a/...", "-root//a/z/..."]),
+                strings(&["root//a/b:x", "-root//a/z/..."]),
+                Vec::new(),
+            ],
+            shards
+        );
+        assert_eq!(
+            strings(&["root//d:y", "-root//a/z/...", "//relative:t"]),
+            local
+        );
+    }
+
+    #[test]
+    fn test_shard_patterns_all_offloaded() {
+        let (shards, local) = shard_patterns(&["root//a/"], &strings(&["root//a:x", "-root//a:y"]));
+        assert_eq!(vec![strings(&["root//a:x", "-root//a:y"])], shards);
+        assert!(local.is_empty());
+    }
+
+    #[test]
+    fn test_parse_offload_shard() -> anyhow::Result<()> {
+        assert_eq!(
+            OffloadShard {
+                prefix: "root//a/".to_owned(),
+                remote: None,
+            },
+            "root//a/".parse()?
+        );
+        assert_eq!(
+            OffloadShard {
+                prefix: "root//a/".to_owned(),
+                remote: Some("dev@host:/repo".parse()?),
+            },
+            "root//a/=dev@host:/repo".parse()?
+        );
+        assert!("=dev@host:/repo".parse::<OffloadShard>().is_err());
+        assert!("root//a/=host".parse::<OffloadShard>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_build_report() -> anyhow::Result<()> {
+        let mut report = json!({
+            "success": true,
+            "truncated": false,
+            "results": {
+                "root//d:y": {
+                    "outputs": {"DEFAULT": ["buck-out/v2/gen/root/d/y"]},
+                    "errors": [{"message_content": "e", "cause_index": 0}],
+                },
+            },
+            "failures": {},
+            "strings": {"h1": "a"},
+            "cache_hits": {"hit_rate": 1.0},
+        });
+        merge_build_report(
+            &mut report,
+            json!({
+                "success": false,
+                "truncated": false,
+                "results": {
+                    "root//a:x": {
+                        "outputs": {"DEFAULT": ["buck-out/analysis-worker-0/gen/root/a/x"]},
+                        "errors": [{"message_content": "f", "cause_index": 0}],
+                    },
+                },
+                "failures": {"root//a:x": "f"},
+                "strings": {"h2": "b"},
+                "cache_hits": {"hit_rate": 0.0},
+            }),
+            "buck-out/analysis-worker-0",
+            "buck-out/v2",
+        )?;
+        assert_eq!(
+            json!({
+                "success": false,
+                "truncated": false,
+                "results": {
+                    "root//d:y": {
+                        "outputs": {"DEFAULT": ["buck-out/v2/gen/root/d/y"]},
+                        "errors": [{"message_content": "e", "cause_index": 0}],
+                    },
+                    "root//a:x": {
+                        "outputs": {"DEFAULT": ["buck-out/v2/gen/root/a/x"]},
+                        "errors": [{"message_content": "f", "cause_index": 1}],
+                    },
+                },
+                "failures": {"root//a:x": "f"},
+                "strings": {"h1": "a", "h2": "b"},
+                "cache_hits": {"hit_rate": 1.0},
+            }),
+            report
+        );
+        Ok(())
+    }
+}
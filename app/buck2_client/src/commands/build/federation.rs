@@ -0,0 +1,303 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Build federation: build targets of other checkouts, each with its own daemon, as part of a
+//! build in this one.
+//!
+//! The checkouts are declared in the `[federation]` section of the root `.buckconfig`, like
+//! `deps = ../deps`, and their target patterns are prefixed with their name, like
+//! `deps@//lib:foo`. Each checkout builds its patterns with `buck2 build --out`, with the config,
+//! target platforms and modifiers of this build, and writes their default outputs, along with a
+//! manifest when there are several, into a new directory under `buck-out/<isolation
+//! dir>/federated/<name>/`. The directories of previous builds are then removed.
+//!
+//! Targets of this checkout reference those outputs through `<buck2.federation_dir>/<name>/`,
+//! which holds a relative symlink to each of them, so they are source files here, like
+//! `export_file(name = "foo", src = "federated/deps/libfoo.so")`. As each build writes its
+//! outputs to a new directory, the symlinks change with it, which invalidates what depends on
+//! them.
+
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::path::PathBuf;
+use std::process::ExitStatus;
+
+use anyhow::Context;
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::common::target_cfg::TargetCfgWithUniverseOptions;
+use buck2_client_ctx::common::CommonBuildConfigurationOptions;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
+use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use futures::future;
+
+use crate::commands::build::nested_build::NestedBuild;
+
+#[derive(Debug, buck2_error::Error)]
+#[buck2(input)]
+enum FederationError {
+    #[error(
+        "Unknown checkout `{0}` in target pattern `{1}`, checkouts are declared in the `[federation]` section of the root .buckconfig"
+    )]
+    UnknownCheckout(String, String),
+    #[error("Build in checkout `{name}` failed ({status}), see {log}")]
+    BuildFailed {
+        name: String,
+        status: ExitStatus,
+        log: String,
+    },
+    #[error(
+        "Target patterns of other checkouts, like `{0}`, cannot be built with `--remote-daemon`, their daemons would run on this machine"
+    )]
+    WithRemoteDaemon(String),
+}
+
+/// Split a target pattern of another checkout, like `deps@//lib:foo` or `-deps@//lib:bar`, into
+/// the name of the checkout and the pattern in it.
+fn split_federated_pattern(pattern: &str) -> Option<(&str, String)> {
+    let (exclusion, rest) = match pattern.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", pattern),
+    };
+    let (name, pattern) = rest.split_once('@')?;
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return None;
+    }
+    Some((name, format!("{}{}", exclusion, pattern)))
+}
+
+/// Split `patterns` into those of each other checkout, by name, and those of this checkout.
+fn split_federated_patterns(patterns: &[String]) -> (BTreeMap<String, Vec<String>>, Vec<String>) {
+    let mut federated: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut local = Vec::new();
+    for pattern in patterns {
+        match split_federated_pattern(pattern) {
+            Some((name, pattern)) => federated.entry(name.to_owned()).or_default().push(pattern),
+            None => local.push(pattern.clone()),
+        }
+    }
+    (federated, local)
+}
+
+/// The target of a symlink in `link_dir` to the output `name` in `outputs_dir`, both relative to
+/// the project root, relative to the symlink so that the checkout can be moved.
+fn link_target(
+    link_dir: &ForwardRelativePath,
+    outputs_dir: &ProjectRelativePath,
+    name: &str,
+) -> PathBuf {
+    let mut target = PathBuf::new();
+    for _ in link_dir.iter() {
+        target.push("..");
+    }
+    target.push(outputs_dir.as_str());
+    target.push(name);
+    target
+}
+
+/// Replace the contents of `link_dir` with a symlink to each output in `outputs_dir`.
+fn link_outputs(
+    project_root: &AbsNormPath,
+    link_dir: &ForwardRelativePath,
+    outputs_dir: &ProjectRelativePath,
+) -> anyhow::Result<()> {
+    let link_dir_path = project_root.join(link_dir);
+    fs_util::remove_all(&link_dir_path)?;
+    fs_util::create_dir_all(&link_dir_path)?;
+    for entry in fs_util::read_dir(project_root.join(outputs_dir))? {
+        let name = entry?.file_name();
+        let name = name
+            .to_str()
+            .context("Federated output name is not UTF-8")?;
+        fs_util::symlink(
+            link_target(link_dir, outputs_dir, name),
+            link_dir_path.join(ForwardRelativePath::new(name)?),
+        )?;
+    }
+    Ok(())
+}
+
+/// Remove the outputs of previous builds in `outputs_root`, all but `current`.
+fn remove_stale_outputs(outputs_root: &AbsNormPath, current: &str) -> anyhow::Result<()> {
+    for entry in fs_util::read_dir(outputs_root)? {
+        let entry = entry?;
+        if entry.file_name() != current {
+            fs_util::remove_all(
+                outputs_root.join(ForwardRelativePath::new(
+                    entry
+                        .file_name()
+                        .to_str()
+                        .context("Federated outputs directory name is not UTF-8")?,
+                )?),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Build the patterns of other checkouts in those checkouts, link their outputs into this one,
+/// and return the patterns left to build here.
+pub(crate) async fn build_federated(
+    patterns: Vec<String>,
+    config_opts: &CommonBuildConfigurationOptions,
+    target_cfg: &TargetCfgWithUniverseOptions,
+    ctx: &ClientCommandContext<'_>,
+) -> anyhow::Result<Vec<String>> {
+    let (federated, local) = split_federated_patterns(&patterns);
+    if federated.is_empty() {
+        return Ok(patterns);
+    }
+    if ctx.remote_daemon.is_some() {
+        let (name, patterns) = federated.iter().next().unwrap();
+        return Err(FederationError::WithRemoteDaemon(format!("{}@{}", name, patterns[0])).into());
+    }
+
+    let config = ctx.immediate_config.federation_config()?;
+    let paths = ctx.paths()?;
+    let project_root = paths.project_root().root();
+    let links_dir = ForwardRelativePath::new(&config.outputs_dir)?;
+    let federated_dir = paths
+        .buck_out_dir()
+        .join(ForwardRelativePath::new("federated")?);
+    let build_dir = ctx.trace_id.to_string();
+
+    let tmp_dir = paths.tmp_dir();
+    fs_util::create_dir_all(&tmp_dir)?;
+
+    let mut builds = Vec::new();
+    for (name, patterns) in &federated {
+        let checkout = config.checkouts.get(name).ok_or_else(|| {
+            FederationError::UnknownCheckout(name.clone(), format!("{}@{}", name, patterns[0]))
+        })?;
+        let checkout = project_root.as_path().join(checkout);
+
+        let outputs_dir = federated_dir
+            .join(ForwardRelativePath::new(name)?)
+            .join(ForwardRelativePath::new(&build_dir)?);
+        // `--out` copies a single output inside an existing directory rather than to its path.
+        fs_util::create_dir_all(project_root.join(&outputs_dir))?;
+        let log_file = tmp_dir.join(ForwardRelativePath::new(&format!(
+            "federation-{}-{}.log",
+            name, ctx.trace_id
+        ))?);
+
+        let mut build =
+            NestedBuild::new(&checkout, None, config_opts, target_cfg, &ctx.working_dir)?;
+        build
+            .args([
+                OsStr::new("--out"),
+                project_root.join(&outputs_dir).as_os_str(),
+            ])
+            .without_target_universe();
+
+        let child = build.spawn(patterns, &log_file).with_context(|| {
+            format!(
+                "Failed to start build in checkout `{}` at `{}`",
+                name,
+                checkout.display()
+            )
+        })?;
+        builds.push((name, outputs_dir, log_file, child));
+    }
+
+    let statuses = future::try_join_all(
+        builds
+            .iter_mut()
+            .map(|(_, _, _, child)| async move { child.wait().await }),
+    )
+    .await?;
+    for ((name, _, log_file, _), status) in builds.iter().zip(&statuses) {
+        if !status.success() {
+            return Err(FederationError::BuildFailed {
+                name: (*name).clone(),
+                status: *status,
+                log: log_file.to_string(),
+            }
+            .into());
+        }
+    }
+
+    for (name, outputs_dir, _, _) in &builds {
+        link_outputs(
+            project_root,
+            &links_dir.join(ForwardRelativePath::new(name)?),
+            outputs_dir,
+        )
+        .with_context(|| format!("Failed to link the outputs of checkout `{}`", name))?;
+        remove_stale_outputs(
+            &project_root.join(federated_dir.join(ForwardRelativePath::new(name)?)),
+            &build_dir,
+        )?;
+    }
+
+    Ok(local)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn strings(s: &[&str]) -> Vec<String> {
+        s.iter().map(|s| (*s).to_owned()).collect()
+    }
+
+    #[test]
+    fn test_split_federated_pattern() {
+        assert_eq!(
+            Some(("deps", "//lib:foo".to_owned())),
+            split_federated_pattern("deps@//lib:foo")
+        );
+        assert_eq!(
+            Some(("deps", "-cell//lib/...".to_owned())),
+            split_federated_pattern("-deps@cell//lib/...")
+        );
+        assert_eq!(None, split_federated_pattern("//lib:foo"));
+        assert_eq!(None, split_federated_pattern("@//lib:foo"));
+        assert_eq!(None, split_federated_pattern("//lib:foo@bar"));
+    }
+
+    #[test]
+    fn test_split_federated_patterns() {
+        let (federated, local) = split_federated_patterns(&strings(&[
+            "//app:main",
+            "deps@//lib/...",
+            "-deps@//lib:broken",
+            "tools@//bin:tool",
+            "-//app:old",
+        ]));
+        assert_eq!(
+            BTreeMap::from([
+                ("deps".to_owned(), strings(&["//lib/...", "-//lib:broken"])),
+                ("tools".to_owned(), strings(&["//bin:tool"])),
+            ]),
+            federated
+        );
+        assert_eq!(strings(&["//app:main", "-//app:old"]), local);
+    }
+
+    #[test]
+    fn test_link_target() -> anyhow::Result<()> {
+        assert_eq!(
+            Path::new("../../buck-out/v2/federated/deps/1234/libfoo.so"),
+            link_target(
+                ForwardRelativePath::new("federated/deps")?,
+                ProjectRelativePath::new("buck-out/v2/federated/deps/1234")?,
+                "libfoo.so",
+            )
+        );
+        Ok(())
+    }
+}
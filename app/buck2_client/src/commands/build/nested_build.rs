@@ -0,0 +1,154 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! `buck2 build` subprocesses run as part of a build, like shadow builds and the builds of
+//! federated checkouts, with the configuration of that build.
+
+use std::ffi::OsStr;
+use std::path::Path;
+use std::process::Stdio;
+
+use anyhow::Context;
+use buck2_client_ctx::common::target_cfg::TargetCfgWithUniverseOptions;
+use buck2_client_ctx::common::CommonBuildConfigurationOptions;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_path::AbsPath;
+use buck2_core::fs::working_dir::WorkingDir;
+
+/// A `buck2 build` without console, with the config values, config files, target platforms,
+/// modifiers and target universe of the build which runs it.
+pub(crate) struct NestedBuild {
+    command: tokio::process::Command,
+    config_values: Vec<String>,
+    config_files: Vec<String>,
+    target_platforms: Option<String>,
+    modifiers: Vec<String>,
+    target_universe: Vec<String>,
+}
+
+impl NestedBuild {
+    /// A build run from `dir`, which selects the checkout it builds, in `isolation_dir` if set.
+    pub(crate) fn new(
+        dir: &Path,
+        isolation_dir: Option<&str>,
+        config_opts: &CommonBuildConfigurationOptions,
+        target_cfg: &TargetCfgWithUniverseOptions,
+        working_dir: &WorkingDir,
+    ) -> anyhow::Result<NestedBuild> {
+        let mut command = tokio::process::Command::new(
+            std::env::current_exe().context("Failed to get current exe")?,
+        );
+        command.current_dir(dir);
+        if let Some(isolation_dir) = isolation_dir {
+            command.arg("--isolation-dir").arg(isolation_dir);
+        }
+        command.arg("build").args(["--console", "none"]);
+        Ok(NestedBuild {
+            command,
+            config_values: config_opts.config_values.clone(),
+            // The build may run from another directory, so config files relative to the current
+            // one are made absolute. Cell paths are resolved by its daemon.
+            config_files: config_opts
+                .config_files
+                .iter()
+                .map(|file| {
+                    if file.contains("//") {
+                        file.clone()
+                    } else {
+                        working_dir.resolve(Path::new(file)).display().to_string()
+                    }
+                })
+                .collect(),
+            target_platforms: target_cfg.target_cfg.target_platforms.clone(),
+            modifiers: target_cfg.target_cfg.cli_modifier.clone(),
+            target_universe: target_cfg.target_universe.clone(),
+        })
+    }
+
+    /// Add arguments of `buck2 build`.
+    pub(crate) fn args<I, S>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.command.args(args);
+        self
+    }
+
+    /// The `command` to run the build, which can be adjusted before it is spawned.
+    pub(crate) fn command(&mut self) -> &mut tokio::process::Command {
+        &mut self.command
+    }
+
+    /// Also add these config values, after those of this build.
+    pub(crate) fn add_config_values(&mut self, values: &[String]) -> &mut Self {
+        self.config_values.extend(values.iter().cloned());
+        self
+    }
+
+    /// Also add these config files, after those of this build.
+    pub(crate) fn add_config_files(&mut self, files: &[String]) -> &mut Self {
+        self.config_files.extend(files.iter().cloned());
+        self
+    }
+
+    /// Build for these target platforms rather than those of this build.
+    pub(crate) fn target_platforms(&mut self, platforms: Option<String>) -> &mut Self {
+        if platforms.is_some() {
+            self.target_platforms = platforms;
+        }
+        self
+    }
+
+    /// Do not use the target universe of this build, whose targets are of another checkout.
+    pub(crate) fn without_target_universe(&mut self) -> &mut Self {
+        self.target_universe.clear();
+        self
+    }
+
+    /// Start the build of `patterns`, with its stderr written to `log_file`. It is killed if
+    /// the returned child is dropped.
+    pub(crate) fn spawn(
+        mut self,
+        patterns: &[String],
+        log_file: &AbsPath,
+    ) -> anyhow::Result<tokio::process::Child> {
+        for config in &self.config_values {
+            self.command.arg("--config").arg(config);
+        }
+        for file in &self.config_files {
+            self.command.arg("--config-file").arg(file);
+        }
+        if let Some(platforms) = &self.target_platforms {
+            self.command.arg("--target-platforms").arg(platforms);
+        }
+        for modifier in &self.modifiers {
+            self.command.arg("--modifier").arg(modifier);
+        }
+        if !self.target_universe.is_empty() {
+            self.command
+                .arg("--target-universe")
+                .arg(self.target_universe.join(","));
+        }
+        self.command
+            .arg("--")
+            .args(patterns)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(
+                fs_util::create_file(log_file)
+                    .with_context(|| {
+                        format!("Failed to create build log `{}`", log_file.display())
+                    })?
+                    .into_file(),
+            )
+            .kill_on_drop(true);
+        Ok(self.command.spawn()?)
+    }
+}
@@ -8,6 +8,7 @@
  */
 
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::io;
 use std::path::Path;
 
@@ -33,16 +34,38 @@ struct CopyContext {
     relative_symlink_boundary: AbsNormPathBuf,
 }
 
-/// Given a list of targets built by this command, extracts a reasonable default output from the list and writes it
+/// Name of the manifest written to the `--out` directory when copying multiple outputs.
+const OUT_MANIFEST_NAME: &str = "buck2-out-manifest.json";
+
+struct OutputToBeCopied {
+    target: String,
+    /// Path of the output relative to the project root.
+    output: String,
+    from_path: AbsNormPathBuf,
+    is_dir: bool,
+}
+
+/// An entry of the manifest written to the `--out` directory.
+#[derive(serde::Serialize)]
+struct OutManifestEntry<'a> {
+    target: &'a str,
+    output: &'a str,
+    /// Path of the copy, relative to the `--out` directory.
+    path: &'a str,
+}
+
+/// Given a list of targets built by this command, extracts their default outputs and writes them
 /// to the path given by `out`.
 ///
-/// In order to extract a "reasonable default output", this function will bail if any of the following are true:
-///  1. Multiple top-level targets were built, in which case the correct output to write is ambiguous,
-///  2. A single top-level target was built, but it produced zero default outputs,
-///  3. A single top-level target was built, but it produced more than two default outputs
+/// This function will bail if any of the top-level targets produced zero default outputs.
+///
+/// If a single default output was produced, it is copied to the output path. If the given path is
+/// a directory then all output files will be copied inside of it.
 ///
-/// Otherwise, we'll extract the single default output from the single top-level target and copy it to the output
-/// path. If the given path is a directory then all output files will be copied inside of it.
+/// Otherwise, the output path must be a directory (it is created if it does not exist), and each
+/// default output of each target is copied inside of it, under its file name. A manifest named
+/// `buck2-out-manifest.json` is written next to them, listing the target, the original output and
+/// the copy for each output. Outputs with the same file name are an error.
 ///
 /// Symbolic links are preserved. However, if a relative symlink within one of the outputs points outside that output,
 /// it will be converted into an absolute link pointing to the same target as the original link.
@@ -50,18 +73,13 @@ struct CopyContext {
 /// link would point to the same original target or a copied one.
 ///
 /// As a special case, `--out -` is interpreted as `--out /dev/stdout` and allows multiple output files to be
-/// written to it.
+/// written to it, as long as each target produced a single default output.
 pub(super) async fn copy_to_out(
     targets: &[BuildTarget],
     root_path: &ProjectRoot,
     working_dir: &WorkingDir,
     out: &OutputDestinationArg,
 ) -> anyhow::Result<()> {
-    struct OutputToBeCopied {
-        from_path: AbsNormPathBuf,
-        is_dir: bool,
-    }
-
     let mut outputs_to_be_copied = Vec::new();
     for target in targets {
         let default_outputs: Vec<&BuildOutput> = target
@@ -75,76 +93,62 @@ pub(super) async fn copy_to_out(
             })
             .collect();
 
-        let single_default_output = match default_outputs.len() {
-            0 => {
+        match (default_outputs.len(), out) {
+            (0, _) => {
                 return Err(anyhow::anyhow!(
                     "target {} produced zero default outputs",
                     target.target
                 ));
             }
-            1 => &default_outputs[0],
-            n => {
+            (1, _) | (_, OutputDestinationArg::Path(..)) => {}
+            (n, OutputDestinationArg::Stream) => {
                 return Err(anyhow::anyhow!(
                     "target {} produced {} outputs, choice of output is ambiguous",
                     target.target,
                     n
                 ));
             }
-        };
-
-        let output_path = root_path
-            .root()
-            .join(ForwardRelativePath::new(&single_default_output.path)?);
-        let output_meta = tokio::fs::metadata(&output_path)
-            .await
-            .context("Error inspecting file metadata")?;
-        let is_dir = output_meta.is_dir();
+        }
 
-        outputs_to_be_copied.push(OutputToBeCopied {
-            from_path: output_path,
-            is_dir,
-        });
+        for output in default_outputs {
+            let output_path = root_path
+                .root()
+                .join(ForwardRelativePath::new(&output.path)?);
+            let output_meta = tokio::fs::metadata(&output_path)
+                .await
+                .context("Error inspecting file metadata")?;
+
+            outputs_to_be_copied.push(OutputToBeCopied {
+                target: target.target.clone(),
+                output: output.path.clone(),
+                from_path: output_path,
+                is_dir: output_meta.is_dir(),
+            });
+        }
     }
 
     match out {
         OutputDestinationArg::Stream => {
             // Check no output is a directory. We allow outputting any number of
             // files (including 0) to stdout.
-            if let Some(dir_i) = outputs_to_be_copied.iter().position(|o| o.is_dir) {
+            if let Some(dir) = outputs_to_be_copied.iter().find(|o| o.is_dir) {
                 return Err(anyhow::anyhow!(
                     "target {} produces a default output that is a directory, and cannot be sent to stdout",
-                    targets[dir_i].target,
-                ));
-            }
-        }
-        OutputDestinationArg::Path(..) => {
-            // Check we are outputting exactly 1 target. Okay if directory.
-            if outputs_to_be_copied.len() != 1 {
-                return Err(anyhow::anyhow!(
-                    "build command built multiple top-level targets, choice of output is ambiguous"
+                    dir.target,
                 ));
             }
-        }
-    }
-
-    for to_be_copied in outputs_to_be_copied {
-        match out {
-            OutputDestinationArg::Stream => {
+            for to_be_copied in outputs_to_be_copied {
                 let mut file = async_fs_util::open(&to_be_copied.from_path).await?;
                 tokio::io::copy(&mut file, &mut tokio::io::stdout())
                     .await
                     .map_err(convert_broken_pipe_error)?;
             }
-            OutputDestinationArg::Path(path) => {
-                let path = path.resolve(working_dir);
-                if to_be_copied.is_dir {
-                    let context = CopyContext {
-                        relative_symlink_boundary: fs_util::canonicalize(&to_be_copied.from_path)?,
-                    };
-                    copy_directory(&to_be_copied.from_path, &path, &context).await?;
-                } else {
-                    copy_file(&to_be_copied.from_path, &path).await?;
-                }
+        }
+        OutputDestinationArg::Path(path) => {
+            let path = path.resolve(working_dir);
+            match outputs_to_be_copied.as_slice() {
+                [single] => copy_output(single, &path).await?,
+                outputs => copy_outputs_to_dir(outputs, &path).await?,
             }
         }
     }
@@ -152,6 +156,71 @@ pub(super) async fn copy_to_out(
     Ok(())
 }
 
+async fn copy_output(to_be_copied: &OutputToBeCopied, dst: &AbsPath) -> anyhow::Result<()> {
+    if to_be_copied.is_dir {
+        let context = CopyContext {
+            relative_symlink_boundary: fs_util::canonicalize(&to_be_copied.from_path)?,
+        };
+        copy_directory(&to_be_copied.from_path, dst, &context).await
+    } else {
+        copy_file(&to_be_copied.from_path, dst).await
+    }
+}
+
+/// Copies each output inside the `dst` directory under its file name, and writes a manifest.
+async fn copy_outputs_to_dir(outputs: &[OutputToBeCopied], dst: &AbsPath) -> anyhow::Result<()> {
+    if dst.exists() && !dst.is_dir() {
+        return Err(anyhow::anyhow!(
+            "--out must be a directory when copying multiple outputs, but {} is not",
+            dst.display()
+        ));
+    }
+    tokio::fs::create_dir_all(dst).await?;
+
+    let mut copied: HashMap<&str, &OutputToBeCopied> = HashMap::new();
+    let mut manifest = Vec::with_capacity(outputs.len());
+    for to_be_copied in outputs {
+        let name = to_be_copied
+            .from_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .with_context(|| format!("Output {} has no file name", to_be_copied.output))?;
+        if name == OUT_MANIFEST_NAME {
+            return Err(anyhow::anyhow!(
+                "output {} of target {} conflicts with the --out manifest",
+                to_be_copied.output,
+                to_be_copied.target
+            ));
+        }
+        if let Some(previous) = copied.insert(name, to_be_copied) {
+            return Err(anyhow::anyhow!(
+                "outputs {} of target {} and {} of target {} would both be copied to `{}` in --out",
+                previous.output,
+                previous.target,
+                to_be_copied.output,
+                to_be_copied.target,
+                name
+            ));
+        }
+
+        copy_output(to_be_copied, &dst.join(name))
+            .await
+            .with_context(|| format!("Copying output {}", to_be_copied.output))?;
+        manifest.push(OutManifestEntry {
+            target: &to_be_copied.target,
+            output: &to_be_copied.output,
+            path: name,
+        });
+    }
+
+    let mut manifest = serde_json::to_vec_pretty(&manifest)?;
+    manifest.push(b'\n');
+    tokio::fs::write(dst.join(OUT_MANIFEST_NAME), manifest)
+        .await
+        .context("Writing --out manifest")?;
+    Ok(())
+}
+
 fn copy_symlink<P: AsRef<AbsPath>, Q: AsRef<AbsPath>>(
     src_path: P,
     dst_path: Q,
@@ -513,6 +582,58 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_copy_outputs_to_dir() -> anyhow::Result<()> {
+        let src_dir = tempfile::tempdir()?;
+        let src_path = fs_util::canonicalize(src_dir.path())?;
+        std::fs::create_dir_all(src_path.as_path().join("a"))?;
+        std::fs::write(src_path.as_path().join("a/out.txt"), "some content")?;
+        std::fs::create_dir_all(src_path.as_path().join("b/lib"))?;
+        std::fs::write(src_path.as_path().join("b/lib/file"), "more content")?;
+        let output = |target: &str, path: &str, is_dir: bool| -> anyhow::Result<_> {
+            Ok(OutputToBeCopied {
+                target: target.to_owned(),
+                output: path.to_owned(),
+                from_path: src_path.join(ForwardRelativePath::new(path)?),
+                is_dir,
+            })
+        };
+
+        let dst_dir = tempfile::tempdir()?;
+        let dst_path = AbsPath::new(dst_dir.path())?.join("out");
+        let outputs = [
+            output("root//:a", "a/out.txt", false)?,
+            output("root//:b", "b/lib", true)?,
+        ];
+        copy_outputs_to_dir(&outputs, &dst_path).await?;
+
+        assert!(dst_path.join("out.txt").is_file());
+        assert!(dst_path.join("lib/file").is_file());
+        let manifest: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(dst_path.join(OUT_MANIFEST_NAME))?)?;
+        assert_eq!(
+            serde_json::json!([
+                {"target": "root//:a", "output": "a/out.txt", "path": "out.txt"},
+                {"target": "root//:b", "output": "b/lib", "path": "lib"},
+            ]),
+            manifest
+        );
+
+        let conflicting = [
+            output("root//:a", "a/out.txt", false)?,
+            output("root//:c", "a/out.txt", false)?,
+        ];
+        let err = copy_outputs_to_dir(&conflicting, &dst_path)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("would both be copied to `out.txt`")
+        );
+
+        Ok(())
+    }
+
     #[cfg(unix)]
     mod unix {
 
@@ -0,0 +1,378 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Shadow builds: build the same targets a second time in another configuration and report the
+//! outputs which differ from the primary build.
+//!
+//! The shadow build runs as a separate `buck2 build` in its own isolation dir (so it gets its own
+//! daemon and buck-out) at the lowest scheduling priority, concurrently with the primary build.
+//! Both builds write their output hashes, which are compared once both builds are done.
+
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fmt;
+
+use anyhow::Context;
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::common::target_cfg::TargetCfgWithUniverseOptions;
+use buck2_client_ctx::common::CommonBuildConfigurationOptions;
+use buck2_client_ctx::final_console::FinalConsole;
+use buck2_client_ctx::path_arg::PathArg;
+use buck2_common::process_priority::BackgroundPriority;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_path::AbsPath;
+use buck2_core::fs::paths::abs_path::AbsPathBuf;
+use serde::Deserialize;
+
+use crate::commands::build::nested_build::NestedBuild;
+
+#[derive(Debug, buck2_error::Error)]
+#[buck2(input)]
+enum ShadowBuildError {
+    #[error("Shadow build must use a different isolation dir than the primary build (`{0}`)")]
+    SameIsolationDir(String),
+}
+
+#[derive(Debug, clap::Parser, Default)]
+#[clap(next_help_heading = "Shadow Build Options")]
+pub(crate) struct ShadowBuildOptions {
+    /// Also build the targets in a low-priority shadow build with this config value added, and
+    /// report the outputs which differ from the primary build. Can be repeated.
+    #[clap(long, value_name = "SECTION.OPTION=VALUE")]
+    shadow_config: Vec<String>,
+
+    /// Like `--shadow-config`, but read the config values from a file. Cells cannot be redefined
+    /// this way.
+    #[clap(long, value_name = "PATH")]
+    shadow_config_file: Vec<String>,
+
+    /// Build the shadow build for this target platform instead of the primary one.
+    #[clap(long, value_name = "PLATFORM")]
+    shadow_target_platforms: Option<String>,
+
+    /// Isolation dir used by the shadow build's daemon.
+    #[clap(long, value_name = "NAME", default_value = "shadow")]
+    shadow_isolation_dir: String,
+
+    /// Write the divergence report to this file instead of printing it.
+    #[clap(long, value_name = "PATH")]
+    shadow_report: Option<PathArg>,
+}
+
+impl ShadowBuildOptions {
+    pub(crate) fn enabled(&self) -> bool {
+        !self.shadow_config.is_empty()
+            || !self.shadow_config_file.is_empty()
+            || self.shadow_target_platforms.is_some()
+    }
+}
+
+/// A shadow build which has been started and not yet compared against the primary build.
+pub(crate) struct ShadowBuild {
+    child: tokio::process::Child,
+    hashes_file: AbsPathBuf,
+    log_file: AbsPathBuf,
+    report: Option<AbsPathBuf>,
+    /// Where the primary build writes its output hashes if the user did not ask for them.
+    primary_hashes_file: AbsPathBuf,
+    /// Files removed with the shadow build.
+    temporary_files: Vec<AbsPathBuf>,
+}
+
+impl ShadowBuild {
+    pub(crate) fn spawn(
+        opts: &ShadowBuildOptions,
+        patterns: &[String],
+        config_opts: &CommonBuildConfigurationOptions,
+        target_cfg: &TargetCfgWithUniverseOptions,
+        ctx: &ClientCommandContext<'_>,
+    ) -> anyhow::Result<ShadowBuild> {
+        let paths = ctx.paths()?;
+        if paths.isolation.as_str() == opts.shadow_isolation_dir {
+            return Err(
+                ShadowBuildError::SameIsolationDir(opts.shadow_isolation_dir.clone()).into(),
+            );
+        }
+
+        let tmp_dir = paths.tmp_dir();
+        fs_util::create_dir_all(&tmp_dir)?;
+        let tmp_dir = tmp_dir.into_abs_path_buf();
+        let hashes_file = tmp_dir.join(format!("shadow-hashes-{}.json", ctx.trace_id));
+        let log_file = tmp_dir.join(format!("shadow-{}.log", ctx.trace_id));
+        let primary_hashes_file = tmp_dir.join(format!("primary-hashes-{}.json", ctx.trace_id));
+
+        let mut build = NestedBuild::new(
+            ctx.working_dir.path().as_path(),
+            Some(&opts.shadow_isolation_dir),
+            config_opts,
+            target_cfg,
+            &ctx.working_dir,
+        )?;
+        build
+            .args([OsStr::new("--output-hashes-file"), hashes_file.as_os_str()])
+            .add_config_values(&opts.shadow_config)
+            .add_config_files(&opts.shadow_config_file)
+            .target_platforms(opts.shadow_target_platforms.clone());
+
+        #[cfg(unix)]
+        {
+            // The shadow build should not slow down the primary one.
+            let priority = BackgroundPriority {
+                nice: 19,
+                ..BackgroundPriority::default()
+            };
+            unsafe {
+                build.command().pre_exec(move || {
+                    priority.apply_to_current_process();
+                    Ok(())
+                });
+            }
+        }
+
+        let child = build
+            .spawn(patterns, &log_file)
+            .context("Failed to start shadow build")?;
+        Ok(ShadowBuild {
+            child,
+            temporary_files: vec![hashes_file.clone()],
+            hashes_file,
+            log_file,
+            report: opts
+                .shadow_report
+                .as_ref()
+                .map(|p| p.resolve(&ctx.working_dir)),
+            primary_hashes_file,
+        })
+    }
+
+    /// A file for the output hashes of the primary build, when the user did not ask for them,
+    /// which is removed with the shadow build.
+    pub(crate) fn temporary_primary_hashes_file(&mut self) -> AbsPathBuf {
+        self.temporary_files.push(self.primary_hashes_file.clone());
+        self.primary_hashes_file.clone()
+    }
+
+    /// Wait for the shadow build and compare its outputs with the ones in `primary_hashes_file`.
+    ///
+    /// Neither a failing shadow build nor divergent outputs fail the primary build: they are only
+    /// reported as warnings.
+    pub(crate) async fn finish(
+        mut self,
+        console: &FinalConsole,
+        primary_hashes_file: &AbsPath,
+    ) -> anyhow::Result<()> {
+        console.print_stderr("Waiting for shadow build...")?;
+        let status = self.child.wait().await?;
+        if !status.success() {
+            console.print_warning(&format!(
+                "Shadow build failed ({}), see {}",
+                status,
+                self.log_file.display()
+            ))?;
+            return Ok(());
+        }
+
+        let primary = load_output_hashes(primary_hashes_file)?;
+        let shadow = load_output_hashes(&self.hashes_file)?;
+        let divergences = compare_output_hashes(&primary, &shadow);
+
+        if divergences.is_empty() {
+            console.print_success("Shadow build outputs match the primary build")?;
+            return Ok(());
+        }
+
+        match &self.report {
+            Some(report) => {
+                let mut contents = String::new();
+                for divergence in &divergences {
+                    contents.push_str(&divergence.to_string());
+                    contents.push('\n');
+                }
+                fs_util::write(report, contents)?;
+                console.print_warning(&format!(
+                    "Shadow build: {} outputs diverge from the primary build, see {}",
+                    divergences.len(),
+                    report.display()
+                ))?;
+            }
+            None => {
+                console.print_warning(&format!(
+                    "Shadow build: {} outputs diverge from the primary build:",
+                    divergences.len()
+                ))?;
+                for divergence in &divergences {
+                    console.print_warning(&format!("  {}", divergence))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ShadowBuild {
+    fn drop(&mut self) {
+        for file in &self.temporary_files {
+            // They are not written if the builds failed.
+            let _ignored = fs_util::remove_file(file);
+        }
+    }
+}
+
+/// An entry of the file written by `--output-hashes-file`.
+#[derive(Deserialize)]
+struct OutputHash {
+    path: String,
+    kind: String,
+    digest: Option<String>,
+    symlink_rel_path: Option<String>,
+    target: Option<String>,
+}
+
+impl OutputHash {
+    fn fingerprint(&self) -> String {
+        match (&self.digest, &self.symlink_rel_path, &self.target) {
+            (Some(digest), _, _) => format!("{} {}", self.kind, digest),
+            (None, Some(path), _) | (None, None, Some(path)) => format!("{} {}", self.kind, path),
+            (None, None, None) => self.kind.clone(),
+        }
+    }
+}
+
+/// Make output paths of the two builds comparable: drop the `buck-out/<isolation dir>` prefix,
+/// and the configuration hash components, which are expected to differ.
+fn normalize_output_path(path: &str) -> String {
+    let mut components: Vec<&str> = path.split('/').collect();
+    if components.first() == Some(&"buck-out") && components.len() > 2 {
+        components.drain(..2);
+    }
+    components.retain(|c| !is_configuration_hash(c));
+    components.join("/")
+}
+
+fn is_configuration_hash(component: &str) -> bool {
+    component.len() == 16 && component.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn load_output_hashes(path: &AbsPath) -> anyhow::Result<BTreeMap<String, String>> {
+    let contents = fs_util::read_to_string(path)?;
+    let hashes: Vec<OutputHash> = serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse output hashes `{}`", path.display()))?;
+    Ok(hashes
+        .into_iter()
+        .map(|h| (normalize_output_path(&h.path), h.fingerprint()))
+        .collect())
+}
+
+#[derive(Debug, PartialEq)]
+enum Divergence {
+    OnlyInPrimary(String),
+    OnlyInShadow(String),
+    Differs {
+        path: String,
+        primary: String,
+        shadow: String,
+    },
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Divergence::OnlyInPrimary(path) => write!(f, "- {}", path),
+            Divergence::OnlyInShadow(path) => write!(f, "+ {}", path),
+            Divergence::Differs {
+                path,
+                primary,
+                shadow,
+            } => write!(f, "~ {}: {} -> {}", path, primary, shadow),
+        }
+    }
+}
+
+fn compare_output_hashes(
+    primary: &BTreeMap<String, String>,
+    shadow: &BTreeMap<String, String>,
+) -> Vec<Divergence> {
+    let mut divergences = Vec::new();
+    for (path, fingerprint) in primary {
+        match shadow.get(path) {
+            None => divergences.push(Divergence::OnlyInPrimary(path.clone())),
+            Some(other) if other != fingerprint => divergences.push(Divergence::Differs {
+                path: path.clone(),
+                primary: fingerprint.clone(),
+                shadow: other.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+    for path in shadow.keys() {
+        if !primary.contains_key(path) {
+            divergences.push(Divergence::OnlyInShadow(path.clone()));
+        }
+    }
+    divergences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_output_path() {
+        assert_eq!(
+            "gen/root/foo/__bar__/out.txt",
+            normalize_output_path("buck-out/v2/gen/root/904931f735703749/foo/__bar__/out.txt")
+        );
+        assert_eq!(
+            "gen/root/foo/__bar__/out.txt",
+            normalize_output_path("buck-out/shadow/gen/root/213ed1b7ab869379/foo/__bar__/out.txt")
+        );
+        assert_eq!("foo/out.txt", normalize_output_path("foo/out.txt"));
+    }
+
+    #[test]
+    fn test_compare_output_hashes() {
+        let primary = BTreeMap::from([
+            ("a".to_owned(), "file 1:1".to_owned()),
+            ("b".to_owned(), "file 2:1".to_owned()),
+            ("c".to_owned(), "directory".to_owned()),
+        ]);
+        let shadow = BTreeMap::from([
+            ("a".to_owned(), "file 1:1".to_owned()),
+            ("b".to_owned(), "file 3:1".to_owned()),
+            ("d".to_owned(), "directory".to_owned()),
+        ]);
+        assert_eq!(
+            vec![
+                Divergence::Differs {
+                    path: "b".to_owned(),
+                    primary: "file 2:1".to_owned(),
+                    shadow: "file 3:1".to_owned(),
+                },
+                Divergence::OnlyInPrimary("c".to_owned()),
+                Divergence::OnlyInShadow("d".to_owned()),
+            ],
+            compare_output_hashes(&primary, &shadow)
+        );
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let hashes: Vec<OutputHash> = serde_json::from_str(
+            r#"[{"path":"a","kind":"file","digest":"fb19:20","digest_kind":"SHA1","is_exec":false},
+                {"path":"b","kind":"symlink","symlink_rel_path":"../a"},
+                {"path":"c","kind":"directory"}]"#,
+        )
+        .unwrap();
+        let fingerprints: Vec<String> = hashes.iter().map(|h| h.fingerprint()).collect();
+        assert_eq!(
+            vec!["file fb19:20", "symlink ../a", "directory"],
+            fingerprints
+        );
+    }
+}
@@ -0,0 +1,160 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! `--targets-file`: target patterns read from a JSON file, each optionally carrying options for
+//! the targets it matches.
+//!
+//! The file is a JSON list whose entries are either a pattern, or an object with a `pattern` and
+//! per-target options:
+//!
+//! ```json
+//! [
+//!   "//app/...",
+//!   "-//app/experimental/...",
+//!   {
+//!     "pattern": "//app:integration_test",
+//!     "test_args": ["--verbose"],
+//!     "timeout": "10m",
+//!     "required_labels": ["integration"]
+//!   }
+//! ]
+//! ```
+//!
+//! Patterns from the file are added after the ones given on the command line. `buck2 build` only
+//! uses the patterns; the options apply to `buck2 test`.
+
+use anyhow::Context;
+use buck2_cli_proto::TestTargetOptions;
+use buck2_client_ctx::path_arg::PathArg;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::working_dir::WorkingDir;
+use serde::Deserialize;
+
+#[derive(Debug, clap::Parser, Default)]
+pub(crate) struct TargetsFileOptions {
+    /// Read additional target patterns from this JSON file. Entries can be plain patterns, or
+    /// objects with a `pattern` and the `test_args`, `timeout` and `required_labels` to use when
+    /// testing the targets it matches.
+    #[clap(long, value_name = "PATH")]
+    targets_file: Option<PathArg>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum TargetsFileEntry {
+    Pattern(String),
+    WithOptions(PatternWithOptions),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PatternWithOptions {
+    pattern: String,
+    #[serde(default)]
+    test_args: Vec<String>,
+    /// A humantime duration, e.g. `5m 10s`.
+    #[serde(default)]
+    timeout: Option<String>,
+    #[serde(default)]
+    required_labels: Vec<String>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct TargetsFile {
+    pub(crate) patterns: Vec<String>,
+    /// Options for the entries that have any.
+    pub(crate) test_options: Vec<TestTargetOptions>,
+}
+
+impl TargetsFileOptions {
+    pub(crate) fn read(&self, working_dir: &WorkingDir) -> anyhow::Result<TargetsFile> {
+        let Some(path) = &self.targets_file else {
+            return Ok(TargetsFile::default());
+        };
+        let path = path.resolve(working_dir);
+        let contents = fs_util::read_to_string(&path)?;
+        parse_targets_file(&contents)
+            .with_context(|| format!("Error parsing targets file `{}`", path))
+    }
+}
+
+fn parse_targets_file(contents: &str) -> anyhow::Result<TargetsFile> {
+    let entries: Vec<TargetsFileEntry> = serde_json::from_str(contents)?;
+    let mut targets_file = TargetsFile::default();
+    for entry in entries {
+        let entry = match entry {
+            TargetsFileEntry::Pattern(pattern) => {
+                targets_file.patterns.push(pattern);
+                continue;
+            }
+            TargetsFileEntry::WithOptions(entry) => entry,
+        };
+        let timeout = entry
+            .timeout
+            .map(|t| {
+                let t = humantime::parse_duration(&t)
+                    .with_context(|| format!("Invalid timeout `{}` for `{}`", t, entry.pattern))?;
+                anyhow::Ok(prost_types::Duration::try_from(t)?)
+            })
+            .transpose()?;
+        if !entry.test_args.is_empty() || timeout.is_some() || !entry.required_labels.is_empty() {
+            targets_file.test_options.push(TestTargetOptions {
+                pattern: entry.pattern.clone(),
+                extra_args: entry.test_args,
+                timeout,
+                required_labels: entry.required_labels,
+            });
+        }
+        targets_file.patterns.push(entry.pattern);
+    }
+    Ok(targets_file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_targets_file() -> anyhow::Result<()> {
+        let parsed = parse_targets_file(
+            r#"[
+                "//app/...",
+                {"pattern": "//app:plain"},
+                {
+                    "pattern": "//app:test",
+                    "test_args": ["--verbose"],
+                    "timeout": "1m 30s",
+                    "required_labels": ["integration"]
+                }
+            ]"#,
+        )?;
+        assert_eq!(
+            parsed.patterns,
+            vec!["//app/...", "//app:plain", "//app:test"]
+        );
+        assert_eq!(
+            parsed.test_options,
+            vec![TestTargetOptions {
+                pattern: "//app:test".to_owned(),
+                extra_args: vec!["--verbose".to_owned()],
+                timeout: Some(prost_types::Duration {
+                    seconds: 90,
+                    nanos: 0
+                }),
+                required_labels: vec!["integration".to_owned()],
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_targets_file_rejects_unknown_options() {
+        assert!(parse_targets_file(r#"[{"pattern": "//app:test", "retries": 3}]"#).is_err());
+    }
+}
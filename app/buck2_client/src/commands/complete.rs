@@ -0,0 +1,267 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::collections::BTreeSet;
+
+use async_trait::async_trait;
+use buck2_cli_proto::targets_request;
+use buck2_cli_proto::targets_request::OutputFormat;
+use buck2_cli_proto::TargetsRequest;
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::common::target_cfg::TargetCfgOptions;
+use buck2_client_ctx::common::ui::CommonConsoleOptions;
+use buck2_client_ctx::common::CommonBuildConfigurationOptions;
+use buck2_client_ctx::common::CommonEventLogOptions;
+use buck2_client_ctx::common::CommonStarlarkOptions;
+use buck2_client_ctx::daemon::client::BuckdClientConnector;
+use buck2_client_ctx::daemon::client::StdoutPartialResultHandler;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_client_ctx::streaming::StreamingCommand;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
+use buck2_core::fs::paths::file_name::FileNameBuf;
+
+/// Print completions for a partially typed target pattern, one per line.
+///
+/// This is used by the scripts generated by `buck2 completion`; packages
+/// are found by listing directories, and target names are looked up through
+/// the daemon.
+#[derive(Debug, clap::Parser)]
+#[clap(name = "complete")]
+pub struct CompleteCommand {
+    /// Partial target pattern to complete, e.g. `//foo/ba` or `//foo/bar:ba`.
+    #[clap(long, value_name = "PARTIAL_PATTERN")]
+    target: String,
+}
+
+/// How a partially typed target pattern is completed.
+#[derive(Debug, PartialEq)]
+enum PartialPattern<'a> {
+    /// `pkg:na`: complete target names within `pkg`.
+    Target { package: &'a str, name: &'a str },
+    /// `cell//dir/su`: complete the next path segment below `dir`, which
+    /// includes its trailing `/` (or is empty for a pattern relative to the
+    /// working directory).
+    Package { dir: &'a str, segment: &'a str },
+}
+
+impl<'a> PartialPattern<'a> {
+    fn parse(partial: &'a str) -> Self {
+        if let Some((package, name)) = partial.split_once(':') {
+            PartialPattern::Target { package, name }
+        } else {
+            match partial.rfind('/') {
+                Some(i) => PartialPattern::Package {
+                    dir: &partial[..=i],
+                    segment: &partial[i + 1..],
+                },
+                None => PartialPattern::Package {
+                    dir: "",
+                    segment: partial,
+                },
+            }
+        }
+    }
+}
+
+/// Complete target names within `package` from the labels the daemon returned for `package:`.
+fn complete_targets<'b>(
+    package: &str,
+    name: &str,
+    labels: impl IntoIterator<Item = &'b str>,
+) -> BTreeSet<String> {
+    labels
+        .into_iter()
+        .filter_map(|label| label.rsplit_once(':'))
+        .filter(|(_, target)| target.starts_with(name))
+        .map(|(_, target)| format!("{package}:{target}"))
+        .collect()
+}
+
+/// Complete the next path segment below `dir` by listing `dir_path`, the directory it resolves
+/// to, rather than loading every package below it. Subdirectories with a buildfile complete to
+/// a package, and subdirectories which have subdirectories of their own complete to a directory.
+fn complete_packages(
+    dir: &str,
+    segment: &str,
+    dir_path: &AbsNormPath,
+    buildfiles: &[FileNameBuf],
+) -> anyhow::Result<BTreeSet<String>> {
+    let is_dir = |path: &AbsNormPath| fs_util::metadata(path).map_or(false, |m| m.is_dir());
+    let mut completions = BTreeSet::new();
+    for entry in fs_util::read_dir(dir_path)? {
+        let entry = entry?;
+        let Some(name) = entry.file_name().to_str().map(|n| n.to_owned()) else {
+            continue;
+        };
+        if !name.starts_with(segment) || (name.starts_with('.') && !segment.starts_with('.')) {
+            continue;
+        }
+        let path = entry.path();
+        if !is_dir(&path) {
+            continue;
+        }
+        if buildfiles
+            .iter()
+            .any(|buildfile| fs_util::metadata(path.join(buildfile)).map_or(false, |m| m.is_file()))
+        {
+            completions.insert(format!("{dir}{name}:"));
+        }
+        let has_subdirs = fs_util::read_dir(&path)
+            .map(|mut entries| entries.any(|e| e.map_or(false, |e| is_dir(&e.path()))))
+            .unwrap_or(false);
+        if has_subdirs {
+            completions.insert(format!("{dir}{name}/"));
+        }
+    }
+    Ok(completions)
+}
+
+#[async_trait]
+impl StreamingCommand for CompleteCommand {
+    const COMMAND_NAME: &'static str = "complete";
+
+    async fn exec_impl(
+        self,
+        buckd: &mut BuckdClientConnector,
+        matches: &clap::ArgMatches,
+        ctx: &mut ClientCommandContext<'_>,
+    ) -> ExitResult {
+        let completions = match PartialPattern::parse(&self.target) {
+            PartialPattern::Package { dir, segment } => {
+                let (dir_path, buildfiles) = ctx.immediate_config.resolve_package_dir(dir)?;
+                complete_packages(dir, segment, &dir_path, &buildfiles)?
+            }
+            PartialPattern::Target { package, name } => {
+                let context = Some(ctx.client_context(matches, &self)?);
+                let request = TargetsRequest {
+                    context,
+                    target_patterns: vec![format!("{package}:")],
+                    output_format: OutputFormat::Text as i32,
+                    targets: Some(targets_request::Targets::Other(targets_request::Other {
+                        target_hash_graph_type: targets_request::TargetHashGraphType::None as i32,
+                        keep_going: true,
+                        cached: true,
+                        ..Default::default()
+                    })),
+                    target_cfg: Some(TargetCfgOptions::default().target_cfg()),
+                    ..Default::default()
+                };
+
+                let response = buckd
+                    .with_flushing()
+                    .targets(
+                        request,
+                        ctx.stdin()
+                            .console_interaction_stream(CommonConsoleOptions::none_ref()),
+                        &mut StdoutPartialResultHandler,
+                    )
+                    .await??;
+                complete_targets(package, name, response.serialized_targets_output.lines())
+            }
+        };
+
+        for completion in completions {
+            buck2_client_ctx::println!("{}", completion)?;
+        }
+        ExitResult::success()
+    }
+
+    fn console_opts(&self) -> &CommonConsoleOptions {
+        CommonConsoleOptions::none_ref()
+    }
+
+    fn event_log_opts(&self) -> &CommonEventLogOptions {
+        CommonEventLogOptions::default_ref()
+    }
+
+    fn build_config_opts(&self) -> &CommonBuildConfigurationOptions {
+        CommonBuildConfigurationOptions::default_ref()
+    }
+
+    fn starlark_opts(&self) -> &CommonStarlarkOptions {
+        CommonStarlarkOptions::default_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
+
+    use super::*;
+
+    const LABELS: &[&str] = &["root//foo:lib", "root//foo:test"];
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            PartialPattern::Target {
+                package: "cell//foo",
+                name: "li"
+            },
+            PartialPattern::parse("cell//foo:li")
+        );
+        assert_eq!(
+            PartialPattern::Package {
+                dir: "//foo/",
+                segment: "ba"
+            },
+            PartialPattern::parse("//foo/ba")
+        );
+        assert_eq!(
+            PartialPattern::Package {
+                dir: "",
+                segment: "fo"
+            },
+            PartialPattern::parse("fo")
+        );
+    }
+
+    #[test]
+    fn test_complete_targets() {
+        assert_eq!(
+            vec!["//foo:lib", "//foo:test"],
+            Vec::from_iter(complete_targets("//foo", "", LABELS.iter().copied()))
+        );
+        assert_eq!(
+            vec![":test"],
+            Vec::from_iter(complete_targets("", "t", LABELS.iter().copied()))
+        );
+    }
+
+    #[test]
+    fn test_complete_packages() -> anyhow::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let root = AbsNormPath::new(tempdir.path())?;
+        for dir in ["foo/bar", "foo/baz/qux", "other", ".hidden/x"] {
+            fs_util::create_dir_all(root.join(ForwardRelativePath::new(dir)?))?;
+        }
+        for buildfile in ["foo/BUCK", "foo/bar/BUCK", "foo/baz/qux/BUCK", "other/BUCK"] {
+            fs_util::write(root.join(ForwardRelativePath::new(buildfile)?), "")?;
+        }
+        let buildfiles = [FileNameBuf::try_from("BUCK".to_owned())?];
+
+        let complete = |dir: &str, segment: &str| -> anyhow::Result<Vec<String>> {
+            let dir_path = root.join(ForwardRelativePath::new(
+                dir.trim_start_matches("//").trim_end_matches('/'),
+            )?);
+            Ok(Vec::from_iter(complete_packages(
+                dir,
+                segment,
+                &dir_path,
+                &buildfiles,
+            )?))
+        };
+
+        assert_eq!(vec!["//foo/", "//foo:", "//other:"], complete("//", "")?);
+        assert_eq!(vec!["//foo/bar:", "//foo/baz/"], complete("//foo/", "ba")?);
+        assert_eq!(vec!["//.hidden/"], complete("//", ".")?);
+        Ok(())
+    }
+}
@@ -20,6 +20,7 @@ use clap_complete::generate;
 enum Shell {
     Bash,
     Zsh,
+    Fish,
 }
 
 #[derive(Debug, clap::Parser)]
@@ -29,6 +30,10 @@ enum Shell {
 /// For a one-time setup, run one of the following commands:
 ///     source <(buck2 completion bash)
 ///     source <(buck2 completion zsh)
+///     buck2 completion fish | source
+///
+/// Words that look like target patterns (containing `//` or `:`) are
+/// completed by asking the daemon for packages and targets.
 pub struct CompletionCommand {
     #[clap(value_enum, help = "shell for which to generate completion script")]
     shell: Shell,
@@ -41,10 +46,13 @@ impl CompletionCommand {
         _matches: &clap::ArgMatches,
         _ctx: ClientCommandContext<'_>,
     ) -> ExitResult {
-        match self.shell {
-            Shell::Bash => print_completions(clap_complete::Shell::Bash, command),
-            Shell::Zsh => print_completions(clap_complete::Shell::Zsh, command),
+        let (shell, dynamic) = match self.shell {
+            Shell::Bash => (clap_complete::Shell::Bash, BASH_TARGET_COMPLETION),
+            Shell::Zsh => (clap_complete::Shell::Zsh, ZSH_TARGET_COMPLETION),
+            Shell::Fish => (clap_complete::Shell::Fish, FISH_TARGET_COMPLETION),
         };
+        print_completions(shell, command);
+        buck2_client_ctx::print!("{}", dynamic)?;
         ExitResult::success()
     }
 }
@@ -52,3 +60,50 @@ impl CompletionCommand {
 fn print_completions(shell: clap_complete::Shell, cmd: &mut Command) {
     generate(shell, cmd, cmd.get_name().to_owned(), &mut io::stdout());
 }
+
+/// Wraps the static `_buck2` function generated by clap.
+const BASH_TARGET_COMPLETION: &str = r#"
+_buck2_targets() {
+    local cur
+    if declare -F _get_comp_words_by_ref >/dev/null; then
+        _get_comp_words_by_ref -n =: cur
+    else
+        cur="${COMP_WORDS[COMP_CWORD]}"
+    fi
+    if [[ "$cur" == *//* || "$cur" == *:* ]]; then
+        local IFS=$'\n'
+        COMPREPLY=( $(buck2 complete --target="$cur" 2>/dev/null) )
+        if declare -F __ltrim_colon_completions >/dev/null; then
+            __ltrim_colon_completions "$cur"
+        fi
+        compopt -o nospace
+        return 0
+    fi
+    _buck2 "$@"
+}
+complete -F _buck2_targets -o bashdefault -o default buck2
+"#;
+
+/// Wraps the static `_buck2` function generated by clap.
+const ZSH_TARGET_COMPLETION: &str = r#"
+_buck2_targets() {
+    if [[ "$PREFIX" == *//* || "$PREFIX" == *:* ]]; then
+        local -a targets
+        targets=("${(@f)$(buck2 complete --target="$PREFIX" 2>/dev/null)}")
+        compadd -Q -S '' -- "${targets[@]}"
+        return
+    fi
+    _buck2 "$@"
+}
+compdef _buck2_targets buck2
+"#;
+
+const FISH_TARGET_COMPLETION: &str = r#"
+function __buck2_complete_targets
+    set -l cur (commandline -ct)
+    if string match -q -- '*//*' $cur; or string match -q -- '*:*' $cur
+        buck2 complete --target=$cur 2>/dev/null
+    end
+end
+complete -c buck2 -f -a '(__buck2_complete_targets)'
+"#;
@@ -14,13 +14,18 @@ use buck2_client_ctx::streaming::BuckSubcommand;
 use buck2_common::argv::Argv;
 use buck2_common::argv::SanitizedArgv;
 use chrome_trace::ChromeTraceCommand;
+use clock_check::ClockCheckCommand;
+use corrupt_scan::CorruptScanCommand;
 use crash::CrashCommand;
 use dice_dump::DiceDumpCommand;
 use file_status::FileStatusCommand;
 use flush_dep_files::FlushDepFilesCommand;
+use graph_diff::GraphDiffCommand;
 use heap_dump::HeapDumpCommand;
+use heap_profile::HeapProfileCommand;
 use internal_version::InternalVersionCommand;
 use materialize::MaterializeCommand;
+use why_miss::WhyMissCommand;
 
 use crate::commands::debug::allocative::AllocativeCommand;
 use crate::commands::debug::daemon_dir::DaemonDirCommand;
@@ -39,6 +44,8 @@ use crate::commands::log::debug_what_ran::DebugWhatRanCommand;
 mod allocative;
 mod allocator_stats;
 mod chrome_trace;
+mod clock_check;
+mod corrupt_scan;
 mod crash;
 mod daemon_dir;
 mod dice_dump;
@@ -46,7 +53,9 @@ mod eval;
 mod exe;
 mod file_status;
 mod flush_dep_files;
+mod graph_diff;
 mod heap_dump;
+mod heap_profile;
 mod internal_version;
 mod log_perf;
 mod materialize;
@@ -56,6 +65,7 @@ mod segfault;
 mod set_log_filter;
 mod trace_io;
 pub(crate) mod upload_re_logs;
+mod why_miss;
 
 #[derive(Debug, clap::Parser)]
 #[clap(about = "Hidden debug commands useful for testing buck2")]
@@ -67,6 +77,7 @@ pub enum DebugCommand {
     /// Useful to make sure that we're reporting it correctly.
     SegFault(SegfaultCommand),
     HeapDump(HeapDumpCommand),
+    HeapProfile(HeapProfileCommand),
     /// Dumps allocator stat
     AllocatorStats(AllocatorStatsCommand),
     /// Dump the DICE graph to a file and saves it to disk.
@@ -85,6 +96,13 @@ pub enum DebugCommand {
     UploadReLogs(UploadReLogsCommand),
     /// Validates that Buck2 and disk agree on the state of files.
     FileStatus(FileStatusCommand),
+    /// Checks that materialized artifacts in buck-out match the digests Buck2 recorded for them.
+    CorruptScan(CorruptScanCommand),
+    /// Checks for files with mtimes in the future, filesystems with coarse timestamps, and clock
+    /// skew with remote execution workers.
+    ClockCheck(ClockCheckCommand),
+    /// Shows what changed the digest of an action since its previous execution.
+    WhyMiss(WhyMissCommand),
     /// Shows the commands that buck ran
     #[clap(alias = "whatran", hide = true)]
     WhatRan(DebugWhatRanCommand),
@@ -103,6 +121,8 @@ pub enum DebugCommand {
     #[clap(subcommand)]
     Paranoid(ParanoidCommand),
     Eval(EvalCommand),
+    #[clap(subcommand)]
+    GraphDiff(GraphDiffCommand),
 }
 
 impl DebugCommand {
@@ -112,6 +132,7 @@ impl DebugCommand {
             DebugCommand::DiceDump(cmd) => cmd.exec(matches, ctx),
             DebugCommand::Crash(cmd) => cmd.exec(matches, ctx),
             DebugCommand::HeapDump(cmd) => cmd.exec(matches, ctx),
+            DebugCommand::HeapProfile(cmd) => cmd.exec(matches, ctx),
             DebugCommand::AllocatorStats(cmd) => cmd.exec(matches, ctx),
             DebugCommand::Replay(cmd) => cmd.exec(matches, ctx),
             DebugCommand::InternalVersion(cmd) => cmd.exec(matches, ctx),
@@ -126,11 +147,15 @@ impl DebugCommand {
             DebugCommand::Allocative(cmd) => cmd.exec(matches, ctx),
             DebugCommand::SetLogFilter(cmd) => cmd.exec(matches, ctx),
             DebugCommand::FileStatus(cmd) => cmd.exec(matches, ctx),
+            DebugCommand::CorruptScan(cmd) => cmd.exec(matches, ctx),
+            DebugCommand::ClockCheck(cmd) => cmd.exec(matches, ctx),
+            DebugCommand::WhyMiss(cmd) => cmd.exec(matches, ctx),
             DebugCommand::LogPerf(cmd) => cmd.exec(matches, ctx),
             DebugCommand::TraceIo(cmd) => cmd.exec(matches, ctx),
             DebugCommand::PersistEventLogs(cmd) => cmd.exec(matches, ctx),
             DebugCommand::Paranoid(cmd) => cmd.exec(matches, ctx),
             DebugCommand::Eval(cmd) => cmd.exec(matches, ctx),
+            DebugCommand::GraphDiff(cmd) => cmd.exec(matches, ctx),
         }
     }
 
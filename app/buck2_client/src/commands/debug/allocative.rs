@@ -55,6 +55,7 @@ impl StreamingCommand for AllocativeCommand {
                 AllocativeRequest {
                     context: Some(context),
                     output_path: self.output.resolve(&ctx.working_dir).into_string()?,
+                    heap_profile: false,
                 },
                 ctx.stdin().console_interaction_stream(self.console_opts()),
                 &mut NoPartialResultHandler,
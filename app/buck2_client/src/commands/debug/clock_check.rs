@@ -0,0 +1,101 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::fmt::Write;
+
+use async_trait::async_trait;
+use buck2_cli_proto::new_generic::ClockCheckRequest;
+use buck2_cli_proto::new_generic::NewGenericRequest;
+use buck2_cli_proto::new_generic::NewGenericResponse;
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::common::ui::CommonConsoleOptions;
+use buck2_client_ctx::common::CommonBuildConfigurationOptions;
+use buck2_client_ctx::common::CommonCommandOptions;
+use buck2_client_ctx::common::CommonEventLogOptions;
+use buck2_client_ctx::common::CommonStarlarkOptions;
+use buck2_client_ctx::daemon::client::BuckdClientConnector;
+use buck2_client_ctx::exit_result::ExitCode;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_client_ctx::streaming::StreamingCommand;
+
+#[derive(Debug, clap::Parser)]
+pub struct ClockCheckCommand {
+    /// Directories to check the mtimes of files in, relative to the project root. Defaults to the
+    /// whole project, except `buck-out` and version control directories.
+    #[clap(value_name = "PATH")]
+    paths: Vec<String>,
+
+    /// Stop after checking the mtimes of this many files.
+    #[clap(long, default_value = "1000000")]
+    max_files: u64,
+
+    #[clap(flatten)]
+    common_opts: CommonCommandOptions,
+}
+
+#[async_trait]
+impl StreamingCommand for ClockCheckCommand {
+    const COMMAND_NAME: &'static str = "clock-check";
+
+    async fn exec_impl(
+        self,
+        buckd: &mut BuckdClientConnector,
+        matches: &clap::ArgMatches,
+        ctx: &mut ClientCommandContext<'_>,
+    ) -> ExitResult {
+        let context = ctx.client_context(matches, &self)?;
+        let resp = buckd
+            .with_flushing()
+            .new_generic(
+                context,
+                NewGenericRequest::ClockCheck(ClockCheckRequest {
+                    paths: self.paths,
+                    max_files: self.max_files,
+                }),
+                ctx.stdin()
+                    .console_interaction_stream(&self.common_opts.console_opts),
+            )
+            .await??;
+        let NewGenericResponse::ClockCheck(resp) = resp else {
+            return ExitResult::bail("Unexpected response type from generic command");
+        };
+
+        let mut stdout = String::new();
+        for warning in &resp.warnings {
+            writeln!(stdout, "{}: {}", warning.kind, warning.message)?;
+        }
+
+        buck2_client_ctx::eprintln!(
+            "Checked the mtimes of {} file(s), found {} problem(s)",
+            resp.checked_files,
+            resp.warnings.len()
+        )?;
+        if resp.warnings.is_empty() {
+            ExitResult::success().with_stdout(stdout.into_bytes())
+        } else {
+            ExitResult::status(ExitCode::UserError).with_stdout(stdout.into_bytes())
+        }
+    }
+
+    fn console_opts(&self) -> &CommonConsoleOptions {
+        &self.common_opts.console_opts
+    }
+
+    fn event_log_opts(&self) -> &CommonEventLogOptions {
+        &self.common_opts.event_log_opts
+    }
+
+    fn build_config_opts(&self) -> &CommonBuildConfigurationOptions {
+        &self.common_opts.config_opts
+    }
+
+    fn starlark_opts(&self) -> &CommonStarlarkOptions {
+        &self.common_opts.starlark_opts
+    }
+}
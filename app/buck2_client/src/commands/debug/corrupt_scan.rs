@@ -0,0 +1,114 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::fmt::Write;
+
+use async_trait::async_trait;
+use buck2_cli_proto::new_generic::CorruptScanRequest;
+use buck2_cli_proto::new_generic::NewGenericRequest;
+use buck2_cli_proto::new_generic::NewGenericResponse;
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::common::ui::CommonConsoleOptions;
+use buck2_client_ctx::common::CommonBuildConfigurationOptions;
+use buck2_client_ctx::common::CommonCommandOptions;
+use buck2_client_ctx::common::CommonEventLogOptions;
+use buck2_client_ctx::common::CommonStarlarkOptions;
+use buck2_client_ctx::daemon::client::BuckdClientConnector;
+use buck2_client_ctx::exit_result::ExitCode;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_client_ctx::streaming::StreamingCommand;
+
+#[derive(Debug, clap::Parser)]
+pub struct CorruptScanCommand {
+    /// Fraction of materialized artifacts to check, between 0 and 1.
+    #[clap(long, default_value = "1.0")]
+    sample_rate: f64,
+
+    /// Delete corrupted artifacts, and download the ones that came from the CAS again.
+    #[clap(long)]
+    repair: bool,
+
+    #[clap(flatten)]
+    common_opts: CommonCommandOptions,
+}
+
+#[async_trait]
+impl StreamingCommand for CorruptScanCommand {
+    const COMMAND_NAME: &'static str = "corrupt-scan";
+
+    fn existing_only() -> bool {
+        true
+    }
+
+    async fn exec_impl(
+        self,
+        buckd: &mut BuckdClientConnector,
+        matches: &clap::ArgMatches,
+        ctx: &mut ClientCommandContext<'_>,
+    ) -> ExitResult {
+        let context = ctx.client_context(matches, &self)?;
+        let resp = buckd
+            .with_flushing()
+            .new_generic(
+                context,
+                NewGenericRequest::CorruptScan(CorruptScanRequest {
+                    sample_rate: self.sample_rate,
+                    repair: self.repair,
+                }),
+                ctx.stdin()
+                    .console_interaction_stream(&self.common_opts.console_opts),
+            )
+            .await??;
+        let NewGenericResponse::CorruptScan(resp) = resp else {
+            return ExitResult::bail("Unexpected response type from generic command");
+        };
+
+        let mut unrepaired = 0;
+        let mut stdout = String::new();
+        for artifact in &resp.corrupt {
+            let status = match (artifact.repaired, &artifact.repair_error) {
+                (Some(true), _) => "REPAIRED".to_owned(),
+                (Some(false), Some(e)) => format!("UNREPAIRED ({})", e),
+                (Some(false), None) => "UNREPAIRED".to_owned(),
+                (None, _) => "CORRUPT".to_owned(),
+            };
+            if artifact.repaired != Some(true) {
+                unrepaired += 1;
+            }
+            writeln!(stdout, "{} {}: {}", status, artifact.path, artifact.reason)?;
+        }
+
+        buck2_client_ctx::eprintln!(
+            "Checked {} artifact(s), found {} corrupted",
+            resp.checked,
+            resp.corrupt.len()
+        )?;
+        if unrepaired == 0 {
+            ExitResult::success().with_stdout(stdout.into_bytes())
+        } else {
+            ExitResult::status(ExitCode::UserError).with_stdout(stdout.into_bytes())
+        }
+    }
+
+    fn console_opts(&self) -> &CommonConsoleOptions {
+        &self.common_opts.console_opts
+    }
+
+    fn event_log_opts(&self) -> &CommonEventLogOptions {
+        &self.common_opts.event_log_opts
+    }
+
+    fn build_config_opts(&self) -> &CommonBuildConfigurationOptions {
+        &self.common_opts.config_opts
+    }
+
+    fn starlark_opts(&self) -> &CommonStarlarkOptions {
+        &self.common_opts.starlark_opts
+    }
+}
@@ -31,6 +31,17 @@ pub struct FileStatusCommand {
     #[clap(long, short, help = "Print all matches")]
     show_matches: bool,
 
+    /// Instead of checking for mismatches, print for each path a JSON line with what the daemon
+    /// knows about it: whether it is ignored, its metadata on disk and in DICE, and the last
+    /// event the file watcher saw for it.
+    #[clap(long, conflicts_with = "show_matches")]
+    report: bool,
+
+    /// Include in the report the DICE keys which depend on each path. This walks the whole DICE
+    /// graph, so it can be slow on a large daemon.
+    #[clap(long, requires = "report")]
+    dependents: bool,
+
     #[clap(flatten)]
     common_opts: CommonCommandOptions,
 }
@@ -59,6 +70,8 @@ impl StreamingCommand for FileStatusCommand {
                         .paths
                         .try_map(|x| x.resolve(&ctx.working_dir).into_string())?,
                     show_matches: self.show_matches,
+                    report: self.report,
+                    dependents: self.dependents,
                 },
                 ctx.stdin()
                     .console_interaction_stream(&self.common_opts.console_opts),
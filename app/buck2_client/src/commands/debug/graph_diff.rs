@@ -0,0 +1,575 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::hash::Hash;
+use std::hash::Hasher;
+
+use async_trait::async_trait;
+use buck2_cli_proto::AqueryRequest;
+use buck2_cli_proto::CqueryRequest;
+use buck2_cli_proto::QueryOutputFormat;
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::command_outcome::CommandOutcome;
+use buck2_client_ctx::common::target_cfg::TargetCfgOptions;
+use buck2_client_ctx::common::ui::CommonConsoleOptions;
+use buck2_client_ctx::common::CommonBuildConfigurationOptions;
+use buck2_client_ctx::common::CommonCommandOptions;
+use buck2_client_ctx::common::CommonEventLogOptions;
+use buck2_client_ctx::common::CommonStarlarkOptions;
+use buck2_client_ctx::daemon::client::BuckdClientConnector;
+use buck2_client_ctx::events_ctx::PartialResultCtx;
+use buck2_client_ctx::events_ctx::PartialResultHandler;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_client_ctx::path_arg::PathArg;
+use buck2_client_ctx::streaming::BuckSubcommand;
+use buck2_client_ctx::streaming::StreamingCommand;
+use buck2_core::fs::fs_util;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// The attribute of a configured target listing its dependencies.
+const DEPS_ATTR: &str = "buck.deps";
+
+/// Compare two configured graphs, to review the impact of a buckconfig, prelude or
+/// source change before landing it.
+///
+/// Dump the graph in each state with `dump` (for example before and after the change,
+/// or with different `-c` flags), then `compare` the two dumps. Reported are targets
+/// added and removed, attribute changes, dependency edge changes, and changes of the
+/// actions the targets register.
+#[derive(Debug, clap::Subcommand)]
+pub enum GraphDiffCommand {
+    Dump(DumpCommand),
+    Compare(CompareCommand),
+}
+
+impl GraphDiffCommand {
+    pub fn exec(self, matches: &clap::ArgMatches, ctx: ClientCommandContext<'_>) -> ExitResult {
+        let matches = matches.subcommand().expect("subcommand not found").1;
+        match self {
+            Self::Dump(cmd) => cmd.exec(matches, ctx),
+            Self::Compare(cmd) => cmd.exec(ctx),
+        }
+    }
+}
+
+/// Write the configured graph of the given targets and their deps to a file.
+#[derive(Debug, clap::Parser)]
+pub struct DumpCommand {
+    /// Target patterns whose transitive configured graph to dump.
+    #[clap(value_name = "TARGET_PATTERNS", required = true)]
+    patterns: Vec<String>,
+
+    /// File to write the graph to.
+    #[clap(long, short = 'o', value_name = "PATH")]
+    output: PathArg,
+
+    /// Do not dump the actions of the targets, which requires analysing them.
+    #[clap(long)]
+    no_actions: bool,
+
+    #[clap(flatten)]
+    target_cfg: TargetCfgOptions,
+
+    #[clap(flatten)]
+    common_opts: CommonCommandOptions,
+}
+
+/// Compare two graphs written by `dump`.
+#[derive(Debug, clap::Parser)]
+pub struct CompareCommand {
+    /// The graph before the change.
+    #[clap(value_name = "BEFORE")]
+    before: PathArg,
+
+    /// The graph after the change.
+    #[clap(value_name = "AFTER")]
+    after: PathArg,
+
+    /// Kinds of changes to report. Can be repeated. Defaults to all kinds.
+    #[clap(long, value_enum)]
+    kind: Vec<ChangeKind>,
+
+    /// Only report targets and actions whose label contains this string.
+    #[clap(long, value_name = "STRING")]
+    filter: Option<String>,
+
+    /// Do not report changes of this attribute. Can be repeated.
+    #[clap(long, value_name = "ATTRIBUTE")]
+    ignore_attr: Vec<String>,
+
+    /// Match targets and actions by their unconfigured label, ignoring the configuration
+    /// in parentheses. Use this when the change affects configuration hashes.
+    #[clap(long)]
+    ignore_configurations: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[clap(rename_all = "snake_case")]
+enum ChangeKind {
+    Targets,
+    Attrs,
+    Edges,
+    Actions,
+}
+
+/// Attributes of a node, as output by `cquery` or `aquery` as JSON.
+type Attrs = BTreeMap<String, serde_json::Value>;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct GraphDump {
+    targets: BTreeMap<String, Attrs>,
+    actions: BTreeMap<String, Attrs>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Change {
+    TargetAdded(String),
+    TargetRemoved(String),
+    AttrChanged {
+        target: String,
+        attr: String,
+        before: Option<serde_json::Value>,
+        after: Option<serde_json::Value>,
+    },
+    EdgeAdded(String, String),
+    EdgeRemoved(String, String),
+    ActionAdded(String),
+    ActionRemoved(String),
+    ActionChanged {
+        action: String,
+        before: u64,
+        after: u64,
+        attrs: Vec<String>,
+    },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn value(v: &Option<serde_json::Value>) -> String {
+            v.as_ref().map_or("<unset>".to_owned(), |v| v.to_string())
+        }
+        match self {
+            Change::TargetAdded(t) => write!(f, "+ target {t}"),
+            Change::TargetRemoved(t) => write!(f, "- target {t}"),
+            Change::AttrChanged {
+                target,
+                attr,
+                before,
+                after,
+            } => write!(
+                f,
+                "~ attr {target} {attr}: {} -> {}",
+                value(before),
+                value(after)
+            ),
+            Change::EdgeAdded(from, to) => write!(f, "+ edge {from} -> {to}"),
+            Change::EdgeRemoved(from, to) => write!(f, "- edge {from} -> {to}"),
+            Change::ActionAdded(a) => write!(f, "+ action {a}"),
+            Change::ActionRemoved(a) => write!(f, "- action {a}"),
+            Change::ActionChanged {
+                action,
+                before,
+                after,
+                attrs,
+            } => write!(
+                f,
+                "~ action {action}: digest {before:016x} -> {after:016x} ({})",
+                attrs.join(", ")
+            ),
+        }
+    }
+}
+
+/// Remove configurations, e.g. ` (root//platforms:linux#0123456789abcdef)`, from a label.
+/// Other parenthesized parts, such as the identifier of an action, are kept.
+fn strip_configurations(label: &str) -> String {
+    let mut res = String::with_capacity(label.len());
+    let mut rest = label;
+    while let Some(start) = rest.find(" (") {
+        let Some(len) = rest[start..].find(')') else {
+            break;
+        };
+        let group = &rest[start + 2..start + len];
+        res.push_str(&rest[..start]);
+        if !(group.contains('#') || group.starts_with('<')) {
+            res.push_str(&rest[start..=start + len]);
+        }
+        rest = &rest[start + len + 1..];
+    }
+    res.push_str(rest);
+    res
+}
+
+fn digest(attrs: &Attrs) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(attrs)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    hasher.finish()
+}
+
+fn deps(attrs: &Attrs) -> BTreeSet<&str> {
+    match attrs.get(DEPS_ATTR) {
+        Some(serde_json::Value::Array(deps)) => deps.iter().filter_map(|d| d.as_str()).collect(),
+        _ => BTreeSet::new(),
+    }
+}
+
+impl CompareCommand {
+    fn wants(&self, kind: ChangeKind) -> bool {
+        self.kind.is_empty() || self.kind.contains(&kind)
+    }
+
+    fn normalize(&self, dump: GraphDump) -> GraphDump {
+        if !self.ignore_configurations {
+            return dump;
+        }
+        let strip = |nodes: BTreeMap<String, Attrs>| {
+            nodes
+                .into_iter()
+                .map(|(label, mut attrs)| {
+                    if let Some(serde_json::Value::Array(deps)) = attrs.get_mut(DEPS_ATTR) {
+                        for dep in deps {
+                            if let Some(s) = dep.as_str() {
+                                *dep = serde_json::Value::String(strip_configurations(s));
+                            }
+                        }
+                    }
+                    (strip_configurations(&label), attrs)
+                })
+                .collect()
+        };
+        GraphDump {
+            targets: strip(dump.targets),
+            actions: strip(dump.actions),
+        }
+    }
+
+    fn diff(&self, before: &GraphDump, after: &GraphDump) -> Vec<Change> {
+        let selected = |label: &str| match &self.filter {
+            Some(filter) => label.contains(filter.as_str()),
+            None => true,
+        };
+        let mut changes = Vec::new();
+
+        for (label, before_attrs) in &before.targets {
+            if !selected(label) {
+                continue;
+            }
+            let Some(after_attrs) = after.targets.get(label) else {
+                if self.wants(ChangeKind::Targets) {
+                    changes.push(Change::TargetRemoved(label.clone()));
+                }
+                continue;
+            };
+            if self.wants(ChangeKind::Attrs) {
+                let names: BTreeSet<&String> =
+                    before_attrs.keys().chain(after_attrs.keys()).collect();
+                for name in names {
+                    if name == DEPS_ATTR || self.ignore_attr.contains(name) {
+                        continue;
+                    }
+                    let (b, a) = (before_attrs.get(name), after_attrs.get(name));
+                    if b != a {
+                        changes.push(Change::AttrChanged {
+                            target: label.clone(),
+                            attr: name.clone(),
+                            before: b.cloned(),
+                            after: a.cloned(),
+                        });
+                    }
+                }
+            }
+            if self.wants(ChangeKind::Edges) {
+                let (b, a) = (deps(before_attrs), deps(after_attrs));
+                for dep in a.difference(&b) {
+                    changes.push(Change::EdgeAdded(label.clone(), (*dep).to_owned()));
+                }
+                for dep in b.difference(&a) {
+                    changes.push(Change::EdgeRemoved(label.clone(), (*dep).to_owned()));
+                }
+            }
+        }
+        if self.wants(ChangeKind::Targets) {
+            for label in after.targets.keys() {
+                if selected(label) && !before.targets.contains_key(label) {
+                    changes.push(Change::TargetAdded(label.clone()));
+                }
+            }
+        }
+
+        if self.wants(ChangeKind::Actions) {
+            for (action, before_attrs) in &before.actions {
+                if !selected(action) {
+                    continue;
+                }
+                match after.actions.get(action) {
+                    None => changes.push(Change::ActionRemoved(action.clone())),
+                    Some(after_attrs) if after_attrs != before_attrs => {
+                        let attrs = before_attrs
+                            .keys()
+                            .chain(after_attrs.keys())
+                            .collect::<BTreeSet<_>>()
+                            .into_iter()
+                            .filter(|k| before_attrs.get(*k) != after_attrs.get(*k))
+                            .cloned()
+                            .collect();
+                        changes.push(Change::ActionChanged {
+                            action: action.clone(),
+                            before: digest(before_attrs),
+                            after: digest(after_attrs),
+                            attrs,
+                        });
+                    }
+                    Some(_) => {}
+                }
+            }
+            for action in after.actions.keys() {
+                if selected(action) && !before.actions.contains_key(action) {
+                    changes.push(Change::ActionAdded(action.clone()));
+                }
+            }
+        }
+        changes
+    }
+
+    fn read(&self, path: &PathArg, ctx: &ClientCommandContext<'_>) -> anyhow::Result<GraphDump> {
+        let path = path.resolve(&ctx.working_dir);
+        let dump = serde_json::from_slice(&fs_util::read(&path)?)?;
+        Ok(self.normalize(dump))
+    }
+
+    fn exec(self, ctx: ClientCommandContext<'_>) -> ExitResult {
+        let before = self.read(&self.before, &ctx)?;
+        let after = self.read(&self.after, &ctx)?;
+        let changes = self.diff(&before, &after);
+        for change in &changes {
+            buck2_client_ctx::println!("{}", change)?;
+        }
+        buck2_client_ctx::eprintln!("{} changes", changes.len())?;
+        ExitResult::success()
+    }
+}
+
+/// Receive StdoutBytes, just capture them.
+#[derive(Default)]
+struct CaptureStdout {
+    buf: Vec<u8>,
+}
+
+#[async_trait]
+impl PartialResultHandler for CaptureStdout {
+    type PartialResult = buck2_cli_proto::StdoutBytes;
+
+    async fn handle_partial_result(
+        &mut self,
+        _ctx: PartialResultCtx<'_, '_>,
+        partial_res: Self::PartialResult,
+    ) -> anyhow::Result<()> {
+        self.buf.extend(partial_res.data);
+        Ok(())
+    }
+}
+
+impl DumpCommand {
+    fn query(&self) -> String {
+        let literals: Vec<String> = self.patterns.iter().map(|p| format!("\"{}\"", p)).collect();
+        format!("deps(set({}))", literals.join(" "))
+    }
+}
+
+#[async_trait]
+impl StreamingCommand for DumpCommand {
+    const COMMAND_NAME: &'static str = "graph-diff-dump";
+
+    async fn exec_impl(
+        self,
+        buckd: &mut BuckdClientConnector,
+        matches: &clap::ArgMatches,
+        ctx: &mut ClientCommandContext<'_>,
+    ) -> ExitResult {
+        let context = ctx.client_context(matches, &self)?;
+        let mut dump = GraphDump::default();
+
+        let mut capture = CaptureStdout::default();
+        let outcome = buckd
+            .with_flushing()
+            .cquery(
+                CqueryRequest {
+                    query: self.query(),
+                    query_args: Vec::new(),
+                    context: Some(context.clone()),
+                    output_attributes: vec![String::new()],
+                    target_universe: Vec::new(),
+                    target_cfg: Some(self.target_cfg.target_cfg()),
+                    show_providers: false,
+                    list_providers: false,
+                    provider_fields: Vec::new(),
+                    at_invocation: String::new(),
+                    unstable_output_format: QueryOutputFormat::Json as i32,
+                },
+                None,
+                &mut capture,
+            )
+            .await?;
+        if let CommandOutcome::Failure(result) = outcome {
+            return result;
+        }
+        dump.targets = serde_json::from_slice(&capture.buf)?;
+
+        if !self.no_actions {
+            let mut capture = CaptureStdout::default();
+            let outcome = buckd
+                .with_flushing()
+                .aquery(
+                    AqueryRequest {
+                        query: self.query(),
+                        query_args: Vec::new(),
+                        target_cfg: Some(self.target_cfg.target_cfg()),
+                        context: Some(context),
+                        output_attributes: vec![String::new()],
+                        unstable_output_format: QueryOutputFormat::Json as i32,
+                    },
+                    None,
+                    &mut capture,
+                )
+                .await?;
+            if let CommandOutcome::Failure(result) = outcome {
+                return result;
+            }
+            dump.actions = serde_json::from_slice(&capture.buf)?;
+        }
+
+        let output = self.output.resolve(&ctx.working_dir);
+        fs_util::write(&output, serde_json::to_vec_pretty(&dump)?)?;
+        buck2_client_ctx::eprintln!(
+            "Wrote {} targets and {} actions to `{}`",
+            dump.targets.len(),
+            dump.actions.len(),
+            output
+        )?;
+        ExitResult::success()
+    }
+
+    fn console_opts(&self) -> &CommonConsoleOptions {
+        &self.common_opts.console_opts
+    }
+
+    fn event_log_opts(&self) -> &CommonEventLogOptions {
+        &self.common_opts.event_log_opts
+    }
+
+    fn build_config_opts(&self) -> &CommonBuildConfigurationOptions {
+        &self.common_opts.config_opts
+    }
+
+    fn starlark_opts(&self) -> &CommonStarlarkOptions {
+        &self.common_opts.starlark_opts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compare(args: &[&str]) -> CompareCommand {
+        use clap::Parser;
+        CompareCommand::parse_from(["compare", "before.json", "after.json"].iter().chain(args))
+    }
+
+    fn dump(targets: &[(&str, serde_json::Value)], actions: &[(&str, &str)]) -> GraphDump {
+        GraphDump {
+            targets: targets
+                .iter()
+                .map(|(l, attrs)| {
+                    (
+                        (*l).to_owned(),
+                        serde_json::from_value(attrs.clone()).unwrap(),
+                    )
+                })
+                .collect(),
+            actions: actions
+                .iter()
+                .map(|(l, cmd)| {
+                    (
+                        (*l).to_owned(),
+                        [("cmd".to_owned(), serde_json::json!(cmd))]
+                            .into_iter()
+                            .collect(),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_strip_configurations() {
+        assert_eq!(
+            "root//foo:bar",
+            strip_configurations("root//foo:bar (root//platforms:linux#0123456789abcdef)")
+        );
+        assert_eq!(
+            "root//foo:bar (cxx_compile a.c)",
+            strip_configurations("root//foo:bar (cfg#1234) (cxx_compile a.c)")
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let before = dump(
+            &[
+                (
+                    "//:a",
+                    serde_json::json!({"srcs": ["a.c"], "buck.deps": ["//:b"]}),
+                ),
+                ("//:b", serde_json::json!({})),
+            ],
+            &[("//:a (compile)", "cc a.c")],
+        );
+        let after = dump(
+            &[
+                (
+                    "//:a",
+                    serde_json::json!({"srcs": ["a.c", "b.c"], "buck.deps": ["//:c"]}),
+                ),
+                ("//:c", serde_json::json!({})),
+            ],
+            &[("//:a (compile)", "cc -O2 a.c")],
+        );
+
+        let changes: Vec<String> = compare(&[])
+            .diff(&before, &after)
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        assert_eq!(
+            vec![
+                "~ attr //:a srcs: [\"a.c\"] -> [\"a.c\",\"b.c\"]",
+                "+ edge //:a -> //:c",
+                "- edge //:a -> //:b",
+                "- target //:b",
+                "+ target //:c",
+            ],
+            changes[..5]
+        );
+        assert!(changes[5].starts_with("~ action //:a (compile): digest "));
+        assert!(changes[5].ends_with("(cmd)"));
+
+        let changes = compare(&["--kind", "targets", "--filter", ":c"]).diff(&before, &after);
+        assert_eq!(vec![Change::TargetAdded("//:c".to_owned())], changes);
+
+        let changes = compare(&["--kind", "attrs", "--ignore-attr", "srcs"]).diff(&before, &after);
+        assert!(changes.is_empty());
+    }
+}
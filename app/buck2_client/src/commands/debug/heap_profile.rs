@@ -0,0 +1,87 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use async_trait::async_trait;
+use buck2_cli_proto::AllocativeRequest;
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::common::ui::CommonConsoleOptions;
+use buck2_client_ctx::common::CommonBuildConfigurationOptions;
+use buck2_client_ctx::common::CommonEventLogOptions;
+use buck2_client_ctx::common::CommonStarlarkOptions;
+use buck2_client_ctx::daemon::client::BuckdClientConnector;
+use buck2_client_ctx::daemon::client::NoPartialResultHandler;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_client_ctx::path_arg::PathArg;
+use buck2_client_ctx::streaming::StreamingCommand;
+
+/// Profile the memory retained by the daemon.
+///
+/// Writes to the output directory an allocative flamegraph (`flamegraph.svg`), the retained size
+/// aggregated by type (`retained_by_type.txt`) and, if the daemon was started with
+/// `MALLOC_CONF=prof:true`, a jemalloc heap profile (`jemalloc.heap`).
+#[derive(Debug, clap::Parser)]
+pub struct HeapProfileCommand {
+    /// Output directory path for profile data.
+    ///
+    /// Directory will be created if it does not exist.
+    #[clap(
+        long,
+        short = 'o',
+        value_name = "PATH",
+        default_value = "heap-profile-out"
+    )]
+    output: PathArg,
+}
+
+#[async_trait]
+impl StreamingCommand for HeapProfileCommand {
+    const COMMAND_NAME: &'static str = "heap_profile";
+
+    fn existing_only() -> bool {
+        true
+    }
+
+    async fn exec_impl(
+        self,
+        buckd: &mut BuckdClientConnector,
+        _matches: &clap::ArgMatches,
+        ctx: &mut ClientCommandContext<'_>,
+    ) -> ExitResult {
+        let context = ctx.empty_client_context("debug-heap-profile")?;
+        buckd
+            .with_flushing()
+            .allocative(
+                AllocativeRequest {
+                    context: Some(context),
+                    output_path: self.output.resolve(&ctx.working_dir).into_string()?,
+                    heap_profile: true,
+                },
+                ctx.stdin().console_interaction_stream(self.console_opts()),
+                &mut NoPartialResultHandler,
+            )
+            .await??;
+        ExitResult::success()
+    }
+
+    fn console_opts(&self) -> &CommonConsoleOptions {
+        CommonConsoleOptions::default_ref()
+    }
+
+    fn event_log_opts(&self) -> &CommonEventLogOptions {
+        CommonEventLogOptions::default_ref()
+    }
+
+    fn build_config_opts(&self) -> &CommonBuildConfigurationOptions {
+        CommonBuildConfigurationOptions::default_ref()
+    }
+
+    fn starlark_opts(&self) -> &CommonStarlarkOptions {
+        CommonStarlarkOptions::default_ref()
+    }
+}
@@ -0,0 +1,126 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::fmt::Write;
+
+use async_trait::async_trait;
+use buck2_cli_proto::new_generic::NewGenericRequest;
+use buck2_cli_proto::new_generic::NewGenericResponse;
+use buck2_cli_proto::new_generic::WhyMissRequest;
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::common::ui::CommonConsoleOptions;
+use buck2_client_ctx::common::CommonBuildConfigurationOptions;
+use buck2_client_ctx::common::CommonCommandOptions;
+use buck2_client_ctx::common::CommonEventLogOptions;
+use buck2_client_ctx::common::CommonStarlarkOptions;
+use buck2_client_ctx::daemon::client::BuckdClientConnector;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_client_ctx::streaming::StreamingCommand;
+
+/// Compares an action to the previous version of it executed by the daemon, and prints what
+/// changed its digest, and so caused it to miss the action cache.
+///
+/// Only actions executed since the daemon started, with `build.record_action_digests = true`, can
+/// be compared. Only digests are recorded, so this tells which argument, variable or part of the
+/// action changed, but not its old and new values.
+#[derive(Debug, clap::Parser)]
+pub struct WhyMissCommand {
+    /// The action digest (`hash:size`, as printed by `buck2 log what-ran`), or the label of the
+    /// target which owns the action.
+    #[clap(value_name = "ACTION")]
+    action: String,
+
+    /// Select the action with this category, e.g. `cxx_compile`.
+    #[clap(long)]
+    category: Option<String>,
+
+    /// Select the action with this identifier, e.g. the source file it compiles.
+    #[clap(long)]
+    identifier: Option<String>,
+
+    #[clap(flatten)]
+    common_opts: CommonCommandOptions,
+}
+
+#[async_trait]
+impl StreamingCommand for WhyMissCommand {
+    const COMMAND_NAME: &'static str = "why-miss";
+
+    async fn exec_impl(
+        self,
+        buckd: &mut BuckdClientConnector,
+        matches: &clap::ArgMatches,
+        ctx: &mut ClientCommandContext<'_>,
+    ) -> ExitResult {
+        let context = ctx.client_context(matches, &self)?;
+        let resp = buckd
+            .with_flushing()
+            .new_generic(
+                context,
+                NewGenericRequest::WhyMiss(WhyMissRequest {
+                    action: self.action,
+                    category: self.category,
+                    identifier: self.identifier,
+                }),
+                ctx.stdin()
+                    .console_interaction_stream(&self.common_opts.console_opts),
+            )
+            .await??;
+        let NewGenericResponse::WhyMiss(resp) = resp else {
+            return ExitResult::bail("Unexpected response type from generic command");
+        };
+
+        let Some(previous_digest) = resp.previous_digest else {
+            buck2_client_ctx::eprintln!(
+                "No previous version of {} ({}) was executed since the daemon started",
+                resp.action,
+                resp.digest
+            )?;
+            return ExitResult::success();
+        };
+        buck2_client_ctx::eprintln!(
+            "Comparing {} ({}) to its previous version ({})",
+            resp.action,
+            resp.digest,
+            previous_digest
+        )?;
+
+        let mut stdout = String::new();
+        for d in &resp.differences {
+            let name = if d.name.is_empty() {
+                d.kind.clone()
+            } else {
+                format!("{} {}", d.kind, d.name)
+            };
+            match (&d.old, &d.new) {
+                (Some(old), Some(new)) => writeln!(stdout, "{}: {} -> {}", name, old, new)?,
+                (None, Some(new)) => writeln!(stdout, "{}: added {}", name, new)?,
+                (Some(old), None) => writeln!(stdout, "{}: removed {}", name, old)?,
+                (None, None) => writeln!(stdout, "{}: changed", name)?,
+            }
+        }
+        ExitResult::success().with_stdout(stdout.into_bytes())
+    }
+
+    fn console_opts(&self) -> &CommonConsoleOptions {
+        &self.common_opts.console_opts
+    }
+
+    fn event_log_opts(&self) -> &CommonEventLogOptions {
+        &self.common_opts.event_log_opts
+    }
+
+    fn build_config_opts(&self) -> &CommonBuildConfigurationOptions {
+        &self.common_opts.config_opts
+    }
+
+    fn starlark_opts(&self) -> &CommonStarlarkOptions {
+        &self.common_opts.starlark_opts
+    }
+}
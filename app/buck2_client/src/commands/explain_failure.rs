@@ -0,0 +1,507 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::io;
+use std::io::IsTerminal;
+use std::io::Write;
+use std::process::Command;
+
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
+use buck2_data::action_execution_end;
+use buck2_data::command_execution;
+use buck2_data::command_execution_kind;
+use buck2_event_log::stream_value::StreamValue;
+use buck2_event_observer::display::display_action_identity;
+use buck2_event_observer::display::sanitize_output_colors;
+use buck2_event_observer::display::TargetDisplayOptions;
+use crossterm::cursor;
+use crossterm::event;
+use crossterm::event::Event;
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEventKind;
+use crossterm::execute;
+use crossterm::queue;
+use crossterm::style::Print;
+use crossterm::style::PrintStyledContent;
+use crossterm::style::Stylize;
+use crossterm::terminal;
+use futures::TryStreamExt;
+
+use crate::commands::log::options::EventLogOptions;
+
+/// Interactively explore the actions that failed in a build.
+///
+/// Lists the failed actions of the selected invocation. Select one to see its
+/// full stderr, the command it ran, its environment and digests, or press `r`
+/// to re-run it locally from the project root.
+///
+/// When stdout is not a terminal, the failures are printed instead.
+#[derive(Debug, clap::Parser)]
+pub struct ExplainFailureCommand {
+    #[clap(flatten)]
+    event_log: EventLogOptions,
+}
+
+impl ExplainFailureCommand {
+    pub fn exec(self, _matches: &clap::ArgMatches, ctx: ClientCommandContext<'_>) -> ExitResult {
+        let project_root = ctx.paths()?.project_root().root().to_owned();
+        let failures = ctx.with_runtime(|ctx| async move {
+            let log_path = self.event_log.get(&ctx).await?;
+            let (invocation, mut events) = log_path
+                .unpack_stream_filtered(|index| {
+                    index.kinds.iter().any(|k| k == "SpanEnd.ActionExecution")
+                })
+                .await?;
+
+            buck2_client_ctx::eprintln!(
+                "Showing failures from: {}",
+                invocation.display_command_line()
+            )?;
+
+            let mut failures = Vec::new();
+            while let Some(event) = events.try_next().await? {
+                if let StreamValue::Event(event) = event {
+                    if let Some(buck2_data::buck_event::Data::SpanEnd(end)) = &event.data {
+                        if let Some(buck2_data::span_end_event::Data::ActionExecution(action)) =
+                            &end.data
+                        {
+                            if action.failed {
+                                failures.push(FailedAction::from_action(action)?);
+                            }
+                        }
+                    }
+                }
+            }
+            anyhow::Ok(failures)
+        })?;
+
+        if failures.is_empty() {
+            buck2_client_ctx::eprintln!("No actions failed")?;
+        } else if io::stdout().is_terminal() {
+            Explorer::new(failures).run(&project_root)?;
+        } else {
+            buck2_client_ctx::stdio::print_with_writer::<anyhow::Error, _>(|w| {
+                for failure in &failures {
+                    for line in failure.detail_lines() {
+                        writeln!(w, "{}", line)?;
+                    }
+                    writeln!(w)?;
+                }
+                Ok(())
+            })?;
+        }
+        ExitResult::success()
+    }
+}
+
+/// A command that can be run again on this host.
+struct LocalCommand {
+    argv: Vec<String>,
+    env: Vec<(String, String)>,
+}
+
+impl LocalCommand {
+    fn new(argv: &[String], env: &[buck2_data::EnvironmentEntry]) -> Self {
+        Self {
+            argv: argv.to_vec(),
+            env: env
+                .iter()
+                .map(|e| (e.key.clone(), e.value.clone()))
+                .collect(),
+        }
+    }
+}
+
+/// What the event log tells about one failed action.
+pub(crate) struct FailedAction {
+    /// Owning target and action name.
+    pub(crate) identity: String,
+    /// Why the action failed, on one line.
+    pub(crate) reason: String,
+    pub(crate) stderr: String,
+    action_digest: Option<String>,
+    /// Present if the last command attempted ran locally.
+    local: Option<LocalCommand>,
+    /// Inputs materialized for a failed remote command.
+    inputs: Vec<String>,
+}
+
+impl FailedAction {
+    pub(crate) fn from_action(action: &buck2_data::ActionExecutionEnd) -> anyhow::Result<Self> {
+        let identity = display_action_identity(
+            action.key.as_ref(),
+            action.name.as_ref(),
+            TargetDisplayOptions::for_log(),
+        )?;
+        // The command shown to the user is always the last one attempted.
+        let command = action.commands.last();
+        let details = command.and_then(|c| c.details.as_ref());
+
+        let reason = match &action.error {
+            Some(action_execution_end::Error::Unknown(message)) => {
+                message.lines().next().unwrap_or_default().to_owned()
+            }
+            Some(action_execution_end::Error::MissingOutputs(missing)) => missing.message.clone(),
+            Some(action_execution_end::Error::CommandExecutionError(_)) | None => {
+                match command.and_then(|c| c.status.as_ref()) {
+                    Some(command_execution::Status::Failure(_)) => {
+                        match details.and_then(|d| d.signed_exit_code) {
+                            Some(code) => format!("Command failed with exit code {}", code),
+                            None => "Command failed".to_owned(),
+                        }
+                    }
+                    Some(command_execution::Status::Timeout(_)) => "Command timed out".to_owned(),
+                    Some(command_execution::Status::Error(error)) => {
+                        format!("Error during {}: {}", error.stage, error.error)
+                    }
+                    _ => "Action failed".to_owned(),
+                }
+            }
+        };
+
+        let mut stderr = details
+            .map(|d| sanitize_output_colors(d.stderr.as_bytes()))
+            .unwrap_or_default();
+        if stderr.is_empty() {
+            if let Some(action_execution_end::Error::Unknown(message)) = &action.error {
+                stderr = message.clone();
+            }
+        }
+
+        let (action_digest, local, inputs) = match details
+            .and_then(|d| d.command_kind.as_ref())
+            .and_then(|k| k.command.as_ref())
+        {
+            Some(command_execution_kind::Command::LocalCommand(c)) => (
+                Some(c.action_digest.clone()),
+                Some(LocalCommand::new(&c.argv, &c.env)),
+                Vec::new(),
+            ),
+            Some(command_execution_kind::Command::WorkerCommand(c)) => {
+                let argv: Vec<String> = c.fallback_exe.iter().chain(&c.argv).cloned().collect();
+                (
+                    Some(c.action_digest.clone()),
+                    Some(LocalCommand::new(&argv, &c.env)),
+                    Vec::new(),
+                )
+            }
+            Some(command_execution_kind::Command::WorkerInitCommand(c)) => {
+                (None, Some(LocalCommand::new(&c.argv, &c.env)), Vec::new())
+            }
+            Some(command_execution_kind::Command::RemoteCommand(c)) => (
+                Some(c.action_digest.clone()),
+                None,
+                c.materialized_inputs_for_failed.clone(),
+            ),
+            Some(command_execution_kind::Command::OmittedLocalCommand(c)) => {
+                (Some(c.action_digest.clone()), None, Vec::new())
+            }
+            None => (None, None, Vec::new()),
+        };
+
+        Ok(Self {
+            identity,
+            reason,
+            stderr,
+            action_digest,
+            local,
+            inputs,
+        })
+    }
+
+    fn detail_lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("Action: {}", self.identity),
+            format!("Reason: {}", self.reason),
+        ];
+        if let Some(digest) = &self.action_digest {
+            lines.push(format!("Action digest: {}", digest));
+        }
+        match &self.local {
+            Some(local) => {
+                lines.push(format!(
+                    "Command: {}",
+                    shlex::try_join(local.argv.iter().map(|a| a.as_str()))
+                        .unwrap_or_else(|_| local.argv.join(" "))
+                ));
+                if !local.env.is_empty() {
+                    lines.push("Environment:".to_owned());
+                    for (key, value) in &local.env {
+                        lines.push(format!("  {}={}", key, value));
+                    }
+                }
+            }
+            None => lines.push("Command: <not run locally>".to_owned()),
+        }
+        if !self.inputs.is_empty() {
+            lines.push("Materialized inputs:".to_owned());
+            for input in &self.inputs {
+                lines.push(format!("  {}", input));
+            }
+        }
+        lines.push(String::new());
+        if self.stderr.is_empty() {
+            lines.push("<stderr is empty>".to_owned());
+        } else {
+            lines.push("Stderr:".to_owned());
+            lines.extend(self.stderr.lines().map(ToOwned::to_owned));
+        }
+        lines
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum View {
+    List,
+    Details { scroll: usize },
+}
+
+#[derive(Debug, PartialEq)]
+enum Step {
+    Continue,
+    Rerun,
+    Quit,
+}
+
+/// State of the interactive failure explorer.
+struct Explorer {
+    failures: Vec<FailedAction>,
+    selected: usize,
+    view: View,
+    /// Message shown in the footer until the next key press.
+    status: Option<String>,
+}
+
+/// Restores the terminal when dropped.
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enter() -> anyhow::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(RawTerminal)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ignored = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ignored = terminal::disable_raw_mode();
+    }
+}
+
+impl Explorer {
+    fn new(failures: Vec<FailedAction>) -> Self {
+        Self {
+            failures,
+            selected: 0,
+            view: View::List,
+            status: None,
+        }
+    }
+
+    fn run(mut self, project_root: &AbsNormPath) -> anyhow::Result<()> {
+        let mut raw = Some(RawTerminal::enter()?);
+        loop {
+            let (width, height) = terminal::size()?;
+            self.render(width as usize, height as usize)?;
+            let key = match event::read()? {
+                Event::Key(key) if key.kind != KeyEventKind::Release => key.code,
+                _ => continue,
+            };
+            match self.on_key(key, (height as usize).saturating_sub(2)) {
+                Step::Continue => {}
+                Step::Quit => return Ok(()),
+                Step::Rerun => match &self.failures[self.selected].local {
+                    Some(local) if !local.argv.is_empty() => {
+                        drop(raw.take());
+                        let res = rerun(local, project_root);
+                        raw = Some(RawTerminal::enter()?);
+                        res?;
+                    }
+                    _ => self.status = Some("This action did not run locally".to_owned()),
+                },
+            }
+        }
+    }
+
+    fn on_key(&mut self, key: KeyCode, page: usize) -> Step {
+        self.status = None;
+        let last = self.failures.len().saturating_sub(1);
+        match (&mut self.view, key) {
+            (_, KeyCode::Char('q')) => return Step::Quit,
+            (_, KeyCode::Char('r')) => return Step::Rerun,
+            (View::List, KeyCode::Up | KeyCode::Char('k')) => {
+                self.selected = self.selected.saturating_sub(1)
+            }
+            (View::List, KeyCode::Down | KeyCode::Char('j')) => {
+                self.selected = (self.selected + 1).min(last)
+            }
+            (View::List, KeyCode::PageUp) => self.selected = self.selected.saturating_sub(page),
+            (View::List, KeyCode::PageDown) => self.selected = (self.selected + page).min(last),
+            (View::List, KeyCode::Enter) => self.view = View::Details { scroll: 0 },
+            (View::List, KeyCode::Esc) => return Step::Quit,
+            (View::Details { .. }, KeyCode::Esc | KeyCode::Backspace | KeyCode::Left) => {
+                self.view = View::List
+            }
+            (View::Details { scroll }, KeyCode::Up | KeyCode::Char('k')) => {
+                *scroll = scroll.saturating_sub(1)
+            }
+            (View::Details { scroll }, KeyCode::Down | KeyCode::Char('j')) => *scroll += 1,
+            (View::Details { scroll }, KeyCode::PageUp) => *scroll = scroll.saturating_sub(page),
+            (View::Details { scroll }, KeyCode::PageDown) => *scroll += page,
+            _ => {}
+        }
+        Step::Continue
+    }
+
+    fn render(&mut self, width: usize, height: usize) -> anyhow::Result<()> {
+        let body_height = height.saturating_sub(2);
+        let (header, body, highlight) = match &mut self.view {
+            View::List => {
+                let offset = (self.selected + 1).saturating_sub(body_height);
+                let body = self
+                    .failures
+                    .iter()
+                    .map(|f| format!("{}: {}", f.identity, f.reason))
+                    .skip(offset)
+                    .take(body_height)
+                    .collect::<Vec<_>>();
+                (
+                    format!(
+                        "{} failed actions  [up/down] select  [enter] details  [r] re-run  [q] quit",
+                        self.failures.len()
+                    ),
+                    body,
+                    Some(self.selected - offset),
+                )
+            }
+            View::Details { scroll } => {
+                let lines = self.failures[self.selected].detail_lines();
+                *scroll = (*scroll).min(lines.len().saturating_sub(body_height));
+                (
+                    format!(
+                        "Failure {} of {}  [up/down] scroll  [esc] back  [r] re-run  [q] quit",
+                        self.selected + 1,
+                        self.failures.len()
+                    ),
+                    lines.into_iter().skip(*scroll).take(body_height).collect(),
+                    None,
+                )
+            }
+        };
+
+        let mut stdout = io::stdout().lock();
+        queue!(
+            stdout,
+            terminal::Clear(terminal::ClearType::All),
+            cursor::MoveTo(0, 0),
+            PrintStyledContent(truncate(&header, width).reverse())
+        )?;
+        for (i, line) in body.iter().enumerate() {
+            queue!(stdout, cursor::MoveTo(0, (i + 1) as u16))?;
+            let line = truncate(line, width);
+            if Some(i) == highlight {
+                queue!(stdout, PrintStyledContent(line.bold().reverse()))?;
+            } else {
+                queue!(stdout, Print(line))?;
+            }
+        }
+        if let Some(status) = &self.status {
+            queue!(
+                stdout,
+                cursor::MoveTo(0, height.saturating_sub(1) as u16),
+                PrintStyledContent(truncate(status, width).yellow())
+            )?;
+        }
+        stdout.flush()?;
+        Ok(())
+    }
+}
+
+fn truncate(line: &str, width: usize) -> String {
+    line.replace('\t', "    ").chars().take(width).collect()
+}
+
+fn rerun(command: &LocalCommand, project_root: &AbsNormPath) -> anyhow::Result<()> {
+    buck2_client_ctx::eprintln!(
+        "Running in {}: {}",
+        project_root,
+        shlex::try_join(command.argv.iter().map(|a| a.as_str()))?
+    )?;
+    let status = Command::new(&command.argv[0])
+        .args(&command.argv[1..])
+        .envs(command.env.iter().map(|(k, v)| (k, v)))
+        .current_dir(project_root)
+        .status();
+    match status {
+        Ok(status) => buck2_client_ctx::eprintln!("Command exited with {}", status)?,
+        Err(e) => buck2_client_ctx::eprintln!("Failed to run command: {}", e)?,
+    }
+    buck2_client_ctx::eprintln!("Press enter to return")?;
+    io::stdin().read_line(&mut String::new())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure(identity: &str) -> FailedAction {
+        FailedAction {
+            identity: identity.to_owned(),
+            reason: "Command failed with exit code 1".to_owned(),
+            stderr: "error: oops\n".to_owned(),
+            action_digest: Some("abc:10".to_owned()),
+            local: Some(LocalCommand {
+                argv: vec!["cc".to_owned(), "a b.c".to_owned()],
+                env: vec![("FOO".to_owned(), "bar".to_owned())],
+            }),
+            inputs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_detail_lines() {
+        assert_eq!(
+            vec![
+                "Action: root//:a (cxx_compile a.c)",
+                "Reason: Command failed with exit code 1",
+                "Action digest: abc:10",
+                "Command: cc 'a b.c'",
+                "Environment:",
+                "  FOO=bar",
+                "",
+                "Stderr:",
+                "error: oops",
+            ],
+            failure("root//:a (cxx_compile a.c)").detail_lines()
+        );
+    }
+
+    #[test]
+    fn test_navigation() {
+        let mut explorer = Explorer::new(vec![failure("a"), failure("b")]);
+        assert_eq!(Step::Continue, explorer.on_key(KeyCode::Up, 10));
+        assert_eq!(0, explorer.selected);
+        explorer.on_key(KeyCode::Down, 10);
+        explorer.on_key(KeyCode::Down, 10);
+        assert_eq!(1, explorer.selected);
+        explorer.on_key(KeyCode::Enter, 10);
+        assert_eq!(View::Details { scroll: 0 }, explorer.view);
+        explorer.on_key(KeyCode::PageDown, 10);
+        assert_eq!(View::Details { scroll: 10 }, explorer.view);
+        explorer.on_key(KeyCode::Esc, 10);
+        assert_eq!(View::List, explorer.view);
+        assert_eq!(Step::Rerun, explorer.on_key(KeyCode::Char('r'), 10));
+        assert_eq!(Step::Quit, explorer.on_key(KeyCode::Esc, 10));
+    }
+}
@@ -33,6 +33,14 @@ pub struct InstallCommand {
     )]
     installer_debug: bool,
 
+    #[clap(
+        long,
+        help = "Keep running after the install, and install again when the daemon notices that a file \
+        in the project changed, until interrupted. Installers which support it are kept running, and \
+        only sent the artifacts which changed"
+    )]
+    hot_reload: bool,
+
     #[clap(flatten)]
     android_install_opts: AndroidInstallOptions,
 
@@ -178,6 +186,13 @@ impl StreamingCommand for InstallCommand {
             extra_run_args.push("-k".to_owned());
         }
 
+        if self.hot_reload {
+            self.common_opts
+                .console_opts
+                .final_console()
+                .print_stderr("Installing on changes, press Ctrl-C to stop")?;
+        }
+
         let response = buckd
             .with_flushing()
             .install(
@@ -188,6 +203,7 @@ impl StreamingCommand for InstallCommand {
                     build_opts: Some(self.build_opts.to_proto()),
                     installer_run_args: extra_run_args,
                     installer_debug: self.installer_debug,
+                    hot_reload: self.hot_reload,
                 },
                 ctx.stdin()
                     .console_interaction_stream(&self.common_opts.console_opts),
@@ -10,12 +10,14 @@
 mod critical_path;
 pub(crate) mod debug_replay;
 pub(crate) mod debug_what_ran;
+mod html_report;
 pub(crate) mod options;
 pub(crate) mod path_log;
 mod replay;
 mod show_log;
 mod show_user_log;
 mod summary;
+mod upload;
 mod what_cmd;
 mod what_failed;
 mod what_materialized;
@@ -88,9 +90,11 @@ pub enum LogCommand {
     WhatMaterialized(what_materialized::WhatMaterializedCommand),
     WhatUploaded(what_uploaded::WhatUploadedCommand),
     CriticalPath(critical_path::CriticalPathCommand),
+    HtmlReport(html_report::HtmlReportCommand),
     Replay(replay::ReplayCommand),
     ShowUser(show_user_log::ShowUserLogCommand),
     Summary(summary::SummaryCommand),
+    Upload(upload::UploadLogCommand),
 }
 
 impl LogCommand {
@@ -105,9 +109,11 @@ impl LogCommand {
             Self::WhatMaterialized(cmd) => cmd.exec(matches, ctx),
             Self::WhatUploaded(cmd) => cmd.exec(matches, ctx),
             Self::CriticalPath(cmd) => cmd.exec(matches, ctx),
+            Self::HtmlReport(cmd) => cmd.exec(matches, ctx),
             Self::Replay(cmd) => cmd.exec(matches, ctx),
             Self::ShowUser(cmd) => cmd.exec(matches, ctx),
             Self::Summary(cmd) => cmd.exec(matches, ctx),
+            Self::Upload(cmd) => cmd.exec(matches, ctx),
         }
     }
 
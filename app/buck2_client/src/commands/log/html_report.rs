@@ -0,0 +1,427 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_client_ctx::path_arg::PathArg;
+use buck2_core::fs::fs_util;
+use buck2_data::ActionExecutionKind;
+use buck2_event_log::stream_value::StreamValue;
+use buck2_event_observer::display;
+use buck2_event_observer::display::TargetDisplayOptions;
+use buck2_event_observer::fmt_duration::fmt_duration;
+use tokio_stream::StreamExt;
+
+use crate::commands::explain_failure::FailedAction;
+use crate::commands::log::options::EventLogOptions;
+
+/// Maximum number of actions drawn on the timeline, longest first.
+const MAX_TIMELINE_ACTIONS: usize = 2000;
+
+/// Write a self-contained HTML report for a selected build.
+///
+/// The report shows a timeline of the actions that ran, failed actions with
+/// their stderr, cache statistics and the critical path. It needs no other
+/// files, so it can be attached to CI runs.
+#[derive(Debug, clap::Parser)]
+pub struct HtmlReportCommand {
+    #[clap(flatten)]
+    event_log: EventLogOptions,
+
+    /// File to write the report to, rather than sending it to stdout.
+    #[clap(long, short = 'o', value_name = "PATH")]
+    output: Option<PathArg>,
+}
+
+impl HtmlReportCommand {
+    pub fn exec(self, _matches: &clap::ArgMatches, ctx: ClientCommandContext<'_>) -> ExitResult {
+        let Self { event_log, output } = self;
+        let output = output.map(|p| p.resolve(&ctx.working_dir));
+
+        let html = ctx.with_runtime(|ctx| async move {
+            let log_path = event_log.get(&ctx).await?;
+            let (invocation, mut events) = log_path.unpack_stream().await?;
+
+            let mut report = Report::default();
+            while let Some(event) = events.try_next().await? {
+                match event {
+                    StreamValue::Event(event) => report.event(&event)?,
+                    StreamValue::Result(..) | StreamValue::PartialResult(..) => {}
+                }
+            }
+            report.render(
+                &invocation.display_command_line(),
+                &invocation.trace_id.to_string(),
+            )
+        })?;
+
+        match output {
+            Some(path) => fs_util::write(path, html)?,
+            None => buck2_client_ctx::stdio::print_with_writer::<anyhow::Error, _>(|w| {
+                w.write_all(html.as_bytes())?;
+                Ok(())
+            })?,
+        }
+        ExitResult::success()
+    }
+}
+
+struct TimelineAction {
+    identity: String,
+    start: SystemTime,
+    duration: Duration,
+    kind: ActionExecutionKind,
+    failed: bool,
+}
+
+struct CriticalPathRow {
+    kind: &'static str,
+    name: String,
+    duration: Option<Duration>,
+}
+
+#[derive(Default)]
+struct Report {
+    start: Option<SystemTime>,
+    end: Option<SystemTime>,
+    actions: Vec<TimelineAction>,
+    kinds: BTreeMap<String, u64>,
+    failures: Vec<FailedAction>,
+    critical_path: Vec<CriticalPathRow>,
+}
+
+impl Report {
+    fn event(&mut self, event: &buck2_data::BuckEvent) -> anyhow::Result<()> {
+        let Some(timestamp) = event
+            .timestamp
+            .clone()
+            .and_then(|t| SystemTime::try_from(t).ok())
+        else {
+            return Ok(());
+        };
+        self.start = Some(self.start.map_or(timestamp, |s| s.min(timestamp)));
+        self.end = Some(self.end.map_or(timestamp, |e| e.max(timestamp)));
+
+        match &event.data {
+            Some(buck2_data::buck_event::Data::SpanEnd(end)) => {
+                if let Some(buck2_data::span_end_event::Data::ActionExecution(action)) = &end.data {
+                    let duration = end
+                        .duration
+                        .clone()
+                        .and_then(|d| Duration::try_from(d).ok())
+                        .unwrap_or_default();
+                    let kind = ActionExecutionKind::from_i32(action.execution_kind)
+                        .unwrap_or(ActionExecutionKind::NotSet);
+                    *self.kinds.entry(format!("{:?}", kind)).or_default() += 1;
+                    self.actions.push(TimelineAction {
+                        identity: display::display_action_identity(
+                            action.key.as_ref(),
+                            action.name.as_ref(),
+                            TargetDisplayOptions::for_log(),
+                        )?,
+                        start: timestamp.checked_sub(duration).unwrap_or(timestamp),
+                        duration,
+                        kind,
+                        failed: action.failed,
+                    });
+                    if action.failed {
+                        self.failures.push(FailedAction::from_action(action)?);
+                    }
+                }
+            }
+            Some(buck2_data::buck_event::Data::Instant(instant)) => {
+                if let Some(buck2_data::instant_event::Data::BuildGraphInfo(info)) = &instant.data {
+                    self.critical_path = info
+                        .critical_path2
+                        .iter()
+                        .filter_map(|entry| critical_path_row(entry).transpose())
+                        .collect::<anyhow::Result<_>>()?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn render(mut self, command_line: &str, trace_id: &str) -> anyhow::Result<String> {
+        let start = self.start.unwrap_or(SystemTime::UNIX_EPOCH);
+        let total = self
+            .end
+            .and_then(|end| end.duration_since(start).ok())
+            .unwrap_or_default();
+
+        let mut html = String::new();
+        write!(
+            html,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>buck2 build report {trace_id}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n\
+             <h1>buck2 build report</h1>\n<p><code>{command}</code></p>\n\
+             <p>Build ID: <code>{trace_id}</code> &middot; Duration: {duration} &middot; \
+             {actions} actions &middot; {failures} failed</p>\n",
+            trace_id = escape(trace_id),
+            command = escape(command_line),
+            duration = fmt_duration(total, 1.0),
+            actions = self.actions.len(),
+            failures = self.failures.len(),
+        )?;
+
+        html.push_str("<h2>Failures</h2>\n");
+        if self.failures.is_empty() {
+            html.push_str("<p>No actions failed.</p>\n");
+        }
+        for failure in &self.failures {
+            writeln!(
+                html,
+                "<details><summary><b>{}</b>: {}</summary><pre>{}</pre></details>",
+                escape(&failure.identity),
+                escape(&failure.reason),
+                escape(if failure.stderr.is_empty() {
+                    "<stderr is empty>"
+                } else {
+                    &failure.stderr
+                }),
+            )?;
+        }
+
+        html.push_str("<h2>Cache statistics</h2>\n<table>\n<tr><th>Execution kind</th><th>Actions</th></tr>\n");
+        for (kind, count) in &self.kinds {
+            writeln!(html, "<tr><td>{}</td><td>{}</td></tr>", escape(kind), count)?;
+        }
+        let cached = [
+            ActionExecutionKind::ActionCache,
+            ActionExecutionKind::RemoteDepFileCache,
+            ActionExecutionKind::LocalDepFile,
+        ]
+        .iter()
+        .map(|k| self.kinds.get(&format!("{:?}", k)).copied().unwrap_or(0))
+        .sum::<u64>();
+        let ran = cached
+            + [
+                ActionExecutionKind::Local,
+                ActionExecutionKind::LocalWorker,
+                ActionExecutionKind::Remote,
+            ]
+            .iter()
+            .map(|k| self.kinds.get(&format!("{:?}", k)).copied().unwrap_or(0))
+            .sum::<u64>();
+        html.push_str("</table>\n");
+        if ran > 0 {
+            writeln!(
+                html,
+                "<p>Cache hit rate: {:.1}% of {} commands</p>",
+                cached as f64 * 100.0 / ran as f64,
+                ran
+            )?;
+        }
+
+        html.push_str("<h2>Critical path</h2>\n");
+        if self.critical_path.is_empty() {
+            html.push_str("<p>No critical path was recorded.</p>\n");
+        } else {
+            html.push_str("<table>\n<tr><th>Kind</th><th>Name</th><th>Duration</th></tr>\n");
+            for row in &self.critical_path {
+                writeln!(
+                    html,
+                    "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    row.kind,
+                    escape(&row.name),
+                    row.duration.map_or(String::new(), |d| fmt_duration(d, 1.0)),
+                )?;
+            }
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("<h2>Timeline</h2>\n");
+        if self.actions.len() > MAX_TIMELINE_ACTIONS {
+            writeln!(
+                html,
+                "<p>Showing the {} longest of {} actions.</p>",
+                MAX_TIMELINE_ACTIONS,
+                self.actions.len()
+            )?;
+            self.actions.sort_by(|a, b| b.duration.cmp(&a.duration));
+            self.actions.truncate(MAX_TIMELINE_ACTIONS);
+        }
+        self.actions.sort_by_key(|a| a.start);
+        html.push_str("<div class=\"timeline\">\n");
+        for lane in lanes(&self.actions) {
+            html.push_str("<div class=\"lane\">");
+            for action in lane {
+                let offset = action.start.duration_since(start).unwrap_or_default();
+                let percent = |d: Duration| d.as_secs_f64() * 100.0 / total.as_secs_f64().max(1e-9);
+                write!(
+                    html,
+                    "<div class=\"bar {class}\" style=\"left:{left:.3}%;width:{width:.3}%\" \
+                     title=\"{title} ({duration})\"></div>",
+                    class = if action.failed {
+                        "failed"
+                    } else {
+                        timeline_class(action.kind)
+                    },
+                    left = percent(offset),
+                    width = percent(action.duration),
+                    title = escape(&action.identity),
+                    duration = fmt_duration(action.duration, 1.0),
+                )?;
+            }
+            html.push_str("</div>\n");
+        }
+        html.push_str(
+            "</div>\n<p class=\"legend\"><span class=\"local\">local</span> \
+             <span class=\"remote\">remote</span> <span class=\"cached\">cached</span> \
+             <span class=\"other\">other</span> <span class=\"failed\">failed</span></p>\n\
+             </body>\n</html>\n",
+        );
+        Ok(html)
+    }
+}
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em}\
+    pre{background:#f4f4f4;padding:1em;overflow:auto}\
+    table{border-collapse:collapse}td,th{border:1px solid #ccc;padding:2px 8px;text-align:left}\
+    .timeline{border:1px solid #ccc}.lane{position:relative;height:6px;margin:1px 0}\
+    .bar{position:absolute;height:100%;min-width:1px}\
+    .local{background:#4c8bf5}.remote{background:#34a853}.cached{background:#a0a0a0}\
+    .other{background:#fbbc05}.failed{background:#ea4335}\
+    .legend span{padding:0 6px;color:#fff}";
+
+fn timeline_class(kind: ActionExecutionKind) -> &'static str {
+    match kind {
+        ActionExecutionKind::Local | ActionExecutionKind::LocalWorker => "local",
+        ActionExecutionKind::Remote => "remote",
+        ActionExecutionKind::ActionCache
+        | ActionExecutionKind::RemoteDepFileCache
+        | ActionExecutionKind::LocalDepFile => "cached",
+        _ => "other",
+    }
+}
+
+/// Packs actions sorted by start time into lanes of non-overlapping actions.
+fn lanes(actions: &[TimelineAction]) -> Vec<Vec<&TimelineAction>> {
+    let mut lanes: Vec<(SystemTime, Vec<&TimelineAction>)> = Vec::new();
+    for action in actions {
+        let end = action.start + action.duration;
+        match lanes
+            .iter_mut()
+            .find(|(lane_end, _)| *lane_end <= action.start)
+        {
+            Some((lane_end, lane)) => {
+                *lane_end = end;
+                lane.push(action);
+            }
+            None => lanes.push((end, vec![action])),
+        }
+    }
+    lanes.into_iter().map(|(_, lane)| lane).collect()
+}
+
+fn critical_path_row(
+    entry: &buck2_data::CriticalPathEntry2,
+) -> anyhow::Result<Option<CriticalPathRow>> {
+    use buck2_data::critical_path_entry2::Entry;
+
+    let opts = TargetDisplayOptions::for_log();
+    let (kind, name) = match &entry.entry {
+        Some(Entry::Analysis(analysis)) => {
+            use buck2_data::critical_path_entry2::analysis::Target;
+            match &analysis.target {
+                Some(Target::StandardTarget(t)) => (
+                    "analysis",
+                    display::display_configured_target_label(t, opts)?,
+                ),
+                None => return Ok(None),
+            }
+        }
+        Some(Entry::ActionExecution(action)) => {
+            use buck2_data::critical_path_entry2::action_execution::Owner;
+            let owner = match &action.owner {
+                Some(Owner::TargetLabel(t)) => display::display_configured_target_label(t, opts)?,
+                Some(Owner::BxlKey(t)) => display::display_bxl_key(t)?,
+                Some(Owner::AnonTarget(t)) => display::display_anon_target(t)?,
+                None => return Ok(None),
+            };
+            match &action.name {
+                Some(name) => (
+                    "action",
+                    format!("{} ({} {})", owner, name.category, name.identifier),
+                ),
+                None => ("action", owner),
+            }
+        }
+        Some(Entry::Materialization(materialization)) => {
+            ("materialization", materialization.path.clone())
+        }
+        Some(Entry::ComputeCriticalPath(..)) => ("compute-critical-path", String::new()),
+        Some(Entry::Load(load)) => ("load", load.package.clone()),
+        Some(Entry::Listing(listing)) => ("listing", listing.package.clone()),
+        None => return Ok(None),
+    };
+    Ok(Some(CriticalPathRow {
+        kind,
+        name,
+        duration: entry
+            .total_duration
+            .clone()
+            .and_then(|d| Duration::try_from(d).ok()),
+    }))
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(start: u64, duration: u64) -> TimelineAction {
+        TimelineAction {
+            identity: String::new(),
+            start: SystemTime::UNIX_EPOCH + Duration::from_secs(start),
+            duration: Duration::from_secs(duration),
+            kind: ActionExecutionKind::Local,
+            failed: false,
+        }
+    }
+
+    #[test]
+    fn test_lanes() {
+        let actions = [action(0, 5), action(1, 2), action(3, 1), action(5, 1)];
+        let lanes = lanes(&actions)
+            .into_iter()
+            .map(|lane| lane.len())
+            .collect::<Vec<_>>();
+        assert_eq!(vec![2, 2], lanes);
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            "&lt;a href=&quot;x&quot;&gt; &amp;",
+            escape("<a href=\"x\"> &")
+        );
+    }
+}
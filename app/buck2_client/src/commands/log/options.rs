@@ -20,6 +20,7 @@ use buck2_core::fs::paths::file_name::FileNameBuf;
 use buck2_event_log::file_names::find_log_by_trace_id;
 use buck2_event_log::file_names::retrieve_nth_recent_log;
 use buck2_event_log::read::EventLogPathBuf;
+use buck2_event_log::storage::event_log_storage;
 use buck2_event_log::utils::Encoding;
 use buck2_util::indent::indent;
 use buck2_util::process::async_background_command;
@@ -50,7 +51,8 @@ pub(crate) struct EventLogOptions {
     #[clap(long, requires = "trace_id")]
     allow_remote: bool,
 
-    /// Do not allow downloading the log from manifold if it's not found locally.
+    /// Do not allow downloading the log from manifold (or from the log storage configured in
+    /// `buck2_log_storage`) if it's not found locally.
     #[clap(long, requires = "trace_id")]
     no_remote: bool,
 
@@ -123,6 +125,30 @@ impl EventLogOptions {
         // Delete the file on failure.
         let temp_path = TempPath::new_path(temp_path);
 
+        if let Some(storage) = event_log_storage(ctx.immediate_config.log_storage_config()?)? {
+            buck2_client_ctx::eprintln!("Downloading `{}` from log storage", manifold_file_name)?;
+            storage
+                .download(manifold_file_name.as_str(), temp_path.path())
+                .await?;
+        } else {
+            self.download_from_manifold(&manifold_file_name, &temp_path)
+                .await?;
+        }
+
+        fs_util::create_dir_all(log_path.parent().context("Error identifying log dir")?)?;
+        fs_util::rename(temp_path.path(), &log_path)?;
+        buck2_client_ctx::eprintln!("Downloaded event-log to `{}`", log_path.display())?;
+
+        temp_path.close()?;
+
+        Ok(log_path.into_abs_path_buf())
+    }
+
+    async fn download_from_manifold(
+        &self,
+        manifold_file_name: &FileName,
+        temp_path: &TempPath,
+    ) -> anyhow::Result<()> {
         let args = [
             "get",
             &format!("buck2_logs/flat/{}", manifold_file_name),
@@ -148,13 +174,6 @@ impl EventLogOptions {
             )
             .into());
         }
-
-        fs_util::create_dir_all(log_path.parent().context("Error identifying log dir")?)?;
-        fs_util::rename(temp_path.path(), &log_path)?;
-        buck2_client_ctx::eprintln!("Downloaded event-log to `{}`", log_path.display())?;
-
-        temp_path.close()?;
-
-        Ok(log_path.into_abs_path_buf())
+        Ok(())
     }
 }
@@ -10,6 +10,9 @@
 use buck2_client_ctx::client_ctx::ClientCommandContext;
 use buck2_client_ctx::exit_result::ExitResult;
 use buck2_client_ctx::stdio;
+use buck2_data::EventLogSegmentIndex;
+use buck2_event_log::segment::EventKeys;
+use buck2_event_log::stream_value::StreamValue;
 use tokio_stream::StreamExt;
 
 use crate::commands::log::options::EventLogOptions;
@@ -19,16 +22,55 @@ use crate::commands::log::options::EventLogOptions;
 pub struct ShowLogCommand {
     #[clap(flatten)]
     event_log: EventLogOptions,
+
+    /// Only show events with this span id or parent span id. Can be repeated.
+    #[clap(long = "span-id", value_name = "ID")]
+    span_ids: Vec<u64>,
+
+    /// Only show events of actions and analyses of this target, given as an unconfigured label
+    /// like `cell//pkg:name`. Can be repeated.
+    #[clap(long = "target", value_name = "TARGET")]
+    targets: Vec<String>,
+}
+
+#[derive(Clone)]
+struct ShowLogFilter {
+    span_ids: Vec<u64>,
+    targets: Vec<String>,
+}
+
+impl ShowLogFilter {
+    fn matches(&self, span_ids: &[u64], targets: &[String]) -> bool {
+        (self.span_ids.is_empty() || span_ids.iter().any(|id| self.span_ids.contains(id)))
+            && (self.targets.is_empty() || targets.iter().any(|t| self.targets.contains(t)))
+    }
+
+    fn matches_segment(&self, index: &EventLogSegmentIndex) -> bool {
+        self.matches(&index.span_ids, &index.targets)
+    }
+
+    fn matches_event(&self, event: &buck2_data::BuckEvent) -> bool {
+        let keys = EventKeys::of(event);
+        self.matches(&keys.span_ids, keys.target.as_slice())
+    }
 }
 
 impl ShowLogCommand {
     pub fn exec(self, _matches: &clap::ArgMatches, ctx: ClientCommandContext<'_>) -> ExitResult {
-        let Self { event_log } = self;
+        let Self {
+            event_log,
+            span_ids,
+            targets,
+        } = self;
 
         ctx.with_runtime(|ctx| async move {
             let log_path = event_log.get(&ctx).await?;
 
-            let (invocation, mut events) = log_path.unpack_stream().await?;
+            let filter = ShowLogFilter { span_ids, targets };
+            let segment_filter = filter.clone();
+            let (invocation, mut events) = log_path
+                .unpack_stream_filtered(move |index| segment_filter.matches_segment(index))
+                .await?;
 
             let mut buf = Vec::new();
 
@@ -37,6 +79,11 @@ impl ShowLogCommand {
             stdio::print_bytes(b"\n")?;
 
             while let Some(event) = events.try_next().await? {
+                if let StreamValue::Event(event) = &event {
+                    if !filter.matches_event(event) {
+                        continue;
+                    }
+                }
                 buf.clear();
                 serde_json::to_writer(&mut buf, &event)?;
                 stdio::print_bytes(&buf)?;
@@ -0,0 +1,43 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_event_log::storage::event_log_storage_name;
+use buck2_event_log::storage::require_event_log_storage;
+
+use crate::commands::log::options::EventLogOptions;
+
+/// Upload the selected log to the log storage configured in `buck2_log_storage`.
+///
+/// Others can then read it with `buck2 log show --trace-id <ID>` (or any other `buck2 log`
+/// command), provided they use the same log storage configuration.
+#[derive(Debug, clap::Parser)]
+pub struct UploadLogCommand {
+    #[clap(flatten)]
+    event_log: EventLogOptions,
+}
+
+impl UploadLogCommand {
+    pub fn exec(self, _matches: &clap::ArgMatches, ctx: ClientCommandContext<'_>) -> ExitResult {
+        let Self { event_log } = self;
+
+        ctx.with_runtime(|ctx| async move {
+            let storage = require_event_log_storage(ctx.immediate_config.log_storage_config()?)?;
+            let log_path = event_log.get(&ctx).await?;
+            let name = event_log_storage_name(&log_path)?;
+            let location = storage.upload(log_path.path(), &name).await?;
+            buck2_client_ctx::eprintln!("Uploaded event log to `{}`", location)?;
+            buck2_client_ctx::println!("{}", log_path.uuid_from_filename()?)?;
+            anyhow::Ok(())
+        })?;
+
+        ExitResult::success()
+    }
+}
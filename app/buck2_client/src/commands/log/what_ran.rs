@@ -14,6 +14,7 @@ use anyhow::Context;
 use buck2_client_ctx::client_ctx::ClientCommandContext;
 use buck2_client_ctx::exit_result::ExitResult;
 use buck2_data::re_platform::Property;
+use buck2_data::EventLogSegmentIndex;
 use buck2_event_log::stream_value::StreamValue;
 use buck2_event_observer::what_ran;
 use buck2_event_observer::what_ran::CommandReproducer;
@@ -133,7 +134,9 @@ impl WhatRanCommand {
             ctx.with_runtime(|ctx| async move {
                 let log_path = event_log.get(&ctx).await?;
 
-                let (invocation, events) = log_path.unpack_stream().await?;
+                let (invocation, events) = log_path
+                    .unpack_stream_filtered(has_what_ran_relevant_events)
+                    .await?;
 
                 buck2_client_ctx::eprintln!(
                     "Showing commands from: {}{}",
@@ -160,6 +163,27 @@ impl WhatRanCommand {
     }
 }
 
+/// Kinds of the events `what-ran` looks at, as recorded in event log segment indices.
+const WHAT_RAN_RELEVANT_KINDS: &[&str] = &[
+    "SpanStart.ActionExecution",
+    "SpanStart.TestDiscovery",
+    "SpanStart.TestStart",
+    "SpanStart.LocalResources",
+    "SpanStart.ExecutorStage",
+    "SpanEnd.ActionExecution",
+    "SpanEnd.TestDiscovery",
+    "SpanEnd.TestEnd",
+    "SpanEnd.LocalResources",
+    "SpanEnd.ExecutorStage",
+];
+
+fn has_what_ran_relevant_events(index: &EventLogSegmentIndex) -> bool {
+    index
+        .kinds
+        .iter()
+        .any(|kind| WHAT_RAN_RELEVANT_KINDS.contains(&kind.as_str()))
+}
+
 #[allow(clippy::vec_box)]
 struct WhatRanEntry {
     /// Known to be a WhatRanRelevantAction.
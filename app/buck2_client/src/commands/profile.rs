@@ -35,6 +35,9 @@ use buck2_common::argv::SanitizedArgv;
 use dupe::Dupe;
 
 use super::bxl::BxlCommandOptions;
+use crate::commands::profile::dice::ProfileDiceCommand;
+
+mod dice;
 
 #[derive(Debug, clap::Parser)]
 #[clap(about = "Run starlark profiler")]
@@ -42,12 +45,19 @@ pub enum ProfileCommand {
     Analysis(ProfileAnalysisCommand),
     Loading(ProfileLoadingCommand),
     Bxl(ProfileBxlCommand),
+    Dice(ProfileDiceCommand),
 }
 
 impl ProfileCommand {
     pub fn exec(self, matches: &clap::ArgMatches, ctx: ClientCommandContext<'_>) -> ExitResult {
         let submatches = matches.subcommand().expect("subcommand not found").1;
-        ProfileSubcommand { subcommand: self }.exec(submatches, ctx)
+        let subcommand = match self {
+            ProfileCommand::Analysis(analysis) => StarlarkProfile::Analysis(analysis),
+            ProfileCommand::Loading(loading) => StarlarkProfile::Loading(loading),
+            ProfileCommand::Bxl(bxl) => StarlarkProfile::Bxl(bxl),
+            ProfileCommand::Dice(dice) => return dice.exec(submatches, ctx),
+        };
+        ProfileSubcommand { subcommand }.exec(submatches, ctx)
     }
 
     pub fn sanitize_argv(&self, argv: Argv) -> SanitizedArgv {
@@ -140,8 +150,15 @@ struct ProfileCommonOptions {
     common_opts: CommonCommandOptions,
 }
 
+/// The subcommands which run the Starlark profiler in the daemon.
+enum StarlarkProfile {
+    Analysis(ProfileAnalysisCommand),
+    Loading(ProfileLoadingCommand),
+    Bxl(ProfileBxlCommand),
+}
+
 struct ProfileSubcommand {
-    subcommand: ProfileCommand,
+    subcommand: StarlarkProfile,
 }
 
 fn profile_mode_to_profile(mode: BuckProfileMode) -> buck2_cli_proto::ProfileMode {
@@ -162,9 +179,9 @@ fn profile_mode_to_profile(mode: BuckProfileMode) -> buck2_cli_proto::ProfileMod
 impl ProfileSubcommand {
     fn common_opts(&self) -> &ProfileCommonOptions {
         match &self.subcommand {
-            ProfileCommand::Analysis(analysis) => &analysis.profile_common_opts,
-            ProfileCommand::Loading(loading) => &loading.profile_common_opts,
-            ProfileCommand::Bxl(bxl) => &bxl.profile_common_opts,
+            StarlarkProfile::Analysis(analysis) => &analysis.profile_common_opts,
+            StarlarkProfile::Loading(loading) => &loading.profile_common_opts,
+            StarlarkProfile::Bxl(bxl) => &bxl.profile_common_opts,
         }
     }
 }
@@ -192,7 +209,7 @@ impl StreamingCommand for ProfileSubcommand {
         let profiler = profile_mode_to_profile(profile_mode);
 
         let profile_opts = match &self.subcommand {
-            ProfileCommand::Loading(loading) => ProfileOpts::TargetProfile(TargetProfile {
+            StarlarkProfile::Loading(loading) => ProfileOpts::TargetProfile(TargetProfile {
                 target_patterns: loading.buck_opts.target_patterns.clone(),
                 action: target_profile::Action::Loading as i32,
                 target_cfg: Some(
@@ -209,7 +226,7 @@ impl StreamingCommand for ProfileSubcommand {
                     .clone(),
                 recursive: loading.buck_opts.recursive,
             }),
-            ProfileCommand::Analysis(analysis) => ProfileOpts::TargetProfile(TargetProfile {
+            StarlarkProfile::Analysis(analysis) => ProfileOpts::TargetProfile(TargetProfile {
                 target_patterns: analysis.buck_opts.target_patterns.clone(),
                 action: target_profile::Action::Analysis as i32,
                 target_cfg: Some(
@@ -226,7 +243,7 @@ impl StreamingCommand for ProfileSubcommand {
                     .clone(),
                 recursive: analysis.buck_opts.recursive,
             }),
-            ProfileCommand::Bxl(bxl) => {
+            StarlarkProfile::Bxl(bxl) => {
                 if !bxl
                     .profile_common_opts
                     .target_cfg
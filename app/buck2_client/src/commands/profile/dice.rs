@@ -0,0 +1,141 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::fmt::Write;
+
+use anyhow::Context;
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_client_ctx::path_arg::PathArg;
+use buck2_core::fs::fs_util;
+use buck2_event_log::stream_value::StreamValue;
+use tokio_stream::StreamExt;
+
+use crate::commands::log::options::EventLogOptions;
+
+#[derive(Debug, buck2_error::Error)]
+enum ProfileDiceError {
+    #[error(
+        "The selected command did not report a DICE profile (it was not run with `-c buck2.profile_dice=true`, or did not compute anything)"
+    )]
+    NoProfile,
+}
+
+/// Profile DICE computations of a command, by key type.
+///
+/// Reads the event log of a command which already ran with `-c buck2.profile_dice=true`, e.g.
+/// `buck2 build`, so nothing needs to be run again. Writes to the output directory:
+///
+/// * `dice.json`: number of keys computed and total self time, by key type.
+///
+/// * `flame.svg`: a flamegraph of self time by key type, and `flame.src`, its source.
+///
+/// Self time excludes the time spent waiting for the dependencies requested during compute.
+#[derive(Debug, clap::Parser)]
+pub struct ProfileDiceCommand {
+    #[clap(flatten)]
+    event_log: EventLogOptions,
+
+    /// Output directory for profile data.
+    ///
+    /// Directory will be created if it does not exist, and files in it overwritten.
+    #[clap(long, short = 'o', value_name = "PATH")]
+    output: PathArg,
+}
+
+impl ProfileDiceCommand {
+    pub fn exec(self, _matches: &clap::ArgMatches, ctx: ClientCommandContext<'_>) -> ExitResult {
+        let Self { event_log, output } = self;
+        let output_dir = output.resolve(&ctx.working_dir);
+
+        let profile = ctx.with_runtime(|ctx| async move {
+            let log_path = event_log.get(&ctx).await?;
+            let (invocation, mut events) = log_path.unpack_stream().await?;
+            buck2_client_ctx::eprintln!(
+                "Profiling DICE computations of: {}",
+                invocation.display_command_line()
+            )?;
+
+            let mut profile = None;
+            while let Some(event) = events.try_next().await? {
+                if let StreamValue::Event(event) = event {
+                    if let Some(buck2_data::buck_event::Data::Instant(instant)) = event.data {
+                        if let Some(buck2_data::instant_event::Data::DiceKeyTypeProfile(p)) =
+                            instant.data
+                        {
+                            profile = Some(p);
+                        }
+                    }
+                }
+            }
+            anyhow::Ok(profile.ok_or(ProfileDiceError::NoProfile)?)
+        })?;
+
+        fs_util::create_dir_if_not_exists(&output_dir)?;
+        fs_util::write(
+            output_dir.join("dice.json"),
+            serde_json::to_string_pretty(&profile)?,
+        )
+        .context("Failed to write profile")?;
+
+        let flame = flame_src(&profile);
+        let mut svg = Vec::new();
+        inferno::flamegraph::from_reader(
+            &mut inferno::flamegraph::Options::default(),
+            flame.as_bytes(),
+            &mut svg,
+        )
+        .context("writing SVG from profile data")?;
+        fs_util::write(output_dir.join("flame.src"), &flame).context("Failed to write profile")?;
+        fs_util::write(output_dir.join("flame.svg"), &svg).context("Failed to write profile")?;
+
+        buck2_client_ctx::println!("DICE profile has been written to {}", output.display())?;
+        ExitResult::success()
+    }
+}
+
+/// Folded stacks, one per key type, weighted by self time in microseconds.
+fn flame_src(profile: &buck2_data::DiceKeyTypeProfile) -> String {
+    let mut flame = String::new();
+    for cost in &profile.key_types {
+        // Key type names can contain `;`, e.g. in generic arguments, which separates frames.
+        let key_type = cost.key_type.replace(';', ":");
+        // inferno does not like empty frames.
+        let weight = cost.self_duration_us.max(1);
+        writeln!(flame, "dice;{} {}", key_type, weight).unwrap();
+    }
+    flame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flame_src() {
+        let profile = buck2_data::DiceKeyTypeProfile {
+            key_types: vec![
+                buck2_data::DiceKeyTypeCost {
+                    key_type: "AnalysisKey".to_owned(),
+                    computed: 3,
+                    self_duration_us: 1500,
+                },
+                buck2_data::DiceKeyTypeCost {
+                    key_type: "Foo<A;B>".to_owned(),
+                    computed: 1,
+                    self_duration_us: 0,
+                },
+            ],
+        };
+        assert_eq!(
+            "dice;AnalysisKey 1500\ndice;Foo<A:B> 1\n",
+            flame_src(&profile)
+        );
+    }
+}
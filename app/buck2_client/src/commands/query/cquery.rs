@@ -42,6 +42,12 @@ passed as the universe and all transitive deps of them).  When not
 provided, we implicitly set the universe to be rooted at every
 target literal in the `cquery`.
 
+With `--at <invocation-id>`, the query runs against the configured graph
+of an earlier build instead of the current one, so it reflects exactly
+what that build saw even if the working copy has since changed. The daemon
+keeps the graphs of the last `buck2.configured_graph_snapshots` builds
+in memory (none by default); they are lost when the daemon restarts.
+
 Run `buck2 docs cquery` or
 "#,
         if_else_opensource!(
@@ -83,6 +89,22 @@ pub struct CqueryCommand {
     )]
     show_providers: bool,
 
+    /// Show the names of the providers of the query result instead of the attributes and labels,
+    /// with the fields selected by `--provider-field`. Use it to discover what a rule provides.
+    #[clap(long, conflicts_with = "show_providers")]
+    list_providers: bool,
+
+    /// With `--list-providers`, also show the provider fields matching this regex, e.g.
+    /// `DefaultInfo\.default_outputs` or `RunInfo\.`. Values are rendered as JSON, one level
+    /// deep. May be repeated.
+    #[clap(long, value_name = "PROVIDER.FIELD", requires = "list_providers")]
+    provider_field: Vec<String>,
+
+    /// Query the configured graph of the build with this invocation id (the trace id of
+    /// its event log) instead of the current graph.
+    #[clap(long, value_name = "INVOCATION_ID")]
+    at: Option<String>,
+
     #[clap(flatten)]
     target_cfg: TargetCfgWithUniverseOptions,
 
@@ -116,6 +138,9 @@ impl StreamingCommand for CqueryCommand {
                     target_universe: self.target_cfg.target_universe,
                     target_cfg: Some(self.target_cfg.target_cfg.target_cfg()),
                     show_providers: self.show_providers,
+                    list_providers: self.list_providers,
+                    provider_fields: self.provider_field,
+                    at_invocation: self.at.unwrap_or_default(),
                     unstable_output_format,
                 },
                 ctx.stdin()
@@ -116,6 +116,11 @@ impl StreamingCommand for RunCommand {
                     final_artifact_materializations: Materializations::Materialize as i32,
                     target_universe: Vec::new(),
                     output_hashes_file: None,
+                    last_build_links: None,
+                    min_cache_hit_rate: None,
+                    warn_on_low_cache_hit_rate: false,
+                    offloaded_outputs: Vec::new(),
+                    aspects: Vec::new(),
                 },
                 ctx.stdin()
                     .console_interaction_stream(&self.common_opts.console_opts),
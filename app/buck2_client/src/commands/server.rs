@@ -19,7 +19,12 @@ use buck2_client_ctx::streaming::StreamingCommand;
 
 #[derive(Debug, clap::Parser)]
 #[clap(about = "Start, query, and control the http server")]
-pub struct ServerCommand {}
+pub struct ServerCommand {
+    /// Print the process information of the daemon as JSON, which clients on other machines
+    /// use to connect to it with `--remote-daemon`.
+    #[clap(long)]
+    process_info: bool,
+}
 
 #[async_trait]
 impl StreamingCommand for ServerCommand {
@@ -32,7 +37,12 @@ impl StreamingCommand for ServerCommand {
         _ctx: &mut ClientCommandContext<'_>,
     ) -> ExitResult {
         let status = buckd.with_flushing().status(false).await?;
-        buck2_client_ctx::println!("buckd.endpoint={}", status.process_info.unwrap().endpoint)?;
+        let process_info = status.process_info.unwrap();
+        if self.process_info {
+            buck2_client_ctx::println!("{}", serde_json::to_string(&process_info)?)?;
+        } else {
+            buck2_client_ctx::println!("buckd.endpoint={}", process_info.endpoint)?;
+        }
         ExitResult::success()
     }
 
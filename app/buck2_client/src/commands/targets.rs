@@ -39,6 +39,8 @@ enum TargetsError {
     /// Clap should report it, but if we missed something, this is a fallback.
     #[error("Flags are mutually exclusive")]
     IncompatibleArguments,
+    #[error("`--output-format binary` requires `--streaming`")]
+    BinaryRequiresStreaming,
 }
 
 // Use non-camel case so the possible values match buck1's
@@ -83,6 +85,30 @@ enum TargetHashFunction {
     Strong,
 }
 
+#[derive(Debug, clap::ValueEnum, Clone, Copy, Dupe, PartialEq, Eq)]
+#[clap(rename_all = "snake_case")]
+enum TargetsOutputFormat {
+    Text,
+    Json,
+    JsonLines,
+    Stats,
+    /// Length-delimited `TargetsOutputEntry` protobuf messages (see `daemon.proto`).
+    /// Requires `--streaming`.
+    Binary,
+}
+
+impl TargetsOutputFormat {
+    fn to_proto(self) -> OutputFormat {
+        match self {
+            TargetsOutputFormat::Text => OutputFormat::Text,
+            TargetsOutputFormat::Json => OutputFormat::Json,
+            TargetsOutputFormat::JsonLines => OutputFormat::JsonLines,
+            TargetsOutputFormat::Stats => OutputFormat::Stats,
+            TargetsOutputFormat::Binary => OutputFormat::Binary,
+        }
+    }
+}
+
 #[derive(Debug, clap::ValueEnum, Clone, Dupe)]
 enum Compression {
     None,
@@ -119,6 +145,16 @@ pub struct TargetsCommand {
     #[clap(long)]
     stats: bool,
 
+    /// Output format. An alternative to `--json`, `--json-lines` and `--stats`, and the only
+    /// way to request the compact `binary` format.
+    #[clap(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        conflicts_with_all = ["json", "json_lines", "stats"]
+    )]
+    output_format: Option<TargetsOutputFormat>,
+
     /// Print the fully-qualified build target for the specified aliases
     #[clap(long, alias = "resolvealias")]
     resolve_alias: bool,
@@ -238,7 +274,20 @@ pub struct TargetsCommand {
 impl TargetsCommand {
     #[allow(clippy::if_same_then_else)]
     fn output_format(&self) -> anyhow::Result<OutputFormat> {
-        if self.json {
+        if let Some(output_format) = self.output_format {
+            match output_format {
+                TargetsOutputFormat::Binary if !self.streaming => {
+                    return Err(TargetsError::BinaryRequiresStreaming.into());
+                }
+                TargetsOutputFormat::Text | TargetsOutputFormat::Stats
+                    if !self.attributes.get()?.is_empty() =>
+                {
+                    return Err(TargetsError::IncompatibleArguments.into());
+                }
+                _ => {}
+            }
+            Ok(output_format.to_proto())
+        } else if self.json {
             if self.json_lines || self.stats {
                 return Err(TargetsError::IncompatibleArguments.into());
             }
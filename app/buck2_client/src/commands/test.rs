@@ -7,9 +7,12 @@
  * of this source tree.
  */
 
+use std::path::PathBuf;
+
 use anyhow::Context;
 use async_trait::async_trait;
 use buck2_cli_proto::CounterWithExamples;
+use buck2_cli_proto::TestListing;
 use buck2_cli_proto::TestRequest;
 use buck2_cli_proto::TestSessionOptions;
 use buck2_client_ctx::client_ctx::ClientCommandContext;
@@ -28,14 +31,55 @@ use buck2_client_ctx::output_destination_arg::OutputDestinationArg;
 use buck2_client_ctx::path_arg::PathArg;
 use buck2_client_ctx::stdio::eprint_line;
 use buck2_client_ctx::streaming::StreamingCommand;
+use buck2_client_ctx::subscribers::subscriber::EventSubscriber;
 use buck2_client_ctx::subscribers::superconsole::test::span_from_build_failure_count;
 use buck2_client_ctx::subscribers::superconsole::test::TestCounterColumn;
+use buck2_client_ctx::subscribers::test_report::TestReportSubscriber;
+use buck2_common::init::LogStorageConfig;
 use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
+use buck2_core::fs::paths::abs_path::AbsPath;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
 use buck2_core::fs::working_dir::WorkingDir;
+use buck2_event_log::storage::require_event_log_storage;
+use buck2_wrapper_common::invocation_id::TraceId;
 use superconsole::Line;
 use superconsole::Span;
 
 use crate::commands::build::print_build_result;
+use crate::commands::build::targets_file::TargetsFileOptions;
+
+#[derive(serde::Serialize)]
+struct ListedTarget<'a> {
+    target: &'a str,
+    suites: Vec<ListedSuite<'a>>,
+}
+
+#[derive(serde::Serialize)]
+struct ListedSuite<'a> {
+    name: &'a str,
+    tests: &'a [String],
+}
+
+fn listing_json(listing: &[TestListing]) -> anyhow::Result<Vec<u8>> {
+    let targets = listing
+        .iter()
+        .map(|target| ListedTarget {
+            target: &target.target,
+            suites: target
+                .suites
+                .iter()
+                .map(|suite| ListedSuite {
+                    name: &suite.name,
+                    tests: &suite.test_names,
+                })
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+    let mut json = serde_json::to_vec_pretty(&targets)?;
+    json.push(b'\n');
+    Ok(json)
+}
 
 fn forward_output_to_path(
     output: &str,
@@ -46,6 +90,37 @@ fn forward_output_to_path(
         .context("Failed to write test executor output to path")
 }
 
+/// Uploads the files in the test artifacts directory to the test artifacts location of the log
+/// storage, under the trace id of the command, and returns how many were uploaded.
+async fn upload_test_artifacts(
+    config: &LogStorageConfig,
+    artifacts_dir: &AbsNormPath,
+    trace_id: &TraceId,
+) -> anyhow::Result<usize> {
+    let storage = require_event_log_storage(&config.for_test_artifacts())?;
+    let mut uploaded = 0;
+    // Upload what the symlinks written by the tests point to.
+    for entry in walkdir::WalkDir::new(artifacts_dir).follow_links(true) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry
+            .path()
+            .strip_prefix(artifacts_dir)?
+            .to_str()
+            .context("Test artifact path is not valid UTF-8")?
+            .replace('\\', "/");
+        let path = AbsPath::new(entry.path())?;
+        storage
+            .upload(path, &format!("{}/{}", trace_id, relative))
+            .await
+            .with_context(|| format!("Failed to upload `{}`", entry.path().display()))?;
+        uploaded += 1;
+    }
+    Ok(uploaded)
+}
+
 fn print_error_counter(
     console: &FinalConsole,
     counter: &CounterWithExamples,
@@ -130,6 +205,9 @@ If include patterns are present, regardless of whether exclude patterns are pres
     #[clap(name = "TARGET_PATTERNS", help = "Patterns to test")]
     patterns: Vec<String>,
 
+    #[clap(flatten)]
+    targets_file_opts: TargetsFileOptions,
+
     /// Writes the test executor stdout to the provided path
     ///
     /// --test-executor-stdout=- will write to stdout
@@ -146,6 +224,28 @@ If include patterns are present, regardless of whether exclude patterns are pres
     #[clap(long)]
     ignore_tests_attribute: bool,
 
+    /// List the tests as JSON instead of running them.
+    ///
+    /// The test executor is asked to only enumerate the tests. Targets whose test cases it cannot
+    /// enumerate are listed without any.
+    #[clap(long, conflicts_with = "test_executor_stdout")]
+    list: bool,
+
+    /// Write the test results to this file as JUnit XML: one `testsuite` per target, with
+    /// retried attempts as `flakyFailure` or `rerunFailure`, and skipped incompatible targets.
+    #[clap(long, value_name = "PATH")]
+    junit: Option<PathBuf>,
+
+    /// Write the test results to this file in the Test Anything Protocol (TAP) version 13 format.
+    #[clap(long, value_name = "PATH")]
+    tap: Option<PathBuf>,
+
+    /// Upload the artifacts collected from the tests (logs, screenshots...) to the test artifacts
+    /// location of the log storage configured in `buck2_log_storage`, under the trace id of this
+    /// command.
+    #[clap(long)]
+    upload_test_artifacts: bool,
+
     /// Writes the test executor stderr to the provided path
     ///
     /// --test-executor-stderr=- will write to stderr
@@ -195,12 +295,15 @@ impl StreamingCommand for TestCommand {
         ctx: &mut ClientCommandContext<'_>,
     ) -> ExitResult {
         let context = ctx.client_context(matches, &self)?;
+        let targets_file = self.targets_file_opts.read(&ctx.working_dir)?;
+        let mut patterns = self.patterns.clone();
+        patterns.extend(targets_file.patterns);
         let response = buckd
             .with_flushing()
             .test(
                 TestRequest {
                     context: Some(context),
-                    target_patterns: self.patterns.clone(),
+                    target_patterns: patterns,
                     target_cfg: Some(self.target_cfg.target_cfg()),
                     test_executor_args: self.test_executor_args,
                     excluded_labels: self.exclude,
@@ -225,6 +328,8 @@ impl StreamingCommand for TestCommand {
                         .transpose()
                         .context("Invalid `timeout`")?,
                     ignore_tests_attribute: self.ignore_tests_attribute,
+                    target_options: targets_file.test_options,
+                    list_only: self.list,
                 },
                 ctx.stdin()
                     .console_interaction_stream(&self.common_opts.console_opts),
@@ -263,6 +368,17 @@ impl StreamingCommand for TestCommand {
             console.print_error(&format!("{} BUILDS FAILED", build_errors.len()))?;
         }
 
+        if self.list {
+            let exit_result = if !build_errors.is_empty() {
+                ExitResult::from_errors(build_errors.iter().copied())
+            } else if let Some(exit_code) = response.exit_code {
+                ExitResult::status_extended(exit_code)
+            } else {
+                ExitResult::bail("Test executor did not provide an exit code")
+            };
+            return exit_result.with_stdout(listing_json(&response.listing)?);
+        }
+
         // TODO(nmj): Might make sense for us to expose the event ctx, and use its
         //            handle_stdout method, instead of raw buck2_client::println!s here.
         // TODO: also remove the duplicate information when the above is done.
@@ -298,6 +414,30 @@ impl StreamingCommand for TestCommand {
             console.print_stderr(message.as_str())?;
         }
 
+        if !response.artifacts_dir.is_empty() {
+            console.print_stderr(&format!("Test artifacts: {}", response.artifacts_dir))?;
+            if self.upload_test_artifacts {
+                let artifacts_dir = ctx
+                    .paths()?
+                    .project_root()
+                    .resolve(ProjectRelativePath::new(&response.artifacts_dir)?);
+                let uploaded = upload_test_artifacts(
+                    ctx.immediate_config.log_storage_config()?,
+                    &artifacts_dir,
+                    &ctx.trace_id,
+                )
+                .await;
+                match uploaded {
+                    Ok(uploaded) => console.print_stderr(&format!(
+                        "Uploaded {} test artifacts under `{}`",
+                        uploaded, ctx.trace_id
+                    ))?,
+                    Err(e) => console
+                        .print_warning(&format!("Failed to upload test artifacts: {:#}", e))?,
+                }
+            }
+        }
+
         match self.test_executor_stderr {
             Some(OutputDestinationArg::Path(path)) => {
                 forward_output_to_path(&response.executor_stderr, &path, &ctx.working_dir)?;
@@ -344,6 +484,16 @@ impl StreamingCommand for TestCommand {
         &self.common_opts.config_opts
     }
 
+    fn extra_subscribers(&self) -> Vec<Box<dyn EventSubscriber>> {
+        if self.junit.is_none() && self.tap.is_none() {
+            return Vec::new();
+        }
+        vec![Box::new(TestReportSubscriber::new(
+            self.junit.clone(),
+            self.tap.clone(),
+        ))]
+    }
+
     fn starlark_opts(&self) -> &CommonStarlarkOptions {
         &self.common_opts.starlark_opts
     }
@@ -0,0 +1,32 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+mod list;
+
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_client_ctx::streaming::BuckSubcommand;
+
+use crate::commands::toolchain::list::ToolchainListCommand;
+
+#[derive(Debug, clap::Subcommand)]
+#[clap(about = "Commands for inspecting toolchains discovered on the host")]
+pub enum ToolchainCommand {
+    /// Lists host toolchains probed by the daemon and the files which use them.
+    List(ToolchainListCommand),
+}
+
+impl ToolchainCommand {
+    pub fn exec(self, matches: &clap::ArgMatches, ctx: ClientCommandContext<'_>) -> ExitResult {
+        let matches = matches.subcommand().expect("subcommand not found").1;
+        match self {
+            ToolchainCommand::List(cmd) => cmd.exec(matches, ctx),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::fmt::Write;
+
+use async_trait::async_trait;
+use buck2_cli_proto::new_generic::NewGenericRequest;
+use buck2_cli_proto::new_generic::NewGenericResponse;
+use buck2_cli_proto::new_generic::ToolchainListRequest;
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::common::ui::CommonConsoleOptions;
+use buck2_client_ctx::common::CommonBuildConfigurationOptions;
+use buck2_client_ctx::common::CommonCommandOptions;
+use buck2_client_ctx::common::CommonEventLogOptions;
+use buck2_client_ctx::common::CommonStarlarkOptions;
+use buck2_client_ctx::daemon::client::BuckdClientConnector;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_client_ctx::streaming::StreamingCommand;
+
+/// List toolchains found on the host.
+///
+/// The built-in probes (clang, gcc, rustc and python) are always run. Other probes are listed
+/// once a build file calling `host_toolchain()` for them has been evaluated by this daemon.
+/// Each toolchain is shown with the build files whose evaluation requested it.
+#[derive(Debug, clap::Parser)]
+pub struct ToolchainListCommand {
+    /// Print the results as JSON.
+    #[clap(long)]
+    json: bool,
+
+    #[clap(flatten)]
+    common_opts: CommonCommandOptions,
+}
+
+#[async_trait]
+impl StreamingCommand for ToolchainListCommand {
+    const COMMAND_NAME: &'static str = "toolchain-list";
+
+    async fn exec_impl(
+        self,
+        buckd: &mut BuckdClientConnector,
+        matches: &clap::ArgMatches,
+        ctx: &mut ClientCommandContext<'_>,
+    ) -> ExitResult {
+        let context = ctx.client_context(matches, &self)?;
+        let resp = buckd
+            .with_flushing()
+            .new_generic(
+                context,
+                NewGenericRequest::ToolchainList(ToolchainListRequest {}),
+                ctx.stdin()
+                    .console_interaction_stream(&self.common_opts.console_opts),
+            )
+            .await??;
+        let NewGenericResponse::ToolchainList(resp) = resp else {
+            return ExitResult::bail("Unexpected response type from generic command");
+        };
+
+        let mut stdout = String::new();
+        if self.json {
+            stdout = serde_json::to_string_pretty(&resp)?;
+            stdout.push('\n');
+        } else {
+            for toolchain in &resp.toolchains {
+                match &toolchain.path {
+                    Some(path) => writeln!(
+                        stdout,
+                        "{} {} ({})",
+                        toolchain.name,
+                        toolchain.version.as_deref().unwrap_or("<unknown version>"),
+                        path
+                    )?,
+                    None => writeln!(
+                        stdout,
+                        "{} <not found> (looked for {})",
+                        toolchain.name,
+                        toolchain.binaries.join(", ")
+                    )?,
+                }
+                for user in &toolchain.used_by {
+                    writeln!(stdout, "  used by {}", user)?;
+                }
+            }
+        }
+        ExitResult::success().with_stdout(stdout.into_bytes())
+    }
+
+    fn console_opts(&self) -> &CommonConsoleOptions {
+        &self.common_opts.console_opts
+    }
+
+    fn event_log_opts(&self) -> &CommonEventLogOptions {
+        &self.common_opts.event_log_opts
+    }
+
+    fn build_config_opts(&self) -> &CommonBuildConfigurationOptions {
+        &self.common_opts.config_opts
+    }
+
+    fn starlark_opts(&self) -> &CommonStarlarkOptions {
+        &self.common_opts.starlark_opts
+    }
+}
@@ -0,0 +1,128 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::fmt::Write;
+
+use async_trait::async_trait;
+use buck2_cli_proto::new_generic::NewGenericRequest;
+use buck2_cli_proto::new_generic::NewGenericResponse;
+use buck2_cli_proto::new_generic::VerifyRequest;
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::common::target_cfg::TargetCfgOptions;
+use buck2_client_ctx::common::ui::CommonConsoleOptions;
+use buck2_client_ctx::common::CommonBuildConfigurationOptions;
+use buck2_client_ctx::common::CommonCommandOptions;
+use buck2_client_ctx::common::CommonEventLogOptions;
+use buck2_client_ctx::common::CommonStarlarkOptions;
+use buck2_client_ctx::daemon::client::BuckdClientConnector;
+use buck2_client_ctx::exit_result::ExitCode;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_client_ctx::streaming::StreamingCommand;
+use clap::ArgMatches;
+
+/// Build targets and check the artifact assertions they declare.
+///
+/// Targets declare assertions with the `ArtifactAssertionsInfo` provider: a maximum artifact
+/// size, dynamic libraries the artifact must not link against, and symbols it must define.
+/// Each matched target's asserted artifact is built and checked, and the command fails if any
+/// assertion does not hold. Targets without `ArtifactAssertionsInfo` are ignored.
+#[derive(Debug, clap::Parser)]
+#[clap(name = "verify")]
+pub struct VerifyCommand {
+    /// Print the results as JSON.
+    #[clap(long)]
+    json: bool,
+
+    /// Patterns to verify.
+    #[clap(name = "TARGET_PATTERNS")]
+    patterns: Vec<String>,
+
+    #[clap(flatten)]
+    target_cfg: TargetCfgOptions,
+
+    #[clap(flatten)]
+    common_opts: CommonCommandOptions,
+}
+
+#[async_trait]
+impl StreamingCommand for VerifyCommand {
+    const COMMAND_NAME: &'static str = "verify";
+
+    async fn exec_impl(
+        self,
+        buckd: &mut BuckdClientConnector,
+        matches: &ArgMatches,
+        ctx: &mut ClientCommandContext<'_>,
+    ) -> ExitResult {
+        let context = ctx.client_context(matches, &self)?;
+        let resp = buckd
+            .with_flushing()
+            .new_generic(
+                context,
+                NewGenericRequest::Verify(VerifyRequest {
+                    target_patterns: self.patterns,
+                    target_cfg: self.target_cfg.target_cfg(),
+                }),
+                ctx.stdin()
+                    .console_interaction_stream(&self.common_opts.console_opts),
+            )
+            .await??;
+        let NewGenericResponse::Verify(resp) = resp else {
+            return ExitResult::bail("Unexpected response type from generic command");
+        };
+
+        let mut failed = 0;
+        let mut stdout = String::new();
+        for target in &resp.targets {
+            for assertion in &target.assertions {
+                if !assertion.passed {
+                    failed += 1;
+                }
+                if !self.json {
+                    writeln!(
+                        stdout,
+                        "{} {} ({}) {}: {}",
+                        if assertion.passed { "PASS" } else { "FAIL" },
+                        target.target,
+                        target.artifact,
+                        assertion.assertion,
+                        assertion.message,
+                    )?;
+                }
+            }
+        }
+        if self.json {
+            stdout = serde_json::to_string_pretty(&resp)?;
+            stdout.push('\n');
+        }
+
+        if failed == 0 {
+            ExitResult::success().with_stdout(stdout.into_bytes())
+        } else {
+            buck2_client_ctx::eprintln!("{} assertion(s) failed", failed)?;
+            ExitResult::status(ExitCode::UserError).with_stdout(stdout.into_bytes())
+        }
+    }
+
+    fn console_opts(&self) -> &CommonConsoleOptions {
+        &self.common_opts.console_opts
+    }
+
+    fn event_log_opts(&self) -> &CommonEventLogOptions {
+        &self.common_opts.event_log_opts
+    }
+
+    fn build_config_opts(&self) -> &CommonBuildConfigurationOptions {
+        &self.common_opts.config_opts
+    }
+
+    fn starlark_opts(&self) -> &CommonStarlarkOptions {
+        &self.common_opts.starlark_opts
+    }
+}
@@ -41,6 +41,30 @@ enum ArgExpansionError {
     PythonExecutionFailed { source: io::Error, cmd: Command },
     #[error("Unable to read line from stdin")]
     StdinReadError { source: anyhow::Error },
+    #[error(
+        "Invalid directive `{directive}` at `{path}:{line}`, expected `#if os|arch ==|!= VALUE[,VALUE]`, `#else` or `#endif`"
+    )]
+    InvalidDirective {
+        path: String,
+        line: usize,
+        directive: String,
+    },
+    #[error("Unexpected `{directive}` at `{path}:{line}`, no `#if` is open")]
+    UnbalancedDirective {
+        path: String,
+        line: usize,
+        directive: String,
+    },
+    #[error("Unterminated `#if` in flag file `{path}`")]
+    UnterminatedIf { path: String },
+    #[error("Environment variable `{var}` used at `{path}:{line}` is not set")]
+    UnsetEnvVar {
+        path: String,
+        line: usize,
+        var: String,
+    },
+    #[error("Unterminated `$(env ...)` at `{path}:{line}`")]
+    UnterminatedEnvMacro { path: String, line: usize },
 }
 
 /// Log that a relative flag file was not found in CWD, but was found, and used, from the cell root
@@ -154,12 +178,14 @@ fn expand_argfile_contents(flagfile: &ArgFile) -> anyhow::Result<Vec<String>> {
                     source: source.into(),
                     path: path.to_string_lossy().into_owned(),
                 })?;
-                if line.is_empty() {
-                    continue;
-                }
                 lines.push(line);
             }
-            Ok(lines)
+            preprocess_argfile(
+                lines,
+                &path.to_string_lossy(),
+                &HostFacts::current(),
+                &|var| std::env::var(var).ok(),
+            )
         }
         ArgFile::PythonExecutable(path, flag) => {
             let mut cmd = background_command(if is_open_source() {
@@ -206,6 +232,155 @@ fn expand_argfile_contents(flagfile: &ArgFile) -> anyhow::Result<Vec<String>> {
     }
 }
 
+/// What `#if` conditions in flag files are evaluated against.
+struct HostFacts {
+    os: &'static str,
+    arch: &'static str,
+}
+
+impl HostFacts {
+    fn current() -> Self {
+        Self {
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+        }
+    }
+}
+
+// Preprocesses the lines of a flag file:
+//  - `#if os == linux`, `#if arch != aarch64,x86_64`, `#else` and `#endif` lines (which may
+//    nest) select the lines that apply to the host,
+//  - `$(env VAR)` is replaced with the value of the environment variable `VAR`,
+//  - empty lines are dropped.
+//
+// Preprocessing is strict: malformed directives, unbalanced blocks and unset variables are
+// errors, rather than being passed through as arguments.
+fn preprocess_argfile(
+    lines: Vec<String>,
+    path: &str,
+    host: &HostFacts,
+    env: &dyn Fn(&str) -> Option<String>,
+) -> anyhow::Result<Vec<String>> {
+    struct Block {
+        /// Whether the lines around the block are kept.
+        outer: bool,
+        holds: bool,
+        in_else: bool,
+    }
+
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut active = true;
+    let mut result = Vec::new();
+
+    for (index, line) in lines.into_iter().enumerate() {
+        let line_number = index + 1;
+        let directive = line.trim();
+        let invalid = || ArgExpansionError::InvalidDirective {
+            path: path.to_owned(),
+            line: line_number,
+            directive: directive.to_owned(),
+        };
+        let unbalanced = || ArgExpansionError::UnbalancedDirective {
+            path: path.to_owned(),
+            line: line_number,
+            directive: directive.to_owned(),
+        };
+
+        match directive.split_whitespace().next() {
+            Some("#if") => {
+                let holds =
+                    eval_argfile_condition(&directive["#if".len()..], host).ok_or_else(invalid)?;
+                blocks.push(Block {
+                    outer: active,
+                    holds,
+                    in_else: false,
+                });
+                active = active && holds;
+            }
+            Some("#else") if directive == "#else" => {
+                let block = blocks
+                    .last_mut()
+                    .filter(|block| !block.in_else)
+                    .ok_or_else(unbalanced)?;
+                block.in_else = true;
+                active = block.outer && !block.holds;
+            }
+            Some("#endif") if directive == "#endif" => {
+                active = blocks.pop().ok_or_else(unbalanced)?.outer;
+            }
+            Some("#else" | "#endif" | "#elif" | "#ifdef" | "#ifndef") => {
+                return Err(invalid().into());
+            }
+            _ => {
+                if active && !line.is_empty() {
+                    result.push(interpolate_env(&line, path, line_number, env)?);
+                }
+            }
+        }
+    }
+
+    if !blocks.is_empty() {
+        return Err(ArgExpansionError::UnterminatedIf {
+            path: path.to_owned(),
+        }
+        .into());
+    }
+
+    Ok(result)
+}
+
+/// Evaluates `os == linux`, `arch != x86_64,aarch64` and the like. `None` if malformed.
+fn eval_argfile_condition(condition: &str, host: &HostFacts) -> Option<bool> {
+    let mut parts = condition.split_whitespace();
+    let (fact, op, values) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() {
+        return None;
+    }
+    let fact = match fact {
+        "os" => host.os,
+        "arch" => host.arch,
+        _ => return None,
+    };
+    let matches = values.split(',').any(|value| value == fact);
+    match op {
+        "==" => Some(matches),
+        "!=" => Some(!matches),
+        _ => None,
+    }
+}
+
+fn interpolate_env(
+    line: &str,
+    path: &str,
+    line_number: usize,
+    env: &dyn Fn(&str) -> Option<String>,
+) -> anyhow::Result<String> {
+    const START: &str = "$(env ";
+
+    let mut result = String::new();
+    let mut rest = line;
+    while let Some(start) = rest.find(START) {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + START.len()..];
+        let end = after
+            .find(')')
+            .ok_or_else(|| ArgExpansionError::UnterminatedEnvMacro {
+                path: path.to_owned(),
+                line: line_number,
+            })?;
+        let var = after[..end].trim();
+        let value = env(var).ok_or_else(|| ArgExpansionError::UnsetEnvVar {
+            path: path.to_owned(),
+            line: line_number,
+            var: var.to_owned(),
+        })?;
+        result.push_str(&value);
+        rest = &after[end + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
 // Resolves a path argument to an absolute path, so that it can be read.
 fn resolve_flagfile(path: &str, context: &mut ImmediateConfigContext) -> anyhow::Result<ArgFile> {
     if path == "-" {
@@ -286,6 +461,57 @@ mod tests {
         assert_eq!(vec!["a".to_owned(), "b".to_owned()], lines);
     }
 
+    fn preprocess(contents: &str) -> anyhow::Result<Vec<String>> {
+        preprocess_argfile(
+            contents.lines().map(ToOwned::to_owned).collect(),
+            "mode-file",
+            &HostFacts {
+                os: "linux",
+                arch: "aarch64",
+            },
+            &|var| (var == "USER").then(|| "alice".to_owned()),
+        )
+    }
+
+    #[test]
+    fn test_preprocess_conditions() {
+        let lines = preprocess(
+            "--a\n\
+             #if os == macos,windows\n\
+             --b\n\
+             #if arch == aarch64\n\
+             --c\n\
+             #endif\n\
+             #else\n\
+             --d\n\
+             #if arch != aarch64\n\
+             --e\n\
+             #endif\n\
+             #endif\n\
+             --f",
+        )
+        .unwrap();
+        assert_eq!(vec!["--a", "--d", "--f"], lines);
+    }
+
+    #[test]
+    fn test_preprocess_env() {
+        let lines = preprocess("--config=user=$(env USER)\n-c x=$(config a.b)").unwrap();
+        assert_eq!(vec!["--config=user=alice", "-c x=$(config a.b)"], lines);
+
+        assert!(preprocess("$(env HOME)").is_err());
+        assert!(preprocess("$(env USER").is_err());
+    }
+
+    #[test]
+    fn test_preprocess_strict() {
+        assert!(preprocess("#if os linux\n#endif").is_err());
+        assert!(preprocess("#if os == linux").is_err());
+        assert!(preprocess("#endif").is_err());
+        assert!(preprocess("#if os == linux\n#else\n#else\n#endif").is_err());
+        assert!(preprocess("#elif os == linux").is_err());
+    }
+
     #[test]
     fn test_relative_inclusion() {
         // Currently all @-files both on the command line and in files are relative to the current directory.
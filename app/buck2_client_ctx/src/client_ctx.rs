@@ -14,6 +14,7 @@ use buck2_cli_proto::client_context::HostArchOverride as GrpcHostArchOverride;
 use buck2_cli_proto::client_context::HostPlatformOverride as GrpcHostPlatformOverride;
 use buck2_cli_proto::client_context::PreemptibleWhen as GrpcPreemptibleWhen;
 use buck2_cli_proto::ClientContext;
+use buck2_cli_proto::EnvironmentVariable;
 use buck2_common::argv::Argv;
 use buck2_common::invocation_paths::InvocationPaths;
 use buck2_core::error::buck2_hard_error_env;
@@ -30,8 +31,11 @@ use crate::common::HostArchOverride;
 use crate::common::HostPlatformOverride;
 use crate::common::PreemptibleWhen;
 use crate::daemon::client::connect::BuckdConnectOptions;
+use crate::daemon::client::remote::RemoteDaemon;
 use crate::daemon::client::BuckdClientConnector;
+use crate::daemon_constraints::get_nested_invocation_delegate_trace_id;
 use crate::daemon_constraints::get_possibly_nested_invocation_daemon_uuid;
+use crate::error_format::ErrorFormat;
 use crate::exit_result::ExitResult;
 use crate::immediate_config::ImmediateConfigContext;
 use crate::restarter::Restarter;
@@ -45,6 +49,7 @@ pub struct ClientCommandContext<'a> {
     pub paths: buck2_error::Result<InvocationPaths>,
     pub working_dir: WorkingDir,
     pub verbosity: Verbosity,
+    pub error_format: ErrorFormat,
     /// When set, this function is called to launch in process daemon.
     /// The function returns `Ok` when daemon successfully started
     /// and ready to accept connections.
@@ -58,6 +63,8 @@ pub struct ClientCommandContext<'a> {
     pub runtime: &'a Runtime,
     pub oncall: Option<String>,
     pub client_metadata: Vec<ClientMetadata>,
+    /// When set, commands are run by the daemon of this checkout on another machine.
+    pub remote_daemon: Option<RemoteDaemon>,
 }
 
 impl<'a> ClientCommandContext<'a> {
@@ -114,9 +121,20 @@ impl<'a> ClientCommandContext<'a> {
         &self,
         options: BuckdConnectOptions<'a>,
     ) -> anyhow::Result<BuckdClientConnector<'a>> {
-        BuckdConnectOptions { ..options }
-            .connect(self.paths()?)
-            .await
+        match &self.remote_daemon {
+            None => options.connect(self.paths()?).await,
+            Some(remote) => options.connect_remote(remote, self.paths()?).await,
+        }
+    }
+
+    /// Translate a path the daemon reported to the same path on this machine.
+    pub fn daemon_path_to_local(&self, path: String) -> anyhow::Result<String> {
+        Ok(match &self.remote_daemon {
+            None => path,
+            Some(remote) => remote
+                .to_local(self.paths()?.project_root().root(), &path)
+                .into_owned(),
+        })
     }
 
     pub fn client_context<T: StreamingCommand>(
@@ -128,7 +146,7 @@ impl<'a> ClientCommandContext<'a> {
         let config_opts = cmd.build_config_opts();
         let starlark_opts = cmd.starlark_opts();
 
-        Ok(ClientContext {
+        let mut context = ClientContext {
             config_overrides: config_opts.config_overrides(arg_matches)?,
             host_platform: match config_opts.host_platform_override() {
                 HostPlatformOverride::Default => GrpcHostPlatformOverride::DefaultPlatform,
@@ -155,6 +173,7 @@ impl<'a> ClientCommandContext<'a> {
                 Some(PreemptibleWhen::Never) => GrpcPreemptibleWhen::Never,
                 Some(PreemptibleWhen::Always) => GrpcPreemptibleWhen::Always,
                 Some(PreemptibleWhen::OnDifferentState) => GrpcPreemptibleWhen::OnDifferentState,
+                Some(PreemptibleWhen::OnRebuild) => GrpcPreemptibleWhen::OnRebuild,
             }
             .into(),
             argfiles: self
@@ -164,23 +183,44 @@ impl<'a> ClientCommandContext<'a> {
                 .map(|path| path.to_string())
                 .collect(),
             target_call_stacks: starlark_opts.target_call_stacks,
-            ..self.empty_client_context(cmd.logging_name())?
-        })
+            client_env: client_env(self.immediate_config.env_passthrough()?),
+            reattachable: config_opts.reattachable,
+            ..self.local_client_context(cmd.logging_name())?
+        };
+        self.translate_for_remote_daemon(&mut context)?;
+        Ok(context)
     }
 
     /// A client context for commands where CommonConfigOptions are not provided.
     pub fn empty_client_context(&self, command_name: &str) -> anyhow::Result<ClientContext> {
+        let mut context = self.local_client_context(command_name)?;
+        self.translate_for_remote_daemon(&mut context)?;
+        Ok(context)
+    }
+
+    /// Translate the paths of `context` to the remote checkout with `--remote-daemon`.
+    fn translate_for_remote_daemon(&self, context: &mut ClientContext) -> anyhow::Result<()> {
+        if let Some(remote) = &self.remote_daemon {
+            remote.translate_client_context(self.paths()?.project_root().root(), context)?;
+        }
+        Ok(())
+    }
+
+    /// An empty client context, with the paths of this machine.
+    fn local_client_context(&self, command_name: &str) -> anyhow::Result<ClientContext> {
         #[derive(Debug, buck2_error::Error)]
         #[error("Current directory is not UTF-8")]
         struct CurrentDirIsNotUtf8;
 
+        let working_dir = self
+            .working_dir
+            .path()
+            .to_str()
+            .context(CurrentDirIsNotUtf8)?
+            .to_owned();
+
         Ok(ClientContext {
-            working_dir: self
-                .working_dir
-                .path()
-                .to_str()
-                .context(CurrentDirIsNotUtf8)?
-                .to_owned(),
+            working_dir,
             config_overrides: Default::default(),
             host_platform: Default::default(),
             host_arch: Default::default(),
@@ -204,6 +244,9 @@ impl<'a> ClientCommandContext<'a> {
                 .map(ClientMetadata::to_proto)
                 .collect(),
             preemptible: Default::default(),
+            client_env: Vec::new(),
+            reattachable: false,
+            delegate_to_trace_id: get_nested_invocation_delegate_trace_id().unwrap_or_default(),
         })
     }
 
@@ -215,3 +258,16 @@ impl<'a> ClientCommandContext<'a> {
         Ok(self.immediate_config.daemon_startup_config()?.allow_vpnless)
     }
 }
+
+/// The variables of the client's environment which actions may declare dependencies on, sent
+/// even when unset so that the daemon sees them being unset. Values that are not UTF-8 cannot
+/// be passed through and are sent as unset.
+fn client_env(names: &[String]) -> Vec<EnvironmentVariable> {
+    names
+        .iter()
+        .map(|name| EnvironmentVariable {
+            key: name.clone(),
+            value: std::env::var(name).ok(),
+        })
+        .collect()
+}
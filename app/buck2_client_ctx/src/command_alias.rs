@@ -0,0 +1,248 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Expansion of the command aliases and default arguments defined in the
+//! `buck2_command_alias` and `buck2_command_defaults` sections of the root buckconfig.
+
+use anyhow::Context as _;
+use buck2_common::init::CommandAliasConfig;
+
+use crate::immediate_config::ImmediateConfigContext;
+
+#[derive(buck2_error::Error, Debug)]
+enum CommandAliasError {
+    #[error("Command alias `{0}` is empty")]
+    EmptyAlias(String),
+    #[error(
+        "Command alias `{0}` must expand to a buck2 command, but it starts with `{1}` (aliases cannot refer to other aliases)"
+    )]
+    NotACommand(String, String),
+}
+
+/// How the command line was rewritten by an alias or default arguments.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommandAliasExpansion {
+    /// The alias the command was invoked with, if it was invoked with one.
+    pub alias: Option<String>,
+    /// The arguments inserted by the alias and the command defaults.
+    pub args: Vec<String>,
+}
+
+/// Expand a command alias at the position of the subcommand, then insert the default arguments
+/// of the resulting command. `before_subcommand` parses the options which can precede the
+/// subcommand, to find where it is. Aliases never shadow builtin commands, and outside of a
+/// project nothing is expanded.
+pub fn expand_command_aliases(
+    args: Vec<String>,
+    before_subcommand: &clap::Command,
+    is_builtin_command: &dyn Fn(&str) -> bool,
+    context: &mut ImmediateConfigContext,
+) -> anyhow::Result<Vec<String>> {
+    let Ok(config) = context.command_alias_config() else {
+        return Ok(args);
+    };
+    let (args, expansion) = expand(args, before_subcommand, config, is_builtin_command)?;
+    if let Some(expansion) = expansion {
+        context.set_command_alias_expansion(expansion);
+    }
+    Ok(args)
+}
+
+fn split(name: &str, value: &str) -> anyhow::Result<Vec<String>> {
+    shlex::split(value).with_context(|| format!("Invalid expansion for `{}`: `{}`", name, value))
+}
+
+/// The position of the subcommand in `args`. Parsing the options before it, rather than looking
+/// for the first argument which is not a flag, avoids mistaking the value of an option for it.
+/// If the options don't parse, the command line is left for clap to report the error.
+fn subcommand_position(args: &[String], before_subcommand: &clap::Command) -> Option<usize> {
+    let matches = before_subcommand
+        .clone()
+        .allow_external_subcommands(true)
+        .try_get_matches_from(args)
+        .ok()?;
+    let (_, subcommand_matches) = matches.subcommand()?;
+    let subcommand_args = subcommand_matches.get_raw("").map_or(0, |args| args.len());
+    let position = args.len().checked_sub(subcommand_args + 1)?;
+    // Everything after `--` is a positional argument.
+    if args[..position].iter().any(|arg| arg == "--") {
+        return None;
+    }
+    Some(position)
+}
+
+fn expand(
+    mut args: Vec<String>,
+    before_subcommand: &clap::Command,
+    config: &CommandAliasConfig,
+    is_builtin_command: &dyn Fn(&str) -> bool,
+) -> anyhow::Result<(Vec<String>, Option<CommandAliasExpansion>)> {
+    let Some(position) = subcommand_position(&args, before_subcommand) else {
+        return Ok((args, None));
+    };
+
+    let mut expansion = CommandAliasExpansion {
+        alias: None,
+        args: Vec::new(),
+    };
+
+    let name = &args[position];
+    if let Some(value) = config
+        .aliases
+        .get(name)
+        .filter(|_| !is_builtin_command(name))
+    {
+        let mut tokens = split(name, value)?;
+        if tokens.is_empty() {
+            return Err(CommandAliasError::EmptyAlias(name.clone()).into());
+        }
+        if !is_builtin_command(&tokens[0]) {
+            return Err(CommandAliasError::NotACommand(name.clone(), tokens.swap_remove(0)).into());
+        }
+        expansion.alias = Some(name.clone());
+        expansion.args.extend(tokens[1..].iter().cloned());
+        args.splice(position..=position, tokens);
+    }
+
+    let command = &args[position];
+    if let Some(value) = config.defaults.get(command) {
+        let tokens = split(command, value)?;
+        expansion.args.splice(0..0, tokens.iter().cloned());
+        args.splice(position + 1..position + 1, tokens);
+    }
+
+    if expansion.alias.is_none() && expansion.args.is_empty() {
+        return Ok((args, None));
+    }
+    Ok((args, Some(expansion)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    fn config() -> CommandAliasConfig {
+        CommandAliasConfig {
+            aliases: BTreeMap::from([
+                ("build-opt".to_owned(), "build @mode/opt".to_owned()),
+                ("build".to_owned(), "test".to_owned()),
+                ("nested".to_owned(), "build-opt".to_owned()),
+            ]),
+            defaults: BTreeMap::from([("build".to_owned(), "--console 'simple'".to_owned())]),
+        }
+    }
+
+    fn before_subcommand() -> clap::Command {
+        clap::Command::new("buck2")
+            .arg(clap::Arg::new("isolation_dir").long("isolation-dir"))
+            .arg(
+                clap::Arg::new("no_buckd")
+                    .long("no-buckd")
+                    .action(clap::ArgAction::SetTrue),
+            )
+    }
+
+    fn run(args: &[&str]) -> anyhow::Result<(Vec<String>, Option<CommandAliasExpansion>)> {
+        expand(
+            args.iter().map(|s| (*s).to_owned()).collect(),
+            &before_subcommand(),
+            &config(),
+            &|name| ["build", "test"].contains(&name),
+        )
+    }
+
+    #[test]
+    fn test_expand_alias_and_defaults() -> anyhow::Result<()> {
+        let (args, expansion) = run(&["buck2", "--isolation-dir=x", "build-opt", "//:a"])?;
+        assert_eq!(
+            vec![
+                "buck2",
+                "--isolation-dir=x",
+                "build",
+                "--console",
+                "simple",
+                "@mode/opt",
+                "//:a"
+            ],
+            args
+        );
+        assert_eq!(
+            Some(CommandAliasExpansion {
+                alias: Some("build-opt".to_owned()),
+                args: vec![
+                    "--console".to_owned(),
+                    "simple".to_owned(),
+                    "@mode/opt".to_owned()
+                ],
+            }),
+            expansion
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_option_values_are_not_subcommands() -> anyhow::Result<()> {
+        let (args, expansion) = run(&[
+            "buck2",
+            "--isolation-dir",
+            "build-opt",
+            "--no-buckd",
+            "build-opt",
+        ])?;
+        assert_eq!(
+            vec![
+                "buck2",
+                "--isolation-dir",
+                "build-opt",
+                "--no-buckd",
+                "build",
+                "--console",
+                "simple",
+                "@mode/opt"
+            ],
+            args
+        );
+        assert_eq!(Some("build-opt".to_owned()), expansion.unwrap().alias);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_options_not_expanded() -> anyhow::Result<()> {
+        let (args, expansion) = run(&["buck2", "--unknown", "build-opt"])?;
+        assert_eq!(vec!["buck2", "--unknown", "build-opt"], args);
+        assert_eq!(None, expansion);
+        Ok(())
+    }
+
+    #[test]
+    fn test_builtin_not_shadowed() -> anyhow::Result<()> {
+        let (args, expansion) = run(&["buck2", "test", "//:a"])?;
+        assert_eq!(vec!["buck2", "test", "//:a"], args);
+        assert_eq!(None, expansion);
+
+        let (args, _) = run(&["buck2", "build"])?;
+        assert_eq!(vec!["buck2", "build", "--console", "simple"], args);
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_nested_aliases() {
+        assert!(run(&["buck2", "nested"]).is_err());
+    }
+
+    #[test]
+    fn test_after_double_dash() -> anyhow::Result<()> {
+        let (args, expansion) = run(&["buck2", "--", "build-opt"])?;
+        assert_eq!(vec!["buck2", "--", "build-opt"], args);
+        assert_eq!(None, expansion);
+        Ok(())
+    }
+}
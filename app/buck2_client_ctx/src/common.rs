@@ -73,6 +73,7 @@ pub enum PreemptibleWhen {
     Never, // Read; "If I am Never, then never preempt me" (the default)
     Always,
     OnDifferentState, // Read; "if a command comes in, preempt me on different state"
+    OnRebuild,        // Read; "if the same command comes in on different state, preempt me"
 }
 
 #[derive(
@@ -177,6 +178,17 @@ pub struct CommonBuildConfigurationOptions {
     /// Used to configure when this command could be preempted by another command.
     #[clap(long, ignore_case = true, value_enum)]
     pub preemptible: Option<PreemptibleWhen>,
+
+    /// Keep the command running in the daemon if this client disconnects, so
+    /// that `buck2 attach <invocation-id>` can resume streaming its output.
+    /// A command with no client attached is cancelled after ten minutes.
+    #[clap(long)]
+    pub reattachable: bool,
+
+    /// Fail `buck2 build` if any target being built uses a deprecated rule or attribute,
+    /// instead of printing a warning. Equivalent to `-c buck2.strict_deprecations=true`.
+    #[clap(long)]
+    pub strict_deprecations: bool,
 }
 
 impl CommonBuildConfigurationOptions {
@@ -250,7 +262,15 @@ impl CommonBuildConfigurationOptions {
         ordered_merged_configs.extend(config_values_args);
         ordered_merged_configs.sort_by(|(lhs_index, _), (rhs_index, _)| lhs_index.cmp(rhs_index));
 
-        Ok(ordered_merged_configs.into_map(|(_, config_arg)| config_arg))
+        let mut config_overrides = ordered_merged_configs.into_map(|(_, config_arg)| config_arg);
+        if self.strict_deprecations {
+            // Last, so that the flag wins over config files and `-c` values.
+            config_overrides.push(ConfigOverride {
+                config_override: "buck2.strict_deprecations=true".to_owned(),
+                config_type: ConfigType::Value as i32,
+            });
+        }
+        Ok(config_overrides)
     }
 
     pub fn host_platform_override(&self) -> HostPlatformOverride {
@@ -279,6 +299,8 @@ impl CommonBuildConfigurationOptions {
             reuse_current_config: false,
             exit_when_different_state: false,
             preemptible: Some(PreemptibleWhen::Never),
+            reattachable: false,
+            strict_deprecations: false,
         };
         &DEFAULT
     }
@@ -147,6 +147,9 @@ pub struct CommonBuildOptions {
 
     /// If Buck hits an error, continue doing as much work as possible before exiting.
     ///
+    /// Everything that does not depend on a failure is still built, and the final report
+    /// separates targets that failed from those skipped due to a failed dependency.
+    ///
     /// See `--fail-fast` for more details.
     #[clap(long, group = "fail-when")]
     keep_going: bool,
@@ -164,6 +167,16 @@ pub struct CommonBuildOptions {
     /// Materializes inputs for failed actions which ran on RE
     #[clap(long)]
     materialize_failed_inputs: bool,
+
+    /// Run local actions at a lower CPU and IO priority, so that the machine stays responsive
+    /// during a large build.
+    ///
+    /// The priority is configured by `build.background_nice` (niceness, default 10) and
+    /// `build.background_idle_io` (use the idle IO scheduling class on Linux, default true). On
+    /// Windows, actions get the idle priority class when the niceness is at least 10, and the below
+    /// normal one otherwise.
+    #[clap(long)]
+    background: bool,
 }
 
 impl CommonBuildOptions {
@@ -215,6 +228,7 @@ impl CommonBuildOptions {
             skip_missing_targets: self.skip_missing_targets,
             skip_incompatible_targets: self.skip_incompatible_targets,
             materialize_failed_inputs: self.materialize_failed_inputs,
+            background: self.background,
             unstable_include_failures_build_report,
             unstable_include_package_project_relative_paths,
         }
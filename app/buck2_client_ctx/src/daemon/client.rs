@@ -10,6 +10,7 @@
 use std::fs::create_dir_all;
 use std::fs::File;
 use std::mem;
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::Context;
@@ -38,6 +39,7 @@ use tonic::Status;
 use crate::command_outcome::CommandOutcome;
 use crate::console_interaction_stream::ConsoleInteractionStream;
 use crate::daemon::client::connect::BuckAddAuthTokenInterceptor;
+use crate::daemon::client::remote::SshTunnel;
 use crate::events_ctx::EventsCtx;
 use crate::events_ctx::FileTailers;
 use crate::events_ctx::PartialResultCtx;
@@ -46,6 +48,7 @@ use crate::subscribers::observer::ErrorObserver;
 
 pub mod connect;
 pub mod kill;
+pub mod remote;
 
 use crate::startup_deadline::StartupDeadline;
 
@@ -149,6 +152,8 @@ pub struct BuckdClient<'a> {
     daemon_dir: DaemonDir,
     // TODO(brasselsprouts): events_ctx should own tailers
     tailers: Option<FileTailers>,
+    /// The tunnel to the daemon when it runs on another machine.
+    tunnel: Option<Arc<SshTunnel>>,
     pub(crate) events_ctx: EventsCtx<'a>,
 }
 
@@ -217,7 +222,11 @@ fn grpc_to_stream(
 
 impl<'a> BuckdClient<'a> {
     fn open_tailers(&mut self) -> anyhow::Result<()> {
-        let tailers = FileTailers::new(&self.daemon_dir)?;
+        // The stdout and stderr of a remote daemon are not on this machine.
+        let tailers = match self.tunnel {
+            Some(_) => FileTailers::empty(),
+            None => FileTailers::new(&self.daemon_dir)?,
+        };
         self.tailers = Some(tailers);
 
         Ok(())
@@ -588,6 +597,7 @@ impl<'a, 'b> FlushingBuckdClient<'a, 'b> {
     wrap_method!(status(snapshot: bool), StatusResponse);
     wrap_method!(set_log_filter(log_filter: SetLogFilterRequest), ());
     stream_method!(trace_io, TraceIoRequest, TraceIoResponse, NoPartialResult);
+    stream_method!(attach, AttachRequest, AttachResponse, NoPartialResult);
 
     pub async fn new_generic(
         &mut self,
@@ -12,6 +12,7 @@ use std::ffi::OsStr;
 use std::fs::File;
 use std::io::BufReader;
 use std::net::Ipv4Addr;
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::Context;
@@ -48,6 +49,8 @@ use tonic::Status;
 use crate::command_outcome::CommandOutcome;
 use crate::daemon::client::kill;
 use crate::daemon::client::kill::hard_kill_until;
+use crate::daemon::client::remote::RemoteDaemon;
+use crate::daemon::client::remote::SshTunnel;
 use crate::daemon::client::BuckdClient;
 use crate::daemon::client::BuckdClientConnector;
 use crate::daemon::client::BuckdLifecycleLock;
@@ -58,6 +61,7 @@ use crate::events_ctx::EventsCtx;
 use crate::immediate_config::ImmediateConfigContext;
 use crate::startup_deadline::StartupDeadline;
 use crate::subscribers::classify_server_stderr::classify_server_stderr;
+use crate::subscribers::errorconsole::ErrorConsole;
 use crate::subscribers::stdout_stderr_forwarder::StdoutStderrForwarder;
 use crate::subscribers::subscribers::EventSubscribers;
 
@@ -501,9 +505,11 @@ impl<'a> BuckdLifecycle<'a> {
 /// BootstrapBuckdClient by querying constraints. This is a separate step so that we retry
 /// establishing the channel but not querying constraints.
 pub struct BuckdChannel {
-    info: DaemonProcessInfo,
-    daemon_dir: DaemonDir,
-    client: DaemonApiClient<InterceptedService<Channel, BuckAddAuthTokenInterceptor>>,
+    pub(crate) info: DaemonProcessInfo,
+    pub(crate) daemon_dir: DaemonDir,
+    pub(crate) client: DaemonApiClient<InterceptedService<Channel, BuckAddAuthTokenInterceptor>>,
+    /// The tunnel to the daemon when it runs on another machine.
+    pub(crate) tunnel: Option<Arc<SshTunnel>>,
 }
 
 impl BuckdChannel {
@@ -513,6 +519,7 @@ impl BuckdChannel {
             info,
             daemon_dir,
             mut client,
+            tunnel,
         } = self;
 
         let constraints = get_constraints(&mut client)
@@ -524,6 +531,7 @@ impl BuckdChannel {
             daemon_dir,
             client,
             constraints,
+            tunnel,
         })
     }
 }
@@ -535,7 +543,8 @@ pub struct BootstrapBuckdClient {
     daemon_dir: DaemonDir,
     client: DaemonApiClient<InterceptedService<Channel, BuckAddAuthTokenInterceptor>>,
     /// The constraints for the daemon we're connected to.
-    constraints: buck2_cli_proto::DaemonConstraints,
+    pub(crate) constraints: buck2_cli_proto::DaemonConstraints,
+    tunnel: Option<Arc<SshTunnel>>,
 }
 
 impl BootstrapBuckdClient {
@@ -584,6 +593,7 @@ impl BootstrapBuckdClient {
                 constraints: self.constraints,
                 events_ctx: EventsCtx::new(subscribers),
                 tailers: None,
+                tunnel: self.tunnel,
             },
         }
     }
@@ -627,6 +637,18 @@ impl<'a> BuckdConnectOptions<'a> {
         }
     }
 
+    /// Connect to a daemon satisfying `constraints`, to run commands as with `--console none`:
+    /// only its stdout, its stderr and the errors of the commands are printed.
+    pub fn no_console(constraints: BuckdConnectConstraints) -> Self {
+        Self {
+            constraints,
+            subscribers: EventSubscribers::new(vec![
+                Box::new(StdoutStderrForwarder),
+                Box::new(ErrorConsole),
+            ]),
+        }
+    }
+
     pub async fn connect(
         mut self,
         paths: &InvocationPaths,
@@ -644,6 +666,27 @@ impl<'a> BuckdConnectOptions<'a> {
             }
         }
     }
+
+    /// Connect to the daemon of a checkout on another machine. It is started there if it is not
+    /// running, regardless of the constraints.
+    pub async fn connect_remote(
+        mut self,
+        remote: &RemoteDaemon,
+        paths: &InvocationPaths,
+    ) -> anyhow::Result<BuckdClientConnector<'a>> {
+        match remote
+            .connect(paths)
+            .await
+            .with_context(|| format!("Failed to connect to buck daemon on `{}`", remote.host))
+            .map_err(buck2_error::Error::from)
+        {
+            Ok(client) => Ok(client.with_subscribers(self.subscribers)),
+            Err(e) => {
+                self.subscribers.handle_daemon_connection_failure(&e);
+                Err(e.into())
+            }
+        }
+    }
 }
 
 pub async fn establish_connection_existing(
@@ -747,20 +790,16 @@ async fn establish_connection_inner(
                             Err(reason) => reason,
                         };
 
+                        // Restarting the daemon would kill the command whose action is
+                        // running this one.
                         if is_nested_invocation(
                             get_possibly_nested_invocation_daemon_uuid().as_ref(),
                             &client.constraints,
                         ) {
-                            match reason {
-                                ConstraintUnsatisfiedReason::TraceIo
-                                | ConstraintUnsatisfiedReason::StartupConfig => {
-                                    return Err(BuckdConnectError::ConnectError {
-                                        stderr: format!("buck2 daemon constraint mismatch during nested invocation: {}", reason),
-                                    }
-                                    .into());
-                                }
-                                _ => (),
+                            return Err(BuckdConnectError::NestedInvocationConstraintMismatch {
+                                reason,
                             }
+                            .into());
                         }
 
                         event_subscribers
@@ -1008,6 +1047,7 @@ impl<'a> BuckdProcessInfo<'a> {
             info: self.info.clone(),
             daemon_dir: self.daemon_dir.clone(),
             client,
+            tunnel: None,
         })
     }
 
@@ -1063,6 +1103,11 @@ enum BuckdConnectError {
         expected: DaemonConstraintsRequest,
         actual: buck2_cli_proto::DaemonConstraints,
     },
+    #[error(
+        "buck2 daemon constraint mismatch during recursive invocation of Buck from an action: {reason}. Use `--isolation-dir` on the inner invocation to run it on a separate daemon"
+    )]
+    #[buck2(input, tag = NestedInvocation)]
+    NestedInvocationConstraintMismatch { reason: ConstraintUnsatisfiedReason },
     #[error("Error connecting to the daemon, daemon stderr follows:\n{stderr}")]
     #[buck2(tag = Some(classify_server_stderr(stderr)))]
     ConnectError { stderr: String },
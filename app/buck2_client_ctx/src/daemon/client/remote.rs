@@ -0,0 +1,395 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Remote daemon mode: the client runs on one machine, usually a laptop with a copy of the
+//! checkout, and the daemon on another, usually a devserver which owns the checkout that is built.
+//!
+//! With `buck2 --remote-daemon [user@]host:/path/to/checkout`, the client starts or finds the
+//! daemon of that checkout with `ssh host buck2 server --process-info`, forwards a local port to
+//! its endpoint with `ssh -L`, and then talks gRPC to it as to a local daemon: events are streamed
+//! over the tunnel, and paths are translated between the two checkouts.
+
+use std::borrow::Cow;
+use std::mem;
+use std::net::Ipv4Addr;
+use std::net::TcpListener;
+use std::process::Stdio;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use buck2_cli_proto::config_override::ConfigType;
+use buck2_cli_proto::ClientContext;
+use buck2_cli_proto::DaemonProcessInfo;
+use buck2_common::buckd_connection::ConnectionType;
+use buck2_common::invocation_paths::InvocationPaths;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
+use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
+use tokio::process::Child;
+use tokio::sync::Mutex;
+
+use crate::daemon::client::connect::buckd_startup_timeout;
+use crate::daemon::client::connect::new_daemon_api_client;
+use crate::daemon::client::connect::BootstrapBuckdClient;
+use crate::daemon::client::connect::BuckdChannel;
+use crate::daemon_constraints;
+use crate::startup_deadline::StartupDeadline;
+
+#[derive(Debug, buck2_error::Error)]
+enum RemoteDaemonError {
+    #[error(
+        "Remote daemon should be of format `[user@]host:/absolute/path/to/checkout`, but got `{0}`"
+    )]
+    Format(String),
+    #[error("Failed to start or find the daemon on `{host}` ({status}): {stderr}")]
+    ProcessInfo {
+        host: String,
+        status: std::process::ExitStatus,
+        stderr: String,
+    },
+    #[error("SSH tunnel to `{0}` exited ({1})")]
+    TunnelExited(String, std::process::ExitStatus),
+    #[error(
+        "Daemon on `{host}` is buck2 version `{remote}`, but this client is version `{local}`, they must be the same"
+    )]
+    VersionMismatch {
+        host: String,
+        remote: String,
+        local: String,
+    },
+}
+
+/// The daemon of a checkout on another machine, given to `buck2 --remote-daemon`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RemoteDaemon {
+    /// The host to `ssh` to, like `user@devserver`.
+    pub host: String,
+    /// The absolute path of the checkout on the host.
+    pub project_root: String,
+}
+
+impl FromStr for RemoteDaemon {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.split_once(':') {
+            // A host starting with `-` would be an option of `ssh`.
+            Some((host, project_root))
+                if !host.is_empty() && !host.starts_with('-') && project_root.starts_with('/') =>
+            {
+                Ok(RemoteDaemon {
+                    host: host.to_owned(),
+                    project_root: project_root.trim_end_matches('/').to_owned(),
+                })
+            }
+            _ => Err(RemoteDaemonError::Format(s.to_owned()).into()),
+        }
+    }
+}
+
+impl RemoteDaemon {
+    /// Translate `path`, in the checkout at `local_root`, to the same path in the remote checkout.
+    pub fn to_remote(
+        &self,
+        local_root: &AbsNormPath,
+        path: &AbsNormPath,
+    ) -> anyhow::Result<String> {
+        let relative = path.strip_prefix(local_root).with_context(|| {
+            format!(
+                "`{}` is not in the checkout `{}` built by the remote daemon",
+                path, local_root
+            )
+        })?;
+        Ok(if relative.is_empty() {
+            self.project_root.clone()
+        } else {
+            format!("{}/{}", self.project_root, relative)
+        })
+    }
+
+    /// Translate the paths of `context`, made in the checkout at `local_root`, to the remote
+    /// checkout. Argfiles outside of the checkout, which do not exist on the remote machine, are
+    /// left out, as the daemon only traces them.
+    pub fn translate_client_context(
+        &self,
+        local_root: &AbsNormPath,
+        context: &mut ClientContext,
+    ) -> anyhow::Result<()> {
+        context.working_dir =
+            self.to_remote(local_root, AbsNormPath::new(&context.working_dir)?)?;
+        for config_override in &mut context.config_overrides {
+            // Cell-relative config files are resolved by the daemon.
+            if config_override.config_type == ConfigType::File as i32
+                && !config_override.config_override.contains("//")
+            {
+                config_override.config_override = self.to_remote(
+                    local_root,
+                    AbsNormPath::new(&config_override.config_override)?,
+                )?;
+            }
+        }
+        context.argfiles = mem::take(&mut context.argfiles)
+            .into_iter()
+            .filter_map(|path| {
+                self.to_remote(local_root, AbsNormPath::new(&path).ok()?)
+                    .ok()
+            })
+            .collect();
+        Ok(())
+    }
+
+    /// Translate `path`, in the remote checkout, to the same path in the checkout at
+    /// `local_root`. Paths outside of the remote checkout are returned as they are.
+    pub fn to_local<'a>(&self, local_root: &AbsNormPath, path: &'a str) -> Cow<'a, str> {
+        let relative = match path.strip_prefix(&self.project_root) {
+            Some("") => Some(""),
+            Some(rest) => rest.strip_prefix('/'),
+            None => None,
+        };
+        match relative.map(ForwardRelativePath::new) {
+            Some(Ok(relative)) => Cow::Owned(local_root.join(relative).to_string()),
+            _ => Cow::Borrowed(path),
+        }
+    }
+
+    /// Start or find the daemon of the remote checkout, with the isolation dir of `paths`.
+    async fn process_info(&self, paths: &InvocationPaths) -> anyhow::Result<DaemonProcessInfo> {
+        let script = format!(
+            "{} && {}",
+            shlex::try_join(["cd", &self.project_root])?,
+            shlex::try_join([
+                "buck2",
+                "--isolation-dir",
+                paths.isolation.as_str(),
+                "server",
+                "--process-info"
+            ])?
+        );
+        let output = tokio::process::Command::new("ssh")
+            .args(["-o", "BatchMode=yes", &self.host, "--", &script])
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output()
+            .await
+            .with_context(|| format!("Failed to run `ssh {}`", self.host))?;
+        if !output.status.success() {
+            return Err(RemoteDaemonError::ProcessInfo {
+                host: self.host.clone(),
+                status: output.status,
+                stderr: String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+            }
+            .into());
+        }
+        let stdout = String::from_utf8(output.stdout).context("Process info is not UTF-8")?;
+        let last_line = stdout.lines().last().unwrap_or_default();
+        serde_json::from_str(last_line)
+            .with_context(|| format!("Invalid process info from `{}`: `{}`", self.host, last_line))
+    }
+
+    pub(crate) async fn connect(
+        &self,
+        paths: &InvocationPaths,
+    ) -> anyhow::Result<BootstrapBuckdClient> {
+        let info = self.process_info(paths).await?;
+        let tunnel = Arc::new(SshTunnel::open(&self.host, &info.endpoint)?);
+
+        let deadline = StartupDeadline::duration_from_now(buckd_startup_timeout()?)?;
+        let client = deadline
+            .retrying(
+                "connect to the remote daemon through the SSH tunnel",
+                Duration::from_millis(20),
+                Duration::from_millis(500),
+                || async {
+                    tunnel.check_alive(&self.host).await?;
+                    let client = new_daemon_api_client(
+                        ConnectionType::Tcp { port: tunnel.port },
+                        info.auth_token.clone(),
+                    )
+                    .await?;
+                    BuckdChannel {
+                        info: info.clone(),
+                        daemon_dir: paths.daemon_dir()?,
+                        client,
+                        tunnel: Some(tunnel.clone()),
+                    }
+                    .upgrade()
+                    .await
+                },
+            )
+            .await?;
+
+        // Unlike a local daemon, a remote one is not restarted when its version is different.
+        let local = daemon_constraints::version();
+        if client.constraints.version != local {
+            return Err(RemoteDaemonError::VersionMismatch {
+                host: self.host.clone(),
+                remote: client.constraints.version.clone(),
+                local,
+            }
+            .into());
+        }
+        Ok(client)
+    }
+}
+
+/// An `ssh -L` process forwarding a local port to the endpoint of a remote daemon, killed when
+/// dropped.
+pub struct SshTunnel {
+    port: u16,
+    child: Mutex<Child>,
+}
+
+impl SshTunnel {
+    fn open(host: &str, endpoint: &str) -> anyhow::Result<SshTunnel> {
+        let target = match ConnectionType::parse(endpoint)? {
+            ConnectionType::Tcp { port } => format!("{}:{}", Ipv4Addr::LOCALHOST, port),
+            ConnectionType::Uds { unix_socket } => unix_socket
+                .to_str()
+                .context("Daemon socket path is not UTF-8")?
+                .to_owned(),
+        };
+        // Pick a free port for `ssh` to listen on.
+        let port = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?
+            .local_addr()?
+            .port();
+        let child = tokio::process::Command::new("ssh")
+            .args([
+                "-o",
+                "BatchMode=yes",
+                "-o",
+                "ExitOnForwardFailure=yes",
+                "-N",
+                "-L",
+                &format!("{}:{}:{}", Ipv4Addr::LOCALHOST, port, target),
+                host,
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to start SSH tunnel to `{}`", host))?;
+        Ok(SshTunnel {
+            port,
+            child: Mutex::new(child),
+        })
+    }
+
+    async fn check_alive(&self, host: &str) -> anyhow::Result<()> {
+        match self.child.lock().await.try_wait()? {
+            Some(status) => Err(RemoteDaemonError::TunnelExited(host.to_owned(), status).into()),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_cli_proto::ConfigOverride;
+    use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+
+    use super::*;
+
+    fn remote() -> RemoteDaemon {
+        RemoteDaemon {
+            host: "me@devserver".to_owned(),
+            project_root: "/data/repo".to_owned(),
+        }
+    }
+
+    fn local_root() -> AbsNormPathBuf {
+        if cfg!(windows) {
+            AbsNormPathBuf::from("C:\\repo".to_owned()).unwrap()
+        } else {
+            AbsNormPathBuf::from("/home/me/repo".to_owned()).unwrap()
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(remote(), "me@devserver:/data/repo/".parse().unwrap());
+        assert!("devserver".parse::<RemoteDaemon>().is_err());
+        assert!("devserver:data/repo".parse::<RemoteDaemon>().is_err());
+        assert!(":/data/repo".parse::<RemoteDaemon>().is_err());
+        assert!(
+            "-oProxyCommand=x:/data/repo"
+                .parse::<RemoteDaemon>()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_to_remote() -> anyhow::Result<()> {
+        let root = local_root();
+        assert_eq!("/data/repo", remote().to_remote(&root, &root)?);
+        assert_eq!(
+            "/data/repo/app/lib",
+            remote().to_remote(&root, &root.join(ForwardRelativePath::new("app/lib")?))?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_local() -> anyhow::Result<()> {
+        let root = local_root();
+        assert_eq!(root.to_string(), remote().to_local(&root, "/data/repo"));
+        assert_eq!(
+            root.join(ForwardRelativePath::new("buck-out/v2/gen/out")?)
+                .to_string(),
+            remote().to_local(&root, "/data/repo/buck-out/v2/gen/out")
+        );
+        assert_eq!("/data/repo2/x", remote().to_local(&root, "/data/repo2/x"));
+        assert_eq!("/tmp/x", remote().to_local(&root, "/tmp/x"));
+        Ok(())
+    }
+    #[test]
+    fn test_translate_client_context() -> anyhow::Result<()> {
+        let root = local_root();
+        let in_checkout = |path: &str| -> anyhow::Result<String> {
+            Ok(root.join(ForwardRelativePath::new(path)?).to_string())
+        };
+        let outside = if cfg!(windows) {
+            "C:\\tmp\\args"
+        } else {
+            "/tmp/args"
+        };
+        let mut context = ClientContext {
+            working_dir: in_checkout("app")?,
+            config_overrides: vec![
+                ConfigOverride {
+                    config_override: in_checkout("modes/dev")?,
+                    config_type: ConfigType::File as i32,
+                },
+                ConfigOverride {
+                    config_override: "cell//modes/dev".to_owned(),
+                    config_type: ConfigType::File as i32,
+                },
+                ConfigOverride {
+                    config_override: "a.b=c".to_owned(),
+                    config_type: ConfigType::Value as i32,
+                },
+            ],
+            argfiles: vec![in_checkout("modes/args")?, outside.to_owned()],
+            ..Default::default()
+        };
+        remote().translate_client_context(&root, &mut context)?;
+        assert_eq!("/data/repo/app", context.working_dir);
+        assert_eq!(
+            vec!["/data/repo/modes/dev", "cell//modes/dev", "a.b=c"],
+            context
+                .config_overrides
+                .iter()
+                .map(|c| c.config_override.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(vec!["/data/repo/modes/args"], context.argfiles);
+        Ok(())
+    }
+}
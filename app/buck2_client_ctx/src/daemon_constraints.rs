@@ -23,6 +23,16 @@ pub fn get_possibly_nested_invocation_daemon_uuid() -> Option<String> {
     std::env::var("BUCK2_DAEMON_UUID").ok()
 }
 
+/// The trace id of the outer command, when the action running this nested invocation opted into
+/// running it on the state of that command with `BUCK2_NESTED_INVOCATION=delegate`.
+pub fn get_nested_invocation_delegate_trace_id() -> Option<String> {
+    // Same as above, these are set for actions and are not meant to be set by users.
+    if std::env::var("BUCK2_NESTED_INVOCATION").ok()? != "delegate" {
+        return None;
+    }
+    std::env::var("BUCK_BUILD_ID").ok()
+}
+
 /// Generates the daemon constraints *for the currently running daemon.*
 ///
 /// Note that this function is called *from the daemon* and represents the daemon's constraints -
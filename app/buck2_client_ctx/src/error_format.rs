@@ -0,0 +1,80 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! `--error-format json`: errors of a command are printed to stderr as one JSON object per line,
+//! so that tools wrapping buck2 can present them without parsing the text output.
+
+use buck2_events::errors::create_error_report;
+use dupe::Dupe;
+use serde_json::json;
+
+use crate::subscribers::recorder::process_error_report;
+
+#[derive(Debug, Clone, Dupe, Copy, Default, clap::ValueEnum)]
+#[clap(rename_all = "lower")]
+pub enum ErrorFormat {
+    /// Human readable messages.
+    #[default]
+    Text,
+    /// One JSON object per error.
+    Json,
+}
+
+/// The JSON object printed for `report`, with enums rendered as their names.
+pub(crate) fn error_report_json(report: buck2_data::ErrorReport) -> serde_json::Value {
+    let category = match report.tier.and_then(buck2_data::error::ErrorTier::from_i32) {
+        Some(buck2_data::error::ErrorTier::Input) => Some("user"),
+        Some(buck2_data::error::ErrorTier::Tier0) => Some("infra"),
+        Some(buck2_data::error::ErrorTier::UnusedDefaultCategory) | None => None,
+    };
+    let report = process_error_report(report);
+    json!({
+        "message": report.message,
+        "category": category,
+        "type": report.typ,
+        "tags": report.tags,
+        "best_tag": report.best_tag,
+        "source_location": report.source_location,
+        "sub_error_categories": report.sub_error_categories,
+    })
+}
+
+/// The JSON object printed for an error raised in the client.
+pub(crate) fn client_error_json(error: anyhow::Error) -> serde_json::Value {
+    let error: buck2_error::Error = error.into();
+    error_report_json(create_error_report(&error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_report_json() {
+        let report = buck2_data::ErrorReport {
+            tier: Some(buck2_data::error::ErrorTier::Input as i32),
+            message: "Unknown target `foo`".to_owned(),
+            source_location: Some("buck2_foo/src/lib.rs::FooError".to_owned()),
+            tags: vec![buck2_data::error::ErrorTag::Analysis as i32],
+            ..Default::default()
+        };
+        assert_eq!(
+            json!({
+                "message": "Unknown target `foo`",
+                "category": "user",
+                "type": null,
+                "tags": ["ANALYSIS"],
+                "best_tag": "ANALYSIS",
+                "source_location": "buck2_foo/src/lib.rs::FooError",
+                "sub_error_categories": [],
+            }),
+            error_report_json(report)
+        );
+    }
+}
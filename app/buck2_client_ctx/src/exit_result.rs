@@ -15,6 +15,10 @@ use std::ops::FromResidual;
 use std::process::Command;
 
 use buck2_core::fs::paths::abs_path::AbsPathBuf;
+use buck2_events::errors::create_error_report;
+
+use crate::error_format::client_error_json;
+use crate::error_format::ErrorFormat;
 
 pub struct ExecArgs {
     prog: String,
@@ -27,11 +31,19 @@ pub struct ExecArgs {
 /// exit code. This is designed to be used as the return value from `main()`.
 ///
 /// The exit code is u8 integer and has the following meanings
-/// - Success             : 0
-/// - Uncategorized Error : 1
-/// - Infra Error         : 2
-/// - User Error          : 3
-/// - Signal Interruption : 129-192 (128 + signal number)
+/// - Success                   : 0
+/// - Uncategorized Error       : 1
+/// - Infra Error               : 2
+/// - User Error                : 3
+/// - Daemon Is Busy            : 4
+/// - Daemon Preempted          : 5
+/// - Daemon Connection Failure : 11
+/// - Test Failure              : 32 (returned by the test runner, see below)
+/// - Signal Interruption       : 129-192 (128 + signal number)
+///
+/// Errors are classified the same way whether they are returned by the daemon or raised in the
+/// client: by their tags first, then by their tier. `buck2 test` returns the code of the test
+/// runner when the build succeeded, and the code of the build errors otherwise.
 ///
 /// We can easily turn a anyhow::Result (or anyhow::Error, or even a message) into a ExitResult,
 /// but the reverse is not possible: once created, the only useful thing we can with a
@@ -43,6 +55,9 @@ pub struct ExitResult {
     /// Some stdout output that should be emitted prior to exiting. This allows commands to buffer
     /// their final output and choose not to send it if we opt to restart the command.
     stdout: Vec<u8>,
+
+    /// How the error, if any, is printed.
+    error_format: ErrorFormat,
 }
 
 enum ExitResultVariant {
@@ -66,6 +81,7 @@ impl ExitResult {
         Self {
             variant: ExitResultVariant::Status(status),
             stdout: Vec::new(),
+            error_format: ErrorFormat::Text,
         }
     }
 
@@ -93,6 +109,7 @@ impl ExitResult {
                 env,
             }),
             stdout: Vec::new(),
+            error_format: ErrorFormat::Text,
         }
     }
 
@@ -101,16 +118,20 @@ impl ExitResult {
     }
 
     pub fn err(err: anyhow::Error) -> Self {
-        let exit_code = if let Some(io_error) = err.downcast_ref::<ClientIoError>()
+        let (exit_code, err) = if let Some(io_error) = err.downcast_ref::<ClientIoError>()
             && io_error.0.kind() == io::ErrorKind::BrokenPipe
         {
-            ExitCode::BrokenPipe
+            (ExitCode::BrokenPipe, err)
         } else {
-            ExitCode::UnknownFailure
+            let err: buck2_error::Error = err.into();
+            let exit_code = ExitCode::for_errors([&create_error_report(&err)])
+                .unwrap_or(ExitCode::UnknownFailure);
+            (exit_code, err.into())
         };
         Self {
             variant: ExitResultVariant::StatusWithErr(exit_code, err),
             stdout: Vec::new(),
+            error_format: ErrorFormat::Text,
         }
     }
 
@@ -118,6 +139,7 @@ impl ExitResult {
         Self {
             variant: ExitResultVariant::StatusWithErr(exit_code, err),
             stdout: Vec::new(),
+            error_format: ErrorFormat::Text,
         }
     }
 
@@ -135,43 +157,23 @@ impl ExitResult {
         self
     }
 
+    pub fn with_error_format(mut self, error_format: ErrorFormat) -> Self {
+        self.error_format = error_format;
+        self
+    }
+
     pub fn report(self) -> ! {
         match crate::stdio::print_bytes(&self.stdout) {
-            Ok(()) => self.variant.report(),
-            Err(e) => Self::err(e).variant.report(),
+            Ok(()) => self.variant.report(self.error_format),
+            Err(e) => Self::err(e).variant.report(self.error_format),
         }
     }
 
     pub fn from_errors<'a>(errors: impl IntoIterator<Item = &'a buck2_data::ErrorReport>) -> Self {
-        let mut has_infra = false;
-        let mut has_user = false;
-        for e in errors {
-            if e.tags
-                .contains(&(buck2_data::error::ErrorTag::DaemonIsBusy as i32))
-            {
-                return Self::status(ExitCode::DaemonIsBusy);
-            }
-            if e.tags
-                .contains(&(buck2_data::error::ErrorTag::DaemonPreempted as i32))
-            {
-                return Self::status(ExitCode::DaemonPreempted);
-            }
-            match e.tier.and_then(buck2_data::error::ErrorTier::from_i32) {
-                Some(buck2_data::error::ErrorTier::Tier0) => has_infra = true,
-                Some(buck2_data::error::ErrorTier::Input) => has_user = true,
-                Some(buck2_data::error::ErrorTier::UnusedDefaultCategory) | None => (),
-            }
-        }
-        if has_infra {
-            return Self::status(ExitCode::InfraError);
-        }
-        if has_user {
-            return Self::status(ExitCode::UserError);
-        }
         // FIXME(JakobDegen): For compatibility with pre-existing behavior, we return infra failure
-        // here. However, it would be more honest to return the `1` status code that we use for
-        // "unknown"
-        Self::status(ExitCode::InfraError)
+        // for unclassified errors. However, it would be more honest to return the `1` status code
+        // that we use for "unknown"
+        Self::status(ExitCode::for_errors(errors).unwrap_or(ExitCode::InfraError))
     }
 }
 
@@ -214,7 +216,7 @@ impl<E: Into<::anyhow::Error>> FromResidual<Result<Infallible, E>> for ExitResul
 
 /// Implementing Termination lets us set the exit code for the process.
 impl ExitResultVariant {
-    pub fn report(self) -> ! {
+    pub fn report(self, error_format: ErrorFormat) -> ! {
         // NOTE: We use writeln instead of println so we don't panic if stderr is closed. This
         // ensures we get the desired exit code printed instead of potentially a panic.
         let mut exit_code = match self {
@@ -233,7 +235,14 @@ impl ExitResultVariant {
                         // No logging for those.
                     }
                     _ => {
-                        let _ignored = writeln!(io::stderr().lock(), "Command failed: {:?}", e);
+                        let _ignored = match error_format {
+                            ErrorFormat::Text => {
+                                writeln!(io::stderr().lock(), "Command failed: {:?}", e)
+                            }
+                            ErrorFormat::Json => {
+                                writeln!(io::stderr().lock(), "{}", client_error_json(e))
+                            }
+                        };
                     }
                 }
 
@@ -289,6 +298,44 @@ pub enum ExitCode {
 }
 
 impl ExitCode {
+    /// The exit code for a command which failed with `errors`, or `None` if none of them is
+    /// classified.
+    fn for_errors<'a>(
+        errors: impl IntoIterator<Item = &'a buck2_data::ErrorReport>,
+    ) -> Option<ExitCode> {
+        let mut has_infra = false;
+        let mut has_user = false;
+        for e in errors {
+            if e.tags
+                .contains(&(buck2_data::error::ErrorTag::DaemonIsBusy as i32))
+            {
+                return Some(ExitCode::DaemonIsBusy);
+            }
+            if e.tags
+                .contains(&(buck2_data::error::ErrorTag::DaemonPreempted as i32))
+            {
+                return Some(ExitCode::DaemonPreempted);
+            }
+            if e.tags
+                .contains(&(buck2_data::error::ErrorTag::DaemonConnect as i32))
+            {
+                return Some(ExitCode::ConnectError);
+            }
+            match e.tier.and_then(buck2_data::error::ErrorTier::from_i32) {
+                Some(buck2_data::error::ErrorTier::Tier0) => has_infra = true,
+                Some(buck2_data::error::ErrorTier::Input) => has_user = true,
+                Some(buck2_data::error::ErrorTier::UnusedDefaultCategory) | None => (),
+            }
+        }
+        if has_infra {
+            return Some(ExitCode::InfraError);
+        }
+        if has_user {
+            return Some(ExitCode::UserError);
+        }
+        None
+    }
+
     pub fn exit_code(self) -> u8 {
         use ExitCode::*;
         match self {
@@ -345,3 +392,45 @@ fn execv(args: ExecArgs) -> ! {
     ));
     ExitResult::err(err).report()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(
+        tier: Option<buck2_data::error::ErrorTier>,
+        tags: &[buck2_data::error::ErrorTag],
+    ) -> buck2_data::ErrorReport {
+        buck2_data::ErrorReport {
+            tier: tier.map(|t| t as i32),
+            tags: tags.iter().map(|t| *t as i32).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_exit_code_for_errors() {
+        use buck2_data::error::ErrorTag;
+        use buck2_data::error::ErrorTier;
+
+        let code = |errors: &[buck2_data::ErrorReport]| {
+            ExitCode::for_errors(errors).map(|c| c.exit_code())
+        };
+
+        assert_eq!(None, code(&[]));
+        assert_eq!(None, code(&[report(None, &[])]));
+        assert_eq!(Some(3), code(&[report(Some(ErrorTier::Input), &[])]));
+        assert_eq!(
+            Some(2),
+            code(&[
+                report(Some(ErrorTier::Input), &[]),
+                report(Some(ErrorTier::Tier0), &[])
+            ])
+        );
+        assert_eq!(
+            Some(11),
+            code(&[report(Some(ErrorTier::Tier0), &[ErrorTag::DaemonConnect])])
+        );
+        assert_eq!(Some(4), code(&[report(None, &[ErrorTag::DaemonIsBusy])]));
+    }
+}
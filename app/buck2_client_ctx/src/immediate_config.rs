@@ -12,23 +12,37 @@ use std::sync::OnceLock;
 use std::time::SystemTime;
 
 use anyhow::Context as _;
+use buck2_common::buildfiles::buildfile_names;
+use buck2_common::init::CommandAliasConfig;
 use buck2_common::init::DaemonStartupConfig;
+use buck2_common::init::FederationConfig;
+use buck2_common::init::LogStorageConfig;
 use buck2_common::invocation_roots::find_invocation_roots;
 use buck2_common::legacy_configs::cells::BuckConfigBasedCells;
+use buck2_common::legacy_configs::configs::LegacyBuckConfigs;
+use buck2_common::legacy_configs::key::BuckconfigKeyRef;
 use buck2_core::buck2_env;
 use buck2_core::cells::CellResolver;
 use buck2_core::fs::fs_util;
 use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
 use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
 use buck2_core::fs::paths::abs_path::AbsPath;
+use buck2_core::fs::paths::file_name::FileNameBuf;
 use buck2_core::fs::project::ProjectRoot;
 use buck2_core::fs::working_dir::WorkingDir;
 use prost::Message;
 
+use crate::command_alias::CommandAliasExpansion;
+
 /// Limited view of the root config. This does not follow includes.
 struct ImmediateConfig {
     cell_resolver: CellResolver,
+    configs: LegacyBuckConfigs,
     daemon_startup_config: DaemonStartupConfig,
+    log_storage_config: LogStorageConfig,
+    command_alias_config: CommandAliasConfig,
+    federation_config: FederationConfig,
+    env_passthrough: Vec<String>,
 }
 
 impl ImmediateConfig {
@@ -44,9 +58,20 @@ impl ImmediateConfig {
             .context("No config for root cell")?;
 
         Ok(ImmediateConfig {
-            cell_resolver: cells.cell_resolver,
             daemon_startup_config: DaemonStartupConfig::new(root_config)
                 .context("Error loading daemon startup config")?,
+            log_storage_config: LogStorageConfig::from_config(root_config)
+                .context("Error loading log storage config")?,
+            command_alias_config: CommandAliasConfig::from_config(root_config),
+            federation_config: FederationConfig::from_config(root_config),
+            env_passthrough: root_config
+                .parse_list(BuckconfigKeyRef {
+                    section: "buck2",
+                    property: "env_passthrough",
+                })?
+                .unwrap_or_default(),
+            cell_resolver: cells.cell_resolver,
+            configs: cells.configs_by_name,
         })
     }
 }
@@ -55,7 +80,12 @@ impl ImmediateConfig {
 /// processing any includes).
 struct ImmediateConfigContextData {
     cell_resolver: CellResolver,
+    configs: LegacyBuckConfigs,
     daemon_startup_config: DaemonStartupConfig,
+    log_storage_config: LogStorageConfig,
+    command_alias_config: CommandAliasConfig,
+    federation_config: FederationConfig,
+    env_passthrough: Vec<String>,
     project_filesystem: ProjectRoot,
 }
 
@@ -68,6 +98,7 @@ pub struct ImmediateConfigContext<'a> {
     data: OnceLock<ImmediateConfigContextData>,
     cwd: &'a WorkingDir,
     trace: Vec<AbsNormPathBuf>,
+    command_alias_expansion: Option<CommandAliasExpansion>,
 }
 
 impl<'a> ImmediateConfigContext<'a> {
@@ -76,6 +107,7 @@ impl<'a> ImmediateConfigContext<'a> {
             data: OnceLock::new(),
             cwd,
             trace: Vec::new(),
+            command_alias_expansion: None,
         }
     }
 
@@ -87,10 +119,36 @@ impl<'a> ImmediateConfigContext<'a> {
         &self.trace
     }
 
+    pub(crate) fn set_command_alias_expansion(&mut self, expansion: CommandAliasExpansion) {
+        self.command_alias_expansion = Some(expansion);
+    }
+
+    /// The command alias or default arguments applied to the command line, if any.
+    pub fn command_alias_expansion(&self) -> Option<&CommandAliasExpansion> {
+        self.command_alias_expansion.as_ref()
+    }
+
     pub fn daemon_startup_config(&self) -> anyhow::Result<&DaemonStartupConfig> {
         Ok(&self.data()?.daemon_startup_config)
     }
 
+    pub fn log_storage_config(&self) -> anyhow::Result<&LogStorageConfig> {
+        Ok(&self.data()?.log_storage_config)
+    }
+
+    pub fn command_alias_config(&self) -> anyhow::Result<&CommandAliasConfig> {
+        Ok(&self.data()?.command_alias_config)
+    }
+
+    pub fn federation_config(&self) -> anyhow::Result<&FederationConfig> {
+        Ok(&self.data()?.federation_config)
+    }
+
+    /// Client environment variables which actions may depend on with `env_passthrough`.
+    pub fn env_passthrough(&self) -> anyhow::Result<&[String]> {
+        Ok(&self.data()?.env_passthrough)
+    }
+
     pub(crate) fn canonicalize(&self, path: &Path) -> anyhow::Result<AbsNormPathBuf> {
         fs_util::canonicalize(self.cwd.path().as_path().join(path))
     }
@@ -117,6 +175,27 @@ impl<'a> ImmediateConfigContext<'a> {
         Ok(data.project_filesystem.resolve(&proj_relative))
     }
 
+    /// Resolves the directory of a package pattern, either a cell path (i.e., contains `//`) or a
+    /// path relative to the working directory, into an absolute path. Also returns the buildfile
+    /// names of the cell of the directory, as far as they can be known without following includes.
+    pub fn resolve_package_dir(
+        &self,
+        dir: &str,
+    ) -> anyhow::Result<(AbsNormPathBuf, Vec<FileNameBuf>)> {
+        let data = self.data()?;
+        let cwd = data.project_filesystem.relativize(self.cwd.path())?;
+        let dir = dir.trim_end_matches('/');
+        let proj_relative = match dir.split_once("//") {
+            Some((cell_alias, cell_relative_path)) => data
+                .cell_resolver
+                .resolve_cell_relative_path(cell_alias, cell_relative_path, cwd.as_ref())?,
+            None => cwd.join_normalized(dir)?,
+        };
+        let cell = data.cell_resolver.find(&proj_relative)?;
+        let buildfiles = buildfile_names(&data.configs, data.cell_resolver.root_cell(), cell)?;
+        Ok((data.project_filesystem.resolve(&proj_relative), buildfiles))
+    }
+
     fn data(&self) -> anyhow::Result<&ImmediateConfigContextData> {
         self.data
             .get_or_try_init(|| {
@@ -146,7 +225,12 @@ impl<'a> ImmediateConfigContext<'a> {
 
                 anyhow::Ok(ImmediateConfigContextData {
                     cell_resolver: cfg.cell_resolver,
+                    configs: cfg.configs,
                     daemon_startup_config,
+                    log_storage_config: cfg.log_storage_config,
+                    command_alias_config: cfg.command_alias_config,
+                    federation_config: cfg.federation_config,
+                    env_passthrough: cfg.env_passthrough,
                     project_filesystem,
                 })
             })
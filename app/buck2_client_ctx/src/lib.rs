@@ -22,11 +22,13 @@ pub mod build_count;
 pub mod client_cpu_tracker;
 pub mod client_ctx;
 pub mod client_metadata;
+pub mod command_alias;
 pub mod command_outcome;
 pub mod common;
 pub mod console_interaction_stream;
 pub mod daemon;
 pub mod daemon_constraints;
+pub mod error_format;
 pub mod events_ctx;
 pub mod exit_result;
 pub mod file_tailer;
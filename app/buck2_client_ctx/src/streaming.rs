@@ -25,6 +25,7 @@ use crate::daemon::client::connect::BuckdConnectOptions;
 use crate::daemon::client::connect::DaemonConstraintsRequest;
 use crate::daemon::client::connect::DesiredTraceIoState;
 use crate::daemon::client::BuckdClientConnector;
+use crate::error_format::ErrorFormat;
 use crate::exit_result::ExitCode;
 use crate::exit_result::ExitResult;
 use crate::path_arg::PathArg;
@@ -34,6 +35,7 @@ use crate::subscribers::get::try_get_build_graph_stats;
 use crate::subscribers::get::try_get_build_id_writer;
 use crate::subscribers::get::try_get_event_log_subscriber;
 use crate::subscribers::get::try_get_re_log_subscriber;
+use crate::subscribers::json_error_console::JsonErrorConsole;
 use crate::subscribers::recorder::try_get_invocation_recorder;
 use crate::subscribers::subscriber::EventSubscriber;
 use crate::subscribers::subscribers::EventSubscribers;
@@ -50,7 +52,7 @@ fn default_subscribers<'a, T: StreamingCommand>(
     // and log it in another (invocation_recorder)
     let log_size_counter_bytes = Some(Arc::new(AtomicU64::new(0)));
 
-    subscribers.push(get_console_with_root(
+    let console = get_console_with_root(
         ctx.trace_id.dupe(),
         console_opts.console_type,
         ctx.verbosity,
@@ -58,7 +60,11 @@ fn default_subscribers<'a, T: StreamingCommand>(
         None,
         T::COMMAND_NAME,
         console_opts.superconsole_config(),
-    )?);
+    )?;
+    match ctx.error_format {
+        ErrorFormat::Text => subscribers.push(console),
+        ErrorFormat::Json => subscribers.push(Box::new(JsonErrorConsole::new(console))),
+    }
 
     if let Some(event_log) = try_get_event_log_subscriber(cmd, ctx, log_size_counter_bytes.clone())?
     {
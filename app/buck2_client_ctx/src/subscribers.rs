@@ -7,12 +7,14 @@
  * of this source tree.
  */
 
+pub mod action_lockfile;
 pub(crate) mod build_graph_stats;
 pub(crate) mod build_id_writer;
 pub(crate) mod classify_server_stderr;
 pub(crate) mod errorconsole;
 pub mod event_log;
 pub mod get;
+pub(crate) mod json_error_console;
 pub(crate) mod observer;
 pub mod re_log;
 pub mod recorder;
@@ -22,3 +24,4 @@ pub mod subscriber;
 pub mod subscribers;
 pub mod superconsole;
 pub(crate) mod system_warning;
+pub mod test_report;
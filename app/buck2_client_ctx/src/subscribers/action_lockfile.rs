@@ -0,0 +1,193 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Records the digests of the actions needed by a build into a lockfile, and checks builds
+//! against a previously recorded lockfile. The digests come from the build response, which
+//! covers every action of the requested targets, including the ones not executed again.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use buck2_cli_proto::command_result;
+use buck2_core::fs::async_fs_util;
+use buck2_core::fs::working_dir::WorkingDir;
+use buck2_event_observer::display::display_action_identity;
+use buck2_event_observer::display::TargetDisplayOptions;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::subscribers::subscriber::EventSubscriber;
+
+const LOCKFILE_VERSION: u32 = 1;
+
+/// How many mismatching actions are listed in the error.
+const MAX_REPORTED_MISMATCHES: usize = 10;
+
+#[derive(Debug, buck2_error::Error)]
+enum ActionLockfileError {
+    #[error("Lockfile `{path}` has unsupported version {version}")]
+    UnsupportedVersion { path: String, version: u32 },
+    #[error(
+        "{count} action(s) have a different action digest than recorded in lockfile `{path}`:\n{mismatches}"
+    )]
+    Mismatch {
+        path: String,
+        count: usize,
+        mismatches: String,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct Lockfile {
+    version: u32,
+    /// Keyed by action identity, i.e. owner, category and identifier.
+    actions: BTreeMap<String, LockedAction>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LockedAction {
+    /// Digest of the command the action ran, if it ran one.
+    action_digest: Option<String>,
+    /// Digests of the action's outputs.
+    outputs: Vec<String>,
+}
+
+pub struct ActionLockfileSubscriber {
+    /// Where to write the lockfile for this build.
+    output: Option<PathBuf>,
+    /// Lockfile this build must match.
+    check_against: Option<PathBuf>,
+    actions: BTreeMap<String, LockedAction>,
+}
+
+impl ActionLockfileSubscriber {
+    pub fn new(output: Option<PathBuf>, check_against: Option<PathBuf>) -> Self {
+        Self {
+            output,
+            check_against,
+            actions: BTreeMap::new(),
+        }
+    }
+
+    fn record_actions(
+        &mut self,
+        actions: &[buck2_cli_proto::BuildActionDigests],
+    ) -> anyhow::Result<()> {
+        for action in actions {
+            let identity = display_action_identity(
+                action.key.as_ref(),
+                action.name.as_ref(),
+                TargetDisplayOptions::for_log(),
+            )?;
+            self.actions.insert(
+                identity,
+                LockedAction {
+                    action_digest: action.action_digest.clone(),
+                    outputs: action.output_digests.clone(),
+                },
+            );
+        }
+        Ok(())
+    }
+
+    async fn write_lockfile(&self, path: &Path) -> anyhow::Result<()> {
+        let path = WorkingDir::current_dir()?.resolve(path);
+        let lockfile = serde_json::to_string_pretty(&LockfileRef {
+            version: LOCKFILE_VERSION,
+            actions: &self.actions,
+        })?;
+        async_fs_util::write(&path, lockfile)
+            .await
+            .with_context(|| format!("Error writing lockfile `{}`", path))
+    }
+
+    async fn check_lockfile(&self, path: &Path) -> anyhow::Result<()> {
+        let path = WorkingDir::current_dir()?.resolve(path);
+        let lockfile: Lockfile = serde_json::from_str(
+            &async_fs_util::read_to_string(&path)
+                .await
+                .with_context(|| format!("Error reading lockfile `{}`", path))?,
+        )
+        .with_context(|| format!("Error parsing lockfile `{}`", path))?;
+        if lockfile.version != LOCKFILE_VERSION {
+            return Err(ActionLockfileError::UnsupportedVersion {
+                path: path.to_string(),
+                version: lockfile.version,
+            }
+            .into());
+        }
+
+        // Only actions needed by both builds can be compared.
+        let mismatches: Vec<_> = self
+            .actions
+            .iter()
+            .filter_map(|(identity, action)| {
+                let locked = lockfile.actions.get(identity)?;
+                (locked.action_digest != action.action_digest).then_some((identity, locked, action))
+            })
+            .collect();
+        if mismatches.is_empty() {
+            return Ok(());
+        }
+
+        let mut listed = String::new();
+        for (identity, locked, action) in mismatches.iter().take(MAX_REPORTED_MISMATCHES) {
+            writeln!(
+                listed,
+                "  {}: expected {}, got {}",
+                identity,
+                locked.action_digest.as_deref().unwrap_or("no command"),
+                action.action_digest.as_deref().unwrap_or("no command"),
+            )?;
+        }
+        if mismatches.len() > MAX_REPORTED_MISMATCHES {
+            writeln!(
+                listed,
+                "  ... and {} more",
+                mismatches.len() - MAX_REPORTED_MISMATCHES
+            )?;
+        }
+        Err(ActionLockfileError::Mismatch {
+            path: path.to_string(),
+            count: mismatches.len(),
+            mismatches: listed,
+        }
+        .into())
+    }
+}
+
+/// Same as `Lockfile`, but borrows the actions so we do not need to copy them to serialize.
+#[derive(Serialize)]
+struct LockfileRef<'a> {
+    version: u32,
+    actions: &'a BTreeMap<String, LockedAction>,
+}
+
+#[async_trait]
+impl EventSubscriber for ActionLockfileSubscriber {
+    async fn handle_command_result(
+        &mut self,
+        result: &buck2_cli_proto::CommandResult,
+    ) -> anyhow::Result<()> {
+        if let Some(command_result::Result::BuildResponse(response)) = &result.result {
+            self.record_actions(&response.action_digests)?;
+            if let Some(path) = &self.output {
+                self.write_lockfile(path).await?;
+            }
+            if let Some(path) = &self.check_against {
+                self.check_lockfile(path).await?;
+            }
+        }
+        Ok(())
+    }
+}
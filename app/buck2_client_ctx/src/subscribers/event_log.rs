@@ -15,6 +15,7 @@ use buck2_common::argv::SanitizedArgv;
 use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
 use buck2_core::fs::paths::abs_path::AbsPathBuf;
 use buck2_core::fs::working_dir::WorkingDir;
+use buck2_event_log::storage::EventLogStorage;
 use buck2_event_log::write::WriteEventLog;
 use buck2_events::BuckEvent;
 use buck2_util::cleanup_ctx::AsyncCleanupContext;
@@ -41,6 +42,7 @@ impl<'a> EventLog<'a> {
         command_name: String,
         log_size_counter_bytes: Option<Arc<AtomicU64>>,
         allow_vpnless: bool,
+        upload_on_failure: Option<Arc<dyn EventLogStorage>>,
     ) -> anyhow::Result<EventLog> {
         Ok(Self {
             async_cleanup_context: Some(async_cleanup_context),
@@ -53,6 +55,7 @@ impl<'a> EventLog<'a> {
                 command_name,
                 log_size_counter_bytes,
                 allow_vpnless,
+                upload_on_failure,
             )?,
         })
     }
@@ -10,6 +10,7 @@
 use std::sync::atomic::AtomicU64;
 use std::sync::Arc;
 
+use buck2_event_log::storage::event_log_storage;
 use buck2_event_observer::event_observer::NoopEventObserverExtra;
 use buck2_event_observer::verbosity::Verbosity;
 use buck2_wrapper_common::invocation_id::TraceId;
@@ -97,6 +98,12 @@ pub(crate) fn try_get_event_log_subscriber<'a, T: StreamingCommand>(
         return Ok(None);
     }
     let logdir = ctx.paths()?.log_dir();
+    let log_storage_config = ctx.immediate_config.log_storage_config()?;
+    let upload_on_failure = if log_storage_config.upload_on_failure {
+        event_log_storage(log_storage_config)?
+    } else {
+        None
+    };
     let log = EventLog::new(
         logdir,
         ctx.working_dir.clone(),
@@ -110,6 +117,7 @@ pub(crate) fn try_get_event_log_subscriber<'a, T: StreamingCommand>(
         T::COMMAND_NAME.to_owned(),
         log_size_counter_bytes,
         ctx.allow_vpnless()?,
+        upload_on_failure,
     )?;
     Ok(Some(Box::new(log)))
 }
@@ -0,0 +1,95 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use buck2_events::BuckEvent;
+
+use crate::error_format::error_report_json;
+use crate::subscribers::observer::ErrorObserver;
+use crate::subscribers::subscriber::EventSubscriber;
+use crate::subscribers::subscriber::Tick;
+
+/// Wraps a console for `--error-format json`: the errors of the command are printed as JSON
+/// instead of being rendered by the console.
+pub(crate) struct JsonErrorConsole<'a> {
+    console: Box<dyn EventSubscriber + 'a>,
+}
+
+impl<'a> JsonErrorConsole<'a> {
+    pub(crate) fn new(console: Box<dyn EventSubscriber + 'a>) -> Self {
+        Self { console }
+    }
+}
+
+#[async_trait]
+impl<'a> EventSubscriber for JsonErrorConsole<'a> {
+    async fn handle_output(&mut self, raw_output: &[u8]) -> anyhow::Result<()> {
+        self.console.handle_output(raw_output).await
+    }
+
+    async fn handle_tailer_stderr(&mut self, stderr: &str) -> anyhow::Result<()> {
+        self.console.handle_tailer_stderr(stderr).await
+    }
+
+    async fn handle_console_interaction(&mut self, c: char) -> anyhow::Result<()> {
+        self.console.handle_console_interaction(c).await
+    }
+
+    async fn handle_events(&mut self, events: &[Arc<BuckEvent>]) -> anyhow::Result<()> {
+        self.console.handle_events(events).await
+    }
+
+    async fn handle_command_result(
+        &mut self,
+        result: &buck2_cli_proto::CommandResult,
+    ) -> anyhow::Result<()> {
+        let buck2_cli_proto::CommandResult {
+            result: Some(buck2_cli_proto::command_result::Result::Error(e)),
+        } = result
+        else {
+            return self.console.handle_command_result(result).await;
+        };
+
+        // Let the console finish (e.g. the superconsole clears its status), without any errors
+        // to render.
+        self.console
+            .handle_command_result(&buck2_cli_proto::CommandResult { result: None })
+            .await?;
+        for e in &e.errors {
+            crate::eprintln!("{}", error_report_json(e.clone()))?;
+        }
+        Ok(())
+    }
+
+    async fn handle_error(&mut self, error: &buck2_error::Error) -> anyhow::Result<()> {
+        self.console.handle_error(error).await
+    }
+
+    async fn tick(&mut self, tick: &Tick) -> anyhow::Result<()> {
+        self.console.tick(tick).await
+    }
+
+    async fn exit(&mut self) -> anyhow::Result<()> {
+        self.console.exit().await
+    }
+
+    fn as_error_observer(&self) -> Option<&dyn ErrorObserver> {
+        self.console.as_error_observer()
+    }
+
+    fn handle_daemon_connection_failure(&mut self, error: &buck2_error::Error) {
+        self.console.handle_daemon_connection_failure(error)
+    }
+
+    fn handle_daemon_started(&mut self, reason: buck2_data::DaemonWasStartedReason) {
+        self.console.handle_daemon_started(reason)
+    }
+}
@@ -53,6 +53,7 @@ use termwiz::istty::IsTty;
 use crate::build_count::BuildCountManager;
 use crate::client_ctx::ClientCommandContext;
 use crate::client_metadata::ClientMetadata;
+use crate::command_alias::CommandAliasExpansion;
 use crate::common::CommonEventLogOptions;
 use crate::subscribers::classify_server_stderr::classify_server_stderr;
 use crate::subscribers::observer::ErrorObserver;
@@ -81,6 +82,8 @@ pub(crate) struct InvocationRecorder<'a> {
     write_to_path: Option<AbsPathBuf>,
     command_name: &'static str,
     cli_args: Vec<String>,
+    command_alias: Option<String>,
+    command_alias_args: Vec<String>,
     isolation_dir: String,
     start_time: Instant,
     async_cleanup_context: AsyncCleanupContext<'a>,
@@ -177,6 +180,7 @@ impl<'a> InvocationRecorder<'a> {
         write_to_path: Option<AbsPathBuf>,
         command_name: &'static str,
         sanitized_argv: Vec<String>,
+        command_alias_expansion: Option<CommandAliasExpansion>,
         trace_id: TraceId,
         isolation_dir: String,
         build_count_manager: BuildCountManager,
@@ -190,6 +194,10 @@ impl<'a> InvocationRecorder<'a> {
             write_to_path,
             command_name,
             cli_args: sanitized_argv,
+            command_alias: command_alias_expansion
+                .as_ref()
+                .and_then(|e| e.alias.clone()),
+            command_alias_args: command_alias_expansion.map(|e| e.args).unwrap_or_default(),
             isolation_dir,
             start_time: Instant::now(),
             async_cleanup_context,
@@ -407,6 +415,8 @@ impl<'a> InvocationRecorder<'a> {
             re_session_id: self.re_session_id.take().unwrap_or_default(),
             re_experiment_name: self.re_experiment_name.take().unwrap_or_default(),
             cli_args: self.cli_args.clone(),
+            command_alias: self.command_alias.clone(),
+            command_alias_args: self.command_alias_args.clone(),
             critical_path_duration: self.critical_path_duration.and_then(|x| x.try_into().ok()),
             metadata: Some(metadata),
             tags: self.tags.drain(..).collect(),
@@ -1199,7 +1209,9 @@ impl<'a> InvocationRecorder<'a> {
     }
 }
 
-fn process_error_report(error: buck2_data::ErrorReport) -> buck2_data::ProcessedErrorReport {
+pub(crate) fn process_error_report(
+    error: buck2_data::ErrorReport,
+) -> buck2_data::ProcessedErrorReport {
     let best_tag = best_tag(error.tags.iter().filter_map(|tag|
     // This should never fail, but it is safer to just ignore incorrect integers.
     ErrorTag::from_i32(*tag)))
@@ -1403,6 +1415,7 @@ pub(crate) fn try_get_invocation_recorder<'a>(
         write_to_path,
         command_name,
         sanitized_argv,
+        ctx.immediate_config.command_alias_expansion().cloned(),
         ctx.trace_id.dupe(),
         ctx.paths()?.isolation.as_str().to_owned(),
         BuildCountManager::new(ctx.paths()?.build_count_dir()),
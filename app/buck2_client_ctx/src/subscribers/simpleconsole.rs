@@ -18,6 +18,7 @@ use std::time::SystemTime;
 use anyhow::Context;
 use async_trait::async_trait;
 use buck2_event_observer::display;
+use buck2_event_observer::display::display_buck_configs;
 use buck2_event_observer::display::display_file_watcher_end;
 use buck2_event_observer::display::TargetDisplayOptions;
 use buck2_event_observer::event_observer::EventObserver;
@@ -237,6 +238,22 @@ where
         Ok(())
     }
 
+    pub(crate) async fn handle_buck_configs(
+        &mut self,
+        buck_configs: &buck2_data::BuckConfigs,
+    ) -> anyhow::Result<()> {
+        if self.verbosity.print_status() {
+            let lines = display_buck_configs(buck_configs);
+            for x in &lines {
+                echo!("{}", x)?;
+            }
+            if !lines.is_empty() {
+                self.notify_printed();
+            }
+        }
+        Ok(())
+    }
+
     pub(crate) async fn handle_event(&mut self, event: &Arc<BuckEvent>) -> anyhow::Result<()> {
         self.update_event_observer(event)?;
 
@@ -313,6 +330,9 @@ where
                     buck2_data::instant_event::Data::ActionError(error) => {
                         self.handle_action_error(error).await
                     }
+                    buck2_data::instant_event::Data::BuckConfigs(buck_configs) => {
+                        self.handle_buck_configs(buck_configs).await
+                    }
                     _ => Ok(()),
                 }
             }
@@ -394,6 +414,15 @@ where
             echo!("Test session: {}", test_session.info)?;
         }
 
+        if self.verbosity.print_summary() {
+            echo!(
+                "{}",
+                self.observer()
+                    .resource_summary()
+                    .render(self.observer().action_stats())
+            )?;
+        }
+
         Ok(())
     }
 
@@ -17,6 +17,7 @@ use anyhow::Context as _;
 use async_trait::async_trait;
 use buck2_data::CommandExecutionDetails;
 use buck2_event_observer::display;
+use buck2_event_observer::display::display_buck_configs;
 use buck2_event_observer::display::display_file_watcher_end;
 use buck2_event_observer::display::TargetDisplayOptions;
 use buck2_event_observer::event_observer::DebugEventObserverExtra;
@@ -485,6 +486,9 @@ impl StatefulSuperConsoleImpl {
                     buck2_data::instant_event::Data::ActionError(error) => {
                         self.handle_action_error(error).await
                     }
+                    buck2_data::instant_event::Data::BuckConfigs(buck_configs) => {
+                        self.handle_buck_configs(buck_configs).await
+                    }
                     _ => Ok(()),
                 }
             }
@@ -532,6 +536,19 @@ impl StatefulSuperConsoleImpl {
         Ok(())
     }
 
+    async fn handle_buck_configs(
+        &mut self,
+        buck_configs: &buck2_data::BuckConfigs,
+    ) -> anyhow::Result<()> {
+        if self.verbosity.print_status() {
+            self.super_console.emit(Lines(
+                display_buck_configs(buck_configs).into_map(|x| Line::sanitized(&x)),
+            ));
+        }
+
+        Ok(())
+    }
+
     async fn handle_console_message(
         &mut self,
         message: &buck2_data::ConsoleMessage,
@@ -721,6 +738,14 @@ impl StatefulSuperConsoleImpl {
         &mut self,
         result: &buck2_cli_proto::CommandResult,
     ) -> anyhow::Result<()> {
+        if self.verbosity.print_summary() {
+            let observer = &self.state.simple_console.observer;
+            let summary = observer.resource_summary().render(observer.action_stats());
+            self.super_console.emit(Lines::from_multiline_string(
+                &summary,
+                ContentStyle::default(),
+            ));
+        }
         let lines = StatefulSuperConsole::render_result_errors(result);
         self.super_console.emit(lines);
         Ok(())
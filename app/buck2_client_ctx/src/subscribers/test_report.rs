@@ -0,0 +1,519 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Writes the results of `buck2 test` as JUnit XML or TAP, so that CI systems can ingest them.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use buck2_cli_proto::command_result;
+use buck2_core::fs::async_fs_util;
+use buck2_core::fs::working_dir::WorkingDir;
+use buck2_data::TestStatus;
+use buck2_event_observer::display::display_configured_target_label;
+use buck2_event_observer::display::TargetDisplayOptions;
+use buck2_events::BuckEvent;
+
+use crate::subscribers::subscriber::EventSubscriber;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Pass,
+    Fail,
+    /// The test could not run or did not finish: fatal, timeout, unknown.
+    Error,
+    Skip,
+}
+
+impl Outcome {
+    fn from_status(status: TestStatus) -> Self {
+        match status {
+            TestStatus::Pass | TestStatus::ListingSuccess => Outcome::Pass,
+            TestStatus::Fail | TestStatus::ListingFailed | TestStatus::Rerun => Outcome::Fail,
+            TestStatus::Skip | TestStatus::Omitted => Outcome::Skip,
+            TestStatus::Fatal | TestStatus::Timeout | TestStatus::Unknown => Outcome::Error,
+            TestStatus::NotSetTestStatus => Outcome::Error,
+        }
+    }
+}
+
+struct TestCase {
+    name: String,
+    status: TestStatus,
+    duration: Option<Duration>,
+    message: Option<String>,
+    details: String,
+    /// Details of the failed attempts of this test that were retried.
+    reruns: Vec<String>,
+    /// Project relative paths of the artifacts collected for this test.
+    artifacts: Vec<String>,
+}
+
+impl TestCase {
+    fn outcome(&self) -> Outcome {
+        Outcome::from_status(self.status)
+    }
+}
+
+pub struct TestReportSubscriber {
+    junit: Option<PathBuf>,
+    tap: Option<PathBuf>,
+    /// Test cases, by target.
+    suites: BTreeMap<String, Vec<TestCase>>,
+}
+
+impl TestReportSubscriber {
+    pub fn new(junit: Option<PathBuf>, tap: Option<PathBuf>) -> Self {
+        Self {
+            junit,
+            tap,
+            suites: BTreeMap::new(),
+        }
+    }
+
+    fn handle_test_result(&mut self, result: &buck2_data::TestResult) -> anyhow::Result<()> {
+        let suite = match &result.target_label {
+            Some(label) => {
+                display_configured_target_label(label, TargetDisplayOptions::for_build_report())?
+            }
+            None => String::new(),
+        };
+        let status = TestStatus::from_i32(result.status).unwrap_or(TestStatus::Unknown);
+        let cases = self.suites.entry(suite).or_default();
+
+        // A rerun is a failed attempt of a test that is then retried: it is reported with the
+        // attempt that follows it.
+        let mut reruns = Vec::new();
+        if let Some(i) = cases
+            .iter()
+            .position(|c| c.name == result.name && c.status == TestStatus::Rerun)
+        {
+            let rerun = cases.remove(i);
+            reruns = rerun.reruns;
+            reruns.push(rerun.details);
+        }
+
+        cases.push(TestCase {
+            name: result.name.clone(),
+            status,
+            duration: result
+                .duration
+                .clone()
+                .and_then(|d| Duration::try_from(d).ok()),
+            message: result.msg.as_ref().map(|m| m.msg.clone()),
+            details: result.details.clone(),
+            reruns,
+            artifacts: result.artifacts.clone(),
+        });
+        Ok(())
+    }
+
+    fn handle_test_target_skipped(
+        &mut self,
+        skipped: &buck2_data::TestTargetSkipped,
+    ) -> anyhow::Result<()> {
+        let target = match &skipped.target_label {
+            Some(label) => {
+                display_configured_target_label(label, TargetDisplayOptions::for_build_report())?
+            }
+            None => String::new(),
+        };
+        self.suites
+            .entry(target.clone())
+            .or_default()
+            .push(TestCase {
+                name: target,
+                status: TestStatus::Skip,
+                duration: None,
+                message: Some(format!("Incompatible target: {}", skipped.reason)),
+                details: String::new(),
+                reruns: Vec::new(),
+                artifacts: Vec::new(),
+            });
+        Ok(())
+    }
+
+    async fn write(path: &PathBuf, contents: String) -> anyhow::Result<()> {
+        let path = WorkingDir::current_dir()?.resolve(path);
+        async_fs_util::write(&path, contents)
+            .await
+            .with_context(|| format!("Error writing test report `{}`", path))
+    }
+}
+
+/// Escapes text for an XML attribute or element, dropping the control characters XML does not
+/// allow (test output often contains terminal escapes).
+fn xml_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The `tests`, `failures`, `errors`, `skipped` and `time` attributes of a `testsuite` or
+/// `testsuites` element.
+fn junit_totals<'a>(cases: impl IntoIterator<Item = &'a TestCase>) -> String {
+    let (mut tests, mut failures, mut errors, mut skipped) = (0, 0, 0, 0);
+    let mut time = Duration::ZERO;
+    for case in cases {
+        tests += 1;
+        match case.outcome() {
+            Outcome::Pass => {}
+            Outcome::Fail => failures += 1,
+            Outcome::Error => errors += 1,
+            Outcome::Skip => skipped += 1,
+        }
+        time += case.duration.unwrap_or_default();
+    }
+    format!(
+        r#"tests="{}" failures="{}" errors="{}" skipped="{}" time="{:.3}""#,
+        tests,
+        failures,
+        errors,
+        skipped,
+        time.as_secs_f64()
+    )
+}
+
+fn junit(suites: &BTreeMap<String, Vec<TestCase>>) -> String {
+    let mut xml = String::new();
+    writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+    writeln!(
+        xml,
+        "<testsuites {}>",
+        junit_totals(suites.values().flatten())
+    )
+    .unwrap();
+
+    for (suite, cases) in suites {
+        writeln!(
+            xml,
+            r#"  <testsuite name="{}" {}>"#,
+            xml_escape(suite),
+            junit_totals(cases)
+        )
+        .unwrap();
+        for case in cases {
+            let message = xml_escape(case.message.as_deref().unwrap_or_default());
+            let details = xml_escape(&case.details);
+            let mut children = match case.outcome() {
+                Outcome::Pass => Vec::new(),
+                Outcome::Fail => vec![format!(
+                    r#"<failure message="{}">{}</failure>"#,
+                    message, details
+                )],
+                Outcome::Error => vec![format!(
+                    r#"<error message="{}" type="{}">{}</error>"#,
+                    message,
+                    case.status.as_str_name().to_lowercase(),
+                    details
+                )],
+                Outcome::Skip => vec![format!(r#"<skipped message="{}"/>"#, message)],
+            };
+            // Failed attempts of a test that was retried, as reported by Maven Surefire.
+            let rerun_element = if case.outcome() == Outcome::Pass {
+                "flakyFailure"
+            } else {
+                "rerunFailure"
+            };
+            for rerun in &case.reruns {
+                children.push(format!(
+                    r#"<{} message="rerun">{}</{}>"#,
+                    rerun_element,
+                    xml_escape(rerun),
+                    rerun_element
+                ));
+            }
+            // Attachments, as understood by the Jenkins JUnit Attachments plugin.
+            if !case.artifacts.is_empty() {
+                let attachments: String = case
+                    .artifacts
+                    .iter()
+                    .map(|a| format!("[[ATTACHMENT|{}]]\n", xml_escape(a)))
+                    .collect();
+                children.push(format!("<system-out>{}</system-out>", attachments));
+            }
+
+            write!(
+                xml,
+                r#"    <testcase name="{}" classname="{}" time="{:.3}""#,
+                xml_escape(&case.name),
+                xml_escape(suite),
+                case.duration.unwrap_or_default().as_secs_f64()
+            )
+            .unwrap();
+            if children.is_empty() {
+                writeln!(xml, "/>").unwrap();
+            } else {
+                writeln!(xml, ">").unwrap();
+                for child in children {
+                    writeln!(xml, "      {}", child).unwrap();
+                }
+                writeln!(xml, "    </testcase>").unwrap();
+            }
+        }
+        writeln!(xml, "  </testsuite>").unwrap();
+    }
+    writeln!(xml, "</testsuites>").unwrap();
+    xml
+}
+
+/// Escapes a TAP test description: `#` starts a directive.
+fn tap_description(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('#', "\\#")
+}
+
+fn tap(suites: &BTreeMap<String, Vec<TestCase>>) -> String {
+    let cases: Vec<(&String, &TestCase)> = suites
+        .iter()
+        .flat_map(|(suite, cases)| cases.iter().map(move |case| (suite, case)))
+        .collect();
+
+    let mut tap = String::new();
+    writeln!(tap, "TAP version 13").unwrap();
+    writeln!(tap, "1..{}", cases.len()).unwrap();
+    for (i, (suite, case)) in cases.iter().enumerate() {
+        let outcome = case.outcome();
+        let ok = match outcome {
+            Outcome::Pass | Outcome::Skip => "ok",
+            Outcome::Fail | Outcome::Error => "not ok",
+        };
+        write!(
+            tap,
+            "{} {} - {}",
+            ok,
+            i + 1,
+            tap_description(&if case.name == **suite {
+                case.name.clone()
+            } else {
+                format!("{} {}", suite, case.name)
+            }),
+        )
+        .unwrap();
+        if outcome == Outcome::Skip {
+            write!(
+                tap,
+                " # SKIP {}",
+                case.message
+                    .as_deref()
+                    .unwrap_or_default()
+                    .replace('\n', " ")
+            )
+            .unwrap();
+        }
+        writeln!(tap).unwrap();
+
+        if outcome == Outcome::Pass
+            && case.reruns.is_empty()
+            && case.duration.is_none()
+            && case.artifacts.is_empty()
+        {
+            continue;
+        }
+        // Diagnostics, as a YAML block.
+        writeln!(tap, "  ---").unwrap();
+        writeln!(
+            tap,
+            "  status: {}",
+            case.status.as_str_name().to_lowercase()
+        )
+        .unwrap();
+        if let Some(duration) = case.duration {
+            writeln!(tap, "  duration_ms: {}", duration.as_millis()).unwrap();
+        }
+        if !case.reruns.is_empty() {
+            writeln!(tap, "  reruns: {}", case.reruns.len()).unwrap();
+        }
+        if !case.artifacts.is_empty() {
+            writeln!(tap, "  artifacts:").unwrap();
+            for artifact in &case.artifacts {
+                writeln!(tap, "    - {}", serde_json::to_string(artifact).unwrap()).unwrap();
+            }
+        }
+        if matches!(outcome, Outcome::Fail | Outcome::Error) {
+            if let Some(message) = &case.message {
+                writeln!(
+                    tap,
+                    "  message: {}",
+                    serde_json::to_string(message).unwrap()
+                )
+                .unwrap();
+            }
+            if !case.details.is_empty() {
+                writeln!(tap, "  details: |").unwrap();
+                for line in case.details.lines() {
+                    writeln!(tap, "    {}", line).unwrap();
+                }
+            }
+        }
+        writeln!(tap, "  ...").unwrap();
+    }
+    tap
+}
+
+#[async_trait]
+impl EventSubscriber for TestReportSubscriber {
+    async fn handle_events(&mut self, events: &[Arc<BuckEvent>]) -> anyhow::Result<()> {
+        for event in events {
+            if let buck2_data::buck_event::Data::Instant(instant) = event.data() {
+                match &instant.data {
+                    Some(buck2_data::instant_event::Data::TestResult(result)) => {
+                        self.handle_test_result(result)?
+                    }
+                    Some(buck2_data::instant_event::Data::TestTargetSkipped(skipped)) => {
+                        self.handle_test_target_skipped(skipped)?
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_command_result(
+        &mut self,
+        result: &buck2_cli_proto::CommandResult,
+    ) -> anyhow::Result<()> {
+        if let Some(command_result::Result::TestResponse(..)) = &result.result {
+            if let Some(path) = &self.junit {
+                Self::write(path, junit(&self.suites)).await?;
+            }
+            if let Some(path) = &self.tap {
+                Self::write(path, tap(&self.suites)).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target_label() -> buck2_data::ConfiguredTargetLabel {
+        buck2_data::ConfiguredTargetLabel {
+            label: Some(buck2_data::TargetLabel {
+                package: "root//foo".to_owned(),
+                name: "t".to_owned(),
+            }),
+            configuration: Some(buck2_data::Configuration {
+                full_name: "cfg".to_owned(),
+            }),
+            execution_configuration: None,
+        }
+    }
+
+    fn result(name: &str, status: TestStatus, details: &str) -> buck2_data::TestResult {
+        buck2_data::TestResult {
+            name: name.to_owned(),
+            status: status as i32,
+            msg: None,
+            duration: Some(Duration::from_millis(1500).try_into().unwrap()),
+            details: details.to_owned(),
+            target_label: Some(target_label()),
+            artifacts: Vec::new(),
+        }
+    }
+
+    fn subscriber() -> TestReportSubscriber {
+        let mut subscriber = TestReportSubscriber::new(None, None);
+        for r in [
+            result("a", TestStatus::Pass, ""),
+            result("b", TestStatus::Rerun, "flaky <1>"),
+            result("b", TestStatus::Pass, ""),
+            buck2_data::TestResult {
+                artifacts: vec!["buck-out/v2/test/s/artifacts/c/log.txt".to_owned()],
+                ..result("c", TestStatus::Fail, "assert & fail")
+            },
+        ] {
+            subscriber.handle_test_result(&r).unwrap();
+        }
+        subscriber
+            .handle_test_target_skipped(&buck2_data::TestTargetSkipped {
+                target_label: Some(target_label()),
+                reason: "os".to_owned(),
+            })
+            .unwrap();
+        subscriber
+    }
+
+    #[test]
+    fn test_junit() {
+        assert_eq!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites tests="4" failures="1" errors="0" skipped="1" time="4.500">
+  <testsuite name="root//foo:t (cfg)" tests="4" failures="1" errors="0" skipped="1" time="4.500">
+    <testcase name="a" classname="root//foo:t (cfg)" time="1.500"/>
+    <testcase name="b" classname="root//foo:t (cfg)" time="1.500">
+      <flakyFailure message="rerun">flaky &lt;1&gt;</flakyFailure>
+    </testcase>
+    <testcase name="c" classname="root//foo:t (cfg)" time="1.500">
+      <failure message="">assert &amp; fail</failure>
+      <system-out>[[ATTACHMENT|buck-out/v2/test/s/artifacts/c/log.txt]]
+</system-out>
+    </testcase>
+    <testcase name="root//foo:t (cfg)" classname="root//foo:t (cfg)" time="0.000">
+      <skipped message="Incompatible target: os"/>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#,
+            junit(&subscriber().suites)
+        );
+    }
+
+    #[test]
+    fn test_tap() {
+        assert_eq!(
+            r#"TAP version 13
+1..4
+ok 1 - root//foo:t (cfg) a
+  ---
+  status: pass
+  duration_ms: 1500
+  ...
+ok 2 - root//foo:t (cfg) b
+  ---
+  status: pass
+  duration_ms: 1500
+  reruns: 1
+  ...
+not ok 3 - root//foo:t (cfg) c
+  ---
+  status: fail
+  duration_ms: 1500
+  artifacts:
+    - "buck-out/v2/test/s/artifacts/c/log.txt"
+  details: |
+    assert & fail
+  ...
+ok 4 - root//foo:t (cfg) # SKIP Incompatible target: os
+  ---
+  status: skip
+  ...
+"#,
+            tap(&subscriber().suites)
+        );
+    }
+}
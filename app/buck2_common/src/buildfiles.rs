@@ -18,6 +18,8 @@ use dice::Key;
 use gazebo::prelude::SliceExt as _;
 use gazebo::prelude::VecExt as _;
 
+use crate::dice::cells::HasCellResolver;
+use crate::legacy_configs::configs::LegacyBuckConfigs;
 use crate::legacy_configs::dice::HasLegacyConfigs;
 use crate::legacy_configs::key::BuckconfigKeyRef;
 use crate::legacy_configs::view::LegacyBuckConfigView;
@@ -64,11 +66,76 @@ fn parse_buildfile_name(mut config: impl LegacyBuckConfigView) -> anyhow::Result
     Ok(base)
 }
 
+/// Deal with the root cell's `buildfile_names` section, which lets the root cell pick the
+/// buildfile names of other cells (e.g. third-party cells whose config it doesn't control). The
+/// list is used verbatim, in priority order, with no `.v2` variants added.
+fn parse_buildfile_name_override(
+    mut root_config: impl LegacyBuckConfigView,
+    cell: CellName,
+) -> anyhow::Result<Option<Vec<FileNameBuf>>> {
+    root_config
+        .parse_list::<String>(BuckconfigKeyRef {
+            section: "buildfile_names",
+            property: cell.as_str(),
+        })?
+        .map(|names| names.into_try_map(FileNameBuf::try_from))
+        .transpose()
+}
+
+/// The buildfile names of `cell`, for callers that have parsed the configs themselves rather
+/// than going through DICE (e.g. client side commands).
+pub fn buildfile_names(
+    configs: &LegacyBuckConfigs,
+    root_cell: CellName,
+    cell: CellName,
+) -> anyhow::Result<Vec<FileNameBuf>> {
+    if let Some(names) = parse_buildfile_name_override(configs.get(root_cell)?, cell)? {
+        return Ok(names);
+    }
+    parse_buildfile_name(configs.get(cell)?)
+}
+
+/// What to do when a package directory contains more than one of the configured buildfile
+/// names, e.g. both `BUCK` and `BUILD.bazel` while migrating between build systems.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, allocative::Allocative)]
+pub enum MultipleBuildfilesPolicy {
+    /// Use the candidate that comes first in the configured list.
+    UseFirst,
+    /// Fail to load the package, so that stale buildfiles can't silently shadow each other.
+    Error,
+}
+
+#[derive(buck2_error::Error, Debug)]
+#[buck2(input)]
+#[error("Invalid value `{0}` for `buildfile.on_multiple`, expected `first` or `error`")]
+struct InvalidMultipleBuildfilesPolicy(String);
+
+fn parse_multiple_buildfiles_policy(
+    mut config: impl LegacyBuckConfigView,
+) -> anyhow::Result<MultipleBuildfilesPolicy> {
+    match config
+        .get(BuckconfigKeyRef {
+            section: "buildfile",
+            property: "on_multiple",
+        })?
+        .as_deref()
+    {
+        None | Some("first") => Ok(MultipleBuildfilesPolicy::UseFirst),
+        Some("error") => Ok(MultipleBuildfilesPolicy::Error),
+        Some(other) => Err(InvalidMultipleBuildfilesPolicy(other.to_owned()).into()),
+    }
+}
+
 pub trait HasBuildfiles {
     fn get_buildfiles(
         &mut self,
         cell: CellName,
     ) -> impl Future<Output = anyhow::Result<Arc<[FileNameBuf]>>>;
+
+    fn get_multiple_buildfiles_policy(
+        &mut self,
+        cell: CellName,
+    ) -> impl Future<Output = anyhow::Result<MultipleBuildfilesPolicy>>;
 }
 
 #[derive(
@@ -92,6 +159,11 @@ impl Key for BuildfilesKey {
         ctx: &mut DiceComputations,
         _cancellations: &CancellationContext,
     ) -> Self::Value {
+        let root_cell = ctx.get_cell_resolver().await?.root_cell();
+        let root_config = ctx.get_legacy_config_on_dice(root_cell).await?;
+        if let Some(names) = parse_buildfile_name_override(root_config.view(ctx), self.0)? {
+            return Ok(names.into());
+        }
         let config = ctx.get_legacy_config_on_dice(self.0).await?;
         Ok(parse_buildfile_name(config.view(ctx))?.into())
     }
@@ -104,10 +176,50 @@ impl Key for BuildfilesKey {
     }
 }
 
+#[derive(
+    Clone,
+    derive_more::Display,
+    Debug,
+    Hash,
+    Eq,
+    PartialEq,
+    allocative::Allocative
+)]
+#[display(fmt = "MultipleBuildfilesPolicyKey({})", "self.0")]
+struct MultipleBuildfilesPolicyKey(CellName);
+
+#[async_trait::async_trait]
+impl Key for MultipleBuildfilesPolicyKey {
+    type Value = buck2_error::Result<MultipleBuildfilesPolicy>;
+
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        let config = ctx.get_legacy_config_on_dice(self.0).await?;
+        Ok(parse_multiple_buildfiles_policy(config.view(ctx))?)
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        match (x, y) {
+            (Ok(x), Ok(y)) => x == y,
+            _ => false,
+        }
+    }
+}
+
 impl HasBuildfiles for DiceComputations<'_> {
     async fn get_buildfiles(&mut self, cell: CellName) -> anyhow::Result<Arc<[FileNameBuf]>> {
         Ok(self.compute(&BuildfilesKey(cell)).await??)
     }
+
+    async fn get_multiple_buildfiles_policy(
+        &mut self,
+        cell: CellName,
+    ) -> anyhow::Result<MultipleBuildfilesPolicy> {
+        Ok(self.compute(&MultipleBuildfilesPolicyKey(cell)).await??)
+    }
 }
 
 #[cfg(test)]
@@ -120,6 +232,9 @@ mod tests {
     use indoc::indoc;
 
     use crate::buildfiles::parse_buildfile_name;
+    use crate::buildfiles::parse_buildfile_name_override;
+    use crate::buildfiles::parse_multiple_buildfiles_policy;
+    use crate::buildfiles::MultipleBuildfilesPolicy;
     use crate::legacy_configs::cells::BuckConfigBasedCells;
     use crate::legacy_configs::configs::testing::TestConfigParserFileOps;
 
@@ -196,4 +311,63 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_buildfile_names_from_root() -> anyhow::Result<()> {
+        let mut file_ops = TestConfigParserFileOps::new(&[
+            (
+                "/.buckconfig",
+                indoc!(
+                    r#"
+                            [cells]
+                                root = .
+                                bazel = bazel/
+                            [buildfile_names]
+                                bazel = BUILD.bazel, BUILD
+                        "#
+                ),
+            ),
+            (
+                "/bazel/.buckconfig",
+                indoc!(
+                    r#"
+                            [buildfile]
+                                name = TARGETS
+                                on_multiple = error
+                        "#
+                ),
+            ),
+        ])?;
+
+        let project_fs = create_project_filesystem();
+        let configs = BuckConfigBasedCells::parse_with_file_ops(
+            &project_fs,
+            &mut file_ops,
+            &[],
+            ProjectRelativePath::empty(),
+        )?
+        .configs_by_name;
+
+        let root = configs.get(CellName::testing_new("root"))?;
+        assert_eq!(
+            Some(vec!["BUILD.bazel", "BUILD"]),
+            parse_buildfile_name_override(root, CellName::testing_new("bazel"))?
+                .map(|names| names.map(|f| f.as_str().to_owned()))
+        );
+        assert_eq!(
+            None,
+            parse_buildfile_name_override(root, CellName::testing_new("root"))?
+        );
+
+        assert_eq!(
+            MultipleBuildfilesPolicy::UseFirst,
+            parse_multiple_buildfiles_policy(root)?
+        );
+        assert_eq!(
+            MultipleBuildfilesPolicy::Error,
+            parse_multiple_buildfiles_policy(configs.get(CellName::testing_new("bazel"))?)?
+        );
+
+        Ok(())
+    }
 }
@@ -10,6 +10,7 @@
 //! Common dice operations
 
 pub mod cells;
+pub mod client_env;
 pub mod cycles;
 pub mod data;
 pub mod file_ops;
@@ -0,0 +1,290 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Dice computations exposing the client's environment.
+//!
+//! The client only sends the variables listed in `buck2.env_passthrough` of the root buckconfig.
+//! Each of them is injected as its own key, so that a change to one variable only invalidates
+//! the computations which depend on that variable. The names sent are injected too, so that
+//! computations which read a variable which is no longer sent are invalidated.
+
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use allocative::Allocative;
+use async_trait::async_trait;
+use derive_more::Display;
+use dice::DiceComputations;
+use dice::DiceTransactionUpdater;
+use dice::InjectedKey;
+use futures::FutureExt;
+use sorted_vector_map::SortedVectorMap;
+
+#[derive(Debug, buck2_error::Error)]
+#[buck2(input)]
+enum ClientEnvError {
+    #[error(
+        "Environment variable `{0}` is used by `env_passthrough` but is not listed in \
+        `buck2.env_passthrough` of the root buckconfig, so the client does not send it"
+    )]
+    NotDeclared(String),
+}
+
+#[derive(Clone, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "ClientEnvVar({})", _0)]
+struct ClientEnvVarKey(Arc<str>);
+
+impl InjectedKey for ClientEnvVarKey {
+    type Value = Option<Arc<str>>;
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+/// Names of the variables sent by the client of the current command. Only these were injected,
+/// and those injected by earlier commands may be stale.
+#[derive(Clone, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "DeclaredClientEnv")]
+struct DeclaredClientEnvKey;
+
+impl InjectedKey for DeclaredClientEnvKey {
+    type Value = Arc<BTreeSet<String>>;
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+pub trait SetClientEnvironment {
+    /// Inject the variables sent by the client, with `None` for those which are not set. These
+    /// are all the variables which computations may read.
+    fn set_client_environment(
+        &mut self,
+        env: impl IntoIterator<Item = (String, Option<String>)>,
+    ) -> anyhow::Result<()>;
+}
+
+impl SetClientEnvironment for DiceTransactionUpdater {
+    fn set_client_environment(
+        &mut self,
+        env: impl IntoIterator<Item = (String, Option<String>)>,
+    ) -> anyhow::Result<()> {
+        let mut declared = BTreeSet::new();
+        self.changed_to(env.into_iter().map(|(name, value)| {
+            let key = ClientEnvVarKey(Arc::from(name.as_str()));
+            declared.insert(name);
+            (key, value.map(Arc::from))
+        }))?;
+        Ok(self.changed_to([(DeclaredClientEnvKey, Arc::new(declared))])?)
+    }
+}
+
+#[async_trait]
+pub trait GetClientEnvironment {
+    /// Value of a variable in the environment of the client which issued the current command.
+    async fn get_client_env_var(&mut self, name: &str) -> anyhow::Result<Option<Arc<str>>>;
+
+    /// Values of the given variables which are set in the client environment. Unset variables
+    /// are omitted, but the computation still depends on them.
+    async fn get_client_env_vars(
+        &mut self,
+        names: &[String],
+    ) -> anyhow::Result<SortedVectorMap<String, String>>;
+}
+
+#[async_trait]
+impl GetClientEnvironment for DiceComputations<'_> {
+    async fn get_client_env_var(&mut self, name: &str) -> anyhow::Result<Option<Arc<str>>> {
+        if !self.compute(&DeclaredClientEnvKey).await?.contains(name) {
+            return Err(ClientEnvError::NotDeclared(name.to_owned()).into());
+        }
+        Ok(self.compute(&ClientEnvVarKey(Arc::from(name))).await?)
+    }
+
+    async fn get_client_env_vars(
+        &mut self,
+        names: &[String],
+    ) -> anyhow::Result<SortedVectorMap<String, String>> {
+        let values = self
+            .compute_join(names.iter(), |ctx: &mut DiceComputations, name| {
+                async move { ctx.get_client_env_var(name).await }.boxed()
+            })
+            .await;
+        let mut res = SortedVectorMap::new();
+        for (name, value) in names.iter().zip(values) {
+            if let Some(value) = value? {
+                res.insert(name.clone(), (*value).to_owned());
+            }
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+
+    use buck2_futures::cancellation::CancellationContext;
+    use dice::DetectCycles;
+    use dice::Dice;
+    use dice::DiceTransaction;
+    use dice::Key;
+    use dice::UserComputationData;
+    use dupe::Dupe;
+
+    use super::*;
+
+    /// Counts the computations of `ReadClientEnvKey`.
+    #[derive(Default)]
+    struct Computations(AtomicUsize);
+
+    /// Reads a client variable, like an action declaring it in `env_passthrough`.
+    #[derive(Clone, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+    #[display(fmt = "ReadClientEnv({})", _0)]
+    struct ReadClientEnvKey(&'static str);
+
+    #[async_trait]
+    impl Key for ReadClientEnvKey {
+        type Value = buck2_error::Result<Option<Arc<str>>>;
+
+        async fn compute(
+            &self,
+            ctx: &mut DiceComputations,
+            _cancellations: &CancellationContext,
+        ) -> Self::Value {
+            ctx.per_transaction_data()
+                .data
+                .get::<Arc<Computations>>()
+                .unwrap()
+                .0
+                .fetch_add(1, Ordering::SeqCst);
+            Ok(ctx.get_client_env_var(self.0).await?)
+        }
+
+        fn validity(x: &Self::Value) -> bool {
+            x.is_ok()
+        }
+
+        fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+            match (x, y) {
+                (Ok(x), Ok(y)) => x == y,
+                _ => false,
+            }
+        }
+    }
+
+    /// A command whose client sent `env`.
+    async fn command(
+        dice: &Arc<Dice>,
+        computations: &Arc<Computations>,
+        env: &[(&str, Option<&str>)],
+    ) -> DiceTransaction {
+        let mut data = UserComputationData::new();
+        data.data.set(computations.dupe());
+        let mut updater = dice.updater_with_data(data);
+        updater
+            .set_client_environment(
+                env.iter()
+                    .map(|(name, value)| ((*name).to_owned(), value.map(str::to_owned))),
+            )
+            .unwrap();
+        updater.commit().await
+    }
+
+    async fn read(ctx: &mut DiceTransaction, name: &'static str) -> anyhow::Result<Option<String>> {
+        Ok(ctx
+            .compute(&ReadClientEnvKey(name))
+            .await??
+            .map(|value| (*value).to_owned()))
+    }
+
+    #[tokio::test]
+    async fn test_recompute_only_when_value_changes() -> anyhow::Result<()> {
+        let dice = Dice::builder().build(DetectCycles::Enabled);
+        let computations = Arc::new(Computations::default());
+        let count = || computations.0.load(Ordering::SeqCst);
+
+        let mut ctx = command(
+            &dice,
+            &computations,
+            &[("FOO", Some("1")), ("BAR", Some("1"))],
+        )
+        .await;
+        assert_eq!(Some("1".to_owned()), read(&mut ctx, "FOO").await?);
+        assert_eq!(1, count());
+
+        // Same value, and another variable changed.
+        let mut ctx = command(
+            &dice,
+            &computations,
+            &[("FOO", Some("1")), ("BAR", Some("2"))],
+        )
+        .await;
+        assert_eq!(Some("1".to_owned()), read(&mut ctx, "FOO").await?);
+        assert_eq!(1, count());
+
+        let mut ctx = command(
+            &dice,
+            &computations,
+            &[("FOO", Some("2")), ("BAR", Some("2"))],
+        )
+        .await;
+        assert_eq!(Some("2".to_owned()), read(&mut ctx, "FOO").await?);
+        assert_eq!(2, count());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unset() -> anyhow::Result<()> {
+        let dice = Dice::builder().build(DetectCycles::Enabled);
+        let computations = Arc::new(Computations::default());
+
+        let mut ctx = command(&dice, &computations, &[("FOO", None)]).await;
+        assert_eq!(None, read(&mut ctx, "FOO").await?);
+
+        let mut ctx = command(&dice, &computations, &[("FOO", Some("1"))]).await;
+        assert_eq!(Some("1".to_owned()), read(&mut ctx, "FOO").await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_not_declared() -> anyhow::Result<()> {
+        let dice = Dice::builder().build(DetectCycles::Enabled);
+        let computations = Arc::new(Computations::default());
+
+        let mut ctx = command(&dice, &computations, &[("FOO", Some("1"))]).await;
+        let err = read(&mut ctx, "BAR").await.unwrap_err();
+        assert!(
+            format!("{:#}", err).contains("`BAR` is used by `env_passthrough`"),
+            "{:#}",
+            err
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_no_longer_declared() -> anyhow::Result<()> {
+        let dice = Dice::builder().build(DetectCycles::Enabled);
+        let computations = Arc::new(Computations::default());
+
+        let mut ctx = command(&dice, &computations, &[("FOO", Some("1"))]).await;
+        assert_eq!(Some("1".to_owned()), read(&mut ctx, "FOO").await?);
+
+        // `FOO` is still injected with the value sent by the previous command, but must not be
+        // used, even by computations which read it then.
+        let mut ctx = command(&dice, &computations, &[("BAR", Some("1"))]).await;
+        assert!(read(&mut ctx, "FOO").await.is_err());
+
+        let mut ctx = command(&dice, &computations, &[("FOO", Some("1"))]).await;
+        assert_eq!(Some("1".to_owned()), read(&mut ctx, "FOO").await?);
+        Ok(())
+    }
+}
@@ -25,3 +25,15 @@ pub fn find_buildfile<'a>(
     }
     None
 }
+
+/// All buildfile candidates present in the listing, in priority order.
+pub fn find_all_buildfiles<'a>(
+    buildfile_candidates: &'a [FileNameBuf],
+    dir_listing: &[SimpleDirEntry],
+) -> Vec<&'a FileName> {
+    buildfile_candidates
+        .iter()
+        .filter(|candidate| dir_listing.iter().any(|e| e.file_name == **candidate))
+        .map(|candidate| candidate.as_ref())
+        .collect()
+}
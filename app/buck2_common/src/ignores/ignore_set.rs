@@ -14,6 +14,26 @@ use globset::GlobSetBuilder;
 use once_cell::sync::Lazy;
 use regex::Regex;
 
+/// File at the root of a cell listing additional ignored paths in gitignore syntax.
+pub const IGNORE_FILE_NAME: &str = ".buck2ignore";
+
+static GLOB_CHARS: Lazy<Regex> = Lazy::new(|| Regex::new(r"[*?{\[]").unwrap());
+
+#[derive(Debug, buck2_error::Error)]
+#[buck2(input)]
+enum IgnoreFileError {
+    #[error(
+        "Negated patterns are not supported in `{}`, got `{0}`",
+        IGNORE_FILE_NAME
+    )]
+    Negation(String),
+    #[error(
+        "Patterns in `{}` must not contain commas, got `{0}`",
+        IGNORE_FILE_NAME
+    )]
+    Comma(String),
+}
+
 #[derive(Debug, Allocative)]
 pub struct IgnoreSet {
     #[allocative(skip)]
@@ -65,8 +85,6 @@ impl IgnoreSet {
 
             let val = val.trim_end_matches('/');
 
-            static GLOB_CHARS: Lazy<Regex> = Lazy::new(|| Regex::new(r"[*?{\[]").unwrap());
-
             if GLOB_CHARS.is_match(val) {
                 patterns_builder.add(
                     globset::GlobBuilder::new(val)
@@ -85,6 +103,45 @@ impl IgnoreSet {
         })
     }
 
+    /// Converts the lines of an ignore file written in gitignore syntax to ignore spec entries.
+    ///
+    /// Blank lines and lines starting with `#` are skipped. Patterns containing a `/` are relative
+    /// to the cell root, other patterns match a file or directory name at any depth. Anything
+    /// below a matched directory is ignored too. Negated (`!`) patterns are not supported.
+    pub fn spec_from_ignore_file<'a>(
+        lines: impl IntoIterator<Item = &'a str>,
+    ) -> anyhow::Result<Vec<String>> {
+        let mut spec = Vec::new();
+        for line in lines {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('!') {
+                return Err(IgnoreFileError::Negation(line.to_owned()).into());
+            }
+            if line.contains(',') {
+                return Err(IgnoreFileError::Comma(line.to_owned()).into());
+            }
+
+            let pattern = line.trim_end_matches('/');
+            let pattern = match pattern.strip_prefix('/') {
+                Some(anchored) => anchored.to_owned(),
+                None if pattern.contains('/') => pattern.to_owned(),
+                None => format!("**/{}", pattern),
+            };
+            if pattern.is_empty() {
+                continue;
+            }
+            // Non-glob entries of the spec already cover their subtree.
+            if GLOB_CHARS.is_match(&pattern) {
+                spec.push(format!("{}/**", pattern));
+            }
+            spec.push(pattern);
+        }
+        Ok(spec)
+    }
+
     /// Returns a pattern that matches the candidate if there is one.
     pub(crate) fn matches_candidate(&self, candidate: &Candidate) -> Option<&str> {
         match self.globset.matches_candidate(candidate).as_slice() {
@@ -109,4 +166,32 @@ mod tests {
         assert!(set.is_match(CellRelativePath::testing_new("buck-out/gen/src/file.txt")));
         assert!(!set.is_match(CellRelativePath::testing_new("src/file.txt")));
     }
+
+    #[test]
+    fn test_spec_from_ignore_file() {
+        let spec = IgnoreSet::spec_from_ignore_file(
+            "# vendored code\n/third-party/\nnode_modules\n\ngenerated/*.pb\n".lines(),
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                "third-party",
+                "**/node_modules/**",
+                "**/node_modules",
+                "generated/*.pb/**",
+                "generated/*.pb",
+            ],
+            spec
+        );
+
+        let set = IgnoreSet::from_ignore_spec(&spec.join(","), false).unwrap();
+        assert!(set.is_match(CellRelativePath::testing_new("third-party/lib/a.c")));
+        assert!(set.is_match(CellRelativePath::testing_new("node_modules")));
+        assert!(set.is_match(CellRelativePath::testing_new("web/node_modules/x/y.js")));
+        assert!(set.is_match(CellRelativePath::testing_new("generated/a.pb")));
+        assert!(!set.is_match(CellRelativePath::testing_new("src/third-party/a.c")));
+        assert!(!set.is_match(CellRelativePath::testing_new("generated/sub/a.pb")));
+
+        assert!(IgnoreSet::spec_from_ignore_file(["!keep"]).is_err());
+    }
 }
@@ -7,6 +7,7 @@
  * of this source tree.
  */
 
+use std::collections::BTreeMap;
 use std::str::FromStr;
 use std::time::Duration;
 
@@ -219,6 +220,43 @@ impl FromStr for ResourceControlStatus {
     }
 }
 
+/// Trade-off between the memory used by the daemon and the speed of incremental builds.
+/// The corresponding buckconfig is `buck2.memory_profile`, one of `{default | low}`.
+///
+/// Only the memory held between commands is affected: the peak memory of a command is the same
+/// with both profiles, as `low` does not (yet) disable speculative caches, evict analysis results
+/// from DICE during a command, or use more compact representations of the graph.
+#[derive(
+    Allocative,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq
+)]
+pub enum MemoryProfile {
+    #[default]
+    /// The build graph and the dep files are kept between commands.
+    Default,
+    /// The build graph and the dep files are dropped whenever no command is running, so an idle
+    /// daemon uses little memory, but every command starts from a cold graph.
+    Low,
+}
+
+impl FromStr for MemoryProfile {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "default" => Ok(Self::Default),
+            "low" => Ok(Self::Low),
+            _ => Err(anyhow::anyhow!("Invalid memory profile: `{}`", s)),
+        }
+    }
+}
+
 impl ResourceControlConfig {
     pub fn from_config(config: &LegacyBuckConfig) -> anyhow::Result<Self> {
         if let Some(env_conf) = buck2_env!(
@@ -250,6 +288,178 @@ impl ResourceControlConfig {
     }
 }
 
+/// Where event logs are shared from, read by the client only.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LogStorageConfig {
+    /// The backend logs are uploaded to and downloaded from, if any.
+    /// The corresponding buckconfig is `buck2_log_storage.backend`, one of `{dir | http | command}`.
+    pub backend: Option<LogStorageBackend>,
+    /// Absolute path of the directory logs are copied to, for the `dir` backend.
+    /// The corresponding buckconfig is `buck2_log_storage.dir`.
+    pub dir: Option<String>,
+    /// URL prefix logs are `PUT` to and `GET` from, for the `http` backend. Works with S3 or GCS
+    /// buckets accepting unsigned requests, or any server implementing those two methods.
+    /// The corresponding buckconfig is `buck2_log_storage.url`.
+    pub url: Option<String>,
+    /// Command invoked as `<command> upload <path> <name>` or `<command> download <name> <path>`,
+    /// for the `command` backend.
+    /// The corresponding buckconfig is `buck2_log_storage.command`.
+    pub command: Option<String>,
+    /// Whether the log of a failed command is uploaded when the command exits.
+    /// The corresponding buckconfig is `buck2_log_storage.upload_on_failure`.
+    pub upload_on_failure: bool,
+    /// Directory test artifacts are copied to, for the `dir` backend. Defaults to the
+    /// `test_artifacts` subdirectory of `dir`.
+    /// The corresponding buckconfig is `buck2_log_storage.test_artifacts_dir`.
+    pub test_artifacts_dir: Option<String>,
+    /// URL prefix test artifacts are `PUT` to, for the `http` backend. Defaults to
+    /// `<url>/test_artifacts`.
+    /// The corresponding buckconfig is `buck2_log_storage.test_artifacts_url`.
+    pub test_artifacts_url: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogStorageBackend {
+    /// Logs are copied to a (typically shared) directory.
+    Dir,
+    /// Logs are uploaded with HTTP `PUT` and downloaded with HTTP `GET`.
+    Http,
+    /// Logs are uploaded and downloaded by a user-provided command.
+    Command,
+}
+
+impl FromStr for LogStorageBackend {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dir" => Ok(Self::Dir),
+            "http" => Ok(Self::Http),
+            "command" => Ok(Self::Command),
+            _ => Err(anyhow::anyhow!("Invalid log storage backend: `{}`", s)),
+        }
+    }
+}
+
+impl LogStorageConfig {
+    pub fn from_config(config: &LegacyBuckConfig) -> anyhow::Result<Self> {
+        let get = |property| {
+            config
+                .get(BuckconfigKeyRef {
+                    section: "buck2_log_storage",
+                    property,
+                })
+                .map(ToOwned::to_owned)
+        };
+        Ok(Self {
+            backend: config.parse(BuckconfigKeyRef {
+                section: "buck2_log_storage",
+                property: "backend",
+            })?,
+            dir: get("dir"),
+            url: get("url"),
+            command: get("command"),
+            upload_on_failure: config
+                .parse(BuckconfigKeyRef {
+                    section: "buck2_log_storage",
+                    property: "upload_on_failure",
+                })?
+                .unwrap_or(false),
+            test_artifacts_dir: get("test_artifacts_dir"),
+            test_artifacts_url: get("test_artifacts_url"),
+        })
+    }
+
+    /// The storage test artifacts are uploaded to: the same backend as event logs, with the
+    /// directory or URL configured for test artifacts, so they are kept apart from the logs.
+    pub fn for_test_artifacts(&self) -> Self {
+        Self {
+            backend: self.backend,
+            dir: self.test_artifacts_dir.clone().or_else(|| {
+                self.dir
+                    .as_ref()
+                    .map(|dir| format!("{}/test_artifacts", dir.trim_end_matches('/')))
+            }),
+            url: self.test_artifacts_url.clone().or_else(|| {
+                self.url
+                    .as_ref()
+                    .map(|url| format!("{}/test_artifacts", url.trim_end_matches('/')))
+            }),
+            command: self.command.clone(),
+            upload_on_failure: false,
+            test_artifacts_dir: None,
+            test_artifacts_url: None,
+        }
+    }
+}
+
+/// User-defined command aliases and default arguments, expanded by the client before parsing the
+/// command line.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CommandAliasConfig {
+    /// Maps an alias to the command line it stands for, like `build-opt = build @mode/opt`.
+    /// The corresponding buckconfig section is `buck2_command_alias`.
+    pub aliases: BTreeMap<String, String>,
+    /// Maps a command to arguments inserted right after it, like `build = --console simple`.
+    /// The corresponding buckconfig section is `buck2_command_defaults`.
+    pub defaults: BTreeMap<String, String>,
+}
+
+impl CommandAliasConfig {
+    pub fn from_config(config: &LegacyBuckConfig) -> Self {
+        let section = |name| {
+            config
+                .get_section(name)
+                .map(|section| {
+                    section
+                        .iter()
+                        .map(|(key, value)| (key.to_owned(), value.as_str().to_owned()))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        Self {
+            aliases: section("buck2_command_alias"),
+            defaults: section("buck2_command_defaults"),
+        }
+    }
+}
+
+/// Other checkouts which `buck2 build` can build targets in, with their own daemons, and whose
+/// outputs it links into this checkout.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FederationConfig {
+    /// Maps the name of a checkout to its project root, absolute or relative to this one, like
+    /// `deps = ../deps`. The corresponding buckconfig section is `federation`.
+    pub checkouts: BTreeMap<String, String>,
+    /// The directory, relative to the project root, where the outputs of each checkout, which are
+    /// written to buck-out, are linked under its name. The corresponding buckconfig key is
+    /// `buck2.federation_dir`.
+    pub outputs_dir: String,
+}
+
+impl FederationConfig {
+    pub fn from_config(config: &LegacyBuckConfig) -> Self {
+        Self {
+            checkouts: config
+                .get_section("federation")
+                .map(|section| {
+                    section
+                        .iter()
+                        .map(|(key, value)| (key.to_owned(), value.as_str().to_owned()))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            outputs_dir: config
+                .get(BuckconfigKeyRef {
+                    section: "buck2",
+                    property: "federation_dir",
+                })
+                .unwrap_or("federated")
+                .to_owned(),
+        }
+    }
+}
+
 /// Configurations that are used at startup by the daemon. Those are actually read by the client,
 /// and passed on to the daemon.
 ///
@@ -270,6 +480,8 @@ pub struct DaemonStartupConfig {
     pub materializations: Option<String>,
     pub http: HttpConfig,
     pub resource_control: ResourceControlConfig,
+    idle_timeout_secs: Option<u64>,
+    pub memory_profile: MemoryProfile,
 }
 
 impl DaemonStartupConfig {
@@ -311,9 +523,24 @@ impl DaemonStartupConfig {
                 .map(ToOwned::to_owned),
             http: HttpConfig::from_config(config)?,
             resource_control: ResourceControlConfig::from_config(config)?,
+            idle_timeout_secs: config.parse(BuckconfigKeyRef {
+                section: "buck2",
+                property: "daemon_idle_timeout_secs",
+            })?,
+            memory_profile: config
+                .parse(BuckconfigKeyRef {
+                    section: "buck2",
+                    property: "memory_profile",
+                })?
+                .unwrap_or_default(),
         })
     }
 
+    /// How long the daemon waits without running any command before shutting itself down.
+    pub fn idle_timeout(&self) -> Timeout {
+        Timeout::new(self.idle_timeout_secs.map(Duration::from_secs))
+    }
+
     pub fn serialize(&self) -> anyhow::Result<String> {
         serde_json::to_string(&self).context("Error serializing DaemonStartupConfig")
     }
@@ -332,6 +559,8 @@ impl DaemonStartupConfig {
             materializations: None,
             http: HttpConfig::default(),
             resource_control: ResourceControlConfig::default(),
+            idle_timeout_secs: None,
+            memory_profile: MemoryProfile::default(),
         }
     }
 }
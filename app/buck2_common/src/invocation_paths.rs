@@ -114,6 +114,12 @@ impl InvocationPaths {
             .join(ForwardRelativePath::unchecked_new("dice_dump"))
     }
 
+    /// Output of the config generators declared in the root cell buckconfig.
+    pub fn generated_config_dir(&self) -> AbsNormPathBuf {
+        self.buck_out_path()
+            .join(ForwardRelativePath::unchecked_new("generated_config"))
+    }
+
     pub fn buck_out_dir_prefix() -> &'static ProjectRelativePath {
         ProjectRelativePath::unchecked_new("buck-out")
     }
@@ -160,8 +166,21 @@ impl InvocationPaths {
         FileName::unchecked_new("materializer_state")
     }
 
+    /// Subdirectory of `cache_dir` storing the checkpoints of completed actions
+    pub fn action_checkpoints_path(&self) -> AbsNormPathBuf {
+        self.cache_dir_path()
+            .join(self.action_checkpoints_dir_name())
+    }
+
+    pub fn action_checkpoints_dir_name(&self) -> &FileName {
+        FileName::unchecked_new("action_checkpoints")
+    }
+
     pub fn valid_cache_dirs(&self) -> Vec<&FileName> {
-        vec![self.materializer_state_dir_name()]
+        vec![
+            self.materializer_state_dir_name(),
+            self.action_checkpoints_dir_name(),
+        ]
     }
 }
 
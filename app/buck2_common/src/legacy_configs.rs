@@ -14,6 +14,7 @@ mod access;
 pub mod cells;
 pub mod configs;
 pub mod dice;
+mod generators;
 pub mod key;
 mod parser;
 pub(crate) mod path;
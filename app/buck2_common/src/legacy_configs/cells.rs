@@ -20,6 +20,8 @@ use buck2_core::cells::cell_root_path::CellRootPath;
 use buck2_core::cells::cell_root_path::CellRootPathBuf;
 use buck2_core::cells::external::ExternalCellOrigin;
 use buck2_core::cells::external::GitCellSetup;
+use buck2_core::cells::external::LockfileCellSetup;
+use buck2_core::cells::external::LockfileFormat;
 use buck2_core::cells::name::CellName;
 use buck2_core::cells::CellResolver;
 use buck2_core::cells::CellsAggregator;
@@ -32,6 +34,7 @@ use buck2_core::fs::project_rel_path::ProjectRelativePath;
 use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
 use buck2_error::BuckErrorContext;
 use dice::DiceComputations;
+use dupe::Dupe;
 
 use crate::cas_digest::RawDigest;
 use crate::dice::cells::HasCellResolver;
@@ -52,6 +55,8 @@ use crate::legacy_configs::configs::LegacyConfigCmdArg;
 use crate::legacy_configs::configs::MainConfigFile;
 use crate::legacy_configs::configs::ResolvedLegacyConfigArg;
 use crate::legacy_configs::dice::HasInjectedLegacyConfigs;
+use crate::legacy_configs::generators::ConfigGenerators;
+use crate::legacy_configs::key::BuckconfigKeyRef;
 use crate::legacy_configs::path::BuckConfigFile;
 use crate::legacy_configs::path::DEFAULT_BUCK_CONFIG_FILES;
 
@@ -64,6 +69,11 @@ enum CellsError {
     MissingRootCellName,
     #[error("Unknown cell name `{}` when parsing external cell declarations", _0)]
     UnknownCellName(NonEmptyCellAlias),
+    #[error(
+        "Buckconfig of cell `{0}` defines `[{1}]` in an included file, which is not supported \
+        with `buck2.lazy_cell_configs`: move them to the buckconfig of the cell"
+    )]
+    CellsDefinedInLazyInclude(CellRootPathBuf, &'static str),
 }
 
 /// Used for creating a CellResolver in a buckv1-compatible way based on values
@@ -90,6 +100,7 @@ impl BuckConfigBasedCells {
     ) -> anyhow::Result<CellResolver> {
         let opts = BuckConfigParseOptions {
             follow_includes: false,
+            generated_config_dir: None,
         };
         let cells = Self::parse_with_file_ops_and_options(
             project_fs,
@@ -124,6 +135,27 @@ impl BuckConfigBasedCells {
         )
     }
 
+    /// Like `parse_with_config_args`, but also runs the config generators of the root cell,
+    /// caching their output in `generated_config_dir`.
+    pub fn parse_with_config_args_and_generators(
+        project_fs: &ProjectRoot,
+        config_args: &[LegacyConfigCmdArg],
+        cwd: &ProjectRelativePath,
+        generated_config_dir: AbsNormPathBuf,
+    ) -> anyhow::Result<Self> {
+        let opts = BuckConfigParseOptions {
+            follow_includes: true,
+            generated_config_dir: Some(generated_config_dir),
+        };
+        Self::parse_with_file_ops_and_options(
+            project_fs,
+            &mut DefaultConfigParserFileOps {},
+            config_args,
+            cwd,
+            opts,
+        )
+    }
+
     pub fn parse_with_file_ops(
         project_fs: &ProjectRoot,
         file_ops: &mut dyn ConfigParserFileOps,
@@ -132,6 +164,7 @@ impl BuckConfigBasedCells {
     ) -> anyhow::Result<Self> {
         let opts = BuckConfigParseOptions {
             follow_includes: true,
+            generated_config_dir: None,
         };
         Self::parse_with_file_ops_and_options(project_fs, file_ops, config_args, cwd, opts)
     }
@@ -139,6 +172,7 @@ impl BuckConfigBasedCells {
     pub fn parse_no_follow_includes(project_fs: &ProjectRoot) -> anyhow::Result<Self> {
         let opts = BuckConfigParseOptions {
             follow_includes: false,
+            generated_config_dir: None,
         };
         Self::parse_with_file_ops_and_options(
             project_fs,
@@ -225,101 +259,160 @@ impl BuckConfigBasedCells {
         let processed_config_args =
             LegacyBuckConfig::process_config_args(config_args, &cell_resolution, &mut file_ops)?;
 
-        while let Some(path) = work.pop() {
-            if buckconfigs.contains_key(&path) || cells_aggregator.is_external(&path) {
-                continue;
+        let caching_file_ops = CachingFileOps::new(&mut file_ops);
+        // With `buck2.lazy_cell_configs`, configs of cells other than the root are only parsed
+        // upfront to discover cells, without following includes, and are dropped afterwards: they
+        // are parsed on demand through DICE instead, see `LegacyBuckConfigForCellKey`.
+        let mut lazy_cells = false;
+
+        // Cells are discovered from the configs of the cells found so far, so configs are parsed
+        // in waves: first the root cell, then all the cells it declares concurrently, and so on.
+        // Results are processed in the order of `work`, so that errors are deterministic.
+        while !work.is_empty() {
+            let mut wave = Vec::new();
+            for path in std::mem::take(&mut work) {
+                if !buckconfigs.contains_key(&path)
+                    && !cells_aggregator.is_external(&path)
+                    && !wave.contains(&path)
+                {
+                    wave.push(path);
+                }
             }
 
-            // Blocking is ok because we know the fileops don't suspend
-            let buckconfig_paths = futures::executor::block_on(get_buckconfig_paths_for_cell(
-                &path,
-                project_fs,
-                &mut file_ops,
-            ))?;
-
-            let config =
-                futures::executor::block_on(LegacyBuckConfig::parse_with_file_ops_with_includes(
-                    buckconfig_paths.as_slice(),
-                    project_fs.resolve(path.as_project_relative_path()),
-                    &mut file_ops,
-                    &processed_config_args,
-                    options.follow_includes,
-                ))?;
-
-            let is_root = path.is_repo_root();
-
-            let repositories = config
-                .get_section("repositories")
-                .or_else(|| config.get_section("cells"));
-            if let Some(repositories) = repositories {
-                for (alias, alias_path) in repositories.iter() {
-                    let alias_path = CellRootPathBuf::new(path
-                        .join_normalized(RelativePath::new(alias_path.as_str()))
-                        .with_context(|| {
-                            format!(
-                                "expected alias path to be a relative path, but found `{}` for `{}` in buckconfig `{}`",
-                                alias_path.as_str(),
-                                alias,
-                                path
-                            )
-                        })?);
-                    let alias = NonEmptyCellAlias::new(alias.to_owned())?;
-                    if is_root {
-                        root_aliases.insert(alias.clone(), alias_path.clone());
+            let follow_includes = options.follow_includes && !lazy_cells;
+            let parsed = parse_concurrently(&wave, |path| {
+                let mut file_ops = &caching_file_ops;
+                // Blocking is ok because we know the fileops don't suspend, except to wait for
+                // another thread.
+                futures::executor::block_on(async {
+                    let buckconfig_paths =
+                        get_buckconfig_paths_for_cell(path, project_fs, &mut file_ops).await?;
+                    let config = LegacyBuckConfig::parse_with_file_ops_with_includes(
+                        buckconfig_paths.as_slice(),
+                        project_fs.resolve(path.as_project_relative_path()),
+                        &mut file_ops,
+                        &processed_config_args,
+                        follow_includes,
+                    )
+                    .await?;
+                    anyhow::Ok((buckconfig_paths, config))
+                })
+            });
+
+            for (path, parsed) in wave.into_iter().zip(parsed) {
+                let (mut buckconfig_paths, mut config) = parsed?;
+
+                let is_root = path.is_repo_root();
+
+                // Second phase: fragments produced by the config generators of the root cell are
+                // parsed after its config files, so they can override them, but before the
+                // command line arguments.
+                if is_root {
+                    if let Some(generated_config_dir) = &options.generated_config_dir {
+                        let fragments = ConfigGenerators::new(project_fs, generated_config_dir)
+                            .generate(&config)?;
+                        if !fragments.is_empty() {
+                            buckconfig_paths.extend(fragments);
+                            config = futures::executor::block_on(
+                                LegacyBuckConfig::parse_with_file_ops_with_includes(
+                                    buckconfig_paths.as_slice(),
+                                    project_fs.resolve(path.as_project_relative_path()),
+                                    &mut &caching_file_ops,
+                                    &processed_config_args,
+                                    options.follow_includes,
+                                ),
+                            )?;
+                        }
+                    }
+                }
+
+                let repositories = config
+                    .get_section("repositories")
+                    .or_else(|| config.get_section("cells"));
+                if let Some(repositories) = repositories {
+                    for (alias, alias_path) in repositories.iter() {
+                        let alias_path = CellRootPathBuf::new(path
+                            .join_normalized(RelativePath::new(alias_path.as_str()))
+                            .with_context(|| {
+                                format!(
+                                    "expected alias path to be a relative path, but found `{}` for `{}` in buckconfig `{}`",
+                                    alias_path.as_str(),
+                                    alias,
+                                    path
+                                )
+                            })?);
+                        let alias = NonEmptyCellAlias::new(alias.to_owned())?;
+                        if is_root {
+                            root_aliases.insert(alias.clone(), alias_path.clone());
+                        }
+                        cells_aggregator.add_cell_entry(path.clone(), alias, alias_path.clone())?;
+                        work.push(alias_path);
                     }
-                    cells_aggregator.add_cell_entry(path.clone(), alias, alias_path.clone())?;
-                    work.push(alias_path);
                 }
-            }
 
-            if is_root {
-                if cells_aggregator.get_name(&path).is_none() {
-                    return Err(CellsError::MissingRootCellName.into());
+                if is_root {
+                    if cells_aggregator.get_name(&path).is_none() {
+                        return Err(CellsError::MissingRootCellName.into());
+                    }
+                    // Only lazy when following includes, otherwise nothing is gained.
+                    lazy_cells = options.follow_includes
+                        && config
+                            .parse(BuckconfigKeyRef {
+                                section: "buck2",
+                                property: "lazy_cell_configs",
+                            })?
+                            .unwrap_or(false);
+                } else {
+                    for (alias, alias_path) in &root_aliases {
+                        cells_aggregator.add_cell_entry(
+                            path.clone(),
+                            alias.clone(),
+                            alias_path.clone(),
+                        )?;
+                    }
                 }
-            } else {
-                for (alias, alias_path) in &root_aliases {
-                    cells_aggregator.add_cell_entry(
+
+                for (alias, destination) in Self::get_cell_aliases_from_config(&config)? {
+                    let alias_path = cells_aggregator.add_cell_alias(
                         path.clone(),
                         alias.clone(),
-                        alias_path.clone(),
+                        destination,
                     )?;
+                    if is_root {
+                        root_aliases.insert(alias, alias_path.clone());
+                    }
                 }
-            }
 
-            for (alias, destination) in Self::get_cell_aliases_from_config(&config)? {
-                let alias_path =
-                    cells_aggregator.add_cell_alias(path.clone(), alias.clone(), destination)?;
                 if is_root {
-                    root_aliases.insert(alias, alias_path.clone());
-                }
-            }
-
-            if is_root {
-                if let Some(external_cells) = config.get_section("external_cells") {
-                    for (alias, origin) in external_cells.iter() {
-                        let alias = NonEmptyCellAlias::new(alias.to_owned())?;
-                        let target = root_aliases
-                            .get(&alias)
-                            .ok_or(CellsError::UnknownCellName(alias))?;
-                        let name = cells_aggregator
-                            .get_name(target)
-                            .internal_error("We just checked that this cell exists")?;
-                        let origin =
-                            Self::parse_external_cell_origin(name, origin.as_str(), &config)?;
-                        if let ExternalCellOrigin::Bundled(name) = origin {
-                            EXTERNAL_CELLS_IMPL.get()?.check_bundled_cell_exists(name)?;
+                    if let Some(external_cells) = config.get_section("external_cells") {
+                        for (alias, origin) in external_cells.iter() {
+                            let alias = NonEmptyCellAlias::new(alias.to_owned())?;
+                            let target = root_aliases
+                                .get(&alias)
+                                .ok_or(CellsError::UnknownCellName(alias))?;
+                            let name = cells_aggregator
+                                .get_name(target)
+                                .internal_error("We just checked that this cell exists")?;
+                            let origin =
+                                Self::parse_external_cell_origin(name, origin.as_str(), &config)?;
+                            if let ExternalCellOrigin::Bundled(name) = origin {
+                                EXTERNAL_CELLS_IMPL.get()?.check_bundled_cell_exists(name)?;
+                            }
+                            cells_aggregator.mark_external_cell(target.to_owned(), origin)?;
                         }
-                        cells_aggregator.mark_external_cell(target.to_owned(), origin)?;
                     }
                 }
-            }
 
-            buckconfigs.insert(path, config);
+                buckconfigs.insert(path, config);
+            }
         }
 
+        drop(caching_file_ops);
+
         let cell_resolver = cells_aggregator.make_cell_resolver()?;
         let configs_by_name = buckconfigs
             .into_iter()
+            .filter(|(path, _)| !lazy_cells || path.is_repo_root())
             .map(|(path, config)| {
                 Ok((cell_resolver.find(path.as_project_relative_path())?, config))
             })
@@ -350,9 +443,46 @@ impl BuckConfigBasedCells {
         Ok(aliases.into_iter())
     }
 
+    /// With `buck2.lazy_cell_configs`, cells are discovered from the configs of non-root cells
+    /// parsed without following includes, so the included files must not define cells.
+    fn check_no_cells_in_includes(
+        cell_path: &CellRootPath,
+        without_includes: &LegacyBuckConfig,
+        with_includes: &LegacyBuckConfig,
+    ) -> anyhow::Result<()> {
+        for (section, legacy_section) in [
+            ("repositories", "cells"),
+            ("repository_aliases", "cell_aliases"),
+        ] {
+            let entries = |config: &LegacyBuckConfig| {
+                config
+                    .get_section(section)
+                    .or_else(|| config.get_section(legacy_section))
+                    .map(|section| {
+                        section
+                            .iter()
+                            .map(|(name, value)| (name.to_owned(), value.as_str().to_owned()))
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default()
+            };
+            if entries(without_includes) != entries(with_includes) {
+                return Err(CellsError::CellsDefinedInLazyInclude(
+                    cell_path.to_buf(),
+                    legacy_section,
+                )
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse the config of a cell which was not parsed upfront. `lazy` is set for the cells skipped
+    /// because of `buck2.lazy_cell_configs`, whose includes are then checked not to define cells.
     pub(crate) async fn parse_single_cell_with_dice(
         ctx: &mut DiceComputations<'_>,
         cell_path: &CellRootPath,
+        lazy: bool,
     ) -> anyhow::Result<LegacyBuckConfig> {
         let resolver = ctx.get_cell_resolver().await?;
         let io_provider = ctx.global_data().get_io_provider();
@@ -441,14 +571,28 @@ impl BuckConfigBasedCells {
         let config_paths =
             get_buckconfig_paths_for_cell(cell_path, project_fs, &mut file_ops).await?;
 
-        LegacyBuckConfig::parse_with_file_ops_with_includes(
+        let config = LegacyBuckConfig::parse_with_file_ops_with_includes(
             &config_paths,
             project_fs.resolve(cell_path.as_project_relative_path()),
             &mut file_ops,
             overrides.as_ref(),
             /* follow includes */ true,
         )
-        .await
+        .await?;
+
+        if lazy {
+            let without_includes = LegacyBuckConfig::parse_with_file_ops_with_includes(
+                &config_paths,
+                project_fs.resolve(cell_path.as_project_relative_path()),
+                &mut file_ops,
+                overrides.as_ref(),
+                /* follow includes */ false,
+            )
+            .await?;
+            Self::check_no_cells_in_includes(cell_path, &without_includes, &config)?;
+        }
+
+        Ok(config)
     }
 
     fn parse_external_cell_origin(
@@ -462,6 +606,14 @@ impl BuckConfigBasedCells {
             Unknown(String),
             #[error("Missing buckconfig `{0}.{1}` for external cell configuration")]
             MissingConfiguration(String, String),
+            #[error(
+                "Expected `{0}.rule` to be of the form `cell//path:file.bzl:function`, where `function` is an identifier, got `{1}`"
+            )]
+            InvalidLockfileRule(String, String),
+            #[error(
+                "Cannot infer lockfile format for `{0}`, set `{1}.format` to one of `cargo`, `npm`, `python`"
+            )]
+            UnknownLockfileFormat(String, String),
         }
 
         let get_config = |section: &str, property: &str| {
@@ -487,10 +639,155 @@ impl BuckConfigBasedCells {
                 git_origin: get_config(section, "git_origin")?.into(),
                 commit,
             }))
+        } else if value == "lockfile" {
+            let section = &format!("external_cell_{}", cell.as_str());
+            let lockfile = get_config(section, "lockfile")?;
+            // Make sure this is usable as a path in the project (and in buck-out).
+            let _ = ProjectRelativePath::new(lockfile)?;
+            let format = match config.get(crate::legacy_configs::key::BuckconfigKeyRef {
+                section,
+                property: "format",
+            }) {
+                Some(format) => Self::parse_lockfile_format(format),
+                None => lockfile
+                    .rsplit('/')
+                    .next()
+                    .and_then(Self::infer_lockfile_format),
+            }
+            .ok_or_else(|| {
+                ExternalCellOriginParseError::UnknownLockfileFormat(
+                    lockfile.to_owned(),
+                    section.to_owned(),
+                )
+            })?;
+            let rule = get_config(section, "rule")?;
+            let (rule_bzl, rule_name) = rule
+                .rsplit_once(':')
+                // The name is spliced into the generated build file, so it must be an identifier.
+                .filter(|(bzl, name)| bzl.ends_with(".bzl") && Self::is_identifier(name))
+                .ok_or_else(|| {
+                    ExternalCellOriginParseError::InvalidLockfileRule(
+                        section.to_owned(),
+                        rule.to_owned(),
+                    )
+                })?;
+            Ok(ExternalCellOrigin::Lockfile(LockfileCellSetup {
+                lockfile: lockfile.into(),
+                format,
+                rule_bzl: rule_bzl.into(),
+                rule_name: rule_name.into(),
+            }))
         } else {
             Err(ExternalCellOriginParseError::Unknown(value.to_owned()).into())
         }
     }
+
+    fn is_identifier(name: &str) -> bool {
+        let mut chars = name.chars();
+        chars
+            .next()
+            .map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+    }
+
+    fn parse_lockfile_format(value: &str) -> Option<LockfileFormat> {
+        match value {
+            "cargo" => Some(LockfileFormat::Cargo),
+            "npm" => Some(LockfileFormat::Npm),
+            "python" => Some(LockfileFormat::Python),
+            _ => None,
+        }
+    }
+
+    fn infer_lockfile_format(file_name: &str) -> Option<LockfileFormat> {
+        match file_name {
+            "Cargo.lock" => Some(LockfileFormat::Cargo),
+            "package-lock.json" => Some(LockfileFormat::Npm),
+            _ if file_name.starts_with("requirements") && file_name.ends_with(".txt") => {
+                Some(LockfileFormat::Python)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// File ops shared by the threads parsing cell configs. Each file is read once, so that config
+/// files included by many cells are not read again for each of them.
+struct CachingFileOps<'a> {
+    inner: futures::lock::Mutex<&'a mut dyn ConfigParserFileOps>,
+    lines: parking_lot::Mutex<HashMap<AbsNormPathBuf, Arc<Vec<String>>>>,
+}
+
+impl<'a> CachingFileOps<'a> {
+    fn new(inner: &'a mut dyn ConfigParserFileOps) -> Self {
+        Self {
+            inner: futures::lock::Mutex::new(inner),
+            lines: parking_lot::Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ConfigParserFileOps for &CachingFileOps<'_> {
+    async fn read_file_lines(
+        &mut self,
+        path: &AbsNormPath,
+    ) -> anyhow::Result<Box<dyn Iterator<Item = Result<String, io::Error>> + Send>> {
+        let cached = self.lines.lock().get(path).cloned();
+        let lines = match cached {
+            Some(lines) => lines,
+            None => {
+                let lines = self.inner.lock().await.read_file_lines(path).await?;
+                // Read outside of the lock, so that other threads can read concurrently.
+                let lines = Arc::new(lines.collect::<Result<Vec<_>, _>>()?);
+                self.lines.lock().insert(path.to_buf(), lines.dupe());
+                lines
+            }
+        };
+        Ok(Box::new(
+            (0..lines.len()).map(move |i| Ok(lines[i].clone())),
+        ))
+    }
+
+    async fn file_exists(&mut self, path: &AbsNormPath) -> bool {
+        if self.lines.lock().contains_key(path) {
+            return true;
+        }
+        self.inner.lock().await.file_exists(path).await
+    }
+
+    async fn read_dir(&mut self, path: &AbsNormPath) -> anyhow::Result<Vec<ConfigDirEntry>> {
+        self.inner.lock().await.read_dir(path).await
+    }
+}
+
+/// Apply `parse` to the configs of `cells` on up to one thread per CPU, returning the results in
+/// the same order.
+fn parse_concurrently<T: Send>(
+    cells: &[CellRootPathBuf],
+    parse: impl Fn(&CellRootPathBuf) -> anyhow::Result<T> + Sync,
+) -> Vec<anyhow::Result<T>> {
+    let threads = std::thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(cells.len());
+    if threads <= 1 {
+        return cells.iter().map(parse).collect();
+    }
+    let chunk_size = (cells.len() + threads - 1) / threads;
+    let parse = &parse;
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = cells
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(parse).collect::<Vec<_>>()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| match handle.join() {
+                Ok(results) => results,
+                Err(panic) => std::panic::resume_unwind(panic),
+            })
+            .collect()
+    })
 }
 
 async fn get_buckconfig_paths_for_cell(
@@ -600,12 +897,17 @@ pub(crate) fn create_project_filesystem() -> ProjectRoot {
 
 #[cfg(test)]
 mod tests {
+    use std::io;
     use std::sync::Arc;
 
     use buck2_core::cells::cell_root_path::CellRootPath;
     use buck2_core::cells::external::ExternalCellOrigin;
     use buck2_core::cells::external::GitCellSetup;
+    use buck2_core::cells::external::LockfileCellSetup;
+    use buck2_core::cells::external::LockfileFormat;
     use buck2_core::cells::name::CellName;
+    use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
+    use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
     use buck2_core::fs::project_rel_path::ProjectRelativePath;
     use dice::DiceComputations;
     use indoc::indoc;
@@ -615,8 +917,12 @@ mod tests {
     use crate::external_cells::EXTERNAL_CELLS_IMPL;
     use crate::legacy_configs::cells::create_project_filesystem;
     use crate::legacy_configs::cells::BuckConfigBasedCells;
+    use crate::legacy_configs::cells::CachingFileOps;
+    use crate::legacy_configs::configs::testing;
     use crate::legacy_configs::configs::testing::TestConfigParserFileOps;
     use crate::legacy_configs::configs::tests::assert_config_value;
+    use crate::legacy_configs::configs::ConfigDirEntry;
+    use crate::legacy_configs::configs::ConfigParserFileOps;
     use crate::legacy_configs::configs::LegacyConfigCmdArg;
     use crate::legacy_configs::key::BuckconfigKeyRef;
 
@@ -694,6 +1000,119 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_lazy_cell_configs() -> anyhow::Result<()> {
+        let mut file_ops = TestConfigParserFileOps::new(&[
+            (
+                "/.buckconfig",
+                indoc!(
+                    r#"
+                            [cells]
+                                root = .
+                                other = other/
+                            [buck2]
+                                lazy_cell_configs = true
+                        "#
+                ),
+            ),
+            (
+                "/other/.buckconfig",
+                indoc!(
+                    r#"
+                            [cells]
+                                other = .
+                                third_party = ../third_party/
+                            [foo]
+                                bar = baz
+                        "#
+                ),
+            ),
+            (
+                "/third_party/.buckconfig",
+                indoc!(
+                    r#"
+                            [cells]
+                                third_party = .
+                        "#
+                ),
+            ),
+        ])?;
+
+        let project_fs = create_project_filesystem();
+        let cells = BuckConfigBasedCells::parse_with_file_ops(
+            &project_fs,
+            &mut file_ops,
+            &[],
+            ProjectRelativePath::empty(),
+        )?;
+
+        // Cells declared by other cells are still discovered.
+        let tp_instance = cells
+            .cell_resolver
+            .get(CellName::testing_new("third_party"))?;
+        assert_eq!("third_party", tp_instance.path().as_str());
+
+        // But only the root cell config is parsed upfront.
+        let configs: Vec<_> = cells.configs_by_name.iter().map(|(name, _)| name).collect();
+        assert_eq!(vec![CellName::testing_new("root")], configs);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lazy_cell_configs_cells_in_includes() -> anyhow::Result<()> {
+        let cell_config = indoc!(
+            r#"
+                    <file:cells>
+                    [cells]
+                        other = .
+                "#
+        );
+        let with_includes = testing::parse(
+            &[
+                ("/other/.buckconfig", cell_config),
+                (
+                    "/other/cells",
+                    indoc!(
+                        r#"
+                            [cells]
+                                third_party = ../third_party/
+                        "#
+                    ),
+                ),
+            ],
+            "/other/.buckconfig",
+        )?;
+        let without_includes = testing::parse(
+            &[(
+                "/other/.buckconfig",
+                indoc!(
+                    r#"
+                            [cells]
+                                other = .
+                        "#
+                ),
+            )],
+            "/other/.buckconfig",
+        )?;
+
+        let cell_path = CellRootPath::testing_new("other");
+        BuckConfigBasedCells::check_no_cells_in_includes(
+            cell_path,
+            &without_includes,
+            &without_includes,
+        )?;
+        let err = BuckConfigBasedCells::check_no_cells_in_includes(
+            cell_path,
+            &without_includes,
+            &with_includes,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("`[cells]`"), "{err}");
+
+        Ok(())
+    }
+
     #[test]
     fn test_multi_cell_with_config_file() -> anyhow::Result<()> {
         let mut file_ops = TestConfigParserFileOps::new(&[
@@ -1364,4 +1783,173 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_lockfile_external_cell() -> anyhow::Result<()> {
+        initialize_external_cells_impl();
+
+        let mut file_ops = TestConfigParserFileOps::new(&[(
+            "/.buckconfig",
+            indoc!(
+                r#"
+                    [cells]
+                        root = .
+                        crates = third-party/crates/
+                    [external_cells]
+                        crates = lockfile
+                    [external_cell_crates]
+                        lockfile = third-party/rust/Cargo.lock
+                        rule = root//third-party/rust:defs.bzl:cargo_package
+                "#
+            ),
+        )])?;
+
+        let project_fs = create_project_filesystem();
+        let resolver = BuckConfigBasedCells::parse_with_file_ops(
+            &project_fs,
+            &mut file_ops,
+            &[],
+            ProjectRelativePath::empty(),
+        )?
+        .cell_resolver;
+
+        let instance = resolver.get(CellName::testing_new("crates")).unwrap();
+
+        assert_eq!(
+            instance.external(),
+            Some(&ExternalCellOrigin::Lockfile(LockfileCellSetup {
+                lockfile: "third-party/rust/Cargo.lock".into(),
+                format: LockfileFormat::Cargo,
+                rule_bzl: "root//third-party/rust:defs.bzl".into(),
+                rule_name: "cargo_package".into(),
+            })),
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lockfile_external_cell_unknown_format() -> anyhow::Result<()> {
+        initialize_external_cells_impl();
+
+        let mut file_ops = TestConfigParserFileOps::new(&[(
+            "/.buckconfig",
+            indoc!(
+                r#"
+                    [cells]
+                        root = .
+                        deps = third-party/deps/
+                    [external_cells]
+                        deps = lockfile
+                    [external_cell_deps]
+                        lockfile = third-party/deps.lock
+                        rule = root//third-party:defs.bzl:package
+                "#
+            ),
+        )])?;
+
+        let project_fs = create_project_filesystem();
+        let e = BuckConfigBasedCells::parse_with_file_ops(
+            &project_fs,
+            &mut file_ops,
+            &[],
+            ProjectRelativePath::empty(),
+        )
+        .err()
+        .unwrap();
+
+        let e = format!("{:?}", e);
+        assert!(e.contains("Cannot infer lockfile format"), "error: {}", e);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lockfile_external_cell_invalid_rule_name() -> anyhow::Result<()> {
+        initialize_external_cells_impl();
+
+        for rule in [
+            "root//third-party:defs.bzl:",
+            "root//third-party:defs.bzl:1package",
+            "root//third-party:defs.bzl:package() or fail",
+            "root//third-party:defs.bzl:pack-age",
+        ] {
+            let config = indoc!(
+                r#"
+                    [cells]
+                        root = .
+                        deps = third-party/deps/
+                    [external_cells]
+                        deps = lockfile
+                    [external_cell_deps]
+                        lockfile = third-party/Cargo.lock
+                        rule = RULE
+                "#
+            )
+            .replace("RULE", rule);
+            let mut file_ops = TestConfigParserFileOps::new(&[("/.buckconfig", config.as_str())])?;
+
+            let project_fs = create_project_filesystem();
+            let e = BuckConfigBasedCells::parse_with_file_ops(
+                &project_fs,
+                &mut file_ops,
+                &[],
+                ProjectRelativePath::empty(),
+            )
+            .err()
+            .unwrap();
+
+            let e = format!("{:?}", e);
+            assert!(e.contains("to be of the form"), "error: {}", e);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_caching_file_ops_reads_once() -> anyhow::Result<()> {
+        struct CountingFileOps(TestConfigParserFileOps, usize);
+
+        #[async_trait::async_trait]
+        impl ConfigParserFileOps for CountingFileOps {
+            async fn read_file_lines(
+                &mut self,
+                path: &AbsNormPath,
+            ) -> anyhow::Result<Box<dyn Iterator<Item = Result<String, io::Error>> + Send>>
+            {
+                self.1 += 1;
+                self.0.read_file_lines(path).await
+            }
+
+            async fn file_exists(&mut self, path: &AbsNormPath) -> bool {
+                self.0.file_exists(path).await
+            }
+
+            async fn read_dir(
+                &mut self,
+                path: &AbsNormPath,
+            ) -> anyhow::Result<Vec<ConfigDirEntry>> {
+                self.0.read_dir(path).await
+            }
+        }
+
+        let mut inner = CountingFileOps(
+            TestConfigParserFileOps::new(&[("/common.bcfg", "[a]\n  b = c\n")])?,
+            0,
+        );
+        let caching = CachingFileOps::new(&mut inner);
+        let path = AbsNormPathBuf::from(if cfg!(windows) {
+            "C:/common.bcfg".to_owned()
+        } else {
+            "/common.bcfg".to_owned()
+        })?;
+        for _ in 0..2 {
+            let lines = futures::executor::block_on((&caching).read_file_lines(&path))?
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(vec!["[a]", "  b = c"], lines);
+        }
+        drop(caching);
+        assert_eq!(1, inner.1);
+        Ok(())
+    }
 }
@@ -22,6 +22,7 @@ use buck2_core::cells::CellResolver;
 use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
 use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
 use buck2_core::fs::paths::file_name::FileNameBuf;
+use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
 use buck2_core::fs::project::ProjectRoot;
 use buck2_core::fs::project_rel_path::ProjectRelativePath;
 use derive_more::Display;
@@ -32,6 +33,7 @@ use gazebo::prelude::*;
 use starlark_map::small_map::SmallMap;
 use starlark_map::sorted_map::SortedMap;
 
+use crate::ignores::ignore_set::IGNORE_FILE_NAME;
 use crate::legacy_configs::cells::BuckConfigBasedCells;
 use crate::legacy_configs::parser::LegacyConfigParser;
 
@@ -591,6 +593,12 @@ impl LegacyBuckConfig {
                 .parse_file(&main_config_file.path, None, follow_includes, file_ops)
                 .await?;
         }
+        parser
+            .parse_ignore_file(
+                &cell_path.join(ForwardRelativePath::new(IGNORE_FILE_NAME)?),
+                file_ops,
+            )
+            .await?;
 
         for config_arg in config_args {
             match config_arg {
@@ -618,6 +626,8 @@ impl LegacyBuckConfig {
 pub(crate) struct BuckConfigParseOptions {
     // Defines whether includes are followed, this can significantly reduce parse time.
     pub(crate) follow_includes: bool,
+    // Where to cache the output of config generators. Generators only run when this is set.
+    pub(crate) generated_config_dir: Option<AbsNormPathBuf>,
 }
 
 pub(crate) fn push_all_files_from_a_directory<'a>(
@@ -927,6 +937,27 @@ pub(crate) mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_ignore_file() -> anyhow::Result<()> {
+        let config = parse(
+            &[
+                (
+                    "/config",
+                    indoc!(
+                        r#"
+            [project]
+                ignore = .git
+        "#
+                    ),
+                ),
+                ("/config/.buck2ignore", "# vendored\n/vendor/\n"),
+            ],
+            "/config",
+        )?;
+        assert_config_value(&config, "project", "ignore", ".git,vendor");
+        Ok(())
+    }
+
     #[test]
     fn test_references() -> anyhow::Result<()> {
         let config = parse(
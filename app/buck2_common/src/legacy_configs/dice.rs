@@ -222,16 +222,20 @@ impl Key for LegacyBuckConfigForCellKey {
         let cells = ctx.get_cell_resolver().await?;
         let this_cell = cells.get(self.cell_name)?;
         if this_cell.external().is_some() {
-            return BuckConfigBasedCells::parse_single_cell_with_dice(ctx, this_cell.path())
+            return BuckConfigBasedCells::parse_single_cell_with_dice(ctx, this_cell.path(), false)
                 .await
                 .map_err(Into::into);
         }
 
         let legacy_configs = ctx.get_injected_legacy_configs().await?;
-        legacy_configs
-            .get(self.cell_name)
-            .map(|x| x.dupe())
-            .map_err(buck2_error::Error::from)
+        match legacy_configs.data.get(&self.cell_name) {
+            Some(config) => Ok(config.dupe()),
+            // Not parsed upfront because of `buck2.lazy_cell_configs`: parse it now that it is
+            // needed.
+            None => BuckConfigBasedCells::parse_single_cell_with_dice(ctx, this_cell.path(), true)
+                .await
+                .map_err(Into::into),
+        }
     }
 
     fn equality(x: &Self::Value, y: &Self::Value) -> bool {
@@ -0,0 +1,303 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Buckconfig fragments generated by commands, declared in the `[config_generators]` section
+//! of the root cell config.
+//!
+//! Each generator is an executable (a path relative to the project root, or absolute) which
+//! prints a buckconfig fragment to stdout. The fragment is merged into the root cell config,
+//! below command line overrides. Generators run in the daemon, and their output is cached in
+//! buck-out together with a stamp of the executable and of the inputs declared in
+//! `[config_generator_inputs]`: a generator only runs again when one of those changes.
+//! Generators are killed when they run for longer than [`GENERATOR_TIMEOUT`].
+//! Generated fragments cannot declare generators themselves.
+
+use std::fmt::Write;
+use std::io::Read;
+use std::path::Path;
+use std::process::Command;
+use std::process::Output;
+use std::process::Stdio;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::UNIX_EPOCH;
+
+use anyhow::Context;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+use buck2_core::fs::paths::RelativePath;
+use buck2_core::fs::project::ProjectRoot;
+
+use crate::legacy_configs::configs::LegacyBuckConfig;
+use crate::legacy_configs::configs::MainConfigFile;
+use crate::legacy_configs::key::BuckconfigKeyRef;
+
+pub(crate) const CONFIG_GENERATORS_SECTION: &str = "config_generators";
+const CONFIG_GENERATOR_INPUTS_SECTION: &str = "config_generator_inputs";
+
+/// How long a generator may run before it is killed.
+const GENERATOR_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, buck2_error::Error)]
+enum ConfigGeneratorError {
+    #[error("Invalid config generator name `{0}`: must only contain letters, digits, `_` and `-`")]
+    InvalidName(String),
+    #[error("Config generator `{name}` failed with {status}:\n{stderr}")]
+    Failed {
+        name: String,
+        status: String,
+        stderr: String,
+    },
+    #[error("Config generator `{0}` did not finish within {1:?} and was killed")]
+    TimedOut(String, Duration),
+}
+
+/// Config generators declared in the root cell config, and where their output is cached.
+pub(crate) struct ConfigGenerators<'a> {
+    project_root: &'a ProjectRoot,
+    out_dir: &'a AbsNormPath,
+    timeout: Duration,
+}
+
+impl<'a> ConfigGenerators<'a> {
+    pub(crate) fn new(project_root: &'a ProjectRoot, out_dir: &'a AbsNormPath) -> Self {
+        Self {
+            project_root,
+            out_dir,
+            timeout: GENERATOR_TIMEOUT,
+        }
+    }
+
+    /// Run the generators declared in `config` whose output is stale, and return the
+    /// generated fragments, in the order of the generator names.
+    pub(crate) fn generate(
+        &self,
+        config: &LegacyBuckConfig,
+    ) -> anyhow::Result<Vec<MainConfigFile>> {
+        let Some(section) = config.get_section(CONFIG_GENERATORS_SECTION) else {
+            return Ok(Vec::new());
+        };
+        let mut generators: Vec<(String, String)> = section
+            .iter()
+            .map(|(name, exe)| (name.to_owned(), exe.as_str().to_owned()))
+            .collect();
+        generators.sort();
+
+        let mut fragments = Vec::with_capacity(generators.len());
+        for (name, exe) in generators {
+            let path = self
+                .generate_one(config, &name, &exe)
+                .with_context(|| format!("Running config generator `{}`", name))?;
+            fragments.push(MainConfigFile {
+                path,
+                owned_by_project: true,
+            });
+        }
+        Ok(fragments)
+    }
+
+    fn generate_one(
+        &self,
+        config: &LegacyBuckConfig,
+        name: &str,
+        exe: &str,
+    ) -> anyhow::Result<AbsNormPathBuf> {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(ConfigGeneratorError::InvalidName(name.to_owned()).into());
+        }
+
+        let exe = self.resolve(exe)?;
+        let mut inputs = vec![exe.clone()];
+        if let Some(declared) = config.parse_list::<String>(BuckconfigKeyRef {
+            section: CONFIG_GENERATOR_INPUTS_SECTION,
+            property: name,
+        })? {
+            for input in declared {
+                inputs.push(self.resolve(&input)?);
+            }
+        }
+
+        let fragment_path = self
+            .out_dir
+            .join_normalized(format!("{}.buckconfig", name))?;
+        let stamp_path = self.out_dir.join_normalized(format!("{}.stamp", name))?;
+
+        let stamp = stamp(&inputs)?;
+        if fs_util::try_exists(&fragment_path)?
+            && fs_util::read_to_string_if_exists(&stamp_path)?.as_deref() == Some(stamp.as_str())
+        {
+            return Ok(fragment_path);
+        }
+
+        tracing::info!("Running config generator `{}`: {}", name, exe);
+        let output = self.run(name, &exe)?;
+        if !output.status.success() {
+            return Err(ConfigGeneratorError::Failed {
+                name: name.to_owned(),
+                status: output.status.to_string(),
+                stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            }
+            .into());
+        }
+
+        fs_util::create_dir_all(self.out_dir)?;
+        fs_util::write(&fragment_path, &output.stdout)?;
+        fs_util::write(&stamp_path, stamp)?;
+        Ok(fragment_path)
+    }
+
+    /// Run the generator `exe`, killing it if it doesn't finish within the timeout.
+    fn run(&self, name: &str, exe: &AbsNormPath) -> anyhow::Result<Output> {
+        let mut child = Command::new(exe.as_path())
+            .current_dir(self.project_root.root().as_path())
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Spawning `{}`", exe))?;
+        // Drain the pipes on their own threads, so the generator doesn't block on a full pipe
+        // while it is being waited for.
+        let stdout = read_pipe(child.stdout.take());
+        let stderr = read_pipe(child.stderr.take());
+
+        let deadline = Instant::now() + self.timeout;
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                let _ignored = child.kill();
+                let _ignored = child.wait();
+                return Err(ConfigGeneratorError::TimedOut(name.to_owned(), self.timeout).into());
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        };
+        Ok(Output {
+            status,
+            stdout: join_pipe(stdout)?,
+            stderr: join_pipe(stderr)?,
+        })
+    }
+
+    fn resolve(&self, path: &str) -> anyhow::Result<AbsNormPathBuf> {
+        if Path::new(path).is_absolute() {
+            AbsNormPathBuf::try_from(path.to_owned())
+        } else {
+            self.project_root
+                .root()
+                .join_normalized(RelativePath::new(path))
+        }
+    }
+}
+
+fn read_pipe(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<std::io::Result<Vec<u8>>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            pipe.read_to_end(&mut buf)?;
+        }
+        Ok(buf)
+    })
+}
+
+fn join_pipe(handle: JoinHandle<std::io::Result<Vec<u8>>>) -> anyhow::Result<Vec<u8>> {
+    Ok(handle
+        .join()
+        .map_err(|_| anyhow::anyhow!("Thread reading config generator output panicked"))??)
+}
+
+/// Summary of the size and modification time of `paths`, which changes when any of them does.
+fn stamp(paths: &[AbsNormPathBuf]) -> anyhow::Result<String> {
+    let mut stamp = String::new();
+    for path in paths {
+        match fs_util::metadata(path) {
+            Ok(metadata) => {
+                let mtime = metadata
+                    .modified()?
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |d| d.as_nanos());
+                writeln!(stamp, "{}\t{}\t{}", path, metadata.len(), mtime)?;
+            }
+            Err(_) => writeln!(stamp, "{}\tmissing", path)?,
+        }
+    }
+    Ok(stamp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::legacy_configs::configs::testing::parse;
+
+    #[test]
+    fn test_stamp_changes_with_inputs() -> anyhow::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let root = AbsNormPathBuf::try_from(tempdir.path().to_owned())?;
+        let input = root.join_normalized("input")?;
+
+        let missing = stamp(&[input.clone()])?;
+        fs_util::write(&input, "a")?;
+        let present = stamp(&[input.clone()])?;
+        assert_ne!(missing, present);
+        fs_util::write(&input, "ab")?;
+        assert_ne!(present, stamp(&[input])?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_generator_name() -> anyhow::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let root = ProjectRoot::new_unchecked(AbsNormPathBuf::try_from(tempdir.path().to_owned())?);
+        let out_dir = root.root().join_normalized("out")?;
+        let config = parse(
+            &[(
+                "/config",
+                indoc::indoc!(
+                    r#"
+                    [config_generators]
+                      bad/name = gen.sh
+                "#
+                ),
+            )],
+            "/config",
+        )?;
+        let err = ConfigGenerators::new(&root, &out_dir)
+            .generate(&config)
+            .unwrap_err();
+        assert!(format!("{:#}", err).contains("Invalid config generator name"));
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_generator_timeout() -> anyhow::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tempdir = tempfile::tempdir()?;
+        let root = ProjectRoot::new_unchecked(AbsNormPathBuf::try_from(tempdir.path().to_owned())?);
+        let out_dir = root.root().join_normalized("out")?;
+        let exe = root.root().join_normalized("gen.sh")?;
+        fs_util::write(&exe, "#!/bin/sh\nsleep 60\n")?;
+        std::fs::set_permissions(exe.as_path(), std::fs::Permissions::from_mode(0o755))?;
+
+        let generators = ConfigGenerators {
+            timeout: Duration::from_millis(100),
+            ..ConfigGenerators::new(&root, &out_dir)
+        };
+        let err = generators.run("slow", &exe).unwrap_err();
+        assert!(format!("{:#}", err).contains("did not finish"));
+        Ok(())
+    }
+}
@@ -22,6 +22,7 @@ use once_cell::sync::Lazy;
 use regex::Regex;
 use starlark_map::sorted_map::SortedMap;
 
+use crate::ignores::ignore_set::IgnoreSet;
 use crate::legacy_configs::configs::ConfigArgumentPair;
 use crate::legacy_configs::configs::ConfigArgumentParseError;
 use crate::legacy_configs::configs::ConfigData;
@@ -121,6 +122,44 @@ impl LegacyConfigParser {
         Ok(())
     }
 
+    /// Appends the patterns of a gitignore style ignore file to `project.ignore`.
+    pub(crate) async fn parse_ignore_file(
+        &mut self,
+        path: &AbsNormPath,
+        file_ops: &mut dyn ConfigParserFileOps,
+    ) -> anyhow::Result<()> {
+        if !file_ops.file_exists(path).await {
+            return Ok(());
+        }
+        let lines = file_ops
+            .read_file_lines(path)
+            .await?
+            .collect::<Result<Vec<_>, _>>()?;
+        let spec = IgnoreSet::spec_from_ignore_file(lines.iter().map(|x| x.as_str()))
+            .with_context(|| format!("Error parsing ignore file `{}`", path))?;
+        if spec.is_empty() {
+            return Ok(());
+        }
+
+        self.start_file(path, None)?;
+        let source = self.location(0);
+        let section = self
+            .values
+            .entry("project".to_owned())
+            .or_insert_with(SectionBuilder::default);
+        let value = match section.values.get("ignore") {
+            Some(existing) if !existing.raw_value().trim().is_empty() => {
+                format!("{},{}", existing.raw_value(), spec.join(","))
+            }
+            _ => spec.join(","),
+        };
+        section
+            .values
+            .insert("ignore".to_owned(), ConfigValue::new_raw(source, value));
+        self.finish_file();
+        Ok(())
+    }
+
     fn push_file(&mut self, line: usize, path: &AbsNormPath) -> anyhow::Result<()> {
         let include_source = ConfigFileLocationWithLine {
                 source_file: self.current_file.dupe().unwrap_or_else(|| panic!("push_file() called without any files on the include stack. top-level files should use start_file()")),
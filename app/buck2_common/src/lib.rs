@@ -52,7 +52,9 @@ pub mod memory;
 pub mod package_boundary;
 pub mod package_listing;
 pub mod pattern;
+pub mod process_priority;
 pub mod scope;
+pub mod soft_error_policies;
 pub mod sqlite;
 pub mod starlark_profiler;
 pub mod systemd;
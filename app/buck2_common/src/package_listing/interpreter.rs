@@ -24,7 +24,10 @@ use itertools::Itertools;
 use starlark_map::sorted_set::SortedSet;
 use starlark_map::sorted_vec::SortedVec;
 
+use crate::buildfiles::HasBuildfiles;
+use crate::buildfiles::MultipleBuildfilesPolicy;
 use crate::dice::file_ops::DiceFileComputations;
+use crate::find_buildfile::find_all_buildfiles;
 use crate::find_buildfile::find_buildfile;
 use crate::ignores::file_ignores::FileIgnoreReason;
 use crate::io::ReadDirError;
@@ -110,6 +113,11 @@ pub enum GatherPackageListingError {
         candidates: Vec<FileNameBuf>,
     },
     #[buck2(input)]
+    MultipleBuildFiles {
+        package: CellPath,
+        found: Vec<FileNameBuf>,
+    },
+    #[buck2(input)]
     DirectoryDoesNotExist {
         package: CellPath,
         expected_path: CellPath,
@@ -230,12 +238,23 @@ impl std::fmt::Display for GatherPackageListingError {
                 write!(f, "gathering package listing for `{}`", &package)?;
                 return Ok(());
             }
+            GatherPackageListingError::MultipleBuildFiles { package, found } => {
+                write!(
+                    f,
+                    "package `{}:` contains multiple buildfiles {}, remove all but one (or set `buildfile.on_multiple = first` to use the first one)",
+                    package,
+                    found.iter().map(|v| format!("`{}`", v)).join(", ")
+                )?;
+                return Ok(());
+            }
             GatherPackageListingError::NoBuildFile {
                 candidates,
                 package,
             } => {
-                if let Some(primary_candidate) =
-                    candidates.iter().find(|v| v.extension() != Some("v2"))
+                if let Some(primary_candidate) = candidates
+                    .iter()
+                    .find(|v| v.extension() != Some("v2"))
+                    .or_else(|| candidates.first())
                 {
                     (
                         package,
@@ -499,6 +518,23 @@ async fn gather_package_listing_impl(
     let buildfile_candidates = DiceFileComputations::buildfiles(ctx, root.cell_name())
         .await
         .map_err(|e| GatherPackageListingError::anyhow(cell_path, e))?;
+    let policy = ctx
+        .get_multiple_buildfiles_policy(root.cell_name())
+        .await
+        .map_err(|e| GatherPackageListingError::anyhow(cell_path, e))?;
+    if policy == MultipleBuildfilesPolicy::Error {
+        let entries = DiceFileComputations::read_dir(ctx, cell_path)
+            .await
+            .map_err(|e| GatherPackageListingError::anyhow(cell_path, e))?
+            .included;
+        let found = find_all_buildfiles(&buildfile_candidates, &entries);
+        if found.len() > 1 {
+            return Err(GatherPackageListingError::MultipleBuildFiles {
+                package: cell_path.to_owned(),
+                found: found.into_iter().map(|f| f.to_owned()).collect(),
+            });
+        }
+    }
     Ok(Directory::gather(
         ctx,
         &buildfile_candidates,
@@ -18,6 +18,7 @@ use dice::DiceComputations;
 use gazebo::prelude::*;
 
 use crate::dice::cells::HasCellResolver;
+use crate::pattern::resolve::PatternOperation;
 use crate::pattern::resolve::ResolveTargetPatterns;
 use crate::pattern::resolve::ResolvedPattern;
 use crate::target_aliases::BuckConfigTargetAliasResolver;
@@ -77,6 +78,23 @@ pub async fn parse_patterns_from_cli_args<T: PatternType>(
     target_patterns.try_map(|value| parser.parse_pattern(&value))
 }
 
+/// Parse target patterns out of command line arguments, where patterns prefixed with `-` exclude
+/// the targets they match from the patterns before them.
+///
+/// See [PatternOperation] for the precedence rules.
+pub async fn parse_pattern_operations_from_cli_args<T: PatternType>(
+    ctx: &mut DiceComputations<'_>,
+    target_patterns: &[String],
+    cwd: &ProjectRelativePath,
+) -> anyhow::Result<Vec<PatternOperation<T>>> {
+    let parser = PatternParser::new(ctx, cwd).await?;
+
+    target_patterns.try_map(|value| match value.strip_prefix('-') {
+        Some(excluded) => Ok(PatternOperation::Exclude(parser.parse_pattern(excluded)?)),
+        None => Ok(PatternOperation::Include(parser.parse_pattern(value)?)),
+    })
+}
+
 pub async fn parse_patterns_from_cli_args_typed<T: PatternType>(
     ctx: &mut DiceComputations<'_>,
     patterns: &UnparsedPatterns<T>,
@@ -89,6 +107,6 @@ pub async fn parse_and_resolve_patterns_from_cli_args<T: PatternType>(
     target_patterns: &[String],
     cwd: &ProjectRelativePath,
 ) -> anyhow::Result<ResolvedPattern<T>> {
-    let patterns = parse_patterns_from_cli_args(ctx, target_patterns, cwd).await?;
-    ResolveTargetPatterns::resolve(ctx, &patterns).await
+    let operations = parse_pattern_operations_from_cli_args(ctx, target_patterns, cwd).await?;
+    ResolveTargetPatterns::resolve_operations(ctx, &operations).await
 }
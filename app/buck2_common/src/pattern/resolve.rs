@@ -15,6 +15,7 @@ use buck2_core::pattern::pattern::ParsedPattern;
 use buck2_core::pattern::pattern_type::ConfiguredProvidersPatternExtra;
 use buck2_core::pattern::pattern_type::PatternType;
 use buck2_core::target::name::TargetName;
+use buck2_core::target::name::TargetNameRef;
 use dice::DiceComputations;
 use dupe::Dupe;
 use gazebo::prelude::VecExt;
@@ -30,6 +31,31 @@ enum ResolvedPatternError {
     InvalidPattern(&'static str, String),
 }
 
+/// A target pattern that either adds to or, when written with a leading `-` on the command line,
+/// removes from the targets matched by the patterns before it.
+///
+/// Operations apply left to right, so in `//app/... -//app/experimental/... //app/experimental:ok`
+/// everything under `//app` is included except `//app/experimental/...`, apart from
+/// `//app/experimental:ok`.
+#[derive(Debug, Clone)]
+pub enum PatternOperation<T: PatternType> {
+    Include(ParsedPattern<T>),
+    Exclude(ParsedPattern<T>),
+}
+
+impl<T: PatternType> PatternOperation<T> {
+    /// The patterns that add targets, ignoring exclusions.
+    pub fn included(operations: &[Self]) -> Vec<ParsedPattern<T>> {
+        operations
+            .iter()
+            .filter_map(|op| match op {
+                PatternOperation::Include(pattern) => Some(pattern.clone()),
+                PatternOperation::Exclude(_) => None,
+            })
+            .collect()
+    }
+}
+
 /// Pattern where `foo/...` is expanded to matching packages.
 /// Targets are not validated yet, and `:` is not yet expanded.
 #[derive(Debug)]
@@ -56,12 +82,42 @@ where
             match s {
                 PackageSpec::Targets(ref mut t) => t.push((target_name, extra)),
                 PackageSpec::All => {}
+                PackageSpec::AllExcept(excluded) => {
+                    excluded.retain(|e| e != &target_name);
+                    if excluded.is_empty() {
+                        *s = PackageSpec::All;
+                    }
+                }
             }
         } else {
             self.specs
                 .insert(package, PackageSpec::Targets(vec![(target_name, extra)]));
         }
     }
+
+    pub fn remove_package(&mut self, package: &PackageLabel) {
+        self.specs.shift_remove(package);
+    }
+
+    pub fn remove_target(&mut self, package: &PackageLabel, target_name: &TargetNameRef) {
+        let Some(s) = self.specs.get_mut(package) else {
+            return;
+        };
+        match s {
+            PackageSpec::Targets(t) => {
+                t.retain(|(name, _)| name.as_ref() != target_name);
+                if t.is_empty() {
+                    self.specs.shift_remove(package);
+                }
+            }
+            PackageSpec::All => *s = PackageSpec::AllExcept(vec![target_name.to_owned()]),
+            PackageSpec::AllExcept(excluded) => {
+                if !excluded.iter().any(|e| e.as_ref() == target_name) {
+                    excluded.push(target_name.to_owned());
+                }
+            }
+        }
+    }
 }
 
 impl ResolvedPattern<ConfiguredProvidersPatternExtra> {
@@ -82,6 +138,7 @@ impl ResolvedPattern<ConfiguredProvidersPatternExtra> {
                     })?)
                 }
                 PackageSpec::All => PackageSpec::All,
+                PackageSpec::AllExcept(excluded) => PackageSpec::AllExcept(excluded),
             };
             specs.insert(package, spec);
         }
@@ -96,20 +153,53 @@ impl ResolveTargetPatterns {
     pub async fn resolve<P: PatternType>(
         ctx: &mut DiceComputations<'_>,
         patterns: &[ParsedPattern<P>],
+    ) -> anyhow::Result<ResolvedPattern<P>> {
+        let operations: Vec<_> = patterns
+            .iter()
+            .map(|p| PatternOperation::Include(p.clone()))
+            .collect();
+        Self::resolve_operations(ctx, &operations).await
+    }
+
+    /// Resolves a list of [PatternOperation] to a [ResolvedPattern], applying them in order.
+    pub async fn resolve_operations<P: PatternType>(
+        ctx: &mut DiceComputations<'_>,
+        operations: &[PatternOperation<P>],
     ) -> anyhow::Result<ResolvedPattern<P>> {
         ctx.with_linear_recompute(|ctx| async move {
-            resolve_target_patterns_impl(patterns, &DiceFileOps(&ctx)).await
+            resolve_target_patterns_impl(operations, &DiceFileOps(&ctx)).await
         })
         .await
     }
 }
 
 async fn resolve_target_patterns_impl<P: PatternType>(
-    patterns: &[ParsedPattern<P>],
+    operations: &[PatternOperation<P>],
     file_ops: &dyn FileOps,
 ) -> anyhow::Result<ResolvedPattern<P>> {
     let mut resolved = ResolvedPattern::new();
-    for pattern in patterns {
+    for operation in operations {
+        let pattern = match operation {
+            PatternOperation::Include(pattern) => pattern,
+            PatternOperation::Exclude(pattern) => {
+                // Exclusions only remove from what was included so far, so unlike inclusions
+                // they never need to look at the file system.
+                match pattern {
+                    ParsedPattern::Target(package, target_name, _) => {
+                        resolved.remove_target(package, target_name.as_ref());
+                    }
+                    ParsedPattern::Package(package) => {
+                        resolved.remove_package(package);
+                    }
+                    ParsedPattern::Recursive(cell_path) => {
+                        resolved.specs.retain(|package, _| {
+                            !package.as_cell_path().starts_with(cell_path.as_ref())
+                        });
+                    }
+                }
+                continue;
+            }
+        };
         match pattern {
             ParsedPattern::Target(package, target_name, extra) => {
                 resolved.add_target(package.dupe(), target_name.clone(), extra.clone());
@@ -161,6 +251,7 @@ mod tests {
     use crate::file_ops::testing::TestFileOps;
     use crate::file_ops::FileOps;
     use crate::pattern::resolve::resolve_target_patterns_impl;
+    use crate::pattern::resolve::PatternOperation;
     use crate::pattern::resolve::ResolvedPattern;
 
     #[derive(Clone)]
@@ -215,7 +306,11 @@ mod tests {
             T: PatternType,
         {
             let patterns: Vec<_> = patterns.map(|p| {
-                ParsedPattern::<T>::parse_precise(
+                let (p, exclude) = match p.strip_prefix('-') {
+                    Some(p) => (p, true),
+                    None => (*p, false),
+                };
+                let pattern = ParsedPattern::<T>::parse_precise(
                     p,
                     CellName::testing_new("root"),
                     &self.resolver,
@@ -225,7 +320,12 @@ mod tests {
                         .unwrap()
                         .testing_cell_alias_resolver(),
                 )
-                .unwrap()
+                .unwrap();
+                if exclude {
+                    PatternOperation::Exclude(pattern)
+                } else {
+                    PatternOperation::Include(pattern)
+                }
             });
 
             resolve_target_patterns_impl(&patterns, &*self.file_ops).await
@@ -401,4 +501,48 @@ mod tests {
                 ]);
         })
     }
+
+    #[tokio::test]
+    async fn test_exclusions() -> anyhow::Result<()> {
+        let tester = TestPatternResolver::new(
+            &[("root", "")],
+            &[
+                "app/BUCK",
+                "app/experimental/BUCK",
+                "app/experimental/deeper/BUCK",
+                "app/lib/BUCK",
+            ],
+        )?;
+        tester
+            .resolve::<TargetPatternExtra>(&[
+                "//app/...",
+                "-//app/experimental/...",
+                "-//app/lib:bad",
+                "-//app:",
+            ])
+            .await?
+            .assert_eq(&[(
+                PackageLabel::testing_parse("root//app/lib"),
+                PackageSpec::AllExcept(vec![TargetName::testing_new("bad")]),
+            )]);
+        // Later inclusions take precedence over earlier exclusions.
+        tester
+            .resolve::<TargetPatternExtra>(&[
+                "//app/...",
+                "-//app/experimental/...",
+                "//app/experimental:ok",
+                "-//app/lib:bad",
+                "//app/lib:bad",
+                "-//app/lib:",
+            ])
+            .await?
+            .assert_eq(&[
+                (PackageLabel::testing_parse("root//app"), PackageSpec::All),
+                (
+                    PackageLabel::testing_parse("root//app/experimental"),
+                    PackageSpec::Targets(vec![(TargetName::testing_new("ok"), TargetPatternExtra)]),
+                ),
+            ]);
+        Ok(())
+    }
 }
@@ -0,0 +1,60 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use dupe::Dupe;
+
+/// Lowered CPU and IO priority for the commands Buck runs locally when building in the background
+/// (`--background`), so that a large build does not make the machine unusable.
+#[derive(Clone, Copy, Dupe, Debug, PartialEq, Eq)]
+pub struct BackgroundPriority {
+    /// Niceness added to the commands, up to 19. On Windows, commands get the idle priority class
+    /// when this is at least 10, and the below normal priority class otherwise.
+    pub nice: i32,
+    /// Whether to put the commands in the idle IO scheduling class. Only supported on Linux.
+    pub idle_io: bool,
+}
+
+impl Default for BackgroundPriority {
+    fn default() -> Self {
+        Self {
+            nice: 10,
+            idle_io: true,
+        }
+    }
+}
+
+impl BackgroundPriority {
+    /// Lower the priority of the current process. This is meant to be called between `fork` and
+    /// `exec`, so it only makes async-signal-safe calls. It is best effort: failing to lower the
+    /// priority does not prevent the command from running.
+    #[cfg(unix)]
+    pub fn apply_to_current_process(&self) {
+        use nix::libc;
+
+        unsafe {
+            libc::nice(self.nice);
+        }
+
+        #[cfg(target_os = "linux")]
+        if self.idle_io {
+            const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+            const IOPRIO_CLASS_IDLE: libc::c_int = 3;
+            const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+
+            unsafe {
+                libc::syscall(
+                    libc::SYS_ioprio_set,
+                    IOPRIO_WHO_PROCESS,
+                    0,
+                    IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! The `soft_error_policies` of the root cell buckconfig, on DICE, so that computations
+//! reporting soft errors are invalidated when the policies change.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use allocative::Allocative;
+use async_trait::async_trait;
+use buck2_core::soft_error_policy::SoftErrorPolicies;
+use buck2_core::soft_error_policy::SoftErrorPolicy;
+use buck2_futures::cancellation::CancellationContext;
+use derive_more::Display;
+use dice::DiceComputations;
+use dice::Key;
+use dupe::Dupe;
+
+use crate::dice::cells::HasCellResolver;
+use crate::legacy_configs::dice::HasLegacyConfigs;
+use crate::legacy_configs::key::BuckconfigKeyRef;
+
+const SOFT_ERROR_POLICIES_SECTION: &str = "soft_error_policies";
+
+#[derive(Hash, Eq, PartialEq, Clone, Dupe, Display, Debug, Allocative)]
+#[display(fmt = "{:?}", self)]
+struct SoftErrorPoliciesKey;
+
+#[async_trait]
+impl Key for SoftErrorPoliciesKey {
+    type Value = buck2_error::Result<Arc<SoftErrorPolicies>>;
+
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        let root_cell = ctx.get_cell_resolver().await?.root_cell();
+        // The whole section is needed, so this depends on the whole root config, but
+        // `equality` stops the invalidation here when the policies do not change.
+        let config = ctx.get_legacy_config_for_cell(root_cell).await?;
+        let mut by_category = HashMap::new();
+        if let Some(section) = config.get_section(SOFT_ERROR_POLICIES_SECTION) {
+            for (category, _) in section.iter() {
+                if let Some(policy) = config.parse::<SoftErrorPolicy>(BuckconfigKeyRef {
+                    section: SOFT_ERROR_POLICIES_SECTION,
+                    property: category,
+                })? {
+                    by_category.insert(category.to_owned(), policy);
+                }
+            }
+        }
+        Ok(Arc::new(SoftErrorPolicies::new(by_category)))
+    }
+
+    fn validity(x: &Self::Value) -> bool {
+        x.is_ok()
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        match (x, y) {
+            (Ok(x), Ok(y)) => x == y,
+            _ => false,
+        }
+    }
+}
+
+#[async_trait]
+pub trait HasSoftErrorPolicies {
+    /// Policies deciding which soft errors are errors, to pass to `soft_error!` as `policies`.
+    async fn get_soft_error_policies(&mut self) -> buck2_error::Result<Arc<SoftErrorPolicies>>;
+}
+
+#[async_trait]
+impl HasSoftErrorPolicies for DiceComputations<'_> {
+    async fn get_soft_error_policies(&mut self) -> buck2_error::Result<Arc<SoftErrorPolicies>> {
+        self.compute(&SoftErrorPoliciesKey).await?
+    }
+}
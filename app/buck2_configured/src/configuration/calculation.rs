@@ -65,6 +65,15 @@ pub enum ConfigurationError {
     PlatformEvalUnequalConfiguration(TargetLabel, TargetLabel),
 }
 
+/// When `build.execution_platforms` is not set, use an execution platform generated from the
+/// facts `host_info()` exposes about the host instead of the legacy execution platform.
+const AUTO_HOST_EXECUTION_PLATFORM_BUCKCONFIG: BuckconfigKeyRef = BuckconfigKeyRef {
+    section: "build",
+    property: "auto_host_execution_platform",
+};
+
+const AUTO_HOST_EXECUTION_PLATFORM_TARGET: &str = "prelude//platforms:host";
+
 async fn get_target_platform_detector(
     ctx: &mut DiceComputations<'_>,
 ) -> buck2_error::Result<Arc<TargetPlatformDetector>> {
@@ -121,7 +130,8 @@ async fn get_target_platform_detector(
     ctx.compute(&TargetPlatformDetectorKey).await?
 }
 
-/// Returns the configured [ExecutionPlatforms] or None if `build.execution_platforms` is not configured.
+/// Returns the configured [ExecutionPlatforms] or None if neither `build.execution_platforms` nor
+/// `build.auto_host_execution_platform` is configured.
 async fn compute_execution_platforms(
     ctx: &mut DiceComputations<'_>,
 ) -> buck2_error::Result<Option<ExecutionPlatforms>> {
@@ -135,7 +145,22 @@ async fn compute_execution_platforms(
     let execution_platforms_target = match execution_platforms_target {
         Some(v) => TargetLabel::parse(&v, cells.root_cell(), &cells, &cell_alias_resolver)?,
         None => {
-            return Ok(None);
+            let auto_host_execution_platform = ctx
+                .parse_legacy_config_property::<bool>(
+                    cells.root_cell(),
+                    AUTO_HOST_EXECUTION_PLATFORM_BUCKCONFIG,
+                )
+                .await?
+                .unwrap_or(false);
+            if !auto_host_execution_platform {
+                return Ok(None);
+            }
+            TargetLabel::parse(
+                AUTO_HOST_EXECUTION_PLATFORM_TARGET,
+                cells.root_cell(),
+                &cells,
+                &cell_alias_resolver,
+            )?
         }
     };
 
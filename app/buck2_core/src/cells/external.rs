@@ -18,6 +18,7 @@ use crate::cells::name::CellName;
 pub enum ExternalCellOrigin {
     Bundled(CellName),
     Git(GitCellSetup),
+    Lockfile(LockfileCellSetup),
 }
 
 #[derive(
@@ -37,11 +38,53 @@ pub struct GitCellSetup {
     pub commit: Arc<str>,
 }
 
+/// The ecosystem a lockfile backing a `lockfile` external cell belongs to.
+#[derive(
+    Debug,
+    derive_more::Display,
+    Clone,
+    Copy,
+    Dupe,
+    allocative::Allocative,
+    PartialEq,
+    Eq,
+    Hash
+)]
+pub enum LockfileFormat {
+    #[display(fmt = "cargo")]
+    Cargo,
+    #[display(fmt = "npm")]
+    Npm,
+    #[display(fmt = "python")]
+    Python,
+}
+
+#[derive(
+    Debug,
+    derive_more::Display,
+    Clone,
+    Dupe,
+    allocative::Allocative,
+    PartialEq,
+    Eq,
+    Hash
+)]
+#[display(fmt = "lockfile({}, {}, {}:{})", format, lockfile, rule_bzl, rule_name)]
+pub struct LockfileCellSetup {
+    /// Project relative path of the lockfile the cell is synthesized from.
+    pub lockfile: Arc<str>,
+    pub format: LockfileFormat,
+    /// The `.bzl` file (as a load path) that exports the function invoked once per locked package.
+    pub rule_bzl: Arc<str>,
+    pub rule_name: Arc<str>,
+}
+
 impl fmt::Display for ExternalCellOrigin {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Bundled(cell) => write!(f, "bundled({})", cell),
             Self::Git(git) => write!(f, "{}", git),
+            Self::Lockfile(lockfile) => write!(f, "{}", lockfile),
         }
     }
 }
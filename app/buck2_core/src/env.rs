@@ -10,3 +10,4 @@
 pub mod __macro_refs;
 pub mod helper;
 pub mod registry;
+pub mod usage;
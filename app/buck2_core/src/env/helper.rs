@@ -13,6 +13,9 @@ use std::sync::OnceLock;
 
 use anyhow::Context;
 
+use crate::env::usage::record_process_env_usage;
+use crate::env::usage::EnvUsageSource;
+
 pub struct EnvHelper<T> {
     convert: fn(&str) -> anyhow::Result<T>,
     var: &'static str,
@@ -43,9 +46,13 @@ impl<T> EnvHelper<T> {
             .get_or_try_init(move || match env::var(var) {
                 Ok(v) => {
                     tracing::info!("Env override found: ${} = {}", var, v);
+                    record_process_env_usage(EnvUsageSource::Daemon, var, Some(&v), None);
                     Ok(Some((convert)(&v).map_err(anyhow::Error::from)?))
                 }
-                Err(VarError::NotPresent) => Ok(None),
+                Err(VarError::NotPresent) => {
+                    record_process_env_usage(EnvUsageSource::Daemon, var, None, None);
+                    Ok(None)
+                }
                 Err(VarError::NotUnicode(..)) => Err(anyhow::anyhow!("Variable is not unicode")),
             })
             .map(Option::as_ref)
@@ -0,0 +1,92 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Record of the environment variables which influenced the build, for `buck2 audit env`.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+use dupe::Dupe;
+use once_cell::sync::Lazy;
+
+/// Who read an environment variable.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Dupe,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    derive_more::Display,
+    serde::Serialize
+)]
+#[serde(rename_all = "snake_case")]
+pub enum EnvUsageSource {
+    /// The daemon process itself, e.g. `BUCK2_*` knobs. Read from the daemon's environment,
+    /// once per daemon.
+    #[display(fmt = "daemon")]
+    Daemon,
+    /// The Starlark interpreter. Read from the daemon's environment.
+    #[display(fmt = "interpreter")]
+    Interpreter,
+    /// Actions, via `env_passthrough`. Read from the client's environment, per invocation.
+    #[display(fmt = "action")]
+    Action,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct EnvUsage {
+    pub source: EnvUsageSource,
+    pub name: String,
+    pub value: Option<String>,
+    /// Whether the value is different from the one seen by the last invocation which started
+    /// before and read it, and so invalidated the `consumers`. Only tracked for actions.
+    pub changed: bool,
+    /// What depends on the variable, e.g. the actions which declared it.
+    pub consumers: BTreeSet<String>,
+}
+
+/// Reads by the daemon and the interpreter, for the lifetime of the daemon. Reads by actions
+/// are recorded per invocation by the daemon state.
+static PROCESS_ENV_USAGE: Lazy<Mutex<BTreeMap<(EnvUsageSource, String), EnvUsage>>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+/// Record that the daemon or the interpreter read `name` from the daemon's environment.
+pub fn record_process_env_usage(
+    source: EnvUsageSource,
+    name: &str,
+    value: Option<&str>,
+    consumer: Option<&str>,
+) {
+    let mut usage = PROCESS_ENV_USAGE.lock().unwrap();
+    let entry = usage
+        .entry((source, name.to_owned()))
+        .or_insert_with(|| EnvUsage {
+            source,
+            name: name.to_owned(),
+            value: None,
+            changed: false,
+            consumers: BTreeSet::new(),
+        });
+    entry.value = value.map(|v| v.to_owned());
+    entry.consumers.extend(consumer.map(|c| c.to_owned()));
+}
+
+/// Reads by the daemon and the interpreter so far, sorted by source, then name.
+pub fn process_env_usage() -> Vec<EnvUsage> {
+    PROCESS_ENV_USAGE
+        .lock()
+        .unwrap()
+        .values()
+        .cloned()
+        .collect()
+}
@@ -20,6 +20,8 @@ use starlark_map::small_set::SmallSet;
 
 use crate::env::__macro_refs::buck2_env;
 use crate::is_open_source;
+use crate::soft_error_policy::SoftErrorPolicies;
+use crate::soft_error_policy::SoftErrorSeverity;
 
 type StructuredErrorHandler = Box<
     dyn for<'a> Fn(&'a str, &anyhow::Error, (&'a str, u32, u32), StructuredErrorOptions)
@@ -141,6 +143,11 @@ pub struct StructuredErrorOptions {
     pub daemon_in_memory_state_is_corrupted: bool,
     pub daemon_materializer_state_is_corrupted: bool,
     pub action_cache_is_corrupted: bool,
+    /// What the error is about, e.g. a package, to select `soft_error_policies` rules by path.
+    pub path: Option<String>,
+    /// Policies of the repository, read from the buckconfig of the computation reporting the
+    /// error, deciding whether it is an error or a warning.
+    pub policies: Option<Arc<SoftErrorPolicies>>,
 }
 
 impl Default for StructuredErrorOptions {
@@ -151,6 +158,8 @@ impl Default for StructuredErrorOptions {
             daemon_in_memory_state_is_corrupted: false,
             daemon_materializer_state_is_corrupted: false,
             action_cache_is_corrupted: false,
+            path: None,
+            policies: None,
         }
     }
 }
@@ -172,6 +181,9 @@ pub fn handle_soft_error(
         return Err(err);
     }
 
+    let path = options.path.clone();
+    let policies = options.policies.clone();
+
     once.call_once(|| {
         ALL_SOFT_ERROR_COUNTERS.lock().unwrap().push(count);
     });
@@ -187,6 +199,16 @@ pub fn handle_soft_error(
         return Err(err.context("Upgraded warning to failure via $BUCK2_HARD_ERROR"));
     }
 
+    if let Some(policies) = &policies {
+        match policies.severity(category, path.as_deref()) {
+            Some(SoftErrorSeverity::Error) => {
+                return Err(err.context("Upgraded warning to failure via `soft_error_policies`"));
+            }
+            Some(SoftErrorSeverity::Warning) => return Ok(err),
+            None => {}
+        }
+    }
+
     if is_open_source() {
         // We don't log these, and we have no legacy users, and they might not upgrade that often,
         // so lets just break open source things immediately.
@@ -287,6 +287,38 @@ pub enum HybridExecutionLevel {
     },
 }
 
+/// Overrides how a hybrid executor schedules a command. This is configured per action category in
+/// the `[hybrid_execution_policies]` buckconfig section. Whether to fall back on failures (as
+/// opposed to errors) is still decided by the executor's [`HybridExecutionLevel`].
+#[derive(Display, Debug, Eq, PartialEq, Clone, Copy, Dupe, Hash, Allocative)]
+pub enum HybridExecutionPolicy {
+    /// Race both executors, and cancel whichever one loses.
+    #[display(fmt = "race")]
+    Race,
+    /// Execute remotely, and fall back to local execution if that does not succeed.
+    #[display(fmt = "remote_then_local")]
+    RemoteThenLocal,
+    /// Execute locally, and fall back to remote execution if that does not succeed.
+    #[display(fmt = "local_then_remote")]
+    LocalThenRemote,
+}
+
+impl FromStr for HybridExecutionPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "race" => Ok(HybridExecutionPolicy::Race),
+            "remote_then_local" => Ok(HybridExecutionPolicy::RemoteThenLocal),
+            "local_then_remote" => Ok(HybridExecutionPolicy::LocalThenRemote),
+            _ => Err(anyhow::anyhow!(
+                "Invalid HybridExecutionPolicy: `{}` (expected `race`, `remote_then_local` or `local_then_remote`)",
+                s
+            )),
+        }
+    }
+}
+
 impl CommandExecutorConfig {
     pub fn testing_local() -> Arc<CommandExecutorConfig> {
         Arc::new(CommandExecutorConfig {
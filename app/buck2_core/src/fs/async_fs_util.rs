@@ -26,6 +26,11 @@ pub async fn write<P: AsRef<AbsPath>>(path: P, content: impl AsRef<[u8]>) -> any
     Ok(tokio::task::spawn_blocking(move || fs_util::write(path, content)).await??)
 }
 
+pub async fn read<P: AsRef<AbsPath>>(path: P) -> anyhow::Result<Vec<u8>> {
+    let path = path.as_ref().to_owned();
+    Ok(tokio::task::spawn_blocking(move || fs_util::read(path)).await??)
+}
+
 pub async fn read_to_string<P: AsRef<AbsPath>>(path: P) -> anyhow::Result<String> {
     let path = path.as_ref().to_owned();
     Ok(tokio::task::spawn_blocking(move || fs_util::read_to_string(path)).await??)
@@ -42,3 +47,9 @@ pub async fn create_dir_all<P: AsRef<AbsPath>>(dir: P) -> anyhow::Result<()> {
     let dir = dir.as_ref().to_owned();
     Ok(tokio::task::spawn_blocking(move || fs_util::create_dir_all(dir)).await??)
 }
+
+pub async fn copy<P: AsRef<AbsPath>, Q: AsRef<AbsPath>>(from: P, to: Q) -> anyhow::Result<u64> {
+    let from = from.as_ref().to_owned();
+    let to = to.as_ref().to_owned();
+    Ok(tokio::task::spawn_blocking(move || fs_util::copy(from, to)).await??)
+}
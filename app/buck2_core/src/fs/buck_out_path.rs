@@ -233,6 +233,7 @@ impl BuckOutPathResolver {
             match origin {
                 ExternalCellOrigin::Bundled(_) => ForwardRelativePath::new("bundled").unwrap(),
                 ExternalCellOrigin::Git(_) => ForwardRelativePath::new("git").unwrap(),
+                ExternalCellOrigin::Lockfile(_) => ForwardRelativePath::new("lockfile").unwrap(),
             },
             match &origin {
                 ExternalCellOrigin::Bundled(cell) => {
@@ -241,6 +242,10 @@ impl BuckOutPathResolver {
                 ExternalCellOrigin::Git(setup) => {
                     ForwardRelativePath::new(setup.commit.as_ref()).unwrap()
                 }
+                // Validated to be a forward relative path when the cell configuration is parsed.
+                ExternalCellOrigin::Lockfile(setup) => {
+                    ForwardRelativePath::new(setup.lockfile.as_ref()).unwrap()
+                }
             },
             path.as_ref(),
         ]))
@@ -15,6 +15,8 @@ use std::fs;
 use std::fs::File;
 use std::io;
 use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
 use std::io::Write;
 use std::ops::Deref;
 use std::path::Path;
@@ -357,6 +359,24 @@ pub fn copy<P: AsRef<AbsPath>, Q: AsRef<AbsPath>>(from: P, to: Q) -> Result<u64,
     )
 }
 
+pub fn hard_link<P: AsRef<AbsPath>, Q: AsRef<AbsPath>>(
+    original: P,
+    link: Q,
+) -> Result<(), IoError> {
+    let _guard = IoCounterKey::Hardlink.guard();
+    make_error!(
+        fs::hard_link(
+            original.as_ref().as_maybe_relativized(),
+            link.as_ref().as_maybe_relativized(),
+        ),
+        format!(
+            "hard_link(original={}, link={})",
+            P::as_ref(&original).display(),
+            Q::as_ref(&link).display()
+        ),
+    )
+}
+
 pub fn read_link<P: AsRef<AbsPath>>(path: P) -> Result<PathBuf, IoError> {
     let _guard = IoCounterKey::ReadLink.guard();
     make_error!(
@@ -551,6 +571,13 @@ pub struct FileWriteGuard {
     _guard: IoCounterGuard,
 }
 
+impl FileWriteGuard {
+    /// The file, e.g. to redirect the output of a process to it.
+    pub fn into_file(self) -> File {
+        self.file
+    }
+}
+
 impl Write for FileWriteGuard {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         self.file.write(buf)
@@ -601,6 +628,12 @@ impl Read for FileReadGuard {
     }
 }
 
+impl Seek for FileReadGuard {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.file.seek(pos)
+    }
+}
+
 pub fn open_file<P: AsRef<AbsPath>>(path: P) -> Result<FileReadGuard, IoError> {
     let guard = IoCounterKey::Read.guard();
     let file = make_error!(
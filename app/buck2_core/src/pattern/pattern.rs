@@ -1050,13 +1050,26 @@ where
     Ok(Some(res))
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub enum PackageSpec<T: PatternType> {
     /// Given targets in a package.
     Targets(Vec<(TargetName, T)>),
     /// All targets in a package, without subpackages.
     /// Syntax for this variant is `foo:`.
     All,
+    /// All targets in a package except the given ones, without subpackages.
+    /// Produced by excluding targets on the command line, e.g. `foo: -foo:bar`.
+    AllExcept(Vec<TargetName>),
+}
+
+impl<T: PatternType> PackageSpec<T> {
+    /// Whether `name` was excluded from a spec that otherwise matches all targets in a package.
+    pub fn excludes(&self, name: &TargetNameRef) -> bool {
+        match self {
+            PackageSpec::Targets(_) | PackageSpec::All => false,
+            PackageSpec::AllExcept(excluded) => excluded.iter().any(|e| e.as_ref() == name),
+        }
+    }
 }
 
 #[cfg(test)]
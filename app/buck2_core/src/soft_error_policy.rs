@@ -0,0 +1,239 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Repository policy deciding whether soft errors are errors or warnings.
+//!
+//! Policies are configured per soft error category in the `soft_error_policies`
+//! buckconfig section of the root cell, e.g.:
+//!
+//! ```ini
+//! [soft_error_policies]
+//! source_file_missing = warning, fbcode//new/ error, fbcode//newer/ error 25%
+//! ```
+//!
+//! Each comma-separated rule is `[<path prefix>] <error|warning> [<percent>%]`.
+//! The rule with the longest prefix matching the path the soft error is about
+//! applies. A percentage stages the rollout of an error: it applies to that
+//! share of paths (or of hosts, for soft errors not about a path), the others
+//! get a warning.
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::str::FromStr;
+
+use allocative::Allocative;
+use dupe::Dupe;
+use os_str_bytes::OsStrBytes;
+
+#[derive(buck2_error::Error, Debug)]
+enum SoftErrorPolicyError {
+    #[error(
+        "Invalid soft error policy rule `{0}`, \
+        expected `[<path prefix>] <error|warning> [<percent>%]`"
+    )]
+    InvalidRule(String),
+}
+
+/// What a soft error is reported as.
+#[derive(Copy, Clone, Dupe, Debug, PartialEq, Eq, Allocative)]
+pub enum SoftErrorSeverity {
+    Warning,
+    Error,
+}
+
+impl FromStr for SoftErrorSeverity {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "warning" => Ok(Self::Warning),
+            "error" => Ok(Self::Error),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Allocative)]
+struct SoftErrorPolicyRule {
+    /// Empty to match everything.
+    path_prefix: String,
+    severity: SoftErrorSeverity,
+    /// Share of paths an `Error` applies to, between 0 and 1.
+    rollout: f64,
+}
+
+impl SoftErrorPolicyRule {
+    fn matches(&self, path: Option<&str>) -> bool {
+        if self.path_prefix.is_empty() {
+            return true;
+        }
+        match path.and_then(|p| p.strip_prefix(self.path_prefix.as_str())) {
+            Some(rest) => {
+                rest.is_empty() || rest.starts_with('/') || self.path_prefix.ends_with('/')
+            }
+            None => false,
+        }
+    }
+}
+
+/// Policy for one soft error category.
+#[derive(Clone, Debug, PartialEq, Allocative)]
+pub struct SoftErrorPolicy {
+    rules: Vec<SoftErrorPolicyRule>,
+}
+
+impl SoftErrorPolicy {
+    /// Severity of a soft error about `path`, or `None` if no rule matches.
+    pub fn severity(&self, path: Option<&str>) -> Option<SoftErrorSeverity> {
+        self.severity_inner(path, || hostname::get().ok())
+    }
+
+    fn severity_inner<F>(&self, path: Option<&str>, get_hostname: F) -> Option<SoftErrorSeverity>
+    where
+        F: FnOnce() -> Option<OsString>,
+    {
+        let rule = self
+            .rules
+            .iter()
+            .filter(|r| r.matches(path))
+            .max_by_key(|r| r.path_prefix.len())?;
+        if rule.severity == SoftErrorSeverity::Error && rule.rollout < 1.0 {
+            let key = match path {
+                Some(path) => path.as_bytes().to_vec(),
+                None => get_hostname()?.to_raw_bytes().into_owned(),
+            };
+            // Same approach as `RolloutPercentage`: compare the first byte of a hash.
+            if (blake3::hash(&key).as_bytes()[0] as f64 / 256_f64) >= rule.rollout {
+                return Some(SoftErrorSeverity::Warning);
+            }
+        }
+        Some(rule.severity)
+    }
+}
+
+impl FromStr for SoftErrorPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let mut rules = Vec::new();
+        for rule in s.split(',') {
+            let invalid = || SoftErrorPolicyError::InvalidRule(rule.trim().to_owned());
+            let tokens: Vec<&str> = rule.split_whitespace().collect();
+            let (path_prefix, tokens) = match tokens.split_first() {
+                Some((first, _)) if first.parse::<SoftErrorSeverity>().is_ok() => ("", &tokens[..]),
+                Some((first, rest)) => (*first, rest),
+                None => return Err(invalid().into()),
+            };
+            let (severity, rollout) = match tokens {
+                [severity] => (severity.parse().map_err(|()| invalid())?, 1.0),
+                [severity, percent] => {
+                    let severity = severity.parse().map_err(|()| invalid())?;
+                    let percent = percent
+                        .strip_suffix('%')
+                        .and_then(|p| p.parse::<f64>().ok())
+                        .filter(|p| (0.0..=100.0).contains(p))
+                        .ok_or_else(invalid)?;
+                    if severity != SoftErrorSeverity::Error {
+                        return Err(invalid().into());
+                    }
+                    (severity, percent / 100.0)
+                }
+                _ => return Err(invalid().into()),
+            };
+            rules.push(SoftErrorPolicyRule {
+                path_prefix: path_prefix.to_owned(),
+                severity,
+                rollout,
+            });
+        }
+        Ok(SoftErrorPolicy { rules })
+    }
+}
+
+/// Soft error policies of a repository, by category.
+#[derive(Default, Debug, PartialEq, Allocative)]
+pub struct SoftErrorPolicies {
+    by_category: HashMap<String, SoftErrorPolicy>,
+}
+
+impl SoftErrorPolicies {
+    pub fn new(by_category: HashMap<String, SoftErrorPolicy>) -> Self {
+        Self { by_category }
+    }
+
+    pub fn severity(&self, category: &str, path: Option<&str>) -> Option<SoftErrorSeverity> {
+        self.by_category.get(category)?.severity(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn severity(policy: &str, path: Option<&str>) -> Option<SoftErrorSeverity> {
+        policy
+            .parse::<SoftErrorPolicy>()
+            .unwrap()
+            .severity_inner(path, || Some("host".into()))
+    }
+
+    #[test]
+    fn test_longest_prefix_wins() {
+        let policy = "warning, cell//a error, cell//a/b/ warning";
+        assert_eq!(Some(SoftErrorSeverity::Warning), severity(policy, None));
+        assert_eq!(
+            Some(SoftErrorSeverity::Error),
+            severity(policy, Some("cell//a/c"))
+        );
+        assert_eq!(
+            Some(SoftErrorSeverity::Warning),
+            severity(policy, Some("cell//ab"))
+        );
+        assert_eq!(
+            Some(SoftErrorSeverity::Warning),
+            severity(policy, Some("cell//a/b/c"))
+        );
+        assert_eq!(None, severity("cell//a error", Some("cell//b")));
+        assert_eq!(None, severity("cell//a error", None));
+    }
+
+    #[test]
+    fn test_rollout() {
+        assert_eq!(
+            Some(SoftErrorSeverity::Error),
+            severity("error 100%", Some("cell//a"))
+        );
+        assert_eq!(
+            Some(SoftErrorSeverity::Warning),
+            severity("error 0%", Some("cell//a"))
+        );
+        let paths = (0..1000)
+            .map(|i| format!("cell//p{}", i))
+            .collect::<Vec<_>>();
+        let errors = paths
+            .iter()
+            .filter(|p| severity("error 25%", Some(p)) == Some(SoftErrorSeverity::Error))
+            .count();
+        assert!((150..350).contains(&errors), "{}", errors);
+    }
+
+    #[test]
+    fn test_invalid() {
+        for policy in [
+            "",
+            "fatal",
+            "cell//a",
+            "warning 50%",
+            "error 150%",
+            "a b c d",
+        ] {
+            assert!(policy.parse::<SoftErrorPolicy>().is_err(), "{}", policy);
+        }
+    }
+}
@@ -124,75 +124,80 @@ fn main() -> io::Result<()> {
         .type_attribute("buck.data.CommandExecutionStats", "#[derive(Copy, dupe::Dupe)]")
         .type_attribute(".", "#[derive(::serde::Serialize, ::serde::Deserialize)]")
         .type_attribute(".", "#[derive(::allocative::Allocative)]")
+        .type_attribute(".", "#[derive(::schemars::JsonSchema)]")
         .field_attribute(
             "timestamp",
-            "#[serde(with = \"crate::serialize_timestamp\")]",
+            "#[serde(with = \"crate::serialize_timestamp\")] #[schemars(with = \"Option<(i64, i32)>\")]",
         )
         .field_attribute(
             "duration",
-            "#[serde(rename = \"duration_us\", with = \"crate::serialize_duration_as_micros\")]",
+            "#[serde(rename = \"duration_us\", with = \"crate::serialize_duration_as_micros\")] #[schemars(with = \"Option<i64>\")]",
         )
         .field_attribute(
             "command_duration",
-            "#[serde(rename = \"command_duration_us\", with = \"crate::serialize_duration_as_micros\")]",
+            "#[serde(rename = \"command_duration_us\", with = \"crate::serialize_duration_as_micros\")] #[schemars(with = \"Option<i64>\")]",
         )
         .field_attribute(
             "client_walltime",
-            "#[serde(rename = \"client_walltime_us\", with = \"crate::serialize_duration_as_micros\")]",
+            "#[serde(rename = \"client_walltime_us\", with = \"crate::serialize_duration_as_micros\")] #[schemars(with = \"Option<i64>\")]",
         )
         .field_attribute(
             "critical_path_duration",
-            "#[serde(rename = \"critical_path_duration_us\", with = \"crate::serialize_duration_as_micros\")]",
+            "#[serde(rename = \"critical_path_duration_us\", with = \"crate::serialize_duration_as_micros\")] #[schemars(with = \"Option<i64>\")]",
         )
         .field_attribute(
             "ActionExecutionEnd.wall_time",
-            "#[serde(rename = \"wall_time_us\", with = \"crate::serialize_duration_as_micros\")]",
+            "#[serde(rename = \"wall_time_us\", with = \"crate::serialize_duration_as_micros\")] #[schemars(with = \"Option<i64>\")]",
         )
         .field_attribute(
             "ActionKey.id",
-            "#[serde(with = \"crate::serialize_bytes\")]",
+            "#[serde(with = \"crate::serialize_bytes\")] #[schemars(with = \"String\")]",
         )
         // When serializing using Serde we don't want those to just be i32s, since those are
         // meaningless without the Protobuf schema.
         .field_attribute(
             "ActionExecutionStart.kind",
-            "#[serde(with = \"crate::serialize_action_kind\")]",
+            "#[serde(with = \"crate::serialize_action_kind\")] #[schemars(with = \"crate::ActionKind\")]",
         )
         .field_attribute(
             "ActionExecutionEnd.kind",
-            "#[serde(with = \"crate::serialize_action_kind\")]",
+            "#[serde(with = \"crate::serialize_action_kind\")] #[schemars(with = \"crate::ActionKind\")]",
+        )
+        .field_attribute(
+            "ActionInfraRetry.backoff",
+            "#[serde(rename = \"backoff_us\", with = \"crate::serialize_duration_as_micros\")] #[schemars(with = \"Option<i64>\")]",
         )
         .field_attribute(
             "RemoteCommand.queue_time",
-            "#[serde(rename = \"queue_time_us\", with = \"crate::serialize_duration_as_micros\")]",
+            "#[serde(rename = \"queue_time_us\", with = \"crate::serialize_duration_as_micros\")] #[schemars(with = \"Option<i64>\")]",
         )
         .field_attribute(
             "concurrent_command_blocking_duration",
-            "#[serde(rename = \"concurrent_command_blocking_duration_us\", with = \"crate::serialize_duration_as_micros\")]",
+            "#[serde(rename = \"concurrent_command_blocking_duration_us\", with = \"crate::serialize_duration_as_micros\")] #[schemars(with = \"Option<i64>\")]",
         )
         .field_attribute(
             "bxl_ensure_artifacts_duration",
-            "#[serde(rename = \"bxl_ensure_artifacts_duration_us\", with = \"crate::serialize_duration_as_micros\")]",
+            "#[serde(rename = \"bxl_ensure_artifacts_duration_us\", with = \"crate::serialize_duration_as_micros\")] #[schemars(with = \"Option<i64>\")]",
         )
         .field_attribute(
             "install_duration",
-            "#[serde(rename = \"install_duration_us\", with = \"crate::serialize_duration_as_micros\")]",
+            "#[serde(rename = \"install_duration_us\", with = \"crate::serialize_duration_as_micros\")] #[schemars(with = \"Option<i64>\")]",
         )
         .field_attribute(
             "CriticalPathEntry2.user_duration",
-            "#[serde(rename = \"user_duration_us\", with = \"crate::serialize_duration_as_micros\")]",
+            "#[serde(rename = \"user_duration_us\", with = \"crate::serialize_duration_as_micros\")] #[schemars(with = \"Option<i64>\")]",
         )
         .field_attribute(
             "CriticalPathEntry2.total_duration",
-            "#[serde(rename = \"total_duration_us\", with = \"crate::serialize_duration_as_micros\")]",
+            "#[serde(rename = \"total_duration_us\", with = \"crate::serialize_duration_as_micros\")] #[schemars(with = \"Option<i64>\")]",
         )
         .field_attribute(
             "CriticalPathEntry2.potential_improvement_duration",
-            "#[serde(rename = \"potential_improvement_duration_us\", with = \"crate::serialize_duration_as_micros\")]",
+            "#[serde(rename = \"potential_improvement_duration_us\", with = \"crate::serialize_duration_as_micros\")] #[schemars(with = \"Option<i64>\")]",
         )
         .field_attribute(
             "CriticalPathEntry2.queue_duration",
-            "#[serde(rename = \"queue_duration_us\", with = \"crate::serialize_duration_as_micros\")]",
+            "#[serde(rename = \"queue_duration_us\", with = \"crate::serialize_duration_as_micros\")] #[schemars(with = \"Option<i64>\")]",
         )
         .type_attribute(
             "buck.data.CriticalPathEntry2.entry",
@@ -232,27 +237,27 @@ fn main() -> io::Result<()> {
         )
         .field_attribute(
             "buck.data.CommandExecutionMetadata.wall_time",
-            "#[serde(rename = \"wall_time_us\", with = \"crate::serialize_duration_as_micros\")]",
+            "#[serde(rename = \"wall_time_us\", with = \"crate::serialize_duration_as_micros\")] #[schemars(with = \"Option<i64>\")]",
         )
         .field_attribute(
             "buck.data.CommandExecutionMetadata.execution_time",
-            "#[serde(rename = \"execution_time_us\", with = \"crate::serialize_duration_as_micros\")]",
+            "#[serde(rename = \"execution_time_us\", with = \"crate::serialize_duration_as_micros\")] #[schemars(with = \"Option<i64>\")]",
         )
         .field_attribute(
             "buck.data.CommandExecutionMetadata.start_time",
-            "#[serde(with = \"crate::serialize_timestamp\")]",
+            "#[serde(with = \"crate::serialize_timestamp\")] #[schemars(with = \"Option<(i64, i32)>\")]",
         )
         .field_attribute(
             "buck.data.CommandExecutionMetadata.input_materialization_duration",
-            "#[serde(rename = \"input_materialization_duration_us\", with = \"crate::serialize_duration_as_micros\")]",
+            "#[serde(rename = \"input_materialization_duration_us\", with = \"crate::serialize_duration_as_micros\")] #[schemars(with = \"Option<i64>\")]",
         )
         .field_attribute(
             "buck.data.CommandExecutionMetadata.hashing_duration",
-            "#[serde(rename = \"hashing_duration_us\", with = \"crate::serialize_duration_as_micros\")]",
+            "#[serde(rename = \"hashing_duration_us\", with = \"crate::serialize_duration_as_micros\")] #[schemars(with = \"Option<i64>\")]",
         )
         .field_attribute(
             "buck.data.CommandExecutionMetadata.queue_duration",
-            "#[serde(rename = \"queue_duration_us\", with = \"crate::serialize_duration_as_micros\")]",
+            "#[serde(rename = \"queue_duration_us\", with = \"crate::serialize_duration_as_micros\")] #[schemars(with = \"Option<i64>\")]",
         )
         .boxed("RecordEvent.data.invocation_record")
         .boxed("SpanEndEvent.data.action_execution")
@@ -31,10 +31,13 @@ fn tag_rank(tag: ErrorTag) -> u32 {
         ErrorTag::DaemonWontDieFromKill => line!(),
         ErrorTag::DaemonIsBusy => line!(),
         ErrorTag::DaemonPreempted => line!(),
+        ErrorTag::NestedInvocation => line!(),
         ErrorTag::DaemonConnect => line!(),
         ErrorTag::GrpcResponseMessageTooLarge => line!(),
         ErrorTag::ClientGrpc => line!(),
         ErrorTag::NoValidCerts => line!(),
+        ErrorTag::DownloadSignatureInvalid => line!(),
+        ErrorTag::DownloadChecksumMismatch => line!(),
         ErrorTag::IoBrokenPipe => line!(),
         ErrorTag::IoConnectionAborted => line!(),
         ErrorTag::IoNotConnected => line!(),
@@ -73,6 +76,7 @@ pub(crate) fn error_tag_category(tag: ErrorTag) -> Option<Tier> {
         ErrorTag::DaemonConnect => None,
         ErrorTag::DaemonIsBusy => Some(Tier::Input),
         ErrorTag::DaemonPreempted => Some(Tier::Input),
+        ErrorTag::NestedInvocation => Some(Tier::Input),
         ErrorTag::InternalError => Some(Tier::Tier0),
         // FIXME(JakobDegen): Make this bad experience once that's available. Usually when this
         // happens, it's probably because the user tried to shut down with Ctrl+C and something
@@ -81,6 +85,8 @@ pub(crate) fn error_tag_category(tag: ErrorTag) -> Option<Tier> {
         ErrorTag::GrpcResponseMessageTooLarge => Some(Tier::Tier0),
         ErrorTag::ClientGrpc => Some(Tier::Tier0),
         ErrorTag::NoValidCerts => Some(Tier::Input),
+        ErrorTag::DownloadSignatureInvalid => Some(Tier::Input),
+        ErrorTag::DownloadChecksumMismatch => Some(Tier::Input),
         ErrorTag::IoBrokenPipe => None,
         ErrorTag::IoConnectionAborted => Some(Tier::Tier0),
         ErrorTag::IoNotConnected => Some(Tier::Input), // This typically means eden is not mounted
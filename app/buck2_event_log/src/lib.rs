@@ -21,6 +21,8 @@ use tokio::task::JoinHandle;
 
 pub mod file_names;
 pub mod read;
+pub mod segment;
+pub mod storage;
 pub mod stream_value;
 pub mod ttl;
 pub mod user_event_types;
@@ -8,6 +8,7 @@
  */
 
 use std::io;
+use std::io::SeekFrom;
 use std::pin::Pin;
 use std::str::FromStr;
 use std::sync::atomic::AtomicUsize;
@@ -24,22 +25,30 @@ use buck2_cli_proto::*;
 use buck2_core::fs::async_fs_util;
 use buck2_core::fs::paths::abs_path::AbsPath;
 use buck2_core::fs::paths::abs_path::AbsPathBuf;
+use buck2_data::EventLogSegmentIndex;
 use buck2_events::BuckEvent;
 use buck2_wrapper_common::invocation_id::TraceId;
+use futures::stream;
 use futures::stream::BoxStream;
 use futures::stream::Stream;
 use futures::stream::TryStreamExt;
 use futures::StreamExt;
 use pin_project::pin_project;
+use prost::bytes::Buf;
+use prost::bytes::BytesMut;
 use prost::Message;
 use regex::Regex;
 use tokio::io::AsyncBufReadExt;
 use tokio::io::AsyncRead;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncSeekExt;
 use tokio::io::BufReader;
 use tokio::io::ReadBuf;
 use tokio_stream::wrappers::LinesStream;
 use tokio_util::codec::FramedRead;
 
+use crate::segment::read_segments;
+use crate::segment::EventLogSegment;
 use crate::stream_value::StreamValue;
 use crate::utils::Compression;
 use crate::utils::Encoding;
@@ -213,9 +222,17 @@ impl EventLogPathBuf {
         let mut stream = FramedRead::new(log_file, ProtobufSplitter);
 
         let invocation = stream.try_next().await?.context("No invocation found")?;
-        let invocation = buck2_data::Invocation::decode_length_delimited(invocation)
-            .context("Invalid Invocation")?;
-        let invocation = Invocation {
+        let invocation = Self::decode_invocation(invocation)?;
+
+        let events = stream.and_then(|data| async move { Self::decode_stream_value(data) });
+
+        Ok((invocation, events.boxed()))
+    }
+
+    fn decode_invocation(data: impl Buf) -> anyhow::Result<Invocation> {
+        let invocation =
+            buck2_data::Invocation::decode_length_delimited(data).context("Invalid Invocation")?;
+        Ok(Invocation {
             command_line_args: invocation.command_line_args,
             expanded_command_line_args: invocation.expanded_command_line_args,
             working_dir: invocation.working_dir,
@@ -225,20 +242,74 @@ impl EventLogPathBuf {
                 .transpose()
                 .context("Invalid TraceId")?
                 .unwrap_or_else(TraceId::null),
-        };
+        })
+    }
 
-        let events = stream.and_then(|data| async move {
-            let val = buck2_cli_proto::CommandProgress::decode_length_delimited(data)
-                .context("Invalid CommandProgress")?;
-            match val.progress {
-                Some(command_progress::Progress::Event(event)) => Ok(StreamValue::Event(event)),
-                Some(command_progress::Progress::Result(result)) => Ok(StreamValue::Result(result)),
-                Some(command_progress::Progress::PartialResult(result)) => {
-                    Ok(StreamValue::PartialResult(result))
-                }
-                None => Err(anyhow::anyhow!("Event type not recognized")),
+    fn decode_stream_value(data: impl Buf) -> anyhow::Result<StreamValue> {
+        let val = buck2_cli_proto::CommandProgress::decode_length_delimited(data)
+            .context("Invalid CommandProgress")?;
+        match val.progress {
+            Some(command_progress::Progress::Event(event)) => Ok(StreamValue::Event(event)),
+            Some(command_progress::Progress::Result(result)) => Ok(StreamValue::Result(result)),
+            Some(command_progress::Progress::PartialResult(result)) => {
+                Ok(StreamValue::PartialResult(result))
             }
-        });
+            None => Err(anyhow::anyhow!("Event type not recognized")),
+        }
+    }
+
+    /// Stream the records of one segment of a segmented log.
+    async fn open_segment(
+        path: &AbsPath,
+        segment: &EventLogSegment,
+    ) -> anyhow::Result<BoxStream<'static, anyhow::Result<BytesMut>>> {
+        let mut file = async_fs_util::open(path).await?;
+        file.seek(SeekFrom::Start(segment.offset)).await?;
+        let file = match segment.size {
+            Some(size) => Box::new(file.take(size)) as EventLogReader,
+            None => Box::new(file) as EventLogReader,
+        };
+        Ok(FramedRead::new(ZstdDecoder::new(BufReader::new(file)), ProtobufSplitter).boxed())
+    }
+
+    /// Like `unpack_stream`, but only decompresses the segments of the log whose index matches
+    /// `filter`. Segments without an index (and logs that are not segmented at all) are always
+    /// read, so callers still need to filter the events they get.
+    pub async fn unpack_stream_filtered<'a>(
+        &self,
+        filter: impl Fn(&EventLogSegmentIndex) -> bool + Send + 'a,
+    ) -> anyhow::Result<(Invocation, BoxStream<'a, anyhow::Result<StreamValue>>)> {
+        if self.encoding.mode != LogMode::Protobuf
+            || !matches!(self.encoding.compression, Compression::Zstd)
+        {
+            let (invocation, events) = self.unpack_stream().await?;
+            return Ok((invocation, events.boxed()));
+        }
+
+        let segments = read_segments(async_fs_util::open(&self.path).await?).await?;
+        let mut segments = segments.into_iter();
+
+        // The invocation is the first record of the first segment.
+        let first = segments.next().context("No invocation found")?;
+        let mut first = Self::open_segment(&self.path, &first).await?;
+        let invocation = first.try_next().await?.context("No invocation found")?;
+        let invocation = Self::decode_invocation(invocation)?;
+
+        let path = self.path.clone();
+        let rest = stream::iter(
+            segments
+                .filter(move |segment| segment.index.as_ref().map_or(true, |index| filter(index)))
+                .map(anyhow::Ok),
+        )
+        .and_then(move |segment| {
+            let path = path.clone();
+            async move { Self::open_segment(&path, &segment).await }
+        })
+        .try_flatten();
+
+        let events = first
+            .chain(rest)
+            .and_then(|data| async move { Self::decode_stream_value(data) });
 
         Ok((invocation, events.boxed()))
     }
@@ -301,10 +372,12 @@ impl EventLogPathBuf {
                 GzipDecoder::new(BufReader::new(file)),
                 decompressed_bytes,
             )) as EventLogReader,
-            Compression::Zstd => Box::new(CountingReader::new(
-                ZstdDecoder::new(BufReader::new(file)),
-                decompressed_bytes,
-            )) as EventLogReader,
+            Compression::Zstd => {
+                // Segmented logs are a sequence of zstd frames.
+                let mut decoder = ZstdDecoder::new(BufReader::new(file));
+                decoder.multiple_members(true);
+                Box::new(CountingReader::new(decoder, decompressed_bytes)) as EventLogReader
+            }
         };
 
         Ok(file)
@@ -0,0 +1,413 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Segmented layout of zstd-compressed protobuf event logs.
+//!
+//! The log is a sequence of independent zstd frames, each holding whole records and followed by a
+//! zstd skippable frame carrying an [`EventLogSegmentIndex`]. Regular zstd decoders decompress the
+//! whole file as usual (skippable frames are ignored), while readers that understand the index
+//! can locate frames by walking their headers and only decompress the segments they need.
+
+use std::collections::BTreeSet;
+use std::io::SeekFrom;
+use std::io::Write;
+use std::mem;
+
+use buck2_data::EventLogSegmentIndex;
+use buck2_event_observer::display::display_action_owner;
+use buck2_event_observer::display::display_analysis_target;
+use buck2_event_observer::display::TargetDisplayOptions;
+use gazebo::variants::VariantName;
+use prost::Message;
+use thiserror::Error;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncSeek;
+use tokio::io::AsyncSeekExt;
+
+const ZSTD_MAGIC: u32 = 0xFD2FB528;
+const SKIPPABLE_MAGIC_BASE: u32 = 0x184D2A50;
+const SKIPPABLE_MAGIC_MASK: u32 = 0xFFFFFFF0;
+/// Skippable frame magic used for segment indices.
+const SEGMENT_INDEX_MAGIC: u32 = 0x184D2A5B;
+
+/// Uncompressed size after which a segment is sealed.
+const MAX_SEGMENT_SIZE: u64 = 4 << 20;
+
+#[derive(Error, Debug)]
+enum EventLogSegmentError {
+    #[error("Unknown frame magic `{0:#x}` at offset {1}")]
+    UnknownMagic(u32, u64),
+    #[error("Reserved block type in zstd frame at offset {0}")]
+    ReservedBlockType(u64),
+}
+
+/// Keys under which an event is indexed.
+#[derive(Default, Debug)]
+pub struct EventKeys {
+    pub span_ids: Vec<u64>,
+    pub target: Option<String>,
+    pub action_category: Option<String>,
+    pub kind: Option<String>,
+}
+
+impl EventKeys {
+    pub fn of(event: &buck2_data::BuckEvent) -> Self {
+        use buck2_data::buck_event::Data;
+
+        let mut keys = EventKeys {
+            span_ids: [event.span_id, event.parent_id]
+                .into_iter()
+                .filter(|id| *id != 0)
+                .collect(),
+            ..Default::default()
+        };
+
+        let opts = TargetDisplayOptions::for_console(false);
+        let owner_and_name = |key: &Option<buck2_data::ActionKey>,
+                              name: &Option<buck2_data::ActionName>| {
+            (
+                key.as_ref()
+                    .and_then(|k| k.owner.as_ref())
+                    .and_then(|o| display_action_owner(o, opts).ok()),
+                name.as_ref().map(|n| n.category.clone()),
+            )
+        };
+
+        let inner = match &event.data {
+            Some(Data::SpanStart(start)) => start.data.as_ref().map(|data| {
+                use buck2_data::span_start_event::Data;
+                match data {
+                    Data::ActionExecution(action) => {
+                        (keys.target, keys.action_category) =
+                            owner_and_name(&action.key, &action.name);
+                    }
+                    Data::Analysis(analysis) => {
+                        keys.target = analysis
+                            .target
+                            .as_ref()
+                            .and_then(|t| display_analysis_target(t, opts).ok());
+                    }
+                    _ => {}
+                }
+                data.variant_name()
+            }),
+            Some(Data::SpanEnd(end)) => end.data.as_ref().map(|data| {
+                if let buck2_data::span_end_event::Data::ActionExecution(action) = data {
+                    (keys.target, keys.action_category) = owner_and_name(&action.key, &action.name);
+                }
+                data.variant_name()
+            }),
+            Some(Data::Instant(instant)) => instant.data.as_ref().map(|data| data.variant_name()),
+            Some(Data::Record(record)) => record.data.as_ref().map(|data| data.variant_name()),
+            None => None,
+        };
+
+        if let Some(outer) = &event.data {
+            keys.kind = Some(match inner {
+                Some(inner) => format!("{}.{}", outer.variant_name(), inner),
+                None => outer.variant_name().to_owned(),
+            });
+        }
+
+        keys
+    }
+}
+
+#[derive(Default)]
+struct SegmentIndexBuilder {
+    records: u64,
+    uncompressed_size: u64,
+    span_ids: BTreeSet<u64>,
+    targets: BTreeSet<String>,
+    action_categories: BTreeSet<String>,
+    kinds: BTreeSet<String>,
+}
+
+impl SegmentIndexBuilder {
+    fn add(&mut self, size: usize, event: Option<&buck2_data::BuckEvent>) {
+        self.records += 1;
+        self.uncompressed_size += size as u64;
+        if let Some(event) = event {
+            let keys = EventKeys::of(event);
+            self.span_ids.extend(keys.span_ids);
+            self.targets.extend(keys.target);
+            self.action_categories.extend(keys.action_category);
+            self.kinds.extend(keys.kind);
+        }
+    }
+
+    fn build(self) -> EventLogSegmentIndex {
+        EventLogSegmentIndex {
+            records: self.records,
+            uncompressed_size: self.uncompressed_size,
+            span_ids: self.span_ids.into_iter().collect(),
+            targets: self.targets.into_iter().collect(),
+            action_categories: self.action_categories.into_iter().collect(),
+            kinds: self.kinds.into_iter().collect(),
+        }
+    }
+}
+
+/// Compresses records into indexed segments. Compressed bytes accumulate in memory and are
+/// handed out by [`SegmentEncoder::take_output`].
+pub(crate) struct SegmentEncoder {
+    encoder: zstd::stream::write::Encoder<'static, Vec<u8>>,
+    index: SegmentIndexBuilder,
+    /// Sealed segments not yet taken.
+    output: Vec<u8>,
+}
+
+impl SegmentEncoder {
+    pub(crate) fn new() -> anyhow::Result<Self> {
+        Ok(Self {
+            encoder: Self::new_frame()?,
+            index: SegmentIndexBuilder::default(),
+            output: Vec::new(),
+        })
+    }
+
+    fn new_frame() -> anyhow::Result<zstd::stream::write::Encoder<'static, Vec<u8>>> {
+        Ok(zstd::stream::write::Encoder::new(
+            Vec::new(),
+            zstd::DEFAULT_COMPRESSION_LEVEL,
+        )?)
+    }
+
+    /// Append one serialized record, sealing the current segment if it grew large enough.
+    pub(crate) fn write_record(
+        &mut self,
+        record: &[u8],
+        event: Option<&buck2_data::BuckEvent>,
+    ) -> anyhow::Result<()> {
+        self.encoder.write_all(record)?;
+        self.index.add(record.len(), event);
+        if self.index.uncompressed_size >= MAX_SEGMENT_SIZE {
+            self.seal()?;
+        }
+        Ok(())
+    }
+
+    /// Make everything written so far decodable, without ending the segment.
+    pub(crate) fn flush(&mut self) -> anyhow::Result<()> {
+        self.encoder.flush()?;
+        Ok(())
+    }
+
+    /// End the current segment and write its index.
+    pub(crate) fn seal(&mut self) -> anyhow::Result<()> {
+        if self.index.records == 0 {
+            return Ok(());
+        }
+
+        let encoder = mem::replace(&mut self.encoder, Self::new_frame()?);
+        self.output.append(&mut encoder.finish()?);
+
+        let index = mem::take(&mut self.index).build().encode_to_vec();
+        self.output
+            .extend_from_slice(&SEGMENT_INDEX_MAGIC.to_le_bytes());
+        self.output
+            .extend_from_slice(&u32::try_from(index.len())?.to_le_bytes());
+        self.output.extend_from_slice(&index);
+        Ok(())
+    }
+
+    /// Compressed bytes produced since the last call.
+    pub(crate) fn take_output(&mut self) -> Vec<u8> {
+        let pending = mem::take(self.encoder.get_mut());
+        if self.output.is_empty() {
+            pending
+        } else {
+            let mut output = mem::take(&mut self.output);
+            output.extend_from_slice(&pending);
+            output
+        }
+    }
+}
+
+/// A segment of an event log, as found by [`read_segments`].
+#[derive(Debug)]
+pub struct EventLogSegment {
+    /// Offset of the segment's zstd frame in the file.
+    pub offset: u64,
+    /// Compressed size of the frame, or `None` if it is not complete yet.
+    pub size: Option<u64>,
+    /// Index of the segment, absent if the segment was not sealed yet, or if the log was not
+    /// written in segments.
+    pub index: Option<EventLogSegmentIndex>,
+}
+
+async fn read_u32_or_eof<R: AsyncRead + Unpin>(file: &mut R) -> anyhow::Result<Option<u32>> {
+    let mut buf = [0; 4];
+    let mut read = 0;
+    while read < buf.len() {
+        let n = file.read(&mut buf[read..]).await?;
+        if n == 0 {
+            return Ok(None);
+        }
+        read += n;
+    }
+    Ok(Some(u32::from_le_bytes(buf)))
+}
+
+/// Size of the zstd frame whose magic was just read, not counting the magic. `None` if the file
+/// ends before the frame does.
+async fn zstd_frame_size<R: AsyncRead + AsyncSeek + Unpin>(
+    file: &mut R,
+    offset: u64,
+) -> anyhow::Result<Option<u64>> {
+    let mut descriptor = [0; 1];
+    if file.read(&mut descriptor).await? == 0 {
+        return Ok(None);
+    }
+    let descriptor = descriptor[0];
+    let fcs_flag = descriptor >> 6;
+    let single_segment = descriptor & 0x20 != 0;
+    let checksum = descriptor & 0x04 != 0;
+    let dict_id_size = [0, 1, 2, 4][(descriptor & 0x03) as usize];
+    let fcs_size = match fcs_flag {
+        0 if single_segment => 1,
+        0 => 0,
+        1 => 2,
+        2 => 4,
+        _ => 8,
+    };
+    let window_size = if single_segment { 0 } else { 1 };
+    let header = window_size + dict_id_size + fcs_size;
+    let mut size = 1 + header;
+    file.seek(SeekFrom::Current(header as i64)).await?;
+
+    loop {
+        let mut block_header = [0; 3];
+        if file.read_exact(&mut block_header).await.is_err() {
+            return Ok(None);
+        }
+        let block_header =
+            u32::from_le_bytes([block_header[0], block_header[1], block_header[2], 0]);
+        let last = block_header & 1 != 0;
+        let block_size = match (block_header >> 1) & 0x03 {
+            1 => 1,
+            3 => return Err(EventLogSegmentError::ReservedBlockType(offset).into()),
+            _ => (block_header >> 3) as u64,
+        };
+        file.seek(SeekFrom::Current(block_size as i64)).await?;
+        size += 3 + block_size;
+        if last {
+            break;
+        }
+    }
+
+    if checksum {
+        file.seek(SeekFrom::Current(4)).await?;
+        size += 4;
+    }
+
+    Ok(Some(size))
+}
+
+/// List the segments of a zstd-compressed event log, without decompressing them.
+pub async fn read_segments<R: AsyncRead + AsyncSeek + Unpin>(
+    mut file: R,
+) -> anyhow::Result<Vec<EventLogSegment>> {
+    let file_size = file.seek(SeekFrom::End(0)).await?;
+    file.seek(SeekFrom::Start(0)).await?;
+
+    let mut segments: Vec<EventLogSegment> = Vec::new();
+    let mut offset = 0;
+    while let Some(magic) = read_u32_or_eof(&mut file).await? {
+        if magic == ZSTD_MAGIC {
+            let size = zstd_frame_size(&mut file, offset)
+                .await?
+                .map(|size| 4 + size)
+                .filter(|size| offset + size <= file_size);
+            segments.push(EventLogSegment {
+                offset,
+                size,
+                index: None,
+            });
+            match size {
+                Some(size) => offset += size,
+                None => break,
+            }
+        } else if magic & SKIPPABLE_MAGIC_MASK == SKIPPABLE_MAGIC_BASE {
+            let Some(len) = read_u32_or_eof(&mut file).await? else {
+                break;
+            };
+            if offset + 8 + len as u64 > file_size {
+                break;
+            }
+            if magic == SEGMENT_INDEX_MAGIC {
+                let mut buf = vec![0; len as usize];
+                file.read_exact(&mut buf).await?;
+                if let Some(segment) = segments.last_mut() {
+                    segment.index = Some(EventLogSegmentIndex::decode(buf.as_slice())?);
+                }
+            } else {
+                file.seek(SeekFrom::Current(len as i64)).await?;
+            }
+            offset += 8 + len as u64;
+        } else {
+            return Err(EventLogSegmentError::UnknownMagic(magic, offset).into());
+        }
+    }
+
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::io::Read;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_segments_round_trip() -> anyhow::Result<()> {
+        let mut encoder = SegmentEncoder::new()?;
+        let mut file = Vec::new();
+
+        encoder.write_record(b"first", None)?;
+        encoder.seal()?;
+        file.extend(encoder.take_output());
+        encoder.write_record(b"second", None)?;
+        encoder.write_record(b"third", None)?;
+        encoder.flush()?;
+        file.extend(encoder.take_output());
+
+        let segments = read_segments(Cursor::new(&file)).await?;
+        assert_eq!(2, segments.len());
+        assert_eq!(0, segments[0].offset);
+        let index = segments[0].index.as_ref().unwrap();
+        assert_eq!(1, index.records);
+        assert_eq!(5, index.uncompressed_size);
+        // The second segment is only flushed, so it has no end and no index yet.
+        assert_eq!(None, segments[1].size);
+        assert!(segments[1].index.is_none());
+
+        encoder.seal()?;
+        file.extend(encoder.take_output());
+        let segments = read_segments(Cursor::new(&file)).await?;
+        assert_eq!(2, segments.len());
+        assert_eq!(2, segments[1].index.as_ref().unwrap().records);
+        assert_eq!(
+            file.len() as u64,
+            segments[1].offset + segments[1].size.unwrap() + 8 + {
+                let index = segments[1].index.as_ref().unwrap();
+                index.encoded_len() as u64
+            }
+        );
+
+        // A regular decoder reads the whole log.
+        let mut decoded = String::new();
+        zstd::stream::read::Decoder::new(file.as_slice())?.read_to_string(&mut decoded)?;
+        assert_eq!("firstsecondthird", decoded);
+
+        Ok(())
+    }
+}
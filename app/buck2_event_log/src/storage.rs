@@ -0,0 +1,277 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Backends event logs can be shared through, configured in the `buck2_log_storage` section.
+
+use std::process::ExitStatus;
+use std::process::Stdio;
+use std::sync::Arc;
+
+use anyhow::Context as _;
+use async_trait::async_trait;
+use buck2_common::init::LogStorageBackend;
+use buck2_common::init::LogStorageConfig;
+use buck2_core::fs::async_fs_util;
+use buck2_core::fs::paths::abs_path::AbsPath;
+use buck2_core::fs::paths::abs_path::AbsPathBuf;
+use buck2_http::HttpClientBuilder;
+use buck2_util::process::async_background_command;
+use thiserror::Error;
+
+use crate::read::EventLogPathBuf;
+
+#[derive(Error, Debug)]
+enum LogStorageError {
+    #[error("`buck2_log_storage.{0}` must be set to use the `{1}` log storage backend")]
+    MissingProperty(&'static str, &'static str),
+    #[error("`buck2_log_storage.dir` must be an absolute path, got `{0}`")]
+    RelativeDir(String),
+    #[error("`buck2_log_storage.command` is empty")]
+    EmptyCommand,
+    #[error("Log storage command `{0}` failed with {1}; stderr:\n{2}")]
+    CommandFailed(String, ExitStatus, String),
+    #[error("No log storage is configured, set `buck2_log_storage.backend` in your buckconfig")]
+    NotConfigured,
+}
+
+/// A place event logs are uploaded to, and downloaded from, to share them between machines.
+#[async_trait]
+pub trait EventLogStorage: Send + Sync {
+    /// Store the log at `path` under `name`, and return a description of where it went.
+    async fn upload(&self, path: &AbsPath, name: &str) -> anyhow::Result<String>;
+
+    /// Fetch the log stored under `name` into `path`.
+    async fn download(&self, name: &str, path: &AbsPath) -> anyhow::Result<()>;
+}
+
+/// Name a log is stored under: its trace id followed by its extension.
+pub fn event_log_storage_name(log: &EventLogPathBuf) -> anyhow::Result<String> {
+    Ok(format!("{}{}", log.uuid_from_filename()?, log.extension()))
+}
+
+/// The configured log storage, if any.
+pub fn event_log_storage(
+    config: &LogStorageConfig,
+) -> anyhow::Result<Option<Arc<dyn EventLogStorage>>> {
+    fn required<'a>(
+        value: &'a Option<String>,
+        property: &'static str,
+        backend: &'static str,
+    ) -> anyhow::Result<&'a str> {
+        Ok(value
+            .as_deref()
+            .ok_or(LogStorageError::MissingProperty(property, backend))?)
+    }
+
+    let storage: Arc<dyn EventLogStorage> = match config.backend {
+        None => return Ok(None),
+        Some(LogStorageBackend::Dir) => {
+            let dir = required(&config.dir, "dir", "dir")?;
+            Arc::new(DirLogStorage {
+                dir: AbsPathBuf::new(dir)
+                    .map_err(|_| LogStorageError::RelativeDir(dir.to_owned()))?,
+            })
+        }
+        Some(LogStorageBackend::Http) => Arc::new(HttpLogStorage {
+            url: required(&config.url, "url", "http")?
+                .trim_end_matches('/')
+                .to_owned(),
+        }),
+        Some(LogStorageBackend::Command) => {
+            let command = required(&config.command, "command", "command")?;
+            let argv = shlex::split(command)
+                .with_context(|| format!("Invalid `buck2_log_storage.command`: `{}`", command))?;
+            if argv.is_empty() {
+                return Err(LogStorageError::EmptyCommand.into());
+            }
+            Arc::new(CommandLogStorage { argv })
+        }
+    };
+    Ok(Some(storage))
+}
+
+/// Like `event_log_storage`, but fails if no storage is configured.
+pub fn require_event_log_storage(
+    config: &LogStorageConfig,
+) -> anyhow::Result<Arc<dyn EventLogStorage>> {
+    Ok(event_log_storage(config)?.ok_or(LogStorageError::NotConfigured)?)
+}
+
+struct DirLogStorage {
+    dir: AbsPathBuf,
+}
+
+#[async_trait]
+impl EventLogStorage for DirLogStorage {
+    async fn upload(&self, path: &AbsPath, name: &str) -> anyhow::Result<String> {
+        let dest = self.dir.join(name);
+        // Names may contain slashes, e.g. those of test artifacts.
+        if let Some(parent) = dest.parent() {
+            async_fs_util::create_dir_all(parent).await?;
+        }
+        async_fs_util::copy(path, &dest).await?;
+        Ok(dest.to_string())
+    }
+
+    async fn download(&self, name: &str, path: &AbsPath) -> anyhow::Result<()> {
+        let src = self.dir.join(name);
+        async_fs_util::copy(&src, path).await?;
+        Ok(())
+    }
+}
+
+struct HttpLogStorage {
+    /// URL prefix, without trailing slash.
+    url: String,
+}
+
+#[async_trait]
+impl EventLogStorage for HttpLogStorage {
+    async fn upload(&self, path: &AbsPath, name: &str) -> anyhow::Result<String> {
+        let url = format!("{}/{}", self.url, name);
+        let body = async_fs_util::read(path).await?;
+        HttpClientBuilder::oss()?
+            .build()
+            .put(
+                &url,
+                body.into(),
+                vec![(
+                    "Content-Type".to_owned(),
+                    "application/octet-stream".to_owned(),
+                )],
+            )
+            .await?;
+        Ok(url)
+    }
+
+    async fn download(&self, name: &str, path: &AbsPath) -> anyhow::Result<()> {
+        let url = format!("{}/{}", self.url, name);
+        let client = HttpClientBuilder::oss()?.build();
+        let response = client.get(&url).await?;
+        let body = buck2_http::to_bytes(response.into_body()).await?;
+        async_fs_util::write(path, body).await
+    }
+}
+
+struct CommandLogStorage {
+    argv: Vec<String>,
+}
+
+impl CommandLogStorage {
+    async fn run(&self, args: &[&str]) -> anyhow::Result<String> {
+        let output = async_background_command(&self.argv[0])
+            .args(&self.argv[1..])
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .await
+            .with_context(|| format!("Error spawning log storage command `{}`", self.argv[0]))?;
+        if !output.status.success() {
+            return Err(LogStorageError::CommandFailed(
+                self.argv.join(" "),
+                output.status,
+                String::from_utf8_lossy(&output.stderr).into_owned(),
+            )
+            .into());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+    }
+}
+
+#[async_trait]
+impl EventLogStorage for CommandLogStorage {
+    async fn upload(&self, path: &AbsPath, name: &str) -> anyhow::Result<String> {
+        let path = path.to_str()?;
+        let location = self.run(&["upload", path, name]).await?;
+        // The command may print where the log went; otherwise the name identifies it.
+        Ok(if location.is_empty() {
+            name.to_owned()
+        } else {
+            location
+        })
+    }
+
+    async fn download(&self, name: &str, path: &AbsPath) -> anyhow::Result<()> {
+        let path = path.to_str()?;
+        self.run(&["download", name, path]).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_dir_storage_round_trip() -> anyhow::Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let root = AbsPathBuf::new(tmp.path().to_path_buf())?;
+        let storage = event_log_storage(&LogStorageConfig {
+            backend: Some(LogStorageBackend::Dir),
+            dir: Some(root.join("shared").to_string()),
+            ..Default::default()
+        })?
+        .unwrap();
+
+        let log = root.join("log.pb.zst");
+        async_fs_util::write(&log, b"contents").await?;
+        let location = storage.upload(&log, "trace.pb.zst").await?;
+        assert_eq!(
+            root.join("shared").join("trace.pb.zst").to_string(),
+            location
+        );
+
+        let downloaded = root.join("downloaded.pb.zst");
+        storage.download("trace.pb.zst", &downloaded).await?;
+        assert_eq!(
+            "contents",
+            async_fs_util::read_to_string(&downloaded).await?
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_dir_storage_for_test_artifacts() -> anyhow::Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let root = AbsPathBuf::new(tmp.path().to_path_buf())?;
+        let config = LogStorageConfig {
+            backend: Some(LogStorageBackend::Dir),
+            dir: Some(root.join("shared").to_string()),
+            ..Default::default()
+        };
+        let storage = require_event_log_storage(&config.for_test_artifacts())?;
+
+        let artifact = root.join("screenshot.png");
+        async_fs_util::write(&artifact, b"contents").await?;
+        let location = storage
+            .upload(&artifact, "trace/test/screenshot.png")
+            .await?;
+        assert_eq!(
+            root.join("shared")
+                .join("test_artifacts")
+                .join("trace/test/screenshot.png")
+                .to_string(),
+            location
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_property() {
+        let err = event_log_storage(&LogStorageConfig {
+            backend: Some(LogStorageBackend::Http),
+            ..Default::default()
+        })
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("buck2_log_storage.url"), "{}", err);
+    }
+}
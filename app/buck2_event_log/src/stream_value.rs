@@ -10,10 +10,11 @@
 use allocative::Allocative;
 use buck2_cli_proto::CommandResult;
 use buck2_cli_proto::PartialResult;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 
-#[derive(Allocative, Deserialize, Serialize)]
+#[derive(Allocative, Deserialize, Serialize, JsonSchema)]
 #[allow(clippy::large_enum_variant)]
 pub enum StreamValue {
     Result(Box<CommandResult>),
@@ -12,6 +12,7 @@ use buck2_core::fs::paths::abs_path::AbsPathBuf;
 use buck2_wrapper_common::invocation_id::TraceId;
 use dupe::Dupe;
 use itertools::Itertools;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 use thiserror::Error;
@@ -129,7 +130,7 @@ pub(crate) enum Compression {
     Zstd,
 }
 
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Eq, PartialEq)]
 pub struct Invocation {
     pub command_line_args: Vec<String>,
     /// Command line args with expanded `@` args.
@@ -139,6 +140,7 @@ pub struct Invocation {
     /// and `AbsPathBuf` is not.
     pub working_dir: String,
     #[serde(default = "TraceId::null")]
+    #[schemars(with = "String")]
     pub trace_id: TraceId,
 }
 
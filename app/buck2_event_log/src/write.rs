@@ -20,6 +20,7 @@ use buck2_core::fs::paths::abs_path::AbsPathBuf;
 use buck2_core::fs::working_dir::WorkingDir;
 use buck2_events::BuckEvent;
 use buck2_wrapper_common::invocation_id::TraceId;
+use dupe::Dupe;
 use futures::future::Future;
 use prost::Message;
 use serde::Serialize;
@@ -30,6 +31,8 @@ use crate::file_names::remove_old_logs;
 use crate::read::EventLogPathBuf;
 use crate::should_block_on_log_upload;
 use crate::should_upload_log;
+use crate::storage::event_log_storage_name;
+use crate::storage::EventLogStorage;
 use crate::user_event_types::try_get_user_event;
 use crate::utils::Encoding;
 use crate::utils::EventLogErrors;
@@ -62,6 +65,11 @@ pub struct WriteEventLog {
     buf: Vec<u8>,
     log_size_counter_bytes: Option<Arc<AtomicU64>>,
     allow_vpnless: bool,
+    /// Where to upload the log if the command fails.
+    upload_on_failure: Option<Arc<dyn EventLogStorage>>,
+    /// The main log, once opened.
+    log_path: Option<EventLogPathBuf>,
+    command_failed: bool,
 }
 
 impl WriteEventLog {
@@ -74,6 +82,7 @@ impl WriteEventLog {
         command_name: String,
         log_size_counter_bytes: Option<Arc<AtomicU64>>,
         allow_vpnless: bool,
+        upload_on_failure: Option<Arc<dyn EventLogStorage>>,
     ) -> anyhow::Result<Self> {
         Ok(Self {
             state: LogWriterState::Unopened {
@@ -87,6 +96,9 @@ impl WriteEventLog {
             buf: Vec::new(),
             log_size_counter_bytes,
             allow_vpnless,
+            upload_on_failure,
+            log_path: None,
+            command_failed: false,
         })
     }
 
@@ -162,12 +174,15 @@ impl WriteEventLog {
             encoding,
         };
         let writer = start_persist_event_log_subprocess(
-            path,
+            path.clone(),
             event.trace_id()?.clone(),
             self.log_size_counter_bytes.clone(),
             self.allow_vpnless,
+            // The log must be complete on disk before we can upload it.
+            self.upload_on_failure.is_some(),
         )
         .await?;
+        self.log_path = Some(path);
         let mut writers = vec![writer];
 
         // Also open the user's log file, if any as provided, with no encoding.
@@ -211,6 +226,12 @@ impl WriteEventLog {
     pub fn exit(&mut self) -> impl Future<Output = ()> + 'static + Send + Sync {
         // Shut down writers, flush all our files before exiting.
         let state = std::mem::replace(&mut self.state, LogWriterState::Closed);
+        let upload = match (&self.upload_on_failure, &self.log_path) {
+            (Some(storage), Some(log_path)) if self.command_failed => {
+                Some((storage.dupe(), log_path.clone()))
+            }
+            _ => None,
+        };
 
         async move {
             let mut writers = match state {
@@ -235,6 +256,22 @@ impl WriteEventLog {
                 .map(|proc| wait_for_child_and_log(proc, "Event Log"));
 
             futures::future::join_all(futs).await;
+
+            if let Some((storage, log_path)) = upload {
+                // Spawned so that the returned future stays `Sync`.
+                let uploaded = tokio::spawn(async move {
+                    let name = event_log_storage_name(&log_path)?;
+                    storage.upload(&log_path.path, &name).await
+                })
+                .await;
+                match uploaded {
+                    Ok(Ok(location)) => {
+                        tracing::warn!("Command failed, event log uploaded to `{}`", location)
+                    }
+                    Ok(Err(e)) => tracing::warn!("Error uploading event log: {:#}", e),
+                    Err(e) => tracing::warn!("Error uploading event log: {:#}", e),
+                }
+            }
         }
     }
 }
@@ -244,6 +281,7 @@ async fn start_persist_event_log_subprocess(
     trace_id: TraceId,
     bytes_written: Option<Arc<AtomicU64>>,
     allow_vpnless: bool,
+    wait_for_exit: bool,
 ) -> anyhow::Result<NamedEventLogWriter> {
     let current_exe = std::env::current_exe().context("No current_exe")?;
     let mut command = buck2_util::process::async_background_command(current_exe);
@@ -268,7 +306,7 @@ async fn start_persist_event_log_subprocess(
     }
     command.stdout(Stdio::null()).stdin(Stdio::piped());
 
-    let block = should_block_on_log_upload()?;
+    let block = wait_for_exit || should_block_on_log_upload()?;
     if block {
         command.stderr(Stdio::piped());
     } else {
@@ -290,13 +328,13 @@ async fn start_persist_event_log_subprocess(
         None
     };
 
-    Ok(NamedEventLogWriter::new(
+    NamedEventLogWriter::new(
         path,
         pipe,
         bytes_written,
         EventLogType::System,
         process_to_wait_for,
-    ))
+    )
 }
 
 async fn open_event_log_for_writing(
@@ -316,13 +354,7 @@ async fn open_event_log_for_writing(
             )
         })?;
 
-    Ok(NamedEventLogWriter::new(
-        path,
-        file,
-        bytes_written,
-        event_log_type,
-        None,
-    ))
+    NamedEventLogWriter::new(path, file, bytes_written, event_log_type, None)
 }
 
 impl WriteEventLog {
@@ -361,6 +393,10 @@ impl WriteEventLog {
             }
         }
 
+        if command_failed(result) {
+            self.command_failed = true;
+        }
+
         let event = StreamValueForWrite::Result(result);
 
         self.write_ln(&[event]).await
@@ -403,6 +439,20 @@ impl SerializeForLog for Invocation {
     }
 }
 
+/// Whether the command failed: it returned an error, or a response reporting errors, e.g. the
+/// targets which failed to build with `--keep-going`, or a non-zero exit code, e.g. failing tests.
+fn command_failed(result: &CommandResult) -> bool {
+    match &result.result {
+        Some(command_result::Result::Error(_)) => true,
+        Some(command_result::Result::BuildResponse(response)) => !response.errors.is_empty(),
+        Some(command_result::Result::BxlResponse(response)) => !response.errors.is_empty(),
+        Some(command_result::Result::TestResponse(response)) => {
+            !response.errors.is_empty() || response.exit_code.map_or(false, |code| code != 0)
+        }
+        _ => false,
+    }
+}
+
 #[derive(Serialize)]
 pub enum StreamValueForWrite<'a> {
     Result(&'a CommandResult),
@@ -441,6 +491,13 @@ impl<'a> SerializeForLog for StreamValueForWrite<'a> {
 
         Ok(false)
     }
+
+    fn buck_event(&self) -> Option<&buck2_data::BuckEvent> {
+        match self {
+            StreamValueForWrite::Event(event) => Some(event),
+            StreamValueForWrite::Result(_) => None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -474,6 +531,9 @@ mod tests {
                 buf: Vec::new(),
                 log_size_counter_bytes: None,
                 allow_vpnless: false,
+                upload_on_failure: None,
+                log_path: None,
+                command_failed: false,
             })
         }
     }
@@ -634,4 +694,38 @@ mod tests {
         .encode_length_delimited_to_vec();
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn test_command_failed() {
+        fn result(result: command_result::Result) -> CommandResult {
+            CommandResult {
+                result: Some(result),
+            }
+        }
+
+        assert!(!command_failed(&result(
+            command_result::Result::BuildResponse(Default::default())
+        )));
+        assert!(command_failed(&result(
+            command_result::Result::BuildResponse(buck2_cli_proto::BuildResponse {
+                errors: vec![Default::default()],
+                ..Default::default()
+            })
+        )));
+        assert!(!command_failed(&result(
+            command_result::Result::TestResponse(buck2_cli_proto::TestResponse {
+                exit_code: Some(0),
+                ..Default::default()
+            })
+        )));
+        assert!(command_failed(&result(
+            command_result::Result::TestResponse(buck2_cli_proto::TestResponse {
+                exit_code: Some(32),
+                ..Default::default()
+            })
+        )));
+        assert!(command_failed(&result(command_result::Result::Error(
+            Default::default()
+        ))));
+    }
 }
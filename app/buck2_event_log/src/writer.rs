@@ -23,6 +23,7 @@ use tokio::io::AsyncWrite;
 use tokio::io::AsyncWriteExt;
 
 use crate::read::EventLogPathBuf;
+use crate::segment::SegmentEncoder;
 use crate::utils::Compression;
 use crate::utils::LogMode;
 use crate::FutureChildOutput;
@@ -91,6 +92,8 @@ pub(crate) struct NamedEventLogWriter {
     /// If this writing is done by a subprocess, that process's output, assuming we intend to wait
     /// for it to exit.
     process_to_wait_for: Option<FutureChildOutput>,
+    /// Set when records are compressed into indexed segments rather than by `file`.
+    segment: Option<SegmentEncoder>,
 }
 
 impl NamedEventLogWriter {
@@ -100,7 +103,20 @@ impl NamedEventLogWriter {
         bytes_written: Option<Arc<AtomicU64>>,
         event_log_type: EventLogType,
         process_to_wait_for: Option<FutureChildOutput>,
-    ) -> Self {
+    ) -> anyhow::Result<Self> {
+        let segmented = event_log_type == EventLogType::System
+            && path.encoding.mode == LogMode::Protobuf
+            && matches!(path.encoding.compression, Compression::Zstd);
+        if segmented {
+            return Ok(Self {
+                path,
+                file: Box::new(CountingReader::new(file, bytes_written)),
+                event_log_type,
+                process_to_wait_for,
+                segment: Some(SegmentEncoder::new()?),
+            });
+        }
+
         let file = match path.encoding.compression {
             Compression::None => {
                 Box::new(CountingReader::new(file, bytes_written)) as EventLogWriter
@@ -114,15 +130,21 @@ impl NamedEventLogWriter {
                 async_compression::Level::Default,
             )) as EventLogWriter,
         };
-        Self {
+        Ok(Self {
             path,
             file,
             event_log_type,
             process_to_wait_for,
-        }
+            segment: None,
+        })
     }
 
     pub(crate) async fn flush(&mut self) -> anyhow::Result<()> {
+        if let Some(segment) = &mut self.segment {
+            segment.flush()?;
+            let output = segment.take_output();
+            self.write_all(&output).await?;
+        }
         match self.file.flush().await {
             Ok(_) => Ok(()),
             Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {
@@ -138,11 +160,23 @@ impl NamedEventLogWriter {
     }
 
     pub(crate) async fn shutdown(&mut self) {
+        if let Err(e) = self.seal_segment().await {
+            tracing::warn!("Failed to seal log file at `{}`: {:#}", self.path.path, e);
+        }
         if let Err(e) = self.file.shutdown().await {
             tracing::warn!("Failed to flush log file at `{}`: {:#}", self.path.path, e);
         }
     }
 
+    async fn seal_segment(&mut self) -> anyhow::Result<()> {
+        if let Some(segment) = &mut self.segment {
+            segment.seal()?;
+            let output = segment.take_output();
+            self.write_all(&output).await?;
+        }
+        Ok(())
+    }
+
     pub(crate) fn child(mut self) -> Option<FutureChildOutput> {
         self.process_to_wait_for.take()
     }
@@ -192,9 +226,19 @@ impl NamedEventLogWriter {
         I: IntoIterator<Item = &'b T> + Clone + 'b,
     {
         for event in events.clone() {
+            let start = buf.len();
             self.serialize_event(&mut buf, event)?;
+            if let Some(segment) = &mut self.segment {
+                segment.write_record(&buf[start..], event.buck_event())?;
+            }
+        }
+        match &mut self.segment {
+            Some(segment) => {
+                let output = segment.take_output();
+                self.write_all(&output).await?;
+            }
+            None => self.write_all(&buf).await?,
         }
-        self.write_all(&buf).await?;
         Ok(())
     }
 }
@@ -203,4 +247,9 @@ pub(crate) trait SerializeForLog {
     fn serialize_to_json(&self, buf: &mut Vec<u8>) -> anyhow::Result<()>;
     fn serialize_to_protobuf_length_delimited(&self, buf: &mut Vec<u8>) -> anyhow::Result<()>;
     fn maybe_serialize_user_event(&self, buf: &mut Vec<u8>) -> anyhow::Result<bool>;
+
+    /// The event being logged, used to index segmented logs.
+    fn buck_event(&self) -> Option<&buck2_data::BuckEvent> {
+        None
+    }
 }
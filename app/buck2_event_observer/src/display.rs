@@ -9,6 +9,7 @@
 
 // TODO(brasselsprouts): move this onto the original core types and convert in events
 
+use std::collections::BTreeSet;
 use std::fmt;
 use std::fmt::Write;
 use std::sync::Arc;
@@ -458,6 +459,52 @@ pub fn display_file_watcher_end(file_watcher_end: &buck2_data::FileWatcherEnd) -
     res
 }
 
+/// What changed in the configuration since the last command, to explain why targets are evaluated
+/// or analysed again. Files changes are reported by [`display_file_watcher_end`].
+pub fn display_buck_configs(buck_configs: &buck2_data::BuckConfigs) -> Vec<String> {
+    const MAX_PRINT_SECTIONS: usize = 5;
+    let mut res = Vec::new();
+
+    if let Some(count) = buck_configs.config_diff_count.filter(|count| *count > 0) {
+        let sections: BTreeSet<&str> = buck_configs
+            .cell_diff
+            .values()
+            .flat_map(|cell| cell.section_diff.keys().map(|s| s.as_str()))
+            .collect();
+        let mut msg = format!(
+            "Buckconfig changed: {} value{}",
+            count,
+            if count == 1 { "" } else { "s" }
+        );
+        if !sections.is_empty() {
+            msg.push_str(" in ");
+            let mut comma = commas();
+            for section in sections.iter().take(MAX_PRINT_SECTIONS) {
+                comma(&mut msg).unwrap();
+                write!(&mut msg, "[{}]", section).unwrap();
+            }
+            if sections.len() > MAX_PRINT_SECTIONS {
+                write!(
+                    &mut msg,
+                    " and {} more sections",
+                    sections.len() - MAX_PRINT_SECTIONS
+                )
+                .unwrap();
+            }
+        }
+        res.push(msg);
+    }
+
+    if !buck_configs.changed_cells.is_empty() {
+        res.push(format!(
+            "Cells changed: {}",
+            buck_configs.changed_cells.join(", ")
+        ));
+    }
+
+    res
+}
+
 pub fn display_executor_stage(
     stage: &buck2_data::executor_stage_start::Stage,
 ) -> Option<&'static str> {
@@ -874,8 +921,49 @@ pub fn sanitize_output_colors(stderr: &[u8]) -> String {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
     use super::*;
 
+    #[test]
+    fn test_display_buck_configs() {
+        let section = |name: &str| (name.to_owned(), buck2_data::SectionConfigDiff::default());
+        let buck_configs = buck2_data::BuckConfigs {
+            new_configs_used: true,
+            config_diff_count: Some(3),
+            config_diff_size: Some(30),
+            cell_diff: HashMap::from([
+                (
+                    "root".to_owned(),
+                    buck2_data::CellConfigDiff {
+                        section_diff: HashMap::from([section("cxx"), section("build")]),
+                    },
+                ),
+                (
+                    "prelude".to_owned(),
+                    buck2_data::CellConfigDiff {
+                        section_diff: HashMap::from([section("build")]),
+                    },
+                ),
+            ]),
+            changed_cells: vec!["third_party".to_owned()],
+        };
+        assert_eq!(
+            vec![
+                "Buckconfig changed: 3 values in [build], [cxx]",
+                "Cells changed: third_party",
+            ],
+            display_buck_configs(&buck_configs)
+        );
+
+        let unchanged = buck2_data::BuckConfigs {
+            new_configs_used: false,
+            config_diff_count: Some(0),
+            ..Default::default()
+        };
+        assert!(display_buck_configs(&unchanged).is_empty());
+    }
+
     #[test]
     fn removes_color_characters() {
         let message = "\x1b[0mFoo\t\x1b[34mBar\n\x1b[DBaz\r\nQuz";
@@ -19,6 +19,7 @@ use crate::debug_events::DebugEventsState;
 use crate::dice_state::DiceState;
 use crate::progress::BuildProgressStateTracker;
 use crate::re_state::ReState;
+use crate::resource_summary::ResourceSummary;
 use crate::session_info::SessionInfo;
 use crate::span_tracker::BuckEventSpanTracker;
 use crate::starlark_debug::StarlarkDebuggerState;
@@ -30,6 +31,7 @@ pub struct EventObserver<E> {
     pub action_stats: ActionStats,
     re_state: ReState,
     two_snapshots: TwoSnapshots, // NOTE: We got many more copies of this than we should.
+    resource_summary: ResourceSummary,
     system_info: buck2_data::SystemInfo,
     session_info: SessionInfo,
     test_state: TestState,
@@ -49,6 +51,7 @@ where
             action_stats: ActionStats::default(),
             re_state: ReState::new(),
             two_snapshots: TwoSnapshots::default(),
+            resource_summary: ResourceSummary::default(),
             system_info: buck2_data::SystemInfo::default(),
             session_info: SessionInfo {
                 trace_id,
@@ -63,6 +66,7 @@ where
 
     pub fn observe(&mut self, receive_time: Instant, event: &Arc<BuckEvent>) -> anyhow::Result<()> {
         self.span_tracker.handle_event(receive_time, event)?;
+        self.resource_summary.update(event);
 
         {
             use buck2_data::buck_event::Data::*;
@@ -152,6 +156,10 @@ where
         &self.two_snapshots
     }
 
+    pub fn resource_summary(&self) -> &ResourceSummary {
+        &self.resource_summary
+    }
+
     pub fn system_info(&self) -> &buck2_data::SystemInfo {
         &self.system_info
     }
@@ -22,6 +22,7 @@ pub mod last_command_execution_kind;
 pub mod pending_estimate;
 pub mod progress;
 pub mod re_state;
+pub mod resource_summary;
 pub mod session_info;
 pub mod span_tracker;
 pub mod starlark_debug;
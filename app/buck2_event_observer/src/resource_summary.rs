@@ -0,0 +1,242 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Resources used by a command, printed at its end with `-v summary` so users do not need to dig
+//! through the event log for the basics.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::Write;
+use std::time::SystemTime;
+
+use buck2_events::BuckEvent;
+
+use crate::action_stats::ActionStats;
+use crate::fmt_duration::fmt_duration;
+use crate::humanized::HumanizedBytes;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Phase {
+    Loading,
+    Analysis,
+    Execution,
+    Materialization,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Phase::Loading => write!(f, "loading"),
+            Phase::Analysis => write!(f, "analysis"),
+            Phase::Execution => write!(f, "execution"),
+            Phase::Materialization => write!(f, "materialization"),
+        }
+    }
+}
+
+#[derive(Default)]
+struct NetworkBytes {
+    download: u64,
+    upload: u64,
+}
+
+impl NetworkBytes {
+    fn from_snapshot(snapshot: &buck2_data::Snapshot) -> Self {
+        Self {
+            download: snapshot.re_download_bytes + snapshot.http_download_bytes,
+            upload: snapshot.re_upload_bytes,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ResourceSummary {
+    /// First start and last end of the spans of each phase.
+    phases: BTreeMap<Phase, (SystemTime, Option<SystemTime>)>,
+    first_snapshot: Option<NetworkBytes>,
+    last_snapshot: Option<NetworkBytes>,
+    peak_rss: Option<u64>,
+    /// Bytes of the outputs of local actions, and of materialized artifacts.
+    written_to_buck_out: u64,
+}
+
+impl ResourceSummary {
+    pub fn update(&mut self, event: &BuckEvent) {
+        use buck2_data::buck_event::Data;
+
+        match event.data() {
+            Data::SpanStart(start) => {
+                use buck2_data::span_start_event::Data::*;
+
+                let phase = match &start.data {
+                    Some(Load(_) | LoadPackage(_)) => Phase::Loading,
+                    Some(Analysis(_)) => Phase::Analysis,
+                    Some(ActionExecution(_)) => Phase::Execution,
+                    Some(Materialization(_) | FinalMaterialization(_)) => Phase::Materialization,
+                    _ => return,
+                };
+                self.phases
+                    .entry(phase)
+                    .or_insert((event.timestamp(), None));
+            }
+            Data::SpanEnd(end) => {
+                use buck2_data::span_end_event::Data::*;
+
+                let phase = match &end.data {
+                    Some(Load(_) | LoadPackage(_)) => Phase::Loading,
+                    Some(Analysis(_)) => Phase::Analysis,
+                    Some(ActionExecution(action)) => {
+                        if matches!(
+                            buck2_data::ActionExecutionKind::from_i32(action.execution_kind),
+                            Some(
+                                buck2_data::ActionExecutionKind::Local
+                                    | buck2_data::ActionExecutionKind::LocalWorker
+                            )
+                        ) {
+                            self.written_to_buck_out += action.output_size;
+                        }
+                        Phase::Execution
+                    }
+                    Some(Materialization(materialization)) => {
+                        if materialization.success {
+                            self.written_to_buck_out += materialization.total_bytes;
+                        }
+                        Phase::Materialization
+                    }
+                    Some(FinalMaterialization(_)) => Phase::Materialization,
+                    _ => return,
+                };
+                if let Some((_, last_end)) = self.phases.get_mut(&phase) {
+                    *last_end = Some(event.timestamp());
+                }
+            }
+            Data::Instant(instant) => {
+                if let Some(buck2_data::instant_event::Data::Snapshot(snapshot)) = &instant.data {
+                    if self.first_snapshot.is_none() {
+                        self.first_snapshot = Some(NetworkBytes::from_snapshot(snapshot));
+                    }
+                    self.last_snapshot = Some(NetworkBytes::from_snapshot(snapshot));
+                    if let Some(rss) = snapshot.buck2_rss {
+                        self.peak_rss = Some(self.peak_rss.map_or(rss, |peak| peak.max(rss)));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The summary, one item per line.
+    pub fn render(&self, action_stats: &ActionStats) -> String {
+        let mut s = "Resource usage:".to_owned();
+
+        let phases: Vec<_> = self
+            .phases
+            .iter()
+            .filter_map(|(phase, (start, end))| {
+                let elapsed = end.as_ref()?.duration_since(*start).ok()?;
+                Some(format!("{} {}", phase, fmt_duration(elapsed, 1.0)))
+            })
+            .collect();
+        if !phases.is_empty() {
+            write!(s, "\n  Wall time: {}", phases.join(", ")).unwrap();
+        }
+
+        if action_stats.log_stats() {
+            write!(s, "\n  {}", action_stats).unwrap();
+        }
+
+        if let (Some(first), Some(last)) = (&self.first_snapshot, &self.last_snapshot) {
+            write!(
+                s,
+                "\n  Network: {} downloaded, {} uploaded",
+                HumanizedBytes::new(last.download.saturating_sub(first.download)),
+                HumanizedBytes::new(last.upload.saturating_sub(first.upload))
+            )
+            .unwrap();
+        }
+
+        if let Some(peak_rss) = self.peak_rss {
+            write!(s, "\n  Peak daemon RSS: {}", HumanizedBytes::new(peak_rss)).unwrap();
+        }
+
+        write!(
+            s,
+            "\n  Written to buck-out: {}",
+            HumanizedBytes::new(self.written_to_buck_out)
+        )
+        .unwrap();
+
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use buck2_events::span::SpanId;
+    use buck2_wrapper_common::invocation_id::TraceId;
+
+    use super::*;
+
+    fn event(timestamp: SystemTime, span_id: u64, data: buck2_data::buck_event::Data) -> BuckEvent {
+        BuckEvent::new(
+            timestamp,
+            TraceId::new(),
+            Some(SpanId::from_u64(span_id).unwrap()),
+            None,
+            data,
+        )
+    }
+
+    #[test]
+    fn test_render() {
+        let t0 = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+        let mut summary = ResourceSummary::default();
+
+        summary.update(&event(
+            t0,
+            1,
+            buck2_data::SpanStartEvent {
+                data: Some(buck2_data::AnalysisStart::default().into()),
+            }
+            .into(),
+        ));
+        summary.update(&event(
+            t0 + Duration::from_millis(2500),
+            1,
+            buck2_data::SpanEndEvent {
+                data: Some(buck2_data::AnalysisEnd::default().into()),
+                ..Default::default()
+            }
+            .into(),
+        ));
+        summary.update(&event(
+            t0,
+            2,
+            buck2_data::SpanEndEvent {
+                data: Some(
+                    buck2_data::MaterializationEnd {
+                        total_bytes: 2048,
+                        success: true,
+                        ..Default::default()
+                    }
+                    .into(),
+                ),
+                ..Default::default()
+            }
+            .into(),
+        ));
+
+        assert_eq!(
+            "Resource usage:\n  Wall time: analysis 2.5s\n  Written to buck-out: 2.0KiB",
+            summary.render(&ActionStats::default())
+        );
+    }
+}
@@ -19,7 +19,7 @@ enum VerbosityError {
     UnknownItem(String),
 }
 
-const VERBOSITY_ITEM_VARIANTS: usize = 7;
+const VERBOSITY_ITEM_VARIANTS: usize = 8;
 
 /// The logging verbosity to use in our various consoles.
 ///
@@ -64,6 +64,8 @@ pub enum VerbosityItem {
     Stats,
     /// Some commands print a success message to stderr when they succeed
     Success,
+    /// Print a summary of the resources used by the command when it finishes
+    Summary,
     // ** update VERBOSITY_ITEM_VARIANTS const if more items are added **
 }
 
@@ -76,6 +78,7 @@ impl VerbosityLevel {
                 VerbosityItem::FullFailedCommand,
                 VerbosityItem::Actions,
                 VerbosityItem::Stats,
+                VerbosityItem::Summary,
             ],
             Self::AllCommands => vec![VerbosityItem::Commands],
             Self::AllStderr => vec![VerbosityItem::Stderr],
@@ -122,6 +125,7 @@ impl VerbosityItem {
             "status" => Self::Status,
             "stats" => Self::Stats,
             "success" => Self::Success,
+            "summary" => Self::Summary,
             _ => return Err(VerbosityError::UnknownItem(value.to_owned()).into()),
         };
         Ok(item)
@@ -204,6 +208,11 @@ impl Verbosity {
     pub fn print_success_message(self) -> bool {
         self.has(VerbosityItem::Success)
     }
+
+    /// Whether a summary of the resources used by the command should be printed when it finishes.
+    pub fn print_summary(self) -> bool {
+        self.has(VerbosityItem::Summary)
+    }
 }
 
 impl Default for Verbosity {
@@ -250,6 +259,7 @@ mod tests {
         assert!(verbosity.print_all_actions());
         assert!(!verbosity.print_all_commands());
         assert!(!verbosity.print_success_stderr());
+        assert!(verbosity.print_summary());
     }
 
     #[test]
@@ -9,6 +9,7 @@
 
 pub mod action_digest;
 pub mod action_digest_and_blobs;
+pub mod action_digest_history;
 pub mod blobs;
 pub mod blocking;
 pub mod cache_uploader;
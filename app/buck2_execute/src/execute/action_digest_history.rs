@@ -0,0 +1,388 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! History of the digests of the actions prepared by this daemon, and of what they were computed
+//! from, so that an action cache miss can be explained by comparing an action to its previous
+//! version (`buck2 debug why-miss`). It is only recorded with `build.record_action_digests`.
+//!
+//! Only digests are kept, not the arguments, environment or inputs themselves: a difference
+//! tells which part of the action changed, e.g. which argument or variable, but not its values.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::collections::VecDeque;
+use std::fmt;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use buck2_common::file_ops::TrackedFileDigest;
+use buck2_core::directory::FingerprintedDirectory;
+use dupe::Dupe;
+use itertools::Itertools;
+use linked_hash_map::LinkedHashMap;
+use once_cell::sync::Lazy;
+use sorted_vector_map::SortedVectorMap;
+
+use crate::execute::action_digest::ActionDigest;
+use crate::execute::prepared::PreparedAction;
+use crate::execute::request::CommandExecutionRequest;
+
+/// Number of actions whose history is kept. The actions prepared least recently are dropped first.
+const MAX_ACTIONS: usize = 20_000;
+/// Number of distinct versions kept per action.
+const MAX_RECORDS_PER_ACTION: usize = 3;
+
+/// Identifies an action across builds.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ActionDigestHistoryKey {
+    pub owner: String,
+    pub category: String,
+    pub identifier: Option<String>,
+}
+
+impl fmt::Display for ActionDigestHistoryKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.identifier {
+            Some(identifier) => write!(f, "{} ({} {})", self.owner, self.category, identifier),
+            None => write!(f, "{} ({})", self.owner, self.category),
+        }
+    }
+}
+
+/// Digest of a part of an action, only comparable within the daemon which computed it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartDigest(u64);
+
+impl PartDigest {
+    pub fn of(part: &(impl Hash + ?Sized)) -> Self {
+        let mut hasher = DefaultHasher::new();
+        part.hash(&mut hasher);
+        Self(hasher.finish())
+    }
+}
+
+impl fmt::Display for PartDigest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// Digests of what the digest of an action was computed from.
+#[derive(Clone)]
+pub struct ActionDigestRecord {
+    pub digest: ActionDigest,
+    /// One per argument.
+    pub argv: Vec<PartDigest>,
+    /// Of the value of each variable.
+    pub env: SortedVectorMap<String, PartDigest>,
+    pub working_directory: PartDigest,
+    pub platform: PartDigest,
+    pub outputs: PartDigest,
+    pub timeout: Option<Duration>,
+    /// Fingerprint of the input directory.
+    pub inputs: TrackedFileDigest,
+}
+
+impl ActionDigestRecord {
+    pub fn new(request: &CommandExecutionRequest, prepared: &PreparedAction) -> Self {
+        Self {
+            digest: prepared.digest(),
+            argv: request
+                .all_args()
+                .map(|arg| PartDigest::of(arg.as_str()))
+                .collect(),
+            env: request
+                .env()
+                .iter()
+                .map(|(name, value)| (name.clone(), PartDigest::of(value)))
+                .collect(),
+            working_directory: PartDigest::of(&request.working_directory().map(|p| p.as_str())),
+            platform: PartDigest::of(
+                &prepared
+                    .platform
+                    .properties
+                    .iter()
+                    .map(|p| (&p.name, &p.value))
+                    .collect::<BTreeMap<_, _>>(),
+            ),
+            outputs: PartDigest::of(
+                &request
+                    .paths()
+                    .output_paths()
+                    .iter()
+                    .map(|(p, _)| p.as_str())
+                    .collect::<Vec<_>>(),
+            ),
+            timeout: request.timeout(),
+            inputs: request.paths().input_directory().fingerprint().dupe(),
+        }
+    }
+}
+
+/// The versions of the actions prepared most recently, least recently prepared first.
+struct ActionDigestHistory {
+    actions: LinkedHashMap<ActionDigestHistoryKey, VecDeque<ActionDigestRecord>>,
+    max_actions: usize,
+}
+
+impl ActionDigestHistory {
+    fn new(max_actions: usize) -> Self {
+        Self {
+            actions: LinkedHashMap::new(),
+            max_actions,
+        }
+    }
+
+    fn record(&mut self, key: ActionDigestHistoryKey, record: ActionDigestRecord) {
+        let mut records = self.actions.remove(&key).unwrap_or_default();
+        records.retain(|r| r.digest != record.digest);
+        records.push_back(record);
+        while records.len() > MAX_RECORDS_PER_ACTION {
+            records.pop_front();
+        }
+        self.actions.insert(key, records);
+        while self.actions.len() > self.max_actions {
+            self.actions.pop_front();
+        }
+    }
+}
+
+static HISTORY: Lazy<Mutex<ActionDigestHistory>> =
+    Lazy::new(|| Mutex::new(ActionDigestHistory::new(MAX_ACTIONS)));
+
+/// Record the digest of an action about to be executed or looked up in the action cache.
+pub fn record_action_digest(key: ActionDigestHistoryKey, record: ActionDigestRecord) {
+    HISTORY.lock().unwrap().record(key, record);
+}
+
+/// The recorded versions of the actions matching `filter`, oldest first.
+pub fn action_digest_history(
+    filter: impl Fn(&ActionDigestHistoryKey) -> bool,
+) -> Vec<(ActionDigestHistoryKey, Vec<ActionDigestRecord>)> {
+    HISTORY
+        .lock()
+        .unwrap()
+        .actions
+        .iter()
+        .filter(|(key, _)| filter(key))
+        .map(|(key, records)| (key.clone(), records.iter().cloned().collect()))
+        .collect()
+}
+
+/// A difference between two versions of an action. `old` or `new` is `None` when the element is
+/// absent from that version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActionDigestDifference {
+    /// One of `argv`, `env`, `input`, `output`, `platform`, `working_directory` or `timeout`.
+    pub kind: &'static str,
+    /// The name of the element, e.g. the name of a variable, or empty for parts which are
+    /// compared as a whole.
+    pub name: String,
+    /// The digest of the element, or its value for the timeout.
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+/// The differences between the elements `old` and `new` were computed from. There are none iff
+/// their digests are equal.
+pub fn diff_action_digest_records(
+    old: &ActionDigestRecord,
+    new: &ActionDigestRecord,
+) -> Vec<ActionDigestDifference> {
+    let mut diffs = Vec::new();
+    diff_argv(&old.argv, &new.argv, &mut diffs);
+    diff_maps("env", old.env.iter(), new.env.iter(), &mut diffs);
+    diff_part("input", &old.inputs, &new.inputs, &mut diffs);
+    diff_part("output", &old.outputs, &new.outputs, &mut diffs);
+    diff_part("platform", &old.platform, &new.platform, &mut diffs);
+    diff_part(
+        "working_directory",
+        &old.working_directory,
+        &new.working_directory,
+        &mut diffs,
+    );
+    if old.timeout != new.timeout {
+        diffs.push(ActionDigestDifference {
+            kind: "timeout",
+            name: String::new(),
+            old: old.timeout.map(|t| format!("{}s", t.as_secs_f64())),
+            new: new.timeout.map(|t| format!("{}s", t.as_secs_f64())),
+        });
+    }
+    diffs
+}
+
+fn diff_part<T: fmt::Display + PartialEq>(
+    kind: &'static str,
+    old: &T,
+    new: &T,
+    diffs: &mut Vec<ActionDigestDifference>,
+) {
+    if old != new {
+        diffs.push(ActionDigestDifference {
+            kind,
+            name: String::new(),
+            old: Some(old.to_string()),
+            new: Some(new.to_string()),
+        });
+    }
+}
+
+/// Report the arguments between the longest common prefix and suffix of `old` and `new`.
+fn diff_argv(old: &[PartDigest], new: &[PartDigest], diffs: &mut Vec<ActionDigestDifference>) {
+    if old == new {
+        return;
+    }
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let changed = |args: &[PartDigest]| {
+        let args = &args[prefix..args.len() - suffix];
+        (!args.is_empty()).then(|| args.iter().join(" "))
+    };
+    diffs.push(ActionDigestDifference {
+        kind: "argv",
+        name: format!("argv[{}]", prefix),
+        old: changed(old),
+        new: changed(new),
+    });
+}
+
+fn diff_maps<'a, K, V>(
+    kind: &'static str,
+    old: impl IntoIterator<Item = (&'a K, V)>,
+    new: impl IntoIterator<Item = (&'a K, V)>,
+    diffs: &mut Vec<ActionDigestDifference>,
+) where
+    K: fmt::Display + Ord + 'a,
+    V: fmt::Display + PartialEq,
+{
+    let mut entries: BTreeMap<&K, (Option<V>, Option<V>)> = BTreeMap::new();
+    for (k, v) in old {
+        entries.entry(k).or_default().0 = Some(v);
+    }
+    for (k, v) in new {
+        entries.entry(k).or_default().1 = Some(v);
+    }
+    for (k, (old, new)) in entries {
+        if old != new {
+            diffs.push(ActionDigestDifference {
+                kind,
+                name: k.to_string(),
+                old: old.map(|v| v.to_string()),
+                new: new.map(|v| v.to_string()),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::digest_config::DigestConfig;
+
+    fn record(argv: &[&str], env: &[(&str, &str)], inputs: &str) -> ActionDigestRecord {
+        ActionDigestRecord {
+            digest: ActionDigest::new_sha1([0; 20], 0),
+            argv: argv.iter().map(|a| PartDigest::of(*a)).collect(),
+            env: env
+                .iter()
+                .map(|(k, v)| ((*k).to_owned(), PartDigest::of(*v)))
+                .collect(),
+            working_directory: PartDigest::of(&None::<&str>),
+            platform: PartDigest::of(&BTreeMap::<String, String>::new()),
+            outputs: PartDigest::of(&Vec::<&str>::new()),
+            timeout: None,
+            inputs: TrackedFileDigest::from_content(
+                inputs.as_bytes(),
+                DigestConfig::testing_default().cas_digest_config(),
+            ),
+        }
+    }
+
+    #[test]
+    fn test_diff_action_digest_records() {
+        let old = record(
+            &["cc", "-c", "a.c", "-o", "a.o"],
+            &[("LANG", "C"), ("TZ", "UTC")],
+            "a",
+        );
+        let new = record(
+            &["cc", "-O2", "-c", "a.c", "-o", "a.o"],
+            &[("LANG", "C"), ("PWD", "/tmp")],
+            "a2",
+        );
+
+        let diffs = diff_action_digest_records(&old, &new);
+        let summary: Vec<_> = diffs
+            .iter()
+            .map(|d| (d.kind, d.name.as_str(), d.old.is_some(), d.new.is_some()))
+            .collect();
+        assert_eq!(
+            vec![
+                ("argv", "argv[1]", false, true),
+                ("env", "PWD", false, true),
+                ("env", "TZ", true, false),
+                ("input", "", true, true),
+            ],
+            summary
+        );
+        assert_eq!(
+            Some(PartDigest::of("-O2").to_string()),
+            diffs[0].new.clone()
+        );
+
+        assert_eq!(Vec::<ActionDigestDifference>::new(), {
+            let same = record(&["cc"], &[], "a");
+            diff_action_digest_records(&same, &same.clone())
+        });
+    }
+
+    #[test]
+    fn test_history_evicts_least_recently_recorded() {
+        let key = |owner: &str| ActionDigestHistoryKey {
+            owner: owner.to_owned(),
+            category: "cxx_compile".to_owned(),
+            identifier: None,
+        };
+        let version = |n: u8| ActionDigestRecord {
+            digest: ActionDigest::new_sha1([n; 20], 0),
+            ..record(&[], &[], "")
+        };
+        let mut history = ActionDigestHistory::new(2);
+        history.record(key("a"), version(1));
+        history.record(key("b"), version(1));
+        // Recording `a` again makes `b` the least recently recorded.
+        history.record(key("a"), version(2));
+        history.record(key("c"), version(1));
+        let owners: Vec<_> = history.actions.keys().map(|k| k.owner.as_str()).collect();
+        assert_eq!(vec!["a", "c"], owners);
+        assert_eq!(2, history.actions[&key("a")].len());
+
+        // A version recorded again replaces the older record of it.
+        history.record(key("a"), version(1));
+        let digests: Vec<_> = history.actions[&key("a")]
+            .iter()
+            .map(|r| r.digest)
+            .collect();
+        assert_eq!(
+            vec![
+                ActionDigest::new_sha1([2; 20], 0),
+                ActionDigest::new_sha1([1; 20], 0)
+            ],
+            digests
+        );
+    }
+}
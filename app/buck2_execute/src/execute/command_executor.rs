@@ -7,12 +7,15 @@
  * of this source tree.
  */
 
+use std::borrow::Cow;
 use std::ops::ControlFlow;
 use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::Context;
 use buck2_common::file_ops::TrackedFileDigest;
+use buck2_core::directory;
+use buck2_core::directory::DirectoryEntry;
 use buck2_core::directory::FingerprintedDirectory;
 use buck2_core::execution_types::executor_config::CommandGenerationOptions;
 use buck2_core::execution_types::executor_config::OutputPathsBehavior;
@@ -180,11 +183,12 @@ impl CommandExecutor {
                 }
                 CommandExecutionInput::ScratchPath(_) => None,
             });
+            let env = self.env_with_scratch_tmpdir(request);
             let action = re_create_action(
                 request.all_args_vec(),
                 request.paths().output_paths(),
                 request.working_directory().map(|p| p.as_str().to_owned()),
-                request.env(),
+                &env,
                 input_digest,
                 action_metadata_blobs,
                 request.timeout(),
@@ -199,6 +203,48 @@ impl CommandExecutor {
             anyhow::Ok(action)
         })
     }
+
+    /// Commands which declared a scratch path use it as their temporary directory. Local
+    /// executors already do that for every command, so this only matters for remote ones.
+    /// Remote workers run commands from their working directory in a root that differs
+    /// between machines, so the path is relative to that working directory. They only create
+    /// the directories of the input root, so the scratch path is only used when it is an
+    /// (empty) directory of it.
+    fn env_with_scratch_tmpdir<'a>(
+        &self,
+        request: &'a CommandExecutionRequest,
+    ) -> Cow<'a, SortedVectorMap<String, String>> {
+        if !request.declared_scratch() {
+            return Cow::Borrowed(request.env());
+        }
+        let Some(scratch) = request.inputs().iter().find_map(|x| match x {
+            CommandExecutionInput::ScratchPath(path) => Some(path),
+            _ => None,
+        }) else {
+            return Cow::Borrowed(request.env());
+        };
+
+        let scratch = self
+            .0
+            .artifact_fs
+            .buck_out_path_resolver()
+            .resolve_scratch(scratch);
+        if !matches!(
+            directory::find(request.paths().input_directory(), scratch.iter()),
+            Ok(Some(DirectoryEntry::Dir(_)))
+        ) {
+            return Cow::Borrowed(request.env());
+        }
+        let scratch = request.relative_to_working_directory(&scratch);
+        let mut env = request.env().clone();
+        for var in ["TMPDIR", "TMP", "TEMP"] {
+            // Values set by the command itself take precedence.
+            if !env.contains_key(var) {
+                env.insert(var.to_owned(), scratch.as_str().to_owned());
+            }
+        }
+        Cow::Owned(env)
+    }
 }
 
 fn re_create_action(
@@ -8,6 +8,8 @@
  */
 
 use std::ffi::OsString;
+use std::str::FromStr;
+use std::sync::Arc;
 use std::sync::OnceLock;
 
 use dupe::Dupe;
@@ -57,13 +59,53 @@ const ENV_ALLOW_LIST: &[&str] = &[
     "WINDIR",
 ];
 
-#[derive(Copy, Clone, Dupe, Debug)]
+/// How local actions get their environment, from `build.action_env_isolation`.
+#[derive(Copy, Clone, Dupe, Debug, Default, PartialEq, Eq)]
+pub enum EnvIsolationMode {
+    /// Local actions inherit the environment of the daemon (and so of the shell which started
+    /// it), except for a few variables known to cause issues.
+    #[default]
+    Inherit,
+    /// Like `Inherit`, but warn about inherited variables which are not in the passthrough list.
+    /// Used to migrate to `Strict`.
+    Warn,
+    /// Local actions only get the variables in the passthrough list, in addition to the ones
+    /// they set themselves.
+    Strict,
+}
+
+impl FromStr for EnvIsolationMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "inherit" => Ok(EnvIsolationMode::Inherit),
+            "warn" => Ok(EnvIsolationMode::Warn),
+            "strict" => Ok(EnvIsolationMode::Strict),
+            _ => Err(anyhow::anyhow!(
+                "Invalid action environment isolation mode: `{}` (expected `inherit`, `warn` or `strict`)",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Dupe, Debug)]
 pub struct EnvironmentInheritance {
     clear: bool,
-    values: &'static [(&'static str, OsString)],
+    values: Arc<[(String, OsString)]>,
     exclusions: &'static [&'static str],
 }
 
+/// Variables which are never inherited by local actions, unless explicitly passed through.
+const LOCAL_COMMAND_EXCLUSIONS: &[&str] = &[
+    "PYTHONPATH",
+    "PYTHONHOME",
+    "PYTHONSTARTUP",
+    "LD_LIBRARY_PATH",
+    "LD_PRELOAD",
+];
+
 impl EnvironmentInheritance {
     pub fn test_allowlist() -> Self {
         // This is made to be a list of lists in case we want to include lists from different
@@ -72,23 +114,23 @@ impl EnvironmentInheritance {
 
         // We create this *once* since getenv is actually not cheap (being O(n) of the environment
         // size).
-        static TEST_CELL: OnceLock<Vec<(&'static str, OsString)>> = OnceLock::new();
+        static TEST_CELL: OnceLock<Arc<[(String, OsString)]>> = OnceLock::new();
 
         let values = TEST_CELL.get_or_init(|| {
             let mut ret = Vec::new();
             for list in allowlists.iter() {
                 for key in list.iter() {
                     if let Some(value) = std::env::var_os(key) {
-                        ret.push((*key, value));
+                        ret.push(((*key).to_owned(), value));
                     }
                 }
             }
-            ret
+            ret.into()
         });
 
         Self {
             clear: true,
-            values,
+            values: values.dupe(),
             exclusions: &[],
         }
     }
@@ -98,27 +140,60 @@ impl EnvironmentInheritance {
     pub fn local_command_exclusions() -> Self {
         Self {
             clear: false,
-            values: &[],
-            exclusions: &[
-                "PYTHONPATH",
-                "PYTHONHOME",
-                "PYTHONSTARTUP",
-                "LD_LIBRARY_PATH",
-                "LD_PRELOAD",
-            ],
+            values: Arc::new([]),
+            exclusions: LOCAL_COMMAND_EXCLUSIONS,
+        }
+    }
+
+    /// Only the given variables, with their values in the daemon environment.
+    pub fn passthrough(names: &[String]) -> Self {
+        let values: Vec<_> = names
+            .iter()
+            .filter_map(|name| Some((name.clone(), std::env::var_os(name)?)))
+            .collect();
+        Self {
+            clear: true,
+            values: values.into(),
+            exclusions: &[],
         }
     }
 
+    /// Environment of local run actions for the given isolation mode.
+    pub fn local_actions(mode: EnvIsolationMode, passthrough: &[String]) -> Self {
+        match mode {
+            EnvIsolationMode::Inherit | EnvIsolationMode::Warn => Self::local_command_exclusions(),
+            EnvIsolationMode::Strict => Self::passthrough(passthrough),
+        }
+    }
+
+    /// Passthrough list used when `build.action_env_passthrough` is not set.
+    pub fn default_passthrough() -> Vec<String> {
+        ENV_ALLOW_LIST.iter().map(|v| (*v).to_owned()).collect()
+    }
+
+    /// Variables of the daemon environment which local actions inherit, but would not with
+    /// strict isolation and the given passthrough list. Sorted.
+    pub fn isolation_violations(passthrough: &[String]) -> Vec<String> {
+        let mut violations: Vec<String> = std::env::vars_os()
+            .filter_map(|(name, _)| name.into_string().ok())
+            .filter(|name| {
+                !LOCAL_COMMAND_EXCLUSIONS.contains(&name.as_str()) && !passthrough.contains(name)
+            })
+            .collect();
+        violations.sort();
+        violations
+    }
+
     pub fn empty() -> Self {
         Self {
-            values: &[],
+            values: Arc::new([]),
             exclusions: &[],
             clear: true,
         }
     }
 
-    pub fn values(&self) -> impl Iterator<Item = (&'static str, &'static OsString)> {
-        self.values.iter().map(|(k, v)| (*k, v))
+    pub fn values(&self) -> impl Iterator<Item = (&str, &OsString)> {
+        self.values.iter().map(|(k, v)| (k.as_str(), v))
     }
 
     pub fn exclusions(&self) -> impl Iterator<Item = &'static str> {
@@ -17,11 +17,13 @@ use buck2_common::local_resource_state::LocalResourceState;
 use buck2_core::directory::DirectoryEntry;
 use buck2_core::directory::DirectoryIterator;
 use buck2_core::directory::FingerprintedDirectory;
+use buck2_core::execution_types::executor_config::HybridExecutionPolicy;
 use buck2_core::execution_types::executor_config::RemoteExecutorDependency;
 use buck2_core::fs::artifact_path_resolver::ArtifactFs;
 use buck2_core::fs::buck_out_path::BuckOutPath;
 use buck2_core::fs::buck_out_path::BuckOutScratchPath;
 use buck2_core::fs::buck_out_path::BuckOutTestPath;
+use buck2_core::fs::paths::RelativePathBuf;
 use buck2_core::fs::project_rel_path::ProjectRelativePath;
 use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
 use buck2_core::soft_error;
@@ -311,6 +313,8 @@ pub struct CommandExecutionRequest {
     /// Whether this command should override the fallback-only behavior on an hybrid executor and
     /// thus always run as if the executor was full-hybrid, assuming it is capable.
     force_full_hybrid_if_capable: bool,
+    /// Overrides how a hybrid executor schedules this command.
+    hybrid_policy: Option<HybridExecutionPolicy>,
     /// Whether to disable capturing performance counters for this execution.
     disable_miniperf: bool,
     required_local_resources: SortedSet<LocalResourceState>,
@@ -324,6 +328,15 @@ pub struct CommandExecutionRequest {
     pub remote_dep_file_key: Option<DepFileDigest>,
     /// RE dependencies to pass in action metadata.
     remote_execution_dependencies: Vec<RemoteExecutorDependency>,
+    /// When set, stdout and stderr of a local command are forwarded to the console line by line
+    /// while it runs, each line prefixed with this label (usually the target).
+    stream_output: Option<String>,
+    /// Whether the executor should avoid exposing values that differ between builds or machines
+    /// (build IDs, absolute temporary directories) to the command.
+    reproducible: bool,
+    /// Whether the command declared that it uses its scratch path: it is then its temporary
+    /// directory on every executor, and is deleted once the command finishes.
+    declared_scratch: bool,
 }
 
 impl CommandExecutionRequest {
@@ -347,12 +360,16 @@ impl CommandExecutionRequest {
             outputs_cleanup: true,
             local_environment_inheritance: None,
             force_full_hybrid_if_capable: false,
+            hybrid_policy: None,
             disable_miniperf: false,
             required_local_resources: SortedSet::new(),
             worker: None,
             unique_input_inodes: false,
             remote_dep_file_key: None,
             remote_execution_dependencies: Vec::new(),
+            stream_output: None,
+            reproducible: false,
+            declared_scratch: false,
         }
     }
 
@@ -476,6 +493,12 @@ impl CommandExecutionRequest {
         self.working_directory.as_deref()
     }
 
+    /// `path` relative to the directory the command runs in, for values of the environment
+    /// which must not be absolute paths.
+    pub fn relative_to_working_directory(&self, path: &ProjectRelativePath) -> RelativePathBuf {
+        relative_to_working_directory(self.working_directory(), path)
+    }
+
     pub fn with_local_environment_inheritance(
         mut self,
         local_environment_inheritance: EnvironmentInheritance,
@@ -497,6 +520,15 @@ impl CommandExecutionRequest {
         self.force_full_hybrid_if_capable
     }
 
+    pub fn with_hybrid_policy(mut self, hybrid_policy: Option<HybridExecutionPolicy>) -> Self {
+        self.hybrid_policy = hybrid_policy;
+        self
+    }
+
+    pub fn hybrid_policy(&self) -> Option<HybridExecutionPolicy> {
+        self.hybrid_policy
+    }
+
     pub fn with_disable_miniperf(mut self, disable_miniperf: bool) -> Self {
         self.disable_miniperf = disable_miniperf;
         self
@@ -544,6 +576,47 @@ impl CommandExecutionRequest {
     pub fn remote_execution_dependencies(&self) -> &Vec<RemoteExecutorDependency> {
         &self.remote_execution_dependencies
     }
+
+    pub fn with_stream_output(mut self, stream_output: Option<String>) -> Self {
+        self.stream_output = stream_output;
+        self
+    }
+
+    pub fn stream_output(&self) -> Option<&str> {
+        self.stream_output.as_deref()
+    }
+
+    pub fn with_reproducible(mut self, reproducible: bool) -> Self {
+        self.reproducible = reproducible;
+        self
+    }
+
+    pub fn reproducible(&self) -> bool {
+        self.reproducible
+    }
+
+    pub fn with_declared_scratch(mut self, declared_scratch: bool) -> Self {
+        self.declared_scratch = declared_scratch;
+        self
+    }
+
+    pub fn declared_scratch(&self) -> bool {
+        self.declared_scratch
+    }
+}
+
+fn relative_to_working_directory(
+    working_directory: Option<&ProjectRelativePath>,
+    path: &ProjectRelativePath,
+) -> RelativePathBuf {
+    let path = path.as_forward_relative_path().as_relative_path();
+    match working_directory {
+        Some(working_directory) => working_directory
+            .as_forward_relative_path()
+            .as_relative_path()
+            .relative(path),
+        None => path.to_owned(),
+    }
 }
 
 /// Is an output a file or a directory
@@ -700,3 +773,27 @@ impl ResolvedCommandExecutionOutput {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use buck2_core::fs::project_rel_path::ProjectRelativePath;
+
+    use super::relative_to_working_directory;
+
+    #[test]
+    fn test_relative_to_working_directory() {
+        let scratch = ProjectRelativePath::unchecked_new("buck-out/v2/tmp/a");
+        assert_eq!(
+            "buck-out/v2/tmp/a",
+            relative_to_working_directory(None, scratch).as_str()
+        );
+        assert_eq!(
+            "../../buck-out/v2/tmp/a",
+            relative_to_working_directory(
+                Some(ProjectRelativePath::unchecked_new("foo/bar")),
+                scratch
+            )
+            .as_str()
+        );
+    }
+}
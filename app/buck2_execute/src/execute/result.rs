@@ -69,6 +69,29 @@ impl CommandExecutionStatus {
             CommandExecutionStatus::Cancelled => None,
         }
     }
+
+    /// Whether this status is an error caused by infrastructure (RE connectivity, CAS transfers,
+    /// materialization or local IO) rather than by the command itself. Such errors are usually
+    /// transient, so the action is worth retrying.
+    pub fn is_infra_error(&self) -> bool {
+        match self {
+            CommandExecutionStatus::Error { stage, typ, .. } => {
+                !matches!(typ, CommandExecutionErrorType::StorageResourceExhausted)
+                    && matches!(
+                        *stage,
+                        "remote_call_error"
+                            | "upload"
+                            | "remote_action_cache"
+                            | "remote_dep_file"
+                            | "materialize_inputs_failed"
+                            | "materialize_outputs"
+                            | "prepare_output_dirs_failed"
+                            | "calculate_output_values_failed"
+                    )
+            }
+            _ => false,
+        }
+    }
 }
 
 impl Display for CommandExecutionStatus {
@@ -7,6 +7,7 @@
  * of this source tree.
  */
 
+use buck2_common::process_priority::BackgroundPriority;
 use dupe::Dupe;
 
 /// Command-level config that can tweak how the executors work.
@@ -17,4 +18,11 @@ pub struct ExecutorGlobalKnobs {
     /// Whether to emit action keys to execution logs (thos are pretty verbose and omitted by
     /// default).
     pub log_action_keys: bool,
+
+    /// How long a local action that exceeded its timeout is given to exit after `SIGTERM`
+    /// before it is sent `SIGKILL`.
+    pub action_timeout_kill_grace_period_s: Option<u32>,
+
+    /// When set, local commands run at a lower CPU and IO priority (`--background`).
+    pub background_priority: Option<BackgroundPriority>,
 }
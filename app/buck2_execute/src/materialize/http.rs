@@ -149,7 +149,7 @@ enum HttpDownloadError {
     Client(#[source] HttpError),
 
     #[error("Invalid {0} digest. Expected {1}, got {2}. URL: {3}")]
-    #[buck2(input)]
+    #[buck2(input, tag = DownloadChecksumMismatch)]
     InvalidChecksum(&'static str, String, String, String),
 
     #[error(
@@ -16,6 +16,7 @@ use buck2_common::file_ops::FileMetadata;
 use buck2_core::base_deferred_key::BaseDeferredKey;
 use buck2_core::directory::DirectoryEntry;
 use buck2_core::execution_types::executor_config::RemoteExecutorUseCase;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
 use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
 use buck2_events::dispatch::EventDispatcher;
 use buck2_futures::cancellation::CancellationContext;
@@ -237,6 +238,35 @@ pub trait Materializer: Allocative + Send + Sync + 'static {
         None
     }
 
+    /// A counter which changes whenever the materializer forgets about artifacts it had
+    /// materialized, e.g. because their paths were invalidated or cleaned. An artifact which was
+    /// materialized at a given generation is still materialized while the generation is the
+    /// same. [`None`] if the materializer does not track this.
+    fn materialized_generation(&self) -> Option<u64> {
+        None
+    }
+
+    /// Whether `path` was recorded with `record_requested_materialized` as materialized with
+    /// `value`, and the materialized generation has not changed since. Requested artifacts for
+    /// which this is true do not need to go through the materializer again.
+    fn is_requested_materialized(
+        &self,
+        _path: &ProjectRelativePath,
+        _value: &ArtifactValue,
+    ) -> bool {
+        false
+    }
+
+    /// Record that the requested artifact at `path` was materialized with `value`, at the
+    /// materialized generation `generation` read before materializing it.
+    fn record_requested_materialized(
+        &self,
+        _path: ProjectRelativePathBuf,
+        _value: ArtifactValue,
+        _generation: u64,
+    ) {
+    }
+
     /// Currently no-op for all materializers except deferred materializer
     fn log_materializer_state(&self, _events: &EventDispatcher) {}
 
@@ -646,6 +676,15 @@ pub trait DeferredMaterializerExtensions: Send + Sync {
         tracked_only: bool,
     ) -> anyhow::Result<buck2_cli_proto::CleanStaleResponse>;
 
+    /// Check that materialized artifacts on disk still match the metadata recorded when they were
+    /// materialized. Only a `sample_rate` fraction of artifacts is checked. If `repair` is set,
+    /// corrupted artifacts are fetched again from the CAS.
+    async fn corrupt_scan(
+        &self,
+        sample_rate: f64,
+        repair: bool,
+    ) -> anyhow::Result<buck2_cli_proto::new_generic::CorruptScanResponse>;
+
     async fn test_iter(&self, count: usize) -> anyhow::Result<String>;
     async fn flush_all_access_times(&self) -> anyhow::Result<String>;
 
@@ -0,0 +1,248 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Detached signature verification for downloaded files (typically toolchain archives).
+//!
+//! Checksums only protect against the file changing after its digest was recorded. Signatures
+//! additionally let a rule pin the publisher of the file, which is what matters when the digest
+//! itself was copied from the same (possibly compromised) place as the file.
+
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use allocative::Allocative;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_path::AbsPath;
+use buck2_core::fs::project::ProjectRoot;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_http::retries::http_retry;
+use buck2_http::retries::AsHttpError;
+use buck2_http::retries::HttpError;
+use buck2_http::HttpClient;
+use buck2_util::process::async_background_command;
+use dupe::Dupe;
+use futures::StreamExt;
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Dupe,
+    Allocative,
+    derive_more::Display,
+    PartialEq,
+    Eq
+)]
+pub enum SignatureKind {
+    #[display(fmt = "minisign")]
+    Minisign,
+    #[display(fmt = "gpg")]
+    Gpg,
+}
+
+/// A detached signature that a download must verify against before it is exposed to rules.
+#[derive(Debug, Clone, Dupe, Allocative)]
+pub struct Signature {
+    pub kind: SignatureKind,
+    pub url: Arc<str>,
+    /// For `minisign` the base64 public key, for `gpg` an ASCII armored public key.
+    pub public_key: Arc<str>,
+}
+
+#[derive(buck2_error::Error, Debug)]
+enum SignatureError {
+    #[error("Unknown signature kind `{0}`, expected `minisign` or `gpg`")]
+    #[buck2(input)]
+    UnknownKind(String),
+    #[error("Signature `public_key` must not be empty")]
+    #[buck2(input)]
+    EmptyPublicKey,
+    #[error("Error downloading {kind} signature from `{url}`")]
+    Download {
+        kind: SignatureKind,
+        url: String,
+        #[source]
+        source: HttpError,
+    },
+    #[error(
+        "Invalid {kind} signature for `{url}` (signature `{signature_url}`), refusing to use the downloaded file:\n{output}"
+    )]
+    #[buck2(input, tag = DownloadSignatureInvalid)]
+    Invalid {
+        kind: SignatureKind,
+        url: String,
+        signature_url: String,
+        output: String,
+    },
+    /// The signature was not checked: this is a problem with the host, not with the download.
+    #[error("Could not run `{0}` to verify a downloaded file, is it installed?")]
+    #[buck2(tier0)]
+    ToolNotFound(&'static str, #[source] std::io::Error),
+}
+
+impl AsHttpError for SignatureError {
+    fn as_http_error(&self) -> Option<&HttpError> {
+        match self {
+            Self::Download { source, .. } => Some(source),
+            Self::UnknownKind(..)
+            | Self::EmptyPublicKey
+            | Self::Invalid { .. }
+            | Self::ToolNotFound(..) => None,
+        }
+    }
+}
+
+impl Signature {
+    pub fn new(kind: &str, url: &str, public_key: &str) -> anyhow::Result<Self> {
+        let kind = match kind {
+            "minisign" => SignatureKind::Minisign,
+            "gpg" => SignatureKind::Gpg,
+            _ => return Err(SignatureError::UnknownKind(kind.to_owned()).into()),
+        };
+        let public_key = public_key.trim();
+        if public_key.is_empty() {
+            return Err(SignatureError::EmptyPublicKey.into());
+        }
+        Ok(Self {
+            kind,
+            url: Arc::from(url),
+            public_key: Arc::from(public_key),
+        })
+    }
+
+    async fn download(&self, client: &HttpClient) -> anyhow::Result<Vec<u8>> {
+        Ok(http_retry(
+            || async {
+                let map_err = |source| SignatureError::Download {
+                    kind: self.kind,
+                    url: self.url.to_string(),
+                    source,
+                };
+                let mut stream = client
+                    .get(&self.url)
+                    .await
+                    .map_err(|e| map_err(HttpError::Client(e)))?
+                    .into_body();
+                let mut bytes = Vec::new();
+                while let Some(chunk) = stream.next().await {
+                    let chunk = chunk.map_err(|source| {
+                        map_err(HttpError::Transfer {
+                            received: bytes.len() as u64,
+                            url: self.url.to_string(),
+                            source,
+                        })
+                    })?;
+                    bytes.extend_from_slice(&chunk);
+                }
+                Result::<_, SignatureError>::Ok(bytes)
+            },
+            vec![2, 4, 8].into_iter().map(Duration::from_secs).collect(),
+        )
+        .await?)
+    }
+
+    /// Verify the file at `path` (which was downloaded from `url`) against this signature.
+    ///
+    /// Verification shells out to the `minisign` or `gpg` binary, and never consults the user's
+    /// own keyring: the only trusted key is the one declared alongside the download.
+    pub async fn verify(
+        &self,
+        client: &HttpClient,
+        fs: &ProjectRoot,
+        path: &ProjectRelativePath,
+        url: &str,
+    ) -> anyhow::Result<()> {
+        let signature = self.download(client).await?;
+
+        let scratch_dir = tempfile::tempdir()?;
+        let scratch = AbsPath::new(scratch_dir.path())?;
+        let signature_path = scratch.join("signature");
+        fs_util::write(&signature_path, signature)?;
+        let file = fs.resolve(path);
+
+        let (tool, output) = match self.kind {
+            SignatureKind::Minisign => {
+                let output = async_background_command("minisign")
+                    .arg("-V")
+                    .arg("-q")
+                    .arg("-m")
+                    .arg(file.as_path())
+                    .arg("-x")
+                    .arg(signature_path.as_path())
+                    .arg("-P")
+                    .arg(self.public_key.as_ref())
+                    .stdin(Stdio::null())
+                    .output()
+                    .await;
+                ("minisign", output)
+            }
+            SignatureKind::Gpg => {
+                let key_path = scratch.join("key.asc");
+                fs_util::write(&key_path, self.public_key.as_bytes())?;
+                let keyring_path = scratch.join("keyring.gpg");
+                // `--dearmor` turns the declared key into a keyring usable with `gpgv`, which
+                // (unlike `gpg --verify`) only ever trusts the keyring it is given.
+                let dearmor = async_background_command("gpg")
+                    .arg("--batch")
+                    .arg("--yes")
+                    .arg("--output")
+                    .arg(keyring_path.as_path())
+                    .arg("--dearmor")
+                    .arg(key_path.as_path())
+                    .stdin(Stdio::null())
+                    .output()
+                    .await
+                    .map_err(|e| SignatureError::ToolNotFound("gpg", e))?;
+                if !dearmor.status.success() {
+                    ("gpg", Ok(dearmor))
+                } else {
+                    let output = async_background_command("gpgv")
+                        .arg("--keyring")
+                        .arg(keyring_path.as_path())
+                        .arg(signature_path.as_path())
+                        .arg(file.as_path())
+                        .stdin(Stdio::null())
+                        .output()
+                        .await;
+                    ("gpgv", output)
+                }
+            }
+        };
+
+        let output = output.map_err(|e| SignatureError::ToolNotFound(tool, e))?;
+        if !output.status.success() {
+            return Err(SignatureError::Invalid {
+                kind: self.kind,
+                url: url.to_owned(),
+                signature_url: self.url.to_string(),
+                output: String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_new() {
+        let signature =
+            Signature::new("minisign", "https://example.com/a.minisig", " key\n").unwrap();
+        assert_eq!(signature.kind, SignatureKind::Minisign);
+        assert_eq!(&*signature.public_key, "key");
+
+        assert!(Signature::new("pgp", "https://example.com/a.sig", "key").is_err());
+        assert!(Signature::new("gpg", "https://example.com/a.sig", "  ").is_err());
+    }
+}
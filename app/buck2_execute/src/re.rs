@@ -9,6 +9,7 @@
 
 pub mod action_identity;
 pub mod client;
+pub mod clock_skew;
 pub mod convert;
 pub mod manager;
 pub mod metadata;
@@ -9,6 +9,7 @@
 
 use std::sync::Arc;
 use std::time::Duration;
+use std::time::SystemTime;
 
 use allocative::Allocative;
 use anyhow::Context;
@@ -18,6 +19,7 @@ use buck2_core::execution_types::executor_config::RemoteExecutorUseCase;
 use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
 use buck2_core::fs::project::ProjectRoot;
 use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_events::dispatch::get_dispatcher_opt;
 use buck2_re_configuration::RemoteExecutionStaticMetadata;
 use buck2_re_configuration::RemoteExecutionStaticMetadataImpl;
 use chrono::DateTime;
@@ -72,6 +74,7 @@ use crate::execute::manager::CommandExecutionManager;
 use crate::knobs::ExecutorGlobalKnobs;
 use crate::materialize::materializer::Materializer;
 use crate::re::action_identity::ReActionIdentity;
+use crate::re::clock_skew;
 use crate::re::convert::platform_to_proto;
 use crate::re::metadata::RemoteExecutionMetadataExt;
 use crate::re::stats::OpStats;
@@ -92,6 +95,54 @@ pub enum ExecuteResponseOrCancelled {
     Cancelled,
 }
 
+/// Cancels a remote execution through the RE API when the build stops waiting for it, so it does
+/// not keep using RE capacity. This happens when the command is interrupted or the computation
+/// is cancelled (which drops the future executing the action), or when the execution loses a race
+/// against a local execution.
+struct CancelExecutionOnDrop {
+    client: RemoteExecutionClient,
+    action_digest: ActionDigest,
+    use_case: RemoteExecutorUseCase,
+    /// Set while the execution is in progress on RE.
+    operation_name: Option<String>,
+}
+
+impl Drop for CancelExecutionOnDrop {
+    fn drop(&mut self) {
+        let Some(operation_name) = self.operation_name.take() else {
+            return;
+        };
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let client = self.client.dupe();
+        let action_digest = self.action_digest.dupe();
+        let use_case = self.use_case;
+        let dispatcher = get_dispatcher_opt();
+        handle.spawn(async move {
+            let res = client
+                .data
+                .client
+                .cancel_execution(operation_name.clone(), use_case)
+                .await;
+            if let Err(e) = &res {
+                tracing::debug!(
+                    "Failed to cancel RE execution of {}: {:#}",
+                    action_digest,
+                    e
+                );
+            }
+            if let Some(dispatcher) = dispatcher {
+                dispatcher.instant_event(buck2_data::RemoteExecutionCancelled {
+                    action_digest: action_digest.to_string(),
+                    operation_name,
+                    error: res.err().map(|e| format!("{:#}", e)),
+                });
+            }
+        });
+    }
+}
+
 #[derive(Allocative)]
 struct RemoteExecutionClientData {
     client: RemoteExecutionClientImpl,
@@ -267,7 +318,15 @@ impl RemoteExecutionClient {
         re_resource_units: Option<i64>,
         knobs: &ExecutorGlobalKnobs,
     ) -> anyhow::Result<ExecuteResponseOrCancelled> {
-        self.data
+        let mut cancel_on_drop = CancelExecutionOnDrop {
+            client: self.dupe(),
+            action_digest: action_digest.dupe(),
+            use_case,
+            operation_name: None,
+        };
+        let sent = SystemTime::now();
+        let res = self
+            .data
             .executes
             .op(self
                 .data
@@ -284,9 +343,24 @@ impl RemoteExecutionClient {
                     re_max_queue_time,
                     re_resource_units,
                     knobs,
+                    &mut cancel_on_drop.operation_name,
                 )
                 .map_err(|e| self.decorate_error("execute", e)))
-            .await
+            .await;
+        if !matches!(res, Ok(ExecuteResponseOrCancelled::Cancelled)) {
+            // The execution is finished, or RE failed it: there is nothing to cancel.
+            cancel_on_drop.operation_name = None;
+        }
+        if let Ok(ExecuteResponseOrCancelled::Response(response)) = &res {
+            if !response.cached_result {
+                clock_skew::record_remote_execution(
+                    sent,
+                    SystemTime::now(),
+                    &response.action_result.execution_metadata,
+                );
+            }
+        }
+        res
     }
 
     pub async fn materialize_files(
@@ -805,6 +879,7 @@ impl RemoteExecutionClientImpl {
         re_max_queue_time: Option<Duration>,
         platform: &remote_execution::Platform,
         knobs: &ExecutorGlobalKnobs,
+        operation_name: &mut Option<String>,
     ) -> anyhow::Result<ExecuteResponseOrCancelled> {
         use buck2_data::re_stage;
         use buck2_data::ReExecute;
@@ -836,6 +911,7 @@ impl RemoteExecutionClientImpl {
             report_stage: re_stage::Stage,
             manager: &mut CommandExecutionManager,
             re_max_queue_time: Option<Duration>,
+            operation_name: &mut Option<String>,
         ) -> anyhow::Result<ResponseOrStateChange> {
             executor_stage_async(
                 buck2_data::ReStage {
@@ -864,6 +940,10 @@ impl RemoteExecutionClientImpl {
 
                         let event = event.context("Error was returned on the stream by RE")?;
 
+                        if !event.operation_name.is_empty() {
+                            *operation_name = Some(event.operation_name.clone());
+                        }
+
                         if event.execute_response.is_some() || event.stage != previous_stage {
                             return Ok(ResponseOrStateChange::Present(event));
                         }
@@ -951,6 +1031,7 @@ impl RemoteExecutionClientImpl {
                 ),
                 manager,
                 re_max_queue_time,
+                operation_name,
             )
             .await?;
 
@@ -984,6 +1065,7 @@ impl RemoteExecutionClientImpl {
         re_max_queue_time: Option<Duration>,
         re_resource_units: Option<i64>,
         knobs: &ExecutorGlobalKnobs,
+        operation_name: &mut Option<String>,
     ) -> anyhow::Result<ExecuteResponseOrCancelled> {
         let metadata = RemoteExecutionMetadata {
             platform: Some(re_platform(platform)),
@@ -1031,11 +1113,23 @@ impl RemoteExecutionClientImpl {
             re_max_queue_time,
             platform,
             knobs,
+            operation_name,
         )
         .await
         .with_context(|| format!("RE: execution with digest {}", &action_digest))
     }
 
+    async fn cancel_execution(
+        &self,
+        operation_name: String,
+        use_case: RemoteExecutorUseCase,
+    ) -> anyhow::Result<()> {
+        self.client()
+            .get_execution_client()
+            .cancel_operation(use_case.metadata(None), operation_name)
+            .await
+    }
+
     /// Fetches a list of digests from the CAS and casts them to Tree objects.
     /// If fetching or decoding fails for one or more digests, returns an Err.
     async fn download_typed_blobs<T: Message + Default>(
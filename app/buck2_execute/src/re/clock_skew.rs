@@ -0,0 +1,127 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Skew between the local clock and the clock of remote execution workers, estimated from the
+//! actions they execute.
+//!
+//! A worker completes an action after the action is sent, and before its result is received, so
+//! each action bounds the skew. The estimate is the intersection of these bounds, which gets
+//! tighter as actions execute.
+
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use remote_execution::TExecutedActionMetadata;
+
+/// Bounds of the skew of the clock of RE workers, in milliseconds, positive when it is ahead of
+/// the local clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockSkewBounds {
+    pub min_ms: i64,
+    pub max_ms: i64,
+    /// Number of actions the bounds were computed from.
+    pub samples: u64,
+}
+
+impl ClockSkewBounds {
+    /// The smallest skew consistent with the bounds: zero if the clocks may agree.
+    pub fn certain_skew_ms(&self) -> i64 {
+        if self.min_ms > 0 {
+            self.min_ms
+        } else if self.max_ms < 0 {
+            self.max_ms
+        } else {
+            0
+        }
+    }
+
+    fn intersect(self, sample: ClockSkewBounds) -> ClockSkewBounds {
+        let min_ms = self.min_ms.max(sample.min_ms);
+        let max_ms = self.max_ms.min(sample.max_ms);
+        if min_ms > max_ms {
+            // One of the clocks was adjusted since: start over.
+            return sample;
+        }
+        ClockSkewBounds {
+            min_ms,
+            max_ms,
+            samples: self.samples + sample.samples,
+        }
+    }
+}
+
+static BOUNDS: Mutex<Option<ClockSkewBounds>> = Mutex::new(None);
+
+fn unix_ms(time: SystemTime) -> Option<i64> {
+    let since_epoch = time.duration_since(SystemTime::UNIX_EPOCH).ok()?;
+    i64::try_from(since_epoch.as_millis()).ok()
+}
+
+/// Record the timestamps of an action executed by RE (not served from its cache), sent and
+/// received at the given local times.
+pub(crate) fn record_remote_execution(
+    sent: SystemTime,
+    received: SystemTime,
+    metadata: &TExecutedActionMetadata,
+) {
+    let completed = &metadata.worker_completed_timestamp;
+    if completed.seconds <= 0 {
+        // Not reported by this RE implementation.
+        return;
+    }
+    let (Some(sent), Some(received)) = (unix_ms(sent), unix_ms(received)) else {
+        return;
+    };
+    let completed = completed.seconds * 1000 + i64::from(completed.nanos) / 1_000_000;
+    let sample = ClockSkewBounds {
+        min_ms: completed - received,
+        max_ms: completed - sent,
+        samples: 1,
+    };
+
+    let mut bounds = BOUNDS.lock().unwrap();
+    *bounds = Some(match *bounds {
+        Some(bounds) => bounds.intersect(sample),
+        None => sample,
+    });
+}
+
+/// The skew of the clock of RE workers, if this daemon executed any action remotely.
+pub fn remote_execution_clock_skew() -> Option<ClockSkewBounds> {
+    *BOUNDS.lock().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds(min_ms: i64, max_ms: i64) -> ClockSkewBounds {
+        ClockSkewBounds {
+            min_ms,
+            max_ms,
+            samples: 1,
+        }
+    }
+
+    #[test]
+    fn test_certain_skew() {
+        assert_eq!(0, bounds(-100, 200).certain_skew_ms());
+        assert_eq!(5000, bounds(5000, 6000).certain_skew_ms());
+        assert_eq!(-3000, bounds(-4000, -3000).certain_skew_ms());
+    }
+
+    #[test]
+    fn test_intersect() {
+        let b = bounds(-1000, 9000).intersect(bounds(2000, 12000));
+        assert_eq!((2000, 9000, 2), (b.min_ms, b.max_ms, b.samples));
+
+        // Disjoint bounds: the clock changed, only the new sample is kept.
+        assert_eq!(bounds(-500, 0), b.intersect(bounds(-500, 0)));
+    }
+}
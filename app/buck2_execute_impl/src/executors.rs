@@ -10,6 +10,7 @@
 pub mod action_cache;
 pub mod action_cache_upload_permission_checker;
 pub mod caching;
+pub mod checkpoint;
 pub(crate) mod empty_action_result;
 pub mod hybrid;
 pub mod local;
@@ -0,0 +1,607 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Checkpoints of completed actions, so that a build interrupted by `Ctrl-C`, the machine going
+//! to sleep or the daemon crashing resumes from the actions it already ran.
+//!
+//! When an action runs on RE or hits the action cache, its outputs are in the CAS, and the
+//! deferred materializer may not have downloaded them. The action result is recorded in a sqlite
+//! db in the cache dir, keyed by action digest, until the outputs expire. The next time the same
+//! action is to run, including in a new daemon, its outputs are declared from the checkpoint
+//! instead, like for an action cache hit, without querying the action cache.
+//!
+//! Only actions whose outputs are in the CAS are checkpointed. The outputs of local actions which
+//! were not uploaded to the cache are only on the local disk, and a new daemon runs those actions
+//! again (or gets them from the action cache if they were uploaded).
+//!
+//! The sqlite queries run on a dedicated thread, so they do not block the tokio runtime.
+
+use std::ops::ControlFlow;
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use buck2_action_metadata_proto::action_checkpoint;
+use buck2_action_metadata_proto::ActionCheckpoint;
+use buck2_action_metadata_proto::CheckpointDigest;
+use buck2_core::directory::DirectoryEntry;
+use buck2_core::directory::FingerprintedDirectory;
+use buck2_core::execution_types::executor_config::RemoteExecutorUseCase;
+use buck2_core::fs::artifact_path_resolver::ArtifactFs;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
+use buck2_core::fs::paths::file_name::FileName;
+use buck2_execute::directory::ActionDirectoryMember;
+use buck2_execute::execute::action_digest::ActionDigest;
+use buck2_execute::execute::kind::CommandExecutionKind;
+use buck2_execute::execute::kind::RemoteCommandExecutionDetails;
+use buck2_execute::execute::manager::CommandExecutionManager;
+use buck2_execute::execute::prepared::PreparedCommand;
+use buck2_execute::execute::prepared::PreparedCommandExecutor;
+use buck2_execute::execute::prepared::PreparedCommandOptionalExecutor;
+use buck2_execute::execute::request::ExecutorPreference;
+use buck2_execute::execute::result::CommandExecutionResult;
+use buck2_execute::materialize::materializer::Materializer;
+use buck2_execute::re::action_identity::ReActionIdentity;
+use buck2_execute::re::manager::ManagedRemoteExecutionClient;
+use buck2_execute::re::remote_action_result::ActionCacheResult;
+use buck2_futures::cancellation::CancellationContext;
+use buck2_util::threads::thread_spawn;
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Utc;
+use dupe::Dupe;
+use prost::Message;
+use remote_execution::ActionResultResponse;
+use remote_execution::DigestWithStatus;
+use remote_execution::TActionResult2;
+use remote_execution::TCode;
+use remote_execution::TDigest;
+use remote_execution::TDirectory2;
+use remote_execution::TFile;
+use remote_execution::TStatus;
+use rusqlite::Connection;
+use rusqlite::OptionalExtension;
+use tokio::sync::mpsc;
+use tokio::sync::oneshot;
+
+use crate::re::download::download_action_results;
+use crate::re::download::DownloadResult;
+use crate::re::paranoid_download::ParanoidDownloader;
+
+const TABLE_NAME: &str = "action_checkpoints";
+
+/// How long a checkpoint is kept when there are no output files to take the expiry from.
+const DEFAULT_CHECKPOINT_TTL_SECONDS: i64 = 3600;
+
+#[derive(Debug, buck2_error::Error)]
+#[buck2(tier0)]
+enum ActionCheckpointsError {
+    #[error("The action checkpoints thread exited")]
+    ThreadExited,
+}
+
+/// The sqlite db of the checkpoints. It is only accessed from the checkpoints thread.
+struct ActionCheckpointsDb {
+    connection: Connection,
+}
+
+impl ActionCheckpointsDb {
+    const DB_FILENAME: &'static str = "db.sqlite";
+
+    fn open(dir: &AbsNormPath) -> anyhow::Result<Self> {
+        fs_util::create_dir_all(dir)?;
+        let connection = Connection::open(dir.join(FileName::unchecked_new(Self::DB_FILENAME)))?;
+        if cfg!(unix) {
+            connection.pragma_update(None, "journal_mode", "WAL")?;
+        }
+        // Like for the materializer state, losing the last checkpoints on power loss only means
+        // running those actions again, which is better than an `fsync` during the build.
+        connection.pragma_update(None, "synchronous", "OFF")?;
+        connection
+            .execute(
+                &format!(
+                    "CREATE TABLE IF NOT EXISTS {} (
+                        action_digest   TEXT NOT NULL PRIMARY KEY,
+                        checkpoint      BLOB NOT NULL,
+                        expires_at      INTEGER NOT NULL
+                    )",
+                    TABLE_NAME
+                ),
+                [],
+            )
+            .with_context(|| format!("creating sqlite table {}", TABLE_NAME))?;
+        // Expired checkpoints are of no use.
+        connection.execute(
+            &format!("DELETE FROM {} WHERE expires_at <= ?1", TABLE_NAME),
+            [Utc::now().timestamp()],
+        )?;
+        Ok(Self { connection })
+    }
+
+    /// The action result checkpointed for `action_digest`, if its outputs have not expired.
+    fn get(&self, action_digest: &ActionDigest) -> anyhow::Result<Option<ActionResultResponse>> {
+        let row: Option<(Vec<u8>, i64)> = self
+            .connection
+            .query_row(
+                &format!(
+                    "SELECT checkpoint, expires_at FROM {} WHERE action_digest = ?1",
+                    TABLE_NAME
+                ),
+                [action_digest.to_string()],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .with_context(|| format!("reading from sqlite table {}", TABLE_NAME))?;
+
+        let Some((checkpoint, expires_at)) = row else {
+            return Ok(None);
+        };
+        let ttl = expires_at - Utc::now().timestamp();
+        if ttl <= 0 {
+            return Ok(None);
+        }
+        Ok(Some(ActionResultResponse {
+            action_result: checkpoint_to_action_result(ActionCheckpoint::decode(
+                checkpoint.as_slice(),
+            )?),
+            ttl,
+        }))
+    }
+
+    fn insert(
+        &self,
+        action_digest: &ActionDigest,
+        checkpoint: &ActionCheckpoint,
+        expires_at: DateTime<Utc>,
+    ) -> anyhow::Result<()> {
+        self.connection
+            .execute(
+                &format!(
+                    "INSERT OR REPLACE INTO {} (action_digest, checkpoint, expires_at) VALUES (?1, ?2, ?3)",
+                    TABLE_NAME
+                ),
+                rusqlite::params![
+                    action_digest.to_string(),
+                    checkpoint.encode_to_vec(),
+                    expires_at.timestamp(),
+                ],
+            )
+            .with_context(|| format!("inserting into sqlite table {}", TABLE_NAME))?;
+        Ok(())
+    }
+}
+
+enum ActionCheckpointsCommand {
+    Get(
+        ActionDigest,
+        oneshot::Sender<anyhow::Result<Option<ActionResultResponse>>>,
+    ),
+    Insert(ActionDigest, ActionCheckpoint, DateTime<Utc>),
+}
+
+/// The checkpoints of the actions whose outputs are in the CAS, persisted across daemons.
+pub struct ActionCheckpoints {
+    /// Sends queries to the thread owning the sqlite db. The thread exits when this is dropped.
+    command_sender: mpsc::UnboundedSender<ActionCheckpointsCommand>,
+}
+
+impl ActionCheckpoints {
+    /// Open the checkpoints in `dir`, creating it if needed.
+    pub fn open(dir: &AbsNormPath) -> anyhow::Result<Self> {
+        let db = ActionCheckpointsDb::open(dir)?;
+        let (command_sender, mut command_receiver) = mpsc::unbounded_channel();
+        thread_spawn("buck2-checkpoints", move || {
+            while let Some(command) = command_receiver.blocking_recv() {
+                match command {
+                    ActionCheckpointsCommand::Get(action_digest, sender) => {
+                        let _ignored = sender.send(db.get(&action_digest));
+                    }
+                    ActionCheckpointsCommand::Insert(action_digest, checkpoint, expires_at) => {
+                        if let Err(e) = db.insert(&action_digest, &checkpoint, expires_at) {
+                            tracing::warn!(
+                                "Failed to checkpoint action `{}`: {:#}",
+                                action_digest,
+                                e
+                            );
+                        }
+                    }
+                }
+            }
+        })
+        .context("Cannot start action checkpoints thread")?;
+        Ok(Self { command_sender })
+    }
+
+    /// The action result checkpointed for `action_digest`, if its outputs have not expired.
+    async fn get(
+        &self,
+        action_digest: &ActionDigest,
+    ) -> anyhow::Result<Option<ActionResultResponse>> {
+        let (sender, receiver) = oneshot::channel();
+        self.command_sender
+            .send(ActionCheckpointsCommand::Get(action_digest.dupe(), sender))
+            .map_err(|_| ActionCheckpointsError::ThreadExited)?;
+        receiver
+            .await
+            .map_err(|_| ActionCheckpointsError::ThreadExited)?
+    }
+
+    /// Record `checkpoint` in the background.
+    fn insert(
+        &self,
+        action_digest: &ActionDigest,
+        checkpoint: ActionCheckpoint,
+        expires_at: DateTime<Utc>,
+    ) {
+        if self
+            .command_sender
+            .send(ActionCheckpointsCommand::Insert(
+                action_digest.dupe(),
+                checkpoint,
+                expires_at,
+            ))
+            .is_err()
+        {
+            tracing::warn!(
+                "Failed to checkpoint action `{}`: {}",
+                action_digest,
+                ActionCheckpointsError::ThreadExited
+            );
+        }
+    }
+
+    /// Checkpoint `result` if it succeeded and its outputs are in the CAS. Failing to do so only
+    /// means the action runs again after an interruption, so it does not fail the action.
+    fn record(&self, action_digest: &ActionDigest, result: &CommandExecutionResult) {
+        let Some(action_result) = &result.action_result else {
+            return;
+        };
+        if !result.was_success() {
+            return;
+        }
+        self.insert(
+            action_digest,
+            action_result_to_checkpoint(action_result),
+            outputs_expire(result),
+        );
+    }
+}
+
+/// When the first of the output files of `result` expires from the CAS.
+fn outputs_expire(result: &CommandExecutionResult) -> DateTime<Utc> {
+    let mut expires = None;
+    for value in result.outputs.values() {
+        let files = match value.entry() {
+            DirectoryEntry::Leaf(ActionDirectoryMember::File(f)) => vec![f.digest.expires()],
+            DirectoryEntry::Leaf(_) => Vec::new(),
+            DirectoryEntry::Dir(d) => d
+                .fingerprinted_unordered_walk()
+                .without_paths()
+                .filter_map(|entry| match entry {
+                    DirectoryEntry::Leaf(ActionDirectoryMember::File(f)) => {
+                        Some(f.digest.expires())
+                    }
+                    _ => None,
+                })
+                .collect(),
+        };
+        expires = files.into_iter().chain(expires).min();
+    }
+    expires.unwrap_or_else(|| Utc::now() + Duration::seconds(DEFAULT_CHECKPOINT_TTL_SECONDS))
+}
+
+fn digest_to_checkpoint(digest: &TDigest) -> CheckpointDigest {
+    CheckpointDigest {
+        hash: digest.hash.clone(),
+        size_in_bytes: digest.size_in_bytes,
+    }
+}
+
+fn checkpoint_to_digest(digest: Option<CheckpointDigest>) -> TDigest {
+    let digest = digest.unwrap_or_default();
+    TDigest {
+        hash: digest.hash,
+        size_in_bytes: digest.size_in_bytes,
+        ..Default::default()
+    }
+}
+
+fn action_result_to_checkpoint(action_result: &TActionResult2) -> ActionCheckpoint {
+    ActionCheckpoint {
+        output_files: action_result
+            .output_files
+            .iter()
+            .map(|f| action_checkpoint::File {
+                name: f.name.clone(),
+                digest: Some(digest_to_checkpoint(&f.digest.digest)),
+                executable: f.executable,
+            })
+            .collect(),
+        output_directories: action_result
+            .output_directories
+            .iter()
+            .map(|d| action_checkpoint::Directory {
+                path: d.path.clone(),
+                tree_digest: Some(digest_to_checkpoint(&d.tree_digest)),
+                root_directory_digest: Some(digest_to_checkpoint(&d.root_directory_digest)),
+            })
+            .collect(),
+        stdout_raw: action_result.stdout_raw.clone(),
+        stdout_digest: action_result
+            .stdout_digest
+            .as_ref()
+            .map(digest_to_checkpoint),
+        stderr_raw: action_result.stderr_raw.clone(),
+        stderr_digest: action_result
+            .stderr_digest
+            .as_ref()
+            .map(digest_to_checkpoint),
+    }
+}
+
+fn checkpoint_to_action_result(checkpoint: ActionCheckpoint) -> TActionResult2 {
+    TActionResult2 {
+        output_files: checkpoint
+            .output_files
+            .into_iter()
+            .map(|f| TFile {
+                digest: DigestWithStatus {
+                    digest: checkpoint_to_digest(f.digest),
+                    status: TStatus {
+                        code: TCode::OK,
+                        message: String::new(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                name: f.name,
+                executable: f.executable,
+                ..Default::default()
+            })
+            .collect(),
+        output_directories: checkpoint
+            .output_directories
+            .into_iter()
+            .map(|d| TDirectory2 {
+                path: d.path,
+                tree_digest: checkpoint_to_digest(d.tree_digest),
+                root_directory_digest: checkpoint_to_digest(d.root_directory_digest),
+                ..Default::default()
+            })
+            .collect(),
+        exit_code: 0,
+        stdout_raw: checkpoint.stdout_raw,
+        stdout_digest: checkpoint
+            .stdout_digest
+            .map(|d| checkpoint_to_digest(Some(d))),
+        stderr_raw: checkpoint.stderr_raw,
+        stderr_digest: checkpoint
+            .stderr_digest
+            .map(|d| checkpoint_to_digest(Some(d))),
+        ..Default::default()
+    }
+}
+
+/// Serves actions from their checkpoints, and checkpoints the action cache hits of `inner`.
+pub struct CheckpointChecker {
+    pub checkpoints: Arc<ActionCheckpoints>,
+    pub artifact_fs: ArtifactFs,
+    pub materializer: Arc<dyn Materializer>,
+    pub re_client: ManagedRemoteExecutionClient,
+    pub re_use_case: RemoteExecutorUseCase,
+    pub re_action_key: Option<String>,
+    pub paranoid: Option<ParanoidDownloader>,
+    pub inner: Arc<dyn PreparedCommandOptionalExecutor>,
+}
+
+#[async_trait]
+impl PreparedCommandOptionalExecutor for CheckpointChecker {
+    async fn maybe_execute(
+        &self,
+        command: &PreparedCommand<'_, '_>,
+        manager: CommandExecutionManager,
+        cancellations: &CancellationContext,
+    ) -> ControlFlow<CommandExecutionResult, CommandExecutionManager> {
+        let action_digest = &command.prepared_action.action_and_blobs.action;
+
+        let response = self
+            .checkpoints
+            .get(action_digest)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!(
+                    "Failed to read checkpoint of action `{}`: {:#}",
+                    action_digest,
+                    e
+                );
+                None
+            });
+        let Some(response) = response else {
+            return match self
+                .inner
+                .maybe_execute(command, manager, cancellations)
+                .await
+            {
+                ControlFlow::Break(result) => {
+                    self.checkpoints.record(action_digest, &result);
+                    ControlFlow::Break(result)
+                }
+                ControlFlow::Continue(manager) => ControlFlow::Continue(manager),
+            };
+        };
+
+        let request = command.request;
+        let details = RemoteCommandExecutionDetails {
+            action_digest: action_digest.dupe(),
+            session_id: self.re_client.get_session_id().await.ok(),
+            use_case: self.re_use_case,
+            platform: command.prepared_action.platform.clone(),
+            remote_dep_file_key: *request.remote_dep_file_key(),
+        };
+        let manager = manager.with_execution_kind(CommandExecutionKind::ActionCache {
+            details: details.clone(),
+        });
+        let identity = ReActionIdentity::new(
+            command.target,
+            self.re_action_key.as_deref(),
+            request.paths(),
+        );
+
+        tracing::info!(
+            "Action is checkpointed, skipping execution of:\n```\n$ {}\n```\n for action `{}`",
+            request.all_args_str(),
+            action_digest,
+        );
+        let response = ActionCacheResult(response, buck2_data::CacheType::ActionCache);
+        let DownloadResult::Result(mut res) = download_action_results(
+            request,
+            self.materializer.as_ref(),
+            &self.re_client,
+            self.re_use_case,
+            command.digest_config,
+            manager,
+            &identity,
+            buck2_data::CacheHit {
+                action_digest: action_digest.to_string(),
+                action_key: None,
+                cache_type: buck2_data::CacheType::ActionCache.into(),
+            }
+            .into(),
+            request.paths(),
+            request.outputs(),
+            details,
+            &response,
+            self.paranoid.as_ref(),
+            cancellations,
+            0,
+            &self.artifact_fs,
+            false,
+        )
+        .await;
+        res.action_result = Some(response.0.action_result);
+        ControlFlow::Break(res)
+    }
+}
+
+/// Checkpoints the results of `inner` which are in the CAS.
+pub struct CheckpointingExecutor {
+    pub checkpoints: Arc<ActionCheckpoints>,
+    pub inner: Arc<dyn PreparedCommandExecutor>,
+}
+
+#[async_trait]
+impl PreparedCommandExecutor for CheckpointingExecutor {
+    async fn exec_cmd(
+        &self,
+        command: &PreparedCommand<'_, '_>,
+        manager: CommandExecutionManager,
+        cancellations: &CancellationContext,
+    ) -> CommandExecutionResult {
+        let result = self.inner.exec_cmd(command, manager, cancellations).await;
+        self.checkpoints
+            .record(&command.prepared_action.action_and_blobs.action, &result);
+        result
+    }
+
+    fn is_local_execution_possible(&self, executor_preference: ExecutorPreference) -> bool {
+        self.inner.is_local_execution_possible(executor_preference)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_common::cas_digest::CasDigestConfig;
+    use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+
+    use super::*;
+
+    fn digest(hash: &str) -> TDigest {
+        TDigest {
+            hash: hash.to_owned(),
+            size_in_bytes: 3,
+            ..Default::default()
+        }
+    }
+
+    fn action_result() -> TActionResult2 {
+        TActionResult2 {
+            output_files: vec![TFile {
+                digest: DigestWithStatus {
+                    digest: digest("aaaa"),
+                    ..Default::default()
+                },
+                name: "out/lib.so".to_owned(),
+                executable: true,
+                ..Default::default()
+            }],
+            output_directories: vec![TDirectory2 {
+                path: "out/headers".to_owned(),
+                tree_digest: digest("bbbb"),
+                root_directory_digest: digest("cccc"),
+                ..Default::default()
+            }],
+            stdout_raw: Some(b"built".to_vec()),
+            stderr_digest: Some(digest("dddd")),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_checkpoint_roundtrip() -> anyhow::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let dir = AbsNormPathBuf::new(tempdir.path().join("action_checkpoints"))?;
+        let config = CasDigestConfig::testing_default();
+        let checkpointed = ActionDigest::from_content(b"checkpointed", config);
+        let expired = ActionDigest::from_content(b"expired", config);
+        let missing = ActionDigest::from_content(b"missing", config);
+        let checkpoint = action_result_to_checkpoint(&action_result());
+
+        let db = ActionCheckpointsDb::open(&dir)?;
+        db.insert(&checkpointed, &checkpoint, Utc::now() + Duration::hours(1))?;
+        db.insert(&expired, &checkpoint, Utc::now() - Duration::hours(1))?;
+        drop(db);
+
+        // Checkpoints are kept across daemons.
+        let db = ActionCheckpointsDb::open(&dir)?;
+        let response = db.get(&checkpointed)?.unwrap();
+        assert!(response.ttl > 0);
+        assert_eq!(
+            checkpoint,
+            action_result_to_checkpoint(&response.action_result)
+        );
+        assert!(db.get(&expired)?.is_none());
+        assert!(db.get(&missing)?.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_checkpoints_thread() -> anyhow::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let dir = AbsNormPathBuf::new(tempdir.path().join("action_checkpoints"))?;
+        let config = CasDigestConfig::testing_default();
+        let checkpointed = ActionDigest::from_content(b"checkpointed", config);
+        let checkpoint = action_result_to_checkpoint(&action_result());
+
+        let checkpoints = ActionCheckpoints::open(&dir)?;
+        assert!(checkpoints.get(&checkpointed).await?.is_none());
+        checkpoints.insert(
+            &checkpointed,
+            checkpoint.clone(),
+            Utc::now() + Duration::hours(1),
+        );
+        // Queries are processed in order, so the insert is visible to the next read.
+        let response = checkpoints.get(&checkpointed).await?.unwrap();
+        assert_eq!(
+            checkpoint,
+            action_result_to_checkpoint(&response.action_result)
+        );
+        Ok(())
+    }
+}
@@ -18,6 +18,7 @@ use buck2_common::liveliness_observer::LivelinessGuard;
 use buck2_common::liveliness_observer::LivelinessObserver;
 use buck2_common::liveliness_observer::LivelinessObserverExt;
 use buck2_core::execution_types::executor_config::HybridExecutionLevel;
+use buck2_core::execution_types::executor_config::HybridExecutionPolicy;
 use buck2_events::dispatch::EventDispatcher;
 use buck2_execute::execute::claim::Claim;
 use buck2_execute::execute::claim::ClaimManager;
@@ -99,8 +100,19 @@ where
         &self,
         command: &PreparedCommand<'_, '_>,
     ) -> anyhow::Result<ExecutorPreference> {
-        self.executor_preference
-            .and(command.request.executor_preference())
+        let executor_preference = self
+            .executor_preference
+            .and(command.request.executor_preference())?;
+        // A preference expressed by the executor or the action itself wins over the policy.
+        match command.request.hybrid_policy() {
+            Some(HybridExecutionPolicy::RemoteThenLocal) => {
+                executor_preference.and(ExecutorPreference::RemotePreferred)
+            }
+            Some(HybridExecutionPolicy::LocalThenRemote) => {
+                executor_preference.and(ExecutorPreference::LocalPreferred)
+            }
+            Some(HybridExecutionPolicy::Race) | None => Ok(executor_preference),
+        }
     }
 
     /// Indicate whether an action is too big to run on RE.
@@ -161,6 +173,16 @@ where
             ),
         };
 
+        // A policy for this command overrides how the executor would otherwise schedule it, but
+        // keeps the executor's choice of whether to fall back on failures.
+        let (is_limited, fallback_only, low_pass_filter) = match command.request.hybrid_policy() {
+            None => (is_limited, fallback_only, low_pass_filter),
+            Some(HybridExecutionPolicy::Race) => (false, false, false),
+            Some(
+                HybridExecutionPolicy::RemoteThenLocal | HybridExecutionPolicy::LocalThenRemote,
+            ) => (false, true, false),
+        };
+
         // Note that this only sets up these futures, nothing will happen until they are awaited
         // (this is important in the case where we shouldn't be sending one of them).
         let local_result = self.local_exec_cmd(
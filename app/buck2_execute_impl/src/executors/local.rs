@@ -11,6 +11,7 @@ use std::borrow::Cow;
 use std::ffi::OsStr;
 use std::ffi::OsString;
 use std::ops::ControlFlow;
+use std::path::PathBuf;
 use std::process::Command;
 use std::sync::Arc;
 use std::time::Duration;
@@ -63,10 +64,11 @@ use buck2_execute::knobs::ExecutorGlobalKnobs;
 use buck2_execute::materialize::materializer::MaterializationError;
 use buck2_execute::materialize::materializer::Materializer;
 use buck2_forkserver::client::ForkserverClient;
-use buck2_forkserver::run::gather_output;
+use buck2_forkserver::run::gather_output_with_streaming;
 use buck2_forkserver::run::maybe_absolutize_exe;
 use buck2_forkserver::run::timeout_into_cancellation;
 use buck2_forkserver::run::GatherOutputStatus;
+use buck2_forkserver::run::OutputStream;
 use buck2_futures::cancellable_future::CancellationObserver;
 use buck2_futures::cancellation::CancellationContext;
 use buck2_util::process::background_command;
@@ -145,6 +147,7 @@ impl LocalExecutor {
         env_inheritance: Option<&'a EnvironmentInheritance>,
         liveliness_observer: impl LivelinessObserver + 'static,
         disable_miniperf: bool,
+        on_output: impl FnMut(OutputStream, &[u8]) + Send + 'a,
     ) -> impl futures::future::Future<
         Output = anyhow::Result<(GatherOutputStatus, Vec<u8>, Vec<u8>)>,
     > + Send
@@ -166,16 +169,21 @@ impl LocalExecutor {
                             env,
                             &working_directory,
                             timeout,
+                            // Commands with a timeout get a grace period between `SIGTERM` and
+                            // `SIGKILL` when they are killed.
+                            timeout.and(self.knobs.action_timeout_kill_grace_period_s),
                             env_inheritance,
                             liveliness_observer,
                             self.knobs.enable_miniperf && !disable_miniperf,
+                            self.knobs.background_priority,
+                            on_output,
                         )
                         .await
                     }
 
                     #[cfg(not(unix))]
                     {
-                        let _unused = (forkserver, disable_miniperf);
+                        let _unused = (forkserver, disable_miniperf, on_output);
                         Err(anyhow::anyhow!("Forkserver is not supported off-UNIX"))
                     }
                 }
@@ -200,7 +208,13 @@ impl LocalExecutor {
                     let cancellation =
                         select(timeout.boxed(), alive.boxed()).map(|r| r.factor_first().0);
 
-                    gather_output(cmd, cancellation).await
+                    gather_output_with_streaming(
+                        cmd,
+                        self.knobs.background_priority,
+                        cancellation,
+                        on_output,
+                    )
+                    .await
                 }
                 .with_context(|| format!("Failed to gather output from command: {}", exe)),
             }
@@ -302,29 +316,35 @@ impl LocalExecutor {
             args.join(" "),
         );
 
-        let scratch_path_abs;
+        let tmpdir_path;
 
         let tmpdirs = if let Some(scratch_path) = scratch_path {
-            // For the $TMPDIR - important it is absolute
-            scratch_path_abs = self.artifact_fs.fs().resolve(scratch_path);
+            // For the $TMPDIR - important it is absolute, unless the command must not see paths
+            // that differ between machines. The relative path is resolved from the directory the
+            // command runs in, so it still points to the scratch directory.
+            tmpdir_path = if request.reproducible() {
+                PathBuf::from(request.relative_to_working_directory(scratch_path).as_str())
+            } else {
+                self.artifact_fs.fs().resolve(scratch_path).into_path_buf()
+            };
 
             if cfg!(windows) {
                 const MAX_PATH: usize = 260;
-                if scratch_path_abs.as_os_str().len() > MAX_PATH {
+                if tmpdir_path.as_os_str().len() > MAX_PATH {
                     return manager.error(
                         "scratch_dir_too_long",
                         anyhow::anyhow!(
                             "Scratch directory path is longer than MAX_PATH: {}",
-                            scratch_path_abs
+                            tmpdir_path.display()
                         ),
                     );
                 }
                 vec![
-                    ("TEMP", scratch_path_abs.as_os_str()),
-                    ("TMP", scratch_path_abs.as_os_str()),
+                    ("TEMP", tmpdir_path.as_os_str()),
+                    ("TMP", tmpdir_path.as_os_str()),
                 ]
             } else {
-                vec![("TMPDIR", scratch_path_abs.as_os_str())]
+                vec![("TMPDIR", tmpdir_path.as_os_str())]
             }
         } else {
             vec![]
@@ -372,6 +392,9 @@ impl LocalExecutor {
                 )))
         };
         let liveliness_observer = manager.inner.liveliness_observer.dupe().and(cancellation);
+        let mut output_forwarder = request
+            .stream_output()
+            .map(|label| OutputForwarder::new(label, dispatcher.dupe()));
 
         let (worker, manager) = self
             .initialize_worker(request, manager, dispatcher)
@@ -444,9 +467,17 @@ impl LocalExecutor {
                         request.local_environment_inheritance(),
                         liveliness_observer,
                         request.disable_miniperf(),
+                        |stream, bytes| {
+                            if let Some(forwarder) = &mut output_forwarder {
+                                forwarder.add(stream, bytes);
+                            }
+                        },
                     )
                     .await
                 };
+                if let Some(forwarder) = &mut output_forwarder {
+                    forwarder.flush();
+                }
 
                 let execution_time = execution_start.elapsed();
 
@@ -467,6 +498,18 @@ impl LocalExecutor {
         .boxed()
         .await;
 
+        if let Some(scratch_path) = scratch_path.as_ref().filter(|_| request.declared_scratch()) {
+            // The command is done with its scratch directory, don't leave it behind.
+            let path = self.artifact_fs.fs().resolve(scratch_path);
+            if let Err(e) = self
+                .blocking_executor
+                .execute_io_inline(|| Ok(fs_util::remove_all(&path)?))
+                .await
+            {
+                tracing::warn!("Failed to delete scratch directory `{}`: {:#}", path, e);
+            }
+        }
+
         let (status, stdout, stderr) = match res {
             Ok(res) => res,
             Err(e) => {
@@ -1072,10 +1115,59 @@ impl EnvironmentBuilder for Command {
     }
 }
 
+/// Forwards the output of a running command to the console, one complete line at a time,
+/// prefixed with a label identifying the action.
+struct OutputForwarder<'a> {
+    label: &'a str,
+    dispatcher: EventDispatcher,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
+
+impl<'a> OutputForwarder<'a> {
+    fn new(label: &'a str, dispatcher: EventDispatcher) -> Self {
+        Self {
+            label,
+            dispatcher,
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, stream: OutputStream, bytes: &[u8]) {
+        let buffer = match stream {
+            OutputStream::Stdout => &mut self.stdout,
+            OutputStream::Stderr => &mut self.stderr,
+        };
+        buffer.extend_from_slice(bytes);
+        while let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            Self::emit(self.label, &self.dispatcher, &line[..end]);
+        }
+    }
+
+    /// Emit the last lines if the command did not terminate them.
+    fn flush(&mut self) {
+        for buffer in [&mut self.stdout, &mut self.stderr] {
+            if !buffer.is_empty() {
+                Self::emit(self.label, &self.dispatcher, buffer);
+                buffer.clear();
+            }
+        }
+    }
+
+    fn emit(label: &str, dispatcher: &EventDispatcher, line: &[u8]) {
+        let line = String::from_utf8_lossy(line);
+        dispatcher.console_message(format!("[{}] {}", label, line.trim_end_matches('\r')));
+    }
+}
+
 #[cfg(unix)]
 mod unix {
     use std::os::unix::ffi::OsStrExt;
 
+    use buck2_common::process_priority::BackgroundPriority;
+
     use super::*;
 
     pub async fn exec_via_forkserver(
@@ -1085,9 +1177,12 @@ mod unix {
         env: impl IntoIterator<Item = (impl AsRef<OsStr>, impl AsRef<OsStr>)>,
         working_directory: &AbsPath,
         command_timeout: Option<Duration>,
+        graceful_shutdown_timeout_s: Option<u32>,
         env_inheritance: Option<&EnvironmentInheritance>,
         liveliness_observer: impl LivelinessObserver + 'static,
         enable_miniperf: bool,
+        background_priority: Option<BackgroundPriority>,
+        on_output: impl FnMut(OutputStream, &[u8]),
     ) -> anyhow::Result<(GatherOutputStatus, Vec<u8>, Vec<u8>)> {
         let exe = exe.as_ref();
 
@@ -1104,11 +1199,21 @@ mod unix {
             timeout: command_timeout.try_map(|d| d.try_into())?,
             enable_miniperf,
             std_redirects: None,
-            graceful_shutdown_timeout_s: None,
+            graceful_shutdown_timeout_s,
+            background_priority: background_priority.map(|p| {
+                buck2_forkserver_proto::BackgroundPriority {
+                    nice: p.nice,
+                    idle_io: p.idle_io,
+                }
+            }),
         };
         apply_local_execution_environment(&mut req, working_directory, env, env_inheritance);
         forkserver
-            .execute(req, async move { liveliness_observer.while_alive().await })
+            .execute_with_streaming(
+                req,
+                async move { liveliness_observer.while_alive().await },
+                on_output,
+            )
             .await
     }
 
@@ -1170,6 +1275,7 @@ mod tests {
     use buck2_core::fs::project::ProjectRootTemp;
     use buck2_execute::execute::blocking::testing::DummyBlockingExecutor;
     use buck2_execute::materialize::nodisk::NoDiskMaterializer;
+    use buck2_forkserver::run::gather_output;
     use host_sharing::HostSharingStrategy;
 
     use super::*;
@@ -1312,6 +1418,7 @@ mod tests {
                 None,
                 NoopLivelinessObserver::create(),
                 false,
+                |_, _| {},
             )
             .await?;
         assert!(matches!(status, GatherOutputStatus::Finished { exit_code, .. } if exit_code == 0));
@@ -1348,6 +1455,7 @@ mod tests {
                 Some(&EnvironmentInheritance::empty()),
                 NoopLivelinessObserver::create(),
                 false,
+                |_, _| {},
             )
             .await?;
         assert!(matches!(status, GatherOutputStatus::Finished { exit_code, .. } if exit_code == 0));
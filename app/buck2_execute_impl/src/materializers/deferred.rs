@@ -8,9 +8,11 @@
  */
 
 pub mod clean_stale;
+mod corrupt_scan;
 mod extension;
 mod file_tree;
 mod io_handler;
+mod shared_store;
 mod subscriptions;
 
 #[cfg(test)]
@@ -37,6 +39,7 @@ use buck2_common::liveliness_observer::LivelinessGuard;
 use buck2_core::buck2_env;
 use buck2_core::directory::unordered_entry_walk;
 use buck2_core::directory::DirectoryEntry;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
 use buck2_core::fs::paths::RelativePathBuf;
 use buck2_core::fs::project::ProjectRoot;
 use buck2_core::fs::project_rel_path::ProjectRelativePath;
@@ -77,6 +80,7 @@ use buck2_wrapper_common::invocation_id::TraceId;
 use chrono::DateTime;
 use chrono::Duration;
 use chrono::Utc;
+use dashmap::DashMap;
 use derivative::Derivative;
 use derive_more::Display;
 use dupe::Clone_;
@@ -161,6 +165,9 @@ pub struct DeferredMaterializerAccessor<T: IoHandler + 'static> {
 
     stats: Arc<DeferredMaterializerStats>,
 
+    /// Requested artifacts known to be materialized, see `is_requested_materialized`.
+    materialized_requested: MaterializedRequestedArtifacts,
+
     /// Logs verbose events about materializer to the event log when enabled.
     verbose_materializer_log: bool,
 }
@@ -174,11 +181,60 @@ impl<T: IoHandler> Drop for DeferredMaterializerAccessor<T> {
     }
 }
 
+/// Requested artifacts which are known to be materialized, so that building them again when
+/// they have not changed does not go through the command thread, which would otherwise check
+/// every output of the build each time.
+#[derive(Allocative, Default)]
+struct MaterializedRequestedArtifacts {
+    /// The materialized generation last seen. Entries from older generations are dropped when
+    /// it changes.
+    generation: AtomicU64,
+    /// The value each artifact was materialized with, and the generation it was materialized at.
+    artifacts: DashMap<ProjectRelativePathBuf, (ArtifactValue, u64)>,
+}
+
+impl MaterializedRequestedArtifacts {
+    /// Drop all entries if the materialized generation changed since it was last seen.
+    fn observe_generation(&self, generation: u64) {
+        if self.generation.swap(generation, Ordering::Relaxed) != generation {
+            self.artifacts.clear();
+        }
+    }
+
+    fn is_materialized(
+        &self,
+        path: &ProjectRelativePath,
+        value: &ArtifactValue,
+        generation: u64,
+    ) -> bool {
+        self.observe_generation(generation);
+        match self.artifacts.get(path) {
+            Some(entry) => entry.0 == *value && entry.1 == generation,
+            None => false,
+        }
+    }
+
+    fn record(
+        &self,
+        path: ProjectRelativePathBuf,
+        value: ArtifactValue,
+        generation: u64,
+        current_generation: u64,
+    ) {
+        self.observe_generation(current_generation);
+        if generation == current_generation {
+            self.artifacts.insert(path, (value, generation));
+        }
+    }
+}
+
 /// Statistics we collect while operating the Deferred Materializer.
 #[derive(Allocative, Default)]
 pub struct DeferredMaterializerStats {
     declares: AtomicU64,
     declares_reused: AtomicU64,
+    /// Number of times paths were removed from the tree, exposed as the materialized generation.
+    invalidations: AtomicU64,
 }
 
 fn access_time_update_max_buffer_size() -> anyhow::Result<usize> {
@@ -192,6 +248,11 @@ pub struct DeferredMaterializerConfigs {
     pub update_access_times: AccessTimesUpdates,
     pub verbose_materializer_log: bool,
     pub clean_stale_config: Option<CleanStaleConfig>,
+    /// Check the digests of files downloaded from the CAS, and download corrupted ones again.
+    pub verify_cas_downloads: bool,
+    /// Directory of a content store shared with other isolation dirs and checkouts, used to
+    /// materialize files downloaded from the CAS as hardlinks.
+    pub shared_content_store: Option<AbsNormPathBuf>,
 }
 
 pub struct TtlRefreshConfiguration {
@@ -667,6 +728,9 @@ enum ArtifactMaterializationStage {
         /// Should not be deleted without invalidating DICE nodes, which currently
         /// means killing the daemon.
         active: bool,
+        /// Set when the artifact was declared by this daemon as a download from the CAS,
+        /// meaning it can be downloaded again if it gets corrupted on disk.
+        cas_download: Option<Arc<CasDownloadInfo>>,
     },
 }
 
@@ -702,6 +766,15 @@ pub enum ArtifactMaterializationMethod {
     Test,
 }
 
+impl ArtifactMaterializationMethod {
+    fn cas_download_info(&self) -> Option<Arc<CasDownloadInfo>> {
+        match self {
+            ArtifactMaterializationMethod::CasDownload { info } => Some(info.dupe()),
+            _ => None,
+        }
+    }
+}
+
 trait MaterializationMethodToProto {
     fn to_proto(&self) -> buck2_data::MaterializationMethod;
 }
@@ -969,6 +1042,32 @@ impl<T: IoHandler + Allocative> Materializer for DeferredMaterializerAccessor<T>
         Some(self as _)
     }
 
+    fn materialized_generation(&self) -> Option<u64> {
+        Some(self.stats.invalidations.load(Ordering::Relaxed))
+    }
+
+    fn is_requested_materialized(&self, path: &ProjectRelativePath, value: &ArtifactValue) -> bool {
+        self.materialized_requested.is_materialized(
+            path,
+            value,
+            self.stats.invalidations.load(Ordering::Relaxed),
+        )
+    }
+
+    fn record_requested_materialized(
+        &self,
+        path: ProjectRelativePathBuf,
+        value: ArtifactValue,
+        generation: u64,
+    ) {
+        self.materialized_requested.record(
+            path,
+            value,
+            generation,
+            self.stats.invalidations.load(Ordering::Relaxed),
+        )
+    }
+
     fn log_materializer_state(&self, events: &EventDispatcher) {
         events.instant_event(self.materializer_state_info.clone())
     }
@@ -1034,6 +1133,8 @@ impl DeferredMaterializerAccessor<DefaultIoHandler> {
             re_client_manager,
             io_executor,
             http_client,
+            configs.verify_cas_downloads,
+            configs.shared_content_store,
         ));
 
         let command_processor = {
@@ -1091,6 +1192,7 @@ impl DeferredMaterializerAccessor<DefaultIoHandler> {
             io,
             materializer_state_info,
             stats,
+            materialized_requested: MaterializedRequestedArtifacts::default(),
             verbose_materializer_log: configs.verbose_materializer_log,
         })
     }
@@ -1407,6 +1509,7 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
                     )
                 });
 
+                self.stats.invalidations.fetch_add(1, Ordering::Relaxed);
                 let existing_futs = self
                     .tree
                     .invalidate_paths_and_collect_futures(paths, self.sqlite_db.as_mut());
@@ -1583,6 +1686,7 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
                     metadata,
                     last_access_time: Utc::now(),
                     active: true,
+                    cas_download: None,
                 },
                 processing: Processing::Done(self.version_tracker.next()),
             }),
@@ -1604,6 +1708,7 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
                 ArtifactMaterializationStage::Materialized {
                     metadata,
                     last_access_time,
+                    cas_download,
                     ..
                 } => {
                     // NOTE: This is for testing performance when hitting mismatches with disk
@@ -1631,6 +1736,9 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
                             metadata: metadata.dupe(),
                             last_access_time: *last_access_time,
                             active: true,
+                            cas_download: method
+                                .cas_download_info()
+                                .or_else(|| cas_download.dupe()),
                         };
                         data.deps = deps;
 
@@ -1767,9 +1875,9 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
 
         match &mut data.stage {
             ArtifactMaterializationStage::Materialized {
-                metadata: _,
                 last_access_time,
                 active,
+                ..
             } => {
                 // Treat this case much like a `declare_existing`
                 *active = true;
@@ -2095,10 +2203,7 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
                             tracing::debug!("artifact is already materialized");
                             None
                         }
-                        ArtifactMaterializationStage::Declared {
-                            entry,
-                            method: _method,
-                        } => {
+                        ArtifactMaterializationStage::Declared { entry, method } => {
                             let metadata = ArtifactMetadata::new(entry);
                             // NOTE: We only insert this artifact if there isn't an in-progress cleanup
                             // future on this path.
@@ -2116,6 +2221,7 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
                                 metadata,
                                 last_access_time: timestamp,
                                 active: true,
+                                cas_download: method.cas_download_info(),
                             })
                         }
                     };
@@ -2180,6 +2286,7 @@ impl ArtifactTree {
                             metadata,
                             last_access_time,
                             active: false,
+                            cas_download: None,
                         },
                         processing: Processing::Done(Version(0)),
                     }),
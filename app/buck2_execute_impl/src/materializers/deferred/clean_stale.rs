@@ -8,6 +8,7 @@
  */
 
 use std::collections::HashMap;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -182,6 +183,10 @@ impl CleanStaleArtifactsCommand {
     ) -> anyhow::Result<PendingCleanResult> {
         let (liveliness_observer, liveliness_guard) = LivelinessGuard::create_sync();
         *processor.command_sender.clean_guard.lock() = Some(liveliness_guard);
+        processor
+            .stats
+            .invalidations
+            .fetch_add(1, Ordering::Relaxed);
 
         if let Some(sqlite_db) = processor.sqlite_db.as_mut() {
             if !processor.defer_write_actions {
@@ -545,6 +550,7 @@ impl<'a, T: IoHandler> StaleFinder<'a, T> {
                             active: false,
                             last_access_time,
                             metadata,
+                            ..
                         },
                     ..
                 }) if *last_access_time < self.keep_since_time => {
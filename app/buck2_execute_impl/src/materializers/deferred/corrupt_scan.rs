@@ -0,0 +1,361 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Verification of materialized artifacts against the metadata the materializer recorded for them.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::sync::Arc;
+
+use anyhow::Context as _;
+use buck2_cli_proto::new_generic::CorruptArtifact;
+use buck2_cli_proto::new_generic::CorruptScanResponse;
+use buck2_common::file_ops::FileDigestConfig;
+use buck2_core::directory::DirectoryEntry;
+use buck2_core::directory::FingerprintedDirectory;
+use buck2_core::execution_types::executor_config::RemoteExecutorUseCase;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+use buck2_events::dispatch::get_dispatcher;
+use buck2_execute::artifact_value::ArtifactValue;
+use buck2_execute::digest::CasDigestToReExt;
+use buck2_execute::directory::re_directory_to_re_tree;
+use buck2_execute::directory::re_tree_to_directory;
+use buck2_execute::directory::ActionDirectoryEntry;
+use buck2_execute::directory::ActionDirectoryMember;
+use buck2_execute::directory::ActionSharedDirectory;
+use buck2_execute::directory::INTERNER;
+use buck2_execute::entry::build_entry_from_disk;
+use buck2_execute::materialize::materializer::CasDownloadInfo;
+use buck2_execute::output_size::OutputSize;
+use chrono::TimeZone;
+use chrono::Utc;
+use derivative::Derivative;
+use dupe::Dupe;
+use futures::stream;
+use futures::StreamExt;
+use futures::TryStreamExt;
+use remote_execution as RE;
+use tokio::sync::oneshot;
+use tokio::sync::oneshot::Sender;
+
+use crate::materializers::deferred::extension::ExtensionCommand;
+use crate::materializers::deferred::io_handler::IoHandler;
+use crate::materializers::deferred::ArtifactMaterializationMethod;
+use crate::materializers::deferred::ArtifactMaterializationStage;
+use crate::materializers::deferred::ArtifactMetadata;
+use crate::materializers::deferred::DeferredMaterializerAccessor;
+use crate::materializers::deferred::DeferredMaterializerCommandProcessor;
+use crate::materializers::deferred::MaterializerCommand;
+use crate::materializers::deferred::Processing;
+
+/// How many artifacts are hashed at the same time.
+const CHECK_CONCURRENCY: usize = 16;
+
+/// Collects a sample of the materialized artifacts along with their recorded metadata.
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub(super) struct ListMaterializedArtifacts {
+    sample_rate: f64,
+    /// Mixed into the sampling hash so that each scan checks a different subset of artifacts.
+    seed: u64,
+    #[derivative(Debug = "ignore")]
+    sender: Sender<Vec<MaterializedArtifact>>,
+}
+
+struct MaterializedArtifact {
+    path: ProjectRelativePathBuf,
+    metadata: ArtifactMetadata,
+    /// How to download the artifact again, if it came from the CAS.
+    cas_download: Option<Arc<CasDownloadInfo>>,
+}
+
+impl<T: IoHandler> ExtensionCommand<T> for ListMaterializedArtifacts {
+    fn execute(self: Box<Self>, processor: &mut DeferredMaterializerCommandProcessor<T>) {
+        let mut artifacts = Vec::new();
+        for (path, data) in processor.tree.iter_with_paths() {
+            let (metadata, cas_download) = match &data.stage {
+                ArtifactMaterializationStage::Declared { .. } => continue,
+                ArtifactMaterializationStage::Materialized {
+                    metadata,
+                    cas_download,
+                    ..
+                } => (metadata, cas_download),
+            };
+            // Artifacts that are being materialized or cleaned are expected to not match.
+            if let Processing::Active { .. } = &data.processing {
+                continue;
+            }
+
+            let path = ProjectRelativePathBuf::from(path);
+            if is_sampled(&path, self.sample_rate, self.seed) {
+                artifacts.push(MaterializedArtifact {
+                    path,
+                    metadata: metadata.dupe(),
+                    cas_download: cas_download.dupe(),
+                });
+            }
+        }
+        let _ignored = self.sender.send(artifacts);
+    }
+}
+
+fn is_sampled(path: &ProjectRelativePath, sample_rate: f64, seed: u64) -> bool {
+    if sample_rate >= 1.0 {
+        return true;
+    }
+    let mut hasher = DefaultHasher::new();
+    seed.hash(&mut hasher);
+    path.hash(&mut hasher);
+    (hasher.finish() as f64 / u64::MAX as f64) < sample_rate
+}
+
+pub(super) async fn corrupt_scan<T: IoHandler>(
+    accessor: &DeferredMaterializerAccessor<T>,
+    sample_rate: f64,
+    repair: bool,
+) -> anyhow::Result<CorruptScanResponse> {
+    let (sender, receiver) = oneshot::channel();
+    accessor
+        .command_sender
+        .send(MaterializerCommand::Extension(Box::new(
+            ListMaterializedArtifacts {
+                sample_rate,
+                seed: Utc::now().timestamp_millis() as u64,
+                sender,
+            },
+        )))?;
+    let artifacts = receiver.await.context("No response from materializer")?;
+    let checked = artifacts.len() as u64;
+
+    let io = accessor.io.dupe();
+    let mismatches: Vec<_> = stream::iter(artifacts)
+        .map(|artifact| {
+            let io = io.dupe();
+            async move {
+                let reason = match check_artifact(&*io, &artifact.path, &artifact.metadata).await {
+                    Ok(reason) => reason,
+                    Err(e) => Some(format!("Error checking artifact: {:#}", e)),
+                };
+                reason.map(|reason| (artifact, reason))
+            }
+        })
+        .buffer_unordered(CHECK_CONCURRENCY)
+        .filter_map(|mismatch| async move { mismatch })
+        .collect()
+        .await;
+
+    let mut corrupt = Vec::with_capacity(mismatches.len());
+    for (artifact, reason) in mismatches {
+        let (repaired, repair_error) = if repair {
+            match repair_artifact(accessor, &artifact).await {
+                Ok(()) => (Some(true), None),
+                Err(e) => (Some(false), Some(format!("{:#}", e))),
+            }
+        } else {
+            (None, None)
+        };
+        corrupt.push(CorruptArtifact {
+            path: artifact.path.to_string(),
+            reason,
+            repaired,
+            repair_error,
+        });
+    }
+    corrupt.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(CorruptScanResponse { checked, corrupt })
+}
+
+/// Hashes the artifact on disk and returns how it differs from `metadata`, if it does.
+async fn check_artifact<T: IoHandler>(
+    io: &T,
+    path: &ProjectRelativePath,
+    metadata: &ArtifactMetadata,
+) -> anyhow::Result<Option<String>> {
+    let digest_config = io.digest_config();
+    let (entry, _hashing_info) = build_entry_from_disk(
+        io.fs().resolve(path),
+        FileDigestConfig::build(digest_config.cas_digest_config()),
+        io.io_executor().as_ref(),
+        io.fs().root(),
+    )
+    .await?;
+    let entry = match entry {
+        Some(entry) => entry,
+        None => return Ok(Some("missing on disk".to_owned())),
+    };
+
+    let reason = match (&metadata.0, entry) {
+        (DirectoryEntry::Dir(expected), DirectoryEntry::Dir(actual)) => {
+            let actual = actual.fingerprint(digest_config.as_directory_serializer());
+            let fingerprint = actual.fingerprint();
+            // Directories fingerprinted with another algorithm (e.g. the one used by the RE
+            // backend) cannot be compared by hash, so fall back to comparing sizes.
+            if fingerprint.raw_digest().algorithm() == expected.fingerprint.raw_digest().algorithm()
+            {
+                (*fingerprint != expected.fingerprint).then(|| {
+                    format!(
+                        "expected directory {}, found directory {}",
+                        expected.fingerprint, fingerprint
+                    )
+                })
+            } else {
+                let actual_size = DirectoryEntry::Dir(actual)
+                    .calc_output_count_and_bytes()
+                    .bytes;
+                (actual_size != expected.total_size).then(|| {
+                    format!(
+                        "expected directory of {} bytes, found {} bytes",
+                        expected.total_size, actual_size
+                    )
+                })
+            }
+        }
+        (
+            DirectoryEntry::Leaf(ActionDirectoryMember::File(expected)),
+            DirectoryEntry::Leaf(ActionDirectoryMember::File(actual)),
+        ) => {
+            let digest_matches = if actual.digest.raw_digest().algorithm()
+                == expected.digest.raw_digest().algorithm()
+            {
+                actual.digest == expected.digest
+            } else {
+                actual.digest.size() == expected.digest.size()
+            };
+            if !digest_matches {
+                Some(format!(
+                    "expected file {}, found file {}",
+                    expected.digest, actual.digest
+                ))
+            } else if actual.is_executable != expected.is_executable {
+                Some(format!(
+                    "expected is_executable={}, found is_executable={}",
+                    expected.is_executable, actual.is_executable
+                ))
+            } else {
+                None
+            }
+        }
+        (DirectoryEntry::Leaf(expected), DirectoryEntry::Leaf(actual)) => {
+            (*expected != actual).then(|| format!("expected {}, found {}", expected, actual))
+        }
+        (expected, actual) => Some(format!(
+            "expected {}, found {}",
+            describe_kind(expected),
+            describe_kind(&actual)
+        )),
+    };
+    Ok(reason)
+}
+
+fn describe_kind<D>(entry: &ActionDirectoryEntry<D>) -> &'static str {
+    match entry {
+        DirectoryEntry::Dir(..) => "a directory",
+        DirectoryEntry::Leaf(ActionDirectoryMember::File(..)) => "a file",
+        DirectoryEntry::Leaf(ActionDirectoryMember::Symlink(..)) => "a symlink",
+        DirectoryEntry::Leaf(ActionDirectoryMember::ExternalSymlink(..)) => "an external symlink",
+    }
+}
+
+#[derive(Debug, buck2_error::Error)]
+enum RepairError {
+    #[error(
+        "Artifact was not downloaded from the CAS, so it was deleted instead of repaired. \
+        Run `buck2 kill` and build again to recreate it"
+    )]
+    #[buck2(tier0)]
+    NotFromCas,
+}
+
+/// Deletes the artifact and, if it was downloaded from the CAS, downloads it again. Artifacts
+/// produced any other way (e.g. by a local action) are only deleted, since their content cannot be
+/// fetched. This goes through the command thread so that the materializer state stays consistent
+/// with what is on disk.
+async fn repair_artifact<T: IoHandler>(
+    accessor: &DeferredMaterializerAccessor<T>,
+    artifact: &MaterializedArtifact,
+) -> anyhow::Result<()> {
+    let path = &artifact.path;
+    let entry = match &artifact.cas_download {
+        Some(_) => Some(fetch_entry(&*accessor.io, &artifact.metadata).await?),
+        None => None,
+    };
+
+    let (sender, receiver) = oneshot::channel();
+    accessor
+        .command_sender
+        .send(MaterializerCommand::InvalidateFilePaths(
+            vec![path.clone()],
+            sender,
+            get_dispatcher(),
+        ))?;
+    receiver
+        .await?
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Error deleting corrupted artifact")?;
+
+    let (Some(entry), Some(info)) = (entry, &artifact.cas_download) else {
+        return Err(RepairError::NotFromCas.into());
+    };
+
+    accessor.command_sender.send(MaterializerCommand::Declare(
+        path.clone(),
+        ArtifactValue::from(entry),
+        Box::new(ArtifactMaterializationMethod::CasDownload { info: info.dupe() }),
+        get_dispatcher(),
+    ))?;
+
+    let (sender, receiver) = oneshot::channel();
+    accessor.command_sender.send(MaterializerCommand::Ensure(
+        vec![path.clone()],
+        get_dispatcher(),
+        sender,
+    ))?;
+    receiver
+        .await
+        .context("Receiving materialization future from command thread.")?
+        .try_collect::<()>()
+        .await?;
+    Ok(())
+}
+
+/// Rebuilds the full entry for an artifact from its recorded metadata. For directories, this means
+/// fetching the directory tree from the CAS, since only its fingerprint is kept in memory.
+async fn fetch_entry<T: IoHandler>(
+    io: &T,
+    metadata: &ArtifactMetadata,
+) -> anyhow::Result<ActionDirectoryEntry<ActionSharedDirectory>> {
+    let dir = match &metadata.0 {
+        DirectoryEntry::Leaf(member) => return Ok(DirectoryEntry::Leaf(member.dupe())),
+        DirectoryEntry::Dir(dir) => dir,
+    };
+
+    let use_case = RemoteExecutorUseCase::buck2_default();
+    let connection = io.re_client_manager().get_re_connection();
+    let client = connection.get_client();
+    let root = client
+        .download_typed_blobs::<RE::Directory>(None, vec![dir.fingerprint.to_re()], use_case)
+        .await
+        .and_then(|dirs| dirs.into_iter().next().context("RE response was empty"))
+        .with_context(|| format!("Error downloading dir: {}", dir.fingerprint))?;
+    let tree = re_directory_to_re_tree(root, &client, use_case).await?;
+
+    // The files are downloaded right away, so their expiration time does not matter.
+    let builder =
+        re_tree_to_directory(&tree, &Utc.timestamp_opt(0, 0).unwrap(), io.digest_config())
+            .context("Invalid directory")?;
+    Ok(DirectoryEntry::Dir(
+        builder
+            .fingerprint(io.digest_config().as_directory_serializer())
+            .shared(&*INTERNER),
+    ))
+}
@@ -40,6 +40,7 @@ use tokio_stream::wrappers::UnboundedReceiverStream;
 
 use crate::materializers::deferred::clean_stale::CleanStaleArtifactsCommand;
 use crate::materializers::deferred::clean_stale::CleanStaleArtifactsExtensionCommand;
+use crate::materializers::deferred::corrupt_scan;
 use crate::materializers::deferred::io_handler::create_ttl_refresh;
 use crate::materializers::deferred::io_handler::IoHandler;
 use crate::materializers::deferred::subscriptions::MaterializerSubscriptionOperation;
@@ -424,6 +425,14 @@ impl<T: IoHandler> DeferredMaterializerExtensions for DeferredMaterializerAccess
         recv.await?.await.map(|res| res.into())
     }
 
+    async fn corrupt_scan(
+        &self,
+        sample_rate: f64,
+        repair: bool,
+    ) -> anyhow::Result<buck2_cli_proto::new_generic::CorruptScanResponse> {
+        corrupt_scan::corrupt_scan(self, sample_rate, repair).await
+    }
+
     async fn test_iter(&self, count: usize) -> anyhow::Result<String> {
         let (sender, receiver) = oneshot::channel();
         self.command_sender
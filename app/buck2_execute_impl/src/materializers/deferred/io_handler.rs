@@ -59,6 +59,7 @@ use remote_execution::TDigest;
 use tracing::instrument;
 
 use crate::materializers::deferred::clean_stale::CleanInvalidatedPathRequest;
+use crate::materializers::deferred::shared_store::SharedContentStore;
 use crate::materializers::deferred::ArtifactMaterializationMethod;
 use crate::materializers::deferred::ArtifactMaterializationStage;
 use crate::materializers::deferred::ArtifactTree;
@@ -82,11 +83,49 @@ pub struct DefaultIoHandler {
     /// Executor for blocking IO operations
     io_executor: Arc<dyn BlockingExecutor>,
     http_client: HttpClient,
+    /// Whether to check the digests of files downloaded from the CAS.
+    verify_cas_downloads: bool,
+    shared_content_store: Option<SharedContentStore>,
 }
 
 struct MaterializationStat {
     file_count: u64,
     total_bytes: u64,
+    redownloaded_file_count: u64,
+}
+
+/// How many times files that were corrupted when downloaded from the CAS are downloaded again
+/// before the materialization fails.
+const MAX_CORRUPTED_DOWNLOAD_RETRIES: usize = 2;
+
+#[derive(Debug, buck2_error::Error)]
+enum CasDownloadError {
+    #[error(
+        "{count} file(s) declared by action {origin} did not match their digest after {attempts} download attempts (first: `{path}`, expected {expected}, got {actual})"
+    )]
+    Corrupted {
+        count: usize,
+        origin: String,
+        attempts: usize,
+        path: ProjectRelativePathBuf,
+        expected: String,
+        actual: String,
+    },
+}
+
+/// A file to download from the CAS.
+#[derive(Clone)]
+struct CasDownload {
+    path: ProjectRelativePathBuf,
+    digest: FileDigest,
+    is_executable: bool,
+}
+
+/// A downloaded file whose contents do not match its digest.
+struct CorruptedDownload {
+    download: CasDownload,
+    /// What we found on disk instead.
+    actual: String,
 }
 
 #[async_trait]
@@ -137,6 +176,7 @@ pub trait IoHandler: Sized + Sync + Send + 'static {
     fn read_dir(&self, path: &AbsNormPathBuf) -> Result<ReadDir, IoError>;
     fn buck_out_path(&self) -> &ProjectRelativePathBuf;
     fn re_client_manager(&self) -> &Arc<ReConnectionManager>;
+    fn io_executor(&self) -> &Arc<dyn BlockingExecutor>;
     fn fs(&self) -> &ProjectRoot;
     fn digest_config(&self) -> DigestConfig;
 }
@@ -149,6 +189,8 @@ impl DefaultIoHandler {
         re_client_manager: Arc<ReConnectionManager>,
         io_executor: Arc<dyn BlockingExecutor>,
         http_client: HttpClient,
+        verify_cas_downloads: bool,
+        shared_content_store: Option<AbsNormPathBuf>,
     ) -> Self {
         Self {
             fs,
@@ -157,6 +199,9 @@ impl DefaultIoHandler {
             re_client_manager,
             io_executor,
             http_client,
+            verify_cas_downloads,
+            shared_content_store: shared_content_store
+                .map(|root| SharedContentStore::new(root, digest_config)),
         }
     }
     /// Materializes an `entry` at `path`, using the materialization `method`
@@ -183,59 +228,140 @@ impl DefaultIoHandler {
         // Materialize files
         match method.as_ref() {
             ArtifactMaterializationMethod::CasDownload { info } => {
-                let mut files = Vec::new();
+                let mut downloads = Vec::new();
 
                 {
                     let mut walk = unordered_entry_walk(entry.as_ref());
 
                     while let Some((entry_path, entry)) = walk.next() {
                         if let DirectoryEntry::Leaf(ActionDirectoryMember::File(f)) = entry {
-                            let name = path.join_normalized(entry_path.get())?;
-                            let digest = maybe_tombstone_digest(f.digest.data())?.to_re();
-
-                            tracing::trace!(name = %name, digest = %digest, "push download");
-                            let name = self
-                                .fs
-                                .resolve(&name)
-                                .as_maybe_relativized_str()?
-                                .to_owned();
-
-                            files.push(NamedDigestWithPermissions {
-                                named_digest: NamedDigest {
-                                    name,
-                                    digest,
-                                    ..Default::default()
-                                },
+                            downloads.push(CasDownload {
+                                path: path.join_normalized(entry_path.get())?,
+                                digest: maybe_tombstone_digest(f.digest.data())?.dupe(),
                                 is_executable: f.is_executable,
-                                ..Default::default()
                             });
                         }
                     }
                 }
-                stat.file_count = files.len().try_into().unwrap_or_default();
-                stat.total_bytes = files
-                    .iter()
-                    .map(|x| u64::try_from(x.named_digest.digest.size_in_bytes).unwrap_or_default())
-                    .sum();
+                stat.file_count = downloads.len().try_into().unwrap_or_default();
+                stat.total_bytes = downloads.iter().map(|x| x.digest.size()).sum();
+
+                if let Some(store) = &self.shared_content_store {
+                    downloads = self
+                        .io_executor
+                        .execute_io_inline(|| {
+                            materialize_from_shared_store(store, &self.fs, downloads)
+                        })
+                        .await?;
+                }
+                let downloaded = downloads.clone();
 
                 let connection = self.re_client_manager.get_re_connection();
                 let re_client = connection.get_client();
 
-                re_client
-                    .materialize_files(files, info.re_use_case)
-                    .await
-                    .map_err(|e| match e.downcast_ref::<REClientError>() {
-                        Some(e) if e.code == TCode::NOT_FOUND => MaterializeEntryError::NotFound {
-                            info: info.dupe(),
-                            debug: Arc::from(e.message.as_str()),
-                        },
-                        _ => MaterializeEntryError::Error(e.context({
-                            format!(
-                                "Error materializing files declared by action: {}",
-                                info.origin
-                            )
-                        })),
-                    })?;
+                let mut attempts = 0;
+                loop {
+                    if downloads.is_empty() {
+                        break;
+                    }
+
+                    let mut files = Vec::with_capacity(downloads.len());
+                    for download in &downloads {
+                        let digest = download.digest.to_re();
+                        tracing::trace!(name = %download.path, digest = %digest, "push download");
+                        let name = self
+                            .fs
+                            .resolve(&download.path)
+                            .as_maybe_relativized_str()?
+                            .to_owned();
+
+                        files.push(NamedDigestWithPermissions {
+                            named_digest: NamedDigest {
+                                name,
+                                digest,
+                                ..Default::default()
+                            },
+                            is_executable: download.is_executable,
+                            ..Default::default()
+                        });
+                    }
+
+                    re_client
+                        .materialize_files(files, info.re_use_case)
+                        .await
+                        .map_err(|e| match e.downcast_ref::<REClientError>() {
+                            Some(e) if e.code == TCode::NOT_FOUND => {
+                                MaterializeEntryError::NotFound {
+                                    info: info.dupe(),
+                                    debug: Arc::from(e.message.as_str()),
+                                }
+                            }
+                            _ => MaterializeEntryError::Error(e.context({
+                                format!(
+                                    "Error materializing files declared by action: {}",
+                                    info.origin
+                                )
+                            })),
+                        })?;
+                    attempts += 1;
+
+                    if !self.verify_cas_downloads {
+                        break;
+                    }
+
+                    let corrupted = self
+                        .io_executor
+                        .execute_io_inline(|| {
+                            find_corrupted_downloads(&self.fs, self.digest_config, &downloads)
+                        })
+                        .await?;
+                    let Some(first) = corrupted.first() else {
+                        break;
+                    };
+
+                    if attempts > MAX_CORRUPTED_DOWNLOAD_RETRIES {
+                        return Err(MaterializeEntryError::Error(
+                            CasDownloadError::Corrupted {
+                                count: corrupted.len(),
+                                origin: info.origin.to_string(),
+                                attempts,
+                                path: first.download.path.clone(),
+                                expected: first.download.digest.to_string(),
+                                actual: first.actual.clone(),
+                            }
+                            .into(),
+                        ));
+                    }
+
+                    tracing::warn!(
+                        "Downloading {} corrupted file(s) again (first: `{}`, expected {}, got {})",
+                        corrupted.len(),
+                        first.download.path,
+                        first.download.digest,
+                        first.actual,
+                    );
+
+                    // Delete the corrupted files so that they can be downloaded again.
+                    downloads = corrupted.into_iter().map(|c| c.download).collect();
+                    self.io_executor
+                        .execute_io_inline(|| {
+                            for download in &downloads {
+                                fs_util::remove_file(self.fs.resolve(&download.path))?;
+                            }
+                            Ok(())
+                        })
+                        .await?;
+                    stat.redownloaded_file_count += downloads.len() as u64;
+                }
+
+                if let Some(store) = &self.shared_content_store {
+                    self.io_executor
+                        .execute_io_inline(|| {
+                            add_to_shared_store(store, &self.fs, &downloaded);
+                            Ok(())
+                        })
+                        .await?;
+                }
             }
             ArtifactMaterializationMethod::HttpDownload { info } => {
                 async {
@@ -403,6 +529,7 @@ impl IoHandler for DefaultIoHandler {
                 let mut stat = MaterializationStat {
                     file_count: 0,
                     total_bytes: 0,
+                    redownloaded_file_count: 0,
                 };
                 let res = self
                     .materialize_entry_span(path, method.dupe(), entry, &mut stat, cancellations)
@@ -419,6 +546,7 @@ impl IoHandler for DefaultIoHandler {
                         success: error.is_none(),
                         error,
                         method: Some(method.to_proto() as i32),
+                        redownloaded_file_count: stat.redownloaded_file_count,
                     },
                 )
             })
@@ -447,6 +575,10 @@ impl IoHandler for DefaultIoHandler {
         &self.re_client_manager
     }
 
+    fn io_executor(&self) -> &Arc<dyn BlockingExecutor> {
+        &self.io_executor
+    }
+
     fn fs(&self) -> &ProjectRoot {
         &self.fs
     }
@@ -490,6 +622,89 @@ fn maybe_tombstone_digest(digest: &FileDigest) -> anyhow::Result<&FileDigest> {
     Ok(digest)
 }
 
+/// Check downloaded files against the digests they were downloaded for.
+fn find_corrupted_downloads(
+    fs: &ProjectRoot,
+    digest_config: DigestConfig,
+    downloads: &[CasDownload],
+) -> anyhow::Result<Vec<CorruptedDownload>> {
+    let mut corrupted = Vec::new();
+    for download in downloads {
+        let path = fs.resolve(&download.path);
+        let size = match fs_util::symlink_metadata_if_exists(&path)? {
+            Some(meta) => meta.len(),
+            None => {
+                corrupted.push(CorruptedDownload {
+                    download: download.clone(),
+                    actual: "missing file".to_owned(),
+                });
+                continue;
+            }
+        };
+        if size != download.digest.size() {
+            corrupted.push(CorruptedDownload {
+                download: download.clone(),
+                actual: format!("{} bytes", size),
+            });
+            continue;
+        }
+
+        // We can only compare hashes if we hash with the same algorithm as the digest. Otherwise,
+        // matching sizes are the best we can check.
+        let actual = FileDigest::from_reader(
+            fs_util::open_file(&path)?,
+            digest_config.cas_digest_config(),
+        )?;
+        if actual.raw_digest().algorithm() == download.digest.raw_digest().algorithm()
+            && actual != download.digest
+        {
+            corrupted.push(CorruptedDownload {
+                download: download.clone(),
+                actual: actual.to_string(),
+            });
+        }
+    }
+    Ok(corrupted)
+}
+
+/// Materialize the files available in the shared content store, and return the ones which still
+/// need to be downloaded.
+fn materialize_from_shared_store(
+    store: &SharedContentStore,
+    fs: &ProjectRoot,
+    downloads: Vec<CasDownload>,
+) -> anyhow::Result<Vec<CasDownload>> {
+    let mut missing = Vec::new();
+    for download in downloads {
+        if !store.materialize(
+            &download.digest,
+            download.is_executable,
+            &fs.resolve(&download.path),
+        )? {
+            missing.push(download);
+        }
+    }
+    Ok(missing)
+}
+
+/// Add downloaded files to the shared content store. This is best effort: failing to do so does
+/// not fail the materialization.
+fn add_to_shared_store(store: &SharedContentStore, fs: &ProjectRoot, downloads: &[CasDownload]) {
+    for download in downloads {
+        if let Err(e) = store.insert(
+            &download.digest,
+            download.is_executable,
+            &fs.resolve(&download.path),
+        ) {
+            tracing::warn!(
+                "Failed to add `{}` to the shared content store: {:#}",
+                download.path,
+                e
+            );
+        }
+    }
+}
+
 /// Spawn a task to refresh TTLs.
 pub(super) fn create_ttl_refresh(
     tree: &ArtifactTree,
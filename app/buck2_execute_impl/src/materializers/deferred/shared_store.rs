@@ -0,0 +1,205 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! A content-addressed store of files downloaded from the CAS, shared by several isolation dirs
+//! or checkouts on the same machine.
+//!
+//! Files are materialized out of the store as hardlinks (or copies when the store and the
+//! buck-out are on different filesystems), so identical outputs only use disk space once. Since
+//! hardlinks share their contents, blobs are made read-only when they are added, which also
+//! makes the outputs linked to them read-only. Blobs are still hashed before being used, so one
+//! which was modified anyway (e.g. after a `chmod`) is dropped rather than materialized.
+
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+use buck2_common::file_ops::FileDigest;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+use buck2_core::fs::paths::forward_rel_path::ForwardRelativePathBuf;
+use buck2_execute::digest_config::DigestConfig;
+
+pub(super) struct SharedContentStore {
+    root: AbsNormPathBuf,
+    digest_config: DigestConfig,
+}
+
+impl SharedContentStore {
+    pub(super) fn new(root: AbsNormPathBuf, digest_config: DigestConfig) -> Self {
+        Self {
+            root,
+            digest_config,
+        }
+    }
+
+    /// Where a blob lives in the store. Executable and non-executable files are stored separately
+    /// since hardlinks share their permissions.
+    fn blob_path(
+        &self,
+        digest: &FileDigest,
+        is_executable: bool,
+    ) -> anyhow::Result<AbsNormPathBuf> {
+        let hash = digest.raw_digest().to_string();
+        let path = ForwardRelativePathBuf::try_from(format!(
+            "{}/{}/{}_{}{}",
+            digest.raw_digest().algorithm(),
+            &hash[..2],
+            hash,
+            digest.size(),
+            if is_executable { "_x" } else { "" },
+        ))?;
+        Ok(self.root.join(path))
+    }
+
+    /// Materialize `dest` from the store. Returns `false` if the store does not have the file.
+    pub(super) fn materialize(
+        &self,
+        digest: &FileDigest,
+        is_executable: bool,
+        dest: &AbsNormPath,
+    ) -> anyhow::Result<bool> {
+        let blob = self.blob_path(digest, is_executable)?;
+        let Some(meta) = fs_util::symlink_metadata_if_exists(&blob)? else {
+            return Ok(false);
+        };
+        if !self.is_intact(&blob, meta.len(), digest)? {
+            // Somebody modified an output in place: the blob is corrupted.
+            tracing::warn!("Removing corrupted blob `{}` from the shared store", blob);
+            fs_util::remove_file(&blob)?;
+            return Ok(false);
+        }
+        link_or_copy(&blob, dest)?;
+        Ok(true)
+    }
+
+    /// Whether the contents of `blob` still match `digest`.
+    fn is_intact(
+        &self,
+        blob: &AbsNormPath,
+        size: u64,
+        digest: &FileDigest,
+    ) -> anyhow::Result<bool> {
+        if size != digest.size() {
+            return Ok(false);
+        }
+        let actual = FileDigest::from_reader(
+            fs_util::open_file(blob)?,
+            self.digest_config.cas_digest_config(),
+        )?;
+        // Hashes can only be compared if the store hashes with the algorithm of the digest.
+        Ok(actual.raw_digest().algorithm() != digest.raw_digest().algorithm() || actual == *digest)
+    }
+
+    /// Add a file which was just downloaded to the store, unless it is there already.
+    pub(super) fn insert(
+        &self,
+        digest: &FileDigest,
+        is_executable: bool,
+        src: &AbsNormPath,
+    ) -> anyhow::Result<()> {
+        static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let blob = self.blob_path(digest, is_executable)?;
+        if fs_util::try_exists(&blob)? {
+            return Ok(());
+        }
+        if let Some(parent) = blob.parent() {
+            fs_util::create_dir_all(parent)?;
+        }
+
+        // Several daemons may insert the same blob concurrently, so the blob is only ever created
+        // by a rename.
+        let tmp = AbsNormPathBuf::new(blob.as_path().with_extension(format!(
+            "tmp.{}.{}",
+            std::process::id(),
+            TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        )))?;
+        link_or_copy(src, &tmp)?;
+        let mut permissions = fs_util::symlink_metadata(&tmp)?.permissions();
+        permissions.set_readonly(true);
+        fs_util::set_permissions(&tmp, permissions)?;
+        fs_util::rename(&tmp, &blob)?;
+        Ok(())
+    }
+}
+
+fn link_or_copy(src: &AbsNormPath, dest: &AbsNormPath) -> anyhow::Result<()> {
+    if fs_util::hard_link(src, dest).is_err() {
+        fs_util::copy(src, dest)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_common::cas_digest::CasDigestConfig;
+
+    use super::*;
+
+    fn digest(data: &[u8]) -> FileDigest {
+        FileDigest::from_content(data, CasDigestConfig::testing_default())
+    }
+
+    fn store(root: &AbsNormPath) -> SharedContentStore {
+        SharedContentStore::new(
+            root.join(ForwardRelativePathBuf::unchecked_new("store".to_owned())),
+            DigestConfig::testing_default(),
+        )
+    }
+
+    #[test]
+    fn test_insert_and_materialize() -> anyhow::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let root = AbsNormPathBuf::new(tempdir.path().to_path_buf())?;
+        let store = store(&root);
+
+        let src = root.join(ForwardRelativePathBuf::unchecked_new("src".to_owned()));
+        let dest = root.join(ForwardRelativePathBuf::unchecked_new("dest".to_owned()));
+        fs_util::write(&src, b"data")?;
+        let digest = digest(b"data");
+
+        assert!(!store.materialize(&digest, false, &dest)?);
+        store.insert(&digest, false, &src)?;
+        // Inserting twice is fine.
+        store.insert(&digest, false, &src)?;
+        // Executable files are stored separately.
+        assert!(!store.materialize(&digest, true, &dest)?);
+
+        assert!(store.materialize(&digest, false, &dest)?);
+        assert_eq!("data", fs_util::read_to_string(&dest)?);
+        // Blobs, and so the outputs linked to them, cannot be modified in place.
+        assert!(fs_util::symlink_metadata(&dest)?.permissions().readonly());
+        Ok(())
+    }
+
+    #[test]
+    fn test_corrupted_blob() -> anyhow::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let root = AbsNormPathBuf::new(tempdir.path().to_path_buf())?;
+        let store = store(&root);
+
+        let src = root.join(ForwardRelativePathBuf::unchecked_new("src".to_owned()));
+        let dest = root.join(ForwardRelativePathBuf::unchecked_new("dest".to_owned()));
+        fs_util::write(&src, b"data")?;
+        let digest = digest(b"data");
+        store.insert(&digest, false, &src)?;
+
+        // `src` is a hardlink to the blob: modifying it, after making it writable again,
+        // corrupts the blob, even without changing its size.
+        let mut permissions = fs_util::symlink_metadata(&src)?.permissions();
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        fs_util::set_permissions(&src, permissions)?;
+        fs_util::write(&src, b"DATA")?;
+        assert!(!store.materialize(&digest, false, &dest)?);
+        assert!(!fs_util::try_exists(&dest)?);
+        Ok(())
+    }
+}
@@ -326,6 +326,10 @@ mod state_machine {
             unimplemented!()
         }
 
+        fn io_executor(&self) -> &Arc<dyn BlockingExecutor> {
+            unimplemented!()
+        }
+
         fn fs(&self) -> &ProjectRoot {
             &self.fs
         }
@@ -1327,3 +1331,32 @@ fn test_materialize_stack_display() {
     let s = MaterializeStack::Child(&s, ProjectRelativePath::new("bar/baz").unwrap());
     assert_eq!("foo -> bar/baz", s.to_string());
 }
+
+#[test]
+fn test_materialized_requested_artifacts() {
+    let path = ProjectRelativePathBuf::unchecked_new("buck-out/v2/gen/foo".to_owned());
+    let file = |content: &str| {
+        ArtifactValue::file(FileMetadata {
+            digest: TrackedFileDigest::from_content(
+                content.as_bytes(),
+                DigestConfig::testing_default().cas_digest_config(),
+            ),
+            is_executable: false,
+        })
+    };
+    let artifacts = MaterializedRequestedArtifacts::default();
+
+    artifacts.record(path.clone(), file("a"), 1, 1);
+    assert!(artifacts.is_materialized(&path, &file("a"), 1));
+    assert!(!artifacts.is_materialized(&path, &file("b"), 1));
+
+    // Paths were invalidated while materializing.
+    artifacts.record(path.clone(), file("a"), 1, 2);
+    assert!(!artifacts.is_materialized(&path, &file("a"), 2));
+
+    artifacts.record(path.clone(), file("a"), 2, 2);
+    assert!(artifacts.is_materialized(&path, &file("a"), 2));
+    // Paths were invalidated since.
+    assert!(!artifacts.is_materialized(&path, &file("a"), 3));
+    assert!(artifacts.artifacts.is_empty());
+}
@@ -26,6 +26,7 @@ use dice::DiceComputations;
 
 mod bundled;
 mod git;
+mod lockfile;
 
 struct ConcreteExternalCellsImpl;
 
@@ -50,6 +51,9 @@ impl buck2_common::external_cells::ExternalCellsImpl for ConcreteExternalCellsIm
             ExternalCellOrigin::Git(setup) => {
                 Ok(git::get_file_ops_delegate(ctx, cell_name, setup).await? as _)
             }
+            ExternalCellOrigin::Lockfile(setup) => {
+                Ok(lockfile::get_file_ops_delegate(ctx, cell_name, setup).await? as _)
+            }
         }
     }
 
@@ -90,6 +94,9 @@ impl buck2_common::external_cells::ExternalCellsImpl for ConcreteExternalCellsIm
         let materialized_path = match origin {
             ExternalCellOrigin::Bundled(cell) => bundled::materialize_all(ctx, cell).await?,
             ExternalCellOrigin::Git(setup) => git::materialize_all(ctx, cell, setup).await?,
+            ExternalCellOrigin::Lockfile(setup) => {
+                lockfile::materialize_all(ctx, cell, setup).await?
+            }
         };
 
         io.project_root().copy(&materialized_path, &dest_path)
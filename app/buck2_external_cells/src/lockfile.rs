@@ -0,0 +1,638 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Lockfile backed external cells.
+//!
+//! The cell is synthesized in memory from an ecosystem lockfile checked into the repo: its root
+//! contains a single build file which loads a user provided function and invokes it once per
+//! locked package. That function plays the role of a "repository rule" and is free to declare
+//! whatever targets it needs for the package. Since the lockfile is read through DICE, editing it
+//! invalidates the generated build file without having to re-run any offline generator.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Arc;
+
+use anyhow::Context;
+use buck2_build_api::actions::artifact::get_artifact_fs::GetArtifactFs;
+use buck2_common::dice::cells::HasCellResolver;
+use buck2_common::dice::file_ops::delegate::FileOpsDelegate;
+use buck2_common::dice::file_ops::DiceFileComputations;
+use buck2_common::file_ops::FileMetadata;
+use buck2_common::file_ops::FileType;
+use buck2_common::file_ops::RawDirEntry;
+use buck2_common::file_ops::RawPathMetadata;
+use buck2_common::file_ops::TrackedFileDigest;
+use buck2_core::cells::external::ExternalCellOrigin;
+use buck2_core::cells::external::LockfileCellSetup;
+use buck2_core::cells::external::LockfileFormat;
+use buck2_core::cells::name::CellName;
+use buck2_core::cells::paths::CellRelativePath;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+use buck2_execute::digest_config::DigestConfig;
+use buck2_execute::digest_config::HasDigestConfig;
+use buck2_execute::materialize::materializer::HasMaterializer;
+use buck2_execute::materialize::materializer::WriteRequest;
+use cmp_any::PartialEqAny;
+use dice::CancellationContext;
+use dice::DiceComputations;
+use dice::Key;
+use dupe::Dupe;
+
+/// Name of the build file generated at the root of the cell.
+const GENERATED_BUILDFILE: &str = "BUCK";
+
+#[derive(buck2_error::Error, Debug)]
+enum LockfileError {
+    #[error("Error parsing {0} lockfile `{1}`")]
+    #[buck2(input)]
+    Parse(LockfileFormat, String),
+    #[error("Invalid requirement `{0}` in `{1}`, expected `name==version`")]
+    #[buck2(input)]
+    InvalidRequirement(String, String),
+    #[error("Path not found in lockfile cell: `{0}`")]
+    MissingFile(String),
+    #[error("Lockfile cells only contain generated files at their root, got `{0}`")]
+    ExpectedRoot(String),
+}
+
+/// A single package pinned by a lockfile, in an ecosystem agnostic form.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct LockedPackage {
+    pub(crate) name: String,
+    pub(crate) version: String,
+    /// Where the package is fetched from, if the lockfile records it.
+    pub(crate) source: Option<String>,
+    /// Checksums in whatever form the lockfile uses (e.g. `sha256:...` or SRI hashes).
+    pub(crate) checksums: Vec<String>,
+}
+
+fn parse_cargo_lock(contents: &str) -> anyhow::Result<Vec<LockedPackage>> {
+    #[derive(serde::Deserialize)]
+    struct CargoLock {
+        #[serde(default)]
+        package: Vec<CargoPackage>,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct CargoPackage {
+        name: String,
+        version: String,
+        source: Option<String>,
+        checksum: Option<String>,
+    }
+
+    let lock: CargoLock = toml::from_str(contents)?;
+    Ok(lock
+        .package
+        .into_iter()
+        // Packages without a source are members of the workspace the lockfile belongs to, they
+        // are not third-party code.
+        .filter(|p| p.source.is_some())
+        .map(|p| LockedPackage {
+            name: p.name,
+            version: p.version,
+            source: p.source,
+            checksums: p
+                .checksum
+                .into_iter()
+                .map(|c| format!("sha256:{}", c))
+                .collect(),
+        })
+        .collect())
+}
+
+fn parse_package_lock_json(contents: &str) -> anyhow::Result<Vec<LockedPackage>> {
+    #[derive(serde::Deserialize)]
+    struct PackageLock {
+        // `lockfileVersion` 2 and 3
+        #[serde(default)]
+        packages: BTreeMap<String, NpmPackage>,
+        // `lockfileVersion` 1
+        #[serde(default)]
+        dependencies: BTreeMap<String, NpmPackage>,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct NpmPackage {
+        version: Option<String>,
+        resolved: Option<String>,
+        integrity: Option<String>,
+        #[serde(default)]
+        link: bool,
+        #[serde(default)]
+        dependencies: BTreeMap<String, serde_json::Value>,
+    }
+
+    fn make(name: &str, p: NpmPackage) -> Option<LockedPackage> {
+        if p.link {
+            return None;
+        }
+        Some(LockedPackage {
+            name: name.to_owned(),
+            version: p.version?,
+            source: p.resolved,
+            checksums: p.integrity.into_iter().collect(),
+        })
+    }
+
+    fn collect_v1(deps: BTreeMap<String, NpmPackage>, out: &mut Vec<LockedPackage>) {
+        for (name, mut p) in deps {
+            // Nested `dependencies` in v1 lockfiles are full package entries.
+            let nested = std::mem::take(&mut p.dependencies)
+                .into_iter()
+                .filter_map(|(k, v)| Some((k, serde_json::from_value(v).ok()?)))
+                .collect();
+            out.extend(make(&name, p));
+            collect_v1(nested, out);
+        }
+    }
+
+    let lock: PackageLock = serde_json::from_str(contents)?;
+    let mut out = Vec::new();
+    if !lock.packages.is_empty() {
+        for (path, p) in lock.packages {
+            // The empty key is the project itself.
+            let Some((_, name)) = path.rsplit_once("node_modules/") else {
+                continue;
+            };
+            out.extend(make(name, p));
+        }
+    } else {
+        collect_v1(lock.dependencies, &mut out);
+    }
+    Ok(out)
+}
+
+/// Parses a fully pinned requirements file, as produced by e.g. `pip-compile --generate-hashes`.
+fn parse_requirements_lock(contents: &str, lockfile: &str) -> anyhow::Result<Vec<LockedPackage>> {
+    let mut out = Vec::new();
+    // Join line continuations first, hashes are usually listed on their own lines.
+    let joined = contents.replace("\\\r\n", " ").replace("\\\n", " ");
+    for line in joined.lines() {
+        let line = match line.split_once(" #") {
+            Some((line, _comment)) => line,
+            None => line,
+        }
+        .trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('-') {
+            continue;
+        }
+
+        let mut words = line.split_whitespace();
+        let requirement = words.next().unwrap_or_default();
+        // Environment markers don't affect which package is pinned.
+        let requirement = requirement.split(';').next().unwrap_or_default();
+        let (name, version) = requirement.split_once("==").ok_or_else(|| {
+            LockfileError::InvalidRequirement(requirement.to_owned(), lockfile.to_owned())
+        })?;
+        // Drop extras, `foo[bar]==1.0` pins `foo`.
+        let name = name.split('[').next().unwrap_or_default();
+        let checksums = words
+            .filter_map(|w| w.strip_prefix("--hash="))
+            .map(ToOwned::to_owned)
+            .collect();
+        out.push(LockedPackage {
+            name: name.to_owned(),
+            version: version.to_owned(),
+            source: None,
+            checksums,
+        });
+    }
+    Ok(out)
+}
+
+pub(crate) fn parse_lockfile(
+    format: LockfileFormat,
+    lockfile: &str,
+    contents: &str,
+) -> anyhow::Result<Vec<LockedPackage>> {
+    let mut packages = match format {
+        LockfileFormat::Cargo => parse_cargo_lock(contents),
+        LockfileFormat::Npm => parse_package_lock_json(contents),
+        LockfileFormat::Python => parse_requirements_lock(contents, lockfile),
+    }
+    .with_context(|| LockfileError::Parse(format, lockfile.to_owned()))?;
+    // Make the generated build file independent of the lockfile's ordering, and drop packages
+    // that appear more than once (e.g. the same npm package hoisted at several paths).
+    packages.sort();
+    packages.dedup();
+    Ok(packages)
+}
+
+fn starlark_string(s: &str) -> String {
+    // JSON string literals are valid Starlark string literals.
+    serde_json::to_string(s).unwrap()
+}
+
+pub(crate) fn generate_buildfile(setup: &LockfileCellSetup, packages: &[LockedPackage]) -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "# @{} by buck2 from `{}`, do not edit.",
+        "generated", setup.lockfile
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "load({}, {})",
+        starlark_string(&setup.rule_bzl),
+        starlark_string(&setup.rule_name)
+    )
+    .unwrap();
+    for p in packages {
+        writeln!(out).unwrap();
+        writeln!(out, "{}(", setup.rule_name).unwrap();
+        writeln!(
+            out,
+            "    ecosystem = {},",
+            starlark_string(&setup.format.to_string())
+        )
+        .unwrap();
+        writeln!(out, "    name = {},", starlark_string(&p.name)).unwrap();
+        writeln!(out, "    version = {},", starlark_string(&p.version)).unwrap();
+        match &p.source {
+            Some(source) => writeln!(out, "    source = {},", starlark_string(source)).unwrap(),
+            None => writeln!(out, "    source = None,").unwrap(),
+        }
+        let checksums: Vec<_> = p.checksums.iter().map(|c| starlark_string(c)).collect();
+        writeln!(out, "    checksums = [{}],", checksums.join(", ")).unwrap();
+        writeln!(out, ")").unwrap();
+    }
+    out
+}
+
+#[derive(Clone, PartialEq, Eq, allocative::Allocative)]
+struct GeneratedFile {
+    contents: String,
+    metadata: FileMetadata,
+}
+
+#[derive(allocative::Allocative, PartialEq, Eq)]
+pub(crate) struct LockfileFileOpsDelegate {
+    // All generated files live at the root of the cell.
+    files: BTreeMap<String, GeneratedFile>,
+}
+
+impl LockfileFileOpsDelegate {
+    fn new(files: impl IntoIterator<Item = (String, String)>, digest_config: DigestConfig) -> Self {
+        let digest_config = digest_config.cas_digest_config().source_files_config();
+        let files = files
+            .into_iter()
+            .map(|(name, contents)| {
+                let metadata = FileMetadata {
+                    digest: TrackedFileDigest::from_content(contents.as_bytes(), digest_config),
+                    is_executable: false,
+                };
+                (name, GeneratedFile { contents, metadata })
+            })
+            .collect();
+        Self { files }
+    }
+
+    fn get_file(&self, path: &CellRelativePath) -> Option<&GeneratedFile> {
+        if path.is_empty() || path.as_str().contains('/') {
+            return None;
+        }
+        self.files.get(path.as_str())
+    }
+}
+
+#[async_trait::async_trait]
+impl FileOpsDelegate for LockfileFileOpsDelegate {
+    async fn read_file_if_exists(
+        &self,
+        path: &'async_trait CellRelativePath,
+    ) -> anyhow::Result<Option<String>> {
+        Ok(self.get_file(path).map(|f| f.contents.clone()))
+    }
+
+    /// Return the list of file outputs, sorted.
+    async fn read_dir(
+        &self,
+        path: &'async_trait CellRelativePath,
+    ) -> anyhow::Result<Vec<RawDirEntry>> {
+        if !path.is_empty() {
+            return Err(if self.get_file(path).is_some() {
+                LockfileError::ExpectedRoot(path.to_string()).into()
+            } else {
+                LockfileError::MissingFile(path.to_string()).into()
+            });
+        }
+        // `BTreeMap` iteration is already sorted.
+        Ok(self
+            .files
+            .keys()
+            .map(|name| RawDirEntry {
+                file_name: name.clone(),
+                file_type: FileType::File,
+            })
+            .collect())
+    }
+
+    async fn read_path_metadata_if_exists(
+        &self,
+        path: &'async_trait CellRelativePath,
+    ) -> anyhow::Result<Option<RawPathMetadata>> {
+        if path.is_empty() {
+            return Ok(Some(RawPathMetadata::Directory));
+        }
+        Ok(self
+            .get_file(path)
+            .map(|f| RawPathMetadata::File(f.metadata.clone())))
+    }
+
+    fn eq_token(&self) -> PartialEqAny {
+        PartialEqAny::new(self)
+    }
+}
+
+async fn declare_all_source_artifacts(
+    ctx: &mut DiceComputations<'_>,
+    setup: &LockfileCellSetup,
+    ops: &LockfileFileOpsDelegate,
+) -> anyhow::Result<()> {
+    let artifact_fs = ctx.get_artifact_fs().await?;
+    let buck_out_resolver = artifact_fs.buck_out_path_resolver();
+
+    let requests: Vec<_> = ops
+        .files
+        .iter()
+        .map(|(name, file)| WriteRequest {
+            path: buck_out_resolver.resolve_external_cell_source(
+                CellRelativePath::unchecked_new(name),
+                ExternalCellOrigin::Lockfile(setup.dupe()),
+            ),
+            content: file.contents.as_bytes().to_vec(),
+            is_executable: false,
+        })
+        .collect();
+
+    let materializer = ctx.per_transaction_data().get_materializer();
+    materializer
+        .declare_write(Box::new(move || Ok(requests)))
+        .await
+        .map(|_| ())
+}
+
+pub(crate) async fn get_file_ops_delegate(
+    ctx: &mut DiceComputations<'_>,
+    cell_name: CellName,
+    setup: LockfileCellSetup,
+) -> anyhow::Result<Arc<LockfileFileOpsDelegate>> {
+    #[derive(
+        dupe::Dupe,
+        Clone,
+        Debug,
+        derive_more::Display,
+        PartialEq,
+        Eq,
+        Hash,
+        allocative::Allocative
+    )]
+    #[display(fmt = "({}, {})", _0, _1)]
+    struct LockfileFileOpsDelegateKey(CellName, LockfileCellSetup);
+
+    #[async_trait::async_trait]
+    impl Key for LockfileFileOpsDelegateKey {
+        type Value = buck2_error::Result<Arc<LockfileFileOpsDelegate>>;
+
+        async fn compute(
+            &self,
+            ctx: &mut DiceComputations,
+            _cancellations: &CancellationContext,
+        ) -> Self::Value {
+            let setup = &self.1;
+            let cells = ctx.get_cell_resolver().await?;
+            let lockfile_path = cells.get_cell_path(ProjectRelativePath::new(&setup.lockfile)?)?;
+            // Reading through DICE makes this key depend on the lockfile's contents.
+            let contents = DiceFileComputations::read_file(ctx, lockfile_path.as_ref()).await?;
+            let packages = parse_lockfile(setup.format, &setup.lockfile, &contents)?;
+
+            let buildfile = generate_buildfile(setup, &packages);
+            // Pin the buildfile name, the default list would also probe for `BUCK.v2`.
+            let buckconfig = format!("[buildfile]\n  name_v2 = {}\n", GENERATED_BUILDFILE);
+            let ops = LockfileFileOpsDelegate::new(
+                [
+                    (".buckconfig".to_owned(), buckconfig),
+                    (GENERATED_BUILDFILE.to_owned(), buildfile),
+                ],
+                ctx.global_data().get_digest_config(),
+            );
+            declare_all_source_artifacts(ctx, setup, &ops).await?;
+            Ok(Arc::new(ops))
+        }
+
+        fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+            match (x, y) {
+                (Ok(x), Ok(y)) => x == y,
+                _ => false,
+            }
+        }
+    }
+
+    Ok(ctx
+        .compute(&LockfileFileOpsDelegateKey(cell_name, setup))
+        .await??)
+}
+
+pub(crate) async fn materialize_all(
+    ctx: &mut DiceComputations<'_>,
+    cell: CellName,
+    setup: LockfileCellSetup,
+) -> anyhow::Result<ProjectRelativePathBuf> {
+    let artifact_fs = ctx.get_artifact_fs().await?;
+    let buck_out_resolver = artifact_fs.buck_out_path_resolver();
+
+    let ops = get_file_ops_delegate(ctx, cell, setup.dupe()).await?;
+    let paths = ops
+        .files
+        .keys()
+        .map(|name| {
+            buck_out_resolver.resolve_external_cell_source(
+                CellRelativePath::unchecked_new(name),
+                ExternalCellOrigin::Lockfile(setup.dupe()),
+            )
+        })
+        .collect();
+
+    let materializer = ctx.per_transaction_data().get_materializer();
+    materializer.ensure_materialized(paths).await?;
+    Ok(buck_out_resolver.resolve_external_cell_source(
+        CellRelativePath::unchecked_new(""),
+        ExternalCellOrigin::Lockfile(setup),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_cargo_lock() {
+        let packages = parse_lockfile(
+            LockfileFormat::Cargo,
+            "Cargo.lock",
+            indoc!(
+                r#"
+                version = 3
+
+                [[package]]
+                name = "myproject"
+                version = "0.1.0"
+
+                [[package]]
+                name = "serde"
+                version = "1.0.197"
+                source = "registry+https://github.com/rust-lang/crates.io-index"
+                checksum = "3fb1c873e1b9b056a4dc4c0c198b24c3ffa059243875552b2bd0933b1aee4ce2"
+                "#
+            ),
+        )
+        .unwrap();
+        assert_eq!(
+            packages,
+            vec![LockedPackage {
+                name: "serde".to_owned(),
+                version: "1.0.197".to_owned(),
+                source: Some("registry+https://github.com/rust-lang/crates.io-index".to_owned()),
+                checksums: vec![
+                    "sha256:3fb1c873e1b9b056a4dc4c0c198b24c3ffa059243875552b2bd0933b1aee4ce2"
+                        .to_owned()
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_package_lock_json() {
+        let packages = parse_lockfile(
+            LockfileFormat::Npm,
+            "package-lock.json",
+            indoc!(
+                r#"
+                {
+                  "lockfileVersion": 3,
+                  "packages": {
+                    "": { "name": "app", "version": "1.0.0" },
+                    "node_modules/left-pad": {
+                      "version": "1.3.0",
+                      "resolved": "https://registry.npmjs.org/left-pad/-/left-pad-1.3.0.tgz",
+                      "integrity": "sha512-abc"
+                    },
+                    "node_modules/a/node_modules/left-pad": {
+                      "version": "1.3.0",
+                      "resolved": "https://registry.npmjs.org/left-pad/-/left-pad-1.3.0.tgz",
+                      "integrity": "sha512-abc"
+                    },
+                    "node_modules/local": { "resolved": "../local", "link": true }
+                  }
+                }
+                "#
+            ),
+        )
+        .unwrap();
+        assert_eq!(
+            packages,
+            vec![LockedPackage {
+                name: "left-pad".to_owned(),
+                version: "1.3.0".to_owned(),
+                source: Some("https://registry.npmjs.org/left-pad/-/left-pad-1.3.0.tgz".to_owned()),
+                checksums: vec!["sha512-abc".to_owned()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_requirements_lock() {
+        let packages = parse_lockfile(
+            LockfileFormat::Python,
+            "requirements.txt",
+            indoc!(
+                r#"
+                # This file is autogenerated by pip-compile
+                --index-url https://pypi.org/simple
+
+                requests[socks]==2.31.0 \
+                    --hash=sha256:aaaa \
+                    --hash=sha256:bbbb
+                    # via -r requirements.in
+                idna==3.6 ; python_version >= "3.8"  # via requests
+                "#
+            ),
+        )
+        .unwrap();
+        assert_eq!(
+            packages,
+            vec![
+                LockedPackage {
+                    name: "idna".to_owned(),
+                    version: "3.6".to_owned(),
+                    source: None,
+                    checksums: vec![],
+                },
+                LockedPackage {
+                    name: "requests".to_owned(),
+                    version: "2.31.0".to_owned(),
+                    source: None,
+                    checksums: vec!["sha256:aaaa".to_owned(), "sha256:bbbb".to_owned()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_requirements_unpinned() {
+        let e = parse_lockfile(LockfileFormat::Python, "requirements.txt", "requests>=2\n")
+            .unwrap_err();
+        assert!(format!("{:?}", e).contains("expected `name==version`"));
+    }
+
+    #[test]
+    fn test_generate_buildfile() {
+        let setup = LockfileCellSetup {
+            lockfile: "third-party/Cargo.lock".into(),
+            format: LockfileFormat::Cargo,
+            rule_bzl: "root//third-party:defs.bzl".into(),
+            rule_name: "cargo_package".into(),
+        };
+        let buildfile = generate_buildfile(
+            &setup,
+            &[LockedPackage {
+                name: "serde".to_owned(),
+                version: "1.0.197".to_owned(),
+                source: None,
+                checksums: vec!["sha256:aa".to_owned()],
+            }],
+        );
+        assert_eq!(
+            buildfile,
+            // Split up so that this file itself isn't considered generated.
+            format!(
+                "# @{} by buck2 from `third-party/Cargo.lock`, do not edit.\n",
+                "generated"
+            ) + indoc!(
+                r#"
+                load("root//third-party:defs.bzl", "cargo_package")
+
+                cargo_package(
+                    ecosystem = "cargo",
+                    name = "serde",
+                    version = "1.0.197",
+                    source = None,
+                    checksums = ["sha256:aa"],
+                )
+                "#
+            )
+        );
+    }
+}
@@ -21,6 +21,7 @@ use buck2_core::cells::CellResolver;
 use buck2_core::fs::project::ProjectRoot;
 use buck2_core::is_open_source;
 use dice::DiceTransactionUpdater;
+use tokio::sync::watch;
 
 use crate::fs_hash_crawler::FsHashCrawler;
 use crate::mergebase::Mergebase;
@@ -33,6 +34,10 @@ pub trait FileWatcher: Allocative + Send + Sync + 'static {
         &self,
         dice: DiceTransactionUpdater,
     ) -> anyhow::Result<(DiceTransactionUpdater, Mergebase)>;
+
+    /// A receiver marked as changed whenever the watcher is told about changes, which the next
+    /// `sync` then reports.
+    async fn subscribe(&self) -> anyhow::Result<watch::Receiver<()>>;
 }
 
 impl dyn FileWatcher {
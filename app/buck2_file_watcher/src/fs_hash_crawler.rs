@@ -36,6 +36,7 @@ use buck2_events::dispatch::span_async;
 use compact_str::CompactString;
 use dice::DiceTransactionUpdater;
 use dupe::Dupe;
+use tokio::sync::watch;
 
 use crate::file_watcher::FileWatcher;
 use crate::mergebase::Mergebase;
@@ -106,6 +107,11 @@ impl FileWatcher for FsHashCrawler {
         )
         .await
     }
+    async fn subscribe(&self) -> anyhow::Result<watch::Receiver<()>> {
+        Err(anyhow::anyhow!(
+            "The fs_hash_crawler file watcher only finds changes when synced"
+        ))
+    }
 }
 
 #[derive(Ord, PartialOrd, Eq, PartialEq, Debug)]
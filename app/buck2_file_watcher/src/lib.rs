@@ -15,5 +15,6 @@ pub mod file_watcher;
 mod fs_hash_crawler;
 pub mod mergebase;
 mod notify;
+pub mod recent_events;
 mod stats;
 mod watchman;
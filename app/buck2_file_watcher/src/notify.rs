@@ -33,6 +33,7 @@ use notify::EventKind;
 use notify::RecommendedWatcher;
 use notify::Watcher;
 use starlark_map::ordered_set::OrderedSet;
+use tokio::sync::watch;
 use tracing::info;
 
 use crate::file_watcher::FileWatcher;
@@ -181,6 +182,8 @@ pub struct NotifyFileWatcher {
     #[allocative(skip)]
     watcher: RecommendedWatcher,
     data: Arc<Mutex<anyhow::Result<NotifyFileData>>>,
+    #[allocative(skip)]
+    changes: Arc<watch::Sender<()>>,
 }
 
 impl NotifyFileWatcher {
@@ -191,17 +194,28 @@ impl NotifyFileWatcher {
     ) -> anyhow::Result<Self> {
         let data = Arc::new(Mutex::new(Ok(NotifyFileData::new())));
         let data2 = data.dupe();
+        let changes = Arc::new(watch::channel(()).0);
+        let changes2 = changes.dupe();
         let root2 = root.dupe();
         let mut watcher = notify::recommended_watcher(move |event| {
             let mut guard = data2.lock().unwrap();
             if let Ok(state) = &mut *guard {
-                if let Err(e) = state.process(event, &root2, &cells, &ignore_specs) {
-                    *guard = Err(e);
+                let events = state.events.len();
+                match state.process(event, &root2, &cells, &ignore_specs) {
+                    Ok(()) if state.events.len() == events => return,
+                    Ok(()) => {}
+                    Err(e) => *guard = Err(e),
                 }
+                // The next sync reports the change, or the error.
+                changes2.send_replace(());
             }
         })?;
         watcher.watch(root.root().as_path(), notify::RecursiveMode::Recursive)?;
-        Ok(Self { watcher, data })
+        Ok(Self {
+            watcher,
+            data,
+            changes,
+        })
     }
 
     fn sync2(
@@ -239,4 +253,7 @@ impl FileWatcher for NotifyFileWatcher {
         )
         .await
     }
+    async fn subscribe(&self) -> anyhow::Result<watch::Receiver<()>> {
+        Ok(self.changes.subscribe())
+    }
 }
@@ -0,0 +1,104 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Last event processed by the file watcher for recently changed paths, so that
+//! `buck2 debug file-status` can tell whether a change was noticed.
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Number of paths we remember events for, the oldest ones are forgotten first.
+const MAX_RECENT_EVENTS: usize = 10000;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RecentFileEvent {
+    pub event: buck2_data::FileWatcherEventType,
+    pub kind: buck2_data::FileWatcherKind,
+    pub time: SystemTime,
+}
+
+#[derive(Default)]
+struct RecentEvents {
+    /// Event and sequence number by cell path.
+    events: HashMap<String, (u64, RecentFileEvent)>,
+    /// Cell paths in the order they were recorded. A path recorded again is pushed again, so
+    /// entries whose sequence number is outdated are skipped on eviction.
+    order: VecDeque<(u64, String)>,
+    next_seq: u64,
+}
+
+static RECENT_EVENTS: Mutex<Option<RecentEvents>> = Mutex::new(None);
+
+pub(crate) fn record(
+    cell_path: &str,
+    event: buck2_data::FileWatcherEventType,
+    kind: buck2_data::FileWatcherKind,
+) {
+    let mut recent = RECENT_EVENTS.lock().unwrap();
+    let recent = recent.get_or_insert_with(RecentEvents::default);
+    let seq = recent.next_seq;
+    recent.next_seq += 1;
+    recent.events.insert(
+        cell_path.to_owned(),
+        (
+            seq,
+            RecentFileEvent {
+                event,
+                kind,
+                time: SystemTime::now(),
+            },
+        ),
+    );
+    recent.order.push_back((seq, cell_path.to_owned()));
+    while recent.events.len() > MAX_RECENT_EVENTS || recent.order.len() > 2 * MAX_RECENT_EVENTS {
+        let Some((seq, path)) = recent.order.pop_front() else {
+            break;
+        };
+        if recent.events.get(&path).map(|(s, _)| *s) == Some(seq) {
+            recent.events.remove(&path);
+        }
+    }
+}
+
+/// The last event seen for a cell path, if it changed recently.
+pub fn last_event(cell_path: &str) -> Option<RecentFileEvent> {
+    RECENT_EVENTS
+        .lock()
+        .unwrap()
+        .as_ref()?
+        .events
+        .get(cell_path)
+        .map(|(_, event)| *event)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_last_event() {
+        record(
+            "root//recent_events_test/a",
+            buck2_data::FileWatcherEventType::Create,
+            buck2_data::FileWatcherKind::File,
+        );
+        record(
+            "root//recent_events_test/a",
+            buck2_data::FileWatcherEventType::Modify,
+            buck2_data::FileWatcherKind::File,
+        );
+        assert_eq!(
+            buck2_data::FileWatcherEventType::Modify,
+            last_event("root//recent_events_test/a").unwrap().event
+        );
+        assert!(last_event("root//recent_events_test/b").is_none());
+    }
+}
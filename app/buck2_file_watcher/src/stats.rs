@@ -63,6 +63,8 @@ impl FileWatcherStats {
         self.stats.events_total += 1;
         self.stats.events_processed += 1;
 
+        crate::recent_events::record(&path, event, kind);
+
         if self.changes.len() < MAX_FILE_CHANGE_RECORDS {
             self.changes.push(buck2_data::FileWatcherEvent {
                 event: event as i32,
@@ -196,6 +196,47 @@ impl WatchmanClient {
     }
 }
 
+/// Subscribes to changes to the files matching `expr` under `path`. The receiver is marked as
+/// changed whenever watchman reports some, until the subscription is cancelled.
+pub async fn subscribe_to_changes(
+    connector: &Connector,
+    path: CanonicalPath,
+    expr: Expr,
+) -> anyhow::Result<tokio::sync::watch::Receiver<()>> {
+    let client = with_timeout(connector.connect())
+        .await
+        .context("Connecting to watchman")?;
+    let root = with_timeout(client.resolve_root(path))
+        .await
+        .context("Resolving watchman root")?;
+    let (mut subscription, _) = with_timeout(client.subscribe::<NameOnly>(
+        &root,
+        SubscribeRequest {
+            expression: Some(expr),
+            empty_on_fresh_instance: true,
+            ..SubscribeRequest::default()
+        },
+    ))
+    .await
+    .context("Subscribing to watchman")?;
+
+    let (tx, rx) = tokio::sync::watch::channel(());
+    tokio::spawn(async move {
+        // The subscription ends with the client.
+        let _client = client;
+        loop {
+            match subscription.next().await {
+                Ok(SubscriptionData::FilesChanged(_)) => {
+                    tx.send_replace(());
+                }
+                Ok(SubscriptionData::Canceled) | Err(_) => break,
+                Ok(_) => {}
+            }
+        }
+    });
+    Ok(rx)
+}
+
 #[async_trait]
 pub trait SyncableQueryProcessor: Send + Sync {
     type Output;
@@ -26,15 +26,19 @@ use buck2_core::rollout_percentage::RolloutPercentage;
 use buck2_events::dispatch::span_async;
 use buck2_util::process::async_background_command;
 use dice::DiceTransactionUpdater;
+use tokio::sync::watch;
+use tokio::sync::OnceCell;
 use tracing::info;
 use tracing::warn;
 use watchman_client::expr::Expr;
+use watchman_client::prelude::CanonicalPath;
 use watchman_client::prelude::Connector;
 use watchman_client::prelude::FileType;
 
 use crate::file_watcher::FileWatcher;
 use crate::mergebase::Mergebase;
 use crate::stats::FileWatcherStats;
+use crate::watchman::core::subscribe_to_changes;
 use crate::watchman::core::SyncableQuery;
 use crate::watchman::core::SyncableQueryProcessor;
 use crate::watchman::core::WatchmanEvent;
@@ -315,6 +319,11 @@ impl SyncableQueryProcessor for WatchmanQueryProcessor {
 pub(crate) struct WatchmanFileWatcher {
     #[allocative(skip)]
     query: SyncableQuery<buck2_data::FileWatcherStats, DiceTransactionUpdater>,
+    #[allocative(skip)]
+    project_root: CanonicalPath,
+    /// Subscribed to on first use, since the daemon rarely needs it.
+    #[allocative(skip)]
+    changes: OnceCell<watch::Receiver<()>>,
 }
 
 /// The watchman query is constructed once on daemon startup. It is an unfiltered watchman query
@@ -353,11 +362,7 @@ impl WatchmanFileWatcher {
         let query = SyncableQuery::new(
             Connector::new(),
             project_root,
-            Expr::Any(vec![
-                Expr::FileType(FileType::Regular),
-                Expr::FileType(FileType::Directory),
-                Expr::FileType(FileType::Symlink),
-            ]),
+            Self::expr(),
             Box::new(WatchmanQueryProcessor {
                 cells,
                 ignore_specs,
@@ -369,7 +374,22 @@ impl WatchmanFileWatcher {
             watchman_merge_base,
         )?;
 
-        Ok(Self { query })
+        let project_root = CanonicalPath::canonicalize(project_root)
+            .with_context(|| format!("Error canonicalizing: `{}`", project_root))?;
+
+        Ok(Self {
+            query,
+            project_root,
+            changes: OnceCell::new(),
+        })
+    }
+
+    fn expr() -> Expr {
+        Expr::Any(vec![
+            Expr::FileType(FileType::Regular),
+            Expr::FileType(FileType::Directory),
+            Expr::FileType(FileType::Symlink),
+        ])
     }
 }
 
@@ -396,4 +416,13 @@ impl FileWatcher for WatchmanFileWatcher {
         )
         .await
     }
+    async fn subscribe(&self) -> anyhow::Result<watch::Receiver<()>> {
+        let changes = self
+            .changes
+            .get_or_try_init(|| {
+                subscribe_to_changes(&Connector::new(), self.project_root.clone(), Self::expr())
+            })
+            .await?;
+        Ok(changes.clone())
+    }
 }
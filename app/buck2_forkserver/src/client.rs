@@ -26,8 +26,9 @@ use tonic::transport::Channel;
 use tonic::Request;
 
 use crate::convert::decode_event_stream;
-use crate::run::decode_command_event_stream;
+use crate::run::decode_command_event_stream_with_output;
 use crate::run::GatherOutputStatus;
+use crate::run::OutputStream;
 
 #[derive(Clone, Dupe, Allocative)]
 pub struct ForkserverClient {
@@ -92,6 +93,19 @@ impl ForkserverClient {
         req: buck2_forkserver_proto::CommandRequest,
         cancel: C,
     ) -> anyhow::Result<(GatherOutputStatus, Vec<u8>, Vec<u8>)>
+    where
+        C: Future<Output = ()> + Send + 'static,
+    {
+        self.execute_with_streaming(req, cancel, |_, _| {}).await
+    }
+
+    /// Like `execute`, but also pass the output to `on_output` while the command is running.
+    pub async fn execute_with_streaming<C>(
+        &self,
+        req: buck2_forkserver_proto::CommandRequest,
+        cancel: C,
+        on_output: impl FnMut(OutputStream, &[u8]),
+    ) -> anyhow::Result<(GatherOutputStatus, Vec<u8>, Vec<u8>)>
     where
         C: Future<Output = ()> + Send + 'static,
     {
@@ -123,7 +137,7 @@ impl ForkserverClient {
             .context("Error dispatching command to Forkserver")?
             .into_inner();
         let stream = decode_event_stream(stream);
-        decode_command_event_stream(stream).await
+        decode_command_event_stream_with_output(stream, on_output).await
     }
 
     pub async fn set_log_filter(&self, log_filter: String) -> anyhow::Result<()> {
@@ -22,9 +22,11 @@ use std::time::Duration;
 
 use anyhow::Context as _;
 use async_trait::async_trait;
+use buck2_common::process_priority::BackgroundPriority;
 use buck2_core::fs::fs_util;
 use buck2_core::fs::paths::abs_path::AbsPath;
 use bytes::Bytes;
+use dupe::Dupe;
 use futures::future::Future;
 use futures::future::FutureExt;
 use futures::stream::Stream;
@@ -82,6 +84,13 @@ enum StdioEvent {
     Stderr(Bytes),
 }
 
+/// Which output of a command a chunk of bytes comes from.
+#[derive(Debug, Clone, Copy, Dupe, PartialEq, Eq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
 impl From<StdioEvent> for CommandEvent {
     fn from(stdio: StdioEvent) -> Self {
         match stdio {
@@ -270,6 +279,18 @@ where
 pub(crate) async fn decode_command_event_stream<S>(
     stream: S,
 ) -> anyhow::Result<(GatherOutputStatus, Vec<u8>, Vec<u8>)>
+where
+    S: Stream<Item = anyhow::Result<CommandEvent>>,
+{
+    decode_command_event_stream_with_output(stream, |_, _| {}).await
+}
+
+/// Like `decode_command_event_stream`, but also pass the output to `on_output` as soon as it is
+/// produced by the command.
+pub(crate) async fn decode_command_event_stream_with_output<S>(
+    stream: S,
+    mut on_output: impl FnMut(OutputStream, &[u8]),
+) -> anyhow::Result<(GatherOutputStatus, Vec<u8>, Vec<u8>)>
 where
     S: Stream<Item = anyhow::Result<CommandEvent>>,
 {
@@ -280,8 +301,14 @@ where
 
     while let Some(event) = stream.try_next().await? {
         match event {
-            CommandEvent::Stdout(bytes) => stdout.extend(&bytes),
-            CommandEvent::Stderr(bytes) => stderr.extend(&bytes),
+            CommandEvent::Stdout(bytes) => {
+                on_output(OutputStream::Stdout, &bytes);
+                stdout.extend(&bytes);
+            }
+            CommandEvent::Stderr(bytes) => {
+                on_output(OutputStream::Stderr, &bytes);
+                stderr.extend(&bytes);
+            }
             CommandEvent::Exit(exit) => return Ok((exit, stdout, stderr)),
         }
     }
@@ -298,7 +325,24 @@ pub async fn gather_output<T>(
 where
     T: Future<Output = anyhow::Result<GatherOutputStatus>> + Send,
 {
-    let cmd = ProcessCommand::new(cmd);
+    gather_output_with_streaming(cmd, None, cancellation, |_, _| {}).await
+}
+
+/// Like `gather_output`, but also pass the output to `on_output` while the command is running,
+/// and optionally lower the priority of the command.
+pub async fn gather_output_with_streaming<T>(
+    cmd: Command,
+    background_priority: Option<BackgroundPriority>,
+    cancellation: T,
+    on_output: impl FnMut(OutputStream, &[u8]),
+) -> anyhow::Result<(GatherOutputStatus, Vec<u8>, Vec<u8>)>
+where
+    T: Future<Output = anyhow::Result<GatherOutputStatus>> + Send,
+{
+    let mut cmd = ProcessCommand::new(cmd);
+    if let Some(priority) = background_priority {
+        cmd.background_priority(priority);
+    }
 
     let process_details =
         spawn_retry_txt_busy(cmd, || tokio::time::sleep(Duration::from_millis(50))).await;
@@ -310,7 +354,7 @@ where
         DefaultKillProcess::default(),
         true,
     )?;
-    decode_command_event_stream(stream).await
+    decode_command_event_stream_with_output(stream, on_output).await
 }
 
 /// Dependency injection for kill. We use this in testing.
@@ -413,7 +457,6 @@ mod tests {
 
     use assert_matches::assert_matches;
     use buck2_util::process::background_command;
-    use dupe::Dupe;
 
     use super::*;
 
@@ -434,6 +477,56 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_gather_output_with_streaming() -> anyhow::Result<()> {
+        let mut cmd = background_command("sh");
+        cmd.args(["-c", "echo out; echo err >&2; echo out2"]);
+
+        let mut streamed_stdout = Vec::new();
+        let mut streamed_stderr = Vec::new();
+        let (status, stdout, stderr) =
+            gather_output_with_streaming(cmd, None, futures::future::pending(), |stream, bytes| {
+                match stream {
+                    OutputStream::Stdout => streamed_stdout.extend_from_slice(bytes),
+                    OutputStream::Stderr => streamed_stderr.extend_from_slice(bytes),
+                }
+            })
+            .await?;
+        assert!(matches!(status, GatherOutputStatus::Finished { exit_code, .. } if exit_code == 0));
+        assert_eq!(stdout, b"out\nout2\n");
+        assert_eq!(stderr, b"err\n");
+        assert_eq!(streamed_stdout, stdout);
+        assert_eq!(streamed_stderr, stderr);
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_gather_output_with_background_priority() -> anyhow::Result<()> {
+        async fn niceness(priority: Option<BackgroundPriority>) -> anyhow::Result<i32> {
+            let cmd = background_command("nice");
+            let (status, stdout, _stderr) =
+                gather_output_with_streaming(cmd, priority, futures::future::pending(), |_, _| {})
+                    .await?;
+            assert!(
+                matches!(status, GatherOutputStatus::Finished { exit_code, .. } if exit_code == 0)
+            );
+            Ok(str::from_utf8(&stdout)?.trim().parse()?)
+        }
+
+        let normal = niceness(None).await?;
+        let background = niceness(Some(BackgroundPriority {
+            nice: 5,
+            idle_io: true,
+        }))
+        .await?;
+        assert_eq!(std::cmp::min(normal + 5, 19), background);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_gather_does_not_wait_for_children() -> anyhow::Result<()> {
         // If we wait for sleep, this will time out.
@@ -11,6 +11,7 @@ use std::process::Command as StdCommand;
 use std::process::ExitStatus;
 use std::process::Stdio;
 
+use buck2_common::process_priority::BackgroundPriority;
 use thiserror::Error;
 use tokio::io;
 use tokio::process::ChildStderr;
@@ -50,6 +51,14 @@ impl ProcessCommand {
         })
     }
 
+    pub(crate) fn background_priority(
+        &mut self,
+        priority: BackgroundPriority,
+    ) -> &mut ProcessCommand {
+        self.inner.background_priority(priority);
+        self
+    }
+
     #[allow(dead_code)]
     pub(crate) fn stdout<T: Into<Stdio>>(&mut self, cfg: T) -> &mut ProcessCommand {
         self.inner.stdout(cfg.into());
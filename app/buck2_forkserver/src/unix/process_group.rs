@@ -15,6 +15,7 @@ use std::time::Duration;
 
 use anyhow::Context;
 use buck2_common::kill_util::try_terminate_process_gracefully;
+use buck2_common::process_priority::BackgroundPriority;
 use nix::sys::signal;
 use nix::sys::signal::Signal;
 use nix::unistd::Pid;
@@ -38,6 +39,15 @@ impl ProcessCommandImpl {
         self.inner.spawn()
     }
 
+    pub(crate) fn background_priority(&mut self, priority: BackgroundPriority) {
+        unsafe {
+            self.inner.pre_exec(move || {
+                priority.apply_to_current_process();
+                Ok(())
+            });
+        }
+    }
+
     pub(crate) fn stdout(&mut self, stdout: Stdio) {
         self.inner.stdout(stdout);
     }
@@ -18,6 +18,7 @@ use std::sync::Arc;
 
 use anyhow::Context as _;
 use buck2_common::convert::ProstDurationExt;
+use buck2_common::process_priority::BackgroundPriority;
 use buck2_core::fs::fs_util;
 use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
 use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
@@ -115,6 +116,7 @@ impl Forkserver for UnixForkserverService {
                 enable_miniperf,
                 std_redirects,
                 graceful_shutdown_timeout_s,
+                background_priority,
             } = msg;
 
             let exe = OsStr::from_bytes(&exe);
@@ -166,6 +168,12 @@ impl Forkserver for UnixForkserverService {
                 cmd.stdout(File::create(OsStr::from_bytes(&std_redirects.stdout))?);
                 cmd.stderr(File::create(OsStr::from_bytes(&std_redirects.stderr))?);
             }
+            if let Some(priority) = background_priority {
+                cmd.background_priority(BackgroundPriority {
+                    nice: priority.nice,
+                    idle_io: priority.idle_io,
+                });
+            }
 
             let process_group = cmd.spawn().map_err(anyhow::Error::from);
 
@@ -17,17 +17,22 @@ use std::process::Stdio;
 use std::time::Duration;
 
 use anyhow::Context;
+use buck2_common::process_priority::BackgroundPriority;
 use tokio::io;
 use tokio::process::ChildStderr;
 use tokio::process::ChildStdout;
+use winapi::shared::minwindef::DWORD;
 use winapi::um::processthreadsapi;
+use winapi::um::winbase;
 
 use crate::win::child_process::ChildProcess;
 use crate::win::job_object::JobObject;
+use crate::win::utils::result_bool;
 use crate::win::utils::result_dword;
 
 pub(crate) struct ProcessCommandImpl {
     inner: Command,
+    priority_class: Option<DWORD>,
 }
 
 impl ProcessCommandImpl {
@@ -37,11 +42,30 @@ impl ProcessCommandImpl {
         cmd.creation_flags(
             winapi::um::winbase::CREATE_NO_WINDOW | winapi::um::winbase::CREATE_SUSPENDED,
         );
-        Self { inner: cmd }
+        Self {
+            inner: cmd,
+            priority_class: None,
+        }
+    }
+
+    pub(crate) fn background_priority(&mut self, priority: BackgroundPriority) {
+        self.priority_class = Some(if priority.nice >= 10 {
+            winbase::IDLE_PRIORITY_CLASS
+        } else {
+            winbase::BELOW_NORMAL_PRIORITY_CLASS
+        });
     }
 
     pub(crate) fn spawn(&mut self) -> io::Result<Child> {
-        self.inner.spawn()
+        let child = self.inner.spawn()?;
+        if let Some(priority_class) = self.priority_class {
+            // The process is still suspended, and processes it starts inherit the idle and below
+            // normal priority classes. This is best effort, like on Unix.
+            let _ignored = result_bool(unsafe {
+                processthreadsapi::SetPriorityClass(child.as_raw_handle(), priority_class)
+            });
+        }
+        Ok(child)
     }
 
     #[allow(dead_code)]
@@ -0,0 +1,272 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Optional compatibility layer for cells which still contain Bazel `BUILD` files.
+//!
+//! Enabled per cell with `[bazel_compat] enabled = true`. When enabled, build files in the cell:
+//!
+//! * implicitly load a mapping prelude (`bazel_compat.prelude`) on top of the regular prelude,
+//! * see Bazel rule names aliased to Buck rules (`bazel_compat.rule_aliases`),
+//! * may use Bazel `glob()` arguments and semantics (`bazel_compat.glob = bazel`),
+//! * may write labels in Bazel syntax (`@repo//pkg`, `//pkg` for `//pkg:pkg`).
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use allocative::Allocative;
+use buck2_common::legacy_configs::key::BuckconfigKeyRef;
+use buck2_common::legacy_configs::view::LegacyBuckConfigView;
+use buck2_core::bzl::ImportPath;
+use buck2_core::cells::build_file_cell::BuildFileCell;
+use buck2_core::cells::cell_path::CellPath;
+use buck2_core::cells::paths::CellRelativePathBuf;
+use buck2_core::cells::CellAliasResolver;
+use dupe::Dupe;
+
+const SECTION: &str = "bazel_compat";
+
+#[derive(Debug, buck2_error::Error)]
+#[buck2(input)]
+enum BazelCompatError {
+    #[error("Invalid `{SECTION}.glob` value `{0}`, expected `buck` or `bazel`")]
+    UnknownGlobSemantics(String),
+    #[error("Invalid `{SECTION}.rule_aliases` entry `{0}`, expected `bazel_name=buck_name`")]
+    InvalidRuleAlias(String),
+}
+
+/// How `glob()` behaves in build files of the cell.
+#[derive(Debug, Clone, Copy, Dupe, PartialEq, Eq, Allocative)]
+pub enum GlobSemantics {
+    /// The regular Buck semantics: only `include` and `exclude` are accepted.
+    Buck,
+    /// Bazel semantics: `allow_empty` and `exclude_directories` are accepted, and a glob
+    /// matching nothing is an error unless `allow_empty` is set (or defaulted by config).
+    Bazel { allow_empty: bool },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Allocative)]
+pub struct BazelCompat {
+    /// Loaded into every build file of the cell after the regular prelude, so it can both add
+    /// Bazel-only symbols and shadow prelude symbols with Bazel-compatible wrappers.
+    pub prelude: Option<ImportPath>,
+    /// Pairs of `(bazel_name, buck_name)`, the build file symbol `bazel_name` is bound to
+    /// whatever `buck_name` is bound to after all implicit imports have been applied.
+    pub rule_aliases: Vec<(Arc<str>, Arc<str>)>,
+    pub glob: GlobSemantics,
+    /// Accept Bazel label syntax in attributes.
+    pub labels: bool,
+}
+
+impl BazelCompat {
+    /// Returns `None` unless the compatibility layer is enabled for the cell.
+    pub fn parse(
+        config: &mut impl LegacyBuckConfigView,
+        cell_name: BuildFileCell,
+        cell_alias_resolver: &CellAliasResolver,
+    ) -> anyhow::Result<Option<BazelCompat>> {
+        let key = |property| BuckconfigKeyRef {
+            section: SECTION,
+            property,
+        };
+
+        if !config.parse::<bool>(key("enabled"))?.unwrap_or(false) {
+            return Ok(None);
+        }
+
+        // Same path-like format as `buildfile.includes`.
+        let prelude = config
+            .get(key("prelude"))?
+            .map(|i| {
+                let (cell_alias, path): (&str, &str) = i.split_once("//").unwrap_or(("", &*i));
+                let path = CellRelativePathBuf::try_from(path.to_owned())?;
+                let path = CellPath::new(cell_alias_resolver.resolve(cell_alias)?, path);
+                ImportPath::new_with_build_file_cells(path, cell_name)
+            })
+            .transpose()?;
+
+        let rule_aliases = config
+            .parse_list::<String>(key("rule_aliases"))?
+            .unwrap_or_default()
+            .into_iter()
+            .map(|entry| match entry.split_once('=') {
+                Some((bazel, buck)) if !bazel.trim().is_empty() && !buck.trim().is_empty() => {
+                    Ok((Arc::from(bazel.trim()), Arc::from(buck.trim())))
+                }
+                _ => Err(BazelCompatError::InvalidRuleAlias(entry).into()),
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let glob = match config.get(key("glob"))?.as_deref() {
+            None | Some("buck") => GlobSemantics::Buck,
+            Some("bazel") => GlobSemantics::Bazel {
+                allow_empty: config
+                    .parse::<bool>(key("glob_allow_empty"))?
+                    .unwrap_or(true),
+            },
+            Some(other) => {
+                return Err(BazelCompatError::UnknownGlobSemantics(other.to_owned()).into());
+            }
+        };
+
+        let labels = config.parse::<bool>(key("labels"))?.unwrap_or(true);
+
+        Ok(Some(BazelCompat {
+            prelude,
+            rule_aliases,
+            glob,
+            labels,
+        }))
+    }
+}
+
+/// Rewrite a Bazel label into the equivalent Buck label, returning other strings unchanged.
+///
+/// * `@repo//pkg:name` and `@@repo//pkg:name` become `repo//pkg:name`, with `repo` resolved as
+///   a cell alias,
+/// * `@//pkg:name` becomes `//pkg:name`,
+/// * `@repo` becomes `repo//:repo`,
+/// * a label without a target name, such as `//pkg/foo`, gets the implicit `:foo`.
+pub fn normalize_bazel_label(label: &str) -> Cow<'_, str> {
+    let mut label = Cow::Borrowed(label);
+
+    if let Some(rest) = label.strip_prefix('@') {
+        let rest = rest.strip_prefix('@').unwrap_or(rest);
+        label = if rest.contains("//") {
+            Cow::Owned(rest.to_owned())
+        } else {
+            Cow::Owned(format!("{rest}//:{rest}"))
+        };
+    }
+
+    if let Some((cell, path)) = label.split_once("//") {
+        if !path.is_empty() && !path.contains(':') && !path.ends_with("...") {
+            let name = path.rsplit('/').next().unwrap_or(path);
+            label = Cow::Owned(format!("{cell}//{path}:{name}"));
+        }
+    }
+
+    label
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use buck2_common::legacy_configs::configs::testing::parse;
+    use buck2_core::cells::alias::NonEmptyCellAlias;
+    use buck2_core::cells::name::CellName;
+
+    use super::*;
+
+    fn parse_compat(config: &str) -> anyhow::Result<Option<BazelCompat>> {
+        let config = parse(&[("/config", config)], "/config")?;
+        let mut aliases = HashMap::new();
+        aliases.insert(
+            NonEmptyCellAlias::testing_new("compat"),
+            CellName::testing_new("compat"),
+        );
+        let resolver = CellAliasResolver::new(CellName::testing_new("root"), aliases)?;
+        BazelCompat::parse(
+            &mut &config,
+            BuildFileCell::new(CellName::testing_new("root")),
+            &resolver,
+        )
+    }
+
+    #[test]
+    fn test_parse_disabled() -> anyhow::Result<()> {
+        assert_eq!(None, parse_compat("")?);
+        assert_eq!(
+            None,
+            parse_compat(
+                r#"
+                [bazel_compat]
+                    enabled = false
+                    glob = nonsense
+                "#
+            )?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_defaults() -> anyhow::Result<()> {
+        let compat = parse_compat(
+            r#"
+            [bazel_compat]
+                enabled = true
+            "#,
+        )?
+        .unwrap();
+        assert_eq!(None, compat.prelude);
+        assert!(compat.rule_aliases.is_empty());
+        assert_eq!(GlobSemantics::Buck, compat.glob);
+        assert!(compat.labels);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_all_settings() -> anyhow::Result<()> {
+        let compat = parse_compat(
+            r#"
+            [bazel_compat]
+                enabled = true
+                prelude = compat//prelude.bzl
+                rule_aliases = cc_library=cxx_library, cc_binary = cxx_binary
+                glob = bazel
+                glob_allow_empty = false
+                labels = false
+            "#,
+        )?
+        .unwrap();
+        assert_eq!(
+            "compat//prelude.bzl@root",
+            compat.prelude.unwrap().to_string()
+        );
+        assert_eq!(
+            vec![
+                (Arc::from("cc_library"), Arc::from("cxx_library")),
+                (Arc::from("cc_binary"), Arc::from("cxx_binary")),
+            ],
+            compat.rule_aliases
+        );
+        assert_eq!(GlobSemantics::Bazel { allow_empty: false }, compat.glob);
+        assert!(!compat.labels);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        for setting in [
+            "glob = nonsense",
+            "rule_aliases = cc_library",
+            "rule_aliases = =cxx_library",
+        ] {
+            let config = format!("[bazel_compat]\n  enabled = true\n  {setting}\n");
+            assert!(parse_compat(&config).is_err(), "{setting}");
+        }
+    }
+
+    #[test]
+    fn test_normalize_bazel_label() {
+        assert_eq!("cell//foo:bar", normalize_bazel_label("cell//foo:bar"));
+        assert_eq!(":bar", normalize_bazel_label(":bar"));
+        assert_eq!("bar.c", normalize_bazel_label("bar.c"));
+        assert_eq!("absl//base:core", normalize_bazel_label("@absl//base:core"));
+        assert_eq!(
+            "absl//base:core",
+            normalize_bazel_label("@@absl//base:core")
+        );
+        assert_eq!("//foo:bar", normalize_bazel_label("@//foo:bar"));
+        assert_eq!("zlib//:zlib", normalize_bazel_label("@zlib"));
+        assert_eq!("//foo/bar:bar", normalize_bazel_label("//foo/bar"));
+        assert_eq!("absl//base:base", normalize_bazel_label("@absl//base"));
+        assert_eq!("//foo/...", normalize_bazel_label("//foo/..."));
+    }
+}
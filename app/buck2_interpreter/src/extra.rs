@@ -7,6 +7,9 @@
  * of this source tree.
  */
 
+pub mod host_facts;
+pub mod pkg_config;
+pub mod toolchain_probe;
 pub mod xcode;
 
 use allocative::Allocative;
@@ -0,0 +1,393 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Facts about the host machine, beyond its OS and architecture, exposed through `host_info()`
+//! so that execution platforms can be derived from them.
+//!
+//! Facts which can change while the daemon is running are wrapped in a [`HostFact`], which
+//! caches them according to a [`HostFactPolicy`]. They are read once per command, when the
+//! interpreter configuration is injected into DICE, so that configuration, Starlark and
+//! execution platform selection all see the same value for the whole command, and a change
+//! invalidates whatever depends on it.
+//!
+//! Facts computed for arguments given by build files, like toolchain probes or `pkg-config`
+//! queries, are kept in a [`HostFactMap`], and each is held by a DICE key, which is invalidated
+//! when the fact is stale.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use allocative::Allocative;
+use dice::DiceComputations;
+use dice::Key;
+use dupe::Dupe;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use tokio::runtime::RuntimeFlavor;
+
+#[derive(buck2_error::Error, Debug)]
+enum HostFactError {
+    #[error("Host facts can only be computed from Starlark on a multi-threaded runtime")]
+    NoMultiThreadRuntime,
+}
+
+/// Version of the glibc the daemon is running against.
+#[derive(Debug, Default, PartialEq, Clone, Allocative)]
+pub struct GlibcVersionInfo {
+    /// e.g. "2.35"
+    pub version_string: String,
+    /// The "2" in "2.35"
+    pub major_version: String,
+    /// The "35" in "2.35"
+    pub minor_version: String,
+}
+
+impl GlibcVersionInfo {
+    fn parse(version: &str) -> Option<Self> {
+        let mut parts = version.trim().split('.');
+        let major = parts.next().filter(|p| !p.is_empty())?;
+        let minor = parts.next().unwrap_or("0");
+        Some(Self {
+            version_string: version.trim().to_owned(),
+            major_version: major.to_owned(),
+            minor_version: minor.to_owned(),
+        })
+    }
+
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    fn get() -> Option<Self> {
+        // SAFETY: `gnu_get_libc_version` returns a pointer to a static NUL-terminated string.
+        let version = unsafe { std::ffi::CStr::from_ptr(libc::gnu_get_libc_version()) };
+        Self::parse(version.to_str().ok()?)
+    }
+
+    #[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+    fn get() -> Option<Self> {
+        None
+    }
+}
+
+/// Facts that don't change over the lifetime of the daemon, so they are only computed once.
+#[derive(Debug, Default, PartialEq, Clone, Allocative)]
+pub struct HostFacts {
+    /// `None` if the host does not use glibc.
+    pub glibc: Option<GlibcVersionInfo>,
+    /// Number of CPUs available to the daemon.
+    pub cpu_count: u64,
+    /// Total physical memory, 0 if it could not be determined.
+    pub memory_bytes: u64,
+}
+
+impl HostFacts {
+    pub fn get() -> &'static HostFacts {
+        static HOST_FACTS: Lazy<HostFacts> = Lazy::new(|| HostFacts {
+            glibc: GlibcVersionInfo::get(),
+            cpu_count: std::thread::available_parallelism().map_or(1, |n| n.get() as u64),
+            memory_bytes: buck2_util::system_stats::system_memory_stats(),
+        });
+        &HOST_FACTS
+    }
+}
+
+/// When a cached [`HostFact`] must be recomputed.
+pub struct HostFactPolicy {
+    /// Recompute after this long, even if no trigger changed. `None` to keep the value until a
+    /// trigger changes.
+    pub ttl: Option<Duration>,
+    /// Paths whose modification time, size or symlink target changing invalidate the value.
+    /// They are computed on each read, so they can depend on the current state of the host,
+    /// e.g. follow a symlink.
+    pub triggers: Box<dyn Fn() -> Vec<PathBuf> + Send + Sync>,
+}
+
+/// Identity of a trigger path, `None` if it does not exist.
+type TriggerStamp = Option<(Option<SystemTime>, u64, Option<PathBuf>)>;
+
+fn trigger_stamp(path: &PathBuf) -> TriggerStamp {
+    let metadata = std::fs::symlink_metadata(path).ok()?;
+    Some((
+        metadata.modified().ok(),
+        metadata.len(),
+        std::fs::read_link(path).ok(),
+    ))
+}
+
+struct CachedHostFact<T> {
+    value: T,
+    computed_at: Instant,
+    triggers: Vec<(PathBuf, TriggerStamp)>,
+}
+
+/// A fact about the host which is cached by the daemon, and recomputed when its policy says it
+/// is stale. Errors are not cached.
+pub struct HostFact<T> {
+    policy: HostFactPolicy,
+    compute: Box<dyn Fn() -> anyhow::Result<T> + Send + Sync>,
+    cached: Mutex<Option<CachedHostFact<T>>>,
+}
+
+impl<T: Clone> HostFact<T> {
+    pub fn new(
+        policy: HostFactPolicy,
+        compute: impl Fn() -> anyhow::Result<T> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            policy,
+            compute: Box::new(compute),
+            cached: Mutex::new(None),
+        }
+    }
+
+    fn stamp(&self) -> Vec<(PathBuf, TriggerStamp)> {
+        (self.policy.triggers)()
+            .into_iter()
+            .map(|path| {
+                let stamp = trigger_stamp(&path);
+                (path, stamp)
+            })
+            .collect()
+    }
+
+    fn is_fresh(&self, cached: &CachedHostFact<T>, triggers: &[(PathBuf, TriggerStamp)]) -> bool {
+        let expired = self
+            .policy
+            .ttl
+            .map_or(false, |ttl| cached.computed_at.elapsed() >= ttl);
+        !expired && cached.triggers == triggers
+    }
+
+    /// Whether there is a cached value which the next read would recompute.
+    pub fn is_stale(&self) -> bool {
+        let triggers = self.stamp();
+        match &*self.cached.lock() {
+            Some(cached) => !self.is_fresh(cached, &triggers),
+            None => false,
+        }
+    }
+
+    /// The cached value, or a freshly computed one if it is stale.
+    pub fn get(&self) -> anyhow::Result<T> {
+        let triggers = self.stamp();
+        let mut cached = self.cached.lock();
+        if let Some(cached) = &*cached {
+            if self.is_fresh(cached, &triggers) {
+                return Ok(cached.value.clone());
+            }
+        }
+        // Computing under the lock makes concurrent readers wait for the new value rather than
+        // all computing it.
+        let value = (self.compute)()?;
+        *cached = Some(CachedHostFact {
+            value: value.clone(),
+            computed_at: Instant::now(),
+            triggers,
+        });
+        Ok(value)
+    }
+
+    /// Drop the cached value, so that the next read recomputes it.
+    pub fn invalidate(&self) {
+        *self.cached.lock() = None;
+    }
+}
+
+/// Host facts computed for distinct keys, e.g. the arguments of a probe, which are kept for the
+/// lifetime of the daemon.
+pub struct HostFactMap<K, T> {
+    facts: Mutex<HashMap<K, Arc<HostFact<T>>>>,
+}
+
+impl<K, T> Default for HostFactMap<K, T> {
+    fn default() -> Self {
+        Self {
+            facts: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, T: Clone> HostFactMap<K, T> {
+    /// The fact for `key`, created with `new` on first use, or recomputed if it is stale. It is
+    /// not computed under the lock of the map, so facts for other keys can be read meanwhile.
+    pub fn get(&self, key: &K, new: impl FnOnce(&K) -> HostFact<T>) -> anyhow::Result<T> {
+        let fact = self
+            .facts
+            .lock()
+            .entry(key.clone())
+            .or_insert_with(|| Arc::new(new(key)))
+            .dupe();
+        fact.get()
+    }
+
+    /// All the keys read so far.
+    pub fn keys(&self) -> Vec<K> {
+        self.facts.lock().keys().cloned().collect()
+    }
+
+    /// All the keys read so far, with their current facts.
+    pub fn entries(&self) -> anyhow::Result<Vec<(K, T)>> {
+        let facts: Vec<_> = self
+            .facts
+            .lock()
+            .iter()
+            .map(|(key, fact)| (key.clone(), fact.dupe()))
+            .collect();
+        facts
+            .into_iter()
+            .map(|(key, fact)| Ok((key, fact.get()?)))
+            .collect()
+    }
+
+    /// Invalidate the facts read so far which are stale, so that they are computed again on the
+    /// next read, and return their keys.
+    pub fn invalidate_stale(&self) -> Vec<K> {
+        self.facts
+            .lock()
+            .iter()
+            .filter(|(_, fact)| fact.is_stale())
+            .map(|(key, fact)| {
+                fact.invalidate();
+                key.clone()
+            })
+            .collect()
+    }
+}
+
+/// Compute a key from the evaluation of Starlark, which cannot await: the thread blocks on the
+/// key, and the runtime moves its other tasks elsewhere meanwhile.
+pub(crate) fn compute_from_starlark<K, T>(ctx: &mut DiceComputations, key: &K) -> anyhow::Result<T>
+where
+    K: Key<Value = buck2_error::Result<T>>,
+{
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(|| handle.block_on(async { Ok(ctx.compute(key).await??) }))
+        }
+        _ => Err(HostFactError::NoMultiThreadRuntime.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_glibc_version() {
+        let want = GlibcVersionInfo {
+            version_string: "2.35".to_owned(),
+            major_version: "2".to_owned(),
+            minor_version: "35".to_owned(),
+        };
+        assert_eq!(Some(want), GlibcVersionInfo::parse("2.35"));
+        assert_eq!("0", GlibcVersionInfo::parse("3").unwrap().minor_version);
+        assert_eq!(None, GlibcVersionInfo::parse(""));
+    }
+
+    fn counting_fact(policy: HostFactPolicy) -> (HostFact<usize>, Arc<AtomicUsize>) {
+        let count = Arc::new(AtomicUsize::new(0));
+        let counter = count.clone();
+        let fact = HostFact::new(policy, move || {
+            Ok(counter.fetch_add(1, Ordering::SeqCst) + 1)
+        });
+        (fact, count)
+    }
+
+    #[test]
+    fn test_host_fact_trigger() -> anyhow::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let trigger = tempdir.path().join("trigger");
+        let triggers = trigger.clone();
+        let (fact, _) = counting_fact(HostFactPolicy {
+            ttl: None,
+            triggers: Box::new(move || vec![triggers.clone()]),
+        });
+
+        assert!(!fact.is_stale());
+        assert_eq!(1, fact.get()?);
+        assert_eq!(1, fact.get()?);
+        std::fs::write(&trigger, "a")?;
+        assert!(fact.is_stale());
+        assert_eq!(2, fact.get()?);
+        assert!(!fact.is_stale());
+        assert_eq!(2, fact.get()?);
+        std::fs::write(&trigger, "ab")?;
+        assert_eq!(3, fact.get()?);
+        fact.invalidate();
+        assert_eq!(4, fact.get()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_host_fact_map_invalidate_stale() -> anyhow::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let dir = tempdir.path().to_owned();
+        let count = Arc::new(AtomicUsize::new(0));
+        let map = HostFactMap::<String, String>::default();
+        let get = |key: &str| {
+            map.get(&key.to_owned(), |key| {
+                let trigger = dir.join(key);
+                let key = key.clone();
+                let count = count.clone();
+                HostFact::new(
+                    HostFactPolicy {
+                        ttl: None,
+                        triggers: Box::new(move || vec![trigger.clone()]),
+                    },
+                    move || Ok(format!("{}{}", key, count.fetch_add(1, Ordering::SeqCst))),
+                )
+            })
+        };
+
+        assert_eq!("a0", get("a")?);
+        assert_eq!("b1", get("b")?);
+        assert_eq!("a0", get("a")?);
+        assert!(map.invalidate_stale().is_empty());
+
+        std::fs::write(tempdir.path().join("b"), "")?;
+        assert_eq!(vec!["b".to_owned()], map.invalidate_stale());
+        assert_eq!("a0", get("a")?);
+        assert_eq!("b2", get("b")?);
+
+        std::fs::write(tempdir.path().join("a"), "")?;
+        assert_eq!(vec!["a".to_owned()], map.invalidate_stale());
+        assert!(map.invalidate_stale().is_empty());
+        assert_eq!("a3", get("a")?);
+
+        let mut entries = map.entries()?;
+        entries.sort();
+        assert_eq!(
+            vec![
+                ("a".to_owned(), "a3".to_owned()),
+                ("b".to_owned(), "b2".to_owned())
+            ],
+            entries
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_host_fact_ttl() -> anyhow::Result<()> {
+        let (fact, count) = counting_fact(HostFactPolicy {
+            ttl: Some(Duration::ZERO),
+            triggers: Box::new(Vec::new),
+        });
+        fact.get()?;
+        fact.get()?;
+        assert_eq!(2, count.load(Ordering::SeqCst));
+        Ok(())
+    }
+}
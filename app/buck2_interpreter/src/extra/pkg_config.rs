@@ -0,0 +1,311 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Queries of `pkg-config` for libraries installed on the host, made by `pkg_config()`.
+//!
+//! Each query runs `pkg-config` (or `$PKG_CONFIG`) with a fixed set of arguments, and its result
+//! is cached by the daemon in a [`HostFactMap`], and invalidated when the `.pc` file of the
+//! package, or a directory of the `pkg-config` search path, changes.
+//!
+//! On DICE, each query is a key, computed on the blocking executor. Stale queries are detected at
+//! the start of each command, which then invalidates their keys: only the files which made a
+//! query whose result changed are evaluated again.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use allocative::Allocative;
+use anyhow::Context;
+use async_trait::async_trait;
+use buck2_core::env::usage::record_process_env_usage;
+use buck2_core::env::usage::EnvUsageSource;
+use buck2_execute::execute::blocking::HasBlockingExecutor;
+use buck2_futures::cancellation::CancellationContext;
+use buck2_util::process::background_command;
+use derive_more::Display;
+use dice::DiceComputations;
+use dice::DiceTransactionUpdater;
+use dice::Key;
+use once_cell::sync::Lazy;
+
+use crate::extra::host_facts::compute_from_starlark;
+use crate::extra::host_facts::HostFact;
+use crate::extra::host_facts::HostFactMap;
+use crate::extra::host_facts::HostFactPolicy;
+
+#[derive(buck2_error::Error, Debug)]
+enum PkgConfigError {
+    #[error(
+        "Invalid pkg-config package name `{0}`: must only contain letters, digits, `-`, `_`, `.` and `+`"
+    )]
+    InvalidPackage(String),
+    #[error("`{command}` failed with {status}:\n{stderr}")]
+    Failed {
+        command: String,
+        status: String,
+        stderr: String,
+    },
+}
+
+/// A library to look up with `pkg-config`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Allocative)]
+pub struct PkgConfigQuery {
+    /// e.g. "zlib"
+    pub package: String,
+    /// Whether to ask for the flags to link the library statically.
+    pub static_libs: bool,
+}
+
+impl PkgConfigQuery {
+    pub fn new(package: &str, static_libs: bool) -> anyhow::Result<Self> {
+        if package.is_empty()
+            || package.starts_with('-')
+            || !package
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '+'))
+        {
+            return Err(PkgConfigError::InvalidPackage(package.to_owned()).into());
+        }
+        Ok(Self {
+            package: package.to_owned(),
+            static_libs,
+        })
+    }
+
+    fn run(&self) -> anyhow::Result<Option<PkgConfigPackage>> {
+        let binary = pkg_config_binary(&self.package);
+        let exists = background_command(&binary)
+            .args(["--exists", &self.package])
+            .status()
+            .with_context(|| format!("Error running `{}`", binary))?;
+        if !exists.success() {
+            return Ok(None);
+        }
+        let mut libs_args = vec!["--libs"];
+        if self.static_libs {
+            libs_args.push("--static");
+        }
+        Ok(Some(PkgConfigPackage {
+            version: run(&binary, &["--modversion"], &self.package)?
+                .trim()
+                .to_owned(),
+            cflags: split_flags(&run(&binary, &["--cflags"], &self.package)?),
+            libs: split_flags(&run(&binary, &libs_args, &self.package)?),
+        }))
+    }
+
+    /// Paths whose change can change the result of the query: the directories of the search
+    /// path, where the `.pc` file may appear or disappear, and the `.pc` file in each of them.
+    fn triggers(&self) -> Vec<PathBuf> {
+        let file_name = format!("{}.pc", self.package);
+        search_path(&self.package)
+            .iter()
+            .flat_map(|dir| [dir.clone(), dir.join(&file_name)])
+            .collect()
+    }
+}
+
+/// Flags of a library, as reported by `pkg-config`.
+#[derive(Debug, Clone, PartialEq, Eq, Allocative)]
+pub struct PkgConfigPackage {
+    /// e.g. "1.2.13"
+    pub version: String,
+    /// Flags to compile against the library, e.g. `["-I/usr/include/foo"]`.
+    pub cflags: Vec<String>,
+    /// Flags to link against the library, e.g. `["-L/usr/lib/foo", "-lfoo"]`.
+    pub libs: Vec<String>,
+}
+
+static PKG_CONFIG_QUERIES: Lazy<HostFactMap<PkgConfigQuery, Option<PkgConfigPackage>>> =
+    Lazy::new(HostFactMap::default);
+
+/// Where `pkg-config` looks for `.pc` files.
+struct SearchPath {
+    /// The variables it was computed from, and their values.
+    vars: Vec<(&'static str, Option<String>)>,
+    dirs: Vec<PathBuf>,
+}
+
+/// The environment of the daemon does not change, so the search path is only computed once.
+static SEARCH_PATH: Lazy<SearchPath> = Lazy::new(|| {
+    let mut vars = Vec::new();
+    let mut dirs = Vec::new();
+    for var in ["PKG_CONFIG_PATH", "PKG_CONFIG_LIBDIR"] {
+        let value = std::env::var_os(var);
+        vars.push((
+            var,
+            value
+                .as_ref()
+                .and_then(|v| v.to_str())
+                .map(|v| v.to_owned()),
+        ));
+        if let Some(value) = value {
+            dirs.extend(std::env::split_paths(&value).filter(|d| !d.as_os_str().is_empty()));
+            if var == "PKG_CONFIG_LIBDIR" {
+                // It replaces the default search path.
+                return SearchPath { vars, dirs };
+            }
+        }
+    }
+    // If `pkg-config` is not installed, there is no default search path.
+    if let Ok(default) = run(
+        &pkg_config_binary("pkg-config"),
+        &["--variable", "pc_path"],
+        "pkg-config",
+    ) {
+        dirs.extend(std::env::split_paths(default.trim()).filter(|d| !d.as_os_str().is_empty()));
+    }
+    SearchPath { vars, dirs }
+});
+
+/// Directories where `pkg-config` looks for the `.pc` file of `package`. The variables they
+/// depend on are recorded as read for `package` on every call, not only when first computed.
+fn search_path(package: &str) -> &'static [PathBuf] {
+    for (var, value) in &SEARCH_PATH.vars {
+        record_process_env_usage(
+            EnvUsageSource::Interpreter,
+            var,
+            value.as_deref(),
+            Some(package),
+        );
+    }
+    &SEARCH_PATH.dirs
+}
+
+fn pkg_config_binary(package: &str) -> String {
+    let binary = std::env::var("PKG_CONFIG").ok();
+    record_process_env_usage(
+        EnvUsageSource::Interpreter,
+        "PKG_CONFIG",
+        binary.as_deref(),
+        Some(package),
+    );
+    binary.unwrap_or_else(|| "pkg-config".to_owned())
+}
+
+fn run(binary: &str, args: &[&str], package: &str) -> anyhow::Result<String> {
+    let output = background_command(binary)
+        .args(args)
+        .arg(package)
+        .output()
+        .with_context(|| format!("Error running `{}`", binary))?;
+    if !output.status.success() {
+        return Err(PkgConfigError::Failed {
+            command: format!("{} {} {}", binary, args.join(" "), package),
+            status: output.status.to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        }
+        .into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Split flags printed by `pkg-config`, which escapes spaces within a flag with backslashes.
+fn split_flags(flags: &str) -> Vec<String> {
+    shlex::split(flags).unwrap_or_else(|| flags.split_whitespace().map(|f| f.to_owned()).collect())
+}
+
+/// Look up a library with `pkg-config`, returning `None` if it is not installed. The result is
+/// cached until the files it was read from change. This blocks on `pkg-config`: evaluation goes
+/// through [`OpaquePkgConfigOnDice`] instead.
+pub fn pkg_config(query: &PkgConfigQuery) -> anyhow::Result<Option<PkgConfigPackage>> {
+    PKG_CONFIG_QUERIES
+        .get(query, |query| {
+            let triggers = query.clone();
+            let compute = query.clone();
+            HostFact::new(
+                HostFactPolicy {
+                    ttl: None,
+                    triggers: Box::new(move || triggers.triggers()),
+                },
+                move || compute.run(),
+            )
+        })
+        .with_context(|| format!("Error querying pkg-config for `{}`", query.package))
+}
+
+#[derive(Debug, Display, Clone, Eq, PartialEq, Hash, Allocative)]
+#[display(fmt = "PkgConfig({}, static = {})", "_0.package", "_0.static_libs")]
+struct PkgConfigQueryKey(PkgConfigQuery);
+
+#[async_trait]
+impl Key for PkgConfigQueryKey {
+    type Value = buck2_error::Result<Option<Arc<PkgConfigPackage>>>;
+
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        let query = &self.0;
+        let found = ctx
+            .get_blocking_executor()
+            .execute_io_inline(|| pkg_config(query))
+            .await?;
+        Ok(found.map(Arc::new))
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        match (x, y) {
+            (Ok(x), Ok(y)) => x == y,
+            _ => false,
+        }
+    }
+
+    fn validity(x: &Self::Value) -> bool {
+        x.is_ok()
+    }
+}
+
+/// Look up a library with `pkg-config` on DICE, from the evaluation of Starlark.
+pub fn pkg_config_on_dice(
+    ctx: &mut DiceComputations,
+    query: &PkgConfigQuery,
+) -> anyhow::Result<Option<Arc<PkgConfigPackage>>> {
+    compute_from_starlark(ctx, &PkgConfigQueryKey(query.clone()))
+}
+
+pub trait InvalidateStalePkgConfig {
+    /// Invalidate the `pkg-config` queries on DICE whose result may have changed since the last
+    /// command.
+    fn invalidate_stale_pkg_config(&mut self) -> anyhow::Result<()>;
+}
+
+impl InvalidateStalePkgConfig for DiceTransactionUpdater {
+    fn invalidate_stale_pkg_config(&mut self) -> anyhow::Result<()> {
+        let stale = PKG_CONFIG_QUERIES.invalidate_stale();
+        if !stale.is_empty() {
+            self.changed(stale.into_iter().map(PkgConfigQueryKey).collect::<Vec<_>>())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_package_names() {
+        assert!(PkgConfigQuery::new("zlib", false).is_ok());
+        assert!(PkgConfigQuery::new("gtk+-3.0", true).is_ok());
+        assert!(PkgConfigQuery::new("", false).is_err());
+        assert!(PkgConfigQuery::new("--help", false).is_err());
+        assert!(PkgConfigQuery::new("zlib >= 1.2", false).is_err());
+    }
+
+    #[test]
+    fn test_split_flags() {
+        assert_eq!(
+            vec!["-I/usr/include/my dir", "-DFOO=1"],
+            split_flags("-I/usr/include/my\\ dir  -DFOO=1 \n")
+        );
+        assert!(split_flags("\n").is_empty());
+    }
+}
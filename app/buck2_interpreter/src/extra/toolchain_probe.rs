@@ -0,0 +1,466 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Discovery of toolchains installed on the host.
+//!
+//! Toolchain macros call `host_toolchain()` to look for a compiler or interpreter on the host
+//! `PATH`. Each distinct probe is a DICE key, run on the blocking executor. The daemon keeps the
+//! probes run so far in [`HostToolchains`], which detects at the start of each command the probes
+//! whose binary, or a directory of `PATH`, changed: their keys are then invalidated, and only the
+//! files which requested them are evaluated again. The files that requested each probe in their
+//! last evaluation are recorded, so that `buck2 toolchain list` can report what was discovered
+//! and who uses it.
+
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use allocative::Allocative;
+use anyhow::Context;
+use async_trait::async_trait;
+use buck2_core::env::usage::record_process_env_usage;
+use buck2_core::env::usage::EnvUsageSource;
+use buck2_core::fs::fs_util;
+use buck2_execute::execute::blocking::HasBlockingExecutor;
+use buck2_futures::cancellation::CancellationContext;
+use buck2_util::process::background_command;
+use derive_more::Display;
+use dice::DiceComputations;
+use dice::DiceTransactionUpdater;
+use dice::Key;
+use dice::UserComputationData;
+use dupe::Dupe;
+use futures::FutureExt;
+use parking_lot::Mutex;
+use regex::Regex;
+
+use crate::extra::host_facts::compute_from_starlark;
+use crate::extra::host_facts::HostFact;
+use crate::extra::host_facts::HostFactMap;
+use crate::extra::host_facts::HostFactPolicy;
+
+#[derive(buck2_error::Error, Debug)]
+enum HostToolchainProbeError {
+    #[error("No built-in probe for toolchain `{0}`, `binaries` must be specified")]
+    UnknownToolchain(String),
+    #[error("Probe for toolchain `{0}` must list at least one binary")]
+    NoBinaries(String),
+    #[error("Version regex `{0}` for toolchain `{1}` must have a capture group")]
+    NoCaptureGroup(String, String),
+}
+
+/// Default arguments passed to a probed binary to print its version.
+const DEFAULT_VERSION_ARGS: &[&str] = &["--version"];
+
+/// Default regex used to extract a version from the output of a probed binary.
+const DEFAULT_VERSION_REGEX: &str = r"(\d+(?:\.\d+)+)";
+
+/// Description of how to find a toolchain on the host.
+#[derive(Debug, Display, Clone, PartialEq, Eq, Hash, Allocative)]
+#[display(fmt = "{}", name)]
+pub struct HostToolchainProbe {
+    /// e.g. "clang"
+    pub name: String,
+    /// Binaries to look for on `PATH`, in order of preference.
+    pub binaries: Vec<String>,
+    /// Arguments which make the binary print its version.
+    pub version_args: Vec<String>,
+    /// Regex with a capture group matching the version in the output of the binary.
+    pub version_regex: String,
+}
+
+impl HostToolchainProbe {
+    /// Names of the toolchains which can be probed without further configuration.
+    pub const BUILTIN: &'static [&'static str] = &["clang", "gcc", "rustc", "python"];
+
+    /// Construct a probe, filling in defaults from the built-in probe of the same name.
+    pub fn new(
+        name: &str,
+        binaries: Option<Vec<String>>,
+        version_args: Option<Vec<String>>,
+        version_regex: Option<String>,
+    ) -> anyhow::Result<Self> {
+        let builtin = Self::builtin(name);
+        let binaries = match (binaries, &builtin) {
+            (Some(binaries), _) => binaries,
+            (None, Some(builtin)) => builtin.binaries.clone(),
+            (None, None) => {
+                return Err(HostToolchainProbeError::UnknownToolchain(name.to_owned()).into());
+            }
+        };
+        if binaries.is_empty() {
+            return Err(HostToolchainProbeError::NoBinaries(name.to_owned()).into());
+        }
+        let version_args = version_args
+            .or_else(|| builtin.as_ref().map(|b| b.version_args.clone()))
+            .unwrap_or_else(|| {
+                DEFAULT_VERSION_ARGS
+                    .iter()
+                    .map(|a| (*a).to_owned())
+                    .collect()
+            });
+        let version_regex = version_regex
+            .or_else(|| builtin.as_ref().map(|b| b.version_regex.clone()))
+            .unwrap_or_else(|| DEFAULT_VERSION_REGEX.to_owned());
+        let re = Regex::new(&version_regex)
+            .with_context(|| format!("Invalid version regex for toolchain `{}`", name))?;
+        if re.captures_len() < 2 {
+            return Err(
+                HostToolchainProbeError::NoCaptureGroup(version_regex, name.to_owned()).into(),
+            );
+        }
+        Ok(Self {
+            name: name.to_owned(),
+            binaries,
+            version_args,
+            version_regex,
+        })
+    }
+
+    /// The built-in probe for a toolchain, if there is one.
+    pub fn builtin(name: &str) -> Option<Self> {
+        let (binaries, version_regex): (&[&str], &str) = match name {
+            "clang" => (&["clang"], r"clang version (\d+(?:\.\d+)*)"),
+            "gcc" => (&["gcc"], r"(\d+\.\d+(?:\.\d+)?)"),
+            "rustc" => (&["rustc"], r"rustc (\d+\.\d+\.\d+\S*)"),
+            "python" => (&["python3", "python"], r"Python (\d+\.\d+(?:\.\d+)?)"),
+            _ => return None,
+        };
+        Some(Self {
+            name: name.to_owned(),
+            binaries: binaries.iter().map(|b| (*b).to_owned()).collect(),
+            version_args: DEFAULT_VERSION_ARGS
+                .iter()
+                .map(|a| (*a).to_owned())
+                .collect(),
+            version_regex: version_regex.to_owned(),
+        })
+    }
+
+    /// Run the probe, without caching.
+    pub fn run(&self) -> anyhow::Result<Option<HostToolchain>> {
+        let path = match self.binaries.iter().find_map(|b| find_in_path(b)) {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        let output = background_command(&path)
+            .args(&self.version_args)
+            .output()
+            .with_context(|| format!("Error running `{}`", path.display()))?;
+        // Some tools (e.g. older Pythons) print their version to stderr.
+        let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+        text.push_str(&String::from_utf8_lossy(&output.stderr));
+        let re = Regex::new(&self.version_regex)?;
+        let version = re
+            .captures(&text)
+            .and_then(|c| c.get(1))
+            .map(|m| m.as_str().to_owned());
+        Ok(Some(HostToolchain {
+            path: path.to_string_lossy().into_owned(),
+            version,
+        }))
+    }
+
+    /// Paths whose change can change the result of the probe: the directories of `PATH`, where
+    /// a binary may appear or disappear, and the binary that was found, which may be upgraded in
+    /// place or be a symlink switched to another version.
+    fn triggers(&self) -> Vec<PathBuf> {
+        let mut triggers = search_path();
+        if let Some(path) = self.binaries.iter().find_map(|b| find_in_path(b)) {
+            if let Ok(resolved) = fs_util::canonicalize(&path) {
+                triggers.push(resolved.into_path_buf());
+            }
+            triggers.push(path);
+        }
+        triggers
+    }
+}
+
+/// A toolchain found on the host.
+#[derive(Debug, Clone, PartialEq, Eq, Allocative)]
+pub struct HostToolchain {
+    /// Absolute path to the binary that was found.
+    pub path: String,
+    /// Version reported by the binary, if it could be parsed.
+    pub version: Option<String>,
+}
+
+/// A probe that was run by this daemon, and the files that requested it.
+#[derive(Debug, Clone)]
+pub struct ProbedHostToolchain {
+    pub probe: HostToolchainProbe,
+    pub result: Option<HostToolchain>,
+    pub used_by: Vec<String>,
+}
+
+/// The probes run by the daemon, and the files which requested them. Owned by the daemon and
+/// shared by its commands.
+#[derive(Default, Allocative)]
+pub struct HostToolchains {
+    #[allocative(skip)]
+    probes: HostFactMap<HostToolchainProbe, Option<HostToolchain>>,
+    /// The probes requested by each file in its last evaluation.
+    #[allocative(skip)]
+    uses: Mutex<HashMap<String, BTreeSet<HostToolchainProbe>>>,
+}
+
+impl HostToolchains {
+    /// Run a probe, or return its cached result if it already ran in this daemon and nothing it
+    /// depends on changed since.
+    fn probe(&self, probe: &HostToolchainProbe) -> anyhow::Result<Option<HostToolchain>> {
+        self.probes
+            .get(probe, |probe| {
+                let triggers = probe.clone();
+                let compute = probe.clone();
+                HostFact::new(
+                    HostFactPolicy {
+                        ttl: None,
+                        triggers: Box::new(move || triggers.triggers()),
+                    },
+                    move || compute.run(),
+                )
+            })
+            .with_context(|| format!("Error probing host toolchain `{}`", probe.name))
+    }
+
+    /// Record that `user` requested `probe`.
+    fn record_use(&self, user: &str, probe: &HostToolchainProbe) {
+        self.uses
+            .lock()
+            .entry(user.to_owned())
+            .or_default()
+            .insert(probe.clone());
+    }
+
+    /// Forget the probes requested by `user`, which is about to be evaluated again.
+    pub fn forget_uses(&self, user: &str) {
+        self.uses.lock().remove(user);
+    }
+
+    /// All the probes run by this daemon so far, sorted by name.
+    pub fn probed(&self) -> anyhow::Result<Vec<ProbedHostToolchain>> {
+        let probes = self
+            .probes
+            .entries()
+            .context("Error probing host toolchains")?;
+        let mut used_by: HashMap<&HostToolchainProbe, Vec<String>> = HashMap::new();
+        let uses = self.uses.lock();
+        for (user, probes) in uses.iter() {
+            for probe in probes {
+                used_by.entry(probe).or_default().push(user.clone());
+            }
+        }
+        let mut res: Vec<_> = probes
+            .into_iter()
+            .map(|(probe, result)| {
+                let mut used_by = used_by.remove(&probe).unwrap_or_default();
+                used_by.sort();
+                ProbedHostToolchain {
+                    probe,
+                    result,
+                    used_by,
+                }
+            })
+            .collect();
+        res.sort_by(|a, b| {
+            (&a.probe.name, &a.probe.binaries).cmp(&(&b.probe.name, &b.probe.binaries))
+        });
+        Ok(res)
+    }
+}
+
+pub trait HasHostToolchains {
+    fn set_host_toolchains(&mut self, toolchains: Arc<HostToolchains>);
+
+    fn get_host_toolchains(&self) -> Arc<HostToolchains>;
+}
+
+impl HasHostToolchains for UserComputationData {
+    fn set_host_toolchains(&mut self, toolchains: Arc<HostToolchains>) {
+        self.data.set(toolchains);
+    }
+
+    fn get_host_toolchains(&self) -> Arc<HostToolchains> {
+        self.data
+            .get::<Arc<HostToolchains>>()
+            .expect("Host toolchains should be set")
+            .dupe()
+    }
+}
+
+#[derive(Debug, Display, Clone, Eq, PartialEq, Hash, Allocative)]
+#[display(fmt = "HostToolchain({})", _0)]
+struct HostToolchainProbeKey(HostToolchainProbe);
+
+#[async_trait]
+impl Key for HostToolchainProbeKey {
+    type Value = buck2_error::Result<Option<HostToolchain>>;
+
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        let toolchains = ctx.per_transaction_data().get_host_toolchains();
+        let probe = &self.0;
+        Ok(ctx
+            .get_blocking_executor()
+            .execute_io_inline(|| toolchains.probe(probe))
+            .await?)
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        match (x, y) {
+            (Ok(x), Ok(y)) => x == y,
+            _ => false,
+        }
+    }
+
+    fn validity(x: &Self::Value) -> bool {
+        x.is_ok()
+    }
+}
+
+/// Run a probe on DICE from the evaluation of Starlark, recording `user` as a user of the
+/// toolchain.
+pub fn probe_host_toolchain_on_dice(
+    ctx: &mut DiceComputations,
+    probe: &HostToolchainProbe,
+    user: &str,
+) -> anyhow::Result<Option<HostToolchain>> {
+    ctx.per_transaction_data()
+        .get_host_toolchains()
+        .record_use(user, probe);
+    compute_from_starlark(ctx, &HostToolchainProbeKey(probe.clone()))
+}
+
+/// Run probes on DICE, without recording a user.
+pub async fn probe_host_toolchains(
+    ctx: &mut DiceComputations<'_>,
+    probes: Vec<HostToolchainProbe>,
+) -> anyhow::Result<Vec<Option<HostToolchain>>> {
+    ctx.try_compute_join(probes, |ctx, probe| {
+        async move { Ok(ctx.compute(&HostToolchainProbeKey(probe)).await??) }.boxed()
+    })
+    .await
+}
+
+pub trait InvalidateStaleHostToolchains {
+    /// Invalidate the probes on DICE whose result may have changed since the last command.
+    fn invalidate_stale_host_toolchains(
+        &mut self,
+        toolchains: &HostToolchains,
+    ) -> anyhow::Result<()>;
+}
+
+impl InvalidateStaleHostToolchains for DiceTransactionUpdater {
+    fn invalidate_stale_host_toolchains(
+        &mut self,
+        toolchains: &HostToolchains,
+    ) -> anyhow::Result<()> {
+        let stale = toolchains.probes.invalidate_stale();
+        if !stale.is_empty() {
+            self.changed(
+                stale
+                    .into_iter()
+                    .map(HostToolchainProbeKey)
+                    .collect::<Vec<_>>(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Directories of the `PATH` of the daemon.
+fn search_path() -> Vec<PathBuf> {
+    std::env::var_os("PATH")
+        .map(|path| {
+            std::env::split_paths(&path)
+                .filter(|d| !d.as_os_str().is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn find_in_path(binary: &str) -> Option<PathBuf> {
+    if Path::new(binary).is_absolute() {
+        return Path::new(binary).is_file().then(|| PathBuf::from(binary));
+    }
+    let path = std::env::var_os("PATH");
+    record_process_env_usage(
+        EnvUsageSource::Interpreter,
+        "PATH",
+        path.as_ref().and_then(|p| p.to_str()),
+        Some(binary),
+    );
+    let path = path?;
+    std::env::split_paths(&path).find_map(|dir| {
+        let candidate = dir.join(binary);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        if cfg!(windows) {
+            let candidate = candidate.with_extension("exe");
+            if candidate.is_file() {
+                return Some(candidate);
+            }
+        }
+        None
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_defaults() {
+        let probe = HostToolchainProbe::new("python", None, None, None).unwrap();
+        assert_eq!(probe, HostToolchainProbe::builtin("python").unwrap());
+        assert_eq!(vec!["python3", "python"], probe.binaries);
+    }
+
+    #[test]
+    fn test_unknown_requires_binaries() {
+        assert!(HostToolchainProbe::new("zig", None, None, None).is_err());
+        let probe =
+            HostToolchainProbe::new("zig", Some(vec!["zig".to_owned()]), None, None).unwrap();
+        assert_eq!(vec!["--version"], probe.version_args);
+        assert_eq!(DEFAULT_VERSION_REGEX, probe.version_regex);
+    }
+
+    #[test]
+    fn test_regex_needs_capture_group() {
+        assert!(HostToolchainProbe::new("clang", None, None, Some(r"\d+".to_owned())).is_err());
+    }
+
+    #[test]
+    fn test_missing_binary() {
+        let toolchains = HostToolchains::default();
+        let probe = HostToolchainProbe::new(
+            "missing",
+            Some(vec!["buck2-test-binary-that-does-not-exist".to_owned()]),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(None, toolchains.probe(&probe).unwrap());
+        toolchains.record_use("root//:BUCK", &probe);
+        let probed = toolchains.probed().unwrap();
+        assert_eq!(1, probed.len());
+        assert_eq!(probe, probed[0].probe);
+        assert_eq!(vec!["root//:BUCK"], probed[0].used_by);
+
+        toolchains.forget_uses("root//:BUCK");
+        let probed = toolchains.probed().unwrap();
+        assert!(probed[0].used_by.is_empty());
+    }
+}
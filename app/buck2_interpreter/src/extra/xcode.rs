@@ -10,14 +10,19 @@
 use std::io;
 use std::path::Path;
 use std::path::PathBuf;
+use std::time::Duration;
 
 use allocative::Allocative;
 use anyhow::Context;
 use buck2_core::fs::fs_util;
 use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+use once_cell::sync::Lazy;
 use regex::Regex;
 use serde::Deserialize;
 
+use crate::extra::host_facts::HostFact;
+use crate::extra::host_facts::HostFactPolicy;
+
 #[derive(buck2_error::Error, Debug)]
 enum XcodeVersionError {
     #[error("XCode select symlink `{}` resolved to path without parent: `{}`", XCODE_SELECT_SYMLINK, _0.display())]
@@ -30,6 +35,29 @@ enum XcodeVersionError {
 
 const XCODE_SELECT_SYMLINK: &str = "/var/db/xcode_select_link";
 
+/// The selected Xcode changes with `xcode-select --switch`, which replaces the symlink, or when
+/// the selected Xcode is updated in place, which rewrites its `version.plist`. The TTL catches
+/// anything else, e.g. a symlink replaced within the mtime granularity.
+static SELECTED_XCODE_VERSION: Lazy<HostFact<Option<XcodeVersionInfo>>> = Lazy::new(|| {
+    HostFact::new(
+        HostFactPolicy {
+            ttl: Some(Duration::from_secs(60 * 60)),
+            triggers: Box::new(|| {
+                let mut triggers = vec![PathBuf::from(XCODE_SELECT_SYMLINK)];
+                if let Ok(Some(resolved)) =
+                    fs_util::canonicalize_if_exists(PathBuf::from(XCODE_SELECT_SYMLINK))
+                {
+                    if let Some(parent) = resolved.parent() {
+                        triggers.push(parent.as_path().join("version.plist"));
+                    }
+                }
+                triggers
+            }),
+        },
+        XcodeVersionInfo::new,
+    )
+});
+
 /// Only fields we care about from Xcode version.plist.
 #[derive(Deserialize)]
 #[allow(non_snake_case)]
@@ -54,6 +82,11 @@ pub struct XcodeVersionInfo {
 }
 
 impl XcodeVersionInfo {
+    /// Version of the selected Xcode, cached by the daemon until the selection changes.
+    pub fn selected() -> anyhow::Result<Option<Self>> {
+        SELECTED_XCODE_VERSION.get()
+    }
+
     // Construct from version.plist in root of Xcode install dir.
     pub fn new() -> anyhow::Result<Option<Self>> {
         let resolved_xcode_path =
@@ -25,12 +25,14 @@ use dice::DiceComputations;
 use dice::Key;
 use dupe::Dupe;
 
+use crate::bazel_compat::BazelCompat;
 use crate::package_imports::PackageImplicitImports;
 
 #[derive(PartialEq, Allocative)]
 pub struct ImplicitImportPaths {
     pub root_import: Option<ImportPath>,
     pub package_imports: PackageImplicitImports,
+    pub bazel_compat: Option<Arc<BazelCompat>>,
 }
 
 impl ImplicitImportPaths {
@@ -67,15 +69,22 @@ impl ImplicitImportPaths {
                 })?
                 .as_deref(),
         )?;
+        let bazel_compat =
+            BazelCompat::parse(&mut config, cell_name, cell_alias_resolver)?.map(Arc::new);
         Ok(ImplicitImportPaths {
             root_import,
             package_imports,
+            bazel_compat,
         })
     }
 
     pub fn root_import(&self) -> Option<&ImportPath> {
         self.root_import.as_ref()
     }
+
+    pub fn bazel_compat(&self) -> Option<&Arc<BazelCompat>> {
+        self.bazel_compat.as_ref()
+    }
 }
 
 #[async_trait]
@@ -16,6 +16,7 @@
 #![feature(never_type)]
 #![feature(box_patterns)]
 
+pub mod bazel_compat;
 pub mod build_context;
 pub mod coerce;
 pub mod dice;
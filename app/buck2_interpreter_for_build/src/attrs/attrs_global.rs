@@ -23,6 +23,7 @@ use buck2_node::attrs::attr_type::AttrType;
 use buck2_node::attrs::coercion_context::AttrCoercionContext;
 use buck2_node::attrs::configurable::AttrIsConfigurable;
 use buck2_node::attrs::display::AttrDisplayWithContextExt;
+use buck2_node::deprecation::Deprecation;
 use buck2_node::provider_id_set::ProviderIdSet;
 use derive_more::Display;
 use dupe::Dupe;
@@ -438,6 +439,27 @@ fn attr_module(registry: &mut MethodsBuilder) {
         )))
     }
 
+    /// Marks an attribute as deprecated. Commands loading targets which set the attribute
+    /// explicitly print a warning listing them, builds fail with `--strict-deprecations`.
+    /// Only has an effect on attributes passed directly to `rule()`.
+    ///
+    /// ```python
+    /// attrs.deprecated(attrs.bool(default = False), "Use `link_style` instead", removal_milestone = "2024-06")
+    /// ```
+    fn deprecated<'v>(
+        #[starlark(this)] _this: Value<'v>,
+        #[starlark(require = pos)] inner: &StarlarkAttribute,
+        #[starlark(require = pos)] message: &str,
+        #[starlark(require = named)] removal_milestone: Option<&str>,
+    ) -> anyhow::Result<StarlarkAttribute> {
+        Ok(StarlarkAttribute::new(
+            inner.clone_attribute().with_deprecation(Deprecation {
+                message: message.to_owned(),
+                removal: removal_milestone.map(|x| x.to_owned()),
+            }),
+        ))
+    }
+
     /// Takes a target (as per `deps`) and passes a `label` to the rule.
     /// Validates that the target exists, but does not introduce a dependency on it.
     fn label<'v>(
@@ -584,6 +606,28 @@ fn attr_module(registry: &mut MethodsBuilder) {
         Attribute::attr(eval, default, doc, AttrType::int())
     }
 
+    /// Takes a duration from the user, either an int of milliseconds or a string with a unit
+    /// (`"250ms"`, `"30s"`, `"5m"`, `"2h"`, `"1d"`), supplies an int of milliseconds to the rule.
+    fn duration<'v>(
+        #[starlark(this)] _this: Value<'v>,
+        #[starlark(require = named)] default: Option<Value<'v>>,
+        #[starlark(require = named, default = "")] doc: &str,
+        eval: &mut Evaluator<'v, '_, '_>,
+    ) -> anyhow::Result<StarlarkAttribute> {
+        Attribute::attr(eval, default, doc, AttrType::duration())
+    }
+
+    /// Takes a memory size from the user, either an int of bytes or a string with a unit
+    /// (`"10B"`, `"2KB"`, `"512MiB"`, `"4GiB"`), supplies an int of bytes to the rule.
+    fn memory_size<'v>(
+        #[starlark(this)] _this: Value<'v>,
+        #[starlark(require = named)] default: Option<Value<'v>>,
+        #[starlark(require = named, default = "")] doc: &str,
+        eval: &mut Evaluator<'v, '_, '_>,
+    ) -> anyhow::Result<StarlarkAttribute> {
+        Attribute::attr(eval, default, doc, AttrType::memory_size())
+    }
+
     fn query<'v>(
         #[starlark(this)] _this: Value<'v>,
         #[starlark(require = named, default = "")] doc: &str,
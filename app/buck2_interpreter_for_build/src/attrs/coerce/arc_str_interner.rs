@@ -9,12 +9,15 @@
 
 use std::cell::RefCell;
 
+use buck2_node::attrs::str_interner::intern_str_globally;
 use buck2_util::arc_str::ArcStr;
 use dupe::Dupe;
 use hashbrown::raw::RawTable;
 
 use crate::attrs::coerce::str_hash::str_hash;
 
+/// Per-file cache in front of the daemon-wide string interner, which avoids taking its locks
+/// for strings repeated within a file.
 pub(crate) struct ArcStrInterner {
     cache: RefCell<RawTable<(u64, ArcStr)>>,
 }
@@ -38,7 +41,7 @@ impl ArcStrInterner {
             return v.dupe();
         }
 
-        let value = ArcStr::from(s);
+        let value = intern_str_globally(s);
         cache.insert(hash, (hash, value.dupe()), |(h, _v)| *h);
         value
     }
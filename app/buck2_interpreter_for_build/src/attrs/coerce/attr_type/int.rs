@@ -27,13 +27,29 @@ impl AttrTypeCoerce for IntAttrType {
         _ctx: &dyn AttrCoercionContext,
         value: Value,
     ) -> anyhow::Result<CoercedAttr> {
-        match i64::unpack_value(value).into_anyhow_result()? {
-            Some(x) => Ok(CoercedAttr::Int(x)),
-            None => Err(anyhow::anyhow!(CoercionError::type_error("int", value))),
+        if let Some(x) = i64::unpack_value(value).into_anyhow_result()? {
+            return Ok(CoercedAttr::Int(x));
+        }
+        if let Some(s) = value.unpack_str() {
+            if let Some(x) = self.parse_str(s) {
+                return Ok(CoercedAttr::Int(x?));
+            }
+        }
+        match self {
+            IntAttrType::Int => Err(anyhow::anyhow!(CoercionError::type_error("int", value))),
+            _ => Err(anyhow::anyhow!(CoercionError::type_error(
+                "int or str",
+                value
+            ))),
         }
     }
 
     fn starlark_type(&self) -> TyMaybeSelect {
-        TyMaybeSelect::Basic(Ty::int())
+        match self {
+            IntAttrType::Int => TyMaybeSelect::Basic(Ty::int()),
+            IntAttrType::Duration | IntAttrType::MemorySize => {
+                TyMaybeSelect::Basic(Ty::union2(Ty::int(), Ty::string()))
+            }
+        }
     }
 }
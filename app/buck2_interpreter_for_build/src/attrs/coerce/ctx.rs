@@ -7,6 +7,7 @@
  * of this source tree.
  */
 
+use std::borrow::Cow;
 use std::cell::RefCell;
 use std::fmt;
 use std::fmt::Debug;
@@ -25,7 +26,9 @@ use buck2_core::pattern::pattern_type::ProvidersPatternExtra;
 use buck2_core::pattern::pattern_type::TargetPatternExtra;
 use buck2_core::provider::label::ProvidersLabel;
 use buck2_core::soft_error;
+use buck2_core::soft_error_policy::SoftErrorPolicies;
 use buck2_core::target::label::interner::ConcurrentTargetLabelInterner;
+use buck2_interpreter::bazel_compat::normalize_bazel_label;
 use buck2_node::attrs::coerced_attr::CoercedAttr;
 use buck2_node::attrs::coerced_path::CoercedDirectory;
 use buck2_node::attrs::coerced_path::CoercedPath;
@@ -78,6 +81,10 @@ pub struct BuildAttrCoercionContext {
     enclosing_package: Option<(PackageLabel, PackageListing)>,
     /// Does this package (if present) have a package boundary exception on it.
     package_boundary_exception: bool,
+    /// Accept Bazel label syntax, see `normalize_bazel_label`.
+    bazel_labels: bool,
+    /// Decide whether the soft errors about this package are errors.
+    soft_error_policies: Option<Arc<SoftErrorPolicies>>,
     /// Allocator for `label_cache`.
     alloc: Bump,
     global_label_interner: Arc<ConcurrentTargetLabelInterner>,
@@ -119,6 +126,8 @@ impl BuildAttrCoercionContext {
             cell_alias_resolver,
             enclosing_package,
             package_boundary_exception,
+            bazel_labels: false,
+            soft_error_policies: None,
             alloc: Bump::new(),
             global_label_interner,
             label_cache: RefCell::new(HashTable::new()),
@@ -162,6 +171,22 @@ impl BuildAttrCoercionContext {
         )
     }
 
+    /// Accept Bazel label syntax when coercing labels, used for cells with `bazel_compat` enabled.
+    pub fn with_bazel_labels(mut self, bazel_labels: bool) -> Self {
+        self.bazel_labels = bazel_labels;
+        self
+    }
+
+    /// Apply the repository `soft_error_policies` to the soft errors raised while coercing.
+    pub fn with_soft_error_policies(mut self, soft_error_policies: Arc<SoftErrorPolicies>) -> Self {
+        self.soft_error_policies = Some(soft_error_policies);
+        self
+    }
+
+    pub(crate) fn soft_error_policies(&self) -> Option<&Arc<SoftErrorPolicies>> {
+        self.soft_error_policies.as_ref()
+    }
+
     pub fn parse_pattern<P: PatternType>(&self, value: &str) -> anyhow::Result<ParsedPattern<P>> {
         ParsedPattern::parsed_opt_absolute(
             value,
@@ -174,11 +199,16 @@ impl BuildAttrCoercionContext {
 
     fn coerce_label_no_cache(&self, value: &str) -> anyhow::Result<ProvidersLabel> {
         // TODO(nmj): Make this take an import path / package
-        match self.parse_pattern::<ProvidersPatternExtra>(value)? {
+        let value = if self.bazel_labels {
+            normalize_bazel_label(value)
+        } else {
+            Cow::Borrowed(value)
+        };
+        match self.parse_pattern::<ProvidersPatternExtra>(&value)? {
             ParsedPattern::Target(package, target_name, providers) => {
                 Ok(providers.into_providers_label(package, target_name.as_ref()))
             }
-            _ => Err(BuildAttrCoercionContextError::RequiredLabel(value.to_owned()).into()),
+            _ => Err(BuildAttrCoercionContextError::RequiredLabel(value.into_owned()).into()),
         }
     }
 
@@ -263,7 +293,12 @@ impl AttrCoercionContext for BuildAttrCoercionContext {
                 if self.package_boundary_exception {
                     info!("{} (could be due to a package boundary violation)", e);
                 } else {
-                    soft_error!("source_directory_includes_subpackage", e.into())?;
+                    soft_error!(
+                        "source_directory_includes_subpackage",
+                        e.into(),
+                        path: Some(package.to_string()),
+                        policies: self.soft_error_policies.dupe()
+                    )?;
                 }
             }
             let files = listing.files_within(&path).duped().collect();
@@ -277,7 +312,13 @@ impl AttrCoercionContext for BuildAttrCoercionContext {
             if self.package_boundary_exception {
                 info!("{} (could be due to a package boundary violation)", e);
             } else {
-                soft_error!("source_file_missing", e.into(), quiet: true)?;
+                soft_error!(
+                    "source_file_missing",
+                    e.into(),
+                    quiet: true,
+                    path: Some(package.to_string()),
+                    policies: self.soft_error_policies.dupe()
+                )?;
             }
 
             Ok(CoercedPath::File(path.to_arc()))
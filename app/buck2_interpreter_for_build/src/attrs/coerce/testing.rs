@@ -101,6 +101,7 @@ pub fn to_value<'v>(env: &'v Module, globals: &Globals, content: &str) -> Value<
             .unwrap()
             .testing_cell_alias_resolver()
             .dupe(),
+        None,
     )
     .unwrap();
 
@@ -15,6 +15,14 @@ use std::sync::Arc;
 use buck2_common::legacy_configs::configs::LegacyBuckConfig;
 use buck2_common::legacy_configs::dice::OpaqueLegacyBuckConfigOnDice;
 use buck2_common::legacy_configs::key::BuckconfigKeyRef;
+use buck2_interpreter::extra::pkg_config::pkg_config;
+use buck2_interpreter::extra::pkg_config::pkg_config_on_dice;
+use buck2_interpreter::extra::pkg_config::PkgConfigPackage;
+use buck2_interpreter::extra::pkg_config::PkgConfigQuery;
+use buck2_interpreter::extra::toolchain_probe::probe_host_toolchain_on_dice;
+use buck2_interpreter::extra::toolchain_probe::HasHostToolchains;
+use buck2_interpreter::extra::toolchain_probe::HostToolchain;
+use buck2_interpreter::extra::toolchain_probe::HostToolchainProbe;
 use dice::DiceComputations;
 use hashbrown::raw::RawTable;
 use starlark::collections::Hashed;
@@ -35,6 +43,23 @@ pub trait BuckConfigsViewForStarlark {
     ) -> anyhow::Result<Option<Arc<str>>>;
 
     fn read_root_cell_config(&mut self, key: BuckconfigKeyRef) -> anyhow::Result<Option<Arc<str>>>;
+
+    /// Result of a `pkg_config()` call, which is host state rather than config, but is read the
+    /// same way.
+    fn pkg_config(
+        &mut self,
+        query: &PkgConfigQuery,
+    ) -> anyhow::Result<Option<Arc<PkgConfigPackage>>>;
+
+    /// Result of a `host_toolchain()` call requested by the file `user`.
+    fn host_toolchain(
+        &mut self,
+        probe: &HostToolchainProbe,
+        user: &str,
+    ) -> anyhow::Result<Option<HostToolchain>>;
+
+    /// Forget the toolchains requested by the file `user`, which is about to be evaluated again.
+    fn forget_host_toolchain_uses(&mut self, user: &str);
 }
 
 struct BuckConfigsInner<'a> {
@@ -165,6 +190,24 @@ impl<'a> LegacyBuckConfigsForStarlark<'a> {
         // `StringValue` caches the hashes.
         self.get_impl(section.get_hashed_str(), key.get_hashed_str(), true)
     }
+
+    pub(crate) fn pkg_config(
+        &self,
+        query: &PkgConfigQuery,
+    ) -> anyhow::Result<Option<Arc<PkgConfigPackage>>> {
+        self.inner.borrow_mut().configs_view.pkg_config(query)
+    }
+
+    pub(crate) fn host_toolchain(
+        &self,
+        probe: &HostToolchainProbe,
+        user: &str,
+    ) -> anyhow::Result<Option<HostToolchain>> {
+        self.inner
+            .borrow_mut()
+            .configs_view
+            .host_toolchain(probe, user)
+    }
 }
 
 pub(crate) struct ConfigsOnDiceViewForStarlark<'a, 'd> {
@@ -198,6 +241,28 @@ impl BuckConfigsViewForStarlark for ConfigsOnDiceViewForStarlark<'_, '_> {
     fn read_root_cell_config(&mut self, key: BuckconfigKeyRef) -> anyhow::Result<Option<Arc<str>>> {
         self.root_buckconfig.lookup(self.ctx, key)
     }
+
+    fn pkg_config(
+        &mut self,
+        query: &PkgConfigQuery,
+    ) -> anyhow::Result<Option<Arc<PkgConfigPackage>>> {
+        pkg_config_on_dice(self.ctx, query)
+    }
+
+    fn host_toolchain(
+        &mut self,
+        probe: &HostToolchainProbe,
+        user: &str,
+    ) -> anyhow::Result<Option<HostToolchain>> {
+        probe_host_toolchain_on_dice(self.ctx, probe, user)
+    }
+
+    fn forget_host_toolchain_uses(&mut self, user: &str) {
+        self.ctx
+            .per_transaction_data()
+            .get_host_toolchains()
+            .forget_uses(user);
+    }
 }
 
 pub struct LegacyConfigsViewForStarlark {
@@ -228,4 +293,21 @@ impl BuckConfigsViewForStarlark for LegacyConfigsViewForStarlark {
     fn read_root_cell_config(&mut self, key: BuckconfigKeyRef) -> anyhow::Result<Option<Arc<str>>> {
         Ok(self.root_cell_config.get(key).map(|v| v.to_owned().into()))
     }
+
+    fn pkg_config(
+        &mut self,
+        query: &PkgConfigQuery,
+    ) -> anyhow::Result<Option<Arc<PkgConfigPackage>>> {
+        Ok(pkg_config(query)?.map(Arc::new))
+    }
+
+    fn host_toolchain(
+        &mut self,
+        probe: &HostToolchainProbe,
+        _user: &str,
+    ) -> anyhow::Result<Option<HostToolchain>> {
+        probe.run()
+    }
+
+    fn forget_host_toolchain_uses(&mut self, _user: &str) {}
 }
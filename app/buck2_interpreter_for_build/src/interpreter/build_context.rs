@@ -14,13 +14,16 @@ use buck2_core::cells::build_file_cell::BuildFileCell;
 use buck2_core::cells::cell_path::CellPath;
 use buck2_core::cells::CellResolver;
 use buck2_core::package::PackageLabel;
+use buck2_core::soft_error_policy::SoftErrorSeverity;
 use buck2_interpreter::build_context::STARLARK_PATH_FROM_BUILD_CONTEXT;
 use buck2_interpreter::file_type::StarlarkFileType;
 use buck2_interpreter::paths::bxl::BxlFilePath;
 use buck2_interpreter::paths::path::StarlarkPath;
+use dupe::Dupe;
 use starlark::any::ProvidesStaticType;
 use starlark::environment::Module;
 use starlark::eval::Evaluator;
+use tracing::warn;
 
 use crate::interpreter::buckconfig::BuckConfigsViewForStarlark;
 use crate::interpreter::buckconfig::LegacyBuckConfigsForStarlark;
@@ -30,6 +33,9 @@ use crate::interpreter::functions::host_info::HostInfo;
 use crate::interpreter::module_internals::ModuleInternals;
 use crate::super_package::eval_ctx::PackageFileEvalCtx;
 
+/// Category of the `soft_error_policies` applied to build files querying the host.
+const HERMETICITY_SOFT_ERROR_CATEGORY: &str = "hermeticity";
+
 #[derive(buck2_error::Error, Debug)]
 enum BuildContextError {
     #[error(
@@ -52,6 +58,11 @@ enum BuildContextError {
         "Base path is only defined for build file or PACKAGE file; current file context is {0:?}"
     )]
     BasePathOnlyDefinedForPackageOrBuildFile(StarlarkFileType),
+    #[error(
+        "`{0}()` makes package `{1}` depend on the host, which the `hermeticity` soft error policy forbids"
+    )]
+    #[buck2(input)]
+    NotHermetic(String, PackageLabel),
 }
 
 #[derive(Debug)]
@@ -221,6 +232,27 @@ impl<'a> BuildContext<'a> {
     pub(crate) fn base_path(&self) -> anyhow::Result<CellPath> {
         self.additional.base_path()
     }
+
+    /// Apply the `hermeticity` soft error policy to a call of `function_name`, which makes the
+    /// result of the build file depend on what is installed on the host.
+    pub(crate) fn check_hermeticity(&self, function_name: &str) -> anyhow::Result<()> {
+        let PerFileTypeContext::Build(module) = &self.additional else {
+            return Ok(());
+        };
+        let Some(policies) = module.attr_coercion_context().soft_error_policies() else {
+            return Ok(());
+        };
+        let package = module.buildfile_path().package();
+        let err = BuildContextError::NotHermetic(function_name.to_owned(), package.dupe());
+        match policies.severity(HERMETICITY_SOFT_ERROR_CATEGORY, Some(&package.to_string())) {
+            Some(SoftErrorSeverity::Error) => Err(err.into()),
+            Some(SoftErrorSeverity::Warning) => {
+                warn!("{}", err);
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
 }
 
 pub(crate) fn init_starlark_path_from_build_context() {
@@ -37,6 +37,7 @@ use buck2_node::nodes::eval_result::EvaluationResult;
 use buck2_node::nodes::frontend::TargetGraphCalculation;
 use buck2_node::nodes::frontend::TargetGraphCalculationImpl;
 use buck2_node::nodes::frontend::TARGET_GRAPH_CALCULATION_IMPL;
+use buck2_node::package_values_calculation::PackageFileValues;
 use buck2_node::package_values_calculation::PackageValuesCalculation;
 use buck2_node::package_values_calculation::PACKAGE_VALUES_CALCULATION;
 use derive_more::Display;
@@ -52,6 +53,7 @@ use starlark_map::small_map::SmallMap;
 use crate::interpreter::dice_calculation_delegate::testing::EvalImportKey;
 use crate::interpreter::dice_calculation_delegate::HasCalculationDelegate;
 use crate::interpreter::global_interpreter_state::HasGlobalInterpreterState;
+use crate::super_package::package_value::SuperPackageValuesImpl;
 
 // Key for 'InterpreterCalculation::get_interpreter_results'
 #[derive(Clone, Dupe, Display, Debug, Eq, Hash, PartialEq, Allocative)]
@@ -261,6 +263,33 @@ impl PackageValuesCalculation for PackageValuesCalculationInstance {
             .await?;
         super_package.package_values().package_values_json()
     }
+
+    async fn package_values_provenance(
+        &self,
+        ctx: &mut DiceComputations<'_>,
+        package: PackageLabel,
+    ) -> anyhow::Result<Vec<PackageFileValues>> {
+        let chain = ctx
+            .get_interpreter_calculator(
+                package.cell_name(),
+                BuildFileCell::new(package.cell_name()),
+            )
+            .await?
+            .package_file_chain(package)
+            .await?;
+        chain
+            .into_iter()
+            .map(|(path, parent, this)| {
+                Ok(PackageFileValues {
+                    path: path.to_string(),
+                    values: SuperPackageValuesImpl::written_values_json(
+                        &**this.package_values(),
+                        &**parent.package_values(),
+                    )?,
+                })
+            })
+            .collect()
+    }
 }
 
 pub struct IntepreterResultsKeyActivationData {
@@ -7,16 +7,20 @@
  * of this source tree.
  */
 
+use std::sync::Arc;
+
 use allocative::Allocative;
 use buck2_core::cells::build_file_cell::BuildFileCell;
 use buck2_core::cells::CellAliasResolver;
 use buck2_core::cells::CellResolver;
+use buck2_interpreter::bazel_compat::BazelCompat;
 
 #[derive(Clone, Debug, Allocative)]
 pub struct InterpreterCellInfo {
     cell_name: BuildFileCell,
     cell_resolver: CellResolver,
     cell_alias_resolver: CellAliasResolver,
+    bazel_compat: Option<Arc<BazelCompat>>,
 }
 
 impl InterpreterCellInfo {
@@ -24,11 +28,13 @@ impl InterpreterCellInfo {
         cell_name: BuildFileCell,
         cell_resolver: CellResolver,
         cell_alias_resolver: CellAliasResolver,
+        bazel_compat: Option<Arc<BazelCompat>>,
     ) -> anyhow::Result<Self> {
         Ok(Self {
             cell_name,
             cell_resolver,
             cell_alias_resolver,
+            bazel_compat,
         })
     }
 
@@ -43,4 +49,9 @@ impl InterpreterCellInfo {
     pub fn cell_alias_resolver(&self) -> &CellAliasResolver {
         &self.cell_alias_resolver
     }
+
+    /// Bazel compatibility settings, if enabled for the cell.
+    pub fn bazel_compat(&self) -> Option<&BazelCompat> {
+        self.bazel_compat.as_deref()
+    }
 }
@@ -13,6 +13,7 @@ use std::sync::Arc;
 use allocative::Allocative;
 use buck2_common::package_listing::listing::PackageListing;
 use buck2_core::build_file_path::BuildFilePath;
+use buck2_core::soft_error_policy::SoftErrorPolicies;
 use buck2_core::target::label::interner::ConcurrentTargetLabelInterner;
 use buck2_interpreter::extra::xcode::XcodeVersionInfo;
 use buck2_interpreter::extra::InterpreterHostArchitecture;
@@ -21,6 +22,7 @@ use buck2_interpreter::file_loader::LoadedModules;
 use buck2_interpreter::package_imports::ImplicitImport;
 use buck2_interpreter::paths::module::StarlarkModulePath;
 use buck2_interpreter::prelude_path::PreludePath;
+use buck2_node::feature_flags::FeatureFlags;
 use buck2_node::super_package::SuperPackage;
 use dupe::Dupe;
 use starlark::environment::Globals;
@@ -116,6 +118,7 @@ impl BuildInterpreterConfiguror {
         package_listing: PackageListing,
         super_package: SuperPackage,
         package_boundary_exception: bool,
+        soft_error_policies: Arc<SoftErrorPolicies>,
         loaded_modules: &LoadedModules,
         implicit_import: Option<&Arc<ImplicitImport>>,
     ) -> anyhow::Result<ModuleInternals> {
@@ -143,9 +146,13 @@ impl BuildInterpreterConfiguror {
             (buildfile_path.package().dupe(), package_listing.dupe()),
             package_boundary_exception,
             self.global_target_interner.dupe(),
-        );
+        )
+        .with_bazel_labels(cell_info.bazel_compat().is_some_and(|c| c.labels))
+        .with_soft_error_policies(soft_error_policies);
 
         let imports = loaded_modules.imports().cloned().collect();
+        let feature_flags =
+            FeatureFlags::from_super_package_values(&**super_package.package_values())?;
 
         Ok(ModuleInternals::new(
             attr_coercer,
@@ -156,6 +163,7 @@ impl BuildInterpreterConfiguror {
             skip_targets_with_duplicate_names,
             package_listing,
             super_package,
+            feature_flags,
         ))
     }
 
@@ -20,6 +20,7 @@ use buck2_common::legacy_configs::dice::OpaqueLegacyBuckConfigOnDice;
 use buck2_common::package_boundary::HasPackageBoundaryExceptions;
 use buck2_common::package_listing::dice::DicePackageListingResolver;
 use buck2_common::package_listing::listing::PackageListing;
+use buck2_common::soft_error_policies::HasSoftErrorPolicies;
 use buck2_core::build_file_path::BuildFilePath;
 use buck2_core::cells::build_file_cell::BuildFileCell;
 use buck2_core::cells::name::CellName;
@@ -113,6 +114,7 @@ impl<'c, 'd> HasCalculationDelegate<'c, 'd> for DiceComputations<'d> {
                     self.1,
                     ctx.get_cell_resolver().await?,
                     cell_alias_resolver,
+                    implicit_import_paths.bazel_compat.clone(),
                 )?;
 
                 Ok(Arc::new(InterpreterForCell::new(
@@ -191,7 +193,15 @@ impl<'c, 'd: 'c> DiceCalculationDelegate<'c, 'd> {
         &'a mut self,
         starlark_file: StarlarkPath<'_>,
     ) -> anyhow::Result<(AstModule, ModuleDeps)> {
-        let ParseData(ast, imports) = self.parse_file(starlark_file).await??;
+        let parse = self.parse_file(starlark_file).await?;
+        self.prepare_eval_from_parse(parse).await
+    }
+
+    async fn prepare_eval_from_parse(
+        &mut self,
+        parse: ParseResult,
+    ) -> anyhow::Result<(AstModule, ModuleDeps)> {
+        let ParseData(ast, imports) = parse?;
         let deps = CycleGuard::<LoadCycleDescriptor>::new(self.ctx)?
             .guard_this(Self::eval_deps(self.ctx, &imports))
             .await
@@ -278,10 +288,10 @@ impl<'c, 'd: 'c> DiceCalculationDelegate<'c, 'd> {
     }
 
     /// Return `None` if there's no `PACKAGE` file in the directory.
-    pub async fn prepare_package_file_eval(
+    async fn find_package_file(
         &mut self,
         package: PackageLabel,
-    ) -> anyhow::Result<Option<(PackageFilePath, AstModule, ModuleDeps)>> {
+    ) -> anyhow::Result<Option<PackageFilePath>> {
         // This is cached if evaluating a `PACKAGE` file next to a `BUCK` file.
         let dir = DiceFileComputations::read_dir(self.ctx, package.as_cell_path()).await?;
         // Note:
@@ -301,14 +311,58 @@ impl<'c, 'd: 'c> DiceCalculationDelegate<'c, 'd> {
             ) {
                 continue;
             }
-            let (module, deps) = self
-                .prepare_eval(StarlarkPath::PackageFile(&package_file_path))
-                .await?;
-            return Ok(Some((package_file_path, module, deps)));
+            return Ok(Some(package_file_path));
         }
         Ok(None)
     }
 
+    /// Return `None` if there's no `PACKAGE` file in the directory.
+    pub async fn prepare_package_file_eval(
+        &mut self,
+        package: PackageLabel,
+    ) -> anyhow::Result<Option<(PackageFilePath, AstModule, ModuleDeps)>> {
+        let Some(package_file_path) = self.find_package_file(package).await? else {
+            return Ok(None);
+        };
+        let (module, deps) = self
+            .prepare_eval(StarlarkPath::PackageFile(&package_file_path))
+            .await?;
+        Ok(Some((package_file_path, module, deps)))
+    }
+
+    /// `PACKAGE` files applying to `package`, starting from the project root,
+    /// each with the state before (from the parent files) and after its evaluation.
+    pub(crate) async fn package_file_chain(
+        &mut self,
+        package: PackageLabel,
+    ) -> anyhow::Result<Vec<(PackageFilePath, SuperPackage, SuperPackage)>> {
+        let cell_resolver = self.ctx.get_cell_resolver().await?;
+        let mut dirs = Vec::new();
+        let mut dir = Some(package);
+        while let Some(package) = dir {
+            let proj_rel_path = cell_resolver.resolve_path(package.as_cell_path())?;
+            dir = match proj_rel_path.parent() {
+                Some(parent) => {
+                    let parent_cell = cell_resolver.get_cell_path(parent)?;
+                    Some(PackageLabel::from_cell_path(parent_cell.as_ref()))
+                }
+                None => None,
+            };
+            dirs.push(package);
+        }
+
+        let mut chain = Vec::new();
+        for dir in dirs.into_iter().rev() {
+            let Some(package_file_path) = self.find_package_file(dir.dupe()).await? else {
+                continue;
+            };
+            let parent = self.eval_parent_package_file(dir.dupe()).await?;
+            let this = self.eval_package_file(dir).await?;
+            chain.push((package_file_path, parent, this));
+        }
+        Ok(chain)
+    }
+
     async fn eval_package_file_uncached(
         &mut self,
         path: PackageLabel,
@@ -444,6 +498,27 @@ impl<'c, 'd: 'c> DiceCalculationDelegate<'c, 'd> {
     pub async fn eval_build_file(
         &mut self,
         package: PackageLabel,
+    ) -> buck2_error::Result<Arc<EvaluationResult>> {
+        self.eval_build_file_impl(package, None).await
+    }
+
+    /// Evaluate `content` as if it were the build file of `package`.
+    ///
+    /// The result is not cached, and the package's real build file is not read.
+    /// This is used to evaluate ad-hoc code in the environment of a package, so `content`
+    /// is parsed with the `.bzl` dialect, which allows `def` and top-level statements.
+    pub async fn eval_build_file_with_content(
+        &mut self,
+        package: PackageLabel,
+        content: String,
+    ) -> buck2_error::Result<Arc<EvaluationResult>> {
+        self.eval_build_file_impl(package, Some(content)).await
+    }
+
+    async fn eval_build_file_impl(
+        &mut self,
+        package: PackageLabel,
+        content: Option<String>,
     ) -> buck2_error::Result<Arc<EvaluationResult>> {
         let ((), listing, profile_mode) = self
             .ctx
@@ -464,9 +539,18 @@ impl<'c, 'd: 'c> DiceCalculationDelegate<'c, 'd> {
         };
 
         let build_file_path = BuildFilePath::new(package.dupe(), listing.buildfile().to_owned());
-        let (ast, deps) = self
-            .prepare_eval(StarlarkPath::BuildFile(&build_file_path))
-            .await?;
+        let (ast, deps) = match content {
+            None => {
+                self.prepare_eval(StarlarkPath::BuildFile(&build_file_path))
+                    .await?
+            }
+            Some(content) => {
+                let parse = self
+                    .configs
+                    .parse_repl(StarlarkPath::BuildFile(&build_file_path), content)?;
+                self.prepare_eval_from_parse(parse).await?
+            }
+        };
         let super_package = self
             .eval_package_file_for_build_file(package.dupe(), &listing)
             .await?;
@@ -476,6 +560,7 @@ impl<'c, 'd: 'c> DiceCalculationDelegate<'c, 'd> {
             .get_package_boundary_exception(package.as_cell_path())
             .await?
             .is_some();
+        let soft_error_policies = self.ctx.get_soft_error_policies().await?;
         let buckconfig = self.get_legacy_buck_config_for_starlark().await?;
         let root_buckconfig = self.ctx.get_legacy_root_config_on_dice().await?;
         let module_id = build_file_path.to_string();
@@ -504,6 +589,7 @@ impl<'c, 'd: 'c> DiceCalculationDelegate<'c, 'd> {
                             listing,
                             super_package,
                             package_boundary_exception,
+                            soft_error_policies,
                             ast,
                             deps.get_loaded_modules(),
                             provider,
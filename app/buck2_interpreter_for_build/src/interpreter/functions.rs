@@ -9,9 +9,11 @@
 
 pub(crate) mod dedupe;
 pub(crate) mod host_info;
+pub(crate) mod host_toolchain;
 pub(crate) mod internals;
 pub(crate) mod load_symbols;
 pub(crate) mod path;
+pub(crate) mod pkg_config;
 pub(crate) mod read_config;
 pub(crate) mod regex;
 pub(crate) mod sha256;
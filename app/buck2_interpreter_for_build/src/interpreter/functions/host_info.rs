@@ -8,6 +8,8 @@
  */
 
 use allocative::Allocative;
+use buck2_interpreter::extra::host_facts::GlibcVersionInfo;
+use buck2_interpreter::extra::host_facts::HostFacts;
 use buck2_interpreter::extra::xcode::XcodeVersionInfo;
 use buck2_interpreter::extra::InterpreterHostArchitecture;
 use buck2_interpreter::extra::InterpreterHostPlatform;
@@ -29,6 +31,7 @@ fn new_host_info(
     host_platform: InterpreterHostPlatform,
     host_architecture: InterpreterHostArchitecture,
     xcode_info: Option<&XcodeVersionInfo>,
+    facts: &HostFacts,
 ) -> OwnedFrozenValue {
     let heap = FrozenHeap::new();
 
@@ -88,6 +91,30 @@ fn new_host_info(
         )
     };
 
+    let glibc = {
+        let mk_value = |sel: fn(&GlibcVersionInfo) -> &String| match &facts.glibc {
+            Some(i) => heap.alloc(sel(i).as_str()),
+            None => FrozenValue::new_none(),
+        };
+
+        new_struct(
+            &heap,
+            &[
+                ("version_string", mk_value(|x| &x.version_string)),
+                ("major_version", mk_value(|x| &x.major_version)),
+                ("minor_version", mk_value(|x| &x.minor_version)),
+            ],
+        )
+    };
+
+    let resources = new_struct(
+        &heap,
+        &[
+            ("cpu_count", heap.alloc(facts.cpu_count)),
+            ("memory_bytes", heap.alloc(facts.memory_bytes)),
+        ],
+    );
+
     let info = new_struct(
         &heap,
         &[
@@ -98,6 +125,8 @@ fn new_host_info(
             // is quick, cheap and Buck v1 compatible.
             ("buck2", FrozenValue::new_bool(true)),
             ("xcode", xcode),
+            ("glibc", glibc),
+            ("resources", resources),
         ],
     );
 
@@ -132,8 +161,28 @@ pub(crate) fn register_host_info(builder: &mut GlobalsBuilder) {
     ///         is_x86_64=True|False,
     ///         is_unknown=True|False,
     ///     ),
+    ///     xcode=struct(
+    ///         version_string="14.1.2"|None,
+    ///         major_version="14"|None,
+    ///         minor_version="1"|None,
+    ///         patch_version="2"|None,
+    ///         build_number="14B47b"|None,
+    ///     ),
+    ///     glibc=struct(
+    ///         version_string="2.35"|None,
+    ///         major_version="2"|None,
+    ///         minor_version="35"|None,
+    ///     ),
+    ///     resources=struct(
+    ///         cpu_count=int,
+    ///         memory_bytes=int,
+    ///     ),
     /// )
     /// ```
+    ///
+    /// `xcode` fields are `None` unless the host is macOS with Xcode selected, and `glibc` fields
+    /// are `None` unless the host is Linux with glibc. `memory_bytes` is 0 if it could not be
+    /// determined.
     #[starlark(speculative_exec_safe)]
     fn host_info<'v>(
         eval: &mut Evaluator<'v, '_, '_>,
@@ -151,10 +200,11 @@ pub(crate) fn register_host_info(builder: &mut GlobalsBuilder) {
 #[derive(Derivative, Clone, Debug, Allocative)]
 #[derivative(PartialEq)]
 pub(crate) struct HostInfo {
-    // These first three fields are for equality only, otherwise not used
+    // These first four fields are for equality only, otherwise not used
     platform: InterpreterHostPlatform,
     arch: InterpreterHostArchitecture,
     xcode: Option<XcodeVersionInfo>,
+    facts: HostFacts,
     // The actual value which we ignore for equality, which is OK because of above
     #[derivative(PartialEq = "ignore")]
     value: OwnedFrozenValue,
@@ -166,11 +216,13 @@ impl HostInfo {
         arch: InterpreterHostArchitecture,
         xcode: Option<XcodeVersionInfo>,
     ) -> Self {
-        let value = new_host_info(platform, arch, xcode.as_ref());
+        let facts = HostFacts::get().clone();
+        let value = new_host_info(platform, arch, xcode.as_ref(), &facts);
         Self {
             platform,
             arch,
             xcode,
+            facts,
             value,
         }
     }
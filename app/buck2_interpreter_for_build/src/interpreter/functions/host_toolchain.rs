@@ -0,0 +1,70 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use buck2_interpreter::extra::toolchain_probe::HostToolchainProbe;
+use starlark::environment::GlobalsBuilder;
+use starlark::eval::Evaluator;
+use starlark::starlark_module;
+use starlark::values::list_or_tuple::UnpackListOrTuple;
+use starlark::values::none::NoneOr;
+use starlark::values::structs::AllocStruct;
+use starlark::values::structs::StructRef;
+use starlark::values::ValueOfUnchecked;
+
+use crate::interpreter::build_context::BuildContext;
+
+#[starlark_module]
+pub(crate) fn register_host_toolchain(builder: &mut GlobalsBuilder) {
+    /// Look for a toolchain installed on the host, returning `None` if it is not found.
+    ///
+    /// Built-in probes exist for `clang`, `gcc`, `rustc` and `python`. Other toolchains can be
+    /// probed by specifying the `binaries` to look for on `PATH`, the `version_args` which make
+    /// the binary print its version (default `["--version"]`) and a `version_regex` whose first
+    /// capture group matches the version. The same parameters override the built-in probes.
+    ///
+    /// ```python
+    /// host_toolchain("clang")
+    /// # struct(name="clang", path="/usr/bin/clang", version="17.0.6")
+    /// host_toolchain("zig", binaries = ["zig"], version_args = ["version"])
+    /// ```
+    ///
+    /// The result of each probe is cached by the daemon until the binary it found, or a directory
+    /// of `PATH`, changes, in which case build files calling it are evaluated again. `version` is
+    /// `None` if the output of the binary did not match `version_regex`. Probed toolchains, and
+    /// the files which requested them, are listed by `buck2 toolchain list`. The `hermeticity`
+    /// category of `soft_error_policies` can make calls from build files a warning or an error.
+    fn host_toolchain<'v>(
+        #[starlark(require = pos)] name: &str,
+        #[starlark(require = named)] binaries: Option<UnpackListOrTuple<String>>,
+        #[starlark(require = named)] version_args: Option<UnpackListOrTuple<String>>,
+        #[starlark(require = named)] version_regex: Option<String>,
+        eval: &mut Evaluator<'v, '_, '_>,
+    ) -> anyhow::Result<NoneOr<ValueOfUnchecked<'v, StructRef<'v>>>> {
+        let probe = HostToolchainProbe::new(
+            name,
+            binaries.map(|b| b.items),
+            version_args.map(|a| a.items),
+            version_regex,
+        )?;
+        let build_context = BuildContext::from_context(eval)?;
+        build_context.check_hermeticity("host_toolchain")?;
+        let user = build_context.additional.starlark_path().to_string();
+        let toolchain = match build_context.buckconfigs.host_toolchain(&probe, &user)? {
+            Some(toolchain) => toolchain,
+            None => return Ok(NoneOr::None),
+        };
+        let heap = eval.heap();
+        let value = heap.alloc(AllocStruct([
+            ("name", heap.alloc(name)),
+            ("path", heap.alloc(toolchain.path)),
+            ("version", heap.alloc(toolchain.version)),
+        ]));
+        Ok(NoneOr::Other(ValueOfUnchecked::new(value)))
+    }
+}
@@ -7,6 +7,7 @@
  * of this source tree.
  */
 
+use buck2_interpreter::bazel_compat::GlobSemantics;
 use starlark::environment::GlobalsBuilder;
 use starlark::eval::Evaluator;
 use starlark::starlark_module;
@@ -19,6 +20,19 @@ use crate::interpreter::build_context::BuildContext;
 use crate::interpreter::globspec::GlobSpec;
 use crate::interpreter::module_internals::ModuleInternals;
 
+#[derive(Debug, buck2_error::Error)]
+#[buck2(input)]
+enum GlobError {
+    #[error(
+        "`glob()` only accepts `{0}` in cells with `bazel_compat.glob = bazel` set in their buckconfig"
+    )]
+    BazelArgument(&'static str),
+    #[error("`glob()` only matches files, `exclude_directories = 0` is not supported")]
+    IncludeDirectories,
+    #[error("`glob({0:?})` matched no files, pass `allow_empty = True` if that is expected")]
+    Empty(Vec<String>),
+}
+
 #[starlark_module]
 pub(crate) fn register_path(builder: &mut GlobalsBuilder) {
     /// The `glob()` function specifies a set of files using patterns.
@@ -49,15 +63,51 @@ pub(crate) fn register_path(builder: &mut GlobalsBuilder) {
     ///
     /// Currently `glob` is evaluated case-insensitively on all file systems, but we expect
     /// that to change to case sensitive in the near future.
+    ///
+    /// In cells with `bazel_compat.glob = bazel`, the Bazel `allow_empty` and
+    /// `exclude_directories` parameters are also accepted, and a `glob` matching no files
+    /// is an error unless `allow_empty` is set.
     fn glob<'v>(
         include: UnpackListOrTuple<String>,
         #[starlark(require = named, default=UnpackListOrTuple::default())]
         exclude: UnpackListOrTuple<String>,
+        #[starlark(require = named)] allow_empty: Option<bool>,
+        #[starlark(require = named)] exclude_directories: Option<i32>,
         eval: &mut Evaluator<'v, '_, '_>,
     ) -> anyhow::Result<ValueOfUnchecked<'v, UnpackList<String>>> {
+        let semantics = BuildContext::from_context(eval)?
+            .cell_info()
+            .bazel_compat()
+            .map_or(GlobSemantics::Buck, |c| c.glob);
+        let allow_empty = match semantics {
+            GlobSemantics::Buck => {
+                if allow_empty.is_some() {
+                    return Err(GlobError::BazelArgument("allow_empty").into());
+                }
+                if exclude_directories.is_some() {
+                    return Err(GlobError::BazelArgument("exclude_directories").into());
+                }
+                true
+            }
+            GlobSemantics::Bazel {
+                allow_empty: default_allow_empty,
+            } => {
+                if exclude_directories == Some(0) {
+                    return Err(GlobError::IncludeDirectories.into());
+                }
+                allow_empty.unwrap_or(default_allow_empty)
+            }
+        };
+
         let extra = ModuleInternals::from_context(eval, "glob")?;
         let spec = GlobSpec::new(&include.items, &exclude.items)?;
-        let res = extra.resolve_glob(&spec).map(|path| path.as_str());
+        let res: Vec<&str> = extra
+            .resolve_glob(&spec)
+            .map(|path| path.as_str())
+            .collect();
+        if res.is_empty() && !allow_empty {
+            return Err(GlobError::Empty(include.items).into());
+        }
         Ok(eval.heap().alloc_typed_unchecked(AllocList(res)).cast())
     }
 
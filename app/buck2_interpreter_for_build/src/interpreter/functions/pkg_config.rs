@@ -0,0 +1,59 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use buck2_interpreter::extra::pkg_config::PkgConfigQuery;
+use starlark::environment::GlobalsBuilder;
+use starlark::eval::Evaluator;
+use starlark::starlark_module;
+use starlark::values::none::NoneOr;
+use starlark::values::structs::AllocStruct;
+use starlark::values::structs::StructRef;
+use starlark::values::ValueOfUnchecked;
+
+use crate::interpreter::build_context::BuildContext;
+
+#[starlark_module]
+pub(crate) fn register_pkg_config(builder: &mut GlobalsBuilder) {
+    /// Look up a library installed on the host with `pkg-config`, returning `None` if it is not
+    /// installed.
+    ///
+    /// ```python
+    /// pkg_config("zlib")
+    /// # struct(name="zlib", version="1.2.13", cflags=[], libs=["-lz"])
+    /// pkg_config("zlib", static = True)
+    /// ```
+    ///
+    /// `static` asks for the flags to link the library statically, including its private
+    /// dependencies. `pkg-config` is found on `PATH`, or set with the `PKG_CONFIG` environment
+    /// variable of the daemon. Results are cached by the daemon until the `.pc` file of the
+    /// package, or a directory of the `pkg-config` search path, changes: the files which made a
+    /// query whose result changed are then evaluated again. The `hermeticity` category of `soft_error_policies`
+    /// can make calls from build files a warning or an error.
+    fn pkg_config<'v>(
+        #[starlark(require = pos)] package: &str,
+        #[starlark(require = named, default = false)] r#static: bool,
+        eval: &mut Evaluator<'v, '_, '_>,
+    ) -> anyhow::Result<NoneOr<ValueOfUnchecked<'v, StructRef<'v>>>> {
+        let build_context = BuildContext::from_context(eval)?;
+        build_context.check_hermeticity("pkg_config")?;
+        let query = PkgConfigQuery::new(package, r#static)?;
+        let found = match build_context.buckconfigs.pkg_config(&query)? {
+            Some(found) => found,
+            None => return Ok(NoneOr::None),
+        };
+        let heap = eval.heap();
+        let value = heap.alloc(AllocStruct([
+            ("name", heap.alloc(package)),
+            ("version", heap.alloc(found.version.as_str())),
+            ("cflags", heap.alloc(found.cflags.as_slice())),
+            ("libs", heap.alloc(found.libs.as_slice())),
+        ]));
+        Ok(NoneOr::Other(ValueOfUnchecked::new(value)))
+    }
+}
@@ -27,9 +27,11 @@ use starlark::environment::LibraryExtension;
 use crate::attrs::attrs_global::register_attrs;
 use crate::interpreter::functions::dedupe::register_dedupe;
 use crate::interpreter::functions::host_info::register_host_info;
+use crate::interpreter::functions::host_toolchain::register_host_toolchain;
 use crate::interpreter::functions::internals::register_internals;
 use crate::interpreter::functions::load_symbols::register_load_symbols;
 use crate::interpreter::functions::path::register_path;
+use crate::interpreter::functions::pkg_config::register_pkg_config;
 use crate::interpreter::functions::read_config::register_read_config;
 use crate::interpreter::functions::regex::register_regex;
 use crate::interpreter::functions::sha256::register_sha256;
@@ -57,6 +59,8 @@ fn register_buck2_natives(builder: &mut GlobalsBuilder) {
     from_late_binding(&REGISTER_BUCK2_CFG_CONSTRUCTOR_GLOBALS, builder);
     register_module_natives(builder);
     register_host_info(builder);
+    register_host_toolchain(builder);
+    register_pkg_config(builder);
     register_read_config(builder);
     register_read_package_value(builder);
     register_soft_error(builder);
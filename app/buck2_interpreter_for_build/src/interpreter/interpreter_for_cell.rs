@@ -13,6 +13,7 @@
 
 use std::cell::OnceCell;
 use std::cell::RefCell;
+use std::ffi::OsString;
 use std::sync::Arc;
 
 use allocative::Allocative;
@@ -24,7 +25,10 @@ use buck2_core::build_file_path::BuildFilePath;
 use buck2_core::bzl::ImportPath;
 use buck2_core::cells::build_file_cell::BuildFileCell;
 use buck2_core::cells::cell_path::CellPath;
+use buck2_core::env::usage::record_process_env_usage;
+use buck2_core::env::usage::EnvUsageSource;
 use buck2_core::soft_error;
+use buck2_core::soft_error_policy::SoftErrorPolicies;
 use buck2_error::BuckErrorContext;
 use buck2_event_observer::humanized::HumanizedBytes;
 use buck2_events::dispatch::get_dispatcher;
@@ -56,6 +60,7 @@ use starlark::codemap::FileSpan;
 use starlark::environment::FrozenModule;
 use starlark::environment::Module;
 use starlark::syntax::AstModule;
+use starlark::syntax::Dialect;
 use starlark::values::OwnedFrozenRef;
 
 use crate::interpreter::buckconfig::BuckConfigsViewForStarlark;
@@ -73,6 +78,13 @@ use crate::super_package::eval_ctx::PackageFileEvalCtx;
 #[error("Tabs are not allowed in Buck files: `{0}`")]
 struct StarlarkTabsError(OwnedStarlarkPath);
 
+#[derive(Debug, buck2_error::Error)]
+#[error(
+    "Bazel rule alias `{0}` refers to `{1}`, which is not defined by the prelude or the Bazel compat prelude"
+)]
+#[buck2(input)]
+struct BazelRuleAliasError(String, String);
+
 #[derive(Debug, buck2_error::Error)]
 enum StarlarkPeakMemoryError {
     #[error(
@@ -264,8 +276,20 @@ struct EvalResult {
 }
 
 impl InterpreterForCell {
+    /// Read a variable from the daemon environment, recording it for `buck2 audit env`.
+    fn env_var(name: &str) -> Option<OsString> {
+        let value = std::env::var_os(name);
+        record_process_env_usage(
+            EnvUsageSource::Interpreter,
+            name,
+            value.as_ref().and_then(|v| v.to_str()),
+            None,
+        );
+        value
+    }
+
     fn verbose_gc() -> anyhow::Result<bool> {
-        match std::env::var_os("BUCK2_STARLARK_VERBOSE_GC") {
+        match Self::env_var("BUCK2_STARLARK_VERBOSE_GC") {
             Some(val) => Ok(!val.is_empty()),
             None => Ok(false),
         }
@@ -274,8 +298,7 @@ impl InterpreterForCell {
     fn is_ignore_attrs_for_profiling() -> anyhow::Result<bool> {
         // If unsure, feel free to break this code or just delete it.
         // It is intended only for profiling of very specific use cases.
-        let ignore_attrs_for_profiling = match std::env::var_os("BUCK2_IGNORE_ATTRS_FOR_PROFILING")
-        {
+        let ignore_attrs_for_profiling = match Self::env_var("BUCK2_IGNORE_ATTRS_FOR_PROFILING") {
             Some(val) => !val.is_empty(),
             None => false,
         };
@@ -345,6 +368,7 @@ impl InterpreterForCell {
         package_listing: &PackageListing,
         super_package: SuperPackage,
         package_boundary_exception: bool,
+        soft_error_policies: Arc<SoftErrorPolicies>,
         loaded_modules: &LoadedModules,
     ) -> anyhow::Result<(Module, ModuleInternals)> {
         let internals = self.global_state.configuror.new_extra_context(
@@ -353,6 +377,7 @@ impl InterpreterForCell {
             package_listing.dupe(),
             super_package,
             package_boundary_exception,
+            soft_error_policies,
             loaded_modules,
             self.package_import(build_file),
         )?;
@@ -369,6 +394,28 @@ impl InterpreterForCell {
             env.import_public_symbols(root_env);
         }
 
+        if let Some(bazel_compat) = self.cell_info.bazel_compat() {
+            if let Some(prelude) = &bazel_compat.prelude {
+                let prelude_env = loaded_modules
+                    .map
+                    .get(&StarlarkModulePath::LoadFile(prelude))
+                    .with_internal_error(|| {
+                        format!(
+                            "Should've had an env for the Bazel compat prelude `{}`",
+                            prelude
+                        )
+                    })?
+                    .env();
+                env.import_public_symbols(prelude_env);
+            }
+            for (bazel_name, buck_name) in &bazel_compat.rule_aliases {
+                let value = env.get(buck_name).ok_or_else(|| {
+                    BazelRuleAliasError(bazel_name.to_string(), buck_name.to_string())
+                })?;
+                env.set(bazel_name, value);
+            }
+        }
+
         Ok((env, internals))
     }
 
@@ -398,6 +445,10 @@ impl InterpreterForCell {
         self.implicit_import_paths.root_import.clone()
     }
 
+    fn bazel_compat_prelude(&self) -> Option<&ImportPath> {
+        self.cell_info.bazel_compat()?.prelude.as_ref()
+    }
+
     fn prelude_import(&self, import: StarlarkPath) -> Option<&PreludePath> {
         let prelude_import = self.global_state.configuror.prelude_import();
         if let Some(prelude_import) = prelude_import {
@@ -423,6 +474,29 @@ impl InterpreterForCell {
         self: &Arc<Self>,
         import: StarlarkPath,
         content: String,
+    ) -> anyhow::Result<ParseResult> {
+        let dialect = import
+            .file_type()
+            .dialect(self.global_state.disable_starlark_types);
+        self.parse_with_dialect(import, content, &dialect)
+    }
+
+    /// Parse `content` as the build file `import`, but with the dialect of `.bzl` files,
+    /// so that code typed into the REPL can define functions.
+    pub(crate) fn parse_repl(
+        self: &Arc<Self>,
+        import: StarlarkPath,
+        content: String,
+    ) -> anyhow::Result<ParseResult> {
+        let dialect = StarlarkFileType::Bzl.dialect(self.global_state.disable_starlark_types);
+        self.parse_with_dialect(import, content, &dialect)
+    }
+
+    fn parse_with_dialect(
+        self: &Arc<Self>,
+        import: StarlarkPath,
+        content: String,
+        dialect: &Dialect,
     ) -> anyhow::Result<ParseResult> {
         // Indentation with tabs is prohibited by starlark spec and configured starlark dialect.
         // This check also prohibits tabs even where spaces are not significant,
@@ -437,12 +511,7 @@ impl InterpreterForCell {
             .cell_resolver
             .resolve_path(import.path().as_ref().as_ref())?;
 
-        let disable_starlark_types = self.global_state.disable_starlark_types;
-        let ast = match AstModule::parse(
-            project_relative_path.as_str(),
-            content,
-            &import.file_type().dialect(disable_starlark_types),
-        ) {
+        let ast = match AstModule::parse(project_relative_path.as_str(), content, dialect) {
             Ok(ast) => ast,
             Err(e) => {
                 return Ok(Err(ParseError(
@@ -466,6 +535,9 @@ impl InterpreterForCell {
                 if let Some(i) = self.root_import() {
                     implicit_imports.push(OwnedStarlarkModulePath::LoadFile(i));
                 }
+                if let Some(i) = self.bazel_compat_prelude() {
+                    implicit_imports.push(OwnedStarlarkModulePath::LoadFile(i.clone()));
+                }
             }
         }
         ParseData::new(ast, implicit_imports, &self.load_resolver(import)).map(Ok)
@@ -490,6 +562,7 @@ impl InterpreterForCell {
         unstable_typecheck: bool,
     ) -> anyhow::Result<EvalResult> {
         let import = extra_context.starlark_path();
+        buckconfigs.forget_host_toolchain_uses(&import.to_string());
         let globals = self.global_state.globals();
         let file_loader =
             InterpreterFileLoader::new(loaded_modules, Arc::new(self.load_resolver(import)));
@@ -644,6 +717,7 @@ impl InterpreterForCell {
         listing: PackageListing,
         super_package: SuperPackage,
         package_boundary_exception: bool,
+        soft_error_policies: Arc<SoftErrorPolicies>,
         ast: AstModule,
         loaded_modules: LoadedModules,
         eval_provider: &mut dyn StarlarkEvaluatorProvider,
@@ -654,6 +728,7 @@ impl InterpreterForCell {
             &listing,
             super_package,
             package_boundary_exception,
+            soft_error_policies,
             &loaded_modules,
         )?;
         let eval_result = self.eval(
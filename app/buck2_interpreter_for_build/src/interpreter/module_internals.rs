@@ -21,6 +21,7 @@ use buck2_core::package::package_relative_path::PackageRelativePath;
 use buck2_core::target::name::TargetNameRef;
 use buck2_events::dispatch::console_message;
 use buck2_interpreter::package_imports::ImplicitImport;
+use buck2_node::feature_flags::FeatureFlags;
 use buck2_node::nodes::eval_result::EvaluationResult;
 use buck2_node::nodes::targets_map::TargetsMap;
 use buck2_node::nodes::targets_map::TargetsMapRecordError;
@@ -91,6 +92,8 @@ pub struct ModuleInternals {
     /// The files owned by this directory. Is `None` for .bzl files.
     package_listing: PackageListing,
     pub(crate) super_package: SuperPackage,
+    /// Feature flags from `super_package`, shared by all the targets of the package.
+    feature_flags: FeatureFlags,
 }
 
 #[derive(Debug)]
@@ -132,6 +135,7 @@ impl ModuleInternals {
         skip_targets_with_duplicate_names: bool,
         package_listing: PackageListing,
         super_package: SuperPackage,
+        feature_flags: FeatureFlags,
     ) -> Self {
         Self {
             attr_coercion_context,
@@ -143,6 +147,7 @@ impl ModuleInternals {
             skip_targets_with_duplicate_names,
             package_listing,
             super_package,
+            feature_flags,
         }
     }
 
@@ -202,6 +207,7 @@ impl ModuleInternals {
                             package: Arc::new(Package {
                                 buildfile_path: self.buildfile_path.dupe(),
                                 oncall,
+                                feature_flags: self.feature_flags.clone(),
                             }),
                             recorder: TargetsRecorder::new(),
                         });
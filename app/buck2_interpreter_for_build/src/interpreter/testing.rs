@@ -21,6 +21,7 @@ use buck2_core::cells::cell_root_path::CellRootPathBuf;
 use buck2_core::cells::name::CellName;
 use buck2_core::cells::*;
 use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+use buck2_core::soft_error_policy::SoftErrorPolicies;
 use buck2_core::target::label::interner::ConcurrentTargetLabelInterner;
 use buck2_interpreter::extra::InterpreterHostArchitecture;
 use buck2_interpreter::extra::InterpreterHostPlatform;
@@ -184,6 +185,7 @@ impl Tester {
             build_file_cell,
             self.cell_resolver.dupe(),
             self.cell_alias_resolver.dupe(),
+            import_paths.bazel_compat.clone(),
         )?;
         Ok(Arc::new(InterpreterForCell::new(
             cell_info,
@@ -305,6 +307,7 @@ impl Tester {
             package_listing,
             SuperPackage::empty::<SuperPackageValuesImpl>(),
             false,
+            Arc::new(SoftErrorPolicies::default()),
             ast,
             loaded_modules,
             &mut provider,
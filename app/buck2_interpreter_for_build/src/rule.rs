@@ -22,6 +22,7 @@ use buck2_interpreter::types::rule::FROZEN_RULE_GET_IMPL;
 use buck2_interpreter::types::transition::transition_id_from_value;
 use buck2_node::attrs::attr::Attribute;
 use buck2_node::attrs::spec::AttributeSpec;
+use buck2_node::deprecation::Deprecation;
 use buck2_node::nodes::unconfigured::RuleKind;
 use buck2_node::nodes::unconfigured::TargetNode;
 use buck2_node::rule::Rule;
@@ -96,6 +97,8 @@ pub struct RuleCallable<'v> {
     uses_plugins: Vec<PluginKind>,
     /// This kind of the rule, e.g. whether it can be used in configuration context.
     rule_kind: RuleKind,
+    /// Set if the rule is deprecated.
+    deprecation: Option<Deprecation>,
     /// The raw docstring for this rule
     docs: Option<String>,
     /// When evaluating rule function, take only the `name` argument, ignore the others.
@@ -141,6 +144,8 @@ enum RuleError {
     IsConfigurationAndToolchain,
     #[error("`rule` can only be declared in bzl files")]
     RuleNonInBzl,
+    #[error("`removal_milestone` can only be specified for deprecated rules, with `deprecated`")]
+    RemovalMilestoneWithoutDeprecated,
 }
 
 impl<'v> AllocValue<'v> for RuleCallable<'v> {
@@ -158,6 +163,7 @@ impl<'v> RuleCallable<'v> {
         is_configuration_rule: bool,
         is_toolchain_rule: bool,
         uses_plugins: Vec<Value<'v>>,
+        deprecation: Option<Deprecation>,
         artifact_promise_mappings: Option<ArtifactPromiseMappings<'v>>,
         eval: &mut Evaluator<'v, '_, '_>,
     ) -> anyhow::Result<RuleCallable<'v>> {
@@ -209,6 +215,7 @@ impl<'v> RuleCallable<'v> {
             cfg,
             rule_kind,
             uses_plugins,
+            deprecation,
             docs: Some(doc.to_owned()),
             ignore_attrs_for_profiling: build_context.ignore_attrs_for_profiling,
             artifact_promise_mappings,
@@ -308,6 +315,7 @@ impl<'v> Freeze for RuleCallable<'v> {
                 cfg: self.cfg,
                 rule_kind: self.rule_kind,
                 uses_plugins: self.uses_plugins,
+                deprecation: self.deprecation,
             }),
             rule_type,
             implementation: frozen_impl,
@@ -443,6 +451,10 @@ pub fn register_rule_function(builder: &mut GlobalsBuilder) {
     ///     "exe": attrs.option(attrs.bool(), default = False),
     /// })
     /// ```
+    ///
+    /// A rule can be marked as deprecated with `deprecated = "Use new_rule instead"`, and
+    /// optionally `removal_milestone`. Commands loading targets of a deprecated rule print a
+    /// warning listing them, builds fail with `--strict-deprecations`.
     fn rule<'v>(
         #[starlark(require = named)] r#impl: StarlarkCallable<
             'v,
@@ -456,8 +468,18 @@ pub fn register_rule_function(builder: &mut GlobalsBuilder) {
         #[starlark(require = named, default = false)] is_toolchain_rule: bool,
         #[starlark(require = named, default = UnpackListOrTuple::default())]
         uses_plugins: UnpackListOrTuple<Value<'v>>,
+        #[starlark(require = named)] deprecated: Option<&str>,
+        #[starlark(require = named)] removal_milestone: Option<&str>,
         eval: &mut Evaluator<'v, '_, '_>,
     ) -> anyhow::Result<RuleCallable<'v>> {
+        let deprecation = match (deprecated, removal_milestone) {
+            (Some(message), removal) => Some(Deprecation {
+                message: message.to_owned(),
+                removal: removal.map(|x| x.to_owned()),
+            }),
+            (None, Some(_)) => return Err(RuleError::RemovalMilestoneWithoutDeprecated.into()),
+            (None, None) => None,
+        };
         RuleCallable::new(
             r#impl,
             attrs,
@@ -466,6 +488,7 @@ pub fn register_rule_function(builder: &mut GlobalsBuilder) {
             is_configuration_rule,
             is_toolchain_rule,
             uses_plugins.items,
+            deprecation,
             None,
             eval,
         )
@@ -496,6 +519,7 @@ pub fn register_rule_function(builder: &mut GlobalsBuilder) {
             false,
             false,
             Vec::new(),
+            None,
             Some(ArtifactPromiseMappings {
                 mappings: artifact_promise_mappings
                     .iter()
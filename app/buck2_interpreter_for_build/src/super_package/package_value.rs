@@ -15,9 +15,12 @@ use allocative::Allocative;
 use anyhow::Context;
 use buck2_error::BuckErrorContext;
 use buck2_interpreter::file_type::StarlarkFileType;
+use buck2_node::feature_flags::feature_flag_key;
+use buck2_node::feature_flags::FeatureFlagValue;
 use buck2_node::metadata::key::MetadataKey;
 use buck2_node::metadata::key::MetadataKeyRef;
 use buck2_node::metadata::super_package_values::SuperPackageValues;
+use buck2_node::package_values_calculation::WrittenPackageValue;
 use dupe::Dupe;
 use starlark::environment::GlobalsBuilder;
 use starlark::eval::Evaluator;
@@ -71,6 +74,38 @@ impl SuperPackageValuesImpl {
             }))
         }
     }
+
+    /// Values written by the `PACKAGE` file which produced `this` from `parent`.
+    pub(crate) fn written_values_json(
+        this: &dyn SuperPackageValues,
+        parent: &dyn SuperPackageValues,
+    ) -> anyhow::Result<SmallMap<MetadataKey, WrittenPackageValue>> {
+        let this = Self::get(this)?;
+        let parent = Self::get(parent)?;
+        let mut written = SmallMap::new();
+        for (key, value) in &this.values {
+            // Inherited values are shared with the parent, so comparing pointers is exact
+            // even when a `PACKAGE` file overwrites a value with an equal one.
+            let parent_value = parent.values.get(key);
+            let inherited = parent_value.is_some_and(|parent_value| {
+                parent_value
+                    .owned_frozen_value()
+                    .value()
+                    .to_value()
+                    .ptr_eq(value.owned_frozen_value().value().to_value())
+            });
+            if !inherited {
+                written.insert(
+                    key.clone(),
+                    WrittenPackageValue {
+                        value: value.to_json_value()?,
+                        overwrite: parent_value.is_some(),
+                    },
+                );
+            }
+        }
+        Ok(written)
+    }
 }
 
 impl SuperPackageValues for SuperPackageValuesImpl {
@@ -160,6 +195,39 @@ impl OwnedFrozenStarlarkPackageValue {
     }
 }
 
+fn write_package_value_impl<'v>(
+    function_name: &str,
+    key: &MetadataKeyRef,
+    value: Value<'v>,
+    overwrite: bool,
+    eval: &mut Evaluator<'v, '_, '_>,
+) -> anyhow::Result<()> {
+    let package_ctx = BuildContext::from_context(eval)?
+        .additional
+        .require_package_file(function_name)?;
+
+    let package_file_extra = PackageFileExtra::get_or_init(eval)?;
+
+    if package_file_extra.package_values.borrow().contains_key(key) {
+        return Err(PackageValueError::KeyAlreadySetInThisFile(key.to_owned()).into());
+    }
+
+    if !overwrite {
+        if package_ctx.parent.package_values().contains_key(key) {
+            return Err(PackageValueError::KeySetInParentFile(key.to_owned()).into());
+        }
+    }
+
+    let value = StarlarkPackageValue::new(value)?;
+
+    package_file_extra
+        .package_values
+        .borrow_mut()
+        .insert(key.to_owned(), value);
+
+    Ok(())
+}
+
 #[starlark_module]
 pub(crate) fn register_write_package_value(globals: &mut GlobalsBuilder) {
     /// Set the value to be accessible in the nested `PACKAGE` files.
@@ -174,30 +242,34 @@ pub(crate) fn register_write_package_value(globals: &mut GlobalsBuilder) {
         eval: &mut Evaluator<'v, '_, '_>,
     ) -> anyhow::Result<NoneType> {
         let key = MetadataKeyRef::new(key)?;
+        write_package_value_impl("write_package_value", key, value, overwrite, eval)?;
+        Ok(NoneType)
+    }
 
-        let package_ctx = BuildContext::from_context(eval)?
-            .additional
-            .require_package_file("write_package_value")?;
-
-        let package_file_extra = PackageFileExtra::get_or_init(eval)?;
-
-        if package_file_extra.package_values.borrow().contains_key(key) {
-            return Err(PackageValueError::KeyAlreadySetInThisFile(key.to_owned()).into());
-        }
-
-        if !overwrite {
-            if package_ctx.parent.package_values().contains_key(key) {
-                return Err(PackageValueError::KeySetInParentFile(key.to_owned()).into());
-            }
-        }
-
-        let value = StarlarkPackageValue::new(value)?;
-
-        package_file_extra
-            .package_values
-            .borrow_mut()
-            .insert(key.to_owned(), value);
-
+    /// Set a feature flag for this package and all the packages below it,
+    /// replacing the value set by any parent `PACKAGE` file.
+    ///
+    /// The value must be a bool, an int or a string. Rule implementations read the flags
+    /// of the package of the target they analyze with `ctx.feature_flag(name)`,
+    /// and `buck2 audit feature-flags` lists the flags of packages.
+    ///
+    /// ```python
+    /// write_feature_flag("new_link_strategy", True)
+    /// ```
+    fn write_feature_flag<'v>(
+        #[starlark(require = pos)] name: &str,
+        #[starlark(require = pos)] value: Value<'v>,
+        eval: &mut Evaluator<'v, '_, '_>,
+    ) -> anyhow::Result<NoneType> {
+        let key = feature_flag_key(name)?;
+        FeatureFlagValue::from_json(name, &value.to_json_value()?)?;
+        write_package_value_impl(
+            "write_feature_flag",
+            MetadataKeyRef::unchecked_new(key.as_str()),
+            value,
+            true,
+            eval,
+        )?;
         Ok(NoneType)
     }
 }
@@ -563,3 +563,129 @@ fn test_oncall() -> anyhow::Result<()> {
     );
     Ok(())
 }
+
+#[test]
+fn test_glob_rejects_bazel_arguments() -> anyhow::Result<()> {
+    let mut tester = Tester::new()?;
+    tester.run_starlark_test_expecting_error(
+        indoc!(
+            r#"
+            def test():
+                glob(["*.java"], allow_empty = True)
+            "#
+        ),
+        "only accepts `allow_empty` in cells with `bazel_compat.glob = bazel`",
+    );
+    tester.run_starlark_test_expecting_error(
+        indoc!(
+            r#"
+            def test():
+                glob(["*.java"], exclude_directories = 1)
+            "#
+        ),
+        "only accepts `exclude_directories`",
+    );
+    Ok(())
+}
+
+#[test]
+fn test_bazel_compat_glob() -> anyhow::Result<()> {
+    let mut tester = Tester::with_cells(buck2_interpreter_for_build::interpreter::testing::cells(
+        Some(indoc!(
+            r#"
+            [bazel_compat]
+                enabled = true
+                glob = bazel
+                glob_allow_empty = false
+        "#
+        )),
+    )?)?;
+    tester.run_starlark_test(indoc!(
+        r#"
+            def test():
+                assert_eq(["file1.java", "file2.java"], glob(["*.java"], exclude_directories = 1))
+                assert_eq([], glob(["*.cpp"], allow_empty = True))
+            "#
+    ))?;
+    tester.run_starlark_test_expecting_error(
+        indoc!(
+            r#"
+            def test():
+                glob(["*.cpp"])
+            "#
+        ),
+        "matched no files, pass `allow_empty = True`",
+    );
+    tester.run_starlark_test_expecting_error(
+        indoc!(
+            r#"
+            def test():
+                glob(["*.java"], exclude_directories = 0)
+            "#
+        ),
+        "`exclude_directories = 0` is not supported",
+    );
+    Ok(())
+}
+
+#[test]
+fn test_bazel_compat_prelude_and_rule_aliases() -> anyhow::Result<()> {
+    let cells = |rule_aliases: &str| {
+        buck2_interpreter_for_build::interpreter::testing::cells(Some(
+            &indoc!(
+                r#"
+                [bazel_compat]
+                    enabled = true
+                    prelude = //bazel.bzl
+                    rule_aliases = RULE_ALIASES
+                "#
+            )
+            .replace("RULE_ALIASES", rule_aliases),
+        ))
+    };
+    let bazel_prelude = indoc!(
+        r#"
+            def _impl(ctx):
+                pass
+            export_file = rule(impl=_impl, attrs = {})
+
+            def cc_binary(name):
+                export_file(name = name)
+        "#
+    );
+
+    let mut tester = Tester::with_cells(cells("cc_library=export_file")?)?;
+    tester.add_import(&ImportPath::testing_new("root//:bazel.bzl"), bazel_prelude)?;
+    let eval_result = tester.eval_build_file(
+        &Tester::build_file_path(),
+        indoc!(
+            r#"
+                cc_library(name = "lib")
+                cc_binary(name = "bin")
+                "#
+        ),
+        PackageListing::testing_empty(),
+    )?;
+    let target_names = eval_result
+        .targets()
+        .keys()
+        .map(|t| t.as_str().to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(vec!["lib", "bin"], target_names);
+
+    let mut tester = Tester::with_cells(cells("cc_test=undefined_rule")?)?;
+    tester.add_import(&ImportPath::testing_new("root//:bazel.bzl"), bazel_prelude)?;
+    let err = tester
+        .eval_build_file(
+            &Tester::build_file_path(),
+            "",
+            PackageListing::testing_empty(),
+        )
+        .unwrap_err();
+    assert!(
+        format!("{:?}", err).contains("Bazel rule alias `cc_test` refers to `undefined_rule`"),
+        "{:?}",
+        err
+    );
+    Ok(())
+}
@@ -126,6 +126,53 @@ fn rule_unbound() {
     );
 }
 
+#[test]
+fn rule_deprecated() -> anyhow::Result<()> {
+    let mut tester = rule_tester();
+    tester.run_starlark_test(indoc!(
+        r#"
+        def impl(ctx):
+            pass
+
+        old_rule = rule(
+            impl=impl,
+            attrs={
+                "flag": attrs.deprecated(attrs.bool(default=False), "Use `mode`", removal_milestone="2025-01"),
+                "mode": attrs.string(default=""),
+            },
+            deprecated="Use `new_rule`",
+            removal_milestone="2025-01",
+        )
+
+        def test():
+            assert_eq(None, old_rule(name="target_name", flag=True))
+        "#
+    ))?;
+    Ok(())
+}
+
+#[test]
+fn rule_removal_milestone_requires_deprecated() {
+    let mut tester = rule_tester();
+    tester.run_starlark_bzl_test_expecting_error(
+        indoc!(
+            r#"
+        def impl(ctx):
+            pass
+
+        frozen_rule = rule(
+            impl=impl,
+            attrs={},
+            removal_milestone="2025-01",
+        )
+        def test():
+            pass
+        "#
+        ),
+        "can only be specified for deprecated rules",
+    );
+}
+
 #[test]
 fn udr_is_recorded() -> buck2_error::Result<()> {
     let content = indoc!(
@@ -31,6 +31,7 @@ pub mod internal;
 pub mod json;
 pub mod serialize;
 pub mod spec;
+pub mod str_interner;
 pub mod testing;
 pub mod traversal;
 pub mod values;
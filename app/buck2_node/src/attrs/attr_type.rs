@@ -132,7 +132,7 @@ impl AttrType {
             AttrTypeInner::ConfiguredDep(_) => attr("configured_dep"),
             AttrTypeInner::PluginDep(_) => attr("plugin_dep"),
             AttrTypeInner::Bool(_) => attr("bool"),
-            AttrTypeInner::Int(_) => attr("int"),
+            AttrTypeInner::Int(x) => attr(x.name()),
             AttrTypeInner::Dep(_) => attr("dep"),
             AttrTypeInner::Query(_) => attr("query"),
             AttrTypeInner::Dict(x) => x.fmt_with_arg(f, &arg()),
@@ -191,7 +191,23 @@ impl AttrType {
 
     pub fn int() -> Self {
         Self(Arc::new(AttrTypeInner2 {
-            inner: AttrTypeInner::Int(IntAttrType),
+            inner: AttrTypeInner::Int(IntAttrType::Int),
+            may_have_queries: false,
+        }))
+    }
+
+    /// A duration, given as an int of milliseconds or a string like `"30s"`.
+    pub fn duration() -> Self {
+        Self(Arc::new(AttrTypeInner2 {
+            inner: AttrTypeInner::Int(IntAttrType::Duration),
+            may_have_queries: false,
+        }))
+    }
+
+    /// A memory size, given as an int of bytes or a string like `"512MiB"`.
+    pub fn memory_size() -> Self {
+        Self(Arc::new(AttrTypeInner2 {
+            inner: AttrTypeInner::Int(IntAttrType::MemorySize),
             may_have_queries: false,
         }))
     }
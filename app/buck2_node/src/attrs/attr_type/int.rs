@@ -10,5 +10,118 @@
 use allocative::Allocative;
 use dupe::Dupe;
 
+#[derive(Debug, buck2_error::Error)]
+#[buck2(input)]
+enum IntAttrError {
+    #[error(
+        "Invalid duration `{0}`, expected a number followed by one of `ms`, `s`, `m`, `h`, `d`"
+    )]
+    InvalidDuration(String),
+    #[error(
+        "Invalid memory size `{0}`, expected a number followed by one of `B`, `KB`, `MB`, `GB`, `TB`, `KiB`, `MiB`, `GiB`, `TiB`"
+    )]
+    InvalidMemorySize(String),
+    #[error("`{0}` is too large")]
+    Overflow(String),
+}
+
+/// Integer attributes. Durations and memory sizes can also be written as strings with a unit,
+/// like `"30s"` or `"512MiB"`, and are normalized to milliseconds and bytes.
 #[derive(Debug, Eq, PartialEq, Hash, Allocative, Clone, Copy, Dupe)]
-pub struct IntAttrType;
+pub enum IntAttrType {
+    Int,
+    Duration,
+    MemorySize,
+}
+
+const DURATION_UNITS: &[(&str, i64)] = &[
+    ("ms", 1),
+    ("s", 1_000),
+    ("m", 60 * 1_000),
+    ("h", 60 * 60 * 1_000),
+    ("d", 24 * 60 * 60 * 1_000),
+];
+
+const MEMORY_SIZE_UNITS: &[(&str, i64)] = &[
+    ("B", 1),
+    ("KB", 1_000),
+    ("MB", 1_000_000),
+    ("GB", 1_000_000_000),
+    ("TB", 1_000_000_000_000),
+    ("KiB", 1 << 10),
+    ("MiB", 1 << 20),
+    ("GiB", 1 << 30),
+    ("TiB", 1 << 40),
+];
+
+fn parse_with_units(s: &str, units: &[(&str, i64)]) -> Option<anyhow::Result<i64>> {
+    let split = s.find(|c: char| !c.is_ascii_digit())?;
+    let (number, unit) = s.split_at(split);
+    let number: i64 = number.parse().ok()?;
+    let (_, multiplier) = units.iter().find(|(u, _)| *u == unit.trim_start())?;
+    Some(
+        number
+            .checked_mul(*multiplier)
+            .ok_or_else(|| IntAttrError::Overflow(s.to_owned()).into()),
+    )
+}
+
+impl IntAttrType {
+    /// Name of the `attrs` function creating this type.
+    pub fn name(self) -> &'static str {
+        match self {
+            IntAttrType::Int => "int",
+            IntAttrType::Duration => "duration",
+            IntAttrType::MemorySize => "memory_size",
+        }
+    }
+
+    /// Parse a string with a unit to the normalized integer value, or `None` for plain ints.
+    pub fn parse_str(self, s: &str) -> Option<anyhow::Result<i64>> {
+        match self {
+            IntAttrType::Int => None,
+            IntAttrType::Duration => Some(
+                parse_with_units(s, DURATION_UNITS)
+                    .unwrap_or_else(|| Err(IntAttrError::InvalidDuration(s.to_owned()).into())),
+            ),
+            IntAttrType::MemorySize => Some(
+                parse_with_units(s, MEMORY_SIZE_UNITS)
+                    .unwrap_or_else(|| Err(IntAttrError::InvalidMemorySize(s.to_owned()).into())),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duration() {
+        let parse = |s: &str| IntAttrType::Duration.parse_str(s).unwrap();
+        assert_eq!(30_000, parse("30s").unwrap());
+        assert_eq!(300_000, parse("5m").unwrap());
+        assert_eq!(250, parse("250ms").unwrap());
+        assert_eq!(7_200_000, parse("2 h").unwrap());
+        assert!(parse("5").is_err());
+        assert!(parse("s").is_err());
+        assert!(parse("1.5s").is_err());
+        assert!(parse("5w").is_err());
+    }
+
+    #[test]
+    fn test_memory_size() {
+        let parse = |s: &str| IntAttrType::MemorySize.parse_str(s).unwrap();
+        assert_eq!(512 << 20, parse("512MiB").unwrap());
+        assert_eq!(2_000, parse("2KB").unwrap());
+        assert_eq!(10, parse("10B").unwrap());
+        assert!(parse("10").is_err());
+        assert!(parse("10mb").is_err());
+        assert!(parse("99999999999TiB").is_err());
+    }
+
+    #[test]
+    fn test_int() {
+        assert!(IntAttrType::Int.parse_str("5s").is_none());
+    }
+}
@@ -160,6 +160,15 @@ impl CoercedSelector {
     fn all_values(&self) -> impl Iterator<Item = &'_ CoercedAttr> {
         self.all_entries().map(|(_, v)| v)
     }
+
+    /// Keys of the branches, excluding `DEFAULT`.
+    pub fn keys(&self) -> impl Iterator<Item = &ConfigurationSettingKey> {
+        self.entries.iter().map(|(k, _)| k)
+    }
+
+    pub fn has_default(&self) -> bool {
+        self.default.is_some()
+    }
 }
 
 /// CoercedAttr is the "coerced" representation of an attribute. It has been type-checked and converted to
@@ -579,6 +588,87 @@ impl CoercedAttr {
         }
     }
 
+    /// Visits all the selects in this attribute, including the selects nested in the branches of
+    /// other selects.
+    pub fn visit_selectors<'a>(&'a self, visit: &mut dyn FnMut(&'a CoercedSelector)) {
+        match self {
+            CoercedAttr::Selector(s) => {
+                visit(s);
+                for v in s.all_values() {
+                    v.visit_selectors(visit);
+                }
+            }
+            CoercedAttr::Concat(items) => {
+                for item in &**items {
+                    item.visit_selectors(visit);
+                }
+            }
+            CoercedAttr::List(vals) => {
+                for v in vals.iter() {
+                    v.visit_selectors(visit);
+                }
+            }
+            CoercedAttr::Tuple(vals) => {
+                for v in vals.iter() {
+                    v.visit_selectors(visit);
+                }
+            }
+            CoercedAttr::Dict(d) => {
+                for (k, v) in d.iter() {
+                    k.visit_selectors(visit);
+                    v.visit_selectors(visit);
+                }
+            }
+            CoercedAttr::OneOf(l, _) => l.visit_selectors(visit),
+            _ => {}
+        }
+    }
+
+    /// Collects the keys of the `select()` branches taken in the provided context, including the
+    /// keys of nested selects in the taken branches. Nothing is collected for a default branch.
+    pub fn selected_keys<'a>(
+        &'a self,
+        ctx: &dyn AttrConfigurationContext,
+        res: &mut Vec<&'a ConfigurationSettingKey>,
+    ) -> anyhow::Result<()> {
+        match self {
+            CoercedAttr::Selector(s) => {
+                let taken = Self::select(ctx, s)?;
+                if let Some((k, _)) = s.entries.iter().find(|(_, v)| std::ptr::eq(v, taken)) {
+                    res.push(k);
+                }
+                taken.selected_keys(ctx, res)
+            }
+            CoercedAttr::Concat(items) => {
+                for item in &**items {
+                    item.selected_keys(ctx, res)?;
+                }
+                Ok(())
+            }
+            CoercedAttr::List(vals) => {
+                for v in vals.iter() {
+                    v.selected_keys(ctx, res)?;
+                }
+                Ok(())
+            }
+            CoercedAttr::Tuple(vals) => {
+                for v in vals.iter() {
+                    v.selected_keys(ctx, res)?;
+                }
+                Ok(())
+            }
+            CoercedAttr::Dict(d) => {
+                for (k, v) in d.iter() {
+                    k.selected_keys(ctx, res)?;
+                    v.selected_keys(ctx, res)?;
+                }
+                Ok(())
+            }
+            CoercedAttr::OneOf(l, _) => l.selected_keys(ctx, res),
+            _ => Ok(()),
+        }
+    }
+
     /// Returns the "configured" representation of the attribute in the provided context.
     /// This handles the resolution of the select() conditions and delegates to
     /// the actual attr type for handling any appropriate configuration-time
@@ -208,7 +208,7 @@ impl<'a, 't> CoercedAttrWithType<'a, 't> {
                 Err(CoercedAttrWithTypeError::Select.into())
             }
             CoercedAttr::Bool(b) => Ok(CoercedAttrWithType::Bool(*b, BoolAttrType)),
-            CoercedAttr::Int(i) => Ok(CoercedAttrWithType::Int(*i, IntAttrType)),
+            CoercedAttr::Int(i) => Ok(CoercedAttrWithType::Int(*i, IntAttrType::Int)),
             CoercedAttr::String(s) => Ok(CoercedAttrWithType::String(s, StringAttrType)),
             CoercedAttr::List(l) => Ok(CoercedAttrWithType::AnyList(l)),
             CoercedAttr::Tuple(t) => Ok(CoercedAttrWithType::AnyTuple(t)),
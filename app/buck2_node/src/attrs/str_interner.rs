@@ -0,0 +1,124 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Daemon-wide interner for strings stored in coerced attributes.
+//!
+//! Attribute strings (compiler flags, labels in `string` attributes, licenses, ...) are heavily
+//! repeated across BUCK files, so sharing them between packages saves a lot of memory on large
+//! graphs. Strings are kept alive by the attributes referencing them: entries which are only
+//! referenced by the interner are dropped when the table grows.
+
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use allocative::Allocative;
+use buck2_util::arc_str::ArcStr;
+use buck2_util::hash::BuckHasherBuilder;
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use dupe::Dupe;
+use once_cell::sync::Lazy;
+
+/// Don't bother trimming tables smaller than this.
+const MIN_TRIM_LEN: usize = 1 << 16;
+
+#[allocative::root]
+static GLOBAL_STR_INTERNER: Lazy<GlobalStrInterner> = Lazy::new(GlobalStrInterner::new);
+
+/// Share `s` with equal strings previously interned in this daemon.
+pub fn intern_str_globally(s: &str) -> ArcStr {
+    GLOBAL_STR_INTERNER.intern(s)
+}
+
+#[derive(Allocative)]
+struct GlobalStrInterner {
+    strings: DashMap<ArcStr, (), BuckHasherBuilder>,
+    /// Number of insertions since the last trim, plus the size after it. `DashMap::len` locks
+    /// every shard, so it is too expensive to call on every insertion.
+    approx_len: AtomicUsize,
+    /// Table size at which unreferenced strings are dropped next.
+    trim_at: AtomicUsize,
+}
+
+impl GlobalStrInterner {
+    fn new() -> GlobalStrInterner {
+        GlobalStrInterner {
+            strings: DashMap::with_hasher(BuckHasherBuilder),
+            approx_len: AtomicUsize::new(0),
+            trim_at: AtomicUsize::new(MIN_TRIM_LEN),
+        }
+    }
+
+    fn intern(&self, s: &str) -> ArcStr {
+        if s.is_empty() {
+            return ArcStr::default();
+        }
+
+        if let Some(e) = self.strings.get(s) {
+            return e.key().dupe();
+        }
+
+        let value = match self.strings.entry(ArcStr::from(s)) {
+            Entry::Occupied(e) => return e.key().dupe(),
+            Entry::Vacant(e) => {
+                let value = e.key().dupe();
+                e.insert(());
+                value
+            }
+        };
+
+        // The shard lock is released at this point.
+        let len = self.approx_len.fetch_add(1, Ordering::Relaxed) + 1;
+        if len >= self.trim_at.load(Ordering::Relaxed) {
+            self.trim();
+        }
+        value
+    }
+
+    /// Drop the strings which are no longer referenced outside of the interner.
+    fn trim(&self) {
+        // Strings are only copied out of the table under the shard lock, which `retain` holds,
+        // so a count of one cannot increase concurrently.
+        self.strings.retain(|s, _| ArcStr::strong_count(s) > 1);
+        let len = self.strings.len();
+        self.approx_len.store(len, Ordering::Relaxed);
+        self.trim_at
+            .store((len * 2).max(MIN_TRIM_LEN), Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use crate::attrs::str_interner::GlobalStrInterner;
+
+    #[test]
+    fn test_global_str_interner() {
+        let interner = GlobalStrInterner::new();
+        let foo0 = interner.intern("foo");
+        let foo1 = interner.intern("foo");
+        assert!(ptr::eq(foo0.as_ptr(), foo1.as_ptr()));
+        assert_eq!(1, interner.strings.len());
+        assert!(interner.intern("").is_empty());
+        assert_eq!(1, interner.strings.len());
+    }
+
+    #[test]
+    fn test_global_str_interner_trim() {
+        let interner = GlobalStrInterner::new();
+        let foo = interner.intern("foo");
+        drop(interner.intern("bar"));
+        assert_eq!(2, interner.strings.len());
+
+        interner.trim();
+        assert_eq!(1, interner.strings.len());
+        assert!(ptr::eq(foo.as_ptr(), interner.intern("foo").as_ptr()));
+    }
+}
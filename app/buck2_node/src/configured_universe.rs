@@ -19,6 +19,7 @@ use buck2_core::cells::cell_path::CellPath;
 use buck2_core::fs::project_rel_path::ProjectRelativePath;
 use buck2_core::package::PackageLabel;
 use buck2_core::pattern::pattern::PackageSpec;
+use buck2_core::pattern::pattern::ParsedPattern;
 use buck2_core::pattern::pattern_type::PatternType;
 use buck2_core::pattern::pattern_type::TargetPatternExtra;
 use buck2_core::provider::label::ConfiguredProvidersLabel;
@@ -186,6 +187,30 @@ impl CqueryUniverse {
         .collect()
     }
 
+    /// The nodes matching `pattern`, resolved against the targets of the universe rather than
+    /// the packages in the working copy, e.g. for a universe retained from an earlier build.
+    pub fn get_matching(
+        &self,
+        pattern: &ParsedPattern<TargetPatternExtra>,
+    ) -> TargetSet<ConfiguredTargetNode> {
+        self.iter()
+            .filter(|node| pattern.matches(node.label().unconfigured()))
+            .map(|node| node.to_owned())
+            .collect()
+    }
+
+    /// The node with the given label, if it is in the universe.
+    pub fn get_node(&self, label: &ConfiguredTargetLabel) -> Option<ConfiguredTargetNode> {
+        self.data
+            .data()
+            .targets
+            .get(&label.pkg())?
+            .get(label.name())?
+            .iter()
+            .find(|node| node.0.label() == label)
+            .map(|node| node.0.to_owned())
+    }
+
     pub fn contains(&self, label: &ConfiguredTargetLabel) -> bool {
         self.get_target_label(label.unconfigured())
             .iter()
@@ -235,10 +260,11 @@ impl CqueryUniverse {
                             })
                     }))
                 }
-                PackageSpec::All => Either::Right(
+                PackageSpec::All | PackageSpec::AllExcept(_) => Either::Right(
                     package_universe
                         .values()
                         .flatten()
+                        .filter(|node| !spec.excludes(node.0.label().name()))
                         .map(|node| (node.0, P::default())),
                 ),
             })
@@ -275,14 +301,17 @@ impl CqueryUniverse {
 #[cfg(test)]
 mod tests {
     use buck2_common::pattern::resolve::ResolvedPattern;
+    use buck2_core::cells::cell_path::CellPath;
     use buck2_core::configuration::bound_label::BoundConfigurationLabel;
     use buck2_core::configuration::data::ConfigurationData;
     use buck2_core::configuration::hash::ConfigurationHash;
     use buck2_core::execution_types::execution::ExecutionPlatformResolution;
     use buck2_core::package::PackageLabel;
     use buck2_core::pattern::pattern::PackageSpec;
+    use buck2_core::pattern::pattern::ParsedPattern;
     use buck2_core::pattern::pattern_type::ConfigurationPredicate;
     use buck2_core::pattern::pattern_type::ConfiguredProvidersPatternExtra;
+    use buck2_core::pattern::pattern_type::TargetPatternExtra;
     use buck2_core::provider::label::ConfiguredProvidersLabel;
     use buck2_core::provider::label::NonDefaultProvidersName;
     use buck2_core::provider::label::ProviderName;
@@ -373,4 +402,52 @@ mod tests {
             )))
         );
     }
+
+    #[tokio::test]
+    async fn test_get_matching() {
+        let node = |label: &str| {
+            ConfiguredTargetNode::testing_new(
+                ConfiguredTargetLabel::testing_parse(label, ConfigurationData::testing_new()),
+                "idris_library",
+                ExecutionPlatformResolution::new(None, Vec::new()),
+                vec![],
+                vec![],
+            )
+        };
+        let universe = CqueryUniverse::build(&TargetSet::from_iter([
+            node("foo//bar:baz"),
+            node("foo//bar:qux"),
+            node("foo//bar/sub:baz"),
+            node("foo//barn:baz"),
+        ]))
+        .unwrap();
+        let matching = |pattern: ParsedPattern<TargetPatternExtra>| {
+            let mut labels: Vec<String> = universe
+                .get_matching(&pattern)
+                .iter()
+                .map(|node| node.label().unconfigured().to_string())
+                .collect();
+            labels.sort();
+            labels
+        };
+
+        assert_eq!(
+            vec!["foo//bar:baz"],
+            matching(ParsedPattern::Target(
+                PackageLabel::testing_parse("foo//bar"),
+                TargetName::testing_new("baz"),
+                TargetPatternExtra,
+            ))
+        );
+        assert_eq!(
+            vec!["foo//bar:baz", "foo//bar:qux"],
+            matching(ParsedPattern::Package(PackageLabel::testing_parse(
+                "foo//bar"
+            )))
+        );
+        assert_eq!(
+            vec!["foo//bar/sub:baz", "foo//bar:baz", "foo//bar:qux"],
+            matching(ParsedPattern::Recursive(CellPath::testing_new("foo//bar")))
+        );
+    }
 }
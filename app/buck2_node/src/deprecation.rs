@@ -0,0 +1,364 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Deprecation of rules and attributes.
+//!
+//! Rules declared with `rule(deprecated = ...)` and attributes wrapped in `attrs.deprecated(...)`
+//! are reported once per command, aggregated by deprecated item, for the targets the command
+//! loads. With `buck2.strict_deprecations` (set by `--strict-deprecations`) any use by a target
+//! being built is an error, as are uses by targets in packages for which the `deprecation`
+//! category of `soft_error_policies` is `error`; commands which only inspect the graph, like
+//! `targets` or queries, still just warn.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::Write;
+
+use allocative::Allocative;
+use buck2_common::legacy_configs::dice::HasLegacyConfigs;
+use buck2_common::legacy_configs::key::BuckconfigKeyRef;
+use buck2_common::soft_error_policies::HasSoftErrorPolicies;
+use buck2_core::soft_error_policy::SoftErrorPolicies;
+use buck2_core::soft_error_policy::SoftErrorSeverity;
+use buck2_core::target::label::label::TargetLabel;
+use buck2_events::dispatch::console_message;
+use dice::DiceComputations;
+use dupe::Dupe;
+
+use crate::attrs::inspect_options::AttrInspectOptions;
+use crate::nodes::unconfigured::TargetNodeRef;
+
+pub const STRICT_DEPRECATIONS_BUCKCONFIG: BuckconfigKeyRef = BuckconfigKeyRef {
+    section: "buck2",
+    property: "strict_deprecations",
+};
+
+/// Category of the `soft_error_policies` applied to uses of deprecated items, by package.
+const DEPRECATION_SOFT_ERROR_CATEGORY: &str = "deprecation";
+
+/// Number of targets listed for each deprecated item, the rest are only counted.
+const MAX_TARGETS_LISTED: usize = 5;
+
+#[derive(Debug, buck2_error::Error)]
+#[buck2(input)]
+enum DeprecationError {
+    #[error(
+        "{0} use(s) of deprecated rules or attributes, failing because of `--strict-deprecations`"
+    )]
+    Strict(usize),
+    #[error(
+        "{0} use(s) of deprecated rules or attributes in packages where `soft_error_policies` makes them errors"
+    )]
+    Policy(usize),
+}
+
+/// Why a rule or an attribute is deprecated, and when it is going away.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Allocative)]
+pub struct Deprecation {
+    /// What to use instead.
+    pub message: String,
+    /// Free form name of the release or date after which the item may be removed.
+    pub removal: Option<String>,
+}
+
+impl fmt::Display for Deprecation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(removal) = &self.removal {
+            write!(f, " (to be removed in {})", removal)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub enum DeprecatedItem {
+    Rule(String),
+    Attribute { rule: String, attribute: String },
+}
+
+impl fmt::Display for DeprecatedItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeprecatedItem::Rule(rule) => write!(f, "rule `{}`", rule),
+            DeprecatedItem::Attribute { rule, attribute } => {
+                write!(f, "attribute `{}` of rule `{}`", attribute, rule)
+            }
+        }
+    }
+}
+
+/// Uses of deprecated items, grouped by item.
+#[derive(Default, Debug)]
+pub struct DeprecationReport {
+    uses: BTreeMap<DeprecatedItem, (Deprecation, Vec<TargetLabel>)>,
+}
+
+impl DeprecationReport {
+    pub fn new() -> DeprecationReport {
+        DeprecationReport::default()
+    }
+
+    fn add(&mut self, item: DeprecatedItem, deprecation: &Deprecation, target: &TargetLabel) {
+        self.uses
+            .entry(item)
+            .or_insert_with(|| (deprecation.clone(), Vec::new()))
+            .1
+            .push(target.dupe());
+    }
+
+    /// Record the deprecated rule or explicitly set deprecated attributes of a target.
+    pub fn add_target(&mut self, node: TargetNodeRef) {
+        let rule = node.rule_type().name();
+        if let Some(deprecation) = node.rule_deprecation() {
+            self.add(
+                DeprecatedItem::Rule(rule.to_owned()),
+                deprecation,
+                node.label(),
+            );
+        }
+        for attr in node.attrs(AttrInspectOptions::DefinedOnly) {
+            if let Some(deprecation) = attr.attr.deprecation() {
+                self.add(
+                    DeprecatedItem::Attribute {
+                        rule: rule.to_owned(),
+                        attribute: attr.name.to_owned(),
+                    },
+                    deprecation,
+                    node.label(),
+                );
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.uses.is_empty()
+    }
+
+    /// Total number of uses, counting each target once per deprecated item.
+    pub fn count(&self) -> usize {
+        self.uses.values().map(|(_, targets)| targets.len()).sum()
+    }
+
+    pub fn message(&self) -> String {
+        let mut res = String::new();
+        for (item, (deprecation, targets)) in &self.uses {
+            writeln!(
+                res,
+                "Deprecated {} used by {} target(s): {}",
+                item,
+                targets.len(),
+                deprecation
+            )
+            .unwrap();
+            for target in targets.iter().take(MAX_TARGETS_LISTED) {
+                writeln!(res, "    {}", target).unwrap();
+            }
+            if targets.len() > MAX_TARGETS_LISTED {
+                writeln!(res, "    and {} more", targets.len() - MAX_TARGETS_LISTED).unwrap();
+            }
+        }
+        res.truncate(res.trim_end().len());
+        res
+    }
+
+    /// Fail if there are any uses and `strict` is set, or if there are uses in packages for
+    /// which `policies` make deprecations errors.
+    fn check(&self, strict: bool, policies: &SoftErrorPolicies) -> anyhow::Result<()> {
+        if strict && !self.is_empty() {
+            return Err(DeprecationError::Strict(self.count()).into());
+        }
+        let errors = self
+            .uses
+            .values()
+            .flat_map(|(_, targets)| targets)
+            .filter(|target| {
+                policies.severity(
+                    DEPRECATION_SOFT_ERROR_CATEGORY,
+                    Some(&target.pkg().to_string()),
+                ) == Some(SoftErrorSeverity::Error)
+            })
+            .count();
+        if errors > 0 {
+            return Err(DeprecationError::Policy(errors).into());
+        }
+        Ok(())
+    }
+}
+
+fn collect_report<'a>(targets: impl IntoIterator<Item = TargetNodeRef<'a>>) -> DeprecationReport {
+    let mut report = DeprecationReport::new();
+    for target in targets {
+        report.add_target(target);
+    }
+    if !report.is_empty() {
+        console_message(report.message());
+    }
+    report
+}
+
+/// Warn about the deprecated rules and attributes used by `targets`. Never fails, strict
+/// deprecations only apply to builds, see `check_build_deprecations`.
+pub fn report_deprecations<'a>(targets: impl IntoIterator<Item = TargetNodeRef<'a>>) {
+    collect_report(targets);
+}
+
+/// Warn about the deprecated rules and attributes used by the targets being built, or fail if
+/// strict deprecations are enabled or `soft_error_policies` make them errors.
+pub async fn check_build_deprecations<'a>(
+    ctx: &mut DiceComputations<'_>,
+    targets: impl IntoIterator<Item = TargetNodeRef<'a>>,
+) -> anyhow::Result<()> {
+    let report = collect_report(targets);
+    if report.is_empty() {
+        return Ok(());
+    }
+    let strict = ctx
+        .get_legacy_root_config_on_dice()
+        .await?
+        .view(ctx)
+        .parse::<bool>(STRICT_DEPRECATIONS_BUCKCONFIG)?
+        .unwrap_or(false);
+    let policies = ctx.get_soft_error_policies().await?;
+    report.check(strict, &policies)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use buck2_core::bzl::ImportPath;
+    use buck2_core::package::PackageLabel;
+    use buck2_core::soft_error_policy::SoftErrorPolicy;
+    use buck2_core::target::name::TargetName;
+
+    use super::*;
+    use crate::attrs::attr::Attribute;
+    use crate::attrs::attr_type::string::StringLiteral;
+    use crate::attrs::attr_type::AttrType;
+    use crate::attrs::coerced_attr::CoercedAttr;
+    use crate::nodes::unconfigured::testing::TargetNodeExt;
+    use crate::nodes::unconfigured::TargetNode;
+    use crate::rule_type::RuleType;
+    use crate::rule_type::StarlarkRuleType;
+
+    fn deprecation(message: &str) -> Deprecation {
+        Deprecation {
+            message: message.to_owned(),
+            removal: None,
+        }
+    }
+
+    fn node(name: &str, set_deprecated_attr: bool) -> TargetNode {
+        let label = TargetLabel::new(
+            PackageLabel::testing_parse("cell//pkg"),
+            TargetName::testing_new(name).as_ref(),
+        );
+        let rule_type = RuleType::Starlark(Arc::new(StarlarkRuleType {
+            import_path: ImportPath::testing_new("cell//pkg:rules.bzl"),
+            name: "my_rule".to_owned(),
+        }));
+        let attrs = if set_deprecated_attr {
+            vec![(
+                "old",
+                Attribute::new(None, "", AttrType::string())
+                    .with_deprecation(deprecation("Use `new` instead")),
+                CoercedAttr::String(StringLiteral("x".into())),
+            )]
+        } else {
+            Vec::new()
+        };
+        TargetNode::testing_new(label, rule_type, attrs, Vec::new())
+    }
+
+    #[test]
+    fn test_report_deprecated_attribute() {
+        let nodes = [node("a", true), node("b", false), node("c", true)];
+        let mut report = DeprecationReport::new();
+        for node in &nodes {
+            report.add_target(node.as_ref());
+        }
+        assert_eq!(2, report.count());
+        assert_eq!(
+            "Deprecated attribute `old` of rule `my_rule` used by 2 target(s): Use `new` instead\n    cell//pkg:a\n    cell//pkg:c",
+            report.message()
+        );
+    }
+
+    #[test]
+    fn test_report_truncates_targets() {
+        let mut report = DeprecationReport::new();
+        let deprecation = Deprecation {
+            message: "Use `new_rule` instead".to_owned(),
+            removal: Some("2025.01".to_owned()),
+        };
+        for i in 0..7 {
+            report.add(
+                DeprecatedItem::Rule("old_rule".to_owned()),
+                &deprecation,
+                node(&format!("t{}", i), false).label(),
+            );
+        }
+        assert_eq!(7, report.count());
+        let message = report.message();
+        assert!(
+            message.starts_with("Deprecated rule `old_rule` used by 7 target(s): Use `new_rule` instead (to be removed in 2025.01)\n"),
+            "{}",
+            message
+        );
+        assert!(message.contains("    cell//pkg:t4\n"), "{}", message);
+        assert!(!message.contains("cell//pkg:t5"), "{}", message);
+        assert!(message.ends_with("    and 2 more"), "{}", message);
+    }
+
+    #[test]
+    fn test_check_warn_and_strict() {
+        let policies = SoftErrorPolicies::default();
+        let empty = DeprecationReport::new();
+        assert!(empty.check(false, &policies).is_ok());
+        assert!(empty.check(true, &policies).is_ok());
+
+        let mut report = DeprecationReport::new();
+        report.add_target(node("a", true).as_ref());
+        // Without strict deprecations the uses are only printed.
+        assert!(report.check(false, &policies).is_ok());
+        let err = report.check(true, &policies).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("1 use(s) of deprecated rules or attributes"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_check_soft_error_policies() {
+        let policies = |policy: &str| {
+            SoftErrorPolicies::new(HashMap::from([(
+                DEPRECATION_SOFT_ERROR_CATEGORY.to_owned(),
+                policy.parse::<SoftErrorPolicy>().unwrap(),
+            )]))
+        };
+        let mut report = DeprecationReport::new();
+        report.add_target(node("a", true).as_ref());
+        report.add_target(node("b", true).as_ref());
+
+        assert!(report.check(false, &policies("warning")).is_ok());
+        assert!(report.check(false, &policies("cell//other error")).is_ok());
+        let err = report
+            .check(false, &policies("warning, cell//pkg error"))
+            .unwrap_err();
+        assert!(
+            err.to_string().starts_with("2 use(s) of deprecated"),
+            "{}",
+            err
+        );
+    }
+}
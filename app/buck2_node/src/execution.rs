@@ -32,8 +32,9 @@ pub static GET_EXECUTION_PLATFORMS: LateBinding<&'static dyn GetExecutionPlatfor
 #[allow(async_fn_in_trait)]
 pub trait GetExecutionPlatforms: Send {
     /// Returns a list of the configured execution platforms. This looks up the providers on the target
-    /// configured **in the root cell's buckconfig** with key `build.execution_platforms`. If there's no
-    /// value configured, it will return `None` which indicates we should fallback to the legacy execution
+    /// configured **in the root cell's buckconfig** with key `build.execution_platforms`, or on
+    /// `prelude//platforms:host` if `build.auto_host_execution_platform` is set instead. If neither is
+    /// configured, it will return `None` which indicates we should fallback to the legacy execution
     /// platform behavior.
     async fn get_execution_platforms(&mut self) -> buck2_error::Result<Option<ExecutionPlatforms>>;
 }
@@ -0,0 +1,159 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Per-package feature flags.
+//!
+//! Flags are package values in the `feature_flag` namespace, written with
+//! `write_feature_flag` in `PACKAGE` files. Nested `PACKAGE` files override the flags of their
+//! parents, so a behavior change can be rolled out one directory at a time. Rule
+//! implementations read the flags of the package of the analyzed target with
+//! `ctx.feature_flag()`.
+
+use std::fmt;
+
+use allocative::Allocative;
+use serde::Serialize;
+use starlark_map::small_map::SmallMap;
+use starlark_map::sorted_map::SortedMap;
+
+use crate::metadata::key::MetadataKey;
+use crate::metadata::super_package_values::SuperPackageValues;
+
+pub const FEATURE_FLAG_NAMESPACE: &str = "feature_flag";
+
+#[derive(Debug, buck2_error::Error)]
+#[buck2(input)]
+enum FeatureFlagError {
+    #[error("Feature flag name must be non-empty and must not contain dots, got `{0}`")]
+    InvalidName(String),
+    #[error("Feature flag `{0}` must be a bool, an int or a string, got `{1}`")]
+    InvalidValue(String, serde_json::Value),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Allocative, Serialize)]
+#[serde(untagged)]
+pub enum FeatureFlagValue {
+    Bool(bool),
+    Int(i64),
+    String(String),
+}
+
+impl FeatureFlagValue {
+    pub fn from_json(name: &str, value: &serde_json::Value) -> anyhow::Result<FeatureFlagValue> {
+        match value {
+            serde_json::Value::Bool(x) => Ok(FeatureFlagValue::Bool(*x)),
+            serde_json::Value::String(x) => Ok(FeatureFlagValue::String(x.clone())),
+            serde_json::Value::Number(x) if x.is_i64() => {
+                Ok(FeatureFlagValue::Int(x.as_i64().unwrap()))
+            }
+            _ => Err(FeatureFlagError::InvalidValue(name.to_owned(), value.clone()).into()),
+        }
+    }
+}
+
+impl fmt::Display for FeatureFlagValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeatureFlagValue::Bool(x) => write!(f, "{}", if *x { "True" } else { "False" }),
+            FeatureFlagValue::Int(x) => write!(f, "{}", x),
+            FeatureFlagValue::String(x) => write!(f, "{:?}", x),
+        }
+    }
+}
+
+/// Key of the package value storing the flag `name`.
+pub fn feature_flag_key(name: &str) -> anyhow::Result<MetadataKey> {
+    if name.is_empty() || name.contains('.') {
+        return Err(FeatureFlagError::InvalidName(name.to_owned()).into());
+    }
+    Ok(MetadataKey::try_from(format!(
+        "{}.{}",
+        FEATURE_FLAG_NAMESPACE, name
+    ))?)
+}
+
+/// Feature flags visible in a package, after applying all the `PACKAGE` files above it.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Hash, Allocative)]
+pub struct FeatureFlags(SortedMap<String, FeatureFlagValue>);
+
+impl FeatureFlags {
+    pub fn from_super_package_values(
+        values: &dyn SuperPackageValues,
+    ) -> anyhow::Result<FeatureFlags> {
+        if values.is_empty() {
+            return Ok(FeatureFlags::default());
+        }
+        Self::from_package_values_json(&values.package_values_json()?)
+    }
+
+    /// Extract the flags from all package values of a package.
+    pub fn from_package_values_json(
+        values: &SmallMap<MetadataKey, serde_json::Value>,
+    ) -> anyhow::Result<FeatureFlags> {
+        let mut flags = Vec::new();
+        for (key, value) in values {
+            if let Some((FEATURE_FLAG_NAMESPACE, name)) = key.as_str().split_once('.') {
+                flags.push((name.to_owned(), FeatureFlagValue::from_json(name, value)?));
+            }
+        }
+        Ok(FeatureFlags(flags.into_iter().collect()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&FeatureFlagValue> {
+        self.0.get(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &FeatureFlagValue)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_from_package_values() {
+        let values = SmallMap::from_iter([
+            (
+                MetadataKey::try_from("feature_flag.new_linker".to_owned()).unwrap(),
+                json!(true),
+            ),
+            (
+                MetadataKey::try_from("feature_flag.level".to_owned()).unwrap(),
+                json!(3),
+            ),
+            (
+                MetadataKey::try_from("other.value".to_owned()).unwrap(),
+                json!([1, 2]),
+            ),
+        ]);
+        let flags = FeatureFlags::from_package_values_json(&values).unwrap();
+        assert_eq!(
+            vec![
+                ("level", &FeatureFlagValue::Int(3)),
+                ("new_linker", &FeatureFlagValue::Bool(true)),
+            ],
+            flags.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(feature_flag_key("a.b").is_err());
+        assert!(feature_flag_key("").is_err());
+        assert!(FeatureFlagValue::from_json("x", &json!([1])).is_err());
+    }
+}
@@ -0,0 +1,89 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Configured graphs of recent builds, retained by the daemon so that `cquery --at` can
+//! query them after the working copy has changed.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use allocative::Allocative;
+use dice::UserComputationData;
+use dupe::Dupe;
+use parking_lot::Mutex;
+
+use crate::configured_universe::CqueryUniverse;
+
+/// Graphs of the last builds, owned by the daemon and shared by its commands.
+#[derive(Default, Allocative)]
+pub struct GraphSnapshots {
+    /// Graphs keyed by invocation id, most recent last.
+    snapshots: Mutex<VecDeque<(String, Arc<CqueryUniverse>)>>,
+}
+
+impl GraphSnapshots {
+    /// Retain `universe` for the invocation `trace_id`, evicting the oldest graphs beyond `keep`.
+    pub fn record(&self, trace_id: String, universe: Arc<CqueryUniverse>, keep: usize) {
+        let mut snapshots = self.snapshots.lock();
+        snapshots.push_back((trace_id, universe));
+        while snapshots.len() > keep {
+            snapshots.pop_front();
+        }
+    }
+
+    /// The graph retained for the invocation `trace_id`.
+    pub fn get(&self, trace_id: &str) -> Option<Arc<CqueryUniverse>> {
+        self.snapshots
+            .lock()
+            .iter()
+            .rev()
+            .find(|(id, _)| id == trace_id)
+            .map(|(_, universe)| universe.dupe())
+    }
+}
+
+pub trait HasGraphSnapshots {
+    fn set_graph_snapshots(&mut self, snapshots: Arc<GraphSnapshots>);
+
+    fn get_graph_snapshots(&self) -> Arc<GraphSnapshots>;
+}
+
+impl HasGraphSnapshots for UserComputationData {
+    fn set_graph_snapshots(&mut self, snapshots: Arc<GraphSnapshots>) {
+        self.data.set(snapshots);
+    }
+
+    fn get_graph_snapshots(&self) -> Arc<GraphSnapshots> {
+        self.data
+            .get::<Arc<GraphSnapshots>>()
+            .expect("Graph snapshots should be set")
+            .dupe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_query::query::syntax::simple::eval::set::TargetSet;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_record_evicts_oldest() {
+        let snapshots = GraphSnapshots::default();
+        let universe = || Arc::new(CqueryUniverse::build(&TargetSet::new()).unwrap());
+        snapshots.record("a".to_owned(), universe(), 2);
+        snapshots.record("b".to_owned(), universe(), 2);
+        assert!(snapshots.get("a").is_some());
+        snapshots.record("c".to_owned(), universe(), 2);
+        assert!(snapshots.get("a").is_none());
+        assert!(snapshots.get("b").is_some());
+        assert!(snapshots.get("c").is_some());
+        assert!(snapshots.get("d").is_none());
+    }
+}
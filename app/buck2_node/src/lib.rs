@@ -16,7 +16,10 @@ pub mod call_stack;
 pub mod cfg_constructor;
 pub mod configuration;
 pub mod configured_universe;
+pub mod deprecation;
 pub mod execution;
+pub mod feature_flags;
+pub mod graph_snapshots;
 pub mod load_patterns;
 pub mod metadata;
 pub mod nodes;
@@ -31,6 +31,7 @@ use futures::Stream;
 use futures::StreamExt;
 use itertools::Itertools;
 
+use crate::deprecation::report_deprecations;
 use crate::nodes::eval_result::EvaluationResult;
 use crate::nodes::frontend::TargetGraphCalculation;
 use crate::nodes::unconfigured::TargetNode;
@@ -263,17 +264,22 @@ pub async fn load_patterns<T: PatternType>(
     parsed_patterns: Vec<ParsedPattern<T>>,
     skip_missing_targets: MissingTargetBehavior,
 ) -> anyhow::Result<LoadedPatterns<T>> {
-    ctx.with_linear_recompute(|ctx| async move {
-        let (spec, mut load_package_futs) =
-            resolve_patterns_and_load_buildfiles(&ctx, parsed_patterns).await?;
-
-        let mut results: BTreeMap<PackageLabel, buck2_error::Result<Arc<EvaluationResult>>> =
-            BTreeMap::new();
-        while let Some((pkg, load_res)) = load_package_futs.next().await {
-            results.insert(pkg, load_res.map_err(buck2_error::Error::from));
-        }
+    let loaded = ctx
+        .with_linear_recompute(|ctx| async move {
+            let (spec, mut load_package_futs) =
+                resolve_patterns_and_load_buildfiles(&ctx, parsed_patterns).await?;
+
+            let mut results: BTreeMap<PackageLabel, buck2_error::Result<Arc<EvaluationResult>>> =
+                BTreeMap::new();
+            while let Some((pkg, load_res)) = load_package_futs.next().await {
+                results.insert(pkg, load_res.map_err(buck2_error::Error::from));
+            }
 
-        apply_spec(spec, results, skip_missing_targets)
-    })
-    .await
+            apply_spec(spec, results, skip_missing_targets)
+        })
+        .await?;
+
+    // Packages which failed to load are reported by the callers.
+    report_deprecations(loaded.iter_loaded_targets().filter_map(|t| t.ok()));
+    Ok(loaded)
 }
@@ -58,6 +58,7 @@ use crate::attrs::internal::TESTS_ATTRIBUTE_FIELD;
 use crate::configuration::resolved::ConfigurationSettingKey;
 use crate::configuration::resolved::ResolvedConfiguration;
 use crate::configuration::resolved::ResolvedConfigurationSettings;
+use crate::feature_flags::FeatureFlags;
 use crate::nodes::attributes::DEPS;
 use crate::nodes::attributes::EXECUTION_PLATFORM;
 use crate::nodes::attributes::ONCALL;
@@ -358,6 +359,10 @@ impl ConfiguredTargetNode {
         self.as_ref().inputs()
     }
 
+    pub fn selected_keys(&self) -> anyhow::Result<Vec<(&str, &ConfigurationSettingKey)>> {
+        self.as_ref().selected_keys()
+    }
+
     #[inline]
     pub fn queries(
         &self,
@@ -642,6 +647,10 @@ impl<'a> ConfiguredTargetNodeRef<'a> {
         self.0.get().target_node.oncall()
     }
 
+    pub fn feature_flags(self) -> &'a FeatureFlags {
+        self.0.get().target_node.feature_flags()
+    }
+
     pub fn special_attrs(self) -> impl Iterator<Item = (&'a str, ConfiguredAttr)> {
         let typ_attr = ConfiguredAttr::String(StringLiteral(self.rule_type().name().into()));
         let deps_attr = ConfiguredAttr::List(
@@ -703,6 +712,21 @@ impl<'a> ConfiguredTargetNodeRef<'a> {
         })
     }
 
+    /// Keys of the `select()` branches taken when configuring the attributes of this node, with
+    /// the name of the attribute containing the `select()`.
+    pub fn selected_keys(self) -> anyhow::Result<Vec<(&'a str, &'a ConfigurationSettingKey)>> {
+        let ctx = self.attr_configuration_context();
+        let mut res = Vec::new();
+        for attr in self.0.get().target_node.attrs(AttrInspectOptions::All) {
+            let mut keys = Vec::new();
+            attr.value.selected_keys(&ctx, &mut keys)?;
+            res.extend(keys.into_iter().map(|k| (attr.name, k)));
+        }
+        res.sort();
+        res.dedup();
+        Ok(res)
+    }
+
     pub fn inputs(self) -> impl Iterator<Item = CellPath> + 'a {
         struct InputsCollector {
             inputs: Vec<CellPath>,
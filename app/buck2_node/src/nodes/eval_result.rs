@@ -195,9 +195,12 @@ impl EvaluationResult {
         Option<MissingTargets>,
     ) {
         match spec {
-            PackageSpec::All => {
+            spec @ (PackageSpec::All | PackageSpec::AllExcept(_)) => {
                 let mut label_to_node = BTreeMap::new();
                 for target_info in self.targets().values() {
+                    if spec.excludes(target_info.label().name()) {
+                        continue;
+                    }
                     label_to_node.insert(
                         (target_info.label().name().to_owned(), T::default()),
                         target_info.to_owned(),
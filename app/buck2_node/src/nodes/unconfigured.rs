@@ -39,6 +39,8 @@ use crate::attrs::traversal::CoercedAttrTraversal;
 use crate::attrs::values::AttrValues;
 use crate::call_stack::StarlarkCallStack;
 use crate::configuration::resolved::ConfigurationSettingKey;
+use crate::deprecation::Deprecation;
+use crate::feature_flags::FeatureFlags;
 use crate::metadata::map::MetadataMap;
 use crate::nodes::attributes::CONFIGURATION_DEPS;
 use crate::nodes::attributes::DEPS;
@@ -121,6 +123,10 @@ impl TargetNodeData {
         self.package.oncall.as_ref().map(|x| x.as_str())
     }
 
+    pub fn feature_flags(&self) -> &FeatureFlags {
+        &self.package.feature_flags
+    }
+
     pub fn call_stack(&self) -> Option<String> {
         self.call_stack.as_ref().map(|s| s.to_string())
     }
@@ -528,6 +534,10 @@ impl<'a> TargetNodeRef<'a> {
         &self.0.get().rule.uses_plugins
     }
 
+    pub fn rule_deprecation(self) -> Option<&'a Deprecation> {
+        self.0.get().rule.deprecation.as_ref()
+    }
+
     pub fn inputs(self) -> impl Iterator<Item = CellPath> + 'a {
         struct InputsCollector {
             inputs: Vec<CellPath>,
@@ -666,10 +676,12 @@ pub mod testing {
                     rule_kind: RuleKind::Normal,
                     cfg: None,
                     uses_plugins: Vec::new(),
+                    deprecation: None,
                 }),
                 Arc::new(Package {
                     buildfile_path,
                     oncall: None,
+                    feature_flags: FeatureFlags::default(),
                 }),
                 label,
                 attributes,
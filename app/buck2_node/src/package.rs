@@ -12,6 +12,7 @@ use std::sync::Arc;
 use allocative::Allocative;
 use buck2_core::build_file_path::BuildFilePath;
 
+use crate::feature_flags::FeatureFlags;
 use crate::oncall::Oncall;
 
 /// Package-specific data for `TargetNode`.
@@ -24,4 +25,6 @@ pub struct Package {
     pub buildfile_path: Arc<BuildFilePath>,
     /// The oncall attribute, if set
     pub oncall: Option<Oncall>,
+    /// Feature flags set by the `PACKAGE` files of the package and its parents.
+    pub feature_flags: FeatureFlags,
 }
@@ -22,6 +22,30 @@ pub trait PackageValuesCalculation: Send + Sync + 'static {
         ctx: &mut DiceComputations<'_>,
         package: PackageLabel,
     ) -> anyhow::Result<SmallMap<MetadataKey, serde_json::Value>>;
+
+    /// The `PACKAGE` files applying to `package`, starting from the project root,
+    /// with the values each of them writes.
+    async fn package_values_provenance(
+        &self,
+        ctx: &mut DiceComputations<'_>,
+        package: PackageLabel,
+    ) -> anyhow::Result<Vec<PackageFileValues>>;
+}
+
+/// Package values written by one `PACKAGE` file.
+#[derive(Debug, serde::Serialize)]
+pub struct PackageFileValues {
+    /// Path of the `PACKAGE` file, like `root//foo/PACKAGE`.
+    pub path: String,
+    /// Values set by this file. Values set by parent files and not overwritten are not included.
+    pub values: SmallMap<MetadataKey, WrittenPackageValue>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct WrittenPackageValue {
+    pub value: serde_json::Value,
+    /// The key was already set by a parent file, and this file replaced it with `overwrite = True`.
+    pub overwrite: bool,
 }
 
 pub static PACKAGE_VALUES_CALCULATION: LateBinding<&'static dyn PackageValuesCalculation> =
@@ -14,6 +14,7 @@ use buck2_core::configuration::transition::id::TransitionId;
 use buck2_core::plugins::PluginKind;
 
 use crate::attrs::spec::AttributeSpec;
+use crate::deprecation::Deprecation;
 use crate::nodes::unconfigured::RuleKind;
 use crate::rule_type::RuleType;
 
@@ -31,4 +32,6 @@ pub struct Rule {
     pub cfg: Option<Arc<TransitionId>>,
     /// The plugin kinds that are used by the target
     pub uses_plugins: Vec<PluginKind>,
+    /// Set if the rule is deprecated, uses are reported as warnings.
+    pub deprecation: Option<Deprecation>,
 }
@@ -13,10 +13,12 @@ use buck2_core::configuration::config_setting::ConfigSettingData;
 use buck2_core::configuration::constraints::ConstraintKey;
 use buck2_core::configuration::constraints::ConstraintValue;
 use buck2_node::attrs::attr_type::bool::BoolLiteral;
+use buck2_node::attrs::attr_type::list::ListLiteral;
 use buck2_node::attrs::attr_type::string::StringLiteral;
 use buck2_node::attrs::coerced_attr::CoercedAttr;
 use buck2_node::attrs::coerced_attr::CoercedSelector;
 use buck2_node::attrs::fmt_context::AttrFmtContext;
+use buck2_node::attrs::testing::configuration_ctx;
 use buck2_node::configuration::resolved::ConfigurationSettingKey;
 use buck2_util::arc_str::ArcSlice;
 use buck2_util::arc_str::ArcStr;
@@ -215,3 +217,34 @@ fn test_to_json_selector() {
         .to_string()
     );
 }
+
+#[test]
+fn test_selected_keys() {
+    let select = |keys: &[&str]| {
+        CoercedAttr::Selector(Box::new(
+            CoercedSelector::new(
+                ArcSlice::from_iter(keys.iter().map(|k| {
+                    (
+                        ConfigurationSettingKey::testing_parse(k),
+                        CoercedAttr::Bool(BoolLiteral(true)),
+                    )
+                })),
+                Some(CoercedAttr::Bool(BoolLiteral(false))),
+            )
+            .unwrap(),
+        ))
+    };
+    let attr = CoercedAttr::List(ListLiteral(ArcSlice::new([
+        select(&["root//some:config", "root//other:config"]),
+        select(&["root//some:config"]),
+    ])));
+
+    let mut keys = Vec::new();
+    attr.selected_keys(&configuration_ctx(), &mut keys).unwrap();
+    assert_eq!(
+        vec![&ConfigurationSettingKey::testing_parse(
+            "root//other:config"
+        )],
+        keys
+    );
+}
@@ -10,8 +10,12 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use buck2_build_api::analysis::calculation::RuleAnalysisCalculation;
+use buck2_build_api::interpreter::rule_defs::provider::collection::FrozenProviderCollectionValue;
 use buck2_core::cells::cell_path::CellPath;
 use buck2_core::configuration::compatibility::MaybeCompatible;
+use buck2_core::provider::label::ConfiguredProvidersLabel;
+use buck2_core::provider::label::ProvidersName;
 use buck2_core::target::configured_target_label::ConfiguredTargetLabel;
 use buck2_core::target::label::label::TargetLabel;
 use buck2_error::BuckErrorContext;
@@ -34,8 +38,10 @@ use buck2_query::query::syntax::simple::functions::HasModuleDescription;
 use buck2_query::query::traversal::async_depth_first_postorder_traversal;
 use buck2_query::query::traversal::async_depth_limited_traversal;
 use dice::DiceComputations;
+use dupe::Dupe;
 use tracing::warn;
 
+use crate::cquery::functions::CqueryFunctions;
 use crate::uquery::environment::allbuildfiles;
 use crate::uquery::environment::rbuildfiles;
 use crate::uquery::environment::QueryLiterals;
@@ -87,7 +93,10 @@ impl<'c> CqueryEnvironment<'c> {
     pub(crate) fn describe() -> QueryEnvironmentDescription {
         QueryEnvironmentDescription {
             name: "Cquery Environment".to_owned(),
-            mods: vec![DefaultQueryFunctionsModule::<Self>::describe()],
+            mods: vec![
+                DefaultQueryFunctionsModule::<Self>::describe(),
+                CqueryFunctions::describe(),
+            ],
         }
     }
 
@@ -95,6 +104,11 @@ impl<'c> CqueryEnvironment<'c> {
         &self,
         label: &ConfiguredTargetLabel,
     ) -> anyhow::Result<ConfiguredTargetNode> {
+        // Nodes of the universe are the same as those computed by the delegate, except
+        // when querying a graph retained from an earlier build, where they must be used.
+        if let Some(node) = self.universe.as_ref().and_then(|u| u.get_node(label)) {
+            return Ok(node);
+        }
         self.delegate.get_node_for_configured_target(label).await
     }
 
@@ -107,6 +121,18 @@ impl<'c> CqueryEnvironment<'c> {
             .await
     }
 
+    /// The providers produced by the analysis of `target`, or `None` if it is incompatible.
+    pub(crate) async fn get_providers(
+        &self,
+        target: &ConfiguredTargetNode,
+    ) -> anyhow::Result<Option<FrozenProviderCollectionValue>> {
+        let label = ConfiguredProvidersLabel::new(target.label().dupe(), ProvidersName::Default);
+        match self.delegate.ctx().get_providers(&label).await? {
+            MaybeCompatible::Compatible(providers) => Ok(Some(providers)),
+            MaybeCompatible::Incompatible(_) => Ok(None),
+        }
+    }
+
     fn owner_correct(&self, path: &CellPath) -> anyhow::Result<Vec<ConfiguredTargetNode>> {
         let universe = self
             .universe
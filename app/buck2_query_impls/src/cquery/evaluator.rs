@@ -16,7 +16,6 @@ use buck2_events::dispatch::console_message;
 use buck2_node::configured_universe::CqueryUniverse;
 use buck2_node::nodes::configured::ConfiguredTargetNode;
 use buck2_query::query::syntax::simple::eval::values::QueryEvaluationResult;
-use buck2_query::query::syntax::simple::functions::DefaultQueryFunctionsModule;
 use dice::DiceComputations;
 use dupe::Dupe;
 use futures::stream::FuturesUnordered;
@@ -25,6 +24,7 @@ use gazebo::prelude::*;
 
 use crate::analysis::evaluator::eval_query;
 use crate::cquery::environment::CqueryEnvironment;
+use crate::cquery::functions::cquery_functions;
 use crate::dice::DiceQueryData;
 use crate::dice::DiceQueryDelegate;
 use crate::uquery::environment::PreresolvedQueryLiterals;
@@ -37,17 +37,9 @@ pub(crate) async fn eval_cquery(
     query_args: &[String],
     target_universe: Option<&[String]>,
 ) -> anyhow::Result<QueryEvaluationResult<ConfiguredTargetNode>> {
-    let dispatcher = dice_query_delegate
-        .ctx()
-        .per_transaction_data()
-        .get_dispatcher()
-        .dupe();
-    let functions = DefaultQueryFunctionsModule::new();
-    let dice_query_delegate = &dice_query_delegate;
-
     let target_universe = match target_universe {
-        None => None,
-        Some(target_universe) => Some(Arc::new(
+        None => TargetUniverse::FromLiterals,
+        Some(target_universe) => TargetUniverse::Given(Arc::new(
             build_cquery_universe_from_literals(
                 target_universe,
                 dice_query_delegate.query_data(),
@@ -57,6 +49,48 @@ pub(crate) async fn eval_cquery(
         )),
     };
 
+    eval_cquery_with_universe(dice_query_delegate, query, query_args, target_universe).await
+}
+
+/// Evaluate a cquery against the configured graph retained from an earlier build.
+pub(crate) async fn eval_cquery_in_snapshot(
+    dice_query_delegate: DiceQueryDelegate<'_, '_>,
+    query: &str,
+    query_args: &[String],
+    snapshot: Arc<CqueryUniverse>,
+) -> anyhow::Result<QueryEvaluationResult<ConfiguredTargetNode>> {
+    eval_cquery_with_universe(
+        dice_query_delegate,
+        query,
+        query_args,
+        TargetUniverse::Snapshot(snapshot),
+    )
+    .await
+}
+
+enum TargetUniverse {
+    /// Built from the literals of the query.
+    FromLiterals,
+    /// Built from `--target-universe`, literals are resolved against the current packages.
+    Given(Arc<CqueryUniverse>),
+    /// Retained from an earlier build. Literals are resolved against its targets only, since
+    /// the packages may have changed since.
+    Snapshot(Arc<CqueryUniverse>),
+}
+
+async fn eval_cquery_with_universe(
+    dice_query_delegate: DiceQueryDelegate<'_, '_>,
+    query: &str,
+    query_args: &[String],
+    target_universe: TargetUniverse,
+) -> anyhow::Result<QueryEvaluationResult<ConfiguredTargetNode>> {
+    let dispatcher = dice_query_delegate
+        .ctx()
+        .per_transaction_data()
+        .get_dispatcher()
+        .dupe();
+    let functions = cquery_functions();
+    let dice_query_delegate = &dice_query_delegate;
     let target_universe = &target_universe;
 
     eval_query(
@@ -66,7 +100,7 @@ pub(crate) async fn eval_cquery(
         query_args,
         |literals| async move {
             let (resolved_literals, universe) = match target_universe {
-                None => {
+                TargetUniverse::FromLiterals => {
                     if literals.is_empty() {
                         console_message(
                         "Query has no target literals and `--target-universe` is not specified.\n\
@@ -90,9 +124,16 @@ pub(crate) async fn eval_cquery(
                         Arc::new(universe),
                     )
                 }
-                Some(universe) => (
-                    resolve_literals_in_universe(&dice_query_delegate, &literals, &universe)
-                        .await?,
+                TargetUniverse::Given(universe) => (
+                    resolve_literals_in_universe(&dice_query_delegate, &literals, universe).await?,
+                    universe.dupe(),
+                ),
+                TargetUniverse::Snapshot(universe) => (
+                    resolve_literals_in_snapshot(
+                        dice_query_delegate.query_data(),
+                        &literals,
+                        universe,
+                    ),
                     universe.dupe(),
                 ),
             };
@@ -168,3 +209,23 @@ async fn resolve_literals_in_universe(
     let resolved = resolution_futs.collect().await;
     Ok(PreresolvedQueryLiterals::new(resolved))
 }
+
+/// Resolve the literals against the targets of `snapshot` only: the packages they were loaded
+/// from may have changed or been deleted since it was taken.
+fn resolve_literals_in_snapshot(
+    query_data: &DiceQueryData,
+    literals: &[String],
+    snapshot: &CqueryUniverse,
+) -> PreresolvedQueryLiterals<ConfiguredTargetNode> {
+    let resolved = literals
+        .iter()
+        .map(|lit| {
+            let result = query_data
+                .literal_parser()
+                .parse_target_pattern(lit)
+                .map(|pattern| snapshot.get_matching(&pattern));
+            (lit.to_owned(), result.map_err(buck2_error::Error::from))
+        })
+        .collect();
+    PreresolvedQueryLiterals::new(resolved)
+}
@@ -0,0 +1,213 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::fmt;
+use std::fmt::Debug;
+use std::future::Future;
+use std::marker::PhantomData;
+
+use buck2_node::nodes::configured::ConfiguredTargetNode;
+use buck2_query::query::syntax::simple::eval::error::QueryError;
+use buck2_query::query::syntax::simple::eval::set::TargetSet;
+use buck2_query::query::syntax::simple::eval::values::QueryValue;
+use buck2_query::query::syntax::simple::functions::helpers::QueryBinaryOp;
+use buck2_query::query::syntax::simple::functions::helpers::QueryFunction;
+use buck2_query::query::syntax::simple::functions::DefaultQueryFunctionsModule;
+use buck2_query::query::syntax::simple::functions::QueryFunctions;
+use buck2_query::query_module;
+use buck2_query_parser::BinaryOp;
+use buck2_util::future::try_join_all;
+use dupe::Dupe;
+
+use crate::cquery::environment::CqueryEnvironment;
+
+pub(crate) fn cquery_functions<'a>() -> impl QueryFunctions<Env = CqueryEnvironment<'a>> {
+    struct Functions<'a> {
+        defaults: DefaultQueryFunctionsModule<CqueryEnvironment<'a>>,
+        extra_functions: CqueryFunctions<'a>,
+    }
+
+    impl Debug for Functions<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Functions").finish_non_exhaustive()
+        }
+    }
+
+    impl<'a> QueryFunctions for Functions<'a> {
+        type Env = CqueryEnvironment<'a>;
+
+        fn get(&self, name: &str) -> Option<&dyn QueryFunction<CqueryEnvironment<'a>>> {
+            if let Some(v) = self.extra_functions.get(name) {
+                Some(v)
+            } else {
+                self.defaults.get(name)
+            }
+        }
+
+        fn get_op(&self, op: BinaryOp) -> Option<&dyn QueryBinaryOp<CqueryEnvironment<'a>>> {
+            if let Some(v) = self.extra_functions.get_op(op) {
+                Some(v)
+            } else {
+                self.defaults.get_op(op)
+            }
+        }
+    }
+
+    Functions {
+        defaults: DefaultQueryFunctionsModule::new(),
+        extra_functions: CqueryFunctions(PhantomData),
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct CqueryFunctions<'a>(pub(crate) PhantomData<&'a ()>);
+
+#[query_module(CqueryEnvironment<'a>)]
+impl<'a> CqueryFunctions<'a> {
+    /// Obtain the configuration dependencies of the given targets: the `config_setting`,
+    /// `constraint_value` and similar targets referenced by their `select()` keys and
+    /// configuration attributes, whether or not the corresponding branch was taken.
+    ///
+    /// Example:
+    /// `buck2 cquery "config_deps(//foo:bar)"`
+    pub(crate) async fn config_deps(
+        &self,
+        targets: TargetSet<ConfiguredTargetNode>,
+    ) -> Result<QueryValue<ConfiguredTargetNode>, QueryError> {
+        let mut res = TargetSet::new();
+        for target in &targets {
+            for dep in target.configuration_deps() {
+                res.insert(dep.dupe());
+            }
+        }
+        Ok(res.into())
+    }
+
+    /// Obtain the configuration targets of the `select()` branches which were taken when
+    /// configuring the given targets. A `default` branch has no key and contributes nothing.
+    ///
+    /// Example:
+    /// `buck2 cquery "select_keys(//foo:bar)"`
+    ///
+    /// To check whether a constraint drives any select in a graph:
+    /// `buck2 cquery "select_keys(deps(//foo:bar)) intersect //constraints:linux"`
+    pub(crate) async fn select_keys(
+        &self,
+        targets: TargetSet<ConfiguredTargetNode>,
+    ) -> Result<QueryValue<ConfiguredTargetNode>, QueryError> {
+        let mut res = TargetSet::new();
+        for target in &targets {
+            let keys = target.selected_keys()?;
+            for dep in target.configuration_deps() {
+                if keys.iter().any(|(_, k)| &k.0 == dep.label().unconfigured()) {
+                    res.insert(dep.dupe());
+                }
+            }
+        }
+        Ok(res.into())
+    }
+
+    /// Filter the targets by whether their analysis produces the provider named `provider`
+    /// (e.g. `RunInfo`). Incompatible targets are dropped. The targets are analyzed, so this is
+    /// more expensive than `kind`, but does not rely on rule naming conventions.
+    ///
+    /// Example, to find all runnable targets under `//services/...`:
+    /// `buck2 cquery "kind_provider(RunInfo, //services/...)"`
+    pub(crate) async fn kind_provider(
+        &self,
+        env: &CqueryEnvironment<'a>,
+        provider: String,
+        targets: TargetSet<ConfiguredTargetNode>,
+    ) -> Result<QueryValue<ConfiguredTargetNode>, QueryError> {
+        let res = filter_by_provider(&targets, &provider, |target| async move {
+            let providers = env.get_providers(target).await?;
+            anyhow::Ok(providers.map(|providers| {
+                providers
+                    .provider_collection()
+                    .provider_ids()
+                    .iter()
+                    .map(|id| id.name().to_owned())
+                    .collect()
+            }))
+        })
+        .await?;
+        Ok(res.into())
+    }
+}
+
+/// The targets whose providers, as listed by `provider_names` (`None` for an incompatible
+/// target), include `provider`.
+async fn filter_by_provider<'t, F, Fut>(
+    targets: &'t TargetSet<ConfiguredTargetNode>,
+    provider: &str,
+    provider_names: F,
+) -> anyhow::Result<TargetSet<ConfiguredTargetNode>>
+where
+    F: Fn(&'t ConfiguredTargetNode) -> Fut,
+    Fut: Future<Output = anyhow::Result<Option<Vec<String>>>>,
+{
+    let matches = try_join_all(targets.iter().map(provider_names)).await?;
+    Ok(targets
+        .iter()
+        .zip(matches)
+        .filter_map(|(target, names)| {
+            names
+                .map_or(false, |names| names.iter().any(|name| name == provider))
+                .then(|| target.dupe())
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_core::configuration::data::ConfigurationData;
+    use buck2_core::execution_types::execution::ExecutionPlatformResolution;
+    use buck2_core::target::configured_target_label::ConfiguredTargetLabel;
+
+    use super::*;
+
+    fn node(label: &str) -> ConfiguredTargetNode {
+        ConfiguredTargetNode::testing_new(
+            ConfiguredTargetLabel::testing_parse(label, ConfigurationData::testing_new()),
+            "foo_rule",
+            ExecutionPlatformResolution::unspecified(),
+            vec![],
+            vec![],
+        )
+    }
+
+    #[tokio::test]
+    async fn test_filter_by_provider() -> anyhow::Result<()> {
+        let targets: TargetSet<_> = [
+            "cell//pkg:runnable",
+            "cell//pkg:library",
+            "cell//pkg:incompatible",
+        ]
+        .into_iter()
+        .map(node)
+        .collect();
+
+        let res = filter_by_provider(&targets, "RunInfo", |target| async move {
+            Ok(match target.label().name().as_str() {
+                "runnable" => Some(vec!["DefaultInfo".to_owned(), "RunInfo".to_owned()]),
+                "library" => Some(vec!["DefaultInfo".to_owned()]),
+                _ => None,
+            })
+        })
+        .await?;
+
+        assert_eq!(
+            vec!["cell//pkg:runnable"],
+            res.iter()
+                .map(|t| t.label().unconfigured().to_string())
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+}
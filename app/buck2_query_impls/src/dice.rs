@@ -81,7 +81,7 @@ pub(crate) struct LiteralParser {
 
 impl LiteralParser {
     // We allow provider names and flavors in the value and it gets stripped out for the result as queries operate on the target graphs.
-    fn parse_target_pattern(
+    pub(crate) fn parse_target_pattern(
         &self,
         value: &str,
     ) -> anyhow::Result<ParsedPattern<TargetPatternExtra>> {
@@ -7,6 +7,8 @@
  * of this source tree.
  */
 
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use buck2_build_api::actions::query::ActionQueryNode;
 use buck2_build_api::query::oneshot::QueryFrontend;
@@ -22,9 +24,11 @@ use dice::DiceComputations;
 
 use crate::aquery::evaluator::get_aquery_evaluator;
 use crate::cquery::evaluator::eval_cquery;
+use crate::cquery::evaluator::eval_cquery_in_snapshot;
 use crate::cquery::evaluator::preresolve_literals_and_build_universe;
 use crate::dice::get_dice_query_delegate;
 use crate::uquery::evaluator::get_uquery_evaluator;
+use crate::user_functions::expand_user_query_functions;
 
 struct QueryFrontendImpl;
 
@@ -41,6 +45,7 @@ impl QueryFrontend for QueryFrontendImpl {
         query: &str,
         query_args: &[String],
     ) -> anyhow::Result<QueryEvaluationResult<TargetNode>> {
+        let query = &expand_user_query_functions(ctx, query).await?;
         ctx.with_linear_recompute(|ctx| async move {
             let evaluator = get_uquery_evaluator(&ctx, working_dir).await?;
             evaluator.eval_query(query, query_args).await
@@ -57,6 +62,7 @@ impl QueryFrontend for QueryFrontendImpl {
         global_cfg_options: GlobalCfgOptions,
         target_universe: Option<&[String]>,
     ) -> anyhow::Result<QueryEvaluationResult<ConfiguredTargetNode>> {
+        let query = &expand_user_query_functions(ctx, query).await?;
         ctx.with_linear_recompute(|ctx| async move {
             let dice_query_delegate =
                 get_dice_query_delegate(&ctx, working_dir, global_cfg_options).await?;
@@ -78,6 +84,24 @@ impl QueryFrontend for QueryFrontendImpl {
         .await
     }
 
+    async fn eval_cquery_in_snapshot(
+        &self,
+        ctx: &mut DiceComputations<'_>,
+        working_dir: &ProjectRelativePath,
+        query: &str,
+        query_args: &[String],
+        global_cfg_options: GlobalCfgOptions,
+        snapshot: Arc<CqueryUniverse>,
+    ) -> anyhow::Result<QueryEvaluationResult<ConfiguredTargetNode>> {
+        let query = &expand_user_query_functions(ctx, query).await?;
+        ctx.with_linear_recompute(|ctx| async move {
+            let dice_query_delegate =
+                get_dice_query_delegate(&ctx, working_dir, global_cfg_options).await?;
+            eval_cquery_in_snapshot(dice_query_delegate, query, query_args, snapshot).await
+        })
+        .await
+    }
+
     async fn eval_aquery(
         &self,
         ctx: &mut DiceComputations<'_>,
@@ -86,6 +110,7 @@ impl QueryFrontend for QueryFrontendImpl {
         query_args: &[String],
         global_cfg_options: GlobalCfgOptions,
     ) -> anyhow::Result<QueryEvaluationResult<ActionQueryNode>> {
+        let query = &expand_user_query_functions(ctx, query).await?;
         ctx.with_linear_recompute(|ctx| async move {
             let evaluator = get_aquery_evaluator(&ctx, working_dir, global_cfg_options).await?;
             evaluator.eval_query(query, query_args).await
@@ -19,6 +19,7 @@ mod description;
 pub(crate) mod dice;
 pub(crate) mod frontend;
 pub(crate) mod uquery;
+mod user_functions;
 
 pub fn init_late_bindings() {
     static ONCE: Once = Once::new();
@@ -0,0 +1,332 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Query functions defined by users.
+//!
+//! `buck2.query_functions` names a bzl file defining a `QUERY_FUNCTIONS` dict, which maps a
+//! signature like `"tests_of(target)"` to a query expression referring to the parameters as
+//! `$target`. Calls to these functions in query expressions are expanded before evaluation, so
+//! they can use any builtin function and other user defined functions.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use buck2_common::dice::cells::HasCellResolver;
+use buck2_common::legacy_configs::dice::HasLegacyConfigs;
+use buck2_common::legacy_configs::key::BuckconfigKeyRef;
+use buck2_common::legacy_configs::view::LegacyBuckConfigView;
+use buck2_core::bzl::ImportPath;
+use buck2_core::cells::build_file_cell::BuildFileCell;
+use buck2_interpreter::load_module::InterpreterCalculation;
+use buck2_interpreter::parse_import::parse_bzl_path_with_config;
+use buck2_interpreter::parse_import::ParseImportOptions;
+use buck2_interpreter::parse_import::RelativeImports;
+use buck2_interpreter::paths::module::StarlarkModulePath;
+use buck2_query_parser::parse_expr;
+use buck2_query_parser::span::Span;
+use buck2_query_parser::Expr;
+use buck2_query_parser::SpannedExpr;
+use dice::DiceComputations;
+use starlark::values::dict::DictRef;
+
+pub(crate) const QUERY_FUNCTIONS_BUCKCONFIG: BuckconfigKeyRef = BuckconfigKeyRef {
+    section: "buck2",
+    property: "query_functions",
+};
+
+const QUERY_FUNCTIONS_GLOBAL: &str = "QUERY_FUNCTIONS";
+
+/// Guards against functions calling themselves.
+const MAX_EXPANSION_DEPTH: usize = 32;
+
+#[derive(Debug, buck2_error::Error)]
+#[buck2(input)]
+enum UserQueryFunctionError {
+    #[error("`{0}` must define `{}`", QUERY_FUNCTIONS_GLOBAL)]
+    MissingGlobal(ImportPath),
+    #[error(
+        "`{}` in `{0}` must be a dict from function signatures to query expressions",
+        QUERY_FUNCTIONS_GLOBAL
+    )]
+    NotADict(ImportPath),
+    #[error("Invalid query function signature `{0}`, expected `name(param, ...)`")]
+    InvalidSignature(String),
+    #[error("Query function `{0}` expects {1} argument(s), got {2}")]
+    WrongArgCount(String, usize, usize),
+    #[error(
+        "Query functions are nested more than {} levels deep, `{0}` is probably recursive",
+        MAX_EXPANSION_DEPTH
+    )]
+    TooDeep(String),
+}
+
+#[derive(Debug)]
+struct UserQueryFunction {
+    params: Vec<String>,
+    body: String,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct UserQueryFunctions(HashMap<String, UserQueryFunction>);
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_signature(signature: &str) -> anyhow::Result<(String, Vec<String>)> {
+    let invalid = || UserQueryFunctionError::InvalidSignature(signature.to_owned());
+    let (name, rest) = signature.split_once('(').ok_or_else(invalid)?;
+    let params = rest.trim_end().strip_suffix(')').ok_or_else(invalid)?;
+    let name = name.trim();
+    if !is_identifier(name) {
+        return Err(invalid().into());
+    }
+    let params: Vec<String> = if params.trim().is_empty() {
+        Vec::new()
+    } else {
+        params.split(',').map(|p| p.trim().to_owned()).collect()
+    };
+    if !params.iter().all(|p| is_identifier(p)) {
+        return Err(invalid().into());
+    }
+    Ok((name.to_owned(), params))
+}
+
+impl UserQueryFunctions {
+    pub(crate) fn new<'a>(
+        functions: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> anyhow::Result<UserQueryFunctions> {
+        let mut res = HashMap::new();
+        for (signature, body) in functions {
+            let (name, params) = parse_signature(signature)?;
+            // Report syntax errors where the function is defined, not where it is used.
+            parse_expr(body)?;
+            res.insert(
+                name,
+                UserQueryFunction {
+                    params,
+                    body: body.to_owned(),
+                },
+            );
+        }
+        Ok(UserQueryFunctions(res))
+    }
+
+    fn calls_user_function(&self, expr: &SpannedExpr) -> bool {
+        match &expr.value {
+            Expr::Function {
+                function_name,
+                args,
+            } => {
+                self.0.contains_key(function_name.fragment())
+                    || args.iter().any(|x| self.calls_user_function(x))
+            }
+            Expr::BinaryOpSequence(left, rest) => {
+                self.calls_user_function(left)
+                    || rest.iter().any(|(_, x)| self.calls_user_function(x))
+            }
+            Expr::String(_) | Expr::Integer(_) | Expr::Set(_) | Expr::FileSet(_) => false,
+        }
+    }
+
+    /// Replace the calls to user defined functions in `query` with their bodies.
+    pub(crate) fn expand(&self, query: &str) -> anyhow::Result<String> {
+        if self.0.is_empty() {
+            return Ok(query.to_owned());
+        }
+        let expr = parse_expr(query)?;
+        if !self.calls_user_function(&expr) {
+            // Keep the query as written for error messages.
+            return Ok(query.to_owned());
+        }
+        let mut res = String::new();
+        self.render(&expr, &HashMap::new(), 0, &mut res)?;
+        Ok(res)
+    }
+
+    fn render_words(
+        &self,
+        function: &str,
+        words: &[Span],
+        bindings: &HashMap<String, String>,
+        out: &mut String,
+    ) {
+        out.push_str(function);
+        out.push('(');
+        for (i, word) in words.iter().enumerate() {
+            if i != 0 {
+                out.push(' ');
+            }
+            let word = word.fragment();
+            match word.strip_prefix('$').and_then(|p| bindings.get(p)) {
+                Some(arg) => out.push_str(arg),
+                None => out.push_str(word),
+            }
+        }
+        out.push(')');
+    }
+
+    fn render(
+        &self,
+        expr: &SpannedExpr,
+        bindings: &HashMap<String, String>,
+        depth: usize,
+        out: &mut String,
+    ) -> anyhow::Result<()> {
+        match &expr.value {
+            Expr::String(word) => match word.strip_prefix('$').and_then(|p| bindings.get(p)) {
+                Some(arg) => out.push_str(arg),
+                None => write!(out, "{}", expr)?,
+            },
+            Expr::Integer(_) => write!(out, "{}", expr)?,
+            Expr::Function {
+                function_name,
+                args,
+            } => {
+                let name = function_name.fragment();
+                let args = args
+                    .iter()
+                    .map(|arg| {
+                        let mut res = String::new();
+                        self.render(arg, bindings, depth, &mut res)?;
+                        anyhow::Ok(res)
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                match self.0.get(name) {
+                    Some(function) => {
+                        if depth >= MAX_EXPANSION_DEPTH {
+                            return Err(UserQueryFunctionError::TooDeep(name.to_owned()).into());
+                        }
+                        if function.params.len() != args.len() {
+                            return Err(UserQueryFunctionError::WrongArgCount(
+                                name.to_owned(),
+                                function.params.len(),
+                                args.len(),
+                            )
+                            .into());
+                        }
+                        let body = parse_expr(&function.body)?;
+                        let bindings = function.params.iter().cloned().zip(args).collect();
+                        self.render(&body, &bindings, depth + 1, out)?;
+                    }
+                    None => write!(out, "{}({})", name, args.join(", "))?,
+                }
+            }
+            Expr::BinaryOpSequence(left, rest) => {
+                out.push('(');
+                self.render(left, bindings, depth, out)?;
+                for (op, right) in rest {
+                    write!(out, " {} ", op)?;
+                    self.render(right, bindings, depth, out)?;
+                }
+                out.push(')');
+            }
+            Expr::Set(words) => self.render_words("set", words, bindings, out),
+            Expr::FileSet(words) => self.render_words("fileset", words, bindings, out),
+        }
+        Ok(())
+    }
+}
+
+async fn get_user_query_functions(
+    ctx: &mut DiceComputations<'_>,
+) -> anyhow::Result<UserQueryFunctions> {
+    let Some(path) = ctx
+        .get_legacy_root_config_on_dice()
+        .await?
+        .view(ctx)
+        .get(QUERY_FUNCTIONS_BUCKCONFIG)?
+    else {
+        return Ok(UserQueryFunctions::default());
+    };
+
+    let cell_resolver = ctx.get_cell_resolver().await?;
+    let root_cell = cell_resolver.root_cell();
+    let cell_alias_resolver = ctx.get_cell_alias_resolver(root_cell).await?;
+    let import_path = parse_bzl_path_with_config(
+        &cell_alias_resolver,
+        &path,
+        &ParseImportOptions {
+            allow_missing_at_symbol: true,
+            relative_import_option: RelativeImports::Disallow,
+        },
+        BuildFileCell::new(root_cell),
+    )?;
+    let module = ctx
+        .get_loaded_module(StarlarkModulePath::LoadFile(&import_path))
+        .await?;
+    let functions = module
+        .env()
+        .get_option(QUERY_FUNCTIONS_GLOBAL)?
+        .ok_or_else(|| UserQueryFunctionError::MissingGlobal(import_path.clone()))?;
+    let functions = DictRef::from_value(functions.value())
+        .ok_or_else(|| UserQueryFunctionError::NotADict(import_path.clone()))?;
+    let functions = functions
+        .iter()
+        .map(|(k, v)| Some((k.unpack_str()?, v.unpack_str()?)))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| UserQueryFunctionError::NotADict(import_path.clone()))?;
+    UserQueryFunctions::new(functions)
+}
+
+/// Expand the user defined query functions called by `query`.
+pub(crate) async fn expand_user_query_functions(
+    ctx: &mut DiceComputations<'_>,
+    query: &str,
+) -> anyhow::Result<String> {
+    get_user_query_functions(ctx).await?.expand(query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        let functions = UserQueryFunctions::new([
+            ("tests_of(target)", "testsof(rdeps(//..., $target))"),
+            (
+                "libs_in(universe, kind)",
+                "kind($kind, deps($universe)) except tests_of($universe)",
+            ),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            "deps(//foo:bar)",
+            functions.expand("deps(//foo:bar)").unwrap()
+        );
+        assert_eq!(
+            "testsof(rdeps('//...', '//foo:bar'))",
+            functions.expand("tests_of(//foo:bar)").unwrap()
+        );
+        assert_eq!(
+            "(kind('rust_library', deps('//a:b')) - testsof(rdeps('//...', '//a:b')))",
+            functions.expand("libs_in(//a:b, rust_library)").unwrap()
+        );
+        assert!(functions.expand("tests_of(//a:b, //c:d)").is_err());
+    }
+
+    #[test]
+    fn test_recursive() {
+        let functions = UserQueryFunctions::new([("loop(x)", "loop($x)")]).unwrap();
+        assert!(functions.expand("loop(//a:b)").is_err());
+    }
+
+    #[test]
+    fn test_invalid_signature() {
+        assert!(UserQueryFunctions::new([("tests of(x)", "deps($x)")]).is_err());
+        assert!(UserQueryFunctions::new([("tests_of(x", "deps($x)")]).is_err());
+        assert!(UserQueryFunctions::new([("tests_of(x)", "deps($x")]).is_err());
+    }
+}
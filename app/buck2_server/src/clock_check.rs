@@ -0,0 +1,293 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! `buck2 debug clock-check`: detection of clock and mtime anomalies.
+//!
+//! Buck2 compares mtimes to decide whether host facts (e.g. probed toolchains or `pkg-config`
+//! queries) and generated configs are stale, and so do many tools run by local actions. Files with
+//! mtimes in the future, or filesystems which only store whole seconds, make such changes go
+//! unnoticed. A clock skew with remote execution workers makes the timings they report wrong, and
+//! mtimes compared across machines meaningless.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use anyhow::Context;
+use buck2_cli_proto::new_generic::ClockCheckRequest;
+use buck2_cli_proto::new_generic::ClockCheckResponse;
+use buck2_cli_proto::new_generic::ClockCheckWarning;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
+use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_events::dispatch::span_async;
+use buck2_execute::re::clock_skew::remote_execution_clock_skew;
+use buck2_server_ctx::command_end::command_end;
+use buck2_server_ctx::ctx::ServerCommandContextTrait;
+
+use crate::ctx::ServerCommandContext;
+
+/// Mtimes less than this far in the future are tolerated, as the mtime of a file being written
+/// may be ahead of the time it is checked at on network filesystems.
+const FUTURE_MTIME_TOLERANCE: Duration = Duration::from_secs(60);
+const RE_CLOCK_SKEW_TOLERANCE_MS: i64 = 60_000;
+/// Minimum number of files checked to report that they all have whole-second mtimes.
+const MIN_FILES_FOR_COARSE_MTIMES: u64 = 100;
+const MAX_LISTED_FILES: usize = 10;
+const SKIPPED_DIRS: &[&str] = &[".git", ".hg", ".jj", ".sl", "buck-out"];
+
+pub(crate) async fn clock_check_command(
+    context: &ServerCommandContext<'_>,
+    req: ClockCheckRequest,
+) -> anyhow::Result<ClockCheckResponse> {
+    let start_event = buck2_data::CommandStart {
+        metadata: context.request_metadata().await?,
+        data: Some(buck2_data::ClockCheckCommandStart {}.into()),
+    };
+    span_async(start_event, async move {
+        let result = clock_check(context, req)
+            .await
+            .context("Failed to check clocks and mtimes")
+            .map_err(Into::into);
+        let end_event = command_end(&result, buck2_data::ClockCheckCommandEnd {});
+        (result.map_err(Into::into), end_event)
+    })
+    .await
+}
+
+async fn clock_check(
+    context: &ServerCommandContext<'_>,
+    req: ClockCheckRequest,
+) -> anyhow::Result<ClockCheckResponse> {
+    let project_root = context.base_context.project_root.clone();
+    let dirs = if req.paths.is_empty() {
+        vec![project_root.root().to_buf()]
+    } else {
+        req.paths
+            .iter()
+            .map(|p| Ok(project_root.resolve(ProjectRelativePath::new(p)?)))
+            .collect::<anyhow::Result<Vec<_>>>()?
+    };
+    let probe_dir = project_root
+        .resolve(&context.buck_out_dir)
+        .join(ForwardRelativePath::new("tmp")?);
+
+    let mut res = tokio::task::spawn_blocking(move || {
+        let mut scan = MtimeScan::new(SystemTime::now(), req.max_files);
+        for dir in &dirs {
+            scan.scan(dir);
+        }
+        let mut warnings = scan.warnings(project_root.root().as_path());
+        if has_coarse_mtimes(&probe_dir)? {
+            warnings.push(ClockCheckWarning {
+                kind: "coarse_timestamps".to_owned(),
+                message: format!(
+                    "The filesystem of `{}` stores mtimes in whole seconds: changes made within \
+                    the same second as a previous check are not detected by tools comparing \
+                    mtimes. Consider moving the project to a filesystem with finer timestamps.",
+                    probe_dir
+                ),
+            });
+        }
+        anyhow::Ok(ClockCheckResponse {
+            checked_files: scan.checked,
+            warnings,
+        })
+    })
+    .await??;
+
+    if let Some(skew) = remote_execution_clock_skew() {
+        let skew_ms = skew.certain_skew_ms();
+        if skew_ms.abs() > RE_CLOCK_SKEW_TOLERANCE_MS {
+            res.warnings.push(ClockCheckWarning {
+                kind: "re_clock_skew".to_owned(),
+                message: format!(
+                    "The clock of remote execution workers is at least {}s {} the clock of this \
+                    machine (estimated from {} action(s)): timings of remote actions are off, \
+                    and mtimes of files produced remotely cannot be compared to local ones. \
+                    Synchronize the clock of this machine, e.g. with NTP.",
+                    skew_ms.abs() / 1000,
+                    if skew_ms > 0 { "ahead of" } else { "behind" },
+                    skew.samples
+                ),
+            });
+        }
+    }
+
+    Ok(res)
+}
+
+struct MtimeScan {
+    now: SystemTime,
+    max_files: u64,
+    checked: u64,
+    /// Files with whole-second mtimes.
+    whole_seconds: u64,
+    /// Files with mtimes in the future, and how far.
+    future: Vec<(PathBuf, Duration)>,
+}
+
+impl MtimeScan {
+    fn new(now: SystemTime, max_files: u64) -> Self {
+        Self {
+            now,
+            max_files,
+            checked: 0,
+            whole_seconds: 0,
+            future: Vec::new(),
+        }
+    }
+
+    fn scan(&mut self, root: &AbsNormPath) {
+        let mut stack = vec![root.to_buf()];
+        while let Some(dir) = stack.pop() {
+            // Directories which cannot be read are skipped, as they are by the file watcher.
+            let Ok(entries) = fs_util::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                if metadata.is_dir() {
+                    if !SKIPPED_DIRS.iter().any(|d| entry.file_name() == *d) {
+                        stack.push(entry.path());
+                    }
+                } else if metadata.is_file() {
+                    if self.checked >= self.max_files {
+                        return;
+                    }
+                    if let Ok(mtime) = metadata.modified() {
+                        self.check(entry.path().into_path_buf(), mtime);
+                    }
+                }
+            }
+        }
+    }
+
+    fn check(&mut self, path: PathBuf, mtime: SystemTime) {
+        self.checked += 1;
+        if mtime
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(false, |d| d.subsec_nanos() == 0)
+        {
+            self.whole_seconds += 1;
+        }
+        if let Ok(ahead) = mtime.duration_since(self.now) {
+            if ahead > FUTURE_MTIME_TOLERANCE {
+                self.future.push((path, ahead));
+            }
+        }
+    }
+
+    fn warnings(&mut self, project_root: &Path) -> Vec<ClockCheckWarning> {
+        let mut warnings = Vec::new();
+        if !self.future.is_empty() {
+            self.future.sort_by(|a, b| b.1.cmp(&a.1));
+            let mut message = format!(
+                "{} file(s) have an mtime in the future: changes to them are not detected by \
+                tools comparing mtimes until the clock catches up. Touch them (e.g. `touch \
+                <path>`) after checking the clock of this machine, and of the machine they were \
+                copied or extracted from.",
+                self.future.len()
+            );
+            for (path, ahead) in self.future.iter().take(MAX_LISTED_FILES) {
+                let path = path.strip_prefix(project_root).unwrap_or(path);
+                message.push_str(&format!(
+                    "\n  {} ({}s ahead)",
+                    path.display(),
+                    ahead.as_secs()
+                ));
+            }
+            warnings.push(ClockCheckWarning {
+                kind: "future_mtime".to_owned(),
+                message,
+            });
+        }
+        if self.checked >= MIN_FILES_FOR_COARSE_MTIMES && self.whole_seconds == self.checked {
+            warnings.push(ClockCheckWarning {
+                kind: "coarse_timestamps".to_owned(),
+                message: format!(
+                    "All {} files checked have mtimes in whole seconds: their filesystem may not \
+                    store finer timestamps, or they were extracted from an archive which does \
+                    not. Changes made within the same second as a previous check are not \
+                    detected by tools comparing mtimes.",
+                    self.checked
+                ),
+            });
+        }
+        warnings
+    }
+}
+
+/// Whether the filesystem of `dir` only stores mtimes in whole seconds, as observed by writing a
+/// file a few times.
+fn has_coarse_mtimes(dir: &AbsNormPath) -> anyhow::Result<bool> {
+    fs_util::create_dir_all(dir)?;
+    let probe = dir.join(ForwardRelativePath::new(&format!(
+        "clock-check-{}",
+        std::process::id()
+    ))?);
+    let mut coarse = true;
+    for i in 0..3 {
+        fs_util::write(&probe, i.to_string())?;
+        let mtime = fs_util::metadata(&probe)?.modified()?;
+        if mtime.duration_since(SystemTime::UNIX_EPOCH)?.subsec_nanos() != 0 {
+            coarse = false;
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(7));
+    }
+    let _ignored = fs_util::remove_file(&probe);
+    Ok(coarse)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mtime_scan_warnings() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let root = Path::new("/repo");
+        let mut scan = MtimeScan::new(now, 1000);
+        scan.check(root.join("a"), now + Duration::from_secs(3600));
+        scan.check(root.join("b"), now + Duration::from_secs(10));
+        scan.check(root.join("c"), now - Duration::from_millis(1500));
+
+        let warnings = scan.warnings(root);
+        assert_eq!(1, warnings.len());
+        assert_eq!("future_mtime", warnings[0].kind);
+        assert!(warnings[0].message.starts_with("1 file(s)"));
+        assert!(
+            warnings[0]
+                .message
+                .contains(&format!("{} (3600s ahead)", Path::new("a").display()))
+        );
+    }
+
+    #[test]
+    fn test_mtime_scan_whole_seconds() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let mut scan = MtimeScan::new(now, 1000);
+        for i in 0..MIN_FILES_FOR_COARSE_MTIMES {
+            scan.check(
+                PathBuf::from(i.to_string()),
+                now - Duration::from_secs(i + 1),
+            );
+        }
+        let warnings = scan.warnings(Path::new("/"));
+        assert_eq!(
+            vec!["coarse_timestamps"],
+            warnings.iter().map(|w| w.kind.as_str()).collect::<Vec<_>>()
+        );
+    }
+}
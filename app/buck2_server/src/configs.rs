@@ -15,6 +15,8 @@ use buck2_cli_proto::ConfigOverride;
 use buck2_common::legacy_configs::configs::ConfigDiffEntry;
 use buck2_common::legacy_configs::configs::ConfigDiffMetrics;
 use buck2_common::legacy_configs::configs::LegacyConfigCmdArg;
+use buck2_core::cells::instance::CellInstance;
+use buck2_core::cells::CellResolver;
 
 fn config_type_from_i32(value: i32) -> anyhow::Result<ConfigType> {
     ConfigType::from_i32(value).with_context(|| {
@@ -42,6 +44,7 @@ pub(crate) fn get_legacy_config_args<'a, Iter: IntoIterator<Item = &'a ConfigOve
 pub(crate) fn buck_configs(
     new_configs_used: bool,
     metrics: Option<ConfigDiffMetrics>,
+    changed_cells: Vec<String>,
 ) -> buck2_data::BuckConfigs {
     let (config_diff_count, config_diff_size, cell_diff) = match metrics {
         Some(metrics) => (
@@ -57,9 +60,35 @@ pub(crate) fn buck_configs(
         config_diff_count,
         config_diff_size,
         cell_diff,
+        changed_cells,
     }
 }
 
+/// Names of the cells which were added, removed, or moved between `old` and `new`, sorted. Cells
+/// whose aliases or nested cells changed as a consequence are not included.
+pub(crate) fn changed_cells(old: &CellResolver, new: &CellResolver) -> Vec<String> {
+    if old == new {
+        return Vec::new();
+    }
+    let location =
+        |instance: &CellInstance| (instance.path().to_buf(), instance.external().cloned());
+    let old_cells: HashMap<_, _> = old.cells().map(|(name, i)| (name, location(i))).collect();
+    let new_cells: HashMap<_, _> = new.cells().map(|(name, i)| (name, location(i))).collect();
+    let mut changed: Vec<String> = old_cells
+        .iter()
+        .filter(|(name, location)| new_cells.get(name) != Some(location))
+        .map(|(name, _)| name)
+        .chain(
+            new_cells
+                .keys()
+                .filter(|name| !old_cells.contains_key(name)),
+        )
+        .map(|name| name.as_str().to_owned())
+        .collect();
+    changed.sort();
+    changed
+}
+
 fn diff_by_cell(metrics: ConfigDiffMetrics) -> HashMap<String, buck2_data::CellConfigDiff> {
     let mut diff = HashMap::new();
     for (cell, conf) in metrics.diff {
@@ -91,6 +120,7 @@ fn diff_by_cell(metrics: ConfigDiffMetrics) -> HashMap<String, buck2_data::CellC
 mod tests {
     use buck2_common::legacy_configs::configs::CellConfigDiff;
     use buck2_common::legacy_configs::configs::SectionConfigDiff;
+    use buck2_core::cells::cell_root_path::CellRootPathBuf;
     use buck2_core::cells::name::CellName;
     use maplit::hashmap;
     use starlark_map::smallmap;
@@ -99,13 +129,14 @@ mod tests {
 
     #[test]
     fn test_buck_configs_without_metrics() {
-        let buck_configs = buck_configs(true, None);
+        let buck_configs = buck_configs(true, None, Vec::new());
 
         let expected = buck2_data::BuckConfigs {
             new_configs_used: true,
             config_diff_count: None,
             config_diff_size: None,
             cell_diff: HashMap::new(),
+            changed_cells: Vec::new(),
         };
 
         assert_eq!(buck_configs, expected);
@@ -139,7 +170,7 @@ mod tests {
             size_bytes: 10,
             diff_size_exceeded: false,
         };
-        let buck_configs = buck_configs(true, Some(metrics));
+        let buck_configs = buck_configs(true, Some(metrics), Vec::new());
 
         let expected = buck2_data::BuckConfigs {
             new_configs_used: true,
@@ -163,8 +194,37 @@ mod tests {
                     ]
                 }
             ],
+            changed_cells: Vec::new(),
         };
 
         assert_eq!(buck_configs, expected);
     }
+
+    #[test]
+    fn test_changed_cells() {
+        let resolver = |cells: &[(&str, &str)]| {
+            CellResolver::testing_with_names_and_paths(
+                &cells
+                    .iter()
+                    .map(|(name, path)| {
+                        (
+                            CellName::testing_new(name),
+                            CellRootPathBuf::testing_new(path),
+                        )
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        };
+        let old = resolver(&[("root", ""), ("a", "a"), ("b", "b")]);
+
+        assert!(changed_cells(&old, &old).is_empty());
+        assert_eq!(
+            vec!["b", "c"],
+            changed_cells(&old, &resolver(&[("root", ""), ("a", "a"), ("c", "c")]))
+        );
+        assert_eq!(
+            vec!["a"],
+            changed_cells(&old, &resolver(&[("root", ""), ("a", "x/a"), ("b", "b")]))
+        );
+    }
 }
@@ -0,0 +1,56 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use anyhow::Context;
+use buck2_cli_proto::new_generic::CorruptScanRequest;
+use buck2_cli_proto::new_generic::CorruptScanResponse;
+use buck2_events::dispatch::span_async;
+use buck2_server_ctx::command_end::command_end;
+use buck2_server_ctx::ctx::ServerCommandContextTrait;
+
+use crate::ctx::BaseServerCommandContext;
+use crate::ctx::ServerCommandContext;
+
+pub(crate) async fn corrupt_scan_command(
+    context: &ServerCommandContext<'_>,
+    req: CorruptScanRequest,
+) -> anyhow::Result<CorruptScanResponse> {
+    let start_event = buck2_data::CommandStart {
+        metadata: context.request_metadata().await?,
+        data: Some(buck2_data::CorruptScanCommandStart {}.into()),
+    };
+    span_async(start_event, async move {
+        let result = corrupt_scan(&context.base_context, req)
+            .await
+            .context("Failed to scan for corrupted artifacts")
+            .map_err(Into::into);
+        let end_event = command_end(&result, buck2_data::CorruptScanCommandEnd {});
+        (result.map_err(Into::into), end_event)
+    })
+    .await
+}
+
+async fn corrupt_scan(
+    server_ctx: &BaseServerCommandContext,
+    req: CorruptScanRequest,
+) -> anyhow::Result<CorruptScanResponse> {
+    if !(0.0..=1.0).contains(&req.sample_rate) {
+        return Err(anyhow::anyhow!(
+            "Sample rate must be between 0 and 1, got {}",
+            req.sample_rate
+        ));
+    }
+    server_ctx
+        .daemon
+        .materializer
+        .as_deferred_materializer_extension()
+        .context("Deferred materializer is not in use")?
+        .corrupt_scan(req.sample_rate, req.repair)
+        .await
+}
@@ -11,22 +11,32 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 use std::io::BufWriter;
 use std::marker::PhantomData;
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
 
 use allocative::Allocative;
 use anyhow::Context;
 use async_trait::async_trait;
+use buck2_build_api::actions::execute::cache_hit_stats::ActionCacheHitStats;
+use buck2_build_api::actions::execute::cache_hit_stats::HasActionCacheHitStats;
 use buck2_build_api::actions::execute::dice_data::set_fallback_executor_config;
 use buck2_build_api::actions::execute::dice_data::SetCommandExecutor;
 use buck2_build_api::actions::execute::dice_data::SetReClient;
+use buck2_build_api::actions::execute::env_usage::ActionEnvUsage;
+use buck2_build_api::actions::execute::env_usage::HasActionEnvUsage;
 use buck2_build_api::actions::impls::run_action_knobs::HasRunActionKnobs;
 use buck2_build_api::actions::impls::run_action_knobs::RunActionKnobs;
+use buck2_build_api::build::last_successful_build::HasLastSuccessfulBuild;
+use buck2_build_api::build::last_successful_build::LastSuccessfulBuild;
 use buck2_build_api::build::HasCreateUnhashedSymlinkLock;
 use buck2_build_api::build_signals::create_build_signals;
 use buck2_build_api::build_signals::BuildSignalsInstaller;
 use buck2_build_api::build_signals::SetBuildSignals;
 use buck2_build_api::context::SetBuildContextData;
 use buck2_build_api::keep_going::HasKeepGoing;
+use buck2_build_api::sent_install_files::HasSentInstallFiles;
+use buck2_build_api::sent_install_files::SentInstallFiles;
 use buck2_build_api::spawner::BuckSpawner;
 use buck2_build_signals::CriticalPathBackendName;
 use buck2_build_signals::HasCriticalPathBackend;
@@ -37,6 +47,7 @@ use buck2_cli_proto::common_build_options::ExecutionStrategy;
 use buck2_cli_proto::ClientContext;
 use buck2_cli_proto::CommonBuildOptions;
 use buck2_common::dice::cells::HasCellResolver;
+use buck2_common::dice::client_env::SetClientEnvironment;
 use buck2_common::dice::cycles::CycleDetectorAdapter;
 use buck2_common::dice::cycles::PairDiceCycleDetector;
 use buck2_common::dice::data::HasIoProvider;
@@ -50,9 +61,11 @@ use buck2_common::legacy_configs::configs::LegacyConfigCmdArg;
 use buck2_common::legacy_configs::dice::HasInjectedLegacyConfigs;
 use buck2_common::legacy_configs::dice::HasLegacyConfigs;
 use buck2_common::legacy_configs::key::BuckconfigKeyRef;
+use buck2_common::process_priority::BackgroundPriority;
 use buck2_configured::calculation::ConfiguredGraphCycleDescriptor;
 use buck2_core::async_once_cell::AsyncOnceCell;
 use buck2_core::execution_types::executor_config::CommandExecutorConfig;
+use buck2_core::execution_types::executor_config::HybridExecutionPolicy;
 use buck2_core::facebook_only;
 use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
 use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
@@ -71,12 +84,15 @@ use buck2_events::dispatch::EventDispatcher;
 use buck2_events::metadata;
 use buck2_execute::execute::blocking::BlockingExecutor;
 use buck2_execute::execute::blocking::SetBlockingExecutor;
+use buck2_execute::execute::environment_inheritance::EnvIsolationMode;
+use buck2_execute::execute::environment_inheritance::EnvironmentInheritance;
 use buck2_execute::knobs::ExecutorGlobalKnobs;
 use buck2_execute::materialize::materializer::Materializer;
 use buck2_execute::materialize::materializer::SetMaterializer;
 use buck2_execute::re::client::RemoteExecutionClient;
 use buck2_execute::re::manager::ReConnectionHandle;
 use buck2_execute::re::manager::ReConnectionObserver;
+use buck2_execute_impl::executors::checkpoint::ActionCheckpoints;
 use buck2_execute_impl::executors::worker::WorkerPool;
 use buck2_execute_impl::low_pass_filter::LowPassFilter;
 use buck2_execute_impl::re::paranoid_download::ParanoidDownloader;
@@ -86,6 +102,10 @@ use buck2_forkserver::client::ForkserverClient;
 use buck2_futures::cancellation::ExplicitCancellationContext;
 use buck2_http::HttpClient;
 use buck2_interpreter::dice::starlark_debug::SetStarlarkDebugger;
+use buck2_interpreter::extra::pkg_config::InvalidateStalePkgConfig;
+use buck2_interpreter::extra::toolchain_probe::HasHostToolchains;
+use buck2_interpreter::extra::toolchain_probe::HostToolchains;
+use buck2_interpreter::extra::toolchain_probe::InvalidateStaleHostToolchains;
 use buck2_interpreter::extra::xcode::XcodeVersionInfo;
 use buck2_interpreter::extra::InterpreterHostArchitecture;
 use buck2_interpreter::extra::InterpreterHostPlatform;
@@ -94,8 +114,11 @@ use buck2_interpreter::starlark_profiler::config::StarlarkProfilerConfiguration;
 use buck2_interpreter_for_build::interpreter::configuror::BuildInterpreterConfiguror;
 use buck2_interpreter_for_build::interpreter::cycles::LoadCycleDescriptor;
 use buck2_interpreter_for_build::interpreter::interpreter_setup::setup_interpreter;
+use buck2_node::graph_snapshots::GraphSnapshots;
+use buck2_node::graph_snapshots::HasGraphSnapshots;
 use buck2_server_ctx::concurrency::DiceDataProvider;
 use buck2_server_ctx::concurrency::DiceUpdater;
+use buck2_server_ctx::concurrency::NestedInvocation;
 use buck2_server_ctx::ctx::DiceAccessor;
 use buck2_server_ctx::ctx::PrivateStruct;
 use buck2_server_ctx::ctx::ServerCommandContextTrait;
@@ -105,6 +128,7 @@ use buck2_server_starlark_debug::create_debugger_handle;
 use buck2_server_starlark_debug::BuckStarlarkDebuggerHandle;
 use buck2_util::arc_str::ArcS;
 use buck2_util::truncate::truncate_container;
+use buck2_wrapper_common::invocation_id::TraceId;
 use dice::DiceComputations;
 use dice::DiceData;
 use dice::DiceTransactionUpdater;
@@ -125,6 +149,7 @@ use crate::daemon::common::parse_concurrency;
 use crate::daemon::common::CommandExecutorFactory;
 use crate::daemon::state::DaemonStateData;
 use crate::dice_tracker::BuckDiceTracker;
+use crate::dice_tracker::DiceKeyTypeCosts;
 use crate::heartbeat_guard::HeartbeatGuard;
 use crate::host_info;
 use crate::snapshot::SnapshotCollector;
@@ -190,6 +215,9 @@ pub struct ServerCommandContext<'a> {
     disable_starlark_types: bool,
     unstable_typecheck: bool,
 
+    /// Environment of the client, for actions which declare `env_passthrough`.
+    client_env: Vec<(String, Option<String>)>,
+
     pub buck_out_dir: ProjectRelativePathBuf,
     isolation_prefix: FileNameBuf,
 
@@ -199,6 +227,9 @@ pub struct ServerCommandContext<'a> {
     /// The CellResolver and Configs loader for this command
     cell_configs_loader: Arc<CellConfigLoader>,
 
+    /// Cost of the DICE computations of this command, reported when it is dropped.
+    dice_key_type_costs: Arc<DiceKeyTypeCosts>,
+
     /// Keep emitting heartbeat events while the ServerCommandContext is alive  We put this in an
     /// Option so that we can ensure heartbeat events are cancelled before everything else is
     /// dropped.
@@ -207,6 +238,9 @@ pub struct ServerCommandContext<'a> {
     /// Daemon uuid passed in from the client side to detect nested invocation.
     pub(crate) daemon_uuid_from_client: Option<String>,
 
+    /// Trace id of the outer command, for nested invocations delegated to its state.
+    delegate_to_trace_id: Option<String>,
+
     /// Command named passed from the CLI
     pub(crate) command_name: String,
 
@@ -304,6 +338,7 @@ impl<'a> ServerCommandContext<'a> {
             working_dir: working_dir_project_relative.dupe(),
             reuse_current_config: client_context.reuse_current_config,
             config_overrides,
+            generated_config_dir: paths.generated_config_dir(),
             loaded_cell_configs: AsyncOnceCell::new(),
         });
 
@@ -328,8 +363,16 @@ impl<'a> ServerCommandContext<'a> {
             skip_targets_with_duplicate_names: client_context.skip_targets_with_duplicate_names,
             disable_starlark_types: client_context.disable_starlark_types,
             unstable_typecheck: client_context.unstable_typecheck,
+            client_env: client_context
+                .client_env
+                .iter()
+                .map(|v| (v.key.clone(), v.value.clone()))
+                .collect(),
+            dice_key_type_costs: Arc::new(DiceKeyTypeCosts::default()),
             heartbeat_guard_handle: Some(heartbeat_guard_handle),
             daemon_uuid_from_client: client_context.daemon_uuid.clone(),
+            delegate_to_trace_id: Some(client_context.delegate_to_trace_id.clone())
+                .filter(|id| !id.is_empty()),
             command_name: client_context.command_name.clone(),
             sanitized_argv: client_context.sanitized_argv.clone(),
             debugger_handle,
@@ -398,10 +441,16 @@ impl<'a> ServerCommandContext<'a> {
 
         let create_unhashed_symlink_lock =
             self.base_context.daemon.create_unhashed_outputs_lock.dupe();
+        let graph_snapshots = self.base_context.daemon.graph_snapshots.dupe();
+        let last_successful_build = self.base_context.daemon.last_successful_build.dupe();
+        let sent_install_files = self.base_context.daemon.sent_install_files.dupe();
+        let action_env_usage = self.base_context.daemon.action_env_usage.dupe();
+        let host_toolchains = self.base_context.daemon.host_toolchains.dupe();
 
         DiceCommandDataProvider {
             cell_configs_loader: self.cell_configs_loader.dupe(),
             events: self.events().dupe(),
+            dice_key_type_costs: self.dice_key_type_costs.dupe(),
             execution_strategy,
             run_action_knobs,
             concurrency,
@@ -415,6 +464,11 @@ impl<'a> ServerCommandContext<'a> {
             skip_cache_read,
             skip_cache_write,
             create_unhashed_symlink_lock,
+            graph_snapshots,
+            last_successful_build,
+            sent_install_files,
+            action_env_usage,
+            host_toolchains,
             starlark_debugger: self.debugger_handle.dupe(),
             keep_going: self
                 .build_options
@@ -422,11 +476,16 @@ impl<'a> ServerCommandContext<'a> {
                 .map_or(false, |opts| opts.keep_going),
             http_client: self.base_context.daemon.http_client.dupe(),
             paranoid: self.base_context.daemon.paranoid.dupe(),
+            action_checkpoints: self.base_context.daemon.action_checkpoints.dupe(),
             spawner: self.base_context.spawner.dupe(),
             materialize_failed_inputs: self
                 .build_options
                 .as_ref()
                 .map_or(false, |opts| opts.materialize_failed_inputs),
+            background: self
+                .build_options
+                .as_ref()
+                .map_or(false, |opts| opts.background),
         }
     }
 
@@ -440,6 +499,7 @@ impl<'a> ServerCommandContext<'a> {
 
         Ok(DiceCommandUpdater {
             file_watcher: self.base_context.daemon.file_watcher.dupe(),
+            host_toolchains: self.base_context.daemon.host_toolchains.dupe(),
             cell_config_loader: self.cell_configs_loader.dupe(),
             buck_out_dir: self.buck_out_dir.clone(),
             interpreter_platform,
@@ -451,6 +511,7 @@ impl<'a> ServerCommandContext<'a> {
             events: self.events().dupe(),
             disable_starlark_types: self.disable_starlark_types,
             unstable_typecheck: self.unstable_typecheck,
+            client_env: self.client_env.clone(),
             skip_targets_with_duplicate_names: self.skip_targets_with_duplicate_names,
             record_target_call_stacks: self.record_target_call_stacks,
         })
@@ -470,6 +531,8 @@ struct CellConfigLoader {
     /// Reuses build config from the previous invocation if there is one
     reuse_current_config: bool,
     config_overrides: Vec<LegacyConfigCmdArg>,
+    /// Where the output of config generators is cached.
+    generated_config_dir: AbsNormPathBuf,
     loaded_cell_configs: AsyncOnceCell<buck2_error::Result<BuckConfigBasedCellsStatus>>,
 }
 
@@ -481,6 +544,8 @@ struct BuckConfigBasedCellsStatus {
     new_configs: bool,
     // None on init and first config, Some after config change
     config_metrics: Option<ConfigDiffMetrics>,
+    // empty on init and first config
+    changed_cells: Vec<String>,
 }
 
 impl CellConfigLoader {
@@ -511,6 +576,7 @@ impl CellConfigLoader {
                             },
                             new_configs: false,
                             config_metrics: None,
+                            changed_cells: Vec::new(),
                         });
                     } else {
                         // If there is no previous command but the flag was set, then the flag is ignored, the command behaves as if there isn't the reuse config flag.
@@ -519,8 +585,12 @@ impl CellConfigLoader {
                         );
                     }
                 }
-                let cells_and_configs = BuckConfigBasedCells::parse_with_config_args(&self.project_root, &self.config_overrides, &self.working_dir)
-                    .map_err(buck2_error::Error::from)?;
+                // Parsing the configs may run config generators, which must not block an async
+                // thread.
+                let cells_and_configs = tokio::task::block_in_place(|| {
+                    BuckConfigBasedCells::parse_with_config_args_and_generators(&self.project_root, &self.config_overrides, &self.working_dir, self.generated_config_dir.clone())
+                })
+                .map_err(buck2_error::Error::from)?;
 
                 let (new_configs, config_metrics) = if dice_ctx.is_injected_legacy_configs_key_set().await? {
                     let injected_legacy_configs = dice_ctx.get_injected_legacy_configs().await?;
@@ -531,10 +601,16 @@ impl CellConfigLoader {
                     // first invocation of a daemon
                     (true, None)
                 };
+                let changed_cells = if dice_ctx.is_cell_resolver_key_set().await? {
+                    configs::changed_cells(&dice_ctx.get_cell_resolver().await?, &cells_and_configs.cell_resolver)
+                } else {
+                    Vec::new()
+                };
                 buck2_error::Ok(BuckConfigBasedCellsStatus {
                     cells_and_configs,
                     new_configs,
                     config_metrics,
+                    changed_cells,
                 })
             })
             .await
@@ -546,6 +622,7 @@ struct DiceCommandDataProvider {
     cell_configs_loader: Arc<CellConfigLoader>,
     execution_strategy: ExecutionStrategy,
     events: EventDispatcher,
+    dice_key_type_costs: Arc<DiceKeyTypeCosts>,
     concurrency: Option<Result<usize, buck2_error::Error>>,
     executor_config: Arc<CommandExecutorConfig>,
     blocking_executor: Arc<dyn BlockingExecutor>,
@@ -558,12 +635,19 @@ struct DiceCommandDataProvider {
     skip_cache_read: bool,
     skip_cache_write: bool,
     create_unhashed_symlink_lock: Arc<Mutex<()>>,
+    graph_snapshots: Arc<GraphSnapshots>,
+    last_successful_build: Arc<LastSuccessfulBuild>,
+    sent_install_files: Arc<SentInstallFiles>,
+    action_env_usage: Arc<ActionEnvUsage>,
+    host_toolchains: Arc<HostToolchains>,
     starlark_debugger: Option<BuckStarlarkDebuggerHandle>,
     keep_going: bool,
     http_client: HttpClient,
     paranoid: Option<ParanoidDownloader>,
+    action_checkpoints: Option<Arc<ActionCheckpoints>>,
     spawner: Arc<BuckSpawner>,
     materialize_failed_inputs: bool,
+    background: bool,
 }
 
 #[async_trait]
@@ -620,6 +704,14 @@ impl DiceDataProvider for DiceCommandDataProvider {
             .unwrap_or_else(RolloutPercentage::always)
             .roll();
 
+        // Measuring compute times has a cost, so only do it for `buck2 profile dice`.
+        let profile_dice = root_config
+            .parse::<bool>(BuckconfigKeyRef {
+                section: "buck2",
+                property: "profile_dice",
+            })?
+            .unwrap_or(false);
+
         let log_configured_graph_size = root_config
             .parse::<bool>(BuckconfigKeyRef {
                 section: "buck2",
@@ -634,9 +726,38 @@ impl DiceDataProvider for DiceCommandDataProvider {
             })?
             .or(Some(10));
 
+        let action_timeout_kill_grace_period_s = root_config
+            .parse::<u32>(BuckconfigKeyRef {
+                section: "build",
+                property: "action_timeout_kill_grace_period_s",
+            })?
+            .or(Some(5));
+
+        let background_priority = if self.background {
+            let default = BackgroundPriority::default();
+            Some(BackgroundPriority {
+                nice: root_config
+                    .parse::<i32>(BuckconfigKeyRef {
+                        section: "build",
+                        property: "background_nice",
+                    })?
+                    .unwrap_or(default.nice),
+                idle_io: root_config
+                    .parse::<bool>(BuckconfigKeyRef {
+                        section: "build",
+                        property: "background_idle_io",
+                    })?
+                    .unwrap_or(default.idle_io),
+            })
+        } else {
+            None
+        };
+
         let executor_global_knobs = ExecutorGlobalKnobs {
             enable_miniperf,
             log_action_keys,
+            action_timeout_kill_grace_period_s,
+            background_priority,
         };
 
         let host_sharing_broker =
@@ -673,9 +794,105 @@ impl DiceDataProvider for DiceCommandDataProvider {
             })?
             .unwrap_or(false);
 
+        let mut category_timeouts = HashMap::new();
+        if let Some(section) = root_config.get_section("action_timeouts") {
+            for (category, _) in section.iter() {
+                if let Some(seconds) = root_config.parse::<u64>(BuckconfigKeyRef {
+                    section: "action_timeouts",
+                    property: category,
+                })? {
+                    category_timeouts.insert(category.to_owned(), Duration::from_secs(seconds));
+                }
+            }
+        }
+        run_action_knobs.category_timeouts = Arc::new(category_timeouts);
+
+        let mut category_hybrid_policies = HashMap::new();
+        if let Some(section) = root_config.get_section("hybrid_execution_policies") {
+            for (category, _) in section.iter() {
+                if let Some(policy) =
+                    root_config.parse::<HybridExecutionPolicy>(BuckconfigKeyRef {
+                        section: "hybrid_execution_policies",
+                        property: category,
+                    })?
+                {
+                    category_hybrid_policies.insert(category.to_owned(), policy);
+                }
+            }
+        }
+        run_action_knobs.category_hybrid_policies = Arc::new(category_hybrid_policies);
+
+        if let Some(max_retries) = root_config.parse::<u32>(BuckconfigKeyRef {
+            section: "build",
+            property: "action_infra_retries",
+        })? {
+            run_action_knobs.infra_retry.max_retries = max_retries;
+        }
+        if let Some(backoff_ms) = root_config.parse::<u64>(BuckconfigKeyRef {
+            section: "build",
+            property: "action_infra_retry_backoff_ms",
+        })? {
+            run_action_knobs.infra_retry.initial_backoff = Duration::from_millis(backoff_ms);
+        }
+        if let Some(max_backoff_ms) = root_config.parse::<u64>(BuckconfigKeyRef {
+            section: "build",
+            property: "action_infra_retry_max_backoff_ms",
+        })? {
+            run_action_knobs.infra_retry.max_backoff = Duration::from_millis(max_backoff_ms);
+        }
+
+        run_action_knobs.reproducible = root_config
+            .parse::<bool>(BuckconfigKeyRef {
+                section: "build",
+                property: "reproducible",
+            })?
+            .unwrap_or(false);
+        // Defaults to 1980-01-01, the earliest timestamp zip files can represent.
+        run_action_knobs.source_date_epoch = root_config
+            .parse::<u64>(BuckconfigKeyRef {
+                section: "build",
+                property: "source_date_epoch",
+            })?
+            .unwrap_or(315532800);
+        run_action_knobs.record_action_digests = root_config
+            .parse::<bool>(BuckconfigKeyRef {
+                section: "build",
+                property: "record_action_digests",
+            })?
+            .unwrap_or(false);
+
+        let env_isolation = root_config
+            .parse::<EnvIsolationMode>(BuckconfigKeyRef {
+                section: "build",
+                property: "action_env_isolation",
+            })?
+            .unwrap_or_default();
+        let env_passthrough = root_config
+            .parse_list::<String>(BuckconfigKeyRef {
+                section: "build",
+                property: "action_env_passthrough",
+            })?
+            .unwrap_or_else(EnvironmentInheritance::default_passthrough);
+        if env_isolation == EnvIsolationMode::Warn {
+            let violations = EnvironmentInheritance::isolation_violations(&env_passthrough);
+            if !violations.is_empty() {
+                self.events.console_message(format!(
+                    "Warning: local actions inherit environment variables which are not in `build.action_env_passthrough` and will not be set with `build.action_env_isolation = strict`: {}",
+                    violations.join(", ")
+                ));
+            }
+        }
+        run_action_knobs.local_env_inheritance = Some(EnvironmentInheritance::local_actions(
+            env_isolation,
+            &env_passthrough,
+        ));
+
         let mut data = UserComputationData {
             data,
-            tracker: Arc::new(BuckDiceTracker::new(self.events.dupe())),
+            tracker: Arc::new(BuckDiceTracker::new(
+                self.events.dupe(),
+                profile_dice.then(|| self.dice_key_type_costs.dupe()),
+            )),
             cycle_detector,
             activation_tracker: Some(self.build_signals.activation_tracker.dupe()),
             ..Default::default()
@@ -711,6 +928,7 @@ impl DiceDataProvider for DiceCommandDataProvider {
             worker_pool,
             self.paranoid.dupe(),
             self.materialize_failed_inputs,
+            self.action_checkpoints.dupe(),
         )));
         data.set_blocking_executor(self.blocking_executor.dupe());
         data.set_http_client(self.http_client.dupe());
@@ -718,9 +936,15 @@ impl DiceDataProvider for DiceCommandDataProvider {
         data.set_build_signals(self.build_signals.build_signals.dupe());
         data.set_run_action_knobs(run_action_knobs);
         data.set_create_unhashed_symlink_lock(self.create_unhashed_symlink_lock.dupe());
+        data.set_graph_snapshots(self.graph_snapshots.dupe());
+        data.set_last_successful_build(self.last_successful_build.dupe());
+        data.set_sent_install_files(self.sent_install_files.dupe());
+        data.set_action_env_usage(self.action_env_usage.dupe());
+        data.set_host_toolchains(self.host_toolchains.dupe());
         data.set_starlark_debugger_handle(self.starlark_debugger.clone().map(|v| Box::new(v) as _));
         data.set_keep_going(self.keep_going);
         data.set_critical_path_backend(critical_path_backend);
+        data.set_action_cache_hit_stats(Arc::new(ActionCacheHitStats::default()));
         data.spawner = self.spawner.dupe();
 
         let tags = vec![
@@ -747,6 +971,7 @@ fn create_cycle_detector() -> Arc<dyn UserCycleDetector> {
 
 struct DiceCommandUpdater {
     file_watcher: Arc<dyn FileWatcher>,
+    host_toolchains: Arc<HostToolchains>,
     cell_config_loader: Arc<CellConfigLoader>,
     buck_out_dir: ProjectRelativePathBuf,
     interpreter_platform: InterpreterHostPlatform,
@@ -756,6 +981,7 @@ struct DiceCommandUpdater {
     events: EventDispatcher,
     disable_starlark_types: bool,
     unstable_typecheck: bool,
+    client_env: Vec<(String, Option<String>)>,
     record_target_call_stacks: bool,
     skip_targets_with_duplicate_names: bool,
 }
@@ -771,6 +997,7 @@ impl DiceUpdater for DiceCommandUpdater {
             cells_and_configs,
             new_configs,
             config_metrics,
+            changed_cells,
         } = self
             .cell_config_loader
             .cells_and_configs(&mut ctx.existing_state().await.clone())
@@ -796,6 +1023,9 @@ impl DiceUpdater for DiceCommandUpdater {
         user_data.set_mergebase(mergebase);
 
         ctx.set_buck_out_path(Some(self.buck_out_dir.clone()))?;
+        ctx.set_client_environment(self.client_env.iter().cloned())?;
+        ctx.invalidate_stale_pkg_config()?;
+        ctx.invalidate_stale_host_toolchains(&self.host_toolchains)?;
 
         setup_interpreter(
             &mut ctx,
@@ -808,7 +1038,7 @@ impl DiceUpdater for DiceCommandUpdater {
             self.unstable_typecheck,
         )?;
 
-        let buck_configs = configs::buck_configs(new_configs, config_metrics);
+        let buck_configs = configs::buck_configs(new_configs, config_metrics, changed_cells);
         self.events.instant_event(buck_configs);
 
         Ok(ctx)
@@ -819,6 +1049,10 @@ impl<'a> Drop for ServerCommandContext<'a> {
     fn drop(&mut self) {
         // Ensure we cancel the heartbeat guard first.
         std::mem::drop(self.heartbeat_guard_handle.take());
+
+        if let Some(profile) = self.dice_key_type_costs.to_profile() {
+            self.events().instant_event(profile);
+        }
     }
 }
 
@@ -848,21 +1082,34 @@ impl<'a> ServerCommandContextTrait for ServerCommandContext<'a> {
         self.base_context.daemon.materializer.dupe()
     }
 
+    fn file_watcher(&self) -> Arc<dyn FileWatcher> {
+        self.base_context.daemon.file_watcher.dupe()
+    }
+
     /// Provides a DiceTransaction, initialized on first use and shared after initialization.
     async fn dice_accessor(&self, _private: PrivateStruct) -> buck2_error::Result<DiceAccessor> {
         let (build_signals_installer, deferred_build_signals) = create_build_signals();
 
-        let is_nested_invocation = if let Some(uuid) = &self.daemon_uuid_from_client {
-            uuid == &daemon_id::DAEMON_UUID.to_string()
-        } else {
-            false
+        let nested_invocation = match &self.daemon_uuid_from_client {
+            Some(uuid) if uuid == &daemon_id::DAEMON_UUID.to_string() => {
+                // Opted into by the action running the nested invocation, e.g. with the `env`
+                // of a genrule.
+                match &self.delegate_to_trace_id {
+                    Some(trace_id) => NestedInvocation::Delegated(
+                        TraceId::from_str(trace_id)
+                            .with_context(|| format!("Invalid `BUCK_BUILD_ID`: `{}`", trace_id))?,
+                    ),
+                    None => NestedInvocation::Nested,
+                }
+            }
+            _ => NestedInvocation::No,
         };
 
         Ok(DiceAccessor {
             dice_handler: self.base_context.daemon.dice_manager.dupe(),
             data: Box::new(self.dice_data_constructor(build_signals_installer).await),
             setup: Box::new(self.dice_updater().await?),
-            is_nested_invocation,
+            nested_invocation,
             sanitized_argv: self.sanitized_argv.clone(),
             exit_when_different_state: self.exit_when_different_state,
             preemptible: self.preemptible,
@@ -44,6 +44,9 @@ use buck2_execute_impl::executors::action_cache::ActionCacheChecker;
 use buck2_execute_impl::executors::action_cache::RemoteDepFileCacheChecker;
 use buck2_execute_impl::executors::action_cache_upload_permission_checker::ActionCacheUploadPermissionChecker;
 use buck2_execute_impl::executors::caching::CacheUploader;
+use buck2_execute_impl::executors::checkpoint::ActionCheckpoints;
+use buck2_execute_impl::executors::checkpoint::CheckpointChecker;
+use buck2_execute_impl::executors::checkpoint::CheckpointingExecutor;
 use buck2_execute_impl::executors::hybrid::FallbackTracker;
 use buck2_execute_impl::executors::hybrid::HybridExecutor;
 use buck2_execute_impl::executors::local::LocalExecutor;
@@ -92,6 +95,8 @@ pub struct CommandExecutorFactory {
     /// Cache permission checks per command.
     cache_upload_permission_checker: Arc<ActionCacheUploadPermissionChecker>,
     fallback_tracker: Arc<FallbackTracker>,
+    /// Checkpoints of completed actions, to resume interrupted builds from.
+    action_checkpoints: Option<Arc<ActionCheckpoints>>,
 }
 
 impl CommandExecutorFactory {
@@ -111,6 +116,7 @@ impl CommandExecutorFactory {
         worker_pool: Arc<WorkerPool>,
         paranoid: Option<ParanoidDownloader>,
         materialize_failed_inputs: bool,
+        action_checkpoints: Option<Arc<ActionCheckpoints>>,
     ) -> Self {
         let cache_upload_permission_checker = Arc::new(ActionCacheUploadPermissionChecker::new(
             re_connection.get_client(),
@@ -133,6 +139,7 @@ impl CommandExecutorFactory {
             materialize_failed_inputs,
             cache_upload_permission_checker,
             fallback_tracker: Arc::new(FallbackTracker::new()),
+            action_checkpoints,
         }
     }
 }
@@ -355,12 +362,38 @@ impl HasCommandExecutor for CommandExecutorFactory {
                     _ => None,
                 };
 
-                let cache_checker = if self.paranoid.is_some() {
+                let mut cache_checker = if self.paranoid.is_some() {
                     Arc::new(NoOpCommandOptionalExecutor {}) as _
                 } else {
                     cache_checker_new()
                 };
 
+                // Only the results of RE and of the action cache have their outputs in the CAS,
+                // which is what allows resuming from them without materializing them.
+                let executor = match &self.action_checkpoints {
+                    Some(checkpoints) => {
+                        if !self.skip_cache_read {
+                            cache_checker = Arc::new(CheckpointChecker {
+                                checkpoints: checkpoints.dupe(),
+                                artifact_fs: artifact_fs.clone(),
+                                materializer: self.materializer.dupe(),
+                                re_client: self.re_connection.get_client(),
+                                re_use_case: *re_use_case,
+                                re_action_key: re_action_key.clone(),
+                                paranoid: self.paranoid.dupe(),
+                                inner: cache_checker,
+                            }) as _;
+                        }
+                        executor.map(|executor| {
+                            Arc::new(CheckpointingExecutor {
+                                checkpoints: checkpoints.dupe(),
+                                inner: executor,
+                            }) as _
+                        })
+                    }
+                    None => executor,
+                };
+
                 let cache_uploader = if force_cache_upload()? {
                     Arc::new(CacheUploader::new(
                         artifact_fs.clone(),
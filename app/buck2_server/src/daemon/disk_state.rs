@@ -22,6 +22,7 @@ use buck2_core::rollout_percentage::RolloutPercentage;
 use buck2_execute::digest_config::DigestConfig;
 use buck2_execute::execute::blocking::BlockingExecutor;
 use buck2_execute::materialize::materializer::MaterializationMethod;
+use buck2_execute_impl::executors::checkpoint::ActionCheckpoints;
 use buck2_execute_impl::materializers::deferred::DeferredMaterializerConfigs;
 use buck2_execute_impl::materializers::sqlite::MaterializerState;
 use buck2_execute_impl::materializers::sqlite::MaterializerStateSqliteDb;
@@ -32,6 +33,9 @@ use crate::daemon::server::BuckdServerInitPreferences;
 #[derive(Allocative)]
 pub struct DiskStateOptions {
     pub sqlite_materializer_state: bool,
+    /// Whether to checkpoint the actions whose outputs are in the CAS, to resume interrupted
+    /// builds from them.
+    pub action_checkpoints: bool,
     // In future, this will include the config for dep files on disk
 }
 
@@ -51,12 +55,34 @@ impl DiskStateOptions {
             })?
             .unwrap_or_else(RolloutPercentage::never)
             .roll();
+        let action_checkpoints = root_config
+            .parse(BuckconfigKeyRef {
+                section: "buck2",
+                property: "action_checkpoints",
+            })?
+            .unwrap_or(false);
         Ok(Self {
             sqlite_materializer_state,
+            action_checkpoints,
         })
     }
 }
 
+pub(crate) fn maybe_open_action_checkpoints(
+    options: &DiskStateOptions,
+    paths: &InvocationPaths,
+) -> anyhow::Result<Option<Arc<ActionCheckpoints>>> {
+    if !options.action_checkpoints {
+        // Like the materializer state, the checkpoints are not kept around when disabled.
+        fs_util::remove_all(&paths.action_checkpoints_path())?;
+        return Ok(None);
+    }
+    Ok(Some(Arc::new(
+        ActionCheckpoints::open(&paths.action_checkpoints_path())
+            .context("Error opening action checkpoints")?,
+    )))
+}
+
 pub(crate) async fn maybe_initialize_materializer_sqlite_db(
     options: &DiskStateOptions,
     paths: InvocationPaths,
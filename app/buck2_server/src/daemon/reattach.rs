@@ -0,0 +1,337 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Commands started with `--reattachable` are not cancelled when their client disconnects: they
+//! keep running, and `buck2 attach` can resume streaming their events. The partial results (the
+//! command's stdout) produced while no client is attached are kept and sent to the next one.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Weak;
+use std::time::Duration;
+use std::time::Instant;
+
+use buck2_cli_proto::command_progress;
+use buck2_cli_proto::CommandProgress;
+use buck2_futures::cancellation::future::CancellationHandle;
+use buck2_wrapper_common::invocation_id::TraceId;
+use dupe::Dupe;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::sync::mpsc::UnboundedSender;
+use tonic::Status;
+
+/// How long a command keeps running with no client attached before it is cancelled.
+const DETACHED_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+type Output = UnboundedSender<Result<CommandProgress, Status>>;
+
+static REATTACHABLE_COMMANDS: Lazy<Mutex<HashMap<TraceId, Arc<Mutex<ReattachableCommand>>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, buck2_error::Error)]
+#[buck2(input)]
+enum ReattachError {
+    #[error(
+        "No command with invocation id `{0}` to attach to: it finished, or was not started with `--reattachable`"
+    )]
+    NotFound(TraceId),
+    #[error("Command with invocation id `{0}` already has a client attached")]
+    AlreadyAttached(TraceId),
+}
+
+struct ReattachableCommand {
+    /// The client currently attached, if any.
+    output: Option<Output>,
+    detached_since: Option<Instant>,
+    /// The partial results sent while no client was attached, to send to the next one.
+    detached_partial_results: Vec<CommandProgress>,
+    /// The start events of the spans still open (including the command span), with the order in
+    /// which they were received, to replay to a client attaching.
+    open_spans: HashMap<u64, (u64, CommandProgress)>,
+    next_seq: u64,
+    cancellation_handle: Option<CancellationHandle>,
+}
+
+impl ReattachableCommand {
+    fn track(&mut self, event: &buck2_data::BuckEvent, progress: &CommandProgress) {
+        match &event.data {
+            Some(buck2_data::buck_event::Data::SpanStart(_)) => {
+                self.open_spans
+                    .insert(event.span_id, (self.next_seq, progress.clone()));
+                self.next_seq += 1;
+            }
+            Some(buck2_data::buck_event::Data::SpanEnd(_)) => {
+                self.open_spans.remove(&event.span_id);
+            }
+            _ => {}
+        }
+    }
+
+    fn is_detached(&self) -> bool {
+        self.output
+            .as_ref()
+            .map_or(true, |output| output.is_closed())
+    }
+}
+
+/// Wait for the client behind `output` to disconnect, then cancel the command if no other
+/// client attaches within `DETACHED_TIMEOUT`.
+fn spawn_detach_timer(
+    trace_id: TraceId,
+    command: Weak<Mutex<ReattachableCommand>>,
+    output: Output,
+) {
+    tokio::spawn(async move {
+        output.closed().await;
+        let detached_since = {
+            let Some(command) = command.upgrade() else {
+                return;
+            };
+            let mut command = command.lock();
+            if !command.is_detached() {
+                // Another client attached already.
+                return;
+            }
+            *command.detached_since.get_or_insert_with(Instant::now)
+        };
+
+        tokio::time::sleep(DETACHED_TIMEOUT).await;
+
+        let Some(command) = command.upgrade() else {
+            return;
+        };
+        let mut command = command.lock();
+        if command.is_detached() && command.detached_since == Some(detached_since) {
+            if let Some(cancellation_handle) = command.cancellation_handle.take() {
+                tracing::info!(
+                    "Cancelling command `{}`: no client attached for {:?}",
+                    trace_id,
+                    DETACHED_TIMEOUT
+                );
+                cancellation_handle.cancel();
+            }
+        }
+    });
+}
+
+/// Where the events of a reattachable command are sent: to the client currently attached.
+pub(crate) struct ReattachableOutput {
+    trace_id: TraceId,
+    command: Arc<Mutex<ReattachableCommand>>,
+}
+
+impl ReattachableOutput {
+    pub(crate) fn new(
+        trace_id: TraceId,
+        output: Output,
+        cancellation_handle: CancellationHandle,
+    ) -> Self {
+        let command = Arc::new(Mutex::new(ReattachableCommand {
+            output: Some(output.clone()),
+            detached_since: None,
+            detached_partial_results: Vec::new(),
+            open_spans: HashMap::new(),
+            next_seq: 0,
+            cancellation_handle: Some(cancellation_handle),
+        }));
+        REATTACHABLE_COMMANDS
+            .lock()
+            .insert(trace_id.dupe(), command.dupe());
+        spawn_detach_timer(trace_id.dupe(), Arc::downgrade(&command), output);
+        Self { trace_id, command }
+    }
+
+    pub(crate) fn send(&self, progress: CommandProgress) {
+        let mut command = self.command.lock();
+
+        if let Some(command_progress::Progress::Event(event)) = &progress.progress {
+            command.track(event, &progress);
+        }
+
+        let unsent = match &command.output {
+            Some(output) => match output.send(Ok(progress)) {
+                Ok(()) => return,
+                Err(e) => e.0,
+            },
+            None => Ok(progress),
+        };
+        // The client disconnected: the detach timer cancels the command if none attaches in
+        // time. Other events are dropped, but the output of the command must not be lost.
+        command.output = None;
+        if let Ok(progress) = unsent {
+            if matches!(
+                progress.progress,
+                Some(command_progress::Progress::PartialResult(_))
+            ) {
+                command.detached_partial_results.push(progress);
+            }
+        }
+    }
+}
+
+impl Drop for ReattachableOutput {
+    fn drop(&mut self) {
+        REATTACHABLE_COMMANDS.lock().remove(&self.trace_id);
+    }
+}
+
+/// Attach to the running command `trace_id`: the start events of its open spans are replayed,
+/// then the partial results produced while detached, then its events are streamed until it
+/// finishes.
+pub(crate) fn attach(
+    trace_id: &TraceId,
+) -> anyhow::Result<UnboundedReceiver<Result<CommandProgress, Status>>> {
+    let command_entry = REATTACHABLE_COMMANDS
+        .lock()
+        .get(trace_id)
+        .cloned()
+        .ok_or_else(|| ReattachError::NotFound(trace_id.dupe()))?;
+    let mut command = command_entry.lock();
+
+    if command
+        .output
+        .as_ref()
+        .map_or(false, |output| !output.is_closed())
+    {
+        return Err(ReattachError::AlreadyAttached(trace_id.dupe()).into());
+    }
+
+    let (output, receiver) = tokio::sync::mpsc::unbounded_channel();
+    let mut replay: Vec<_> = command.open_spans.values().collect();
+    replay.sort_by_key(|(seq, _)| *seq);
+    for (_, progress) in replay {
+        let _ignored = output.send(Ok(progress.clone()));
+    }
+    for progress in std::mem::take(&mut command.detached_partial_results) {
+        let _ignored = output.send(Ok(progress));
+    }
+    command.output = Some(output.clone());
+    command.detached_since = None;
+    spawn_detach_timer(trace_id.dupe(), Arc::downgrade(&command_entry), output);
+
+    Ok(receiver)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(span_id: u64, data: buck2_data::buck_event::Data) -> CommandProgress {
+        CommandProgress {
+            progress: Some(command_progress::Progress::Event(Box::new(
+                buck2_data::BuckEvent {
+                    span_id,
+                    data: Some(data),
+                    ..Default::default()
+                },
+            ))),
+        }
+    }
+
+    fn stdout(bytes: &[u8]) -> CommandProgress {
+        CommandProgress {
+            progress: Some(command_progress::Progress::PartialResult(
+                buck2_cli_proto::PartialResult {
+                    partial_result: Some(
+                        buck2_cli_proto::partial_result::PartialResult::StdoutBytes(
+                            buck2_cli_proto::StdoutBytes {
+                                data: bytes.to_vec(),
+                            },
+                        ),
+                    ),
+                },
+            )),
+        }
+    }
+
+    fn reattachable_output(
+        trace_id: &TraceId,
+    ) -> (
+        ReattachableOutput,
+        UnboundedReceiver<Result<CommandProgress, Status>>,
+    ) {
+        let (output, receiver) = tokio::sync::mpsc::unbounded_channel();
+        let command = Arc::new(Mutex::new(ReattachableCommand {
+            output: Some(output),
+            detached_since: None,
+            detached_partial_results: Vec::new(),
+            open_spans: HashMap::new(),
+            next_seq: 0,
+            cancellation_handle: None,
+        }));
+        REATTACHABLE_COMMANDS
+            .lock()
+            .insert(trace_id.dupe(), command.dupe());
+        let output = ReattachableOutput {
+            trace_id: trace_id.dupe(),
+            command,
+        };
+        (output, receiver)
+    }
+
+    #[tokio::test]
+    async fn test_attach_replays_open_spans() {
+        let trace_id = TraceId::new();
+        let (output, receiver) = reattachable_output(&trace_id);
+
+        for span_id in [3, 1, 2] {
+            output.send(progress(
+                span_id,
+                buck2_data::SpanStartEvent::default().into(),
+            ));
+        }
+        output.send(progress(1, buck2_data::SpanEndEvent::default().into()));
+
+        assert!(attach(&trace_id).is_err(), "a client is still attached");
+        drop(receiver);
+
+        let mut receiver = attach(&trace_id).unwrap();
+        let mut replayed = Vec::new();
+        while let Ok(progress) = receiver.try_recv() {
+            match progress.unwrap().progress {
+                Some(command_progress::Progress::Event(event)) => replayed.push(event.span_id),
+                _ => panic!("expected an event"),
+            }
+        }
+        assert_eq!(vec![3, 2], replayed);
+
+        drop(output);
+        assert!(attach(&trace_id).is_err(), "the command has finished");
+    }
+
+    #[tokio::test]
+    async fn test_attach_replays_partial_results_sent_while_detached() {
+        let trace_id = TraceId::new();
+        let (output, receiver) = reattachable_output(&trace_id);
+
+        output.send(stdout(b"a"));
+        drop(receiver);
+        output.send(stdout(b"b"));
+        output.send(progress(1, buck2_data::InstantEvent::default().into()));
+        output.send(stdout(b"c"));
+
+        let mut receiver = attach(&trace_id).unwrap();
+        output.send(stdout(b"d"));
+        let mut replayed = Vec::new();
+        while let Ok(progress) = receiver.try_recv() {
+            match progress.unwrap().progress {
+                Some(command_progress::Progress::PartialResult(
+                    buck2_cli_proto::PartialResult {
+                        partial_result:
+                            Some(buck2_cli_proto::partial_result::PartialResult::StdoutBytes(bytes)),
+                    },
+                )) => replayed.extend(bytes.data),
+                _ => panic!("expected stdout"),
+            }
+        }
+        assert_eq!(b"bcd".to_vec(), replayed);
+    }
+}
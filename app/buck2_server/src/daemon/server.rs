@@ -30,6 +30,7 @@ use buck2_cli_proto::*;
 use buck2_common::buckd_connection::BUCK_AUTH_TOKEN_HEADER;
 use buck2_common::events::HasEvents;
 use buck2_common::init::DaemonStartupConfig;
+use buck2_common::init::Timeout;
 use buck2_common::invocation_paths::InvocationPaths;
 use buck2_common::io::trace::TracingIoProvider;
 use buck2_common::io::IoProvider;
@@ -53,6 +54,7 @@ use buck2_execute_impl::materializers::sqlite::MaterializerStateIdentity;
 use buck2_futures::cancellation::ExplicitCancellationContext;
 use buck2_futures::drop::DropTogether;
 use buck2_futures::spawn::spawn_cancellable;
+use buck2_futures::spawn::FutureAndCancellationHandle;
 use buck2_interpreter::starlark_profiler::config::StarlarkProfilerConfiguration;
 use buck2_profile::starlark_profiler_configuration_from_request;
 use buck2_server_ctx::bxl::BXL_SERVER_COMMANDS;
@@ -99,6 +101,8 @@ use crate::active_commands::ActiveCommandStateWriter;
 use crate::clean_stale::clean_stale_command;
 use crate::ctx::ServerCommandContext;
 use crate::daemon::multi_event_stream::MultiEventStream;
+use crate::daemon::reattach;
+use crate::daemon::reattach::ReattachableOutput;
 use crate::daemon::server_allocative::spawn_allocative;
 use crate::daemon::state::DaemonState;
 use crate::file_status::file_status_command;
@@ -273,6 +277,8 @@ impl BuckdServer {
         let (shutdown_channel, shutdown_receiver): (UnboundedSender<()>, _) = mpsc::unbounded();
         let (command_channel, command_receiver): (UnboundedSender<()>, _) = mpsc::unbounded();
 
+        let idle_timeout = init_ctx.daemon_startup_config.idle_timeout();
+
         let materializations = MaterializationMethod::try_new_from_config_value(
             init_ctx.daemon_startup_config.materializations.as_deref(),
         )?;
@@ -312,7 +318,7 @@ impl BuckdServer {
             rt,
         }));
 
-        let shutdown = server_shutdown_signal(command_receiver, shutdown_receiver)?;
+        let shutdown = server_shutdown_signal(command_receiver, shutdown_receiver, idle_timeout)?;
         let server = Server::builder()
             .layer(interceptor(BuckCheckAuthTokenInterceptor { auth_token }))
             .add_service(
@@ -401,6 +407,7 @@ impl BuckdServer {
         PartialRes: Into<partial_result::PartialResult> + Send + 'static,
     {
         let client_ctx = req.get_ref().client_context()?;
+        let reattachable = client_ctx.reattachable;
 
         // This will reset counters incorrectly if commands are running concurrently.
         // This is fine.
@@ -444,6 +451,7 @@ impl BuckdServer {
             state,
             dispatch.dupe(),
             daemon_shutdown_channel,
+            reattachable,
             move |req, cancellations| {
                 async move {
                     let result: anyhow::Result<Res> = try {
@@ -600,39 +608,56 @@ impl<T: Stream + Send> Stream for SyncStream<T> {
     }
 }
 
+/// Where `pump_events` sends the events of a command.
+enum PumpOutput {
+    /// The client that started the command.
+    Client(tokio::sync::mpsc::UnboundedSender<Result<CommandProgress, tonic::Status>>),
+    /// Whichever client is attached to a command started with `--reattachable`.
+    Reattachable(ReattachableOutput),
+}
+
+impl PumpOutput {
+    fn send(&self, progress: CommandProgress) {
+        match self {
+            // Ignoring errors from writing to `output_send` because they occur only when
+            // the receiving end of the channel is closed. This can happen, for example,
+            // if Tonic drops the streaming response due the client disconnecting.
+            // In these cases, ignoring the errors is intentional as no client is listening.
+            PumpOutput::Client(output_send) => {
+                let _ignore = output_send.send(Ok(progress));
+            }
+            PumpOutput::Reattachable(output) => output.send(progress),
+        }
+    }
+}
+
 fn pump_events(
     mut events: ChannelEventSource,
     mut state: ActiveCommandStateWriter,
-    output_send: tokio::sync::mpsc::UnboundedSender<
-        Result<buck2_cli_proto::CommandProgress, tonic::Status>,
-    >,
+    output: PumpOutput,
 ) {
     // This function returns the receiving channel back to `tonic` as a streaming response.
     while let Some(next_event) = events.receive() {
-        // Ignoring errors from writing to `output_send` because they occur only when
-        // the receiving end of the channel is closed. This can happen, for example,
-        // if Tonic drops the streaming response due the client disconnecting.
-        // In these cases, ignoring the errors is intentional as no client is listening.
         match next_event {
             // The CommandResult event indicates that the spawned
             // computation won't be producing any more events.
             Event::CommandResult(result) => {
-                let _ignore = output_send.send(Ok(CommandProgress {
+                output.send(CommandProgress {
                     progress: Some(command_progress::Progress::Result(result)),
-                }));
+                });
                 return;
             }
             Event::PartialResult(result) => {
-                let _ignore = output_send.send(Ok(CommandProgress {
+                output.send(CommandProgress {
                     progress: Some(command_progress::Progress::PartialResult(Box::new(result))),
-                }));
+                });
             }
             Event::Buck(buck_event) => {
                 state.peek_event(&buck_event);
 
-                let _ignore = output_send.send(Ok(CommandProgress {
+                output.send(CommandProgress {
                     progress: Some(command_progress::Progress::Event(buck_event.into())),
-                }));
+                });
             }
         }
     }
@@ -649,6 +674,7 @@ fn streaming<
     state: ActiveCommandStateWriter,
     dispatcher: EventDispatcher,
     daemon_shutdown_channel: oneshot::Receiver<buck2_data::DaemonShutdown>,
+    reattachable: bool,
     func: F,
     rt: &Handle,
 ) -> Response<ResponseStream>
@@ -679,11 +705,33 @@ where
     );
     let (output_send, output_recv) = tokio::sync::mpsc::unbounded_channel();
 
+    // A reattachable command keeps running when its client disconnects, so it is cancelled through
+    // the reattach registry rather than by dropping the response stream.
+    let (output, spawned) = if reattachable {
+        let FutureAndCancellationHandle {
+            future: _,
+            cancellation_handle,
+        } = spawned;
+        (
+            PumpOutput::Reattachable(ReattachableOutput::new(
+                trace_id.dupe(),
+                output_send,
+                cancellation_handle,
+            )),
+            None,
+        )
+    } else {
+        (
+            PumpOutput::Client(output_send),
+            Some(spawned.into_drop_cancel()),
+        )
+    };
+
     // We run the event consumer on new non-tokio thread to avoid the consumer task from getting stuck behind
     // another tokio task in its lifo task slot. See T96012305 and https://github.com/tokio-rs/tokio/issues/4323 for more
     // information.
     let merge_task = thread_spawn("pump-events", move || {
-        pump_events(events, state, output_send);
+        pump_events(events, state, output);
     });
     if let Err(e) = merge_task {
         return error_to_response_stream(
@@ -731,10 +779,7 @@ where
     let events = MultiEventStream::new(events);
 
     Response::new(Box::pin(SyncStream {
-        wrapped: sync_wrapper::SyncWrapper::new(DropTogether::new(
-            events,
-            spawned.into_drop_cancel(),
-        )),
+        wrapped: sync_wrapper::SyncWrapper::new(DropTogether::new(events, spawned)),
     }))
 }
 
@@ -1230,12 +1275,14 @@ impl DaemonApi for BuckdServer {
             state,
             dispatcher.dupe(),
             daemon_shutdown_channel,
+            false,
             move |req, _| {
                 async move {
                     let result = try {
                         spawn_allocative(
                             this,
                             AbsPathBuf::try_from(req.output_path)?,
+                            req.heap_profile,
                             dispatcher.dupe(),
                         )
                         .await?;
@@ -1427,6 +1474,32 @@ impl DaemonApi for BuckdServer {
         )
         .await
     }
+
+    type AttachStream = ResponseStream;
+    async fn attach(
+        &self,
+        req: Request<AttachRequest>,
+    ) -> Result<Response<ResponseStream>, Status> {
+        // Attaching does not start a command, so it needs no active command of its own.
+        self.check_if_accepting_requests()?;
+
+        let res: anyhow::Result<_> = try {
+            let trace_id = req.into_inner().trace_id.parse()?;
+            reattach::attach(&trace_id)?
+        };
+
+        let output_recv = match res {
+            Ok(v) => v,
+            Err(e) => return Ok(error_to_response_stream(e)),
+        };
+
+        let events = MultiEventStream::new(tokio_stream::wrappers::UnboundedReceiverStream::new(
+            output_recv,
+        ));
+        Ok(Response::new(Box::pin(SyncStream {
+            wrapped: sync_wrapper::SyncWrapper::new(events),
+        })))
+    }
 }
 
 /// Options to configure the execution of a oneshot command (i.e. what happens in `oneshot()`).
@@ -1449,18 +1522,28 @@ trait StreamingCommandOptions<Req>: OneshotCommandOptions {
 fn server_shutdown_signal(
     command_receiver: UnboundedReceiver<()>,
     mut shutdown_receiver: UnboundedReceiver<()>,
+    idle_timeout: Timeout,
 ) -> anyhow::Result<impl Future<Output = ()>> {
-    let mut duration = DEFAULT_INACTIVITY_TIMEOUT;
+    let mut duration = match idle_timeout {
+        Timeout::Value(duration) => Some(duration),
+        Timeout::Default => Some(DEFAULT_INACTIVITY_TIMEOUT),
+        Timeout::NoTimeout => None,
+    };
     if buck2_env!(
         "BUCK2_TESTING_INACTIVITY_TIMEOUT",
         bool,
         applicability = testing
     )? {
-        duration = Duration::from_secs(1);
+        duration = Some(Duration::from_secs(1));
     }
 
     Ok(async move {
-        let timeout = inactivity_timeout(command_receiver, duration);
+        let timeout = async move {
+            match duration {
+                Some(duration) => inactivity_timeout(command_receiver, duration).await,
+                None => futures::future::pending().await,
+            }
+        };
         let shutdown = shutdown_receiver.next();
 
         futures::pin_mut!(shutdown);
@@ -1481,7 +1564,14 @@ async fn inactivity_timeout(mut command_receiver: UnboundedReceiver<()>, duratio
 
         match futures::future::select(command, timer).await {
             futures::future::Either::Left(_) => continue,
-            futures::future::Either::Right(_) => break,
+            futures::future::Either::Right(_) => {
+                // A command started before the timer is still running, so we are not idle.
+                if !crate::active_commands::active_commands().is_empty() {
+                    continue;
+                }
+                tracing::info!("Shutting down after being idle for {:?}", duration);
+                break;
+            }
         };
     }
 }
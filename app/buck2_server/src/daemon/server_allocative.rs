@@ -7,10 +7,14 @@
  * of this source tree.
  */
 
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt::Write;
 use std::sync::Arc;
 
 use allocative::FlameGraph;
 use allocative::FlameGraphBuilder;
+use buck2_common::memory;
 use buck2_core::fs::fs_util;
 use buck2_core::fs::paths::abs_path::AbsPathBuf;
 use buck2_events::dispatch::EventDispatcher;
@@ -73,9 +77,87 @@ fn wrap_flamegraph_with_system_stats(fg: &FlameGraph) -> FlameGraph {
     fg.into_flamegraph()
 }
 
+/// Number of types printed by a heap profile, all of them are in `retained_by_type.txt`.
+const HEAP_PROFILE_TOP_TYPES: usize = 20;
+
+#[derive(Default, Debug, PartialEq)]
+struct TypeSize {
+    /// Size of the values of this type, including everything they own.
+    retained: usize,
+    /// Size of the values of this type, excluding the values of other types they own.
+    self_size: usize,
+}
+
+/// Allocative keys are either type names or field names.
+fn is_type_key(key: &str) -> bool {
+    const PRIMITIVES: &[&str] = &[
+        "bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32",
+        "i64", "i128", "isize", "f32", "f64",
+    ];
+    key.contains("::") || key.starts_with(['(', '[', '&', '*']) || PRIMITIVES.contains(&key)
+}
+
+/// Split a flamegraph stack on `;`, except in type names like `[u8; 32]`.
+fn split_stack(stack: &str) -> Vec<&str> {
+    let mut keys = Vec::new();
+    let mut start = 0;
+    for (i, _) in stack.match_indices(';') {
+        if !stack[i + 1..].starts_with(' ') {
+            keys.push(&stack[start..i]);
+            start = i + 1;
+        }
+    }
+    keys.push(&stack[start..]);
+    keys
+}
+
+/// Aggregate the sizes in a flamegraph (as written by `FlameGraph::write`) by type, largest
+/// retained size first.
+fn sizes_by_type(flamegraph: &str) -> Vec<(String, TypeSize)> {
+    let mut sizes: HashMap<&str, TypeSize> = HashMap::new();
+    for line in flamegraph.lines() {
+        let Some((stack, size)) = line.rsplit_once(' ') else {
+            continue;
+        };
+        let Ok(size) = size.parse::<usize>() else {
+            continue;
+        };
+        let types: Vec<&str> = split_stack(stack)
+            .into_iter()
+            .filter(|key| is_type_key(key))
+            .collect();
+        // Recursive types appear several times in a stack, but retain the memory only once.
+        let mut seen = HashSet::new();
+        for ty in &types {
+            if seen.insert(*ty) {
+                sizes.entry(ty).or_default().retained += size;
+            }
+        }
+        if let Some(ty) = types.last() {
+            sizes.entry(ty).or_default().self_size += size;
+        }
+    }
+    let mut sizes: Vec<_> = sizes
+        .into_iter()
+        .map(|(ty, size)| (ty.to_owned(), size))
+        .collect();
+    sizes.sort_by(|(a_ty, a), (b_ty, b)| b.retained.cmp(&a.retained).then(a_ty.cmp(b_ty)));
+    sizes
+}
+
+fn write_sizes_by_type(sizes: &[(String, TypeSize)]) -> String {
+    let mut res = String::new();
+    writeln!(res, "{:>16} {:>16}  type", "retained", "self").unwrap();
+    for (ty, size) in sizes {
+        writeln!(res, "{:>16} {:>16}  {}", size.retained, size.self_size, ty).unwrap();
+    }
+    res
+}
+
 pub(crate) async fn spawn_allocative(
     buckd_server_data: Arc<BuckdServerData>,
     path: AbsPathBuf,
+    heap_profile: bool,
     dispatcher: EventDispatcher,
 ) -> anyhow::Result<()> {
     tokio::task::spawn_blocking(move || {
@@ -103,9 +185,73 @@ pub(crate) async fn spawn_allocative(
 
         fs_util::write(path.join("warnings.txt"), fg.warnings())?;
 
+        let sizes = sizes_by_type(&fg.flamegraph().write());
+        fs_util::write(
+            path.join("retained_by_type.txt"),
+            write_sizes_by_type(&sizes),
+        )?;
+
+        if heap_profile {
+            let heap_path = path.join("jemalloc.heap");
+            match memory::write_heap_to_file(&heap_path.to_string_lossy()) {
+                Ok(()) => dispatcher.console_message(format!(
+                    "Wrote jemalloc heap profile to `{}`",
+                    heap_path.display()
+                )),
+                Err(e) => {
+                    dispatcher.console_message(format!("Skipping jemalloc heap profile: {:#}", e))
+                }
+            }
+            dispatcher.console_message(format!(
+                "Types retaining the most memory:\n{}",
+                write_sizes_by_type(&sizes[..sizes.len().min(HEAP_PROFILE_TOP_TYPES)]).trim_end()
+            ));
+        }
+
         dispatcher.console_message("Profile written.".to_owned());
 
         anyhow::Ok(())
     })
     .await?
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sizes_by_type() {
+        let flamegraph = "\
+a::Outer;inner;a::Inner 10
+a::Outer;inner;a::Inner;buf;[u8; 4] 4
+a::Outer;next;a::Outer 6
+";
+        let sizes = sizes_by_type(flamegraph);
+        assert_eq!(
+            vec![
+                (
+                    "a::Outer".to_owned(),
+                    TypeSize {
+                        retained: 20,
+                        self_size: 6
+                    }
+                ),
+                (
+                    "a::Inner".to_owned(),
+                    TypeSize {
+                        retained: 14,
+                        self_size: 10
+                    }
+                ),
+                (
+                    "[u8; 4]".to_owned(),
+                    TypeSize {
+                        retained: 4,
+                        self_size: 4
+                    }
+                ),
+            ],
+            sizes
+        );
+    }
+}
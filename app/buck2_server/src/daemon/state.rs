@@ -9,6 +9,7 @@
 
 use std::collections::HashMap;
 use std::path::Path;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
@@ -16,6 +17,9 @@ use std::time::Instant;
 
 use allocative::Allocative;
 use anyhow::Context;
+use buck2_build_api::actions::execute::env_usage::ActionEnvUsage;
+use buck2_build_api::build::last_successful_build::LastSuccessfulBuild;
+use buck2_build_api::sent_install_files::SentInstallFiles;
 use buck2_build_api::spawner::BuckSpawner;
 use buck2_cli_proto::unstable_dice_dump_request::DiceDumpFormat;
 use buck2_common::cas_digest::DigestAlgorithm;
@@ -32,6 +36,7 @@ use buck2_core::buck2_env;
 use buck2_core::cells::name::CellName;
 use buck2_core::facebook_only;
 use buck2_core::fs::cwd::WorkingDirectory;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
 use buck2_core::fs::project::ProjectRoot;
 use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
 use buck2_core::is_open_source;
@@ -48,6 +53,7 @@ use buck2_execute::execute::blocking::BuckBlockingExecutor;
 use buck2_execute::materialize::materializer::MaterializationMethod;
 use buck2_execute::materialize::materializer::Materializer;
 use buck2_execute::re::manager::ReConnectionManager;
+use buck2_execute_impl::executors::checkpoint::ActionCheckpoints;
 use buck2_execute_impl::materializers::deferred::clean_stale::CleanStaleConfig;
 use buck2_execute_impl::materializers::deferred::AccessTimesUpdates;
 use buck2_execute_impl::materializers::deferred::DeferredMaterializer;
@@ -62,6 +68,8 @@ use buck2_file_watcher::file_watcher::FileWatcher;
 use buck2_forkserver::client::ForkserverClient;
 use buck2_http::HttpClient;
 use buck2_http::HttpClientBuilder;
+use buck2_interpreter::extra::toolchain_probe::HostToolchains;
+use buck2_node::graph_snapshots::GraphSnapshots;
 use buck2_re_configuration::RemoteExecutionStaticMetadata;
 use buck2_re_configuration::RemoteExecutionStaticMetadataImpl;
 use buck2_server_ctx::concurrency::ConcurrencyHandler;
@@ -78,6 +86,7 @@ use crate::ctx::BaseServerCommandContext;
 use crate::daemon::check_working_dir;
 use crate::daemon::disk_state::delete_unknown_disk_state;
 use crate::daemon::disk_state::maybe_initialize_materializer_sqlite_db;
+use crate::daemon::disk_state::maybe_open_action_checkpoints;
 use crate::daemon::disk_state::DiskStateOptions;
 use crate::daemon::forkserver::maybe_launch_forkserver;
 use crate::daemon::io_provider::create_io_provider;
@@ -159,9 +168,27 @@ pub struct DaemonStateData {
     #[allocative(skip)]
     pub create_unhashed_outputs_lock: Arc<Mutex<()>>,
 
+    /// Configured graphs of recent builds, for `cquery --at`.
+    pub graph_snapshots: Arc<GraphSnapshots>,
+
+    /// The response to the last successful build, for no-op builds.
+    pub last_successful_build: Arc<LastSuccessfulBuild>,
+
+    /// Files last sent to installers, for file diffs.
+    pub sent_install_files: Arc<SentInstallFiles>,
+
+    /// Client environment variables read by actions, for `buck2 audit env`.
+    pub action_env_usage: Arc<ActionEnvUsage>,
+
+    /// Toolchains probed on the host by `host_toolchain()`, for `buck2 toolchain list`.
+    pub host_toolchains: Arc<HostToolchains>,
+
     /// A unique identifier for the materializer state.
     pub materializer_state_identity: Option<MaterializerStateIdentity>,
 
+    /// Checkpoints of completed actions, if enabled.
+    pub action_checkpoints: Option<Arc<ActionCheckpoints>>,
+
     /// Whether to enable the restarter. This controls whether the client will attempt to restart
     /// the daemon when we hit an error.
     pub enable_restarter: bool,
@@ -406,6 +433,22 @@ impl DaemonState {
 
                 let clean_stale_config = CleanStaleConfig::from_buck_config(root_config)?;
 
+                let verify_cas_downloads = root_config
+                    .parse(BuckconfigKeyRef {
+                        section: "buck2",
+                        property: "verify_cas_downloads",
+                    })?
+                    .unwrap_or(false);
+
+                let shared_content_store = root_config
+                    .get(BuckconfigKeyRef {
+                        section: "buck2",
+                        property: "shared_content_store",
+                    })
+                    .map(|path| AbsNormPathBuf::new(PathBuf::from(path)))
+                    .transpose()
+                    .context("`buck2.shared_content_store` must be an absolute path")?;
+
                 DeferredMaterializerConfigs {
                     materialize_final_artifacts: matches!(
                         materializations,
@@ -420,6 +463,8 @@ impl DaemonState {
                     update_access_times,
                     verbose_materializer_log,
                     clean_stale_config,
+                    verify_cas_downloads,
+                    shared_content_store,
                 }
             };
 
@@ -457,6 +502,10 @@ impl DaemonState {
             let materializer_state_identity =
                 materializer_db.as_ref().map(|d| d.identity().clone());
 
+            let action_checkpoints = (blocking_executor.dupe() as Arc<dyn BlockingExecutor>)
+                .execute_io_inline(|| maybe_open_action_checkpoints(&disk_state_options, &paths))
+                .await?;
+
             #[cfg(fbcode_build)]
             let re_disable_fallocate = static_metadata.disable_fallocate;
 
@@ -573,6 +622,7 @@ impl DaemonState {
                     disk_state_options.sqlite_materializer_state
                 ),
                 format!("paranoid:{}", paranoid.is_some()),
+                format!("action-checkpoints:{}", action_checkpoints.is_some()),
                 format!("remote-dep-files:{}", remote_dep_files_enabled),
                 #[cfg(fbcode_build)]
                 format!("disable-fallocate:{}", re_disable_fallocate),
@@ -592,7 +642,11 @@ impl DaemonState {
             // disable the eager spawn for watchman until we fix dice commit to avoid a panic TODO(bobyf)
             // tokio::task::spawn(watchman_query.sync());
             Ok(Arc::new(DaemonStateData {
-                dice_manager: ConcurrencyHandler::new(dice),
+                dice_manager: ConcurrencyHandler::new(
+                    dice,
+                    init_ctx.daemon_startup_config.memory_profile,
+                )
+                .with_release_caches(buck2_file_watcher::dep_files::flush_dep_files),
                 file_watcher,
                 io,
                 re_client_manager,
@@ -606,7 +660,13 @@ impl DaemonState {
                 disk_state_options,
                 start_time: std::time::Instant::now(),
                 create_unhashed_outputs_lock,
+                graph_snapshots: Arc::new(GraphSnapshots::default()),
+                last_successful_build: Arc::new(LastSuccessfulBuild::default()),
+                sent_install_files: Arc::new(SentInstallFiles::default()),
+                action_env_usage: Arc::new(ActionEnvUsage::default()),
+                host_toolchains: Arc::new(HostToolchains::default()),
                 materializer_state_identity,
+                action_checkpoints,
                 enable_restarter,
                 http_client,
                 paranoid,
@@ -8,6 +8,7 @@
  */
 
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
 
 use allocative::Allocative;
@@ -22,6 +23,7 @@ use futures::channel::mpsc;
 use futures::channel::mpsc::UnboundedReceiver;
 use futures::channel::mpsc::UnboundedSender;
 use futures::StreamExt;
+use parking_lot::Mutex;
 
 /// The BuckDiceTracker keeps track of the started/finished events for a dice computation and periodically sends a snapshot to the client.
 ///
@@ -35,12 +37,53 @@ use futures::StreamExt;
 pub struct BuckDiceTracker {
     #[allocative(skip)]
     event_forwarder: UnboundedSender<DiceEvent>,
+    /// Set when profiling DICE.
+    #[allocative(skip)]
+    costs: Option<Arc<DiceKeyTypeCosts>>,
+}
+
+/// Number of keys computed and their self time, by key type, over a command. Reported in the
+/// event log when the command finishes, for `buck2 profile dice`.
+#[derive(Default)]
+pub struct DiceKeyTypeCosts {
+    costs: Mutex<HashMap<&'static str, (u64, Duration)>>,
+}
+
+impl DiceKeyTypeCosts {
+    fn record(&self, key_type: &'static str, self_time: Duration) {
+        let mut costs = self.costs.lock();
+        let cost = costs.entry(key_type).or_default();
+        cost.0 += 1;
+        cost.1 += self_time;
+    }
+
+    /// `None` if nothing was computed.
+    pub fn to_profile(&self) -> Option<DiceKeyTypeProfile> {
+        let costs = self.costs.lock();
+        if costs.is_empty() {
+            return None;
+        }
+        let mut key_types: Vec<_> = costs
+            .iter()
+            .map(|(key_type, (computed, self_time))| DiceKeyTypeCost {
+                key_type: (*key_type).to_owned(),
+                computed: *computed,
+                self_duration_us: self_time.as_micros() as u64,
+            })
+            .collect();
+        key_types.sort_by(|a, b| {
+            b.self_duration_us
+                .cmp(&a.self_duration_us)
+                .then_with(|| a.key_type.cmp(&b.key_type))
+        });
+        Some(DiceKeyTypeProfile { key_types })
+    }
 }
 
 const DICE_SNAPSHOT_INTERVAL: Duration = Duration::from_millis(500);
 
 impl BuckDiceTracker {
-    pub fn new(events: EventDispatcher) -> Self {
+    pub fn new(events: EventDispatcher, costs: Option<Arc<DiceKeyTypeCosts>>) -> Self {
         let (event_forwarder, receiver) = mpsc::unbounded();
 
         thread_spawn("buck2-dice-tracker", move || {
@@ -55,7 +98,10 @@ impl BuckDiceTracker {
         })
         .unwrap();
 
-        Self { event_forwarder }
+        Self {
+            event_forwarder,
+            costs,
+        }
     }
 
     async fn run_task(events: EventDispatcher, mut receiver: UnboundedReceiver<DiceEvent>) {
@@ -113,4 +159,37 @@ impl DiceEventListener for BuckDiceTracker {
     fn event(&self, event: DiceEvent) {
         let _ = self.event_forwarder.unbounded_send(event);
     }
+
+    fn records_compute_times(&self) -> bool {
+        self.costs.is_some()
+    }
+
+    fn computed(&self, key_type: &'static str, self_time: Duration) {
+        if let Some(costs) = &self.costs {
+            costs.record(key_type, self_time);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dice_key_type_costs() {
+        let costs = DiceKeyTypeCosts::default();
+        assert_eq!(None, costs.to_profile());
+
+        costs.record("A", Duration::from_micros(10));
+        costs.record("B", Duration::from_micros(30));
+        costs.record("A", Duration::from_micros(5));
+
+        let profile = costs.to_profile().unwrap();
+        let got: Vec<_> = profile
+            .key_types
+            .iter()
+            .map(|c| (c.key_type.as_str(), c.computed, c.self_duration_us))
+            .collect();
+        assert_eq!(vec![("B", 1, 30), ("A", 2, 15)], got);
+    }
 }
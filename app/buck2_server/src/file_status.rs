@@ -7,9 +7,12 @@
  * of this source tree.
  */
 
+use std::collections::HashSet;
 use std::fmt;
 use std::io::Write;
 use std::path::Path;
+use std::sync::Arc;
+use std::time::SystemTime;
 
 use async_recursion::async_recursion;
 use async_trait::async_trait;
@@ -18,20 +21,24 @@ use buck2_common::dice::file_ops::DiceFileOps;
 use buck2_common::file_ops::FileOps;
 use buck2_common::file_ops::RawPathMetadata;
 use buck2_common::file_ops::RawSymlink;
+use buck2_common::ignores::file_ignores::FileIgnoreResult;
 use buck2_common::io::fs::FsIoProvider;
 use buck2_common::io::IoProvider;
+use buck2_core::cells::cell_path::CellPath;
 use buck2_core::cells::CellResolver;
 use buck2_core::fs::paths::abs_path::AbsPath;
 use buck2_core::fs::paths::file_name::FileName;
 use buck2_core::fs::paths::file_name::FileNameBuf;
 use buck2_core::fs::project_rel_path::ProjectRelativePath;
 use buck2_execute::digest_config::HasDigestConfig;
+use buck2_file_watcher::recent_events;
 use buck2_server_ctx::ctx::ServerCommandContextTrait;
 use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
 use buck2_server_ctx::stdout_partial_output::StdoutPartialOutput;
 use buck2_server_ctx::template::run_server_command;
 use buck2_server_ctx::template::ServerCommandTemplate;
 use buck2_util::commas::commas;
+use dice::Dice;
 use dice::DiceTransaction;
 use dupe::Dupe;
 use gazebo::variants::VariantName;
@@ -43,8 +50,9 @@ pub(crate) async fn file_status_command(
     partial_result_dispatcher: PartialResultDispatcher<buck2_cli_proto::StdoutBytes>,
     req: buck2_cli_proto::FileStatusRequest,
 ) -> anyhow::Result<buck2_cli_proto::GenericResponse> {
+    let dice = ctx.base_context.daemon.dice_manager.unsafe_dice().dupe();
     run_server_command(
-        FileStatusServerCommand { req },
+        FileStatusServerCommand { req, dice },
         ctx,
         partial_result_dispatcher,
     )
@@ -52,6 +60,8 @@ pub(crate) async fn file_status_command(
 }
 struct FileStatusServerCommand {
     req: buck2_cli_proto::FileStatusRequest,
+    /// Used to find the dependents of paths, see `dice_dependents`.
+    dice: Arc<Dice>,
 }
 
 struct FileStatusResult<'a> {
@@ -137,6 +147,26 @@ impl ServerCommandTemplate for FileStatusServerCommand {
 
         let mut stderr = server_ctx.stderr()?;
 
+        if self.req.report {
+            let dependents = if self.req.dependents {
+                writeln!(&mut stderr, "Walking the DICE graph...")?;
+                Some(DiceDependents::new(&self.dice)?)
+            } else {
+                None
+            };
+            for path in &self.req.paths {
+                let path = project_root.relativize_any(AbsPath::new(Path::new(path))?)?;
+                let report = ctx
+                    .with_linear_recompute(|ctx| async move {
+                        file_report(&DiceFileOps(&ctx), cell_resolver, io, &path).await
+                    })
+                    .await?;
+                let report = report.into_json(dependents.as_ref());
+                writeln!(result.stdout, "{}", serde_json::to_string(&report)?)?;
+            }
+            return Ok(buck2_cli_proto::GenericResponse {});
+        }
+
         for path in &self.req.paths {
             let path = project_root.relativize_any(AbsPath::new(Path::new(path))?)?;
             writeln!(&mut stderr, "Check file status: {}", path)?;
@@ -273,3 +303,167 @@ async fn check_file_status(
 
     Ok(())
 }
+
+/// What the daemon knows about a path, for `--report`.
+struct FileReport {
+    path: String,
+    cell_path: CellPath,
+    ignored: Option<String>,
+    fs: Option<String>,
+    dice: Option<String>,
+    up_to_date: bool,
+}
+
+impl FileReport {
+    fn into_json(self, dependents: Option<&DiceDependents>) -> serde_json::Value {
+        let cell_path = self.cell_path.to_string();
+        let last_event = recent_events::last_event(&cell_path).map(|e| {
+            serde_json::json!({
+                "event": e.event.as_str_name(),
+                "kind": e.kind.as_str_name(),
+                "seconds_ago": SystemTime::now()
+                    .duration_since(e.time)
+                    .map_or(0, |d| d.as_secs()),
+            })
+        });
+        let dependents = dependents.map(|d| d.dependents_of(&cell_path));
+        let mut report = serde_json::json!({
+            "path": self.path,
+            "cell_path": cell_path,
+            "ignored": self.ignored,
+            "fs": self.fs,
+            "dice": self.dice,
+            "up_to_date": self.up_to_date,
+            "last_event": last_event,
+        });
+        if let Some(dependents) = dependents {
+            report["dependents"] = dependents.into();
+        }
+        report
+    }
+}
+
+fn describe_metadata<T: fmt::Display>(metadata: &RawPathMetadata<T>) -> String {
+    match metadata {
+        RawPathMetadata::File(file) => file.to_string(),
+        RawPathMetadata::Directory => "Directory".to_owned(),
+        RawPathMetadata::Symlink {
+            to: RawSymlink::Relative(to),
+            ..
+        } => format!("Symlink(to={})", to),
+        RawPathMetadata::Symlink {
+            to: RawSymlink::External(to),
+            ..
+        } => format!("Symlink(to={})", to),
+    }
+}
+
+async fn file_report(
+    file_ops: &dyn FileOps,
+    cell_resolver: &CellResolver,
+    io: &dyn IoProvider,
+    path: &ProjectRelativePath,
+) -> anyhow::Result<FileReport> {
+    let cell_path = cell_resolver.get_cell_path(path)?;
+    let ignored = match file_ops.is_ignored(cell_path.as_ref()).await? {
+        FileIgnoreResult::Ok => None,
+        FileIgnoreResult::Ignored(reason) => Some(reason.describe()),
+    };
+
+    let fs_metadata = io.read_path_metadata_if_exists(path.to_owned()).await?;
+    let dice_metadata = if ignored.is_some() {
+        None
+    } else {
+        file_ops
+            .read_path_metadata_if_exists(cell_path.as_ref())
+            .await?
+    };
+
+    let up_to_date = match (&fs_metadata, &dice_metadata) {
+        (None, None) => true,
+        (Some(RawPathMetadata::File(fs)), Some(RawPathMetadata::File(dice))) => fs == dice,
+        (Some(RawPathMetadata::Directory), Some(RawPathMetadata::Directory)) => true,
+        (
+            Some(RawPathMetadata::Symlink { to: fs_to, .. }),
+            Some(RawPathMetadata::Symlink { to: dice_to, .. }),
+        ) => match (fs_to, dice_to) {
+            (RawSymlink::Relative(fs), RawSymlink::Relative(dice)) => {
+                *fs == cell_resolver.resolve_path(dice.as_ref().as_ref())?
+            }
+            (RawSymlink::External(fs), RawSymlink::External(dice)) => fs == dice,
+            _ => false,
+        },
+        _ => false,
+    };
+
+    Ok(FileReport {
+        path: path.to_string(),
+        cell_path,
+        ignored,
+        fs: fs_metadata.as_ref().map(describe_metadata),
+        dice: dice_metadata.as_ref().map(describe_metadata),
+        // Ignored paths are not tracked, so there is nothing to be out of date.
+        up_to_date: up_to_date || ignored.is_some(),
+    })
+}
+
+/// Reverse edges of the DICE graph, from a dump of the whole graph: DICE has no API to query
+/// the dependents of a single key.
+struct DiceDependents {
+    /// `(short type name, key)` by node index.
+    nodes: Vec<(String, String)>,
+    /// Dependents by node index.
+    rdeps: Vec<Vec<usize>>,
+}
+
+impl DiceDependents {
+    fn new(dice: &Dice) -> anyhow::Result<DiceDependents> {
+        let mut nodes_tsv = Vec::new();
+        let mut edges_tsv = Vec::new();
+        dice.serialize_tsv(&mut nodes_tsv, &mut edges_tsv, std::io::sink())?;
+
+        let mut nodes = Vec::new();
+        for line in String::from_utf8_lossy(&nodes_tsv).lines() {
+            let mut fields = line.splitn(3, '\t');
+            let (Some(_), Some(type_name), Some(key)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            nodes.push((type_name.to_owned(), key.to_owned()));
+        }
+
+        let mut rdeps = vec![Vec::new(); nodes.len()];
+        for line in String::from_utf8_lossy(&edges_tsv).lines() {
+            let Some((node, dep)) = line.split_once('\t') else {
+                continue;
+            };
+            let (Ok(node), Ok(dep)) = (node.parse::<usize>(), dep.parse::<usize>()) else {
+                continue;
+            };
+            if let Some(rdeps) = rdeps.get_mut(dep) {
+                rdeps.push(node);
+            }
+        }
+
+        Ok(DiceDependents { nodes, rdeps })
+    }
+
+    /// Keys which directly depend on the file operation keys of `cell_path`, as
+    /// `TypeName(key)`, sorted.
+    fn dependents_of(&self, cell_path: &str) -> Vec<String> {
+        let mut res = HashSet::new();
+        for (i, (_, key)) in self.nodes.iter().enumerate() {
+            if key != cell_path {
+                continue;
+            }
+            for &rdep in &self.rdeps[i] {
+                let (type_name, key) = &self.nodes[rdep];
+                res.insert(format!("{}({})", type_name, key));
+            }
+        }
+        let mut res: Vec<_> = res.into_iter().collect();
+        res.sort();
+        res
+    }
+}
@@ -60,7 +60,7 @@ pub fn get_host_info(
                 .context("Constructing `XcodeVersionInfo` from string.")?,
         ),
         None if interpreter_platform == InterpreterHostPlatform::MacOS => {
-            match XcodeVersionInfo::new()
+            match XcodeVersionInfo::selected()
                 .context("Constructing `XcodeVersionInfo` using host platform MacOS.")
             {
                 Ok(v) => v,
@@ -15,7 +15,9 @@
 pub mod active_commands;
 pub mod builtin_docs;
 mod clean_stale;
+mod clock_check;
 mod configs;
+mod corrupt_scan;
 mod ctx;
 pub mod daemon;
 mod dice_tracker;
@@ -30,4 +32,6 @@ pub(crate) mod new_generic;
 pub mod profile;
 mod snapshot;
 mod subscription;
+mod toolchain_list;
 mod trace_io;
+mod why_miss;
@@ -14,8 +14,12 @@ use buck2_server_ctx::other_server_commands::OTHER_SERVER_COMMANDS;
 use buck2_server_ctx::partial_result_dispatcher::NoPartialResult;
 use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
 
+use crate::clock_check::clock_check_command;
+use crate::corrupt_scan::corrupt_scan_command;
 use crate::ctx::ServerCommandContext;
 use crate::materialize::materialize_command;
+use crate::toolchain_list::toolchain_list_command;
+use crate::why_miss::why_miss_command;
 
 pub(crate) async fn new_generic_command(
     context: &ServerCommandContext<'_>,
@@ -44,6 +48,24 @@ pub(crate) async fn new_generic_command(
                 .expand_external_cell(context, partial_result_dispatcher, e)
                 .await?,
         ),
+        NewGenericRequest::Verify(v) => NewGenericResponse::Verify(
+            OTHER_SERVER_COMMANDS
+                .get()?
+                .verify(context, partial_result_dispatcher, v)
+                .await?,
+        ),
+        NewGenericRequest::CorruptScan(c) => {
+            NewGenericResponse::CorruptScan(corrupt_scan_command(context, c).await?)
+        }
+        NewGenericRequest::ToolchainList(t) => {
+            NewGenericResponse::ToolchainList(toolchain_list_command(context, t).await?)
+        }
+        NewGenericRequest::ClockCheck(c) => {
+            NewGenericResponse::ClockCheck(clock_check_command(context, c).await?)
+        }
+        NewGenericRequest::WhyMiss(w) => {
+            NewGenericResponse::WhyMiss(why_miss_command(context, w).await?)
+        }
     };
     let resp = serde_json::to_string(&resp).context("Could not serialize `NewGenericResponse`")?;
     Ok(buck2_cli_proto::NewGenericResponseMessage {
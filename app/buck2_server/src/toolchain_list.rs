@@ -0,0 +1,68 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use anyhow::Context;
+use buck2_cli_proto::new_generic::HostToolchainEntry;
+use buck2_cli_proto::new_generic::ToolchainListRequest;
+use buck2_cli_proto::new_generic::ToolchainListResponse;
+use buck2_events::dispatch::span_async;
+use buck2_interpreter::extra::toolchain_probe::probe_host_toolchains;
+use buck2_interpreter::extra::toolchain_probe::HasHostToolchains;
+use buck2_interpreter::extra::toolchain_probe::HostToolchainProbe;
+use buck2_server_ctx::command_end::command_end;
+use buck2_server_ctx::ctx::ServerCommandContextTrait;
+use dice::DiceTransaction;
+
+use crate::ctx::ServerCommandContext;
+
+pub(crate) async fn toolchain_list_command(
+    context: &ServerCommandContext<'_>,
+    req: ToolchainListRequest,
+) -> anyhow::Result<ToolchainListResponse> {
+    let start_event = buck2_data::CommandStart {
+        metadata: context.request_metadata().await?,
+        data: Some(buck2_data::ToolchainListCommandStart {}.into()),
+    };
+    span_async(start_event, async move {
+        let result = context
+            .with_dice_ctx(|_server_ctx, ctx| toolchain_list(ctx, req))
+            .await
+            .context("Failed to list host toolchains")
+            .map_err(Into::into);
+        let end_event = command_end(&result, buck2_data::ToolchainListCommandEnd {});
+        (result.map_err(Into::into), end_event)
+    })
+    .await
+}
+
+async fn toolchain_list(
+    mut ctx: DiceTransaction,
+    _req: ToolchainListRequest,
+) -> anyhow::Result<ToolchainListResponse> {
+    // Make sure the built-in toolchains are listed even if nothing requested them yet.
+    let builtin = HostToolchainProbe::BUILTIN
+        .iter()
+        .filter_map(|name| HostToolchainProbe::builtin(name))
+        .collect();
+    probe_host_toolchains(&mut ctx, builtin).await?;
+
+    let toolchains = ctx.per_transaction_data().get_host_toolchains();
+    let toolchains = tokio::task::spawn_blocking(move || toolchains.probed())
+        .await??
+        .into_iter()
+        .map(|p| HostToolchainEntry {
+            name: p.probe.name,
+            binaries: p.probe.binaries,
+            path: p.result.as_ref().map(|r| r.path.clone()),
+            version: p.result.and_then(|r| r.version),
+            used_by: p.used_by,
+        })
+        .collect();
+    Ok(ToolchainListResponse { toolchains })
+}
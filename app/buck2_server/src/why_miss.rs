@@ -0,0 +1,151 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! `buck2 debug why-miss`: explain why the digest of an action changed, by comparing what it was
+//! computed from to the previous version of the action prepared by this daemon.
+
+use buck2_cli_proto::new_generic::WhyMissDifference;
+use buck2_cli_proto::new_generic::WhyMissRequest;
+use buck2_cli_proto::new_generic::WhyMissResponse;
+use buck2_events::dispatch::span_async;
+use buck2_execute::execute::action_digest_history::action_digest_history;
+use buck2_execute::execute::action_digest_history::diff_action_digest_records;
+use buck2_execute::execute::action_digest_history::ActionDigestHistoryKey;
+use buck2_server_ctx::command_end::command_end;
+use buck2_server_ctx::ctx::ServerCommandContextTrait;
+use itertools::Itertools;
+
+use crate::ctx::ServerCommandContext;
+
+/// Maximum number of matching actions listed when the request is ambiguous.
+const MAX_LISTED_ACTIONS: usize = 10;
+
+#[derive(Debug, buck2_error::Error)]
+enum WhyMissError {
+    #[buck2(input)]
+    #[error(
+        "No action matching `{0}` was executed since the daemon started: action digests are only \
+        recorded in memory with `build.record_action_digests = true`, build the action again and \
+        retry"
+    )]
+    NotFound(String),
+    #[buck2(input)]
+    #[error("{0} actions match `{1}`, pass `--category` and `--identifier` to select one:\n{2}")]
+    Ambiguous(usize, String, String),
+}
+
+pub(crate) async fn why_miss_command(
+    context: &ServerCommandContext<'_>,
+    req: WhyMissRequest,
+) -> anyhow::Result<WhyMissResponse> {
+    let start_event = buck2_data::CommandStart {
+        metadata: context.request_metadata().await?,
+        data: Some(buck2_data::WhyMissCommandStart {}.into()),
+    };
+    span_async(start_event, async move {
+        let result = why_miss(req).map_err(Into::into);
+        let end_event = command_end(&result, buck2_data::WhyMissCommandEnd {});
+        (result.map_err(Into::into), end_event)
+    })
+    .await
+}
+
+fn why_miss(req: WhyMissRequest) -> anyhow::Result<WhyMissResponse> {
+    let selected = |key: &ActionDigestHistoryKey| {
+        req.category.as_ref().map_or(true, |c| *c == key.category)
+            && req
+                .identifier
+                .as_ref()
+                .map_or(true, |i| Some(i) == key.identifier.as_ref())
+    };
+
+    let (key, records, index) = if is_action_digest(&req.action) {
+        action_digest_history(selected)
+            .into_iter()
+            .find_map(|(key, records)| {
+                let index = records
+                    .iter()
+                    .position(|r| r.digest.to_string() == req.action)?;
+                Some((key, records, index))
+            })
+            .ok_or_else(|| WhyMissError::NotFound(req.action.clone()))?
+    } else {
+        let owner_prefix = format!("{} (", req.action);
+        let mut matches = action_digest_history(|key| {
+            (key.owner == req.action || key.owner.starts_with(&owner_prefix)) && selected(key)
+        });
+        match matches.len() {
+            0 => return Err(WhyMissError::NotFound(req.action).into()),
+            1 => {}
+            n => {
+                return Err(WhyMissError::Ambiguous(
+                    n,
+                    req.action,
+                    matches
+                        .iter()
+                        .take(MAX_LISTED_ACTIONS)
+                        .map(|(key, _)| format!("  {}", key))
+                        .join("\n"),
+                )
+                .into());
+            }
+        }
+        let (key, records) = matches.pop().unwrap();
+        let index = records.len() - 1;
+        (key, records, index)
+    };
+
+    let current = &records[index];
+    let previous = index.checked_sub(1).map(|i| &records[i]);
+    let differences = match previous {
+        Some(previous) => diff_action_digest_records(previous, current)
+            .into_iter()
+            .map(|d| WhyMissDifference {
+                kind: d.kind.to_owned(),
+                name: d.name,
+                old: d.old,
+                new: d.new,
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    Ok(WhyMissResponse {
+        action: key.to_string(),
+        digest: current.digest.to_string(),
+        previous_digest: previous.map(|p| p.digest.to_string()),
+        differences,
+    })
+}
+
+/// Whether `s` looks like an action digest (`hash:size`) rather than a target label.
+fn is_action_digest(s: &str) -> bool {
+    match s.split_once(':') {
+        Some((hash, size)) => {
+            !hash.is_empty()
+                && hash.chars().all(|c| c.is_ascii_hexdigit())
+                && size.parse::<u64>().is_ok()
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_action_digest() {
+        assert!(is_action_digest(
+            "5c5e8d1b0b3b5e4b3c1f3c0a3f4f5a9bb4c6b3ad:142"
+        ));
+        assert!(!is_action_digest("cell//pkg:foo"));
+        assert!(!is_action_digest("//pkg:123"));
+        assert!(!is_action_digest("abcdef"));
+    }
+}
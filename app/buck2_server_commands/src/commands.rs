@@ -12,8 +12,10 @@ pub mod ctargets;
 pub mod debug_eval;
 pub mod expand_external_cell;
 pub mod explain;
+pub(crate) mod graph_snapshot;
 pub(crate) mod init_commands;
 pub mod install;
 pub mod query;
 pub mod targets;
 pub mod targets_show_outputs;
+pub mod verify;
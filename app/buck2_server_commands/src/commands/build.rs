@@ -7,9 +7,12 @@
  * of this source tree.
  */
 
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
 use std::io::BufWriter;
 use std::io::Write;
-use std::sync::Arc;
 
 use anyhow::Context as _;
 use async_trait::async_trait;
@@ -20,9 +23,20 @@ use buck2_artifact::artifact::artifact_dump::ExternalSymlinkInfo;
 use buck2_artifact::artifact::artifact_dump::FileInfo;
 use buck2_artifact::artifact::artifact_dump::SymlinkInfo;
 use buck2_build_api::actions::artifact::get_artifact_fs::GetArtifactFs;
+use buck2_build_api::actions::execute::cache_hit_stats::cache_hit_rate;
+use buck2_build_api::actions::execute::cache_hit_stats::HasActionCacheHitStats;
 use buck2_build_api::build;
+use buck2_build_api::build::aspects::parse_aspects;
 use buck2_build_api::build::build_report::generate_build_report;
+use buck2_build_api::build::build_report::BuildReportCacheHits;
 use buck2_build_api::build::build_report::BuildReportOpts;
+use buck2_build_api::build::dependency_failure::dependency_chains;
+use buck2_build_api::build::dependency_failure::display_root_cause;
+use buck2_build_api::build::dependency_failure::failure_cause;
+use buck2_build_api::build::dependency_failure::is_label_of;
+use buck2_build_api::build::dependency_failure::FailureCause;
+use buck2_build_api::build::dependency_failure::TargetFailure;
+use buck2_build_api::build::last_successful_build::HasLastSuccessfulBuild;
 use buck2_build_api::build::BuildEvent;
 use buck2_build_api::build::BuildTargetResult;
 use buck2_build_api::build::ConfiguredBuildEvent;
@@ -39,29 +53,35 @@ use buck2_common::dice::cells::HasCellResolver;
 use buck2_common::global_cfg_options::GlobalCfgOptions;
 use buck2_common::legacy_configs::dice::HasLegacyConfigs;
 use buck2_common::legacy_configs::key::BuckconfigKeyRef;
-use buck2_common::pattern::parse_from_cli::parse_patterns_from_cli_args;
+use buck2_common::pattern::parse_from_cli::parse_pattern_operations_from_cli_args;
+use buck2_common::pattern::resolve::PatternOperation;
 use buck2_common::pattern::resolve::ResolveTargetPatterns;
 use buck2_common::pattern::resolve::ResolvedPattern;
+use buck2_core::configuration::compatibility::MaybeCompatible;
 use buck2_core::directory::Directory;
 use buck2_core::directory::DirectoryEntry;
 use buck2_core::directory::DirectoryIterator;
 use buck2_core::fs::artifact_path_resolver::ArtifactFs;
 use buck2_core::package::PackageLabel;
 use buck2_core::pattern::pattern::PackageSpec;
-use buck2_core::pattern::pattern::ParsedPattern;
 use buck2_core::pattern::pattern_type::ConfiguredProvidersPatternExtra;
 use buck2_core::pattern::pattern_type::ProvidersPatternExtra;
 use buck2_core::provider::label::ProvidersLabel;
 use buck2_core::provider::label::ProvidersName;
+use buck2_core::target::configured_target_label::ConfiguredTargetLabel;
 use buck2_core::target::label::label::TargetLabel;
 use buck2_error::BuckErrorContext;
 use buck2_events::dispatch::console_message;
 use buck2_events::dispatch::span_async;
 use buck2_events::errors::create_error_report;
+use buck2_execute::digest_config::HasDigestConfig;
 use buck2_execute::directory::ActionDirectoryBuilder;
 use buck2_execute::directory::ActionDirectoryMember;
+use buck2_execute::materialize::materializer::HasMaterializer;
 use buck2_node::configured_universe::CqueryUniverse;
+use buck2_node::deprecation::check_build_deprecations;
 use buck2_node::load_patterns::MissingTargetBehavior;
+use buck2_node::nodes::configured_frontend::ConfiguredTargetNodeCalculation;
 use buck2_node::nodes::frontend::TargetGraphCalculation;
 use buck2_node::nodes::unconfigured::TargetNode;
 use buck2_node::target_calculation::ConfiguredTargetCalculation;
@@ -71,6 +91,7 @@ use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
 use buck2_server_ctx::target_resolution_config::TargetResolutionConfig;
 use buck2_server_ctx::template::run_server_command;
 use buck2_server_ctx::template::ServerCommandTemplate;
+use dice::DiceComputations;
 use dice::DiceTransaction;
 use dice::LinearRecomputeDiceComputations;
 use dupe::Dupe;
@@ -83,10 +104,20 @@ use itertools::Itertools;
 use serde::ser::SerializeSeq;
 use serde::ser::Serializer;
 
+use crate::commands::build::cache_hit_rate::check_cache_hit_rate;
+use crate::commands::build::cache_hit_rate::LowCacheHitRate;
+use crate::commands::build::last_build_links::create_last_build_links;
+use crate::commands::build::noop::NoopBuildChecksum;
+use crate::commands::build::offloaded_outputs::declare_offloaded_outputs;
 use crate::commands::build::result_report::ResultReporter;
 use crate::commands::build::result_report::ResultReporterOptions;
 use crate::commands::build::unhashed_outputs::create_unhashed_outputs;
+use crate::commands::graph_snapshot;
 
+mod cache_hit_rate;
+mod last_build_links;
+mod noop;
+mod offloaded_outputs;
 #[allow(unused)]
 mod result_report;
 mod unhashed_outputs;
@@ -152,7 +183,19 @@ async fn dump_artifacts_to_file(
     artifact_fs: &ArtifactFs,
 ) -> anyhow::Result<()> {
     let file = std::fs::File::create(path).context("Failed to create output hash file")?;
-    let writer = BufWriter::new(file);
+    let mut writer = BufWriter::new(file);
+    write_output_hashes(&mut writer, provider_artifacts, artifact_fs)?;
+    writer.flush().context("Failed to flush output hash file")?;
+    Ok(())
+}
+
+/// Write the outputs in `provider_artifacts` with their digests to `writer`, as a JSON list in
+/// the format of `--output-hashes-file`.
+fn write_output_hashes(
+    writer: impl Write,
+    provider_artifacts: &[ProviderArtifacts],
+    artifact_fs: &ArtifactFs,
+) -> anyhow::Result<()> {
     let mut ser = serde_json::Serializer::new(writer);
     let mut seq = ser
         .serialize_seq(None)
@@ -196,9 +239,6 @@ async fn dump_artifacts_to_file(
 
     seq.end()
         .context("Failed to write vec end to output hash file")?;
-    ser.into_inner()
-        .flush()
-        .context("Failed to flush output hash file")?;
     Ok(())
 }
 
@@ -213,12 +253,23 @@ async fn build(
 
     let cell_resolver = ctx.get_cell_resolver().await?;
 
-    let parsed_patterns: Vec<ParsedPattern<ConfiguredProvidersPatternExtra>> =
-        parse_patterns_from_cli_args(&mut ctx, &request.target_patterns, cwd).await?;
-    server_ctx.log_target_pattern(&parsed_patterns);
+    let noop_build_early_exit = ctx
+        .parse_legacy_config_property(
+            cell_resolver.root_cell(),
+            BuckconfigKeyRef {
+                section: "buck2",
+                property: "noop_build_early_exit",
+            },
+        )
+        .await?
+        .unwrap_or(false);
+
+    let pattern_operations: Vec<PatternOperation<ConfiguredProvidersPatternExtra>> =
+        parse_pattern_operations_from_cli_args(&mut ctx, &request.target_patterns, cwd).await?;
+    server_ctx.log_target_pattern(&PatternOperation::included(&pattern_operations));
 
     let resolved_pattern: ResolvedPattern<ConfiguredProvidersPatternExtra> =
-        ResolveTargetPatterns::resolve(&mut ctx, &parsed_patterns).await?;
+        ResolveTargetPatterns::resolve_operations(&mut ctx, &pattern_operations).await?;
 
     let target_resolution_config = TargetResolutionConfig::from_args(
         &mut ctx,
@@ -231,7 +282,9 @@ async fn build(
     )
     .await?;
 
-    let build_providers = Arc::new(request.build_providers.clone().unwrap());
+    let mut providers_to_build =
+        build_providers_to_providers_to_build(request.build_providers.as_ref().unwrap());
+    providers_to_build.aspects = parse_aspects(cwd, &request.aspects, &cell_resolver)?;
 
     let final_artifact_materializations =
         Materializations::from_i32(request.final_artifact_materializations)
@@ -250,6 +303,48 @@ async fn build(
         )
         .await?
         .unwrap_or_default();
+    let snapshots_to_keep =
+        graph_snapshot::snapshots_to_keep(&mut ctx, cell_resolver.root_cell()).await?;
+
+    check_deprecations(&mut ctx, &resolved_pattern).await?;
+
+    let last_successful_build = ctx.per_transaction_data().get_last_successful_build();
+    let noop_checksum = if noop_build_early_exit {
+        let materialized_generation = ctx
+            .per_transaction_data()
+            .get_materializer()
+            .materialized_generation();
+        NoopBuildChecksum::new(
+            &mut ctx,
+            materialized_generation,
+            request,
+            cwd,
+            &resolved_pattern,
+            &target_resolution_config,
+            &providers_to_build,
+        )
+        .await
+    } else {
+        None
+    };
+    if let Some(checksum) = &noop_checksum {
+        if let Some(response) = last_successful_build.up_to_date_response(checksum.as_bytes()) {
+            console_message("Everything up to date".to_owned());
+            return Ok(response);
+        }
+    }
+
+    if !request.offloaded_outputs.is_empty() {
+        let artifact_fs = ctx.get_artifact_fs().await?;
+        declare_offloaded_outputs(
+            &*ctx.per_transaction_data().get_materializer(),
+            ctx.global_data().get_digest_config(),
+            artifact_fs.buck_out_path_resolver().root(),
+            &request.offloaded_outputs,
+            request.final_artifact_materializations != Materializations::Skip as i32,
+        )
+        .await?;
+    }
 
     let build_result = ctx
         .with_linear_recompute(|ctx| async move {
@@ -257,7 +352,7 @@ async fn build(
                 &ctx,
                 resolved_pattern,
                 target_resolution_config,
-                build_providers,
+                &providers_to_build,
                 &materialization_context,
                 build_opts.fail_fast,
                 MissingTargetBehavior::from_skip(build_opts.skip_missing_targets),
@@ -268,7 +363,24 @@ async fn build(
         })
         .await?;
 
-    process_build_result(server_ctx, ctx, request, build_result).await
+    if snapshots_to_keep > 0 {
+        graph_snapshot::record_snapshot(
+            &mut ctx,
+            server_ctx.events().trace_id().to_string(),
+            build_result
+                .configured
+                .keys()
+                .map(|label| label.target().dupe()),
+            snapshots_to_keep,
+        )
+        .await?;
+    }
+
+    let response = process_build_result(server_ctx, ctx, request, build_result).await?;
+    if let Some(checksum) = noop_checksum {
+        last_successful_build.record_build(*checksum.as_bytes(), &response);
+    }
+    Ok(response)
 }
 
 async fn process_build_result(
@@ -295,6 +407,18 @@ async fn process_build_result(
         &build_result,
     );
 
+    let target_failures = classify_failures(&mut ctx, &build_result).await?;
+
+    let cache_hits = ctx
+        .per_transaction_data()
+        .get_action_cache_hit_stats()
+        .map(|stats| stats.by_category())
+        .unwrap_or_default();
+    let hit_rate = cache_hit_rate(cache_hits.values());
+    let low_cache_hit_rate = request
+        .min_cache_hit_rate
+        .and_then(|min_hit_rate| check_cache_hit_rate(hit_rate, min_hit_rate, &cache_hits));
+
     let serialized_build_report = if build_opts.unstable_print_build_report {
         let esto = &build_opts.unstable_build_report_filename;
         let build_report_opts = BuildReportOpts {
@@ -334,11 +458,53 @@ async fn process_build_result(
             server_ctx.events().trace_id(),
             &build_result.configured,
             &build_result.other_errors,
+            &target_failures,
+            Some(BuildReportCacheHits {
+                hit_rate,
+                min_hit_rate: request.min_cache_hit_rate,
+                by_category: cache_hits,
+            }),
         )?
     } else {
         None
     };
 
+    let succeeded = result_reports.build_errors.errors.is_empty() && target_failures.is_empty();
+    let materialized = request.final_artifact_materializations != Materializations::Skip as i32;
+    if succeeded && materialized {
+        let should_create_last_build_links = match request.last_build_links {
+            Some(links) => links,
+            None => ctx
+                .parse_legacy_config_property(
+                    cell_resolver.root_cell(),
+                    BuckconfigKeyRef {
+                        section: "buck2",
+                        property: "last_build_links",
+                    },
+                )
+                .await?
+                .unwrap_or(false),
+        };
+        if should_create_last_build_links {
+            let lock = ctx
+                .per_transaction_data()
+                .get_create_unhashed_symlink_lock();
+            let _guard = lock.lock().await;
+            // The build itself succeeded, so this should not fail it.
+            if let Err(e) = create_last_build_links(
+                &build_result.configured,
+                &artifact_fs,
+                fs,
+                &server_ctx.events().trace_id().to_string(),
+            ) {
+                console_message(format!(
+                    "Warning: failed to update last build links: {:#}",
+                    e
+                ));
+            }
+        }
+    }
+
     let mut provider_artifacts = Vec::new();
     for v in build_result.configured.into_values() {
         // We omit skipped targets here.
@@ -350,6 +516,24 @@ async fn process_build_result(
         provider_artifacts.extend(&mut outputs);
     }
 
+    let action_digests = if response_options.return_action_digests {
+        let artifacts: Vec<_> = provider_artifacts
+            .iter()
+            .flat_map(|p| p.values.iter().map(|(artifact, _)| artifact))
+            .collect();
+        build::action_digests::action_digests(&mut ctx, artifacts).await?
+    } else {
+        Vec::new()
+    };
+
+    let output_hashes = if response_options.return_output_hashes {
+        let mut output_hashes = Vec::new();
+        write_output_hashes(&mut output_hashes, &provider_artifacts, &artifact_fs)?;
+        Some(String::from_utf8(output_hashes)?)
+    } else {
+        None
+    };
+
     if let Some(output_hashes_file) = &request.output_hashes_file {
         span_async(buck2_data::CreateOutputHashesFileStart {}, async {
             let res = dump_artifacts_to_file(output_hashes_file, &provider_artifacts, &artifact_fs)
@@ -390,29 +574,173 @@ async fn process_build_result(
     }
 
     let build_targets = result_reports.build_targets;
-    let errors = result_reports
+    let mut errors: Vec<_> = result_reports
         .build_errors
         .errors
         .iter()
         .map(create_error_report)
         .unique_by(|e| e.message.clone())
         .collect();
+    if let Some(message) = low_cache_hit_rate {
+        if request.warn_on_low_cache_hit_rate {
+            console_message(message);
+        } else {
+            errors.push(create_error_report(&LowCacheHitRate(message).into()));
+        }
+    }
 
     let project_root = server_ctx.project_root().to_string();
 
+    let failures = target_failures
+        .into_iter()
+        .map(|(target, failure)| {
+            let (skipped_due_to_failed_dependency, root_cause_chain) = match failure {
+                TargetFailure::Failed => (false, Vec::new()),
+                TargetFailure::SkippedDueToFailedDependency { root_cause_chain } => {
+                    (true, root_cause_chain)
+                }
+            };
+            buck2_cli_proto::BuildFailure {
+                target: target.to_string(),
+                skipped_due_to_failed_dependency,
+                root_cause_chain,
+            }
+        })
+        .collect();
+
     Ok(buck2_cli_proto::BuildResponse {
         build_targets,
         project_root,
         serialized_build_report,
         errors,
+        failures,
+        action_digests,
+        output_hashes,
     })
 }
 
+/// Splits the targets that did not build into those that failed themselves and those that were
+/// skipped because a dependency failed.
+async fn classify_failures(
+    ctx: &mut DiceComputations<'_>,
+    build_result: &BuildTargetResult,
+) -> anyhow::Result<BTreeMap<ConfiguredTargetLabel, TargetFailure>> {
+    let mut failures = BTreeMap::new();
+    let mut skipped = BTreeMap::new();
+    for (label, result) in &build_result.configured {
+        // We omit skipped targets here.
+        let Some(result) = result else { continue };
+        let target = label.target();
+        match failure_cause(target, result) {
+            None => {}
+            Some(FailureCause::Target) => {
+                skipped.remove(target);
+                failures.insert(target.dupe(), TargetFailure::Failed);
+            }
+            Some(FailureCause::Dependency(root_cause)) => {
+                if !failures.contains_key(target) {
+                    skipped.entry(target.dupe()).or_insert(root_cause);
+                }
+            }
+        }
+    }
+    if skipped.is_empty() {
+        return Ok(failures);
+    }
+
+    // Find the dependency chains with one traversal of the graph below the skipped targets, and
+    // one reverse traversal per failed dependency.
+    let mut rdeps: HashMap<ConfiguredTargetLabel, Vec<ConfiguredTargetLabel>> = HashMap::new();
+    let mut root_causes: Vec<(
+        &buck2_data::ConfiguredTargetLabel,
+        Option<ConfiguredTargetLabel>,
+    )> = Vec::new();
+    for root_cause in skipped.values() {
+        if !root_causes.iter().any(|(r, _)| *r == root_cause) {
+            root_causes.push((root_cause, None));
+        }
+    }
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::new();
+    for target in skipped.keys() {
+        if let MaybeCompatible::Compatible(node) = ctx.get_configured_target_node(target).await? {
+            if seen.insert(node.label().dupe()) {
+                queue.push_back(node);
+            }
+        }
+    }
+    while let Some(node) = queue.pop_front() {
+        for (root_cause, found) in &mut root_causes {
+            if found.is_none() && is_label_of(*root_cause, node.label()) {
+                *found = Some(node.label().dupe());
+            }
+        }
+        for dep in node.deps() {
+            rdeps
+                .entry(dep.label().dupe())
+                .or_default()
+                .push(node.label().dupe());
+            if seen.insert(dep.label().dupe()) {
+                queue.push_back(dep.dupe());
+            }
+        }
+    }
+
+    for (root_cause, found) in &root_causes {
+        let targets: Vec<&ConfiguredTargetLabel> = skipped
+            .iter()
+            .filter(|(_, r)| r == root_cause)
+            .map(|(target, _)| target)
+            .collect();
+        let chains = match found {
+            Some(found) => dependency_chains(&rdeps, found, targets.iter().copied()),
+            None => HashMap::new(),
+        };
+        for target in targets {
+            let root_cause_chain = match chains.get(target) {
+                Some(chain) => chain.iter().map(|label| label.to_string()).collect(),
+                // The failing target is not reachable through configured deps, so just point at
+                // it.
+                None => vec![target.to_string(), display_root_cause(root_cause)],
+            };
+            failures.insert(
+                target.dupe(),
+                TargetFailure::SkippedDueToFailedDependency { root_cause_chain },
+            );
+        }
+    }
+    Ok(failures)
+}
+
+/// Warn about deprecated rules and attributes used by the requested targets, or fail with
+/// `--strict-deprecations`. Packages which fail to load are reported by the build itself.
+async fn check_deprecations(
+    ctx: &mut DiceComputations<'_>,
+    spec: &ResolvedPattern<ConfiguredProvidersPatternExtra>,
+) -> anyhow::Result<()> {
+    let targets = ctx
+        .compute_join(spec.specs.iter(), |ctx: &mut _, (package, spec)| {
+            async move {
+                match ctx.get_interpreter_results(package.dupe()).await {
+                    Ok(res) => res
+                        .apply_spec(spec.clone())
+                        .0
+                        .into_values()
+                        .collect::<Vec<_>>(),
+                    Err(_) => Vec::new(),
+                }
+            }
+            .boxed()
+        })
+        .await;
+    check_build_deprecations(ctx, targets.iter().flatten().map(|t| t.as_ref())).await
+}
+
 async fn build_targets(
     ctx: &LinearRecomputeDiceComputations<'_>,
     spec: ResolvedPattern<ConfiguredProvidersPatternExtra>,
     target_resolution_config: TargetResolutionConfig,
-    build_providers: Arc<BuildProviders>,
+    providers_to_build: &ProvidersToBuild,
     materialization_context: &MaterializationContext,
     fail_fast: bool,
     missing_target_behavior: MissingTargetBehavior,
@@ -428,7 +756,7 @@ async fn build_targets(
                 ctx,
                 spec,
                 global_cfg_options,
-                build_providers,
+                providers_to_build,
                 materialization_context,
                 missing_target_behavior,
                 skip_incompatible_targets,
@@ -440,7 +768,7 @@ async fn build_targets(
             ctx,
             spec,
             universe,
-            build_providers,
+            providers_to_build,
             materialization_context,
             want_configured_graph_size,
         )
@@ -455,29 +783,25 @@ fn build_targets_in_universe<'a>(
     ctx: &'a LinearRecomputeDiceComputations,
     spec: ResolvedPattern<ConfiguredProvidersPatternExtra>,
     universe: CqueryUniverse,
-    build_providers: Arc<BuildProviders>,
+    providers_to_build: &'a ProvidersToBuild,
     materialization_context: &'a MaterializationContext,
     want_configured_graph_size: bool,
 ) -> impl Stream<Item = ConfiguredBuildEvent> + Unpin + 'a {
-    let providers_to_build = build_providers_to_providers_to_build(&build_providers);
     let provider_labels = universe.get_provider_labels(&spec);
     provider_labels
         .into_iter()
-        .map(|p| {
-            let providers_to_build = providers_to_build.clone();
-            async move {
-                build::build_configured_label(
-                    ctx,
-                    materialization_context,
-                    p,
-                    &providers_to_build,
-                    build::BuildConfiguredLabelOptions {
-                        skippable: false,
-                        want_configured_graph_size,
-                    },
-                )
-                .await
-            }
+        .map(|p| async move {
+            build::build_configured_label(
+                ctx,
+                materialization_context,
+                p,
+                providers_to_build,
+                build::BuildConfiguredLabelOptions {
+                    skippable: false,
+                    want_configured_graph_size,
+                },
+            )
+            .await
         })
         .collect::<FuturesUnordered<_>>()
         .flatten_unordered(None)
@@ -487,7 +811,7 @@ fn build_targets_with_global_target_platform<'a>(
     ctx: &'a LinearRecomputeDiceComputations<'_>,
     spec: ResolvedPattern<ProvidersPatternExtra>,
     global_cfg_options: GlobalCfgOptions,
-    build_providers: Arc<BuildProviders>,
+    providers_to_build: &'a ProvidersToBuild,
     materialization_context: &'a MaterializationContext,
     missing_target_behavior: MissingTargetBehavior,
     skip_incompatible_targets: bool,
@@ -499,7 +823,7 @@ fn build_targets_with_global_target_platform<'a>(
             spec,
             package,
             global_cfg_options.dupe(),
-            build_providers.dupe(),
+            providers_to_build,
             materialization_context,
             missing_target_behavior,
             skip_incompatible_targets,
@@ -546,7 +870,7 @@ async fn build_targets_for_spec<'a>(
     spec: PackageSpec<ProvidersPatternExtra>,
     package: PackageLabel,
     global_cfg_options: GlobalCfgOptions,
-    build_providers: Arc<BuildProviders>,
+    providers_to_build: &'a ProvidersToBuild,
     materialization_context: &'a MaterializationContext,
     missing_target_behavior: MissingTargetBehavior,
     skip_incompatible_targets: bool,
@@ -554,7 +878,7 @@ async fn build_targets_for_spec<'a>(
 ) -> impl Stream<Item = BuildEvent> + 'a {
     let skippable = match spec {
         PackageSpec::Targets(..) => skip_incompatible_targets,
-        PackageSpec::All => true,
+        PackageSpec::All | PackageSpec::AllExcept(_) => true,
     };
 
     let res = match ctx.get().get_interpreter_results(package.dupe()).await {
@@ -574,7 +898,9 @@ async fn build_targets_for_spec<'a>(
                         })
                         .map(Some),
                 ),
-                PackageSpec::All => Either::Right(std::iter::once(None)),
+                PackageSpec::All | PackageSpec::AllExcept(_) => {
+                    Either::Right(std::iter::once(None))
+                }
             };
             return futures::stream::iter(targets.into_iter().map(move |t| {
                 BuildEvent::OtherError {
@@ -619,21 +945,10 @@ async fn build_targets_for_spec<'a>(
         })
         .collect();
 
-    let providers_to_build = build_providers_to_providers_to_build(&build_providers);
-
     todo_targets
         .into_iter()
-        .map(|build_spec| {
-            let providers_to_build = providers_to_build.clone();
-            async move {
-                build_target(
-                    ctx,
-                    build_spec,
-                    &providers_to_build,
-                    materialization_context,
-                )
-                .await
-            }
+        .map(|build_spec| async move {
+            build_target(ctx, build_spec, providers_to_build, materialization_context).await
         })
         .collect::<FuturesUnordered<_>>()
         .flatten_unordered(None)
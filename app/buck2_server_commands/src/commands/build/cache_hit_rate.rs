@@ -0,0 +1,106 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! `--min-cache-hit-rate`: fail (or warn) when too few of the actions run by a build were served
+//! by the remote cache, listing the action categories with the most misses.
+
+use std::collections::BTreeMap;
+
+use buck2_build_api::actions::execute::cache_hit_stats::CategoryCacheHits;
+
+/// Maximum number of categories listed in the message.
+const MAX_LISTED_CATEGORIES: usize = 10;
+
+#[derive(Debug, buck2_error::Error)]
+#[error("{0}")]
+pub(crate) struct LowCacheHitRate(pub(crate) String);
+
+/// A message explaining that `hit_rate` is below `min_hit_rate`, or `None` if it is not.
+pub(crate) fn check_cache_hit_rate(
+    hit_rate: Option<f64>,
+    min_hit_rate: f64,
+    by_category: &BTreeMap<String, CategoryCacheHits>,
+) -> Option<String> {
+    // A build which ran no command did not miss the cache.
+    let hit_rate = hit_rate?;
+    if hit_rate >= min_hit_rate {
+        return None;
+    }
+
+    let mut message = format!(
+        "Remote cache hit rate of {:.1}% is below the minimum of {:.1}% (`--min-cache-hit-rate`). \
+        Actions executed by category:",
+        hit_rate * 100.0,
+        min_hit_rate * 100.0
+    );
+    let mut misses: Vec<_> = by_category
+        .iter()
+        .filter(|(_, hits)| hits.executed > 0)
+        .collect();
+    misses.sort_by(|(c1, h1), (c2, h2)| h2.executed.cmp(&h1.executed).then(c1.cmp(c2)));
+    for (category, hits) in misses.iter().take(MAX_LISTED_CATEGORIES) {
+        message.push_str(&format!(
+            "\n  {}: {} of {}",
+            category,
+            hits.executed,
+            hits.executed + hits.cached
+        ));
+    }
+    if misses.len() > MAX_LISTED_CATEGORIES {
+        message.push_str(&format!(
+            "\n  and {} more categories",
+            misses.len() - MAX_LISTED_CATEGORIES
+        ));
+    }
+    Some(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_cache_hit_rate() {
+        let by_category = BTreeMap::from([
+            (
+                "cxx_compile".to_owned(),
+                CategoryCacheHits {
+                    cached: 10,
+                    executed: 5,
+                },
+            ),
+            (
+                "cxx_link".to_owned(),
+                CategoryCacheHits {
+                    cached: 0,
+                    executed: 7,
+                },
+            ),
+            (
+                "genrule".to_owned(),
+                CategoryCacheHits {
+                    cached: 3,
+                    executed: 0,
+                },
+            ),
+        ]);
+
+        assert_eq!(None, check_cache_hit_rate(None, 0.9, &by_category));
+        assert_eq!(None, check_cache_hit_rate(Some(0.9), 0.9, &by_category));
+        assert_eq!(
+            Some(
+                "Remote cache hit rate of 52.0% is below the minimum of 90.0% \
+                (`--min-cache-hit-rate`). Actions executed by category:\n  \
+                cxx_link: 7 of 7\n  cxx_compile: 5 of 15"
+                    .to_owned()
+            ),
+            check_cache_hit_rate(Some(0.52), 0.9, &by_category)
+        );
+    }
+}
@@ -0,0 +1,219 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Links to the default outputs of the last successful build, in `buck-out/<isolation>/last-build`
+//! (e.g. `buck-out/v2/last-build/foo` for `//pkg:foo`), so that they can be found without parsing
+//! `--show-output`. They are enabled by `buck2.last_build_links`, or per build with
+//! `--last-build-links`.
+//!
+//! The links of a build are created in a new directory of `last-build.d`, and `last-build` is then
+//! replaced with a symlink to that directory by a rename, so that it always points to the complete
+//! links of a single build. Directories created before the one `last-build` pointed to until then
+//! are deleted afterwards: newer ones may belong to builds which have not replaced it yet.
+
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::time::Instant;
+
+use anyhow::Context;
+use buck2_build_api::build::BuildProviderType;
+use buck2_build_api::build::ConfiguredBuildTargetResult;
+use buck2_core::fs::artifact_path_resolver::ArtifactFs;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+use buck2_core::fs::paths::file_name::FileName;
+use buck2_core::fs::paths::file_name::FileNameBuf;
+use buck2_core::fs::project::ProjectRoot;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+use buck2_core::provider::label::ConfiguredProvidersLabel;
+use tracing::info;
+
+const LAST_BUILD: &str = "last-build";
+const LAST_BUILD_DIRS: &str = "last-build.d";
+
+/// Point `last-build` to links to the default outputs of the `configured` targets, named after
+/// each target. Returns the number of links created.
+pub(crate) fn create_last_build_links(
+    configured: &BTreeMap<ConfiguredProvidersLabel, Option<ConfiguredBuildTargetResult>>,
+    artifact_fs: &ArtifactFs,
+    fs: &ProjectRoot,
+    build_id: &str,
+) -> anyhow::Result<u64> {
+    let start = Instant::now();
+
+    let mut by_name: BTreeMap<FileNameBuf, Vec<(&ConfiguredProvidersLabel, _)>> = BTreeMap::new();
+    for (label, result) in configured {
+        let Some(result) = result else { continue };
+        let Some(name) = link_name(label) else {
+            info!("No last build link for `{}`: invalid file name", label);
+            continue;
+        };
+        let mut outputs = Vec::new();
+        for output in result
+            .outputs
+            .iter()
+            .filter_map(|output| output.as_ref().ok())
+        {
+            if !matches!(output.provider_type, BuildProviderType::Default) {
+                continue;
+            }
+            for (artifact, _) in output.values.iter() {
+                let path = artifact.resolve_path(artifact_fs)?;
+                if !outputs.contains(&path) {
+                    outputs.push(path);
+                }
+            }
+        }
+        if !outputs.is_empty() {
+            by_name.entry(name).or_default().push((label, outputs));
+        }
+    }
+
+    let buck_out_root = fs.resolve(artifact_fs.buck_out_path_resolver().root());
+    let dirs = buck_out_root.join(FileName::new(LAST_BUILD_DIRS)?);
+    let links_dir = dirs.join(FileName::new(build_id)?);
+    fs_util::remove_all(&links_dir)?;
+    fs_util::create_dir_all(&links_dir)?;
+
+    let mut created = 0;
+    for (name, targets) in by_name {
+        match targets.as_slice() {
+            [(_, outputs)] => {
+                created += create_links(&links_dir.join(&name), outputs, fs)
+                    .with_context(|| format!("creating last build link `{}`", name))?;
+            }
+            _ => {
+                info!(
+                    "The following targets have a conflicting last build link `{}`: {:?}",
+                    name,
+                    targets
+                        .iter()
+                        .map(|(label, _)| label.to_string())
+                        .collect::<Vec<_>>()
+                );
+            }
+        }
+    }
+
+    let last_build = buck_out_root.join(FileName::new(LAST_BUILD)?);
+    let previous = match fs_util::symlink_metadata_if_exists(&last_build)? {
+        Some(metadata) if metadata.is_symlink() => {
+            let previous = AbsNormPathBuf::new(fs_util::read_link(&last_build)?)?;
+            fs_util::symlink_metadata_if_exists(&previous)?
+        }
+        _ => None,
+    };
+    let cutoff = previous.map(|metadata| metadata.modified()).transpose()?;
+
+    let tmp = dirs.join(FileName::new(&format!("{}.tmp", build_id))?);
+    fs_util::remove_all(&tmp)?;
+    fs_util::symlink(&links_dir, &tmp)?;
+    // A rename cannot replace a directory, nor a symlink to a directory on Windows.
+    if cfg!(windows)
+        || fs_util::symlink_metadata_if_exists(&last_build)?.map_or(false, |m| m.is_dir())
+    {
+        fs_util::remove_all(&last_build)?;
+    }
+    fs_util::rename(&tmp, &last_build)?;
+
+    if let Some(cutoff) = cutoff {
+        for entry in fs_util::read_dir(&dirs)? {
+            let path = entry?.path();
+            if path != links_dir && fs_util::symlink_metadata(&path)?.modified()? < cutoff {
+                fs_util::remove_all(&path)?;
+            }
+        }
+    }
+
+    info!(
+        "Creating {} last build links in {:3}s",
+        created,
+        start.elapsed().as_secs_f64()
+    );
+    Ok(created)
+}
+
+/// The name of the link to the outputs of `label`, e.g. `foo[bar]` for `//pkg:foo[bar]`.
+fn link_name(label: &ConfiguredProvidersLabel) -> Option<FileNameBuf> {
+    let name = format!("{}{}", label.target().name(), label.name()).replace(['/', '\\'], "_");
+    FileNameBuf::try_from(name).ok()
+}
+
+/// A link to the output at `link` if there is a single one, or a directory of links named after
+/// each output otherwise.
+fn create_links(
+    link: &AbsNormPathBuf,
+    outputs: &[ProjectRelativePathBuf],
+    fs: &ProjectRoot,
+) -> anyhow::Result<u64> {
+    if let [output] = outputs {
+        fs_util::symlink(fs.resolve(output), link)?;
+        return Ok(1);
+    }
+
+    fs_util::create_dir_all(link)?;
+    let mut names = HashSet::new();
+    let mut created = 0;
+    for output in outputs {
+        let Some(name) = output.file_name() else {
+            continue;
+        };
+        if !names.insert(name) {
+            info!(
+                "Outputs of `{}` have conflicting file names: `{}`",
+                link, name
+            );
+            continue;
+        }
+        fs_util::symlink(fs.resolve(output), link.join(name))?;
+        created += 1;
+    }
+    Ok(created)
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_core::configuration::data::ConfigurationData;
+    use buck2_core::provider::label::ProviderName;
+    use buck2_core::provider::label::ProvidersName;
+    use buck2_core::target::configured_target_label::ConfiguredTargetLabel;
+
+    use super::*;
+
+    fn label(target: &str, providers: &[&str]) -> ConfiguredProvidersLabel {
+        let mut name = ProvidersName::Default;
+        for provider in providers {
+            name = name.push(ProviderName::new((*provider).to_owned()).unwrap());
+        }
+        ConfiguredProvidersLabel::new(
+            ConfiguredTargetLabel::testing_parse(target, ConfigurationData::testing_new()),
+            name,
+        )
+    }
+
+    #[test]
+    fn test_link_name() {
+        assert_eq!(
+            "foo",
+            link_name(&label("cell//pkg:foo", &[])).unwrap().as_str()
+        );
+        assert_eq!(
+            "foo[bar][baz]",
+            link_name(&label("cell//pkg:foo", &["bar", "baz"]))
+                .unwrap()
+                .as_str()
+        );
+        assert_eq!(
+            "foo[a_b]",
+            link_name(&label("cell//pkg:foo", &["a/b"]))
+                .unwrap()
+                .as_str()
+        );
+    }
+}
@@ -0,0 +1,219 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Early exit for no-op builds, enabled with `buck2.noop_build_early_exit`.
+//!
+//! The daemon remembers the response to the last successful build (see `LastSuccessfulBuild`),
+//! together with a checksum made of its request, of the materializer generation and of the
+//! action graph behind the requested outputs. The action graph checksums cover the definitions
+//! of the actions and the source files they read, and are computed without running anything, so
+//! a build with the same checksum would produce the same outputs, and the materializer has not
+//! forgotten about any of them since: its response is returned without building or checking the
+//! outputs in buck-out.
+//!
+//! Outputs modified outside of buck2 (e.g. deleted by hand) are not noticed until the next
+//! change invalidates the checksum.
+
+use buck2_build_api::build::action_graph_checksum::requested_outputs_checksum;
+use buck2_build_api::build::action_graph_checksum::ActionGraphChecksum;
+use buck2_build_api::build::ProvidersToBuild;
+use buck2_cli_proto::BuildRequest;
+use buck2_common::global_cfg_options::GlobalCfgOptions;
+use buck2_common::pattern::resolve::ResolvedPattern;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_core::pattern::pattern_type::ConfiguredProvidersPatternExtra;
+use buck2_core::pattern::pattern_type::PatternType;
+use buck2_core::pattern::pattern_type::ProvidersPatternExtra;
+use buck2_core::provider::label::ProvidersLabel;
+use buck2_node::nodes::frontend::TargetGraphCalculation;
+use buck2_node::target_calculation::ConfiguredTargetCalculation;
+use buck2_server_ctx::target_resolution_config::TargetResolutionConfig;
+use dice::DiceComputations;
+use dupe::Dupe;
+use futures::FutureExt;
+use gazebo::prelude::VecExt;
+use prost::Message;
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct NoopBuildChecksum([u8; 32]);
+
+impl NoopBuildChecksum {
+    /// Checksum of `request`, or `None` if its response cannot be reused, because the build
+    /// writes files which are not build outputs, returns output hashes or reads outputs of other
+    /// daemons, because the materializer does not tell whether the outputs are still
+    /// materialized, or because the requested targets cannot be resolved without building (which
+    /// the build then reports).
+    pub(crate) async fn new(
+        ctx: &mut DiceComputations<'_>,
+        materialized_generation: Option<u64>,
+        request: &BuildRequest,
+        working_dir: &ProjectRelativePath,
+        spec: &ResolvedPattern<ConfiguredProvidersPatternExtra>,
+        target_resolution_config: &TargetResolutionConfig,
+        providers_to_build: &ProvidersToBuild,
+    ) -> Option<NoopBuildChecksum> {
+        let materialized_generation = materialized_generation?;
+        if request.output_hashes_file.is_some()
+            || !request.offloaded_outputs.is_empty()
+            || request
+                .response_options
+                .as_ref()
+                .map_or(false, |opts| opts.return_output_hashes)
+            || request
+                .build_opts
+                .as_ref()
+                .map_or(false, |opts| opts.unstable_print_build_report)
+        {
+            return None;
+        }
+        let global_cfg_options = match target_resolution_config {
+            TargetResolutionConfig::Default(global_cfg_options) => global_cfg_options,
+            TargetResolutionConfig::Universe(_) => return None,
+        };
+        let action_graph =
+            action_graph_checksums(ctx, spec, global_cfg_options, providers_to_build)
+                .await
+                .ok()??;
+        Self::from_parts(materialized_generation, request, working_dir, &action_graph)
+    }
+
+    fn from_parts(
+        materialized_generation: u64,
+        request: &BuildRequest,
+        working_dir: &ProjectRelativePath,
+        action_graph: &[ActionGraphChecksum],
+    ) -> Option<NoopBuildChecksum> {
+        // The client context differs between invocations, and the target patterns are resolved
+        // against the working directory.
+        let mut request = request.clone();
+        request.context = None;
+        let mut bytes = Vec::new();
+        request.encode(&mut bytes).ok()?;
+
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&materialized_generation.to_le_bytes());
+        hasher.update(working_dir.as_str().as_bytes());
+        hasher.update(b"\0");
+        hasher.update(&(bytes.len() as u64).to_le_bytes());
+        hasher.update(&bytes);
+        for checksum in action_graph {
+            hasher.update(checksum.as_bytes());
+        }
+        Some(NoopBuildChecksum(*hasher.finalize().as_bytes()))
+    }
+
+    pub(crate) fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+/// Action graph checksums of the requested targets, in the order of their labels, or `None` if
+/// some targets are missing.
+async fn action_graph_checksums(
+    ctx: &mut DiceComputations<'_>,
+    spec: &ResolvedPattern<ConfiguredProvidersPatternExtra>,
+    global_cfg_options: &GlobalCfgOptions,
+    providers_to_build: &ProvidersToBuild,
+) -> anyhow::Result<Option<Vec<ActionGraphChecksum>>> {
+    let mut labels = Vec::new();
+    for (package, spec) in spec.specs.iter() {
+        let (targets, missing) = ctx
+            .get_interpreter_results(package.dupe())
+            .await?
+            .apply_spec(spec.clone());
+        if missing.is_some() {
+            return Ok(None);
+        }
+        for ((_, extra), target) in targets {
+            let providers = ProvidersPatternExtra::from_configured_providers(extra)?;
+            labels.push(ProvidersLabel::new(
+                target.label().dupe(),
+                providers.into_providers(),
+            ));
+        }
+    }
+
+    let mut checksums = ctx
+        .try_compute_join(labels, |ctx, label| {
+            async move {
+                let label = ctx
+                    .get_configured_provider_label(&label, global_cfg_options)
+                    .await?;
+                let checksum = requested_outputs_checksum(ctx, &label, providers_to_build).await?;
+                anyhow::Ok((label, checksum))
+            }
+            .boxed()
+        })
+        .await?;
+    checksums.sort_by(|(x, _), (y, _)| x.cmp(y));
+    Ok(Some(checksums.into_map(|(_, checksum)| checksum)))
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_build_api::build::action_graph_checksum::ActionGraphChecksum;
+    use buck2_cli_proto::BuildRequest;
+    use buck2_cli_proto::ClientContext;
+    use buck2_core::fs::project_rel_path::ProjectRelativePath;
+
+    use super::NoopBuildChecksum;
+
+    fn request(target: &str) -> BuildRequest {
+        BuildRequest {
+            target_patterns: vec![target.to_owned()],
+            ..Default::default()
+        }
+    }
+
+    fn checksum(
+        generation: u64,
+        request: &BuildRequest,
+        action_graph: &[ActionGraphChecksum],
+    ) -> NoopBuildChecksum {
+        NoopBuildChecksum::from_parts(
+            generation,
+            request,
+            ProjectRelativePath::empty(),
+            action_graph,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_checksum_covers_request() {
+        assert_eq!(
+            checksum(0, &request("//:a"), &[]),
+            checksum(0, &request("//:a"), &[])
+        );
+        assert_ne!(
+            checksum(0, &request("//:a"), &[]),
+            checksum(0, &request("//:b"), &[])
+        );
+        // The materializer forgot about some artifacts since the build.
+        assert_ne!(
+            checksum(0, &request("//:a"), &[]),
+            checksum(1, &request("//:a"), &[])
+        );
+    }
+
+    #[test]
+    fn test_checksum_ignores_client_context() {
+        let with_context = BuildRequest {
+            context: Some(ClientContext {
+                trace_id: "trace".to_owned(),
+                ..Default::default()
+            }),
+            ..request("//:a")
+        };
+        assert_eq!(
+            checksum(0, &request("//:a"), &[]),
+            checksum(0, &with_context, &[])
+        );
+    }
+}
@@ -0,0 +1,218 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Outputs of the target patterns which `buck2 build --offload-analysis` sent to worker daemons.
+//!
+//! Each worker analyzes and builds its shard of the patterns in its own isolation dir, or on
+//! another machine, and returns its outputs with their digests, as with `--output-hashes-file`.
+//! This daemon declares them in its materializer, at the same paths under its own buck-out, so
+//! they are downloaded from the CAS instead of being analyzed and built again here.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Context;
+use buck2_common::cas_digest::CasDigest;
+use buck2_common::file_ops::FileMetadata;
+use buck2_common::file_ops::TrackedFileDigest;
+use buck2_core::execution_types::executor_config::RemoteExecutorUseCase;
+use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+use buck2_execute::artifact_value::ArtifactValue;
+use buck2_execute::digest_config::DigestConfig;
+use buck2_execute::directory::new_symlink;
+use buck2_execute::directory::ActionDirectoryEntry;
+use buck2_execute::directory::ActionDirectoryMember;
+use buck2_execute::materialize::materializer::CasDownloadInfo;
+use buck2_execute::materialize::materializer::Materializer;
+use buck2_futures::cancellation::CancellationContext;
+use serde::Deserialize;
+
+/// An entry of the outputs of a worker, in the format of `--output-hashes-file`.
+#[derive(Deserialize)]
+struct OutputHash {
+    path: String,
+    #[serde(flatten)]
+    info: OutputInfo,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum OutputInfo {
+    Directory,
+    File {
+        digest: String,
+        is_exec: bool,
+    },
+    Symlink {
+        symlink_rel_path: String,
+    },
+    ExternalSymlink {
+        target: PathBuf,
+        remaining_path: Option<String>,
+    },
+}
+
+/// Where an output of a worker daemon, at `buck-out/<worker isolation dir>/...`, goes in the
+/// buck-out of this daemon, or `None` for source files, which are already in this checkout.
+fn local_output_path(
+    path: &str,
+    buck_out: &ProjectRelativePath,
+) -> anyhow::Result<Option<ProjectRelativePathBuf>> {
+    let mut components = path.splitn(3, '/');
+    match (components.next(), components.next(), components.next()) {
+        (Some("buck-out"), Some(_), Some(rest)) => {
+            Ok(Some(buck_out.join(ForwardRelativePath::new(rest)?)))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// The outputs listed in the output hashes of a worker, at their paths in this daemon's buck-out.
+///
+/// Directories are not listed on their own: declaring their files recreates them, except for
+/// empty ones.
+fn parse_offloaded_outputs(
+    contents: &str,
+    buck_out: &ProjectRelativePath,
+    digest_config: DigestConfig,
+) -> anyhow::Result<Vec<(ProjectRelativePathBuf, ArtifactValue)>> {
+    let hashes: Vec<OutputHash> = serde_json::from_str(contents)?;
+    let mut outputs = Vec::new();
+    for hash in hashes {
+        let Some(path) = local_output_path(&hash.path, buck_out)? else {
+            continue;
+        };
+        let member = match hash.info {
+            OutputInfo::Directory => continue,
+            OutputInfo::File { digest, is_exec } => {
+                let (digest, _) =
+                    CasDigest::parse_digest(&digest, digest_config.cas_digest_config())?;
+                ActionDirectoryMember::File(FileMetadata {
+                    digest: TrackedFileDigest::new(digest, digest_config.cas_digest_config()),
+                    is_executable: is_exec,
+                })
+            }
+            OutputInfo::Symlink { symlink_rel_path } => new_symlink(symlink_rel_path)?,
+            OutputInfo::ExternalSymlink {
+                target,
+                remaining_path,
+            } => match remaining_path {
+                Some(remaining_path) => new_symlink(target.join(remaining_path))?,
+                None => new_symlink(target)?,
+            },
+        };
+        outputs.push((
+            path,
+            ArtifactValue::from(ActionDirectoryEntry::Leaf(member)),
+        ));
+    }
+    Ok(outputs)
+}
+
+/// Declare the outputs returned by worker daemons, one output hashes list per worker, in
+/// `materializer`, and materialize them unless `materialize` is false.
+pub(crate) async fn declare_offloaded_outputs(
+    materializer: &dyn Materializer,
+    digest_config: DigestConfig,
+    buck_out: &ProjectRelativePath,
+    output_hashes: &[String],
+    materialize: bool,
+) -> anyhow::Result<()> {
+    let mut outputs = Vec::new();
+    for hashes in output_hashes {
+        outputs.extend(
+            parse_offloaded_outputs(hashes, buck_out, digest_config)
+                .context("Failed to read offloaded outputs")?,
+        );
+    }
+    if outputs.is_empty() {
+        return Ok(());
+    }
+
+    let paths = outputs.iter().map(|(path, _)| path.clone()).collect();
+    materializer
+        .declare_cas_many(
+            Arc::new(CasDownloadInfo::new_declared(
+                RemoteExecutorUseCase::buck2_default(),
+            )),
+            outputs,
+            CancellationContext::never_cancelled(),
+        )
+        .await?;
+    if materialize {
+        materializer
+            .ensure_materialized(paths)
+            .await
+            .context("Failed to materialize offloaded outputs")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_core::directory::DirectoryEntry;
+
+    use super::*;
+
+    #[test]
+    fn test_local_output_path() {
+        let buck_out = ProjectRelativePath::new("buck-out/v2").unwrap();
+        assert_eq!(
+            "buck-out/v2/gen/root/904931f735703749/foo/__bar__/out.txt",
+            local_output_path(
+                "buck-out/worker/gen/root/904931f735703749/foo/__bar__/out.txt",
+                buck_out
+            )
+            .unwrap()
+            .unwrap()
+            .as_str()
+        );
+        assert_eq!(None, local_output_path("foo/out.txt", buck_out).unwrap());
+        assert_eq!(
+            None,
+            local_output_path("buck-out/worker", buck_out).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_offloaded_outputs() {
+        let digest_config = DigestConfig::testing_default();
+        let outputs = parse_offloaded_outputs(
+            r#"[{"path":"buck-out","kind":"directory"},
+                {"path":"buck-out/worker/gen/out","kind":"directory"},
+                {"path":"src/in.txt","kind":"file","digest":"fb19d5b1546753df5f7741efbabd0d24dcaacd65:20","digest_kind":"SHA1","is_exec":false},
+                {"path":"buck-out/worker/gen/out/a","kind":"file","digest":"fb19d5b1546753df5f7741efbabd0d24dcaacd65:20","digest_kind":"SHA1","is_exec":true},
+                {"path":"buck-out/worker/gen/out/b","kind":"symlink","symlink_rel_path":"a"}]"#,
+            ProjectRelativePath::new("buck-out/v2").unwrap(),
+            digest_config,
+        )
+        .unwrap();
+
+        let paths: Vec<&str> = outputs.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(
+            vec!["buck-out/v2/gen/out/a", "buck-out/v2/gen/out/b"],
+            paths
+        );
+        match outputs[0].1.entry() {
+            DirectoryEntry::Leaf(ActionDirectoryMember::File(metadata)) => {
+                assert!(metadata.is_executable);
+                assert_eq!(20, metadata.digest.size());
+            }
+            _ => panic!("Expected a file"),
+        }
+        match outputs[1].1.entry() {
+            DirectoryEntry::Leaf(ActionDirectoryMember::Symlink(symlink)) => {
+                assert_eq!("a", symlink.target().as_str());
+            }
+            _ => panic!("Expected a symlink"),
+        }
+    }
+}
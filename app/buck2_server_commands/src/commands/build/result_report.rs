@@ -117,6 +117,10 @@ impl<'a> ResultReporter<'a> {
                     continue;
                 }
 
+                if matches!(provider_type, BuildProviderType::Validation) {
+                    continue;
+                }
+
                 for (artifact, _value) in values.iter() {
                     let entry =
                         artifacts
@@ -132,7 +136,7 @@ impl<'a> ResultReporter<'a> {
                         BuildProviderType::Default => {
                             entry.default_info = true;
                         }
-                        BuildProviderType::DefaultOther => {
+                        BuildProviderType::DefaultOther | BuildProviderType::Aspect => {
                             entry.other = true;
                         }
                         BuildProviderType::Run => {
@@ -141,6 +145,7 @@ impl<'a> ResultReporter<'a> {
                         BuildProviderType::Test => {
                             entry.test_info = true;
                         }
+                        BuildProviderType::Validation => {}
                     }
                 }
             }
@@ -119,4 +119,8 @@ impl ServerCommandTemplate for ConfiguredTargetsServerCommand {
             serialized_targets_output,
         })
     }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
 }
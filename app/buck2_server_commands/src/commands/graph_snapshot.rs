@@ -0,0 +1,89 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Configured graphs of recent builds, retained in the daemon so that `cquery --at`
+//! can query them after the working copy has changed.
+
+use std::sync::Arc;
+
+use buck2_common::legacy_configs::dice::HasLegacyConfigs;
+use buck2_common::legacy_configs::key::BuckconfigKeyRef;
+use buck2_core::cells::name::CellName;
+use buck2_core::configuration::compatibility::MaybeCompatible;
+use buck2_core::target::configured_target_label::ConfiguredTargetLabel;
+use buck2_node::configured_universe::CqueryUniverse;
+use buck2_node::graph_snapshots::HasGraphSnapshots;
+use buck2_node::nodes::configured_frontend::ConfiguredTargetNodeCalculation;
+use buck2_query::query::syntax::simple::eval::set::TargetSet;
+use dice::DiceComputations;
+use futures::FutureExt;
+
+#[derive(Debug, buck2_error::Error)]
+enum GraphSnapshotError {
+    #[error(
+        "No configured graph was retained for invocation `{0}`. \
+        The daemon keeps the graphs of the last `buck2.configured_graph_snapshots` builds, \
+        and loses them when it restarts"
+    )]
+    NotFound(String),
+}
+
+/// How many build graphs to retain, from `buck2.configured_graph_snapshots`.
+pub(crate) async fn snapshots_to_keep(
+    ctx: &mut DiceComputations<'_>,
+    root_cell: CellName,
+) -> anyhow::Result<usize> {
+    Ok(ctx
+        .parse_legacy_config_property(
+            root_cell,
+            BuckconfigKeyRef {
+                section: "buck2",
+                property: "configured_graph_snapshots",
+            },
+        )
+        .await?
+        .unwrap_or_default())
+}
+
+/// Retain the configured graph rooted at `targets` for the invocation `trace_id`,
+/// evicting the oldest graphs beyond `keep`.
+pub(crate) async fn record_snapshot(
+    ctx: &mut DiceComputations<'_>,
+    trace_id: String,
+    targets: impl IntoIterator<Item = ConfiguredTargetLabel>,
+    keep: usize,
+) -> anyhow::Result<()> {
+    let nodes = ctx
+        .try_compute_join(targets, |ctx, target| {
+            async move { ctx.get_configured_target_node(&target).await }.boxed()
+        })
+        .await?;
+    let mut roots = TargetSet::new();
+    for node in nodes {
+        if let MaybeCompatible::Compatible(node) = node {
+            roots.insert(node);
+        }
+    }
+    let universe = Arc::new(CqueryUniverse::build(&roots)?);
+    ctx.per_transaction_data()
+        .get_graph_snapshots()
+        .record(trace_id, universe, keep);
+    Ok(())
+}
+
+/// The configured graph retained for the invocation `trace_id`.
+pub(crate) fn get_snapshot(
+    ctx: &DiceComputations<'_>,
+    trace_id: &str,
+) -> anyhow::Result<Arc<CqueryUniverse>> {
+    ctx.per_transaction_data()
+        .get_graph_snapshots()
+        .get(trace_id)
+        .ok_or_else(|| GraphSnapshotError::NotFound(trace_id.to_owned()).into())
+}
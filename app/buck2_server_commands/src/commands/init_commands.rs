@@ -14,6 +14,8 @@ use buck2_cli_proto::new_generic::ExpandExternalCellRequest;
 use buck2_cli_proto::new_generic::ExpandExternalCellResponse;
 use buck2_cli_proto::new_generic::ExplainRequest;
 use buck2_cli_proto::new_generic::ExplainResponse;
+use buck2_cli_proto::new_generic::VerifyRequest;
+use buck2_cli_proto::new_generic::VerifyResponse;
 use buck2_server_ctx::ctx::ServerCommandContextTrait;
 use buck2_server_ctx::other_server_commands::OtherServerCommands;
 use buck2_server_ctx::other_server_commands::OTHER_SERVER_COMMANDS;
@@ -31,6 +33,7 @@ use crate::commands::query::cquery::cquery_command;
 use crate::commands::query::uquery::uquery_command;
 use crate::commands::targets::targets_command;
 use crate::commands::targets_show_outputs::targets_show_outputs_command;
+use crate::commands::verify::verify_command;
 
 struct OtherServerCommandsInstance;
 
@@ -125,6 +128,15 @@ impl OtherServerCommands for OtherServerCommandsInstance {
     ) -> anyhow::Result<ExpandExternalCellResponse> {
         expand_external_cell_command(ctx, partial_result_dispatcher, req).await
     }
+
+    async fn verify(
+        &self,
+        ctx: &dyn ServerCommandContextTrait,
+        partial_result_dispatcher: PartialResultDispatcher<NoPartialResult>,
+        req: VerifyRequest,
+    ) -> anyhow::Result<VerifyResponse> {
+        verify_command(ctx, partial_result_dispatcher, req).await
+    }
 }
 
 pub(crate) fn init_other_server_commands() {
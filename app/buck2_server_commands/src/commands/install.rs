@@ -8,6 +8,7 @@
  */
 
 use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::hash::Hash;
 use std::hash::Hasher;
@@ -15,6 +16,7 @@ use std::net::Ipv4Addr;
 use std::net::SocketAddr;
 use std::net::TcpListener;
 use std::process::Stdio;
+use std::sync::Arc;
 use std::time::Instant;
 
 use anyhow::Context;
@@ -31,11 +33,14 @@ use buck2_build_api::interpreter::rule_defs::cmd_args::CommandLineArgLike;
 use buck2_build_api::interpreter::rule_defs::cmd_args::SimpleCommandLineArtifactVisitor;
 use buck2_build_api::interpreter::rule_defs::provider::builtin::install_info::FrozenInstallInfo;
 use buck2_build_api::interpreter::rule_defs::provider::builtin::run_info::FrozenRunInfo;
+use buck2_build_api::sent_install_files::HasSentInstallFiles;
+use buck2_build_api::sent_install_files::SentInstallFiles;
 use buck2_cli_proto::InstallRequest;
 use buck2_cli_proto::InstallResponse;
 use buck2_common::client_utils::get_channel_tcp;
 use buck2_common::file_ops::FileDigest;
-use buck2_common::pattern::parse_from_cli::parse_patterns_from_cli_args;
+use buck2_common::pattern::parse_from_cli::parse_pattern_operations_from_cli_args;
+use buck2_common::pattern::resolve::PatternOperation;
 use buck2_common::pattern::resolve::ResolveTargetPatterns;
 use buck2_core::directory::DirectoryEntry;
 use buck2_core::execution_types::executor_config::PathSeparatorKind;
@@ -52,6 +57,7 @@ use buck2_core::target::name::TargetName;
 use buck2_data::InstallEventInfoEnd;
 use buck2_data::InstallEventInfoStart;
 use buck2_error::BuckErrorContext;
+use buck2_events::dispatch::console_message;
 use buck2_events::dispatch::get_dispatcher;
 use buck2_events::dispatch::span_async;
 use buck2_execute::artifact::artifact_dyn::ArtifactDyn;
@@ -59,11 +65,15 @@ use buck2_execute::artifact::fs::ExecutorFs;
 use buck2_execute::artifact_value::ArtifactValue;
 use buck2_execute::directory::ActionDirectoryMember;
 use buck2_install_proto::installer_client::InstallerClient;
+use buck2_install_proto::ArtifactsChangedRequest;
+use buck2_install_proto::FileDiff;
 use buck2_install_proto::FileReadyRequest;
 use buck2_install_proto::InstallInfoRequest;
+use buck2_install_proto::InstallResponse as InstallerResponse;
 use buck2_install_proto::ShutdownRequest;
 use buck2_node::nodes::frontend::TargetGraphCalculation;
 use buck2_node::target_calculation::ConfiguredTargetCalculation;
+use buck2_server_ctx::command_end::command_end;
 use buck2_server_ctx::ctx::ServerCommandContextTrait;
 use buck2_server_ctx::global_cfg_options::global_cfg_options_from_client_context;
 use buck2_server_ctx::partial_result_dispatcher::NoPartialResult;
@@ -84,6 +94,13 @@ use starlark_map::small_map::SmallMap;
 use tokio::sync::mpsc;
 use tonic::transport::Channel;
 
+use crate::commands::install::diff::SentFile;
+use crate::commands::install::hot_reload::HotReloadSession;
+use crate::commands::install::hot_reload::HotReloadSessions;
+
+mod diff;
+mod hot_reload;
+
 #[derive(Debug, buck2_error::Error)]
 pub enum InstallError {
     #[error("Target {1}:{0} cannot be installed as it does not expose an InstallInfo provider")]
@@ -109,6 +126,15 @@ pub enum InstallError {
     #[error("Communication with the installer failed with `{err}`")]
     InstallerCommunicationFailure { err: String },
 
+    #[error(
+        "Installer failed to hot reload changed artifacts for `{install_id}`. Error message: `{err}`\n. More details can be found at `{installer_log}`"
+    )]
+    HotReloadFailure {
+        install_id: String,
+        err: String,
+        installer_log: String,
+    },
+
     #[error("Incorrect seconds/nanos argument")]
     NativeDateTime,
 }
@@ -135,9 +161,32 @@ pub(crate) async fn install_command(
     partial_result_dispatcher: PartialResultDispatcher<NoPartialResult>,
     req: InstallRequest,
 ) -> anyhow::Result<InstallResponse> {
+    if req.hot_reload {
+        return install_on_changes_command(ctx, req).await;
+    }
     run_server_command(InstallServerCommand { req }, ctx, partial_result_dispatcher).await
 }
 
+/// `buck2 install --hot-reload`, which acquires DICE again for each install, so it cannot run
+/// within a single `ServerCommandTemplate::command`.
+async fn install_on_changes_command(
+    ctx: &dyn ServerCommandContextTrait,
+    req: InstallRequest,
+) -> anyhow::Result<InstallResponse> {
+    let start_event = buck2_data::CommandStart {
+        metadata: ctx.request_metadata().await?,
+        data: Some(buck2_data::InstallCommandStart {}.into()),
+    };
+    span_async(start_event, async move {
+        let result = hot_reload::install_on_changes(ctx, &req)
+            .await
+            .map_err(Into::into);
+        let end_event = command_end(&result, InstallServerCommand { req }.end_event(&result));
+        (result.map_err(Into::into), end_event)
+    })
+    .await
+}
+
 struct InstallServerCommand {
     req: InstallRequest,
 }
@@ -166,7 +215,7 @@ impl ServerCommandTemplate for InstallServerCommand {
         _partial_result_dispatcher: PartialResultDispatcher<Self::PartialResult>,
         ctx: DiceTransaction,
     ) -> anyhow::Result<Self::Response> {
-        install(server_ctx, ctx, &self.req).await
+        install(server_ctx, ctx, &self.req, None).await
     }
 
     fn is_success(&self, _response: &Self::Response) -> bool {
@@ -179,6 +228,7 @@ async fn install(
     server_ctx: &dyn ServerCommandContextTrait,
     mut ctx: DiceTransaction,
     request: &InstallRequest,
+    hot_reload_sessions: Option<&HotReloadSessions>,
 ) -> anyhow::Result<InstallResponse> {
     let cwd = server_ctx.working_dir();
 
@@ -196,15 +246,14 @@ async fn install(
     let materializations = &materializations; // Don't move this below.
 
     // Note <TargetName> does not return the providers
-    let parsed_patterns = parse_patterns_from_cli_args::<ConfiguredProvidersPatternExtra>(
-        &mut ctx,
-        &request.target_patterns,
-        cwd,
-    )
+    let pattern_operations = parse_pattern_operations_from_cli_args::<
+        ConfiguredProvidersPatternExtra,
+    >(&mut ctx, &request.target_patterns, cwd)
     .await?;
-    server_ctx.log_target_pattern(&parsed_patterns);
+    server_ctx.log_target_pattern(&PatternOperation::included(&pattern_operations));
 
-    let resolved_pattern = ResolveTargetPatterns::resolve(&mut ctx, &parsed_patterns).await?;
+    let resolved_pattern =
+        ResolveTargetPatterns::resolve_operations(&mut ctx, &pattern_operations).await?;
 
     let resolved_pattern = resolved_pattern
         .convert_pattern()
@@ -215,11 +264,13 @@ async fn install(
         let ctx = &mut ctx;
         let targets: Vec<(TargetName, ProvidersPatternExtra)> = match spec {
             buck2_core::pattern::pattern::PackageSpec::Targets(targets) => targets,
-            buck2_core::pattern::pattern::PackageSpec::All => {
+            spec @ (buck2_core::pattern::pattern::PackageSpec::All
+            | buck2_core::pattern::pattern::PackageSpec::AllExcept(_)) => {
                 let interpreter_results = ctx.get_interpreter_results(package.dupe()).await?;
                 interpreter_results
                     .targets()
                     .keys()
+                    .filter(|target| !spec.excludes(target))
                     .map(|target| {
                         (
                             target.to_owned(),
@@ -283,6 +334,7 @@ async fn install(
                     installer_label,
                     installer_run_args,
                     request.installer_debug,
+                    hot_reload_sessions,
                 )
                 .await
             }
@@ -327,8 +379,10 @@ async fn handle_install_request<'a>(
     installer_label: &ConfiguredProvidersLabel,
     initial_installer_run_args: &[String],
     installer_debug: bool,
+    hot_reload_sessions: Option<&HotReloadSessions>,
 ) -> anyhow::Result<()> {
     let (files_tx, files_rx) = mpsc::unbounded_channel();
+    let sent_files = &ctx.per_transaction_data().get_sent_install_files();
     let (artifacts_ready, (installer_ready, installer_finished)) = ctx
         .try_compute2(
             |ctx| {
@@ -340,6 +394,42 @@ async fn handle_install_request<'a>(
             },
             |ctx| {
                 async move {
+                    let install_ids: Vec<String> = install_files_slice
+                        .iter()
+                        .map(|(install_id, _)| (*install_id).to_owned())
+                        .collect();
+                    match hot_reload_sessions.and_then(|sessions| sessions.take(installer_label)) {
+                        Some(mut session) if session.install_ids == install_ids => {
+                            let artifact_fs = ctx.get_artifact_fs().await?;
+                            let installer_ready = Instant::now();
+                            let files =
+                                tokio_stream::wrappers::UnboundedReceiverStream::new(files_rx)
+                                    .collect::<Vec<_>>()
+                                    .await;
+                            if let Err(e) = send_changed_files(
+                                &mut session,
+                                installer_label,
+                                files,
+                                &artifact_fs,
+                                sent_files,
+                            )
+                            .await
+                            {
+                                send_shutdown_command(session.client).await.ok();
+                                return Err(e);
+                            }
+                            if let Some(sessions) = hot_reload_sessions {
+                                sessions.keep(installer_label, session);
+                            }
+                            return anyhow::Ok((installer_ready, Instant::now()));
+                        }
+                        Some(session) => {
+                            // The installer may have exited already.
+                            send_shutdown_command(session.client).await.ok();
+                        }
+                        None => {}
+                    }
+
                     // FIXME: The random unused tcp port might be available when get_random_tcp_port() is called,
                     // but when the installer tries to bind on it, someone else might bind on it.
                     // TODO: choose unused tcp port on installer side.
@@ -377,25 +467,62 @@ async fn handle_install_request<'a>(
                     let artifact_fs = ctx.get_artifact_fs().await?;
 
                     let installer_ready = Instant::now();
+                    let mut supports_file_diffs = true;
+                    let mut supports_hot_reload = true;
                     for (install_id, install_files) in install_files_slice {
-                        send_install_info(client.clone(), install_id, install_files, &artifact_fs)
-                            .await?;
+                        let response = send_install_info(
+                            client.clone(),
+                            install_id,
+                            install_files,
+                            &artifact_fs,
+                        )
+                        .await?;
+                        supports_file_diffs &= response.supports_file_diffs;
+                        supports_hot_reload &= response.supports_hot_reload;
                     }
 
+                    if supports_file_diffs {
+                        sent_files.retain_installs(installer_label, &install_ids);
+                    }
+
+                    let mut sent = HashMap::new();
                     let send_files_result =
                         tokio_stream::wrappers::UnboundedReceiverStream::new(files_rx)
+                            .inspect(|file| {
+                                sent.insert(
+                                    (file.install_id.clone(), file.name.clone()),
+                                    file.artifact_value.dupe(),
+                                );
+                            })
                             .map(anyhow::Ok)
                             .try_for_each_concurrent(None, |file| {
                                 send_file(
                                     file,
                                     &artifact_fs,
                                     client.clone(),
+                                    installer_label,
+                                    supports_file_diffs.then_some(sent_files),
                                     installer_log_filename.to_owned(),
                                 )
                             })
                             .await;
                     let installer_finished = Instant::now();
-                    send_shutdown_command(client.clone()).await?;
+                    if let Some(sessions) = hot_reload_sessions
+                        .filter(|_| supports_hot_reload && send_files_result.is_ok())
+                    {
+                        sessions.keep(
+                            installer_label,
+                            HotReloadSession {
+                                client,
+                                install_log: installer_log_filename,
+                                install_ids,
+                                supports_file_diffs,
+                                sent,
+                            },
+                        );
+                    } else {
+                        send_shutdown_command(client.clone()).await?;
+                    }
                     send_files_result.context("Failed to send artifacts to installer")?;
                     anyhow::Ok((installer_ready, installer_finished))
                 }
@@ -417,7 +544,7 @@ async fn send_install_info(
     install_id: &str,
     install_files: &SmallMap<&str, Artifact>,
     artifact_fs: &ArtifactFs,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<InstallerResponse> {
     let mut files_map = HashMap::new();
     for (file_name, artifact) in install_files {
         let artifact_path = &artifact_fs
@@ -454,7 +581,7 @@ async fn send_install_info(
         ));
     }
 
-    Ok(())
+    Ok(install_info_response)
 }
 
 async fn send_shutdown_command(mut client: InstallerClient<Channel>) -> anyhow::Result<()> {
@@ -618,16 +745,12 @@ async fn connect_to_installer(tcp_port: u16) -> anyhow::Result<InstallerClient<C
     .await
 }
 
-async fn send_file(
-    file: FileResult,
+/// The request telling the installer that `file` is ready, and the path of the file.
+fn file_ready_request(
+    file: &FileResult,
     artifact_fs: &ArtifactFs,
-    mut client: InstallerClient<Channel>,
-    install_log: String,
-) -> anyhow::Result<()> {
-    let install_id = file.install_id;
-    let name = file.name;
-    let artifact = file.artifact;
-
+    diff: Option<FileDiff>,
+) -> anyhow::Result<(FileReadyRequest, AbsNormPathBuf)> {
     enum Data<'a> {
         Digest(&'a FileDigest), // NOTE: A misnommer, this is rather BlobDigest.
         Symlink(String),
@@ -655,24 +778,123 @@ async fn send_file(
         Data::Symlink(sym) => (format!("re-symlink:{}", sym), 0, "".to_owned()), // Messy :(
     };
 
-    let path = &artifact_fs
+    let path = artifact_fs
         .fs()
-        .resolve(&artifact.resolve_path(artifact_fs)?);
-    let request = tonic::Request::new(FileReadyRequest {
-        install_id: install_id.to_owned(),
-        name: name.to_owned(),
-        digest,
-        digest_algorithm,
-        size,
-        path: path.to_string(),
-    });
+        .resolve(&file.artifact.resolve_path(artifact_fs)?);
+    Ok((
+        FileReadyRequest {
+            install_id: file.install_id.clone(),
+            name: file.name.clone(),
+            digest,
+            digest_algorithm,
+            size,
+            path: path.to_string(),
+            diff,
+        },
+        path,
+    ))
+}
+
+/// Sends the files which changed since they were last sent to a hot reloading installer, one
+/// request per install id.
+async fn send_changed_files(
+    session: &mut HotReloadSession,
+    installer_label: &ConfiguredProvidersLabel,
+    files: Vec<FileResult>,
+    artifact_fs: &ArtifactFs,
+    sent_files: &Arc<SentInstallFiles>,
+) -> anyhow::Result<()> {
+    let mut changed: BTreeMap<String, Vec<(FileResult, FileReadyRequest)>> = BTreeMap::new();
+    for file in files {
+        let previous = session
+            .sent
+            .get(&(file.install_id.clone(), file.name.clone()));
+        if previous == Some(&file.artifact_value) {
+            continue;
+        }
+        let diff = match previous {
+            Some(previous) if session.supports_file_diffs => {
+                diff::diff(previous, &file.artifact_value)?
+            }
+            _ => None,
+        };
+        let (request, _path) = file_ready_request(&file, artifact_fs, diff)?;
+        changed
+            .entry(file.install_id.clone())
+            .or_default()
+            .push((file, request));
+    }
+
+    let changed_count: usize = changed.values().map(|files| files.len()).sum();
+    for (install_id, files) in changed {
+        let (files, requests): (Vec<_>, Vec<_>) = files.into_iter().unzip();
+        let response = session
+            .client
+            .artifacts_changed(tonic::Request::new(ArtifactsChangedRequest {
+                install_id: install_id.clone(),
+                files: requests,
+            }))
+            .await;
+        let err = match response {
+            Ok(response) => response.into_inner().error_detail.map(|e| e.message),
+            Err(status) => Some(status.message().to_owned()),
+        };
+        if let Some(err) = err {
+            return Err(InstallError::HotReloadFailure {
+                install_id,
+                err,
+                installer_log: session.install_log.clone(),
+            }
+            .into());
+        }
+        for file in files {
+            if session.supports_file_diffs {
+                SentFile::new(sent_files, installer_label, &file.install_id, &file.name)
+                    .record(Some(file.artifact_value.dupe()));
+            }
+            session
+                .sent
+                .insert((file.install_id, file.name), file.artifact_value);
+        }
+    }
+
+    if changed_count > 0 {
+        console_message(format!(
+            "Hot reloaded {} changed artifact(s)",
+            changed_count
+        ));
+    }
+    Ok(())
+}
+
+async fn send_file(
+    file: FileResult,
+    artifact_fs: &ArtifactFs,
+    mut client: InstallerClient<Channel>,
+    installer_label: &ConfiguredProvidersLabel,
+    sent_files: Option<&Arc<SentInstallFiles>>,
+    install_log: String,
+) -> anyhow::Result<()> {
+    let install_id = file.install_id.clone();
+    let name = file.name.clone();
+
+    // Only installers which support file diffs are sent diffs against the files they were sent.
+    let sent_file =
+        sent_files.map(|sent_files| SentFile::new(sent_files, installer_label, &install_id, &name));
+    let diff = match &sent_file {
+        Some(sent_file) => sent_file.diff(&file.artifact_value)?,
+        None => None,
+    };
+    let (request, path) = file_ready_request(&file, artifact_fs, diff)?;
+    let path = &path;
+    let request = tonic::Request::new(request);
 
     let start = InstallEventInfoStart {
         artifact_name: name.to_owned(),
         file_path: path.to_string(),
     };
     let end = InstallEventInfoEnd {};
-    span_async(start, async {
+    let result = span_async(start, async {
         let mut outcome: anyhow::Result<()> = Ok(());
         let response_result = client.file_ready(request).await;
         let response = match response_result {
@@ -718,6 +940,9 @@ async fn send_file(
         }
         (outcome, end)
     })
-    .await?;
-    Ok(())
+    .await;
+    if let Some(sent_file) = sent_file {
+        sent_file.record(result.is_ok().then(|| file.artifact_value.dupe()));
+    }
+    result
 }
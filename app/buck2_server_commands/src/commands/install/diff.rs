@@ -0,0 +1,296 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Diffs of install files against the version last sent to an installer, so that installers
+//! which support them only push what changed to the device.
+//!
+//! The daemon remembers the value of every file it sent to an installer which supports file
+//! diffs (see `SentInstallFiles`), per installer, install id and name. Installers own the state
+//! of the device: a diff is only a hint, which applies on top of its `previous_digest`.
+//!
+//! Only directories are diffed: a file artifact, such as an APK, is sent whole when it changes.
+
+use std::sync::Arc;
+
+use buck2_build_api::sent_install_files::SentInstallFiles;
+use buck2_common::file_ops::TrackedFileDigest;
+use buck2_core::directory::DirectoryEntry;
+use buck2_core::directory::FingerprintedDirectory;
+use buck2_core::provider::label::ConfiguredProvidersLabel;
+use buck2_execute::artifact_value::ArtifactValue;
+use buck2_execute::directory::ActionDirectoryMember;
+use buck2_install_proto::FileDiff;
+use buck2_install_proto::FileDiffEntry;
+
+type Dir<'a> = dyn FingerprintedDirectory<ActionDirectoryMember, TrackedFileDigest> + 'a;
+
+/// A file sent to an installer which supports file diffs.
+pub(crate) struct SentFile<'a> {
+    sent_files: &'a Arc<SentInstallFiles>,
+    installer: &'a ConfiguredProvidersLabel,
+    install_id: &'a str,
+    name: &'a str,
+}
+
+impl<'a> SentFile<'a> {
+    pub(crate) fn new(
+        sent_files: &'a Arc<SentInstallFiles>,
+        installer: &'a ConfiguredProvidersLabel,
+        install_id: &'a str,
+        name: &'a str,
+    ) -> Self {
+        Self {
+            sent_files,
+            installer,
+            install_id,
+            name,
+        }
+    }
+
+    /// Diff of `value` against the value last sent, if any and if they can be diffed.
+    pub(crate) fn diff(&self, value: &ArtifactValue) -> anyhow::Result<Option<FileDiff>> {
+        match self
+            .sent_files
+            .get(self.installer, self.install_id, self.name)
+        {
+            Some(previous) => diff(&previous, value),
+            None => Ok(None),
+        }
+    }
+
+    /// Record that `value` was sent, or forget about the file if sending it failed.
+    pub(crate) fn record(&self, value: Option<ArtifactValue>) {
+        self.sent_files
+            .record(self.installer, self.install_id, self.name, value)
+    }
+}
+
+pub(crate) fn diff(
+    previous: &ArtifactValue,
+    current: &ArtifactValue,
+) -> anyhow::Result<Option<FileDiff>> {
+    match (previous.entry(), current.entry()) {
+        (DirectoryEntry::Dir(previous), DirectoryEntry::Dir(current)) => {
+            let mut diff = FileDiff {
+                previous_digest: previous.fingerprint().data().raw_digest().to_string(),
+                ..FileDiff::default()
+            };
+            if previous.fingerprint() != current.fingerprint() {
+                diff_dirs(previous, current, "", &mut diff)?;
+            }
+            Ok(Some(diff))
+        }
+        (
+            DirectoryEntry::Leaf(ActionDirectoryMember::File(previous)),
+            DirectoryEntry::Leaf(ActionDirectoryMember::File(current)),
+        ) if previous == current => Ok(Some(FileDiff {
+            previous_digest: previous.digest.data().raw_digest().to_string(),
+            ..FileDiff::default()
+        })),
+        _ => Ok(None),
+    }
+}
+
+fn join(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{}/{}", prefix, name)
+    }
+}
+
+fn diff_dirs<'a>(
+    previous: &'a Dir<'a>,
+    current: &'a Dir<'a>,
+    prefix: &str,
+    diff: &mut FileDiff,
+) -> anyhow::Result<()> {
+    for (name, entry) in current.fingerprinted_entries() {
+        let path = join(prefix, name.as_str());
+        match (previous.get(name), entry) {
+            (Some(DirectoryEntry::Dir(previous)), DirectoryEntry::Dir(current)) => {
+                if previous.fingerprint() != current.fingerprint() {
+                    diff_dirs(previous, current, &path, diff)?;
+                }
+            }
+            (Some(DirectoryEntry::Leaf(previous)), DirectoryEntry::Leaf(current))
+                if previous == current => {}
+            (previous, entry) => {
+                // Entries of a different kind are replaced, rather than merged.
+                if matches!(
+                    (&previous, &entry),
+                    (Some(DirectoryEntry::Dir(_)), DirectoryEntry::Leaf(_))
+                        | (Some(DirectoryEntry::Leaf(_)), DirectoryEntry::Dir(_))
+                ) {
+                    diff.removed.push(path.clone());
+                }
+                add_entry(entry, path, diff)?;
+            }
+        }
+    }
+    for (name, _) in previous.fingerprinted_entries() {
+        if current.get(name).is_none() {
+            diff.removed.push(join(prefix, name.as_str()));
+        }
+    }
+    Ok(())
+}
+
+fn add_entry<'a>(
+    entry: DirectoryEntry<&'a Dir<'a>, &'a ActionDirectoryMember>,
+    path: String,
+    diff: &mut FileDiff,
+) -> anyhow::Result<()> {
+    let mut changed = FileDiffEntry {
+        path,
+        ..FileDiffEntry::default()
+    };
+    match entry {
+        DirectoryEntry::Dir(dir) => {
+            for (name, entry) in dir.fingerprinted_entries() {
+                add_entry(entry, join(&changed.path, name.as_str()), diff)?;
+            }
+            return Ok(());
+        }
+        DirectoryEntry::Leaf(ActionDirectoryMember::File(file)) => {
+            changed.digest = file.digest.data().raw_digest().to_string();
+            changed.size = file.digest.data().size();
+            changed.is_executable = file.is_executable;
+        }
+        DirectoryEntry::Leaf(ActionDirectoryMember::Symlink(symlink)) => {
+            changed.symlink_target = symlink.target().as_str().to_owned();
+        }
+        DirectoryEntry::Leaf(ActionDirectoryMember::ExternalSymlink(symlink)) => {
+            changed.symlink_target = symlink.with_full_target()?.target_str().to_owned();
+        }
+    }
+    diff.changed.push(changed);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_common::file_ops::FileMetadata;
+    use buck2_common::file_ops::TrackedFileDigest;
+    use buck2_core::directory::DirectoryEntry;
+    use buck2_core::fs::project_rel_path::ProjectRelativePath;
+    use buck2_execute::artifact_value::ArtifactValue;
+    use buck2_execute::digest_config::DigestConfig;
+    use buck2_execute::directory::insert_file;
+    use buck2_execute::directory::ActionDirectoryBuilder;
+    use buck2_execute::directory::INTERNER;
+    use buck2_install_proto::FileDiff;
+
+    use super::diff;
+
+    fn file(content: &str) -> FileMetadata {
+        FileMetadata {
+            digest: TrackedFileDigest::from_content(
+                content.as_bytes(),
+                DigestConfig::testing_default().cas_digest_config(),
+            ),
+            is_executable: false,
+        }
+    }
+
+    fn dir(files: &[(&str, &str)]) -> ArtifactValue {
+        let mut builder = ActionDirectoryBuilder::empty();
+        for (path, content) in files {
+            insert_file(
+                &mut builder,
+                ProjectRelativePath::new(path).unwrap(),
+                file(content),
+            )
+            .unwrap();
+        }
+        ArtifactValue::dir(
+            builder
+                .fingerprint(DigestConfig::testing_default().as_directory_serializer())
+                .shared(&*INTERNER),
+        )
+    }
+
+    fn changed_and_removed(diff: &FileDiff) -> (Vec<&str>, Vec<&str>) {
+        let mut changed: Vec<_> = diff.changed.iter().map(|e| e.path.as_str()).collect();
+        let mut removed: Vec<_> = diff.removed.iter().map(|p| p.as_str()).collect();
+        changed.sort();
+        removed.sort();
+        (changed, removed)
+    }
+
+    #[test]
+    fn test_diff_unchanged_dir() -> anyhow::Result<()> {
+        let previous = dir(&[("a", "1"), ("sub/b", "2")]);
+        let diff = diff(&previous, &dir(&[("a", "1"), ("sub/b", "2")]))?.unwrap();
+        let DirectoryEntry::Dir(previous) = previous.entry() else {
+            unreachable!()
+        };
+        assert_eq!(
+            previous.fingerprint().data().raw_digest().to_string(),
+            diff.previous_digest
+        );
+        assert_eq!((vec![], vec![]), changed_and_removed(&diff));
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_dirs() -> anyhow::Result<()> {
+        let diff = diff(
+            &dir(&[("a", "1"), ("sub/b", "2"), ("sub/c", "3"), ("gone/d", "4")]),
+            &dir(&[("a", "1"), ("sub/b", "22"), ("sub/e", "5"), ("new/f", "6")]),
+        )?
+        .unwrap();
+        assert_eq!(
+            (vec!["new/f", "sub/b", "sub/e"], vec!["gone", "sub/c"]),
+            changed_and_removed(&diff)
+        );
+        let changed = diff.changed.iter().find(|e| e.path == "sub/b").unwrap();
+        assert_eq!(
+            file("22").digest.data().raw_digest().to_string(),
+            changed.digest
+        );
+        assert_eq!(2, changed.size);
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_dirs_kind_changed() -> anyhow::Result<()> {
+        let diff = diff(
+            &dir(&[("x", "1"), ("y/z", "2")]),
+            &dir(&[("x/w", "3"), ("y", "4")]),
+        )?
+        .unwrap();
+        assert_eq!(
+            (vec!["x/w", "y"], vec!["x", "y"]),
+            changed_and_removed(&diff)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_files() -> anyhow::Result<()> {
+        let apk = ArtifactValue::file(file("apk"));
+
+        // An unchanged file has an empty diff.
+        let diff_same = diff(&apk, &ArtifactValue::file(file("apk")))?.unwrap();
+        assert_eq!(
+            file("apk").digest.data().raw_digest().to_string(),
+            diff_same.previous_digest
+        );
+        assert_eq!((vec![], vec![]), changed_and_removed(&diff_same));
+
+        // A changed file is sent whole.
+        assert!(diff(&apk, &ArtifactValue::file(file("apk2")))?.is_none());
+
+        // So is an artifact which changed kind.
+        assert!(diff(&apk, &dir(&[("a", "1")]))?.is_none());
+        assert!(diff(&dir(&[("a", "1")]), &apk)?.is_none());
+        Ok(())
+    }
+}
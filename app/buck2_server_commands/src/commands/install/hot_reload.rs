@@ -0,0 +1,113 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! `buck2 install --hot-reload`.
+//!
+//! The command keeps running until the client disconnects, and installs again whenever the
+//! file watcher of the daemon is told about changes.
+//!
+//! An installer which declares `supports_hot_reload` is not shut down after the install. When
+//! the same install is requested again, it is sent the files which changed since, instead of
+//! being launched again. It is shut down when it fails, when the install is requested for
+//! different targets, and when the command ends.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use anyhow::Context as _;
+use buck2_cli_proto::InstallRequest;
+use buck2_cli_proto::InstallResponse;
+use buck2_core::provider::label::ConfiguredProvidersLabel;
+use buck2_events::dispatch::console_message;
+use buck2_execute::artifact_value::ArtifactValue;
+use buck2_install_proto::installer_client::InstallerClient;
+use buck2_server_ctx::ctx::ServerCommandContextTrait;
+use buck2_server_ctx::ctx::ServerCommandDiceContext;
+use tonic::transport::Channel;
+
+use crate::commands::install::install;
+use crate::commands::install::send_shutdown_command;
+
+pub(crate) struct HotReloadSession {
+    pub(crate) client: InstallerClient<Channel>,
+    pub(crate) install_log: String,
+    /// The install ids the installer was told about, in order.
+    pub(crate) install_ids: Vec<String>,
+    pub(crate) supports_file_diffs: bool,
+    /// The files sent to the installer, keyed by install id and name.
+    pub(crate) sent: HashMap<(String, String), ArtifactValue>,
+}
+
+/// The installers kept running by one command, keyed by installer. They are shut down when the
+/// command ends.
+#[derive(Default)]
+pub(crate) struct HotReloadSessions {
+    sessions: Mutex<HashMap<String, HotReloadSession>>,
+}
+
+impl HotReloadSessions {
+    /// The running session of `installer`, if any. It is kept running only if it is given back
+    /// to `keep`.
+    pub(crate) fn take(&self, installer: &ConfiguredProvidersLabel) -> Option<HotReloadSession> {
+        self.sessions.lock().unwrap().remove(&installer.to_string())
+    }
+
+    pub(crate) fn keep(&self, installer: &ConfiguredProvidersLabel, session: HotReloadSession) {
+        self.sessions
+            .lock()
+            .unwrap()
+            .insert(installer.to_string(), session);
+    }
+}
+
+impl Drop for HotReloadSessions {
+    fn drop(&mut self) {
+        let sessions = self.sessions.get_mut().unwrap();
+        for (_, session) in sessions.drain() {
+            // Dropped when the command is cancelled, so the shutdown cannot be awaited. The
+            // installer may have exited already.
+            tokio::spawn(async move { send_shutdown_command(session.client).await.ok() });
+        }
+    }
+}
+
+/// Install, then install again after every change, until the command is cancelled because the
+/// client disconnected.
+pub(crate) async fn install_on_changes(
+    server_ctx: &dyn ServerCommandContextTrait,
+    request: &InstallRequest,
+) -> anyhow::Result<InstallResponse> {
+    let sessions = &HotReloadSessions::default();
+    let mut changes = server_ctx
+        .file_watcher()
+        .subscribe()
+        .await
+        .context("Hot reload requires a file watcher which reports changes")?;
+    loop {
+        // The changes reported from now on are not synced into DICE yet, so they trigger
+        // another install.
+        changes.borrow_and_update();
+        server_ctx
+            .with_dice_ctx(|server_ctx, ctx| async move {
+                // Failed installs are reported, and retried after the next change.
+                match install(server_ctx, ctx, request, Some(sessions)).await {
+                    Ok(_) => console_message("Install succeeded, waiting for changes".to_owned()),
+                    Err(e) => {
+                        console_message(format!("Install failed, waiting for changes: {:#}", e))
+                    }
+                }
+                anyhow::Ok(())
+            })
+            .await?;
+        changes
+            .changed()
+            .await
+            .context("The file watcher stopped reporting changes")?;
+    }
+}
@@ -36,7 +36,9 @@ use buck2_util::truncate::truncate;
 use dice::DiceTransaction;
 use dice::LinearRecomputeDiceComputations;
 use dupe::Dupe;
+use regex::RegexSet;
 
+use crate::commands::graph_snapshot::get_snapshot;
 use crate::commands::query::printer::ProviderLookUp;
 use crate::commands::query::printer::QueryResultPrinter;
 use crate::commands::query::printer::ShouldPrintProviders;
@@ -154,7 +156,10 @@ async fn cquery(
         target_universe,
         context,
         show_providers,
+        list_providers,
+        provider_fields,
         target_cfg,
+        at_invocation,
         ..
     } = request;
     // The request will always have a universe value, an empty one indicates the user didn't provide a universe.
@@ -176,21 +181,44 @@ async fn cquery(
     )
     .await?;
 
-    let query_result = QUERY_FRONTEND
-        .get()?
-        .eval_cquery(
-            &mut ctx,
-            server_ctx.working_dir(),
-            query,
-            query_args,
-            global_cfg_options,
-            target_universe,
-        )
-        .await?;
+    let query_result = if at_invocation.is_empty() {
+        QUERY_FRONTEND
+            .get()?
+            .eval_cquery(
+                &mut ctx,
+                server_ctx.working_dir(),
+                query,
+                query_args,
+                global_cfg_options,
+                target_universe,
+            )
+            .await?
+    } else {
+        let snapshot = get_snapshot(&ctx, at_invocation)?;
+        QUERY_FRONTEND
+            .get()?
+            .eval_cquery_in_snapshot(
+                &mut ctx,
+                server_ctx.working_dir(),
+                query,
+                query_args,
+                global_cfg_options,
+                snapshot,
+            )
+            .await?
+    };
+
+    let provider_fields = RegexSet::new(provider_fields)?;
+    let provider_fields = &provider_fields;
 
     ctx.with_linear_recompute(|ctx| async move {
         let should_print_providers = if *show_providers {
             ShouldPrintProviders::Yes(&ctx as &dyn ProviderLookUp<ConfiguredTargetNode>)
+        } else if *list_providers {
+            ShouldPrintProviders::Summary(
+                &ctx as &dyn ProviderLookUp<ConfiguredTargetNode>,
+                provider_fields,
+            )
         } else {
             ShouldPrintProviders::No
         };
@@ -51,6 +51,8 @@ use crate::dot::DotCompact;
 pub enum ShouldPrintProviders<'a, T> {
     No,
     Yes(&'a dyn ProviderLookUp<T>),
+    /// Print the names of the providers, with the fields whose `Provider.field` matches.
+    Summary(&'a dyn ProviderLookUp<T>, &'a RegexSet),
 }
 
 #[async_trait]
@@ -83,7 +85,7 @@ impl<'a, T: QueryTarget> TargetSetJsonPrinter<'a, T> {
                 .await?,
             is_complex: attributes.is_some()
                 || target_call_stacks
-                || print_providers.unpack_yes().is_some(),
+                || !matches!(print_providers, ShouldPrintProviders::No),
         })
     }
 }
@@ -91,10 +93,27 @@ impl<'a, T: QueryTarget> TargetSetJsonPrinter<'a, T> {
 struct PrintableQueryTarget<'a, T: QueryTarget> {
     value: &'a T,
     attributes: &'a Option<RegexSet>,
-    providers: Option<FrozenProviderCollectionValue>,
+    providers: Option<PrintableProviders>,
     target_call_stacks: bool,
 }
 
+enum PrintableProviders {
+    Full(FrozenProviderCollectionValue),
+    Summary(serde_json::Map<String, serde_json::Value>),
+}
+
+impl Serialize for PrintableProviders {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            PrintableProviders::Full(providers) => providers.serialize(serializer),
+            PrintableProviders::Summary(summary) => summary.serialize(serializer),
+        }
+    }
+}
+
 impl<'a, T: QueryTarget> PrintableQueryTarget<'a, T> {
     fn label(&self) -> String {
         self.value.node_key().to_string()
@@ -120,13 +139,27 @@ impl<'a, T: QueryCommandTarget> Display for PrintableQueryTarget<'a, T> {
             }
         }
 
-        if let Some(providers) = &self.providers {
-            use std::fmt::Write;
-            write!(
-                IndentWriter::new("  ", f),
-                "{:#}",
-                providers.provider_collection()
-            )?;
+        match &self.providers {
+            Some(PrintableProviders::Full(providers)) => {
+                use std::fmt::Write;
+                write!(
+                    IndentWriter::new("  ", f),
+                    "{:#}",
+                    providers.provider_collection()
+                )?;
+            }
+            Some(PrintableProviders::Summary(summary)) => {
+                for (i, (provider, fields)) in summary.iter().enumerate() {
+                    if i > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "  {}", provider)?;
+                    for (field, value) in fields.as_object().into_iter().flatten() {
+                        write!(f, "\n    {} = {}", field, value)?;
+                    }
+                }
+            }
+            None => {}
         }
 
         Ok(())
@@ -461,8 +494,16 @@ async fn printable_targets<'a, T: QueryTarget>(
             target_call_stacks,
             providers: match print_providers {
                 ShouldPrintProviders::No => None,
-                ShouldPrintProviders::Yes(lookup) => {
-                    Some(lookup.lookup(t).await?.require_compatible()?)
+                ShouldPrintProviders::Yes(lookup) => Some(PrintableProviders::Full(
+                    lookup.lookup(t).await?.require_compatible()?,
+                )),
+                ShouldPrintProviders::Summary(lookup, fields) => {
+                    let providers = lookup.lookup(t).await?.require_compatible()?;
+                    Some(PrintableProviders::Summary(
+                        providers.provider_collection().summary(|provider, field| {
+                            fields.is_match(&format!("{}.{}", provider, field))
+                        }),
+                    ))
                 }
             },
         })
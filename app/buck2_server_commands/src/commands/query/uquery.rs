@@ -117,6 +117,10 @@ impl ServerCommandTemplate for UqueryServerCommand {
     fn is_success(&self, _: &Self::Response) -> bool {
         true
     }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
 }
 
 async fn uquery(
@@ -9,6 +9,7 @@
 
 mod default;
 pub(crate) mod fmt;
+pub mod json_schema;
 mod resolve_alias;
 mod streaming;
 use std::fs::File;
@@ -167,6 +168,10 @@ impl ServerCommandTemplate for TargetsServerCommand {
                 .collect(),
         }
     }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
 }
 
 async fn targets(
@@ -125,7 +125,7 @@ pub(crate) async fn targets_batch(
         })
         .await?;
 
-    let mut buffer = String::new();
+    let mut buffer = Vec::new();
     formatter.begin(&mut buffer);
     let mut stats = Stats::default();
     let mut needs_separator = false;
@@ -151,7 +151,7 @@ pub(crate) async fn targets_batch(
                             super_package: res.super_package(),
                         },
                         &mut buffer,
-                    )
+                    )?;
                 }
             }
             Err(e) => {
@@ -162,7 +162,7 @@ pub(crate) async fn targets_batch(
                     formatter.separator(&mut buffer);
                 }
                 needs_separator = true;
-                formatter.package_error(package.dupe(), e, &mut buffer, &mut stderr);
+                formatter.package_error(package.dupe(), e, &mut buffer, &mut stderr)?;
 
                 server_ctx.stderr()?.write_all(stderr.as_bytes())?;
 
@@ -178,7 +178,7 @@ pub(crate) async fn targets_batch(
     } else {
         Ok(TargetsResponse {
             error_count: stats.errors,
-            serialized_targets_output: buffer,
+            serialized_targets_output: String::from_utf8(buffer)?,
         })
     }
 }
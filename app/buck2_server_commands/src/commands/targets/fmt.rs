@@ -7,14 +7,18 @@
  * of this source tree.
  */
 
+use std::collections::BTreeMap;
 use std::collections::BTreeSet;
-use std::fmt::Write;
+use std::io;
+use std::io::Write;
 use std::sync::Arc;
 
+use buck2_cli_proto::targets_output_entry;
 use buck2_cli_proto::targets_request;
 use buck2_cli_proto::targets_request::OutputFormat;
 use buck2_cli_proto::targets_request::TargetHashGraphType;
 use buck2_cli_proto::HasClientContext;
+use buck2_cli_proto::TargetsOutputEntry;
 use buck2_cli_proto::TargetsRequest;
 use buck2_core::bzl::ImportPath;
 use buck2_core::cells::cell_path::CellPath;
@@ -34,8 +38,13 @@ use buck2_node::nodes::attributes::TYPE;
 use buck2_node::nodes::unconfigured::TargetNodeRef;
 use buck2_node::super_package::SuperPackage;
 use buck2_util::indent::indent;
+use dupe::Dupe;
 use gazebo::prelude::SliceExt;
+use indexmap::IndexMap;
+use prost::Message;
 use regex::RegexSet;
+use schemars::JsonSchema;
+use serde::Serialize;
 
 use crate::json::QuotedJson;
 use crate::target_hash::BuckTargetHash;
@@ -47,32 +56,37 @@ pub(crate) struct TargetInfo<'a> {
 }
 
 fn package_error_to_stderr(package: PackageLabel, error: &buck2_error::Error, stderr: &mut String) {
-    writeln!(stderr, "Error parsing {package}\n{error:?}").unwrap();
+    stderr.push_str(&format!("Error parsing {package}\n{error:?}\n"));
 }
 
+/// Formatters write bytes rather than strings because the binary format is not UTF-8.
 #[allow(unused_variables)]
 pub(crate) trait TargetFormatter: Send + Sync {
-    fn begin(&self, buffer: &mut String) {}
-    fn end(&self, stats: &Stats, buffer: &mut String) {}
+    fn begin(&self, buffer: &mut Vec<u8>) {}
+    fn end(&self, stats: &Stats, buffer: &mut Vec<u8>) {}
     /// Called between each target/imports/package_error
-    fn separator(&self, buffer: &mut String) {}
-    fn target(&self, target_info: TargetInfo<'_>, buffer: &mut String) {}
+    fn separator(&self, buffer: &mut Vec<u8>) {}
+    fn target(&self, target_info: TargetInfo<'_>, buffer: &mut Vec<u8>) -> anyhow::Result<()> {
+        Ok(())
+    }
     fn imports(
         &self,
         source: &CellPath,
         imports: &[ImportPath],
         package: Option<PackageLabel>,
-        buffer: &mut String,
-    ) {
+        buffer: &mut Vec<u8>,
+    ) -> anyhow::Result<()> {
+        Ok(())
     }
     fn package_error(
         &self,
         package: PackageLabel,
         error: &buck2_error::Error,
-        stdout: &mut String,
+        stdout: &mut Vec<u8>,
         stderr: &mut String,
-    ) {
+    ) -> anyhow::Result<()> {
         package_error_to_stderr(package, error, stderr);
+        Ok(())
     }
 }
 
@@ -81,46 +95,58 @@ pub(crate) struct JsonWriter {
 }
 
 impl JsonWriter {
-    pub(crate) fn begin(&self, buffer: &mut String) {
+    pub(crate) fn begin(&self, buffer: &mut Vec<u8>) {
         if !self.json_lines {
-            buffer.push_str("[\n");
+            buffer.extend_from_slice(b"[\n");
         }
     }
 
-    pub(crate) fn end(&self, buffer: &mut String) {
+    pub(crate) fn end(&self, buffer: &mut Vec<u8>) {
         if !self.json_lines {
-            buffer.push_str("\n]\n");
+            buffer.extend_from_slice(b"\n]\n");
         }
     }
 
-    pub(crate) fn separator(&self, buffer: &mut String) {
+    pub(crate) fn separator(&self, buffer: &mut Vec<u8>) {
         if !self.json_lines {
-            buffer.push_str(",\n");
+            buffer.extend_from_slice(b",\n");
         }
     }
 
-    pub(crate) fn entry_start(&self, buffer: &mut String) {
+    pub(crate) fn entry_start(&self, buffer: &mut Vec<u8>) {
         if self.json_lines {
-            buffer.push('{');
+            buffer.push(b'{');
         } else {
-            buffer.push_str("  {\n");
+            buffer.extend_from_slice(b"  {\n");
         }
     }
 
-    pub(crate) fn entry_end(&self, buffer: &mut String, first: bool) {
+    pub(crate) fn entry_end(&self, buffer: &mut Vec<u8>, first: bool) {
         if self.json_lines {
-            buffer.push_str("}\n");
+            buffer.extend_from_slice(b"}\n");
         } else {
             if !first {
-                buffer.push('\n');
+                buffer.push(b'\n');
             }
-            buffer.push_str("  }");
+            buffer.extend_from_slice(b"  }");
+        }
+    }
+
+    /// Write a whole entry, laid out like `entry_start`, `entry_item` and `entry_end` do.
+    pub(crate) fn entry(&self, buffer: &mut Vec<u8>, entry: &impl Serialize) {
+        if self.json_lines {
+            serde_json::to_writer(&mut *buffer, entry).unwrap();
+            buffer.push(b'\n');
+        } else {
+            let mut serializer =
+                serde_json::Serializer::with_formatter(&mut *buffer, EntryFormatter { depth: 0 });
+            entry.serialize(&mut serializer).unwrap();
         }
     }
 
     pub(crate) fn entry_item(
         &self,
-        buffer: &mut String,
+        buffer: &mut Vec<u8>,
         first: &mut bool,
         key: &str,
         value: QuotedJson,
@@ -128,12 +154,12 @@ impl JsonWriter {
         if *first {
             *first = false;
         } else if self.json_lines {
-            buffer.push(',');
+            buffer.push(b',');
         } else {
-            buffer.push_str(",\n");
+            buffer.extend_from_slice(b",\n");
         }
         if !self.json_lines {
-            buffer.push_str("    ");
+            buffer.extend_from_slice(b"    ");
         }
         write!(
             buffer,
@@ -145,6 +171,105 @@ impl JsonWriter {
     }
 }
 
+/// Puts each field of an entry on its own line, and writes the values compactly.
+struct EntryFormatter {
+    depth: usize,
+}
+
+impl serde_json::ser::Formatter for EntryFormatter {
+    fn begin_object<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.depth += 1;
+        if self.depth == 1 {
+            writer.write_all(b"  {")
+        } else {
+            writer.write_all(b"{")
+        }
+    }
+
+    fn end_object<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.depth -= 1;
+        if self.depth == 0 {
+            writer.write_all(b"\n  }")
+        } else {
+            writer.write_all(b"}")
+        }
+    }
+
+    fn begin_object_key<W: ?Sized + io::Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        match (self.depth, first) {
+            (1, true) => writer.write_all(b"\n    "),
+            (1, false) => writer.write_all(b",\n    "),
+            (_, true) => Ok(()),
+            (_, false) => writer.write_all(b","),
+        }
+    }
+}
+
+/// A target. With `--output-attribute`, only the matching fields are present.
+///
+/// The names of the fields generated by buck are the ones of `buck2_node::nodes::attributes`.
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct TargetJson {
+    /// The rule type, like `prelude//rules.bzl:cxx_library`.
+    #[serde(rename = "buck.type", skip_serializing_if = "Option::is_none")]
+    rule_type: Option<String>,
+    /// Labels of the dependencies of the target.
+    #[serde(rename = "buck.deps", skip_serializing_if = "Option::is_none")]
+    deps: Option<Vec<String>>,
+    /// Source files of the target.
+    #[serde(rename = "buck.inputs", skip_serializing_if = "Option::is_none")]
+    inputs: Option<Vec<String>>,
+    /// Present with `--show-target-hash`.
+    #[serde(rename = "buck.target_hash", skip_serializing_if = "Option::is_none")]
+    target_hash: Option<String>,
+    #[serde(rename = "buck.package", skip_serializing_if = "Option::is_none")]
+    package: Option<String>,
+    /// Present with `--package-values` or `--package-values-regex`.
+    #[serde(
+        rename = "buck.package_values",
+        skip_serializing_if = "Option::is_none"
+    )]
+    package_values: Option<serde_json::Map<String, serde_json::Value>>,
+    #[serde(rename = "buck.oncall", skip_serializing_if = "Option::is_none")]
+    oncall: Option<String>,
+    /// Attributes of the target, keyed by name. The shape of the values depends on the attribute
+    /// types declared by the rule.
+    #[serde(flatten)]
+    #[schemars(with = "BTreeMap<String, serde_json::Value>")]
+    attributes: IndexMap<String, serde_json::Value>,
+    /// Present with `--stack`.
+    #[serde(
+        rename = "buck.target_call_stack",
+        skip_serializing_if = "Option::is_none"
+    )]
+    target_call_stack: Option<String>,
+}
+
+/// The imports of a build file or a `.bzl` file, with `--imports`.
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct ImportsJson {
+    /// Absent for `.bzl` files.
+    #[serde(rename = "buck.package", skip_serializing_if = "Option::is_none")]
+    package: Option<String>,
+    #[serde(rename = "buck.file")]
+    file: String,
+    #[serde(rename = "buck.imports")]
+    imports: Vec<String>,
+}
+
+/// A package which failed to load, with `--keep-going`.
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct PackageErrorJson {
+    #[serde(rename = "buck.package")]
+    package: String,
+    #[serde(rename = "buck.error")]
+    error: String,
+}
+
 struct JsonFormat {
     attributes: Option<RegexSet>,
     attr_inspect_opts: AttrInspectOptions,
@@ -154,103 +279,188 @@ struct JsonFormat {
 }
 
 impl TargetFormatter for JsonFormat {
-    fn begin(&self, buffer: &mut String) {
+    fn begin(&self, buffer: &mut Vec<u8>) {
         self.writer.begin(buffer)
     }
 
-    fn end(&self, _stats: &Stats, buffer: &mut String) {
+    fn end(&self, _stats: &Stats, buffer: &mut Vec<u8>) {
         self.writer.end(buffer)
     }
 
-    fn separator(&self, buffer: &mut String) {
+    fn separator(&self, buffer: &mut Vec<u8>) {
         self.writer.separator(buffer)
     }
 
-    fn target(&self, target_info: TargetInfo<'_>, buffer: &mut String) {
-        self.writer.entry_start(buffer);
-        let mut first = true;
+    fn target(&self, target_info: TargetInfo<'_>, buffer: &mut Vec<u8>) -> anyhow::Result<()> {
+        let node = target_info.node;
+        let selected = |k: &str| {
+            self.attributes
+                .as_ref()
+                .map_or(true, |filter| filter.is_match(k))
+        };
 
-        fn print_attr(
-            this: &JsonFormat,
-            buffer: &mut String,
-            first: &mut bool,
-            k: &str,
-            v: impl FnOnce() -> QuotedJson,
-        ) {
-            if let Some(filter) = &this.attributes {
-                if !filter.is_match(k) {
-                    return;
-                }
-            }
-            this.writer.entry_item(buffer, first, k, v());
-        }
+        let package_values = match &self.package_values {
+            Some(filter) if selected(PACKAGE_VALUES) => Some(
+                target_info
+                    .super_package
+                    .package_values()
+                    .package_values_json()?
+                    .iter()
+                    .filter(|(k, _)| filter.is_match(k.as_str()))
+                    .map(|(k, v)| (k.as_str().to_owned(), v.clone()))
+                    .collect(),
+            ),
+            _ => None,
+        };
 
-        print_attr(self, buffer, &mut first, TYPE, || {
-            QuotedJson::quote_str(&target_info.node.rule_type().to_string())
-        });
-        print_attr(self, buffer, &mut first, DEPS, || {
-            QuotedJson::list(target_info.node.deps().map(QuotedJson::quote_display))
-        });
-
-        print_attr(self, buffer, &mut first, INPUTS, || {
-            QuotedJson::list(target_info.node.inputs().map(QuotedJson::quote_display))
-        });
-
-        if let Some(hash) = target_info.target_hash {
-            print_attr(self, buffer, &mut first, TARGET_HASH, || {
-                QuotedJson::quote_display(hash)
-            });
-        }
-        print_attr(self, buffer, &mut first, PACKAGE, || {
-            QuotedJson::quote_display(target_info.node.label().pkg())
-        });
+        let entry = TargetJson {
+            rule_type: selected(TYPE).then(|| node.rule_type().to_string()),
+            deps: selected(DEPS).then(|| node.deps().map(|d| d.to_string()).collect()),
+            inputs: selected(INPUTS).then(|| node.inputs().map(|i| i.to_string()).collect()),
+            target_hash: target_info
+                .target_hash
+                .filter(|_| selected(TARGET_HASH))
+                .map(|h| h.to_string()),
+            package: selected(PACKAGE).then(|| node.label().pkg().to_string()),
+            package_values,
+            oncall: node
+                .oncall()
+                .filter(|_| selected(ONCALL))
+                .map(|o| o.to_owned()),
+            attributes: node
+                .attrs(self.attr_inspect_opts)
+                .filter(|a| selected(a.name))
+                .map(|a| {
+                    Ok((
+                        a.name.to_owned(),
+                        value_to_json(a.value, node.label().pkg())?,
+                    ))
+                })
+                .collect::<anyhow::Result<_>>()?,
+            target_call_stack: if self.target_call_stacks && selected(TARGET_CALL_STACK) {
+                node.call_stack()
+            } else {
+                None
+            },
+        };
+        self.writer.entry(buffer, &entry);
+        Ok(())
+    }
 
+    fn imports(
+        &self,
+        source: &CellPath,
+        imports: &[ImportPath],
+        package: Option<PackageLabel>,
+        buffer: &mut Vec<u8>,
+    ) -> anyhow::Result<()> {
+        self.writer.entry(
+            buffer,
+            &ImportsJson {
+                package: package.map(|p| p.to_string()),
+                file: source.to_string(),
+                imports: imports.map(|i| i.path().to_string()),
+            },
+        );
+        Ok(())
+    }
+
+    fn package_error(
+        &self,
+        package: PackageLabel,
+        error: &buck2_error::Error,
+        stdout: &mut Vec<u8>,
+        stderr: &mut String,
+    ) -> anyhow::Result<()> {
+        // When an error happens we print it to stdout (as a JSON entry) and to stderr (as a human message).
+        // If the user has keep-going turned on, they'll get the JSON on stdout, but also have the error message appear on stderr.
+        // If the user has keep-going turned off, they'll only see one error message and then abort.
+        package_error_to_stderr(package, error, stderr);
+        self.writer.entry(
+            stdout,
+            &PackageErrorJson {
+                package: package.to_string(),
+                error: format!("{error:?}"),
+            },
+        );
+        Ok(())
+    }
+}
+
+/// Length-delimited `TargetsOutputEntry` messages, meant for tools consuming the whole graph.
+struct BinaryFormat {
+    attributes: Option<RegexSet>,
+    attr_inspect_opts: AttrInspectOptions,
+    target_call_stacks: bool,
+    package_values: Option<RegexSet>,
+}
+
+impl BinaryFormat {
+    fn write_entry(
+        &self,
+        entry: targets_output_entry::Entry,
+        buffer: &mut Vec<u8>,
+    ) -> anyhow::Result<()> {
+        TargetsOutputEntry { entry: Some(entry) }.encode_length_delimited(buffer)?;
+        Ok(())
+    }
+
+    fn is_attribute_selected(&self, name: &str) -> bool {
+        self.attributes
+            .as_ref()
+            .map_or(true, |filter| filter.is_match(name))
+    }
+}
+
+impl TargetFormatter for BinaryFormat {
+    fn target(&self, target_info: TargetInfo<'_>, buffer: &mut Vec<u8>) -> anyhow::Result<()> {
+        let node = target_info.node;
+        let mut attributes = Vec::new();
         if let Some(filter) = &self.package_values {
-            print_attr(self, buffer, &mut first, PACKAGE_VALUES, || {
+            if self.is_attribute_selected(PACKAGE_VALUES) {
                 let package_values = serde_json::Value::Object(
                     target_info
                         .super_package
                         .package_values()
-                        .package_values_json()
-                        // TODO(nga): return error.
-                        .unwrap()
+                        .package_values_json()?
                         .iter()
                         .filter(|(k, _)| filter.is_match(k.as_str()))
                         .map(|(k, v)| (k.as_str().to_owned(), v.clone()))
                         .collect(),
                 );
-                QuotedJson::from_serde_json_value(package_values)
-            });
-        }
-
-        if let Some(oncall) = target_info.node.oncall() {
-            print_attr(self, buffer, &mut first, ONCALL, || {
-                QuotedJson::quote_display(oncall)
-            });
-        }
-
-        for a in target_info.node.attrs(self.attr_inspect_opts) {
-            print_attr(self, buffer, &mut first, a.name, || {
-                QuotedJson::from_serde_json_value(
-                    value_to_json(a.value, target_info.node.label().pkg()).unwrap(),
-                )
-            });
+                attributes.push(targets_output_entry::Attribute {
+                    name: PACKAGE_VALUES.to_owned(),
+                    value_json: package_values.to_string(),
+                });
+            }
         }
-
-        if self.target_call_stacks {
-            match target_info.node.call_stack() {
-                Some(call_stack) => {
-                    print_attr(self, buffer, &mut first, TARGET_CALL_STACK, || {
-                        QuotedJson::quote_str(&call_stack)
-                    });
-                }
-                None => {
-                    // Should not happen.
-                }
+        for a in node.attrs(self.attr_inspect_opts) {
+            if self.is_attribute_selected(a.name) {
+                attributes.push(targets_output_entry::Attribute {
+                    name: a.name.to_owned(),
+                    value_json: value_to_json(a.value, node.label().pkg())?.to_string(),
+                });
             }
         }
 
-        self.writer.entry_end(buffer, first);
+        self.write_entry(
+            targets_output_entry::Entry::Target(targets_output_entry::Target {
+                label: node.label().to_string(),
+                rule_type: node.rule_type().to_string(),
+                package: node.label().pkg().to_string(),
+                deps: node.deps().map(|d| d.to_string()).collect(),
+                inputs: node.inputs().map(|i| i.to_string()).collect(),
+                target_hash: target_info.target_hash.map(|h| h.to_string()),
+                oncall: node.oncall().map(|o| o.to_owned()),
+                attributes,
+                call_stack: if self.target_call_stacks {
+                    node.call_stack()
+                } else {
+                    None
+                },
+            }),
+            buffer,
+        )
     }
 
     fn imports(
@@ -258,59 +468,33 @@ impl TargetFormatter for JsonFormat {
         source: &CellPath,
         imports: &[ImportPath],
         package: Option<PackageLabel>,
-        buffer: &mut String,
-    ) {
-        self.writer.entry_start(buffer);
-        let mut first = true;
-        if let Some(package) = package {
-            self.writer.entry_item(
-                buffer,
-                &mut first,
-                PACKAGE,
-                QuotedJson::quote_str(&package.to_string()),
-            );
-        }
-        self.writer.entry_item(
+        buffer: &mut Vec<u8>,
+    ) -> anyhow::Result<()> {
+        self.write_entry(
+            targets_output_entry::Entry::Imports(targets_output_entry::Imports {
+                package: package.map(|p| p.to_string()),
+                file: source.to_string(),
+                imports: imports.map(|i| i.path().to_string()),
+            }),
             buffer,
-            &mut first,
-            "buck.file",
-            QuotedJson::quote_str(&source.to_string()),
-        );
-        self.writer.entry_item(
-            buffer,
-            &mut first,
-            "buck.imports",
-            QuotedJson::list(imports.map(|d| QuotedJson::quote_display(d.path()))),
-        );
-        self.writer.entry_end(buffer, first);
+        )
     }
 
     fn package_error(
         &self,
         package: PackageLabel,
         error: &buck2_error::Error,
-        stdout: &mut String,
+        stdout: &mut Vec<u8>,
         stderr: &mut String,
-    ) {
-        // When an error happens we print it to stdout (as a JSON entry) and to stderr (as a human message).
-        // If the user has keep-going turned on, they'll get the JSON on stdout, but also have the error message appear on stderr.
-        // If the user has keep-going turned off, they'll only see one error message and then abort.
-        package_error_to_stderr(package, error, stderr);
-        self.writer.entry_start(stdout);
-        let mut first = true;
-        self.writer.entry_item(
-            stdout,
-            &mut first,
-            PACKAGE,
-            QuotedJson::quote_display(package),
-        );
-        self.writer.entry_item(
+    ) -> anyhow::Result<()> {
+        package_error_to_stderr(package.dupe(), error, stderr);
+        self.write_entry(
+            targets_output_entry::Entry::PackageError(targets_output_entry::PackageError {
+                package: package.to_string(),
+                error: format!("{error:?}"),
+            }),
             stdout,
-            &mut first,
-            "buck.error",
-            QuotedJson::quote_str(&format!("{error:?}")),
-        );
-        self.writer.entry_end(stdout, first);
+        )
     }
 }
 
@@ -367,7 +551,7 @@ impl Stats {
 struct StatsFormat;
 
 impl TargetFormatter for StatsFormat {
-    fn end(&self, stats: &Stats, buffer: &mut String) {
+    fn end(&self, stats: &Stats, buffer: &mut Vec<u8>) {
         writeln!(buffer, "{:?}", stats).unwrap()
     }
 }
@@ -377,7 +561,7 @@ struct TargetNameFormat {
     target_hash_graph_type: TargetHashGraphType,
 }
 impl TargetFormatter for TargetNameFormat {
-    fn target(&self, target_info: TargetInfo<'_>, buffer: &mut String) {
+    fn target(&self, target_info: TargetInfo<'_>, buffer: &mut Vec<u8>) -> anyhow::Result<()> {
         if self.target_hash_graph_type != TargetHashGraphType::None {
             match target_info.target_hash {
                 Some(hash) => {
@@ -389,14 +573,17 @@ impl TargetFormatter for TargetNameFormat {
             writeln!(buffer, "{}", target_info.node.label()).unwrap();
         }
         if self.target_call_stacks {
-            print_target_call_stack_after_target(buffer, target_info.node.call_stack().as_deref());
+            if let Some(call_stack) = target_info.node.call_stack() {
+                write!(buffer, "{}", indent("  ", &call_stack)).unwrap();
+            }
         }
+        Ok(())
     }
 }
 
 pub(crate) fn print_target_call_stack_after_target(out: &mut String, call_stack: Option<&str>) {
     if let Some(call_stack) = call_stack {
-        write!(out, "{}", indent("  ", call_stack)).unwrap();
+        out.push_str(&indent("  ", call_stack).to_string());
     }
 }
 
@@ -410,17 +597,33 @@ pub(crate) fn create_formatter(
     let target_call_stacks = request.client_context()?.target_call_stacks;
 
     match output_format {
-        OutputFormat::Json | OutputFormat::JsonLines => {}
+        OutputFormat::Json | OutputFormat::JsonLines | OutputFormat::Binary => {}
         _ => {
             // Self-check.
             if !other.output_attributes.is_empty() {
                 return Err(internal_error!(
-                    "Attributes can only be specified when output format is JSON"
+                    "Attributes can only be specified when output format is JSON or binary"
                 ));
             }
         }
     }
 
+    let attributes = if other.output_attributes.is_empty() {
+        None
+    } else {
+        Some(RegexSet::new(&other.output_attributes)?)
+    };
+    let attr_inspect_opts = if other.include_default_attributes {
+        AttrInspectOptions::All
+    } else {
+        AttrInspectOptions::DefinedOnly
+    };
+    let package_values = if other.package_values.is_empty() {
+        None
+    } else {
+        Some(RegexSet::new(&other.package_values)?)
+    };
+
     match output_format {
         OutputFormat::Unknown => Err(internal_error!("`output_format` is not set")),
         OutputFormat::Stats => Ok(Arc::new(StatsFormat)),
@@ -430,25 +633,121 @@ pub(crate) fn create_formatter(
                 .expect("buck cli should send valid target hash graph type"),
         })),
         OutputFormat::Json | OutputFormat::JsonLines => Ok(Arc::new(JsonFormat {
-            attributes: if other.output_attributes.is_empty() {
-                None
-            } else {
-                Some(RegexSet::new(&other.output_attributes)?)
-            },
-            attr_inspect_opts: if other.include_default_attributes {
-                AttrInspectOptions::All
-            } else {
-                AttrInspectOptions::DefinedOnly
-            },
+            attributes,
+            attr_inspect_opts,
             target_call_stacks,
-            package_values: if other.package_values.is_empty() {
-                None
-            } else {
-                Some(RegexSet::new(&other.package_values)?)
-            },
+            package_values,
             writer: JsonWriter {
                 json_lines: output_format == OutputFormat::JsonLines,
             },
         })),
+        OutputFormat::Binary => {
+            if !other.streaming {
+                return Err(internal_error!(
+                    "Binary output format is only supported with `--streaming`"
+                ));
+            }
+            Ok(Arc::new(BinaryFormat {
+                attributes,
+                attr_inspect_opts,
+                target_call_stacks,
+                package_values,
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_both(json_lines: bool) -> (String, String) {
+        let writer = JsonWriter { json_lines };
+
+        let mut by_item = Vec::new();
+        let mut first = true;
+        writer.entry_start(&mut by_item);
+        writer.entry_item(
+            &mut by_item,
+            &mut first,
+            PACKAGE,
+            QuotedJson::quote_str("root//foo"),
+        );
+        writer.entry_item(
+            &mut by_item,
+            &mut first,
+            "srcs",
+            QuotedJson::from_serde_json_value(serde_json::json!({"a": ["b.c"], "d": {}})),
+        );
+        writer.entry_end(&mut by_item, first);
+
+        let mut by_entry = Vec::new();
+        let mut attributes = IndexMap::new();
+        attributes.insert(
+            "srcs".to_owned(),
+            serde_json::json!({"a": ["b.c"], "d": {}}),
+        );
+        writer.entry(
+            &mut by_entry,
+            &TargetJson {
+                rule_type: None,
+                deps: None,
+                inputs: None,
+                target_hash: None,
+                package: Some("root//foo".to_owned()),
+                package_values: None,
+                oncall: None,
+                attributes,
+                target_call_stack: None,
+            },
+        );
+
+        (
+            String::from_utf8(by_item).unwrap(),
+            String::from_utf8(by_entry).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_entry_layout() {
+        let (by_item, by_entry) = write_both(false);
+        assert_eq!(by_item, by_entry);
+        let (by_item, by_entry) = write_both(true);
+        assert_eq!(by_item, by_entry);
+    }
+
+    #[test]
+    fn test_target_json_field_names() {
+        let entry = serde_json::to_value(TargetJson {
+            rule_type: Some(String::new()),
+            deps: Some(Vec::new()),
+            inputs: Some(Vec::new()),
+            target_hash: Some(String::new()),
+            package: Some(String::new()),
+            package_values: Some(serde_json::Map::new()),
+            oncall: Some(String::new()),
+            attributes: IndexMap::new(),
+            target_call_stack: Some(String::new()),
+        })
+        .unwrap();
+        let keys: BTreeSet<&str> = entry
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(|k| k.as_str())
+            .collect();
+        assert_eq!(
+            BTreeSet::from([
+                TYPE,
+                DEPS,
+                INPUTS,
+                TARGET_HASH,
+                PACKAGE,
+                PACKAGE_VALUES,
+                ONCALL,
+                TARGET_CALL_STACK
+            ]),
+            keys
+        );
     }
 }
@@ -0,0 +1,33 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! JSON Schema of the `buck2 targets --json` output, generated from the types `JsonFormat`
+//! serializes.
+
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+
+use crate::commands::targets::fmt::ImportsJson;
+use crate::commands::targets::fmt::PackageErrorJson;
+use crate::commands::targets::fmt::TargetJson;
+
+#[derive(JsonSchema)]
+#[schemars(untagged)]
+#[allow(dead_code)]
+enum TargetsJsonEntry {
+    Target(TargetJson),
+    Imports(ImportsJson),
+    PackageError(PackageErrorJson),
+}
+
+/// JSON Schema of `buck2 targets --json`. With `--json-lines`, each line matches the schema of
+/// the array items.
+pub fn targets_json_schema() -> RootSchema {
+    schemars::schema_for!(Vec<TargetsJsonEntry>)
+}
@@ -13,11 +13,13 @@
 enum ResolveAliasError {
     #[error("`--stat` format is not supported by `--resolve-alias`")]
     StatFormatNotSupported,
+    #[error("`--output-format binary` is not supported by `--resolve-alias`")]
+    BinaryFormatNotSupported,
 }
 
 use std::collections::HashMap;
 use std::collections::HashSet;
-use std::fmt::Write;
+use std::io::Write;
 
 use anyhow::Context;
 use buck2_cli_proto::targets_request::OutputFormat;
@@ -40,32 +42,32 @@ use crate::json::QuotedJson;
 
 trait ResolveAliasFormatter {
     /// Before writing anything.
-    fn begin(&self, buffer: &mut String);
+    fn begin(&self, buffer: &mut Vec<u8>);
 
     /// After writing everything.
-    fn end(&self, buffer: &mut String);
+    fn end(&self, buffer: &mut Vec<u8>);
 
     /// Between items
-    fn separator(&self, buffer: &mut String);
+    fn separator(&self, buffer: &mut Vec<u8>);
 
     /// Emit an alias
-    fn emit(&self, alias: &str, label: &TargetLabel, buffer: &mut String);
+    fn emit(&self, alias: &str, label: &TargetLabel, buffer: &mut Vec<u8>);
 }
 
 impl ResolveAliasFormatter for JsonWriter {
-    fn begin(&self, buffer: &mut String) {
+    fn begin(&self, buffer: &mut Vec<u8>) {
         self.begin(buffer);
     }
 
-    fn end(&self, buffer: &mut String) {
+    fn end(&self, buffer: &mut Vec<u8>) {
         self.end(buffer);
     }
 
-    fn separator(&self, buffer: &mut String) {
+    fn separator(&self, buffer: &mut Vec<u8>) {
         self.separator(buffer);
     }
 
-    fn emit(&self, alias: &str, label: &TargetLabel, buffer: &mut String) {
+    fn emit(&self, alias: &str, label: &TargetLabel, buffer: &mut Vec<u8>) {
         let mut first = true;
         self.entry_start(buffer);
         self.entry_item(buffer, &mut first, "alias", QuotedJson::quote_str(alias));
@@ -89,15 +91,15 @@ impl ResolveAliasFormatter for JsonWriter {
 struct LinesWriter;
 
 impl ResolveAliasFormatter for LinesWriter {
-    fn begin(&self, _buffer: &mut String) {}
+    fn begin(&self, _buffer: &mut Vec<u8>) {}
 
-    fn end(&self, _buffer: &mut String) {}
+    fn end(&self, _buffer: &mut Vec<u8>) {}
 
-    fn separator(&self, buffer: &mut String) {
-        buffer.push('\n');
+    fn separator(&self, buffer: &mut Vec<u8>) {
+        buffer.push(b'\n');
     }
 
-    fn emit(&self, _alias: &str, label: &TargetLabel, buffer: &mut String) {
+    fn emit(&self, _alias: &str, label: &TargetLabel, buffer: &mut Vec<u8>) {
         write!(buffer, "{}", label).unwrap();
     }
 }
@@ -142,7 +144,7 @@ pub(crate) async fn targets_resolve_aliases(
         .into_iter()
         .collect();
 
-    let mut buffer = String::new();
+    let mut buffer = Vec::new();
 
     let output_format = OutputFormat::from_i32(request.output_format)
         .internal_error("Invalid value of `output_format`")?;
@@ -161,6 +163,7 @@ pub(crate) async fn targets_resolve_aliases(
             &json_writer as &dyn ResolveAliasFormatter
         }
         OutputFormat::Stats => return Err(ResolveAliasError::StatFormatNotSupported.into()),
+        OutputFormat::Binary => return Err(ResolveAliasError::BinaryFormatNotSupported.into()),
     };
 
     let mut needs_separator = false;
@@ -201,6 +204,6 @@ pub(crate) async fn targets_resolve_aliases(
 
     Ok(TargetsResponse {
         error_count: 0,
-        serialized_targets_output: buffer,
+        serialized_targets_output: String::from_utf8(buffer)?,
     })
 }
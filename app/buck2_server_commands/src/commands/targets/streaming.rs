@@ -49,8 +49,8 @@ use crate::commands::targets::fmt::TargetFormatter;
 use crate::commands::targets::fmt::TargetInfo;
 use crate::target_hash::TargetHashes;
 
-fn write_str(outputter: &mut dyn Write, s: &mut String) -> anyhow::Result<()> {
-    outputter.write_all(s.as_bytes())?;
+fn write_bytes(outputter: &mut dyn Write, s: &mut Vec<u8>) -> anyhow::Result<()> {
+    outputter.write_all(s)?;
     s.clear();
     Ok(())
 }
@@ -71,7 +71,7 @@ pub(crate) async fn targets_streaming(
         stats: Stats,           // Stats to merge in
         package: PackageLabel,  // The package I was operating on
         stderr: Option<String>, // Print to stderr (and break unless keep_going is set)
-        stdout: String,         // Print to stdout
+        stdout: Vec<u8>,        // Print to stdout
     }
 
     let imported = Arc::new(Mutex::new(SmallSet::new()));
@@ -94,7 +94,7 @@ pub(crate) async fn targets_streaming(
                                 stats: Stats::default(),
                                 package: package.dupe(),
                                 stderr: None,
-                                stdout: String::new(),
+                                stdout: Vec::new(),
                             };
                             let targets = {
                                 // This bit of code is the heavy CPU stuff, so guard it with the threads
@@ -110,13 +110,14 @@ pub(crate) async fn targets_streaming(
                                     err,
                                     &mut res.stdout,
                                     &mut stderr,
-                                );
+                                )?;
                                 res.stderr = Some(stderr);
+                                anyhow::Ok(())
                             };
                             match targets {
                                 Ok((eval_result, targets, err)) => {
                                     if let Some(err) = err {
-                                        show_err(&err.into());
+                                        show_err(&err.into())?;
                                         formatter.separator(&mut res.stdout);
                                     }
                                     res.stats.success += 1;
@@ -127,7 +128,7 @@ pub(crate) async fn targets_streaming(
                                             eval_imports,
                                             Some(package.dupe()),
                                             &mut res.stdout,
-                                        );
+                                        )?;
                                         imported
                                             .lock()
                                             .unwrap()
@@ -147,11 +148,11 @@ pub(crate) async fn targets_streaming(
                                                 super_package: eval_result.super_package(),
                                             },
                                             &mut res.stdout,
-                                        )
+                                        )?;
                                     }
                                 }
                                 Err(err) => {
-                                    show_err(&err.into());
+                                    show_err(&err.into())?;
                                 }
                             }
                             anyhow::Ok(res)
@@ -167,7 +168,7 @@ pub(crate) async fn targets_streaming(
         // Use unlimited parallelism - tokio will restrict us anyway
         .buffer_unordered(1000000);
 
-    let mut buffer = String::new();
+    let mut buffer = Vec::new();
     formatter.begin(&mut buffer);
     let mut stats = Stats::default();
     let mut needs_separator = false;
@@ -188,8 +189,8 @@ pub(crate) async fn targets_streaming(
                 formatter.separator(&mut buffer);
             }
             needs_separator = true;
-            write_str(outputter, &mut buffer)?;
-            write_str(outputter, &mut res.stdout)?;
+            write_bytes(outputter, &mut buffer)?;
+            write_bytes(outputter, &mut res.stdout)?;
         }
         if imports {
             // Need to also find imports from PACKAGE files
@@ -209,8 +210,8 @@ pub(crate) async fn targets_streaming(
                         formatter.separator(&mut buffer);
                     }
                     needs_separator = true;
-                    formatter.imports(package_file_path.path(), &imports, None, &mut buffer);
-                    write_str(outputter, &mut buffer)?;
+                    formatter.imports(package_file_path.path(), &imports, None, &mut buffer)?;
+                    write_bytes(outputter, &mut buffer)?;
                     imported.lock().unwrap().extend(imports.into_iter());
                 }
                 // TODO(nga): we should cross cell boundary:
@@ -235,14 +236,14 @@ pub(crate) async fn targets_streaming(
             // No need to parallelise these this step because it will already be on the DICE graph
             let loaded = dice.get_loaded_module_from_import_path(&path).await?;
             let imports = loaded.imports().cloned().collect::<Vec<_>>();
-            formatter.imports(path.path(), &imports, None, &mut buffer);
+            formatter.imports(path.path(), &imports, None, &mut buffer)?;
             todo.extend(imports);
-            write_str(outputter, &mut buffer)?;
+            write_bytes(outputter, &mut buffer)?;
         }
     }
 
     formatter.end(&stats, &mut buffer);
-    write_str(outputter, &mut buffer)?;
+    write_bytes(outputter, &mut buffer)?;
     Ok(stats)
 }
 
@@ -325,8 +326,13 @@ async fn load_targets(
                 Ok((result, targets, None))
             }
         }
-        PackageSpec::All => {
-            let targets = result.targets().values().map(|t| t.to_owned()).collect();
+        spec @ (PackageSpec::All | PackageSpec::AllExcept(_)) => {
+            let targets = result
+                .targets()
+                .values()
+                .filter(|t| !spec.excludes(t.label().name()))
+                .map(|t| t.to_owned())
+                .collect();
             Ok((result, targets, None))
         }
     }
@@ -180,8 +180,9 @@ async fn retrieve_artifacts_for_spec(
     let available_targets = res.targets();
 
     let todo_targets: Vec<(ProvidersLabel, &GlobalCfgOptions)> = match spec {
-        PackageSpec::All => available_targets
+        PackageSpec::All | PackageSpec::AllExcept(_) => available_targets
             .keys()
+            .filter(|t| !spec.excludes(t))
             .map(|t| {
                 (
                     ProvidersLabel::default_for(TargetLabel::new(package.dupe(), t)),
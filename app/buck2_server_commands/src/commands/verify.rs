@@ -0,0 +1,433 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Server-side implementation of `buck2 verify`.
+
+use anyhow::Context;
+use async_trait::async_trait;
+use buck2_build_api::actions::artifact::get_artifact_fs::GetArtifactFs;
+use buck2_build_api::analysis::calculation::RuleAnalysisCalculation;
+use buck2_build_api::artifact_groups::ArtifactGroup;
+use buck2_build_api::build::materialize_artifact_group;
+use buck2_build_api::build::MaterializationContext;
+use buck2_build_api::interpreter::rule_defs::provider::builtin::artifact_assertions_info::FrozenArtifactAssertionsInfo;
+use buck2_cli_proto::new_generic::VerifyAssertionResult;
+use buck2_cli_proto::new_generic::VerifyRequest;
+use buck2_cli_proto::new_generic::VerifyResponse;
+use buck2_cli_proto::new_generic::VerifyTargetResult;
+use buck2_core::configuration::compatibility::MaybeCompatible;
+use buck2_core::directory::unordered_entry_walk;
+use buck2_core::directory::DirectoryEntry;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
+use buck2_execute::artifact::artifact_dyn::ArtifactDyn;
+use buck2_execute::artifact_value::ArtifactValue;
+use buck2_execute::directory::ActionDirectoryMember;
+use buck2_execute::output_size::OutputSize;
+use buck2_node::target_calculation::ConfiguredTargetCalculation;
+use buck2_server_ctx::ctx::ServerCommandContextTrait;
+use buck2_server_ctx::global_cfg_options::global_cfg_options_from_client_context;
+use buck2_server_ctx::partial_result_dispatcher::NoPartialResult;
+use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
+use buck2_server_ctx::pattern_parse_and_resolve::parse_and_resolve_provider_labels_from_cli_args;
+use buck2_server_ctx::template::run_server_command;
+use buck2_server_ctx::template::ServerCommandTemplate;
+use dice::DiceTransaction;
+use dupe::Dupe;
+use futures::FutureExt;
+use object::read::elf::Dyn;
+use object::read::elf::FileHeader;
+use object::Object;
+use object::ObjectSymbol;
+
+pub(crate) async fn verify_command(
+    ctx: &dyn ServerCommandContextTrait,
+    partial_result_dispatcher: PartialResultDispatcher<NoPartialResult>,
+    req: VerifyRequest,
+) -> anyhow::Result<VerifyResponse> {
+    run_server_command(VerifyServerCommand { req }, ctx, partial_result_dispatcher).await
+}
+
+struct VerifyServerCommand {
+    req: VerifyRequest,
+}
+
+#[async_trait]
+impl ServerCommandTemplate for VerifyServerCommand {
+    type StartEvent = buck2_data::VerifyCommandStart;
+    type EndEvent = buck2_data::VerifyCommandEnd;
+    type Response = VerifyResponse;
+    type PartialResult = NoPartialResult;
+
+    async fn command(
+        &self,
+        server_ctx: &dyn ServerCommandContextTrait,
+        _partial_result_dispatcher: PartialResultDispatcher<Self::PartialResult>,
+        ctx: DiceTransaction,
+    ) -> anyhow::Result<Self::Response> {
+        verify(server_ctx, ctx, &self.req).await
+    }
+
+    fn is_success(&self, response: &Self::Response) -> bool {
+        response
+            .targets
+            .iter()
+            .all(|t| t.assertions.iter().all(|a| a.passed))
+    }
+}
+
+/// Assertions declared by a target, copied out of its `ArtifactAssertionsInfo`.
+struct ArtifactAssertions {
+    max_size_bytes: Option<u64>,
+    disallowed_dynamic_deps: Vec<String>,
+    required_symbols: Vec<String>,
+}
+
+async fn verify(
+    server_ctx: &dyn ServerCommandContextTrait,
+    mut ctx: DiceTransaction,
+    req: &VerifyRequest,
+) -> anyhow::Result<VerifyResponse> {
+    let global_cfg_options =
+        global_cfg_options_from_client_context(&req.target_cfg, server_ctx, &mut ctx).await?;
+    let labels = parse_and_resolve_provider_labels_from_cli_args(
+        &mut ctx,
+        &req.target_patterns,
+        server_ctx.working_dir(),
+    )
+    .await?;
+    let artifact_fs = ctx.get_artifact_fs().await?;
+    let global_cfg_options = &global_cfg_options;
+
+    // Build the asserted artifacts of all the targets first, then check them.
+    let built = ctx
+        .try_compute_join(labels, |ctx, label| {
+            async move {
+                let label = ctx
+                    .get_configured_provider_label(&label, global_cfg_options)
+                    .await?;
+                let providers = match ctx.get_providers(&label).await? {
+                    MaybeCompatible::Incompatible(_) => return anyhow::Ok(None),
+                    MaybeCompatible::Compatible(providers) => providers,
+                };
+                let Some(info) = providers
+                    .provider_collection()
+                    .builtin_provider::<FrozenArtifactAssertionsInfo>()
+                else {
+                    return Ok(None);
+                };
+                let artifact = info.artifact()?;
+                let assertions = ArtifactAssertions {
+                    max_size_bytes: info.max_size_bytes()?,
+                    disallowed_dynamic_deps: info
+                        .disallowed_dynamic_deps()
+                        .into_iter()
+                        .map(|s| s.to_owned())
+                        .collect(),
+                    required_symbols: info
+                        .required_symbols()
+                        .into_iter()
+                        .map(|s| s.to_owned())
+                        .collect(),
+                };
+                let values = materialize_artifact_group(
+                    ctx,
+                    &ArtifactGroup::Artifact(artifact.dupe()),
+                    &MaterializationContext::force_materializations(),
+                )
+                .await?;
+                let value = values
+                    .iter()
+                    .next()
+                    .map(|(_, value)| value.dupe())
+                    .with_context(|| format!("Artifact `{}` was not built", artifact))?;
+                Ok(Some((label, artifact, value, assertions)))
+            }
+            .boxed()
+        })
+        .await?;
+
+    let mut targets = Vec::new();
+    for (label, artifact, value, assertions) in built.into_iter().flatten() {
+        let path = artifact.resolve_path(&artifact_fs)?;
+        let abs_path = server_ctx.project_root().resolve(&path);
+        targets.push(VerifyTargetResult {
+            target: label.to_string(),
+            artifact: path.to_string(),
+            assertions: check_artifact(&abs_path, &value, &assertions)?,
+        });
+    }
+
+    Ok(VerifyResponse { targets })
+}
+
+fn check_artifact(
+    path: &AbsNormPath,
+    value: &ArtifactValue,
+    assertions: &ArtifactAssertions,
+) -> anyhow::Result<Vec<VerifyAssertionResult>> {
+    let mut results = Vec::new();
+
+    if let Some(max_size_bytes) = assertions.max_size_bytes {
+        // This comes from the digests of the artifact value, so directories are sized without
+        // reading them back.
+        let size = value.calc_output_count_and_bytes().bytes;
+        results.push(VerifyAssertionResult {
+            assertion: "max_size_bytes".to_owned(),
+            passed: size <= max_size_bytes,
+            message: format!("size is {} bytes, limit is {} bytes", size, max_size_bytes),
+        });
+    }
+
+    if assertions.disallowed_dynamic_deps.is_empty() && assertions.required_symbols.is_empty() {
+        return Ok(results);
+    }
+    let objects = object_files(path, value)?;
+
+    if !assertions.disallowed_dynamic_deps.is_empty() {
+        let mut deps = Vec::new();
+        for data in &objects {
+            deps.extend(dynamic_deps(data)?);
+        }
+        for dep in &assertions.disallowed_dynamic_deps {
+            let found = deps.contains(dep);
+            results.push(VerifyAssertionResult {
+                assertion: "disallowed_dynamic_deps".to_owned(),
+                passed: !found,
+                message: if found {
+                    format!("depends on `{}`", dep)
+                } else {
+                    format!("does not depend on `{}`", dep)
+                },
+            });
+        }
+    }
+
+    if !assertions.required_symbols.is_empty() {
+        let mut symbols = Vec::new();
+        for data in &objects {
+            symbols.extend(defined_symbols(data)?);
+        }
+        for symbol in &assertions.required_symbols {
+            let found = symbols.contains(symbol);
+            results.push(VerifyAssertionResult {
+                assertion: "required_symbols".to_owned(),
+                passed: found,
+                message: if found {
+                    format!("defines `{}`", symbol)
+                } else {
+                    format!("does not define `{}`", symbol)
+                },
+            });
+        }
+    }
+
+    Ok(results)
+}
+
+/// Contents of the object files of an artifact: the artifact itself if it is a file, or the files
+/// inside it that parse as object files if it is a directory.
+fn object_files(path: &AbsNormPath, value: &ArtifactValue) -> anyhow::Result<Vec<Vec<u8>>> {
+    let mut objects = Vec::new();
+    let mut walk = unordered_entry_walk(value.entry().as_ref());
+    while let Some((file_path, entry)) = walk.next() {
+        if let DirectoryEntry::Leaf(ActionDirectoryMember::File(..)) = entry {
+            let file_path = file_path.get();
+            if file_path.as_str().is_empty() {
+                objects.push(fs_util::read(path)?);
+            } else {
+                let data = fs_util::read(path.join(&file_path))?;
+                if object::FileKind::parse(&*data).is_ok() {
+                    objects.push(data);
+                }
+            }
+        }
+    }
+    Ok(objects)
+}
+
+/// Names of the dynamic libraries an object file links against.
+fn dynamic_deps(data: &[u8]) -> anyhow::Result<Vec<String>> {
+    let mut deps = match object::FileKind::parse(data)? {
+        object::FileKind::Elf32 => {
+            elf_needed::<object::elf::FileHeader32<object::Endianness>>(data)?
+        }
+        object::FileKind::Elf64 => {
+            elf_needed::<object::elf::FileHeader64<object::Endianness>>(data)?
+        }
+        _ => Vec::new(),
+    };
+    let file = object::File::parse(data)?;
+    for import in file.imports()? {
+        if !import.library().is_empty() {
+            deps.push(String::from_utf8_lossy(import.library()).into_owned());
+        }
+    }
+    deps.sort();
+    deps.dedup();
+    Ok(deps)
+}
+
+/// `DT_NEEDED` entries of an ELF file.
+fn elf_needed<Elf: FileHeader<Endian = object::Endianness>>(
+    data: &[u8],
+) -> anyhow::Result<Vec<String>> {
+    let header = Elf::parse(data)?;
+    let endian = header.endian()?;
+    let sections = header.sections(endian, data)?;
+    let mut needed = Vec::new();
+    if let Some((entries, link)) = sections.dynamic(endian, data)? {
+        let strings = sections.strings(endian, data, link)?;
+        for entry in entries {
+            if entry.tag32(endian) == Some(object::elf::DT_NEEDED) {
+                let offset: u64 = entry.d_val(endian).into();
+                let name = strings
+                    .get(offset as u32)
+                    .map_err(|()| anyhow::anyhow!("Invalid `DT_NEEDED` string offset"))?;
+                needed.push(String::from_utf8_lossy(name).into_owned());
+            }
+        }
+    }
+    Ok(needed)
+}
+
+/// Names of the symbols an object file defines.
+fn defined_symbols(data: &[u8]) -> anyhow::Result<Vec<String>> {
+    let file = object::File::parse(data)?;
+    let mut symbols = Vec::new();
+    for symbol in file.symbols().chain(file.dynamic_symbols()) {
+        if symbol.is_definition() {
+            if let Ok(name) = symbol.name() {
+                symbols.push(name.to_owned());
+            }
+        }
+    }
+    symbols.sort();
+    symbols.dedup();
+    Ok(symbols)
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_common::file_ops::FileMetadata;
+    use buck2_common::file_ops::TrackedFileDigest;
+    use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
+    use buck2_core::fs::project::ProjectRootTemp;
+    use buck2_execute::digest_config::DigestConfig;
+    use buck2_execute::directory::ActionDirectoryBuilder;
+    use buck2_execute::directory::INTERNER;
+
+    use super::*;
+
+    fn file_entry(content: &str) -> FileMetadata {
+        FileMetadata {
+            digest: TrackedFileDigest::from_content(
+                content.as_bytes(),
+                DigestConfig::testing_default().cas_digest_config(),
+            ),
+            is_executable: false,
+        }
+    }
+
+    fn dir_value(files: &[(&str, &str)]) -> ArtifactValue {
+        let digest_config = DigestConfig::testing_default();
+        let mut builder = ActionDirectoryBuilder::empty();
+        for (path, content) in files {
+            builder
+                .insert(
+                    ForwardRelativePath::new(path).unwrap(),
+                    DirectoryEntry::Leaf(ActionDirectoryMember::File(file_entry(content))),
+                )
+                .unwrap();
+        }
+        ArtifactValue::dir(
+            builder
+                .fingerprint(digest_config.as_directory_serializer())
+                .shared(&*INTERNER),
+        )
+    }
+
+    fn assertions(max_size_bytes: Option<u64>, required_symbols: &[&str]) -> ArtifactAssertions {
+        ArtifactAssertions {
+            max_size_bytes,
+            disallowed_dynamic_deps: vec!["libbad.so".to_owned()],
+            required_symbols: required_symbols.iter().map(|s| (*s).to_owned()).collect(),
+        }
+    }
+
+    fn passed(results: &[VerifyAssertionResult]) -> Vec<(&str, bool)> {
+        results
+            .iter()
+            .map(|r| (r.assertion.as_str(), r.passed))
+            .collect()
+    }
+
+    #[test]
+    fn test_file_max_size() {
+        let fs = ProjectRootTemp::new().unwrap();
+        let value = ArtifactValue::file(file_entry("12345"));
+        let mut assertions = assertions(Some(5), &[]);
+        assertions.disallowed_dynamic_deps.clear();
+
+        let results = check_artifact(fs.path().root(), &value, &assertions).unwrap();
+        assert_eq!(vec![("max_size_bytes", true)], passed(&results));
+
+        assertions.max_size_bytes = Some(4);
+        let results = check_artifact(fs.path().root(), &value, &assertions).unwrap();
+        assert_eq!(vec![("max_size_bytes", false)], passed(&results));
+    }
+
+    #[test]
+    fn test_file_not_an_object() {
+        let fs = ProjectRootTemp::new().unwrap();
+        fs.write_file("out", "not an object");
+        let value = ArtifactValue::file(file_entry("not an object"));
+
+        assert!(
+            check_artifact(
+                &fs.path()
+                    .root()
+                    .join(ForwardRelativePath::new("out").unwrap()),
+                &value,
+                &assertions(None, &["main"]),
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_directory() {
+        let fs = ProjectRootTemp::new().unwrap();
+        fs.write_file("out/a.txt", "123");
+        fs.write_file("out/sub/b.txt", "4567");
+        let value = dir_value(&[("a.txt", "123"), ("sub/b.txt", "4567")]);
+        let path = fs
+            .path()
+            .root()
+            .join(ForwardRelativePath::new("out").unwrap());
+
+        // The size is the sum of the files, and files that are not objects are skipped rather
+        // than failing the check.
+        let results = check_artifact(&path, &value, &assertions(Some(7), &["main"])).unwrap();
+        assert_eq!(
+            vec![
+                ("max_size_bytes", true),
+                ("disallowed_dynamic_deps", true),
+                ("required_symbols", false),
+            ],
+            passed(&results)
+        );
+
+        let results = check_artifact(&path, &value, &assertions(Some(6), &[])).unwrap();
+        assert_eq!(
+            vec![("max_size_bytes", false), ("disallowed_dynamic_deps", true)],
+            passed(&results)
+        );
+    }
+}
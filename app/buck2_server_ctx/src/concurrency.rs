@@ -22,6 +22,10 @@ use anyhow::Context;
 use async_condvar_fair::Condvar;
 use async_trait::async_trait;
 use buck2_cli_proto::client_context::PreemptibleWhen;
+use buck2_common::dice::cells::SetCellResolver;
+use buck2_common::init::MemoryProfile;
+use buck2_common::legacy_configs::dice::HasInjectedLegacyConfigs;
+use buck2_common::legacy_configs::dice::SetLegacyConfigs;
 use buck2_core::soft_error;
 use buck2_data::DiceBlockConcurrentCommandEnd;
 use buck2_data::DiceBlockConcurrentCommandStart;
@@ -67,8 +71,18 @@ enum ConcurrencyHandlerError {
     #[error(
         "Recursive invocation of Buck, with a different state. Use `--isolation-dir` on the inner invocation to fix this. Trace Ids: {0}. Recursive invocation command: `{1}`"
     )]
-    #[buck2(input)]
+    #[buck2(input, tag = NestedInvocation)]
     NestedInvocationWithDifferentStates(String, String),
+    #[error(
+        "Recursive invocation of Buck delegated to the command `{0}`, which is not running. Trace Ids: {1}"
+    )]
+    #[buck2(input, tag = NestedInvocation)]
+    DelegatedToInactiveCommand(TraceId, String),
+    #[error(
+        "Recursive invocation of Buck delegated to the command `{0}`, with different configs. Delegated invocations run on the state of the outer command, so they must use its configs. Recursive invocation command: `{1}`"
+    )]
+    #[buck2(input, tag = NestedInvocation)]
+    DelegatedWithDifferentConfigs(TraceId, String),
     #[error("`--exit-when-different-state` was set")]
     #[buck2(tag = DaemonIsBusy)]
     ExitWhenDifferentState,
@@ -78,6 +92,19 @@ enum ConcurrencyHandlerError {
     ExitOnPreemption,
 }
 
+/// Whether a command was started by an action of another command running on this daemon.
+#[derive(Clone, Dupe, Debug, PartialEq, Eq)]
+pub enum NestedInvocation {
+    No,
+    /// Runs alongside the outer command if it has the same state, and fails otherwise, since
+    /// waiting for the outer command to finish would deadlock.
+    Nested,
+    /// Like `Nested`, but runs on the state of the outer command with this trace id even if it
+    /// asked for a different one, e.g. because of files changed by the outer command. It still
+    /// fails if it asked for different configs.
+    Delegated(TraceId),
+}
+
 #[derive(Clone, Dupe, Copy, Debug)]
 pub enum RunState {
     NestedSameState,
@@ -105,6 +132,10 @@ pub struct ConcurrencyHandler {
     dice: Arc<Dice>,
     /// Used to prevent commands (clean --stale) from running in parallel with dice commands
     exclusive_command_lock: Arc<ExclusiveCommandLock>,
+    memory_profile: MemoryProfile,
+    /// Drops the caches kept outside of DICE, see `release_memory`.
+    #[allocative(skip)]
+    release_caches: Option<fn()>,
 }
 
 #[derive(Allocative)]
@@ -142,6 +173,10 @@ struct CommandData {
     preemption_setting: PreemptibleWhen,
     #[allocative(skip)]
     preempt: Option<oneshot::Sender<()>>,
+    /// The command does not execute actions, see `ConcurrencyHandler::enter`.
+    read_only: bool,
+    /// The transaction the command runs on, once it has acquired access to DICE.
+    transaction: Option<DiceTransaction>,
 }
 
 impl CommandData {
@@ -319,7 +354,7 @@ impl ExclusiveCommandLock {
 }
 
 impl ConcurrencyHandler {
-    pub fn new(dice: Arc<Dice>) -> Self {
+    pub fn new(dice: Arc<Dice>, memory_profile: MemoryProfile) -> Self {
         ConcurrencyHandler {
             data: Arc::new(Mutex::new(ConcurrencyHandlerData {
                 dice_status: DiceStatus::idle(),
@@ -331,23 +366,57 @@ impl ConcurrencyHandler {
             cond: Default::default(),
             dice,
             exclusive_command_lock: Arc::new(ExclusiveCommandLock::new()),
+            memory_profile,
+            release_caches: None,
+        }
+    }
+
+    /// Set the function dropping caches which live outside of DICE (e.g. dep files), called
+    /// along with dropping the DICE state with `MemoryProfile::Low`.
+    pub fn with_release_caches(self, release_caches: fn()) -> Self {
+        ConcurrencyHandler {
+            release_caches: Some(release_caches),
+            ..self
+        }
+    }
+
+    /// With `MemoryProfile::Low`, drop everything computed by previous commands once no command
+    /// is running, so that the daemon does not hold on to that memory while idle.
+    async fn release_memory(&self) -> anyhow::Result<()> {
+        if self.memory_profile != MemoryProfile::Low {
+            return Ok(());
         }
+        tracing::info!("No active commands, dropping DICE state and caches");
+        let mut updater = self.dice.updater().unstable_take();
+        // Same as a new daemon: commands check whether these are set to tell a first command.
+        updater.set_none_cell_resolver()?;
+        updater.set_none_legacy_configs()?;
+        updater.set_none_legacy_config_overrides()?;
+        updater.commit().await;
+        if let Some(release_caches) = self.release_caches {
+            release_caches();
+        }
+        Ok(())
     }
 
     /// Enters a critical section that requires concurrent command synchronization,
     /// and runs the given `exec` function in the critical section.
+    ///
+    /// `read_only` commands never execute actions, so when they run on the active DICE version
+    /// they do not preempt the commands already running on it.
     pub async fn enter<F, Fut, R>(
         &self,
         event_dispatcher: EventDispatcher,
         data: &dyn DiceDataProvider,
         updates: &dyn DiceUpdater,
         exec: F,
-        is_nested_invocation: bool,
+        nested_invocation: NestedInvocation,
         sanitized_argv: Vec<String>,
         exclusive_cmd: Option<String>,
         exit_when_different_state: bool,
         cancellations: &ExplicitCancellationContext,
         preemptible: PreemptibleWhen,
+        read_only: bool,
     ) -> anyhow::Result<R>
     where
         F: FnOnce(DiceTransaction) -> Fut,
@@ -381,10 +450,11 @@ impl ConcurrencyHandler {
                                 data,
                                 updates,
                                 events,
-                                is_nested_invocation,
+                                nested_invocation,
                                 sanitized_argv,
                                 exit_when_different_state,
                                 preemptible,
+                                read_only,
                             )
                         })
                         .await,
@@ -414,10 +484,11 @@ impl ConcurrencyHandler {
         user_data: &dyn DiceDataProvider,
         updates: &dyn DiceUpdater,
         event_dispatcher: EventDispatcher,
-        is_nested_invocation: bool,
+        nested_invocation: NestedInvocation,
         sanitized_argv: Vec<String>,
         exit_when_different_state: bool,
         preemptible: PreemptibleWhen,
+        read_only: bool,
     ) -> anyhow::Result<(
         OnExecExit,
         DiceTransaction,
@@ -439,12 +510,14 @@ impl ConcurrencyHandler {
 
         let (preempt_sender, preempt_receiver) = oneshot::channel::<()>();
 
-        let command_data = CommandData {
+        let mut command_data = CommandData {
             trace_id: trace.dupe(),
             argv: sanitized_argv,
             dispatcher: event_dispatcher.dupe(),
             preemption_setting: preemptible,
             preempt: Some(preempt_sender),
+            read_only,
+            transaction: None,
         };
 
         let (transaction, tainted) = loop {
@@ -502,11 +575,27 @@ impl ConcurrencyHandler {
                     if let Some(active) = active {
                         let is_same_state = transaction.equivalent(&active.version);
 
+                        // Read-only commands (queries, audits, ...) never execute actions, so on
+                        // the active DICE version they join the commands running on it without
+                        // preempting them. On a different version they wait like other commands:
+                        // only one version is active at a time.
+                        if is_same_state && read_only && nested_invocation == NestedInvocation::No {
+                            tracing::debug!("Read-only command joins the active DICE version");
+                            event_dispatcher.instant_event(DiceEqualityCheck {
+                                is_equal: is_same_state,
+                            });
+                            break (transaction, false);
+                        }
+
                         // If we have a different state, attempt to transition to cleanup. This will
                         // succeed only if the current state is not in use.
                         if !is_same_state {
                             // If the active commands are preemptible, preempt them.
-                            self.cancel_preemptible_commands(&mut data, is_same_state);
+                            self.cancel_preemptible_commands(
+                                &mut data,
+                                is_same_state,
+                                &command_data.argv,
+                            );
 
                             // transition to cleanup == "wait until all other blocking commands finish"
                             if data.transition_to_cleanup(&self.dice) {
@@ -520,11 +609,45 @@ impl ConcurrencyHandler {
                             is_equal: is_same_state,
                         });
 
-                        let bypass_semaphore =
-                            self.determine_bypass_semaphore(is_same_state, is_nested_invocation);
+                        let bypass_semaphore = self.determine_bypass_semaphore(
+                            is_same_state,
+                            nested_invocation != NestedInvocation::No,
+                        );
 
                         match bypass_semaphore {
                             BypassSemaphore::Error => {
+                                if let NestedInvocation::Delegated(outer_trace_id) =
+                                    &nested_invocation
+                                {
+                                    // The outer command is still running, so its transaction is
+                                    // still valid.
+                                    let outer = data
+                                        .active_commands
+                                        .values()
+                                        .filter(|c| &c.trace_id == outer_trace_id)
+                                        .find_map(|c| c.transaction.as_ref())
+                                        .ok_or_else(|| {
+                                            ConcurrencyHandlerError::DelegatedToInactiveCommand(
+                                                outer_trace_id.dupe(),
+                                                format_traces(&data.active_commands, &command_data),
+                                            )
+                                        })?
+                                        .dupe();
+                                    if !same_configs(transaction, outer.dupe()).await? {
+                                        return Err(
+                                            ConcurrencyHandlerError::DelegatedWithDifferentConfigs(
+                                                outer_trace_id.dupe(),
+                                                command_data.format_argv(),
+                                            )
+                                            .into(),
+                                        );
+                                    }
+                                    event_dispatcher.console_message(format!(
+                                        "Recursive invocation of Buck with a different state, running on the state of the outer command. Trace Ids: {}",
+                                        format_traces(&data.active_commands, &command_data),
+                                    ));
+                                    break (outer, false);
+                                }
                                 return Err(
                                     ConcurrencyHandlerError::NestedInvocationWithDifferentStates(
                                         format_traces(&data.active_commands, &command_data),
@@ -535,7 +658,11 @@ impl ConcurrencyHandler {
                             }
                             BypassSemaphore::Run(state) => {
                                 self.emit_logs(state, &data.active_commands, &command_data)?;
-                                self.cancel_preemptible_commands(&mut data, is_same_state);
+                                self.cancel_preemptible_commands(
+                                    &mut data,
+                                    is_same_state,
+                                    &command_data.argv,
+                                );
                                 break (transaction, false);
                             }
                             BypassSemaphore::Block => {
@@ -593,6 +720,8 @@ impl ConcurrencyHandler {
             data.previously_tainted = true;
         }
 
+        command_data.transaction = Some(transaction.dupe());
+
         // create the on exit drop handler, which will take care of notifying tasks.
         let drop_guard = OnExecExit::new(self.dupe(), command_id, command_data, data);
         // This adds the task to the list of all tasks (see ::new impl)
@@ -605,13 +734,24 @@ impl ConcurrencyHandler {
         &self.dice
     }
 
-    fn cancel_preemptible_commands(&self, data: &mut ConcurrencyHandlerData, is_same_state: bool) {
+    fn cancel_preemptible_commands(
+        &self,
+        data: &mut ConcurrencyHandlerData,
+        is_same_state: bool,
+        argv: &[String],
+    ) {
         // If the active commands are preemptible, interrupt them.
         for cmd in data.active_commands.values_mut() {
-            if cmd.preemption_setting == PreemptibleWhen::Never {
-                continue;
-            }
-            if is_same_state && cmd.preemption_setting == PreemptibleWhen::OnDifferentState {
+            let preempt = match cmd.preemption_setting {
+                PreemptibleWhen::Never => false,
+                PreemptibleWhen::Always => true,
+                PreemptibleWhen::OnDifferentState => !is_same_state,
+                // The command is superseded by a rerun of itself on newer sources. It stops at
+                // its next cancellation point: the results of the actions it already finished
+                // are kept by DICE and the materializer, so the new command does not redo them.
+                PreemptibleWhen::OnRebuild => !is_same_state && cmd.argv == argv,
+            };
+            if !preempt {
                 continue;
             }
             match cmd.preempt.take() {
@@ -679,6 +819,19 @@ fn format_traces(
     trace_ids.iter().join(", ")
 }
 
+/// Whether both transactions were set up with the same buckconfigs. The legacy configs are
+/// always injected, but to `None` until the first command sets them.
+async fn same_configs(mut x: DiceTransaction, mut y: DiceTransaction) -> anyhow::Result<bool> {
+    let x_set = x.is_injected_legacy_configs_key_set().await?;
+    let y_set = y.is_injected_legacy_configs_key_set().await?;
+    if !x_set || !y_set {
+        return Ok(x_set == y_set);
+    }
+    Ok(x.get_injected_legacy_configs()
+        .await?
+        .compare(&y.get_injected_legacy_configs().await?))
+}
+
 /// Held to execute a command so that when the command is canceled, we properly remove its state
 /// from the handler so that it's no longer registered as a ongoing command.
 struct OnExecExit(Option<(ConcurrencyHandler, CommandId)>);
@@ -708,6 +861,10 @@ impl Drop for OnExecExit {
             tracing::info!("Active command was removed: {}", this.1);
 
             if data.has_no_active_commands() {
+                if let Err(e) = this.0.release_memory().await {
+                    tracing::warn!("Failed to release memory: {:#}", e);
+                }
+
                 // we notify all commands since we don't know how many can actually wake up and run
                 // concurrently as several of the currently waiting commands could be "equivalent".
                 // This could cause commands to wake up out of order and race, such that the longest
@@ -722,6 +879,7 @@ impl Drop for OnExecExit {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
     use std::sync::atomic::AtomicBool;
     use std::sync::atomic::Ordering;
     use std::task::Poll;
@@ -730,6 +888,9 @@ mod tests {
     use allocative::Allocative;
     use assert_matches::assert_matches;
     use async_trait::async_trait;
+    use buck2_common::legacy_configs::configs::LegacyBuckConfig;
+    use buck2_common::legacy_configs::configs::LegacyBuckConfigs;
+    use buck2_core::cells::name::CellName;
     use buck2_core::is_open_source;
     use buck2_events::create_source_sink_pair;
     use buck2_events::source::ChannelEventSource;
@@ -810,7 +971,7 @@ mod tests {
 
         let dice = Dice::builder().build(DetectCycles::Enabled);
 
-        let concurrency = ConcurrencyHandler::new(dice);
+        let concurrency = ConcurrencyHandler::new(dice, MemoryProfile::Default);
 
         let traces1 = TraceId::new();
         let traces2 = TraceId::new();
@@ -828,12 +989,13 @@ mod tests {
                     b.wait().await;
                 }
             },
-            true,
+            NestedInvocation::Nested,
             Vec::new(),
             None,
             false,
             ExplicitCancellationContext::testing(),
             PreemptibleWhen::Never,
+            false,
         );
         let fut2 = concurrency.enter(
             EventDispatcher::null_sink_with_trace(traces2),
@@ -845,12 +1007,13 @@ mod tests {
                     b.wait().await;
                 }
             },
-            true,
+            NestedInvocation::Nested,
             Vec::new(),
             None,
             false,
             ExplicitCancellationContext::testing(),
             PreemptibleWhen::Never,
+            false,
         );
         let fut3 = concurrency.enter(
             EventDispatcher::null_sink_with_trace(traces3),
@@ -862,12 +1025,13 @@ mod tests {
                     b.wait().await;
                 }
             },
-            true,
+            NestedInvocation::Nested,
             Vec::new(),
             None,
             false,
             ExplicitCancellationContext::testing(),
             PreemptibleWhen::Never,
+            false,
         );
 
         let (r1, r2, r3) = futures::future::join3(fut1, fut2, fut3).await;
@@ -880,7 +1044,7 @@ mod tests {
     async fn nested_invocation_should_error() {
         let dice = Dice::builder().build(DetectCycles::Enabled);
 
-        let concurrency = ConcurrencyHandler::new(dice);
+        let concurrency = ConcurrencyHandler::new(dice, MemoryProfile::Default);
 
         let traces1 = TraceId::new();
         let traces2 = TraceId::new();
@@ -897,12 +1061,13 @@ mod tests {
                     b.wait().await;
                 }
             },
-            true,
+            NestedInvocation::Nested,
             Vec::new(),
             None,
             false,
             ExplicitCancellationContext::testing(),
             PreemptibleWhen::Never,
+            false,
         );
 
         let fut2 = concurrency.enter(
@@ -915,12 +1080,13 @@ mod tests {
                     b.wait().await;
                 }
             },
-            true,
+            NestedInvocation::Nested,
             Vec::new(),
             None,
             false,
             ExplicitCancellationContext::testing(),
             PreemptibleWhen::Never,
+            false,
         );
 
         match futures::future::try_join(fut1, fut2).await {
@@ -931,11 +1097,100 @@ mod tests {
         }
     }
 
+    struct ConfigsDifferent;
+
+    #[async_trait]
+    impl DiceUpdater for ConfigsDifferent {
+        async fn update(
+            &self,
+            mut ctx: DiceTransactionUpdater,
+            _user_data: &mut UserComputationData,
+        ) -> anyhow::Result<DiceTransactionUpdater> {
+            ctx.set_legacy_configs(LegacyBuckConfigs::new(HashMap::from([(
+                CellName::testing_new("root"),
+                LegacyBuckConfig::empty(),
+            )])))?;
+            Ok(ctx)
+        }
+    }
+
+    async fn delegated_nested_invocation(
+        nested_updates: &dyn DiceUpdater,
+    ) -> anyhow::Result<(DiceEquality, DiceEquality)> {
+        let dice = Dice::builder().build(DetectCycles::Enabled);
+        // Like a new daemon.
+        let mut updater = dice.updater();
+        updater.set_none_legacy_configs()?;
+        updater.commit().await;
+
+        let concurrency = ConcurrencyHandler::new(dice, MemoryProfile::Default);
+
+        let barrier = Arc::new(Barrier::new(2));
+        let outer_trace_id = TraceId::new();
+
+        let fut1 = concurrency.enter(
+            EventDispatcher::null_sink_with_trace(outer_trace_id.dupe()),
+            &TestDiceDataProvider,
+            &NoChanges,
+            |ctx| {
+                let b = barrier.dupe();
+                async move {
+                    b.wait().await;
+                    ctx.equality_token()
+                }
+            },
+            NestedInvocation::No,
+            Vec::new(),
+            None,
+            false,
+            ExplicitCancellationContext::testing(),
+            PreemptibleWhen::Never,
+            false,
+        );
+
+        let fut2 = async {
+            let res = concurrency
+                .enter(
+                    EventDispatcher::null_sink_with_trace(TraceId::new()),
+                    &TestDiceDataProvider,
+                    nested_updates,
+                    |ctx| async move { ctx.equality_token() },
+                    NestedInvocation::Delegated(outer_trace_id.dupe()),
+                    Vec::new(),
+                    None,
+                    false,
+                    ExplicitCancellationContext::testing(),
+                    PreemptibleWhen::Never,
+                    false,
+                )
+                .await;
+            // The outer command waits for this one to have run, or failed.
+            barrier.wait().await;
+            res
+        };
+
+        futures::future::try_join(fut1, fut2).await
+    }
+
+    #[tokio::test]
+    async fn delegated_nested_invocation_runs_on_outer_state() {
+        let (outer, nested) = delegated_nested_invocation(&CtxDifferent).await.unwrap();
+        assert!(outer == nested);
+    }
+
+    #[tokio::test]
+    async fn delegated_nested_invocation_with_different_configs_fails() {
+        let e = delegated_nested_invocation(&ConfigsDifferent)
+            .await
+            .unwrap_err();
+        assert!(e.to_string().contains("with different configs"), "{:#}", e);
+    }
+
     #[tokio::test]
     async fn parallel_invocation_same_transaction() {
         let dice = Dice::builder().build(DetectCycles::Enabled);
 
-        let concurrency = ConcurrencyHandler::new(dice);
+        let concurrency = ConcurrencyHandler::new(dice, MemoryProfile::Default);
 
         let traces1 = TraceId::new();
         let traces2 = TraceId::new();
@@ -953,12 +1208,13 @@ mod tests {
                     b.wait().await;
                 }
             },
-            false,
+            NestedInvocation::No,
             Vec::new(),
             None,
             false,
             ExplicitCancellationContext::testing(),
             PreemptibleWhen::Never,
+            false,
         );
         let fut2 = concurrency.enter(
             EventDispatcher::null_sink_with_trace(traces2),
@@ -970,12 +1226,13 @@ mod tests {
                     b.wait().await;
                 }
             },
-            false,
+            NestedInvocation::No,
             Vec::new(),
             None,
             false,
             ExplicitCancellationContext::testing(),
             PreemptibleWhen::Never,
+            false,
         );
         let fut3 = concurrency.enter(
             EventDispatcher::null_sink_with_trace(traces3),
@@ -987,12 +1244,13 @@ mod tests {
                     b.wait().await;
                 }
             },
-            false,
+            NestedInvocation::No,
             Vec::new(),
             None,
             false,
             ExplicitCancellationContext::testing(),
             PreemptibleWhen::Never,
+            false,
         );
 
         let (r1, r2, r3) = futures::future::join3(fut1, fut2, fut3).await;
@@ -1005,7 +1263,7 @@ mod tests {
     async fn parallel_invocation_different_traceid_blocks() -> anyhow::Result<()> {
         let dice = Dice::builder().build(DetectCycles::Enabled);
 
-        let concurrency = ConcurrencyHandler::new(dice.dupe());
+        let concurrency = ConcurrencyHandler::new(dice.dupe(), MemoryProfile::Default);
 
         let traces1 = TraceId::new();
         let traces2 = traces1.dupe();
@@ -1037,12 +1295,13 @@ mod tests {
                             barrier.wait().await;
                             let _g = b.read().await;
                         },
-                        false,
+                        NestedInvocation::No,
                         Vec::new(),
                         None,
                         false,
                         ExplicitCancellationContext::testing(),
                         PreemptibleWhen::Never,
+                        false,
                     )
                     .await
             }
@@ -1063,12 +1322,13 @@ mod tests {
                             barrier.wait().await;
                             let _g = b.read().await;
                         },
-                        false,
+                        NestedInvocation::No,
                         Vec::new(),
                         None,
                         false,
                         ExplicitCancellationContext::testing(),
                         PreemptibleWhen::Never,
+                        false,
                     )
                     .await
             }
@@ -1091,12 +1351,13 @@ mod tests {
                         |_| async move {
                             arrived.store(true, Ordering::Relaxed);
                         },
-                        false,
+                        NestedInvocation::No,
                         Vec::new(),
                         None,
                         false,
                         ExplicitCancellationContext::testing(),
                         PreemptibleWhen::Never,
+                        false,
                     )
                     .await
             }
@@ -1125,7 +1386,7 @@ mod tests {
     async fn parallel_invocation_exit_when_different_state() -> anyhow::Result<()> {
         let dice = Dice::builder().build(DetectCycles::Enabled);
 
-        let concurrency = ConcurrencyHandler::new(dice.dupe());
+        let concurrency = ConcurrencyHandler::new(dice.dupe(), MemoryProfile::Default);
 
         let traces1 = TraceId::new();
         let traces2 = traces1.dupe();
@@ -1157,12 +1418,13 @@ mod tests {
                             barrier.wait().await;
                             let _g = b.read().await;
                         },
-                        false,
+                        NestedInvocation::No,
                         Vec::new(),
                         None,
                         true,
                         ExplicitCancellationContext::testing(),
                         PreemptibleWhen::Never,
+                        false,
                     )
                     .await
             }
@@ -1183,12 +1445,13 @@ mod tests {
                             barrier.wait().await;
                             let _g = b.read().await;
                         },
-                        false,
+                        NestedInvocation::No,
                         Vec::new(),
                         None,
                         true,
                         ExplicitCancellationContext::testing(),
                         PreemptibleWhen::Never,
+                        false,
                     )
                     .await
             }
@@ -1211,12 +1474,13 @@ mod tests {
                         |_| async move {
                             arrived.store(true, Ordering::Relaxed);
                         },
-                        false,
+                        NestedInvocation::No,
                         Vec::new(),
                         None,
                         true,
                         ExplicitCancellationContext::testing(),
                         PreemptibleWhen::Never,
+                        false,
                     )
                     .await
             }
@@ -1250,7 +1514,7 @@ mod tests {
     async fn parallel_invocation_exit_when_preemptible() -> anyhow::Result<()> {
         let dice = Dice::builder().build(DetectCycles::Enabled);
 
-        let concurrency = ConcurrencyHandler::new(dice.dupe());
+        let concurrency = ConcurrencyHandler::new(dice.dupe(), MemoryProfile::Default);
 
         let traces1 = TraceId::new();
         let traces2 = traces1.dupe();
@@ -1282,12 +1546,13 @@ mod tests {
                             barrier.wait().await;
                             let _g = b.read().await;
                         },
-                        false,
+                        NestedInvocation::No,
                         Vec::new(),
                         None,
                         false,
                         ExplicitCancellationContext::testing(),
                         PreemptibleWhen::Always,
+                        false,
                     )
                     .await
             }
@@ -1308,12 +1573,13 @@ mod tests {
                             barrier.wait().await;
                             let _g = b.read().await;
                         },
-                        false,
+                        NestedInvocation::No,
                         Vec::new(),
                         None,
                         false,
                         ExplicitCancellationContext::testing(),
                         PreemptibleWhen::Never,
+                        false,
                     )
                     .await
             }
@@ -1336,12 +1602,13 @@ mod tests {
                         |_| async move {
                             arrived.store(true, Ordering::Relaxed);
                         },
-                        false,
+                        NestedInvocation::No,
                         Vec::new(),
                         None,
                         false,
                         ExplicitCancellationContext::testing(),
                         PreemptibleWhen::Never,
+                        false,
                     )
                     .await
             }
@@ -1369,6 +1636,166 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn rebuild_preempts_same_command() -> anyhow::Result<()> {
+        let dice = Dice::builder().build(DetectCycles::Enabled);
+
+        let concurrency = ConcurrencyHandler::new(dice.dupe(), MemoryProfile::Default);
+
+        let argv = vec!["buck2".to_owned(), "build".to_owned(), "//:a".to_owned()];
+
+        let block = Arc::new(RwLock::new(()));
+        let blocked = block.write().await;
+
+        let barrier = Arc::new(Barrier::new(2));
+
+        let fut1 = tokio::spawn({
+            let concurrency = concurrency.dupe();
+            let barrier = barrier.dupe();
+            let b = block.dupe();
+            let argv = argv.clone();
+
+            async move {
+                concurrency
+                    .enter(
+                        EventDispatcher::null_sink_with_trace(TraceId::new()),
+                        &TestDiceDataProvider,
+                        &NoChanges,
+                        |_| async move {
+                            barrier.wait().await;
+                            let _g = b.read().await;
+                        },
+                        NestedInvocation::No,
+                        argv,
+                        None,
+                        false,
+                        ExplicitCancellationContext::testing(),
+                        PreemptibleWhen::OnRebuild,
+                        false,
+                    )
+                    .await
+            }
+        });
+
+        barrier.wait().await;
+
+        // Same command on a different state: the first one is preempted instead of blocking
+        // this one until it finishes.
+        concurrency
+            .enter(
+                EventDispatcher::null_sink_with_trace(TraceId::new()),
+                &TestDiceDataProvider,
+                &CtxDifferent,
+                |_| async move {},
+                NestedInvocation::No,
+                argv,
+                None,
+                false,
+                ExplicitCancellationContext::testing(),
+                PreemptibleWhen::Never,
+                false,
+            )
+            .await?;
+
+        let fut1_error: buck2_error::Error = fut1.await?.unwrap_err().into();
+        assert!(
+            fut1_error
+                .tags()
+                .contains(&buck2_error::ErrorTag::DaemonPreempted),
+        );
+
+        drop(blocked);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_only_command_does_not_preempt() -> anyhow::Result<()> {
+        let dice = Dice::builder().build(DetectCycles::Enabled);
+
+        let concurrency = ConcurrencyHandler::new(dice.dupe(), MemoryProfile::Default);
+
+        let block = Arc::new(RwLock::new(()));
+        let blocked = block.write().await;
+
+        let barrier = Arc::new(Barrier::new(2));
+
+        let fut1 = tokio::spawn({
+            let concurrency = concurrency.dupe();
+            let barrier = barrier.dupe();
+            let b = block.dupe();
+
+            async move {
+                concurrency
+                    .enter(
+                        EventDispatcher::null_sink_with_trace(TraceId::new()),
+                        &TestDiceDataProvider,
+                        &NoChanges,
+                        |_| async move {
+                            barrier.wait().await;
+                            let _g = b.read().await;
+                        },
+                        NestedInvocation::No,
+                        Vec::new(),
+                        None,
+                        false,
+                        ExplicitCancellationContext::testing(),
+                        PreemptibleWhen::Always,
+                        false,
+                    )
+                    .await
+            }
+        });
+
+        barrier.wait().await;
+
+        // A read-only command on the same state runs alongside the first command without
+        // preempting it.
+        concurrency
+            .enter(
+                EventDispatcher::null_sink_with_trace(TraceId::new()),
+                &TestDiceDataProvider,
+                &NoChanges,
+                |_| async move {},
+                NestedInvocation::No,
+                Vec::new(),
+                None,
+                false,
+                ExplicitCancellationContext::testing(),
+                PreemptibleWhen::Never,
+                true,
+            )
+            .await?;
+
+        // On a different state it waits for the first command like any other command.
+        let different_state = concurrency.enter(
+            EventDispatcher::null_sink_with_trace(TraceId::new()),
+            &TestDiceDataProvider,
+            &CtxDifferent,
+            |_| async move {},
+            NestedInvocation::No,
+            Vec::new(),
+            None,
+            false,
+            ExplicitCancellationContext::testing(),
+            PreemptibleWhen::Never,
+            true,
+        );
+        futures::pin_mut!(different_state);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(100), &mut different_state)
+                .await
+                .is_err()
+        );
+
+        drop(blocked);
+
+        fut1.await??;
+        different_state.await?;
+
+        Ok(())
+    }
+
     #[derive(Clone, Dupe, Derivative, Allocative, Display)]
     #[derivative(Hash, Eq, PartialEq, Debug)]
     #[display(fmt = "CleanupTestKey")]
@@ -1410,7 +1837,7 @@ mod tests {
 
         let dice = Dice::builder().build(DetectCycles::Enabled);
 
-        let concurrency = ConcurrencyHandler::new(dice.dupe());
+        let concurrency = ConcurrencyHandler::new(dice.dupe(), MemoryProfile::Default);
 
         // Kick off our computation and wait until it's running.
 
@@ -1440,12 +1867,13 @@ mod tests {
                         _ = started => {}
                     }
                 },
-                false,
+                NestedInvocation::No,
                 Vec::new(),
                 None,
                 false,
                 ExplicitCancellationContext::testing(),
                 PreemptibleWhen::Never,
+                false,
             )
             .await?;
 
@@ -1460,12 +1888,13 @@ mod tests {
                     // The key should still be evaluating by now.
                     assert!(key.is_executing.is_locked());
                 },
-                false,
+                NestedInvocation::No,
                 Vec::new(),
                 None,
                 false,
                 ExplicitCancellationContext::testing(),
                 PreemptibleWhen::Never,
+                false,
             )
             .await?;
 
@@ -1479,12 +1908,13 @@ mod tests {
                 |_dice| async move {
                     assert!(!key.is_executing.is_locked());
                 },
-                false,
+                NestedInvocation::No,
                 Vec::new(),
                 None,
                 false,
                 ExplicitCancellationContext::testing(),
                 PreemptibleWhen::Never,
+                false,
             )
             .await?;
 
@@ -1562,7 +1992,7 @@ mod tests {
     #[tokio::test]
     async fn exclusive_command_lock() -> anyhow::Result<()> {
         let dice = Dice::builder().build(DetectCycles::Enabled);
-        let concurrency = ConcurrencyHandler::new(dice.dupe());
+        let concurrency = ConcurrencyHandler::new(dice.dupe(), MemoryProfile::Default);
         let (mut source, sink) = create_source_sink_pair();
         let dispatcher = EventDispatcher::new(TraceId::new(), sink);
 
@@ -1588,12 +2018,13 @@ mod tests {
                                 }
                                 tokio::task::yield_now().await;
                             },
-                            false,
+                            NestedInvocation::No,
                             Vec::new(),
                             exclusive_cmd,
                             false,
                             ExplicitCancellationContext::testing(),
                             PreemptibleWhen::Never,
+                            false,
                         )
                         .await
                 }
@@ -1649,7 +2080,7 @@ mod tests {
     async fn test_thundering_herd() -> anyhow::Result<()> {
         let dice = Dice::builder().build(DetectCycles::Enabled);
 
-        let concurrency = ConcurrencyHandler::new(dice.dupe());
+        let concurrency = ConcurrencyHandler::new(dice.dupe(), MemoryProfile::Default);
 
         let concurrency = &concurrency;
 
@@ -1664,12 +2095,13 @@ mod tests {
                         dice.compute(&K).await.unwrap();
                         tokio::task::yield_now().await;
                     },
-                    false,
+                    NestedInvocation::No,
                     Vec::new(),
                     None,
                     false,
                     ExplicitCancellationContext::testing(),
                     PreemptibleWhen::Never,
+                    false,
                 )
                 .await
         });
@@ -1685,7 +2117,7 @@ mod tests {
     async fn test_updates_are_synchronized() -> anyhow::Result<()> {
         let dice = Dice::builder().build(DetectCycles::Enabled);
 
-        let concurrency = ConcurrencyHandler::new(dice.dupe());
+        let concurrency = ConcurrencyHandler::new(dice.dupe(), MemoryProfile::Default);
 
         struct Updater {
             should_be_able_to_run: AtomicBool,
@@ -1721,12 +2153,13 @@ mod tests {
             |_dice| async move {
                 tokio::task::yield_now().await;
             },
-            false,
+            NestedInvocation::No,
             Vec::new(),
             None,
             false,
             ExplicitCancellationContext::testing(),
             PreemptibleWhen::Never,
+            false,
         );
 
         pin_mut!(fut1);
@@ -1742,12 +2175,13 @@ mod tests {
             |_dice| async move {
                 tokio::task::yield_now().await;
             },
-            false,
+            NestedInvocation::No,
             Vec::new(),
             None,
             false,
             ExplicitCancellationContext::testing(),
             PreemptibleWhen::Never,
+            false,
         );
 
         pin_mut!(fut2);
@@ -28,6 +28,7 @@ use buck2_data::DiceCriticalSectionEnd;
 use buck2_data::DiceCriticalSectionStart;
 use buck2_events::dispatch::EventDispatcher;
 use buck2_execute::materialize::materializer::Materializer;
+use buck2_file_watcher::file_watcher::FileWatcher;
 use buck2_futures::cancellation::ExplicitCancellationContext;
 use dice::DiceComputations;
 use dice::DiceTransaction;
@@ -36,6 +37,7 @@ use dupe::Dupe;
 use crate::concurrency::ConcurrencyHandler;
 use crate::concurrency::DiceDataProvider;
 use crate::concurrency::DiceUpdater;
+use crate::concurrency::NestedInvocation;
 use crate::stderr_output_guard::StderrOutputGuard;
 
 #[async_trait]
@@ -52,6 +54,8 @@ pub trait ServerCommandContextTrait: Send + Sync {
 
     fn materializer(&self) -> Arc<dyn Materializer>;
 
+    fn file_watcher(&self) -> Arc<dyn FileWatcher>;
+
     /// exposes the dice for scoped access, but isn't intended to be callable by anyone
     async fn dice_accessor(&self, private: PrivateStruct) -> buck2_error::Result<DiceAccessor>;
 
@@ -85,7 +89,7 @@ pub struct DiceAccessor {
     pub dice_handler: ConcurrencyHandler,
     pub data: Box<dyn DiceDataProvider>,
     pub setup: Box<dyn DiceUpdater>,
-    pub is_nested_invocation: bool,
+    pub nested_invocation: NestedInvocation,
     pub sanitized_argv: Vec<String>,
     pub exit_when_different_state: bool,
     pub preemptible: PreemptibleWhen,
@@ -100,10 +104,19 @@ pub trait ServerCommandDiceContext {
         Fut: Future<Output = anyhow::Result<R>> + Send,
         R: Send;
 
+    /// Like `with_dice_ctx`, for commands which only read the target graph and never run analysis
+    /// or execute actions. Such commands do not preempt commands running on the same state.
+    async fn with_dice_ctx_read_only<'v, F, Fut, R>(&'v self, exec: F) -> anyhow::Result<R>
+    where
+        F: FnOnce(&'v dyn ServerCommandContextTrait, DiceTransaction) -> Fut + Send,
+        Fut: Future<Output = anyhow::Result<R>> + Send,
+        R: Send;
+
     async fn with_dice_ctx_maybe_exclusive<'v, F, Fut, R>(
         &'v self,
         exec: F,
         exclusive_cmd: Option<String>,
+        read_only: bool,
     ) -> anyhow::Result<R>
     where
         F: FnOnce(&'v dyn ServerCommandContextTrait, DiceTransaction) -> Fut + Send,
@@ -120,13 +133,23 @@ impl ServerCommandDiceContext for dyn ServerCommandContextTrait + '_ {
         Fut: Future<Output = anyhow::Result<R>> + Send,
         R: Send,
     {
-        self.with_dice_ctx_maybe_exclusive(exec, None).await
+        self.with_dice_ctx_maybe_exclusive(exec, None, false).await
+    }
+
+    async fn with_dice_ctx_read_only<'v, F, Fut, R>(&'v self, exec: F) -> anyhow::Result<R>
+    where
+        F: FnOnce(&'v dyn ServerCommandContextTrait, DiceTransaction) -> Fut + Send,
+        Fut: Future<Output = anyhow::Result<R>> + Send,
+        R: Send,
+    {
+        self.with_dice_ctx_maybe_exclusive(exec, None, true).await
     }
 
     async fn with_dice_ctx_maybe_exclusive<'v, F, Fut, R>(
         &'v self,
         exec: F,
         exclusive_cmd: Option<String>,
+        read_only: bool,
     ) -> anyhow::Result<R>
     where
         F: FnOnce(&'v dyn ServerCommandContextTrait, DiceTransaction) -> Fut + Send,
@@ -137,7 +160,7 @@ impl ServerCommandDiceContext for dyn ServerCommandContextTrait + '_ {
             dice_handler,
             data,
             setup,
-            is_nested_invocation,
+            nested_invocation,
             sanitized_argv,
             exit_when_different_state,
             preemptible,
@@ -201,12 +224,13 @@ impl ServerCommandDiceContext for dyn ServerCommandContextTrait + '_ {
                                     )
                                     .await
                             },
-                            is_nested_invocation,
+                            nested_invocation,
                             sanitized_argv,
                             exclusive_cmd,
                             exit_when_different_state,
                             self.cancellation_context(),
                             preemptible,
+                            read_only,
                         )
                         .await,
                     DiceCriticalSectionEnd {},
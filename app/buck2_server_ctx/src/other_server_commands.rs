@@ -14,6 +14,8 @@ use buck2_cli_proto::new_generic::ExpandExternalCellRequest;
 use buck2_cli_proto::new_generic::ExpandExternalCellResponse;
 use buck2_cli_proto::new_generic::ExplainRequest;
 use buck2_cli_proto::new_generic::ExplainResponse;
+use buck2_cli_proto::new_generic::VerifyRequest;
+use buck2_cli_proto::new_generic::VerifyResponse;
 use buck2_util::late_binding::LateBinding;
 
 use crate::ctx::ServerCommandContextTrait;
@@ -87,6 +89,12 @@ pub trait OtherServerCommands: Send + Sync + 'static {
         partial_result_dispatcher: PartialResultDispatcher<NoPartialResult>,
         req: ExpandExternalCellRequest,
     ) -> anyhow::Result<ExpandExternalCellResponse>;
+    async fn verify(
+        &self,
+        ctx: &dyn ServerCommandContextTrait,
+        partial_result_dispatcher: PartialResultDispatcher<NoPartialResult>,
+        req: VerifyRequest,
+    ) -> anyhow::Result<VerifyResponse>;
 }
 
 pub static OTHER_SERVER_COMMANDS: LateBinding<&'static dyn OtherServerCommands> =
@@ -36,15 +36,20 @@ pub async fn parse_and_resolve_patterns_to_targets_from_cli_args<T: PatternType>
                     extra,
                 }))
             }
-            buck2_core::pattern::pattern::PackageSpec::All => {
+            spec @ (buck2_core::pattern::pattern::PackageSpec::All
+            | buck2_core::pattern::pattern::PackageSpec::AllExcept(_)) => {
                 // Note this code is not parallel. Careful if used in performance sensitive code.
                 let interpreter_results = ctx.get_interpreter_results(package.dupe()).await?;
-                result_targets.extend(interpreter_results.targets().keys().map(|target| {
-                    TargetLabelWithExtra {
-                        target_label: TargetLabel::new(package.dupe(), target),
-                        extra: T::default(),
-                    }
-                }));
+                result_targets.extend(
+                    interpreter_results
+                        .targets()
+                        .keys()
+                        .filter(|target| !spec.excludes(target))
+                        .map(|target| TargetLabelWithExtra {
+                            target_label: TargetLabel::new(package.dupe(), target),
+                            extra: T::default(),
+                        }),
+                );
             }
         }
     }
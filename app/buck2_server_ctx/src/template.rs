@@ -51,6 +51,12 @@ pub trait ServerCommandTemplate: Send + Sync {
         None
     }
 
+    /// If `true`, the command only reads the target graph and never runs analysis or executes
+    /// actions, so it does not preempt commands running on the same state.
+    fn is_read_only(&self) -> bool {
+        false
+    }
+
     /// Additional errors that should be reported via the invocation record, even if the command
     /// successfully produces a response.
     fn additional_telemetry_errors(
@@ -94,6 +100,7 @@ pub async fn run_server_command<T: ServerCommandTemplate>(
                     command.command(server_ctx, partial_result_dispatcher, ctx)
                 },
                 command.exclusive_command_name(),
+                command.is_read_only(),
             )
             .await
             .map_err(Into::into);
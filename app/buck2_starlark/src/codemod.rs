@@ -0,0 +1,346 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Mechanical rewrites of Starlark files.
+//!
+//! Rewrites are computed from the AST but applied as textual edits to the original source,
+//! so everything the AST does not model (comments, blank lines, formatting) is preserved.
+
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+
+use buck2_interpreter::error::BuckStarlarkError;
+use starlark::syntax::AstModule;
+use starlark::syntax::Dialect;
+use starlark_syntax::codemap::Span;
+use starlark_syntax::syntax::ast::ArgumentP;
+use starlark_syntax::syntax::ast::AstExpr;
+use starlark_syntax::syntax::ast::AstLiteral;
+use starlark_syntax::syntax::ast::AstString;
+use starlark_syntax::syntax::ast::ExprP;
+use starlark_syntax::syntax::ast::StmtP;
+use starlark_syntax::syntax::module::AstModuleFields;
+
+/// Wildcard rule name in `rename_attrs`, applies to calls of every rule.
+const ANY_RULE: &str = "*";
+
+#[derive(Debug, buck2_error::Error)]
+#[buck2(input)]
+enum CodemodError {
+    #[error("Invalid codemod spec")]
+    InvalidSpec(#[source] serde_json::Error),
+    #[error("Codemod spec rewrites `{0}` to itself")]
+    Identity(String),
+    #[error("Overlapping rewrites in `{0}`, the codemod spec is ambiguous")]
+    OverlappingEdits(String),
+}
+
+/// The transformations to apply, usually read from a JSON file:
+///
+/// ```json
+/// {
+///   "rename_rules": {"cxx_library_v1": "cxx_library"},
+///   "rename_attrs": {"cxx_library": {"compiler_flags": "preprocessor_flags"}, "*": {"owner": "contacts"}},
+///   "rewrite_labels": {"//old/location": "//new/location"}
+/// }
+/// ```
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct CodemodSpec {
+    /// Rename called functions (typically rules or macros), from old to new name.
+    #[serde(default)]
+    pub(crate) rename_rules: BTreeMap<String, String>,
+    /// Rename named arguments of calls to a rule, keyed by rule name (either the old or the
+    /// new name if the rule is renamed too), or `*` for every rule.
+    #[serde(default)]
+    pub(crate) rename_attrs: BTreeMap<String, BTreeMap<String, String>>,
+    /// Rewrite string literals which are labels under the given prefix. A prefix matches
+    /// the whole literal, or any literal which continues with `:`, `/` or `[` after it.
+    #[serde(default)]
+    pub(crate) rewrite_labels: BTreeMap<String, String>,
+}
+
+impl CodemodSpec {
+    pub(crate) fn parse(spec: &str) -> anyhow::Result<CodemodSpec> {
+        let spec: CodemodSpec = serde_json::from_str(spec).map_err(CodemodError::InvalidSpec)?;
+        let renames = spec
+            .rename_rules
+            .iter()
+            .chain(spec.rename_attrs.values().flatten())
+            .chain(spec.rewrite_labels.iter());
+        for (from, to) in renames {
+            if from == to {
+                return Err(CodemodError::Identity(from.clone()).into());
+            }
+        }
+        Ok(spec)
+    }
+
+    fn rewrite_label(&self, label: &str) -> Option<String> {
+        // Prefer the longest matching prefix so nested moves can be expressed.
+        self.rewrite_labels
+            .iter()
+            .filter(|(from, _)| match label.strip_prefix(from.as_str()) {
+                Some(rest) => {
+                    rest.is_empty()
+                        || rest.starts_with(':')
+                        || rest.starts_with('/')
+                        || rest.starts_with('[')
+                }
+                None => false,
+            })
+            .max_by_key(|(from, _)| from.len())
+            .map(|(from, to)| format!("{}{}", to, &label[from.len()..]))
+    }
+
+    fn renamed_attr(&self, rule: &str, new_rule: Option<&str>, attr: &str) -> Option<&str> {
+        [Some(rule), new_rule, Some(ANY_RULE)]
+            .into_iter()
+            .flatten()
+            .find_map(|rule| self.rename_attrs.get(rule)?.get(attr))
+            .map(|x| x.as_str())
+    }
+}
+
+struct Edit {
+    span: Span,
+    replacement: String,
+}
+
+struct Rewriter<'a> {
+    spec: &'a CodemodSpec,
+    content: &'a str,
+    /// Names bound by `load(..., local = "their")`, calls through these keep their name.
+    aliased: HashSet<String>,
+    edits: Vec<Edit>,
+}
+
+impl<'a> Rewriter<'a> {
+    fn source(&self, span: Span) -> &'a str {
+        &self.content[span.begin().get() as usize..span.end().get() as usize]
+    }
+
+    fn edit(&mut self, span: Span, replacement: String) {
+        self.edits.push(Edit { span, replacement })
+    }
+
+    /// Replace the value of a string literal, keeping its quoting style. Literals using escapes
+    /// or triple quotes are left alone rather than risking changing their meaning.
+    fn edit_string(&mut self, literal: &AstString, value: &str) {
+        let source = self.source(literal.span);
+        let quote = match source.chars().next() {
+            Some(q @ ('"' | '\'')) => q,
+            _ => return,
+        };
+        let simple = source.len() == literal.node.len() + 2
+            && source[1..source.len() - 1] == *literal.node
+            && !value.contains(quote)
+            && !value.contains('\\');
+        if simple {
+            self.edit(literal.span, format!("{quote}{value}{quote}"));
+        }
+    }
+
+    fn label(&mut self, literal: &AstString) {
+        if let Some(label) = self.spec.rewrite_label(literal) {
+            self.edit_string(literal, &label);
+        }
+    }
+
+    fn load(&mut self, args: &[(Span, Span, &str, &AstString)]) {
+        let spec = self.spec;
+        for (local_span, their_span, local, their) in args {
+            let Some(new) = spec.rename_rules.get(their.node.as_str()) else {
+                continue;
+            };
+            if local_span != their_span {
+                // `local = "their"`, the local name is still bound after the rename.
+                self.aliased.insert((*local).to_owned());
+            }
+            self.edit_string(their, new);
+        }
+    }
+
+    fn expr(&mut self, expr: &AstExpr) {
+        let spec = self.spec;
+        match &expr.node {
+            ExprP::Call(callee, args) => {
+                let rule = match &callee.node {
+                    ExprP::Identifier(ident) if !self.aliased.contains(&ident.node.ident) => {
+                        Some((callee.span, ident.node.ident.as_str()))
+                    }
+                    ExprP::Dot(_, attr) => Some((attr.span, attr.node.as_str())),
+                    _ => None,
+                };
+                if let Some((span, rule)) = rule {
+                    let new_rule = spec.rename_rules.get(rule).map(|x| x.as_str());
+                    if let Some(new_rule) = new_rule {
+                        self.edit(span, new_rule.to_owned());
+                    }
+                    for arg in args {
+                        if let ArgumentP::Named(name, _) = &arg.node {
+                            if let Some(new_attr) = spec.renamed_attr(rule, new_rule, name) {
+                                self.edit(name.span, new_attr.to_owned());
+                            }
+                        }
+                    }
+                }
+            }
+            ExprP::Literal(AstLiteral::String(s)) => self.label(s),
+            _ => {}
+        }
+        expr.node.visit_expr(|x| self.expr(x));
+    }
+}
+
+/// Apply `spec` to a single file, returning the new contents if anything changed.
+pub(crate) fn apply_codemod(
+    spec: &CodemodSpec,
+    filename: &str,
+    content: &str,
+    dialect: &Dialect,
+) -> anyhow::Result<Option<String>> {
+    let ast =
+        AstModule::parse(filename, content.to_owned(), dialect).map_err(BuckStarlarkError::new)?;
+    let mut rewriter = Rewriter {
+        spec,
+        content,
+        aliased: HashSet::new(),
+        edits: Vec::new(),
+    };
+
+    // Loads are processed first so that aliases are known before visiting calls.
+    let mut loads = Vec::new();
+    ast.statement().node.visit_stmt(|stmt| {
+        if let StmtP::Load(load) = &stmt.node {
+            loads.push(load);
+        }
+    });
+    if let StmtP::Load(load) = &ast.statement().node {
+        loads.push(load);
+    }
+    for load in loads {
+        rewriter.label(&load.module);
+        let args: Vec<_> = load
+            .args
+            .iter()
+            .map(|arg| {
+                (
+                    arg.local.span,
+                    arg.their.span,
+                    arg.local.node.ident.as_str(),
+                    &arg.their,
+                )
+            })
+            .collect();
+        rewriter.load(&args);
+    }
+
+    let mut exprs = Vec::new();
+    ast.statement().node.visit_expr(|x| exprs.push(x));
+    for expr in exprs {
+        rewriter.expr(expr);
+    }
+
+    if rewriter.edits.is_empty() {
+        return Ok(None);
+    }
+
+    let mut edits = rewriter.edits;
+    edits.sort_by_key(|e| (e.span.begin(), e.span.end()));
+    edits.dedup_by_key(|e| e.span);
+    if edits
+        .windows(2)
+        .any(|w| w[0].span.end() > w[1].span.begin())
+    {
+        return Err(CodemodError::OverlappingEdits(filename.to_owned()).into());
+    }
+
+    let mut res = String::with_capacity(content.len());
+    let mut last = 0;
+    for edit in &edits {
+        res.push_str(&content[last..edit.span.begin().get() as usize]);
+        res.push_str(&edit.replacement);
+        last = edit.span.end().get() as usize;
+    }
+    res.push_str(&content[last..]);
+
+    Ok(if res == content { None } else { Some(res) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(spec: &str, content: &str) -> Option<String> {
+        let spec = CodemodSpec::parse(spec).unwrap();
+        apply_codemod(&spec, "BUCK", content, &Dialect::Extended).unwrap()
+    }
+
+    #[test]
+    fn test_rename_rule_and_attrs() {
+        let spec = r#"{
+            "rename_rules": {"old_library": "new_library"},
+            "rename_attrs": {"new_library": {"flags": "compiler_flags"}, "*": {"owner": "contacts"}}
+        }"#;
+        let content = r#"load("//defs.bzl", "old_library")
+
+# A comment which must survive.
+old_library(
+    name = "foo",
+    flags = ["-O2"],  # trailing comment
+    owner = "me",
+)
+
+other_rule(name = "bar", flags = [], owner = "you")
+"#;
+        let expected = r#"load("//defs.bzl", "new_library")
+
+# A comment which must survive.
+new_library(
+    name = "foo",
+    compiler_flags = ["-O2"],  # trailing comment
+    contacts = "me",
+)
+
+other_rule(name = "bar", flags = [], contacts = "you")
+"#;
+        assert_eq!(Some(expected.to_owned()), apply(spec, content));
+    }
+
+    #[test]
+    fn test_rename_rule_aliased_load() {
+        let spec = r#"{"rename_rules": {"old_library": "new_library"}}"#;
+        let content = "load(\"//defs.bzl\", lib = \"old_library\")\nlib(name = \"foo\")\n";
+        let expected = "load(\"//defs.bzl\", lib = \"new_library\")\nlib(name = \"foo\")\n";
+        assert_eq!(Some(expected.to_owned()), apply(spec, content));
+    }
+
+    #[test]
+    fn test_rewrite_labels() {
+        let spec = r#"{"rewrite_labels": {"//old": "//new", "//old/keep": "//kept"}}"#;
+        let content = r#"x(deps = ["//old:a", '//old/sub:b', "//oldish:c", "//old/keep:d", "//old[sub]"])
+"#;
+        let expected = r#"x(deps = ["//new:a", '//new/sub:b', "//oldish:c", "//kept:d", "//new[sub]"])
+"#;
+        assert_eq!(Some(expected.to_owned()), apply(spec, content));
+    }
+
+    #[test]
+    fn test_no_change() {
+        let spec = r#"{"rename_rules": {"a": "b"}}"#;
+        assert_eq!(None, apply(spec, "c(name = \"a\")\n"));
+    }
+
+    #[test]
+    fn test_invalid_spec() {
+        assert!(CodemodSpec::parse(r#"{"rename_rule": {}}"#).is_err());
+        assert!(CodemodSpec::parse(r#"{"rename_rules": {"a": "a"}}"#).is_err());
+    }
+}
@@ -49,6 +49,25 @@ pub struct StarlarkDebugAttachCommand {
     event_log_opts: CommonEventLogOptions,
 }
 
+/// Debug Starlark evaluation of BUCK and `.bzl` files and of rule analysis.
+///
+/// Serves the Debug Adapter Protocol on stdin and stdout, so that an IDE such
+/// as VS Code can set breakpoints, step through evaluation and inspect Starlark
+/// values. Configure the IDE to launch `buck2 debug starlark` as its debug
+/// adapter, then run builds as usual.
+#[derive(Debug, clap::Parser)]
+#[clap(name = "starlark")]
+pub struct DebugStarlarkCommand {
+    #[clap(flatten)]
+    attach: StarlarkDebugAttachCommand,
+}
+
+impl DebugStarlarkCommand {
+    pub fn exec(self, matches: &clap::ArgMatches, ctx: ClientCommandContext<'_>) -> ExitResult {
+        self.attach.exec(matches, ctx)
+    }
+}
+
 pub fn write_dap_message(out: &mut impl Write, msg: &[u8]) -> anyhow::Result<()> {
     write!(out, "Content-Length: {}\r\n\r\n", msg.len())?;
     out.write_all(msg)?;
@@ -0,0 +1,253 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::collections::HashMap;
+use std::io::Read;
+
+use anyhow::Context;
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::path_arg::PathArg;
+use buck2_common::argv::Argv;
+use buck2_common::argv::SanitizedArgv;
+use buck2_common::buildfiles::buildfile_names;
+use buck2_common::invocation_roots::find_invocation_roots;
+use buck2_common::legacy_configs::cells::BuckConfigBasedCells;
+use buck2_core::cells::name::CellName;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+use buck2_core::fs::paths::file_name::FileNameBuf;
+use buck2_core::fs::project::ProjectRoot;
+use buck2_interpreter::file_type::StarlarkFileType;
+
+use crate::formatter::format_starlark;
+
+/// Build file names recognized outside of a project, where there is no `buildfile.name` config.
+const DEFAULT_BUILD_FILE_NAMES: &[&str] = &[
+    "BUCK",
+    "BUCK.v2",
+    "TARGETS",
+    "TARGETS.v2",
+    "BUILD",
+    "BUILD.bazel",
+];
+
+#[derive(Debug, buck2_error::Error)]
+#[buck2(input)]
+enum FmtError {
+    #[error("{0} file(s) are not formatted")]
+    NotFormatted(usize),
+    #[error("Input from stdin is not formatted")]
+    StdinNotFormatted,
+}
+
+#[derive(Debug, clap::Parser)]
+#[clap(
+    name = "fmt",
+    about = "Format BUCK and .bzl files.",
+    long_about = "Format BUCK and .bzl files.\n\n\
+    Files are parsed and printed back in a canonical layout: four space indentation, double \
+    quoted strings, one argument per line for rule calls in build files, and one element per \
+    line for calls, lists and dicts which were already split over several lines. Comments are \
+    preserved. Formatting a formatted file does not change it."
+)]
+pub struct FmtCommand {
+    /// Do not write any files, fail if any file is not formatted.
+    #[clap(long)]
+    check: bool,
+
+    /// Format the file read from stdin, and write the result to stdout.
+    #[clap(long, conflicts_with = "paths")]
+    stdin: bool,
+
+    /// File name used to pick the dialect for `--stdin`, `BUCK` by default.
+    #[clap(long, value_name = "NAME", requires = "stdin")]
+    stdin_filename: Option<String>,
+
+    /// Files, or directories to search for build and `.bzl` files.
+    #[clap(value_name = "PATH", required_unless_present = "stdin")]
+    paths: Vec<PathArg>,
+}
+
+/// The build file names of the cells of the project, read from `buildfile.name` the same way
+/// the daemon does, so that directories are searched for the files the build would load.
+struct BuildFileNames {
+    /// `None` outside of a project.
+    project: Option<(ProjectRoot, BuckConfigBasedCells)>,
+    by_cell: HashMap<CellName, Vec<FileNameBuf>>,
+}
+
+impl BuildFileNames {
+    fn new(cwd: &AbsNormPath) -> anyhow::Result<Self> {
+        let project = match find_invocation_roots(cwd) {
+            Ok(roots) => {
+                let cells = BuckConfigBasedCells::parse(&roots.project_root)?;
+                Some((roots.project_root, cells))
+            }
+            Err(_) => None,
+        };
+        Ok(BuildFileNames {
+            project,
+            by_cell: HashMap::new(),
+        })
+    }
+
+    /// Whether a file called `name` in `dir` is a build file.
+    fn is_build_file(&mut self, dir: &AbsNormPath, name: &str) -> anyhow::Result<bool> {
+        let default = || DEFAULT_BUILD_FILE_NAMES.contains(&name);
+        let Some((project_root, cells)) = &self.project else {
+            return Ok(default());
+        };
+        let Ok(dir) = project_root.relativize(dir) else {
+            return Ok(default());
+        };
+        let cell = cells.cell_resolver.find(&*dir)?;
+        if !self.by_cell.contains_key(&cell) {
+            if cells.configs_by_name.get(cell).is_err() {
+                // The config of the cell was not loaded (`buck2.lazy_cell_configs`).
+                return Ok(default());
+            }
+            let names = buildfile_names(
+                &cells.configs_by_name,
+                cells.cell_resolver.root_cell(),
+                cell,
+            )?;
+            self.by_cell.insert(cell, names);
+        }
+        Ok(self.by_cell[&cell].iter().any(|n| n.as_str() == name))
+    }
+}
+
+fn file_type(name: &str, is_build_file: bool) -> Option<StarlarkFileType> {
+    if name.ends_with(".bzl") {
+        Some(StarlarkFileType::Bzl)
+    } else if name.ends_with(".bxl") {
+        Some(StarlarkFileType::Bxl)
+    } else if name == "PACKAGE" {
+        Some(StarlarkFileType::Package)
+    } else if is_build_file {
+        Some(StarlarkFileType::Buck)
+    } else {
+        None
+    }
+}
+
+fn collect_files(
+    path: &AbsNormPath,
+    explicit: bool,
+    build_file_names: &mut BuildFileNames,
+    files: &mut Vec<(AbsNormPathBuf, StarlarkFileType)>,
+) -> anyhow::Result<()> {
+    let name = path
+        .file_name()
+        .map(|x| x.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    if fs_util::metadata(path)?.is_dir() {
+        if !explicit && (name.starts_with('.') || name == "buck-out") {
+            return Ok(());
+        }
+        let mut entries = fs_util::read_dir(path)?
+            .map(|e| Ok(e?.path()))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        entries.sort();
+        for entry in entries {
+            if fs_util::symlink_metadata(&entry)?.is_symlink() {
+                continue;
+            }
+            collect_files(&entry, false, build_file_names, files)?;
+        }
+    } else {
+        let dir = path.parent().unwrap_or(path);
+        if let Some(file_type) = file_type(&name, build_file_names.is_build_file(dir, &name)?) {
+            files.push((path.to_owned(), file_type));
+        } else if explicit {
+            // Same as `buck2 starlark lint`: a file asked for explicitly is treated as `.bzl`.
+            files.push((path.to_owned(), StarlarkFileType::Bzl));
+        }
+    }
+    Ok(())
+}
+
+impl FmtCommand {
+    pub fn exec(
+        self,
+        _matches: &clap::ArgMatches,
+        ctx: ClientCommandContext<'_>,
+    ) -> anyhow::Result<()> {
+        if self.stdin {
+            let filename = self.stdin_filename.as_deref().unwrap_or("BUCK");
+            let mut content = String::new();
+            std::io::stdin()
+                .lock()
+                .read_to_string(&mut content)
+                .context("Reading stdin")?;
+            // The file is assumed to be in the current directory.
+            let name = filename.rsplit('/').next().unwrap_or(filename);
+            let is_build_file = BuildFileNames::new(ctx.working_dir.path())?
+                .is_build_file(ctx.working_dir.path(), name)?;
+            let file_type = file_type(name, is_build_file).unwrap_or(StarlarkFileType::Bzl);
+            let formatted = format_starlark(filename, &content, file_type)?;
+            if self.check {
+                if formatted != content {
+                    return Err(FmtError::StdinNotFormatted.into());
+                }
+            } else {
+                buck2_client_ctx::print!("{}", formatted)?;
+            }
+            return Ok(());
+        }
+
+        let mut build_file_names = BuildFileNames::new(ctx.working_dir.path())?;
+        let mut files = Vec::new();
+        for path in &self.paths {
+            let path = fs_util::canonicalize(path.resolve(&ctx.working_dir))?;
+            collect_files(&path, true, &mut build_file_names, &mut files)?;
+        }
+
+        let mut changed = 0;
+        for (path, file_type) in &files {
+            let display = path
+                .as_path()
+                .strip_prefix(ctx.working_dir.path())
+                .unwrap_or(path.as_path())
+                .display()
+                .to_string();
+            let content = fs_util::read_to_string(path)?;
+            let formatted = format_starlark(&display, &content, *file_type)
+                .with_context(|| format!("Formatting `{}`", display))?;
+            if formatted == content {
+                continue;
+            }
+            changed += 1;
+            buck2_client_ctx::println!("{}", display)?;
+            if !self.check {
+                fs_util::write(path, formatted)?;
+            }
+        }
+
+        if self.check && changed > 0 {
+            return Err(FmtError::NotFormatted(changed).into());
+        }
+        buck2_client_ctx::eprintln!(
+            "{} {} of {} files",
+            if self.check {
+                "Would reformat"
+            } else {
+                "Reformatted"
+            },
+            changed,
+            files.len()
+        )?;
+        Ok(())
+    }
+
+    pub fn sanitize_argv(&self, argv: Argv) -> SanitizedArgv {
+        argv.no_need_to_sanitize()
+    }
+}
@@ -0,0 +1,1030 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Canonical formatting of `BUCK` and `.bzl` files.
+//!
+//! The file is parsed with the same parser and dialect as the interpreter uses, and printed back
+//! from the AST. The AST does not record comments, so they are collected from the lexer and
+//! reattached by position: a comment on its own line stays before the next statement, argument
+//! or list element, and a comment at the end of a line stays at the end of that line.
+//!
+//! The layout deliberately follows the author where there is a choice: a call, list or dict is
+//! split one element per line if the original had a line break between its elements (or contains
+//! a comment), and is kept on one line otherwise. Top level rule calls in build files are always
+//! split. Blank lines between statements and elements are kept, but collapsed to one.
+
+use buck2_interpreter::error::BuckStarlarkError;
+use buck2_interpreter::file_type::StarlarkFileType;
+use starlark::syntax::AstModule;
+use starlark_syntax::codemap::CodeMap;
+use starlark_syntax::codemap::Span;
+use starlark_syntax::lexer::Lexer;
+use starlark_syntax::lexer::Token;
+use starlark_syntax::syntax::ast::ArgumentP;
+use starlark_syntax::syntax::ast::AssignOp;
+use starlark_syntax::syntax::ast::AssignTargetP;
+use starlark_syntax::syntax::ast::AstArgument;
+use starlark_syntax::syntax::ast::AstAssignTarget;
+use starlark_syntax::syntax::ast::AstExpr;
+use starlark_syntax::syntax::ast::AstLiteral;
+use starlark_syntax::syntax::ast::AstNoPayload;
+use starlark_syntax::syntax::ast::AstParameter;
+use starlark_syntax::syntax::ast::AstStmt;
+use starlark_syntax::syntax::ast::AstString;
+use starlark_syntax::syntax::ast::BinOp;
+use starlark_syntax::syntax::ast::ClauseP;
+use starlark_syntax::syntax::ast::ExprP;
+use starlark_syntax::syntax::ast::ForClauseP;
+use starlark_syntax::syntax::ast::LoadArgP;
+use starlark_syntax::syntax::ast::ParameterP;
+use starlark_syntax::syntax::ast::StmtP;
+use starlark_syntax::syntax::module::AstModuleFields;
+
+const INDENT: &str = "    ";
+
+/// Minimum precedence an expression needs to be printed without parentheses in a given position.
+/// Follows the grammar: a position accepting `OrTest` requires `OR`, and so on.
+mod prec {
+    pub(super) const TEST: u8 = 1;
+    pub(super) const OR: u8 = 3;
+    pub(super) const AND: u8 = 4;
+    pub(super) const NOT: u8 = 5;
+    pub(super) const COMPARE: u8 = 6;
+    pub(super) const BIT_OR: u8 = 7;
+    pub(super) const BIT_XOR: u8 = 8;
+    pub(super) const BIT_AND: u8 = 9;
+    pub(super) const SHIFT: u8 = 10;
+    pub(super) const ARITH: u8 = 11;
+    pub(super) const PRODUCT: u8 = 12;
+    pub(super) const UNARY: u8 = 13;
+    pub(super) const PRIMARY: u8 = 14;
+}
+
+fn bin_op(op: BinOp) -> (&'static str, u8) {
+    match op {
+        BinOp::Or => ("or", prec::OR),
+        BinOp::And => ("and", prec::AND),
+        BinOp::Equal => ("==", prec::COMPARE),
+        BinOp::NotEqual => ("!=", prec::COMPARE),
+        BinOp::Less => ("<", prec::COMPARE),
+        BinOp::Greater => (">", prec::COMPARE),
+        BinOp::LessOrEqual => ("<=", prec::COMPARE),
+        BinOp::GreaterOrEqual => (">=", prec::COMPARE),
+        BinOp::In => ("in", prec::COMPARE),
+        BinOp::NotIn => ("not in", prec::COMPARE),
+        BinOp::BitOr => ("|", prec::BIT_OR),
+        BinOp::BitXor => ("^", prec::BIT_XOR),
+        BinOp::BitAnd => ("&", prec::BIT_AND),
+        BinOp::LeftShift => ("<<", prec::SHIFT),
+        BinOp::RightShift => (">>", prec::SHIFT),
+        BinOp::Add => ("+", prec::ARITH),
+        BinOp::Subtract => ("-", prec::ARITH),
+        BinOp::Multiply => ("*", prec::PRODUCT),
+        BinOp::Percent => ("%", prec::PRODUCT),
+        BinOp::Divide => ("/", prec::PRODUCT),
+        BinOp::FloorDivide => ("//", prec::PRODUCT),
+    }
+}
+
+fn assign_op(op: AssignOp) -> &'static str {
+    match op {
+        AssignOp::Add => "+=",
+        AssignOp::Subtract => "-=",
+        AssignOp::Multiply => "*=",
+        AssignOp::Divide => "/=",
+        AssignOp::FloorDivide => "//=",
+        AssignOp::Percent => "%=",
+        AssignOp::BitAnd => "&=",
+        AssignOp::BitOr => "|=",
+        AssignOp::BitXor => "^=",
+        AssignOp::LeftShift => "<<=",
+        AssignOp::RightShift => ">>=",
+    }
+}
+
+fn expr_prec(expr: &AstExpr) -> u8 {
+    match &expr.node {
+        ExprP::Lambda(_) | ExprP::If(_) => prec::TEST,
+        ExprP::Op(_, op, _) => bin_op(*op).1,
+        ExprP::Not(_) => prec::NOT,
+        ExprP::Minus(_) | ExprP::Plus(_) | ExprP::BitNot(_) => prec::UNARY,
+        _ => prec::PRIMARY,
+    }
+}
+
+/// An element of a bracketed, comma separated sequence.
+enum Item<'a> {
+    Expr(&'a AstExpr),
+    Arg(&'a AstArgument),
+    Entry(&'a AstExpr, &'a AstExpr),
+    Param(&'a AstParameter),
+    String(&'a AstString),
+    LoadArg(&'a LoadArgP<AstNoPayload>),
+}
+
+impl Item<'_> {
+    fn span(&self) -> Span {
+        match self {
+            Item::Expr(x) => x.span,
+            Item::Arg(x) => x.span,
+            Item::Entry(k, v) => k.span.merge(v.span),
+            Item::Param(x) => x.span,
+            Item::String(x) => x.span,
+            Item::LoadArg(x) => x.span(),
+        }
+    }
+}
+
+struct Printer<'a> {
+    source: &'a str,
+    /// `(begin, end)` of every comment, including the leading `#`.
+    comments: Vec<(usize, usize)>,
+    next_comment: usize,
+    build_file: bool,
+    indent: usize,
+    out: String,
+}
+
+fn begin(span: Span) -> usize {
+    span.begin().get() as usize
+}
+
+fn end(span: Span) -> usize {
+    span.end().get() as usize
+}
+
+impl<'a> Printer<'a> {
+    fn write(&mut self, s: &str) {
+        self.out.push_str(s);
+    }
+
+    fn newline(&mut self) {
+        let trimmed = self.out.trim_end_matches(' ').len();
+        self.out.truncate(trimmed);
+        self.out.push('\n');
+    }
+
+    fn write_indent(&mut self) {
+        for _ in 0..self.indent {
+            self.out.push_str(INDENT);
+        }
+    }
+
+    fn source(&self, span: Span) -> &'a str {
+        &self.source[begin(span)..end(span)]
+    }
+
+    fn peek_comment(&self) -> Option<(usize, usize)> {
+        self.comments.get(self.next_comment).copied()
+    }
+
+    fn has_comment_in(&self, from: usize, to: usize) -> bool {
+        self.comments[self.next_comment..]
+            .iter()
+            .any(|(b, _)| *b >= from && *b < to)
+    }
+
+    /// Whether the source between two positions contains an empty line.
+    fn blank_line_between(&self, from: usize, to: usize) -> bool {
+        if from >= to {
+            return false;
+        }
+        let lines: Vec<&str> = self.source[from..to].split('\n').collect();
+        lines.len() > 2
+            && lines[1..lines.len() - 1]
+                .iter()
+                .any(|l| l.trim().is_empty())
+    }
+
+    fn write_comment(&mut self, (b, e): (usize, usize)) {
+        let text = self.source[b..e].trim_end();
+        self.write(text);
+        self.next_comment += 1;
+    }
+
+    /// Emit, each on its own line, all comments before `pos`.
+    fn own_line_comments(&mut self, pos: usize, last_end: &mut Option<usize>) {
+        while let Some(comment) = self.peek_comment() {
+            if comment.0 >= pos {
+                break;
+            }
+            if let Some(last_end) = *last_end {
+                if self.blank_line_between(last_end, comment.0) {
+                    self.newline();
+                }
+            }
+            self.write_indent();
+            self.write_comment(comment);
+            self.newline();
+            *last_end = Some(comment.1);
+        }
+    }
+
+    /// Emit a comment which is on the same line as `pos`, and before `limit`.
+    fn trailing_comment(&mut self, pos: usize, limit: usize) -> Option<usize> {
+        let comment = self.peek_comment()?;
+        if comment.0 >= pos && comment.0 < limit && !self.source[pos..comment.0].contains('\n') {
+            self.write("  ");
+            self.write_comment(comment);
+            Some(comment.1)
+        } else {
+            None
+        }
+    }
+
+    fn flatten<'s>(stmt: &'s AstStmt, out: &mut Vec<&'s AstStmt>) {
+        match &stmt.node {
+            StmtP::Statements(xs) => xs.iter().for_each(|x| Self::flatten(x, out)),
+            _ => out.push(stmt),
+        }
+    }
+
+    fn block(&mut self, stmts: &[&AstStmt], block_end: usize) {
+        let mut last_end = None;
+        for (i, stmt) in stmts.iter().enumerate() {
+            self.own_line_comments(begin(stmt.span), &mut last_end);
+            if let Some(last_end) = last_end {
+                if self.blank_line_between(last_end, begin(stmt.span)) {
+                    self.newline();
+                }
+            }
+            self.write_indent();
+            if self.stmt(stmt) {
+                // The limit only matters for several statements on one line separated by `;`.
+                let limit = stmts
+                    .get(i + 1)
+                    .map_or(self.source.len(), |s| begin(s.span));
+                let trailing = self.trailing_comment(end(stmt.span), limit);
+                self.newline();
+                last_end = Some(trailing.unwrap_or(end(stmt.span)));
+            } else {
+                last_end = Some(end(stmt.span));
+            }
+            // Comments inside constructs which are always printed on one line.
+            self.own_line_comments(end(stmt.span), &mut last_end);
+        }
+        self.own_line_comments(block_end, &mut last_end);
+    }
+
+    fn body(&mut self, body: &AstStmt) {
+        let mut stmts = Vec::new();
+        Self::flatten(body, &mut stmts);
+        self.indent += 1;
+        self.block(&stmts, end(body.span));
+        self.indent -= 1;
+    }
+
+    /// Print a statement. Returns `true` for simple statements, which are printed without the
+    /// trailing newline, and `false` for compound statements, which print their bodies.
+    fn stmt(&mut self, stmt: &AstStmt) -> bool {
+        match &stmt.node {
+            StmtP::Break => self.write("break"),
+            StmtP::Continue => self.write("continue"),
+            StmtP::Pass => self.write("pass"),
+            StmtP::Return(None) => self.write("return"),
+            StmtP::Return(Some(x)) => {
+                self.write("return ");
+                self.top_expr(x);
+            }
+            StmtP::Expression(x) => {
+                let is_rule_call = self.build_file
+                    && self.indent == 0
+                    && matches!(&x.node, ExprP::Call(_, args) if !args.is_empty());
+                match &x.node {
+                    ExprP::Call(callee, args) if is_rule_call => {
+                        self.expr(callee, prec::PRIMARY);
+                        let items: Vec<_> = args.iter().map(Item::Arg).collect();
+                        self.seq("(", ")", &items, end(callee.span), Some(end(x.span)), true);
+                    }
+                    _ => self.expr(x, prec::TEST),
+                }
+            }
+            StmtP::Assign(assign) => {
+                self.assign_target(&assign.lhs, true);
+                if let Some(ty) = &assign.ty {
+                    self.write(": ");
+                    self.expr(&ty.node.expr, prec::TEST);
+                }
+                self.write(" = ");
+                self.top_expr(&assign.rhs);
+            }
+            StmtP::AssignModify(lhs, op, rhs) => {
+                self.assign_target(lhs, true);
+                self.write(" ");
+                self.write(assign_op(*op));
+                self.write(" ");
+                self.top_expr(rhs);
+            }
+            StmtP::Statements(_) => {
+                let mut stmts = Vec::new();
+                Self::flatten(stmt, &mut stmts);
+                // Callers flatten statements, this is only here for completeness.
+                for (i, s) in stmts.iter().enumerate() {
+                    if i > 0 {
+                        self.newline();
+                        self.write_indent();
+                    }
+                    self.stmt(s);
+                }
+            }
+            StmtP::If(cond, then) => {
+                self.write("if ");
+                self.expr(cond, prec::TEST);
+                self.write(":");
+                self.newline();
+                self.body(then);
+                return false;
+            }
+            StmtP::IfElse(cond, then_else) => {
+                self.if_else("if ", cond, &then_else.0, &then_else.1);
+                return false;
+            }
+            StmtP::For(f) => {
+                self.write("for ");
+                self.assign_target(&f.var, true);
+                self.write(" in ");
+                self.expr(&f.over, prec::TEST);
+                self.write(":");
+                self.newline();
+                self.body(&f.body);
+                return false;
+            }
+            StmtP::Def(def) => {
+                self.write("def ");
+                self.write(&def.name.node.ident);
+                let items: Vec<_> = def.params.iter().map(Item::Param).collect();
+                self.seq("(", ")", &items, end(def.name.span), None, false);
+                if let Some(ret) = &def.return_type {
+                    self.write(" -> ");
+                    self.expr(&ret.node.expr, prec::TEST);
+                }
+                self.write(":");
+                self.newline();
+                self.body(&def.body);
+                return false;
+            }
+            StmtP::Load(load) => {
+                self.write("load");
+                let items: Vec<_> = std::iter::once(Item::String(&load.module))
+                    .chain(load.args.iter().map(Item::LoadArg))
+                    .collect();
+                self.seq(
+                    "(",
+                    ")",
+                    &items,
+                    begin(stmt.span),
+                    Some(end(stmt.span)),
+                    false,
+                );
+            }
+        }
+        true
+    }
+
+    fn if_else(&mut self, keyword: &str, cond: &AstExpr, then: &AstStmt, els: &AstStmt) {
+        self.write(keyword);
+        self.expr(cond, prec::TEST);
+        self.write(":");
+        self.newline();
+        self.body(then);
+
+        // `elif` is parsed as `else` containing a single `if`, tell them apart by the source.
+        let is_elif = matches!(&els.node, StmtP::If(..) | StmtP::IfElse(..))
+            && self.source[..begin(els.span)].trim_end().ends_with("elif");
+        self.write_indent();
+        match &els.node {
+            StmtP::If(cond, then) if is_elif => {
+                self.write("elif ");
+                self.expr(cond, prec::TEST);
+                self.write(":");
+                self.newline();
+                self.body(then);
+            }
+            StmtP::IfElse(cond, then_else) if is_elif => {
+                self.if_else("elif ", cond, &then_else.0, &then_else.1);
+            }
+            _ => {
+                self.write("else:");
+                self.newline();
+                self.body(els);
+            }
+        }
+    }
+
+    /// Expression in a position where an unparenthesized tuple is allowed, e.g. `x = a, b`.
+    fn top_expr(&mut self, expr: &AstExpr) {
+        match &expr.node {
+            ExprP::Tuple(xs) if !xs.is_empty() && !self.parenthesized(expr.span) => {
+                for (i, x) in xs.iter().enumerate() {
+                    if i > 0 {
+                        self.write(", ");
+                    }
+                    self.expr(x, prec::TEST);
+                }
+                if xs.len() == 1 {
+                    self.write(",");
+                }
+            }
+            _ => self.expr(expr, prec::TEST),
+        }
+    }
+
+    /// Whether a tuple was written with parentheses, whose span excludes them.
+    fn parenthesized(&self, span: Span) -> bool {
+        self.source[..begin(span)].trim_end().ends_with('(')
+    }
+
+    fn assign_target(&mut self, target: &AstAssignTarget, top: bool) {
+        match &target.node {
+            AssignTargetP::Tuple(xs) => {
+                if !top {
+                    self.write("(");
+                }
+                for (i, x) in xs.iter().enumerate() {
+                    if i > 0 {
+                        self.write(", ");
+                    }
+                    self.assign_target(x, false);
+                }
+                if xs.len() == 1 {
+                    self.write(",");
+                }
+                if !top {
+                    self.write(")");
+                }
+            }
+            AssignTargetP::Index(a_b) => {
+                self.expr(&a_b.0, prec::PRIMARY);
+                self.write("[");
+                self.expr(&a_b.1, prec::TEST);
+                self.write("]");
+            }
+            AssignTargetP::Dot(a, b) => {
+                self.expr(a, prec::PRIMARY);
+                self.write(".");
+                self.write(b);
+            }
+            AssignTargetP::Identifier(x) => self.write(&x.node.ident),
+        }
+    }
+
+    fn string(&mut self, s: &AstString) {
+        let source = self.source(s.span);
+        // Prefer double quotes, unless that would need escaping.
+        let single = source.len() >= 2
+            && source.starts_with('\'')
+            && !source.starts_with("'''")
+            && !source[1..source.len() - 1].contains(['"', '\\', '\'']);
+        if single {
+            self.write("\"");
+            self.write(&source[1..source.len() - 1]);
+            self.write("\"");
+        } else {
+            self.write(source);
+        }
+    }
+
+    fn expr(&mut self, expr: &AstExpr, min_prec: u8) {
+        let parens = expr_prec(expr) < min_prec;
+        if parens {
+            self.write("(");
+        }
+        match &expr.node {
+            ExprP::Tuple(xs) => {
+                let items: Vec<_> = xs.iter().map(Item::Expr).collect();
+                self.seq("(", ")", &items, begin(expr.span), None, false);
+            }
+            ExprP::Dot(a, b) => {
+                self.expr(a, prec::PRIMARY);
+                self.write(".");
+                self.write(b);
+            }
+            ExprP::Call(callee, args) => {
+                self.expr(callee, prec::PRIMARY);
+                let items: Vec<_> = args.iter().map(Item::Arg).collect();
+                self.seq(
+                    "(",
+                    ")",
+                    &items,
+                    end(callee.span),
+                    Some(end(expr.span)),
+                    false,
+                );
+            }
+            ExprP::Index(a_b) => {
+                self.expr(&a_b.0, prec::PRIMARY);
+                self.write("[");
+                self.expr(&a_b.1, prec::TEST);
+                self.write("]");
+            }
+            ExprP::Index2(a_i0_i1) => {
+                self.expr(&a_i0_i1.0, prec::PRIMARY);
+                self.write("[");
+                self.expr(&a_i0_i1.1, prec::TEST);
+                self.write(", ");
+                self.expr(&a_i0_i1.2, prec::TEST);
+                self.write("]");
+            }
+            ExprP::Slice(a, i0, i1, i2) => {
+                self.expr(a, prec::PRIMARY);
+                self.write("[");
+                if let Some(i0) = i0 {
+                    self.expr(i0, prec::TEST);
+                }
+                self.write(":");
+                if let Some(i1) = i1 {
+                    self.expr(i1, prec::TEST);
+                }
+                if let Some(i2) = i2 {
+                    self.write(":");
+                    self.expr(i2, prec::TEST);
+                }
+                self.write("]");
+            }
+            ExprP::Identifier(x) => self.write(&x.node.ident),
+            ExprP::Lambda(lambda) => {
+                self.write("lambda");
+                for (i, p) in lambda.params.iter().enumerate() {
+                    self.write(if i == 0 { " " } else { ", " });
+                    self.param(p);
+                }
+                self.write(": ");
+                self.expr(&lambda.body, prec::TEST);
+            }
+            ExprP::Literal(AstLiteral::String(s)) => self.string(s),
+            ExprP::Literal(AstLiteral::Ellipsis) => self.write("..."),
+            ExprP::Literal(AstLiteral::Int(_) | AstLiteral::Float(_)) | ExprP::FString(_) => {
+                let source = self.source(expr.span);
+                self.write(source);
+            }
+            ExprP::Not(x) => {
+                self.write("not ");
+                self.expr(x, prec::NOT);
+            }
+            ExprP::Minus(x) => {
+                self.write("-");
+                self.expr(x, prec::UNARY);
+            }
+            ExprP::Plus(x) => {
+                self.write("+");
+                self.expr(x, prec::UNARY);
+            }
+            ExprP::BitNot(x) => {
+                self.write("~");
+                self.expr(x, prec::UNARY);
+            }
+            ExprP::Op(l, op, r) => {
+                let (op, p) = bin_op(*op);
+                // Comparisons are not associative, everything else is left associative.
+                let left = if p == prec::COMPARE { p + 1 } else { p };
+                self.expr(l, left);
+                self.write(" ");
+                self.write(op);
+                self.write(" ");
+                self.expr(r, p + 1);
+            }
+            ExprP::If(c_t_f) => {
+                let (cond, t, f) = &**c_t_f;
+                self.expr(t, prec::OR);
+                self.write(" if ");
+                self.expr(cond, prec::OR);
+                self.write(" else ");
+                self.expr(f, prec::TEST);
+            }
+            ExprP::List(xs) => {
+                let items: Vec<_> = xs.iter().map(Item::Expr).collect();
+                self.seq(
+                    "[",
+                    "]",
+                    &items,
+                    begin(expr.span),
+                    Some(end(expr.span)),
+                    false,
+                );
+            }
+            ExprP::Dict(xs) => {
+                let items: Vec<_> = xs.iter().map(|(k, v)| Item::Entry(k, v)).collect();
+                self.seq(
+                    "{",
+                    "}",
+                    &items,
+                    begin(expr.span),
+                    Some(end(expr.span)),
+                    false,
+                );
+            }
+            ExprP::ListComprehension(x, for_, clauses) => {
+                self.write("[");
+                self.expr(x, prec::TEST);
+                self.clauses(for_, clauses);
+                self.write("]");
+            }
+            ExprP::DictComprehension(k_v, for_, clauses) => {
+                self.write("{");
+                self.expr(&k_v.0, prec::TEST);
+                self.write(": ");
+                self.expr(&k_v.1, prec::TEST);
+                self.clauses(for_, clauses);
+                self.write("}");
+            }
+        }
+        if parens {
+            self.write(")");
+        }
+    }
+
+    fn for_clause(&mut self, f: &ForClauseP<AstNoPayload>) {
+        self.write(" for ");
+        self.assign_target(&f.var, true);
+        self.write(" in ");
+        self.expr(&f.over, prec::OR);
+    }
+
+    fn clauses(&mut self, for_: &ForClauseP<AstNoPayload>, clauses: &[ClauseP<AstNoPayload>]) {
+        self.for_clause(for_);
+        for clause in clauses {
+            match clause {
+                ClauseP::For(f) => self.for_clause(f),
+                ClauseP::If(x) => {
+                    self.write(" if ");
+                    self.expr(x, prec::OR);
+                }
+            }
+        }
+    }
+
+    fn param(&mut self, param: &AstParameter) {
+        let (prefix, name, ty, default) = match &param.node {
+            ParameterP::Normal(n, ty) => ("", Some(n), ty, None),
+            ParameterP::WithDefaultValue(n, ty, d) => ("", Some(n), ty, Some(d)),
+            ParameterP::NoArgs => ("*", None, &None, None),
+            ParameterP::Args(n, ty) => ("*", Some(n), ty, None),
+            ParameterP::KwArgs(n, ty) => ("**", Some(n), ty, None),
+        };
+        self.write(prefix);
+        if let Some(name) = name {
+            self.write(&name.node.ident);
+        }
+        if let Some(ty) = ty {
+            self.write(": ");
+            self.expr(&ty.node.expr, prec::TEST);
+        }
+        if let Some(default) = default {
+            self.write(" = ");
+            self.expr(default, prec::TEST);
+        }
+    }
+
+    fn item(&mut self, item: &Item) {
+        match item {
+            Item::Expr(x) => self.expr(x, prec::TEST),
+            Item::Arg(arg) => match &arg.node {
+                ArgumentP::Positional(x) => self.expr(x, prec::TEST),
+                ArgumentP::Named(name, x) => {
+                    self.write(name);
+                    self.write(" = ");
+                    self.expr(x, prec::TEST);
+                }
+                ArgumentP::Args(x) => {
+                    self.write("*");
+                    self.expr(x, prec::TEST);
+                }
+                ArgumentP::KwArgs(x) => {
+                    self.write("**");
+                    self.expr(x, prec::TEST);
+                }
+            },
+            Item::Entry(k, v) => {
+                self.expr(k, prec::TEST);
+                self.write(": ");
+                self.expr(v, prec::TEST);
+            }
+            Item::Param(p) => self.param(p),
+            Item::String(s) => self.string(s),
+            Item::LoadArg(arg) => {
+                if arg.local.span != arg.their.span {
+                    self.write(&arg.local.node.ident);
+                    self.write(" = ");
+                }
+                self.string(&arg.their);
+            }
+        }
+    }
+
+    /// Print a bracketed sequence. `open_pos` is where the opening bracket is (or could be),
+    /// `close_pos` the end of the closing bracket if known.
+    fn seq(
+        &mut self,
+        open: &str,
+        close: &str,
+        items: &[Item],
+        open_pos: usize,
+        close_pos: Option<usize>,
+        force_multiline: bool,
+    ) {
+        let spans: Vec<Span> = items.iter().map(|x| x.span()).collect();
+        let last_pos = spans.last().map_or(open_pos, |s| end(*s));
+        let region_end = close_pos.unwrap_or(last_pos);
+
+        let line_break = || {
+            let mut prev = open_pos;
+            for span in &spans {
+                if self.source[prev..begin(*span)].contains('\n') {
+                    return true;
+                }
+                prev = end(*span);
+            }
+            close_pos
+                .is_some_and(|close| !spans.is_empty() && self.source[prev..close].contains('\n'))
+        };
+        let multiline =
+            force_multiline || self.has_comment_in(open_pos, region_end) || line_break();
+
+        self.write(open);
+        if !multiline {
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    self.write(", ");
+                }
+                self.item(item);
+            }
+            if items.len() == 1 && open == "(" && matches!(items[0], Item::Expr(_)) {
+                // Single element tuple.
+                self.write(",");
+            }
+            self.write(close);
+            return;
+        }
+
+        self.newline();
+        self.indent += 1;
+        let mut last_end = None;
+        for (i, item) in items.iter().enumerate() {
+            let span = spans[i];
+            self.own_line_comments(begin(span), &mut last_end);
+            if let Some(last_end) = last_end {
+                if self.blank_line_between(last_end, begin(span)) {
+                    self.newline();
+                }
+            }
+            self.write_indent();
+            self.item(item);
+            self.write(",");
+            let limit = spans.get(i + 1).map_or(region_end, |s| begin(*s));
+            let trailing = self.trailing_comment(end(span), limit);
+            self.newline();
+            last_end = Some(trailing.unwrap_or(end(span)));
+            self.own_line_comments(end(span), &mut last_end);
+        }
+        self.own_line_comments(region_end, &mut last_end);
+        self.indent -= 1;
+        self.write_indent();
+        self.write(close);
+    }
+}
+
+/// Format the contents of a Starlark file of the given type.
+pub(crate) fn format_starlark(
+    filename: &str,
+    content: &str,
+    file_type: StarlarkFileType,
+) -> anyhow::Result<String> {
+    let dialect = file_type.dialect(false);
+    let ast =
+        AstModule::parse(filename, content.to_owned(), &dialect).map_err(BuckStarlarkError::new)?;
+
+    let codemap = CodeMap::new(filename.to_owned(), content.to_owned());
+    let mut comments = Vec::new();
+    for token in Lexer::new(content, &dialect, codemap) {
+        // The file parsed, so the lexer cannot fail.
+        if let Ok((b, Token::Comment(_), e)) = token {
+            comments.push((b, e));
+        }
+    }
+
+    let mut printer = Printer {
+        source: content,
+        comments,
+        next_comment: 0,
+        build_file: matches!(
+            file_type,
+            StarlarkFileType::Buck | StarlarkFileType::Package
+        ),
+        indent: 0,
+        out: String::with_capacity(content.len()),
+    };
+    let mut stmts = Vec::new();
+    Printer::flatten(ast.statement(), &mut stmts);
+    printer.block(&stmts, content.len());
+
+    let mut out = printer.out;
+    let trimmed = out.trim_end().len();
+    out.truncate(trimmed);
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(file_type: StarlarkFileType, input: &str, expected: &str) {
+        let formatted = format_starlark("test", input, file_type).unwrap();
+        assert_eq!(expected, formatted);
+        // Formatting must be idempotent.
+        assert_eq!(
+            expected,
+            format_starlark("test", &formatted, file_type).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_build_file() {
+        check(
+            StarlarkFileType::Buck,
+            r#"# Header comment.
+
+load('//defs.bzl','my_rule')
+my_rule(name='foo',srcs=glob(["*.c"]),deps=[
+  # Leading comment.
+  ":bar",":baz", # trailing comment
+])
+
+
+my_rule(name = "bar")
+"#,
+            r#"# Header comment.
+
+load("//defs.bzl", "my_rule")
+my_rule(
+    name = "foo",
+    srcs = glob(["*.c"]),
+    deps = [
+        # Leading comment.
+        ":bar",
+        ":baz",  # trailing comment
+    ],
+)
+
+my_rule(
+    name = "bar",
+)
+"#,
+        );
+    }
+
+    #[test]
+    fn test_bzl_file() {
+        check(
+            StarlarkFileType::Bzl,
+            r#"def f(x,y=1,*args,**kwargs):
+  """Docstring."""
+  if x and not y: return (x+y)*2
+  elif x: pass
+  else:
+      return [a for a in args if a], x, -y
+  a, b = 1, 2
+  return (a,)
+"#,
+            r#"def f(x, y = 1, *args, **kwargs):
+    """Docstring."""
+    if x and not y:
+        return (x + y) * 2
+    elif x:
+        pass
+    else:
+        return [a for a in args if a], x, -y
+    a, b = 1, 2
+    return (a,)
+"#,
+        );
+    }
+
+    fn program(file_type: StarlarkFileType, content: &str) -> String {
+        let ast = AstModule::parse("test", content.to_owned(), &file_type.dialect(false)).unwrap();
+        // Expressions are displayed fully parenthesized, so this is the structure of the program.
+        ast.statement().node.to_string()
+    }
+
+    fn comments(file_type: StarlarkFileType, content: &str) -> Vec<String> {
+        let codemap = CodeMap::new("test".to_owned(), content.to_owned());
+        Lexer::new(content, &file_type.dialect(false), codemap)
+            .filter_map(|token| match token {
+                Ok((_, Token::Comment(c), _)) => Some(c.trim_end().to_owned()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Formatting must not change what the file means, lose a comment, or change a formatted file.
+    fn check_round_trip(file_type: StarlarkFileType, input: &str) {
+        let formatted = format_starlark("test", input, file_type).unwrap();
+        assert_eq!(
+            program(file_type, input),
+            program(file_type, &formatted),
+            "{}",
+            formatted
+        );
+        assert_eq!(
+            comments(file_type, input),
+            comments(file_type, &formatted),
+            "{}",
+            formatted
+        );
+        assert_eq!(
+            formatted,
+            format_starlark("test", &formatted, file_type).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_round_trip_expressions() {
+        check_round_trip(
+            StarlarkFileType::Bzl,
+            r#"
+a = (1 + 2) * 3 - -4 // 5 % 6
+b = not (x and y) or z
+c = (not x) == y
+d = (a if b else c) if d else (lambda q: q + 1)
+e = x | y ^ z & (w << 2) >> 1
+f = ~x + +y
+g = [x * y for x in range(3) if x for y in [1, 2]]
+h = {k: v for k, v in {"a": 1, 'b': 2}.items()}
+i = l[1:2], l[::2], l[:], l[1:], l[-1], l[a, b]
+j = "it's", 'say "hi"', "tab\there", r"raw\d", """multi
+line"""
+k = (1,), (), (1, 2)
+m = x.y.z(1, *args, key = 2, **kwargs)
+o = a in b and a not in c
+p = (a + b).c[d](e)
+"#,
+        );
+    }
+
+    #[test]
+    fn test_round_trip_statements() {
+        check_round_trip(
+            StarlarkFileType::Bzl,
+            r#"# Leading comment.
+load("//foo:bar.bzl", "a", b = "c")  # Load comment.
+
+def f(a, b = 1, *args, c, d = {}, **kwargs) -> str:
+    """Docstring."""
+    # Comment in body.
+    for x, (y, z) in a:
+        if x:
+            continue
+        elif y:   # After elif.
+            break
+        else:
+            pass
+
+    a += 1
+    b[0] -= 2
+    c.d *= 3
+    x: int = 1
+    return None
+
+def g():
+    return
+
+# Trailing comment.
+"#,
+        );
+    }
+
+    #[test]
+    fn test_round_trip_build_file() {
+        check_round_trip(
+            StarlarkFileType::Buck,
+            r#"load(":defs.bzl", "rule")
+
+rule(name = "a", deps = [":b", # b
+    ":c"], visibility = ["PUBLIC"])
+
+rule(
+    # Comment on its own line.
+    name = "b",
+
+    srcs = glob(["**/*.c"], exclude = ["x.c"]) + select({
+        "DEFAULT": [],
+        "config//os:linux": ["linux.c"],  # Linux only.
+    }),
+)
+"#,
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert!(format_starlark("test", "foo(", StarlarkFileType::Bzl).is_err());
+    }
+}
@@ -30,10 +30,19 @@ use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
 
 use crate::debug::StarlarkDebugAttachCommand;
 use crate::lint::StarlarkLintCommand;
+use crate::migrate::StarlarkMigrateCommand;
+use crate::repl::StarlarkReplCommand;
+use crate::repl::StarlarkReplEvalCommand;
 use crate::typecheck::StarlarkTypecheckCommand;
 
-mod debug;
-mod lint;
+mod codemod;
+pub mod debug;
+pub mod fmt;
+mod formatter;
+pub mod lint;
+mod lint_rules;
+mod migrate;
+mod repl;
 pub mod server;
 mod typecheck;
 mod util;
@@ -44,6 +53,7 @@ pub enum StarlarkCommand {
     #[clap(flatten)]
     Opaque(StarlarkOpaqueCommand),
     DebugAttach(StarlarkDebugAttachCommand),
+    Repl(StarlarkReplCommand),
 }
 
 // Used for subcommands that follow `buck2 audit`'s "opaque" pattern where the command object is serialized
@@ -52,7 +62,10 @@ pub enum StarlarkCommand {
 #[derive(Debug, clap::Subcommand, serde::Serialize, serde::Deserialize)]
 pub enum StarlarkOpaqueCommand {
     Lint(StarlarkLintCommand),
+    Migrate(StarlarkMigrateCommand),
     Typecheck(StarlarkTypecheckCommand),
+    #[clap(hide = true)]
+    ReplEval(StarlarkReplEvalCommand),
 }
 
 #[derive(Debug, clap::Parser, serde::Serialize, serde::Deserialize, Default)]
@@ -96,7 +109,9 @@ impl StarlarkOpaqueCommand {
     fn as_subcommand(&self) -> &dyn StarlarkOpaqueSubcommand {
         match self {
             Self::Lint(cmd) => cmd,
+            Self::Migrate(cmd) => cmd,
             Self::Typecheck(cmd) => cmd,
+            Self::ReplEval(cmd) => cmd,
         }
     }
 }
@@ -153,6 +168,7 @@ impl StarlarkCommand {
         match self {
             StarlarkCommand::Opaque(cmd) => cmd.exec(matches, ctx),
             StarlarkCommand::DebugAttach(cmd) => cmd.exec(matches, ctx),
+            StarlarkCommand::Repl(cmd) => cmd.exec(matches, ctx),
         }
     }
 
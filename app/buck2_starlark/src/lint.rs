@@ -7,6 +7,7 @@
  * of this source tree.
  */
 
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::io::Write;
@@ -19,6 +20,8 @@ use buck2_client_ctx::path_arg::PathArg;
 use buck2_common::dice::cells::HasCellResolver;
 use buck2_common::dice::data::HasIoProvider;
 use buck2_common::io::IoProvider;
+use buck2_common::legacy_configs::dice::HasLegacyConfigs;
+use buck2_common::legacy_configs::key::BuckconfigKeyRef;
 use buck2_core::cells::name::CellName;
 use buck2_core::cells::CellResolver;
 use buck2_interpreter::file_type::StarlarkFileType;
@@ -29,31 +32,82 @@ use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
 use dice::DiceTransaction;
 use dupe::Dupe;
 use dupe::OptionDupedExt;
+use futures::StreamExt;
+use futures::TryStreamExt;
 use starlark::analysis::AstModuleLint;
 use starlark::codemap::FileSpan;
 use starlark::errors::EvalSeverity;
 use starlark::errors::Lint;
 use starlark::syntax::AstModule;
+use starlark::syntax::Dialect;
 
+use crate::lint_rules::deprecated_symbols;
+use crate::lint_rules::shadowed_builtins;
 use crate::util::environment::Environment;
 use crate::util::paths::starlark_files;
 use crate::StarlarkCommandCommonOptions;
 use crate::StarlarkOpaqueSubcommand;
 
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Dupe,
+    clap::ValueEnum,
+    serde::Serialize,
+    serde::Deserialize
+)]
+#[clap(rename_all = "snake_case")]
+enum LintOutputFormat {
+    /// `path:line:column: problem`, one lint per line.
+    Text,
+    /// One JSON object per lint, one per line.
+    Json,
+    /// GitHub Actions workflow commands, which show up as annotations on the diff.
+    Annotations,
+}
+
 #[derive(Debug, clap::Parser, serde::Serialize, serde::Deserialize)]
-#[clap(name = "starlark-lint", about = "Run the Starlark linter.")]
+#[clap(
+    name = "starlark-lint",
+    about = "Run the Starlark linter.",
+    long_about = "Run the Starlark linter.\n\n\
+    In addition to the generic Starlark lints (such as `unused-load` and `unused-assign`), \
+    reports `shadowed-builtin` for bindings hiding a global symbol, and `deprecated-symbol` for \
+    uses of symbols listed in the `[lint_deprecated_symbols]` section of the cell buckconfig, \
+    which maps a symbol (`old_rule` or `native.old_rule`) to a message. Lints can be disabled \
+    per cell with `lint.disabled`, or with `--disable`."
+)]
 pub struct StarlarkLintCommand {
     #[clap(flatten)]
     common_opts: StarlarkCommandCommonOptions,
 
+    /// Output format.
+    #[clap(long, value_enum, default_value = "text")]
+    output_format: LintOutputFormat,
+
+    /// Do not report lints with this name, e.g. `unused-assign`. Can be repeated.
+    #[clap(long, value_name = "NAME")]
+    disable: Vec<String>,
+
+    /// Files or directories to lint, globs such as `foo/**/*.bzl`, or package patterns
+    /// such as `//foo/...`.
     #[clap(value_name = "PATH", required = true)]
     paths: Vec<PathArg>,
 }
 
+/// Lint settings from the buckconfig of a cell.
+#[derive(Default)]
+struct LintConfig {
+    disabled: HashSet<String>,
+    deprecated: BTreeMap<String, String>,
+}
+
 /// The cache of names for a path, keyed by its CellName and its path type.
 struct Cache<'a> {
     dice: &'a DiceTransaction,
     cached: HashMap<(CellName, StarlarkFileType), Arc<HashSet<String>>>,
+    configs: HashMap<CellName, Arc<LintConfig>>,
 }
 
 impl<'a> Cache<'a> {
@@ -61,9 +115,40 @@ impl<'a> Cache<'a> {
         Self {
             dice,
             cached: HashMap::new(),
+            configs: HashMap::new(),
         }
     }
 
+    async fn get_config(&mut self, cell: CellName) -> anyhow::Result<Arc<LintConfig>> {
+        if let Some(res) = self.configs.get(&cell) {
+            return Ok(res.dupe());
+        }
+        let config = self.dice.clone().get_legacy_config_for_cell(cell).await?;
+        let disabled = config
+            .parse_list::<String>(BuckconfigKeyRef {
+                section: "lint",
+                property: "disabled",
+            })?
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+        let deprecated = config
+            .get_section("lint_deprecated_symbols")
+            .map(|section| {
+                section
+                    .iter()
+                    .map(|(k, v)| (k.to_owned(), v.as_str().to_owned()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let res = Arc::new(LintConfig {
+            disabled,
+            deprecated,
+        });
+        self.configs.insert(cell, res.dupe());
+        Ok(res)
+    }
+
     pub(crate) async fn get_names(
         &mut self,
         path: &StarlarkPath<'_>,
@@ -80,11 +165,15 @@ impl<'a> Cache<'a> {
     }
 }
 
+/// Read a file and lint it. Parsing and linting run on a blocking thread, so that
+/// many files can be linted in parallel.
 async fn lint_file(
-    path: &StarlarkPath<'_>,
+    path: StarlarkPath<'_>,
     cell_resolver: &CellResolver,
     io: &dyn IoProvider,
-    cache: &mut Cache<'_>,
+    globals: Arc<HashSet<String>>,
+    config: Arc<LintConfig>,
+    disabled: Arc<HashSet<String>>,
 ) -> anyhow::Result<Vec<Lint>> {
     let dialect = path.file_type().dialect(false);
     let proj_path = cell_resolver.resolve_path(path.path().as_ref().as_ref())?;
@@ -93,12 +182,38 @@ async fn lint_file(
         .read_file_if_exists(proj_path)
         .await?
         .with_context(|| format!("File not found: `{}`", path_str))?;
-    match AstModule::parse(&path_str, content.clone(), &dialect) {
-        Ok(ast) => Ok(ast.lint(Some(&*cache.get_names(path).await?))),
+    Ok(tokio::task::spawn_blocking(move || {
+        lint_content(path_str, content, &dialect, &globals, &config, &disabled)
+    })
+    .await?)
+}
+
+fn lint_content(
+    path_str: String,
+    content: String,
+    dialect: &Dialect,
+    globals: &HashSet<String>,
+    config: &LintConfig,
+    disabled: &HashSet<String>,
+) -> Vec<Lint> {
+    match AstModule::parse(&path_str, content.clone(), dialect) {
+        Ok(ast) => {
+            let mut lints = ast.lint(Some(globals));
+            lints.extend(
+                deprecated_symbols(&ast, &config.deprecated)
+                    .into_iter()
+                    .chain(shadowed_builtins(&ast, globals))
+                    .filter(|x| !ast.is_suppressed(&x.short_name, x.location.span)),
+            );
+            lints.retain(|x| {
+                !config.disabled.contains(&x.short_name) && !disabled.contains(&x.short_name)
+            });
+            lints
+        }
         Err(err) => {
             // There was a parse error, so we don't want to fail, we want to give a nice error message
             // Do the best we can - it is probably a `Diagnostic`, which gives us more precise info.
-            Ok(vec![Lint {
+            vec![Lint {
                 location: err
                     .span()
                     .duped()
@@ -107,7 +222,59 @@ async fn lint_file(
                 severity: EvalSeverity::Error,
                 problem: format!("{:#}", err.without_diagnostic()),
                 original: "".to_owned(),
-            }])
+            }]
+        }
+    }
+}
+
+/// Escape data for a GitHub Actions workflow command, properties additionally escape `:` and `,`.
+fn escape_annotation(s: &str, property: bool) -> String {
+    let mut res = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '%' => res.push_str("%25"),
+            '\r' => res.push_str("%0D"),
+            '\n' => res.push_str("%0A"),
+            ':' if property => res.push_str("%3A"),
+            ',' if property => res.push_str("%2C"),
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+fn format_lint(lint: &Lint, format: LintOutputFormat) -> String {
+    let span = lint.location.resolve_span();
+    match format {
+        LintOutputFormat::Text => lint.to_string(),
+        LintOutputFormat::Json => serde_json::json!({
+            "path": lint.location.filename(),
+            "line": span.begin.line + 1,
+            "column": span.begin.column + 1,
+            "end_line": span.end.line + 1,
+            "end_column": span.end.column + 1,
+            "name": lint.short_name,
+            "severity": lint.severity.to_string(),
+            "problem": lint.problem,
+        })
+        .to_string(),
+        LintOutputFormat::Annotations => {
+            let level = match lint.severity {
+                EvalSeverity::Error => "error",
+                EvalSeverity::Warning => "warning",
+                EvalSeverity::Advice | EvalSeverity::Disabled => "notice",
+            };
+            format!(
+                "::{} file={},line={},col={},endLine={},endColumn={},title={}::{}",
+                level,
+                escape_annotation(lint.location.filename(), true),
+                span.begin.line + 1,
+                span.begin.column + 1,
+                span.end.line + 1,
+                span.end.column + 1,
+                escape_annotation(&lint.short_name, true),
+                escape_annotation(&lint.problem, false),
+            )
         }
     }
 }
@@ -131,12 +298,30 @@ impl StarlarkOpaqueSubcommand for StarlarkLintCommand {
                     starlark_files(&mut ctx, &self.paths, server_ctx, &cell_resolver, &**io)
                         .await?;
                 let mut cache = Cache::new(&ctx);
+                let disabled: Arc<HashSet<String>> =
+                    Arc::new(self.disable.iter().cloned().collect());
 
+                let mut jobs = Vec::with_capacity(files.len());
                 for file in &files {
-                    let lints = lint_file(&file.borrow(), cell_resolver, &**io, &mut cache).await?;
+                    let path = file.borrow();
+                    let globals = cache.get_names(&path).await?;
+                    let config = cache.get_config(path.cell()).await?;
+                    jobs.push(lint_file(
+                        path,
+                        cell_resolver,
+                        &**io,
+                        globals,
+                        config,
+                        disabled.dupe(),
+                    ));
+                }
+                let parallelism = std::thread::available_parallelism().map_or(1, |n| n.get());
+                let mut results = futures::stream::iter(jobs).buffered(parallelism);
+
+                while let Some(lints) = results.try_next().await? {
                     lint_count += lints.len();
                     for lint in lints {
-                        writeln!(stdout, "{}", lint)?;
+                        writeln!(stdout, "{}", format_lint(&lint, self.output_format))?;
                     }
                 }
                 if lint_count > 0 {
@@ -0,0 +1,195 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Buck2 specific lints, run in addition to the generic Starlark linter.
+
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+
+use starlark::errors::EvalSeverity;
+use starlark::errors::Lint;
+use starlark::syntax::AstModule;
+use starlark_syntax::codemap::Span;
+use starlark_syntax::syntax::ast::AstAssignIdent;
+use starlark_syntax::syntax::ast::AstExpr;
+use starlark_syntax::syntax::ast::AstStmt;
+use starlark_syntax::syntax::ast::ExprP;
+use starlark_syntax::syntax::ast::ParameterP;
+use starlark_syntax::syntax::ast::StmtP;
+use starlark_syntax::syntax::module::AstModuleFields;
+
+pub(crate) const DEPRECATED_SYMBOL: &str = "deprecated-symbol";
+pub(crate) const SHADOWED_BUILTIN: &str = "shadowed-builtin";
+
+fn lint(
+    ast: &AstModule,
+    span: Span,
+    short_name: &str,
+    severity: EvalSeverity,
+    problem: String,
+) -> Lint {
+    let location = ast.file_span(span);
+    Lint {
+        original: location.source_span().to_owned(),
+        location,
+        short_name: short_name.to_owned(),
+        severity,
+        problem,
+    }
+}
+
+/// Uses of symbols listed in `deprecated`, which maps either a plain name (`old_rule`) or an
+/// attribute of a global (`native.old_rule`) to a message explaining what to use instead.
+pub(crate) fn deprecated_symbols(
+    ast: &AstModule,
+    deprecated: &BTreeMap<String, String>,
+) -> Vec<Lint> {
+    fn go(x: &AstExpr, res: &mut Vec<(Span, String)>) {
+        match &x.node {
+            ExprP::Identifier(ident) => res.push((x.span, ident.node.ident.clone())),
+            ExprP::Dot(obj, attr) => {
+                if let ExprP::Identifier(ident) = &obj.node {
+                    res.push((x.span, format!("{}.{}", ident.node.ident, attr.node)));
+                }
+            }
+            _ => {}
+        }
+        x.visit_expr(|x| go(x, res));
+    }
+
+    fn loads(x: &AstStmt, res: &mut Vec<(Span, String)>) {
+        if let StmtP::Load(load) = &x.node {
+            for arg in &load.args {
+                res.push((arg.their.span, arg.their.node.clone()));
+            }
+        }
+        x.visit_stmt(|x| loads(x, res));
+    }
+
+    if deprecated.is_empty() {
+        return Vec::new();
+    }
+
+    let mut used = Vec::new();
+    ast.statement().visit_expr(|x| go(x, &mut used));
+    loads(ast.statement(), &mut used);
+
+    used.into_iter()
+        .filter_map(|(span, name)| {
+            let message = deprecated.get(&name)?;
+            Some(lint(
+                ast,
+                span,
+                DEPRECATED_SYMBOL,
+                EvalSeverity::Warning,
+                format!("`{}` is deprecated: {}", name, message),
+            ))
+        })
+        .collect()
+}
+
+/// Bindings (assignments, functions, parameters, loop variables and loads) which hide a global
+/// symbol, such as `glob = ...` or `def select(...)`.
+pub(crate) fn shadowed_builtins(ast: &AstModule, globals: &HashSet<String>) -> Vec<Lint> {
+    fn go<'a>(x: &'a AstStmt, res: &mut Vec<&'a AstAssignIdent>) {
+        match &x.node {
+            StmtP::Assign(assign) => assign.lhs.visit_lvalue(|x| res.push(x)),
+            StmtP::For(f) => f.var.visit_lvalue(|x| res.push(x)),
+            StmtP::Def(def) => {
+                res.push(&def.name);
+                for param in &def.params {
+                    match &param.node {
+                        ParameterP::Normal(x, _)
+                        | ParameterP::WithDefaultValue(x, _, _)
+                        | ParameterP::Args(x, _)
+                        | ParameterP::KwArgs(x, _) => res.push(x),
+                        ParameterP::NoArgs => {}
+                    }
+                }
+            }
+            StmtP::Load(load) => res.extend(load.args.iter().map(|x| &x.local)),
+            _ => {}
+        }
+        x.visit_stmt(|x| go(x, res));
+    }
+
+    let mut bound = Vec::new();
+    go(ast.statement(), &mut bound);
+    bound
+        .into_iter()
+        .filter(|x| globals.contains(&x.node.ident))
+        .map(|x| {
+            lint(
+                ast,
+                x.span,
+                SHADOWED_BUILTIN,
+                EvalSeverity::Advice,
+                format!(
+                    "`{}` shadows a global symbol of the same name",
+                    x.node.ident
+                ),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use starlark::syntax::Dialect;
+
+    use super::*;
+
+    fn module(x: &str) -> AstModule {
+        AstModule::parse("BUCK", x.to_owned(), &Dialect::Extended).unwrap()
+    }
+
+    #[test]
+    fn test_deprecated_symbols() {
+        let m = module(
+            r#"
+load(":defs.bzl", "old_rule")
+old_rule(name = "a")
+native.old_library(name = "b")
+new_rule(name = "c")
+"#,
+        );
+        let deprecated = BTreeMap::from([
+            ("old_rule".to_owned(), "use `new_rule`".to_owned()),
+            (
+                "native.old_library".to_owned(),
+                "use `new_library`".to_owned(),
+            ),
+        ]);
+        let lints = deprecated_symbols(&m, &deprecated);
+        let originals: Vec<_> = lints.iter().map(|x| x.original.as_str()).collect();
+        assert_eq!(
+            vec!["old_rule", "native.old_library", "\"old_rule\""],
+            originals
+        );
+    }
+
+    #[test]
+    fn test_shadowed_builtins() {
+        let m = module(
+            r#"
+glob = 1
+def select(x, *args, **kwargs):
+    for read_config in x:
+        pass
+"#,
+        );
+        let globals: HashSet<String> = ["glob", "select", "read_config", "kwargs", "len"]
+            .into_iter()
+            .map(|x| x.to_owned())
+            .collect();
+        let lints = shadowed_builtins(&m, &globals);
+        let originals: Vec<_> = lints.iter().map(|x| x.original.as_str()).collect();
+        assert_eq!(vec!["glob", "select", "kwargs", "read_config"], originals);
+    }
+}
@@ -0,0 +1,137 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::io::Write;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use buck2_cli_proto::ClientContext;
+use buck2_client_ctx::path_arg::PathArg;
+use buck2_common::dice::cells::HasCellResolver;
+use buck2_common::dice::data::HasIoProvider;
+use buck2_core::fs::fs_util;
+use buck2_interpreter::paths::path::OwnedStarlarkPath;
+use buck2_server_ctx::ctx::ServerCommandContextTrait;
+use buck2_server_ctx::ctx::ServerCommandDiceContext;
+use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
+
+use crate::codemod::apply_codemod;
+use crate::codemod::CodemodSpec;
+use crate::util::paths::starlark_files;
+use crate::StarlarkCommandCommonOptions;
+use crate::StarlarkOpaqueSubcommand;
+
+#[derive(Debug, buck2_error::Error)]
+#[buck2(input)]
+enum MigrateError {
+    #[error("{0} file(s) need to be migrated")]
+    NeedsMigration(usize),
+}
+
+#[derive(Debug, clap::Parser, serde::Serialize, serde::Deserialize)]
+#[clap(
+    name = "starlark-migrate",
+    about = "Apply a codemod to build files, renaming rules and attributes and rewriting labels.",
+    long_about = "Apply a codemod to build files, renaming rules and attributes and rewriting labels.\n\n\
+    The transformations are read from a JSON spec with the optional keys `rename_rules` \
+    (`{\"old\": \"new\"}`), `rename_attrs` (`{\"rule\": {\"old\": \"new\"}}`, with `*` matching \
+    every rule) and `rewrite_labels` (`{\"//old/prefix\": \"//new/prefix\"}`). Only the rewritten \
+    tokens change, comments and formatting are preserved."
+)]
+pub struct StarlarkMigrateCommand {
+    #[clap(flatten)]
+    common_opts: StarlarkCommandCommonOptions,
+
+    /// JSON file describing the transformations to apply.
+    #[clap(long, value_name = "PATH")]
+    spec: PathArg,
+
+    /// Do not write any files, fail if any file would be changed.
+    #[clap(long)]
+    check: bool,
+
+    /// Build files, or directories to search for build files.
+    #[clap(value_name = "PATH", required = true)]
+    paths: Vec<PathArg>,
+}
+
+#[async_trait]
+impl StarlarkOpaqueSubcommand for StarlarkMigrateCommand {
+    async fn server_execute(
+        &self,
+        server_ctx: &dyn ServerCommandContextTrait,
+        mut stdout: PartialResultDispatcher<buck2_cli_proto::StdoutBytes>,
+        _client_ctx: ClientContext,
+    ) -> anyhow::Result<()> {
+        let spec_path = self.spec.resolve(server_ctx.working_dir_abs());
+        let spec = fs_util::read_to_string(&spec_path)
+            .with_context(|| format!("Reading codemod spec `{}`", spec_path.display()))?;
+        let spec = &CodemodSpec::parse(&spec)?;
+
+        server_ctx
+            .with_dice_ctx(|server_ctx, mut ctx| async move {
+                let cell_resolver = &ctx.get_cell_resolver().await?;
+                let io = &ctx.global_data().get_io_provider();
+
+                let mut stdout = stdout.as_writer();
+                let files =
+                    starlark_files(&mut ctx, &self.paths, server_ctx, cell_resolver, &**io).await?;
+
+                let mut changed = 0;
+                let mut build_files = 0;
+                for file in &files {
+                    // Only build files are migrated: rules and labels in `.bzl` files are
+                    // frequently computed, so mechanical rewrites there are not safe.
+                    if !matches!(file, OwnedStarlarkPath::BuildFile(_)) {
+                        continue;
+                    }
+                    build_files += 1;
+                    let path = file.borrow();
+                    let proj_path = cell_resolver.resolve_path(path.path().as_ref().as_ref())?;
+                    let content = io
+                        .read_file_if_exists(proj_path.clone())
+                        .await?
+                        .with_context(|| format!("File not found: `{}`", proj_path))?;
+                    let dialect = path.file_type().dialect(false);
+                    let Some(new_content) =
+                        apply_codemod(spec, proj_path.as_str(), &content, &dialect)?
+                    else {
+                        continue;
+                    };
+                    changed += 1;
+                    writeln!(stdout, "{}", proj_path)?;
+                    if !self.check {
+                        let abs_path = server_ctx.project_root().resolve(&proj_path);
+                        fs_util::write(abs_path, new_content)?;
+                    }
+                }
+
+                if self.check && changed > 0 {
+                    return Err(MigrateError::NeedsMigration(changed).into());
+                }
+                writeln!(
+                    server_ctx.stderr()?,
+                    "{} {} of {} build files",
+                    if self.check {
+                        "Would migrate"
+                    } else {
+                        "Migrated"
+                    },
+                    changed,
+                    build_files
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    fn common_opts(&self) -> &StarlarkCommandCommonOptions {
+        &self.common_opts
+    }
+}
@@ -0,0 +1,311 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use async_trait::async_trait;
+use buck2_cli_proto::ClientContext;
+use buck2_cli_proto::GenericRequest;
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::command_outcome::CommandOutcome;
+use buck2_client_ctx::common::ui::CommonConsoleOptions;
+use buck2_client_ctx::common::ui::ConsoleType;
+use buck2_client_ctx::common::CommonBuildConfigurationOptions;
+use buck2_client_ctx::common::CommonEventLogOptions;
+use buck2_client_ctx::common::CommonStarlarkOptions;
+use buck2_client_ctx::daemon::client::BuckdClientConnector;
+use buck2_client_ctx::daemon::client::StdoutPartialResultHandler;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_client_ctx::streaming::StreamingCommand;
+use buck2_common::dice::cells::HasCellResolver;
+use buck2_core::cells::build_file_cell::BuildFileCell;
+use buck2_core::pattern::parse_package::parse_package;
+use buck2_interpreter_for_build::interpreter::dice_calculation_delegate::HasCalculationDelegate;
+use buck2_server_ctx::ctx::ServerCommandContextTrait;
+use buck2_server_ctx::ctx::ServerCommandDiceContext;
+use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
+use once_cell::sync::Lazy;
+use starlark::syntax::AstModule;
+use starlark::syntax::Dialect;
+use tokio::io::AsyncBufReadExt;
+
+use crate::StarlarkCommandCommonOptions;
+use crate::StarlarkOpaqueCommand;
+use crate::StarlarkOpaqueSubcommand;
+
+/// The variable the value of an expression typed into the REPL is bound to.
+const VALUE_VAR: &str = "__repl_value";
+
+/// Start an interactive Starlark interpreter in the environment of a package.
+///
+/// Code typed at the prompt is evaluated as if it were the package's build file:
+/// the prelude, the cell's root import and the package's cell aliases are all available,
+/// and `load()` resolves relative to the package. The value of an expression is printed
+/// unless it is `None`. Statements such as assignments, `def` and `load()` are remembered
+/// for the rest of the session; expressions are evaluated once and are not remembered.
+#[derive(Debug, clap::Parser)]
+#[clap(name = "starlark-repl")]
+pub struct StarlarkReplCommand {
+    #[clap(flatten)]
+    common_opts: StarlarkCommandCommonOptions,
+
+    /// Package to evaluate code in, e.g. `//foo/bar` or `cell//foo`.
+    #[clap(long, default_value = "//")]
+    package: String,
+}
+
+/// Evaluate a single REPL input. This is sent to the daemon by `buck2 starlark repl`
+/// and is not meant to be invoked directly.
+#[derive(Debug, clap::Parser, serde::Serialize, serde::Deserialize)]
+#[clap(name = "starlark-repl-eval")]
+pub struct StarlarkReplEvalCommand {
+    #[clap(flatten)]
+    common_opts: StarlarkCommandCommonOptions,
+
+    #[clap(long)]
+    package: String,
+
+    #[clap(long)]
+    source: String,
+}
+
+#[async_trait]
+impl StarlarkOpaqueSubcommand for StarlarkReplEvalCommand {
+    async fn server_execute(
+        &self,
+        server_ctx: &dyn ServerCommandContextTrait,
+        _stdout: PartialResultDispatcher<buck2_cli_proto::StdoutBytes>,
+        _client_ctx: ClientContext,
+    ) -> anyhow::Result<()> {
+        server_ctx
+            .with_dice_ctx(|server_ctx, mut dice| async move {
+                let cell_resolver = dice.get_cell_resolver().await?;
+                let cell_alias_resolver =
+                    cell_resolver.get_cwd_cell_alias_resolver(server_ctx.working_dir())?;
+                let package = parse_package(&self.package, cell_alias_resolver)?;
+                let cell = package.cell_name();
+                dice.get_interpreter_calculator(cell, BuildFileCell::new(cell))
+                    .await?
+                    .eval_build_file_with_content(package, self.source.clone())
+                    .await?;
+                Ok(())
+            })
+            .await
+    }
+
+    fn common_opts(&self) -> &StarlarkCommandCommonOptions {
+        &self.common_opts
+    }
+}
+
+/// The statements entered so far in a REPL session.
+#[derive(Default)]
+struct Session {
+    statements: Vec<String>,
+}
+
+impl Session {
+    /// The source to evaluate for `input`, and whether `input` should be remembered
+    /// if it evaluates successfully.
+    fn source_for(&self, input: &str) -> (String, bool) {
+        let mut source = String::new();
+        for statement in &self.statements {
+            source.push_str(statement);
+            source.push('\n');
+        }
+        let binding = format!("{VALUE_VAR} = ({input}\n)\n");
+        if AstModule::parse("repl", binding.clone(), &Dialect::Extended).is_ok() {
+            source.push_str(&binding);
+            source.push_str(&format!(
+                "print(repr({VALUE_VAR})) if {VALUE_VAR} != None else None\n"
+            ));
+            (source, false)
+        } else {
+            source.push_str(input);
+            source.push('\n');
+            (source, true)
+        }
+    }
+}
+
+/// Whether `input` is a complete REPL entry, or more lines should be read.
+///
+/// An entry is incomplete while brackets are unbalanced, a string is unterminated, or
+/// it opens an indented block which has not been ended with an empty line.
+fn is_complete(input: &str) -> bool {
+    let mut depth = 0i32;
+    let mut quote: Option<&str> = None;
+    let mut rest = input;
+    while let Some(c) = rest.chars().next() {
+        match quote {
+            Some(q) => {
+                if rest.starts_with('\\') && rest.len() > 1 {
+                    rest = &rest[1..];
+                } else if rest.starts_with(q) {
+                    rest = &rest[q.len()..];
+                    quote = None;
+                    continue;
+                } else if q.len() == 1 && rest.starts_with('\n') {
+                    // Leave unterminated single-line strings for the parser to report.
+                    quote = None;
+                }
+            }
+            None => match c {
+                '#' => {
+                    rest = rest.find('\n').map_or("", |i| &rest[i..]);
+                    continue;
+                }
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => depth -= 1,
+                '"' | '\'' => {
+                    let q = if rest.starts_with("\"\"\"") {
+                        "\"\"\""
+                    } else if rest.starts_with("'''") {
+                        "'''"
+                    } else if c == '"' {
+                        "\""
+                    } else {
+                        "'"
+                    };
+                    rest = &rest[q.len()..];
+                    quote = Some(q);
+                    continue;
+                }
+                _ => {}
+            },
+        }
+        rest = &rest[rest.chars().next().map_or(0, char::len_utf8)..];
+    }
+    if quote.is_some() || depth > 0 {
+        return false;
+    }
+    let opens_block = input
+        .lines()
+        .next()
+        .is_some_and(|line| line.trim_end().ends_with(':'));
+    !opens_block || input.ends_with("\n\n")
+}
+
+#[async_trait]
+impl StreamingCommand for StarlarkReplCommand {
+    const COMMAND_NAME: &'static str = "starlark-repl";
+
+    async fn exec_impl(
+        self,
+        buckd: &mut BuckdClientConnector,
+        matches: &clap::ArgMatches,
+        ctx: &mut ClientCommandContext<'_>,
+    ) -> ExitResult {
+        let context = ctx.client_context(matches, &self)?;
+        let mut session = Session::default();
+        let mut lines = tokio::io::BufReader::new(ctx.stdin()).lines();
+
+        loop {
+            let mut input = String::new();
+            loop {
+                buck2_client_ctx::eprint!("{}", if input.is_empty() { ">>> " } else { "... " })?;
+                let Some(line) = lines.next_line().await? else {
+                    return ExitResult::success();
+                };
+                input.push_str(&line);
+                input.push('\n');
+                if is_complete(&input) {
+                    break;
+                }
+            }
+            let input = input.trim_end();
+            if input.trim().is_empty() {
+                continue;
+            }
+
+            let (source, remember) = session.source_for(input);
+            let command = StarlarkOpaqueCommand::ReplEval(StarlarkReplEvalCommand {
+                common_opts: StarlarkCommandCommonOptions::default(),
+                package: self.package.clone(),
+                source,
+            });
+            let outcome = buckd
+                .with_flushing()
+                .starlark(
+                    GenericRequest {
+                        context: Some(context.clone()),
+                        serialized_opts: serde_json::to_string(&command)?,
+                    },
+                    None,
+                    &mut StdoutPartialResultHandler,
+                )
+                .await?;
+            // On failure the error has already been reported by the console.
+            if let CommandOutcome::Success(_) = outcome {
+                if remember {
+                    session.statements.push(input.to_owned());
+                }
+            }
+        }
+    }
+
+    fn console_opts(&self) -> &CommonConsoleOptions {
+        // The REPL owns the terminal, so only the simple console can be used.
+        static SIMPLE_CONSOLE: Lazy<CommonConsoleOptions> = Lazy::new(|| CommonConsoleOptions {
+            console_type: ConsoleType::Simple,
+            ui: vec![],
+            no_interactive_console: true,
+        });
+        &SIMPLE_CONSOLE
+    }
+
+    fn event_log_opts(&self) -> &CommonEventLogOptions {
+        &self.common_opts.event_log_opts
+    }
+
+    fn build_config_opts(&self) -> &CommonBuildConfigurationOptions {
+        &self.common_opts.config_opts
+    }
+
+    fn starlark_opts(&self) -> &CommonStarlarkOptions {
+        &self.common_opts.starlark_opts
+    }
+
+    fn should_expect_spans(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_complete() {
+        assert!(is_complete("1 + 2\n"));
+        assert!(is_complete("x = [1, 2]  # ) comment\n"));
+        assert!(!is_complete("x = [\n"));
+        assert!(!is_complete("x = \"\"\"(\n"));
+        assert!(is_complete("x = \"(\"\n"));
+        assert!(!is_complete("def f():\n  return 1\n"));
+        assert!(is_complete("def f():\n  return 1\n\n"));
+    }
+
+    #[test]
+    fn test_source_for() {
+        let mut session = Session::default();
+        let (source, remember) = session.source_for("x = 1");
+        assert!(remember);
+        assert_eq!("x = 1\n", source);
+        session.statements.push("x = 1".to_owned());
+
+        let (source, remember) = session.source_for("x + 1");
+        assert!(!remember);
+        assert_eq!(
+            "x = 1\n__repl_value = (x + 1\n)\nprint(repr(__repl_value)) if __repl_value != None else None\n",
+            source
+        );
+
+        let (_, remember) = session.source_for("load(\"//:defs.bzl\", \"foo\")");
+        assert!(remember);
+    }
+}
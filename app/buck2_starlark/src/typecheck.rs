@@ -45,6 +45,8 @@ pub struct StarlarkTypecheckCommand {
     #[clap(flatten)]
     common_opts: StarlarkCommandCommonOptions,
 
+    /// Files or directories to typecheck, globs such as `foo/**/*.bzl`, or package
+    /// patterns such as `//foo/...`.
     #[clap(value_name = "PATH", required = true)]
     paths: Vec<PathArg>,
 }
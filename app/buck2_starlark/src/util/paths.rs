@@ -19,8 +19,10 @@ use buck2_core::build_file_path::BuildFilePath;
 use buck2_core::bzl::ImportPath;
 use buck2_core::cells::CellResolver;
 use buck2_core::fs::paths::file_name::FileName;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
 use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
 use buck2_core::package::PackageLabel;
+use buck2_core::pattern::parse_package::parse_package;
 use buck2_interpreter::paths::bxl::BxlFilePath;
 use buck2_interpreter::paths::package::PackageFilePath;
 use buck2_interpreter::paths::path::OwnedStarlarkPath;
@@ -34,6 +36,33 @@ enum StarlarkFilesError {
     FileNotFound(ProjectRelativePathBuf),
     #[error("Symlinks and other esoteric files are not supported, `{0}`")]
     UnsupportedFileType(ProjectRelativePathBuf),
+    #[error("Path is not valid UTF-8, `{0}`")]
+    NotUtf8(String),
+}
+
+/// Split a package pattern like `cell//foo/...` or `//foo:` into the package and
+/// whether it is recursive.
+fn split_package_pattern(pattern: &str) -> (&str, bool) {
+    match pattern.strip_suffix("...") {
+        Some(package) => match package.strip_suffix('/') {
+            Some(stripped) if !package.ends_with("//") => (stripped, true),
+            _ => (package, true),
+        },
+        None => (pattern.strip_suffix(':').unwrap_or(pattern), false),
+    }
+}
+
+fn is_glob(path: &str) -> bool {
+    path.contains(['*', '?', '['])
+}
+
+/// The longest leading part of a glob which contains no wildcards.
+fn glob_base(glob: &str) -> &str {
+    let wildcard = glob.find(['*', '?', '[']).unwrap_or(glob.len());
+    match glob[..wildcard].rfind('/') {
+        Some(i) => &glob[..i],
+        None => "",
+    }
 }
 
 #[async_recursion]
@@ -123,6 +152,10 @@ async fn starlark_file(
 }
 
 /// Find the paths to apply Starlark to (e.g. linter, typecheck)
+///
+/// Each path is either a file or directory (searched recursively), a glob such as
+/// `foo/**/*.bzl`, or a package pattern such as `cell//foo/...` (recursive) or `//foo:`
+/// (only the files directly in the package directory).
 pub(crate) async fn starlark_files(
     ctx: &mut DiceComputations<'_>,
     paths: &[PathArg],
@@ -133,10 +166,92 @@ pub(crate) async fn starlark_files(
     let mut files = Vec::new();
 
     for path in paths {
-        let path = path.resolve(context.working_dir_abs());
-        let cell_path = cell_resolver.get_cell_path_from_abs_path(&path, context.project_root())?;
-        let proj_path = cell_resolver.resolve_path(cell_path.as_ref())?;
-        starlark_file(ctx, proj_path, None, cell_resolver, io, &mut files).await?;
+        let arg = path
+            .path()
+            .to_str()
+            .ok_or_else(|| StarlarkFilesError::NotUtf8(path.display().to_string()))?;
+        if arg.contains("//") {
+            let (package, recursive) = split_package_pattern(arg);
+            let cell_alias_resolver =
+                cell_resolver.get_cwd_cell_alias_resolver(context.working_dir())?;
+            let package = parse_package(package, cell_alias_resolver)?;
+            let proj_path = cell_resolver.resolve_path(package.as_cell_path())?;
+            if recursive {
+                starlark_file(ctx, proj_path, None, cell_resolver, io, &mut files).await?;
+            } else {
+                for x in io.read_dir(proj_path.clone()).await? {
+                    if x.file_type == FileType::Directory {
+                        continue;
+                    }
+                    let Ok(file_name) = FileName::new(&x.file_name) else {
+                        continue;
+                    };
+                    let mut child_path = proj_path.clone();
+                    child_path.push(file_name);
+                    starlark_file(
+                        ctx,
+                        child_path,
+                        Some(x.file_type),
+                        cell_resolver,
+                        io,
+                        &mut files,
+                    )
+                    .await?;
+                }
+            }
+        } else if is_glob(arg) {
+            let glob = path.resolve(context.working_dir_abs());
+            let glob = context.project_root().relativize_any(&glob)?;
+            let matcher = globset::GlobBuilder::new(glob.as_str())
+                .literal_separator(true)
+                .build()?
+                .compile_matcher();
+            let base = ProjectRelativePath::new(glob_base(glob.as_str()))?.to_buf();
+            let mut found = Vec::new();
+            starlark_file(
+                ctx,
+                base,
+                Some(FileType::Directory),
+                cell_resolver,
+                io,
+                &mut found,
+            )
+            .await?;
+            for file in found {
+                let proj_path =
+                    cell_resolver.resolve_path(file.borrow().path().as_ref().as_ref())?;
+                if matcher.is_match(proj_path.as_str()) {
+                    files.push(file);
+                }
+            }
+        } else {
+            let path = path.resolve(context.working_dir_abs());
+            let cell_path =
+                cell_resolver.get_cell_path_from_abs_path(&path, context.project_root())?;
+            let proj_path = cell_resolver.resolve_path(cell_path.as_ref())?;
+            starlark_file(ctx, proj_path, None, cell_resolver, io, &mut files).await?;
+        }
     }
     Ok(files)
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::util::paths::glob_base;
+    use crate::util::paths::split_package_pattern;
+
+    #[test]
+    fn test_split_package_pattern() {
+        assert_eq!(("//foo", true), split_package_pattern("//foo/..."));
+        assert_eq!(("cell//", true), split_package_pattern("cell//..."));
+        assert_eq!(("//foo", false), split_package_pattern("//foo:"));
+        assert_eq!(("//foo", false), split_package_pattern("//foo"));
+    }
+
+    #[test]
+    fn test_glob_base() {
+        assert_eq!("foo/bar", glob_base("foo/bar/**/*.bzl"));
+        assert_eq!("foo", glob_base("foo/*/defs.bzl"));
+        assert_eq!("", glob_base("*.bzl"));
+    }
+}
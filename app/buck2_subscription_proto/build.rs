@@ -16,6 +16,7 @@ fn main() -> io::Result<()> {
         .setup_protoc()
         .type_attribute(".", "#[derive(::serde::Serialize, ::serde::Deserialize)]")
         .type_attribute(".", "#[derive(::allocative::Allocative)]")
+        .type_attribute(".", "#[derive(::schemars::JsonSchema)]")
         .type_attribute(
             "buck.subscription.SubscriptionRequest.request",
             "#[derive(::derive_more::From)]",
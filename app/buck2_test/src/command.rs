@@ -7,6 +7,7 @@
  * of this source tree.
  */
 
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::path::Path;
@@ -16,6 +17,7 @@ use std::time::Duration;
 
 use anyhow::Context;
 use async_trait::async_trait;
+use buck2_build_api::actions::artifact::get_artifact_fs::GetArtifactFs;
 use buck2_build_api::analysis::calculation::RuleAnalysisCalculation;
 use buck2_build_api::artifact_groups::calculation::ArtifactGroupCalculation;
 use buck2_build_api::artifact_groups::ArtifactGroup;
@@ -34,25 +36,33 @@ use buck2_common::liveliness_observer::LivelinessGuard;
 use buck2_common::liveliness_observer::LivelinessObserver;
 use buck2_common::liveliness_observer::LivelinessObserverExt;
 use buck2_common::liveliness_observer::TimeoutLivelinessObserver;
+use buck2_common::pattern::parse_from_cli::parse_pattern_operations_from_cli_args;
 use buck2_common::pattern::parse_from_cli::parse_patterns_from_cli_args;
+use buck2_common::pattern::resolve::PatternOperation;
 use buck2_common::pattern::resolve::ResolveTargetPatterns;
 use buck2_common::pattern::resolve::ResolvedPattern;
 use buck2_core::buck2_env;
 use buck2_core::cells::name::CellName;
 use buck2_core::cells::CellResolver;
 use buck2_core::configuration::compatibility::MaybeCompatible;
+use buck2_core::fs::buck_out_path::BuckOutTestPath;
 use buck2_core::fs::fs_util;
 use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+use buck2_core::fs::paths::forward_rel_path::ForwardRelativePathBuf;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
 use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
 use buck2_core::package::PackageLabel;
 use buck2_core::pattern::pattern::PackageSpec;
+use buck2_core::pattern::pattern::ParsedPattern;
 use buck2_core::pattern::pattern_type::ConfiguredProvidersPatternExtra;
 use buck2_core::pattern::pattern_type::ProvidersPatternExtra;
+use buck2_core::pattern::pattern_type::TargetPatternExtra;
 use buck2_core::provider::label::ConfiguredProvidersLabel;
 use buck2_core::provider::label::ProvidersLabel;
 use buck2_core::tag_result;
 use buck2_core::target::label::label::TargetLabel;
 use buck2_core::target::name::TargetName;
+use buck2_data::ToProtoMessage;
 use buck2_error::BuckErrorContext;
 use buck2_events::dispatch::console_message;
 use buck2_events::dispatch::with_dispatcher_async;
@@ -87,6 +97,8 @@ use gazebo::prelude::*;
 use indexmap::indexset;
 use indexmap::IndexSet;
 use itertools::Itertools;
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
 use serde::Serialize;
 
 use crate::downward_api::BuckTestDownwardApi;
@@ -99,20 +111,31 @@ use crate::orchestrator::BuckTestOrchestrator;
 use crate::orchestrator::ExecutorMessage;
 use crate::session::TestSession;
 use crate::session::TestSessionOptions;
+use crate::session::TestTargetOptions;
 use crate::translations::build_configured_target_handle;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[allow(dead_code)]
-pub(crate) struct TestReport {
+pub struct TestReport {
+    #[schemars(with = "String")]
     project_root: AbsNormPathBuf,
+    #[schemars(with = "HashMap<String, Vec<String>>")]
     outputs: HashMap<TargetLabel, Vec<ProjectRelativePathBuf>>,
 }
 
+impl TestReport {
+    /// JSON Schema of the test report.
+    pub fn json_schema() -> RootSchema {
+        schemars::schema_for!(TestReport)
+    }
+}
+
 struct TestOutcome {
     errors: Vec<buck2_data::ErrorReport>,
     executor_report: ExecutorReport,
     executor_stdout: String,
     executor_stderr: String,
+    listing: Vec<buck2_cli_proto::TestListing>,
 }
 
 impl TestOutcome {
@@ -129,6 +152,7 @@ struct ExecutorReport {
     exit_code: Option<i32>,
     statuses: TestStatuses,
     info_messages: Vec<String>,
+    discovered: BTreeMap<ConfiguredProvidersLabel, Vec<buck2_cli_proto::test_listing::Suite>>,
 }
 
 impl ExecutorReport {
@@ -143,6 +167,18 @@ impl ExecutorReport {
             ExecutorMessage::InfoMessage(message) => {
                 self.info_messages.push(message.clone());
             }
+            ExecutorMessage::TestsDiscovered {
+                target,
+                suite,
+                names,
+            } => {
+                self.discovered.entry(target.clone()).or_default().push(
+                    buck2_cli_proto::test_listing::Suite {
+                        name: suite.clone(),
+                        test_names: names.clone(),
+                    },
+                );
+            }
         }
     }
 }
@@ -319,11 +355,12 @@ async fn test(
         }
     };
 
-    let parsed_patterns =
-        parse_patterns_from_cli_args(&mut ctx, &request.target_patterns, cwd).await?;
-    server_ctx.log_target_pattern(&parsed_patterns);
+    let pattern_operations =
+        parse_pattern_operations_from_cli_args(&mut ctx, &request.target_patterns, cwd).await?;
+    server_ctx.log_target_pattern(&PatternOperation::included(&pattern_operations));
 
-    let resolved_pattern = ResolveTargetPatterns::resolve(&mut ctx, &parsed_patterns).await?;
+    let resolved_pattern =
+        ResolveTargetPatterns::resolve_operations(&mut ctx, &pattern_operations).await?;
 
     let launcher: Box<dyn ExecutorLauncher> = Box::new(OutOfProcessTestExecutor {
         executable: test_executor,
@@ -336,11 +373,23 @@ async fn test(
         .as_ref()
         .context("Missing `options`")?;
 
+    let target_options = parse_target_options(&mut ctx, &request.target_options, cwd).await?;
+
     let session = TestSession::new(TestSessionOptions {
         allow_re: options.allow_re,
         force_use_project_relative_paths: options.force_use_project_relative_paths,
         force_run_from_project_root: options.force_run_from_project_root,
-    });
+    })
+    .with_target_options(target_options);
+
+    let artifacts_dir = ctx
+        .get_artifact_fs()
+        .await?
+        .buck_out_path_resolver()
+        .resolve_test(&BuckOutTestPath::new(
+            session.artifacts_dir(),
+            ForwardRelativePathBuf::empty(),
+        ));
 
     let build_opts = request
         .build_opts
@@ -354,11 +403,18 @@ async fn test(
         .transpose()
         .context("Invalid `duration`")?;
 
+    let mut external_runner_args = Vec::new();
+    if request.list_only {
+        // Passed ahead of the user's args, which may end with a variadic flag.
+        external_runner_args.push("--list-only".to_owned());
+    }
+    external_runner_args.extend(request.test_executor_args.iter().cloned());
+
     let test_outcome = test_targets(
         ctx,
         resolved_pattern,
         global_cfg_options,
-        request.test_executor_args.clone(),
+        external_runner_args,
         Arc::new(TestLabelFiltering::new(
             request.included_labels.clone(),
             request.excluded_labels.clone(),
@@ -373,12 +429,19 @@ async fn test(
         MissingTargetBehavior::from_skip(build_opts.skip_missing_targets),
         timeout,
         request.ignore_tests_attribute,
+        request.list_only,
     )
     .await?;
 
     // TODO(bobyf) remap exit code for buck reserved exit code
     let exit_code = test_outcome.exit_code().context("No exit code available")?;
 
+    let artifacts_dir = if fs_util::try_exists(server_ctx.project_root().resolve(&artifacts_dir))? {
+        artifacts_dir.to_string()
+    } else {
+        String::new()
+    };
+
     let test_statuses = buck2_cli_proto::test_response::TestStatuses {
         passed: Some(
             test_outcome
@@ -431,9 +494,44 @@ async fn test(
         executor_stdout: test_outcome.executor_stdout,
         executor_stderr: test_outcome.executor_stderr,
         executor_info_messages: test_outcome.executor_report.info_messages,
+        listing: test_outcome.listing,
+        artifacts_dir,
     })
 }
 
+async fn parse_target_options(
+    ctx: &mut DiceComputations<'_>,
+    target_options: &[buck2_cli_proto::TestTargetOptions],
+    cwd: &ProjectRelativePath,
+) -> anyhow::Result<Vec<(ParsedPattern<TargetPatternExtra>, TestTargetOptions)>> {
+    let patterns = parse_patterns_from_cli_args::<TargetPatternExtra>(
+        ctx,
+        &target_options.map(|o| o.pattern.clone()),
+        cwd,
+    )
+    .await?;
+    patterns
+        .into_iter()
+        .zip(target_options)
+        .map(|(pattern, options)| {
+            let timeout = options
+                .timeout
+                .clone()
+                .map(Duration::try_from)
+                .transpose()
+                .with_context(|| format!("Invalid `timeout` for `{}`", options.pattern))?;
+            anyhow::Ok((
+                pattern,
+                TestTargetOptions {
+                    extra_args: options.extra_args.clone(),
+                    timeout,
+                    required_labels: options.required_labels.clone(),
+                },
+            ))
+        })
+        .collect()
+}
+
 async fn test_targets(
     ctx: DiceTransaction,
     pattern: ResolvedPattern<ConfiguredProvidersPatternExtra>,
@@ -448,8 +546,10 @@ async fn test_targets(
     missing_target_behavior: MissingTargetBehavior,
     timeout: Option<Duration>,
     ignore_tests_attribute: bool,
+    list_only: bool,
 ) -> anyhow::Result<TestOutcome> {
     let session = Arc::new(session);
+    let listed_session = session.dupe();
 
     let (mut liveliness_observer, _guard) = LivelinessGuard::create();
     let timeout_observer = timeout.map(|timeout| {
@@ -623,14 +723,40 @@ async fn test_targets(
         }
     }
 
+    let listing = if list_only {
+        test_listing(&listed_session, &executor_report)
+    } else {
+        Vec::new()
+    };
+
     Ok(TestOutcome {
         errors,
         executor_stdout: executor_output.stdout,
         executor_stderr: executor_output.stderr,
         executor_report,
+        listing,
     })
 }
 
+/// Every target that was sent to the test executor, along with the suites it reported for it.
+fn test_listing(
+    session: &TestSession,
+    executor_report: &ExecutorReport,
+) -> Vec<buck2_cli_proto::TestListing> {
+    session
+        .labels()
+        .into_iter()
+        .map(|label| buck2_cli_proto::TestListing {
+            suites: executor_report
+                .discovered
+                .get(&label)
+                .cloned()
+                .unwrap_or_default(),
+            target: label.to_string(),
+        })
+        .collect()
+}
+
 enum TestDriverTask {
     InterpretTarget {
         package: PackageLabel,
@@ -788,6 +914,14 @@ impl<'a, 'e> TestDriver<'a, 'e> {
                 MaybeCompatible::Incompatible(reason) => {
                     if skippable {
                         eprintln!("{}", reason.skipping_message(label.target()));
+                        state
+                            .ctx
+                            .per_transaction_data()
+                            .get_dispatcher()
+                            .instant_event(buck2_data::TestTargetSkipped {
+                                target_label: Some(label.target().as_proto()),
+                                reason: reason.to_string(),
+                            });
                         return Ok(vec![]);
                     } else {
                         return Err(reason.to_err());
@@ -863,7 +997,7 @@ fn spec_to_targets(
 ) -> anyhow::Result<SpecTargets> {
     let skippable = match spec {
         PackageSpec::Targets(..) => skip_incompatible_targets,
-        PackageSpec::All => true,
+        PackageSpec::All | PackageSpec::AllExcept(_) => true,
     };
 
     let (targets, missing) = res.apply_spec(spec);
@@ -906,10 +1040,15 @@ async fn test_target(
             if skip_run_based_on_labels(test_info, &label_filtering) {
                 return Ok(None);
             }
+            let target_options = session.target_options(target.target().unconfigured());
+            if !target_options.has_required_labels(&test_info.labels()) {
+                return Ok(None);
+            }
             run_tests(
                 test_executor,
                 target,
                 test_info,
+                target_options.extra_args,
                 session,
                 cell_resolver,
                 working_dir_cell,
@@ -981,6 +1120,7 @@ fn run_tests<'a, 'b>(
     test_executor: Arc<dyn TestExecutor + 'a>,
     providers_label: ConfiguredProvidersLabel,
     test_info: &'b dyn TestProvider,
+    extra_args: Vec<String>,
     session: &'b TestSession,
     cell_resolver: &'b CellResolver,
     working_dir_cell: CellName,
@@ -990,7 +1130,7 @@ fn run_tests<'a, 'b>(
 
     match maybe_handle {
         Ok(handle) => {
-            let fut = test_info.dispatch(handle, test_executor, working_dir_cell);
+            let fut = test_info.dispatch(handle, test_executor, working_dir_cell, extra_args);
 
             (async move {
                 fut.await
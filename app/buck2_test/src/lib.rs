@@ -20,6 +20,7 @@ pub(crate) mod local_resource_registry;
 pub(crate) mod local_resource_setup;
 pub mod orchestrator;
 pub(crate) mod remote_storage;
+pub(crate) mod resource_requirements;
 pub mod session;
 pub(crate) mod tcp;
 pub mod translations;
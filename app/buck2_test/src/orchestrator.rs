@@ -45,6 +45,8 @@ use buck2_core::execution_types::executor_config::LocalExecutorOptions;
 use buck2_core::execution_types::executor_config::PathSeparatorKind;
 use buck2_core::fs::artifact_path_resolver::ArtifactFs;
 use buck2_core::fs::buck_out_path::BuckOutTestPath;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
 use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
 use buck2_core::fs::paths::forward_rel_path::ForwardRelativePathBuf;
 use buck2_core::fs::project_rel_path::ProjectRelativePath;
@@ -62,6 +64,7 @@ use buck2_data::TestSessionInfo;
 use buck2_data::TestSuite;
 use buck2_data::ToProtoMessage;
 use buck2_error::AnyhowContextForError;
+use buck2_events::dispatch::console_message;
 use buck2_events::dispatch::EventDispatcher;
 use buck2_execute::artifact::fs::ExecutorFs;
 use buck2_execute::artifact_value::ArtifactValue;
@@ -135,6 +138,7 @@ use crate::local_resource_registry::LocalResourceRegistry;
 use crate::local_resource_setup::required_local_resources_setup_contexts;
 use crate::local_resource_setup::LocalResourceSetupContext;
 use crate::remote_storage;
+use crate::resource_requirements;
 use crate::session::TestSession;
 use crate::translations;
 
@@ -145,6 +149,11 @@ pub enum ExecutorMessage {
     TestResult(TestResult),
     ExitCode(i32),
     InfoMessage(String),
+    TestsDiscovered {
+        target: ConfiguredProvidersLabel,
+        suite: String,
+        names: Vec<String>,
+    },
 }
 
 pub struct BuckTestOrchestrator<'a> {
@@ -203,6 +212,53 @@ impl<'a> BuckTestOrchestrator<'a> {
         }
     }
 
+    /// Hard links or copies the artifacts of a test result into the artifacts directory of the
+    /// session, `buck-out/v2/test/<session>/artifacts/<target>/<test>/<name>`, and returns their
+    /// project relative paths. The outputs of a test are deleted when it runs again, so the
+    /// artifacts must not point to them. An artifact which cannot be collected is reported as a
+    /// warning and left out, without failing the test.
+    async fn collect_test_artifacts(&self, result: &TestResult) -> anyhow::Result<Vec<String>> {
+        if result.artifacts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let test_target = self.session.get(result.target)?;
+        let fs = self.dice.clone().get_artifact_fs().await?;
+        let test_dir = artifact_path_component(&test_target.target().unconfigured().to_string())
+            .join(artifact_path_component(&result.name));
+
+        let links = result
+            .artifacts
+            .iter()
+            .map(|artifact| {
+                let path = fs
+                    .buck_out_path_resolver()
+                    .resolve_test(&BuckOutTestPath::new(
+                        self.session.artifacts_dir(),
+                        test_dir.join(&artifact.name),
+                    ));
+                (path, artifact.local_path.clone())
+            })
+            .collect::<Vec<_>>();
+
+        self.dice
+            .get_blocking_executor()
+            .execute_io_inline(|| {
+                let mut collected = Vec::new();
+                for (path, local_path) in links {
+                    match collect_test_artifact(&local_path, &fs.fs().resolve(&path)) {
+                        Ok(()) => collected.push(path.to_string()),
+                        Err(e) => console_message(format!(
+                            "Warning: failed to collect test artifact `{}` of `{}`: {:#}",
+                            local_path, result.name, e
+                        )),
+                    }
+                }
+                Ok(collected)
+            })
+            .await
+    }
+
     async fn require_alive(&self) -> Result<(), Cancelled> {
         if !self.liveliness_observer.is_alive().await {
             return Err(Cancelled);
@@ -226,10 +282,20 @@ impl<'a> BuckTestOrchestrator<'a> {
         self.require_alive().await?;
 
         let test_target = self.session.get(test_target)?;
+        // A timeout given in the targets file overrides the one requested by the test executor.
+        let timeout = self
+            .session
+            .target_options(test_target.target().unconfigured())
+            .timeout
+            .unwrap_or(timeout);
 
         let fs = self.dice.clone().get_artifact_fs().await?;
 
         let test_info = self.get_test_info(&test_target).await?;
+        let host_sharing_requirements = resource_requirements::host_sharing_requirements(
+            host_sharing_requirements,
+            &test_info.resource_requirements(),
+        )?;
         let test_executor = self
             .get_test_executor(&test_target, &test_info, executor_override, &fs)
             .await?;
@@ -437,8 +503,10 @@ impl<'a> TestOrchestrator for BuckTestOrchestrator<'a> {
     }
 
     async fn report_test_result(&self, r: TestResult) -> anyhow::Result<()> {
+        let artifacts = self.collect_test_artifacts(&r).await?;
         let event = buck2_data::instant_event::Data::TestResult(translations::convert_test_result(
             r.clone(),
+            artifacts,
             &self.session,
         )?);
         self.events.instant_event(event);
@@ -458,11 +526,18 @@ impl<'a> TestOrchestrator for BuckTestOrchestrator<'a> {
 
         self.events.instant_event(TestDiscovery {
             data: Some(buck2_data::test_discovery::Data::Tests(TestSuite {
-                suite_name: suite,
-                test_names: names,
+                suite_name: suite.clone(),
+                test_names: names.clone(),
                 target_label: Some(test_target.target().as_proto()),
             })),
         });
+        self.results_channel
+            .unbounded_send(Ok(ExecutorMessage::TestsDiscovered {
+                target: test_target,
+                suite,
+                names,
+            }))
+            .map_err(|_| anyhow::Error::msg("Tests were discovered after end-of-tests"))?;
 
         Ok(())
     }
@@ -495,6 +570,12 @@ impl<'a> TestOrchestrator for BuckTestOrchestrator<'a> {
         required_local_resources: RequiredLocalResources,
     ) -> anyhow::Result<PrepareForLocalExecutionResult> {
         let test_target = self.session.get(test_target)?;
+        // A timeout given in the targets file overrides the one requested by the test executor.
+        let timeout = self
+            .session
+            .target_options(test_target.target().unconfigured())
+            .timeout
+            .unwrap_or(timeout);
 
         let fs = self.dice.clone().get_artifact_fs().await?;
 
@@ -809,6 +890,7 @@ impl<'b> BuckTestOrchestrator<'b> {
         fs: &ArtifactFs,
         test_target_node: &ConfiguredTargetNode,
         executor_override: Option<&CommandExecutorConfig>,
+        resource_requirements: &IndexMap<&str, u64>,
     ) -> anyhow::Result<CommandExecutor> {
         let executor_config = match executor_override {
             Some(o) => o,
@@ -820,7 +902,7 @@ impl<'b> BuckTestOrchestrator<'b> {
 
         let CommandExecutorResponse {
             executor,
-            platform,
+            mut platform,
             cache_checker: _,
             cache_uploader: _,
         } = self
@@ -828,6 +910,7 @@ impl<'b> BuckTestOrchestrator<'b> {
             .clone()
             .get_command_executor_from_dice(executor_config)
             .await?;
+        resource_requirements::apply_to_re_platform(&mut platform, resource_requirements);
         let executor = CommandExecutor::new(
             executor,
             // Caching is not enabled for tests yet. Use the NoOp
@@ -922,6 +1005,7 @@ impl<'b> BuckTestOrchestrator<'b> {
             fs,
             &node,
             resolved_executor_override.as_ref().map(|a| &***a),
+            &test_info.resource_requirements(),
         )
         .await
         .context("Error constructing CommandExecutor")
@@ -1466,6 +1550,54 @@ fn local_resource_setup_command_prepared_for_local_execution(
     }
 }
 
+fn collect_test_artifact(local_path: &AbsNormPath, path: &AbsNormPath) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs_util::create_dir_all(parent)?;
+    }
+    // A later attempt of the same test replaces the artifacts of the earlier one.
+    fs_util::remove_all(path)?;
+    hard_link_or_copy(local_path, path)
+}
+
+/// Hard links the file `src` to `dst`, or copies it when it cannot be linked (for example across
+/// file systems). Directories are recreated and their contents linked, symlinks are recreated.
+fn hard_link_or_copy(src: &AbsNormPath, dst: &AbsNormPath) -> anyhow::Result<()> {
+    let file_type = fs_util::symlink_metadata(src)?.file_type();
+    if file_type.is_dir() {
+        fs_util::create_dir_all(dst)?;
+        for entry in fs_util::read_dir(src)? {
+            let entry = entry?;
+            let file_name = entry.file_name();
+            let name = ForwardRelativePath::new(&file_name)?;
+            hard_link_or_copy(&src.join(name), &dst.join(name))?;
+        }
+    } else if file_type.is_symlink() {
+        fs_util::symlink(fs_util::read_link(src)?, dst)?;
+    } else if fs_util::hard_link(src, dst).is_err() {
+        fs_util::copy(src, dst)?;
+    }
+    Ok(())
+}
+
+/// Turns a target label or a test name into a single path component, replacing the characters
+/// which are not safe in file names.
+fn artifact_path_component(s: &str) -> ForwardRelativePathBuf {
+    let component: String = s
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    match component.as_str() {
+        "" | "." | ".." => ForwardRelativePathBuf::unchecked_new("_".to_owned()),
+        _ => ForwardRelativePathBuf::unchecked_new(component),
+    }
+}
+
 struct LossyEnvironment {
     inner: SortedVectorMap<String, String>,
 }
@@ -1646,6 +1778,7 @@ mod tests {
                     name: "First - test".to_owned(),
                     duration: Some(Duration::from_micros(1)),
                     details: "1".to_owned(),
+                    artifacts: Vec::new(),
                 })
                 .await?;
 
@@ -1657,6 +1790,7 @@ mod tests {
                     name: "Second - test".to_owned(),
                     duration: Some(Duration::from_micros(2)),
                     details: "2".to_owned(),
+                    artifacts: Vec::new(),
                 })
                 .await?;
 
@@ -1678,6 +1812,7 @@ mod tests {
                     name: "First - test".to_owned(),
                     duration: Some(Duration::from_micros(1)),
                     details: "1".to_owned(),
+                    artifacts: Vec::new(),
                 }),
                 ExecutorMessage::TestResult(TestResult {
                     target,
@@ -1687,6 +1822,7 @@ mod tests {
                     name: "Second - test".to_owned(),
                     duration: Some(Duration::from_micros(2)),
                     details: "2".to_owned(),
+                    artifacts: Vec::new(),
                 }),
                 ExecutorMessage::ExitCode(0),
             ]
@@ -1742,4 +1878,18 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_artifact_path_component() {
+        assert_eq!(
+            "cell__pkg_foo",
+            artifact_path_component("cell//pkg:foo").as_str()
+        );
+        assert_eq!(
+            "suite_-_test.case_1_",
+            artifact_path_component("suite - test.case[1]").as_str()
+        );
+        assert_eq!("_", artifact_path_component("..").as_str());
+        assert_eq!("_", artifact_path_component("").as_str());
+    }
 }
@@ -0,0 +1,193 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Honoring the `resource_requirements` declared on `ExternalRunnerTestInfo`.
+
+use buck2_util::system_stats::system_memory_stats;
+use host_sharing::HostSharingRequirements;
+use host_sharing::WeightClass;
+use host_sharing::WeightPercentage;
+use indexmap::IndexMap;
+use itertools::Itertools;
+use once_cell::sync::Lazy;
+use remote_execution as RE;
+
+/// The number of cores a test needs.
+const CPUS: &str = "cpus";
+/// The amount of memory a test needs, in megabytes.
+const MEMORY_MB: &str = "memory_mb";
+
+struct HostCapacity {
+    cpus: u64,
+    memory_mb: u64,
+}
+
+static HOST_CAPACITY: Lazy<HostCapacity> = Lazy::new(|| HostCapacity {
+    cpus: std::thread::available_parallelism().map_or(1, |n| n.get() as u64),
+    memory_mb: system_memory_stats() / (1024 * 1024),
+});
+
+/// Adjust the host sharing requirements requested by the test executor so that a test running
+/// locally gets its share of the host's CPUs and memory, and doesn't share its devices.
+pub(crate) fn host_sharing_requirements(
+    requested: HostSharingRequirements,
+    resource_requirements: &IndexMap<&str, u64>,
+) -> anyhow::Result<HostSharingRequirements> {
+    host_sharing_requirements_for(requested, resource_requirements, &HOST_CAPACITY)
+}
+
+fn host_sharing_requirements_for(
+    requested: HostSharingRequirements,
+    resource_requirements: &IndexMap<&str, u64>,
+    capacity: &HostCapacity,
+) -> anyhow::Result<HostSharingRequirements> {
+    let weight = host_share(resource_requirements, capacity)?;
+    let devices = resource_requirements
+        .iter()
+        .filter(|(key, amount)| **key != CPUS && **key != MEMORY_MB && **amount > 0)
+        .map(|(key, _)| *key)
+        .sorted()
+        .join(",");
+
+    Ok(match requested {
+        HostSharingRequirements::ExclusiveAccess => HostSharingRequirements::ExclusiveAccess,
+        HostSharingRequirements::OnePerToken(token, requested_weight) => {
+            HostSharingRequirements::OnePerToken(token, weight.unwrap_or(requested_weight))
+        }
+        HostSharingRequirements::Shared(requested_weight) => {
+            let weight = weight.unwrap_or(requested_weight);
+            if devices.is_empty() {
+                HostSharingRequirements::Shared(weight)
+            } else {
+                // Tests needing the same devices never run concurrently.
+                HostSharingRequirements::OnePerToken(format!("devices:{}", devices), weight)
+            }
+        }
+    })
+}
+
+/// The largest share of the host's CPUs or memory that the test needs, if it declared either.
+fn host_share(
+    resource_requirements: &IndexMap<&str, u64>,
+    capacity: &HostCapacity,
+) -> anyhow::Result<Option<WeightClass>> {
+    let percentage = [(CPUS, capacity.cpus), (MEMORY_MB, capacity.memory_mb)]
+        .into_iter()
+        .filter_map(|(key, available)| {
+            let required = *resource_requirements.get(key)?;
+            Some(required.saturating_mul(100).div_ceil(available.max(1)))
+        })
+        .max();
+
+    percentage
+        .map(|percentage| {
+            Ok(WeightClass::Percentage(WeightPercentage::try_new(
+                percentage.clamp(1, 100),
+            )?))
+        })
+        .transpose()
+}
+
+/// Add the resource requirements to the Remote Execution platform properties, replacing any
+/// property with the same name.
+pub(crate) fn apply_to_re_platform(
+    platform: &mut RE::Platform,
+    resource_requirements: &IndexMap<&str, u64>,
+) {
+    for (name, amount) in resource_requirements {
+        platform
+            .properties
+            .retain(|property| property.name != *name);
+        platform.properties.push(RE::Property {
+            name: (*name).to_owned(),
+            value: amount.to_string(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::indexmap;
+
+    use super::*;
+
+    const CAPACITY: HostCapacity = HostCapacity {
+        cpus: 8,
+        memory_mb: 16384,
+    };
+
+    fn percentage(value: u8) -> WeightClass {
+        WeightClass::Percentage(WeightPercentage::try_new(value).unwrap())
+    }
+
+    #[test]
+    fn test_no_requirements() -> anyhow::Result<()> {
+        let requested = HostSharingRequirements::Shared(WeightClass::Permits(2));
+        assert_eq!(
+            host_sharing_requirements_for(requested.clone(), &IndexMap::new(), &CAPACITY)?,
+            requested
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_largest_share_wins() -> anyhow::Result<()> {
+        assert_eq!(
+            host_sharing_requirements_for(
+                HostSharingRequirements::default(),
+                &indexmap! { "cpus" => 2, "memory_mb" => 8192 },
+                &CAPACITY
+            )?,
+            HostSharingRequirements::Shared(percentage(50))
+        );
+        assert_eq!(
+            host_sharing_requirements_for(
+                HostSharingRequirements::default(),
+                &indexmap! { "cpus" => 64 },
+                &CAPACITY
+            )?,
+            HostSharingRequirements::Shared(percentage(100))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_devices() -> anyhow::Result<()> {
+        assert_eq!(
+            host_sharing_requirements_for(
+                HostSharingRequirements::default(),
+                &indexmap! { "gpus" => 1, "cpus" => 1 },
+                &CAPACITY
+            )?,
+            HostSharingRequirements::OnePerToken("devices:gpus".to_owned(), percentage(13))
+        );
+        assert_eq!(
+            host_sharing_requirements_for(
+                HostSharingRequirements::ExclusiveAccess,
+                &indexmap! { "gpus" => 1 },
+                &CAPACITY
+            )?,
+            HostSharingRequirements::ExclusiveAccess
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_re_platform() {
+        let mut platform = RE::Platform {
+            properties: vec![RE::Property {
+                name: "cpus".to_owned(),
+                value: "1".to_owned(),
+            }],
+        };
+        apply_to_re_platform(&mut platform, &indexmap! { "cpus" => 4 });
+        assert_eq!(platform.properties.len(), 1);
+        assert_eq!(platform.properties[0].value, "4");
+    }
+}
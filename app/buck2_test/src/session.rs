@@ -9,11 +9,15 @@
 
 use std::sync::atomic::AtomicU64;
 use std::sync::atomic::Ordering;
+use std::time::Duration;
 
 use anyhow::Context as _;
 use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
 use buck2_core::fs::paths::forward_rel_path::ForwardRelativePathBuf;
+use buck2_core::pattern::pattern::ParsedPattern;
+use buck2_core::pattern::pattern_type::TargetPatternExtra;
 use buck2_core::provider::label::ConfiguredProvidersLabel;
+use buck2_core::target::label::label::TargetLabel;
 use buck2_test_api::data::ConfiguredTargetHandle;
 use chrono::Local;
 use dashmap::DashMap;
@@ -27,6 +31,34 @@ pub struct TestSessionOptions {
     pub force_run_from_project_root: bool,
 }
 
+/// Options for testing particular targets, e.g. from `buck2 test --targets-file`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TestTargetOptions {
+    /// Appended to the test command.
+    pub extra_args: Vec<String>,
+    /// Overrides the timeout requested by the test executor.
+    pub timeout: Option<Duration>,
+    /// The target is only tested if it has all of these labels.
+    pub required_labels: Vec<String>,
+}
+
+impl TestTargetOptions {
+    fn merge(&mut self, other: &TestTargetOptions) {
+        self.extra_args.extend(other.extra_args.iter().cloned());
+        if other.timeout.is_some() {
+            self.timeout = other.timeout;
+        }
+        self.required_labels
+            .extend(other.required_labels.iter().cloned());
+    }
+
+    pub fn has_required_labels(&self, labels: &[&str]) -> bool {
+        self.required_labels
+            .iter()
+            .all(|required| labels.contains(&required.as_str()))
+    }
+}
+
 /// The state of a buck2 test command.
 pub struct TestSession {
     /// The next ConfiguredTargetHandle that will be assigned.
@@ -41,6 +73,8 @@ pub struct TestSession {
     /// Options overriding the behavior of tests executed in this session. This is primarily
     /// intended for unstable or debugging features.
     options: TestSessionOptions,
+    /// Options for the targets matching each pattern, in the order they were given.
+    target_options: Vec<(ParsedPattern<TargetPatternExtra>, TestTargetOptions)>,
 }
 
 impl TestSession {
@@ -57,7 +91,27 @@ impl TestSession {
             labels: DashMap::new(),
             prefix,
             options,
+            target_options: Vec::new(),
+        }
+    }
+
+    pub fn with_target_options(
+        mut self,
+        target_options: Vec<(ParsedPattern<TargetPatternExtra>, TestTargetOptions)>,
+    ) -> Self {
+        self.target_options = target_options;
+        self
+    }
+
+    /// The options for `target`, combined from all the patterns matching it.
+    pub fn target_options(&self, target: &TargetLabel) -> TestTargetOptions {
+        let mut options = TestTargetOptions::default();
+        for (pattern, pattern_options) in &self.target_options {
+            if pattern.matches(target) {
+                options.merge(pattern_options);
+            }
         }
+        options
     }
 
     pub fn options(&self) -> TestSessionOptions {
@@ -68,6 +122,13 @@ impl TestSession {
         self.prefix.as_ref()
     }
 
+    /// The directory, relative to the test outputs, where the artifacts of the tests of this
+    /// session are collected.
+    pub fn artifacts_dir(&self) -> ForwardRelativePathBuf {
+        self.prefix
+            .join(ForwardRelativePath::unchecked_new("artifacts"))
+    }
+
     /// Insert a new provider and retrieve the matching handle.
     pub fn register(&self, label: ConfiguredProvidersLabel) -> ConfiguredTargetHandle {
         let id = self.next_id.fetch_add(1, Ordering::Relaxed).into();
@@ -76,6 +137,17 @@ impl TestSession {
         id
     }
 
+    /// All the providers registered so far, sorted.
+    pub fn labels(&self) -> Vec<ConfiguredProvidersLabel> {
+        let mut labels = self
+            .labels
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect::<Vec<_>>();
+        labels.sort();
+        labels
+    }
+
     /// Retrieve the provider for a given handle.
     pub fn get(&self, id: ConfiguredTargetHandle) -> anyhow::Result<ConfiguredProvidersLabel> {
         let res = self
@@ -47,8 +47,10 @@ pub(crate) fn build_configured_target_handle(
     })
 }
 
+/// `artifacts` are the project relative paths where the artifacts of the test were collected.
 pub(crate) fn convert_test_result(
     test_result: buck2_test_api::data::TestResult,
+    artifacts: Vec<String>,
     session: &TestSession,
 ) -> anyhow::Result<buck2_data::TestResult> {
     let buck2_test_api::data::TestResult {
@@ -58,6 +60,7 @@ pub(crate) fn convert_test_result(
         duration,
         details,
         target: test_target,
+        artifacts: _,
     } = test_result;
 
     let test_target = session.get(test_target)?;
@@ -69,6 +72,7 @@ pub(crate) fn convert_test_result(
         duration: duration.and_then(|d| d.try_into().ok()),
         details,
         target_label: Some(test_target.target().as_proto()),
+        artifacts,
     })
 }
 
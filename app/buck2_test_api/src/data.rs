@@ -96,6 +96,19 @@ pub struct TestResult {
     pub duration: Option<Duration>,
     // the output of the test execution (combining stdout and stderr)
     pub details: String,
+    // files produced by the test (logs, screenshots...) to collect for this result
+    pub artifacts: Vec<TestArtifact>,
+}
+
+/// A file or directory produced by a test, which Buck collects into the artifacts directory of the
+/// test session, and references from test reports.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct TestArtifact {
+    /// The path of the artifact within the directory of its test.
+    pub name: ForwardRelativePathBuf,
+    /// Where the test wrote the artifact: an output of an execution, as returned in
+    /// `Output::LocalPath`.
+    pub local_path: AbsNormPathBuf,
 }
 
 /// different possible test results
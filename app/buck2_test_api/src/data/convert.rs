@@ -43,6 +43,7 @@ use crate::data::OutputName;
 use crate::data::RemoteDir;
 use crate::data::RemoteFile;
 use crate::data::RemoteObject;
+use crate::data::TestArtifact;
 use crate::data::TestExecutable;
 use crate::data::TestResult;
 use crate::data::TestStatus;
@@ -256,6 +257,7 @@ impl TryFrom<buck2_test_proto::TestResult> for TestResult {
             msg,
             duration,
             details,
+            artifacts,
         } = s;
 
         let duration = duration
@@ -273,6 +275,11 @@ impl TryFrom<buck2_test_proto::TestResult> for TestResult {
             msg: msg.map(|m| m.msg),
             duration,
             details,
+            artifacts: artifacts
+                .into_iter()
+                .map(|a| a.try_into())
+                .collect::<Result<_, _>>()
+                .context("Invalid `artifacts`")?,
         })
     }
 }
@@ -290,6 +297,40 @@ impl TryInto<buck2_test_proto::TestResult> for TestResult {
             details: self.details,
             msg: self.msg.map(|msg| OptionalMsg { msg }),
             duration: self.duration.try_map(|d| d.try_into())?,
+            artifacts: self
+                .artifacts
+                .into_iter()
+                .map(|a| a.try_into())
+                .collect::<Result<_, _>>()
+                .context("Invalid `artifacts`")?,
+        })
+    }
+}
+
+impl TryFrom<buck2_test_proto::TestArtifact> for TestArtifact {
+    type Error = anyhow::Error;
+
+    fn try_from(s: buck2_test_proto::TestArtifact) -> Result<Self, Self::Error> {
+        let buck2_test_proto::TestArtifact { name, local_path } = s;
+
+        Ok(Self {
+            name: ForwardRelativePathBuf::try_from(name).context("Invalid `name`")?,
+            local_path: local_path.try_into().context("Invalid `local_path`")?,
+        })
+    }
+}
+
+impl TryInto<buck2_test_proto::TestArtifact> for TestArtifact {
+    type Error = anyhow::Error;
+
+    fn try_into(self) -> Result<buck2_test_proto::TestArtifact, Self::Error> {
+        Ok(buck2_test_proto::TestArtifact {
+            name: self.name.into_string(),
+            local_path: self
+                .local_path
+                .to_str()
+                .context("Invalid `local_path`")?
+                .to_owned(),
         })
     }
 }
@@ -1132,6 +1173,29 @@ mod tests {
         assert_roundtrips::<buck2_test_proto::ExecutionResult2, ExecutionResult2>(&result);
     }
 
+    #[test]
+    fn test_result_roundtrips() {
+        let local_path = if cfg!(not(windows)) {
+            "/some/path"
+        } else {
+            "c:/some/path"
+        };
+
+        let result = TestResult {
+            target: ConfiguredTargetHandle(42),
+            name: "name".to_owned(),
+            status: TestStatus::FAIL,
+            msg: Some("msg".to_owned()),
+            duration: Some(Duration::from_secs(1)),
+            details: "details".to_owned(),
+            artifacts: vec![TestArtifact {
+                name: ForwardRelativePathBuf::unchecked_new("logs/out.log".to_owned()),
+                local_path: String::from(local_path).try_into().expect("valid abs path"),
+            }],
+        };
+        assert_roundtrips::<buck2_test_proto::TestResult, TestResult>(&result);
+    }
+
     fn dummy_local_execution_command() -> LocalExecutionCommand {
         let cmd = vec![
             "my_cmd".to_owned(),
@@ -31,6 +31,10 @@ pub struct Config {
     /// Available as a workaround for when test features are available.
     #[clap(long, num_args=1.., allow_hyphen_values = true)]
     pub test_arg: Vec<String>,
+
+    /// Report the tests without running them.
+    #[clap(long)]
+    pub list_only: bool,
 }
 
 /// Uiltity that can be used to parse Env values from CLI arguments.
@@ -76,6 +76,16 @@ impl Buck2TestRunner {
                 );
                 let target_handle = spec.target.handle.to_owned();
 
+                if self.config.list_only {
+                    // Test binaries are opaque to this runner, so each target is reported as a
+                    // single suite without enumerating its test cases.
+                    self.orchestrator_client
+                        .report_tests_discovered(target_handle, spec.target.target, Vec::new())
+                        .await
+                        .expect("Test discovery reporting failed");
+                    return TestStatus::LISTING_SUCCESS;
+                }
+
                 let execution_response = self
                     .execute_test_from_spec(spec)
                     .await
@@ -102,7 +112,8 @@ impl Buck2TestRunner {
             .fold(
                 RunVerdict::Pass,
                 |mut run_verdict, test_status| async move {
-                    if test_status != TestStatus::PASS {
+                    if test_status != TestStatus::PASS && test_status != TestStatus::LISTING_SUCCESS
+                    {
                         run_verdict = RunVerdict::Fail;
                     }
                     run_verdict
@@ -217,6 +228,7 @@ fn get_test_result(
             "---- STDOUT ----\n{:?}\n---- STDERR ----\n{:?}\n",
             execution_result.stdout, execution_result.stderr
         ),
+        artifacts: Vec::new(),
     }
 }
 
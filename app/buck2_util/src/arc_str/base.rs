@@ -126,6 +126,17 @@ impl<P: ArcStrLenStrategy> ArcStrBase<P> {
         self.len() == 0
     }
 
+    /// Number of references to the string. Empty strings are statically allocated and
+    /// report zero.
+    #[inline]
+    pub(crate) fn strong_count(&self) -> u32 {
+        if self.is_empty() {
+            0
+        } else {
+            self.inner().refcount.load(atomic::Ordering::Acquire)
+        }
+    }
+
     #[inline]
     pub(crate) fn as_str(&self) -> &str {
         unsafe {
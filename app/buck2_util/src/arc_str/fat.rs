@@ -64,6 +64,12 @@ impl ArcStr {
     pub fn as_str(&self) -> &str {
         self.base.as_str()
     }
+
+    /// Number of `ArcStr` values pointing to this string, like `Arc::strong_count`.
+    #[inline]
+    pub fn strong_count(this: &ArcStr) -> usize {
+        this.base.strong_count() as usize
+    }
 }
 
 impl Deref for ArcStr {
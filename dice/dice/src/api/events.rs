@@ -7,6 +7,8 @@
  * of this source tree.
  */
 
+use std::time::Duration;
+
 use allocative::Allocative;
 
 #[derive(Allocative, PartialEq, Eq, Debug)]
@@ -32,4 +34,15 @@ pub enum DiceEvent {
 
 pub trait DiceEventListener: Allocative + Send + Sync + 'static {
     fn event(&self, ev: DiceEvent);
+
+    /// Whether to measure how long computes take and report it to `computed`. Measuring has a
+    /// cost, so listeners only ask for it when profiling.
+    fn records_compute_times(&self) -> bool {
+        false
+    }
+
+    /// Compute of a key finished after spending `self_time` running, which excludes the time
+    /// spent waiting for the dependencies requested during compute. Only called if
+    /// `records_compute_times`.
+    fn computed(&self, _key_type: &'static str, _self_time: Duration) {}
 }
@@ -7,9 +7,14 @@
  * of this source tree.
  */
 
+use std::future;
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
 
 use dupe::Dupe;
+use futures::pin_mut;
 
 use crate::api::events::DiceEvent;
 use crate::api::events::DiceEventListener;
@@ -66,4 +71,30 @@ impl DiceEventDispatcher {
         self.tracker
             .event(DiceEvent::ComputeFinished { key_type: desc })
     }
+
+    /// Run the compute of `k`, reporting its self time if the tracker records it.
+    pub(crate) async fn timed_compute<F: Future>(&self, k: DiceKey, compute: F) -> F::Output {
+        if !self.tracker.records_compute_times() {
+            return compute.await;
+        }
+        let (res, self_time) = with_self_time(compute).await;
+        self.tracker
+            .computed(self.dice.key_index.get(k).key_type_name(), self_time);
+        res
+    }
+}
+
+/// Run `fut`, also returning the time spent polling it. Dependencies are computed by other
+/// tasks, so unlike the wall time this excludes the time spent waiting for them.
+pub(crate) async fn with_self_time<F: Future>(fut: F) -> (F::Output, Duration) {
+    pin_mut!(fut);
+    let mut self_time = Duration::ZERO;
+    let res = future::poll_fn(|cx| {
+        let start = Instant::now();
+        let res = fut.as_mut().poll(cx);
+        self_time += start.elapsed();
+        res
+    })
+    .await;
+    (res, self_time)
 }
@@ -9,6 +9,7 @@
 
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::time::Duration;
 
 use allocative::Allocative;
 use async_trait::async_trait;
@@ -29,12 +30,21 @@ use crate::InjectedKey;
 #[derive(Default, Allocative)]
 struct Tracker {
     state: Mutex<Vec<DiceEvent>>,
+    computed: Mutex<Vec<&'static str>>,
 }
 
 impl DiceEventListener for Tracker {
     fn event(&self, event: DiceEvent) {
         self.state.lock().unwrap().push(event);
     }
+
+    fn records_compute_times(&self) -> bool {
+        true
+    }
+
+    fn computed(&self, key_type: &'static str, _self_time: Duration) {
+        self.computed.lock().unwrap().push(key_type);
+    }
 }
 
 #[derive(Clone, Dupe, Debug, Display, Eq, Hash, PartialEq, Allocative)]
@@ -122,6 +132,7 @@ async fn test_events_impl(builder: DiceDataBuilder) -> anyhow::Result<()> {
                 DiceEvent::Finished { key_type: "Stage1" },
             ]
         );
+        assert_eq!(&*tracker.computed.lock().unwrap(), &["Stage0", "Stage1"]);
     }
 
     {
@@ -154,6 +165,7 @@ async fn test_events_impl(builder: DiceDataBuilder) -> anyhow::Result<()> {
                 DiceEvent::Finished { key_type: "Stage1" },
             ]
         );
+        assert_eq!(&*tracker.computed.lock().unwrap(), &["Stage0"]);
     }
 
     Ok(())
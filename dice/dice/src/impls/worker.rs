@@ -275,7 +275,12 @@ impl DiceTaskWorker {
         // TODO(bobyf) these also make good locations where we want to perform instrumentation
         debug!(msg = "running evaluator");
 
-        self.eval.evaluate(self.k, task_state, cycles.clone()).await
+        self.event_dispatcher
+            .timed_compute(
+                self.k,
+                self.eval.evaluate(self.k, task_state, cycles.clone()),
+            )
+            .await
     }
 
     fn activation_info<'a>(
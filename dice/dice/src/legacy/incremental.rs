@@ -53,6 +53,7 @@ use crate::api::projection::DiceProjectionComputations;
 use crate::api::projection::ProjectionKey;
 use crate::api::user_data::UserComputationData;
 use crate::impls::core::graph::history::CellHistory;
+use crate::impls::events::with_self_time;
 use crate::introspection::graph::EngineForIntrospection;
 use crate::legacy::ctx::ComputationData;
 use crate::legacy::dice_futures::dice_future::DiceFuture;
@@ -584,15 +585,21 @@ where
         // TODO(bobyf) these also make good locations where we want to perform instrumentation
         debug!(msg = "running evaluator");
 
+        let eval =
+            self.versioned_cache
+                .storage_properties
+                .eval(k, transaction_ctx, cancellation, extra);
         let EvaluationResult {
             value,
             both_deps,
             extra,
-        } = self
-            .versioned_cache
-            .storage_properties
-            .eval(k, transaction_ctx, cancellation, extra)
-            .await;
+        } = if tracker.records_compute_times() {
+            let (res, self_time) = with_self_time(eval).await;
+            tracker.computed(desc, self_time);
+            res
+        } else {
+            eval.await
+        };
 
         let _guard = match cancellation.try_to_disable_cancellation() {
             Some(g) => g,
@@ -0,0 +1,32 @@
+# Copyright (c) Meta Platforms, Inc. and affiliates.
+#
+# This source code is licensed under both the MIT license found in the
+# LICENSE-MIT file in the root directory of this source tree and the Apache
+# License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+# of this source tree.
+
+def pkg_config_library(
+        name: str,
+        package: [str, None] = None,
+        static: bool = False,
+        **kwargs):
+    """
+    A `prebuilt_cxx_library` for a library installed on the host, with the
+    flags reported by `pkg-config` for `package` (defaults to `name`).
+
+    Fails if the package is not installed. The flags are queried when the
+    build file is evaluated, and queried again when the `.pc` file of the
+    package changes.
+    """
+    package = package or name
+    lib = pkg_config(package, static = static)
+    if lib == None:
+        fail("pkg-config package `{}` is not installed on the host".format(package))
+
+    native.prebuilt_cxx_library(
+        name = name,
+        header_only = True,
+        exported_preprocessor_flags = lib.cflags + kwargs.pop("exported_preprocessor_flags", []),
+        exported_linker_flags = lib.libs + kwargs.pop("exported_linker_flags", []),
+        **kwargs
+    )
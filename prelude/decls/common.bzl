@@ -185,8 +185,8 @@ def _fork_mode():
 
 def _test_rule_timeout_ms():
     return {
-        "test_rule_timeout_ms": attrs.option(attrs.int(), default = None, doc = """
-    If set specifies the maximum amount of time (in milliseconds) in which all of the tests in this
+        "test_rule_timeout_ms": attrs.option(attrs.duration(), default = None, doc = """
+    If set specifies the maximum amount of time (in milliseconds, or with a unit like `"10m"`) in which all of the tests in this
      rule should complete. This overrides the default `rule_timeout` if any has been
      specified in `.buckconfig`
     .
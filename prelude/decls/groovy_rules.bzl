@@ -183,8 +183,8 @@ groovy_test = prelude_rule(
             "std_err_log_level": attrs.option(attrs.one_of(attrs.enum(LogLevel), attrs.int()), default = None),
             "std_out_log_level": attrs.option(attrs.one_of(attrs.enum(LogLevel), attrs.int()), default = None),
             "target": attrs.option(attrs.string(), default = None),
-            "test_case_timeout_ms": attrs.option(attrs.int(), default = None),
-            "test_rule_timeout_ms": attrs.option(attrs.int(), default = None),
+            "test_case_timeout_ms": attrs.option(attrs.duration(), default = None),
+            "test_rule_timeout_ms": attrs.option(attrs.duration(), default = None),
             "test_type": attrs.option(attrs.enum(TestType), default = None),
             "use_cxx_libraries": attrs.option(attrs.bool(), default = None),
             "use_dependency_order_classpath": attrs.option(attrs.bool(), default = None),
@@ -771,7 +771,7 @@ apple_test = prelude_rule(
             "supports_merged_linking": attrs.option(attrs.bool(), default = None),
             "swift_compiler_flags": attrs.list(attrs.arg(), default = []),
             "swift_version": attrs.option(attrs.string(), default = None),
-            "test_rule_timeout_ms": attrs.option(attrs.int(), default = None),
+            "test_rule_timeout_ms": attrs.option(attrs.duration(), default = None),
             "thin_lto": attrs.bool(default = False),
             "try_skip_code_signing": attrs.option(attrs.bool(), default = None),
             "ui_test_target_app": attrs.option(attrs.dep(), default = None),
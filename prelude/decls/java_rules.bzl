@@ -424,7 +424,7 @@ java_test = prelude_rule(
             "source_abi_verification_mode": attrs.option(attrs.enum(SourceAbiVerificationMode), default = None),
             "source_only_abi_deps": attrs.list(attrs.dep(), default = []),
             "specs": attrs.option(attrs.arg(json = True), default = None),
-            "test_case_timeout_ms": attrs.option(attrs.int(), default = None),
+            "test_case_timeout_ms": attrs.option(attrs.duration(), default = None),
             "unbundled_resources_root": attrs.option(attrs.source(allow_directory = True), default = None),
             "use_dependency_order_classpath": attrs.option(attrs.bool(), default = None),
             "_wip_java_plugin_arguments": attrs.dict(attrs.label(), attrs.list(attrs.string()), default = {}),
@@ -191,7 +191,7 @@ sh_test = prelude_rule(
             "run_args": attrs.list(attrs.string(), default = []),
             "run_env": attrs.dict(key = attrs.string(), value = attrs.string(), sorted = False, default = {}),
             "run_test_separately": attrs.bool(default = False),
-            "test_rule_timeout_ms": attrs.option(attrs.int(), default = None),
+            "test_rule_timeout_ms": attrs.option(attrs.duration(), default = None),
         } | re_test_common.test_args()
     ),
 )
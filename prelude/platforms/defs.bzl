@@ -9,6 +9,8 @@ def _execution_platform_impl(ctx: AnalysisContext) -> list[Provider]:
     constraints = dict()
     constraints.update(ctx.attrs.cpu_configuration[ConfigurationInfo].constraints)
     constraints.update(ctx.attrs.os_configuration[ConfigurationInfo].constraints)
+    for extra in ctx.attrs.extra_configurations:
+        constraints.update(extra[ConfigurationInfo].constraints)
     cfg = ConfigurationInfo(constraints = constraints, values = {})
 
     name = ctx.label.raw_target()
@@ -33,6 +35,7 @@ execution_platform = rule(
     impl = _execution_platform_impl,
     attrs = {
         "cpu_configuration": attrs.dep(providers = [ConfigurationInfo]),
+        "extra_configurations": attrs.list(attrs.dep(providers = [ConfigurationInfo]), default = []),
         "os_configuration": attrs.dep(providers = [ConfigurationInfo]),
         "use_windows_path_separators": attrs.bool(),
     },
@@ -58,7 +61,75 @@ def _host_os_configuration() -> str:
     else:
         return "prelude//os:linux"
 
+# Lower bounds of the buckets hosts are sorted into by number of CPUs and by GiB of memory.
+_CPU_COUNT_BUCKETS = [1, 4, 8, 16, 32, 64]
+_MEMORY_GB_BUCKETS = [0, 4, 8, 16, 32, 64, 128]
+
+def _bucket_names(prefix: str, bounds: list[int], unit: str) -> list[str]:
+    names = []
+    for i, lower in enumerate(bounds):
+        if i + 1 < len(bounds):
+            names.append("{}_{}_to_{}{}".format(prefix, lower, bounds[i + 1] - 1, unit))
+        else:
+            names.append("{}_{}{}_or_more".format(prefix, lower, unit))
+    return names
+
+def _bucket_for(value: int, prefix: str, bounds: list[int], unit: str) -> str:
+    names = _bucket_names(prefix, bounds, unit)
+    name = names[0]
+    for lower, candidate in zip(bounds, names):
+        if value >= lower:
+            name = candidate
+    return name
+
+def host_fact_constraint_values() -> dict[str, list[str]]:
+    """
+    The constraint values declared in `prelude//platforms/host`, keyed by the name of their
+    constraint setting.
+    """
+    return {
+        "cpu_count": _bucket_names("cpu_count", _CPU_COUNT_BUCKETS, ""),
+        "glibc": ["glibc_2_{}".format(minor) for minor in range(17, 61)],
+        "memory": _bucket_names("memory", _MEMORY_GB_BUCKETS, "gb"),
+        "xcode": ["xcode_{}".format(major) for major in range(10, 31)],
+    }
+
+def host_fact_constraints():
+    """
+    Declares the constraint settings and values of `host_fact_constraint_values()`.
+    """
+    for setting, values in host_fact_constraint_values().items():
+        native.constraint_setting(
+            name = setting,
+            visibility = ["PUBLIC"],
+        )
+        for value in values:
+            native.constraint_value(
+                name = value,
+                constraint_setting = ":" + setting,
+                visibility = ["PUBLIC"],
+            )
+
+def _host_fact_configurations() -> list[str]:
+    info = host_info()
+    known = host_fact_constraint_values()
+    facts = [_bucket_for(info.resources.cpu_count, "cpu_count", _CPU_COUNT_BUCKETS, "")]
+    if info.resources.memory_bytes > 0:
+        memory_gb = info.resources.memory_bytes // (1024 * 1024 * 1024)
+        facts.append(_bucket_for(memory_gb, "memory", _MEMORY_GB_BUCKETS, "gb"))
+    if info.glibc.major_version == "2":
+        glibc = "glibc_2_{}".format(info.glibc.minor_version)
+        if glibc in known["glibc"]:
+            facts.append(glibc)
+    if info.xcode.major_version != None:
+        xcode = "xcode_{}".format(info.xcode.major_version)
+        if xcode in known["xcode"]:
+            facts.append(xcode)
+    return ["prelude//platforms/host:" + fact for fact in facts]
+
 host_configuration = struct(
     cpu = _host_cpu_configuration(),
     os = _host_os_configuration(),
+    # Constraint values describing the host beyond its OS and CPU, see `prelude//platforms/host`.
+    facts = _host_fact_configurations(),
 )
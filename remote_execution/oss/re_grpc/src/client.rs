@@ -59,6 +59,8 @@ use re_grpc_proto::google::bytestream::ReadResponse;
 use re_grpc_proto::google::bytestream::WriteRequest;
 use re_grpc_proto::google::bytestream::WriteResponse;
 use re_grpc_proto::google::longrunning::operation::Result as OpResult;
+use re_grpc_proto::google::longrunning::operations_client::OperationsClient;
+use re_grpc_proto::google::longrunning::CancelOperationRequest;
 use re_grpc_proto::google::rpc::Code;
 use re_grpc_proto::google::rpc::Status;
 use regex::Regex;
@@ -319,6 +321,7 @@ impl REClientBuilder {
             ));
         }
 
+        let execution = execution.context("Error creating Execution client")?;
         let grpc_clients = GRPCClients {
             cas_client: ContentAddressableStorageClient::with_interceptor(
                 cas.context("Error creating CAS client")?,
@@ -326,7 +329,7 @@ impl REClientBuilder {
             )
             .max_decoding_message_size(max_decoding_msg_size),
             execution_client: ExecutionClient::with_interceptor(
-                execution.context("Error creating Execution client")?,
+                execution.clone(),
                 interceptor.dupe(),
             ),
             action_cache_client: ActionCacheClient::with_interceptor(
@@ -338,6 +341,8 @@ impl REClientBuilder {
                 interceptor.dupe(),
             )
             .max_decoding_message_size(max_decoding_msg_size),
+            // The Operations service is served by the execution engine.
+            operations_client: OperationsClient::with_interceptor(execution, interceptor.dupe()),
         };
 
         Ok(REClient::new(
@@ -447,6 +452,7 @@ pub struct GRPCClients {
     execution_client: ExecutionClient<GrpcService>,
     action_cache_client: ActionCacheClient<GrpcService>,
     bytestream_client: ByteStreamClient<GrpcService>,
+    operations_client: OperationsClient<GrpcService>,
 }
 
 enum DigestRemoteState {
@@ -632,6 +638,7 @@ impl REClient {
                 None => return Ok(None),
             };
 
+            let operation_name = msg.name;
             let status = if msg.done {
                 match msg
                     .result
@@ -671,6 +678,7 @@ impl REClient {
                         ExecuteWithProgressResponse {
                             stage: Stage::COMPLETED,
                             execute_response: Some(execute_response),
+                            operation_name,
                             ..Default::default()
                         }
                     }
@@ -691,6 +699,7 @@ impl REClient {
                 ExecuteWithProgressResponse {
                     stage,
                     execute_response: None,
+                    operation_name,
                     ..Default::default()
                 }
             };
@@ -718,6 +727,25 @@ impl REClient {
         Ok(stream.boxed())
     }
 
+    /// Ask the server to stop an execution started with `execute_with_progress`.
+    pub async fn cancel_operation(
+        &self,
+        metadata: RemoteExecutionMetadata,
+        operation_name: String,
+    ) -> anyhow::Result<()> {
+        let mut client = self.grpc_clients.operations_client.clone();
+        client
+            .cancel_operation(with_re_metadata(
+                CancelOperationRequest {
+                    name: operation_name,
+                },
+                metadata,
+                self.runtime_opts.use_fbcode_metadata,
+            ))
+            .await?;
+        Ok(())
+    }
+
     pub async fn upload(
         &self,
         metadata: RemoteExecutionMetadata,
@@ -1009,6 +1037,9 @@ fn convert_action_result(action_result: ActionResult) -> anyhow::Result<TActionR
     Ok(action_result)
 }
 
+/// How many times an interrupted Bytestream read is resumed before the download fails.
+const MAX_BYTESTREAM_RESUMES: usize = 3;
+
 async fn download_impl<Byt, BytRet, Cas>(
     instance_name: &InstanceName,
     request: DownloadRequest,
@@ -1021,7 +1052,7 @@ where
     BytRet: Stream<Item = Result<ReadResponse, tonic::Status>>,
     Cas: Future<Output = anyhow::Result<BatchReadBlobsResponse>>,
 {
-    let bystream_fut = |digest: TDigest| async move {
+    let bystream_fut = |digest: TDigest, read_offset: i64| async move {
         let hash = digest.hash;
         let size_in_bytes = digest.size_in_bytes;
 
@@ -1034,7 +1065,7 @@ where
 
         bystream_fut(ReadRequest {
             resource_name: resource_name.clone(),
-            read_offset: 0,
+            read_offset,
             read_limit: 0,
         })
         .await
@@ -1108,7 +1139,7 @@ where
     for digest in inlined_digests {
         let data = if digest.size_in_bytes as usize >= max_total_batch_size {
             let mut accum = vec![];
-            let mut responses = bystream_fut(digest.clone()).await?;
+            let mut responses = bystream_fut(digest.clone(), 0).await?;
             while let Some(resp) = responses.next().await {
                 let data = resp
                     .with_context(|| format!("Failed to fetch inline digest: {digest}"))?
@@ -1153,14 +1184,48 @@ where
                     .await
                     .with_context(|| format!("Error writing: {}", req.named_digest.digest))?;
             } else {
-                let mut responses = bystream_fut(req.named_digest.digest.clone()).await?;
-                while let Some(resp) = responses.next().await {
-                    let data = resp
-                        .with_context(|| format!("Failed to fetch file: {:?}", file))?
-                        .data;
-                    file.write_all(&data).await.with_context(|| {
-                        format!("Error writing chunk of: {}", req.named_digest.digest)
-                    })?;
+                // If the stream is interrupted, resume reading from the last byte we wrote
+                // instead of failing the whole download.
+                let size = req.named_digest.digest.size_in_bytes;
+                let mut written = 0;
+                let mut resumes = 0;
+                loop {
+                    let res = async {
+                        let mut responses =
+                            bystream_fut(req.named_digest.digest.clone(), written).await?;
+                        while let Some(resp) = responses.next().await {
+                            let data = resp
+                                .with_context(|| format!("Failed to fetch file: {:?}", file))?
+                                .data;
+                            file.write_all(&data).await.with_context(|| {
+                                format!("Error writing chunk of: {}", req.named_digest.digest)
+                            })?;
+                            written += data.len() as i64;
+                        }
+                        if written < size {
+                            return Err(anyhow::anyhow!(
+                                "Stream ended after {} of {} bytes",
+                                written,
+                                size
+                            ));
+                        }
+                        anyhow::Ok(())
+                    }
+                    .await;
+
+                    match res {
+                        Ok(()) => break,
+                        Err(e) if written < size && resumes < MAX_BYTESTREAM_RESUMES => {
+                            resumes += 1;
+                            tracing::warn!(
+                                "Resuming download of `{}` at offset {}: {:#}",
+                                req.named_digest.digest,
+                                written,
+                                e
+                            );
+                        }
+                        Err(e) => return Err(e),
+                    }
                 }
             }
             file.flush().await.context("Error flushing")?;
@@ -1712,6 +1777,68 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_download_resumes_interrupted_stream() -> anyhow::Result<()> {
+        let work = tempfile::tempdir()?;
+
+        let path = work.path().join("path");
+        let path = path.to_str().context("tempdir is not utf8")?;
+
+        let blob_data = vec![
+            1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18,
+        ];
+
+        let digest = TDigest {
+            hash: "xl".to_owned(),
+            size_in_bytes: 18,
+            ..Default::default()
+        };
+
+        let req = DownloadRequest {
+            file_digests: Some(vec![NamedDigestWithPermissions {
+                named_digest: NamedDigest {
+                    name: path.to_owned(),
+                    digest: digest.clone(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+
+        download_impl(
+            &InstanceName(None),
+            req,
+            10, // kept small to simulate a large file download
+            |_req| async move { Ok(BatchReadBlobsResponse::default()) },
+            |req| {
+                let blob_data = blob_data.clone();
+                async move {
+                    assert_eq!(req.resource_name, "blobs/xl/18");
+                    let responses = match req.read_offset {
+                        // The first read is interrupted after 10 bytes.
+                        0 => vec![
+                            Ok(ReadResponse {
+                                data: blob_data[..10].to_vec(),
+                            }),
+                            Err(tonic::Status::unavailable("connection reset")),
+                        ],
+                        10 => vec![Ok(ReadResponse {
+                            data: blob_data[10..].to_vec(),
+                        })],
+                        offset => panic!("unexpected read_offset: {}", offset),
+                    };
+                    anyhow::Ok(Box::pin(futures::stream::iter(responses)))
+                }
+            },
+        )
+        .await?;
+
+        assert_eq!(tokio::fs::read(&path).await?, blob_data);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_download_inlined() -> anyhow::Result<()> {
         let digest1 = &TDigest {
@@ -212,6 +212,8 @@ pub struct ExecuteWithProgressResponse {
     pub stage: Stage,
     pub execute_response: Option<ExecuteResponse>,
     pub metadata: OperationMetadata,
+    /// Name of the long running operation executing the action, used to cancel it.
+    pub operation_name: String,
 }
 
 #[derive(Clone, Debug, Dupe, Default)]
@@ -54,7 +54,12 @@ class ExitCodeV2(Enum):
     UNKNOWN_ERROR = 1
     INFRA_ERROR = 2
     USER_ERROR = 3
+    DAEMON_IS_BUSY = 4
+    DAEMON_PREEMPTED = 5
     DAEMON_CONNECTION_FAILURE = 11
+    TEST_FAILURE = 32
+    BROKEN_PIPE = 130
+    SIGNAL_INTERRUPT = 141
 
 
 class AutoName(Enum):
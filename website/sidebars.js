@@ -107,12 +107,15 @@ const manualSidebar = [
           'users/build_observability/interactive_console',
           'users/build_observability/logging',
           'users/build_observability/build_report',
+          'users/build_observability/error_format',
+          'users/build_observability/test_reports',
           isInternal() ? 'users/build_observability/observability' : [],
           isInternal() ? 'users/build_observability/scuba' : [],
           isInternal() ? 'users/build_observability/ods' : [],
         ],
       },
       'users/remote_execution',
+      'users/exit_codes',
       {
         type: 'category',
         label: 'Queries',
@@ -126,8 +129,14 @@ const manualSidebar = [
         items: [
           'users/advanced/deferred_materialization',
           'users/advanced/restarter',
+          'users/advanced/reattach',
           'users/advanced/in_memory_cache',
           'users/advanced/external_cells',
+          'users/advanced/bazel_compat',
+          'users/advanced/action_lockfiles',
+          'users/advanced/reproducible_builds',
+          'users/advanced/host_toolchains',
+          'users/advanced/remote_toolchains',
           isInternal() ? 'users/advanced/offline_build_archives' : [],
           isInternal() ? 'users/advanced/vpnless' : [],
         ],
@@ -150,6 +159,7 @@ const manualSidebar = [
       'rule_authors/incremental_actions',
       'rule_authors/alias',
       'rule_authors/local_resources',
+      'rule_authors/validations',
       'rule_authors/package_files',
       isInternal() ? 'rule_authors/client_metadata' : [],
       isInternal() ? 'rule_authors/action_error_handler' : [],